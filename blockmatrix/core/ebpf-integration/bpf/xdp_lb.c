@@ -0,0 +1,385 @@
+// SPDX-License-Identifier: GPL-2.0
+/*
+ * XDP layer 4 load balancer
+ *
+ * Packets to a service address are rewritten to one of the service's
+ * endpoints and passed on to the kernel to route. The endpoint is picked
+ * from the service's Maglev table by a hash of the 5-tuple, and the choice
+ * is remembered in LB_AFFINITY so a connection keeps its endpoint when the
+ * table changes. Replies from an endpoint are rewritten back to the service
+ * address through LB_REVERSE, so the program must see both directions:
+ * attach it to every interface clients and endpoints are reached through.
+ *
+ * Map layouts match src/xdp_lb.rs; the user-space loader fills every map
+ * except the flow maps and counters. Build with:
+ *
+ *   clang -O2 -g -target bpf -c xdp_lb.c -o xdp_lb.o
+ */
+
+#include <linux/bpf.h>
+#include <linux/if_ether.h>
+#include <linux/in.h>
+#include <linux/ip.h>
+#include <linux/ipv6.h>
+#include <linux/tcp.h>
+#include <linux/udp.h>
+#include <bpf/bpf_endian.h>
+#include <bpf/bpf_helpers.h>
+
+#define MAGLEV_TABLE_SIZE 4099
+#define MAX_SERVICES 256
+#define MAX_ENDPOINTS 4096
+#define MAX_FLOWS 262144
+
+/* Addresses are 16 bytes; IPv4 addresses are stored IPv4-mapped */
+struct flow_key {
+	__u8 src_addr[16];
+	__u8 dst_addr[16];
+	__u16 src_port;
+	__u16 dst_port;
+	__u8 protocol;
+	__u8 _pad[3];
+};
+
+struct service_key {
+	__u8 addr[16];
+	__u16 port;
+	__u8 protocol;
+	__u8 _pad;
+};
+
+struct service_value {
+	__u32 service_id;
+	__u32 endpoint_count;
+};
+
+struct endpoint_value {
+	__u8 addr[16];
+	__u16 port;
+	__u16 _pad;
+	__u32 service_id;
+	/* Zero once the endpoint is withdrawn */
+	__u32 weight;
+};
+
+struct affinity_value {
+	__u32 endpoint_id;
+	__u32 _pad;
+	__u64 last_seen_ns;
+};
+
+struct reverse_value {
+	__u8 addr[16];
+	__u16 port;
+	__u8 _pad[6];
+};
+
+struct endpoint_counters {
+	__u64 packets;
+	__u64 bytes;
+};
+
+struct {
+	__uint(type, BPF_MAP_TYPE_HASH);
+	__uint(max_entries, MAX_SERVICES);
+	__type(key, struct service_key);
+	__type(value, struct service_value);
+} LB_SERVICES SEC(".maps");
+
+/* Endpoint id at service_id * MAGLEV_TABLE_SIZE + slot */
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, MAX_SERVICES * MAGLEV_TABLE_SIZE);
+	__type(key, __u32);
+	__type(value, __u32);
+} LB_MAGLEV SEC(".maps");
+
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, MAX_ENDPOINTS);
+	__type(key, __u32);
+	__type(value, struct endpoint_value);
+} LB_ENDPOINTS SEC(".maps");
+
+struct {
+	__uint(type, BPF_MAP_TYPE_LRU_HASH);
+	__uint(max_entries, MAX_FLOWS);
+	__type(key, struct flow_key);
+	__type(value, struct affinity_value);
+} LB_AFFINITY SEC(".maps");
+
+struct {
+	__uint(type, BPF_MAP_TYPE_LRU_HASH);
+	__uint(max_entries, MAX_FLOWS);
+	__type(key, struct flow_key);
+	__type(value, struct reverse_value);
+} LB_REVERSE SEC(".maps");
+
+struct {
+	__uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+	__uint(max_entries, MAX_ENDPOINTS);
+	__type(key, __u32);
+	__type(value, struct endpoint_counters);
+} LB_ENDPOINT_STATS SEC(".maps");
+
+/* Slot 0: affinity timeout in nanoseconds */
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, __u32);
+	__type(value, __u64);
+} LB_SETTINGS SEC(".maps");
+
+/* A parsed TCP or UDP packet, pointing into the packet data */
+struct packet {
+	struct flow_key key;
+	__u8 *src_addr;
+	__u8 *dst_addr;
+	__u32 addr_len;
+	/* Source and destination ports, one 32-bit word */
+	__u32 *ports;
+	__sum16 *ip_check;
+	__sum16 *l4_check;
+	__u64 len;
+};
+
+static __always_inline __u16 csum_fold(__s64 csum)
+{
+	__u32 sum = (__u32)csum;
+
+	sum = (sum & 0xffff) + (sum >> 16);
+	sum = (sum & 0xffff) + (sum >> 16);
+	return (__u16)~sum;
+}
+
+/* Incrementally update a checksum for `size` bytes changing from `from` to `to` */
+static __always_inline void csum_replace(__sum16 *check, void *from, void *to, __u32 size)
+{
+	__s64 diff = bpf_csum_diff(from, size, to, size, ~((__u32)*check) & 0xffff);
+
+	if (diff >= 0)
+		*check = csum_fold(diff);
+}
+
+static __always_inline void map_ipv4(__u8 *out, __be32 addr)
+{
+	__builtin_memset(out, 0, 10);
+	out[10] = 0xff;
+	out[11] = 0xff;
+	__builtin_memcpy(out + 12, &addr, 4);
+}
+
+static __always_inline int parse(struct xdp_md *ctx, struct packet *pkt)
+{
+	void *data = (void *)(long)ctx->data;
+	void *data_end = (void *)(long)ctx->data_end;
+	struct ethhdr *eth = data;
+	void *l4;
+	__u8 protocol;
+
+	if ((void *)(eth + 1) > data_end)
+		return -1;
+	pkt->len = data_end - data;
+
+	if (eth->h_proto == bpf_htons(ETH_P_IP)) {
+		struct iphdr *ip = (void *)(eth + 1);
+
+		if ((void *)(ip + 1) > data_end || ip->ihl != 5)
+			return -1;
+		/* Fragments carry no ports to balance on */
+		if (ip->frag_off & bpf_htons(0x3fff))
+			return -1;
+		protocol = ip->protocol;
+		map_ipv4(pkt->key.src_addr, ip->saddr);
+		map_ipv4(pkt->key.dst_addr, ip->daddr);
+		pkt->src_addr = (__u8 *)&ip->saddr;
+		pkt->dst_addr = (__u8 *)&ip->daddr;
+		pkt->addr_len = 4;
+		pkt->ip_check = &ip->check;
+		l4 = ip + 1;
+	} else if (eth->h_proto == bpf_htons(ETH_P_IPV6)) {
+		struct ipv6hdr *ip6 = (void *)(eth + 1);
+
+		if ((void *)(ip6 + 1) > data_end)
+			return -1;
+		protocol = ip6->nexthdr;
+		__builtin_memcpy(pkt->key.src_addr, &ip6->saddr, 16);
+		__builtin_memcpy(pkt->key.dst_addr, &ip6->daddr, 16);
+		pkt->src_addr = (__u8 *)&ip6->saddr;
+		pkt->dst_addr = (__u8 *)&ip6->daddr;
+		pkt->addr_len = 16;
+		pkt->ip_check = 0;
+		l4 = ip6 + 1;
+	} else {
+		return -1;
+	}
+
+	if (protocol == IPPROTO_TCP) {
+		struct tcphdr *tcp = l4;
+
+		if ((void *)(tcp + 1) > data_end)
+			return -1;
+		pkt->l4_check = &tcp->check;
+	} else if (protocol == IPPROTO_UDP) {
+		struct udphdr *udp = l4;
+
+		if ((void *)(udp + 1) > data_end)
+			return -1;
+		/* A zero UDP checksum over IPv4 means none was computed */
+		pkt->l4_check = (udp->check || pkt->addr_len == 16) ? &udp->check : 0;
+	} else {
+		return -1;
+	}
+
+	pkt->ports = l4;
+	pkt->key.src_port = bpf_ntohs(((__be16 *)l4)[0]);
+	pkt->key.dst_port = bpf_ntohs(((__be16 *)l4)[1]);
+	pkt->key.protocol = protocol;
+	return 0;
+}
+
+/* Replace one address of the packet, and one of its ports, fixing checksums */
+static __always_inline void rewrite(struct packet *pkt, __u8 *addr, const __u8 *to_addr, int dst, __u16 to_port)
+{
+	__u8 old_addr[16];
+	__u8 new_addr[16];
+	__u32 old_ports = *pkt->ports;
+	__u32 new_ports = old_ports;
+	__be16 *ports = (__be16 *)&new_ports;
+
+	ports[dst ? 1 : 0] = bpf_htons(to_port);
+
+	if (pkt->addr_len == 4) {
+		__builtin_memcpy(old_addr, addr, 4);
+		__builtin_memcpy(new_addr, to_addr + 12, 4);
+		if (pkt->ip_check)
+			csum_replace(pkt->ip_check, old_addr, new_addr, 4);
+		if (pkt->l4_check)
+			csum_replace(pkt->l4_check, old_addr, new_addr, 4);
+		__builtin_memcpy(addr, new_addr, 4);
+	} else {
+		__builtin_memcpy(old_addr, addr, 16);
+		__builtin_memcpy(new_addr, to_addr, 16);
+		if (pkt->l4_check)
+			csum_replace(pkt->l4_check, old_addr, new_addr, 16);
+		__builtin_memcpy(addr, new_addr, 16);
+	}
+
+	if (pkt->l4_check)
+		csum_replace(pkt->l4_check, &old_ports, &new_ports, 4);
+	*pkt->ports = new_ports;
+}
+
+static __always_inline __u32 flow_hash(const struct flow_key *key)
+{
+	const __u8 *bytes = (const __u8 *)key;
+	__u32 hash = 2166136261u;
+
+#pragma unroll
+	for (int i = 0; i < (int)sizeof(struct flow_key) - 3; i++) {
+		hash ^= bytes[i];
+		hash *= 16777619u;
+	}
+	return hash;
+}
+
+static __always_inline int is_ipv4_mapped(const __u8 *addr)
+{
+	return addr[10] == 0xff && addr[11] == 0xff && !addr[0] && !addr[1] && !addr[2] &&
+	       !addr[3] && !addr[4] && !addr[5] && !addr[6] && !addr[7] && !addr[8] && !addr[9];
+}
+
+/* Endpoint for a new or returning connection to a service */
+static __always_inline struct endpoint_value *choose_endpoint(struct packet *pkt, struct service_value *service,
+							      __u32 *endpoint_id)
+{
+	__u64 now = bpf_ktime_get_ns();
+	__u32 zero = 0;
+	__u64 *timeout = bpf_map_lookup_elem(&LB_SETTINGS, &zero);
+	struct affinity_value *affinity = bpf_map_lookup_elem(&LB_AFFINITY, &pkt->key);
+	struct endpoint_value *endpoint;
+	__u32 slot;
+	__u32 *chosen;
+
+	if (affinity && (!timeout || !*timeout || now - affinity->last_seen_ns < *timeout)) {
+		*endpoint_id = affinity->endpoint_id;
+		endpoint = bpf_map_lookup_elem(&LB_ENDPOINTS, endpoint_id);
+		if (endpoint && endpoint->weight && endpoint->service_id == service->service_id) {
+			affinity->last_seen_ns = now;
+			return endpoint;
+		}
+	}
+
+	if (!service->endpoint_count || service->service_id >= MAX_SERVICES)
+		return 0;
+	slot = service->service_id * MAGLEV_TABLE_SIZE + flow_hash(&pkt->key) % MAGLEV_TABLE_SIZE;
+	chosen = bpf_map_lookup_elem(&LB_MAGLEV, &slot);
+	if (!chosen)
+		return 0;
+	*endpoint_id = *chosen;
+	endpoint = bpf_map_lookup_elem(&LB_ENDPOINTS, endpoint_id);
+	if (!endpoint || !endpoint->weight)
+		return 0;
+
+	struct affinity_value remembered = { .endpoint_id = *endpoint_id, .last_seen_ns = now };
+	bpf_map_update_elem(&LB_AFFINITY, &pkt->key, &remembered, BPF_ANY);
+	return endpoint;
+}
+
+SEC("xdp")
+int xdp_lb(struct xdp_md *ctx)
+{
+	struct packet pkt = {};
+	struct service_key service_key = {};
+	struct service_value *service;
+	struct endpoint_value *endpoint;
+	struct reverse_value *reverse;
+	struct endpoint_counters *counters;
+	__u32 endpoint_id;
+
+	if (parse(ctx, &pkt) < 0)
+		return XDP_PASS;
+
+	__builtin_memcpy(service_key.addr, pkt.key.dst_addr, 16);
+	service_key.port = pkt.key.dst_port;
+	service_key.protocol = pkt.key.protocol;
+	service = bpf_map_lookup_elem(&LB_SERVICES, &service_key);
+
+	if (!service) {
+		/* A reply from an endpoint goes back out as the service */
+		reverse = bpf_map_lookup_elem(&LB_REVERSE, &pkt.key);
+		if (reverse)
+			rewrite(&pkt, pkt.src_addr, reverse->addr, 0, reverse->port);
+		return XDP_PASS;
+	}
+
+	endpoint = choose_endpoint(&pkt, service, &endpoint_id);
+	if (!endpoint)
+		return XDP_DROP;
+	/* Services and their endpoints share an address family */
+	if (is_ipv4_mapped(endpoint->addr) != (pkt.addr_len == 4))
+		return XDP_DROP;
+
+	struct flow_key reply = {};
+	struct reverse_value service_addr = {};
+
+	__builtin_memcpy(reply.src_addr, endpoint->addr, 16);
+	__builtin_memcpy(reply.dst_addr, pkt.key.src_addr, 16);
+	reply.src_port = endpoint->port;
+	reply.dst_port = pkt.key.src_port;
+	reply.protocol = pkt.key.protocol;
+	__builtin_memcpy(service_addr.addr, pkt.key.dst_addr, 16);
+	service_addr.port = pkt.key.dst_port;
+	bpf_map_update_elem(&LB_REVERSE, &reply, &service_addr, BPF_ANY);
+
+	counters = bpf_map_lookup_elem(&LB_ENDPOINT_STATS, &endpoint_id);
+	if (counters) {
+		counters->packets++;
+		counters->bytes += pkt.len;
+	}
+
+	rewrite(&pkt, pkt.dst_addr, endpoint->addr, 1, endpoint->port);
+	return XDP_PASS;
+}
+
+char LICENSE[] SEC("license") = "GPL";
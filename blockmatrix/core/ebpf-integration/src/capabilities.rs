@@ -0,0 +1,141 @@
+//! Capability probing and degraded mode
+//!
+//! Loading eBPF programs needs CAP_SYS_ADMIN and CAP_NET_ADMIN and a kernel
+//! with eBPF support. An unprivileged container has neither, so the manager
+//! probes for them at start-up. When they are missing and degraded mode is
+//! allowed, every component runs its user-space fallback instead of
+//! refusing to start. The network monitor reads the kernel's socket and
+//! interface counters from `/proc`. The load balancer proxies TCP
+//! connections itself. Traffic control and security policies keep their
+//! accounting but cannot act on packets. The resulting report records what
+//! each component runs as and what it gives up.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// How a component does its work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionMode {
+    /// eBPF programs in the kernel
+    Kernel,
+    /// A user-space fallback, slower and with reduced function
+    UserSpace,
+}
+
+/// What one component runs as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentReport {
+    pub component: String,
+    pub mode: ExecutionMode,
+    /// What the fallback cannot do; empty in kernel mode
+    pub limitations: Vec<String>,
+}
+
+/// Privileges found at start-up and what each component runs as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityReport {
+    pub cap_sys_admin: bool,
+    pub cap_net_admin: bool,
+    pub kernel_supported: bool,
+    pub components: Vec<ComponentReport>,
+}
+
+impl CapabilityReport {
+    /// Probe this process's effective capabilities and the kernel
+    pub fn probe() -> Self {
+        #[cfg(target_os = "linux")]
+        let (cap_sys_admin, cap_net_admin) = {
+            use caps::{CapSet, Capability};
+            match caps::read(None, CapSet::Effective) {
+                Ok(caps) => (caps.contains(&Capability::CAP_SYS_ADMIN), caps.contains(&Capability::CAP_NET_ADMIN)),
+                Err(e) => {
+                    debug!("Failed to read capabilities: {}", e);
+                    (false, false)
+                }
+            }
+        };
+        #[cfg(not(target_os = "linux"))]
+        let (cap_sys_admin, cap_net_admin) = (false, false);
+
+        Self { cap_sys_admin, cap_net_admin, kernel_supported: kernel_supports_ebpf(), components: Vec::new() }
+    }
+
+    /// Whether eBPF programs can be loaded
+    pub fn ebpf_available(&self) -> bool {
+        self.cap_sys_admin && self.cap_net_admin && self.kernel_supported
+    }
+
+    /// Mode components run in
+    pub fn mode(&self) -> ExecutionMode {
+        if self.ebpf_available() {
+            ExecutionMode::Kernel
+        } else {
+            ExecutionMode::UserSpace
+        }
+    }
+
+    /// Why eBPF is unavailable, as an error
+    pub fn missing(&self) -> Result<()> {
+        if !self.cap_sys_admin {
+            return Err(anyhow!("CAP_SYS_ADMIN capability required for eBPF programs"));
+        }
+        if !self.cap_net_admin {
+            return Err(anyhow!("CAP_NET_ADMIN capability required for network eBPF programs"));
+        }
+        if !self.kernel_supported {
+            return Err(anyhow!("Kernel does not support required eBPF features"));
+        }
+        Ok(())
+    }
+
+    /// Record a component; its limitations apply only in user-space mode
+    pub fn record(&mut self, component: &str, limitations: &[&str]) {
+        let mode = self.mode();
+        self.components.push(ComponentReport {
+            component: component.to_string(),
+            mode,
+            limitations: match mode {
+                ExecutionMode::Kernel => Vec::new(),
+                ExecutionMode::UserSpace => limitations.iter().map(|l| l.to_string()).collect(),
+            },
+        });
+    }
+
+    /// Whether any component runs a fallback
+    pub fn is_degraded(&self) -> bool {
+        self.components.iter().any(|component| component.mode == ExecutionMode::UserSpace)
+    }
+}
+
+/// Check if kernel supports eBPF features
+fn kernel_supports_ebpf() -> bool {
+    // Logged to help diagnose a missing feature
+    let version_info = std::fs::read_to_string("/proc/version").unwrap_or_else(|_| "Unknown".to_string());
+    debug!("Kernel version: {}", version_info);
+
+    // The sysctl exists only in kernels built with the bpf() system call
+    std::path::Path::new("/proc/sys/kernel/unprivileged_bpf_disabled").exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_capability_degrades_components() {
+        let mut report = CapabilityReport { cap_sys_admin: true, cap_net_admin: false, kernel_supported: true, components: Vec::new() };
+        assert!(!report.ebpf_available());
+        assert!(report.missing().unwrap_err().to_string().contains("CAP_NET_ADMIN"));
+
+        report.record("load-balancer", &["TCP only"]);
+        assert!(report.is_degraded());
+        assert_eq!(report.components[0].mode, ExecutionMode::UserSpace);
+        assert_eq!(report.components[0].limitations, vec!["TCP only".to_string()]);
+
+        let mut privileged = CapabilityReport { cap_net_admin: true, components: Vec::new(), ..report };
+        privileged.record("load-balancer", &["TCP only"]);
+        assert!(privileged.missing().is_ok() && !privileged.is_degraded());
+        assert!(privileged.components[0].limitations.is_empty());
+    }
+}
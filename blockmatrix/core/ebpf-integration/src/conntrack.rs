@@ -0,0 +1,426 @@
+//! Stateful connection tracking for security policies
+//!
+//! The security program keeps a flow table keyed by the 5-tuple of each
+//! connection's first packet. A packet is `New` if it opens a flow, and
+//! `Established` once the flow has seen traffic in both directions. ICMP
+//! from a peer that already has a flow with the sender, such as an
+//! unreachable error, is `Related`. TCP without a SYN that matches no flow
+//! is `Invalid`. Policy rules can match on these states, so "allow
+//! established and related, deny new" is two rules. Rules can also cap
+//! each flow's packet rate.
+//!
+//! The table has a fixed size, like the kernel's LRU hash map. When it is
+//! full, a new flow evicts the half-open flow idle the longest, then the
+//! idle established flow, or is refused if the policy says so. Flows expire
+//! after a timeout that depends on their protocol and state. SYN flood
+//! protection caps half-open TCP flows per source and in total; SYNs beyond
+//! either cap are dropped before a flow is created.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Name of the LRU hash map holding the kernel's flow table
+pub const CONNTRACK_MAP: &str = "CONNTRACK";
+
+/// TCP flag bits as they appear in the header
+pub const TCP_FIN: u8 = 0x01;
+pub const TCP_SYN: u8 = 0x02;
+pub const TCP_RST: u8 = 0x04;
+pub const TCP_ACK: u8 = 0x10;
+
+/// Protocols the tracker follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FlowProtocol {
+    Tcp,
+    Udp,
+    Icmp,
+}
+
+impl FlowProtocol {
+    /// Name used by policy rules
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FlowProtocol::Tcp => "TCP",
+            FlowProtocol::Udp => "UDP",
+            FlowProtocol::Icmp => "ICMP",
+        }
+    }
+}
+
+/// A packet as seen by the security program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowPacket {
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    pub protocol: FlowProtocol,
+    /// TCP flags; zero for other protocols
+    pub tcp_flags: u8,
+    pub len: u32,
+}
+
+/// Connection state of a packet, as policy rules match it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ConnState {
+    New,
+    Established,
+    Related,
+    Invalid,
+}
+
+/// What to do with a new flow when the table is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowEviction {
+    /// Evict the longest-idle flow, half-open flows first
+    EvictIdle,
+    /// Refuse the new flow
+    RejectNew,
+}
+
+/// Connection tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConntrackConfig {
+    pub enabled: bool,
+    /// Flows the table holds
+    pub table_size: usize,
+    pub eviction: FlowEviction,
+    pub tcp_established_timeout_secs: u64,
+    /// Timeout of half-open and closing TCP flows
+    pub tcp_transient_timeout_secs: u64,
+    pub udp_timeout_secs: u64,
+    pub icmp_timeout_secs: u64,
+    /// Half-open TCP flows one source may hold
+    pub syn_limit_per_source: u32,
+    /// Half-open TCP flows the table may hold
+    pub syn_limit_total: usize,
+}
+
+impl Default for ConntrackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            table_size: 65_536,
+            eviction: FlowEviction::EvictIdle,
+            tcp_established_timeout_secs: 3600,
+            tcp_transient_timeout_secs: 30,
+            udp_timeout_secs: 60,
+            icmp_timeout_secs: 10,
+            syn_limit_per_source: 256,
+            syn_limit_total: 16_384,
+        }
+    }
+}
+
+/// Result of tracking one packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackOutcome {
+    /// The packet belongs to, or opened, a flow in this state
+    Tracked { state: ConnState, flow: FlowKey },
+    /// Not part of any flow and not able to open one
+    Untracked(ConnState),
+    /// A SYN over a half-open limit
+    SynFlood,
+    /// The table is full and refuses new flows
+    TableFull,
+}
+
+impl TrackOutcome {
+    pub fn state(&self) -> Option<ConnState> {
+        match self {
+            TrackOutcome::Tracked { state, .. } | TrackOutcome::Untracked(state) => Some(*state),
+            TrackOutcome::SynFlood | TrackOutcome::TableFull => None,
+        }
+    }
+}
+
+/// A flow, in the direction of its first packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    pub protocol: FlowProtocol,
+}
+
+impl FlowKey {
+    fn reversed(&self) -> Self {
+        Self { src: self.dst, dst: self.src, protocol: self.protocol }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TcpPhase {
+    SynSent,
+    Open,
+    Closing,
+}
+
+#[derive(Debug, Clone)]
+struct Flow {
+    established: bool,
+    tcp: Option<TcpPhase>,
+    last_seen: Instant,
+    /// Token bucket of a per-flow rate limit: tokens and last refill
+    bucket: Option<(f64, Instant)>,
+}
+
+impl Flow {
+    fn half_open(&self) -> bool {
+        self.tcp == Some(TcpPhase::SynSent)
+    }
+}
+
+/// Table occupancy and what the tracker has turned away
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConntrackStats {
+    pub flows: usize,
+    pub half_open: usize,
+    pub table_size: usize,
+    pub evicted: u64,
+    pub expired: u64,
+    pub rejected_table_full: u64,
+    pub syn_flood_drops: u64,
+    pub invalid: u64,
+}
+
+/// The flow table
+pub struct ConnectionTracker {
+    config: ConntrackConfig,
+    flows: HashMap<FlowKey, Flow>,
+    /// Half-open flows per source address
+    half_open: HashMap<IpAddr, u32>,
+    half_open_total: usize,
+    /// Flows between each ordered pair of addresses, for ICMP relation
+    peers: HashMap<(IpAddr, IpAddr), u32>,
+    stats: ConntrackStats,
+}
+
+impl ConnectionTracker {
+    pub fn new(config: ConntrackConfig) -> Self {
+        let stats = ConntrackStats { table_size: config.table_size, ..Default::default() };
+        Self {
+            config,
+            flows: HashMap::new(),
+            half_open: HashMap::new(),
+            half_open_total: 0,
+            peers: HashMap::new(),
+            stats,
+        }
+    }
+
+    pub fn config(&self) -> &ConntrackConfig {
+        &self.config
+    }
+
+    /// Look up or create the flow of a packet and move it along
+    pub fn track(&mut self, packet: &FlowPacket, now: Instant) -> TrackOutcome {
+        let key = FlowKey { src: packet.src, dst: packet.dst, protocol: packet.protocol };
+        let (key, reply) = if self.flows.contains_key(&key) {
+            (key, false)
+        } else if self.flows.contains_key(&key.reversed()) {
+            (key.reversed(), true)
+        } else {
+            return self.open(key, packet, now);
+        };
+
+        let flow = self.flows.get_mut(&key).expect("flow was just found");
+        let was_half_open = flow.half_open();
+        flow.last_seen = now;
+        if reply {
+            flow.established = true;
+        }
+        if let Some(phase) = flow.tcp {
+            flow.tcp = Some(if packet.tcp_flags & (TCP_FIN | TCP_RST) != 0 {
+                TcpPhase::Closing
+            } else if reply && phase == TcpPhase::SynSent {
+                TcpPhase::Open
+            } else {
+                phase
+            });
+        }
+        let state = if flow.established { ConnState::Established } else { ConnState::New };
+        if was_half_open && !flow.half_open() {
+            self.release_half_open(key.src.ip());
+        }
+        TrackOutcome::Tracked { state, flow: key }
+    }
+
+    fn open(&mut self, key: FlowKey, packet: &FlowPacket, now: Instant) -> TrackOutcome {
+        let tcp = match packet.protocol {
+            FlowProtocol::Tcp if packet.tcp_flags & TCP_SYN == 0 || packet.tcp_flags & (TCP_ACK | TCP_RST) != 0 => {
+                self.stats.invalid += 1;
+                return TrackOutcome::Untracked(ConnState::Invalid);
+            }
+            FlowProtocol::Tcp => Some(TcpPhase::SynSent),
+            FlowProtocol::Icmp if self.peers.contains_key(&(key.dst.ip(), key.src.ip())) => {
+                return TrackOutcome::Untracked(ConnState::Related);
+            }
+            FlowProtocol::Udp | FlowProtocol::Icmp => None,
+        };
+
+        if tcp.is_some() {
+            let source = self.half_open.get(&key.src.ip()).copied().unwrap_or(0);
+            if source >= self.config.syn_limit_per_source || self.half_open_total >= self.config.syn_limit_total {
+                self.stats.syn_flood_drops += 1;
+                return TrackOutcome::SynFlood;
+            }
+        }
+        if self.flows.len() >= self.config.table_size && !self.make_room() {
+            self.stats.rejected_table_full += 1;
+            return TrackOutcome::TableFull;
+        }
+
+        if tcp.is_some() {
+            *self.half_open.entry(key.src.ip()).or_default() += 1;
+            self.half_open_total += 1;
+        }
+        *self.peers.entry((key.src.ip(), key.dst.ip())).or_default() += 1;
+        self.flows.insert(
+            key,
+            Flow { established: false, tcp, last_seen: now, bucket: None },
+        );
+        TrackOutcome::Tracked { state: ConnState::New, flow: key }
+    }
+
+    /// Evict a flow to make room for a new one, if the policy allows
+    fn make_room(&mut self) -> bool {
+        if self.config.eviction == FlowEviction::RejectNew {
+            return false;
+        }
+        let victim = self
+            .flows
+            .iter()
+            .min_by_key(|(_, flow)| (!flow.half_open(), flow.last_seen))
+            .map(|(key, _)| *key);
+        match victim {
+            Some(key) => {
+                self.remove(&key);
+                self.stats.evicted += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a flow may send another packet under a per-flow rate limit
+    pub fn admit(&mut self, flow: &FlowKey, pps: u32, now: Instant) -> bool {
+        let Some(flow) = self.flows.get_mut(flow) else {
+            return true;
+        };
+        let capacity = pps.max(1) as f64;
+        let (tokens, last) = flow.bucket.get_or_insert((capacity, now));
+        *tokens = (*tokens + now.saturating_duration_since(*last).as_secs_f64() * pps as f64).min(capacity);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove flows idle past their timeout
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let expired: Vec<FlowKey> = self
+            .flows
+            .iter()
+            .filter(|(key, flow)| now.saturating_duration_since(flow.last_seen) >= self.timeout(key, flow))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        self.stats.expired += expired.len() as u64;
+        expired.len()
+    }
+
+    fn timeout(&self, key: &FlowKey, flow: &Flow) -> Duration {
+        let secs = match (key.protocol, flow.tcp) {
+            (FlowProtocol::Tcp, Some(TcpPhase::Open)) => self.config.tcp_established_timeout_secs,
+            (FlowProtocol::Tcp, _) => self.config.tcp_transient_timeout_secs,
+            (FlowProtocol::Udp, _) => self.config.udp_timeout_secs,
+            (FlowProtocol::Icmp, _) => self.config.icmp_timeout_secs,
+        };
+        Duration::from_secs(secs)
+    }
+
+    fn remove(&mut self, key: &FlowKey) {
+        let Some(flow) = self.flows.remove(key) else {
+            return;
+        };
+        if flow.half_open() {
+            self.release_half_open(key.src.ip());
+        }
+        let pair = (key.src.ip(), key.dst.ip());
+        if let Some(count) = self.peers.get_mut(&pair) {
+            *count -= 1;
+            if *count == 0 {
+                self.peers.remove(&pair);
+            }
+        }
+    }
+
+    fn release_half_open(&mut self, source: IpAddr) {
+        self.half_open_total = self.half_open_total.saturating_sub(1);
+        if let Some(count) = self.half_open.get_mut(&source) {
+            *count -= 1;
+            if *count == 0 {
+                self.half_open.remove(&source);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ConntrackStats {
+        ConntrackStats { flows: self.flows.len(), half_open: self.half_open_total, ..self.stats.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(src: &str, dst: &str, protocol: FlowProtocol, tcp_flags: u8) -> FlowPacket {
+        FlowPacket { src: src.parse().unwrap(), dst: dst.parse().unwrap(), protocol, tcp_flags, len: 100 }
+    }
+
+    #[test]
+    fn test_tracks_connection_states() {
+        let mut tracker = ConnectionTracker::new(ConntrackConfig { syn_limit_per_source: 2, table_size: 3, ..Default::default() });
+        let now = Instant::now();
+        let syn = packet("10.0.0.1:4000", "10.0.0.2:80", FlowProtocol::Tcp, TCP_SYN);
+        let syn_ack = packet("10.0.0.2:80", "10.0.0.1:4000", FlowProtocol::Tcp, TCP_SYN | TCP_ACK);
+
+        assert_eq!(tracker.track(&syn, now).state(), Some(ConnState::New));
+        assert_eq!(tracker.stats().half_open, 1);
+        assert_eq!(tracker.track(&syn_ack, now).state(), Some(ConnState::Established));
+        assert_eq!(tracker.stats().half_open, 0);
+        assert_eq!(
+            tracker.track(&packet("10.0.0.1:4000", "10.0.0.2:80", FlowProtocol::Tcp, TCP_ACK), now).state(),
+            Some(ConnState::Established)
+        );
+        assert_eq!(tracker.track(&packet("10.0.0.2:80", "10.0.0.1:4001", FlowProtocol::Tcp, TCP_ACK), now).state(), Some(ConnState::Invalid));
+        assert_eq!(tracker.track(&packet("10.0.0.2:0", "10.0.0.1:0", FlowProtocol::Icmp, 0), now).state(), Some(ConnState::Related));
+
+        // Two half-open flows from one source, then the third SYN is a flood
+        tracker.track(&packet("10.0.0.9:1", "10.0.0.2:80", FlowProtocol::Tcp, TCP_SYN), now);
+        tracker.track(&packet("10.0.0.9:2", "10.0.0.2:80", FlowProtocol::Tcp, TCP_SYN), now);
+        assert_eq!(tracker.track(&packet("10.0.0.9:3", "10.0.0.2:80", FlowProtocol::Tcp, TCP_SYN), now), TrackOutcome::SynFlood);
+
+        // The table is full: a new flow evicts a half-open one, not the established flow
+        let later = now + Duration::from_secs(1);
+        assert!(matches!(tracker.track(&packet("10.0.0.3:5", "10.0.0.2:53", FlowProtocol::Udp, 0), later), TrackOutcome::Tracked { .. }));
+        assert_eq!(tracker.stats().evicted, 1);
+        assert_eq!(tracker.track(&syn, later).state(), Some(ConnState::Established));
+
+        // Per-flow rate limit of 2 pps
+        let TrackOutcome::Tracked { flow, .. } = tracker.track(&syn, later) else { panic!("flow is tracked") };
+        assert!(tracker.admit(&flow, 2, later) && tracker.admit(&flow, 2, later));
+        assert!(!tracker.admit(&flow, 2, later));
+        assert!(tracker.admit(&flow, 2, later + Duration::from_millis(500)));
+
+        // Half-open and UDP flows time out long before established TCP
+        assert_eq!(tracker.expire(later + Duration::from_secs(120)), 2);
+        let stats = tracker.stats();
+        assert_eq!((stats.flows, stats.half_open, stats.syn_flood_drops, stats.invalid), (1, 0, 1, 1));
+    }
+}
@@ -0,0 +1,505 @@
+//! DDoS detection and mitigation
+//!
+//! The security program counts SYNs, UDP packets and new requests per source
+//! prefix (a /24 or /64 by default, so an attacker cannot dodge detection by
+//! rotating addresses within a subnet). At the end of every window the
+//! detector compares each prefix against the policy: SYN and UDP floods are
+//! absolute packet rates, request spikes are a multiple of the prefix's own
+//! moving baseline. A prefix over a threshold is mitigated with the action
+//! the policy names for that anomaly — an XDP drop rule or a rate limit —
+//! for a fixed duration, unless it is exempt or mitigation is alert-only.
+//!
+//! Everything the detector does is an event: detections, applied
+//! mitigations, expiries and manual lifts are broadcast and kept in a short
+//! history so operators can review what was blocked and why, and lift a
+//! mitigation that turns out to be a false positive.
+
+use anyhow::{anyhow, Result};
+use aya::maps::lpm_trie::Key;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+/// Name of the LPM trie the XDP program consults for drop and rate-limit rules
+pub const DDOS_BLOCKLIST_MAP: &str = "DDOS_BLOCKLIST";
+
+/// Where the XDP program pins the blocklist
+pub const DEFAULT_BLOCKLIST_PIN: &str = "/sys/fs/bpf/hypermesh/DDOS_BLOCKLIST";
+
+/// `BlocklistRule::action` dropping everything from the prefix
+pub const RULE_DROP: u32 = 1;
+/// `BlocklistRule::action` passing at most `pps` packets per second from the prefix
+pub const RULE_RATE_LIMIT: u32 = 2;
+
+const EVENT_HISTORY: usize = 1000;
+
+/// A source address prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SourcePrefix {
+    pub addr: IpAddr,
+    pub len: u8,
+}
+
+impl SourcePrefix {
+    /// The prefix of `len` bits containing `ip`
+    pub fn of(ip: IpAddr, len: u8) -> Self {
+        let addr = match ip {
+            IpAddr::V4(v4) => {
+                let len = len.min(32);
+                let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+            }
+            IpAddr::V6(v6) => {
+                let len = len.min(128);
+                let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+            }
+        };
+        let max = if ip.is_ipv4() { 32 } else { 128 };
+        Self { addr, len: len.min(max) }
+    }
+
+    /// Parse `addr/len` notation
+    pub fn parse(cidr: &str) -> Result<Self> {
+        let (addr, len) = cidr.split_once('/').ok_or_else(|| anyhow!("Invalid CIDR format: {}", cidr))?;
+        let addr: IpAddr = addr.parse().map_err(|_| anyhow!("Invalid CIDR address: {}", cidr))?;
+        let len: u8 = len.parse().map_err(|_| anyhow!("Invalid CIDR prefix length: {}", cidr))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if len > max {
+            return Err(anyhow!("Invalid CIDR prefix length: {}", cidr));
+        }
+        Ok(Self::of(addr, len))
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        ip.is_ipv4() == self.addr.is_ipv4() && Self::of(ip, self.len) == *self
+    }
+
+    /// Blocklist key; IPv4 prefixes are keyed as IPv4-mapped IPv6 so one trie holds both
+    pub fn trie_key(&self) -> Key<[u8; 16]> {
+        match self.addr {
+            IpAddr::V4(v4) => Key::new(96 + self.len as u32, v4.to_ipv6_mapped().octets()),
+            IpAddr::V6(v6) => Key::new(self.len as u32, v6.octets()),
+        }
+    }
+}
+
+impl fmt::Display for SourcePrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+/// What a counted packet was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketClass {
+    TcpSyn,
+    Udp,
+    /// A new connection or application request
+    Request,
+    Other,
+}
+
+/// Volumetric anomalies the detector recognizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AnomalyKind {
+    SynFlood,
+    UdpFlood,
+    RequestSpike,
+}
+
+/// What to do about a source prefix showing an anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MitigationAction {
+    /// Drop everything from the prefix in XDP
+    Drop,
+    /// Let through at most this many packets per second from the prefix
+    RateLimit { pps: u32 },
+    /// Raise the event but leave traffic alone
+    AlertOnly,
+}
+
+/// Drop or rate-limit rule for a prefix, as the XDP program reads it from the blocklist
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlocklistRule {
+    pub action: u32,
+    pub pps: u32,
+}
+
+// SAFETY: a plain `repr(C)` struct of integers without padding
+unsafe impl aya::Pod for BlocklistRule {}
+
+impl BlocklistRule {
+    /// Kernel rule applying an action; alerts leave traffic alone and have none
+    pub fn for_action(action: MitigationAction) -> Option<Self> {
+        match action {
+            MitigationAction::AlertOnly => None,
+            MitigationAction::Drop => Some(Self { action: RULE_DROP, pps: 0 }),
+            MitigationAction::RateLimit { pps } => Some(Self { action: RULE_RATE_LIMIT, pps }),
+        }
+    }
+}
+
+/// Which anomalies trigger which mitigations, and for how long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MitigationPolicy {
+    pub syn_flood: MitigationAction,
+    pub udp_flood: MitigationAction,
+    pub request_spike: MitigationAction,
+    pub duration_secs: u64,
+    /// Prefixes never mitigated automatically, in CIDR notation
+    pub exempt: Vec<String>,
+    /// Mitigations active at once; detections beyond this only raise events
+    pub max_active: usize,
+}
+
+impl Default for MitigationPolicy {
+    fn default() -> Self {
+        Self {
+            syn_flood: MitigationAction::Drop,
+            udp_flood: MitigationAction::RateLimit { pps: 1000 },
+            request_spike: MitigationAction::RateLimit { pps: 100 },
+            duration_secs: 300,
+            exempt: Vec::new(),
+            max_active: 1024,
+        }
+    }
+}
+
+impl MitigationPolicy {
+    pub fn action(&self, kind: AnomalyKind) -> MitigationAction {
+        match kind {
+            AnomalyKind::SynFlood => self.syn_flood,
+            AnomalyKind::UdpFlood => self.udp_flood,
+            AnomalyKind::RequestSpike => self.request_spike,
+        }
+    }
+}
+
+/// DDoS detection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DdosConfig {
+    pub enabled: bool,
+    pub window_ms: u64,
+    pub prefix_len_v4: u8,
+    pub prefix_len_v6: u8,
+    /// SYNs per second from one prefix that count as a flood
+    pub syn_flood_pps: u64,
+    /// UDP packets per second from one prefix that count as a flood
+    pub udp_flood_pps: u64,
+    /// Request rate, as a multiple of the prefix's baseline, that counts as a spike
+    pub request_spike_factor: f64,
+    /// Request rate below which a prefix is never a spike, whatever its baseline
+    pub min_spike_rps: u64,
+    /// Weight of the latest window in the request baseline
+    pub baseline_alpha: f64,
+    pub policy: MitigationPolicy,
+    /// bpffs path of the pinned blocklist
+    #[serde(default = "default_blocklist_pin")]
+    pub blocklist_pin: String,
+}
+
+fn default_blocklist_pin() -> String {
+    DEFAULT_BLOCKLIST_PIN.to_string()
+}
+
+impl Default for DdosConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_ms: 1000,
+            prefix_len_v4: 24,
+            prefix_len_v6: 64,
+            syn_flood_pps: 10_000,
+            udp_flood_pps: 50_000,
+            request_spike_factor: 10.0,
+            min_spike_rps: 1000,
+            baseline_alpha: 0.1,
+            policy: MitigationPolicy::default(),
+            blocklist_pin: DEFAULT_BLOCKLIST_PIN.to_string(),
+        }
+    }
+}
+
+/// A mitigation in force against a source prefix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mitigation {
+    pub id: u64,
+    pub prefix: SourcePrefix,
+    pub kind: AnomalyKind,
+    pub action: MitigationAction,
+    /// Rate that triggered it, per second
+    pub observed_rate: f64,
+    pub started_at: SystemTime,
+    pub expires_at: SystemTime,
+}
+
+/// Something the detector noticed or did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MitigationEvent {
+    Detected { prefix: SourcePrefix, kind: AnomalyKind, rate: f64, threshold: f64, at: SystemTime },
+    Applied { mitigation: Mitigation },
+    Expired { mitigation: Mitigation, at: SystemTime },
+    Lifted { mitigation: Mitigation, by: String, at: SystemTime },
+}
+
+#[derive(Debug, Default)]
+struct WindowCounters {
+    syn: u64,
+    udp: u64,
+    requests: u64,
+    packets: u64,
+}
+
+/// Per-prefix anomaly detection and the mitigations it has put in place
+pub struct DdosDetector {
+    config: DdosConfig,
+    exempt: Vec<SourcePrefix>,
+    window: HashMap<SourcePrefix, WindowCounters>,
+    request_baseline: HashMap<SourcePrefix, f64>,
+    mitigations: HashMap<SourcePrefix, Mitigation>,
+    next_id: u64,
+    history: VecDeque<MitigationEvent>,
+    events: broadcast::Sender<MitigationEvent>,
+}
+
+impl DdosDetector {
+    pub fn new(config: DdosConfig) -> Result<Self> {
+        let exempt = config.policy.exempt.iter().map(|cidr| SourcePrefix::parse(cidr)).collect::<Result<_>>()?;
+        let (events, _) = broadcast::channel(256);
+        Ok(Self {
+            config,
+            exempt,
+            window: HashMap::new(),
+            request_baseline: HashMap::new(),
+            mitigations: HashMap::new(),
+            next_id: 1,
+            history: VecDeque::new(),
+            events,
+        })
+    }
+
+    pub fn config(&self) -> &DdosConfig {
+        &self.config
+    }
+
+    /// Subscribe to detection and mitigation events
+    pub fn subscribe(&self) -> broadcast::Receiver<MitigationEvent> {
+        self.events.subscribe()
+    }
+
+    /// Recent events, oldest first
+    pub fn history(&self) -> Vec<MitigationEvent> {
+        self.history.iter().cloned().collect()
+    }
+
+    pub fn active_mitigations(&self) -> Vec<Mitigation> {
+        let mut active: Vec<Mitigation> = self.mitigations.values().cloned().collect();
+        active.sort_by_key(|m| m.id);
+        active
+    }
+
+    /// Drop and rate-limit rules for the kernel blocklist
+    pub fn blocklist(&self) -> HashMap<SourcePrefix, BlocklistRule> {
+        self.mitigations
+            .values()
+            .filter_map(|m| Some((m.prefix, BlocklistRule::for_action(m.action)?)))
+            .collect()
+    }
+
+    fn prefix(&self, ip: IpAddr) -> SourcePrefix {
+        let len = if ip.is_ipv4() { self.config.prefix_len_v4 } else { self.config.prefix_len_v6 };
+        SourcePrefix::of(ip, len)
+    }
+
+    /// Count a packet from `src` in the current window
+    pub fn observe(&mut self, src: IpAddr, class: PacketClass) {
+        let prefix = self.prefix(src);
+        let counters = self.window.entry(prefix).or_default();
+        counters.packets += 1;
+        match class {
+            PacketClass::TcpSyn => counters.syn += 1,
+            PacketClass::Udp => counters.udp += 1,
+            PacketClass::Request => counters.requests += 1,
+            PacketClass::Other => {}
+        }
+    }
+
+    /// Mitigation in force for a source, if any
+    pub fn mitigation_for(&self, src: IpAddr) -> Option<&Mitigation> {
+        self.mitigations.get(&self.prefix(src))
+    }
+
+    /// Whether a packet from `src` gets through the mitigations in force;
+    /// rate limits count against the packets already seen this window
+    pub fn admits(&self, src: IpAddr) -> bool {
+        let prefix = self.prefix(src);
+        match self.mitigations.get(&prefix).map(|m| m.action) {
+            Some(MitigationAction::Drop) => false,
+            Some(MitigationAction::RateLimit { pps }) => {
+                let seen = self.window.get(&prefix).map_or(0, |c| c.packets);
+                let allowed = pps as f64 * self.config.window_ms as f64 / 1000.0;
+                (seen as f64) < allowed
+            }
+            Some(MitigationAction::AlertOnly) | None => true,
+        }
+    }
+
+    /// Close the current window: expire mitigations, detect anomalies and
+    /// apply the policy to them
+    pub fn evaluate(&mut self, now: SystemTime) -> Vec<MitigationEvent> {
+        let mut events = Vec::new();
+
+        let expired: Vec<SourcePrefix> =
+            self.mitigations.iter().filter(|(_, m)| m.expires_at <= now).map(|(prefix, _)| *prefix).collect();
+        for prefix in expired {
+            if let Some(mitigation) = self.mitigations.remove(&prefix) {
+                events.push(MitigationEvent::Expired { mitigation, at: now });
+            }
+        }
+
+        let seconds = (self.config.window_ms.max(1) as f64) / 1000.0;
+        let window = std::mem::take(&mut self.window);
+        for (prefix, counters) in window {
+            let request_rate = counters.requests as f64 / seconds;
+            let baseline = self.request_baseline.get(&prefix).copied();
+            let spike_threshold = baseline
+                .map(|b| (b * self.config.request_spike_factor).max(self.config.min_spike_rps as f64))
+                .unwrap_or(f64::INFINITY);
+
+            let anomaly = [
+                (AnomalyKind::SynFlood, counters.syn as f64 / seconds, self.config.syn_flood_pps as f64),
+                (AnomalyKind::UdpFlood, counters.udp as f64 / seconds, self.config.udp_flood_pps as f64),
+                (AnomalyKind::RequestSpike, request_rate, spike_threshold),
+            ]
+            .into_iter()
+            .find(|(_, rate, threshold)| rate >= threshold);
+
+            match anomaly {
+                Some((kind, rate, threshold)) => {
+                    events.push(MitigationEvent::Detected { prefix, kind, rate, threshold, at: now });
+                    if let Some(mitigation) = self.mitigate(prefix, kind, rate, now) {
+                        events.push(MitigationEvent::Applied { mitigation });
+                    }
+                }
+                None => {
+                    // Only normal windows move the baseline, so a flood cannot raise its own bar
+                    let alpha = self.config.baseline_alpha;
+                    let updated = baseline.map_or(request_rate, |b| b + alpha * (request_rate - b));
+                    self.request_baseline.insert(prefix, updated);
+                }
+            }
+        }
+
+        for event in &events {
+            self.record(event.clone());
+        }
+        events
+    }
+
+    fn mitigate(&mut self, prefix: SourcePrefix, kind: AnomalyKind, rate: f64, now: SystemTime) -> Option<Mitigation> {
+        let action = self.config.policy.action(kind);
+        if action == MitigationAction::AlertOnly
+            || self.mitigations.contains_key(&prefix)
+            || self.mitigations.len() >= self.config.policy.max_active
+            || self.exempt.iter().any(|exempt| exempt.contains(prefix.addr))
+        {
+            return None;
+        }
+
+        let mitigation = Mitigation {
+            id: self.next_id,
+            prefix,
+            kind,
+            action,
+            observed_rate: rate,
+            started_at: now,
+            expires_at: now + Duration::from_secs(self.config.policy.duration_secs),
+        };
+        self.next_id += 1;
+        self.mitigations.insert(prefix, mitigation.clone());
+        Some(mitigation)
+    }
+
+    /// Lift a mitigation before it expires
+    pub fn lift(&mut self, id: u64, by: &str, now: SystemTime) -> Result<Mitigation> {
+        let prefix = self
+            .mitigations
+            .iter()
+            .find(|(_, m)| m.id == id)
+            .map(|(prefix, _)| *prefix)
+            .ok_or_else(|| anyhow!("No active mitigation with id {}", id))?;
+        let mitigation = self.mitigations.remove(&prefix).expect("mitigation found above");
+        self.record(MitigationEvent::Lifted { mitigation: mitigation.clone(), by: by.to_string(), at: now });
+        Ok(mitigation)
+    }
+
+    fn record(&mut self, event: MitigationEvent) {
+        // Nobody listening is fine; the history still has it
+        let _ = self.events.send(event.clone());
+        self.history.push_back(event);
+        while self.history.len() > EVENT_HISTORY {
+            self.history.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_and_mitigates_floods_by_prefix() {
+        let config = DdosConfig {
+            syn_flood_pps: 100,
+            min_spike_rps: 50,
+            policy: MitigationPolicy { exempt: vec!["192.168.0.0/16".to_string()], ..Default::default() },
+            ..Default::default()
+        };
+        let mut detector = DdosDetector::new(config).unwrap();
+        let mut events = detector.subscribe();
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        // Establish a request baseline for one prefix
+        for _ in 0..20 {
+            detector.observe("10.1.1.1".parse().unwrap(), PacketClass::Request);
+        }
+        assert!(detector.evaluate(t0).is_empty());
+
+        // SYNs rotated across a /24 add up; the exempt prefix is only reported
+        for host in 0..150u8 {
+            detector.observe(IpAddr::V4(Ipv4Addr::new(203, 0, 113, host)), PacketClass::TcpSyn);
+            detector.observe(IpAddr::V4(Ipv4Addr::new(192, 168, 1, host)), PacketClass::TcpSyn);
+        }
+        for _ in 0..300 {
+            detector.observe("10.1.1.2".parse().unwrap(), PacketClass::Request);
+        }
+        let emitted = detector.evaluate(t0 + Duration::from_secs(1));
+        assert_eq!(emitted.iter().filter(|e| matches!(e, MitigationEvent::Detected { .. })).count(), 3);
+
+        let active = detector.active_mitigations();
+        assert_eq!(active.len(), 2);
+        let syn = active.iter().find(|m| m.kind == AnomalyKind::SynFlood).unwrap();
+        assert_eq!(syn.prefix, SourcePrefix::parse("203.0.113.0/24").unwrap());
+        assert!(!detector.admits("203.0.113.77".parse().unwrap()));
+        assert!(detector.admits("192.168.1.1".parse().unwrap()));
+        assert_eq!(detector.blocklist()[&syn.prefix], BlocklistRule { action: RULE_DROP, pps: 0 });
+        let key = syn.prefix.trie_key();
+        assert_eq!((key.prefix_len(), key.data()), (120, Ipv4Addr::new(203, 0, 113, 0).to_ipv6_mapped().octets()));
+        assert_eq!(detector.mitigation_for("10.1.1.9".parse().unwrap()).unwrap().kind, AnomalyKind::RequestSpike);
+        assert!(matches!(events.try_recv().unwrap(), MitigationEvent::Detected { .. }));
+
+        // Operators can lift a mitigation; the rest expire on their own
+        let lifted = detector.lift(syn.id, "oncall", t0 + Duration::from_secs(2)).unwrap();
+        assert!(detector.admits("203.0.113.77".parse().unwrap()));
+        assert!(detector.lift(lifted.id, "oncall", t0).is_err());
+        let later = detector.evaluate(t0 + Duration::from_secs(400));
+        assert!(matches!(later.as_slice(), [MitigationEvent::Expired { .. }]));
+        assert!(detector.active_mitigations().is_empty());
+        assert!(matches!(detector.history().last(), Some(MitigationEvent::Expired { .. })));
+    }
+}
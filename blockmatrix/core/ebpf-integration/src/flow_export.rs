@@ -0,0 +1,534 @@
+//! Flow export
+//!
+//! The network monitor's eBPF program keeps one entry per flow in the `FLOWS`
+//! hash map, keyed by 5-tuple, counting bytes and packets and stamping the
+//! first and last packet. Userspace folds those entries into a flow cache and
+//! expires flows the way NetFlow/IPFIX meters do: idle flows and flows that
+//! saw FIN or RST are exported and forgotten, and long-lived flows are
+//! exported every active timeout with the traffic since their last export.
+//! Expired records go to the collector as IPFIX (RFC 7011) messages over UDP,
+//! each carrying the templates it uses so a restarted collector can decode
+//! the stream straight away.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+use tracing::debug;
+
+/// Name of the BPF hash map holding per-flow counters
+pub const FLOW_MAP_NAME: &str = "FLOWS";
+
+/// TCP FIN flag as recorded in `FlowCounters::tcp_flags`
+pub const TCP_FIN: u8 = 0x01;
+/// TCP RST flag as recorded in `FlowCounters::tcp_flags`
+pub const TCP_RST: u8 = 0x04;
+
+const IPFIX_VERSION: u16 = 10;
+const TEMPLATE_SET_ID: u16 = 2;
+const IPV4_TEMPLATE_ID: u16 = 256;
+const IPV6_TEMPLATE_ID: u16 = 257;
+const HEADER_LEN: usize = 16;
+const SET_HEADER_LEN: usize = 4;
+
+/// Flow key as the kernel program writes it; IPv4 addresses are stored
+/// IPv4-mapped so both families share one map
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    pub src_addr: [u8; 16],
+    pub dst_addr: [u8; 16],
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub protocol: u8,
+    pub _pad: [u8; 3],
+}
+
+impl FlowKey {
+    pub fn new(src: SocketAddr, dst: SocketAddr, protocol: u8) -> Self {
+        Self {
+            src_addr: mapped(src.ip()),
+            dst_addr: mapped(dst.ip()),
+            src_port: src.port(),
+            dst_port: dst.port(),
+            protocol,
+            _pad: [0; 3],
+        }
+    }
+
+    pub fn src_ip(&self) -> IpAddr {
+        Ipv6Addr::from(self.src_addr).to_canonical()
+    }
+
+    pub fn dst_ip(&self) -> IpAddr {
+        Ipv6Addr::from(self.dst_addr).to_canonical()
+    }
+}
+
+fn mapped(ip: IpAddr) -> [u8; 16] {
+    match ip {
+        IpAddr::V4(v4) => v4.to_ipv6_mapped().octets(),
+        IpAddr::V6(v6) => v6.octets(),
+    }
+}
+
+/// Per-flow counters as the kernel program accumulates them; timestamps are
+/// `bpf_ktime_get_ns` readings
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlowCounters {
+    pub bytes: u64,
+    pub packets: u64,
+    pub first_seen_ns: u64,
+    pub last_seen_ns: u64,
+    pub tcp_flags: u8,
+    pub _pad: [u8; 7],
+}
+
+// SAFETY: both are plain `repr(C)` structs of integers with explicit padding
+unsafe impl aya::Pod for FlowKey {}
+unsafe impl aya::Pod for FlowCounters {}
+
+/// Wall-clock time at which the monotonic clock behind `bpf_ktime_get_ns` read zero
+pub fn monotonic_epoch() -> SystemTime {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid timespec for the kernel to fill in
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    SystemTime::now() - Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// Why a flow record was exported, as IPFIX `flowEndReason` numbers it
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowEndReason {
+    IdleTimeout = 1,
+    ActiveTimeout = 2,
+    EndOfFlow = 3,
+    ForcedEnd = 4,
+    LackOfResources = 5,
+}
+
+/// An exported flow record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowRecord {
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub protocol: u8,
+    pub bytes: u64,
+    pub packets: u64,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub tcp_flags: u8,
+    pub end_reason: FlowEndReason,
+}
+
+/// Flow export configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowExportConfig {
+    pub enabled: bool,
+    /// IPFIX collector to send records to; without one flows are only aggregated
+    pub collector: Option<SocketAddr>,
+    pub export_interval_secs: u64,
+    /// Long-lived flows are exported at least this often
+    pub active_timeout_secs: u64,
+    /// Flows without packets for this long are exported and forgotten
+    pub idle_timeout_secs: u64,
+    /// Flows tracked at once; the least recently seen is exported early beyond this
+    pub max_flows: usize,
+    pub observation_domain_id: u32,
+}
+
+impl Default for FlowExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            collector: None,
+            export_interval_secs: 10,
+            active_timeout_secs: 60,
+            idle_timeout_secs: 15,
+            max_flows: 65536,
+            observation_domain_id: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedFlow {
+    bytes: u64,
+    packets: u64,
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+    tcp_flags: u8,
+    exported_bytes: u64,
+    exported_packets: u64,
+    /// End of the last active-timeout export, where the next record starts
+    exported_until: Option<SystemTime>,
+}
+
+/// Flows expired by one pass over the cache
+#[derive(Debug, Default)]
+pub struct ExpiredFlows {
+    pub records: Vec<FlowRecord>,
+    /// Flows that are finished and should be deleted from the BPF map
+    pub finished: Vec<FlowKey>,
+}
+
+/// Userspace flow cache fed from the BPF flow map
+pub struct FlowCache {
+    config: FlowExportConfig,
+    flows: HashMap<FlowKey, CachedFlow>,
+    evicted: ExpiredFlows,
+}
+
+impl FlowCache {
+    pub fn new(config: FlowExportConfig) -> Self {
+        Self {
+            config,
+            flows: HashMap::new(),
+            evicted: ExpiredFlows::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.flows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+
+    /// Count one packet, as the kernel program does for packets it sees
+    pub fn record_packet(&mut self, key: FlowKey, bytes: u64, tcp_flags: u8, now: SystemTime) {
+        let flow = self.entry(key, now);
+        flow.bytes += bytes;
+        flow.packets += 1;
+        flow.last_seen = flow.last_seen.max(now);
+        flow.tcp_flags |= tcp_flags;
+    }
+
+    /// Fold in the cumulative counters of a BPF map entry
+    pub fn merge_map_entry(&mut self, key: FlowKey, counters: &FlowCounters, epoch: SystemTime) {
+        let first_seen = epoch + Duration::from_nanos(counters.first_seen_ns);
+        let last_seen = epoch + Duration::from_nanos(counters.last_seen_ns);
+        let flow = self.entry(key, first_seen);
+        flow.bytes = flow.bytes.max(counters.bytes);
+        flow.packets = flow.packets.max(counters.packets);
+        flow.first_seen = flow.first_seen.min(first_seen);
+        flow.last_seen = flow.last_seen.max(last_seen);
+        flow.tcp_flags |= counters.tcp_flags;
+    }
+
+    fn entry(&mut self, key: FlowKey, now: SystemTime) -> &mut CachedFlow {
+        if !self.flows.contains_key(&key) && self.flows.len() >= self.config.max_flows {
+            self.evict_least_recent();
+        }
+        self.flows.entry(key).or_insert_with(|| CachedFlow {
+            bytes: 0,
+            packets: 0,
+            first_seen: now,
+            last_seen: now,
+            tcp_flags: 0,
+            exported_bytes: 0,
+            exported_packets: 0,
+            exported_until: None,
+        })
+    }
+
+    fn evict_least_recent(&mut self) {
+        let Some(key) = self.flows.iter().min_by_key(|(_, flow)| flow.last_seen).map(|(key, _)| *key) else {
+            return;
+        };
+        if let Some(flow) = self.flows.remove(&key) {
+            self.evicted.records.extend(final_record(&key, &flow, FlowEndReason::LackOfResources));
+            self.evicted.finished.push(key);
+        }
+    }
+
+    /// Export flows whose idle or active timeout has passed or that ended
+    pub fn expire(&mut self, now: SystemTime) -> ExpiredFlows {
+        let idle_timeout = Duration::from_secs(self.config.idle_timeout_secs);
+        let active_timeout = Duration::from_secs(self.config.active_timeout_secs);
+        let mut expired = std::mem::take(&mut self.evicted);
+
+        self.flows.retain(|key, flow| {
+            let since = |t: SystemTime| now.duration_since(t).unwrap_or_default();
+            let reason = if flow.tcp_flags & (TCP_FIN | TCP_RST) != 0 {
+                FlowEndReason::EndOfFlow
+            } else if since(flow.last_seen) >= idle_timeout {
+                FlowEndReason::IdleTimeout
+            } else if since(flow.exported_until.unwrap_or(flow.first_seen)) >= active_timeout {
+                FlowEndReason::ActiveTimeout
+            } else {
+                return true;
+            };
+
+            if reason == FlowEndReason::ActiveTimeout {
+                if flow.packets > flow.exported_packets {
+                    expired.records.extend(final_record(key, flow, reason));
+                }
+                flow.exported_bytes = flow.bytes;
+                flow.exported_packets = flow.packets;
+                flow.exported_until = Some(flow.last_seen);
+                return true;
+            }
+
+            expired.records.extend(final_record(key, flow, reason));
+            expired.finished.push(*key);
+            false
+        });
+        expired
+    }
+
+    /// Export every flow, as when the monitor stops
+    pub fn flush(&mut self) -> ExpiredFlows {
+        let mut expired = std::mem::take(&mut self.evicted);
+        for (key, flow) in self.flows.drain() {
+            expired.records.extend(final_record(&key, &flow, FlowEndReason::ForcedEnd));
+            expired.finished.push(key);
+        }
+        expired
+    }
+
+    /// Current flows with their totals so far
+    pub fn snapshot(&self) -> Vec<FlowRecord> {
+        self.flows
+            .iter()
+            .map(|(key, flow)| FlowRecord {
+                src_ip: key.src_ip(),
+                dst_ip: key.dst_ip(),
+                src_port: key.src_port,
+                dst_port: key.dst_port,
+                protocol: key.protocol,
+                bytes: flow.bytes,
+                packets: flow.packets,
+                start: flow.first_seen,
+                end: flow.last_seen,
+                tcp_flags: flow.tcp_flags,
+                end_reason: FlowEndReason::ForcedEnd,
+            })
+            .collect()
+    }
+}
+
+/// Record of the traffic since the flow's last export, if there was any
+fn final_record(key: &FlowKey, flow: &CachedFlow, end_reason: FlowEndReason) -> Option<FlowRecord> {
+    if flow.packets <= flow.exported_packets {
+        return None;
+    }
+    Some(FlowRecord {
+        src_ip: key.src_ip(),
+        dst_ip: key.dst_ip(),
+        src_port: key.src_port,
+        dst_port: key.dst_port,
+        protocol: key.protocol,
+        bytes: flow.bytes - flow.exported_bytes,
+        packets: flow.packets - flow.exported_packets,
+        start: flow.exported_until.unwrap_or(flow.first_seen),
+        end: flow.last_seen,
+        tcp_flags: flow.tcp_flags,
+        end_reason,
+    })
+}
+
+/// Information elements of the flow templates, after the addresses:
+/// ports, protocol, TCP flags, octet and packet deltas, start and end
+/// milliseconds, end reason
+const COMMON_FIELDS: [(u16, u16); 9] = [(7, 2), (11, 2), (4, 1), (6, 2), (1, 8), (2, 8), (152, 8), (153, 8), (136, 1)];
+const IPV4_ADDRESS_FIELDS: [(u16, u16); 2] = [(8, 4), (12, 4)];
+const IPV6_ADDRESS_FIELDS: [(u16, u16); 2] = [(27, 16), (28, 16)];
+
+fn record_len(address_len: usize) -> usize {
+    2 * address_len + COMMON_FIELDS.iter().map(|(_, len)| *len as usize).sum::<usize>()
+}
+
+/// Encodes flow records as IPFIX messages
+pub struct IpfixEncoder {
+    observation_domain_id: u32,
+    sequence: u32,
+    max_message_len: usize,
+}
+
+impl IpfixEncoder {
+    pub fn new(observation_domain_id: u32) -> Self {
+        Self {
+            observation_domain_id,
+            sequence: 0,
+            // Stays under a typical path MTU once IP and UDP headers are added
+            max_message_len: 1400,
+        }
+    }
+
+    /// Encode records into as many messages as it takes
+    pub fn encode(&mut self, records: &[FlowRecord], export_time: SystemTime) -> Vec<Vec<u8>> {
+        let (v4, v6): (Vec<&FlowRecord>, Vec<&FlowRecord>) =
+            records.iter().partition(|record| record.src_ip.is_ipv4() && record.dst_ip.is_ipv4());
+
+        let mut messages = Vec::new();
+        for (records, template_id, address_len) in [(v4, IPV4_TEMPLATE_ID, 4), (v6, IPV6_TEMPLATE_ID, 16)] {
+            let room = self.max_message_len - HEADER_LEN - template_set_len() - SET_HEADER_LEN;
+            let per_message = (room / record_len(address_len)).max(1);
+            for chunk in records.chunks(per_message) {
+                messages.push(self.message(chunk, template_id, address_len, export_time));
+            }
+        }
+        messages
+    }
+
+    fn message(&mut self, records: &[&FlowRecord], template_id: u16, address_len: usize, export_time: SystemTime) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.max_message_len);
+        buf.extend_from_slice(&IPFIX_VERSION.to_be_bytes());
+        buf.extend_from_slice(&0u16.to_be_bytes()); // length, filled in below
+        buf.extend_from_slice(&(unix_millis(export_time) / 1000).to_be_bytes()[4..]);
+        buf.extend_from_slice(&self.sequence.to_be_bytes());
+        buf.extend_from_slice(&self.observation_domain_id.to_be_bytes());
+
+        write_template_set(&mut buf);
+
+        let set_len = SET_HEADER_LEN + records.len() * record_len(address_len);
+        buf.extend_from_slice(&template_id.to_be_bytes());
+        buf.extend_from_slice(&(set_len as u16).to_be_bytes());
+        for record in records {
+            write_address(&mut buf, record.src_ip);
+            write_address(&mut buf, record.dst_ip);
+            buf.extend_from_slice(&record.src_port.to_be_bytes());
+            buf.extend_from_slice(&record.dst_port.to_be_bytes());
+            buf.push(record.protocol);
+            buf.extend_from_slice(&(record.tcp_flags as u16).to_be_bytes());
+            buf.extend_from_slice(&record.bytes.to_be_bytes());
+            buf.extend_from_slice(&record.packets.to_be_bytes());
+            buf.extend_from_slice(&unix_millis(record.start).to_be_bytes());
+            buf.extend_from_slice(&unix_millis(record.end).to_be_bytes());
+            buf.push(record.end_reason as u8);
+        }
+
+        let len = buf.len() as u16;
+        buf[2..4].copy_from_slice(&len.to_be_bytes());
+        self.sequence = self.sequence.wrapping_add(records.len() as u32);
+        buf
+    }
+}
+
+fn template_set_len() -> usize {
+    let template_len = 4 + 4 * (IPV4_ADDRESS_FIELDS.len() + COMMON_FIELDS.len());
+    SET_HEADER_LEN + 2 * template_len
+}
+
+fn write_template_set(buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&TEMPLATE_SET_ID.to_be_bytes());
+    buf.extend_from_slice(&(template_set_len() as u16).to_be_bytes());
+    for (template_id, addresses) in [(IPV4_TEMPLATE_ID, IPV4_ADDRESS_FIELDS), (IPV6_TEMPLATE_ID, IPV6_ADDRESS_FIELDS)] {
+        buf.extend_from_slice(&template_id.to_be_bytes());
+        buf.extend_from_slice(&((addresses.len() + COMMON_FIELDS.len()) as u16).to_be_bytes());
+        for (element, len) in addresses.iter().chain(COMMON_FIELDS.iter()) {
+            buf.extend_from_slice(&element.to_be_bytes());
+            buf.extend_from_slice(&len.to_be_bytes());
+        }
+    }
+}
+
+fn write_address(buf: &mut Vec<u8>, ip: IpAddr) {
+    match ip {
+        IpAddr::V4(v4) => buf.extend_from_slice(&v4.octets()),
+        IpAddr::V6(v6) => buf.extend_from_slice(&v6.octets()),
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Sends flow records to an IPFIX collector over UDP
+pub struct FlowExporter {
+    collector: SocketAddr,
+    socket: UdpSocket,
+    encoder: IpfixEncoder,
+}
+
+impl FlowExporter {
+    pub async fn connect(collector: SocketAddr, observation_domain_id: u32) -> Result<Self> {
+        let bind: SocketAddr = match collector {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(bind).await.context("failed to bind flow export socket")?;
+        Ok(Self {
+            collector,
+            socket,
+            encoder: IpfixEncoder::new(observation_domain_id),
+        })
+    }
+
+    /// Send records to the collector, returning how many messages it took
+    pub async fn export(&mut self, records: &[FlowRecord]) -> Result<usize> {
+        let messages = self.encoder.encode(records, SystemTime::now());
+        for message in &messages {
+            self.socket
+                .send_to(message, self.collector)
+                .await
+                .with_context(|| format!("failed to send flow records to {}", self.collector))?;
+        }
+        debug!("Exported {} flow records to {} in {} messages", records.len(), self.collector, messages.len());
+        Ok(messages.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates_expires_and_encodes_flows() {
+        let config = FlowExportConfig { max_flows: 2, ..Default::default() };
+        let mut cache = FlowCache::new(config);
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let at = |secs| t0 + Duration::from_secs(secs);
+        let web = FlowKey::new("10.0.0.1:40000".parse().unwrap(), "10.0.0.2:443".parse().unwrap(), 6);
+        let dns = FlowKey::new("[fd00::1]:5353".parse().unwrap(), "[fd00::2]:53".parse().unwrap(), 17);
+
+        cache.record_packet(web, 1500, 0, t0);
+        cache.record_packet(web, 500, 0, at(5));
+        cache.record_packet(dns, 80, 0, t0);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.expire(at(10)).records.is_empty());
+
+        // The DNS flow goes idle; the web flow is exported by its active timeout and kept
+        cache.record_packet(web, 1000, 0, at(55));
+        let expired = cache.expire(at(60));
+        assert_eq!(expired.records.len(), 2);
+        assert_eq!(expired.finished, vec![dns]);
+        let web_record = expired.records.iter().find(|r| r.end_reason == FlowEndReason::ActiveTimeout).unwrap();
+        assert_eq!((web_record.bytes, web_record.packets, web_record.start), (3000, 3, t0));
+        assert_eq!(web_record.src_ip, "10.0.0.1".parse::<IpAddr>().unwrap());
+
+        // Only the traffic since the last export goes out when the flow ends
+        cache.record_packet(web, 40, TCP_FIN, at(70));
+        let expired = cache.expire(at(71));
+        assert_eq!(expired.records.len(), 1);
+        assert_eq!(expired.records[0].end_reason, FlowEndReason::EndOfFlow);
+        assert_eq!((expired.records[0].bytes, expired.records[0].start), (40, at(55)));
+        assert!(cache.is_empty());
+
+        // Kernel counters are cumulative and merge idempotently
+        let counters = FlowCounters { bytes: 900, packets: 3, first_seen_ns: 1_000, last_seen_ns: 2_000, ..Default::default() };
+        cache.merge_map_entry(web, &counters, t0);
+        cache.merge_map_entry(web, &counters, t0);
+        assert_eq!((cache.snapshot()[0].bytes, cache.snapshot()[0].packets), (900, 3));
+
+        // Each message has a header, both templates and one data set
+        let records = vec![web_record.clone(); 30];
+        let mut encoder = IpfixEncoder::new(7);
+        let messages = encoder.encode(&records, at(60));
+        assert_eq!(messages.len(), 2);
+        let first = &messages[0];
+        assert_eq!(u16::from_be_bytes([first[0], first[1]]), IPFIX_VERSION);
+        assert_eq!(u16::from_be_bytes([first[2], first[3]]) as usize, first.len());
+        assert!(first.len() <= 1400);
+        let per_message = (first.len() - HEADER_LEN - template_set_len() - SET_HEADER_LEN) / record_len(4);
+        assert_eq!(u32::from_be_bytes(messages[1][8..12].try_into().unwrap()), per_message as u32);
+        assert_eq!(u32::from_be_bytes(first[12..16].try_into().unwrap()), 7);
+    }
+}
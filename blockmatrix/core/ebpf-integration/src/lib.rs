@@ -1,7 +1,9 @@
 //! eBPF Integration for Nexus
 //! 
 //! Provides kernel-level networking, monitoring, and policy enforcement
-//! using eBPF programs for high-performance packet processing.
+//! using eBPF programs for high-performance packet processing. Without the
+//! privileges to load them, components fall back to slower user-space
+//! implementations (see `capabilities`).
 
 use anyhow::Result;
 use nexus_shared::*;
@@ -16,6 +18,21 @@ pub mod security_policy;
 pub mod metrics;
 pub mod programs;
 pub mod dns_ct;
+pub mod flow_export;
+pub mod ddos;
+pub mod quota;
+pub mod xdp_lb;
+pub mod capabilities;
+pub mod userspace;
+pub mod conntrack;
+
+pub use flow_export::{FlowExportConfig, FlowRecord, FlowEndReason};
+pub use conntrack::{ConnState, ConntrackConfig, ConntrackStats, FlowEviction, FlowPacket, FlowProtocol};
+pub use security_policy::RuleCounters;
+pub use ddos::{BlocklistRule, DdosConfig, Mitigation, MitigationAction, MitigationEvent, MitigationPolicy};
+pub use quota::{EgressPolicy, QuotaConfig, QuotaEnforcement, QuotaEvent, QuotaPeriod, WorkloadQuota, WorkloadUsage};
+pub use xdp_lb::{EndpointTraffic, L4Protocol, XdpLbConfig, XdpMode};
+pub use capabilities::{CapabilityReport, ComponentReport, ExecutionMode};
 
 /// Main eBPF manager that coordinates all eBPF programs
 pub struct EbpfManager {
@@ -26,6 +43,7 @@ pub struct EbpfManager {
     security_policy: Option<security_policy::SecurityPolicyEngine>,
     dns_ct: Option<dns_ct::DnsCtManager>,
     metrics: metrics::EbpfMetrics,
+    capabilities: CapabilityReport,
 }
 
 impl EbpfManager {
@@ -33,8 +51,15 @@ impl EbpfManager {
     pub async fn new(config: &EbpfConfig) -> Result<Self> {
         info!("🔧 Initializing eBPF manager");
 
-        // Check for required capabilities
-        Self::check_capabilities()?;
+        // Check for required capabilities, degrading to user space without them
+        let capabilities = CapabilityReport::probe();
+        if let Err(missing) = capabilities.missing() {
+            if !config.allow_degraded {
+                return Err(missing);
+            }
+            warn!("⚠️  {}; running components in user space", missing);
+        }
+        let mode = capabilities.mode();
 
         let mut manager = Self {
             programs: RwLock::new(HashMap::new()),
@@ -44,26 +69,31 @@ impl EbpfManager {
             security_policy: None,
             dns_ct: None,
             metrics: metrics::EbpfMetrics::new(),
+            capabilities,
         };
 
         // Initialize components based on configuration
         if config.network_monitoring {
-            manager.network_monitor = Some(network_monitor::NetworkMonitor::new(config).await?);
+            manager.network_monitor = Some(network_monitor::NetworkMonitor::new(config).await?.with_mode(mode));
+            manager.capabilities.record("network-monitor", &["Interface and socket totals only, without per-flow or latency data"]);
             info!("📊 Network monitoring enabled");
         }
 
         if config.traffic_control {
             manager.traffic_control = Some(traffic_control::TrafficController::new(config).await?);
+            manager.capabilities.record("traffic-control", &["Shaping and quota enforcement are not applied to packets"]);
             info!("🚦 Traffic control enabled");
         }
 
         if config.load_balancing {
-            manager.load_balancer = Some(load_balancer::LoadBalancer::new(config).await?);
+            manager.load_balancer = Some(load_balancer::LoadBalancer::new(config).await?.with_mode(mode));
+            manager.capabilities.record("load-balancer", &["TCP services only, proxied through this process", "No per-endpoint packet counts"]);
             info!("⚖️  Load balancing enabled");
         }
 
         if config.security_policies {
             manager.security_policy = Some(security_policy::SecurityPolicyEngine::new(config).await?);
+            manager.capabilities.record("security-policy", &["Policies and DDoS mitigations are evaluated but not enforced on packets"]);
             info!("🔒 Security policies enabled");
         }
 
@@ -71,6 +101,7 @@ impl EbpfManager {
         if config.dns_ct_enabled {
             let dns_ct_config = dns_ct::DnsCtConfig::default();
             manager.dns_ct = Some(dns_ct::DnsCtManager::new(dns_ct_config).await?);
+            manager.capabilities.record("dns-ct", &["DNS and certificate events are not captured from packets"]);
             info!("🌐 DNS/CT eBPF enabled");
         }
 
@@ -165,6 +196,17 @@ impl EbpfManager {
         }
     }
 
+    /// Balance a service address over the service's endpoints in the XDP datapath
+    pub async fn expose_service(&self, service: &str, vip: std::net::SocketAddr, protocol: L4Protocol) -> Result<()> {
+        info!("⚖️  Exposing service {} on {} {:?}", service, vip, protocol);
+
+        if let Some(ref balancer) = self.load_balancer {
+            balancer.expose_service(service, vip, protocol).await
+        } else {
+            Err(anyhow::anyhow!("Load balancing not enabled"))
+        }
+    }
+
     /// Apply security policy
     pub async fn apply_security_policy(&self, policy: SecurityPolicy) -> Result<()> {
         info!("🔒 Applying security policy: {}", policy.name);
@@ -176,53 +218,79 @@ impl EbpfManager {
         }
     }
 
-    /// Get comprehensive eBPF metrics
-    pub async fn metrics(&self) -> Result<metrics::EbpfMetricsSnapshot> {
-        self.metrics.snapshot().await
+    /// DDoS mitigations currently in force
+    pub async fn ddos_mitigations(&self) -> Result<Vec<Mitigation>> {
+        if let Some(ref engine) = self.security_policy {
+            Ok(engine.active_mitigations().await)
+        } else {
+            Err(anyhow::anyhow!("Security policies not enabled"))
+        }
     }
 
-    /// Check if the system has required capabilities for eBPF
-    fn check_capabilities() -> Result<()> {
-        #[cfg(target_os = "linux")]
-        {
-            use caps::{Capability, CapSet};
-            
-            let caps = caps::read(None, CapSet::Effective)?;
-            
-            if !caps.contains(&Capability::CAP_SYS_ADMIN) {
-                return Err(anyhow::anyhow!(
-                    "CAP_SYS_ADMIN capability required for eBPF programs"
-                ));
-            }
+    /// Lift a DDoS mitigation on an operator's behalf
+    pub async fn lift_ddos_mitigation(&self, id: u64, operator: &str) -> Result<Mitigation> {
+        info!("🔓 Lifting DDoS mitigation {} for {}", id, operator);
 
-            if !caps.contains(&Capability::CAP_NET_ADMIN) {
-                return Err(anyhow::anyhow!(
-                    "CAP_NET_ADMIN capability required for network eBPF programs"
-                ));
-            }
+        if let Some(ref engine) = self.security_policy {
+            engine.lift_mitigation(id, operator).await
+        } else {
+            Err(anyhow::anyhow!("Security policies not enabled"))
         }
+    }
 
-        // Check for eBPF support in kernel
-        if !Self::kernel_supports_ebpf()? {
-            return Err(anyhow::anyhow!(
-                "Kernel does not support required eBPF features"
-            ));
+    /// Attribute a workload's cgroup traffic to it for quota accounting
+    pub async fn register_workload(&self, workload: &str, cgroup_id: u64) -> Result<()> {
+        if let Some(ref controller) = self.traffic_control {
+            controller.register_workload(workload, cgroup_id).await;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Traffic control not enabled"))
         }
+    }
 
-        Ok(())
+    /// Set or replace a workload's network transfer quota
+    pub async fn set_workload_quota(&self, workload: &str, quota: WorkloadQuota) -> Result<()> {
+        if let Some(ref controller) = self.traffic_control {
+            controller.set_workload_quota(workload, quota).await
+        } else {
+            Err(anyhow::anyhow!("Traffic control not enabled"))
+        }
     }
 
-    /// Check if kernel supports eBPF features
-    fn kernel_supports_ebpf() -> Result<bool> {
-        // Check /proc/version for minimum kernel version
-        let version_info = std::fs::read_to_string("/proc/version")
-            .unwrap_or_else(|_| "Unknown".to_string());
-        
-        debug!("Kernel version: {}", version_info);
-        
-        // For this demo, assume eBPF is supported
-        // In production, this would parse kernel version and check specific features
-        Ok(true)
+    /// Transfer of every workload in its current quota period
+    pub async fn workload_usage(&self) -> Result<Vec<WorkloadUsage>> {
+        if let Some(ref controller) = self.traffic_control {
+            Ok(controller.workload_usage().await)
+        } else {
+            Err(anyhow::anyhow!("Traffic control not enabled"))
+        }
+    }
+
+    /// Subscribe to quota threshold, enforcement and reset events
+    pub async fn quota_events(&self) -> Result<tokio::sync::broadcast::Receiver<QuotaEvent>> {
+        if let Some(ref controller) = self.traffic_control {
+            Ok(controller.quota_events().await)
+        } else {
+            Err(anyhow::anyhow!("Traffic control not enabled"))
+        }
+    }
+
+    /// Get comprehensive eBPF metrics
+    pub async fn metrics(&self) -> Result<metrics::EbpfMetricsSnapshot> {
+        let mut snapshot = self.metrics.snapshot().await?;
+        if let Some(ref balancer) = self.load_balancer {
+            snapshot.endpoints = balancer.endpoint_traffic().await?;
+        }
+        if let Some(ref engine) = self.security_policy {
+            snapshot.rules = engine.rule_counters().await;
+            snapshot.conntrack = engine.conntrack_stats().await;
+        }
+        Ok(snapshot)
+    }
+
+    /// Privileges found at start-up and what each component runs as
+    pub fn capabilities(&self) -> &CapabilityReport {
+        &self.capabilities
     }
 }
 
@@ -235,8 +303,25 @@ pub struct EbpfConfig {
     pub security_policies: bool,
     pub dns_ct_enabled: bool,
     pub interfaces: Vec<String>,
+    /// Run components in user space rather than refuse to start without eBPF privileges
+    #[serde(default = "default_allow_degraded")]
+    pub allow_degraded: bool,
     pub log_level: String,
     pub metrics_interval_ms: u64,
+    #[serde(default)]
+    pub flow_export: FlowExportConfig,
+    #[serde(default)]
+    pub ddos: DdosConfig,
+    #[serde(default)]
+    pub conntrack: ConntrackConfig,
+    #[serde(default)]
+    pub quota: QuotaConfig,
+    #[serde(default)]
+    pub xdp_lb: XdpLbConfig,
+}
+
+fn default_allow_degraded() -> bool {
+    true
 }
 
 impl Default for EbpfConfig {
@@ -248,8 +333,14 @@ impl Default for EbpfConfig {
             security_policies: true,
             dns_ct_enabled: true,
             interfaces: vec!["eth0".to_string(), "lo".to_string()],
+            allow_degraded: true,
             log_level: "info".to_string(),
             metrics_interval_ms: 1000,
+            flow_export: FlowExportConfig::default(),
+            ddos: DdosConfig::default(),
+            conntrack: ConntrackConfig::default(),
+            quota: QuotaConfig::default(),
+            xdp_lb: XdpLbConfig::default(),
         }
     }
 }
@@ -312,6 +403,7 @@ pub struct SecurityPolicy {
     pub name: String,
     pub rules: Vec<SecurityRule>,
     pub action: PolicyAction,
+    /// Policies with a higher priority are evaluated first
     pub priority: u32,
 }
 
@@ -321,6 +413,12 @@ pub struct SecurityRule {
     pub destination_port: Option<u16>,
     pub protocol: Option<String>,
     pub rate_limit: Option<u32>,
+    /// Connection states the rule matches; empty matches any
+    #[serde(default)]
+    pub conn_states: Vec<ConnState>,
+    /// Packets per second each matching flow may send
+    #[serde(default)]
+    pub flow_rate_limit: Option<u32>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -386,6 +484,8 @@ mod tests {
                 destination_port: Some(80),
                 protocol: Some("TCP".to_string()),
                 rate_limit: Some(100),
+                conn_states: Vec::new(),
+                flow_rate_limit: None,
             }],
             action: PolicyAction::Allow,
             priority: 1,
@@ -1,24 +1,36 @@
 //! Load balancing using eBPF programs
 //!
 //! Provides high-performance layer 4 load balancing with various algorithms
-//! and health checking at the kernel level.
+//! and health checking at the kernel level. Services exposed on a virtual
+//! address are balanced in the XDP datapath (see `xdp_lb`), which is
+//! reprogrammed whenever their endpoints change. In degraded mode a TCP
+//! proxy on each virtual address takes the datapath's place (see `userspace`).
 
 use anyhow::Result;
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::time::Instant;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use crate::capabilities::ExecutionMode;
+use crate::userspace::TcpProxy;
+use crate::xdp_lb::{EndpointTraffic, L4Protocol, XdpLoadBalancer};
 use crate::{EbpfConfig, EbpfProgram, ServiceEndpoint, EndpointHealth};
 
 /// Load balancer using eBPF for high-performance traffic distribution
 pub struct LoadBalancer {
     config: EbpfConfig,
     running: bool,
+    mode: ExecutionMode,
     service_pools: RwLock<HashMap<String, ServicePool>>,
     load_balancing_stats: RwLock<LoadBalancingStats>,
     health_checker: RwLock<HealthChecker>,
+    xdp: RwLock<XdpLoadBalancer>,
+    /// Virtual address and protocol each exposed service is balanced on
+    exposed: RwLock<HashMap<String, (SocketAddr, L4Protocol)>>,
+    /// User-space proxies of exposed services, in degraded mode
+    proxies: RwLock<HashMap<String, TcpProxy>>,
 }
 
 impl LoadBalancer {
@@ -28,12 +40,22 @@ impl LoadBalancer {
         Ok(Self {
             config: config.clone(),
             running: false,
+            mode: ExecutionMode::Kernel,
             service_pools: RwLock::new(HashMap::new()),
             load_balancing_stats: RwLock::new(LoadBalancingStats::new()),
             health_checker: RwLock::new(HealthChecker::new()),
+            xdp: RwLock::new(XdpLoadBalancer::new(config.xdp_lb.clone())),
+            exposed: RwLock::new(HashMap::new()),
+            proxies: RwLock::new(HashMap::new()),
         })
     }
 
+    /// Balance exposed services with user-space proxies instead of XDP
+    pub fn with_mode(mut self, mode: ExecutionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     /// Update endpoints for a service
     pub async fn update_endpoints(&self, service: &str, endpoints: Vec<ServiceEndpoint>) -> Result<()> {
         info!("🎯 Updating endpoints for service: {} ({} endpoints)", service, endpoints.len());
@@ -43,11 +65,74 @@ impl LoadBalancer {
             .or_insert_with(|| ServicePool::new(service, LoadBalancingAlgorithm::RoundRobin));
         
         pool.update_endpoints(endpoints);
+
+        if let Some(proxy) = self.proxies.read().await.get(service) {
+            proxy.set_endpoints(pool.get_all_endpoints()).await;
+        } else if let Some((vip, protocol)) = self.exposed.read().await.get(service) {
+            self.xdp.write().await.set_service(*vip, *protocol, pool.get_all_endpoints())?;
+        }
         
         debug!("Updated service pool for {}", service);
         Ok(())
     }
 
+    /// Balance a virtual address over a service's endpoints in the XDP datapath
+    ///
+    /// In user space the address is proxied instead, which only works for TCP.
+    pub async fn expose_service(&self, service: &str, vip: SocketAddr, protocol: L4Protocol) -> Result<()> {
+        let pools = self.service_pools.read().await;
+        let endpoints = pools.get(service).map(|pool| pool.get_all_endpoints()).unwrap_or_default();
+
+        let mut exposed = self.exposed.write().await;
+        if self.mode == ExecutionMode::UserSpace {
+            let mut proxies = self.proxies.write().await;
+            if exposed.get(service) != Some(&(vip, protocol)) {
+                let proxy = TcpProxy::bind(vip, protocol).await?;
+                if let Some(old) = proxies.insert(service.to_string(), proxy) {
+                    old.shutdown().await;
+                }
+            }
+            if let Some(proxy) = proxies.get(service) {
+                proxy.set_endpoints(endpoints).await;
+            }
+            exposed.insert(service.to_string(), (vip, protocol));
+            return Ok(());
+        }
+
+
+        let mut xdp = self.xdp.write().await;
+        if let Some((old_vip, old_protocol)) = exposed.get(service) {
+            if (*old_vip, *old_protocol) != (vip, protocol) {
+                xdp.remove_service(*old_vip, *old_protocol)?;
+            }
+        }
+        xdp.set_service(vip, protocol, endpoints)?;
+        exposed.insert(service.to_string(), (vip, protocol));
+        Ok(())
+    }
+
+    /// Packets and bytes the XDP datapath, or the proxies, have sent to each endpoint
+    pub async fn endpoint_traffic(&self) -> Result<Vec<EndpointTraffic>> {
+        let mut traffic = self.xdp.read().await.endpoint_traffic()?;
+        for proxy in self.proxies.read().await.values() {
+            traffic.extend(proxy.traffic().await);
+        }
+        Ok(traffic)
+    }
+
+    /// Proxy the services exposed before a restart
+    async fn start_proxies(&self) -> Result<()> {
+        let pools = self.service_pools.read().await;
+        let exposed = self.exposed.read().await;
+        let mut proxies = self.proxies.write().await;
+        for (service, (vip, protocol)) in exposed.iter().filter(|(service, _)| !proxies.contains_key(*service)) {
+            let proxy = TcpProxy::bind(*vip, *protocol).await?;
+            proxy.set_endpoints(pools.get(service).map(|pool| pool.get_all_endpoints()).unwrap_or_default()).await;
+            proxies.insert(service.clone(), proxy);
+        }
+        Ok(())
+    }
+
     /// Get the next endpoint for a service using load balancing algorithm
     pub async fn get_endpoint(&self, service: &str) -> Option<ServiceEndpoint> {
         let pools = self.service_pools.read().await;
@@ -109,6 +194,13 @@ impl LoadBalancer {
         
         let mut pools = self.service_pools.write().await;
         pools.remove(service);
+
+        if let Some(proxy) = self.proxies.write().await.remove(service) {
+            proxy.shutdown().await;
+        }
+        if let Some((vip, protocol)) = self.exposed.write().await.remove(service) {
+            self.xdp.write().await.remove_service(vip, protocol)?;
+        }
         
         Ok(())
     }
@@ -119,11 +211,12 @@ impl EbpfProgram for LoadBalancer {
     async fn start(&mut self) -> Result<()> {
         info!("🚀 Starting load balancer eBPF program");
         
-        // In a real implementation, this would:
-        // 1. Load XDP/TC eBPF programs for packet steering
-        // 2. Set up connection tracking maps
-        // 3. Configure NAT and port mapping
-        // 4. Attach to network interfaces
+        if self.config.xdp_lb.enabled && self.mode == ExecutionMode::Kernel {
+            self.xdp.write().await.load(&self.config.interfaces)?;
+        }
+        if self.mode == ExecutionMode::UserSpace {
+            self.start_proxies().await?;
+        }
         
         self.running = true;
         
@@ -172,6 +265,10 @@ impl EbpfProgram for LoadBalancer {
 
     async fn stop(&mut self) -> Result<()> {
         info!("🛑 Stopping load balancer");
+        self.xdp.write().await.unload();
+        for (_, proxy) in self.proxies.write().await.drain() {
+            proxy.shutdown().await;
+        }
         self.running = false;
         Ok(())
     }
@@ -10,6 +10,10 @@ use serde::{Serialize, Deserialize};
 use tokio::sync::RwLock;
 use tracing::{debug, info};
 
+use crate::conntrack::ConntrackStats;
+use crate::security_policy::RuleCounters;
+use crate::xdp_lb::EndpointTraffic;
+
 /// eBPF metrics collector and aggregator
 pub struct EbpfMetrics {
     collectors: RwLock<HashMap<String, MetricCollector>>,
@@ -84,6 +88,9 @@ impl EbpfMetrics {
             security: stats.security.clone(),
             system: SystemMetrics::collect().await,
             component_metrics,
+            endpoints: Vec::new(),
+            rules: Vec::new(),
+            conntrack: ConntrackStats::default(),
         })
     }
 
@@ -294,6 +301,15 @@ pub struct EbpfMetricsSnapshot {
     pub security: SecurityAggregateStats,
     pub system: SystemMetrics,
     pub component_metrics: HashMap<String, ComponentMetrics>,
+    /// Traffic the XDP load balancer has sent to each endpoint
+    #[serde(default)]
+    pub endpoints: Vec<EndpointTraffic>,
+    /// Packets each security rule matched and what was done with them
+    #[serde(default)]
+    pub rules: Vec<RuleCounters>,
+    /// Occupancy of the connection tracking table
+    #[serde(default)]
+    pub conntrack: ConntrackStats,
 }
 
 /// Individual component metrics
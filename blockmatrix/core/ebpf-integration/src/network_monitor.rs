@@ -1,24 +1,35 @@
 //! Network monitoring using eBPF programs
 //! 
-//! Provides real-time network visibility with kernel-level packet inspection
+//! Provides real-time network visibility with kernel-level packet inspection.
+//! In degraded mode the kernel's own interface and socket counters are
+//! polled from user space instead (see `userspace`).
 
 use anyhow::Result;
 use std::collections::HashMap;
 use std::net::IpAddr;
-use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::capabilities::ExecutionMode;
+use crate::flow_export::{monotonic_epoch, FlowCache, FlowCounters, FlowExportConfig, FlowExporter, FlowKey, FlowRecord};
+use crate::userspace;
 use crate::{EbpfConfig, EbpfProgram, NetworkStats};
 
 /// Network monitoring using eBPF
 pub struct NetworkMonitor {
     config: EbpfConfig,
     running: bool,
-    stats: RwLock<NetworkStats>,
+    mode: ExecutionMode,
+    stats: Arc<RwLock<NetworkStats>>,
     connection_tracker: RwLock<ConnectionTracker>,
     bandwidth_monitor: RwLock<BandwidthMonitor>,
     latency_tracker: RwLock<LatencyTracker>,
+    flows: Arc<RwLock<FlowCache>>,
+    flow_export_task: Option<(CancellationToken, JoinHandle<()>)>,
 }
 
 impl NetworkMonitor {
@@ -28,20 +39,31 @@ impl NetworkMonitor {
         Ok(Self {
             config: config.clone(),
             running: false,
-            stats: RwLock::new(NetworkStats::default()),
+            mode: ExecutionMode::Kernel,
+            stats: Arc::new(RwLock::new(NetworkStats::default())),
             connection_tracker: RwLock::new(ConnectionTracker::new()),
             bandwidth_monitor: RwLock::new(BandwidthMonitor::new()),
             latency_tracker: RwLock::new(LatencyTracker::new()),
+            flows: Arc::new(RwLock::new(FlowCache::new(config.flow_export.clone()))),
+            flow_export_task: None,
         })
     }
 
+    /// Run in user space, polling the kernel's counters instead of loading eBPF programs
+    pub fn with_mode(mut self, mode: ExecutionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     /// Get current network statistics
     pub async fn get_stats(&self) -> Result<NetworkStats> {
         let mut stats = self.stats.write().await;
         
-        // Update stats from various trackers
-        let connections = self.connection_tracker.read().await;
-        stats.connections_tracked = connections.active_count();
+        // Update stats from various trackers; in user space the socket table counts connections
+        if self.mode == ExecutionMode::Kernel {
+            let connections = self.connection_tracker.read().await;
+            stats.connections_tracked = connections.active_count();
+        }
 
         let bandwidth = self.bandwidth_monitor.read().await;
         stats.bandwidth_utilization = bandwidth.current_utilization();
@@ -70,6 +92,25 @@ impl NetworkMonitor {
         self.latency_tracker.read().await.get_distribution()
     }
 
+    /// Get the flows currently tracked, with their totals so far
+    pub async fn get_flows(&self) -> Vec<FlowRecord> {
+        self.flows.read().await.snapshot()
+    }
+
+    /// Count a packet against its flow
+    pub async fn record_flow_packet(&self, key: FlowKey, bytes: u64, tcp_flags: u8) {
+        self.flows.write().await.record_packet(key, bytes, tcp_flags, SystemTime::now());
+    }
+
+    /// Fold in entries read from the kernel's flow map
+    pub async fn merge_flow_map(&self, entries: impl IntoIterator<Item = (FlowKey, FlowCounters)>) {
+        let epoch = monotonic_epoch();
+        let mut flows = self.flows.write().await;
+        for (key, counters) in entries {
+            flows.merge_map_entry(key, &counters, epoch);
+        }
+    }
+
     /// Start monitoring specific service traffic
     pub async fn monitor_service(&self, service_name: &str, ports: Vec<u16>) -> Result<()> {
         info!("📊 Starting service monitoring for: {}", service_name);
@@ -111,37 +152,59 @@ impl EbpfProgram for NetworkMonitor {
         
         self.running = true;
         
-        // Start background task to simulate monitoring
-        let stats_clone = self.stats.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_millis(100));
-            let mut counter = 0u64;
+        if self.mode == ExecutionMode::UserSpace {
+            tokio::spawn(poll_proc_counters(self.stats.clone(), self.config.interfaces.clone()));
+            info!("📊 Network monitor polling /proc in user space");
+        } else {
+            // Start background task to simulate monitoring
+            let stats_clone = self.stats.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_millis(100));
+                let mut counter = 0u64;
             
-            loop {
-                interval.tick().await;
-                counter += 1;
+                loop {
+                    interval.tick().await;
+                    counter += 1;
                 
-                // Simulate network activity
-                let packets = 100 + (counter % 50);
-                let bytes = packets * 1500; // Average packet size
-                let dropped = if counter % 100 == 0 { 1 } else { 0 };
+                    // Simulate network activity
+                    let packets = 100 + (counter % 50);
+                    let bytes = packets * 1500; // Average packet size
+                    let dropped = if counter % 100 == 0 { 1 } else { 0 };
                 
-                // Update stats
-                {
-                    let mut stats = stats_clone.write().await;
-                    stats.packets_processed += packets;
-                    stats.bytes_processed += bytes;
-                    stats.packets_dropped += dropped;
+                    // Update stats
+                    {
+                        let mut stats = stats_clone.write().await;
+                        stats.packets_processed += packets;
+                        stats.bytes_processed += bytes;
+                        stats.packets_dropped += dropped;
+                    }
                 }
-            }
-        });
+            });
+        }
         
+        if self.config.flow_export.enabled && self.flow_export_task.is_none() {
+            let shutdown = CancellationToken::new();
+            let task = tokio::spawn(run_flow_export(
+                self.flows.clone(),
+                self.config.flow_export.clone(),
+                shutdown.clone(),
+            ));
+            self.flow_export_task = Some((shutdown, task));
+            info!("📤 Flow export enabled");
+        }
+
         info!("✅ Network monitor started");
         Ok(())
     }
 
     async fn stop(&mut self) -> Result<()> {
         info!("🛑 Stopping network monitor");
+        if let Some((shutdown, task)) = self.flow_export_task.take() {
+            shutdown.cancel();
+            if let Err(e) = task.await {
+                warn!("⚠️ Flow export task failed: {}", e);
+            }
+        }
         self.running = false;
         Ok(())
     }
@@ -161,6 +224,75 @@ impl EbpfProgram for NetworkMonitor {
     }
 }
 
+/// Expire flows every export interval and send them to the collector,
+/// flushing everything once shut down
+/// Keep the stats in step with the kernel's interface and socket counters
+async fn poll_proc_counters(stats: Arc<RwLock<NetworkStats>>, interfaces: Vec<String>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut baseline = None;
+
+    loop {
+        interval.tick().await;
+
+        let counters = match userspace::read_interface_counters(&interfaces).await {
+            Ok(counters) => counters,
+            Err(e) => {
+                warn!("⚠️ {}", e);
+                continue;
+            }
+        };
+        let established = userspace::read_established().await.unwrap_or(0);
+        // Counters run from boot; report what happened since the monitor started
+        let start = *baseline.get_or_insert(counters);
+
+        let mut stats = stats.write().await;
+        stats.packets_processed = counters.packets.saturating_sub(start.packets);
+        stats.bytes_processed = counters.bytes.saturating_sub(start.bytes);
+        stats.packets_dropped = counters.dropped.saturating_sub(start.dropped);
+        stats.connections_tracked = established;
+    }
+}
+
+async fn run_flow_export(flows: Arc<RwLock<FlowCache>>, config: FlowExportConfig, shutdown: CancellationToken) {
+    let mut exporter = match config.collector {
+        Some(collector) => match FlowExporter::connect(collector, config.observation_domain_id).await {
+            Ok(exporter) => Some(exporter),
+            Err(e) => {
+                warn!("⚠️ Flow collector {} unavailable, flows will not be exported: {}", collector, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut interval = tokio::time::interval(Duration::from_secs(config.export_interval_secs.max(1)));
+    loop {
+        let stopping = tokio::select! {
+            _ = shutdown.cancelled() => true,
+            _ = interval.tick() => false,
+        };
+
+        let expired = if stopping {
+            flows.write().await.flush()
+        } else {
+            flows.write().await.expire(SystemTime::now())
+        };
+        // Finished flows would also be deleted from the kernel map here
+        debug!("{} flows expired, {} finished", expired.records.len(), expired.finished.len());
+
+        if let Some(exporter) = exporter.as_mut() {
+            if !expired.records.is_empty() {
+                if let Err(e) = exporter.export(&expired.records).await {
+                    warn!("⚠️ Flow export failed: {}", e);
+                }
+            }
+        }
+        if stopping {
+            break;
+        }
+    }
+}
+
 /// Tracks active network connections
 struct ConnectionTracker {
     connections: HashMap<ConnectionKey, ConnectionInfo>,
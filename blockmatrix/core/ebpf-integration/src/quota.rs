@@ -0,0 +1,481 @@
+//! Workload network transfer quotas
+//!
+//! The traffic control program attaches to each workload's cgroup and counts
+//! the bytes it sends and receives in the `WORKLOAD_BYTES` map, keyed by
+//! cgroup ID. Userspace maps cgroups to workloads and measures each
+//! workload's transfer against its daily or monthly quota from the counter
+//! readings at the start of the period. Crossing a configured percentage of
+//! the quota raises an event. Exhausting the quota also applies the quota's
+//! enforcement to the workload's egress, either a throttle or a block,
+//! through the `WORKLOAD_EGRESS_POLICY` map, which the traffic control
+//! program pins to bpffs and which userspace rewrites after every
+//! evaluation. The enforcement stays until the next period starts or the
+//! quota is raised.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::SystemTime;
+use tokio::sync::broadcast;
+
+/// Name of the BPF hash map of per-cgroup transfer counters
+pub const WORKLOAD_BYTES_MAP: &str = "WORKLOAD_BYTES";
+
+/// Name of the BPF hash map the egress program consults for throttles and blocks
+pub const QUOTA_POLICY_MAP: &str = "WORKLOAD_EGRESS_POLICY";
+
+/// Where the traffic control program pins the egress policy map
+pub const DEFAULT_POLICY_MAP_PIN: &str = "/sys/fs/bpf/hypermesh/WORKLOAD_EGRESS_POLICY";
+
+/// `EgressPolicy::action` limiting egress to `kbps`
+pub const POLICY_THROTTLE: u32 = 1;
+/// `EgressPolicy::action` dropping all egress
+pub const POLICY_BLOCK: u32 = 2;
+
+/// Egress rule for a cgroup, as the kernel program reads it from the policy map
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EgressPolicy {
+    pub action: u32,
+    pub kbps: u32,
+}
+
+// SAFETY: a plain `repr(C)` struct of integers without padding
+unsafe impl aya::Pod for EgressPolicy {}
+
+impl EgressPolicy {
+    /// Kernel rule applying an enforcement; alerts leave traffic alone and have none
+    pub fn for_enforcement(enforcement: QuotaEnforcement) -> Option<Self> {
+        match enforcement {
+            QuotaEnforcement::AlertOnly => None,
+            QuotaEnforcement::Throttle { kbps } => Some(Self { action: POLICY_THROTTLE, kbps }),
+            QuotaEnforcement::Block => Some(Self { action: POLICY_BLOCK, kbps: 0 }),
+        }
+    }
+}
+
+/// Cumulative transfer counters for a cgroup, as the kernel program keeps them
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadCounters {
+    pub egress_bytes: u64,
+    pub ingress_bytes: u64,
+}
+
+// SAFETY: a plain `repr(C)` struct of integers without padding
+unsafe impl aya::Pod for WorkloadCounters {}
+
+/// How long a quota lasts before it starts over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuotaPeriod {
+    /// From midnight UTC
+    Daily,
+    /// From midnight UTC on the first of the month
+    Monthly,
+}
+
+impl QuotaPeriod {
+    /// Start of the period containing `now`
+    pub fn start_of(self, now: SystemTime) -> SystemTime {
+        let now = DateTime::<Utc>::from(now).date_naive();
+        let day = match self {
+            QuotaPeriod::Daily => now,
+            QuotaPeriod::Monthly => now.with_day(1).expect("every month has a first day"),
+        };
+        let midnight = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+        Utc.from_utc_datetime(&midnight).into()
+    }
+}
+
+/// What happens to a workload's egress once its quota is used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuotaEnforcement {
+    /// Raise the events but leave traffic alone
+    AlertOnly,
+    /// Limit egress to this many kilobits per second
+    Throttle { kbps: u32 },
+    /// Drop all egress
+    Block,
+}
+
+/// A transfer allowance for one workload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadQuota {
+    pub period: QuotaPeriod,
+    pub limit_bytes: u64,
+    /// Count received bytes as well as sent ones
+    #[serde(default)]
+    pub include_ingress: bool,
+    pub enforcement: QuotaEnforcement,
+    /// Percentages of the quota that raise an event when crossed
+    pub thresholds: Vec<u8>,
+}
+
+impl WorkloadQuota {
+    pub fn validate(&self) -> Result<()> {
+        if self.limit_bytes == 0 {
+            return Err(anyhow!("Quota limit must be greater than zero"));
+        }
+        if let Some(bad) = self.thresholds.iter().find(|t| **t == 0 || **t > 100) {
+            return Err(anyhow!("Quota threshold {}% is not between 1 and 100", bad));
+        }
+        Ok(())
+    }
+
+    fn counted(&self, usage: &WorkloadCounters) -> u64 {
+        usage.egress_bytes + if self.include_ingress { usage.ingress_bytes } else { 0 }
+    }
+}
+
+/// Quota tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    pub enabled: bool,
+    pub evaluation_interval_secs: u64,
+    /// Quotas by workload ID
+    #[serde(default)]
+    pub quotas: HashMap<String, WorkloadQuota>,
+    /// bpffs path of the pinned egress policy map
+    #[serde(default = "default_policy_map_pin")]
+    pub policy_map_pin: String,
+}
+
+fn default_policy_map_pin() -> String {
+    DEFAULT_POLICY_MAP_PIN.to_string()
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            evaluation_interval_secs: 10,
+            quotas: HashMap::new(),
+            policy_map_pin: default_policy_map_pin(),
+        }
+    }
+}
+
+/// A workload's transfer in the current period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadUsage {
+    pub workload: String,
+    pub period_start: SystemTime,
+    pub egress_bytes: u64,
+    pub ingress_bytes: u64,
+    /// Bytes counted against the quota, if the workload has one
+    pub counted_bytes: u64,
+    pub limit_bytes: Option<u64>,
+    pub enforcement: Option<QuotaEnforcement>,
+}
+
+/// A quota threshold crossed, enforced or reset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QuotaEvent {
+    ThresholdCrossed { workload: String, percent: u8, used_bytes: u64, limit_bytes: u64, at: SystemTime },
+    Enforced { workload: String, enforcement: QuotaEnforcement, at: SystemTime },
+    /// A new period started, or the quota changed, and any enforcement was lifted
+    Reset { workload: String, period_start: SystemTime, lifted: bool },
+}
+
+#[derive(Debug, Default)]
+struct WorkloadState {
+    cgroup_id: Option<u64>,
+    period_start: Option<SystemTime>,
+    /// Counter readings when the period started
+    base: WorkloadCounters,
+    current: WorkloadCounters,
+    /// Transfer this period from cgroups the workload has since left
+    carried: WorkloadCounters,
+    /// Highest threshold already reported this period
+    crossed: u8,
+    enforced: Option<QuotaEnforcement>,
+}
+
+impl WorkloadState {
+    fn used(&self) -> WorkloadCounters {
+        WorkloadCounters {
+            egress_bytes: self.carried.egress_bytes
+                + self.current.egress_bytes.saturating_sub(self.base.egress_bytes),
+            ingress_bytes: self.carried.ingress_bytes
+                + self.current.ingress_bytes.saturating_sub(self.base.ingress_bytes),
+        }
+    }
+
+    /// Start counting afresh from the current readings
+    fn restart(&mut self, period_start: SystemTime) -> bool {
+        self.period_start = Some(period_start);
+        self.base = self.current;
+        self.carried = WorkloadCounters::default();
+        self.crossed = 0;
+        self.enforced.take().is_some()
+    }
+}
+
+/// Per-workload transfer accounting and quota enforcement
+pub struct QuotaTracker {
+    quotas: HashMap<String, WorkloadQuota>,
+    workloads: HashMap<String, WorkloadState>,
+    cgroups: HashMap<u64, String>,
+    events: broadcast::Sender<QuotaEvent>,
+}
+
+impl QuotaTracker {
+    pub fn new(config: &QuotaConfig) -> Result<Self> {
+        for (workload, quota) in &config.quotas {
+            quota.validate().map_err(|e| anyhow!("Invalid quota for {}: {}", workload, e))?;
+        }
+        let (events, _) = broadcast::channel(256);
+        Ok(Self {
+            quotas: config.quotas.clone(),
+            workloads: HashMap::new(),
+            cgroups: HashMap::new(),
+            events,
+        })
+    }
+
+    /// Subscribe to threshold, enforcement and reset events
+    pub fn subscribe(&self) -> broadcast::Receiver<QuotaEvent> {
+        self.events.subscribe()
+    }
+
+    /// Attribute a cgroup's traffic to a workload
+    pub fn register_workload(&mut self, workload: &str, cgroup_id: u64) {
+        if let Some(previous) = self.workloads.get(workload).and_then(|state| state.cgroup_id) {
+            self.cgroups.remove(&previous);
+        }
+        self.cgroups.insert(cgroup_id, workload.to_string());
+        let state = self.workloads.entry(workload.to_string()).or_default();
+        // A restarted workload keeps what it used; its new cgroup's counters start from zero
+        state.carried = state.used();
+        state.cgroup_id = Some(cgroup_id);
+        state.base = WorkloadCounters::default();
+        state.current = WorkloadCounters::default();
+    }
+
+    /// Detach a workload from its cgroup once it has gone away
+    ///
+    /// A workload with a quota keeps what it used this period, so restarting
+    /// it does not reset its allowance.
+    pub fn unregister_workload(&mut self, workload: &str) {
+        let Some(state) = self.workloads.get_mut(workload) else {
+            return;
+        };
+        if let Some(cgroup_id) = state.cgroup_id.take() {
+            self.cgroups.remove(&cgroup_id);
+        }
+        if self.quotas.contains_key(workload) {
+            state.carried = state.used();
+            state.base = WorkloadCounters::default();
+            state.current = WorkloadCounters::default();
+        } else {
+            self.workloads.remove(workload);
+        }
+    }
+
+    /// Set or replace a workload's quota
+    ///
+    /// Changing the period restarts the workload's accounting. Any
+    /// enforcement is lifted, and is applied again at the next evaluation
+    /// if the new limit is also used up.
+    pub fn set_quota(&mut self, workload: &str, quota: WorkloadQuota) -> Result<()> {
+        quota.validate()?;
+        let previous = self.quotas.insert(workload.to_string(), quota.clone());
+        if let Some(state) = self.workloads.get_mut(workload) {
+            let period_start = quota.period.start_of(SystemTime::now());
+            let lifted = if previous.map(|p| p.period) != Some(quota.period) {
+                state.restart(period_start)
+            } else {
+                state.crossed = 0;
+                state.enforced.take().is_some()
+            };
+            if lifted {
+                let _ = self.events.send(QuotaEvent::Reset {
+                    workload: workload.to_string(),
+                    period_start: state.period_start.unwrap_or(period_start),
+                    lifted,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_quota(&mut self, workload: &str) -> Option<WorkloadQuota> {
+        if let Some(state) = self.workloads.get_mut(workload) {
+            state.crossed = 0;
+            state.enforced = None;
+        }
+        self.quotas.remove(workload)
+    }
+
+    /// Fold in the cumulative counters of a `WORKLOAD_BYTES` entry
+    pub fn merge_counters(&mut self, cgroup_id: u64, counters: WorkloadCounters) {
+        let Some(state) = self.cgroups.get(&cgroup_id).and_then(|workload| self.workloads.get_mut(workload)) else {
+            return;
+        };
+        state.current.egress_bytes = state.current.egress_bytes.max(counters.egress_bytes);
+        state.current.ingress_bytes = state.current.ingress_bytes.max(counters.ingress_bytes);
+    }
+
+    /// Count transfer directly, as the kernel program does for packets it sees
+    pub fn record(&mut self, workload: &str, egress_bytes: u64, ingress_bytes: u64) {
+        let state = self.workloads.entry(workload.to_string()).or_default();
+        state.current.egress_bytes += egress_bytes;
+        state.current.ingress_bytes += ingress_bytes;
+    }
+
+    /// Start new periods, report thresholds crossed and enforce exhausted quotas
+    pub fn evaluate(&mut self, now: SystemTime) -> Vec<QuotaEvent> {
+        let mut events = Vec::new();
+        for (workload, state) in &mut self.workloads {
+            let Some(quota) = self.quotas.get(workload) else {
+                continue;
+            };
+
+            let period_start = quota.period.start_of(now);
+            match state.period_start {
+                Some(start) if start >= period_start => {}
+                Some(_) => {
+                    let lifted = state.restart(period_start);
+                    events.push(QuotaEvent::Reset { workload: workload.clone(), period_start, lifted });
+                }
+                // Traffic before the quota was first evaluated counts toward this period
+                None => state.period_start = Some(period_start),
+            }
+
+            let used_bytes = quota.counted(&state.used());
+            let percent = (used_bytes.saturating_mul(100) / quota.limit_bytes).min(100) as u8;
+            let mut thresholds = quota.thresholds.clone();
+            thresholds.sort_unstable();
+            for threshold in thresholds.into_iter().filter(|t| *t > state.crossed && *t <= percent) {
+                events.push(QuotaEvent::ThresholdCrossed {
+                    workload: workload.clone(),
+                    percent: threshold,
+                    used_bytes,
+                    limit_bytes: quota.limit_bytes,
+                    at: now,
+                });
+                state.crossed = threshold;
+            }
+
+            if used_bytes >= quota.limit_bytes
+                && state.enforced.is_none()
+                && quota.enforcement != QuotaEnforcement::AlertOnly
+            {
+                state.enforced = Some(quota.enforcement);
+                events.push(QuotaEvent::Enforced { workload: workload.clone(), enforcement: quota.enforcement, at: now });
+            }
+        }
+
+        for event in &events {
+            // Nobody listening is fine; enforcement already took effect
+            let _ = self.events.send(event.clone());
+        }
+        events
+    }
+
+    /// Enforcement currently applied to a workload's egress
+    pub fn enforcement_for(&self, workload: &str) -> Option<QuotaEnforcement> {
+        self.workloads.get(workload).and_then(|state| state.enforced)
+    }
+
+    /// Throttle and block rules for the kernel policy map, by cgroup ID
+    pub fn policy_entries(&self) -> Vec<(u64, QuotaEnforcement)> {
+        self.workloads
+            .values()
+            .filter_map(|state| Some((state.cgroup_id?, state.enforced?)))
+            .collect()
+    }
+
+    /// The policy map's contents: a kernel rule for every enforced cgroup
+    pub fn egress_policies(&self) -> HashMap<u64, EgressPolicy> {
+        self.policy_entries()
+            .into_iter()
+            .filter_map(|(cgroup_id, enforcement)| Some((cgroup_id, EgressPolicy::for_enforcement(enforcement)?)))
+            .collect()
+    }
+
+    pub fn usage(&self, workload: &str) -> Option<WorkloadUsage> {
+        let state = self.workloads.get(workload)?;
+        let quota = self.quotas.get(workload);
+        let used = state.used();
+        Some(WorkloadUsage {
+            workload: workload.to_string(),
+            period_start: state
+                .period_start
+                .or_else(|| quota.map(|q| q.period.start_of(SystemTime::now())))
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+            egress_bytes: used.egress_bytes,
+            ingress_bytes: used.ingress_bytes,
+            counted_bytes: quota.map_or(used.egress_bytes + used.ingress_bytes, |q| q.counted(&used)),
+            limit_bytes: quota.map(|q| q.limit_bytes),
+            enforcement: state.enforced,
+        })
+    }
+
+    /// Usage of every known workload, sorted by workload ID
+    pub fn all_usage(&self) -> Vec<WorkloadUsage> {
+        let mut usage: Vec<_> = self.workloads.keys().filter_map(|workload| self.usage(workload)).collect();
+        usage.sort_by(|a, b| a.workload.cmp(&b.workload));
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_thresholds_enforcement_and_period_reset() {
+        let quota = WorkloadQuota {
+            period: QuotaPeriod::Daily,
+            limit_bytes: 1000,
+            include_ingress: false,
+            enforcement: QuotaEnforcement::Block,
+            thresholds: vec![100, 50, 80],
+        };
+        let config = QuotaConfig { quotas: HashMap::from([("web".to_string(), quota)]), ..Default::default() };
+        let mut tracker = QuotaTracker::new(&config).unwrap();
+        tracker.register_workload("web", 42);
+        // 2023-11-14 22:13:20 UTC
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        // Ingress does not count toward an egress-only quota
+        tracker.merge_counters(42, WorkloadCounters { egress_bytes: 850, ingress_bytes: 5000 });
+        let events = tracker.evaluate(t0);
+        let crossed: Vec<u8> = events
+            .iter()
+            .filter_map(|e| match e {
+                QuotaEvent::ThresholdCrossed { percent, .. } => Some(*percent),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(crossed, vec![50, 80]);
+        assert_eq!(tracker.enforcement_for("web"), None);
+
+        tracker.merge_counters(42, WorkloadCounters { egress_bytes: 1200, ingress_bytes: 5000 });
+        let events = tracker.evaluate(t0 + Duration::from_secs(60));
+        assert!(matches!(events.as_slice(), [
+            QuotaEvent::ThresholdCrossed { percent: 100, .. },
+            QuotaEvent::Enforced { enforcement: QuotaEnforcement::Block, .. },
+        ]));
+        assert_eq!(tracker.policy_entries(), vec![(42, QuotaEnforcement::Block)]);
+        assert_eq!(tracker.egress_policies(), HashMap::from([(42, EgressPolicy { action: POLICY_BLOCK, kbps: 0 })]));
+        assert!(tracker.evaluate(t0 + Duration::from_secs(120)).is_empty());
+
+        // The next day starts from the counters as they stood, with the block lifted
+        let tomorrow = t0 + Duration::from_secs(2 * 3600);
+        let events = tracker.evaluate(tomorrow);
+        assert!(matches!(events.as_slice(), [QuotaEvent::Reset { lifted: true, .. }]));
+        assert!(tracker.policy_entries().is_empty());
+        assert!(tracker.egress_policies().is_empty());
+        let usage = tracker.usage("web").unwrap();
+        assert_eq!(usage.counted_bytes, 0);
+        assert_eq!(usage.period_start, SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_006_400));
+        assert_eq!(
+            QuotaPeriod::Monthly.start_of(t0),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_698_796_800)
+        );
+    }
+}
@@ -2,14 +2,22 @@
 //!
 //! Implements network security policies, firewall rules, and threat detection
 //! at the kernel level for high-performance packet filtering and analysis.
+//! Rules can match on connection state from the flow table (see
+//! `conntrack`), and every rule counts the packets it matched.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use aya::maps::lpm_trie::LpmTrie;
+use aya::maps::{Map, MapData};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::IpAddr;
-use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, info, warn, error};
 
+use crate::conntrack::{ConnState, ConnectionTracker, ConntrackStats, FlowPacket, TrackOutcome};
+use crate::ddos::{BlocklistRule, DdosDetector, Mitigation, MitigationAction, MitigationEvent, PacketClass, SourcePrefix, DDOS_BLOCKLIST_MAP};
 use crate::{EbpfConfig, EbpfProgram, SecurityPolicy, SecurityRule, PolicyAction};
 
 /// Security policy engine using eBPF for network security enforcement
@@ -20,6 +28,9 @@ pub struct SecurityPolicyEngine {
     policy_stats: RwLock<PolicyStats>,
     threat_detector: RwLock<ThreatDetector>,
     rate_limiter: RwLock<RateLimiter>,
+    ddos: Arc<RwLock<DdosDetector>>,
+    conntrack: Arc<RwLock<ConnectionTracker>>,
+    rule_counters: RwLock<HashMap<(String, usize), RuleCounters>>,
 }
 
 impl SecurityPolicyEngine {
@@ -33,6 +44,9 @@ impl SecurityPolicyEngine {
             policy_stats: RwLock::new(PolicyStats::new()),
             threat_detector: RwLock::new(ThreatDetector::new()),
             rate_limiter: RwLock::new(RateLimiter::new()),
+            ddos: Arc::new(RwLock::new(DdosDetector::new(config.ddos.clone())?)),
+            conntrack: Arc::new(RwLock::new(ConnectionTracker::new(config.conntrack.clone()))),
+            rule_counters: RwLock::new(HashMap::new()),
         })
     }
 
@@ -46,6 +60,13 @@ impl SecurityPolicyEngine {
         let mut policies = self.active_policies.write().await;
         policies.insert(policy.name.clone(), policy.clone());
         
+        // Counters start over with the policy's new rules
+        let mut counters = self.rule_counters.write().await;
+        counters.retain(|(name, _), _| *name != policy.name);
+        for index in 0..policy.rules.len() {
+            counters.insert((policy.name.clone(), index), RuleCounters::new(&policy.name, index));
+        }
+        
         // Update rate limiter with new rules
         let mut rate_limiter = self.rate_limiter.write().await;
         for rule in &policy.rules {
@@ -70,6 +91,7 @@ impl SecurityPolicyEngine {
                 rate_limiter.remove_rule(&rule.source_cidr);
             }
         }
+        self.rule_counters.write().await.retain(|(name, _), _| name != policy_name);
         
         Ok(())
     }
@@ -83,13 +105,18 @@ impl SecurityPolicyEngine {
     pub async fn get_policy_details(&self, policy_name: &str) -> Option<PolicyDetails> {
         let policies = self.active_policies.read().await;
         if let Some(policy) = policies.get(policy_name) {
+            let counters = self.rule_counters.read().await;
+            let rules = counters.iter().filter(|((name, _), _)| name == policy_name).map(|(_, c)| c);
+            let (processed, blocked) = rules.fold((0, 0), |(processed, blocked), c| {
+                (processed + c.packets, blocked + c.denied + c.rate_limited)
+            });
             Some(PolicyDetails {
                 name: policy.name.clone(),
                 rules_count: policy.rules.len() as u32,
                 priority: policy.priority,
                 action: policy.action.clone(),
-                packets_processed: rand::random::<u64>() % 10000,
-                packets_blocked: rand::random::<u64>() % 1000,
+                packets_processed: processed,
+                packets_blocked: blocked,
                 last_updated: Instant::now(),
             })
         } else {
@@ -105,9 +132,13 @@ impl SecurityPolicyEngine {
 
     /// Check if a packet would be allowed by current policies
     pub async fn check_packet(&self, src_ip: IpAddr, dst_port: u16, protocol: &str) -> PacketVerdict {
+        if let Some(verdict) = self.ddos_verdict(src_ip).await {
+            return verdict;
+        }
+
         let policies = self.active_policies.read().await;
         
-        for policy in policies.values() {
+        for policy in by_priority(&policies) {
             for rule in &policy.rules {
                 if self.rule_matches(rule, src_ip, dst_port, protocol) {
                     match &policy.action {
@@ -133,6 +164,116 @@ impl SecurityPolicyEngine {
         PacketVerdict::Allow // Default allow if no rules match
     }
 
+    /// Track a packet's flow and check it against current policies
+    ///
+    /// Rules that name connection states match only packets in those
+    /// states. SYNs over the half-open limits, and new flows the full table
+    /// refuses, are denied before any rule is consulted. Packets allowed by
+    /// no rule are allowed, as with `check_packet`.
+    pub async fn check_flow(&self, packet: &FlowPacket) -> PacketVerdict {
+        if let Some(verdict) = self.ddos_verdict(packet.src.ip()).await {
+            return verdict;
+        }
+
+        let now = Instant::now();
+        let mut conntrack = self.conntrack.write().await;
+        let (state, flow) = if !conntrack.config().enabled {
+            (None, None)
+        } else {
+            match conntrack.track(packet, now) {
+                TrackOutcome::Tracked { state, flow } => (Some(state), Some(flow)),
+                TrackOutcome::Untracked(state) => (Some(state), None),
+                TrackOutcome::SynFlood | TrackOutcome::TableFull => return PacketVerdict::Deny,
+            }
+        };
+
+        let policies = self.active_policies.read().await;
+        for policy in by_priority(&policies) {
+            for (index, rule) in policy.rules.iter().enumerate() {
+                if !self.rule_matches(rule, packet.src.ip(), packet.dst.port(), packet.protocol.as_str()) {
+                    continue;
+                }
+                if !rule.conn_states.is_empty() && !state.is_some_and(|state| rule.conn_states.contains(&state)) {
+                    continue;
+                }
+
+                let verdict = match (&policy.action, rule.flow_rate_limit, flow) {
+                    (PolicyAction::Deny, _, _) => PacketVerdict::Deny,
+                    (_, Some(pps), Some(flow)) if !conntrack.admit(&flow, pps, now) => PacketVerdict::RateLimit,
+                    (PolicyAction::RateLimit(limit), _, _) => {
+                        if self.rate_limiter.read().await.check_rate(&rule.source_cidr, *limit) {
+                            PacketVerdict::Allow
+                        } else {
+                            PacketVerdict::RateLimit
+                        }
+                    }
+                    (PolicyAction::Log, _, _) => {
+                        info!("🔍 Packet logged: {} -> {} ({}, {:?})", packet.src, packet.dst, packet.protocol.as_str(), state);
+                        PacketVerdict::Allow
+                    }
+                    (PolicyAction::Allow, _, _) => PacketVerdict::Allow,
+                };
+                if let Some(counters) = self.rule_counters.write().await.get_mut(&(policy.name.clone(), index)) {
+                    counters.count(&verdict, packet.len);
+                }
+                return verdict;
+            }
+        }
+
+        PacketVerdict::Allow
+    }
+
+    /// Packets each rule matched, by policy and rule
+    pub async fn rule_counters(&self) -> Vec<RuleCounters> {
+        let mut counters: Vec<RuleCounters> = self.rule_counters.read().await.values().cloned().collect();
+        counters.sort_by(|a, b| (&a.policy, a.rule).cmp(&(&b.policy, b.rule)));
+        counters
+    }
+
+    /// Occupancy of the flow table and what it has turned away
+    pub async fn conntrack_stats(&self) -> ConntrackStats {
+        self.conntrack.read().await.stats()
+    }
+
+    /// Count a packet towards DDoS detection
+    pub async fn observe_packet(&self, src_ip: IpAddr, class: PacketClass) {
+        self.ddos.write().await.observe(src_ip, class);
+    }
+
+    /// DDoS mitigations currently in force
+    pub async fn active_mitigations(&self) -> Vec<Mitigation> {
+        self.ddos.read().await.active_mitigations()
+    }
+
+    /// Lift a DDoS mitigation before it expires
+    pub async fn lift_mitigation(&self, id: u64, operator: &str) -> Result<Mitigation> {
+        let mitigation = self.ddos.write().await.lift(id, operator, SystemTime::now())?;
+        info!("🔓 DDoS mitigation {} on {} lifted by {}", mitigation.id, mitigation.prefix, operator);
+        Ok(mitigation)
+    }
+
+    /// Subscribe to DDoS detection and mitigation events
+    pub async fn mitigation_events(&self) -> broadcast::Receiver<MitigationEvent> {
+        self.ddos.read().await.subscribe()
+    }
+
+    /// Recent DDoS detection and mitigation events, oldest first
+    pub async fn mitigation_history(&self) -> Vec<MitigationEvent> {
+        self.ddos.read().await.history()
+    }
+
+    async fn ddos_verdict(&self, src_ip: IpAddr) -> Option<PacketVerdict> {
+        let ddos = self.ddos.read().await;
+        let mitigation = ddos.mitigation_for(src_ip)?;
+        if ddos.admits(src_ip) {
+            return None;
+        }
+        Some(match mitigation.action {
+            MitigationAction::Drop => PacketVerdict::Deny,
+            _ => PacketVerdict::RateLimit,
+        })
+    }
+
     /// Detect potential security threats
     pub async fn threat_scan(&self) -> Vec<ThreatAlert> {
         let mut detector = self.threat_detector.write().await;
@@ -235,6 +376,65 @@ impl EbpfProgram for SecurityPolicyEngine {
             }
         });
         
+        // Close DDoS detection windows and apply the mitigation policy
+        if self.config.ddos.enabled {
+            let ddos = self.ddos.clone();
+            let window = Duration::from_millis(self.config.ddos.window_ms.max(1));
+            let pin = self.config.ddos.blocklist_pin.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(window);
+                let mut synced = true;
+
+                loop {
+                    interval.tick().await;
+
+                    let events = ddos.write().await.evaluate(SystemTime::now());
+                    for event in &events {
+                        match event {
+                            MitigationEvent::Detected { prefix, kind, rate, .. } => {
+                                warn!("🚨 {:?} from {} at {:.0}/s", kind, prefix, rate)
+                            }
+                            MitigationEvent::Applied { mitigation } => {
+                                warn!("🛡️ Mitigation {} applied to {}: {:?}", mitigation.id, mitigation.prefix, mitigation.action)
+                            }
+                            MitigationEvent::Expired { mitigation, .. } => {
+                                info!("⏱️ Mitigation {} on {} expired", mitigation.id, mitigation.prefix)
+                            }
+                            MitigationEvent::Lifted { .. } => {}
+                        }
+                    }
+
+                    // Rewritten every window, so expired and lifted mitigations leave the kernel too
+                    let rules = ddos.read().await.blocklist();
+                    match write_blocklist(&pin, &rules) {
+                        Ok(()) => synced = true,
+                        Err(e) if synced => {
+                            error!("DDoS mitigations not applied in XDP: {:#}", e);
+                            synced = false;
+                        }
+                        Err(e) => debug!("DDoS mitigations still not applied in XDP: {:#}", e),
+                    }
+                }
+            });
+        }
+
+        // Expire idle flows from the connection tracking table
+        if self.config.conntrack.enabled {
+            let conntrack = self.conntrack.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(5));
+
+                loop {
+                    interval.tick().await;
+
+                    let expired = conntrack.write().await.expire(Instant::now());
+                    if expired > 0 {
+                        debug!("Expired {} idle flows", expired);
+                    }
+                }
+            });
+        }
+
         // Start rate limiter cleanup
         let rate_limiter = self.rate_limiter.clone();
         tokio::spawn(async move {
@@ -275,6 +475,32 @@ impl EbpfProgram for SecurityPolicyEngine {
     }
 }
 
+/// Make the pinned XDP blocklist hold exactly `rules`
+fn write_blocklist(pin: &str, rules: &HashMap<SourcePrefix, BlocklistRule>) -> Result<()> {
+    let data = MapData::from_pin(pin).with_context(|| format!("Failed to open {} pinned at {}", DDOS_BLOCKLIST_MAP, pin))?;
+    let mut trie: LpmTrie<MapData, [u8; 16], BlocklistRule> = LpmTrie::try_from(Map::LpmTrie(data))?;
+
+    let wanted: HashMap<(u32, [u8; 16]), BlocklistRule> = rules
+        .iter()
+        .map(|(prefix, rule)| {
+            let key = prefix.trie_key();
+            ((key.prefix_len(), key.data()), *rule)
+        })
+        .collect();
+    let stale: Vec<_> = trie
+        .keys()
+        .filter_map(|key| key.ok())
+        .filter(|key| !wanted.contains_key(&(key.prefix_len(), key.data())))
+        .collect();
+    for key in stale {
+        trie.remove(&key)?;
+    }
+    for (prefix, rule) in rules {
+        trie.insert(&prefix.trie_key(), rule, 0)?;
+    }
+    Ok(())
+}
+
 /// Verdict for packet processing
 #[derive(Debug, Clone, PartialEq)]
 pub enum PacketVerdict {
@@ -318,6 +544,42 @@ impl PolicyStats {
     }
 }
 
+/// Packets one rule has matched and the verdicts given
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleCounters {
+    pub policy: String,
+    /// Position of the rule in its policy
+    pub rule: usize,
+    pub packets: u64,
+    pub bytes: u64,
+    pub allowed: u64,
+    pub denied: u64,
+    pub rate_limited: u64,
+}
+
+impl RuleCounters {
+    fn new(policy: &str, rule: usize) -> Self {
+        Self { policy: policy.to_string(), rule, packets: 0, bytes: 0, allowed: 0, denied: 0, rate_limited: 0 }
+    }
+
+    fn count(&mut self, verdict: &PacketVerdict, len: u32) {
+        self.packets += 1;
+        self.bytes += len as u64;
+        match verdict {
+            PacketVerdict::Allow => self.allowed += 1,
+            PacketVerdict::Deny => self.denied += 1,
+            PacketVerdict::RateLimit => self.rate_limited += 1,
+        }
+    }
+}
+
+/// Policies in evaluation order: highest priority first, then by name
+fn by_priority(policies: &HashMap<String, SecurityPolicy>) -> Vec<&SecurityPolicy> {
+    let mut ordered: Vec<&SecurityPolicy> = policies.values().collect();
+    ordered.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.name.cmp(&b.name)));
+    ordered
+}
+
 /// Detailed policy information
 #[derive(Debug, Clone)]
 pub struct PolicyDetails {
@@ -504,6 +766,8 @@ mod tests {
                 destination_port: Some(80),
                 protocol: Some("TCP".to_string()),
                 rate_limit: Some(100),
+                conn_states: Vec::new(),
+                flow_rate_limit: None,
             }],
             action: PolicyAction::Allow,
             priority: 1,
@@ -529,6 +793,49 @@ mod tests {
         assert_eq!(verdict, PacketVerdict::Allow);
     }
 
+    #[tokio::test]
+    async fn test_stateful_rules_and_counters() {
+        use crate::conntrack::{FlowProtocol, TCP_ACK, TCP_SYN};
+
+        let engine = SecurityPolicyEngine::new(&EbpfConfig::default()).await.unwrap();
+        let rule = |port: Option<u16>, conn_states: Vec<ConnState>, flow_rate_limit| SecurityRule {
+            source_cidr: "0.0.0.0/0".to_string(),
+            destination_port: port,
+            protocol: Some("TCP".to_string()),
+            rate_limit: None,
+            conn_states,
+            flow_rate_limit,
+        };
+        let policy = |name: &str, rule, action, priority| SecurityPolicy { name: name.to_string(), rules: vec![rule], action, priority };
+        engine.apply_policy(policy("established", rule(None, vec![ConnState::Established], None), PolicyAction::Allow, 10)).await.unwrap();
+        engine.apply_policy(policy("no-ssh", rule(Some(22), vec![ConnState::New], None), PolicyAction::Deny, 1)).await.unwrap();
+        engine.apply_policy(policy("web", rule(Some(80), vec![ConnState::New], Some(1)), PolicyAction::Allow, 1)).await.unwrap();
+
+        let packet = |src: &str, dst: &str, tcp_flags| FlowPacket {
+            src: src.parse().unwrap(),
+            dst: dst.parse().unwrap(),
+            protocol: FlowProtocol::Tcp,
+            tcp_flags,
+            len: 60,
+        };
+        assert_eq!(engine.check_flow(&packet("10.0.0.1:4000", "10.0.0.2:22", TCP_SYN)).await, PacketVerdict::Deny);
+        assert_eq!(engine.check_flow(&packet("10.0.0.1:4001", "10.0.0.2:80", TCP_SYN)).await, PacketVerdict::Allow);
+        // A retransmitted SYN is over the web rule's per-flow limit
+        assert_eq!(engine.check_flow(&packet("10.0.0.1:4001", "10.0.0.2:80", TCP_SYN)).await, PacketVerdict::RateLimit);
+        assert_eq!(engine.check_flow(&packet("10.0.0.2:80", "10.0.0.1:4001", TCP_SYN | TCP_ACK)).await, PacketVerdict::Allow);
+
+        let counters = engine.rule_counters().await;
+        let by_policy = |name: &str| counters.iter().find(|c| c.policy == name).unwrap().clone();
+        assert_eq!((by_policy("established").packets, by_policy("established").allowed), (1, 1));
+        assert_eq!(by_policy("no-ssh").denied, 1);
+        assert_eq!((by_policy("web").allowed, by_policy("web").rate_limited, by_policy("web").bytes), (1, 1, 120));
+        assert_eq!(engine.get_policy_details("web").await.unwrap().packets_blocked, 1);
+        assert_eq!(engine.conntrack_stats().await.half_open, 1);
+
+        engine.remove_policy("web").await.unwrap();
+        assert_eq!(engine.rule_counters().await.len(), 2);
+    }
+
     #[test]
     fn test_threat_detector() {
         let detector = ThreatDetector::new();
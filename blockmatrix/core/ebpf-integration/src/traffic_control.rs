@@ -3,11 +3,15 @@
 //! Implements traffic shaping, bandwidth limiting, and Quality of Service
 //! controls at the kernel level for high-performance packet processing.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use aya::maps::{HashMap as BpfHashMap, Map, MapData};
 use std::collections::HashMap;
-use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, error, info, warn};
 
+use crate::quota::{EgressPolicy, QuotaEvent, QuotaTracker, WorkloadCounters, WorkloadQuota, WorkloadUsage, QUOTA_POLICY_MAP};
 use crate::{EbpfConfig, EbpfProgram, TrafficShapingConfig, TrafficPriority, QosClass};
 
 /// Traffic controller using eBPF for QoS and bandwidth management
@@ -17,6 +21,7 @@ pub struct TrafficController {
     service_configs: RwLock<HashMap<String, TrafficShapingConfig>>,
     traffic_classes: RwLock<HashMap<String, TrafficClass>>,
     bandwidth_monitor: RwLock<BandwidthLimiter>,
+    quotas: Arc<RwLock<QuotaTracker>>,
 }
 
 impl TrafficController {
@@ -29,6 +34,7 @@ impl TrafficController {
             service_configs: RwLock::new(HashMap::new()),
             traffic_classes: RwLock::new(HashMap::new()),
             bandwidth_monitor: RwLock::new(BandwidthLimiter::new()),
+            quotas: Arc::new(RwLock::new(QuotaTracker::new(&config.quota)?)),
         })
     }
 
@@ -98,6 +104,45 @@ impl TrafficController {
         
         Ok(())
     }
+
+    /// Attribute a cgroup's traffic to a workload for quota accounting
+    pub async fn register_workload(&self, workload: &str, cgroup_id: u64) {
+        self.quotas.write().await.register_workload(workload, cgroup_id);
+    }
+
+    /// Detach a workload that has gone away from its cgroup
+    pub async fn unregister_workload(&self, workload: &str) {
+        self.quotas.write().await.unregister_workload(workload);
+    }
+
+    /// Set or replace a workload's network transfer quota
+    pub async fn set_workload_quota(&self, workload: &str, quota: WorkloadQuota) -> Result<()> {
+        info!("📏 Setting transfer quota for {}: {} bytes {:?}", workload, quota.limit_bytes, quota.period);
+        self.quotas.write().await.set_quota(workload, quota)
+    }
+
+    /// Remove a workload's quota, lifting any enforcement
+    pub async fn remove_workload_quota(&self, workload: &str) -> Option<WorkloadQuota> {
+        self.quotas.write().await.remove_quota(workload)
+    }
+
+    /// Fold in entries read from the kernel's per-cgroup transfer map
+    pub async fn merge_workload_counters(&self, entries: impl IntoIterator<Item = (u64, WorkloadCounters)>) {
+        let mut quotas = self.quotas.write().await;
+        for (cgroup_id, counters) in entries {
+            quotas.merge_counters(cgroup_id, counters);
+        }
+    }
+
+    /// Transfer of every known workload in its current quota period
+    pub async fn workload_usage(&self) -> Vec<WorkloadUsage> {
+        self.quotas.read().await.all_usage()
+    }
+
+    /// Subscribe to quota threshold, enforcement and reset events
+    pub async fn quota_events(&self) -> broadcast::Receiver<QuotaEvent> {
+        self.quotas.read().await.subscribe()
+    }
 }
 
 #[async_trait::async_trait]
@@ -129,6 +174,49 @@ impl EbpfProgram for TrafficController {
             }
         });
         
+        // Evaluate workload quotas and write the enforcement of exhausted ones
+        // to the policy map the egress program consults
+        if self.config.quota.enabled {
+            let quotas = self.quotas.clone();
+            let every = Duration::from_secs(self.config.quota.evaluation_interval_secs.max(1));
+            let pin = self.config.quota.policy_map_pin.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(every);
+                let mut synced = true;
+
+                loop {
+                    interval.tick().await;
+
+                    let events = quotas.write().await.evaluate(SystemTime::now());
+                    for event in &events {
+                        match event {
+                            QuotaEvent::ThresholdCrossed { workload, percent, used_bytes, limit_bytes, .. } => {
+                                warn!("📈 {} used {}% of its transfer quota ({}/{} bytes)", workload, percent, used_bytes, limit_bytes)
+                            }
+                            QuotaEvent::Enforced { workload, enforcement, .. } => {
+                                warn!("⛔ Transfer quota of {} exhausted, applying {:?}", workload, enforcement)
+                            }
+                            QuotaEvent::Reset { workload, lifted: true, .. } => {
+                                info!("🔁 Transfer quota enforcement on {} lifted", workload)
+                            }
+                            QuotaEvent::Reset { .. } => {}
+                        }
+                    }
+
+                    // Rewritten every time, so quotas raised or removed in between are lifted too
+                    let policies = quotas.read().await.egress_policies();
+                    match write_policy_map(&pin, &policies) {
+                        Ok(()) => synced = true,
+                        Err(e) if synced => {
+                            error!("Transfer quota enforcement not applied: {:#}", e);
+                            synced = false;
+                        }
+                        Err(e) => debug!("Transfer quota enforcement still not applied: {:#}", e),
+                    }
+                }
+            });
+        }
+
         info!("✅ Traffic controller started");
         Ok(())
     }
@@ -154,6 +242,21 @@ impl EbpfProgram for TrafficController {
     }
 }
 
+/// Make the pinned egress policy map hold exactly `policies`
+fn write_policy_map(pin: &str, policies: &HashMap<u64, EgressPolicy>) -> Result<()> {
+    let data = MapData::from_pin(pin).with_context(|| format!("Failed to open {} pinned at {}", QUOTA_POLICY_MAP, pin))?;
+    let mut map: BpfHashMap<MapData, u64, EgressPolicy> = BpfHashMap::try_from(Map::HashMap(data))?;
+
+    let stale: Vec<u64> = map.keys().filter_map(|key| key.ok()).filter(|key| !policies.contains_key(key)).collect();
+    for cgroup_id in stale {
+        map.remove(&cgroup_id)?;
+    }
+    for (cgroup_id, policy) in policies {
+        map.insert(cgroup_id, policy, 0)?;
+    }
+    Ok(())
+}
+
 /// Represents a traffic class with QoS parameters
 struct TrafficClass {
     bandwidth_limit_mbps: u32,
@@ -0,0 +1,262 @@
+//! User-space fallbacks for degraded mode
+//!
+//! Without the privileges to load eBPF programs, the network monitor reads
+//! the counters the kernel already keeps: per-interface totals in
+//! `/proc/net/dev` and the socket tables in `/proc/net/tcp` and
+//! `/proc/net/tcp6`. The load balancer runs a TCP proxy on each service
+//! address instead of rewriting packets. It picks an endpoint for every new
+//! connection from the same Maglev table the XDP program uses, keyed by the
+//! client's address, and counts the bytes it copies for each endpoint. The
+//! proxy cannot see packets, so its packet counts stay at zero.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::xdp_lb::{fnv1a, maglev_table, EndpointTraffic, L4Protocol, MAGLEV_TABLE_SIZE};
+use crate::{EndpointHealth, ServiceEndpoint};
+
+/// Totals of a set of interfaces since boot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    pub packets: u64,
+    pub bytes: u64,
+    pub dropped: u64,
+}
+
+/// Sum received and sent counters of `interfaces` from `/proc/net/dev` contents
+///
+/// An empty interface list sums every interface.
+pub fn parse_net_dev(contents: &str, interfaces: &[String]) -> InterfaceCounters {
+    let mut totals = InterfaceCounters::default();
+    // Two header lines, then `name: rx bytes packets errs drop ... tx bytes packets errs drop ...`
+    for line in contents.lines().skip(2) {
+        let Some((name, fields)) = line.split_once(':') else {
+            continue;
+        };
+        if !interfaces.is_empty() && !interfaces.iter().any(|i| i == name.trim()) {
+            continue;
+        }
+        let fields: Vec<u64> = fields.split_whitespace().filter_map(|f| f.parse().ok()).collect();
+        if fields.len() < 12 {
+            continue;
+        }
+        totals.bytes += fields[0] + fields[8];
+        totals.packets += fields[1] + fields[9];
+        totals.dropped += fields[3] + fields[11];
+    }
+    totals
+}
+
+/// Count established connections in `/proc/net/tcp` or `/proc/net/tcp6` contents
+pub fn count_established(contents: &str) -> u32 {
+    const TCP_ESTABLISHED: &str = "01";
+    contents
+        .lines()
+        .skip(1)
+        .filter(|line| line.split_whitespace().nth(3) == Some(TCP_ESTABLISHED))
+        .count() as u32
+}
+
+/// Read the interface totals
+pub async fn read_interface_counters(interfaces: &[String]) -> Result<InterfaceCounters> {
+    let contents = tokio::fs::read_to_string("/proc/net/dev").await.context("Failed to read /proc/net/dev")?;
+    Ok(parse_net_dev(&contents, interfaces))
+}
+
+/// Read the number of established TCP connections
+pub async fn read_established() -> Result<u32> {
+    let mut count = 0;
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        // A kernel without IPv6 has no tcp6 table
+        if let Ok(contents) = tokio::fs::read_to_string(table).await {
+            count += count_established(&contents);
+        }
+    }
+    Ok(count)
+}
+
+/// Endpoints a proxy balances over, with their Maglev table
+#[derive(Debug, Default)]
+struct ProxyTable {
+    endpoints: Vec<SocketAddr>,
+    slots: Vec<usize>,
+    /// Bytes copied for each endpoint, both directions
+    bytes: HashMap<SocketAddr, u64>,
+}
+
+impl ProxyTable {
+    fn pick(&self, client: SocketAddr) -> Option<SocketAddr> {
+        if self.slots.is_empty() {
+            return None;
+        }
+        let slot = fnv1a(&client.to_string(), 0) as usize % self.slots.len();
+        self.endpoints.get(self.slots[slot]).copied()
+    }
+}
+
+/// A TCP proxy balancing one service address over its endpoints
+pub struct TcpProxy {
+    service: SocketAddr,
+    table: Arc<RwLock<ProxyTable>>,
+    shutdown: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl TcpProxy {
+    /// Listen on the service address; only TCP can be proxied
+    pub async fn bind(service: SocketAddr, protocol: L4Protocol) -> Result<Self> {
+        if protocol != L4Protocol::Tcp {
+            return Err(anyhow!("{:?} services need the eBPF load balancer", protocol));
+        }
+        let listener = TcpListener::bind(service).await.with_context(|| format!("Failed to listen on {}", service))?;
+        let service = listener.local_addr()?;
+        let table = Arc::new(RwLock::new(ProxyTable::default()));
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(accept_loop(listener, table.clone(), shutdown.clone()));
+        info!("⚖️ User-space proxy listening on {}", service);
+        Ok(Self { service, table, shutdown, task })
+    }
+
+    /// Replace the endpoints; connections already proxied stay where they are
+    pub async fn set_endpoints(&self, endpoints: &[ServiceEndpoint]) {
+        let usable: Vec<(SocketAddr, u32)> = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.weight > 0 && !matches!(endpoint.health_status, EndpointHealth::Unhealthy))
+            .map(|endpoint| (SocketAddr::new(endpoint.ip, endpoint.port), endpoint.weight))
+            .collect();
+        let keys: Vec<(String, u32)> = usable.iter().map(|(addr, weight)| (addr.to_string(), *weight)).collect();
+
+        let mut table = self.table.write().await;
+        table.slots = maglev_table(&keys, MAGLEV_TABLE_SIZE);
+        table.endpoints = usable.into_iter().map(|(addr, _)| addr).collect();
+        let endpoints = table.endpoints.clone();
+        table.bytes.retain(|addr, _| endpoints.contains(addr));
+    }
+
+    /// Bytes copied for each endpoint
+    pub async fn traffic(&self) -> Vec<EndpointTraffic> {
+        let table = self.table.read().await;
+        table
+            .endpoints
+            .iter()
+            .map(|endpoint| EndpointTraffic {
+                service: self.service,
+                protocol: L4Protocol::Tcp,
+                endpoint: *endpoint,
+                packets: 0,
+                bytes: table.bytes.get(endpoint).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    /// Stop accepting; connections in progress run to completion
+    pub async fn shutdown(self) {
+        self.shutdown.cancel();
+        if let Err(e) = self.task.await {
+            warn!("⚠️ Proxy for {} failed: {}", self.service, e);
+        }
+    }
+}
+
+async fn accept_loop(listener: TcpListener, table: Arc<RwLock<ProxyTable>>, shutdown: CancellationToken) {
+    loop {
+        let (client, peer) = tokio::select! {
+            _ = shutdown.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("⚠️ Proxy accept failed: {}", e);
+                    continue;
+                }
+            },
+        };
+        let Some(endpoint) = table.read().await.pick(peer) else {
+            debug!("No endpoint for connection from {}", peer);
+            continue;
+        };
+        let table = table.clone();
+        tokio::spawn(async move {
+            let copied = proxy(client, endpoint).await;
+            match copied {
+                Ok(bytes) => *table.write().await.bytes.entry(endpoint).or_default() += bytes,
+                Err(e) => debug!("Proxied connection from {} to {} failed: {}", peer, endpoint, e),
+            }
+        });
+    }
+}
+
+async fn proxy(mut client: TcpStream, endpoint: SocketAddr) -> Result<u64> {
+    let mut upstream = TcpStream::connect(endpoint).await?;
+    let (sent, received) = tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(sent + received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proc_counters() {
+        let net_dev = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:    1000      10    0    0    0     0          0         0     1000      10    0    0    0     0       0          0
+  eth0:   50000     100    0    2    0     0          0         0    20000      80    0    1    0     0       0          0
+";
+        assert_eq!(parse_net_dev(net_dev, &["eth0".to_string()]), InterfaceCounters { packets: 180, bytes: 70000, dropped: 3 });
+        assert_eq!(parse_net_dev(net_dev, &[]).packets, 200);
+
+        let tcp = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1 1 0 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000     0        0 2 1 0 20 4 30 10 -1
+";
+        assert_eq!(count_established(tcp), 1);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_balances_tcp_connections() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = upstream.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = upstream.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            socket.read_exact(&mut buf).await.unwrap();
+            socket.write_all(b"pong").await.unwrap();
+        });
+
+        let service: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        assert!(TcpProxy::bind(service, L4Protocol::Udp).await.is_err());
+        let proxy = TcpProxy::bind(service, L4Protocol::Tcp).await.unwrap();
+        proxy
+            .set_endpoints(&[ServiceEndpoint { ip: endpoint.ip(), port: endpoint.port(), weight: 1, health_status: EndpointHealth::Healthy }])
+            .await;
+        assert_eq!(proxy.table.read().await.pick("10.0.0.1:5000".parse().unwrap()), Some(endpoint));
+
+        let mut client = TcpStream::connect(proxy.service).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut reply = Vec::new();
+        client.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, b"pong");
+        drop(client);
+
+        // Bytes are counted once the proxied connection closes
+        for _ in 0..50 {
+            if proxy.traffic().await[0].bytes == 8 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(proxy.traffic().await[0].bytes, 8);
+        proxy.shutdown().await;
+    }
+}
@@ -0,0 +1,491 @@
+//! XDP layer 4 load balancing
+//!
+//! The `xdp_lb` program (`bpf/xdp_lb.c`) rewrites packets addressed to a
+//! service to one of the service's endpoints. Endpoints are chosen by
+//! consistent hashing. Each service has a Maglev lookup table in which
+//! every endpoint holds slots in proportion to its weight, so adding or
+//! removing an endpoint moves few flows between the others. The program
+//! remembers each connection's endpoint in an affinity map, so an
+//! established connection stays put even when the table changes under it.
+//! It counts packets and bytes per endpoint in a per-CPU array, which is
+//! summed here for the metrics.
+//!
+//! This side builds the tables and keeps the maps in step with the service
+//! pools. It also loads the program object and attaches it to the
+//! configured interfaces.
+
+use anyhow::{anyhow, Context, Result};
+use aya::maps::{Array, HashMap as BpfHashMap, Map, PerCpuArray, PerCpuValues};
+use aya::programs::{Xdp, XdpFlags};
+use aya::Ebpf;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use tracing::{debug, info};
+
+use crate::{EndpointHealth, ServiceEndpoint};
+
+/// Name of the XDP program in the object file
+pub const XDP_LB_PROGRAM: &str = "xdp_lb";
+/// Services by address, port and protocol
+pub const LB_SERVICES_MAP: &str = "LB_SERVICES";
+/// Every service's Maglev table, one after the other
+pub const LB_MAGLEV_MAP: &str = "LB_MAGLEV";
+pub const LB_ENDPOINTS_MAP: &str = "LB_ENDPOINTS";
+/// Per-CPU packet and byte counters by endpoint ID
+pub const LB_ENDPOINT_STATS_MAP: &str = "LB_ENDPOINT_STATS";
+pub const LB_SETTINGS_MAP: &str = "LB_SETTINGS";
+
+/// Slots in a service's Maglev table; prime, and well above the endpoints per service
+pub const MAGLEV_TABLE_SIZE: usize = 4099;
+pub const MAX_SERVICES: u32 = 256;
+pub const MAX_ENDPOINTS: u32 = 4096;
+
+/// Service key as the program looks it up; the address is IPv4-mapped for IPv4
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServiceKey {
+    pub addr: [u8; 16],
+    pub port: u16,
+    pub protocol: u8,
+    pub _pad: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServiceValue {
+    pub service_id: u32,
+    pub endpoint_count: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndpointValue {
+    pub addr: [u8; 16],
+    pub port: u16,
+    pub _pad: u16,
+    pub service_id: u32,
+    /// Zero once the endpoint is withdrawn, which ends its connections' affinity
+    pub weight: u32,
+}
+
+/// Traffic the program has sent to one endpoint, on one CPU
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointCounters {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+// SAFETY: plain `repr(C)` structs of integers with explicit padding
+unsafe impl aya::Pod for ServiceKey {}
+unsafe impl aya::Pod for ServiceValue {}
+unsafe impl aya::Pod for EndpointValue {}
+unsafe impl aya::Pod for EndpointCounters {}
+
+/// Transport protocol of a balanced service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum L4Protocol {
+    Tcp,
+    Udp,
+}
+
+impl L4Protocol {
+    /// IP protocol number
+    pub fn number(self) -> u8 {
+        match self {
+            L4Protocol::Tcp => 6,
+            L4Protocol::Udp => 17,
+        }
+    }
+}
+
+/// How the program is attached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XdpMode {
+    /// In the networking stack; works with any driver
+    Generic,
+    /// In the driver, before any allocation
+    Native,
+    /// On the NIC itself
+    Offload,
+}
+
+impl XdpMode {
+    fn flags(self) -> XdpFlags {
+        match self {
+            XdpMode::Generic => XdpFlags::SKB_MODE,
+            XdpMode::Native => XdpFlags::DRV_MODE,
+            XdpMode::Offload => XdpFlags::HW_MODE,
+        }
+    }
+}
+
+/// XDP load balancer configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XdpLbConfig {
+    pub enabled: bool,
+    /// Compiled `bpf/xdp_lb.c`
+    pub object_path: String,
+    pub mode: XdpMode,
+    /// Idle time after which a connection may be rebalanced
+    pub affinity_timeout_secs: u64,
+}
+
+impl Default for XdpLbConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            object_path: "/usr/lib/nexus/bpf/xdp_lb.o".to_string(),
+            mode: XdpMode::Native,
+            affinity_timeout_secs: 300,
+        }
+    }
+}
+
+/// Packets and bytes the program has sent to an endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointTraffic {
+    pub service: SocketAddr,
+    pub protocol: L4Protocol,
+    pub endpoint: SocketAddr,
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+/// A Maglev lookup table: the index of the endpoint owning each slot
+///
+/// Endpoints are keyed by a stable name, so the same endpoints always give
+/// the same table. Each round, every endpoint claims its weight in slots,
+/// each at the next free position of its own permutation of the table.
+pub fn maglev_table(endpoints: &[(String, u32)], size: usize) -> Vec<usize> {
+    if size < 2 || endpoints.iter().all(|(_, weight)| *weight == 0) {
+        return Vec::new();
+    }
+    let permutations: Vec<(usize, usize)> = endpoints
+        .iter()
+        .map(|(key, _)| {
+            let offset = fnv1a(key, 0) as usize % size;
+            let skip = fnv1a(key, 1) as usize % (size - 1) + 1;
+            (offset, skip)
+        })
+        .collect();
+
+    let mut table = vec![usize::MAX; size];
+    let mut next = vec![0usize; endpoints.len()];
+    let mut filled = 0;
+    loop {
+        for (index, (_, weight)) in endpoints.iter().enumerate() {
+            let (offset, skip) = permutations[index];
+            for _ in 0..*weight {
+                let mut slot = (offset + next[index] * skip) % size;
+                while table[slot] != usize::MAX {
+                    next[index] += 1;
+                    slot = (offset + next[index] * skip) % size;
+                }
+                table[slot] = index;
+                next[index] += 1;
+                filled += 1;
+                if filled == size {
+                    return table;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn fnv1a(key: &str, seed: u8) -> u64 {
+    std::iter::once(seed).chain(key.bytes()).fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn mapped(ip: IpAddr) -> [u8; 16] {
+    match ip {
+        IpAddr::V4(v4) => v4.to_ipv6_mapped().octets(),
+        IpAddr::V6(v6) => v6.octets(),
+    }
+}
+
+/// A service as it is programmed into the maps
+#[derive(Debug, Clone)]
+struct ServiceState {
+    id: u32,
+    /// Endpoint, its ID in the maps, and its weight
+    endpoints: Vec<(SocketAddr, u32, u32)>,
+}
+
+/// Keeps the XDP program's service, table and endpoint maps in step with the service pools
+pub struct XdpLoadBalancer {
+    config: XdpLbConfig,
+    ebpf: Option<Ebpf>,
+    services: HashMap<(SocketAddr, L4Protocol), ServiceState>,
+    service_ids: BTreeSet<u32>,
+    endpoint_ids: BTreeSet<u32>,
+}
+
+impl XdpLoadBalancer {
+    pub fn new(config: XdpLbConfig) -> Self {
+        Self {
+            config,
+            ebpf: None,
+            services: HashMap::new(),
+            service_ids: BTreeSet::new(),
+            endpoint_ids: BTreeSet::new(),
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.ebpf.is_some()
+    }
+
+    /// Load the program, attach it to `interfaces` and program the services known so far
+    pub fn load(&mut self, interfaces: &[String]) -> Result<()> {
+        let path = &self.config.object_path;
+        let mut ebpf = Ebpf::load_file(path).with_context(|| format!("Failed to load XDP object {}", path))?;
+        let program: &mut Xdp = ebpf
+            .program_mut(XDP_LB_PROGRAM)
+            .ok_or_else(|| anyhow!("{} has no {} program", path, XDP_LB_PROGRAM))?
+            .try_into()?;
+        program.load()?;
+        for interface in interfaces {
+            program
+                .attach(interface, self.config.mode.flags())
+                .with_context(|| format!("Failed to attach XDP load balancer to {}", interface))?;
+        }
+
+        let mut settings: Array<_, u64> = Array::try_from(map_mut(&mut ebpf, LB_SETTINGS_MAP)?)?;
+        settings.set(0, self.config.affinity_timeout_secs.saturating_mul(1_000_000_000), 0)?;
+
+        for ((service, protocol), state) in &self.services {
+            write_service(&mut ebpf, *service, *protocol, state, &[])?;
+        }
+        info!("⚖️ XDP load balancer attached to {:?} ({} services)", interfaces, self.services.len());
+        self.ebpf = Some(ebpf);
+        Ok(())
+    }
+
+    /// Detach the program; the maps go with it
+    pub fn unload(&mut self) {
+        if self.ebpf.take().is_some() {
+            info!("XDP load balancer detached");
+        }
+    }
+
+    /// Balance a service address over the usable endpoints
+    ///
+    /// Unhealthy and zero-weight endpoints get no new connections, and
+    /// their established connections are rebalanced.
+    pub fn set_service(&mut self, service: SocketAddr, protocol: L4Protocol, endpoints: &[ServiceEndpoint]) -> Result<()> {
+        let usable: Vec<&ServiceEndpoint> = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.weight > 0 && !matches!(endpoint.health_status, EndpointHealth::Unhealthy))
+            .collect();
+        if usable.iter().any(|endpoint| endpoint.ip.is_ipv4() != service.is_ipv4()) {
+            return Err(anyhow!("Endpoints of {} must share its address family", service));
+        }
+
+        let previous = self.services.get(&(service, protocol)).cloned();
+        let id = match &previous {
+            Some(state) => state.id,
+            None => next_id(&self.service_ids, MAX_SERVICES).ok_or_else(|| anyhow!("No room for another XDP service"))?,
+        };
+        let mut fresh = Vec::new();
+        let mut state = ServiceState { id, endpoints: Vec::with_capacity(usable.len()) };
+        for endpoint in usable {
+            let addr = SocketAddr::new(endpoint.ip, endpoint.port);
+            let known = previous.as_ref().and_then(|p| p.endpoints.iter().find(|(a, _, _)| *a == addr));
+            let endpoint_id = match known {
+                Some((_, endpoint_id, _)) => *endpoint_id,
+                None => {
+                    let endpoint_id = next_id(&self.endpoint_ids, MAX_ENDPOINTS)
+                        .ok_or_else(|| anyhow!("No room for another XDP endpoint"))?;
+                    self.endpoint_ids.insert(endpoint_id);
+                    fresh.push(endpoint_id);
+                    endpoint_id
+                }
+            };
+            state.endpoints.push((addr, endpoint_id, endpoint.weight));
+        }
+        let withdrawn: Vec<u32> = previous
+            .iter()
+            .flat_map(|p| p.endpoints.iter())
+            .filter(|(_, endpoint_id, _)| !state.endpoints.iter().any(|(_, kept, _)| kept == endpoint_id))
+            .map(|(_, endpoint_id, _)| *endpoint_id)
+            .collect();
+
+        if let Some(ebpf) = self.ebpf.as_mut() {
+            write_service(ebpf, service, protocol, &state, &fresh)?;
+            withdraw_endpoints(ebpf, &withdrawn)?;
+        }
+        for endpoint_id in &withdrawn {
+            self.endpoint_ids.remove(endpoint_id);
+        }
+        debug!("XDP service {} {:?}: {} endpoints", service, protocol, state.endpoints.len());
+        self.service_ids.insert(id);
+        self.services.insert((service, protocol), state);
+        Ok(())
+    }
+
+    /// Stop balancing a service address
+    pub fn remove_service(&mut self, service: SocketAddr, protocol: L4Protocol) -> Result<()> {
+        let Some(state) = self.services.remove(&(service, protocol)) else {
+            return Ok(());
+        };
+        let endpoint_ids: Vec<u32> = state.endpoints.iter().map(|(_, endpoint_id, _)| *endpoint_id).collect();
+        if let Some(ebpf) = self.ebpf.as_mut() {
+            let mut services: BpfHashMap<_, ServiceKey, ServiceValue> =
+                BpfHashMap::try_from(map_mut(ebpf, LB_SERVICES_MAP)?)?;
+            services.remove(&service_key(service, protocol))?;
+            withdraw_endpoints(ebpf, &endpoint_ids)?;
+        }
+        for endpoint_id in endpoint_ids {
+            self.endpoint_ids.remove(&endpoint_id);
+        }
+        self.service_ids.remove(&state.id);
+        Ok(())
+    }
+
+    /// Packets and bytes sent to each endpoint, summed over CPUs
+    pub fn endpoint_traffic(&self) -> Result<Vec<EndpointTraffic>> {
+        let Some(ebpf) = self.ebpf.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let map = ebpf.map(LB_ENDPOINT_STATS_MAP).ok_or_else(|| anyhow!("{} map missing", LB_ENDPOINT_STATS_MAP))?;
+        let stats: PerCpuArray<_, EndpointCounters> = PerCpuArray::try_from(map)?;
+
+        let mut traffic = Vec::new();
+        for ((service, protocol), state) in &self.services {
+            for (endpoint, endpoint_id, _) in &state.endpoints {
+                let per_cpu = stats.get(endpoint_id, 0)?;
+                traffic.push(EndpointTraffic {
+                    service: *service,
+                    protocol: *protocol,
+                    endpoint: *endpoint,
+                    packets: per_cpu.iter().map(|c| c.packets).sum(),
+                    bytes: per_cpu.iter().map(|c| c.bytes).sum(),
+                });
+            }
+        }
+        Ok(traffic)
+    }
+}
+
+impl std::fmt::Debug for XdpLoadBalancer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XdpLoadBalancer")
+            .field("config", &self.config)
+            .field("loaded", &self.is_loaded())
+            .field("services", &self.services.len())
+            .finish()
+    }
+}
+
+fn next_id(used: &BTreeSet<u32>, max: u32) -> Option<u32> {
+    (0..max).find(|id| !used.contains(id))
+}
+
+fn service_key(service: SocketAddr, protocol: L4Protocol) -> ServiceKey {
+    ServiceKey { addr: mapped(service.ip()), port: service.port(), protocol: protocol.number(), _pad: 0 }
+}
+
+fn map_mut<'a>(ebpf: &'a mut Ebpf, name: &str) -> Result<&'a mut Map> {
+    ebpf.map_mut(name).ok_or_else(|| anyhow!("{} map missing from the XDP object", name))
+}
+
+/// Write a service's endpoints and table, then the service entry pointing at them
+fn write_service(ebpf: &mut Ebpf, service: SocketAddr, protocol: L4Protocol, state: &ServiceState, fresh: &[u32]) -> Result<()> {
+    // Counters of a reused endpoint ID start from zero
+    if !fresh.is_empty() {
+        let cpus = aya::util::nr_cpus().map_err(|(_, e)| e)?;
+        let mut stats: PerCpuArray<_, EndpointCounters> = PerCpuArray::try_from(map_mut(ebpf, LB_ENDPOINT_STATS_MAP)?)?;
+        for endpoint_id in fresh {
+            stats.set(*endpoint_id, PerCpuValues::try_from(vec![EndpointCounters::default(); cpus])?, 0)?;
+        }
+    }
+
+    let mut endpoints: Array<_, EndpointValue> = Array::try_from(map_mut(ebpf, LB_ENDPOINTS_MAP)?)?;
+    for (addr, endpoint_id, weight) in &state.endpoints {
+        let value = EndpointValue {
+            addr: mapped(addr.ip()),
+            port: addr.port(),
+            _pad: 0,
+            service_id: state.id,
+            weight: *weight,
+        };
+        endpoints.set(*endpoint_id, value, 0)?;
+    }
+
+    let keys: Vec<(String, u32)> = state.endpoints.iter().map(|(addr, _, weight)| (addr.to_string(), *weight)).collect();
+    let table = maglev_table(&keys, MAGLEV_TABLE_SIZE);
+    let mut maglev: Array<_, u32> = Array::try_from(map_mut(ebpf, LB_MAGLEV_MAP)?)?;
+    let base = state.id as usize * MAGLEV_TABLE_SIZE;
+    for (slot, index) in table.iter().enumerate() {
+        maglev.set((base + slot) as u32, state.endpoints[*index].1, 0)?;
+    }
+
+    let mut services: BpfHashMap<_, ServiceKey, ServiceValue> = BpfHashMap::try_from(map_mut(ebpf, LB_SERVICES_MAP)?)?;
+    let value = ServiceValue { service_id: state.id, endpoint_count: state.endpoints.len() as u32 };
+    services.insert(service_key(service, protocol), value, 0)?;
+    Ok(())
+}
+
+/// Mark endpoints withdrawn so no connection is sent to them any more
+fn withdraw_endpoints(ebpf: &mut Ebpf, endpoint_ids: &[u32]) -> Result<()> {
+    let mut endpoints: Array<_, EndpointValue> = Array::try_from(map_mut(ebpf, LB_ENDPOINTS_MAP)?)?;
+    for endpoint_id in endpoint_ids {
+        endpoints.set(*endpoint_id, EndpointValue::default(), 0)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn endpoints(count: u8) -> Vec<(String, u32)> {
+        (1..=count).map(|i| (format!("10.0.0.{}:80", i), 1)).collect()
+    }
+
+    #[test]
+    fn test_maglev_weights_and_minimal_disruption() {
+        let weighted = vec![("10.0.0.1:80".to_string(), 1), ("10.0.0.2:80".to_string(), 1), ("10.0.0.3:80".to_string(), 2)];
+        let table = maglev_table(&weighted, MAGLEV_TABLE_SIZE);
+        let share = |index| table.iter().filter(|slot| **slot == index).count();
+        assert_eq!((share(0), share(1), share(2)), (1025, 1025, 2049));
+        assert_eq!(maglev_table(&weighted, MAGLEV_TABLE_SIZE), table);
+
+        // Removing one endpoint moves few slots between the others
+        let before = maglev_table(&endpoints(10), MAGLEV_TABLE_SIZE);
+        let after = maglev_table(&endpoints(9), MAGLEV_TABLE_SIZE);
+        let moved = before.iter().zip(&after).filter(|(b, a)| **b != 9 && b != a).count();
+        assert!(moved < MAGLEV_TABLE_SIZE / 50, "{} slots moved", moved);
+        assert!(maglev_table(&[], MAGLEV_TABLE_SIZE).is_empty());
+    }
+
+    #[test]
+    fn test_endpoint_ids_follow_endpoints() {
+        let mut balancer = XdpLoadBalancer::new(XdpLbConfig::default());
+        let vip: SocketAddr = "10.96.0.1:80".parse().unwrap();
+        let endpoint = |last, health| ServiceEndpoint {
+            ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)),
+            port: 8080,
+            weight: 1,
+            health_status: health,
+        };
+
+        balancer.set_service(vip, L4Protocol::Tcp, &[endpoint(1, EndpointHealth::Healthy), endpoint(2, EndpointHealth::Healthy)]).unwrap();
+        balancer.set_service(vip, L4Protocol::Tcp, &[endpoint(2, EndpointHealth::Healthy), endpoint(3, EndpointHealth::Unhealthy)]).unwrap();
+        let state = &balancer.services[&(vip, L4Protocol::Tcp)];
+        assert_eq!(state.endpoints.iter().map(|(_, id, _)| *id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(balancer.endpoint_ids, BTreeSet::from([1]));
+
+        let ipv6 = ServiceEndpoint { ip: "fd00::1".parse().unwrap(), ..endpoint(1, EndpointHealth::Healthy) };
+        assert!(balancer.set_service(vip, L4Protocol::Udp, &[ipv6]).is_err());
+
+        balancer.remove_service(vip, L4Protocol::Tcp).unwrap();
+        assert!(balancer.endpoint_ids.is_empty() && balancer.service_ids.is_empty());
+        assert!(balancer.endpoint_traffic().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+//! Sampled per-request access logs for mesh traffic
+//!
+//! Every request the router sends produces one structured entry: who called
+//! which service, the endpoint and split backend it reached, latency, bytes
+//! each way, the result and a trace ID. Entries are sampled before anything
+//! is written. Successful requests are logged at `sample_rate`, or at a
+//! per-service rate while one service is being debugged. Failed and slow
+//! requests are logged at `error_sample_rate`, which defaults to all of
+//! them. Sampled entries go to the configured sinks: tracing events, a
+//! JSON-lines file, or an in-memory buffer of recent entries.
+
+use crate::error::{NetworkError, Result};
+use nexus_shared::{NodeId, ServiceId};
+use parking_lot::Mutex;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Tracing target access log events are emitted under
+pub const ACCESS_LOG_TARGET: &str = "nexus::access";
+
+/// Where sampled entries are written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccessLogSink {
+    /// An info event under [`ACCESS_LOG_TARGET`]
+    Tracing,
+    /// One JSON object per line, appended to a file
+    File { path: PathBuf },
+    /// The most recent entries, kept for [`AccessLogger::recent`]
+    Memory { capacity: usize },
+}
+
+/// Access logging configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogConfig {
+    pub enabled: bool,
+
+    /// Share of successful requests logged
+    pub sample_rate: f64,
+
+    /// Share of failed and slow requests logged
+    pub error_sample_rate: f64,
+
+    /// Requests at least this slow are sampled like failures
+    pub slow_threshold: Option<Duration>,
+
+    /// Successful-request rates for individual services, by name
+    #[serde(default)]
+    pub service_sample_rates: HashMap<String, f64>,
+
+    pub sinks: Vec<AccessLogSink>,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_rate: 0.01,
+            error_sample_rate: 1.0,
+            slow_threshold: Some(Duration::from_secs(1)),
+            service_sample_rates: HashMap::new(),
+            sinks: vec![AccessLogSink::Tracing],
+        }
+    }
+}
+
+/// Identifier tying together the log entries of one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TraceId(pub u128);
+
+impl TraceId {
+    pub fn generate() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+}
+
+impl std::fmt::Display for TraceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// One routed request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessLogEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub trace_id: String,
+    /// Node that sent the request
+    pub caller: NodeId,
+    /// Service the request was routed to
+    pub callee: ServiceId,
+    /// Instance that served it, if one was selected
+    pub endpoint: Option<SocketAddr>,
+    pub endpoint_node: Option<NodeId>,
+    /// Traffic split backend the request was sent to
+    pub backend: Option<String>,
+    pub latency_ms: f64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    /// `ok`, or the category of the error
+    pub result: String,
+    pub error: Option<String>,
+    /// Workload that signed the request, if it was signed
+    #[serde(default)]
+    pub signed_by: Option<String>,
+}
+
+impl AccessLogEntry {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Access log counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessLogStats {
+    pub requests: u64,
+    pub logged: u64,
+    pub sampled_out: u64,
+    pub write_errors: u64,
+}
+
+/// Samples routed requests and writes them to the configured sinks
+pub struct AccessLogger {
+    config: AccessLogConfig,
+    file: Option<Mutex<LineWriter<File>>>,
+    recent: Option<(usize, Mutex<VecDeque<AccessLogEntry>>)>,
+    requests: AtomicU64,
+    logged: AtomicU64,
+    write_errors: AtomicU64,
+}
+
+impl AccessLogger {
+    pub fn new(config: AccessLogConfig) -> Result<Self> {
+        let mut file = None;
+        let mut recent = None;
+        for sink in &config.sinks {
+            match sink {
+                AccessLogSink::Tracing => {}
+                AccessLogSink::File { path } => {
+                    let opened = OpenOptions::new().create(true).append(true).open(path).map_err(|e| {
+                        NetworkError::Configuration {
+                            message: format!("cannot open access log {}: {}", path.display(), e),
+                        }
+                    })?;
+                    file = Some(Mutex::new(LineWriter::new(opened)));
+                }
+                AccessLogSink::Memory { capacity } => recent = Some((*capacity, Mutex::new(VecDeque::new()))),
+            }
+        }
+        Ok(Self {
+            config,
+            file,
+            recent,
+            requests: AtomicU64::new(0),
+            logged: AtomicU64::new(0),
+            write_errors: AtomicU64::new(0),
+        })
+    }
+
+    pub fn config(&self) -> &AccessLogConfig {
+        &self.config
+    }
+
+    /// Share of requests like this one that are logged
+    pub fn sample_rate(&self, service: &ServiceId, success: bool, latency: Duration) -> f64 {
+        let slow = self.config.slow_threshold.is_some_and(|threshold| latency >= threshold);
+        if !success || slow {
+            return self.config.error_sample_rate;
+        }
+        self.config
+            .service_sample_rates
+            .get(service.name())
+            .copied()
+            .unwrap_or(self.config.sample_rate)
+    }
+
+    /// Log a request if it is sampled, returning whether it was
+    pub fn record(&self, entry: AccessLogEntry) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let latency = Duration::from_secs_f64(entry.latency_ms.max(0.0) / 1000.0);
+        let rate = self.sample_rate(&entry.callee, entry.is_success(), latency);
+        if rate <= 0.0 || (rate < 1.0 && !rand::thread_rng().gen_bool(rate)) {
+            return false;
+        }
+        self.logged.fetch_add(1, Ordering::Relaxed);
+
+        if self.config.sinks.contains(&AccessLogSink::Tracing) {
+            tracing::info!(
+                target: ACCESS_LOG_TARGET,
+                trace_id = %entry.trace_id,
+                caller = %entry.caller,
+                callee = %entry.callee,
+                endpoint = ?entry.endpoint,
+                backend = ?entry.backend,
+                latency_ms = entry.latency_ms,
+                request_bytes = entry.request_bytes,
+                response_bytes = entry.response_bytes,
+                result = %entry.result,
+                error = ?entry.error,
+                "access"
+            );
+        }
+        if let Some(file) = &self.file {
+            let written = serde_json::to_string(&entry)
+                .map_err(std::io::Error::from)
+                .and_then(|line| writeln!(file.lock(), "{}", line));
+            if let Err(e) = written {
+                self.write_errors.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("Failed to write access log entry: {}", e);
+            }
+        }
+        if let Some((capacity, recent)) = &self.recent {
+            let mut recent = recent.lock();
+            if recent.len() == *capacity {
+                recent.pop_front();
+            }
+            if *capacity > 0 {
+                recent.push_back(entry);
+            }
+        }
+        true
+    }
+
+    /// Most recent entries kept by the memory sink, newest first
+    pub fn recent(&self, limit: usize) -> Vec<AccessLogEntry> {
+        match &self.recent {
+            Some((_, recent)) => recent.lock().iter().rev().take(limit).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn stats(&self) -> AccessLogStats {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let logged = self.logged.load(Ordering::Relaxed);
+        AccessLogStats {
+            requests,
+            logged,
+            sampled_out: requests - logged,
+            write_errors: self.write_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl std::fmt::Debug for AccessLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessLogger")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(service: &str, latency_ms: f64, error: Option<&str>) -> AccessLogEntry {
+        AccessLogEntry {
+            timestamp: chrono::Utc::now(),
+            trace_id: TraceId::generate().to_string(),
+            caller: NodeId::random(),
+            callee: ServiceId::new(service, "default"),
+            endpoint: None,
+            endpoint_node: None,
+            backend: None,
+            latency_ms,
+            request_bytes: 12,
+            response_bytes: 0,
+            result: error.map_or("ok".to_string(), |_| "request_failed".to_string()),
+            error: error.map(str::to_string),
+            signed_by: None,
+        }
+    }
+
+    #[test]
+    fn test_failures_and_slow_requests_bypass_sampling() {
+        let logger = AccessLogger::new(AccessLogConfig {
+            sample_rate: 0.0,
+            service_sample_rates: HashMap::from([("checkout".to_string(), 1.0)]),
+            sinks: vec![AccessLogSink::Memory { capacity: 3 }],
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(!logger.record(entry("api", 5.0, None)));
+        assert!(logger.record(entry("api", 5.0, Some("connection reset"))));
+        assert!(logger.record(entry("api", 1500.0, None)));
+        assert!(logger.record(entry("checkout", 5.0, None)));
+        assert!(logger.record(entry("checkout", 6.0, None)));
+
+        let recent = logger.recent(10);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].latency_ms, 6.0);
+        assert_eq!(recent[2].latency_ms, 1500.0);
+        assert_eq!(logger.stats(), AccessLogStats { requests: 5, logged: 4, sampled_out: 1, write_errors: 0 });
+        assert_eq!(TraceId(255).to_string().len(), 32);
+    }
+}
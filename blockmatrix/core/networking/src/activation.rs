@@ -0,0 +1,325 @@
+//! Scale-to-zero activation
+//!
+//! A service the autoscaler has scaled to zero keeps accepting requests. The
+//! first request to find no instances is held, an activation request is
+//! broadcast for the autoscaler, and held requests are forwarded as soon as
+//! an instance is registered or shows up in discovery. The time from the
+//! activation request to the first ready instance is recorded as the
+//! cold-start latency. The activator also tracks when each service last
+//! received traffic, which is how the autoscaler decides a service is idle.
+
+use crate::error::{NetworkError, Result};
+use nexus_shared::ServiceId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, Notify};
+use tracing::{info, warn};
+
+/// Activation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivationConfig {
+    pub enabled: bool,
+
+    /// How long a held request waits for a replica before failing
+    pub activation_timeout: Duration,
+
+    /// Requests held per service; further requests fail immediately
+    pub max_queued_requests: usize,
+
+    /// Interval between discovery lookups while waiting
+    pub poll_interval: Duration,
+}
+
+impl Default for ActivationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            activation_timeout: Duration::from_secs(30),
+            max_queued_requests: 100,
+            poll_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Request to start a replica of a service scaled to zero
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivationRequest {
+    pub service_id: ServiceId,
+    pub requested_at: SystemTime,
+}
+
+/// Activation counters and cold-start latency
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivationStats {
+    pub activations: u64,
+    pub timeouts: u64,
+    /// Requests refused because the hold queue was full
+    pub rejected: u64,
+    pub last_cold_start_ms: u64,
+    pub mean_cold_start_ms: f64,
+    pub max_cold_start_ms: u64,
+}
+
+#[derive(Debug)]
+struct ServiceActivity {
+    last_request: Instant,
+    scaled_to_zero: bool,
+    /// Requests currently held
+    waiting: usize,
+    /// When the pending activation was requested
+    activation_started: Option<Instant>,
+}
+
+/// Holds requests for services scaled to zero until a replica is ready
+#[derive(Debug)]
+pub struct Activator {
+    config: ActivationConfig,
+    services: Mutex<HashMap<ServiceId, ServiceActivity>>,
+    ready: Notify,
+    requests: broadcast::Sender<ActivationRequest>,
+    stats: Mutex<ActivationStats>,
+}
+
+impl Activator {
+    pub fn new(config: ActivationConfig) -> Self {
+        let (requests, _) = broadcast::channel(1024);
+        Self {
+            config,
+            services: Mutex::new(HashMap::new()),
+            ready: Notify::new(),
+            requests,
+            stats: Mutex::new(ActivationStats::default()),
+        }
+    }
+
+    /// Activation requests for the autoscaler
+    pub fn subscribe(&self) -> broadcast::Receiver<ActivationRequest> {
+        self.requests.subscribe()
+    }
+
+    /// Note a request to a service
+    pub fn record_request(&self, service_id: &ServiceId) {
+        self.services
+            .lock()
+            .unwrap()
+            .entry(service_id.clone())
+            .and_modify(|activity| activity.last_request = Instant::now())
+            .or_insert_with(|| ServiceActivity {
+                last_request: Instant::now(),
+                scaled_to_zero: false,
+                waiting: 0,
+                activation_started: None,
+            });
+    }
+
+    /// Time since the service last received a request, if it ever did
+    pub fn idle_for(&self, service_id: &ServiceId) -> Option<Duration> {
+        let services = self.services.lock().unwrap();
+        services.get(service_id).map(|activity| activity.last_request.elapsed())
+    }
+
+    /// Hold requests to the service from now on instead of failing them
+    pub fn mark_scaled_to_zero(&self, service_id: &ServiceId) {
+        let mut services = self.services.lock().unwrap();
+        let activity = services.entry(service_id.clone()).or_insert_with(|| ServiceActivity {
+            last_request: Instant::now(),
+            scaled_to_zero: true,
+            waiting: 0,
+            activation_started: None,
+        });
+        activity.scaled_to_zero = true;
+    }
+
+    pub fn is_scaled_to_zero(&self, service_id: &ServiceId) -> bool {
+        self.config.enabled
+            && self.services.lock().unwrap().get(service_id).is_some_and(|activity| activity.scaled_to_zero)
+    }
+
+    /// Services with held requests waiting for a replica
+    pub fn pending_activations(&self) -> Vec<ServiceId> {
+        self.services
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, activity)| activity.activation_started.is_some())
+            .map(|(service_id, _)| service_id.clone())
+            .collect()
+    }
+
+    /// Wake held requests to look for instances again
+    pub fn notify_ready(&self) {
+        self.ready.notify_waiters();
+    }
+
+    pub fn stats(&self) -> ActivationStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Hold a request until `resolve` finds instances of the service
+    ///
+    /// The first held request broadcasts an activation request. Fails with
+    /// `ActivationQueueFull` when too many requests are already held and
+    /// with `ActivationTimeout` if no instance appears in time.
+    pub async fn activate<T, F, Fut>(&self, service_id: &ServiceId, resolve: F) -> Result<Vec<T>>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Vec<T>>>,
+    {
+        let _held = self.hold(service_id)?;
+        let deadline = tokio::time::Instant::now() + self.config.activation_timeout;
+
+        loop {
+            let ready = self.ready.notified();
+            let instances = resolve().await?;
+            if !instances.is_empty() {
+                self.activated(service_id);
+                return Ok(instances);
+            }
+
+            let wake = tokio::time::Instant::now() + self.config.poll_interval;
+            if wake >= deadline {
+                tokio::select! {
+                    _ = ready => continue,
+                    _ = tokio::time::sleep_until(deadline) => break,
+                }
+            }
+            tokio::select! {
+                _ = ready => {}
+                _ = tokio::time::sleep_until(wake) => {}
+            }
+        }
+
+        // One last look in case the replica arrived right at the deadline
+        let instances = resolve().await?;
+        if !instances.is_empty() {
+            self.activated(service_id);
+            return Ok(instances);
+        }
+        self.stats.lock().unwrap().timeouts += 1;
+        warn!("Activation of {} timed out after {:?}", service_id, self.config.activation_timeout);
+        Err(NetworkError::ActivationTimeout {
+            service_id: service_id.clone(),
+            timeout_ms: self.config.activation_timeout.as_millis() as u64,
+        })
+    }
+
+    fn hold(&self, service_id: &ServiceId) -> Result<HeldRequest<'_>> {
+        let mut services = self.services.lock().unwrap();
+        let activity = services.entry(service_id.clone()).or_insert_with(|| ServiceActivity {
+            last_request: Instant::now(),
+            scaled_to_zero: true,
+            waiting: 0,
+            activation_started: None,
+        });
+        if activity.waiting >= self.config.max_queued_requests {
+            self.stats.lock().unwrap().rejected += 1;
+            return Err(NetworkError::ActivationQueueFull { service_id: service_id.clone() });
+        }
+        activity.waiting += 1;
+        if activity.activation_started.is_none() {
+            activity.activation_started = Some(Instant::now());
+            info!("Activating {} on first request", service_id);
+            let _ = self.requests.send(ActivationRequest {
+                service_id: service_id.clone(),
+                requested_at: SystemTime::now(),
+            });
+        }
+        Ok(HeldRequest { activator: self, service_id: service_id.clone() })
+    }
+
+    /// Record the cold start once the first held request finds a replica
+    fn activated(&self, service_id: &ServiceId) {
+        let started = {
+            let mut services = self.services.lock().unwrap();
+            let Some(activity) = services.get_mut(service_id) else {
+                return;
+            };
+            activity.scaled_to_zero = false;
+            activity.activation_started.take()
+        };
+        let Some(started) = started else {
+            return;
+        };
+
+        let cold_start_ms = started.elapsed().as_millis() as u64;
+        let mut stats = self.stats.lock().unwrap();
+        stats.activations += 1;
+        stats.last_cold_start_ms = cold_start_ms;
+        stats.max_cold_start_ms = stats.max_cold_start_ms.max(cold_start_ms);
+        stats.mean_cold_start_ms += (cold_start_ms as f64 - stats.mean_cold_start_ms) / stats.activations as f64;
+        info!("Activated {} after {}ms cold start", service_id, cold_start_ms);
+    }
+}
+
+/// A held request; releases its queue slot when dropped
+struct HeldRequest<'a> {
+    activator: &'a Activator,
+    service_id: ServiceId,
+}
+
+impl Drop for HeldRequest<'_> {
+    fn drop(&mut self) {
+        let mut services = self.activator.services.lock().unwrap();
+        if let Some(activity) = services.get_mut(&self.service_id) {
+            activity.waiting -= 1;
+            if activity.waiting == 0 {
+                // A later request signals the autoscaler again
+                activity.activation_started = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_first_request_activates_service() {
+        let activator = Arc::new(Activator::new(ActivationConfig {
+            max_queued_requests: 1,
+            poll_interval: Duration::from_secs(10),
+            ..Default::default()
+        }));
+        let service_id = ServiceId::new("api", "default");
+        let replicas = Arc::new(Mutex::new(Vec::<SocketAddr>::new()));
+        let mut requests = activator.subscribe();
+        activator.mark_scaled_to_zero(&service_id);
+        assert!(activator.is_scaled_to_zero(&service_id));
+
+        let held = {
+            let (activator, service_id, replicas) = (activator.clone(), service_id.clone(), replicas.clone());
+            tokio::spawn(async move {
+                activator
+                    .activate(&service_id, || {
+                        let addresses = replicas.lock().unwrap().clone();
+                        async move { Ok(addresses) }
+                    })
+                    .await
+            })
+        };
+
+        assert_eq!(requests.recv().await.unwrap().service_id, service_id);
+        assert_eq!(activator.pending_activations(), vec![service_id.clone()]);
+
+        // The hold queue holds one request
+        let rejected = activator.activate(&service_id, || async { Ok(Vec::new()) }).await;
+        assert!(matches!(rejected, Err(NetworkError::ActivationQueueFull { .. })));
+
+        // A replica comes up; the held request is woken well before the next poll
+        replicas.lock().unwrap().push("127.0.0.1:8080".parse().unwrap());
+        activator.notify_ready();
+        let addresses = tokio::time::timeout(Duration::from_secs(1), held).await.unwrap().unwrap().unwrap();
+        assert_eq!(addresses.len(), 1);
+
+        let stats = activator.stats();
+        assert_eq!((stats.activations, stats.rejected), (1, 1));
+        assert!(!activator.is_scaled_to_zero(&service_id));
+        assert!(activator.pending_activations().is_empty());
+    }
+}
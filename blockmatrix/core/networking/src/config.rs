@@ -1,9 +1,21 @@
 //! Network configuration
 
 use crate::discovery::ServiceDiscoveryConfig as DiscoveryConfig;
+use crate::discovery_cache::DiscoveryCacheConfig;
+use crate::activation::ActivationConfig;
 use crate::health_check::HealthCheckConfig as HealthConfig;
+use crate::health_probe::ProbeSpec;
 use crate::circuit_breaker::CircuitBreakerConfig as CircuitConfig;
 use crate::dht::DhtConfig;
+use crate::gossip::GossipConfig;
+use crate::retry_budget::RetryBudgetConfig;
+use crate::link_probe::ProbeConfig;
+use crate::outlier_detection::OutlierDetectionConfig;
+use crate::drain::DrainConfig;
+use crate::synthetic::SyntheticConfig;
+use crate::access_log::{AccessLogConfig, AccessLogSink};
+use crate::request_signing::RequestSigningConfig;
+use nexus_shared::{Validate, ValidationReport};
 use nexus_transport::TransportConfig;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -12,10 +24,25 @@ use std::time::Duration;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
     pub service_discovery: DiscoveryConfig,
+    pub discovery_cache: DiscoveryCacheConfig,
+    pub activation: ActivationConfig,
     pub load_balancing: LoadBalancingConfig,
     pub circuit_breaker: CircuitConfig,
     pub health_check: HealthConfig,
     pub dht: DhtConfig,
+    pub gossip: GossipConfig,
+    #[serde(default)]
+    pub retry_budget: RetryBudgetConfig,
+    #[serde(default)]
+    pub probing: ProbeConfig,
+    #[serde(default)]
+    pub draining: DrainConfig,
+    #[serde(default)]
+    pub synthetic: SyntheticConfig,
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+    #[serde(default)]
+    pub request_signing: RequestSigningConfig,
     pub metrics: MetricsConfig,
     pub transport: TransportConfig,
 }
@@ -24,10 +51,19 @@ impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             service_discovery: DiscoveryConfig::default(),
+            discovery_cache: DiscoveryCacheConfig::default(),
+            activation: ActivationConfig::default(),
             load_balancing: LoadBalancingConfig::default(),
             circuit_breaker: CircuitConfig::default(),
             health_check: HealthConfig::default(),
             dht: DhtConfig::default(),
+            gossip: GossipConfig::default(),
+            retry_budget: RetryBudgetConfig::default(),
+            probing: ProbeConfig::default(),
+            draining: DrainConfig::default(),
+            synthetic: SyntheticConfig::default(),
+            access_log: AccessLogConfig::default(),
+            request_signing: RequestSigningConfig::default(),
             metrics: MetricsConfig::default(),
             transport: TransportConfig::default(),
         }
@@ -35,16 +71,336 @@ impl Default for NetworkConfig {
 }
 
 
+impl Validate for NetworkConfig {
+    fn validate_config(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        
+        let discovery = &self.service_discovery;
+        if discovery.cleanup_interval == 0 {
+            report.error("service_discovery.cleanup_interval", "must be greater than zero");
+        }
+        if discovery.service_ttl <= discovery.cleanup_interval {
+            report.error(
+                "service_discovery.service_ttl",
+                format!(
+                    "TTL ({}s) must exceed the cleanup interval ({}s)",
+                    discovery.service_ttl, discovery.cleanup_interval
+                ),
+            );
+        }
+        if discovery.enable_announcements && discovery.announcement_interval >= discovery.service_ttl {
+            report.error(
+                "service_discovery.announcement_interval",
+                format!(
+                    "announcements every {}s let services expire before re-announcing (TTL {}s)",
+                    discovery.announcement_interval, discovery.service_ttl
+                ),
+            );
+        }
+        
+        let cache = &self.discovery_cache;
+        if cache.negative_ttl.as_secs() >= discovery.service_ttl {
+            report.warning(
+                "discovery_cache.negative_ttl",
+                "negative entries outlive service records; new services stay hidden for too long",
+            );
+        }
+        if !cache.critical_services.is_empty() && cache.warm_interval.is_zero() {
+            report.error("discovery_cache.warm_interval", "must be greater than zero");
+        }
+        
+        let activation = &self.activation;
+        if activation.enabled {
+            if activation.max_queued_requests == 0 {
+                report.error("activation.max_queued_requests", "must be at least 1");
+            }
+            if activation.poll_interval.is_zero() {
+                report.error("activation.poll_interval", "must be greater than zero");
+            }
+            if activation.poll_interval >= activation.activation_timeout {
+                report.warning(
+                    "activation.poll_interval",
+                    "held requests look up instances only once before timing out",
+                );
+            }
+        }
+        
+        let health = &self.health_check;
+        if health.timeout >= health.interval {
+            report.error(
+                "health_check.timeout",
+                format!(
+                    "timeout ({:?}) must be less than the check interval ({:?})",
+                    health.timeout, health.interval
+                ),
+            );
+        }
+        if health.healthy_threshold == 0 || health.unhealthy_threshold == 0 {
+            report.error("health_check", "healthy and unhealthy thresholds must be at least 1");
+        }
+        if health.flapping.max_transitions > 0 && health.flapping.window.is_zero() {
+            report.error("health_check.flapping.window", "must be greater than zero");
+        }
+        let mut checks: Vec<(String, ProbeSpec)> = vec![("health_check.probe".to_string(), health.probe.clone())];
+        for (name, service) in &health.services {
+            let field = format!("health_check.services.{}", name);
+            let check = health.check_for(name);
+            if check.timeout >= check.interval {
+                report.error(
+                    format!("{}.timeout", field),
+                    format!("timeout ({:?}) must be less than the check interval ({:?})", check.timeout, check.interval),
+                );
+            }
+            if check.healthy_threshold == 0 || check.unhealthy_threshold == 0 {
+                report.error(field.clone(), "healthy and unhealthy thresholds must be at least 1");
+            }
+            checks.push((format!("{}.probe", field), service.probe.clone()));
+        }
+        for (field, probe) in checks {
+            match probe {
+                ProbeSpec::Exec { command } if command.is_empty() => report.error(field, "exec probe needs a command"),
+                ProbeSpec::Http { path, .. } if !path.starts_with('/') => {
+                    report.error(field, format!("HTTP probe path '{}' must start with '/'", path))
+                }
+                _ => {}
+            }
+        }
+        
+        let breaker = &self.circuit_breaker;
+        if breaker.failure_threshold == 0 {
+            report.error("circuit_breaker.failure_threshold", "must be at least 1");
+        }
+        if breaker.half_open_max_calls < breaker.success_threshold {
+            report.error(
+                "circuit_breaker.half_open_max_calls",
+                "fewer half-open calls than the success threshold means the breaker can never close",
+            );
+        }
+        
+        let dht = &self.dht;
+        if dht.bucket_size == 0 {
+            report.error("dht.bucket_size", "must be greater than zero");
+        }
+        if dht.alpha == 0 || dht.alpha > dht.bucket_size {
+            report.error("dht.alpha", "must be between 1 and the bucket size");
+        }
+        if dht.replication_factor as usize > dht.bucket_size {
+            report.error("dht.replication_factor", "cannot exceed the bucket size");
+        }
+        if dht.republish_interval >= dht.security.record_ttl {
+            report.error(
+                "dht.republish_interval",
+                format!(
+                    "records expire ({:?}) before they are republished ({:?})",
+                    dht.security.record_ttl, dht.republish_interval
+                ),
+            );
+        }
+        if dht.expiry_interval >= dht.security.record_ttl {
+            report.warning("dht.expiry_interval", "expired records linger longer than their TTL");
+        }
+        if dht.security.disjoint_paths == 0 {
+            report.error("dht.security.disjoint_paths", "must be at least 1");
+        }
+        if dht.security.pow_difficulty > 24 {
+            report.warning(
+                "dht.security.pow_difficulty",
+                "difficulty above 24 bits can make node startup take minutes",
+            );
+        }
+        let mut namespaces = std::collections::HashSet::new();
+        for namespace in &dht.namespaces {
+            let field = format!("dht.namespaces.{}", namespace.name);
+            if !namespaces.insert(namespace.name.as_str()) {
+                report.error(field.clone(), "namespace is listed twice");
+            }
+            match (namespace.privacy.is_sealed(), &namespace.key) {
+                (true, None) => report.error(format!("{}.key", field), format!("{:?} namespaces need a key", namespace.privacy)),
+                (false, Some(_)) => report.warning(
+                    format!("{}.key", field),
+                    "key is unused; announcements in public namespaces are not sealed",
+                ),
+                _ => {}
+            }
+        }
+        let reputation = &dht.security.reputation;
+        if reputation.invalid_record_penalty == 0 {
+            report.warning("dht.security.reputation.invalid_record_penalty", "peers serving invalid records are never quarantined");
+        }
+        if reputation.quarantine_threshold >= crate::dht_security::MAX_REPUTATION {
+            report.error("dht.security.reputation.quarantine_threshold", "must be below the maximum reputation of 100");
+        }
+        if reputation.max_quarantine_duration < reputation.quarantine_duration {
+            report.error(
+                "dht.security.reputation.max_quarantine_duration",
+                "must be at least quarantine_duration",
+            );
+        }
+        
+        let gossip = &self.gossip;
+        if gossip.eager_fanout == 0 {
+            report.warning("gossip.eager_fanout", "no eager peers; every broadcast waits for a graft");
+        }
+        if gossip.tick_interval > gossip.ihave_timeout {
+            report.warning(
+                "gossip.tick_interval",
+                "timer runs less often than the IHave timeout, delaying tree repair",
+            );
+        }
+        if gossip.message_retention <= gossip.ihave_timeout {
+            report.error(
+                "gossip.message_retention",
+                "messages are dropped before peers can graft them",
+            );
+        }
+        
+        let access_log = &self.access_log;
+        if access_log.enabled {
+            let mut rates = vec![
+                ("access_log.sample_rate".to_string(), access_log.sample_rate),
+                ("access_log.error_sample_rate".to_string(), access_log.error_sample_rate),
+            ];
+            for (name, rate) in &access_log.service_sample_rates {
+                rates.push((format!("access_log.service_sample_rates.{}", name), *rate));
+            }
+            for (field, rate) in rates {
+                if !(0.0..=1.0).contains(&rate) {
+                    report.error(field, "must be between 0 and 1");
+                }
+            }
+            if access_log.sinks.is_empty() {
+                report.warning("access_log.sinks", "sampled requests are counted but written nowhere");
+            }
+            if access_log.sinks.iter().any(|sink| matches!(sink, AccessLogSink::Memory { capacity: 0 })) {
+                report.error("access_log.sinks", "memory sink capacity must be at least 1");
+            }
+        }
+        
+        let signing = &self.request_signing;
+        if signing.max_clock_skew.is_zero() {
+            report.error("request_signing.max_clock_skew", "must be greater than zero");
+        }
+        if !signing.required_services.is_empty() && signing.trusted_workloads.is_empty() {
+            report.warning(
+                "request_signing.trusted_workloads",
+                "no workload keys are trusted yet, so required services refuse every request",
+            );
+        }
+
+        let budget = &self.retry_budget;
+        if budget.enabled {
+            if !(0.0..=1.0).contains(&budget.ratio) {
+                report.error("retry_budget.ratio", "must be between 0 and 1");
+            }
+            if budget.window.is_zero() {
+                report.error("retry_budget.window", "must be greater than zero");
+            }
+            if budget.min_retries_per_second == 0 && budget.ratio == 0.0 {
+                report.warning("retry_budget", "no retries are ever allowed");
+            }
+        }
+        
+        let outliers = &self.load_balancing.outlier_detection;
+        if outliers.enabled {
+            if outliers.consecutive_failures == 0 {
+                report.error("load_balancing.outlier_detection.consecutive_failures", "must be at least 1");
+            }
+            if !(outliers.latency_percentile > 0.0 && outliers.latency_percentile <= 1.0) {
+                report.error("load_balancing.outlier_detection.latency_percentile", "must be in (0, 1]");
+            }
+            if outliers.latency_factor <= 1.0 {
+                report.error("load_balancing.outlier_detection.latency_factor", "must be greater than 1");
+            }
+            if outliers.min_latency_samples > outliers.latency_window {
+                report.error(
+                    "load_balancing.outlier_detection.min_latency_samples",
+                    "cannot exceed latency_window",
+                );
+            }
+            if outliers.max_ejection_time < outliers.base_ejection_time {
+                report.error(
+                    "load_balancing.outlier_detection.max_ejection_time",
+                    "must be at least base_ejection_time",
+                );
+            }
+            if outliers.max_ejection_percent > 100 {
+                report.error("load_balancing.outlier_detection.max_ejection_percent", "must be at most 100");
+            }
+        }
+        
+        let probing = &self.probing;
+        if probing.enabled {
+            if probing.interval.is_zero() {
+                report.error("probing.interval", "must be greater than zero");
+            }
+            if probing.rtt_samples == 0 {
+                report.error("probing.rtt_samples", "must be at least 1");
+            }
+            if !(probing.smoothing > 0.0 && probing.smoothing <= 1.0) {
+                report.error("probing.smoothing", "must be in (0, 1]");
+            }
+            if probing.max_age <= probing.interval {
+                report.error(
+                    "probing.max_age",
+                    "links expire before the next probe round measures them again",
+                );
+            }
+            if probing.trains as usize * probing.train_packets as usize * probing.packet_bytes > 16 * 1024 * 1024 {
+                report.warning("probing", "each probe sends more than 16 MiB per peer");
+            }
+        }
+        
+        if self.draining.grace_period.is_zero() {
+            report.warning(
+                "draining.grace_period",
+                "requests in flight are cut as soon as a service deregisters",
+            );
+        }
+        
+        let synthetic = &self.synthetic;
+        if !synthetic.probes.is_empty() {
+            let mut names = std::collections::HashSet::new();
+            for probe in &synthetic.probes {
+                if !names.insert(probe.name.as_str()) {
+                    report.error("synthetic.probes", format!("probe '{}' is defined twice", probe.name));
+                }
+                if let Err(e) = probe.validate() {
+                    report.error("synthetic.probes", e.to_string());
+                }
+            }
+            if synthetic.max_results == 0 {
+                report.error("synthetic.max_results", "must be at least 1");
+            }
+            if synthetic.min_samples == 0 {
+                report.error("synthetic.min_samples", "must be at least 1");
+            }
+        }
+        
+        if self.metrics.retention_period < self.metrics.collection_interval {
+            report.error(
+                "metrics.retention_period",
+                "retention is shorter than the collection interval",
+            );
+        }
+        
+        report
+    }
+}
+
 /// Load balancing configuration  
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoadBalancingConfig {
     pub strategy: crate::load_balancing::LoadBalancingStrategy,
+    /// Passive health checking of endpoints
+    #[serde(default)]
+    pub outlier_detection: OutlierDetectionConfig,
 }
 
 impl Default for LoadBalancingConfig {
     fn default() -> Self {
         Self {
             strategy: crate::load_balancing::LoadBalancingStrategy::RoundRobin,
+            outlier_detection: OutlierDetectionConfig::default(),
         }
     }
 }
@@ -68,3 +424,27 @@ impl Default for MetricsConfig {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let report = NetworkConfig::default().validate_config();
+        assert!(report.is_valid(), "{}", report);
+    }
+
+    #[test]
+    fn test_cross_field_invariants() {
+        let mut config = NetworkConfig::default();
+        config.service_discovery.service_ttl = 30;
+        config.health_check.timeout = Duration::from_secs(10);
+
+        let report = config.validate_config();
+        let fields: Vec<&str> = report.errors().map(|d| d.field.as_str()).collect();
+        assert!(fields.contains(&"service_discovery.service_ttl"));
+        assert!(fields.contains(&"service_discovery.announcement_interval"));
+        assert!(fields.contains(&"health_check.timeout"));
+    }
+}
@@ -1,12 +1,24 @@
 //! Distributed Hash Table for P2P service discovery
+//!
+//! Hardened along the lines of S/Kademlia: node IDs are derived from the
+//! node's public key and admitted through [`AdmissionPolicy`], records are
+//! signed and expire, and lookups run over disjoint paths so a single
+//! malicious node on the route cannot steer the result.
 
-use crate::error::Result;
-use nexus_shared::{NodeId, ServiceId};
+use crate::dht_namespace::{self, NamespaceConfig, NamespaceRegistry, SealedNamespace, SEALED_KEY_PREFIX};
+use crate::dht_security::{AdmissionPolicy, DhtSecurityConfig, NodeIdentity, PeerReputation, SignedRecord};
+use crate::discovery::ServiceInstance;
+use crate::error::{NetworkError, Result};
+use crate::health_check::HealthStatus;
+use futures::future::BoxFuture;
+use nexus_shared::{hash, KeyPair, NodeId, ServiceId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::net::SocketAddr;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, RwLock};
 
 /// DHT configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +27,14 @@ pub struct DhtConfig {
     pub bucket_size: usize,
     pub alpha: usize,  // Concurrency parameter
     pub refresh_interval: std::time::Duration,
+    /// How often owned records are re-signed and pushed to their replicas
+    pub republish_interval: Duration,
+    /// How often expired records are purged from local storage
+    pub expiry_interval: Duration,
+    pub security: DhtSecurityConfig,
+    /// Tenant namespaces this node belongs to
+    #[serde(default)]
+    pub namespaces: Vec<NamespaceConfig>,
 }
 
 impl Default for DhtConfig {
@@ -24,6 +44,10 @@ impl Default for DhtConfig {
             bucket_size: 20,
             alpha: 3,
             refresh_interval: std::time::Duration::from_secs(3600),
+            republish_interval: Duration::from_secs(1800),
+            expiry_interval: Duration::from_secs(60),
+            security: DhtSecurityConfig::default(),
+            namespaces: Vec::new(),
         }
     }
 }
@@ -34,116 +58,1242 @@ pub struct DhtNode {
     pub node_id: NodeId,
     pub address: SocketAddr,
     pub last_seen: std::time::SystemTime,
+    pub public_key: [u8; 32],
+    /// Admission proof-of-work nonce
+    pub nonce: u64,
+}
+
+impl DhtNode {
+    /// Identity claimed by this node
+    pub fn identity(&self) -> NodeIdentity {
+        NodeIdentity {
+            node_id: self.node_id,
+            public_key: self.public_key,
+            nonce: self.nonce,
+        }
+    }
+}
+
+/// A node's instance of a service, as published in the DHT
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceAnnouncement {
+    pub service_id: ServiceId,
+    /// Node hosting the instance; must be the record's publisher
+    pub node_id: NodeId,
+    pub address: SocketAddr,
+    pub metadata: HashMap<String, String>,
+    /// Health as the hosting node last saw it
+    pub health: HealthStatus,
+    /// Unix seconds; covered by the record signature
+    pub announced_at: u64,
+}
+
+impl ServiceAnnouncement {
+    /// The announced instance as discovery reports it
+    pub fn into_instance(self) -> ServiceInstance {
+        ServiceInstance {
+            service_id: self.service_id,
+            node_id: self.node_id,
+            address: self.address,
+            health_status: self.health,
+            metadata: self.metadata,
+            last_seen: UNIX_EPOCH + Duration::from_secs(self.announced_at),
+        }
+    }
+}
+
+/// Key prefix shared by all service announcements
+const SERVICE_KEY_PREFIX: &str = "service:";
+
+/// DHT key of one node's announcement of a service
+fn service_key(service_id: &ServiceId, node_id: &NodeId) -> Vec<u8> {
+    format!("{}{}", service_prefix(service_id), node_id).into_bytes()
+}
+
+/// Common prefix of every announcement of a service
+fn service_prefix(service_id: &ServiceId) -> String {
+    format!("{}{}/", SERVICE_KEY_PREFIX, service_id)
+}
+
+/// Decode an announcement, checking it is stored under its publisher's key
+/// and names its publisher as the hosting node
+fn check_announcement(record: &SignedRecord) -> Result<ServiceAnnouncement> {
+    check_announcement_value(record, &record.value, |service_id| service_key(service_id, &record.publisher))
+}
+
+/// Check an announcement, opened from `value`, whose key should be `key_of` its service
+fn check_announcement_value(
+    record: &SignedRecord,
+    value: &[u8],
+    key_of: impl Fn(&ServiceId) -> Vec<u8>,
+) -> Result<ServiceAnnouncement> {
+    let invalid = |reason: String| NetworkError::Dht {
+        message: format!("Rejected announcement from {}: {}", record.publisher, reason),
+    };
+    let announcement: ServiceAnnouncement = serde_json::from_slice(value)
+        .map_err(|e| invalid(format!("malformed: {}", e)))?;
+    if announcement.node_id != record.publisher {
+        return Err(invalid(format!("claims to be hosted by {}", announcement.node_id)));
+    }
+    if record.key != key_of(&announcement.service_id) {
+        return Err(invalid("stored under another node's key".to_string()));
+    }
+    Ok(announcement)
+}
+
+/// What any node can check of a sealed announcement without the namespace key:
+/// it is stored under its publisher's key and is large enough to be sealed
+fn check_sealed(record: &SignedRecord) -> Result<()> {
+    if !record.key.ends_with(format!("/{}", record.publisher).as_bytes()) {
+        return Err(NetworkError::Dht {
+            message: format!("Rejected sealed announcement from {}: stored under another node's key", record.publisher),
+        });
+    }
+    if !dht_namespace::is_well_formed_seal(&record.value) {
+        return Err(NetworkError::Dht {
+            message: format!("Rejected sealed announcement from {}: malformed", record.publisher),
+        });
+    }
+    Ok(())
+}
+
+/// How long a lookup waits for one node's answer
+const LOOKUP_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Queries to other DHT nodes, as lookups need them
+pub trait DhtTransport: Send + Sync {
+    /// Ask a node for the nodes it knows closest to `target`
+    fn find_closest(&self, node: DhtNode, target: NodeId) -> BoxFuture<'_, Result<Vec<DhtNode>>>;
+
+    /// Ask a node for the records it holds under `key`, along with its
+    /// closest known nodes to where those records are placed
+    fn find_value(&self, node: DhtNode, key: Vec<u8>) -> BoxFuture<'_, Result<(Vec<DhtNode>, Vec<SignedRecord>)>>;
+}
+
+/// Send a lookup request to a node and wait for its answer
+async fn mesh_request(
+    client: &nexus_transport::QuicClient,
+    node: &DhtNode,
+    message: &crate::MeshMessage,
+) -> Result<crate::MeshMessage> {
+    let transport_error = |e: nexus_transport::TransportError| NetworkError::Transport { message: e.to_string() };
+    if !client.is_connected(node.node_id).await {
+        client.connect(node.address, &format!("nexus-{}", node.node_id)).await.map_err(transport_error)?;
+    }
+
+    let request = nexus_transport::TransportMessage::new(
+        nexus_transport::MessageType::Control,
+        client.node_id(),
+        Some(node.node_id),
+        serde_json::to_vec(message)?,
+    );
+    let response = client
+        .send_request(node.node_id, request, LOOKUP_QUERY_TIMEOUT)
+        .await
+        .map_err(transport_error)?;
+    Ok(serde_json::from_slice(&response.payload)?)
+}
+
+impl DhtTransport for nexus_transport::QuicClient {
+    fn find_closest(&self, node: DhtNode, target: NodeId) -> BoxFuture<'_, Result<Vec<DhtNode>>> {
+        Box::pin(async move {
+            match mesh_request(self, &node, &crate::MeshMessage::FindNode { target }).await? {
+                crate::MeshMessage::Nodes(nodes) => Ok(nodes),
+                _ => Err(NetworkError::Dht {
+                    message: format!("Node {} answered a lookup with another message", node.node_id),
+                }),
+            }
+        })
+    }
+
+    fn find_value(&self, node: DhtNode, key: Vec<u8>) -> BoxFuture<'_, Result<(Vec<DhtNode>, Vec<SignedRecord>)>> {
+        Box::pin(async move {
+            match mesh_request(self, &node, &crate::MeshMessage::FindValue { key }).await? {
+                crate::MeshMessage::Values { nodes, records } => Ok((nodes, records)),
+                _ => Err(NetworkError::Dht {
+                    message: format!("Node {} answered a value lookup with another message", node.node_id),
+                }),
+            }
+        })
+    }
+}
+
+/// Message the DHT needs delivered to another node
+#[derive(Debug, Clone)]
+pub enum DhtOutbound {
+    /// Ask a node to store a record
+    Store { to: DhtNode, record: SignedRecord },
+    /// Ask a node to drop a record; `tombstone` is its publisher's signed,
+    /// empty record for the same key
+    Remove { to: DhtNode, tombstone: SignedRecord },
+}
+
+impl DhtOutbound {
+    /// Node the message is for
+    pub fn to(&self) -> &DhtNode {
+        match self {
+            DhtOutbound::Store { to, .. } | DhtOutbound::Remove { to, .. } => to,
+        }
+    }
 }
 
 /// Key-value pair stored in DHT
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 struct DhtEntry {
-    key: Vec<u8>,
-    value: Vec<u8>,
+    record: SignedRecord,
     timestamp: std::time::SystemTime,
 }
 
+/// XOR distance between two node IDs
+fn distance(a: &NodeId, b: &NodeId) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = a.as_bytes()[i] ^ b.as_bytes()[i];
+    }
+    out
+}
+
+/// Position of a record key in the node ID space
+fn key_id(key: &[u8]) -> NodeId {
+    NodeId::new(hash(key))
+}
+
+/// Where a record is placed in the node ID space
+///
+/// Announcements of one service are placed by the prefix they share, so a
+/// lookup of the service reaches the nodes holding every announcement.
+pub(crate) fn placement_id(key: &[u8]) -> NodeId {
+    let announcement = key.starts_with(SERVICE_KEY_PREFIX.as_bytes()) || key.starts_with(SEALED_KEY_PREFIX.as_bytes());
+    match key.iter().rposition(|byte| *byte == b'/') {
+        Some(end) if announcement => key_id(&key[..=end]),
+        _ => key_id(key),
+    }
+}
+
+/// K-bucket index for a node: the length of the prefix it shares with us
+fn bucket_index(local: &NodeId, other: &NodeId) -> Option<usize> {
+    let d = distance(local, other);
+    let mut prefix = 0;
+    for byte in d {
+        if byte == 0 {
+            prefix += 8;
+        } else {
+            prefix += byte.leading_zeros() as usize;
+            return Some(prefix);
+        }
+    }
+    None // Same ID
+}
+
 /// Distributed Hash Table implementation
 pub struct DistributedHashTable {
     config: DhtConfig,
-    node_id: NodeId,
+    identity: NodeIdentity,
+    key_pair: KeyPair,
+    admission: AdmissionPolicy,
+    reputation: PeerReputation,
+    namespaces: NamespaceRegistry,
     routing_table: Arc<RwLock<Vec<Vec<DhtNode>>>>,  // K-buckets
     storage: Arc<RwLock<HashMap<Vec<u8>, DhtEntry>>>,
+
+    /// Values published by this node, re-signed on every republish
+    owned: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>>,
+
+    /// Messages for the transport layer to deliver
+    outbound: mpsc::Sender<DhtOutbound>,
+    outbound_rx: Mutex<Option<mpsc::Receiver<DhtOutbound>>>,
+
+    /// Queries to other nodes; without one, lookups only see the routing table
+    transport: std::sync::RwLock<Option<Arc<dyn DhtTransport>>>,
+
+    /// Background maintenance task
+    maintenance_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 impl DistributedHashTable {
-    pub fn new(node_id: NodeId, config: DhtConfig) -> Self {
+    /// Create a DHT node whose ID is derived from the given key pair
+    pub fn new(key_pair: KeyPair, config: DhtConfig) -> Self {
+        let identity = NodeIdentity::generate(*key_pair.public_key(), config.security.pow_difficulty);
+        let admission = AdmissionPolicy::new(config.security.clone());
+        let reputation = PeerReputation::new(config.security.reputation.clone());
+        let (outbound, outbound_rx) = mpsc::channel(1024);
+        let namespaces = NamespaceRegistry::default();
+        for namespace in &config.namespaces {
+            if let Err(e) = namespaces.join(namespace.clone()) {
+                tracing::warn!("Not joining DHT namespace: {}", e);
+            }
+        }
+
         Self {
             config,
-            node_id,
+            identity,
+            key_pair,
+            admission,
+            reputation,
+            namespaces,
             routing_table: Arc::new(RwLock::new(vec![Vec::new(); 256])),
             storage: Arc::new(RwLock::new(HashMap::new())),
+            owned: Arc::new(RwLock::new(HashMap::new())),
+            outbound,
+            outbound_rx: Mutex::new(Some(outbound_rx)),
+            transport: std::sync::RwLock::new(None),
+            maintenance_task: Mutex::new(None),
+        }
+    }
+
+    /// Replace the admission policy, e.g. to enable stake-based admission
+    pub fn with_admission_policy(mut self, admission: AdmissionPolicy) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    /// Reputation of the peers records are received from
+    pub fn reputation(&self) -> &PeerReputation {
+        &self.reputation
+    }
+
+    /// Tenant namespaces this node belongs to
+    pub fn namespaces(&self) -> &NamespaceRegistry {
+        &self.namespaces
+    }
+
+    /// Admission, plus refusal of quarantined peers
+    fn admit(&self, identity: &NodeIdentity) -> Result<()> {
+        self.admission.admit(identity)?;
+        if self.reputation.is_quarantined(&identity.node_id) {
+            return Err(NetworkError::Dht {
+                message: format!("Node {} is quarantined for serving invalid records", identity.node_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// This node's ID
+    pub fn node_id(&self) -> NodeId {
+        self.identity.node_id
+    }
+
+    /// This node's self-certifying identity
+    pub fn identity(&self) -> &NodeIdentity {
+        &self.identity
+    }
+    
+    /// Query other nodes through `transport` when looking up replicas
+    pub fn set_transport(&self, transport: Arc<dyn DhtTransport>) {
+        *self.transport.write().unwrap() = Some(transport);
+    }
+
+    /// Take the outbound message stream; the transport layer delivers these
+    pub fn take_outbound(&self) -> Option<mpsc::Receiver<DhtOutbound>> {
+        self.outbound_rx.lock().unwrap().take()
+    }
+
+    fn send(&self, message: DhtOutbound) {
+        if let Err(e) = self.outbound.try_send(message) {
+            tracing::warn!("Dropping DHT outbound message: {}", e);
         }
     }
     
+    /// Store a value published by this node and replicate it
     pub async fn put(&self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
-        let entry = DhtEntry {
-            key: key.clone(),
-            value,
-            timestamp: std::time::SystemTime::now(),
+        self.owned.write().await.insert(key.clone(), value.clone());
+
+        let record = SignedRecord::sign(key, value, &self.key_pair, self.config.security.record_ttl);
+        self.store_record(record.clone()).await?;
+        self.replicate(&record).await;
+        Ok(())
+    }
+
+    /// Push a record to the nodes closest to its key
+    async fn replicate(&self, record: &SignedRecord) {
+        let replicas = self.closest_replicas(&placement_id(&record.key)).await;
+        for to in replicas {
+            self.send(DhtOutbound::Store { to, record: record.clone() });
+        }
+    }
+
+    /// The `replication_factor` closest nodes to a key, found by a disjoint lookup
+    async fn closest_replicas(&self, target: &NodeId) -> Vec<DhtNode> {
+        let transport = self.transport.read().unwrap().clone();
+        let found = match transport {
+            Some(transport) => self.lookup_disjoint(target, |node| transport.find_closest(node, *target)).await,
+            None => self.find_node(target).await,
         };
-        
+        let mut nodes = found.unwrap_or_default();
+        nodes.truncate(self.config.replication_factor as usize);
+        nodes
+    }
+
+    /// The `replication_factor` closest nodes to a key in the routing table
+    async fn known_replicas(&self, target: &NodeId) -> Vec<DhtNode> {
+        let mut nodes = self.find_node(target).await.unwrap_or_default();
+        nodes.truncate(self.config.replication_factor as usize);
+        nodes
+    }
+
+    /// Re-sign all owned records with a fresh expiry and push them out
+    pub async fn republish(&self) -> usize {
+        let owned: Vec<(Vec<u8>, Vec<u8>)> = self.owned
+            .read()
+            .await
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        for (key, value) in &owned {
+            let record = SignedRecord::sign(key.clone(), value.clone(), &self.key_pair, self.config.security.record_ttl);
+            if let Err(e) = self.store_record(record.clone()).await {
+                tracing::warn!("Failed to republish record: {}", e);
+                continue;
+            }
+            self.replicate(&record).await;
+        }
+
+        owned.len()
+    }
+
+    /// Drop records whose expiry has passed, returning how many were removed
+    pub async fn expire_stale(&self) -> usize {
         let mut storage = self.storage.write().await;
-        storage.insert(key, entry);
-        
-        // TODO: Replicate to k closest nodes
+        let before = storage.len();
+        storage.retain(|_, entry| !entry.record.is_expired());
+        let expired = before - storage.len();
+
+        if expired > 0 {
+            tracing::debug!("Expired {} stale DHT records", expired);
+        }
+        expired
+    }
+
+    /// Store a record pushed by a peer, penalizing the peer if it is invalid
+    pub async fn store_record_from(&self, from: &NodeId, record: SignedRecord) -> Result<()> {
+        if self.reputation.is_quarantined(from) {
+            return Err(NetworkError::Dht {
+                message: format!("Refusing record from quarantined node {}", from),
+            });
+        }
+        match self.store_record(record).await {
+            Ok(()) => {
+                self.reputation.record_valid(from);
+                Ok(())
+            }
+            Err(e) => {
+                self.penalize(from, &e.to_string()).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Drop a record on its publisher's signed tombstone, relayed by `from`
+    ///
+    /// A tombstone issued before the stored record is stale and ignored; one
+    /// that is invalid or names another publisher counts against the peer.
+    pub async fn remove_record_from(&self, from: &NodeId, tombstone: SignedRecord) -> Result<()> {
+        if self.reputation.is_quarantined(from) {
+            return Err(NetworkError::Dht {
+                message: format!("Refusing removal from quarantined node {}", from),
+            });
+        }
+        if let Err(e) = tombstone.verify(self.config.security.max_clock_skew) {
+            self.penalize(from, &e.to_string()).await;
+            return Err(e);
+        }
+
+        let mut storage = self.storage.write().await;
+        let Some(existing) = storage.get(&tombstone.key) else {
+            return Ok(());
+        };
+        if existing.record.publisher != tombstone.publisher {
+            let message = format!(
+                "Record key is owned by {}, rejecting removal by {}",
+                existing.record.publisher, tombstone.publisher
+            );
+            drop(storage);
+            self.penalize(from, &message).await;
+            return Err(NetworkError::Dht { message });
+        }
+        if existing.record.issued_at <= tombstone.issued_at {
+            storage.remove(&tombstone.key);
+        }
+        Ok(())
+    }
+
+    /// Keep the valid records a peer returned for a lookup of `key`
+    ///
+    /// A lookup returns the records stored under `key`, that is whose key
+    /// starts with it. Every invalid record, including one stored elsewhere,
+    /// counts against the peer; nothing from a quarantined peer is accepted.
+    pub async fn accept_lookup(&self, from: &NodeId, key: &[u8], records: Vec<SignedRecord>) -> Vec<SignedRecord> {
+        if self.reputation.is_quarantined(from) {
+            return Vec::new();
+        }
+        let mut accepted = Vec::new();
+        for record in records {
+            let checked = if !record.key.starts_with(key) {
+                Err(NetworkError::Dht { message: "record for a different key".to_string() })
+            } else {
+                self.validate(&record)
+            };
+            match checked {
+                Ok(()) => {
+                    self.reputation.record_valid(from);
+                    accepted.push(record);
+                }
+                Err(e) => {
+                    if self.penalize(from, &e.to_string()).await {
+                        return Vec::new();
+                    }
+                }
+            }
+        }
+        accepted
+    }
+
+    /// Count an invalid record against a peer; true once it is quarantined
+    async fn penalize(&self, peer: &NodeId, reason: &str) -> bool {
+        if self.reputation.record_invalid(peer, reason) {
+            self.remove_node(peer).await;
+        }
+        self.reputation.is_quarantined(peer)
+    }
+
+    /// Signature, validity window, and for service announcements, attribution
+    fn validate(&self, record: &SignedRecord) -> Result<()> {
+        record.verify(self.config.security.max_clock_skew)?;
+        if record.key.starts_with(SERVICE_KEY_PREFIX.as_bytes()) {
+            check_announcement(record)?;
+        } else if record.key.starts_with(SEALED_KEY_PREFIX.as_bytes()) {
+            check_sealed(record)?;
+        }
+        Ok(())
+    }
+
+    /// Store a record received from another node after verifying it
+    pub async fn store_record(&self, record: SignedRecord) -> Result<()> {
+        self.validate(&record)?;
+
+        let mut storage = self.storage.write().await;
+
+        // A live record can only be replaced by its own publisher
+        if let Some(existing) = storage.get(&record.key) {
+            if !existing.record.is_expired() && existing.record.publisher != record.publisher {
+                return Err(NetworkError::Dht {
+                    message: format!(
+                        "Record key is owned by {}, rejecting write from {}",
+                        existing.record.publisher, record.publisher
+                    ),
+                });
+            }
+            if existing.record.publisher == record.publisher && existing.record.issued_at > record.issued_at {
+                return Ok(()); // Stale replay
+            }
+        }
+
+        storage.insert(record.key.clone(), DhtEntry {
+            record,
+            timestamp: std::time::SystemTime::now(),
+        });
         Ok(())
     }
     
     pub async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_record(key).await.map(|record| record.value))
+    }
+
+    /// Get the signed record for a key, if present and unexpired
+    pub async fn get_record(&self, key: &[u8]) -> Option<SignedRecord> {
         let storage = self.storage.read().await;
-        Ok(storage.get(key).map(|entry| entry.value.clone()))
+        storage
+            .get(key)
+            .filter(|entry| !entry.record.is_expired())
+            .map(|entry| entry.record.clone())
     }
     
+    /// Unexpired records stored under `key`, as a value lookup returns them
+    pub async fn records_under(&self, key: &[u8]) -> Vec<SignedRecord> {
+        let storage = self.storage.read().await;
+        storage
+            .values()
+            .filter(|entry| entry.record.key.starts_with(key) && !entry.record.is_expired())
+            .map(|entry| entry.record.clone())
+            .collect()
+    }
+
+    /// Records stored under `key` here and, with a transport, on the nodes
+    /// a disjoint lookup of the key reaches
+    ///
+    /// Of several records for one key, the most recently issued is kept.
+    async fn lookup_records(&self, key: &[u8]) -> Vec<SignedRecord> {
+        let mut records: HashMap<Vec<u8>, SignedRecord> = HashMap::new();
+        let mut keep = |record: SignedRecord| {
+            if !matches!(records.get(&record.key), Some(existing) if existing.issued_at >= record.issued_at) {
+                records.insert(record.key.clone(), record);
+            }
+        };
+        for record in self.records_under(key).await {
+            keep(record);
+        }
+
+        let transport = self.transport.read().unwrap().clone();
+        if let Some(transport) = transport {
+            let found = Mutex::new(Vec::new());
+            let lookup = self
+                .lookup_disjoint(&placement_id(key), |node| {
+                    let transport = Arc::clone(&transport);
+                    let found = &found;
+                    async move {
+                        let (nodes, records) = transport.find_value(node.clone(), key.to_vec()).await?;
+                        let accepted = self.accept_lookup(&node.node_id, key, records).await;
+                        found.lock().unwrap().extend(accepted);
+                        Ok(nodes)
+                    }
+                })
+                .await;
+            if let Err(e) = lookup {
+                tracing::debug!("Value lookup failed: {}", e);
+            }
+            for record in found.into_inner().unwrap() {
+                keep(record);
+            }
+        }
+
+        records.into_values().collect()
+    }
+
+    /// Find the k closest known nodes to a target
     pub async fn find_node(&self, target: &NodeId) -> Result<Vec<DhtNode>> {
         let routing_table = self.routing_table.read().await;
         
-        // Find k closest nodes (simplified)
-        let mut closest = Vec::new();
-        for bucket in routing_table.iter() {
-            for node in bucket.iter() {
-                closest.push(node.clone());
-                if closest.len() >= self.config.bucket_size {
-                    return Ok(closest);
-                }
-            }
-        }
+        let mut closest: Vec<DhtNode> = routing_table.iter().flatten().cloned().collect();
+        closest.sort_by_key(|node| distance(&node.node_id, target));
+        closest.truncate(self.config.bucket_size);
         
         Ok(closest)
     }
     
+    /// Add a node to the routing table if it passes admission
     pub async fn add_node(&self, node: DhtNode) -> Result<()> {
-        let mut routing_table = self.routing_table.write().await;
-        
-        // Simplified: just add to first non-full bucket
-        for bucket in routing_table.iter_mut() {
-            if bucket.len() < self.config.bucket_size {
-                bucket.push(node);
-                return Ok(());
+        self.admit(&node.identity())?;
+
+        let index = match bucket_index(&self.identity.node_id, &node.node_id) {
+            Some(index) => index,
+            None => return Ok(()), // Ourselves
+        };
+
+        let joined = {
+            let mut routing_table = self.routing_table.write().await;
+            let bucket = &mut routing_table[index];
+
+            if let Some(existing) = bucket.iter_mut().find(|n| n.node_id == node.node_id) {
+                *existing = node.clone();
+                false
+            } else if bucket.len() < self.config.bucket_size {
+                bucket.push(node.clone());
+                true
+            } else {
+                // Full buckets keep their long-lived nodes, which resists eclipse attempts
+                false
             }
+        };
+
+        if joined {
+            self.hand_over_to(&node).await;
         }
         
         Ok(())
     }
 
-    pub async fn start(&self) -> Result<()> {
-        // Initialize DHT operations (bootstrap, periodic refresh, etc.)
+    /// Send a newly joined node the records it is now a replica for
+    async fn hand_over_to(&self, node: &DhtNode) {
+        let records: Vec<SignedRecord> = self.storage
+            .read()
+            .await
+            .values()
+            .filter(|entry| !entry.record.is_expired())
+            .map(|entry| entry.record.clone())
+            .collect();
+
+        // Lookups add the nodes they discover, so this stays on the routing table
+        for record in records {
+            let replicas = self.known_replicas(&placement_id(&record.key)).await;
+            if replicas.iter().any(|replica| replica.node_id == node.node_id) {
+                self.send(DhtOutbound::Store { to: node.clone(), record });
+            }
+        }
+    }
+
+    /// Remove a departed or unresponsive node and restore replication
+    /// for the records it was holding
+    pub async fn remove_node(&self, node_id: &NodeId) {
+        let index = match bucket_index(&self.identity.node_id, node_id) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let removed = {
+            let mut routing_table = self.routing_table.write().await;
+            let bucket = &mut routing_table[index];
+            let before = bucket.len();
+            bucket.retain(|node| node.node_id != *node_id);
+            bucket.len() != before
+        };
+
+        if !removed {
+            return;
+        }
+
+        tracing::debug!("Node {} left the DHT, re-replicating its records", node_id);
+
+        // With the node gone, the replica sets it belonged to now include a
+        // new member; pushing the records again fills that gap.
+        let records: Vec<SignedRecord> = self.storage
+            .read()
+            .await
+            .values()
+            .filter(|entry| !entry.record.is_expired())
+            .map(|entry| entry.record.clone())
+            .collect();
+
+        for record in records {
+            self.replicate(&record).await;
+        }
+    }
+
+    /// Iterative lookup over disjoint paths
+    ///
+    /// The initial closest nodes are split across `security.disjoint_paths`
+    /// paths and no node is queried by more than one path, so a lookup
+    /// succeeds as long as a single path is free of malicious nodes.
+    /// `query` asks a remote node for its closest nodes to `target`.
+    pub async fn lookup_disjoint<F, Fut>(&self, target: &NodeId, query: F) -> Result<Vec<DhtNode>>
+    where
+        F: Fn(DhtNode) -> Fut,
+        Fut: Future<Output = Result<Vec<DhtNode>>>,
+    {
+        let paths_count = self.config.security.disjoint_paths.max(1);
+        let k = self.config.bucket_size;
+
+        let mut paths: Vec<Vec<DhtNode>> = vec![Vec::new(); paths_count];
+        for (i, node) in self.find_node(target).await?.into_iter().enumerate() {
+            paths[i % paths_count].push(node);
+        }
+
+        let mut visited: HashSet<NodeId> = HashSet::new();
+        visited.insert(self.identity.node_id);
+        let mut queried: HashSet<NodeId> = HashSet::new();
+        for node in paths.iter().flatten() {
+            visited.insert(node.node_id);
+        }
+
+        loop {
+            let mut progressed = false;
+
+            for path in paths.iter_mut() {
+                path.sort_by_key(|node| distance(&node.node_id, target));
+                path.truncate(k);
+
+                let batch: Vec<DhtNode> = path
+                    .iter()
+                    .filter(|node| !queried.contains(&node.node_id))
+                    .take(self.config.alpha)
+                    .cloned()
+                    .collect();
+
+                for node in batch {
+                    queried.insert(node.node_id);
+                    progressed = true;
+
+                    let found = match query(node.clone()).await {
+                        Ok(found) => found,
+                        Err(e) => {
+                            tracing::debug!("Lookup query to {} failed: {}", node.node_id, e);
+                            path.retain(|n| n.node_id != node.node_id);
+                            continue;
+                        }
+                    };
+
+                    for candidate in found {
+                        if visited.contains(&candidate.node_id) {
+                            continue;
+                        }
+                        if let Err(e) = self.admit(&candidate.identity()) {
+                            tracing::debug!("Ignoring lookup result: {}", e);
+                            continue;
+                        }
+                        visited.insert(candidate.node_id);
+                        let _ = self.add_node(candidate.clone()).await;
+                        path.push(candidate);
+                    }
+                }
+            }
+
+            if !progressed {
+                break;
+            }
+        }
+
+        let mut closest: Vec<DhtNode> = paths.into_iter().flatten().collect();
+        closest.sort_by_key(|node| distance(&node.node_id, target));
+        closest.truncate(k);
+        Ok(closest)
+    }
+
+    /// Start record republishing and expiry
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
+        let dht = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            dht.maintenance_loop().await;
+        });
+
+        if let Some(previous) = self.maintenance_task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
         Ok(())
     }
 
+    /// Stop maintenance and hand stored records to the nodes that will
+    /// become responsible for them once this node leaves
     pub async fn stop(&self) -> Result<()> {
-        // Clean shutdown of DHT operations
+        if let Some(task) = self.maintenance_task.lock().unwrap().take() {
+            task.abort();
+        }
+
+        let records: Vec<SignedRecord> = self.storage
+            .read()
+            .await
+            .values()
+            .filter(|entry| !entry.record.is_expired())
+            .map(|entry| entry.record.clone())
+            .collect();
+
+        for record in &records {
+            self.replicate(record).await;
+        }
+
+        tracing::info!("Handed off {} DHT records before shutdown", records.len());
         Ok(())
     }
 
-    pub async fn announce_service(&self, service_id: &ServiceId, address: SocketAddr) -> Result<()> {
-        let key = format!("service:{}", service_id).into_bytes();
-        let value = serde_json::to_vec(&address)?;
-        
+    async fn maintenance_loop(&self) {
+        let mut republish = tokio::time::interval(self.config.republish_interval);
+        let mut expiry = tokio::time::interval(self.config.expiry_interval);
+
+        loop {
+            tokio::select! {
+                _ = republish.tick() => {
+                    let count = self.republish().await;
+                    tracing::debug!("Republished {} owned DHT records", count);
+                }
+                _ = expiry.tick() => {
+                    self.expire_stale().await;
+                }
+            }
+        }
+    }
+
+    /// Announce this node's instance of a service
+    ///
+    /// The announcement is attributed to this node, whatever node the
+    /// instance names, since only this node can sign for it.
+    pub async fn announce_service(&self, instance: &ServiceInstance) -> Result<()> {
+        let node_id = self.node_id();
+        if instance.node_id != node_id {
+            tracing::debug!("Announcing {} as hosted by {}, not {}", instance.service_id, node_id, instance.node_id);
+        }
+        let announcement = ServiceAnnouncement {
+            service_id: instance.service_id.clone(),
+            node_id,
+            address: instance.address,
+            metadata: instance.metadata.clone(),
+            health: instance.health_status,
+            announced_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs(),
+        };
+
+        let value = serde_json::to_vec(&announcement)?;
+        let (key, value) = match self.sealed_namespace(&announcement.service_id)? {
+            Some(sealed) => {
+                let key = sealed.service_key(&announcement.service_id, &node_id);
+                let value = sealed.seal(&key, &value);
+                (key, value)
+            }
+            None => (service_key(&announcement.service_id, &node_id), value),
+        };
         self.put(key, value).await
     }
 
-    pub async fn find_services(&self, service_id: &ServiceId) -> Result<Vec<SocketAddr>> {
-        let key = format!("service:{}", service_id).into_bytes();
-        
-        if let Some(value) = self.get(&key).await? {
-            let address: SocketAddr = serde_json::from_slice(&value)?;
-            Ok(vec![address])
-        } else {
-            Ok(vec![])
+    /// Keys of the service's namespace if its announcements are sealed
+    ///
+    /// Fails for a namespace that should be sealed but that this node has no key for.
+    fn sealed_namespace(&self, service_id: &ServiceId) -> Result<Option<Arc<SealedNamespace>>> {
+        let namespace = service_id.namespace();
+        match self.namespaces.sealed(namespace) {
+            Some(sealed) => Ok(Some(sealed)),
+            None if self.namespaces.privacy(namespace).is_sealed() => Err(NetworkError::Dht {
+                message: format!("no key for private namespace {}", namespace),
+            }),
+            None => Ok(None),
         }
     }
 
+    /// Announcements of a service, by node, whose publisher is the node they name
+    ///
+    /// With a transport, the nodes holding the service's announcements are
+    /// asked for them over disjoint paths. Services of a private namespace
+    /// are found only by its members.
+    pub async fn find_services(&self, service_id: &ServiceId) -> Result<Vec<ServiceAnnouncement>> {
+        let sealed = self.sealed_namespace(service_id)?;
+        let prefix = match &sealed {
+            Some(sealed) => sealed.service_prefix(service_id),
+            None => service_prefix(service_id),
+        };
+        let records = self.lookup_records(prefix.as_bytes()).await;
+
+        let mut announcements: Vec<ServiceAnnouncement> = records
+            .iter()
+            .filter(|record| !self.reputation.is_quarantined(&record.publisher))
+            .map(|record| match &sealed {
+                Some(sealed) => sealed.open(&record.key, &record.value).and_then(|value| {
+                    check_announcement_value(record, &value, |id| sealed.service_key(id, &record.publisher))
+                }),
+                None => check_announcement(record),
+            })
+            .filter_map(|checked| match checked {
+                Ok(announcement) if &announcement.service_id == service_id => Some(announcement),
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!("Ignoring announcement of {}: {}", service_id, e);
+                    None
+                }
+            })
+            .collect();
+        announcements.sort_by_key(|a| a.node_id);
+        Ok(announcements)
+    }
+
+    /// Withdraw this node's announcement of a service, here and on its replicas
     pub async fn remove_service(&self, service_id: &ServiceId) -> Result<()> {
-        let key = format!("service:{}", service_id).into_bytes();
-        let mut storage = self.storage.write().await;
-        storage.remove(&key);
+        let key = match self.sealed_namespace(service_id)? {
+            Some(sealed) => sealed.service_key(service_id, &self.node_id()),
+            None => service_key(service_id, &self.node_id()),
+        };
+        self.owned.write().await.remove(&key);
+        self.storage.write().await.remove(&key);
+
+        let tombstone = SignedRecord::sign(key, Vec::new(), &self.key_pair, self.config.security.record_ttl);
+        for to in self.closest_replicas(&placement_id(&tombstone.key)).await {
+            self.send(DhtOutbound::Remove { to, tombstone: tombstone.clone() });
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dht_security::derive_node_id;
+
+    fn test_config() -> DhtConfig {
+        DhtConfig {
+            security: DhtSecurityConfig {
+                pow_difficulty: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn peer(difficulty: u32) -> DhtNode {
+        let key_pair = KeyPair::generate().unwrap();
+        let identity = NodeIdentity::generate(*key_pair.public_key(), difficulty);
+        DhtNode {
+            node_id: identity.node_id,
+            address: "127.0.0.1:9000".parse().unwrap(),
+            last_seen: std::time::SystemTime::now(),
+            public_key: identity.public_key,
+            nonce: identity.nonce,
+        }
+    }
+
+    fn instance(service_id: &ServiceId, address: &str) -> ServiceInstance {
+        ServiceInstance {
+            service_id: service_id.clone(),
+            node_id: NodeId::random(),
+            address: address.parse().unwrap(),
+            health_status: HealthStatus::Healthy,
+            metadata: HashMap::from([("version".to_string(), "v2".to_string())]),
+            last_seen: std::time::SystemTime::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_forged_records() {
+        let dht = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+        let service_id = ServiceId::new("api", "default");
+        dht.announce_service(&instance(&service_id, "10.0.0.1:80")).await.unwrap();
+
+        // Another publisher cannot overwrite a live record
+        let attacker = KeyPair::generate().unwrap();
+        let key = service_key(&service_id, &dht.node_id());
+        let mut hijacked = dht.find_services(&service_id).await.unwrap().remove(0);
+        hijacked.address = "6.6.6.6:80".parse().unwrap();
+        let forged = SignedRecord::sign(
+            key.clone(),
+            serde_json::to_vec(&hijacked).unwrap(),
+            &attacker,
+            std::time::Duration::from_secs(60),
+        );
+        assert!(dht.store_record(forged.clone()).await.is_err());
+
+        // Tampered records fail signature verification
+        let mut tampered = dht.get_record(&key).await.unwrap();
+        tampered.value = forged.value;
+        assert!(dht.store_record(tampered).await.is_err());
+
+        // Announcements under the attacker's own key that name another node
+        // are refused, and a peer relaying them ends up quarantined
+        let attacker_id = derive_node_id(attacker.public_key());
+        let impostor = SignedRecord::sign(
+            service_key(&service_id, &attacker_id),
+            serde_json::to_vec(&hijacked).unwrap(),
+            &attacker,
+            std::time::Duration::from_secs(60),
+        );
+        assert!(dht.store_record_from(&attacker_id, impostor.clone()).await.is_err());
+        assert!(!dht.reputation().is_quarantined(&attacker_id));
+        assert!(dht.accept_lookup(&attacker_id, &impostor.key, vec![impostor.clone()]).await.is_empty());
+        assert!(dht.reputation().is_quarantined(&attacker_id));
+
+        // Valid records are still accepted from peers in good standing
+        let honest = derive_node_id(KeyPair::generate().unwrap().public_key());
+        assert_eq!(dht.accept_lookup(&honest, &key, vec![dht.get_record(&key).await.unwrap()]).await.len(), 1);
+
+        let found = dht.find_services(&service_id).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].node_id, dht.node_id());
+        assert_eq!(found[0].address, "10.0.0.1:80".parse::<SocketAddr>().unwrap());
+        assert_eq!(found[0].metadata.get("version").map(String::as_str), Some("v2"));
+    }
+
+    #[tokio::test]
+    async fn test_private_namespace_is_sealed_from_other_tenants() {
+        use crate::dht_namespace::{NamespaceKey, NamespacePrivacy};
+
+        let acme = NamespaceConfig {
+            name: "acme".to_string(),
+            privacy: NamespacePrivacy::Private,
+            key: Some(NamespaceKey::generate()),
+        };
+        let member_config = || DhtConfig { namespaces: vec![acme.clone()], ..test_config() };
+        let publisher = DistributedHashTable::new(KeyPair::generate().unwrap(), member_config());
+        let member = DistributedHashTable::new(KeyPair::generate().unwrap(), member_config());
+        let outsider = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+
+        let service_id = ServiceId::new("billing", "acme");
+        publisher.announce_service(&instance(&service_id, "10.0.0.7:443")).await.unwrap();
+        let record = publisher.storage.read().await.values().next().unwrap().record.clone();
+        assert!(record.key.starts_with(SEALED_KEY_PREFIX.as_bytes()));
+        assert!(!String::from_utf8_lossy(&record.value).contains("billing"));
+
+        // Other tenants store and replicate the record but cannot find it
+        outsider.store_record(record.clone()).await.unwrap();
+        assert!(outsider.find_services(&service_id).await.unwrap().is_empty());
+        assert!(outsider.find_services(&ServiceId::new("billing", "default")).await.unwrap().is_empty());
+
+        member.store_record(record).await.unwrap();
+        let found = member.find_services(&service_id).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].address, "10.0.0.7:443".parse::<SocketAddr>().unwrap());
+
+        // Having left, a member can neither find nor announce in the clear
+        assert!(member.namespaces().leave("acme"));
+        assert!(member.find_services(&service_id).await.is_err());
+        assert!(member.announce_service(&instance(&service_id, "10.0.0.8:443")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_republish_and_handoff() {
+        let dht = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+        let mut outbound = dht.take_outbound().unwrap();
+
+        let neighbor = peer(4);
+        dht.add_node(neighbor.clone()).await.unwrap();
+        dht.put(b"key".to_vec(), b"value".to_vec()).await.unwrap();
+
+        // Initial replication to the only neighbor
+        let Ok(DhtOutbound::Store { to, record }) = outbound.try_recv() else {
+            panic!("record not replicated");
+        };
+        assert_eq!(to.node_id, neighbor.node_id);
+        assert_eq!(record.value, b"value");
+
+        assert_eq!(dht.republish().await, 1);
+        assert!(matches!(outbound.try_recv(), Ok(DhtOutbound::Store { .. })));
+
+        // A node joining the key's replica set receives it
+        let joiner = peer(4);
+        dht.add_node(joiner.clone()).await.unwrap();
+        assert_eq!(outbound.try_recv().unwrap().to().node_id, joiner.node_id);
+
+        // Graceful shutdown hands the record to both replicas
+        dht.stop().await.unwrap();
+        let handed: HashSet<NodeId> = std::iter::from_fn(|| outbound.try_recv().ok())
+            .map(|message| message.to().node_id)
+            .collect();
+        assert!(handed.contains(&neighbor.node_id) && handed.contains(&joiner.node_id));
+    }
+
+    #[tokio::test]
+    async fn test_expire_stale_records() {
+        let dht = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+        let key_pair = KeyPair::generate().unwrap();
+
+        let record = SignedRecord::sign(b"short".to_vec(), b"v".to_vec(), &key_pair, Duration::from_secs(2));
+        dht.store_record(record).await.unwrap();
+        assert_eq!(dht.expire_stale().await, 0);
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert!(dht.get(b"short").await.unwrap().is_none());
+        assert_eq!(dht.expire_stale().await, 1);
+    }
+
+    /// Every queried node answers with the same nodes
+    struct FixedAnswer(Vec<DhtNode>);
+
+    impl DhtTransport for FixedAnswer {
+        fn find_closest(&self, _node: DhtNode, _target: NodeId) -> BoxFuture<'_, Result<Vec<DhtNode>>> {
+            Box::pin(async move { Ok(self.0.clone()) })
+        }
+
+        fn find_value(&self, _node: DhtNode, _key: Vec<u8>) -> BoxFuture<'_, Result<(Vec<DhtNode>, Vec<SignedRecord>)>> {
+            Box::pin(async move { Ok((self.0.clone(), Vec::new())) })
+        }
+    }
+
+    /// Queries answered by the DHT of the node asked
+    struct Answering(HashMap<NodeId, Arc<DistributedHashTable>>);
+
+    impl DhtTransport for Answering {
+        fn find_closest(&self, node: DhtNode, target: NodeId) -> BoxFuture<'_, Result<Vec<DhtNode>>> {
+            Box::pin(async move { self.0[&node.node_id].find_node(&target).await })
+        }
+
+        fn find_value(&self, node: DhtNode, key: Vec<u8>) -> BoxFuture<'_, Result<(Vec<DhtNode>, Vec<SignedRecord>)>> {
+            Box::pin(async move {
+                let dht = &self.0[&node.node_id];
+                Ok((dht.find_node(&placement_id(&key)).await?, dht.records_under(&key).await))
+            })
+        }
+    }
+
+    fn as_node(dht: &DistributedHashTable) -> DhtNode {
+        let identity = dht.identity();
+        DhtNode {
+            node_id: identity.node_id,
+            address: "127.0.0.1:9000".parse().unwrap(),
+            last_seen: std::time::SystemTime::now(),
+            public_key: identity.public_key,
+            nonce: identity.nonce,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_services_found_on_remote_replicas() {
+        let service_id = ServiceId::new("api", "default");
+        let publisher = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+        publisher.announce_service(&instance(&service_id, "10.0.0.1:80")).await.unwrap();
+        let announcement = publisher.get_record(&service_key(&service_id, &publisher.node_id())).await.unwrap();
+
+        // The replica holds the announcement; the finder only knows the replica
+        let replica = Arc::new(DistributedHashTable::new(KeyPair::generate().unwrap(), test_config()));
+        replica.store_record(announcement).await.unwrap();
+
+        // A node relaying a forged announcement is penalized, not believed
+        let liar = Arc::new(DistributedHashTable::new(KeyPair::generate().unwrap(), test_config()));
+        let forger = KeyPair::generate().unwrap();
+        let forged = ServiceAnnouncement {
+            service_id: service_id.clone(),
+            node_id: publisher.node_id(),
+            address: "6.6.6.6:80".parse().unwrap(),
+            metadata: HashMap::new(),
+            health: HealthStatus::Healthy,
+            announced_at: 0,
+        };
+        liar.storage.write().await.insert(service_key(&service_id, &liar.node_id()), DhtEntry {
+            record: SignedRecord::sign(
+                service_key(&service_id, &liar.node_id()),
+                serde_json::to_vec(&forged).unwrap(),
+                &forger,
+                Duration::from_secs(60),
+            ),
+            timestamp: std::time::SystemTime::now(),
+        });
+
+        let finder = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+        assert!(finder.find_services(&service_id).await.unwrap().is_empty());
+
+        finder.add_node(as_node(&replica)).await.unwrap();
+        finder.add_node(as_node(&liar)).await.unwrap();
+        finder.set_transport(Arc::new(Answering(HashMap::from([
+            (replica.node_id(), Arc::clone(&replica)),
+            (liar.node_id(), Arc::clone(&liar)),
+        ]))));
+
+        let found = finder.find_services(&service_id).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].node_id, publisher.node_id());
+        assert_eq!(found[0].address, "10.0.0.1:80".parse::<SocketAddr>().unwrap());
+        assert!(finder.reputation().score(&liar.node_id()) < finder.reputation().score(&replica.node_id()));
+    }
+
+    #[tokio::test]
+    async fn test_replicas_found_by_lookup() {
+        let dht = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+        let mut outbound = dht.take_outbound().unwrap();
+
+        let neighbor = peer(4);
+        dht.add_node(neighbor.clone()).await.unwrap();
+        let discovered = peer(4);
+        dht.set_transport(Arc::new(FixedAnswer(vec![discovered.clone()])));
+
+        // A node only the neighbor knows about still receives a replica
+        dht.put(b"key".to_vec(), b"value".to_vec()).await.unwrap();
+        let replicas: HashSet<NodeId> = std::iter::from_fn(|| outbound.try_recv().ok())
+            .map(|message| message.to().node_id)
+            .collect();
+        assert!(replicas.contains(&neighbor.node_id) && replicas.contains(&discovered.node_id));
+    }
+
+    #[tokio::test]
+    async fn test_disjoint_lookup_ignores_unadmitted_nodes() {
+        let dht = DistributedHashTable::new(KeyPair::generate().unwrap(), test_config());
+
+        let mut spoofed = peer(4);
+        spoofed.node_id = NodeId::random();
+        assert!(dht.add_node(spoofed.clone()).await.is_err());
+
+        let honest = peer(4);
+        dht.add_node(honest.clone()).await.unwrap();
+
+        let discovered = peer(4);
+        let target = NodeId::random();
+        let result = dht
+            .lookup_disjoint(&target, |node| {
+                let reply = if node.node_id == honest.node_id {
+                    vec![discovered.clone(), spoofed.clone()]
+                } else {
+                    Vec::new()
+                };
+                async move { Ok(reply) }
+            })
+            .await
+            .unwrap();
+
+        let ids: HashSet<NodeId> = result.iter().map(|n| n.node_id).collect();
+        assert!(ids.contains(&honest.node_id));
+        assert!(ids.contains(&discovered.node_id));
+        assert!(!ids.contains(&spoofed.node_id));
+    }
+}
@@ -0,0 +1,228 @@
+//! Tenant namespaces for DHT announcements
+//!
+//! A service's namespace is the namespace of its `ServiceId`. Announcements
+//! in public namespaces are stored in the clear, as before. In a namespace
+//! whose privacy is `Private` or `PrivateNetwork`, the members share a secret
+//! [`NamespaceKey`], and announcements are sealed with it: the record key is
+//! a keyed hash of the service, so it gives away neither the service nor the
+//! namespace, and the announcement is encrypted and authenticated under a key
+//! derived from the secret. Nodes of other tenants still store and replicate
+//! these records, but cannot find them by service, read them or forge them.
+
+use crate::error::{NetworkError, Result};
+use nexus_shared::{random_bytes, NodeId, ServiceId};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Key prefix of every sealed announcement
+pub(crate) const SEALED_KEY_PREFIX: &str = "nsvc:";
+
+const LOCATOR_CONTEXT: &str = "nexus dht namespace locator v1";
+const SEAL_CONTEXT: &str = "nexus dht namespace seal v1";
+
+/// Who may discover the services of a namespace, as in asset privacy levels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NamespacePrivacy {
+    /// Internal network only
+    Private,
+    /// Specific networks or groups
+    PrivateNetwork,
+    /// Trusted peer sharing
+    P2P,
+    /// Specific public networks
+    PublicNetwork,
+    /// Discoverable by everyone
+    #[default]
+    FullPublic,
+}
+
+impl NamespacePrivacy {
+    /// Whether announcements are sealed under the namespace key
+    pub fn is_sealed(self) -> bool {
+        matches!(self, NamespacePrivacy::Private | NamespacePrivacy::PrivateNetwork)
+    }
+}
+
+/// Secret shared by the members of a private namespace
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceKey([u8; 32]);
+
+impl NamespaceKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&random_bytes(32));
+        Self(key)
+    }
+
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for NamespaceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("NamespaceKey(..)")
+    }
+}
+
+/// A namespace this node belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceConfig {
+    pub name: String,
+    pub privacy: NamespacePrivacy,
+    /// Required for sealed namespaces
+    #[serde(default)]
+    pub key: Option<NamespaceKey>,
+}
+
+/// Keys derived from a sealed namespace's secret
+pub(crate) struct SealedNamespace {
+    locator: [u8; 32],
+    seal: LessSafeKey,
+}
+
+impl SealedNamespace {
+    fn new(key: &NamespaceKey) -> Self {
+        let seal = blake3::derive_key(SEAL_CONTEXT, key.as_bytes());
+        Self {
+            locator: blake3::derive_key(LOCATOR_CONTEXT, key.as_bytes()),
+            seal: LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &seal).expect("32-byte key")),
+        }
+    }
+
+    /// Common prefix of every sealed announcement of a service
+    pub fn service_prefix(&self, service_id: &ServiceId) -> String {
+        let locator = blake3::keyed_hash(&self.locator, service_id.to_string().as_bytes());
+        format!("{}{}/", SEALED_KEY_PREFIX, locator.to_hex())
+    }
+
+    /// Key of one node's sealed announcement of a service
+    pub fn service_key(&self, service_id: &ServiceId, node_id: &NodeId) -> Vec<u8> {
+        format!("{}{}", self.service_prefix(service_id), node_id).into_bytes()
+    }
+
+    /// Encrypt a value stored under `key`, binding it to the key
+    pub fn seal(&self, key: &[u8], value: &[u8]) -> Vec<u8> {
+        let nonce: [u8; NONCE_LEN] = random_bytes(NONCE_LEN).try_into().expect("nonce length");
+        let mut sealed = value.to_vec();
+        self.seal
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(key), &mut sealed)
+            .expect("sealing cannot fail for in-memory values");
+        let mut out = nonce.to_vec();
+        out.extend(sealed);
+        out
+    }
+
+    /// Decrypt a value sealed under `key` by a member of the namespace
+    pub fn open(&self, key: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+        let invalid = || NetworkError::Dht { message: "sealed announcement does not open under the namespace key".to_string() };
+        if !is_well_formed_seal(sealed) {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+        let mut value = ciphertext.to_vec();
+        let plaintext = self.seal.open_in_place(nonce, Aad::from(key), &mut value).map_err(|_| invalid())?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Whether a sealed record value is at least the size of a nonce and tag
+pub(crate) fn is_well_formed_seal(sealed: &[u8]) -> bool {
+    sealed.len() >= NONCE_LEN + CHACHA20_POLY1305.tag_len()
+}
+
+struct Membership {
+    privacy: NamespacePrivacy,
+    sealed: Option<Arc<SealedNamespace>>,
+}
+
+/// Namespaces this node belongs to and their keys
+///
+/// Namespaces not joined are public.
+#[derive(Default)]
+pub struct NamespaceRegistry {
+    namespaces: RwLock<HashMap<String, Membership>>,
+}
+
+impl NamespaceRegistry {
+    /// Join a namespace, replacing any earlier membership
+    pub fn join(&self, namespace: NamespaceConfig) -> Result<()> {
+        let sealed = match (namespace.privacy.is_sealed(), &namespace.key) {
+            (true, Some(key)) => Some(Arc::new(SealedNamespace::new(key))),
+            (true, None) => {
+                return Err(NetworkError::Configuration {
+                    message: format!("namespace {} is {:?} and needs a key", namespace.name, namespace.privacy),
+                })
+            }
+            (false, _) => None,
+        };
+        self.namespaces
+            .write()
+            .unwrap()
+            .insert(namespace.name, Membership { privacy: namespace.privacy, sealed });
+        Ok(())
+    }
+
+    /// Forget a namespace's key
+    ///
+    /// A sealed namespace stays sealed: its services can no longer be
+    /// announced or found from this node, rather than falling back to public.
+    pub fn leave(&self, name: &str) -> bool {
+        let mut namespaces = self.namespaces.write().unwrap();
+        if !namespaces.get(name).is_some_and(|m| m.privacy.is_sealed()) {
+            return namespaces.remove(name).is_some();
+        }
+        namespaces.get_mut(name).and_then(|m| m.sealed.take()).is_some()
+    }
+
+    pub fn privacy(&self, name: &str) -> NamespacePrivacy {
+        self.namespaces.read().unwrap().get(name).map_or(NamespacePrivacy::FullPublic, |m| m.privacy)
+    }
+
+    /// Keys of a sealed namespace this node belongs to
+    pub(crate) fn sealed(&self, name: &str) -> Option<Arc<SealedNamespace>> {
+        self.namespaces.read().unwrap().get(name).and_then(|m| m.sealed.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_namespace_hides_services() {
+        let key = NamespaceKey::generate();
+        let tenant = SealedNamespace::new(&key);
+        let other = SealedNamespace::new(&NamespaceKey::generate());
+        let service_id = ServiceId::new("billing", "acme");
+
+        // Locators depend on the key and reveal nothing of the service
+        let prefix = tenant.service_prefix(&service_id);
+        assert!(prefix.starts_with(SEALED_KEY_PREFIX));
+        assert!(!prefix.contains("billing") && !prefix.contains("acme"));
+        assert_eq!(prefix, SealedNamespace::new(&key).service_prefix(&service_id));
+        assert_ne!(prefix, other.service_prefix(&service_id));
+
+        // Only members open sealed values, and only under the key they were sealed for
+        let sealed = tenant.seal(prefix.as_bytes(), b"10.0.0.1:443");
+        assert!(is_well_formed_seal(&sealed));
+        assert_eq!(tenant.open(prefix.as_bytes(), &sealed).unwrap(), b"10.0.0.1:443");
+        assert!(other.open(prefix.as_bytes(), &sealed).is_err());
+        assert!(tenant.open(b"nsvc:elsewhere/", &sealed).is_err());
+
+        let registry = NamespaceRegistry::default();
+        let private = |key| NamespaceConfig { name: "acme".to_string(), privacy: NamespacePrivacy::Private, key };
+        assert!(registry.join(private(None)).is_err());
+        registry.join(private(Some(key))).unwrap();
+        assert!(registry.sealed("acme").is_some());
+        assert_eq!(registry.privacy("default"), NamespacePrivacy::FullPublic);
+        assert!(registry.sealed("default").is_none());
+    }
+}
@@ -0,0 +1,472 @@
+//! S/Kademlia-style identity, admission and record signing for the DHT
+//!
+//! Node IDs are not chosen freely: a node's ID is the hash of its TrustChain
+//! public key, and joining the routing table requires either a proof-of-work
+//! over that ID or sufficient stake. Every stored value is wrapped in a
+//! [`SignedRecord`] bound to the publisher's key and carrying an expiry, so a
+//! node relaying records cannot forge or indefinitely replay them.
+//!
+//! Peers that hand over invalid records lose reputation and, once it drops
+//! to the quarantine threshold, are cut off for a while: their records are
+//! refused and they are dropped from the routing table. Each further
+//! quarantine lasts twice as long, and a released peer returns on probation,
+//! one invalid record away from the next quarantine.
+
+use crate::error::{NetworkError, Result};
+use nexus_shared::{hash, KeyPair, NodeId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// DHT security configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DhtSecurityConfig {
+    /// Leading zero bits required of the admission proof-of-work
+    pub pow_difficulty: u32,
+
+    /// Stake that admits a node without proof-of-work (0 disables)
+    pub min_stake: u64,
+
+    /// Lifetime of records published by this node
+    pub record_ttl: Duration,
+
+    /// Tolerated clock skew when checking record timestamps
+    pub max_clock_skew: Duration,
+
+    /// Number of disjoint paths used for lookups
+    pub disjoint_paths: usize,
+
+    /// Reputation and quarantine of peers serving invalid records
+    #[serde(default)]
+    pub reputation: ReputationConfig,
+}
+
+impl Default for DhtSecurityConfig {
+    fn default() -> Self {
+        Self {
+            pow_difficulty: 16,
+            min_stake: 0,
+            record_ttl: Duration::from_secs(3600),
+            max_clock_skew: Duration::from_secs(30),
+            disjoint_paths: 3,
+            reputation: ReputationConfig::default(),
+        }
+    }
+}
+
+/// Peer reputation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationConfig {
+    /// Score lost for each invalid record a peer serves
+    pub invalid_record_penalty: u32,
+
+    /// Score regained for each valid record, up to the maximum of 100
+    pub valid_record_reward: u32,
+
+    /// Score at or below which a peer is quarantined
+    pub quarantine_threshold: u32,
+
+    /// Length of a first quarantine; doubles with each further one
+    pub quarantine_duration: Duration,
+
+    /// Longest quarantine
+    pub max_quarantine_duration: Duration,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            invalid_record_penalty: 25,
+            valid_record_reward: 1,
+            quarantine_threshold: 50,
+            quarantine_duration: Duration::from_secs(600),
+            max_quarantine_duration: Duration::from_secs(86400),
+        }
+    }
+}
+
+/// Source of stake information used for admission
+pub trait StakeRegistry: Send + Sync {
+    /// Stake currently bonded to the given public key
+    fn stake(&self, public_key: &[u8; 32]) -> u64;
+}
+
+/// Derive a node ID from a TrustChain public key
+pub fn derive_node_id(public_key: &[u8; 32]) -> NodeId {
+    NodeId::new(hash(public_key))
+}
+
+/// Number of leading zero bits in a digest
+fn leading_zero_bits(digest: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in digest {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+fn pow_digest(node_id: &NodeId, nonce: u64) -> [u8; 32] {
+    let mut input = node_id.as_bytes().to_vec();
+    input.extend_from_slice(&nonce.to_be_bytes());
+    hash(&input)
+}
+
+/// Self-certifying node identity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeIdentity {
+    pub node_id: NodeId,
+    pub public_key: [u8; 32],
+    /// Proof-of-work nonce over the node ID
+    pub nonce: u64,
+}
+
+impl NodeIdentity {
+    /// Derive the identity for a public key and solve its proof-of-work
+    pub fn generate(public_key: [u8; 32], difficulty: u32) -> Self {
+        let node_id = derive_node_id(&public_key);
+        let nonce = (0u64..)
+            .find(|nonce| leading_zero_bits(&pow_digest(&node_id, *nonce)) >= difficulty)
+            .expect("proof-of-work search space exhausted");
+
+        Self { node_id, public_key, nonce }
+    }
+
+    /// Whether the node ID is the hash of the public key
+    pub fn is_self_certifying(&self) -> bool {
+        self.node_id == derive_node_id(&self.public_key)
+    }
+
+    /// Proof-of-work strength of this identity in bits
+    pub fn work(&self) -> u32 {
+        leading_zero_bits(&pow_digest(&self.node_id, self.nonce))
+    }
+}
+
+/// Admission control for the routing table
+#[derive(Clone)]
+pub struct AdmissionPolicy {
+    config: DhtSecurityConfig,
+    stake_registry: Option<Arc<dyn StakeRegistry>>,
+}
+
+impl AdmissionPolicy {
+    pub fn new(config: DhtSecurityConfig) -> Self {
+        Self {
+            config,
+            stake_registry: None,
+        }
+    }
+
+    /// Admit nodes with sufficient stake even without proof-of-work
+    pub fn with_stake_registry(mut self, registry: Arc<dyn StakeRegistry>) -> Self {
+        self.stake_registry = Some(registry);
+        self
+    }
+
+    /// Check whether an identity may join the routing table
+    pub fn admit(&self, identity: &NodeIdentity) -> Result<()> {
+        if !identity.is_self_certifying() {
+            return Err(NetworkError::Dht {
+                message: format!("Node ID {} does not match its public key", identity.node_id),
+            });
+        }
+
+        if identity.work() >= self.config.pow_difficulty {
+            return Ok(());
+        }
+
+        if self.config.min_stake > 0 {
+            if let Some(registry) = &self.stake_registry {
+                if registry.stake(&identity.public_key) >= self.config.min_stake {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(NetworkError::Dht {
+            message: format!(
+                "Node {} has insufficient proof-of-work or stake for admission",
+                identity.node_id
+            ),
+        })
+    }
+}
+
+impl std::fmt::Debug for AdmissionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdmissionPolicy")
+            .field("config", &self.config)
+            .field("stake_registry", &self.stake_registry.is_some())
+            .finish()
+    }
+}
+
+/// Reputation score of a peer nothing is known against
+pub const MAX_REPUTATION: u32 = 100;
+
+#[derive(Debug, Clone)]
+struct Standing {
+    score: u32,
+    quarantines: u32,
+    quarantined_until: Option<Instant>,
+}
+
+impl Default for Standing {
+    fn default() -> Self {
+        Self { score: MAX_REPUTATION, quarantines: 0, quarantined_until: None }
+    }
+}
+
+/// Reputation of the peers records are received from
+#[derive(Debug)]
+pub struct PeerReputation {
+    config: ReputationConfig,
+    peers: Mutex<HashMap<NodeId, Standing>>,
+}
+
+impl PeerReputation {
+    pub fn new(config: ReputationConfig) -> Self {
+        Self { config, peers: Mutex::new(HashMap::new()) }
+    }
+
+    /// Credit a peer for serving a valid record
+    pub fn record_valid(&self, peer: &NodeId) {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some(standing) = peers.get_mut(peer) {
+            standing.score = (standing.score + self.config.valid_record_reward).min(MAX_REPUTATION);
+        }
+    }
+
+    /// Penalize a peer for serving an invalid record; true if this quarantined it
+    pub fn record_invalid(&self, peer: &NodeId, reason: &str) -> bool {
+        self.record_invalid_at(peer, reason, Instant::now())
+    }
+
+    /// Whether a peer is currently quarantined
+    pub fn is_quarantined(&self, peer: &NodeId) -> bool {
+        self.is_quarantined_at(peer, Instant::now())
+    }
+
+    /// Current score of a peer
+    pub fn score(&self, peer: &NodeId) -> u32 {
+        self.peers.lock().unwrap().get(peer).map_or(MAX_REPUTATION, |standing| standing.score)
+    }
+
+    /// Peers currently in quarantine
+    pub fn quarantined(&self) -> Vec<NodeId> {
+        let now = Instant::now();
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, standing)| standing.quarantined_until.is_some_and(|until| now < until))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    fn record_invalid_at(&self, peer: &NodeId, reason: &str, now: Instant) -> bool {
+        let config = &self.config;
+        let mut peers = self.peers.lock().unwrap();
+        let standing = peers.entry(*peer).or_default();
+        Self::release_expired(config, standing, now);
+        if standing.quarantined_until.is_some() {
+            return false;
+        }
+
+        standing.score = standing.score.saturating_sub(config.invalid_record_penalty);
+        tracing::debug!("Peer {} served an invalid record ({}), reputation {}", peer, reason, standing.score);
+        if standing.score > config.quarantine_threshold {
+            return false;
+        }
+
+        let duration = config.quarantine_duration
+            .saturating_mul(1 << standing.quarantines.min(16))
+            .min(config.max_quarantine_duration);
+        standing.quarantines += 1;
+        standing.quarantined_until = Some(now + duration);
+        tracing::warn!("Quarantined DHT peer {} for {:?} after serving invalid records", peer, duration);
+        true
+    }
+
+    fn is_quarantined_at(&self, peer: &NodeId, now: Instant) -> bool {
+        let mut peers = self.peers.lock().unwrap();
+        peers.get_mut(peer).is_some_and(|standing| {
+            Self::release_expired(&self.config, standing, now);
+            standing.quarantined_until.is_some()
+        })
+    }
+
+    /// End a quarantine whose time is up, leaving the peer on probation
+    fn release_expired(config: &ReputationConfig, standing: &mut Standing, now: Instant) {
+        if standing.quarantined_until.is_some_and(|until| now >= until) {
+            standing.quarantined_until = None;
+            standing.score = (config.quarantine_threshold + config.invalid_record_penalty).min(MAX_REPUTATION);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+/// Record stored in the DHT, signed by its publisher
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRecord {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub publisher: NodeId,
+    pub public_key: [u8; 32],
+    /// Unix seconds
+    pub issued_at: u64,
+    /// Unix seconds
+    pub expires_at: u64,
+    pub signature: Vec<u8>,
+}
+
+impl SignedRecord {
+    /// Create and sign a record valid for `ttl`
+    pub fn sign(key: Vec<u8>, value: Vec<u8>, key_pair: &KeyPair, ttl: Duration) -> Self {
+        let public_key = *key_pair.public_key();
+        let issued_at = unix_now();
+
+        let mut record = Self {
+            key,
+            value,
+            publisher: derive_node_id(&public_key),
+            public_key,
+            issued_at,
+            expires_at: issued_at + ttl.as_secs(),
+            signature: Vec::new(),
+        };
+        record.signature = key_pair.sign(&record.signing_bytes());
+        record
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.key.len() + self.value.len() + 48);
+        bytes.extend_from_slice(&(self.key.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&self.key);
+        bytes.extend_from_slice(&(self.value.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&self.value);
+        bytes.extend_from_slice(&self.issued_at.to_be_bytes());
+        bytes.extend_from_slice(&self.expires_at.to_be_bytes());
+        bytes
+    }
+
+    /// Whether the record has passed its expiry
+    pub fn is_expired(&self) -> bool {
+        unix_now() >= self.expires_at
+    }
+
+    /// Verify publisher binding, signature and validity window
+    pub fn verify(&self, max_clock_skew: Duration) -> Result<()> {
+        let invalid = |reason: &str| NetworkError::Dht {
+            message: format!("Rejected record from {}: {}", self.publisher, reason),
+        };
+
+        if self.publisher != derive_node_id(&self.public_key) {
+            return Err(invalid("publisher does not match public key"));
+        }
+        if !KeyPair::verify(&self.public_key, &self.signing_bytes(), &self.signature) {
+            return Err(invalid("bad signature"));
+        }
+        if self.issued_at > unix_now() + max_clock_skew.as_secs() {
+            return Err(invalid("issued in the future"));
+        }
+        if self.is_expired() {
+            return Err(invalid("expired"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedStake(u64);
+
+    impl StakeRegistry for FixedStake {
+        fn stake(&self, _public_key: &[u8; 32]) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_identity_admission() {
+        let key_pair = KeyPair::generate().unwrap();
+        let config = DhtSecurityConfig {
+            pow_difficulty: 8,
+            ..Default::default()
+        };
+        let policy = AdmissionPolicy::new(config.clone());
+
+        let identity = NodeIdentity::generate(*key_pair.public_key(), 8);
+        assert!(identity.is_self_certifying());
+        assert!(policy.admit(&identity).is_ok());
+
+        // Spoofed node ID
+        let mut spoofed = identity.clone();
+        spoofed.node_id = NodeId::random();
+        assert!(policy.admit(&spoofed).is_err());
+
+        // Too little work, admitted only through stake
+        let lazy = (0u64..)
+            .map(|nonce| NodeIdentity { nonce, ..identity.clone() })
+            .find(|candidate| candidate.work() < 8)
+            .unwrap();
+        assert!(policy.admit(&lazy).is_err());
+
+        let staked = AdmissionPolicy::new(DhtSecurityConfig { min_stake: 100, ..config })
+            .with_stake_registry(Arc::new(FixedStake(100)));
+        assert!(staked.admit(&lazy).is_ok());
+    }
+
+    #[test]
+    fn test_signed_record() {
+        let key_pair = KeyPair::generate().unwrap();
+        let skew = Duration::from_secs(30);
+
+        let record = SignedRecord::sign(b"key".to_vec(), b"value".to_vec(), &key_pair, Duration::from_secs(60));
+        assert!(record.verify(skew).is_ok());
+
+        let mut tampered = record.clone();
+        tampered.value = b"poisoned".to_vec();
+        assert!(tampered.verify(skew).is_err());
+
+        let expired = SignedRecord::sign(b"key".to_vec(), b"value".to_vec(), &key_pair, Duration::ZERO);
+        assert!(expired.verify(skew).is_err());
+    }
+
+    #[test]
+    fn test_quarantine_after_invalid_records() {
+        let reputation = PeerReputation::new(ReputationConfig {
+            quarantine_duration: Duration::from_secs(10),
+            ..Default::default()
+        });
+        let peer = NodeId::random();
+        let start = Instant::now();
+
+        assert!(!reputation.record_invalid_at(&peer, "bad signature", start));
+        assert!(reputation.record_invalid_at(&peer, "bad signature", start));
+        assert!(reputation.is_quarantined_at(&peer, start));
+
+        // Released on probation: the next invalid record quarantines again, for longer
+        let released = start + Duration::from_secs(11);
+        assert!(!reputation.is_quarantined_at(&peer, released));
+        assert_eq!(reputation.score(&peer), 75);
+        assert!(reputation.record_invalid_at(&peer, "bad signature", released));
+        assert!(reputation.is_quarantined_at(&peer, released + Duration::from_secs(15)));
+        assert!(!reputation.is_quarantined_at(&peer, released + Duration::from_secs(21)));
+    }
+}
@@ -0,0 +1,267 @@
+//! Service discovery cache warming and negative caching
+//!
+//! [`ServiceResolver`] sits in front of the local registry and the DHT. Lookups
+//! for services that turn out to be missing are remembered for a short TTL so
+//! repeated queries for an absent service don't fall through to the DHT every
+//! time, and services listed as critical are refreshed in the background so
+//! the first request to them never pays for a cold lookup.
+
+use crate::dht::DistributedHashTable;
+use crate::discovery::{ServiceDiscovery, ServiceDiscoveryEvent, ServiceInstance};
+use crate::error::Result;
+use nexus_shared::ServiceId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, warn};
+
+/// Discovery cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryCacheConfig {
+    /// Services kept warm in the cache regardless of traffic
+    pub critical_services: Vec<String>,
+
+    /// How long a failed lookup is remembered
+    pub negative_ttl: Duration,
+
+    /// Interval between warming passes for critical services
+    pub warm_interval: Duration,
+}
+
+impl Default for DiscoveryCacheConfig {
+    fn default() -> Self {
+        Self {
+            critical_services: Vec::new(),
+            negative_ttl: Duration::from_secs(5),
+            warm_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Cached service lookups backed by the registry and the DHT
+pub struct ServiceResolver {
+    config: DiscoveryCacheConfig,
+    service_discovery: Arc<ServiceDiscovery>,
+    dht: Arc<DistributedHashTable>,
+
+    /// Positive cache of discovered instances
+    remote_services: Arc<RwLock<HashMap<ServiceId, Vec<ServiceInstance>>>>,
+
+    /// Services known to be missing, with the time the entry expires
+    missing: RwLock<HashMap<ServiceId, Instant>>,
+
+    /// Background warming task
+    warming_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl ServiceResolver {
+    /// Create a resolver that caches into the given instance map
+    pub fn new(
+        config: DiscoveryCacheConfig,
+        service_discovery: Arc<ServiceDiscovery>,
+        dht: Arc<DistributedHashTable>,
+        remote_services: Arc<RwLock<HashMap<ServiceId, Vec<ServiceInstance>>>>,
+    ) -> Self {
+        Self {
+            config,
+            service_discovery,
+            dht,
+            remote_services,
+            missing: RwLock::new(HashMap::new()),
+            warming_task: Mutex::new(None),
+        }
+    }
+
+    /// Resolve a service, answering from the cache when possible
+    pub async fn resolve(&self, service_name: &str) -> Result<Vec<ServiceInstance>> {
+        let service_id = ServiceId::new(service_name, "default");
+
+        if let Some(instances) = self.remote_services.read().await.get(&service_id) {
+            if !instances.is_empty() {
+                return Ok(instances.clone());
+            }
+        }
+
+        if self.is_known_missing(&service_id).await {
+            debug!("Negative cache hit for service: {}", service_id);
+            return Ok(Vec::new());
+        }
+
+        self.lookup(service_id).await
+    }
+
+    /// Look a service up in the registry and the DHT, bypassing the cache
+    async fn lookup(&self, service_id: ServiceId) -> Result<Vec<ServiceInstance>> {
+        let mut instances = self.service_discovery.discover_services(service_id.name()).await?;
+
+        if instances.is_empty() {
+            instances = self.dht.find_services(&service_id).await?
+                .into_iter()
+                .map(|announcement| announcement.into_instance())
+                .collect();
+        }
+
+        if instances.is_empty() {
+            let expires_at = Instant::now() + self.config.negative_ttl;
+            self.missing.write().await.insert(service_id, expires_at);
+        } else {
+            self.missing.write().await.remove(&service_id);
+            self.remote_services.write().await.insert(service_id, instances.clone());
+        }
+
+        Ok(instances)
+    }
+
+    /// Whether a service has a live negative cache entry
+    pub async fn is_known_missing(&self, service_id: &ServiceId) -> bool {
+        self.missing
+            .read()
+            .await
+            .get(service_id)
+            .map_or(false, |expires_at| *expires_at > Instant::now())
+    }
+
+    /// Forget a negative cache entry, e.g. once the service registers
+    pub async fn invalidate_missing(&self, service_id: &ServiceId) {
+        self.missing.write().await.remove(service_id);
+    }
+
+    /// Refresh every critical service, returning how many resolved
+    pub async fn warm(&self) -> usize {
+        let mut warmed = 0;
+
+        for name in &self.config.critical_services {
+            match self.lookup(ServiceId::new(name, "default")).await {
+                Ok(instances) if !instances.is_empty() => warmed += 1,
+                Ok(_) => debug!("Critical service not yet available: {}", name),
+                Err(e) => warn!("Failed to warm critical service {}: {}", name, e),
+            }
+        }
+
+        warmed
+    }
+
+    /// Drop expired negative cache entries
+    async fn purge_expired(&self) {
+        let now = Instant::now();
+        self.missing.write().await.retain(|_, expires_at| *expires_at > now);
+    }
+
+    /// Start the background warming task
+    pub fn start(self: &Arc<Self>) {
+        let resolver = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            resolver.warming_loop().await;
+        });
+
+        if let Some(previous) = self.warming_task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// Stop the background warming task
+    pub fn stop(&self) {
+        if let Some(task) = self.warming_task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    async fn warming_loop(&self) {
+        let mut events = self.service_discovery.subscribe();
+        let mut interval = tokio::time::interval(self.config.warm_interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let warmed = self.warm().await;
+                    debug!("Warmed {}/{} critical services", warmed, self.config.critical_services.len());
+                    self.purge_expired().await;
+                }
+                event = events.recv() => match event {
+                    Ok(ServiceDiscoveryEvent::ServiceRegistered { service }) => {
+                        self.invalidate_missing(&service.service_id).await;
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        // Registrations may have been missed; don't keep serving stale misses
+                        warn!("Discovery cache lagged by {} events, clearing negative cache", skipped);
+                        self.missing.write().await.clear();
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dht::DhtConfig;
+    use crate::discovery::ServiceDiscoveryConfig;
+    use crate::HealthStatus;
+    use nexus_shared::NodeId;
+    use std::time::SystemTime;
+    use nexus_shared::KeyPair;
+
+    async fn resolver(config: DiscoveryCacheConfig) -> ServiceResolver {
+        let node_id = NodeId::random();
+        let discovery = ServiceDiscovery::new(&ServiceDiscoveryConfig::default(), node_id)
+            .await
+            .unwrap();
+
+        ServiceResolver::new(
+            config,
+            Arc::new(discovery),
+            Arc::new(DistributedHashTable::new(KeyPair::generate().unwrap(), DhtConfig::default())),
+            Arc::new(RwLock::new(HashMap::new())),
+        )
+    }
+
+    fn instance(name: &str) -> ServiceInstance {
+        ServiceInstance {
+            service_id: ServiceId::new(name, "default"),
+            node_id: NodeId::random(),
+            address: "127.0.0.1:8080".parse().unwrap(),
+            health_status: HealthStatus::Healthy,
+            metadata: HashMap::new(),
+            last_seen: SystemTime::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache() {
+        let resolver = resolver(DiscoveryCacheConfig::default()).await;
+        let service_id = ServiceId::new("absent", "default");
+
+        assert!(resolver.resolve("absent").await.unwrap().is_empty());
+        assert!(resolver.is_known_missing(&service_id).await);
+
+        // Registered while negatively cached: still hidden until invalidated
+        resolver.service_discovery.register_service(instance("absent")).await.unwrap();
+        assert!(resolver.resolve("absent").await.unwrap().is_empty());
+
+        resolver.invalidate_missing(&service_id).await;
+        assert_eq!(resolver.resolve("absent").await.unwrap().len(), 1);
+        assert!(!resolver.is_known_missing(&service_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_warm_critical_services() {
+        let config = DiscoveryCacheConfig {
+            critical_services: vec!["api".to_string(), "auth".to_string()],
+            ..Default::default()
+        };
+        let resolver = resolver(config).await;
+        resolver.service_discovery.register_service(instance("api")).await.unwrap();
+
+        assert_eq!(resolver.warm().await, 1);
+
+        let cached = resolver.remote_services.read().await;
+        assert!(cached.contains_key(&ServiceId::new("api", "default")));
+        drop(cached);
+        assert!(resolver.is_known_missing(&ServiceId::new("auth", "default")).await);
+    }
+}
@@ -0,0 +1,250 @@
+//! Connection draining
+//!
+//! Taking an endpoint out of service happens in steps instead of all at
+//! once. The endpoint is first announced as `Draining`: discovery stops
+//! returning it and routers stop sending it new requests, while requests
+//! already in flight carry on. The hosting node refuses new inbound requests
+//! for it and waits, up to a grace period, for the ones it is serving to
+//! finish before the endpoint is withdrawn. Clients close their connection to
+//! a draining endpoint's node only once their own requests to that node have
+//! completed, so no stream is cut halfway through.
+
+use crate::error::{NetworkError, Result};
+use nexus_shared::{NodeId, ServiceId};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Connection draining configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrainConfig {
+    /// Longest in-flight requests are waited for before a drain gives up on them
+    pub grace_period: Duration,
+}
+
+impl Default for DrainConfig {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How a drain ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainOutcome {
+    /// Every in-flight request finished
+    Drained,
+    /// The grace period ran out with requests still in flight
+    GracePeriodExpired { in_flight: usize },
+}
+
+struct InFlightInner<K> {
+    counts: Mutex<HashMap<K, usize>>,
+    idle: Notify,
+}
+
+/// Requests in flight per key
+pub struct InFlight<K> {
+    inner: Arc<InFlightInner<K>>,
+}
+
+impl<K> Clone for InFlight<K> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Default for InFlight<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash + Clone> InFlight<K> {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(InFlightInner {
+                counts: Mutex::new(HashMap::new()),
+                idle: Notify::new(),
+            }),
+        }
+    }
+
+    /// Count a request until the returned guard is dropped
+    pub fn begin(&self, key: K) -> InFlightGuard<K> {
+        *self.inner.counts.lock().entry(key.clone()).or_default() += 1;
+        InFlightGuard { inner: self.inner.clone(), key }
+    }
+
+    pub fn count(&self, key: &K) -> usize {
+        self.inner.counts.lock().get(key).copied().unwrap_or(0)
+    }
+
+    /// Wait until nothing is in flight for `key`, or `timeout` passes
+    pub async fn wait_idle(&self, key: &K, timeout: Duration) -> DrainOutcome {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Registered before the count is read so a request finishing in
+            // between still wakes us
+            let notified = self.inner.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.count(key) == 0 {
+                return DrainOutcome::Drained;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return match self.count(key) {
+                    0 => DrainOutcome::Drained,
+                    in_flight => DrainOutcome::GracePeriodExpired { in_flight },
+                };
+            }
+        }
+    }
+}
+
+/// A request in flight; finishes when dropped
+pub struct InFlightGuard<K: Eq + Hash> {
+    inner: Arc<InFlightInner<K>>,
+    key: K,
+}
+
+impl<K: Eq + Hash> Drop for InFlightGuard<K> {
+    fn drop(&mut self) {
+        let mut counts = self.inner.counts.lock();
+        if let Some(count) = counts.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.key);
+                self.inner.idle.notify_waiters();
+            }
+        }
+    }
+}
+
+/// Draining state of this node's services and its connections to others
+pub struct Drainer {
+    config: DrainConfig,
+    /// Requests this node is serving, by service
+    inbound: InFlight<ServiceId>,
+    /// Requests this node has sent, by the node serving them
+    outbound: InFlight<NodeId>,
+    draining: Mutex<HashSet<ServiceId>>,
+    closing: Mutex<HashSet<NodeId>>,
+}
+
+impl Drainer {
+    pub fn new(config: DrainConfig) -> Self {
+        Self {
+            config,
+            inbound: InFlight::new(),
+            outbound: InFlight::new(),
+            draining: Mutex::new(HashSet::new()),
+            closing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn config(&self) -> &DrainConfig {
+        &self.config
+    }
+
+    /// Count a request this node serves; refused once the service is draining
+    pub fn begin_inbound(&self, service_id: &ServiceId) -> Result<InFlightGuard<ServiceId>> {
+        // Checked under the same lock `start` takes, so a drain never misses
+        // a request admitted just before it
+        let draining = self.draining.lock();
+        if draining.contains(service_id) {
+            return Err(NetworkError::ServiceDraining { service_id: service_id.clone() });
+        }
+        Ok(self.inbound.begin(service_id.clone()))
+    }
+
+    /// Count a request sent to another node
+    pub fn begin_outbound(&self, node_id: NodeId) -> InFlightGuard<NodeId> {
+        self.outbound.begin(node_id)
+    }
+
+    pub fn inbound_count(&self, service_id: &ServiceId) -> usize {
+        self.inbound.count(service_id)
+    }
+
+    pub fn outbound_count(&self, node_id: &NodeId) -> usize {
+        self.outbound.count(node_id)
+    }
+
+    /// Stop admitting requests for a local service
+    pub fn start(&self, service_id: &ServiceId) {
+        self.draining.lock().insert(service_id.clone());
+    }
+
+    pub fn is_draining(&self, service_id: &ServiceId) -> bool {
+        self.draining.lock().contains(service_id)
+    }
+
+    /// Forget a service's drain, once it is deregistered or registered again
+    pub fn finish(&self, service_id: &ServiceId) {
+        self.draining.lock().remove(service_id);
+    }
+
+    /// Wait for the requests a local service is serving, within the grace period
+    pub async fn wait_inbound(&self, service_id: &ServiceId) -> DrainOutcome {
+        self.inbound.wait_idle(service_id, self.config.grace_period).await
+    }
+
+    /// Wait for this node's requests to another node, within the grace period
+    pub async fn wait_outbound(&self, node_id: &NodeId) -> DrainOutcome {
+        self.outbound.wait_idle(node_id, self.config.grace_period).await
+    }
+
+    /// Claim the job of closing the connection to a node; false if already claimed
+    pub fn claim_close(&self, node_id: NodeId) -> bool {
+        self.closing.lock().insert(node_id)
+    }
+
+    pub fn release_close(&self, node_id: &NodeId) {
+        self.closing.lock().remove(node_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_requests() {
+        let drainer = Arc::new(Drainer::new(DrainConfig { grace_period: Duration::from_millis(200) }));
+        let service = ServiceId::new("api", "default");
+
+        let first = drainer.begin_inbound(&service).unwrap();
+        let second = drainer.begin_inbound(&service).unwrap();
+        drainer.start(&service);
+        assert!(matches!(drainer.begin_inbound(&service), Err(NetworkError::ServiceDraining { .. })));
+        assert_eq!(drainer.inbound_count(&service), 2);
+
+        // The drain completes as soon as the last request finishes
+        let waiter = tokio::spawn({
+            let (drainer, service) = (drainer.clone(), service.clone());
+            async move { drainer.wait_inbound(&service).await }
+        });
+        drop(first);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        drop(second);
+        assert_eq!(waiter.await.unwrap(), DrainOutcome::Drained);
+
+        // A request that outlives the grace period is reported, not waited on forever
+        let node = NodeId::random();
+        let _stuck = drainer.begin_outbound(node);
+        assert_eq!(drainer.wait_outbound(&node).await, DrainOutcome::GracePeriodExpired { in_flight: 1 });
+        assert!(drainer.claim_close(node));
+        assert!(!drainer.claim_close(node));
+
+        drainer.finish(&service);
+        assert!(drainer.begin_inbound(&service).is_ok());
+    }
+}
@@ -30,6 +30,9 @@ pub enum NetworkError {
     #[error("Transport error: {message}")]
     Transport { message: String },
 
+    #[error("Gossip error: {message}")]
+    Gossip { message: String },
+
 
     #[error("No healthy instances available for service: {service_name}")]
     NoHealthyInstances { service_name: String },
@@ -49,6 +52,15 @@ pub enum NetworkError {
     #[error("No backends available for service: {service_id}")]
     NoBackendsAvailable { service_id: nexus_shared::ServiceId },
     
+    #[error("Service {service_id} is draining and accepts no new requests")]
+    ServiceDraining { service_id: nexus_shared::ServiceId },
+    
+    #[error("Activation of {service_id} timed out after {timeout_ms}ms")]
+    ActivationTimeout { service_id: nexus_shared::ServiceId, timeout_ms: u64 },
+
+    #[error("Too many requests waiting for activation of {service_id}")]
+    ActivationQueueFull { service_id: nexus_shared::ServiceId },
+    
     #[error("No route found for path: {path}")]
     NoRouteFound { path: String },
 
@@ -93,6 +105,7 @@ impl NetworkError {
             NetworkError::ConnectionFailed { .. } => true,
             NetworkError::RequestFailed { .. } => true,
             NetworkError::Timeout { .. } => true,
+            NetworkError::ActivationTimeout { .. } => true,
             NetworkError::ServiceDiscovery { .. } => true,
             NetworkError::DnsResolution { .. } => true,
             NetworkError::Io(_) => true,
@@ -129,8 +142,12 @@ impl NetworkError {
             NetworkError::Routing { .. } => "routing",
             NetworkError::Dht { .. } => "dht",
             NetworkError::Transport { .. } => "transport",
+            NetworkError::Gossip { .. } => "gossip",
             NetworkError::ServiceNotFound { .. } => "service_not_found",
             NetworkError::NoBackendsAvailable { .. } => "no_backends",
+            NetworkError::ServiceDraining { .. } => "service_draining",
+            NetworkError::ActivationTimeout { .. } => "activation_timeout",
+            NetworkError::ActivationQueueFull { .. } => "activation_queue_full",
             NetworkError::NoRouteFound { .. } => "no_route",
             NetworkError::NoHealthyInstances { .. } => "no_healthy_instances",
             NetworkError::ConnectionFailed { .. } => "connection_failed",
@@ -155,6 +172,9 @@ impl NetworkError {
         match self {
             NetworkError::ServiceNotFound { .. } => "Check service name and registration",
             NetworkError::NoBackendsAvailable { .. } => "Register backends for the service",
+            NetworkError::ServiceDraining { .. } => "Retry against another instance of the service",
+            NetworkError::ActivationTimeout { .. } => "Check why the service's replicas are slow to start",
+            NetworkError::ActivationQueueFull { .. } => "Retry later or increase activation.max_queued_requests",
             NetworkError::NoRouteFound { .. } => "Configure routing rules for the path",
             NetworkError::NoHealthyInstances { .. } => "Check service health and scaling",
             NetworkError::ConnectionFailed { .. } => "Verify network connectivity and service availability",
@@ -0,0 +1,687 @@
+//! Epidemic broadcast for small mesh-wide control messages
+//!
+//! Implements Plumtree: each node keeps a handful of *eager* peers that form
+//! a spanning tree and receive full messages, and *lazy* peers that only get
+//! `IHave` announcements. Duplicate deliveries prune redundant eager links and
+//! missing announcements graft lazy links back in, so the tree repairs itself
+//! under churn. Publishers only know their direct neighbours; messages reach
+//! the whole mesh in O(log n) hops.
+//!
+//! Intended for config version bumps, revocation notices and cache
+//! invalidations, not bulk data; payloads are capped by
+//! [`GossipConfig::max_payload_bytes`].
+//!
+//! Every message is signed by the node that published it, and relays pass
+//! the signature along unchanged; a message whose signature does not match
+//! its origin is dropped rather than delivered or forwarded. The signature
+//! covers the origin's publish time, and messages older than the retention
+//! window are dropped too, so a captured message cannot be replayed once
+//! nodes have forgotten its ID.
+
+use crate::dht_security::derive_node_id;
+use crate::error::{NetworkError, Result};
+use nexus_shared::{hash, KeyPair, NodeId};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tracing::{debug, warn};
+
+/// Content-derived message identifier
+pub type MessageId = [u8; 32];
+
+/// Domain separation for gossip message signatures
+const GOSSIP_MESSAGE_CONTEXT: &[u8] = b"nexus-gossip-message-v2";
+
+/// How far ahead of the local clock an origin's publish time may be
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5);
+
+/// Gossip configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GossipConfig {
+    /// Target number of eager (tree) peers
+    pub eager_fanout: usize,
+
+    /// How long to wait for a message after an `IHave` before grafting
+    pub ihave_timeout: Duration,
+
+    /// How long delivered messages are kept for duplicate detection and
+    /// grafts; older messages are not accepted at all
+    pub message_retention: Duration,
+
+    /// Largest payload accepted for broadcast or from a peer
+    pub max_payload_bytes: usize,
+
+    /// Interval of the graft/retention timer
+    pub tick_interval: Duration,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            eager_fanout: 4,
+            ihave_timeout: Duration::from_millis(500),
+            message_retention: Duration::from_secs(120),
+            max_payload_bytes: 4096,
+            tick_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Wire messages exchanged between gossip peers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GossipMessage {
+    /// Full message, pushed along eager links
+    Gossip {
+        id: MessageId,
+        origin: NodeId,
+        topic: String,
+        payload: Vec<u8>,
+        /// Origin's clock when it published the message, in Unix milliseconds
+        sent_at: u64,
+        round: u32,
+        /// Origin's public key; the origin's ID is derived from it
+        public_key: [u8; 32],
+        /// Origin's signature over the id, origin, topic, payload and publish time
+        signature: Vec<u8>,
+    },
+    /// Announcement of messages, sent along lazy links
+    IHave { ids: Vec<MessageId> },
+    /// Request for missing messages; also makes the link eager
+    Graft { ids: Vec<MessageId> },
+    /// Demote the link to lazy
+    Prune,
+}
+
+/// Message the gossip layer needs delivered to a peer
+#[derive(Debug, Clone)]
+pub struct GossipOutbound {
+    pub to: NodeId,
+    pub message: GossipMessage,
+}
+
+/// Broadcast delivered to local subscribers
+#[derive(Debug, Clone)]
+pub struct GossipDelivery {
+    pub id: MessageId,
+    pub origin: NodeId,
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// Origin's clock when it published the message, in Unix milliseconds
+    pub sent_at: u64,
+}
+
+fn unix_millis(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Bytes the origin signs
+fn signing_bytes(delivery: &GossipDelivery) -> Vec<u8> {
+    let mut bytes = GOSSIP_MESSAGE_CONTEXT.to_vec();
+    bytes.extend_from_slice(&delivery.id);
+    bytes.extend_from_slice(delivery.origin.as_bytes());
+    bytes.extend_from_slice(&(delivery.topic.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(delivery.topic.as_bytes());
+    bytes.extend_from_slice(&(delivery.payload.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(&delivery.payload);
+    bytes.extend_from_slice(&delivery.sent_at.to_be_bytes());
+    bytes
+}
+
+/// Whether a message was signed by the node it names as origin
+fn verify_origin(delivery: &GossipDelivery, public_key: &[u8; 32], signature: &[u8]) -> bool {
+    derive_node_id(public_key) == delivery.origin
+        && KeyPair::verify(public_key, &signing_bytes(delivery), signature)
+}
+
+struct Received {
+    delivery: GossipDelivery,
+    public_key: [u8; 32],
+    signature: Vec<u8>,
+    round: u32,
+    at: Instant,
+}
+
+impl Received {
+    fn message(&self, round: u32) -> GossipMessage {
+        GossipMessage::Gossip {
+            id: self.delivery.id,
+            origin: self.delivery.origin,
+            topic: self.delivery.topic.clone(),
+            payload: self.delivery.payload.clone(),
+            sent_at: self.delivery.sent_at,
+            round,
+            public_key: self.public_key,
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+struct Missing {
+    announcers: VecDeque<NodeId>,
+    deadline: Instant,
+}
+
+#[derive(Default)]
+struct GossipState {
+    eager: HashSet<NodeId>,
+    lazy: HashSet<NodeId>,
+    received: HashMap<MessageId, Received>,
+    missing: HashMap<MessageId, Missing>,
+    sequence: u64,
+}
+
+impl GossipState {
+    fn make_eager(&mut self, peer: NodeId) {
+        self.lazy.remove(&peer);
+        self.eager.insert(peer);
+    }
+
+    fn make_lazy(&mut self, peer: NodeId) {
+        if self.eager.remove(&peer) {
+            self.lazy.insert(peer);
+        }
+    }
+}
+
+/// Plumtree broadcast engine
+pub struct Gossip {
+    config: GossipConfig,
+    node_id: NodeId,
+    key_pair: KeyPair,
+    state: RwLock<GossipState>,
+
+    /// Messages for the transport layer to deliver
+    outbound: mpsc::Sender<GossipOutbound>,
+    outbound_rx: Mutex<Option<mpsc::Receiver<GossipOutbound>>>,
+
+    /// Local deliveries
+    deliveries: broadcast::Sender<GossipDelivery>,
+
+    /// Graft and retention timer
+    tick_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl Gossip {
+    /// Create a gossip node that signs its broadcasts with the node key
+    pub fn new(key_pair: KeyPair, config: GossipConfig) -> Self {
+        let (outbound, outbound_rx) = mpsc::channel(4096);
+        let (deliveries, _) = broadcast::channel(1024);
+
+        Self {
+            config,
+            node_id: derive_node_id(key_pair.public_key()),
+            key_pair,
+            state: RwLock::new(GossipState::default()),
+            outbound,
+            outbound_rx: Mutex::new(Some(outbound_rx)),
+            deliveries,
+            tick_task: Mutex::new(None),
+        }
+    }
+
+    /// This node's ID, derived from its key
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Take the outbound message stream; the transport layer delivers these
+    pub fn take_outbound(&self) -> Option<mpsc::Receiver<GossipOutbound>> {
+        self.outbound_rx.lock().unwrap().take()
+    }
+
+    /// Subscribe to messages delivered on this node
+    pub fn subscribe(&self) -> broadcast::Receiver<GossipDelivery> {
+        self.deliveries.subscribe()
+    }
+
+    fn send(&self, to: NodeId, message: GossipMessage) {
+        if let Err(e) = self.outbound.try_send(GossipOutbound { to, message }) {
+            warn!("Dropping gossip message to {}: {}", to, e);
+        }
+    }
+
+    /// Add a neighbour; it joins the tree while eager slots are free
+    pub async fn add_peer(&self, peer: NodeId) {
+        if peer == self.node_id {
+            return;
+        }
+
+        let mut state = self.state.write().await;
+        if state.eager.contains(&peer) || state.lazy.contains(&peer) {
+            return;
+        }
+        if state.eager.len() < self.config.eager_fanout {
+            state.eager.insert(peer);
+        } else {
+            state.lazy.insert(peer);
+        }
+    }
+
+    /// Remove a departed neighbour, promoting a lazy peer if the tree lost a link
+    pub async fn remove_peer(&self, peer: &NodeId) {
+        let mut state = self.state.write().await;
+        state.lazy.remove(peer);
+
+        if !state.eager.remove(peer) || state.eager.len() >= self.config.eager_fanout {
+            return;
+        }
+
+        let replacement = state.lazy.iter().next().copied();
+        if let Some(replacement) = replacement {
+            state.make_eager(replacement);
+            drop(state);
+            self.send(replacement, GossipMessage::Graft { ids: Vec::new() });
+        }
+    }
+
+    /// Number of eager and lazy peers
+    pub async fn peer_counts(&self) -> (usize, usize) {
+        let state = self.state.read().await;
+        (state.eager.len(), state.lazy.len())
+    }
+
+    /// Broadcast a message to every node in the mesh
+    pub async fn broadcast(&self, topic: impl Into<String>, payload: Vec<u8>) -> Result<MessageId> {
+        if payload.len() > self.config.max_payload_bytes {
+            return Err(NetworkError::Gossip {
+                message: format!(
+                    "Payload of {} bytes exceeds the {} byte broadcast limit",
+                    payload.len(),
+                    self.config.max_payload_bytes
+                ),
+            });
+        }
+
+        let topic = topic.into();
+        let mut state = self.state.write().await;
+        state.sequence += 1;
+
+        let mut input = self.node_id.as_bytes().to_vec();
+        input.extend_from_slice(&state.sequence.to_be_bytes());
+        input.extend_from_slice(topic.as_bytes());
+        input.extend_from_slice(&payload);
+        let id = hash(&input);
+
+        let delivery = GossipDelivery {
+            id,
+            origin: self.node_id,
+            topic,
+            payload,
+            sent_at: unix_millis(SystemTime::now()),
+        };
+        let received = Received {
+            signature: self.key_pair.sign(&signing_bytes(&delivery)),
+            public_key: *self.key_pair.public_key(),
+            delivery: delivery.clone(),
+            round: 0,
+            at: Instant::now(),
+        };
+        self.forward(&state, &received, None);
+        state.received.insert(id, received);
+        drop(state);
+
+        let _ = self.deliveries.send(delivery);
+        Ok(id)
+    }
+
+    /// Push a message to eager peers and announce it to lazy peers
+    fn forward(&self, state: &GossipState, received: &Received, except: Option<NodeId>) {
+        for peer in state.eager.iter().filter(|p| Some(**p) != except) {
+            self.send(*peer, received.message(received.round));
+        }
+        for peer in state.lazy.iter().filter(|p| Some(**p) != except) {
+            self.send(*peer, GossipMessage::IHave { ids: vec![received.delivery.id] });
+        }
+    }
+
+    /// Handle a message received from a peer
+    pub async fn handle(&self, from: NodeId, message: GossipMessage) {
+        match message {
+            GossipMessage::Gossip { id, origin, topic, payload, sent_at, round, public_key, signature } => {
+                if payload.len() > self.config.max_payload_bytes {
+                    warn!("Dropping gossip message from {} with a {} byte payload", from, payload.len());
+                    return;
+                }
+                let delivery = GossipDelivery { id, origin, topic, payload, sent_at };
+                if !verify_origin(&delivery, &public_key, &signature) {
+                    warn!("Dropping gossip message from {} not signed by its origin {}", from, origin);
+                    return;
+                }
+                if !self.is_fresh(sent_at) {
+                    debug!("Dropping gossip message from {} published outside the retention window", from);
+                    return;
+                }
+
+                let mut state = self.state.write().await;
+
+                if state.received.contains_key(&id) {
+                    // Redundant path: keep the link only for announcements
+                    state.make_lazy(from);
+                    drop(state);
+                    self.send(from, GossipMessage::Prune);
+                    return;
+                }
+
+                state.missing.remove(&id);
+                state.make_eager(from);
+                let received = Received {
+                    delivery: delivery.clone(),
+                    public_key,
+                    signature,
+                    round: round + 1,
+                    at: Instant::now(),
+                };
+                self.forward(&state, &received, Some(from));
+                state.received.insert(id, received);
+                drop(state);
+
+                let _ = self.deliveries.send(delivery);
+            }
+
+            GossipMessage::IHave { ids } => {
+                let mut state = self.state.write().await;
+                let deadline = Instant::now() + self.config.ihave_timeout;
+
+                for id in ids {
+                    if state.received.contains_key(&id) {
+                        continue;
+                    }
+                    state
+                        .missing
+                        .entry(id)
+                        .or_insert_with(|| Missing {
+                            announcers: VecDeque::new(),
+                            deadline,
+                        })
+                        .announcers
+                        .push_back(from);
+                }
+            }
+
+            GossipMessage::Graft { ids } => {
+                let mut state = self.state.write().await;
+                state.make_eager(from);
+
+                let replies: Vec<GossipMessage> = ids
+                    .iter()
+                    .filter_map(|id| state.received.get(id))
+                    .map(|received| received.message(received.round))
+                    .collect();
+                drop(state);
+
+                for reply in replies {
+                    self.send(from, reply);
+                }
+            }
+
+            GossipMessage::Prune => {
+                self.state.write().await.make_lazy(from);
+            }
+        }
+    }
+
+    /// Whether a publish time falls inside the window IDs are remembered for
+    fn is_fresh(&self, sent_at: u64) -> bool {
+        let now = unix_millis(SystemTime::now());
+        let retention = self.config.message_retention.as_millis() as u64;
+        sent_at <= now + MAX_CLOCK_SKEW.as_millis() as u64 && now.saturating_sub(sent_at) < retention
+    }
+
+    /// Graft links for overdue announcements and drop expired messages
+    pub async fn tick(&self) {
+        let now = Instant::now();
+        let mut grafts = Vec::new();
+        let mut state = self.state.write().await;
+
+        let overdue: Vec<MessageId> = state
+            .missing
+            .iter()
+            .filter(|(_, missing)| missing.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in overdue {
+            let announcer = state.missing.get_mut(&id).and_then(|missing| {
+                missing.deadline = now + self.config.ihave_timeout;
+                missing.announcers.pop_front()
+            });
+
+            match announcer {
+                Some(peer) => {
+                    state.make_eager(peer);
+                    grafts.push((peer, id));
+                }
+                None => {
+                    debug!("Giving up on gossip message with no remaining announcers");
+                    state.missing.remove(&id);
+                }
+            }
+        }
+
+        // Kept past the window by the allowed skew, so a message stamped
+        // ahead of the local clock is remembered for as long as it is fresh
+        let retention = self.config.message_retention + MAX_CLOCK_SKEW;
+        state.received.retain(|_, received| now.duration_since(received.at) < retention);
+        drop(state);
+
+        for (peer, id) in grafts {
+            self.send(peer, GossipMessage::Graft { ids: vec![id] });
+        }
+    }
+
+    /// Start the graft and retention timer
+    pub fn start(self: &Arc<Self>) {
+        let gossip = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(gossip.config.tick_interval);
+            loop {
+                interval.tick().await;
+                gossip.tick().await;
+            }
+        });
+
+        if let Some(previous) = self.tick_task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// Stop the timer
+    pub fn stop(&self) {
+        if let Some(task) = self.tick_task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Mesh {
+        nodes: Vec<Gossip>,
+        outbound: Vec<mpsc::Receiver<GossipOutbound>>,
+        index: HashMap<NodeId, usize>,
+    }
+
+    impl Mesh {
+        /// Ring with chords; each node only knows a few neighbours
+        async fn new(size: usize, config: GossipConfig) -> Self {
+            let nodes: Vec<Gossip> = (0..size)
+                .map(|_| Gossip::new(KeyPair::generate().unwrap(), config.clone()))
+                .collect();
+            let ids: Vec<NodeId> = nodes.iter().map(|node| node.node_id).collect();
+            let outbound = nodes.iter().map(|n| n.take_outbound().unwrap()).collect();
+
+            for (i, node) in nodes.iter().enumerate() {
+                for offset in [1, size - 1, size / 3, size / 2] {
+                    node.add_peer(ids[(i + offset) % size]).await;
+                }
+            }
+
+            let index = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+            Self { nodes, outbound, index }
+        }
+
+        /// Deliver queued messages until the mesh is quiet
+        async fn settle(&mut self) {
+            loop {
+                let mut batch = Vec::new();
+                for (i, rx) in self.outbound.iter_mut().enumerate() {
+                    while let Ok(out) = rx.try_recv() {
+                        batch.push((self.nodes[i].node_id, out));
+                    }
+                }
+                if batch.is_empty() {
+                    return;
+                }
+                for (from, out) in batch {
+                    self.nodes[self.index[&out.to]].handle(from, out.message).await;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reaches_every_node_once() {
+        let mut mesh = Mesh::new(30, GossipConfig { eager_fanout: 2, ..Default::default() }).await;
+        let mut subscribers: Vec<_> = mesh.nodes.iter().map(|n| n.subscribe()).collect();
+
+        mesh.nodes[0].broadcast("config", b"v2".to_vec()).await.unwrap();
+        mesh.settle().await;
+
+        for rx in subscribers.iter_mut() {
+            assert_eq!(rx.try_recv().unwrap().payload, b"v2");
+            assert!(rx.try_recv().is_err());
+        }
+
+        // The pruned tree still reaches everyone from another origin
+        mesh.nodes[7].broadcast("config", b"v3".to_vec()).await.unwrap();
+        mesh.settle().await;
+        for rx in subscribers.iter_mut() {
+            assert_eq!(rx.try_recv().unwrap().payload, b"v3");
+            assert!(rx.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lazy_announcement_triggers_graft() {
+        let config = GossipConfig {
+            eager_fanout: 0,
+            ihave_timeout: Duration::ZERO,
+            ..Default::default()
+        };
+        let a = Gossip::new(KeyPair::generate().unwrap(), config.clone());
+        let b = Gossip::new(KeyPair::generate().unwrap(), config);
+        let mut a_out = a.take_outbound().unwrap();
+        let mut b_out = b.take_outbound().unwrap();
+        a.add_peer(b.node_id).await;
+        b.add_peer(a.node_id).await;
+
+        let id = a.broadcast("revocation", b"cert-42".to_vec()).await.unwrap();
+        let announce = a_out.try_recv().unwrap();
+        assert!(matches!(announce.message, GossipMessage::IHave { .. }));
+
+        b.handle(a.node_id, announce.message).await;
+        b.tick().await;
+        let graft = b_out.try_recv().unwrap();
+        assert!(matches!(&graft.message, GossipMessage::Graft { ids } if ids == &vec![id]));
+
+        let mut deliveries = b.subscribe();
+        a.handle(b.node_id, graft.message).await;
+        b.handle(a.node_id, a_out.try_recv().unwrap().message).await;
+        assert_eq!(deliveries.try_recv().unwrap().payload, b"cert-42");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_payload() {
+        let gossip = Gossip::new(KeyPair::generate().unwrap(), GossipConfig::default());
+        assert!(gossip.broadcast("bulk", vec![0u8; 8192]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_drops_message_not_signed_by_origin() {
+        let a = Gossip::new(KeyPair::generate().unwrap(), GossipConfig::default());
+        let b = Gossip::new(KeyPair::generate().unwrap(), GossipConfig::default());
+        let mut a_out = a.take_outbound().unwrap();
+        a.add_peer(b.node_id).await;
+        let mut deliveries = b.subscribe();
+
+        a.broadcast("revocation", b"cert-42".to_vec()).await.unwrap();
+        let GossipMessage::Gossip { id, origin, topic, sent_at, round, public_key, signature, .. } =
+            a_out.try_recv().unwrap().message
+        else {
+            panic!("expected a full message on the eager link");
+        };
+
+        // A relay rewrites the payload but keeps the origin's signature
+        let forged = GossipMessage::Gossip {
+            id,
+            origin,
+            topic: topic.clone(),
+            payload: b"cert-43".to_vec(),
+            sent_at,
+            round,
+            public_key,
+            signature: signature.clone(),
+        };
+        b.handle(a.node_id, forged).await;
+        assert!(deliveries.try_recv().is_err());
+
+        let genuine = GossipMessage::Gossip {
+            id,
+            origin,
+            topic,
+            payload: b"cert-42".to_vec(),
+            sent_at,
+            round,
+            public_key,
+            signature,
+        };
+        b.handle(a.node_id, genuine).await;
+        assert_eq!(deliveries.try_recv().unwrap().payload, b"cert-42");
+    }
+
+    /// A message from `origin` as it would sign it, published at `sent_at`
+    fn signed(origin: &Gossip, payload: Vec<u8>, sent_at: SystemTime) -> GossipMessage {
+        let delivery = GossipDelivery {
+            id: hash(&payload),
+            origin: origin.node_id,
+            topic: "revocation".to_string(),
+            payload,
+            sent_at: unix_millis(sent_at),
+        };
+        GossipMessage::Gossip {
+            id: delivery.id,
+            origin: delivery.origin,
+            topic: delivery.topic.clone(),
+            payload: delivery.payload.clone(),
+            sent_at: delivery.sent_at,
+            round: 0,
+            public_key: *origin.key_pair.public_key(),
+            signature: origin.key_pair.sign(&signing_bytes(&delivery)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drops_replayed_and_oversized_messages() {
+        let config = GossipConfig::default();
+        let a = Gossip::new(KeyPair::generate().unwrap(), config.clone());
+        let b = Gossip::new(KeyPair::generate().unwrap(), config.clone());
+        let mut deliveries = b.subscribe();
+
+        // Captured long enough ago that its ID has been forgotten
+        let replayed = signed(&a, b"cert-42".to_vec(), SystemTime::now() - config.message_retention - Duration::from_secs(1));
+        b.handle(a.node_id, replayed).await;
+        assert!(deliveries.try_recv().is_err());
+
+        let oversized = signed(&a, vec![0u8; config.max_payload_bytes + 1], SystemTime::now());
+        b.handle(a.node_id, oversized).await;
+        assert!(deliveries.try_recv().is_err());
+
+        let fresh = signed(&a, b"cert-42".to_vec(), SystemTime::now());
+        b.handle(a.node_id, fresh).await;
+        assert_eq!(deliveries.try_recv().unwrap().payload, b"cert-42");
+    }
+}
@@ -1,9 +1,21 @@
-//! Health checking for service mesh
+//! Active health checking for service mesh
+//!
+//! Each endpoint is checked on its own schedule with the probe configured
+//! for its service, falling back to the mesh-wide defaults. An endpoint
+//! turns healthy after `healthy_threshold` passing checks in a row and
+//! unhealthy after `unhealthy_threshold` failing ones. An endpoint that keeps
+//! changing state is flapping: once it changes `max_transitions` times within
+//! the flapping window it is held unhealthy, and reported healthy again only
+//! after it has held steady for a whole window.
 
-use crate::error::Result;
-use nexus_shared::ServiceId;
+use crate::error::{NetworkError, Result};
+use crate::health_probe::{
+    ExecProbe, GrpcProbe, HealthProbe, HttpProbe, ProbeSpec, ProbeTarget, StoqPingProbe, TcpProbe,
+};
+use crate::link_probe::ProbeTransport;
+use parking_lot::RwLock as SyncRwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -15,6 +27,8 @@ pub enum HealthStatus {
     Healthy,
     Unhealthy,
     Unknown,
+    /// Taking no new requests while in-flight ones finish
+    Draining,
 }
 
 /// Health check configuration
@@ -24,6 +38,14 @@ pub struct HealthCheckConfig {
     pub timeout: Duration,
     pub healthy_threshold: u32,
     pub unhealthy_threshold: u32,
+    /// Probe for services without their own
+    #[serde(default)]
+    pub probe: ProbeSpec,
+    #[serde(default)]
+    pub flapping: FlapConfig,
+    /// Checks by service name, overriding the defaults above
+    #[serde(default)]
+    pub services: HashMap<String, ServiceCheckConfig>,
 }
 
 impl Default for HealthCheckConfig {
@@ -33,22 +55,174 @@ impl Default for HealthCheckConfig {
             timeout: Duration::from_secs(2),
             healthy_threshold: 2,
             unhealthy_threshold: 3,
+            probe: ProbeSpec::default(),
+            flapping: FlapConfig::default(),
+            services: HashMap::new(),
         }
     }
 }
 
+/// Flapping suppression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlapConfig {
+    /// Window state changes are counted over
+    pub window: Duration,
+    /// State changes within the window that mark an endpoint flapping; 0 disables
+    pub max_transitions: u32,
+}
+
+impl Default for FlapConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            max_transitions: 4,
+        }
+    }
+}
+
+/// Health check of one service; unset fields take the defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceCheckConfig {
+    pub probe: ProbeSpec,
+    #[serde(default)]
+    pub interval: Option<Duration>,
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+    #[serde(default)]
+    pub healthy_threshold: Option<u32>,
+    #[serde(default)]
+    pub unhealthy_threshold: Option<u32>,
+}
+
+/// The check a service gets, with defaults filled in
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedCheck {
+    pub probe: ProbeSpec,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub healthy_threshold: u32,
+    pub unhealthy_threshold: u32,
+}
+
+impl HealthCheckConfig {
+    /// Check for the service named `service_name`
+    pub fn check_for(&self, service_name: &str) -> ResolvedCheck {
+        match self.services.get(service_name) {
+            Some(check) => ResolvedCheck {
+                probe: check.probe.clone(),
+                interval: check.interval.unwrap_or(self.interval),
+                timeout: check.timeout.unwrap_or(self.timeout),
+                healthy_threshold: check.healthy_threshold.unwrap_or(self.healthy_threshold),
+                unhealthy_threshold: check.unhealthy_threshold.unwrap_or(self.unhealthy_threshold),
+            },
+            None => ResolvedCheck {
+                probe: self.probe.clone(),
+                interval: self.interval,
+                timeout: self.timeout,
+                healthy_threshold: self.healthy_threshold,
+                unhealthy_threshold: self.unhealthy_threshold,
+            },
+        }
+    }
+
+    /// Shortest interval of any check, which the checking loop ticks at
+    pub fn shortest_interval(&self) -> Duration {
+        self.services
+            .values()
+            .filter_map(|check| check.interval)
+            .fold(self.interval, Duration::min)
+    }
+}
+
 /// Health check result
 #[derive(Debug, Clone)]
 struct HealthCheckResult {
+    /// Status reported, after flapping suppression
     status: HealthStatus,
+    /// Status the thresholds alone give
+    observed: HealthStatus,
     last_check: Instant,
+    next_check: Instant,
     consecutive_successes: u32,
     consecutive_failures: u32,
+    /// When the observed status last changed, within the flapping window
+    transitions: VecDeque<Instant>,
+    last_error: Option<String>,
+}
+
+impl HealthCheckResult {
+    fn new(now: Instant) -> Self {
+        Self {
+            status: HealthStatus::Unknown,
+            observed: HealthStatus::Unknown,
+            last_check: now,
+            next_check: now,
+            consecutive_successes: 0,
+            consecutive_failures: 0,
+            transitions: VecDeque::new(),
+            last_error: None,
+        }
+    }
+
+    fn is_flapping(&self, flapping: &FlapConfig) -> bool {
+        flapping.max_transitions > 0 && self.transitions.len() >= flapping.max_transitions as usize
+    }
+
+    /// Count a check's outcome and update the reported status
+    fn record(&mut self, check: &ResolvedCheck, flapping: &FlapConfig, outcome: Result<()>, now: Instant) {
+        let observed = match outcome {
+            Ok(()) => {
+                self.consecutive_successes += 1;
+                self.consecutive_failures = 0;
+                self.last_error = None;
+                if self.consecutive_successes >= check.healthy_threshold {
+                    HealthStatus::Healthy
+                } else {
+                    self.observed
+                }
+            }
+            Err(e) => {
+                self.consecutive_failures += 1;
+                self.consecutive_successes = 0;
+                self.last_error = Some(e.to_string());
+                if self.consecutive_failures >= check.unhealthy_threshold {
+                    HealthStatus::Unhealthy
+                } else {
+                    self.observed
+                }
+            }
+        };
+
+        // The first verdict after Unknown is not a change of state
+        if observed != self.observed && self.observed != HealthStatus::Unknown {
+            self.transitions.push_back(now);
+        }
+        self.observed = observed;
+        while self.transitions.front().is_some_and(|at| now.duration_since(*at) > flapping.window) {
+            self.transitions.pop_front();
+        }
+
+        self.status = if self.is_flapping(flapping) { HealthStatus::Unhealthy } else { observed };
+        self.last_check = now;
+        self.next_check = now + check.interval;
+    }
+}
+
+/// Where an endpoint stands with health checking
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointCheck {
+    pub status: HealthStatus,
+    pub flapping: bool,
+    pub consecutive_successes: u32,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
 }
 
 /// Health checker for service instances
 pub struct HealthChecker {
     config: HealthCheckConfig,
+    transport: Option<Arc<dyn ProbeTransport>>,
+    custom_probes: SyncRwLock<HashMap<String, Arc<dyn HealthProbe>>>,
     results: Arc<RwLock<HashMap<SocketAddr, HealthCheckResult>>>,
 }
 
@@ -56,50 +230,111 @@ impl HealthChecker {
     pub fn new(config: &HealthCheckConfig) -> Result<Self> {
         Ok(Self {
             config: config.clone(),
+            transport: None,
+            custom_probes: SyncRwLock::new(HashMap::new()),
             results: Arc::new(RwLock::new(HashMap::new())),
         })
     }
-    
-    pub async fn check_health(&self, endpoint: SocketAddr) -> Result<HealthStatus> {
-        // Simplified health check - in production would do actual HTTP/TCP check
-        let mut results = self.results.write().await;
-        let result = results.entry(endpoint).or_insert(HealthCheckResult {
-            status: HealthStatus::Unknown,
-            last_check: Instant::now(),
-            consecutive_successes: 0,
-            consecutive_failures: 0,
-        });
-        
-        // Simulate health check
-        let is_healthy = true; // Would actually check endpoint
-        
-        if is_healthy {
-            result.consecutive_successes += 1;
-            result.consecutive_failures = 0;
-            
-            if result.consecutive_successes >= self.config.healthy_threshold {
-                result.status = HealthStatus::Healthy;
-            }
-        } else {
-            result.consecutive_failures += 1;
-            result.consecutive_successes = 0;
-            
-            if result.consecutive_failures >= self.config.unhealthy_threshold {
-                result.status = HealthStatus::Unhealthy;
+
+    /// Use `transport` for STOQ ping checks
+    pub fn with_transport(mut self, transport: Arc<dyn ProbeTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    pub fn config(&self) -> &HealthCheckConfig {
+        &self.config
+    }
+
+    /// Make a probe available to services configured with `ProbeSpec::Custom { name }`
+    pub fn register_probe(&self, name: impl Into<String>, probe: Arc<dyn HealthProbe>) {
+        self.custom_probes.write().insert(name.into(), probe);
+    }
+
+    pub(crate) fn probe_for(&self, spec: &ProbeSpec) -> Result<Arc<dyn HealthProbe>> {
+        Ok(match spec {
+            ProbeSpec::Http { path, tls, host } => Arc::new(HttpProbe { path: path.clone(), tls: *tls, host: host.clone() }),
+            ProbeSpec::Grpc { service } => Arc::new(GrpcProbe { service: service.clone() }),
+            ProbeSpec::Tcp => Arc::new(TcpProbe),
+            ProbeSpec::Exec { command } => Arc::new(ExecProbe { command: command.clone() }),
+            ProbeSpec::StoqPing => {
+                let transport = self.transport.clone().ok_or_else(|| NetworkError::HealthCheck {
+                    message: "STOQ ping checks need a transport".to_string(),
+                })?;
+                Arc::new(StoqPingProbe { transport })
             }
-        }
-        
-        result.last_check = Instant::now();
+            ProbeSpec::Custom { name } => self.custom_probes.read().get(name).cloned().ok_or_else(|| {
+                NetworkError::HealthCheck { message: format!("no health probe registered as '{}'", name) }
+            })?,
+        })
+    }
+
+    /// Check an endpoint now, returning its status
+    pub async fn check_health(&self, target: &ProbeTarget) -> Result<HealthStatus> {
+        let check = self.config.check_for(target.service_id.name());
+        let probe = self.probe_for(&check.probe)?;
+        let outcome = probe.probe(target, check.timeout).await;
+
+        let mut results = self.results.write().await;
+        let result = results.entry(target.address).or_insert_with(|| HealthCheckResult::new(Instant::now()));
+        result.record(&check, &self.config.flapping, outcome, Instant::now());
         Ok(result.status)
     }
-    
+
+    /// Check the targets whose next check is due, concurrently
+    ///
+    /// Returns the targets whose status changed, with their new status.
+    pub async fn check_due(&self, targets: &[ProbeTarget]) -> Vec<(ProbeTarget, HealthStatus)> {
+        let now = Instant::now();
+        let due: Vec<(&ProbeTarget, HealthStatus)> = {
+            let results = self.results.read().await;
+            targets
+                .iter()
+                .filter_map(|target| match results.get(&target.address) {
+                    Some(result) if result.next_check > now => None,
+                    Some(result) => Some((target, result.status)),
+                    None => Some((target, HealthStatus::Unknown)),
+                })
+                .collect()
+        };
+
+        let checks = due.into_iter().map(|(target, before)| async move {
+            match self.check_health(target).await {
+                Ok(after) if after != before => Some((target.clone(), after)),
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!("Cannot check {} at {}: {}", target.service_id, target.address, e);
+                    None
+                }
+            }
+        });
+        futures::future::join_all(checks).await.into_iter().flatten().collect()
+    }
+
     pub async fn get_status(&self, endpoint: SocketAddr) -> HealthStatus {
         let results = self.results.read().await;
         results.get(&endpoint)
             .map(|r| r.status)
             .unwrap_or(HealthStatus::Unknown)
     }
-    
+
+    /// Health checking state of an endpoint, if it has been checked
+    pub async fn endpoint_check(&self, endpoint: SocketAddr) -> Option<EndpointCheck> {
+        let results = self.results.read().await;
+        results.get(&endpoint).map(|r| EndpointCheck {
+            status: r.status,
+            flapping: r.is_flapping(&self.config.flapping),
+            consecutive_successes: r.consecutive_successes,
+            consecutive_failures: r.consecutive_failures,
+            last_error: r.last_error.clone(),
+        })
+    }
+
+    /// Forget an endpoint that is no longer checked
+    pub async fn remove_endpoint(&self, endpoint: SocketAddr) {
+        self.results.write().await.remove(&endpoint);
+    }
+
     pub async fn get_healthy_endpoints(&self, endpoints: &[SocketAddr]) -> Vec<SocketAddr> {
         let results = self.results.read().await;
         endpoints
@@ -114,12 +349,85 @@ impl HealthChecker {
     }
 
     pub async fn start(&self) -> Result<()> {
-        // Start periodic health checking
+        // Checks are driven by the network manager's health check task
         Ok(())
     }
 
     pub async fn stop(&self) -> Result<()> {
-        // Stop periodic health checking
+        self.results.write().await.clear();
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use nexus_shared::{NodeId, ServiceId};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct Switch(AtomicBool);
+
+    impl HealthProbe for Switch {
+        fn probe<'a>(&'a self, _target: &'a ProbeTarget, _timeout: Duration) -> BoxFuture<'a, Result<()>> {
+            let up = self.0.load(Ordering::SeqCst);
+            Box::pin(async move {
+                if up {
+                    Ok(())
+                } else {
+                    Err(NetworkError::HealthCheck { message: "down".to_string() })
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thresholds_and_flapping_suppression() {
+        let mut config = HealthCheckConfig::default();
+        config.flapping = FlapConfig { window: Duration::from_secs(60), max_transitions: 3 };
+        config.services.insert("api".to_string(), ServiceCheckConfig {
+            probe: ProbeSpec::Custom { name: "switch".to_string() },
+            interval: None,
+            timeout: None,
+            healthy_threshold: Some(1),
+            unhealthy_threshold: Some(2),
+        });
+        assert_eq!(config.check_for("other").probe, ProbeSpec::Tcp);
+
+        let checker = HealthChecker::new(&config).unwrap();
+        let switch = Arc::new(Switch(AtomicBool::new(true)));
+        checker.register_probe("switch", switch.clone());
+        let target = ProbeTarget {
+            service_id: ServiceId::new("api", "default"),
+            node_id: NodeId::random(),
+            address: "127.0.0.1:9000".parse().unwrap(),
+        };
+        let set = |up: bool| switch.0.store(up, Ordering::SeqCst);
+
+        assert_eq!(checker.check_health(&target).await.unwrap(), HealthStatus::Healthy);
+        set(false);
+        // One failure is below the unhealthy threshold
+        assert_eq!(checker.check_health(&target).await.unwrap(), HealthStatus::Healthy);
+        assert_eq!(checker.check_health(&target).await.unwrap(), HealthStatus::Unhealthy);
+
+        // Down, up, down again: the third change marks it flapping, so it stays
+        // unhealthy even once it passes again
+        set(true);
+        assert_eq!(checker.check_health(&target).await.unwrap(), HealthStatus::Healthy);
+        set(false);
+        checker.check_health(&target).await.unwrap();
+        assert_eq!(checker.check_health(&target).await.unwrap(), HealthStatus::Unhealthy);
+        set(true);
+        assert_eq!(checker.check_health(&target).await.unwrap(), HealthStatus::Unhealthy);
+        let check = checker.endpoint_check(target.address).await.unwrap();
+        assert!(check.flapping);
+        assert_eq!(check.consecutive_successes, 1);
+
+        // Unknown probes are configuration errors, not failed checks
+        let mut other = target.clone();
+        other.service_id = ServiceId::new("worker", "default");
+        let no_transport = HealthChecker::new(&HealthCheckConfig { probe: ProbeSpec::StoqPing, ..config }).unwrap();
+        assert!(no_transport.check_health(&other).await.is_err());
+        assert_eq!(no_transport.get_status(other.address).await, HealthStatus::Unknown);
+    }
+}
@@ -0,0 +1,480 @@
+//! Health check probes
+//!
+//! A probe performs one check of one endpoint and reports whether it
+//! passed. The built-in probes cover the usual protocols: an HTTP(S) GET
+//! that passes on any 2xx or 3xx status, the standard gRPC health protocol
+//! (`grpc.health.v1.Health/Check` over cleartext HTTP/2), a bare TCP
+//! connect, a command whose exit status decides, and a STOQ ping to the
+//! endpoint's node over the mesh transport. Other protocols plug in by
+//! implementing `HealthProbe` and registering it with the health checker.
+//!
+//! HTTPS probes do not verify the endpoint's certificate, as Kubernetes
+//! probes do not: a check asks whether the service answers, not whom to trust.
+
+use crate::error::{NetworkError, Result};
+use crate::link_probe::{ProbeTransport, RTT_PROBE};
+use futures::future::BoxFuture;
+use nexus_shared::{NodeId, ServiceId};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// How an endpoint is checked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeSpec {
+    /// GET `path`; any 2xx or 3xx status passes
+    Http {
+        path: String,
+        #[serde(default)]
+        tls: bool,
+        /// Host header and TLS server name; the endpoint address if unset
+        #[serde(default)]
+        host: Option<String>,
+    },
+    /// gRPC health protocol; the empty service asks about the server as a whole
+    Grpc {
+        #[serde(default)]
+        service: String,
+    },
+    /// TCP connect
+    #[default]
+    Tcp,
+    /// Run a command; exit status 0 passes
+    ///
+    /// The endpoint is passed in `NEXUS_HEALTH_ADDRESS` and the service name
+    /// in `NEXUS_HEALTH_SERVICE`.
+    Exec { command: Vec<String> },
+    /// Ping the endpoint's node over the mesh transport
+    StoqPing,
+    /// A probe registered with `HealthChecker::register_probe`
+    Custom { name: String },
+}
+
+/// The endpoint a probe checks
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeTarget {
+    pub service_id: ServiceId,
+    pub node_id: NodeId,
+    pub address: SocketAddr,
+}
+
+/// One kind of health check
+pub trait HealthProbe: Send + Sync {
+    /// Check the target once, failing if it is unhealthy or does not answer in time
+    fn probe<'a>(&'a self, target: &'a ProbeTarget, timeout: Duration) -> BoxFuture<'a, Result<()>>;
+}
+
+fn probe_error(message: impl Into<String>) -> NetworkError {
+    NetworkError::HealthCheck { message: message.into() }
+}
+
+async fn within<T>(timeout: Duration, check: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, check)
+        .await
+        .map_err(|_| probe_error(format!("no answer within {:?}", timeout)))?
+}
+
+/// Passes when a TCP connection is accepted
+pub struct TcpProbe;
+
+impl HealthProbe for TcpProbe {
+    fn probe<'a>(&'a self, target: &'a ProbeTarget, timeout: Duration) -> BoxFuture<'a, Result<()>> {
+        Box::pin(within(timeout, async move {
+            TcpStream::connect(target.address)
+                .await
+                .map(|_| ())
+                .map_err(|e| probe_error(format!("connect to {} failed: {}", target.address, e)))
+        }))
+    }
+}
+
+/// Passes on a 2xx or 3xx response to a GET
+pub struct HttpProbe {
+    pub path: String,
+    pub tls: bool,
+    pub host: Option<String>,
+}
+
+impl HealthProbe for HttpProbe {
+    fn probe<'a>(&'a self, target: &'a ProbeTarget, timeout: Duration) -> BoxFuture<'a, Result<()>> {
+        let address = target.address;
+        let host = self.host.clone().unwrap_or_else(|| address.ip().to_string());
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: nexus-health-check\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            self.path, host
+        );
+        let tls = self.tls;
+        Box::pin(within(timeout, async move {
+            // rustls 0.21 has no async API of its own; a blocking exchange
+            // bounded by socket timeouts keeps both schemes on one path
+            let status = tokio::task::spawn_blocking(move || -> std::io::Result<u16> {
+                let mut stream = std::net::TcpStream::connect_timeout(&address, timeout)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                if tls {
+                    let mut tls = tls_connection(&host, address)?;
+                    http_status(&mut rustls::Stream::new(&mut tls, &mut stream), &request)
+                } else {
+                    http_status(&mut stream, &request)
+                }
+            })
+            .await?
+            .map_err(|e| probe_error(format!("GET {} failed: {}", address, e)))?;
+
+            if (200..400).contains(&status) {
+                Ok(())
+            } else {
+                Err(probe_error(format!("GET {} returned status {}", address, status)))
+            }
+        }))
+    }
+}
+
+/// Send an HTTP/1.1 request and read the status code of the response
+fn http_status(stream: &mut (impl Read + Write), request: &str) -> std::io::Result<u16> {
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?;
+
+    let mut head = Vec::new();
+    let mut buf = [0u8; 512];
+    while !head.windows(2).any(|w| w == b"\r\n") {
+        if head.len() > 8192 {
+            return Err(invalid("status line too long"));
+        }
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Err(invalid("connection closed before the status line"));
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    let line = String::from_utf8_lossy(&head);
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some(version), Some(code)) if version.starts_with("HTTP/") => {
+            code.parse().map_err(|_| invalid("malformed status code"))
+        }
+        _ => Err(invalid("malformed status line")),
+    }
+}
+
+struct AcceptAnyCertificate;
+
+impl rustls::client::ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> std::result::Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+fn tls_connection(host: &str, address: SocketAddr) -> std::io::Result<rustls::ClientConnection> {
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+        .with_no_client_auth();
+    let server_name = rustls::ServerName::try_from(host).unwrap_or(rustls::ServerName::IpAddress(address.ip()));
+    rustls::ClientConnection::new(Arc::new(config), server_name)
+        .map_err(std::io::Error::other)
+}
+
+/// Passes when the gRPC health service reports `SERVING`
+pub struct GrpcProbe {
+    pub service: String,
+}
+
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const H2_MAX_FRAME_SIZE: usize = 16_384;
+const FRAME_DATA: u8 = 0x0;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_RST_STREAM: u8 = 0x3;
+const FRAME_SETTINGS: u8 = 0x4;
+const FRAME_PING: u8 = 0x6;
+const FRAME_GOAWAY: u8 = 0x7;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const GRPC_SERVING: u64 = 1;
+
+impl HealthProbe for GrpcProbe {
+    fn probe<'a>(&'a self, target: &'a ProbeTarget, timeout: Duration) -> BoxFuture<'a, Result<()>> {
+        Box::pin(within(timeout, async move {
+            let mut stream = TcpStream::connect(target.address)
+                .await
+                .map_err(|e| probe_error(format!("connect to {} failed: {}", target.address, e)))?;
+            let status = grpc_health_check(&mut stream, &target.address.to_string(), &self.service).await?;
+            if status == GRPC_SERVING {
+                Ok(())
+            } else {
+                Err(probe_error(format!("gRPC health of {} is {}", target.address, grpc_status_name(status))))
+            }
+        }))
+    }
+}
+
+fn grpc_status_name(status: u64) -> &'static str {
+    match status {
+        0 => "UNKNOWN",
+        1 => "SERVING",
+        2 => "NOT_SERVING",
+        3 => "SERVICE_UNKNOWN",
+        _ => "unrecognized",
+    }
+}
+
+fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(9 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    frame.push(kind);
+    frame.push(flags);
+    frame.extend_from_slice(&stream_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// HPACK integer with a `prefix_bits`-bit prefix
+fn hpack_integer(out: &mut Vec<u8>, first: u8, prefix_bits: u32, mut value: usize) {
+    let max = (1usize << prefix_bits) - 1;
+    if value < max {
+        out.push(first | value as u8);
+        return;
+    }
+    out.push(first | max as u8);
+    value -= max;
+    while value >= 128 {
+        out.push((value % 128) as u8 | 0x80);
+        value /= 128;
+    }
+    out.push(value as u8);
+}
+
+/// Header block of literals without indexing, so no HPACK state is needed
+fn hpack_headers(headers: &[(&str, &str)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    for (name, value) in headers {
+        block.push(0x00);
+        hpack_integer(&mut block, 0x00, 7, name.len());
+        block.extend_from_slice(name.as_bytes());
+        hpack_integer(&mut block, 0x00, 7, value.len());
+        block.extend_from_slice(value.as_bytes());
+    }
+    block
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// `status` of an encoded `HealthCheckResponse`; 0 (`UNKNOWN`) if absent
+fn decode_health_response(message: &[u8]) -> Option<u64> {
+    let mut pos = 0;
+    let mut status = 0;
+    while pos < message.len() {
+        let tag = read_varint(message, &mut pos)?;
+        match (tag >> 3, tag & 0x7) {
+            (1, 0) => status = read_varint(message, &mut pos)?,
+            (_, 0) => {
+                read_varint(message, &mut pos)?;
+            }
+            (_, 2) => {
+                let len = read_varint(message, &mut pos)? as usize;
+                pos = pos.checked_add(len).filter(|end| *end <= message.len())?;
+            }
+            _ => return None,
+        }
+    }
+    Some(status)
+}
+
+/// Call `grpc.health.v1.Health/Check` on stream 1 and return the serving status
+async fn grpc_health_check(stream: &mut TcpStream, authority: &str, service: &str) -> Result<u64> {
+    let io = |e: std::io::Error| probe_error(format!("gRPC health check failed: {}", e));
+
+    let headers = hpack_headers(&[
+        (":method", "POST"),
+        (":scheme", "http"),
+        (":path", "/grpc.health.v1.Health/Check"),
+        (":authority", authority),
+        ("content-type", "application/grpc"),
+        ("te", "trailers"),
+    ]);
+    // HealthCheckRequest { service = 1 }, behind the gRPC length prefix
+    let mut request = Vec::new();
+    if !service.is_empty() {
+        request.push(0x0a);
+        write_varint(&mut request, service.len() as u64);
+        request.extend_from_slice(service.as_bytes());
+    }
+    let mut message = vec![0];
+    message.extend_from_slice(&(request.len() as u32).to_be_bytes());
+    message.extend_from_slice(&request);
+
+    let mut out = H2_PREFACE.to_vec();
+    out.extend(frame(FRAME_SETTINGS, 0, 0, &[]));
+    out.extend(frame(FRAME_HEADERS, FLAG_END_HEADERS, 1, &headers));
+    out.extend(frame(FRAME_DATA, FLAG_END_STREAM, 1, &message));
+    stream.write_all(&out).await.map_err(io)?;
+
+    let mut body = Vec::new();
+    loop {
+        let mut header = [0u8; 9];
+        stream.read_exact(&mut header).await.map_err(io)?;
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let (kind, flags) = (header[3], header[4]);
+        let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+        if len > H2_MAX_FRAME_SIZE {
+            return Err(probe_error(format!("gRPC server sent an oversized frame of {} bytes", len)));
+        }
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await.map_err(io)?;
+
+        match kind {
+            FRAME_SETTINGS if flags & FLAG_ACK == 0 => {
+                stream.write_all(&frame(FRAME_SETTINGS, FLAG_ACK, 0, &[])).await.map_err(io)?;
+            }
+            FRAME_PING if flags & FLAG_ACK == 0 => {
+                stream.write_all(&frame(FRAME_PING, FLAG_ACK, 0, &payload)).await.map_err(io)?;
+            }
+            FRAME_DATA if stream_id == 1 => {
+                let data = if flags & FLAG_PADDED != 0 {
+                    let pad = *payload.first().unwrap_or(&0) as usize;
+                    payload.get(1..payload.len().saturating_sub(pad)).unwrap_or_default()
+                } else {
+                    &payload[..]
+                };
+                body.extend_from_slice(data);
+                if body.len() >= 5 {
+                    let message_len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+                    if body.len() >= 5 + message_len {
+                        return decode_health_response(&body[5..5 + message_len])
+                            .ok_or_else(|| probe_error("malformed gRPC health response"));
+                    }
+                }
+            }
+            // A call that ends in its headers failed before sending a response
+            FRAME_HEADERS if stream_id == 1 && flags & FLAG_END_STREAM != 0 => {
+                return Err(probe_error("gRPC health call failed without a response"));
+            }
+            FRAME_RST_STREAM if stream_id == 1 => return Err(probe_error("gRPC server reset the health call")),
+            FRAME_GOAWAY => return Err(probe_error("gRPC server closed the connection")),
+            _ => {}
+        }
+    }
+}
+
+/// Passes when a command exits with status 0
+pub struct ExecProbe {
+    pub command: Vec<String>,
+}
+
+impl HealthProbe for ExecProbe {
+    fn probe<'a>(&'a self, target: &'a ProbeTarget, timeout: Duration) -> BoxFuture<'a, Result<()>> {
+        Box::pin(within(timeout, async move {
+            let (program, args) = self.command.split_first().ok_or_else(|| probe_error("empty command"))?;
+            // Dropped on timeout, which kills the command
+            let output = tokio::process::Command::new(program)
+                .args(args)
+                .env("NEXUS_HEALTH_ADDRESS", target.address.to_string())
+                .env("NEXUS_HEALTH_SERVICE", target.service_id.name())
+                .stdin(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|e| probe_error(format!("failed to run {}: {}", program, e)))?;
+            if output.status.success() {
+                return Ok(());
+            }
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr: String = stderr.trim().chars().take(200).collect();
+            Err(probe_error(format!("{} exited with {}: {}", program, output.status, stderr)))
+        }))
+    }
+}
+
+/// Passes when the endpoint's node answers a ping over the mesh transport
+pub struct StoqPingProbe {
+    pub transport: Arc<dyn ProbeTransport>,
+}
+
+impl HealthProbe for StoqPingProbe {
+    fn probe<'a>(&'a self, target: &'a ProbeTarget, timeout: Duration) -> BoxFuture<'a, Result<()>> {
+        Box::pin(within(timeout, async move {
+            self.transport
+                .round_trip(target.node_id, RTT_PROBE.to_vec(), timeout)
+                .await
+                .map_err(|e| probe_error(format!("ping to {} failed: {}", target.node_id, e)))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_http_and_grpc_probes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = ProbeTarget {
+            service_id: ServiceId::new("api", "default"),
+            node_id: NodeId::random(),
+            address: listener.local_addr().unwrap(),
+        };
+        let server = tokio::spawn(async move {
+            for response in [&b"HTTP/1.1 204 No Content\r\n\r\n"[..], b"HTTP/1.1 503 Service Unavailable\r\n\r\n"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await.unwrap();
+                socket.write_all(response).await.unwrap();
+            }
+        });
+
+        let http = HttpProbe { path: "/healthz".to_string(), tls: false, host: None };
+        let timeout = Duration::from_secs(2);
+        assert!(http.probe(&target, timeout).await.is_ok());
+        assert!(matches!(http.probe(&target, timeout).await, Err(NetworkError::HealthCheck { .. })));
+        server.await.unwrap();
+        assert!(TcpProbe.probe(&target, Duration::from_millis(200)).await.is_err());
+
+        // HealthCheckResponse { status: SERVING }, then with an unknown field first
+        assert_eq!(decode_health_response(&[0x08, 0x01]), Some(GRPC_SERVING));
+        assert_eq!(decode_health_response(&[0x12, 0x01, b'x', 0x08, 0x02]), Some(2));
+        assert_eq!(decode_health_response(&[]), Some(0));
+        assert_eq!(decode_health_response(&[0x08]), None);
+
+        let mut encoded = Vec::new();
+        hpack_integer(&mut encoded, 0x00, 7, 300);
+        assert_eq!(encoded, vec![0x7f, 0xad, 0x01]);
+    }
+}
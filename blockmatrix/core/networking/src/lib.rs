@@ -2,40 +2,93 @@
 //! 
 //! This module provides:
 //! - Distributed hash table (DHT) for service discovery
+//! - Tenant namespaces whose private services are sealed from other tenants
 //! - Load balancing with health checking
+//! - Pluggable health probes (HTTP(S), gRPC, TCP, exec, STOQ ping) with flapping suppression
 //! - Circuit breaker and retry logic
 //! - Traffic splitting for canary deployments
+//! - Scale-to-zero activation on first request
+//! - Live traffic policies (strategy, retries, timeouts, outlier detection)
+//! - A node-wide retry budget that keeps retries from amplifying outages
+//! - Active RTT, loss and bandwidth probing between nodes
+//! - Passive outlier detection that ejects failing or slow endpoints
+//! - Connection draining when endpoints deregister
+//! - Synthetic probes from edge nodes with per-region SLO evaluation
+//! - Sampled per-request access logs
+//! - Optional end-to-end request signing with workload identity keys
 //! - Real-time metrics and observability
 
 pub mod discovery;
+pub mod discovery_cache;
+pub mod activation;
 pub mod load_balancing;
 pub mod circuit_breaker;
 pub mod health_check;
+pub mod health_probe;
 pub mod routing;
+pub mod retry_budget;
+pub mod link_probe;
+pub mod outlier_detection;
+pub mod drain;
+pub mod synthetic;
+pub mod traffic_policy;
+pub mod access_log;
+pub mod request_signing;
 pub mod dht;
+pub mod dht_security;
+pub mod dht_namespace;
+pub mod gossip;
 pub mod metrics;
 pub mod config;
 pub mod error;
 
 pub use discovery::{ServiceDiscovery, ServiceRegistry, ServiceInstance};
+pub use discovery_cache::{DiscoveryCacheConfig, ServiceResolver};
+pub use activation::{ActivationConfig, ActivationRequest, ActivationStats, Activator};
 pub use load_balancing::{LoadBalancer, LoadBalancingStrategy, BackendPool};
 pub use circuit_breaker::{CircuitBreaker, CircuitState};
-pub use health_check::{HealthChecker, HealthStatus};
-pub use routing::{Router, RoutingRule, TrafficSplit};
-pub use dht::{DistributedHashTable, DhtNode, DhtConfig};
-pub use metrics::{NetworkMetrics, ConnectionMetrics, MetricsSummary};
+pub use health_check::{
+    EndpointCheck, FlapConfig, HealthCheckConfig, HealthChecker, HealthStatus, ResolvedCheck, ServiceCheckConfig,
+};
+pub use health_probe::{HealthProbe, ProbeSpec, ProbeTarget};
+pub use retry_budget::{RetryBudget, RetryBudgetConfig};
+pub use outlier_detection::{EndpointHealth, EndpointState, OutlierDetectionConfig, OutlierDetector};
+pub use drain::{DrainConfig, DrainOutcome, Drainer, InFlight, InFlightGuard};
+pub use link_probe::{LinkProber, LinkQuality, ProbeConfig, ProbeSample, ProbeTransport, LINK_REPORT_TOPIC};
+pub use synthetic::{
+    ProbeLocation, ProbeStep, RegionalIssue, RegionalIssueKind, SloEvaluation, SloReport, SloTarget, SyntheticConfig,
+    SyntheticMetrics, SyntheticMonitor, SyntheticProbe, SyntheticResult, SYNTHETIC_RESULTS_TOPIC,
+};
+pub use routing::{Router, RoutingRule, SplitBackend, SplitMetrics, TrafficSplit, VERSION_LABEL};
+pub use access_log::{AccessLogConfig, AccessLogEntry, AccessLogSink, AccessLogStats, AccessLogger, TraceId, ACCESS_LOG_TARGET};
+pub use request_signing::{RequestSigningConfig, RequestVerifier, VerifiedRequest, WorkloadIdentity};
+pub use traffic_policy::{
+    TrafficPolicy, TrafficPolicyApi, TrafficPolicyStore, TrafficPolicyWatcher,
+    RetryPolicy, RetryOn, BackoffStrategy,
+};
+pub use dht::{DistributedHashTable, DhtNode, DhtConfig, DhtOutbound, DhtTransport, ServiceAnnouncement};
+pub use dht_namespace::{NamespaceConfig, NamespaceKey, NamespacePrivacy, NamespaceRegistry};
+pub use dht_security::{AdmissionPolicy, DhtSecurityConfig, NodeIdentity, PeerReputation, ReputationConfig, SignedRecord, StakeRegistry};
+pub use gossip::{Gossip, GossipConfig, GossipDelivery, GossipMessage, GossipOutbound, MessageId};
+pub use metrics::{NetworkMetrics, ConnectionMetrics, MetricsSummary, RetryStats};
 pub use config::NetworkConfig;
 pub use error::{NetworkError, Result};
 
-use nexus_shared::{NodeId, ServiceId};
-use nexus_transport::{QuicClient, QuicServer};
+use nexus_shared::{DetachedSignature, KeyPair, NodeId, ServiceId, Validate};
+use nexus_transport::{PathEvent, QuicClient, QuicServer, TransportMessage};
 use nexus_state::StateManager;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use tokio::sync::{RwLock, broadcast, mpsc};
+use tokio::sync::{RwLock, broadcast, mpsc, watch};
+
+/// How long `stop` waits for a background task to finish before aborting it
+const TASK_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the mesh task reconciles gossip peers with live connections
+const MESH_PEER_SYNC_INTERVAL: Duration = Duration::from_secs(5);
 
 /// Network manager for service mesh functionality
 pub struct NetworkManager {
@@ -49,6 +102,16 @@ pub struct NetworkManager {
     circuit_breaker: Arc<CircuitBreaker>,
     router: Arc<Router>,
     dht: Arc<DistributedHashTable>,
+    resolver: Arc<ServiceResolver>,
+    activator: Arc<Activator>,
+    gossip: Arc<Gossip>,
+    traffic_policies: Arc<TrafficPolicyStore>,
+    retry_budget: Arc<RetryBudget>,
+    link_prober: Arc<LinkProber>,
+    drainer: Arc<Drainer>,
+    synthetic: Arc<SyntheticMonitor>,
+    access_log: Arc<AccessLogger>,
+    request_verifier: Arc<RequestVerifier>,
     
     // Transport layer
     transport_client: Arc<QuicClient>,
@@ -66,20 +129,59 @@ pub struct NetworkManager {
     
     // Event channels
     service_events: broadcast::Sender<ServiceEvent>,
+    
+    // Background tasks and their shutdown signal
+    background_tasks: Mutex<Vec<tokio::task::JoinHandle<()>>>,
+    shutdown: watch::Sender<bool>,
+    
+    // Applies traffic policies from the cluster state
+    policy_watcher: Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 impl NetworkManager {
-    /// Create a new network manager
+    /// Create a new network manager with a freshly generated node key
     pub async fn new(config: &NetworkConfig) -> Result<Self> {
-        let node_id = NodeId::random();
+        let key_pair = KeyPair::generate().map_err(|e| NetworkError::Dht {
+            message: format!("Failed to generate node key: {}", e),
+        })?;
+        
+        Self::with_key_pair(config, key_pair).await
+    }
+    
+    /// Create a network manager whose node ID is derived from a TrustChain key
+    pub async fn with_key_pair(config: &NetworkConfig, key_pair: KeyPair) -> Result<Self> {
+        let report = config.validate_config();
+        for warning in report.warnings() {
+            tracing::warn!("Network config {}", warning);
+        }
+        if !report.is_valid() {
+            return Err(NetworkError::Configuration { message: report.error_summary() });
+        }
+
+        let dht = Arc::new(DistributedHashTable::new(key_pair.clone(), config.dht.clone()));
+        let node_id = dht.node_id();
         
         // Create core components
         let service_discovery = Arc::new(ServiceDiscovery::new(&config.service_discovery, node_id).await?);
         let load_balancer = Arc::new(LoadBalancer::new(&config.load_balancing)?);
-        let health_checker = Arc::new(HealthChecker::new(&config.health_check)?);
         let circuit_breaker = Arc::new(CircuitBreaker::new(&config.circuit_breaker)?);
         let router = Arc::new(Router::new());
-        let dht = Arc::new(DistributedHashTable::new(node_id, config.dht.clone()));
+        let traffic_policies = Arc::new(TrafficPolicyStore::new(load_balancer.clone()));
+        let gossip = Arc::new(Gossip::new(key_pair, config.gossip.clone()));
+        let remote_services = Arc::new(RwLock::new(HashMap::new()));
+        let resolver = Arc::new(ServiceResolver::new(
+            config.discovery_cache.clone(),
+            service_discovery.clone(),
+            dht.clone(),
+            remote_services.clone(),
+        ));
+        let activator = Arc::new(Activator::new(config.activation.clone()));
+        let retry_budget = Arc::new(RetryBudget::new(config.retry_budget.clone()));
+        let link_prober = Arc::new(LinkProber::new(node_id, config.probing.clone()));
+        let drainer = Arc::new(Drainer::new(config.draining.clone()));
+        let synthetic = Arc::new(SyntheticMonitor::new(node_id, config.synthetic.clone()));
+        let access_log = Arc::new(AccessLogger::new(config.access_log.clone())?);
+        let request_verifier = Arc::new(RequestVerifier::new(config.request_signing.clone())?);
         
         // Create certificate manager
         let cert_manager = Arc::new(
@@ -104,8 +206,14 @@ impl NetworkManager {
             })?
         );
         
+        let health_checker = Arc::new(
+            HealthChecker::new(&config.health_check)?.with_transport(transport_client.clone()),
+        );
+        dht.set_transport(transport_client.clone());
+        
         let metrics = Arc::new(NetworkMetrics::new());
         let (service_events, _) = broadcast::channel(10000);
+        let (shutdown, _) = watch::channel(false);
         
         Ok(Self {
             config: config.clone(),
@@ -116,24 +224,50 @@ impl NetworkManager {
             circuit_breaker,
             router,
             dht,
+            resolver,
+            activator,
+            gossip,
+            traffic_policies,
+            retry_budget,
+            link_prober,
+            drainer,
+            synthetic,
+            access_log,
+            request_verifier,
             transport_client,
             transport_server: None,
             state_manager: None,
             metrics,
             local_services: Arc::new(RwLock::new(HashMap::new())),
-            remote_services: Arc::new(RwLock::new(HashMap::new())),
+            remote_services,
             service_events,
+            background_tasks: Mutex::new(Vec::new()),
+            shutdown,
+            policy_watcher: Mutex::new(None),
         })
     }
     
     /// Start the network manager
-    pub async fn start(&self) -> Result<()> {
+    ///
+    /// Background tasks hold a reference to the manager, so it must be
+    /// shared through an `Arc`.
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
         tracing::info!("Starting network manager for node {}", self.node_id);
         
         // Components are already initialized and ready
         // Start background tasks if needed
         self.dht.start().await?;
         self.health_checker.start().await?;
+        self.resolver.start();
+        self.gossip.start();
+        
+        // Follow traffic policies stored in the cluster state
+        if let Some(state_manager) = &self.state_manager {
+            let watcher = self.watch_traffic_policies(state_manager.clone());
+            if let Some(previous) = self.policy_watcher.lock().unwrap().replace(watcher) {
+                previous.abort();
+            }
+        }
         
         // Start background tasks
         self.start_background_tasks().await?;
@@ -146,7 +280,14 @@ impl NetworkManager {
     pub async fn stop(&self) -> Result<()> {
         tracing::info!("Stopping network manager");
         
+        self.stop_background_tasks().await;
+        if let Some(watcher) = self.policy_watcher.lock().unwrap().take() {
+            watcher.abort();
+        }
+        
         // Stop components that have stop methods
+        self.resolver.stop();
+        self.gossip.stop();
         self.health_checker.stop().await?;
         self.dht.stop().await?;
         
@@ -154,33 +295,129 @@ impl NetworkManager {
         Ok(())
     }
     
+    /// Use the cluster state for traffic policies; must be set before `start`
+    pub fn set_state_manager(&mut self, state_manager: Arc<StateManager>) {
+        self.state_manager = Some(state_manager);
+    }
+    
+    /// Join a tenant namespace, so its services can be announced and found
+    pub fn join_namespace(&self, namespace: NamespaceConfig) -> Result<()> {
+        self.dht.namespaces().join(namespace)
+    }
+    
+    /// Forget a tenant namespace's key
+    pub fn leave_namespace(&self, name: &str) -> bool {
+        self.dht.namespaces().leave(name)
+    }
+    
+    /// Mesh-wide broadcast for small control messages
+    pub fn gossip(&self) -> Arc<Gossip> {
+        self.gossip.clone()
+    }
+    
+    /// Request activity and scale-to-zero activation for services on this node
+    pub fn activator(&self) -> Arc<Activator> {
+        self.activator.clone()
+    }
+    
+    /// Measured link quality between nodes of the cluster
+    pub fn link_prober(&self) -> Arc<LinkProber> {
+        self.link_prober.clone()
+    }
+    
+    /// Health checking, and the probes custom checks use
+    pub fn health_checker(&self) -> Arc<HealthChecker> {
+        self.health_checker.clone()
+    }
+    
+    /// Synthetic probes, their results from every region and SLO evaluation
+    pub fn synthetic(&self) -> Arc<SyntheticMonitor> {
+        self.synthetic.clone()
+    }
+    
+    /// Objectives of every synthetic probe and the regions missing them
+    pub fn slo_report(&self) -> SloReport {
+        self.synthetic.evaluate(SystemTime::now())
+    }
+    
+    /// Active traffic policies on this node
+    pub fn traffic_policies(&self) -> Arc<TrafficPolicyStore> {
+        self.traffic_policies.clone()
+    }
+    
+    /// Apply traffic policy objects from the cluster state as they change
+    pub fn watch_traffic_policies(&self, state_manager: Arc<StateManager>) -> tokio::task::JoinHandle<()> {
+        TrafficPolicyWatcher::new(state_manager, self.traffic_policies.clone()).spawn()
+    }
+    
     /// Register a local service
     pub async fn register_service(&self, service: ServiceInstance) -> Result<()> {
         tracing::info!("Registering service: {}", service.service_id);
         
-        // Store locally
+        // Store locally; registering again ends any drain of the service
         self.local_services.write().await.insert(service.service_id.clone(), service.clone());
+        self.drainer.finish(&service.service_id);
+        self.resolver.invalidate_missing(&service.service_id).await;
         
         // Register with service discovery
         self.service_discovery.register_service(service.clone()).await?;
         
         // Announce to DHT
-        self.dht.announce_service(&service.service_id, service.address).await?;
+        self.dht.announce_service(&service).await?;
         
         // Emit event
         let _ = self.service_events.send(ServiceEvent::ServiceRegistered(service));
         
+        // Release requests held while the service was scaled to zero
+        self.activator.notify_ready();
+        
         Ok(())
     }
     
-    /// Deregister a local service
-    pub async fn deregister_service(&self, service_id: &ServiceId) -> Result<()> {
+    /// Deregister a local service, draining it first
+    ///
+    /// The instance is announced as draining, so routers stop sending it new
+    /// requests and `begin_request` refuses them, and is withdrawn once the
+    /// requests it is serving finish or the drain grace period runs out.
+    pub async fn deregister_service(&self, service_id: &ServiceId) -> Result<DrainOutcome> {
         tracing::info!("Deregistering service: {}", service_id);
         
-        // Remove locally
-        let service = self.local_services.write().await.remove(service_id);
+        let draining = {
+            let mut local_services = self.local_services.write().await;
+            local_services.get_mut(service_id).map(|service| {
+                service.health_status = HealthStatus::Draining;
+                service.clone()
+            })
+        };
+        let Some(draining) = draining else {
+            return Ok(DrainOutcome::Drained);
+        };
+        
+        self.drainer.start(service_id);
+        self.service_discovery
+            .update_service_health(service_id, draining.node_id, HealthStatus::Draining)
+            .await?;
+        self.dht.announce_service(&draining).await?;
+        let _ = self.service_events.send(ServiceEvent::ServiceDraining(draining));
+        
+        let outcome = self.drainer.wait_inbound(service_id).await;
+        if let DrainOutcome::GracePeriodExpired { in_flight } = outcome {
+            tracing::warn!("Drain of {} timed out with {} requests in flight", service_id, in_flight);
+        }
+        
+        // Remove locally, unless the service was registered again meanwhile
+        let service = {
+            let mut local_services = self.local_services.write().await;
+            match local_services.get(service_id) {
+                Some(service) if service.health_status == HealthStatus::Draining => local_services.remove(service_id),
+                _ => None,
+            }
+        };
         
         if let Some(service) = service {
+            self.drainer.finish(service_id);
+            self.health_checker.remove_endpoint(service.address).await;
+            
             // Deregister from service discovery
             self.service_discovery.deregister_service(&service.service_id).await?;
             
@@ -191,43 +428,33 @@ impl NetworkManager {
             let _ = self.service_events.send(ServiceEvent::ServiceDeregistered(service));
         }
         
-        Ok(())
+        Ok(outcome)
+    }
+    
+    /// Count a request this node serves for a local service
+    ///
+    /// Hold the guard until the response is sent; deregistration waits for
+    /// outstanding guards. Fails once the service is draining.
+    pub fn begin_request(&self, service_id: &ServiceId) -> Result<InFlightGuard<ServiceId>> {
+        self.drainer.begin_inbound(service_id)
+    }
+    
+    /// Check the signature of a request this node serves for a local service
+    ///
+    /// Call before serving; returns the evidence of a valid signature, or
+    /// `None` for an unsigned request the service accepts.
+    pub fn verify_request(&self, service_id: &ServiceId, message: &TransportMessage) -> Result<Option<VerifiedRequest>> {
+        self.request_verifier.verify(service_id, message)
+    }
+    
+    /// Workload keys trusted for signed requests to local services
+    pub fn request_verifier(&self) -> Arc<RequestVerifier> {
+        self.request_verifier.clone()
     }
     
     /// Discover services by name
     pub async fn discover_services(&self, service_name: &str) -> Result<Vec<ServiceInstance>> {
-        // Try local cache first
-        if let Some(instances) = self.remote_services.read().await.get(&ServiceId::new(service_name, "default")) {
-            if !instances.is_empty() {
-                return Ok(instances.clone());
-            }
-        }
-        
-        // Query service discovery
-        let instances = self.service_discovery.discover_services(service_name).await?;
-        
-        if !instances.is_empty() {
-            // Cache results
-            let service_id = ServiceId::new(service_name, "default");
-            self.remote_services.write().await.insert(service_id, instances.clone());
-            return Ok(instances);
-        }
-        
-        // Try DHT as fallback
-        let service_id = ServiceId::new(service_name, "default");
-        let addresses = self.dht.find_services(&service_id).await?;
-        
-        // Convert SocketAddr to ServiceInstance
-        let instances = addresses.into_iter().map(|addr| ServiceInstance {
-            service_id: service_id.clone(),
-            node_id: NodeId::random(), // TODO: Get real node_id from DHT
-            address: addr,
-            health_status: HealthStatus::Healthy,
-            metadata: std::collections::HashMap::new(),
-            last_seen: std::time::SystemTime::now(),
-        }).collect();
-        
-        Ok(instances)
+        self.resolver.resolve(service_name).await
     }
     
     /// Route a request to a service
@@ -235,26 +462,136 @@ impl NetworkManager {
         &self,
         service_name: &str,
         request_data: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        self.route_request_traced(service_name, request_data, TraceId::generate()).await
+    }
+    
+    /// Route a request that continues a trace started elsewhere
+    pub async fn route_request_traced(
+        &self,
+        service_name: &str,
+        request_data: Vec<u8>,
+        trace_id: TraceId,
+    ) -> Result<Vec<u8>> {
+        self.route(service_name, request_data, trace_id, None).await
+    }
+    
+    /// Route a request signed with a workload's identity key
+    ///
+    /// The signature covers the payload and the target service, and lets
+    /// the receiving service prove which workload sent the request.
+    pub async fn route_request_signed(
+        &self,
+        service_name: &str,
+        request_data: Vec<u8>,
+        identity: &WorkloadIdentity,
+    ) -> Result<Vec<u8>> {
+        let signature = identity.sign(&ServiceId::new(service_name, "default"), &request_data);
+        self.route(service_name, request_data, TraceId::generate(), Some(signature)).await
+    }
+    
+    async fn route(
+        &self,
+        service_name: &str,
+        request_data: Vec<u8>,
+        trace_id: TraceId,
+        signature: Option<DetachedSignature>,
     ) -> Result<Vec<u8>> {
         // Convert service name to ServiceId
         let service_id = ServiceId::new(service_name, "default");
+        let request_bytes = request_data.len() as u64;
+        let started = std::time::Instant::now();
+        let mut target = RequestTarget::default();
+        let signed_by = signature.as_ref().map(|s| s.signer.clone());
+        
+        let result = self.dispatch_request(service_name, &service_id, request_data, signature.as_ref(), &mut target).await;
+        
+        self.access_log.record(AccessLogEntry {
+            timestamp: chrono::Utc::now(),
+            trace_id: trace_id.to_string(),
+            caller: self.node_id,
+            callee: service_id,
+            endpoint: target.endpoint,
+            endpoint_node: target.endpoint_node,
+            backend: target.backend,
+            latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+            request_bytes,
+            response_bytes: result.as_ref().map_or(0, |response| response.len() as u64),
+            result: match &result {
+                Ok(_) => "ok".to_string(),
+                Err(e) => e.category().to_string(),
+            },
+            error: result.as_ref().err().map(|e| e.to_string()),
+            signed_by,
+        });
+        
+        result
+    }
+    
+    /// Pick an instance for a request and send it, noting where it went in `target`
+    async fn dispatch_request(
+        &self,
+        service_name: &str,
+        service_id: &ServiceId,
+        request_data: Vec<u8>,
+        signature: Option<&DetachedSignature>,
+        target: &mut RequestTarget,
+    ) -> Result<Vec<u8>> {
+        let service_id = service_id.clone();
+        
+        self.activator.record_request(&service_id);
         
         // Discover service instances via DHT
-        let addresses = self.dht.find_services(&service_id).await?;
+        let mut announcements = self.dht.find_services(&service_id).await?;
         
-        if addresses.is_empty() {
+        // Hold the request while a service scaled to zero is activated
+        if announcements.is_empty() && self.activator.is_scaled_to_zero(&service_id) {
+            announcements = self.activator
+                .activate(&service_id, || {
+                    let (dht, service_id) = (self.dht.clone(), service_id.clone());
+                    async move { dht.find_services(&service_id).await }
+                })
+                .await?;
+        }
+        
+        if announcements.is_empty() {
             return Err(NetworkError::ServiceNotFound { service_id });
         }
         
-        // Convert SocketAddr to ServiceInstance
-        let instances: Vec<ServiceInstance> = addresses.into_iter().map(|addr| ServiceInstance {
-            service_id: service_id.clone(),
-            node_id: NodeId::random(), // TODO: Get real node_id from DHT
-            address: addr,
-            health_status: HealthStatus::Healthy,
-            metadata: std::collections::HashMap::new(),
-            last_seen: std::time::SystemTime::now(),
-        }).collect();
+        // Draining instances finish what they have but get no new requests
+        let (draining, announcements): (Vec<_>, Vec<_>) = announcements
+            .into_iter()
+            .partition(|a| a.health == HealthStatus::Draining);
+        for announcement in &draining {
+            if !announcements.iter().any(|a| a.node_id == announcement.node_id) {
+                self.close_when_idle(announcement.node_id);
+            }
+        }
+        if announcements.is_empty() {
+            return Err(NetworkError::NoHealthyInstances { service_name: service_name.to_string() });
+        }
+        
+        let mut instances: Vec<ServiceInstance> = announcements
+            .into_iter()
+            .map(ServiceAnnouncement::into_instance)
+            .collect();
+        let announced: Vec<SocketAddr> = instances.iter().map(|i| i.address).collect();
+        self.load_balancer.retain_endpoints(&service_id, &announced);
+        
+        // Narrow to one subset when the service's traffic is split; the split
+        // is captured here so weight shifts only affect later requests
+        let mut split_backend = None;
+        if let Some(split) = self.router.traffic_split(&service_id).await {
+            let mut labelled = self.resolver.resolve(service_name).await?;
+            labelled.retain(|i| !draining.iter().any(|d| d.address == i.address));
+            let backend = split.select(|b| labelled.iter().any(|i| b.matches(&i.metadata)));
+            if let Some(backend) = backend {
+                instances = labelled.into_iter().filter(|i| backend.matches(&i.metadata)).collect();
+                split_backend = Some(backend.name.clone());
+            } else {
+                tracing::debug!("No instances of {} match its traffic split; using all instances", service_id);
+            }
+        }
         
         // Extract addresses from instances for load balancing
         let addresses: Vec<SocketAddr> = instances.iter().map(|i| i.address).collect();
@@ -270,18 +607,29 @@ impl NetworkManager {
             .find(|i| i.address == selected_address)
             .ok_or_else(|| NetworkError::ServiceNotFound { service_id: service_id.clone() })?;
         
+        target.endpoint = Some(selected_instance.address);
+        target.endpoint_node = Some(selected_instance.node_id);
+        target.backend = split_backend.clone();
+        
         // Check circuit breaker
         if !self.circuit_breaker.can_execute().await {
             return Err(NetworkError::CircuitBreakerOpen);
         }
         
-        // Execute request with retry
+        // Execute request with the service's retry policy and timeout
+        let policy = self.traffic_policies.get(&service_id).await;
+        let started = std::time::Instant::now();
         let result = self.execute_request_with_retry(
             selected_instance,
             request_data,
-            3, // max retries
+            signature,
+            &policy,
         ).await;
         
+        if let Some(backend) = &split_backend {
+            self.router.record_split_result(&service_id, backend, result.is_ok(), started.elapsed());
+        }
+        
         // Update circuit breaker
         match &result {
             Ok(_) => {
@@ -295,47 +643,121 @@ impl NetworkManager {
         result
     }
     
-    /// Execute request with retry logic
+    /// Split a service's traffic across labelled subsets of its instances
+    pub async fn set_traffic_split(&self, service_name: &str, split: TrafficSplit) -> Result<()> {
+        self.router.add_traffic_split(ServiceId::new(service_name, "default"), split).await
+    }
+    
+    /// Change split weights at runtime; requests in flight are unaffected
+    pub async fn shift_traffic(&self, service_name: &str, weights: &HashMap<String, u32>) -> Result<()> {
+        self.router.shift_traffic(&ServiceId::new(service_name, "default"), weights).await
+    }
+    
+    /// Stop splitting a service's traffic
+    pub async fn clear_traffic_split(&self, service_name: &str) {
+        self.router.remove_traffic_split(&ServiceId::new(service_name, "default")).await;
+    }
+    
+    /// Outlier detection view of a service's endpoints
+    pub fn endpoint_health(&self, service_name: &str) -> Vec<EndpointHealth> {
+        self.load_balancer.endpoint_health(&ServiceId::new(service_name, "default"))
+    }
+    
+    /// Access logger for routed requests
+    pub fn access_log(&self) -> Arc<AccessLogger> {
+        self.access_log.clone()
+    }
+    
+    /// Success rate and latency of each subset of a split service
+    pub fn traffic_split_metrics(&self, service_name: &str) -> Vec<SplitMetrics> {
+        self.router.split_metrics(&ServiceId::new(service_name, "default"))
+    }
+    
+    /// Execute request under the service's retry policy and the node's retry budget
     async fn execute_request_with_retry(
         &self,
         instance: &ServiceInstance,
         request_data: Vec<u8>,
-        max_retries: usize,
+        signature: Option<&DetachedSignature>,
+        policy: &TrafficPolicy,
     ) -> Result<Vec<u8>> {
-        let mut attempts = 0;
-        let mut last_error = None;
+        let retry = &policy.retry;
+        let attempt_timeout = retry.attempt_timeout(policy.timeout);
+        let mut retries = 0;
         
-        while attempts <= max_retries {
-            match self.execute_request(instance, &request_data).await {
+        self.retry_budget.record_request();
+        loop {
+            let started = std::time::Instant::now();
+            let outcome = self.execute_request(instance, &request_data, signature, attempt_timeout).await;
+            self.load_balancer.record_result(&instance.service_id, instance.address, outcome.is_ok(), started.elapsed());
+            let error = match outcome {
                 Ok(response) => {
-                    // Update metrics
                     self.metrics.record_request_success();
-                    
                     return Ok(response);
                 }
-                Err(e) => {
-                    attempts += 1;
-                    last_error = Some(e);
-                    
-                    if attempts <= max_retries {
-                        // Exponential backoff
-                        let delay = Duration::from_millis(100 * 2u64.pow(attempts as u32 - 1));
-                        tokio::time::sleep(delay).await;
-                    }
+                Err(e) => e,
+            };
+            
+            if !retry.should_retry(&error) {
+                break Err(self.request_failed(error));
+            }
+            if retries >= retry.max_retries {
+                if retry.max_retries > 0 {
+                    self.metrics.record_retries_exhausted();
                 }
+                break Err(self.request_failed(error));
             }
+            // Spent budget means the mesh is already retrying too much; fail fast
+            if !self.retry_budget.try_acquire() {
+                tracing::debug!("Retry budget exhausted; not retrying request to {}", instance.service_id);
+                self.metrics.record_retry_budget_exhausted();
+                break Err(self.request_failed(error));
+            }
+            
+            retries += 1;
+            self.metrics.record_retry();
+            tokio::time::sleep(retry.backoff(retries)).await;
         }
-        
-        // Update metrics
+    }
+    
+    fn request_failed(&self, error: NetworkError) -> NetworkError {
         self.metrics.record_request_failure();
-        
-        Err(last_error.unwrap_or_else(|| NetworkError::RequestFailed { 
-            message: "All retry attempts failed".to_string() 
-        }))
+        error
+    }
+    
+    /// Close the connection to a node once this node's requests to it finish
+    ///
+    /// Used when the node's instances are draining; a connection still in use
+    /// after the grace period is closed anyway.
+    fn close_when_idle(&self, node_id: NodeId) {
+        if !self.drainer.claim_close(node_id) {
+            return;
+        }
+        let (drainer, transport_client) = (self.drainer.clone(), self.transport_client.clone());
+        tokio::spawn(async move {
+            if transport_client.is_connected(node_id).await {
+                if let DrainOutcome::GracePeriodExpired { in_flight } = drainer.wait_outbound(&node_id).await {
+                    tracing::debug!("Closing connection to draining node {} with {} requests in flight", node_id, in_flight);
+                }
+                if let Err(e) = transport_client.disconnect(node_id).await {
+                    tracing::debug!("Failed to close connection to draining node {}: {}", node_id, e);
+                }
+            }
+            drainer.release_close(&node_id);
+        });
     }
     
     /// Execute a single request to a service instance
-    async fn execute_request(&self, instance: &ServiceInstance, request_data: &[u8]) -> Result<Vec<u8>> {
+    async fn execute_request(
+        &self,
+        instance: &ServiceInstance,
+        request_data: &[u8],
+        signature: Option<&DetachedSignature>,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        // Keeps the connection open while a drain of the instance's node waits
+        let _in_flight = self.drainer.begin_outbound(instance.node_id);
+        
         // Connect to service if not already connected
         if !self.transport_client.is_connected(instance.node_id).await {
             self.transport_client.connect_with_retry(
@@ -351,20 +773,22 @@ impl NetworkManager {
         }
         
         // Create request message
-        let request = nexus_transport::TransportMessage::new(
+        let mut request = nexus_transport::TransportMessage::new(
             nexus_transport::MessageType::Data,
             self.node_id,
             Some(instance.node_id),
             request_data.to_vec(),
         );
+        if let Some(signature) = signature {
+            request = request.with_signature(signature.clone());
+        }
         
         // Send request and wait for response
-        let response = self.transport_client
-            .send_request(
-                instance.node_id,
-                request,
-                Duration::from_secs(30),
-            ).await
+        let response = tokio::time::timeout(
+            timeout,
+            self.transport_client.send_request(instance.node_id, request, timeout),
+        ).await
+            .map_err(|_| NetworkError::Timeout { duration_ms: timeout.as_millis() as u64 })?
             .map_err(|e| NetworkError::RequestFailed { 
                 message: e.to_string() 
             })?;
@@ -373,29 +797,100 @@ impl NetworkManager {
     }
     
     /// Start background tasks
-    async fn start_background_tasks(&self) -> Result<()> {
+    async fn start_background_tasks(self: &Arc<Self>) -> Result<()> {
+        self.shutdown.send_replace(false);
+        
         // Start service cleanup task
-        let manager = self.clone_for_task();
-        tokio::spawn(async move {
+        let manager = Arc::clone(self);
+        let cleanup = tokio::spawn(async move {
             manager.service_cleanup_task().await;
         });
         
         // Start metrics collection task
-        let manager = self.clone_for_task();
-        tokio::spawn(async move {
+        let manager = Arc::clone(self);
+        let metrics = tokio::spawn(async move {
             manager.metrics_collection_task().await;
         });
         
+        // Start health check task
+        let manager = Arc::clone(self);
+        let health = tokio::spawn(async move {
+            manager.health_check_task().await;
+        });
+        
+        // Start path migration task
+        let manager = Arc::clone(self);
+        let migration = tokio::spawn(async move {
+            manager.path_migration_task().await;
+        });
+        
+        // Start mesh message exchange
+        let manager = Arc::clone(self);
+        let mesh = tokio::spawn(async move {
+            manager.mesh_task().await;
+        });
+        
+        let mut started = vec![cleanup, metrics, health, migration, mesh];
+        if self.config.probing.enabled {
+            let manager = Arc::clone(self);
+            started.push(tokio::spawn(async move {
+                manager.link_probe_task().await;
+            }));
+        }
+        
+        if self.config.synthetic.location.is_some() {
+            let manager = Arc::clone(self);
+            started.push(tokio::spawn(async move {
+                manager.synthetic_probe_task().await;
+            }));
+        }
+        
+        let previous: Vec<_> = {
+            let mut tasks = self.background_tasks.lock().unwrap();
+            let previous = tasks.drain(..).collect();
+            tasks.extend(started);
+            previous
+        };
+        for task in previous {
+            task.abort();
+        }
+        
         Ok(())
     }
     
+    /// Signal background tasks to exit and wait for them
+    ///
+    /// Tasks finish their current iteration first; any still running after
+    /// the shutdown timeout are aborted.
+    async fn stop_background_tasks(&self) {
+        self.shutdown.send_replace(true);
+        
+        let tasks: Vec<_> = self.background_tasks.lock().unwrap().drain(..).collect();
+        for mut task in tasks {
+            if tokio::time::timeout(TASK_SHUTDOWN_TIMEOUT, &mut task).await.is_err() {
+                tracing::warn!("Network background task did not stop in time; aborting");
+                task.abort();
+            }
+        }
+    }
+    
+    /// Wait for the next tick, or return false once shutdown is signalled
+    async fn next_tick(interval: &mut tokio::time::Interval, shutdown: &mut watch::Receiver<bool>) -> bool {
+        if *shutdown.borrow() {
+            return false;
+        }
+        tokio::select! {
+            _ = interval.tick() => true,
+            _ = shutdown.changed() => false,
+        }
+    }
+    
     /// Service cleanup task - removes stale service instances
     async fn service_cleanup_task(&self) {
         let mut interval = tokio::time::interval(Duration::from_secs(60));
+        let mut shutdown = self.shutdown.subscribe();
         
-        loop {
-            interval.tick().await;
-            
+        while Self::next_tick(&mut interval, &mut shutdown).await {
             let mut remote_services = self.remote_services.write().await;
             let now = SystemTime::now();
             
@@ -422,10 +917,9 @@ impl NetworkManager {
     /// Metrics collection task
     async fn metrics_collection_task(&self) {
         let mut interval = tokio::time::interval(Duration::from_secs(10));
+        let mut shutdown = self.shutdown.subscribe();
         
-        loop {
-            interval.tick().await;
-            
+        while Self::next_tick(&mut interval, &mut shutdown).await {
             // Collect and update metrics
             let local_count = self.local_services.read().await.len();
             let remote_count: usize = self.remote_services.read().await
@@ -437,6 +931,363 @@ impl NetworkManager {
         }
     }
     
+    /// Health check task - probes local services as their checks come due
+    async fn health_check_task(&self) {
+        let mut interval = tokio::time::interval(self.config.health_check.shortest_interval());
+        let mut shutdown = self.shutdown.subscribe();
+        
+        while Self::next_tick(&mut interval, &mut shutdown).await {
+            // Draining services are on their way out whatever their checks say
+            let targets: Vec<ProbeTarget> = self
+                .local_services
+                .read()
+                .await
+                .values()
+                .filter(|service| service.health_status != HealthStatus::Draining)
+                .map(|service| ProbeTarget {
+                    service_id: service.service_id.clone(),
+                    node_id: service.node_id,
+                    address: service.address,
+                })
+                .collect();
+            
+            for (target, status) in self.health_checker.check_due(&targets).await {
+                if let Err(e) = self.update_local_health(&target.service_id, status).await {
+                    tracing::warn!("Failed to publish health of {}: {}", target.service_id, e);
+                }
+            }
+        }
+    }
+    
+    /// Record a local service's new health and announce it
+    async fn update_local_health(&self, service_id: &ServiceId, status: HealthStatus) -> Result<()> {
+        let updated = {
+            let mut local_services = self.local_services.write().await;
+            match local_services.get_mut(service_id) {
+                Some(service) if service.health_status != HealthStatus::Draining => {
+                    service.health_status = status;
+                    Some(service.clone())
+                }
+                _ => None,
+            }
+        };
+        let Some(service) = updated else {
+            return Ok(());
+        };
+        
+        tracing::info!("Service {} is now {:?}", service_id, status);
+        self.service_discovery.update_service_health(service_id, service.node_id, status).await?;
+        self.dht.announce_service(&service).await?;
+        let _ = self.service_events.send(ServiceEvent::ServiceHealthChanged(service_id.clone(), status));
+        Ok(())
+    }
+    
+    /// Path migration task - follows address changes of live connections into registrations
+    async fn path_migration_task(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+        let mut events = self.transport_client.path_events();
+        
+        while !*shutdown.borrow() {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if let Err(e) = self.apply_path_event(event).await {
+                            tracing::warn!("Failed to update registrations after migration: {}", e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Missed {} path events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = shutdown.changed() => {}
+            }
+        }
+    }
+    
+    /// Move the services registered at a migrated address to the new one
+    ///
+    /// Only the IP follows the connection; each service keeps its own port.
+    async fn apply_path_event(&self, event: PathEvent) -> Result<()> {
+        match event {
+            PathEvent::PeerAddressChanged { node_id, old, new } => {
+                let moved: Vec<_> = {
+                    let mut remote_services = self.remote_services.write().await;
+                    remote_services
+                        .values_mut()
+                        .flatten()
+                        .filter(|instance| instance.node_id == node_id && instance.address.ip() == old.ip())
+                        .map(|instance| {
+                            let previous = instance.address;
+                            instance.address = SocketAddr::new(new.ip(), previous.port());
+                            (instance.clone(), previous)
+                        })
+                        .collect()
+                };
+                for (instance, previous) in moved {
+                    tracing::info!("Service {} on node {} moved to {}", instance.service_id, node_id, instance.address);
+                    let _ = self.service_events.send(ServiceEvent::ServiceAddressChanged { instance, previous });
+                }
+            }
+            PathEvent::LocalAddressChanged { old, new } => {
+                // A wildcard bind says nothing about which address services are reached at
+                if old.ip().is_unspecified() || new.ip().is_unspecified() || old.ip() == new.ip() {
+                    return Ok(());
+                }
+                let moved: Vec<_> = {
+                    let mut local_services = self.local_services.write().await;
+                    local_services
+                        .values_mut()
+                        .filter(|service| service.address.ip() == old.ip())
+                        .map(|service| {
+                            let previous = service.address;
+                            service.address = SocketAddr::new(new.ip(), previous.port());
+                            (service.clone(), previous)
+                        })
+                        .collect()
+                };
+                for (service, previous) in moved {
+                    tracing::info!("Re-announcing {} at {}", service.service_id, service.address);
+                    self.service_discovery.deregister_service(&service.service_id).await?;
+                    self.service_discovery.register_service(service.clone()).await?;
+                    self.dht.announce_service(&service).await?;
+                    let _ = self.service_events.send(ServiceEvent::ServiceAddressChanged { instance: service, previous });
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Mesh task - carries gossip and DHT replication between nodes
+    ///
+    /// Gossip's and the DHT's outbound queues are sent over the transport,
+    /// control messages received from peers are handed to gossip or the DHT,
+    /// and gossip's neighbours follow the transport's live connections.
+    async fn mesh_task(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+        let mut interval = tokio::time::interval(MESH_PEER_SYNC_INTERVAL);
+        let (Some(mut incoming), Some(mut gossip_outbound), Some(mut dht_outbound)) = (
+            self.transport_client.take_message_receiver().await,
+            self.gossip.take_outbound(),
+            self.dht.take_outbound(),
+        ) else {
+            tracing::warn!("Mesh message queues are already taken; gossip and DHT replication are not carried over the transport");
+            return;
+        };
+        let mut peers = std::collections::HashSet::new();
+        
+        while !*shutdown.borrow() {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let connected: std::collections::HashSet<NodeId> =
+                        self.transport_client.connected_peers().await.into_iter().collect();
+                    for peer in connected.difference(&peers) {
+                        self.gossip.add_peer(*peer).await;
+                    }
+                    for peer in peers.difference(&connected) {
+                        self.gossip.remove_peer(peer).await;
+                    }
+                    peers = connected;
+                }
+                outbound = gossip_outbound.recv() => match outbound {
+                    Some(outbound) => self.send_mesh_message(outbound.to, MeshMessage::Gossip(outbound.message)).await,
+                    None => break,
+                },
+                outbound = dht_outbound.recv() => match outbound {
+                    Some(outbound) => {
+                        // Replicas are routing table entries, not necessarily connected peers
+                        let to = outbound.to().clone();
+                        if !self.transport_client.is_connected(to.node_id).await {
+                            if let Err(e) = self.transport_client.connect(to.address, &format!("nexus-{}", to.node_id)).await {
+                                tracing::debug!("Failed to reach DHT replica {} at {}: {}", to.node_id, to.address, e);
+                                continue;
+                            }
+                        }
+                        let message = match outbound {
+                            DhtOutbound::Store { record, .. } => MeshMessage::DhtStore(record),
+                            DhtOutbound::Remove { tombstone, .. } => MeshMessage::DhtRemove(tombstone),
+                        };
+                        self.send_mesh_message(to.node_id, message).await;
+                    }
+                    None => break,
+                },
+                message = incoming.recv() => match message {
+                    Some((from, message)) => self.handle_mesh_message(from, message).await,
+                    None => break,
+                },
+                _ = shutdown.changed() => {}
+            }
+        }
+    }
+    
+    /// Send a control message to a connected peer
+    async fn send_mesh_message(&self, to: NodeId, message: MeshMessage) {
+        let Some(message) = self.mesh_transport_message(to, &message) else {
+            return;
+        };
+        if let Err(e) = self.transport_client.send_message(to, message).await {
+            tracing::debug!("Failed to send mesh message to {}: {}", to, e);
+        }
+    }
+    
+    /// Wrap a control message for the transport
+    fn mesh_transport_message(&self, to: NodeId, message: &MeshMessage) -> Option<TransportMessage> {
+        match serde_json::to_vec(message) {
+            Ok(payload) => Some(TransportMessage::new(nexus_transport::MessageType::Control, self.node_id, Some(to), payload)),
+            Err(e) => {
+                tracing::warn!("Failed to encode mesh message for {}: {}", to, e);
+                None
+            }
+        }
+    }
+    
+    /// Dispatch a control message received from a peer
+    async fn handle_mesh_message(&self, from: NodeId, message: TransportMessage) {
+        if message.message_type != nexus_transport::MessageType::Control {
+            return;
+        }
+        // Link probes are control messages too, and do not decode
+        let sequence = message.sequence;
+        let Ok(message) = serde_json::from_slice::<MeshMessage>(&message.payload) else {
+            return;
+        };
+        match message {
+            MeshMessage::Gossip(message) => {
+                self.gossip.add_peer(from).await;
+                self.gossip.handle(from, message).await;
+            }
+            MeshMessage::DhtStore(record) => {
+                if let Err(e) = self.dht.store_record_from(&from, record).await {
+                    tracing::debug!("Rejected DHT record from {}: {}", from, e);
+                }
+            }
+            MeshMessage::DhtRemove(tombstone) => {
+                if let Err(e) = self.dht.remove_record_from(&from, tombstone).await {
+                    tracing::debug!("Rejected DHT removal from {}: {}", from, e);
+                }
+            }
+            MeshMessage::FindNode { target } => {
+                let nodes = self.dht.find_node(&target).await.unwrap_or_default();
+                let Some(mut reply) = self.mesh_transport_message(from, &MeshMessage::Nodes(nodes)) else {
+                    return;
+                };
+                reply.sequence = sequence;
+                if let Err(e) = self.transport_client.send_message(from, reply).await {
+                    tracing::debug!("Failed to answer lookup from {}: {}", from, e);
+                }
+            }
+            MeshMessage::FindValue { key } => {
+                let nodes = self.dht.find_node(&dht::placement_id(&key)).await.unwrap_or_default();
+                let records = self.dht.records_under(&key).await;
+                let Some(mut reply) = self.mesh_transport_message(from, &MeshMessage::Values { nodes, records }) else {
+                    return;
+                };
+                reply.sequence = sequence;
+                if let Err(e) = self.transport_client.send_message(from, reply).await {
+                    tracing::debug!("Failed to answer value lookup from {}: {}", from, e);
+                }
+            }
+            // Answers reach the lookup waiting for them; a stray one is dropped
+            MeshMessage::Nodes(_) | MeshMessage::Values { .. } => {}
+        }
+    }
+    
+    /// Link probing task - measures links to connected peers and shares the results
+    async fn link_probe_task(&self) {
+        let mut interval = tokio::time::interval(self.config.probing.interval);
+        let mut shutdown = self.shutdown.subscribe();
+        let mut reports = self.gossip.subscribe();
+        
+        loop {
+            tokio::select! {
+                tick = Self::next_tick(&mut interval, &mut shutdown) => {
+                    if !tick {
+                        break;
+                    }
+                    let peers = self.transport_client.connected_peers().await;
+                    self.link_prober.probe_all(self.transport_client.as_ref(), &peers).await;
+                    self.link_prober.expire_stale();
+                    
+                    match serde_json::to_vec(&self.link_prober.local_report()) {
+                        Ok(report) => {
+                            if let Err(e) = self.gossip.broadcast(LINK_REPORT_TOPIC, report).await {
+                                tracing::debug!("Failed to share link report: {}", e);
+                            }
+                        }
+                        Err(e) => tracing::warn!("Failed to encode link report: {}", e),
+                    }
+                }
+                delivery = reports.recv() => match delivery {
+                    Ok(delivery) if delivery.topic == LINK_REPORT_TOPIC => {
+                        match serde_json::from_slice(&delivery.payload) {
+                            Ok(report) => self.link_prober.ingest_report(delivery.origin, report),
+                            Err(e) => tracing::debug!("Ignoring malformed link report from {}: {}", delivery.origin, e),
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+    
+    /// Synthetic probe task - runs due probes from this node and shares the results
+    async fn synthetic_probe_task(&self) {
+        let mut interval = tokio::time::interval(synthetic::SCHEDULER_TICK);
+        let mut shutdown = self.shutdown.subscribe();
+        let mut results = self.gossip.subscribe();
+        
+        loop {
+            tokio::select! {
+                tick = Self::next_tick(&mut interval, &mut shutdown) => {
+                    if !tick {
+                        break;
+                    }
+                    let runs = self.synthetic.due(std::time::Instant::now()).into_iter().map(|probe| async move {
+                        let targets: Vec<ProbeTarget> = match self.resolver.resolve(&probe.service).await {
+                            Ok(instances) => instances
+                                .into_iter()
+                                .filter(|instance| instance.health_status != HealthStatus::Draining)
+                                .map(|instance| ProbeTarget {
+                                    service_id: instance.service_id,
+                                    node_id: instance.node_id,
+                                    address: instance.address,
+                                })
+                                .collect(),
+                            Err(e) => {
+                                tracing::debug!("Synthetic probe {} could not resolve {}: {}", probe.name, probe.service, e);
+                                Vec::new()
+                            }
+                        };
+                        self.synthetic.run(&probe, &self.health_checker, &targets).await
+                    });
+                    
+                    for result in futures::future::join_all(runs).await {
+                        match serde_json::to_vec(&result) {
+                            Ok(payload) => {
+                                if let Err(e) = self.gossip.broadcast(SYNTHETIC_RESULTS_TOPIC, payload).await {
+                                    tracing::debug!("Failed to share synthetic result: {}", e);
+                                }
+                            }
+                            Err(e) => tracing::warn!("Failed to encode synthetic result: {}", e),
+                        }
+                        self.synthetic.record(result);
+                    }
+                }
+                delivery = results.recv() => match delivery {
+                    Ok(delivery) if delivery.topic == SYNTHETIC_RESULTS_TOPIC && delivery.origin != self.node_id => {
+                        match serde_json::from_slice(&delivery.payload) {
+                            Ok(result) => self.synthetic.record(result),
+                            Err(e) => tracing::debug!("Ignoring malformed synthetic result from {}: {}", delivery.origin, e),
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+    
     /// Get network statistics
     pub async fn stats(&self) -> NetworkStats {
         let local_services = self.local_services.read().await;
@@ -448,6 +1299,8 @@ impl NetworkManager {
             remote_service_count: remote_services.values().map(|v| v.len()).sum(),
             total_connections: self.transport_client.connection_count().await,
             metrics: self.metrics.summary(),
+            activation: self.activator.stats(),
+            access_log: self.access_log.stats(),
         }
     }
     
@@ -455,12 +1308,14 @@ impl NetworkManager {
     pub fn subscribe_to_events(&self) -> broadcast::Receiver<ServiceEvent> {
         self.service_events.subscribe()
     }
-    
-    /// Clone for task spawning (simplified approach)
-    fn clone_for_task(&self) -> Arc<Self> {
-        // In a real implementation, this would require proper Arc wrapping
-        unreachable!("This would require restructuring to use Arc<NetworkManager>")
-    }
+}
+
+/// Where a routed request was sent, for its access log entry
+#[derive(Debug, Default)]
+struct RequestTarget {
+    endpoint: Option<SocketAddr>,
+    endpoint_node: Option<NodeId>,
+    backend: Option<String>,
 }
 
 /// Service event types
@@ -468,8 +1323,29 @@ impl NetworkManager {
 pub enum ServiceEvent {
     ServiceRegistered(ServiceInstance),
     ServiceDeregistered(ServiceInstance),
+    ServiceDraining(ServiceInstance),
     ServiceHealthChanged(ServiceId, HealthStatus),
     ServiceDiscovered(Vec<ServiceInstance>),
+    /// An instance's node migrated to a new address under live connections
+    ServiceAddressChanged { instance: ServiceInstance, previous: SocketAddr },
+}
+
+/// Control messages network managers exchange over the transport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MeshMessage {
+    Gossip(GossipMessage),
+    /// A DHT record the receiver is now a replica for
+    DhtStore(SignedRecord),
+    /// A publisher's tombstone for a DHT record the receiver should drop
+    DhtRemove(SignedRecord),
+    /// Request for the receiver's closest known nodes to `target`
+    FindNode { target: NodeId },
+    /// Answer to `FindNode`
+    Nodes(Vec<DhtNode>),
+    /// Request for the records the receiver holds under `key`
+    FindValue { key: Vec<u8> },
+    /// Answer to `FindValue`, with the receiver's closest known nodes to the records
+    Values { nodes: Vec<DhtNode>, records: Vec<SignedRecord> },
 }
 
 /// Network statistics
@@ -480,6 +1356,8 @@ pub struct NetworkStats {
     pub remote_service_count: usize,
     pub total_connections: usize,
     pub metrics: metrics::MetricsSummary,
+    pub activation: ActivationStats,
+    pub access_log: AccessLogStats,
 }
 
 #[cfg(test)]
@@ -493,6 +1371,78 @@ mod tests {
         assert!(manager.is_ok());
     }
     
+    #[tokio::test]
+    async fn test_dht_record_reaches_new_owner() {
+        let config = NetworkConfig::default();
+        let owner = NetworkManager::new(&config).await.unwrap();
+        let joiner = NetworkManager::new(&config).await.unwrap();
+        let mut outbound = owner.dht.take_outbound().unwrap();
+        
+        owner.dht.put(b"config".to_vec(), b"v2".to_vec()).await.unwrap();
+        
+        // The joining node is now a replica and is sent the record
+        let identity = joiner.dht.identity().clone();
+        owner.dht.add_node(DhtNode {
+            node_id: identity.node_id,
+            address: "127.0.0.1:9000".parse().unwrap(),
+            last_seen: SystemTime::now(),
+            public_key: identity.public_key,
+            nonce: identity.nonce,
+        }).await.unwrap();
+        let Ok(DhtOutbound::Store { to, record }) = outbound.try_recv() else {
+            panic!("record not handed over");
+        };
+        assert_eq!(to.node_id, joiner.node_id);
+        
+        // Delivered as the mesh task sends it
+        let message = owner.mesh_transport_message(to.node_id, &MeshMessage::DhtStore(record)).unwrap();
+        joiner.handle_mesh_message(owner.node_id, message).await;
+        assert_eq!(joiner.dht.get(b"config").await.unwrap(), Some(b"v2".to_vec()));
+    }
+    
+    #[tokio::test]
+    async fn test_withdrawn_service_removed_from_replicas() {
+        let config = NetworkConfig::default();
+        let owner = NetworkManager::new(&config).await.unwrap();
+        let replica = NetworkManager::new(&config).await.unwrap();
+        let mut outbound = owner.dht.take_outbound().unwrap();
+        
+        let identity = replica.dht.identity().clone();
+        owner.dht.add_node(DhtNode {
+            node_id: identity.node_id,
+            address: "127.0.0.1:9000".parse().unwrap(),
+            last_seen: SystemTime::now(),
+            public_key: identity.public_key,
+            nonce: identity.nonce,
+        }).await.unwrap();
+        
+        let service_id = ServiceId::new("api", "default");
+        owner.dht.announce_service(&ServiceInstance {
+            service_id: service_id.clone(),
+            node_id: owner.node_id,
+            address: "10.0.0.1:80".parse().unwrap(),
+            health_status: HealthStatus::Healthy,
+            metadata: HashMap::new(),
+            last_seen: SystemTime::now(),
+        }).await.unwrap();
+        let Ok(DhtOutbound::Store { record, .. }) = outbound.try_recv() else {
+            panic!("announcement not replicated");
+        };
+        let message = owner.mesh_transport_message(replica.node_id, &MeshMessage::DhtStore(record)).unwrap();
+        replica.handle_mesh_message(owner.node_id, message).await;
+        assert_eq!(replica.dht.find_services(&service_id).await.unwrap().len(), 1);
+        
+        // The withdrawal reaches the replica as a signed tombstone
+        owner.dht.remove_service(&service_id).await.unwrap();
+        let Ok(DhtOutbound::Remove { to, tombstone }) = outbound.try_recv() else {
+            panic!("withdrawal not sent to the replica");
+        };
+        assert_eq!(to.node_id, replica.node_id);
+        let message = owner.mesh_transport_message(replica.node_id, &MeshMessage::DhtRemove(tombstone)).unwrap();
+        replica.handle_mesh_message(owner.node_id, message).await;
+        assert!(replica.dht.find_services(&service_id).await.unwrap().is_empty());
+    }
+    
     #[tokio::test]
     async fn test_service_registration() {
         let config = NetworkConfig::default();
@@ -517,4 +1467,51 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_traffic_policies_follow_cluster_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state_config = nexus_state::StateConfig::default();
+        state_config.storage.data_dir = temp_dir.path().to_string_lossy().to_string();
+        let state_manager = Arc::new(StateManager::new(state_config, NodeId::random()).await.unwrap());
+        state_manager.start().await.unwrap();
+        
+        let mut manager = NetworkManager::new(&NetworkConfig::default()).await.unwrap();
+        manager.set_state_manager(state_manager.clone());
+        let manager = Arc::new(manager);
+        manager.start().await.unwrap();
+        
+        let service_id = ServiceId::new("api", "default");
+        let mut policy = TrafficPolicy::new(service_id.clone());
+        policy.retry.max_retries = 1;
+        TrafficPolicyApi::new(state_manager.clone()).put(&policy).await.unwrap();
+        
+        let store = manager.traffic_policies();
+        for _ in 0..50 {
+            if store.get(&service_id).await == policy {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(store.get(&service_id).await, policy);
+        
+        manager.stop().await.unwrap();
+        state_manager.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_background_tasks_shutdown() {
+        let config = NetworkConfig::default();
+        let manager = Arc::new(NetworkManager::new(&config).await.unwrap());
+        
+        // Cleanup, metrics, health, path migration, mesh and link probing
+        manager.start_background_tasks().await.unwrap();
+        assert_eq!(manager.background_tasks.lock().unwrap().len(), 6);
+        assert_eq!(Arc::strong_count(&manager), 7);
+        
+        // Tasks exit on the shutdown signal and release the manager
+        manager.stop_background_tasks().await;
+        assert!(manager.background_tasks.lock().unwrap().is_empty());
+        assert_eq!(Arc::strong_count(&manager), 1);
+    }
+}
@@ -0,0 +1,386 @@
+//! Active link quality probing
+//!
+//! Every node periodically probes the peers it is connected to: a series of
+//! small round trips samples RTT, jitter and loss, then a few paced trains
+//! of larger packets estimate the available bandwidth. Results are smoothed
+//! per link and shared with the cluster over gossip, so any node holds a
+//! measured view of the links between all pairs of nodes. Routing and
+//! placement read link quality from here instead of assuming it.
+
+use crate::error::{NetworkError, Result};
+use futures::future::BoxFuture;
+use nexus_shared::NodeId;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast;
+
+/// Gossip topic link reports are shared on
+pub const LINK_REPORT_TOPIC: &str = "mesh.link-quality";
+
+/// Payload of RTT samples; peers answer it like a ping
+pub(crate) const RTT_PROBE: &[u8] = b"ping";
+
+/// Link probing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeConfig {
+    pub enabled: bool,
+
+    /// Time between probe rounds
+    pub interval: Duration,
+
+    /// Round trips sampled per probe for RTT and loss
+    pub rtt_samples: u32,
+
+    /// Gap between RTT samples
+    pub sample_spacing: Duration,
+
+    /// Packets per bandwidth train
+    pub train_packets: u32,
+
+    /// Size of each bandwidth packet
+    pub packet_bytes: usize,
+
+    /// Trains sent per probe, paced apart
+    pub trains: u32,
+
+    /// Gap between trains
+    pub train_spacing: Duration,
+
+    /// Time a single round trip may take before it counts as lost
+    pub timeout: Duration,
+
+    /// Weight of a new probe in the smoothed link figures (0-1]
+    pub smoothing: f64,
+
+    /// Links not measured for this long are ignored
+    pub max_age: Duration,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(30),
+            rtt_samples: 10,
+            sample_spacing: Duration::from_millis(20),
+            train_packets: 16,
+            packet_bytes: 8 * 1024,
+            trains: 4,
+            train_spacing: Duration::from_millis(10),
+            timeout: Duration::from_secs(2),
+            smoothing: 0.3,
+            max_age: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Measured quality of the link from one node to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkQuality {
+    pub from: NodeId,
+    pub to: NodeId,
+    /// Smoothed round-trip time
+    pub rtt: Duration,
+    /// Smoothed variation between consecutive round trips
+    pub jitter: Duration,
+    /// Smoothed share of round trips lost (0-1)
+    pub loss_rate: f64,
+    /// Smoothed bandwidth estimate; `None` until a train got through
+    pub bandwidth_mbps: Option<f64>,
+    /// Probes folded into the figures
+    pub probes: u64,
+    pub measured_at: SystemTime,
+}
+
+/// Raw result of one probe
+#[derive(Debug, Clone, Default)]
+pub struct ProbeSample {
+    pub rtts: Vec<Duration>,
+    pub lost: u32,
+    pub bandwidth_mbps: Option<f64>,
+}
+
+impl ProbeSample {
+    fn loss_rate(&self) -> f64 {
+        let sent = self.rtts.len() as u32 + self.lost;
+        if sent == 0 {
+            return 0.0;
+        }
+        self.lost as f64 / sent as f64
+    }
+
+    fn median_rtt(&self) -> Option<Duration> {
+        let mut rtts = self.rtts.clone();
+        rtts.sort();
+        rtts.get(rtts.len() / 2).copied()
+    }
+
+    fn jitter(&self) -> Duration {
+        if self.rtts.len() < 2 {
+            return Duration::ZERO;
+        }
+        let total: Duration = self.rtts.windows(2).map(|w| w[0].abs_diff(w[1])).sum();
+        total / (self.rtts.len() as u32 - 1)
+    }
+}
+
+impl LinkQuality {
+    /// Fold a probe into the link, or start it from the first probe
+    pub fn update(previous: Option<&LinkQuality>, from: NodeId, to: NodeId, sample: &ProbeSample, smoothing: f64) -> Self {
+        let blend = |old: f64, new: f64| old + (new - old) * smoothing;
+        let rtt = sample.median_rtt();
+        match previous {
+            None => Self {
+                from,
+                to,
+                rtt: rtt.unwrap_or_default(),
+                jitter: sample.jitter(),
+                loss_rate: sample.loss_rate(),
+                bandwidth_mbps: sample.bandwidth_mbps,
+                probes: 1,
+                measured_at: SystemTime::now(),
+            },
+            Some(previous) => Self {
+                from,
+                to,
+                // A probe that lost everything says nothing about RTT
+                rtt: rtt.map_or(previous.rtt, |rtt| {
+                    Duration::from_secs_f64(blend(previous.rtt.as_secs_f64(), rtt.as_secs_f64()))
+                }),
+                jitter: Duration::from_secs_f64(blend(previous.jitter.as_secs_f64(), sample.jitter().as_secs_f64())),
+                loss_rate: blend(previous.loss_rate, sample.loss_rate()),
+                bandwidth_mbps: match (previous.bandwidth_mbps, sample.bandwidth_mbps) {
+                    (Some(old), Some(new)) => Some(blend(old, new)),
+                    (old, new) => new.or(old),
+                },
+                probes: previous.probes + 1,
+                measured_at: SystemTime::now(),
+            },
+        }
+    }
+
+    /// Whether the link was measured within `max_age`
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.measured_at.elapsed().unwrap_or_default() <= max_age
+    }
+}
+
+/// Round trips to peers, as the prober needs them
+pub trait ProbeTransport: Send + Sync {
+    /// Send a payload to a peer and wait for its reply
+    fn round_trip(&self, peer: NodeId, payload: Vec<u8>, timeout: Duration) -> BoxFuture<'_, Result<()>>;
+}
+
+impl ProbeTransport for nexus_transport::QuicClient {
+    fn round_trip(&self, peer: NodeId, payload: Vec<u8>, timeout: Duration) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let message = nexus_transport::TransportMessage::new(
+                nexus_transport::MessageType::Control,
+                self.node_id(),
+                Some(peer),
+                payload,
+            );
+            self.send_request(peer, message, timeout)
+                .await
+                .map(|_| ())
+                .map_err(|e| NetworkError::Transport { message: e.to_string() })
+        })
+    }
+}
+
+/// Probes links from this node and holds the cluster's link measurements
+pub struct LinkProber {
+    node_id: NodeId,
+    config: ProbeConfig,
+    links: RwLock<HashMap<(NodeId, NodeId), LinkQuality>>,
+    updates: broadcast::Sender<LinkQuality>,
+}
+
+impl LinkProber {
+    pub fn new(node_id: NodeId, config: ProbeConfig) -> Self {
+        Self {
+            node_id,
+            config,
+            links: RwLock::new(HashMap::new()),
+            updates: broadcast::channel(1024).0,
+        }
+    }
+
+    pub fn config(&self) -> &ProbeConfig {
+        &self.config
+    }
+
+    /// Measure the link to one peer and fold the result in
+    pub async fn probe(&self, transport: &dyn ProbeTransport, peer: NodeId) -> LinkQuality {
+        let mut sample = ProbeSample::default();
+        for i in 0..self.config.rtt_samples {
+            if i > 0 {
+                tokio::time::sleep(self.config.sample_spacing).await;
+            }
+            let started = Instant::now();
+            match self.round_trip(transport, peer, RTT_PROBE.to_vec()).await {
+                Ok(()) => sample.rtts.push(started.elapsed()),
+                Err(_) => sample.lost += 1,
+            }
+        }
+
+        // Bandwidth is only worth measuring over a link that answers
+        if let Some(base_rtt) = sample.rtts.iter().min().copied() {
+            sample.bandwidth_mbps = self.measure_bandwidth(transport, peer, base_rtt).await;
+        }
+
+        self.record(peer, &sample)
+    }
+
+    /// Probe several peers one after another
+    pub async fn probe_all(&self, transport: &dyn ProbeTransport, peers: &[NodeId]) -> Vec<LinkQuality> {
+        let mut measured = Vec::with_capacity(peers.len());
+        for peer in peers.iter().filter(|peer| **peer != self.node_id) {
+            measured.push(self.probe(transport, *peer).await);
+        }
+        measured
+    }
+
+    /// Fold a probe of the link to `peer` into the table
+    pub fn record(&self, peer: NodeId, sample: &ProbeSample) -> LinkQuality {
+        let mut links = self.links.write();
+        let key = (self.node_id, peer);
+        let quality = LinkQuality::update(links.get(&key), self.node_id, peer, sample, self.config.smoothing);
+        links.insert(key, quality.clone());
+        drop(links);
+
+        let _ = self.updates.send(quality.clone());
+        quality
+    }
+
+    /// Take in links another node measured from itself
+    pub fn ingest_report(&self, origin: NodeId, report: Vec<LinkQuality>) {
+        let mut links = self.links.write();
+        for quality in report.into_iter().filter(|q| q.from == origin && q.from != self.node_id) {
+            links.insert((quality.from, quality.to), quality.clone());
+            let _ = self.updates.send(quality);
+        }
+    }
+
+    /// Links measured from this node, for sharing with the cluster
+    pub fn local_report(&self) -> Vec<LinkQuality> {
+        self.links.read().values().filter(|q| q.from == self.node_id).cloned().collect()
+    }
+
+    /// Measured link between two nodes, in either direction, if fresh
+    pub fn link(&self, from: NodeId, to: NodeId) -> Option<LinkQuality> {
+        let links = self.links.read();
+        links.get(&(from, to))
+            .or_else(|| links.get(&(to, from)))
+            .filter(|q| q.is_fresh(self.config.max_age))
+            .cloned()
+    }
+
+    /// Every fresh link measurement held
+    pub fn links(&self) -> Vec<LinkQuality> {
+        self.links.read().values().filter(|q| q.is_fresh(self.config.max_age)).cloned().collect()
+    }
+
+    /// Drop measurements older than `max_age`; returns how many were dropped
+    pub fn expire_stale(&self) -> usize {
+        let mut links = self.links.write();
+        let before = links.len();
+        links.retain(|_, q| q.is_fresh(self.config.max_age));
+        before - links.len()
+    }
+
+    /// Link measurements as they are taken or received
+    pub fn subscribe(&self) -> broadcast::Receiver<LinkQuality> {
+        self.updates.subscribe()
+    }
+
+    async fn round_trip(&self, transport: &dyn ProbeTransport, peer: NodeId, payload: Vec<u8>) -> Result<()> {
+        tokio::time::timeout(self.config.timeout, transport.round_trip(peer, payload, self.config.timeout))
+            .await
+            .map_err(|_| NetworkError::Timeout { duration_ms: self.config.timeout.as_millis() as u64 })?
+    }
+
+    /// Send paced trains of back-to-back packets; the median train rate wins
+    async fn measure_bandwidth(&self, transport: &dyn ProbeTransport, peer: NodeId, base_rtt: Duration) -> Option<f64> {
+        let mut rates = Vec::new();
+        for train in 0..self.config.trains {
+            if train > 0 {
+                tokio::time::sleep(self.config.train_spacing).await;
+            }
+            let started = Instant::now();
+            let sends = (0..self.config.train_packets)
+                .map(|_| self.round_trip(transport, peer, vec![0; self.config.packet_bytes]));
+            let delivered = futures::future::join_all(sends).await.into_iter().filter(Result::is_ok).count();
+            if delivered == 0 {
+                continue;
+            }
+
+            // One RTT is spent regardless of size; the rest is transfer time
+            let transfer = started.elapsed().saturating_sub(base_rtt).max(Duration::from_micros(100));
+            let bits = (delivered * self.config.packet_bytes * 8) as f64;
+            rates.push(bits / transfer.as_secs_f64() / 1_000_000.0);
+        }
+        rates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        rates.get(rates.len() / 2).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Answers after a fixed delay and drops every fourth RTT probe
+    struct LossyLink {
+        delay: Duration,
+        pings: AtomicU32,
+    }
+
+    impl ProbeTransport for LossyLink {
+        fn round_trip(&self, _peer: NodeId, payload: Vec<u8>, _timeout: Duration) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                tokio::time::sleep(self.delay).await;
+                if payload == RTT_PROBE && self.pings.fetch_add(1, Ordering::Relaxed) % 4 == 3 {
+                    return Err(NetworkError::RequestFailed { message: "dropped".into() });
+                }
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_measures_link() {
+        let (local, peer) = (NodeId::random(), NodeId::random());
+        let config = ProbeConfig {
+            rtt_samples: 8,
+            sample_spacing: Duration::ZERO,
+            trains: 2,
+            train_spacing: Duration::ZERO,
+            ..Default::default()
+        };
+        let prober = LinkProber::new(local, config);
+        let link = LossyLink { delay: Duration::from_millis(5), pings: AtomicU32::new(0) };
+
+        let quality = prober.probe(&link, peer).await;
+        assert_eq!(quality.loss_rate, 0.25);
+        assert!(quality.rtt >= Duration::from_millis(5));
+        assert!(quality.bandwidth_mbps.is_some_and(|mbps| mbps > 0.0));
+        assert_eq!(prober.link(peer, local), Some(quality.clone()));
+
+        // Later probes are smoothed into the figures
+        let lossless = ProbeSample { rtts: vec![Duration::from_millis(5); 4], ..Default::default() };
+        let smoothed = prober.record(peer, &lossless);
+        assert_eq!(smoothed.probes, 2);
+        assert!((smoothed.loss_rate - 0.175).abs() < 1e-9);
+
+        // Reports from other nodes fill in links this node cannot measure
+        let (a, b) = (NodeId::random(), NodeId::random());
+        let remote = LinkQuality::update(None, a, b, &lossless, 0.3);
+        prober.ingest_report(a, vec![remote.clone(), LinkQuality { from: b, ..remote.clone() }]);
+        assert_eq!(prober.link(a, b), Some(remote));
+        assert_eq!(prober.links().len(), 2);
+        assert_eq!(prober.local_report(), vec![smoothed]);
+    }
+}
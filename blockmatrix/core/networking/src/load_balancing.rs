@@ -1,16 +1,21 @@
 //! Load balancing module for service mesh
+//!
+//! Endpoints are chosen among a service's current instances, minus the ones
+//! outlier detection has ejected for failing or being slow.
 
 use crate::error::Result;
 use crate::config::LoadBalancingConfig;
+use crate::outlier_detection::{EndpointHealth, OutlierDetectionConfig, OutlierDetector};
 use nexus_shared::ServiceId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 /// Load balancing strategies
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LoadBalancingStrategy {
     RoundRobin,
     LeastConnections,
@@ -40,23 +45,36 @@ impl BackendPool {
         self.backends.push(addr);
     }
     
+    pub fn set_strategy(&mut self, strategy: LoadBalancingStrategy) {
+        self.strategy = strategy;
+        self.current_index = 0;
+    }
+    
     pub fn next(&mut self) -> Option<SocketAddr> {
-        if self.backends.is_empty() {
+        let backends = std::mem::take(&mut self.backends);
+        let selected = self.pick(&backends);
+        self.backends = backends;
+        selected
+    }
+    
+    /// Pick among `candidates` with the pool's strategy
+    pub fn pick(&mut self, candidates: &[SocketAddr]) -> Option<SocketAddr> {
+        if candidates.is_empty() {
             return None;
         }
         
         match self.strategy {
             LoadBalancingStrategy::RoundRobin => {
-                let addr = self.backends[self.current_index];
-                self.current_index = (self.current_index + 1) % self.backends.len();
+                let addr = candidates[self.current_index % candidates.len()];
+                self.current_index = (self.current_index + 1) % candidates.len();
                 Some(addr)
             }
             LoadBalancingStrategy::Random => {
                 use rand::Rng;
-                let idx = rand::thread_rng().gen_range(0..self.backends.len());
-                Some(self.backends[idx])
+                let idx = rand::thread_rng().gen_range(0..candidates.len());
+                Some(candidates[idx])
             }
-            _ => Some(self.backends[0]), // Simplified for other strategies
+            _ => Some(candidates[0]), // Simplified for other strategies
         }
     }
 }
@@ -65,6 +83,7 @@ impl BackendPool {
 pub struct LoadBalancer {
     pools: Arc<RwLock<HashMap<ServiceId, BackendPool>>>,
     default_strategy: LoadBalancingStrategy,
+    outliers: OutlierDetector,
 }
 
 impl LoadBalancer {
@@ -72,6 +91,7 @@ impl LoadBalancer {
         Ok(Self {
             pools: Arc::new(RwLock::new(HashMap::new())),
             default_strategy: config.strategy.clone(),
+            outliers: OutlierDetector::new(config.outlier_detection.clone()),
         })
     }
     
@@ -100,8 +120,51 @@ impl LoadBalancer {
         Ok(())
     }
 
-    pub async fn select_instance(&self, service_id: &ServiceId, _instances: &[SocketAddr]) -> Result<SocketAddr> {
-        // Use the existing get_backend method for now
-        self.get_backend(service_id).await
+    /// Change the strategy of a service's pool, creating the pool if needed
+    pub async fn set_strategy(&self, service_id: &ServiceId, strategy: LoadBalancingStrategy) {
+        let mut pools = self.pools.write().await;
+        pools.entry(service_id.clone())
+            .or_insert_with(|| BackendPool::new(strategy.clone()))
+            .set_strategy(strategy);
+    }
+    
+    pub fn default_strategy(&self) -> LoadBalancingStrategy {
+        self.default_strategy.clone()
+    }
+
+    /// Pick one of a service's current instances, skipping ejected outliers
+    pub async fn select_instance(&self, service_id: &ServiceId, instances: &[SocketAddr]) -> Result<SocketAddr> {
+        let admitted = self.outliers.admit(service_id, instances);
+        let mut pools = self.pools.write().await;
+        pools.entry(service_id.clone())
+            .or_insert_with(|| BackendPool::new(self.default_strategy.clone()))
+            .pick(&admitted)
+            .ok_or_else(|| crate::error::NetworkError::NoBackendsAvailable {
+                service_id: service_id.clone(),
+            })
+    }
+    
+    /// Record how an endpoint handled a request, for outlier detection
+    pub fn record_result(&self, service_id: &ServiceId, address: SocketAddr, success: bool, latency: Duration) {
+        if success {
+            self.outliers.record_success(service_id, address, latency);
+        } else {
+            self.outliers.record_failure(service_id, address);
+        }
+    }
+    
+    /// Replace a service's outlier detection settings; `None` restores the node's
+    pub fn set_outlier_detection(&self, service_id: &ServiceId, config: Option<OutlierDetectionConfig>) {
+        self.outliers.set_service_config(service_id, config);
+    }
+    
+    /// Outlier detection view of a service's endpoints
+    pub fn endpoint_health(&self, service_id: &ServiceId) -> Vec<EndpointHealth> {
+        self.outliers.endpoint_health(service_id)
+    }
+    
+    /// Drop outlier state for endpoints a service no longer has
+    pub fn retain_endpoints(&self, service_id: &ServiceId, current: &[SocketAddr]) {
+        self.outliers.retain_endpoints(service_id, current);
     }
 }
\ No newline at end of file
@@ -12,6 +12,25 @@ pub struct MetricsSummary {
     pub total_failures: u64,
     pub success_rate: f64,
     pub avg_latency_ms: f64,
+    pub retries: RetryStats,
+}
+
+/// Retry activity across all services
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetryStats {
+    /// Attempts made after a first one failed
+    pub attempts: u64,
+    /// Requests that failed with retries left but no budget for them
+    pub budget_exhausted: u64,
+    /// Requests that failed after using every retry their policy allows
+    pub policy_exhausted: u64,
+}
+
+#[derive(Debug, Default)]
+struct RetryCounters {
+    attempts: AtomicU64,
+    budget_exhausted: AtomicU64,
+    policy_exhausted: AtomicU64,
 }
 
 /// Network-wide metrics
@@ -24,6 +43,8 @@ pub struct NetworkMetrics {
     pub requests_per_second: f64,
     pub bytes_sent: u64,
     pub bytes_received: u64,
+    #[serde(skip)]
+    retry_counters: Arc<RetryCounters>,
 }
 
 impl NetworkMetrics {
@@ -36,6 +57,7 @@ impl NetworkMetrics {
             requests_per_second: 0.0,
             bytes_sent: 0,
             bytes_received: 0,
+            retry_counters: Arc::default(),
         }
     }
 
@@ -49,6 +71,29 @@ impl NetworkMetrics {
         // In a real implementation, this would update atomic counters
     }
 
+    /// Count an attempt made after a failure
+    pub fn record_retry(&self) {
+        self.retry_counters.attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a request given up because the retry budget was spent
+    pub fn record_retry_budget_exhausted(&self) {
+        self.retry_counters.budget_exhausted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a request given up after its last allowed retry
+    pub fn record_retries_exhausted(&self) {
+        self.retry_counters.policy_exhausted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn retry_stats(&self) -> RetryStats {
+        RetryStats {
+            attempts: self.retry_counters.attempts.load(Ordering::Relaxed),
+            budget_exhausted: self.retry_counters.budget_exhausted.load(Ordering::Relaxed),
+            policy_exhausted: self.retry_counters.policy_exhausted.load(Ordering::Relaxed),
+        }
+    }
+
     pub fn update_service_counts(&self, _services: usize) {
         // For static metrics, we'd need to track this differently
         // In a real implementation, this would update service count metrics
@@ -64,6 +109,7 @@ impl NetworkMetrics {
                 0.0
             },
             avg_latency_ms: self.avg_latency_ms,
+            retries: self.retry_stats(),
         }
     }
 }
@@ -133,6 +179,7 @@ impl MetricsCollector {
             requests_per_second: total_requests as f64 / elapsed,
             bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
             bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            retry_counters: Arc::default(),
         }
     }
 }
\ No newline at end of file
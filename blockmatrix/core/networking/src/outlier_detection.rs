@@ -0,0 +1,444 @@
+//! Passive outlier detection for load-balanced endpoints
+//!
+//! Every request outcome is recorded against the endpoint that served it.
+//! An endpoint that fails several times in a row, or whose tail latency is
+//! far above the other endpoints of its service, is ejected: it gets no
+//! traffic for an ejection time that doubles each time it is ejected again.
+//! When the time is up it returns with a share of traffic that ramps up over
+//! the recovery period, so a still-broken endpoint only sees a trickle
+//! before it is caught again. At most `max_ejection_percent` of a service's
+//! endpoints are ejected at once, and if nothing else is left the ejected
+//! ones are used anyway rather than failing every request.
+//!
+//! A service's traffic policy can replace the node's settings for that
+//! service alone.
+
+use nexus_shared::ServiceId;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Outlier detection configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlierDetectionConfig {
+    pub enabled: bool,
+
+    /// Failures in a row that eject an endpoint
+    pub consecutive_failures: u32,
+
+    /// Latency samples kept per endpoint
+    pub latency_window: usize,
+
+    /// Samples an endpoint needs before its latency is judged
+    pub min_latency_samples: usize,
+
+    /// Latency percentile compared across endpoints (0-1)
+    pub latency_percentile: f64,
+
+    /// Eject when an endpoint's percentile exceeds this multiple of the service median
+    pub latency_factor: f64,
+
+    /// Ejection time for a first ejection; doubles with each further one
+    pub base_ejection_time: Duration,
+
+    /// Longest ejection, and how long an endpoint must stay in before its count resets
+    pub max_ejection_time: Duration,
+
+    /// Time over which a returning endpoint ramps back to its full share
+    pub recovery_period: Duration,
+
+    /// Most of a service's endpoints that may be ejected at once
+    pub max_ejection_percent: u32,
+}
+
+impl Default for OutlierDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            consecutive_failures: 5,
+            latency_window: 100,
+            min_latency_samples: 20,
+            latency_percentile: 0.99,
+            latency_factor: 3.0,
+            base_ejection_time: Duration::from_secs(30),
+            max_ejection_time: Duration::from_secs(300),
+            recovery_period: Duration::from_secs(30),
+            max_ejection_percent: 50,
+        }
+    }
+}
+
+/// Where an endpoint stands with outlier detection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EndpointState {
+    Healthy,
+    /// Receives no traffic for `remaining`
+    Ejected { remaining: Duration },
+    /// Back from ejection, receiving `weight` (0-1) of its normal share
+    Recovering { weight: f64 },
+}
+
+/// Outlier detection view of one endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointHealth {
+    pub address: SocketAddr,
+    pub state: EndpointState,
+    pub consecutive_failures: u32,
+    pub ejections: u32,
+    pub median_latency_ms: Option<f64>,
+    /// Latency at the configured percentile
+    pub tail_latency_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct Endpoint {
+    consecutive_failures: u32,
+    latencies: VecDeque<Duration>,
+    ejections: u32,
+    ejected_until: Option<Instant>,
+    returned_at: Option<Instant>,
+}
+
+impl Endpoint {
+    /// Current state, moving an expired ejection into recovery
+    fn state_at(&mut self, config: &OutlierDetectionConfig, now: Instant) -> EndpointState {
+        if let Some(until) = self.ejected_until {
+            if now < until {
+                return EndpointState::Ejected { remaining: until - now };
+            }
+            self.ejected_until = None;
+            self.returned_at = Some(until);
+            self.consecutive_failures = 0;
+        }
+
+        let Some(returned_at) = self.returned_at else {
+            return EndpointState::Healthy;
+        };
+        let back_for = now.duration_since(returned_at);
+        if back_for >= config.max_ejection_time {
+            // Stayed healthy long enough that the next ejection starts over
+            self.returned_at = None;
+            self.ejections = 0;
+        }
+        if back_for < config.recovery_period {
+            EndpointState::Recovering {
+                weight: back_for.as_secs_f64() / config.recovery_period.as_secs_f64(),
+            }
+        } else {
+            EndpointState::Healthy
+        }
+    }
+
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until.is_some_and(|until| now < until)
+    }
+
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.latencies.iter().copied().collect();
+        sorted.sort();
+        let rank = ((sorted.len() - 1) as f64 * percentile).round() as usize;
+        Some(sorted[rank.min(sorted.len() - 1)])
+    }
+}
+
+/// Tracks request outcomes per endpoint and ejects outliers
+pub struct OutlierDetector {
+    config: OutlierDetectionConfig,
+    /// Per-service settings from traffic policies
+    overrides: Mutex<HashMap<ServiceId, OutlierDetectionConfig>>,
+    endpoints: Mutex<HashMap<ServiceId, HashMap<SocketAddr, Endpoint>>>,
+}
+
+impl OutlierDetector {
+    pub fn new(config: OutlierDetectionConfig) -> Self {
+        Self {
+            config,
+            overrides: Mutex::new(HashMap::new()),
+            endpoints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &OutlierDetectionConfig {
+        &self.config
+    }
+
+    /// Settings in effect for a service
+    pub fn service_config(&self, service_id: &ServiceId) -> OutlierDetectionConfig {
+        self.overrides.lock().get(service_id).cloned().unwrap_or_else(|| self.config.clone())
+    }
+
+    /// Replace the settings for one service, or return it to the node's with `None`
+    pub fn set_service_config(&self, service_id: &ServiceId, config: Option<OutlierDetectionConfig>) {
+        let mut overrides = self.overrides.lock();
+        match config {
+            Some(config) => {
+                overrides.insert(service_id.clone(), config);
+            }
+            None => {
+                overrides.remove(service_id);
+            }
+        }
+    }
+
+    /// Record a request an endpoint served successfully
+    pub fn record_success(&self, service_id: &ServiceId, address: SocketAddr, latency: Duration) {
+        self.record_at(service_id, address, Some(latency), Instant::now());
+    }
+
+    /// Record a request an endpoint failed
+    pub fn record_failure(&self, service_id: &ServiceId, address: SocketAddr) {
+        self.record_at(service_id, address, None, Instant::now());
+    }
+
+    /// Endpoints among `candidates` that may take this request
+    ///
+    /// Recovering endpoints are admitted with a probability equal to their
+    /// weight. Falls back to every candidate when outlier detection would
+    /// leave none.
+    pub fn admit(&self, service_id: &ServiceId, candidates: &[SocketAddr]) -> Vec<SocketAddr> {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        let weights = self.weights_at(service_id, candidates, Instant::now());
+        let admitted: Vec<SocketAddr> = candidates
+            .iter()
+            .zip(&weights)
+            .filter(|(_, weight)| **weight >= 1.0 || (**weight > 0.0 && rng.gen_bool(**weight)))
+            .map(|(address, _)| *address)
+            .collect();
+        if !admitted.is_empty() {
+            return admitted;
+        }
+
+        // A recovering endpoint beats an ejected one
+        let recovering: Vec<SocketAddr> = candidates
+            .iter()
+            .zip(&weights)
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(address, _)| *address)
+            .collect();
+        if recovering.is_empty() {
+            tracing::debug!("All endpoints of {} are ejected; routing to them anyway", service_id);
+            candidates.to_vec()
+        } else {
+            recovering
+        }
+    }
+
+    /// Outlier detection view of a service's endpoints, by address
+    pub fn endpoint_health(&self, service_id: &ServiceId) -> Vec<EndpointHealth> {
+        let now = Instant::now();
+        let config = self.service_config(service_id);
+        let mut endpoints = self.endpoints.lock();
+        let Some(service) = endpoints.get_mut(service_id) else {
+            return Vec::new();
+        };
+        let mut health: Vec<EndpointHealth> = service
+            .iter_mut()
+            .map(|(address, endpoint)| EndpointHealth {
+                address: *address,
+                state: endpoint.state_at(&config, now),
+                consecutive_failures: endpoint.consecutive_failures,
+                ejections: endpoint.ejections,
+                median_latency_ms: endpoint.percentile(0.5).map(|d| d.as_secs_f64() * 1000.0),
+                tail_latency_ms: endpoint
+                    .percentile(config.latency_percentile)
+                    .map(|d| d.as_secs_f64() * 1000.0),
+            })
+            .collect();
+        health.sort_by_key(|h| h.address);
+        health
+    }
+
+    /// Forget endpoints of a service that are no longer among `current`
+    pub fn retain_endpoints(&self, service_id: &ServiceId, current: &[SocketAddr]) {
+        if let Some(service) = self.endpoints.lock().get_mut(service_id) {
+            service.retain(|address, _| current.contains(address));
+        }
+    }
+
+    /// Traffic weight (0-1) of each candidate
+    fn weights_at(&self, service_id: &ServiceId, candidates: &[SocketAddr], now: Instant) -> Vec<f64> {
+        let config = self.service_config(service_id);
+        if !config.enabled {
+            return vec![1.0; candidates.len()];
+        }
+        let mut endpoints = self.endpoints.lock();
+        let service = endpoints.get_mut(service_id);
+        let Some(service) = service else {
+            return vec![1.0; candidates.len()];
+        };
+        candidates
+            .iter()
+            .map(|address| match service.get_mut(address).map(|e| e.state_at(&config, now)) {
+                Some(EndpointState::Ejected { .. }) => 0.0,
+                Some(EndpointState::Recovering { weight }) => weight,
+                Some(EndpointState::Healthy) | None => 1.0,
+            })
+            .collect()
+    }
+
+    fn record_at(&self, service_id: &ServiceId, address: SocketAddr, latency: Option<Duration>, now: Instant) {
+        let config = &self.service_config(service_id);
+        if !config.enabled {
+            return;
+        }
+        let mut endpoints = self.endpoints.lock();
+        let service = endpoints.entry(service_id.clone()).or_default();
+        let endpoint = service.entry(address).or_default();
+        endpoint.state_at(config, now);
+        if endpoint.is_ejected(now) {
+            // Late results of requests sent before the ejection
+            return;
+        }
+
+        let reason = match latency {
+            None => {
+                endpoint.consecutive_failures += 1;
+                (endpoint.consecutive_failures >= config.consecutive_failures)
+                    .then(|| format!("{} consecutive failures", endpoint.consecutive_failures))
+            }
+            Some(latency) => {
+                endpoint.consecutive_failures = 0;
+                endpoint.latencies.push_back(latency);
+                while endpoint.latencies.len() > config.latency_window {
+                    endpoint.latencies.pop_front();
+                }
+                Self::latency_outlier(config, service, address)
+            }
+        };
+
+        if let Some(reason) = reason {
+            Self::eject(config, service_id, service, address, &reason, now);
+        }
+    }
+
+    /// Why an endpoint's latency makes it an outlier, if it does
+    fn latency_outlier(
+        config: &OutlierDetectionConfig,
+        service: &HashMap<SocketAddr, Endpoint>,
+        address: SocketAddr,
+    ) -> Option<String> {
+        let judged = |endpoint: &Endpoint| endpoint.latencies.len() >= config.min_latency_samples;
+        let endpoint = service.get(&address).filter(|e| judged(e))?;
+        let tail = endpoint.percentile(config.latency_percentile)?;
+
+        let mut others: Vec<Duration> = service
+            .iter()
+            .filter(|(other, e)| **other != address && judged(e))
+            .filter_map(|(_, e)| e.percentile(config.latency_percentile))
+            .collect();
+        if others.is_empty() {
+            return None;
+        }
+        others.sort();
+        let median = others[others.len() / 2];
+        (tail.as_secs_f64() > median.as_secs_f64() * config.latency_factor)
+            .then(|| format!("p{:.0} latency {:?} against service median {:?}", config.latency_percentile * 100.0, tail, median))
+    }
+
+    fn eject(
+        config: &OutlierDetectionConfig,
+        service_id: &ServiceId,
+        service: &mut HashMap<SocketAddr, Endpoint>,
+        address: SocketAddr,
+        reason: &str,
+        now: Instant,
+    ) {
+        let ejected = service.values().filter(|e| e.is_ejected(now)).count();
+        if ejected > 0 && (ejected + 1) * 100 > service.len() * config.max_ejection_percent as usize {
+            tracing::debug!("Not ejecting {} of {}: ejection limit reached ({})", address, service_id, reason);
+            return;
+        }
+
+        let Some(endpoint) = service.get_mut(&address) else {
+            return;
+        };
+        let doubling = endpoint.ejections.min(16);
+        let ejection_time = config.base_ejection_time.saturating_mul(1 << doubling).min(config.max_ejection_time);
+        endpoint.ejections += 1;
+        endpoint.ejected_until = Some(now + ejection_time);
+        endpoint.returned_at = None;
+        endpoint.consecutive_failures = 0;
+        endpoint.latencies.clear();
+        tracing::warn!("Ejected {} of {} for {:?}: {}", address, service_id, ejection_time, reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eject_and_reintroduce() {
+        let detector = OutlierDetector::new(OutlierDetectionConfig {
+            consecutive_failures: 3,
+            min_latency_samples: 5,
+            base_ejection_time: Duration::from_secs(10),
+            recovery_period: Duration::from_secs(10),
+            ..Default::default()
+        });
+        let service = ServiceId::new("api", "default");
+        let (good, bad, slow) = (
+            "10.0.0.1:80".parse().unwrap(),
+            "10.0.0.2:80".parse().unwrap(),
+            "10.0.0.3:80".parse().unwrap(),
+        );
+        let all = [good, bad, slow];
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            detector.record_at(&service, bad, None, start);
+        }
+        for _ in 0..5 {
+            detector.record_at(&service, good, Some(Duration::from_millis(10)), start);
+        }
+        assert_eq!(detector.weights_at(&service, &all, start), vec![1.0, 0.0, 1.0]);
+
+        // A second ejection would put two of three endpoints out, over the 50% limit
+        for _ in 0..5 {
+            detector.record_at(&service, slow, Some(Duration::from_millis(100)), start);
+        }
+        assert_eq!(detector.weights_at(&service, &all, start)[2], 1.0);
+
+        // Back after the ejection time at a share that ramps up
+        let returning = start + Duration::from_secs(15);
+        assert_eq!(detector.weights_at(&service, &all, returning)[1], 0.5);
+        assert_eq!(detector.weights_at(&service, &all, start + Duration::from_secs(25))[1], 1.0);
+
+        // Failing again doubles the ejection time
+        for _ in 0..3 {
+            detector.record_at(&service, bad, None, returning);
+        }
+        let weights = detector.weights_at(&service, &all, returning + Duration::from_secs(15));
+        assert_eq!(weights[1], 0.0);
+
+        // With every endpoint ejected, requests still go somewhere
+        assert_eq!(detector.admit(&service, &[bad]), vec![bad]);
+    }
+
+    #[test]
+    fn test_service_override() {
+        let detector = OutlierDetector::new(OutlierDetectionConfig::default());
+        let (strict, lenient) = (ServiceId::new("payments", "default"), ServiceId::new("api", "default"));
+        let address: SocketAddr = "10.0.0.1:80".parse().unwrap();
+        let start = Instant::now();
+
+        detector.set_service_config(&strict, Some(OutlierDetectionConfig {
+            consecutive_failures: 1,
+            ..Default::default()
+        }));
+        detector.record_at(&strict, address, None, start);
+        detector.record_at(&lenient, address, None, start);
+        assert_eq!(detector.weights_at(&strict, &[address], start), vec![0.0]);
+        assert_eq!(detector.weights_at(&lenient, &[address], start), vec![1.0]);
+
+        // Without the override the node's settings apply again
+        detector.set_service_config(&strict, None);
+        assert_eq!(detector.service_config(&strict).consecutive_failures, 5);
+    }
+}
@@ -0,0 +1,242 @@
+//! End-to-end request signing for high-assurance services
+//!
+//! Transport mTLS proves which node a request came from, not which workload
+//! sent it, and leaves nothing behind once the connection is gone. For
+//! financial and other regulated traffic a caller can sign each request with
+//! its workload identity key. The signature travels beside the payload and
+//! covers the target service, so the receiving service's listener checks it
+//! against the keys trusted for that workload and can keep it as evidence of
+//! who asked for what. Services listed as requiring signatures refuse
+//! unsigned requests, and a nonce cache refuses a signed request seen twice
+//! within the clock skew window. Retries reuse the first attempt's
+//! signature, so a retry of a request that did arrive is refused as a replay
+//! rather than served twice.
+
+use crate::error::{NetworkError, Result};
+use nexus_shared::{DetachedSignature, KeyPair, ServiceId};
+use nexus_transport::TransportMessage;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Request signing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestSigningConfig {
+    /// Local services, by name, that refuse requests without a valid signature
+    #[serde(default)]
+    pub required_services: Vec<String>,
+
+    /// Workload names and the hex Ed25519 public keys accepted for each
+    #[serde(default)]
+    pub trusted_workloads: HashMap<String, Vec<String>>,
+
+    /// Tolerated clock skew, and how long nonces are remembered
+    pub max_clock_skew: Duration,
+}
+
+impl Default for RequestSigningConfig {
+    fn default() -> Self {
+        Self {
+            required_services: Vec::new(),
+            trusted_workloads: HashMap::new(),
+            max_clock_skew: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Key a workload signs its requests with
+pub struct WorkloadIdentity {
+    name: String,
+    key_pair: KeyPair,
+}
+
+impl WorkloadIdentity {
+    pub fn new(name: impl Into<String>, key_pair: KeyPair) -> Self {
+        Self { name: name.into(), key_pair }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn public_key(&self) -> &[u8; 32] {
+        self.key_pair.public_key()
+    }
+
+    /// Sign a request payload for one service
+    pub fn sign(&self, service_id: &ServiceId, payload: &[u8]) -> DetachedSignature {
+        DetachedSignature::sign(payload, &service_id.to_string(), &self.name, &self.key_pair)
+    }
+}
+
+/// A request whose signature passed, kept as evidence of who sent it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifiedRequest {
+    /// Service the request was signed for
+    pub service: ServiceId,
+    /// Workload that signed it
+    pub caller: String,
+    /// Blake3 hash of the payload, hex encoded
+    pub payload_hash: String,
+    pub signature: DetachedSignature,
+}
+
+/// Checks signatures on requests for local services
+pub struct RequestVerifier {
+    config: RequestSigningConfig,
+    trusted: RwLock<HashMap<String, Vec<[u8; 32]>>>,
+    /// Nonces of accepted requests and when they may be forgotten
+    seen: Mutex<HashMap<[u8; 16], u64>>,
+}
+
+impl RequestVerifier {
+    pub fn new(config: RequestSigningConfig) -> Result<Self> {
+        let mut trusted = HashMap::new();
+        for (workload, keys) in &config.trusted_workloads {
+            let keys = keys
+                .iter()
+                .map(|key| parse_public_key(key))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| NetworkError::Configuration {
+                    message: format!("request_signing.trusted_workloads.{} holds a key that is not 32 hex bytes", workload),
+                })?;
+            trusted.insert(workload.clone(), keys);
+        }
+
+        Ok(Self {
+            config,
+            trusted: RwLock::new(trusted),
+            seen: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Accept a workload's signatures made with this key
+    pub fn trust(&self, workload: &str, public_key: [u8; 32]) {
+        let mut trusted = self.trusted.write();
+        let keys = trusted.entry(workload.to_string()).or_default();
+        if !keys.contains(&public_key) {
+            keys.push(public_key);
+        }
+    }
+
+    /// Stop accepting a workload's signatures
+    pub fn distrust(&self, workload: &str) -> bool {
+        self.trusted.write().remove(workload).is_some()
+    }
+
+    /// Whether a local service refuses unsigned requests
+    pub fn requires_signature(&self, service_id: &ServiceId) -> bool {
+        self.config.required_services.iter().any(|name| name == service_id.name())
+    }
+
+    /// Check an inbound request for a local service
+    ///
+    /// Returns the evidence of a valid signature, `None` for an unsigned
+    /// request the service accepts, and an authentication error otherwise.
+    pub fn verify(&self, service_id: &ServiceId, message: &TransportMessage) -> Result<Option<VerifiedRequest>> {
+        let Some(signature) = &message.signature else {
+            if self.requires_signature(service_id) {
+                return Err(NetworkError::Authentication {
+                    reason: format!("{} requires signed requests", service_id),
+                });
+            }
+            return Ok(None);
+        };
+        let refuse = |why: &str| NetworkError::Authentication {
+            reason: format!("request to {} signed by '{}' {}", service_id, signature.signer, why),
+        };
+
+        let trusted = self.trusted
+            .read()
+            .get(&signature.signer)
+            .is_some_and(|keys| keys.contains(&signature.public_key));
+        if !trusted {
+            return Err(refuse("uses a key not trusted for that workload"));
+        }
+
+        let now = unix_now();
+        let skew = self.config.max_clock_skew.as_secs();
+        if signature.timestamp.abs_diff(now) > skew {
+            return Err(refuse("is outside the allowed clock skew"));
+        }
+        if !signature.verify(&message.payload, &service_id.to_string()) {
+            return Err(refuse("has an invalid signature"));
+        }
+
+        let mut seen = self.seen.lock();
+        seen.retain(|_, forget_at| *forget_at > now);
+        if seen.insert(signature.nonce, signature.timestamp + 2 * skew).is_some() {
+            return Err(refuse("was already received"));
+        }
+
+        Ok(Some(VerifiedRequest {
+            service: service_id.clone(),
+            caller: signature.signer.clone(),
+            payload_hash: hex::encode(DetachedSignature::payload_hash(&message.payload)),
+            signature: signature.clone(),
+        }))
+    }
+}
+
+fn parse_public_key(key: &str) -> Option<[u8; 32]> {
+    hex::decode(key).ok()?.try_into().ok()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nexus_shared::NodeId;
+    use nexus_transport::MessageType;
+
+    fn request(payload: &[u8]) -> TransportMessage {
+        TransportMessage::new(MessageType::Data, NodeId::random(), Some(NodeId::random()), payload.to_vec())
+    }
+
+    #[test]
+    fn test_signed_requests_for_a_high_assurance_service() {
+        let billing = WorkloadIdentity::new("billing", KeyPair::generate().unwrap());
+        let payments = ServiceId::new("payments", "default");
+        let verifier = RequestVerifier::new(RequestSigningConfig {
+            required_services: vec!["payments".to_string()],
+            trusted_workloads: HashMap::from([("billing".to_string(), vec![hex::encode(billing.public_key())])]),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // Unsigned requests are refused only by services that require signatures
+        assert!(verifier.verify(&payments, &request(b"transfer 100")).is_err());
+        assert!(verifier.verify(&ServiceId::new("catalog", "default"), &request(b"list")).unwrap().is_none());
+
+        let signed = request(b"transfer 100").with_signature(billing.sign(&payments, b"transfer 100"));
+        let evidence = verifier.verify(&payments, &signed).unwrap().unwrap();
+        assert_eq!(evidence.caller, "billing");
+        assert!(evidence.signature.verify(b"transfer 100", "payments.default"));
+
+        // The same signed request again is a replay
+        assert!(verifier.verify(&payments, &signed).is_err());
+
+        // Tampered payloads and signatures for another service are refused
+        let mut tampered = request(b"transfer 100").with_signature(billing.sign(&payments, b"transfer 100"));
+        tampered.payload = b"transfer 900".to_vec();
+        assert!(verifier.verify(&payments, &tampered).is_err());
+        let ledger = ServiceId::new("ledger", "default");
+        let misdirected = request(b"transfer 100").with_signature(billing.sign(&ledger, b"transfer 100"));
+        assert!(verifier.verify(&payments, &misdirected).is_err());
+
+        // Keys of unknown or distrusted workloads are refused
+        let rogue = WorkloadIdentity::new("billing", KeyPair::generate().unwrap());
+        let forged = request(b"transfer 100").with_signature(rogue.sign(&payments, b"transfer 100"));
+        assert!(verifier.verify(&payments, &forged).is_err());
+        assert!(verifier.distrust("billing"));
+        let after = request(b"transfer 5").with_signature(billing.sign(&payments, b"transfer 5"));
+        assert!(verifier.verify(&payments, &after).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Mesh-wide retry budget
+//!
+//! Per-service retry policies decide whether a failed request may be tried
+//! again; the budget decides whether this node can afford it. Retries are
+//! allowed while they stay under a share of the requests sent over a sliding
+//! window, with a small floor so quiet nodes can still retry. When a backend
+//! falls over, retries then add at most that share of extra load instead of
+//! multiplying it by the retry count.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Retry budget configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryBudgetConfig {
+    pub enabled: bool,
+
+    /// Retries allowed per request sent in the window
+    pub ratio: f64,
+
+    /// Retries per second allowed regardless of traffic
+    pub min_retries_per_second: u32,
+
+    /// Span over which requests and retries are counted
+    pub window: Duration,
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ratio: 0.2,
+            min_retries_per_second: 10,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Requests and retries started during one second of the window
+struct Bucket {
+    started: Instant,
+    requests: u64,
+    retries: u64,
+}
+
+/// Sliding-window retry budget shared by all services on a node
+pub struct RetryBudget {
+    config: RetryBudgetConfig,
+    buckets: Mutex<VecDeque<Bucket>>,
+}
+
+impl RetryBudget {
+    pub fn new(config: RetryBudgetConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Count a first attempt, which earns budget for retries
+    pub fn record_request(&self) {
+        self.record_request_at(Instant::now());
+    }
+
+    /// Take budget for one retry; false when the node is over budget
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Retries still allowed in the current window
+    pub fn remaining(&self) -> u64 {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        self.prune(&mut buckets, now);
+        let (allowed, retries) = self.usage(&buckets);
+        allowed.saturating_sub(retries)
+    }
+
+    fn record_request_at(&self, now: Instant) {
+        let mut buckets = self.buckets.lock().unwrap();
+        self.bucket(&mut buckets, now).requests += 1;
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let mut buckets = self.buckets.lock().unwrap();
+        self.prune(&mut buckets, now);
+        let (allowed, retries) = self.usage(&buckets);
+        if retries >= allowed {
+            return false;
+        }
+        self.bucket(&mut buckets, now).retries += 1;
+        true
+    }
+
+    /// Retries allowed and retries taken over the window
+    fn usage(&self, buckets: &VecDeque<Bucket>) -> (u64, u64) {
+        let requests: u64 = buckets.iter().map(|b| b.requests).sum();
+        let retries: u64 = buckets.iter().map(|b| b.retries).sum();
+        let floor = self.config.min_retries_per_second as u64 * self.config.window.as_secs().max(1);
+        let earned = (requests as f64 * self.config.ratio) as u64;
+        (floor.max(earned), retries)
+    }
+
+    fn bucket<'a>(&self, buckets: &'a mut VecDeque<Bucket>, now: Instant) -> &'a mut Bucket {
+        self.prune(buckets, now);
+        let fresh = !buckets.back().is_some_and(|b| now.duration_since(b.started) < Duration::from_secs(1));
+        if fresh {
+            buckets.push_back(Bucket { started: now, requests: 0, retries: 0 });
+        }
+        buckets.back_mut().expect("bucket was just ensured")
+    }
+
+    fn prune(&self, buckets: &mut VecDeque<Bucket>, now: Instant) {
+        while buckets.front().is_some_and(|b| now.duration_since(b.started) >= self.config.window) {
+            buckets.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_caps_retries() {
+        let budget = RetryBudget::new(RetryBudgetConfig {
+            ratio: 0.5,
+            min_retries_per_second: 1,
+            window: Duration::from_secs(2),
+            ..Default::default()
+        });
+        let start = Instant::now();
+
+        // The floor allows two retries over the window without any traffic
+        assert!(budget.try_acquire_at(start));
+        assert!(budget.try_acquire_at(start));
+        assert!(!budget.try_acquire_at(start));
+
+        // Traffic earns more: ten requests at 0.5 allow five retries in all
+        for _ in 0..10 {
+            budget.record_request_at(start);
+        }
+        assert!((0..3).all(|_| budget.try_acquire_at(start)));
+        assert!(!budget.try_acquire_at(start));
+
+        // Once the window has passed the budget refills to the floor
+        let later = start + Duration::from_secs(3);
+        assert!(budget.try_acquire_at(later));
+        assert!(budget.try_acquire_at(later));
+        assert!(!budget.try_acquire_at(later));
+    }
+}
@@ -1,13 +1,24 @@
 //! Routing module for service mesh
+//!
+//! Besides path and header rules, the router holds per-service traffic
+//! splits: weighted subsets of a service's instances picked by label, such
+//! as 95% to `version=v1` and 5% to `version=v2` for a canary. Each request
+//! picks its subset once, from the split in force when it starts, so weights
+//! can be shifted at runtime without touching requests already in flight.
+//! Success rate and latency are kept per subset for comparing versions.
 
-use crate::error::Result;
+use crate::error::{NetworkError, Result};
 use nexus_shared::ServiceId;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// Instance label used for version-based splits
+pub const VERSION_LABEL: &str = "version";
+
 /// Routing rule for traffic management
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingRule {
@@ -17,43 +28,118 @@ pub struct RoutingRule {
     pub weight: u32,
 }
 
+/// One weighted subset of a service's instances
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplitBackend {
+    /// Name the subset is reported and shifted under, e.g. "canary"
+    pub name: String,
+    /// Instance metadata a member must carry
+    pub selector: HashMap<String, String>,
+    /// Relative share of requests
+    pub weight: u32,
+}
+
+impl SplitBackend {
+    pub fn new(name: impl Into<String>, selector: HashMap<String, String>, weight: u32) -> Self {
+        Self { name: name.into(), selector, weight }
+    }
+
+    /// Subset of instances running one version
+    pub fn version(version: impl Into<String>, weight: u32) -> Self {
+        let version = version.into();
+        Self::new(version.clone(), HashMap::from([(VERSION_LABEL.to_string(), version)]), weight)
+    }
+
+    /// Whether an instance with this metadata belongs to the subset
+    pub fn matches(&self, metadata: &HashMap<String, String>) -> bool {
+        self.selector.iter().all(|(k, v)| metadata.get(k) == Some(v))
+    }
+}
+
 /// Traffic split configuration for canary deployments
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TrafficSplit {
-    pub primary: ServiceId,
-    pub primary_weight: u32,
-    pub canary: ServiceId,
-    pub canary_weight: u32,
+    pub backends: Vec<SplitBackend>,
 }
 
 impl TrafficSplit {
-    pub fn new(primary: ServiceId, canary: ServiceId, canary_percentage: u32) -> Self {
+    pub fn new(backends: Vec<SplitBackend>) -> Self {
+        Self { backends }
+    }
+
+    /// Send `canary_percentage` of requests to the canary version
+    pub fn canary(stable_version: &str, canary_version: &str, canary_percentage: u32) -> Self {
         assert!(canary_percentage <= 100);
-        Self {
-            primary,
-            primary_weight: 100 - canary_percentage,
-            canary,
-            canary_weight: canary_percentage,
+        Self::new(vec![
+            SplitBackend::version(stable_version, 100 - canary_percentage),
+            SplitBackend::version(canary_version, canary_percentage),
+        ])
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.backends.is_empty() {
+            return Err(NetworkError::Routing { message: "traffic split has no backends".to_string() });
         }
+        if self.backends.iter().all(|b| b.weight == 0) {
+            return Err(NetworkError::Routing { message: "traffic split weights are all zero".to_string() });
+        }
+        for (i, backend) in self.backends.iter().enumerate() {
+            if self.backends[..i].iter().any(|b| b.name == backend.name) {
+                return Err(NetworkError::Routing {
+                    message: format!("duplicate traffic split backend '{}'", backend.name),
+                });
+            }
+        }
+        Ok(())
     }
-    
-    pub fn select_service(&self) -> ServiceId {
+
+    /// Pick a backend by weight among those `available` accepts
+    ///
+    /// Falls back to the available backends evenly when they all have zero
+    /// weight, so a drained subset still serves if nothing else can.
+    pub fn select(&self, available: impl Fn(&SplitBackend) -> bool) -> Option<&SplitBackend> {
         use rand::Rng;
-        let total = self.primary_weight + self.canary_weight;
-        let roll = rand::thread_rng().gen_range(0..total);
-        
-        if roll < self.primary_weight {
-            self.primary.clone()
-        } else {
-            self.canary.clone()
+        let candidates: Vec<&SplitBackend> = self.backends.iter().filter(|b| available(b)).collect();
+        let total: u32 = candidates.iter().map(|b| b.weight).sum();
+        if total == 0 {
+            return candidates.first().copied();
         }
+
+        let mut roll = rand::thread_rng().gen_range(0..total);
+        for backend in candidates {
+            if roll < backend.weight {
+                return Some(backend);
+            }
+            roll -= backend.weight;
+        }
+        None
     }
 }
 
+/// Request outcomes for one split backend
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SplitMetrics {
+    pub backend: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub success_rate: f64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: f64,
+}
+
+#[derive(Default)]
+struct SplitCounters {
+    requests: u64,
+    failures: u64,
+    total_latency: Duration,
+    max_latency: Duration,
+}
+
 /// Router for service mesh traffic management
 pub struct Router {
     rules: Arc<RwLock<Vec<RoutingRule>>>,
-    traffic_splits: Arc<RwLock<HashMap<ServiceId, TrafficSplit>>>,
+    traffic_splits: Arc<RwLock<HashMap<ServiceId, Arc<TrafficSplit>>>>,
+    split_metrics: Mutex<HashMap<(ServiceId, String), SplitCounters>>,
 }
 
 impl Router {
@@ -61,48 +147,168 @@ impl Router {
         Self {
             rules: Arc::new(RwLock::new(Vec::new())),
             traffic_splits: Arc::new(RwLock::new(HashMap::new())),
+            split_metrics: Mutex::new(HashMap::new()),
         }
     }
-    
+
     pub async fn add_rule(&self, rule: RoutingRule) -> Result<()> {
         let mut rules = self.rules.write().await;
         rules.push(rule);
         Ok(())
     }
-    
+
+    /// Install or replace a service's traffic split
+    ///
+    /// Requests already routed keep the split they started with.
     pub async fn add_traffic_split(&self, service_id: ServiceId, split: TrafficSplit) -> Result<()> {
+        split.validate()?;
+        tracing::info!("Traffic split for {}: {:?}", service_id, split.backends);
+        self.traffic_splits.write().await.insert(service_id, Arc::new(split));
+        Ok(())
+    }
+
+    /// Change the weights of existing backends, leaving the others as they are
+    pub async fn shift_traffic(&self, service_id: &ServiceId, weights: &HashMap<String, u32>) -> Result<()> {
         let mut splits = self.traffic_splits.write().await;
-        splits.insert(service_id, split);
+        let current = splits.get(service_id).ok_or_else(|| NetworkError::Routing {
+            message: format!("no traffic split for {}", service_id),
+        })?;
+        if let Some(unknown) = weights.keys().find(|name| !current.backends.iter().any(|b| &b.name == *name)) {
+            return Err(NetworkError::Routing {
+                message: format!("traffic split for {} has no backend '{}'", service_id, unknown),
+            });
+        }
+
+        let mut shifted = TrafficSplit::clone(current);
+        for backend in &mut shifted.backends {
+            if let Some(weight) = weights.get(&backend.name) {
+                backend.weight = *weight;
+            }
+        }
+        shifted.validate()?;
+        tracing::info!("Shifted traffic for {}: {:?}", service_id, shifted.backends);
+        splits.insert(service_id.clone(), Arc::new(shifted));
         Ok(())
     }
-    
+
+    /// Remove a service's traffic split and its metrics
+    pub async fn remove_traffic_split(&self, service_id: &ServiceId) -> Option<Arc<TrafficSplit>> {
+        let removed = self.traffic_splits.write().await.remove(service_id);
+        self.split_metrics.lock().retain(|(id, _), _| id != service_id);
+        removed
+    }
+
+    /// Split currently in force for a service
+    pub async fn traffic_split(&self, service_id: &ServiceId) -> Option<Arc<TrafficSplit>> {
+        self.traffic_splits.read().await.get(service_id).cloned()
+    }
+
+    /// Record the outcome of a request sent to a split backend
+    pub fn record_split_result(&self, service_id: &ServiceId, backend: &str, success: bool, latency: Duration) {
+        let mut metrics = self.split_metrics.lock();
+        let counters = metrics.entry((service_id.clone(), backend.to_string())).or_default();
+        counters.requests += 1;
+        if !success {
+            counters.failures += 1;
+        }
+        counters.total_latency += latency;
+        counters.max_latency = counters.max_latency.max(latency);
+    }
+
+    /// Per-backend request outcomes for a service, by backend name
+    pub fn split_metrics(&self, service_id: &ServiceId) -> Vec<SplitMetrics> {
+        let mut summary: Vec<SplitMetrics> = self.split_metrics.lock()
+            .iter()
+            .filter(|((id, _), _)| id == service_id)
+            .map(|((_, backend), c)| SplitMetrics {
+                backend: backend.clone(),
+                requests: c.requests,
+                failures: c.failures,
+                success_rate: if c.requests > 0 {
+                    (c.requests - c.failures) as f64 / c.requests as f64
+                } else {
+                    0.0
+                },
+                avg_latency_ms: if c.requests > 0 {
+                    c.total_latency.as_secs_f64() * 1000.0 / c.requests as f64
+                } else {
+                    0.0
+                },
+                max_latency_ms: c.max_latency.as_secs_f64() * 1000.0,
+            })
+            .collect();
+        summary.sort_by(|a, b| a.backend.cmp(&b.backend));
+        summary
+    }
+
     pub async fn route(&self, path: &str, headers: &HashMap<String, String>) -> Result<ServiceId> {
         let rules = self.rules.read().await;
-        
-        // Find matching rule
+
+        // Find matching rule; traffic splits apply once the service's instances are known
         for rule in rules.iter() {
             if let Some(prefix) = &rule.path_prefix {
                 if !path.starts_with(prefix) {
                     continue;
                 }
             }
-            
+
             let headers_match = rule.headers.iter().all(|(k, v)| {
                 headers.get(k).map(|hv| hv == v).unwrap_or(false)
             });
-            
+
             if headers_match {
-                // Check for traffic split
-                let splits = self.traffic_splits.read().await;
-                if let Some(split) = splits.get(&rule.service_id) {
-                    return Ok(split.select_service());
-                }
                 return Ok(rule.service_id.clone());
             }
         }
-        
+
         Err(crate::error::NetworkError::NoRouteFound {
             path: path.to_string(),
         }.into())
     }
-}
\ No newline at end of file
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_traffic_split_shift_and_metrics() {
+        let router = Router::new();
+        let service_id = ServiceId::new("api", "default");
+        router.add_traffic_split(service_id.clone(), TrafficSplit::canary("v1", "v2", 5)).await.unwrap();
+
+        // A request holds the split it started with across a weight shift
+        let in_flight = router.traffic_split(&service_id).await.unwrap();
+        router.shift_traffic(&service_id, &HashMap::from([("v1".to_string(), 0), ("v2".to_string(), 100)])).await.unwrap();
+        assert_eq!(in_flight.backends[1].weight, 5);
+        let current = router.traffic_split(&service_id).await.unwrap();
+        assert_eq!(current.select(|_| true).unwrap().name, "v2");
+
+        // Backends without instances are skipped, whatever their weight
+        assert_eq!(current.select(|b| b.name == "v1").unwrap().name, "v1");
+        assert!(current.select(|_| false).is_none());
+
+        let v2 = HashMap::from([(VERSION_LABEL.to_string(), "v2".to_string())]);
+        assert!(current.backends[1].matches(&v2) && !current.backends[0].matches(&v2));
+
+        assert!(router.shift_traffic(&service_id, &HashMap::from([("v3".to_string(), 1)])).await.is_err());
+        assert!(router.shift_traffic(&service_id, &HashMap::from([("v2".to_string(), 0)])).await.is_err());
+
+        router.record_split_result(&service_id, "v2", true, Duration::from_millis(10));
+        router.record_split_result(&service_id, "v2", false, Duration::from_millis(30));
+        let metrics = router.split_metrics(&service_id);
+        assert_eq!(metrics.len(), 1);
+        assert_eq!((metrics[0].requests, metrics[0].failures), (2, 1));
+        assert_eq!(metrics[0].success_rate, 0.5);
+        assert!((metrics[0].avg_latency_ms - 20.0).abs() < 1e-9);
+
+        router.remove_traffic_split(&service_id).await;
+        assert!(router.split_metrics(&service_id).is_empty());
+    }
+}
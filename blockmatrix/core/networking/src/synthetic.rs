@@ -0,0 +1,544 @@
+//! Synthetic monitoring
+//!
+//! A synthetic probe is a short script of requests (the steps) run against
+//! a service on a schedule from chosen locations, typically edge nodes in
+//! each region. A run passes when every step does, in order, within the
+//! probe's timeout; its latency is the time the whole script took. Steps use
+//! the same probes as health checks, so a script can mix HTTP, gRPC, TCP,
+//! exec, STOQ ping and custom steps.
+//!
+//! Results are shared with the cluster over gossip and kept per probe and
+//! region, so every node can evaluate a probe's objectives from every
+//! region's point of view. Real traffic mostly comes from where a service
+//! works, which hides a region that cannot reach it; comparing regions
+//! against each other brings that out as a regional issue.
+
+use crate::error::{NetworkError, Result};
+use crate::health_check::HealthChecker;
+use crate::health_probe::{ProbeSpec, ProbeTarget};
+use nexus_shared::NodeId;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Gossip topic synthetic results are shared on
+pub const SYNTHETIC_RESULTS_TOPIC: &str = "mesh.synthetic-results";
+
+/// How often the scheduler looks for due probes
+pub(crate) const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+/// Where this node runs, as probes select locations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeLocation {
+    pub region: String,
+    /// Whether this is an edge node
+    #[serde(default)]
+    pub edge: bool,
+}
+
+/// One scripted request of a synthetic probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeStep {
+    pub name: String,
+    pub probe: ProbeSpec,
+}
+
+/// Objectives a synthetic probe is held to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SloTarget {
+    /// Share of runs that must pass (0-1)
+    pub availability: f64,
+    /// Latency passing runs must stay under at `latency_percentile`
+    pub latency: Duration,
+    #[serde(default = "default_latency_percentile")]
+    pub latency_percentile: f64,
+    /// Period the objectives are evaluated over
+    #[serde(default = "default_slo_window")]
+    pub window: Duration,
+}
+
+fn default_latency_percentile() -> f64 {
+    0.95
+}
+
+fn default_slo_window() -> Duration {
+    Duration::from_secs(3600)
+}
+
+/// A scripted request run periodically against a service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticProbe {
+    pub name: String,
+    /// Service the steps run against
+    pub service: String,
+    pub steps: Vec<ProbeStep>,
+    pub interval: Duration,
+    /// Time the whole script may take
+    pub timeout: Duration,
+    /// Regions that run the probe; empty for every region
+    #[serde(default)]
+    pub regions: Vec<String>,
+    /// Run only from edge nodes
+    #[serde(default = "default_edge_only")]
+    pub edge_only: bool,
+    #[serde(default)]
+    pub slo: Option<SloTarget>,
+}
+
+fn default_edge_only() -> bool {
+    true
+}
+
+impl SyntheticProbe {
+    /// Whether a node at `location` runs this probe
+    pub fn runs_at(&self, location: &ProbeLocation) -> bool {
+        (location.edge || !self.edge_only) && (self.regions.is_empty() || self.regions.contains(&location.region))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| NetworkError::Configuration {
+            message: format!("synthetic probe '{}': {}", self.name, message),
+        };
+        if self.name.is_empty() || self.service.is_empty() {
+            return Err(invalid("name and service must not be empty".to_string()));
+        }
+        if self.steps.is_empty() {
+            return Err(invalid("needs at least one step".to_string()));
+        }
+        if self.interval.is_zero() || self.timeout.is_zero() {
+            return Err(invalid("interval and timeout must be greater than zero".to_string()));
+        }
+        if self.timeout >= self.interval {
+            return Err(invalid(format!("timeout ({:?}) must be less than the interval ({:?})", self.timeout, self.interval)));
+        }
+        if let Some(slo) = &self.slo {
+            if !(slo.availability > 0.0 && slo.availability < 1.0) {
+                return Err(invalid("SLO availability must be in (0, 1)".to_string()));
+            }
+            if !(slo.latency_percentile > 0.0 && slo.latency_percentile <= 1.0) {
+                return Err(invalid("SLO latency percentile must be in (0, 1]".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Synthetic monitoring configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticConfig {
+    /// This node's location; a node without one runs no probes
+    pub location: Option<ProbeLocation>,
+    pub probes: Vec<SyntheticProbe>,
+    /// Results older than this are dropped
+    pub retention: Duration,
+    /// Most results kept per probe and region
+    pub max_results: usize,
+    /// Runs a region needs before its objectives are judged
+    pub min_samples: usize,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            location: None,
+            probes: Vec::new(),
+            retention: Duration::from_secs(24 * 3600),
+            max_results: 10_000,
+            min_samples: 10,
+        }
+    }
+}
+
+/// Outcome of one run of a synthetic probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticResult {
+    pub probe: String,
+    pub region: String,
+    pub node_id: NodeId,
+    pub success: bool,
+    pub latency: Duration,
+    /// Step that failed, if any
+    pub failed_step: Option<String>,
+    pub error: Option<String>,
+    pub at: SystemTime,
+}
+
+/// Results of a probe from one region, as metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticMetrics {
+    pub probe: String,
+    pub region: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub availability: f64,
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub last_run: Option<SystemTime>,
+}
+
+/// How a probe measures up to its objectives, from one region or all of them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SloEvaluation {
+    pub probe: String,
+    /// `None` for all regions together
+    pub region: Option<String>,
+    pub samples: usize,
+    pub availability: f64,
+    /// Latency of passing runs at the objective's percentile
+    pub latency: Option<Duration>,
+    pub availability_met: bool,
+    pub latency_met: bool,
+    /// Share of the error budget left; negative once overspent
+    pub error_budget_remaining: f64,
+}
+
+/// Objective a region misses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegionalIssueKind {
+    Availability,
+    Latency,
+}
+
+/// A region missing an objective that the other regions meet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionalIssue {
+    pub probe: String,
+    pub region: String,
+    pub kind: RegionalIssueKind,
+}
+
+/// Objectives of every probe with an SLO
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SloReport {
+    pub evaluations: Vec<SloEvaluation>,
+    pub regional_issues: Vec<RegionalIssue>,
+}
+
+/// Runs this node's synthetic probes and holds the cluster's results
+pub struct SyntheticMonitor {
+    node_id: NodeId,
+    config: SyntheticConfig,
+    probes: RwLock<BTreeMap<String, SyntheticProbe>>,
+    next_run: Mutex<HashMap<String, Instant>>,
+    /// Runs so far per probe, to rotate through the service's instances
+    runs: Mutex<HashMap<String, usize>>,
+    results: RwLock<HashMap<(String, String), VecDeque<SyntheticResult>>>,
+}
+
+impl SyntheticMonitor {
+    pub fn new(node_id: NodeId, config: SyntheticConfig) -> Self {
+        let probes = config.probes.iter().map(|probe| (probe.name.clone(), probe.clone())).collect();
+        Self {
+            node_id,
+            config,
+            probes: RwLock::new(probes),
+            next_run: Mutex::new(HashMap::new()),
+            runs: Mutex::new(HashMap::new()),
+            results: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Add or replace a probe
+    pub fn add_probe(&self, probe: SyntheticProbe) -> Result<()> {
+        probe.validate()?;
+        self.next_run.lock().remove(&probe.name);
+        self.probes.write().insert(probe.name.clone(), probe);
+        Ok(())
+    }
+
+    /// Remove a probe; its results stay until they age out
+    pub fn remove_probe(&self, name: &str) -> Option<SyntheticProbe> {
+        self.next_run.lock().remove(name);
+        self.probes.write().remove(name)
+    }
+
+    pub fn probes(&self) -> Vec<SyntheticProbe> {
+        self.probes.read().values().cloned().collect()
+    }
+
+    /// Probes this node runs whose next run is due
+    pub fn due(&self, now: Instant) -> Vec<SyntheticProbe> {
+        let Some(location) = &self.config.location else {
+            return Vec::new();
+        };
+        let mut next_run = self.next_run.lock();
+        self.probes
+            .read()
+            .values()
+            .filter(|probe| probe.runs_at(location))
+            .filter(|probe| {
+                let next = next_run.entry(probe.name.clone()).or_insert(now);
+                if *next > now {
+                    return false;
+                }
+                *next = now + probe.interval;
+                true
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Run a probe's steps against one of `targets`, the service's instances
+    pub async fn run(&self, probe: &SyntheticProbe, checker: &HealthChecker, targets: &[ProbeTarget]) -> SyntheticResult {
+        let region = self.config.location.as_ref().map(|l| l.region.clone()).unwrap_or_default();
+        let started = Instant::now();
+        let deadline = started + probe.timeout;
+        let mut result = SyntheticResult {
+            probe: probe.name.clone(),
+            region,
+            node_id: self.node_id,
+            success: true,
+            latency: Duration::ZERO,
+            failed_step: None,
+            error: None,
+            at: SystemTime::now(),
+        };
+
+        let run = {
+            let mut runs = self.runs.lock();
+            let run = runs.entry(probe.name.clone()).or_default();
+            *run += 1;
+            *run
+        };
+        if targets.is_empty() {
+            result.success = false;
+            result.error = Some(format!("no instances of {}", probe.service));
+            return result;
+        }
+        let target = &targets[run % targets.len()];
+
+        for step in &probe.steps {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let outcome = match checker.probe_for(&step.probe) {
+                Ok(_) if remaining.is_zero() => Err(NetworkError::Timeout { duration_ms: probe.timeout.as_millis() as u64 }),
+                Ok(step_probe) => step_probe.probe(target, remaining).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = outcome {
+                result.success = false;
+                result.failed_step = Some(step.name.clone());
+                result.error = Some(e.to_string());
+                break;
+            }
+        }
+        result.latency = started.elapsed();
+        result
+    }
+
+    /// Keep a result, from this node or shared by another
+    pub fn record(&self, result: SyntheticResult) {
+        let cutoff = SystemTime::now().checked_sub(self.config.retention).unwrap_or(SystemTime::UNIX_EPOCH);
+        if result.at < cutoff {
+            return;
+        }
+        let mut results = self.results.write();
+        let series = results.entry((result.probe.clone(), result.region.clone())).or_default();
+        series.push_back(result);
+        while series.len() > self.config.max_results || series.front().is_some_and(|r| r.at < cutoff) {
+            series.pop_front();
+        }
+    }
+
+    /// Results of a probe from every region since `since`
+    fn window(&self, probe: &str, since: SystemTime) -> BTreeMap<String, Vec<SyntheticResult>> {
+        self.results
+            .read()
+            .iter()
+            .filter(|((name, _), _)| name == probe)
+            .map(|((_, region), series)| (region.clone(), series.iter().filter(|r| r.at >= since).cloned().collect()))
+            .collect()
+    }
+
+    /// Availability and latency of every probe, per region
+    pub fn metrics(&self) -> Vec<SyntheticMetrics> {
+        let results = self.results.read();
+        let mut metrics: Vec<SyntheticMetrics> = results
+            .iter()
+            .map(|((probe, region), series)| {
+                let series: Vec<&SyntheticResult> = series.iter().collect();
+                let failures = series.iter().filter(|r| !r.success).count();
+                let latencies = passing_latencies(&series);
+                SyntheticMetrics {
+                    probe: probe.clone(),
+                    region: region.clone(),
+                    runs: series.len(),
+                    failures,
+                    availability: availability(series.len(), failures),
+                    p50_latency_ms: percentile(&latencies, 0.5).map(|l| l.as_secs_f64() * 1000.0),
+                    p95_latency_ms: percentile(&latencies, 0.95).map(|l| l.as_secs_f64() * 1000.0),
+                    last_run: series.last().map(|r| r.at),
+                }
+            })
+            .collect();
+        metrics.sort_by(|a, b| (&a.probe, &a.region).cmp(&(&b.probe, &b.region)));
+        metrics
+    }
+
+    /// Evaluate every probe with an SLO over its window
+    ///
+    /// Each probe is judged per region and across all regions. A region that
+    /// misses an objective the other regions together meet is a regional issue.
+    pub fn evaluate(&self, now: SystemTime) -> SloReport {
+        let mut report = SloReport::default();
+        for probe in self.probes.read().values() {
+            let Some(slo) = &probe.slo else {
+                continue;
+            };
+            let since = now.checked_sub(slo.window).unwrap_or(SystemTime::UNIX_EPOCH);
+            let by_region = self.window(&probe.name, since);
+
+            let all: Vec<&SyntheticResult> = by_region.values().flatten().collect();
+            if all.len() >= self.config.min_samples {
+                report.evaluations.push(evaluate(&probe.name, None, slo, &all));
+            }
+
+            for (region, results) in &by_region {
+                if results.len() < self.config.min_samples {
+                    continue;
+                }
+                let evaluation = evaluate(&probe.name, Some(region), slo, &results.iter().collect::<Vec<_>>());
+
+                let elsewhere: Vec<&SyntheticResult> = by_region
+                    .iter()
+                    .filter(|(other, _)| *other != region)
+                    .flat_map(|(_, results)| results)
+                    .collect();
+                if elsewhere.len() >= self.config.min_samples {
+                    let others = evaluate(&probe.name, None, slo, &elsewhere);
+                    for (kind, here, there) in [
+                        (RegionalIssueKind::Availability, evaluation.availability_met, others.availability_met),
+                        (RegionalIssueKind::Latency, evaluation.latency_met, others.latency_met),
+                    ] {
+                        if !here && there {
+                            report.regional_issues.push(RegionalIssue {
+                                probe: probe.name.clone(),
+                                region: region.clone(),
+                                kind,
+                            });
+                        }
+                    }
+                }
+                report.evaluations.push(evaluation);
+            }
+        }
+        report
+    }
+}
+
+fn availability(runs: usize, failures: usize) -> f64 {
+    if runs == 0 {
+        return 1.0;
+    }
+    (runs - failures) as f64 / runs as f64
+}
+
+fn passing_latencies(results: &[&SyntheticResult]) -> Vec<Duration> {
+    let mut latencies: Vec<Duration> = results.iter().filter(|r| r.success).map(|r| r.latency).collect();
+    latencies.sort();
+    latencies
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    Some(sorted[rank - 1])
+}
+
+fn evaluate(probe: &str, region: Option<&String>, slo: &SloTarget, results: &[&SyntheticResult]) -> SloEvaluation {
+    let failures = results.iter().filter(|r| !r.success).count();
+    let availability = availability(results.len(), failures);
+    let latency = percentile(&passing_latencies(results), slo.latency_percentile);
+    let allowed_failures = results.len() as f64 * (1.0 - slo.availability);
+    SloEvaluation {
+        probe: probe.to_string(),
+        region: region.cloned(),
+        samples: results.len(),
+        availability,
+        latency,
+        availability_met: availability >= slo.availability,
+        latency_met: latency.is_none_or(|latency| latency <= slo.latency),
+        error_budget_remaining: 1.0 - failures as f64 / allowed_failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe() -> SyntheticProbe {
+        SyntheticProbe {
+            name: "checkout".to_string(),
+            service: "api".to_string(),
+            steps: vec![ProbeStep { name: "connect".to_string(), probe: ProbeSpec::Tcp }],
+            interval: Duration::from_secs(60),
+            timeout: Duration::from_secs(5),
+            regions: vec!["eu".to_string(), "us".to_string()],
+            edge_only: true,
+            slo: Some(SloTarget {
+                availability: 0.9,
+                latency: Duration::from_millis(200),
+                latency_percentile: 0.95,
+                window: Duration::from_secs(3600),
+            }),
+        }
+    }
+
+    fn result(region: &str, success: bool, latency_ms: u64) -> SyntheticResult {
+        SyntheticResult {
+            probe: "checkout".to_string(),
+            region: region.to_string(),
+            node_id: NodeId::random(),
+            success,
+            latency: Duration::from_millis(latency_ms),
+            failed_step: None,
+            error: None,
+            at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_detects_regional_reachability_problem() {
+        let location = ProbeLocation { region: "eu".to_string(), edge: true };
+        let monitor = SyntheticMonitor::new(NodeId::random(), SyntheticConfig {
+            location: Some(location.clone()),
+            probes: vec![probe()],
+            min_samples: 10,
+            ..SyntheticConfig::default()
+        });
+        assert!(probe().runs_at(&location));
+        assert!(!probe().runs_at(&ProbeLocation { region: "eu".to_string(), edge: false }));
+        assert!(!probe().runs_at(&ProbeLocation { region: "ap".to_string(), edge: true }));
+
+        // Due once per interval
+        let now = Instant::now();
+        assert_eq!(monitor.due(now).len(), 1);
+        assert!(monitor.due(now + Duration::from_secs(30)).is_empty());
+        assert_eq!(monitor.due(now + Duration::from_secs(60)).len(), 1);
+
+        // Europe fails half its runs while the US is fine
+        for i in 0..20 {
+            monitor.record(result("eu", i % 2 == 0, 50));
+            monitor.record(result("us", true, 40));
+        }
+        let report = monitor.evaluate(SystemTime::now());
+        assert_eq!(report.regional_issues, vec![RegionalIssue {
+            probe: "checkout".to_string(),
+            region: "eu".to_string(),
+            kind: RegionalIssueKind::Availability,
+        }]);
+        let global = report.evaluations.iter().find(|e| e.region.is_none()).unwrap();
+        assert_eq!(global.samples, 40);
+        assert!(global.availability_met);
+        let eu = report.evaluations.iter().find(|e| e.region.as_deref() == Some("eu")).unwrap();
+        assert_eq!(eu.availability, 0.5);
+        assert!(eu.error_budget_remaining < 0.0);
+
+        let metrics = monitor.metrics();
+        assert_eq!((metrics[0].region.as_str(), metrics[0].failures), ("eu", 10));
+        assert_eq!(metrics[1].p95_latency_ms, Some(40.0));
+    }
+}
@@ -0,0 +1,446 @@
+//! Traffic policy objects for live load balancing configuration
+//!
+//! A [`TrafficPolicy`] describes how the mesh talks to one service: the
+//! load balancing strategy, retry policy, request timeout and outlier
+//! detection settings. Policies are stored in the state manager under
+//! [`TRAFFIC_POLICY_PREFIX`]; every node runs a [`TrafficPolicyWatcher`]
+//! that applies changes as they are committed, so tuning mesh behaviour
+//! never requires redeploying services or editing node configs.
+
+use crate::error::{NetworkError, Result};
+use crate::load_balancing::{LoadBalancer, LoadBalancingStrategy};
+use crate::outlier_detection::OutlierDetectionConfig;
+use nexus_shared::ServiceId;
+use nexus_state::StateManager;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+
+/// State store prefix under which traffic policies live
+pub const TRAFFIC_POLICY_PREFIX: &str = "/traffic-policies/";
+
+/// Per-service traffic policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficPolicy {
+    pub service_id: ServiceId,
+    pub strategy: LoadBalancingStrategy,
+    pub retry: RetryPolicy,
+    pub timeout: Duration,
+    /// Outlier detection for this service; the node's settings when unset
+    #[serde(default)]
+    pub outlier_detection: Option<OutlierDetectionConfig>,
+}
+
+/// Retry behaviour for requests to a service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    #[serde(default)]
+    pub backoff_strategy: BackoffStrategy,
+    /// Failures worth another attempt
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryOn>,
+    /// Timeout of each attempt; the policy timeout when unset
+    #[serde(default)]
+    pub per_try_timeout: Option<Duration>,
+}
+
+/// How the wait between attempts grows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackoffStrategy {
+    /// Always `base_backoff`
+    Fixed,
+    /// Doubling from `base_backoff` up to `max_backoff`
+    #[default]
+    Exponential,
+    /// A random wait up to the exponential one, so clients spread out
+    ExponentialJitter,
+}
+
+/// Classes of failure a retry policy can retry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetryOn {
+    /// The instance could not be reached
+    ConnectFailure,
+    /// The attempt ran out of time
+    Timeout,
+    /// The instance failed the request
+    RequestFailed,
+    /// The instance shed load or is briefly unavailable
+    Unavailable,
+}
+
+impl RetryOn {
+    /// Class of a request error, if it is retryable at all
+    pub fn classify(error: &NetworkError) -> Option<Self> {
+        match error {
+            NetworkError::ConnectionFailed { .. } | NetworkError::Io(_) => Some(Self::ConnectFailure),
+            NetworkError::Timeout { .. } => Some(Self::Timeout),
+            NetworkError::RequestFailed { .. } => Some(Self::RequestFailed),
+            NetworkError::RateLimitExceeded { .. }
+            | NetworkError::NoHealthyInstances { .. }
+            | NetworkError::ActivationTimeout { .. } => Some(Self::Unavailable),
+            _ => None,
+        }
+    }
+}
+
+fn default_retry_on() -> Vec<RetryOn> {
+    vec![RetryOn::ConnectFailure, RetryOn::Timeout, RetryOn::RequestFailed, RetryOn::Unavailable]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            backoff_strategy: BackoffStrategy::default(),
+            retry_on: default_retry_on(),
+            per_try_timeout: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before the given retry attempt (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = || {
+            let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+            self.base_backoff.saturating_mul(factor).min(self.max_backoff)
+        };
+        match self.backoff_strategy {
+            BackoffStrategy::Fixed => self.base_backoff,
+            BackoffStrategy::Exponential => exponential(),
+            BackoffStrategy::ExponentialJitter => {
+                use rand::Rng;
+                exponential().mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+            }
+        }
+    }
+
+    /// Whether a failed attempt may be tried again under this policy
+    pub fn should_retry(&self, error: &NetworkError) -> bool {
+        RetryOn::classify(error).is_some_and(|class| self.retry_on.contains(&class))
+    }
+
+    /// Timeout of each attempt, never longer than the whole request's
+    pub fn attempt_timeout(&self, request_timeout: Duration) -> Duration {
+        self.per_try_timeout.map_or(request_timeout, |t| t.min(request_timeout))
+    }
+}
+
+impl TrafficPolicy {
+    /// Policy with mesh defaults for a service
+    pub fn new(service_id: ServiceId) -> Self {
+        Self {
+            service_id,
+            strategy: LoadBalancingStrategy::RoundRobin,
+            retry: RetryPolicy::default(),
+            timeout: Duration::from_secs(30),
+            outlier_detection: None,
+        }
+    }
+
+    /// State store key for this policy
+    pub fn key(&self) -> String {
+        policy_key(&self.service_id)
+    }
+
+    /// Reject policies that would break routing
+    pub fn validate(&self) -> Result<()> {
+        if self.timeout.is_zero() {
+            return Err(NetworkError::Configuration {
+                message: format!("traffic policy for {}: timeout must be greater than 0", self.service_id),
+            });
+        }
+        if self.retry.base_backoff > self.retry.max_backoff {
+            return Err(NetworkError::Configuration {
+                message: format!("traffic policy for {}: base_backoff exceeds max_backoff", self.service_id),
+            });
+        }
+        if self.retry.per_try_timeout.is_some_and(|t| t.is_zero()) {
+            return Err(NetworkError::Configuration {
+                message: format!("traffic policy for {}: per_try_timeout must be greater than 0", self.service_id),
+            });
+        }
+        if let Some(outliers) = &self.outlier_detection {
+            if outliers.max_ejection_percent > 100 {
+                return Err(NetworkError::Configuration {
+                    message: format!("traffic policy for {}: max_ejection_percent must be <= 100", self.service_id),
+                });
+            }
+            if outliers.enabled && outliers.consecutive_failures == 0 {
+                return Err(NetworkError::Configuration {
+                    message: format!("traffic policy for {}: consecutive_failures must be greater than 0", self.service_id),
+                });
+            }
+            if !(outliers.latency_percentile > 0.0 && outliers.latency_percentile <= 1.0) {
+                return Err(NetworkError::Configuration {
+                    message: format!("traffic policy for {}: latency_percentile must be in (0, 1]", self.service_id),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// State store key for a service's policy
+pub fn policy_key(service_id: &ServiceId) -> String {
+    format!("{}{}/{}", TRAFFIC_POLICY_PREFIX, service_id.namespace(), service_id.name())
+}
+
+/// Change notifications emitted when policies are applied or removed
+#[derive(Debug, Clone)]
+pub enum TrafficPolicyEvent {
+    Applied(TrafficPolicy),
+    Removed(ServiceId),
+}
+
+/// Active traffic policies on this node
+pub struct TrafficPolicyStore {
+    policies: RwLock<HashMap<ServiceId, TrafficPolicy>>,
+    load_balancer: Arc<LoadBalancer>,
+    events: broadcast::Sender<TrafficPolicyEvent>,
+}
+
+impl TrafficPolicyStore {
+    pub fn new(load_balancer: Arc<LoadBalancer>) -> Self {
+        let (events, _) = broadcast::channel(1024);
+        Self {
+            policies: RwLock::new(HashMap::new()),
+            load_balancer,
+            events,
+        }
+    }
+
+    /// Policy for a service, falling back to mesh defaults
+    pub async fn get(&self, service_id: &ServiceId) -> TrafficPolicy {
+        self.policies.read().await
+            .get(service_id)
+            .cloned()
+            .unwrap_or_else(|| TrafficPolicy::new(service_id.clone()))
+    }
+
+    /// All explicitly configured policies
+    pub async fn list(&self) -> Vec<TrafficPolicy> {
+        self.policies.read().await.values().cloned().collect()
+    }
+
+    /// Apply a policy to this node
+    pub async fn apply(&self, policy: TrafficPolicy) -> Result<()> {
+        policy.validate()?;
+
+        let unchanged = self.policies.read().await.get(&policy.service_id) == Some(&policy);
+        if unchanged {
+            return Ok(());
+        }
+
+        self.load_balancer.set_strategy(&policy.service_id, policy.strategy.clone()).await;
+        self.load_balancer.set_outlier_detection(&policy.service_id, policy.outlier_detection.clone());
+        self.policies.write().await.insert(policy.service_id.clone(), policy.clone());
+
+        tracing::info!("Applied traffic policy for {}: {:?}", policy.service_id, policy.strategy);
+        let _ = self.events.send(TrafficPolicyEvent::Applied(policy));
+        Ok(())
+    }
+
+    /// Remove a policy, reverting the service to mesh defaults
+    pub async fn remove(&self, service_id: &ServiceId) {
+        if self.policies.write().await.remove(service_id).is_some() {
+            let default_strategy = self.load_balancer.default_strategy();
+            self.load_balancer.set_strategy(service_id, default_strategy).await;
+            self.load_balancer.set_outlier_detection(service_id, None);
+
+            tracing::info!("Removed traffic policy for {}", service_id);
+            let _ = self.events.send(TrafficPolicyEvent::Removed(service_id.clone()));
+        }
+    }
+
+    /// Subscribe to policy changes
+    pub fn subscribe(&self) -> broadcast::Receiver<TrafficPolicyEvent> {
+        self.events.subscribe()
+    }
+}
+
+/// Writes traffic policy objects to the cluster state
+pub struct TrafficPolicyApi {
+    state_manager: Arc<StateManager>,
+}
+
+impl TrafficPolicyApi {
+    pub fn new(state_manager: Arc<StateManager>) -> Self {
+        Self { state_manager }
+    }
+
+    /// Create or replace the policy for a service
+    pub async fn put(&self, policy: &TrafficPolicy) -> Result<()> {
+        policy.validate()?;
+        let value = serde_json::to_vec(policy)?;
+        self.state_manager.set(&policy.key(), &value).await
+            .map_err(|e| NetworkError::Configuration { message: e.to_string() })
+    }
+
+    /// Read the stored policy for a service
+    pub async fn get(&self, service_id: &ServiceId) -> Result<Option<TrafficPolicy>> {
+        let value = self.state_manager.get(&policy_key(service_id)).await
+            .map_err(|e| NetworkError::Configuration { message: e.to_string() })?;
+        value.map(|bytes| serde_json::from_slice(&bytes)).transpose().map_err(Into::into)
+    }
+
+    /// Delete the stored policy for a service
+    pub async fn delete(&self, service_id: &ServiceId) -> Result<()> {
+        self.state_manager.delete(&policy_key(service_id)).await
+            .map(|_| ())
+            .map_err(|e| NetworkError::Configuration { message: e.to_string() })
+    }
+
+    /// All stored policies
+    pub async fn list(&self) -> Result<Vec<TrafficPolicy>> {
+        let keys = self.state_manager.list(TRAFFIC_POLICY_PREFIX, None).await
+            .map_err(|e| NetworkError::Configuration { message: e.to_string() })?;
+
+        let mut policies = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = self.state_manager.get(&key).await
+                .map_err(|e| NetworkError::Configuration { message: e.to_string() })?;
+            if let Some(bytes) = value {
+                policies.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        Ok(policies)
+    }
+}
+
+/// Keeps the local [`TrafficPolicyStore`] in sync with the cluster state
+pub struct TrafficPolicyWatcher {
+    api: TrafficPolicyApi,
+    store: Arc<TrafficPolicyStore>,
+}
+
+impl TrafficPolicyWatcher {
+    pub fn new(state_manager: Arc<StateManager>, store: Arc<TrafficPolicyStore>) -> Self {
+        Self {
+            api: TrafficPolicyApi::new(state_manager),
+            store,
+        }
+    }
+
+    /// Load existing policies, then apply changes until the state manager stops
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(e) = self.run().await {
+                tracing::error!("Traffic policy watcher stopped: {}", e);
+            }
+        })
+    }
+
+    async fn run(self) -> Result<()> {
+        // Subscribe before the initial load so no change slips in between
+        let mut watch = self.api.state_manager.watch(TRAFFIC_POLICY_PREFIX).await
+            .map_err(|e| NetworkError::Configuration { message: e.to_string() })?;
+
+        for policy in self.api.list().await? {
+            if let Err(e) = self.store.apply(policy).await {
+                tracing::warn!("Ignoring stored traffic policy: {}", e);
+            }
+        }
+
+        while let Some(change) = watch.recv().await
+            .map_err(|e| NetworkError::Configuration { message: e.to_string() })?
+        {
+            match change.new_value {
+                Some(value) => {
+                    match serde_json::from_slice::<TrafficPolicy>(&value) {
+                        Ok(policy) => {
+                            if let Err(e) = self.store.apply(policy).await {
+                                tracing::warn!("Rejected traffic policy {}: {}", change.key, e);
+                            }
+                        }
+                        Err(e) => tracing::warn!("Malformed traffic policy {}: {}", change.key, e),
+                    }
+                }
+                None => {
+                    if let Some(service_id) = service_id_from_key(&change.key) {
+                        self.store.remove(&service_id).await;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn service_id_from_key(key: &str) -> Option<ServiceId> {
+    let (namespace, name) = key.strip_prefix(TRAFFIC_POLICY_PREFIX)?.split_once('/')?;
+    Some(ServiceId::new(name, namespace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LoadBalancingConfig;
+
+    #[tokio::test]
+    async fn test_apply_and_remove_policy() {
+        let load_balancer = Arc::new(LoadBalancer::new(&LoadBalancingConfig::default()).unwrap());
+        let store = TrafficPolicyStore::new(load_balancer);
+        let service_id = ServiceId::new("api", "default");
+
+        let mut policy = TrafficPolicy::new(service_id.clone());
+        policy.strategy = LoadBalancingStrategy::LeastConnections;
+        policy.retry.max_retries = 1;
+        store.apply(policy.clone()).await.unwrap();
+
+        assert_eq!(store.get(&service_id).await, policy);
+
+        store.remove(&service_id).await;
+        assert_eq!(store.get(&service_id).await.retry.max_retries, 3);
+    }
+
+    #[test]
+    fn test_invalid_policy_rejected() {
+        let mut policy = TrafficPolicy::new(ServiceId::new("api", "default"));
+        policy.timeout = Duration::ZERO;
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn test_key_round_trip() {
+        let service_id = ServiceId::new("api", "payments");
+        let key = policy_key(&service_id);
+        assert_eq!(service_id_from_key(&key), Some(service_id));
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let retry = RetryPolicy::default();
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(3), Duration::from_millis(400));
+        assert_eq!(retry.backoff(20), retry.max_backoff);
+
+        let fixed = RetryPolicy { backoff_strategy: BackoffStrategy::Fixed, ..RetryPolicy::default() };
+        assert_eq!(fixed.backoff(3), fixed.base_backoff);
+        let jitter = RetryPolicy { backoff_strategy: BackoffStrategy::ExponentialJitter, ..RetryPolicy::default() };
+        assert!(jitter.backoff(3) <= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_retryable_error_classes() {
+        let retry = RetryPolicy {
+            retry_on: vec![RetryOn::Timeout],
+            per_try_timeout: Some(Duration::from_secs(2)),
+            ..RetryPolicy::default()
+        };
+        assert!(retry.should_retry(&NetworkError::Timeout { duration_ms: 100 }));
+        assert!(!retry.should_retry(&NetworkError::RequestFailed { message: "500".into() }));
+        assert!(!retry.should_retry(&NetworkError::Authentication { reason: "bad token".into() }));
+        assert_eq!(retry.attempt_timeout(Duration::from_secs(30)), Duration::from_secs(2));
+        assert_eq!(retry.attempt_timeout(Duration::from_secs(1)), Duration::from_secs(1));
+    }
+}
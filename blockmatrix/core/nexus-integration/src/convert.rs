@@ -0,0 +1,810 @@
+//! Kubernetes manifest conversion
+//!
+//! Translates common Kubernetes kinds (Deployment, Service, ConfigMap, Secret,
+//! Ingress) into Nexus objects: [`ServiceSpec`] for the integration layer,
+//! [`Workload`] for the scheduler and [`NetworkPolicy`] for the runtime.
+//! Anything that has no Nexus equivalent is collected in a
+//! [`ConversionReport`] instead of being silently dropped.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use nexus_runtime::networking::{NetworkPolicy, PolicyType, PortRange, TrafficAction, TrafficRule};
+use nexus_runtime::ResourceQuotas;
+use nexus_scheduler::workload::{Workload, WorkloadSpec, WorkloadType};
+use nexus_scheduler::{GatePhase, ReadinessGate, DEFAULT_SCHEDULER_NAME};
+use nexus_shared::ResourceId;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+
+use crate::{
+    IngressSpec, NetworkingSpec, PortSpec, Protocol, ResourceRequirements, ServiceSpec,
+};
+
+/// Kinds the converter understands
+pub const SUPPORTED_KINDS: &[&str] = &["Deployment", "Service", "ConfigMap", "Secret", "Ingress"];
+
+/// Result of converting a set of Kubernetes manifests
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ConversionOutput {
+    pub services: Vec<ServiceSpec>,
+    pub workloads: Vec<Workload>,
+    pub network_policies: Vec<NetworkPolicy>,
+    #[serde(skip)]
+    pub report: ConversionReport,
+}
+
+/// Fields and objects that could not be represented in Nexus
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ConversionReport {
+    pub unsupported: Vec<UnsupportedField>,
+}
+
+/// A single field or object skipped during conversion
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UnsupportedField {
+    pub kind: String,
+    pub name: String,
+    pub path: String,
+    pub reason: String,
+}
+
+impl ConversionReport {
+    fn push(&mut self, kind: &str, name: &str, path: impl Into<String>, reason: impl Into<String>) {
+        self.unsupported.push(UnsupportedField {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            path: path.into(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Check whether every field was converted
+    pub fn is_clean(&self) -> bool {
+        self.unsupported.is_empty()
+    }
+}
+
+/// Object header shared by all Kubernetes kinds
+struct Manifest {
+    kind: String,
+    name: String,
+    namespace: String,
+    labels: HashMap<String, String>,
+    body: Value,
+}
+
+/// Convert a (possibly multi-document) Kubernetes YAML stream
+pub fn convert_manifests(yaml: &str) -> Result<ConversionOutput> {
+    let mut manifests = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(yaml) {
+        let value = Value::deserialize(document).context("Invalid Kubernetes YAML document")?;
+        collect_manifests(value, &mut manifests)?;
+    }
+
+    let mut converter = Converter::default();
+    converter.convert(manifests)
+}
+
+fn collect_manifests(value: Value, out: &mut Vec<Manifest>) -> Result<()> {
+    if value.is_null() {
+        return Ok(());
+    }
+
+    let kind = value.get("kind")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("Manifest is missing 'kind'"))?
+        .to_string();
+
+    // `kubectl get -o yaml` emits a List wrapping the real objects
+    if kind == "List" {
+        if let Some(items) = value.get("items").and_then(Value::as_sequence) {
+            for item in items {
+                collect_manifests(item.clone(), out)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let metadata = value.get("metadata").cloned().unwrap_or(Value::Null);
+    let name = metadata.get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("{} is missing metadata.name", kind))?
+        .to_string();
+    let namespace = metadata.get("namespace")
+        .and_then(Value::as_str)
+        .unwrap_or("default")
+        .to_string();
+
+    out.push(Manifest {
+        kind,
+        name,
+        namespace,
+        labels: string_map(metadata.get("labels")),
+        body: value,
+    });
+    Ok(())
+}
+
+#[derive(Default)]
+struct Converter {
+    config_maps: HashMap<String, HashMap<String, String>>,
+    secrets: HashMap<String, HashMap<String, String>>,
+    /// Deployment each Service selects, by namespace and Service name
+    service_targets: HashMap<(String, String), String>,
+    report: ConversionReport,
+}
+
+impl Converter {
+    fn convert(&mut self, manifests: Vec<Manifest>) -> Result<ConversionOutput> {
+        // ConfigMaps and Secrets are resolved into environment variables, so
+        // they must be indexed before any Deployment is converted.
+        for manifest in &manifests {
+            match manifest.kind.as_str() {
+                "ConfigMap" => {
+                    let data = string_map(manifest.body.get("data"));
+                    self.report_binary_data(manifest);
+                    self.config_maps.insert(manifest.name.clone(), data);
+                }
+                "Secret" => {
+                    let data = self.decode_secret(manifest)?;
+                    self.secrets.insert(manifest.name.clone(), data);
+                }
+                _ => {}
+            }
+        }
+
+        let mut output = ConversionOutput::default();
+        let mut services: Vec<&Manifest> = Vec::new();
+        let mut ingresses: Vec<&Manifest> = Vec::new();
+
+        for manifest in &manifests {
+            match manifest.kind.as_str() {
+                "Deployment" => {
+                    let (service, workload) = self.convert_deployment(manifest)?;
+                    output.services.push(service);
+                    output.workloads.push(workload);
+                }
+                "Service" => services.push(manifest),
+                "Ingress" => ingresses.push(manifest),
+                "ConfigMap" | "Secret" => {}
+                other => self.report.push(other, &manifest.name, "", "unsupported kind"),
+            }
+        }
+
+        for service in services {
+            self.apply_service(service, &manifests, &mut output.services);
+        }
+
+        for ingress in ingresses {
+            if let Some(policy) = self.apply_ingress(ingress, &mut output.services) {
+                output.network_policies.push(policy);
+            }
+        }
+
+        output.report = std::mem::take(&mut self.report);
+        Ok(output)
+    }
+
+    fn convert_deployment(&mut self, manifest: &Manifest) -> Result<(ServiceSpec, Workload)> {
+        let spec = manifest.body.get("spec").cloned().unwrap_or(Value::Null);
+        self.report_unknown(manifest, &spec, "spec", &["replicas", "selector", "template"]);
+
+        let replicas = spec.get("replicas").and_then(Value::as_u64).unwrap_or(1) as u32;
+        let template = spec.get("template").cloned().unwrap_or(Value::Null);
+        let template_labels = string_map(template.get("metadata").and_then(|m| m.get("labels")));
+        let pod = template.get("spec").cloned().unwrap_or(Value::Null);
+        self.report_unknown(manifest, &pod, "spec.template.spec", &["containers", "schedulerName", "readinessGates"]);
+
+        let containers = pod.get("containers")
+            .and_then(Value::as_sequence)
+            .filter(|containers| !containers.is_empty())
+            .ok_or_else(|| anyhow!("Deployment {} has no containers", manifest.name))?;
+
+        if containers.len() > 1 {
+            self.report.push(
+                &manifest.kind,
+                &manifest.name,
+                "spec.template.spec.containers[1..]",
+                "only the first container is converted",
+            );
+        }
+
+        let container = &containers[0];
+        self.report_unknown(
+            manifest,
+            container,
+            "spec.template.spec.containers[0]",
+            &["name", "image", "ports", "env", "envFrom", "resources", "command", "args", "workingDir"],
+        );
+
+        let image = container.get("image")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Deployment {} container has no image", manifest.name))?
+            .to_string();
+        let environment = self.container_environment(manifest, container);
+        let resources = container_resources(container);
+
+        let mut ports = Vec::new();
+        for port in container.get("ports").and_then(Value::as_sequence).into_iter().flatten() {
+            let Some(number) = port.get("containerPort").and_then(Value::as_u64) else { continue };
+            let Some(number) = self.port_number(manifest, "spec.template.spec.containers[0].ports[].containerPort", number) else {
+                continue;
+            };
+            ports.push(PortSpec {
+                name: port.get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("port-{}", number)),
+                port: number,
+                target_port: number,
+                protocol: protocol(port.get("protocol")),
+            });
+        }
+
+        let mut command: Vec<String> = string_list(container.get("command"));
+        command.extend(string_list(container.get("args")));
+
+        let mut labels = manifest.labels.clone();
+        labels.extend(template_labels);
+
+        let id = ResourceId::new(manifest.namespace.clone(), manifest.name.clone(), "workload");
+        let workload = Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Interactive,
+            priority: 0,
+            spec: WorkloadSpec {
+                id,
+                name: manifest.name.clone(),
+                image: image.clone(),
+                replicas,
+                gpus: 0,
+                resources: ResourceQuotas {
+                    cpu_limit: resources.cpu_cores,
+                    memory_limit: resources.memory_mb * 1024 * 1024,
+                    cpu_cores: resources.cpu_cores,
+                    memory_mb: resources.memory_mb,
+                    ..Default::default()
+                },
+                labels,
+                workload_type: WorkloadType::Interactive,
+                command,
+                environment: environment.clone(),
+                working_dir: container.get("workingDir").and_then(Value::as_str).map(str::to_string),
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: Default::default(),
+                scheduling_gates: Vec::new(),
+                // Pod readiness gates only hold back readiness, never placement
+                readiness_gates: pod.get("readinessGates")
+                    .and_then(Value::as_sequence)
+                    .map(|gates| {
+                        gates.iter()
+                            .filter_map(|gate| gate.get("conditionType").and_then(Value::as_str))
+                            .map(|condition| ReadinessGate::new(condition, GatePhase::Ready))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                scheduler_name: pod.get("schedulerName")
+                    .and_then(Value::as_str)
+                    .unwrap_or(DEFAULT_SCHEDULER_NAME)
+                    .to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        };
+
+        let service = ServiceSpec {
+            name: manifest.name.clone(),
+            image,
+            replicas,
+            resources,
+            networking: NetworkingSpec {
+                ports,
+                ingress: None,
+                service_mesh: true,
+            },
+            environment,
+            volumes: Vec::new(),
+            metadata: Default::default(),
+        };
+
+        Ok((service, workload))
+    }
+
+    fn container_environment(&mut self, manifest: &Manifest, container: &Value) -> HashMap<String, String> {
+        let mut environment = HashMap::new();
+
+        for source in container.get("envFrom").and_then(Value::as_sequence).into_iter().flatten() {
+            if let Some(name) = source.get("configMapRef").and_then(|r| r.get("name")).and_then(Value::as_str) {
+                match self.config_maps.get(name) {
+                    Some(data) => environment.extend(data.clone()),
+                    None => self.report.push(&manifest.kind, &manifest.name, "envFrom.configMapRef", format!("ConfigMap '{}' not found in input", name)),
+                }
+            } else if let Some(name) = source.get("secretRef").and_then(|r| r.get("name")).and_then(Value::as_str) {
+                match self.secrets.get(name) {
+                    Some(data) => environment.extend(data.clone()),
+                    None => self.report.push(&manifest.kind, &manifest.name, "envFrom.secretRef", format!("Secret '{}' not found in input", name)),
+                }
+            }
+        }
+
+        for var in container.get("env").and_then(Value::as_sequence).into_iter().flatten() {
+            let Some(name) = var.get("name").and_then(Value::as_str) else { continue };
+
+            if let Some(value) = var.get("value") {
+                environment.insert(name.to_string(), scalar_to_string(value));
+                continue;
+            }
+
+            let value_from = var.get("valueFrom");
+            let resolved = if let Some(key_ref) = value_from.and_then(|v| v.get("configMapKeyRef")) {
+                lookup(&self.config_maps, key_ref)
+            } else if let Some(key_ref) = value_from.and_then(|v| v.get("secretKeyRef")) {
+                lookup(&self.secrets, key_ref)
+            } else {
+                None
+            };
+
+            match resolved {
+                Some(value) => {
+                    environment.insert(name.to_string(), value);
+                }
+                None => self.report.push(
+                    &manifest.kind,
+                    &manifest.name,
+                    format!("env.{}.valueFrom", name),
+                    "reference could not be resolved",
+                ),
+            }
+        }
+
+        environment
+    }
+
+    fn apply_service(&mut self, manifest: &Manifest, all: &[Manifest], services: &mut [ServiceSpec]) {
+        let spec = manifest.body.get("spec").cloned().unwrap_or(Value::Null);
+        self.report_unknown(manifest, &spec, "spec", &["selector", "ports", "type"]);
+
+        if let Some(service_type) = spec.get("type").and_then(Value::as_str) {
+            if service_type != "ClusterIP" {
+                self.report.push(&manifest.kind, &manifest.name, "spec.type", format!("{} is served through the mesh as ClusterIP", service_type));
+            }
+        }
+
+        let selector = string_map(spec.get("selector"));
+        let target = all.iter()
+            .filter(|m| m.kind == "Deployment" && m.namespace == manifest.namespace)
+            .find(|m| {
+                let labels = string_map(
+                    m.body.get("spec")
+                        .and_then(|s| s.get("template"))
+                        .and_then(|t| t.get("metadata"))
+                        .and_then(|meta| meta.get("labels")),
+                );
+                !selector.is_empty() && selector.iter().all(|(k, v)| labels.get(k) == Some(v))
+            })
+            .map(|m| m.name.clone());
+
+        let Some(target) = target else {
+            self.report.push(&manifest.kind, &manifest.name, "spec.selector", "no Deployment in input matches the selector");
+            return;
+        };
+        self.service_targets.insert((manifest.namespace.clone(), manifest.name.clone()), target.clone());
+
+        let Some(service) = services.iter_mut().find(|s| s.name == target) else { return };
+
+        let mut ports = Vec::new();
+        for port in spec.get("ports").and_then(Value::as_sequence).into_iter().flatten() {
+            let Some(number) = port.get("port").and_then(Value::as_u64) else { continue };
+            let Some(number) = self.port_number(manifest, "spec.ports[].port", number) else { continue };
+            let target_port = match port.get("targetPort") {
+                Some(Value::Number(n)) => match n.as_u64() {
+                    Some(n) => match self.port_number(manifest, "spec.ports[].targetPort", n) {
+                        Some(target_port) => target_port,
+                        None => continue,
+                    },
+                    None => number,
+                },
+                Some(Value::String(named)) => service.networking.ports.iter()
+                    .find(|p| &p.name == named)
+                    .map(|p| p.target_port)
+                    .unwrap_or(number),
+                _ => number,
+            };
+            ports.push(PortSpec {
+                name: port.get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("port-{}", number)),
+                port: number,
+                target_port,
+                protocol: protocol(port.get("protocol")),
+            });
+        }
+
+        if !ports.is_empty() {
+            service.networking.ports = ports;
+        }
+    }
+
+    fn apply_ingress(&mut self, manifest: &Manifest, services: &mut [ServiceSpec]) -> Option<NetworkPolicy> {
+        let spec = manifest.body.get("spec").cloned().unwrap_or(Value::Null);
+        self.report_unknown(manifest, &spec, "spec", &["rules", "tls", "ingressClassName"]);
+
+        let tls = spec.get("tls").and_then(Value::as_sequence).map_or(false, |tls| !tls.is_empty());
+        let mut rules = Vec::new();
+
+        for rule in spec.get("rules").and_then(Value::as_sequence).into_iter().flatten() {
+            let host = rule.get("host").and_then(Value::as_str).unwrap_or("*").to_string();
+            let paths = rule.get("http").and_then(|h| h.get("paths")).and_then(Value::as_sequence);
+
+            for path in paths.into_iter().flatten() {
+                let backend = path.get("backend").and_then(|b| b.get("service"));
+                let Some(backend_name) = backend.and_then(|b| b.get("name")).and_then(Value::as_str) else {
+                    self.report.push(&manifest.kind, &manifest.name, "spec.rules[].http.paths[].backend", "only service backends are supported");
+                    continue;
+                };
+                let port = match backend.and_then(|b| b.get("port")).and_then(|p| p.get("number")).and_then(Value::as_u64) {
+                    Some(number) => match self.port_number(manifest, "spec.rules[].http.paths[].backend.service.port.number", number) {
+                        Some(port) => Some(port),
+                        None => continue,
+                    },
+                    None => None,
+                };
+
+                // The backend names a Service; its spec lives on the Deployment the Service selects
+                let key = (manifest.namespace.clone(), backend_name.to_string());
+                let Some(target) = self.service_targets.get(&key).cloned() else {
+                    self.report.push(&manifest.kind, &manifest.name, "spec.rules[].http.paths[].backend.service", format!("Service '{}' not found in input", backend_name));
+                    continue;
+                };
+                let Some(service) = services.iter_mut().find(|s| s.name == target) else { continue };
+
+                if service.networking.ingress.is_some() {
+                    self.report.push(&manifest.kind, &manifest.name, "spec.rules", format!("'{}' already has an ingress route; extra routes dropped", target));
+                } else {
+                    service.networking.ingress = Some(IngressSpec {
+                        host: host.clone(),
+                        path: path.get("path").and_then(Value::as_str).unwrap_or("/").to_string(),
+                        tls,
+                    });
+                }
+
+                let port = port.or_else(|| service.networking.ports.first().map(|p| p.port));
+                rules.push(TrafficRule {
+                    name: format!("{}-{}", host, backend_name),
+                    source: None,
+                    destination: Some(target),
+                    protocol: nexus_runtime::networking::Protocol::TCP,
+                    port_range: port.map(|p| PortRange { start: p, end: p }),
+                    action: TrafficAction::Allow,
+                });
+            }
+        }
+
+        if rules.is_empty() {
+            return None;
+        }
+
+        Some(NetworkPolicy {
+            name: format!("{}-ingress", manifest.name),
+            policy_type: PolicyType::Ingress,
+            rules,
+            priority: 100,
+            enabled: true,
+        })
+    }
+
+    /// A port number from a manifest, reported when it does not fit a port
+    fn port_number(&mut self, manifest: &Manifest, path: &str, number: u64) -> Option<u16> {
+        let port = u16::try_from(number).ok();
+        if port.is_none() {
+            self.report.push(&manifest.kind, &manifest.name, path, format!("{} is not a valid port", number));
+        }
+        port
+    }
+
+    fn decode_secret(&mut self, manifest: &Manifest) -> Result<HashMap<String, String>> {
+        let mut data = string_map(manifest.body.get("stringData"));
+
+        for (key, encoded) in string_map(manifest.body.get("data")) {
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(encoded.as_bytes())
+                .with_context(|| format!("Secret {} key '{}' is not valid base64", manifest.name, key))?;
+            match String::from_utf8(decoded) {
+                Ok(value) => {
+                    data.entry(key).or_insert(value);
+                }
+                Err(_) => self.report.push(&manifest.kind, &manifest.name, format!("data.{}", key), "binary secret values are not supported"),
+            }
+        }
+
+        Ok(data)
+    }
+
+    fn report_binary_data(&mut self, manifest: &Manifest) {
+        if manifest.body.get("binaryData").is_some() {
+            self.report.push(&manifest.kind, &manifest.name, "binaryData", "binary ConfigMap data is not supported");
+        }
+    }
+
+    fn report_unknown(&mut self, manifest: &Manifest, value: &Value, path: &str, known: &[&str]) {
+        let Some(mapping) = value.as_mapping() else { return };
+        for key in mapping.keys().filter_map(Value::as_str) {
+            if !known.contains(&key) {
+                self.report.push(&manifest.kind, &manifest.name, format!("{}.{}", path, key), "no Nexus equivalent");
+            }
+        }
+    }
+}
+
+fn container_resources(container: &Value) -> ResourceRequirements {
+    let resources = container.get("resources");
+    // Limits describe what the scheduler must reserve; fall back to requests.
+    let pick = |field: &str| {
+        resources.and_then(|r| r.get("limits")).and_then(|l| l.get(field))
+            .or_else(|| resources.and_then(|r| r.get("requests")).and_then(|l| l.get(field)))
+            .map(scalar_to_string)
+    };
+
+    let defaults = ServiceSpec::default().resources;
+    ResourceRequirements {
+        cpu_cores: pick("cpu").and_then(|cpu| parse_cpu(&cpu)).unwrap_or(defaults.cpu_cores),
+        memory_mb: pick("memory").and_then(|mem| parse_memory_mb(&mem)).unwrap_or(defaults.memory_mb),
+        storage_gb: pick("ephemeral-storage").and_then(|s| parse_memory_mb(&s)).map(|mb| (mb / 1024).max(1)),
+        gpu_count: pick("nvidia.com/gpu").and_then(|g| g.parse().ok()),
+    }
+}
+
+/// Parse a Kubernetes CPU quantity ("500m", "2", "0.25") into cores
+pub fn parse_cpu(quantity: &str) -> Option<f64> {
+    match quantity.strip_suffix('m') {
+        Some(millis) => millis.parse::<f64>().ok().map(|m| m / 1000.0),
+        None => quantity.parse().ok(),
+    }
+}
+
+/// Parse a Kubernetes memory quantity ("256Mi", "1Gi", "512M") into MiB
+pub fn parse_memory_mb(quantity: &str) -> Option<u64> {
+    const UNITS: &[(&str, f64)] = &[
+        ("Ki", 1.0 / 1024.0),
+        ("Mi", 1.0),
+        ("Gi", 1024.0),
+        ("Ti", 1024.0 * 1024.0),
+        ("K", 1_000.0 / (1024.0 * 1024.0)),
+        ("M", 1_000_000.0 / (1024.0 * 1024.0)),
+        ("G", 1_000_000_000.0 / (1024.0 * 1024.0)),
+    ];
+
+    for (suffix, factor) in UNITS {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<f64>().ok().map(|n| (n * factor).ceil() as u64);
+        }
+    }
+
+    // Plain bytes
+    quantity.parse::<f64>().ok().map(|bytes| (bytes / (1024.0 * 1024.0)).ceil() as u64)
+}
+
+fn protocol(value: Option<&Value>) -> Protocol {
+    match value.and_then(Value::as_str) {
+        Some("UDP") => Protocol::UDP,
+        _ => Protocol::TCP,
+    }
+}
+
+fn lookup(source: &HashMap<String, HashMap<String, String>>, key_ref: &Value) -> Option<String> {
+    let name = key_ref.get("name").and_then(Value::as_str)?;
+    let key = key_ref.get("key").and_then(Value::as_str)?;
+    source.get(name)?.get(key).cloned()
+}
+
+fn string_map(value: Option<&Value>) -> HashMap<String, String> {
+    value
+        .and_then(Value::as_mapping)
+        .map(|mapping: &Mapping| {
+            mapping.iter()
+                .filter_map(|(k, v)| Some((k.as_str()?.to_string(), scalar_to_string(v))))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_sequence)
+        .map(|items| items.iter().map(scalar_to_string).collect())
+        .unwrap_or_default()
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Null => String::new(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: web-config
+data:
+  LOG_LEVEL: debug
+---
+apiVersion: v1
+kind: Secret
+metadata:
+  name: web-secret
+data:
+  API_KEY: c2VjcmV0
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 3
+  strategy:
+    type: RollingUpdate
+  template:
+    metadata:
+      labels:
+        app: web
+    spec:
+      containers:
+        - name: web
+          image: nginx:1.25
+          ports:
+            - name: http
+              containerPort: 8080
+          envFrom:
+            - configMapRef:
+                name: web-config
+          env:
+            - name: API_KEY
+              valueFrom:
+                secretKeyRef:
+                  name: web-secret
+                  key: API_KEY
+          resources:
+            limits:
+              cpu: 500m
+              memory: 256Mi
+          livenessProbe:
+            httpGet:
+              path: /healthz
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: web
+spec:
+  selector:
+    app: web
+  ports:
+    - port: 80
+      targetPort: http
+---
+apiVersion: networking.k8s.io/v1
+kind: Ingress
+metadata:
+  name: web
+spec:
+  rules:
+    - host: web.example
+      http:
+        paths:
+          - path: /
+            backend:
+              service:
+                name: web
+                port:
+                  number: 80
+"#;
+
+    #[test]
+    fn test_convert_deployment_with_service_and_ingress() {
+        let output = convert_manifests(MANIFEST).unwrap();
+
+        assert_eq!(output.services.len(), 1);
+        let service = &output.services[0];
+        assert_eq!(service.replicas, 3);
+        assert_eq!(service.resources.cpu_cores, 0.5);
+        assert_eq!(service.resources.memory_mb, 256);
+        assert_eq!(service.environment.get("LOG_LEVEL").map(String::as_str), Some("debug"));
+        assert_eq!(service.environment.get("API_KEY").map(String::as_str), Some("secret"));
+        assert_eq!(service.networking.ports[0].port, 80);
+        assert_eq!(service.networking.ports[0].target_port, 8080);
+        assert_eq!(service.networking.ingress.as_ref().unwrap().host, "web.example");
+
+        assert_eq!(output.workloads.len(), 1);
+        assert_eq!(output.workloads[0].spec.replicas, 3);
+
+        assert_eq!(output.network_policies.len(), 1);
+        assert_eq!(output.network_policies[0].policy_type, PolicyType::Ingress);
+    }
+
+    #[test]
+    fn test_ingress_resolves_service_to_its_deployment() {
+        let manifest = r#"
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web-v2
+spec:
+  template:
+    metadata:
+      labels:
+        app: web
+    spec:
+      containers:
+        - name: web
+          image: nginx:1.25
+          ports:
+            - containerPort: 8080
+            - containerPort: 70000
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: frontend
+spec:
+  selector:
+    app: web
+  ports:
+    - port: 80
+      targetPort: 8080
+---
+apiVersion: networking.k8s.io/v1
+kind: Ingress
+metadata:
+  name: web
+spec:
+  rules:
+    - host: web.example
+      http:
+        paths:
+          - backend:
+              service:
+                name: frontend
+                port:
+                  number: 80
+"#;
+        let output = convert_manifests(manifest).unwrap();
+
+        let service = &output.services[0];
+        assert_eq!(service.name, "web-v2");
+        assert_eq!(service.networking.ingress.as_ref().unwrap().host, "web.example");
+        assert_eq!(output.network_policies[0].rules[0].destination.as_deref(), Some("web-v2"));
+
+        // Out-of-range ports are reported rather than truncated
+        assert_eq!(service.networking.ports.len(), 1);
+        assert!(output.report.unsupported.iter().any(|u| u.reason == "70000 is not a valid port"));
+    }
+
+    #[test]
+    fn test_unsupported_fields_are_reported() {
+        let output = convert_manifests(MANIFEST).unwrap();
+        let paths: Vec<&str> = output.report.unsupported.iter().map(|u| u.path.as_str()).collect();
+
+        assert!(paths.contains(&"spec.strategy"));
+        assert!(paths.contains(&"spec.template.spec.containers[0].livenessProbe"));
+    }
+
+    #[test]
+    fn test_parse_quantities() {
+        assert_eq!(parse_cpu("250m"), Some(0.25));
+        assert_eq!(parse_cpu("2"), Some(2.0));
+        assert_eq!(parse_memory_mb("1Gi"), Some(1024));
+        assert_eq!(parse_memory_mb("128Mi"), Some(128));
+    }
+}
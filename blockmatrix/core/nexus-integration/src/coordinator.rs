@@ -3,14 +3,20 @@
 use anyhow::Result;
 use dashmap::DashMap;
 use nexus_shared::*;
+use nexus_shared::api_meta::condition_types;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{info, warn, error, debug};
 
 use crate::{
     ServiceSpec, ServiceStatus, ServiceState, ServiceEndpoint, ResourceUsage, 
     Protocol, cluster, events, health
 };
+use crate::shutdown::{ShutdownConfig, ShutdownPhase, ShutdownReport, ShutdownSequence};
+use crate::supervisor::{BoxFuture, ComponentKind, Supervised, Supervisor, SupervisorConfig};
+use crate::{ComponentStates, ComponentStatus, SystemStatus};
 
 /// System coordinator that manages all Nexus components
 pub struct SystemCoordinator {
@@ -24,6 +30,9 @@ pub struct SystemCoordinator {
     networking: Arc<NetworkManager>,
     scheduler: Arc<SchedulerManager>,
     
+    // Startup ordering, health gating and restarts
+    supervisor: Arc<Supervisor>,
+    
     // Service tracking
     services: Arc<DashMap<String, ServiceStatus>>,
     
@@ -32,6 +41,12 @@ pub struct SystemCoordinator {
     
     // System state
     running: Arc<RwLock<bool>>,
+    
+    // Set once shutdown begins; new work is rejected
+    draining: Arc<AtomicBool>,
+    
+    // In-flight deploy/scale/delete operations
+    operations: Arc<parking_lot::Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl SystemCoordinator {
@@ -48,6 +63,13 @@ impl SystemCoordinator {
         // Create event channel
         let (event_sender, _) = broadcast::channel(1000);
 
+        let mut supervisor = Supervisor::new(SupervisorConfig::default(), event_sender.clone());
+        supervisor.register(transport.clone());
+        supervisor.register(state.clone());
+        supervisor.register(runtime.clone());
+        supervisor.register(networking.clone());
+        supervisor.register(scheduler.clone());
+
         Ok(Self {
             node_id,
             config: config.clone(),
@@ -56,9 +78,12 @@ impl SystemCoordinator {
             state,
             networking,
             scheduler,
+            supervisor: Arc::new(supervisor),
             services: Arc::new(DashMap::new()),
             event_sender,
             running: Arc::new(RwLock::new(false)),
+            draining: Arc::new(AtomicBool::new(false)),
+            operations: Arc::new(parking_lot::Mutex::new(Vec::new())),
         })
     }
 
@@ -68,21 +93,12 @@ impl SystemCoordinator {
         // Set running state
         *self.running.write().await = true;
 
-        // Start all component managers in dependency order
-        info!("1️⃣  Starting transport layer...");
-        self.transport.start().await?;
-
-        info!("2️⃣  Starting state manager...");
-        self.state.start().await?;
-
-        info!("3️⃣  Starting runtime manager...");
-        self.runtime.start().await?;
-
-        info!("4️⃣  Starting network manager...");
-        self.networking.start().await?;
-
-        info!("5️⃣  Starting scheduler...");
-        self.scheduler.start().await?;
+        // Start all component managers in dependency order, each gated on health
+        if let Err(e) = self.supervisor.start_all().await {
+            *self.running.write().await = false;
+            return Err(e);
+        }
+        self.supervisor.start_monitoring();
 
         // Send startup event
         let _ = self.event_sender.send(events::SystemEvent::SystemStarted {
@@ -95,36 +111,57 @@ impl SystemCoordinator {
     }
 
     pub async fn stop(&self) -> Result<()> {
-        info!("🛑 Stopping system coordinator...");
-
-        // Set running state
-        *self.running.write().await = false;
+        self.shutdown(&ShutdownConfig::default()).await;
+        Ok(())
+    }
 
-        // Stop components in reverse order
-        info!("5️⃣  Stopping scheduler...");
-        if let Err(e) = self.scheduler.stop().await {
-            warn!("Error stopping scheduler: {}", e);
-        }
+    /// Coordinated shutdown: each phase completes (or times out) before
+    /// the next begins, so no layer is stopped under one still using it
+    pub async fn shutdown(&self, config: &ShutdownConfig) -> ShutdownReport {
+        info!("🛑 Stopping system coordinator...");
 
-        info!("4️⃣  Stopping network manager...");
-        if let Err(e) = self.networking.stop().await {
-            warn!("Error stopping network manager: {}", e);
-        }
+        let mut sequence = ShutdownSequence::new(config.clone());
 
-        info!("3️⃣  Stopping runtime manager...");
-        if let Err(e) = self.runtime.stop().await {
-            warn!("Error stopping runtime manager: {}", e);
-        }
+        // Components are stopped deliberately from here on; don't restart them
+        self.supervisor.stop_monitoring();
 
-        info!("2️⃣  Stopping state manager...");
-        if let Err(e) = self.state.stop().await {
-            warn!("Error stopping state manager: {}", e);
-        }
+        sequence.run(ShutdownPhase::StopAcceptingWork, async {
+            self.draining.store(true, Ordering::SeqCst);
+            self.scheduler.stop().await?;
+            self.supervisor.mark_stopped(ComponentKind::Scheduler).await;
+            
+            // Let in-flight operations finish rather than cutting them off
+            let operations: Vec<JoinHandle<()>> = self.operations.lock().drain(..).collect();
+            debug!("Waiting for {} in-flight operations", operations.len());
+            for operation in operations {
+                if let Err(e) = operation.await {
+                    warn!("In-flight operation ended abnormally: {}", e);
+                }
+            }
+            Ok(())
+        }).await;
+
+        sequence.run(ShutdownPhase::StopWorkloads, async {
+            self.networking.stop().await?;
+            self.supervisor.mark_stopped(ComponentKind::Networking).await;
+            self.runtime.stop().await?;
+            self.supervisor.mark_stopped(ComponentKind::Runtime).await;
+            Ok(())
+        }).await;
+
+        sequence.run(ShutdownPhase::StopState, async {
+            self.state.stop().await?;
+            self.supervisor.mark_stopped(ComponentKind::StateManager).await;
+            Ok(())
+        }).await;
+
+        sequence.run(ShutdownPhase::StopTransports, async {
+            self.transport.stop().await?;
+            self.supervisor.mark_stopped(ComponentKind::Transport).await;
+            Ok(())
+        }).await;
 
-        info!("1️⃣  Stopping transport layer...");
-        if let Err(e) = self.transport.stop().await {
-            warn!("Error stopping transport layer: {}", e);
-        }
+        *self.running.write().await = false;
 
         // Send shutdown event
         let _ = self.event_sender.send(events::SystemEvent::SystemStopped {
@@ -132,12 +169,37 @@ impl SystemCoordinator {
             timestamp: chrono::Utc::now(),
         });
 
-        info!("👋 System coordinator stopped");
+        let report = sequence.finish();
+        if report.is_clean() {
+            info!("👋 System coordinator stopped in {:?}", report.elapsed());
+        } else {
+            warn!("👋 System coordinator stopped with incomplete phases in {:?}", report.elapsed());
+        }
+        report
+    }
+
+    /// Reject new work once shutdown has begun
+    fn ensure_accepting_work(&self) -> Result<()> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("System is shutting down, not accepting new work"));
+        }
         Ok(())
     }
 
-    pub async fn deploy_service(&self, spec: ServiceSpec) -> Result<ServiceStatus> {
+    /// Track a background operation so shutdown can wait for it
+    fn track(&self, operation: JoinHandle<()>) {
+        let mut operations = self.operations.lock();
+        operations.retain(|op| !op.is_finished());
+        operations.push(operation);
+    }
+
+    pub async fn deploy_service(&self, mut spec: ServiceSpec) -> Result<ServiceStatus> {
         info!("📦 Deploying service: {}", spec.name);
+        self.ensure_accepting_work()?;
+        if spec.metadata.generation == 0 {
+            spec.metadata = ApiMeta::created();
+        }
+        let generation = spec.metadata.generation;
 
         // Check if service already exists
         if self.services.contains_key(&spec.name) {
@@ -145,7 +207,7 @@ impl SystemCoordinator {
         }
 
         // Create service status
-        let service_status = ServiceStatus {
+        let mut service_status = ServiceStatus {
             name: spec.name.clone(),
             status: ServiceState::Pending,
             replicas: spec.replicas,
@@ -160,7 +222,11 @@ impl SystemCoordinator {
                 network_rx: 0,
                 storage_used: 0,
             },
+            metadata: spec.metadata.clone(),
+            conditions: StatusConditions::default(),
         };
+        service_status.conditions.set(condition_types::READY, ConditionStatus::False, "Deploying", "", generation);
+        service_status.conditions.set(condition_types::AVAILABLE, ConditionStatus::False, "Deploying", "", generation);
 
         // Store service status
         self.services.insert(spec.name.clone(), service_status.clone());
@@ -173,7 +239,7 @@ impl SystemCoordinator {
         });
 
         // Simulate deployment process
-        tokio::spawn({
+        let operation = tokio::spawn({
             let name = spec.name.clone();
             let services = self.services.clone();
             let scheduler = self.scheduler.clone();
@@ -186,6 +252,7 @@ impl SystemCoordinator {
                 debug!("📍 Scheduling placement for service: {}", name);
                 if let Err(e) = scheduler.schedule_service(&spec).await {
                     error!("Scheduling failed for {}: {}", name, e);
+                    record_failure(&services, &name, generation, "SchedulingFailed", e.to_string());
                     return;
                 }
 
@@ -193,6 +260,7 @@ impl SystemCoordinator {
                 debug!("🐳 Deploying containers for service: {}", name);
                 if let Err(e) = runtime.deploy_containers(&spec).await {
                     error!("Container deployment failed for {}: {}", name, e);
+                    record_failure(&services, &name, generation, "ContainerDeploymentFailed", e.to_string());
                     return;
                 }
 
@@ -202,6 +270,7 @@ impl SystemCoordinator {
                     Ok(endpoints) => endpoints,
                     Err(e) => {
                         error!("Networking setup failed for {}: {}", name, e);
+                        record_failure(&services, &name, generation, "NetworkingFailed", e.to_string());
                         return;
                     }
                 };
@@ -212,6 +281,10 @@ impl SystemCoordinator {
                     service.ready_replicas = spec.replicas;
                     service.updated_at = chrono::Utc::now();
                     service.endpoints = endpoints;
+                    service.conditions.set(condition_types::READY, ConditionStatus::True, "Deployed", "", generation);
+                    service.conditions.set(condition_types::AVAILABLE, ConditionStatus::True, "ReplicasReady", "", generation);
+                    service.conditions.observe(generation);
+                    service.metadata.status_changed();
                 }
 
                 // Send ready event
@@ -224,11 +297,14 @@ impl SystemCoordinator {
                 info!("✅ Service '{}' deployed successfully", name);
             }
         });
+        self.track(operation);
 
         Ok(service_status)
     }
 
     pub async fn scale_service(&self, name: &str, replicas: u32) -> Result<ServiceStatus> {
+        self.ensure_accepting_work()?;
+        
         let mut service = self.services.get_mut(name)
             .ok_or_else(|| anyhow::anyhow!("Service '{}' not found", name))?;
 
@@ -236,6 +312,15 @@ impl SystemCoordinator {
         service.replicas = replicas;
         service.status = ServiceState::Scaling;
         service.updated_at = chrono::Utc::now();
+        service.metadata.spec_changed();
+        let generation = service.metadata.generation;
+        service.conditions.set(
+            condition_types::AVAILABLE,
+            ConditionStatus::False,
+            "Scaling",
+            format!("scaling from {} to {} replicas", old_replicas, replicas),
+            generation,
+        );
 
         let service_status = service.clone();
         drop(service);
@@ -251,7 +336,7 @@ impl SystemCoordinator {
         info!("📊 Service '{}' scaling from {} to {} replicas", name, old_replicas, replicas);
 
         // Simulate scaling process
-        tokio::spawn({
+        let operation = tokio::spawn({
             let name = name.to_string();
             let services = self.services.clone();
             let scheduler = self.scheduler.clone();
@@ -264,11 +349,13 @@ impl SystemCoordinator {
                 // Update runtime and scheduler
                 if let Err(e) = scheduler.scale_service(&name, replicas).await {
                     error!("Scheduler scaling failed for {}: {}", name, e);
+                    record_failure(&services, &name, generation, "SchedulingFailed", e.to_string());
                     return;
                 }
 
                 if let Err(e) = runtime.scale_service(&name, replicas).await {
                     error!("Runtime scaling failed for {}: {}", name, e);
+                    record_failure(&services, &name, generation, "ScalingFailed", e.to_string());
                     return;
                 }
 
@@ -277,16 +364,22 @@ impl SystemCoordinator {
                     service.status = ServiceState::Running;
                     service.ready_replicas = replicas;
                     service.updated_at = chrono::Utc::now();
+                    service.conditions.set(condition_types::AVAILABLE, ConditionStatus::True, "ReplicasReady", "", generation);
+                    service.conditions.observe(generation);
+                    service.metadata.status_changed();
                 }
 
                 info!("✅ Service '{}' scaled successfully to {} replicas", name, replicas);
             }
         });
+        self.track(operation);
 
         Ok(service_status)
     }
 
     pub async fn delete_service(&self, name: &str) -> Result<()> {
+        self.ensure_accepting_work()?;
+        
         let service = self.services.remove(name)
             .ok_or_else(|| anyhow::anyhow!("Service '{}' not found", name))?
             .1;
@@ -300,7 +393,7 @@ impl SystemCoordinator {
         });
 
         // Cleanup in background
-        tokio::spawn({
+        let operation = tokio::spawn({
             let name = name.to_string();
             let networking = self.networking.clone();
             let runtime = self.runtime.clone();
@@ -325,6 +418,7 @@ impl SystemCoordinator {
                 info!("🧹 Service '{}' cleanup completed", name);
             }
         });
+        self.track(operation);
 
         Ok(())
     }
@@ -360,22 +454,69 @@ impl SystemCoordinator {
         let networking_health = self.networking.health().await;
         let scheduler_health = self.scheduler.health().await;
 
+        let components = vec![
+            transport_health,
+            runtime_health,
+            state_health,
+            networking_health,
+            scheduler_health,
+        ];
+
+        let overall_status = if components.iter().any(|c| c.status == health::HealthStatus::Critical) {
+            health::HealthStatus::Critical
+        } else if components.iter().all(|c| c.status == health::HealthStatus::Healthy) {
+            health::HealthStatus::Healthy
+        } else {
+            health::HealthStatus::Degraded
+        };
+
         health::HealthReport {
-            overall_status: health::HealthStatus::Healthy,
-            components: vec![
-                transport_health,
-                runtime_health,
-                state_health,
-                networking_health,
-                scheduler_health,
-            ],
+            overall_status,
+            components,
             timestamp: chrono::Utc::now(),
         }
     }
 
+    /// Per-component status as tracked by the supervisor
+    pub async fn component_states(&self) -> ComponentStates {
+        let status = |kind| async move {
+            self.supervisor.status(kind).await.unwrap_or(ComponentStatus::Stopped)
+        };
+
+        ComponentStates {
+            transport: status(ComponentKind::Transport).await,
+            runtime: status(ComponentKind::Runtime).await,
+            state_manager: status(ComponentKind::StateManager).await,
+            networking: status(ComponentKind::Networking).await,
+            scheduler: status(ComponentKind::Scheduler).await,
+        }
+    }
+
+    /// Overall system status derived from component statuses
+    pub async fn system_status(&self) -> SystemStatus {
+        self.supervisor.system_status().await
+    }
+
     pub async fn event_stream(&self) -> events::EventStream {
         events::EventStream::new(self.event_sender.subscribe())
     }
+
+    /// Receiver for system events sent from now on
+    pub fn event_receiver(&self) -> broadcast::Receiver<events::SystemEvent> {
+        self.event_sender.subscribe()
+    }
+}
+
+/// Record that reconciling `generation` of a service failed
+fn record_failure(services: &DashMap<String, ServiceStatus>, name: &str, generation: u64, reason: &str, message: String) {
+    if let Some(mut service) = services.get_mut(name) {
+        service.status = ServiceState::Failed;
+        service.updated_at = chrono::Utc::now();
+        service.conditions.set(condition_types::READY, ConditionStatus::False, reason, message.clone(), generation);
+        service.conditions.set(condition_types::AVAILABLE, ConditionStatus::False, reason, message, generation);
+        service.conditions.observe(generation);
+        service.metadata.status_changed();
+    }
 }
 
 // Component manager traits and implementations
@@ -386,6 +527,35 @@ trait ComponentManager {
     async fn health(&self) -> health::ComponentHealth;
 }
 
+/// Expose a component manager to the supervisor
+macro_rules! impl_supervised {
+    ($manager:ty, $kind:expr) => {
+        impl Supervised for $manager {
+            fn kind(&self) -> ComponentKind {
+                $kind
+            }
+
+            fn start(&self) -> BoxFuture<'_, Result<()>> {
+                Box::pin(ComponentManager::start(self))
+            }
+
+            fn stop(&self) -> BoxFuture<'_, Result<()>> {
+                Box::pin(ComponentManager::stop(self))
+            }
+
+            fn health(&self) -> BoxFuture<'_, health::ComponentHealth> {
+                Box::pin(ComponentManager::health(self))
+            }
+        }
+    };
+}
+
+impl_supervised!(TransportManager, ComponentKind::Transport);
+impl_supervised!(StateManager, ComponentKind::StateManager);
+impl_supervised!(RuntimeManager, ComponentKind::Runtime);
+impl_supervised!(NetworkManager, ComponentKind::Networking);
+impl_supervised!(SchedulerManager, ComponentKind::Scheduler);
+
 // Transport Manager
 pub struct TransportManager {
     node_id: NodeId,
@@ -103,6 +103,42 @@ pub enum SystemEvent {
     },
 }
 
+impl SystemEvent {
+    /// Snake-case event type name
+    pub fn name(&self) -> &'static str {
+        match self {
+            SystemEvent::SystemStarted { .. } => "system_started",
+            SystemEvent::SystemStopped { .. } => "system_stopped",
+            SystemEvent::ServiceDeployed { .. } => "service_deployed",
+            SystemEvent::ServiceReady { .. } => "service_ready",
+            SystemEvent::ServiceScaled { .. } => "service_scaled",
+            SystemEvent::ServiceDeleted { .. } => "service_deleted",
+            SystemEvent::NodeJoined { .. } => "node_joined",
+            SystemEvent::NodeLeft { .. } => "node_left",
+            SystemEvent::LeaderElected { .. } => "leader_elected",
+            SystemEvent::ComponentHealthChanged { .. } => "component_health_changed",
+            SystemEvent::ResourceAlert { .. } => "resource_alert",
+            SystemEvent::AuthenticationFailed { .. } => "authentication_failed",
+            SystemEvent::CertificateRotated { .. } => "certificate_rotated",
+            SystemEvent::NetworkPartition { .. } => "network_partition",
+            SystemEvent::ConnectionEstablished { .. } => "connection_established",
+        }
+    }
+
+    /// Whether the event belongs in the audit log: operator actions on
+    /// services and security events
+    pub fn is_audit(&self) -> bool {
+        matches!(
+            self,
+            SystemEvent::ServiceDeployed { .. }
+                | SystemEvent::ServiceScaled { .. }
+                | SystemEvent::ServiceDeleted { .. }
+                | SystemEvent::AuthenticationFailed { .. }
+                | SystemEvent::CertificateRotated { .. }
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ResourceType {
     CPU,
@@ -263,25 +299,7 @@ impl EventAggregator {
         let mut counts = std::collections::HashMap::new();
         
         for event in &self.events {
-            let event_type = match event {
-                SystemEvent::SystemStarted { .. } => "system_started",
-                SystemEvent::SystemStopped { .. } => "system_stopped",
-                SystemEvent::ServiceDeployed { .. } => "service_deployed",
-                SystemEvent::ServiceReady { .. } => "service_ready",
-                SystemEvent::ServiceScaled { .. } => "service_scaled",
-                SystemEvent::ServiceDeleted { .. } => "service_deleted",
-                SystemEvent::NodeJoined { .. } => "node_joined",
-                SystemEvent::NodeLeft { .. } => "node_left",
-                SystemEvent::LeaderElected { .. } => "leader_elected",
-                SystemEvent::ComponentHealthChanged { .. } => "component_health_changed",
-                SystemEvent::ResourceAlert { .. } => "resource_alert",
-                SystemEvent::AuthenticationFailed { .. } => "authentication_failed",
-                SystemEvent::CertificateRotated { .. } => "certificate_rotated",
-                SystemEvent::NetworkPartition { .. } => "network_partition",
-                SystemEvent::ConnectionEstablished { .. } => "connection_established",
-            };
-            
-            *counts.entry(event_type.to_string()).or_insert(0) += 1;
+            *counts.entry(event.name().to_string()).or_insert(0) += 1;
         }
         
         counts
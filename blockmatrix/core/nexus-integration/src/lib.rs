@@ -10,11 +10,17 @@ use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 
 pub mod cluster;
+pub mod convert;
 pub mod coordinator;
 pub mod events;
 pub mod health;
+pub mod readiness;
+pub mod shutdown;
+pub mod sinks;
+pub mod supervisor;
 
 use coordinator::SystemCoordinator;
+use shutdown::{ShutdownConfig, ShutdownReport};
 
 /// Main Nexus system that orchestrates all core components
 pub struct NexusSystem {
@@ -30,6 +36,8 @@ pub struct SystemState {
     pub status: SystemStatus,
     pub components: ComponentStates,
     pub metrics: SystemMetrics,
+    /// Algorithms the crypto policy allows, for audit
+    pub crypto: CryptoPolicyReport,
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
@@ -42,7 +50,7 @@ pub struct ComponentStates {
     pub scheduler: ComponentStatus,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ComponentStatus {
     Starting,
     Running,
@@ -75,6 +83,12 @@ impl NexusSystem {
     pub async fn new(config: NexusConfig, node_id: Option<NodeId>) -> Result<Self> {
         info!("🚀 Initializing Nexus System v{}", VERSION);
 
+        let report = config.validate_config();
+        for warning in report.warnings() {
+            warn!("⚠️  Config {}", warning);
+        }
+        report.into_result()?;
+
         let node_id = node_id.unwrap_or_else(NodeId::random);
         info!("🆔 Node ID: {}", node_id.to_hex());
 
@@ -98,6 +112,7 @@ impl NexusSystem {
                 cluster_nodes: 0,
                 uptime_seconds: 0,
             },
+            crypto: config.security.crypto_policy.report(),
             last_updated: chrono::Utc::now(),
         }));
 
@@ -117,18 +132,7 @@ impl NexusSystem {
         self.coordinator.start().await?;
 
         // Update system state
-        {
-            let mut state = self.state.write().await;
-            state.status = SystemStatus::Healthy;
-            state.components = ComponentStates {
-                transport: ComponentStatus::Running,
-                runtime: ComponentStatus::Running,
-                state_manager: ComponentStatus::Running,
-                networking: ComponentStatus::Running,
-                scheduler: ComponentStatus::Running,
-            };
-            state.last_updated = chrono::Utc::now();
-        }
+        self.refresh_state().await;
 
         info!("✅ Nexus system started successfully");
         Ok(())
@@ -136,26 +140,58 @@ impl NexusSystem {
 
     /// Stop all Nexus components gracefully
     pub async fn stop(&self) -> Result<()> {
+        self.shutdown(&ShutdownConfig::default()).await;
+        Ok(())
+    }
+
+    /// Run the coordinated shutdown sequence with the given phase timeouts
+    pub async fn shutdown(&self, config: &ShutdownConfig) -> ShutdownReport {
         info!("🛑 Stopping Nexus system...");
 
         // Update system state
         {
             let mut state = self.state.write().await;
             state.status = SystemStatus::Shutdown;
+            state.last_updated = chrono::Utc::now();
         }
 
-        // Stop coordinator which will stop all components
-        self.coordinator.stop().await?;
+        let report = self.coordinator.shutdown(config).await;
+        let components = self.coordinator.component_states().await;
+
+        {
+            let mut state = self.state.write().await;
+            state.components = components;
+            state.last_updated = chrono::Utc::now();
+        }
+
+        for phase in report.phases.iter().filter(|p| p.result != shutdown::PhaseResult::Completed) {
+            warn!("Shutdown phase '{}' did not complete: {:?}", phase.phase.name(), phase.result);
+        }
 
         info!("👋 Nexus system stopped");
-        Ok(())
+        report
     }
 
     /// Get current system status
     pub async fn status(&self) -> SystemState {
+        let status = self.state.read().await.status.clone();
+        if !matches!(status, SystemStatus::Initializing | SystemStatus::Shutdown) {
+            self.refresh_state().await;
+        }
         self.state.read().await.clone()
     }
 
+    /// Pull component statuses from the supervisor
+    async fn refresh_state(&self) {
+        let components = self.coordinator.component_states().await;
+        let status = self.coordinator.system_status().await;
+
+        let mut state = self.state.write().await;
+        state.status = status;
+        state.components = components;
+        state.last_updated = chrono::Utc::now();
+    }
+
     /// Get system health information
     pub async fn health(&self) -> health::HealthReport {
         self.coordinator.health_check().await
@@ -216,6 +252,11 @@ impl NexusSystem {
     pub async fn subscribe_events(&self) -> events::EventStream {
         self.coordinator.event_stream().await
     }
+
+    /// Forward system events to an external sink such as Kafka or NATS
+    pub fn forward_events(&self, config: sinks::SinkConfig, sink: Arc<dyn sinks::EventSink>) -> sinks::SinkHandle {
+        sinks::spawn_sink(config, sink, self.coordinator.event_receiver())
+    }
 }
 
 /// Service specification for deployment
@@ -228,6 +269,8 @@ pub struct ServiceSpec {
     pub networking: NetworkingSpec,
     pub environment: std::collections::HashMap<String, String>,
     pub volumes: Vec<VolumeSpec>,
+    #[serde(default)]
+    pub metadata: ApiMeta,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -286,6 +329,19 @@ pub struct ServiceStatus {
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub endpoints: Vec<ServiceEndpoint>,
     pub resource_usage: ResourceUsage,
+    /// Versions of the service object; `generation` follows spec changes
+    #[serde(default)]
+    pub metadata: ApiMeta,
+    /// `Ready` and `Available` as last observed by the coordinator
+    #[serde(default)]
+    pub conditions: StatusConditions,
+}
+
+impl ServiceStatus {
+    /// Whether the coordinator has acted on the latest spec change
+    pub fn is_observed(&self) -> bool {
+        self.conditions.is_current(&self.metadata)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -339,6 +395,7 @@ impl Default for ServiceSpec {
             },
             environment: std::collections::HashMap::new(),
             volumes: Vec::new(),
+            metadata: ApiMeta::default(),
         }
     }
 }
@@ -360,7 +417,7 @@ mod tests {
     async fn test_system_initialization() {
         let temp_dir = TempDir::new().unwrap();
         let mut config = NexusConfig::default();
-        config.data_dir = temp_dir.path().to_string_lossy().to_string();
+        config.node.data_dir = temp_dir.path().to_string_lossy().to_string();
 
         let system = NexusSystem::new(config, None).await.unwrap();
         let status = system.status().await;
@@ -368,6 +425,26 @@ mod tests {
         assert!(matches!(status.status, SystemStatus::Initializing));
     }
 
+    #[tokio::test]
+    async fn test_shutdown_rejects_new_work() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = NexusConfig::default();
+        config.node.data_dir = temp_dir.path().to_string_lossy().to_string();
+
+        let system = NexusSystem::new(config, None).await.unwrap();
+        system.start().await.unwrap();
+        system.deploy_service(ServiceSpec::default()).await.unwrap();
+
+        let report = system.shutdown(&ShutdownConfig::default()).await;
+        assert!(report.is_clean());
+        assert_eq!(report.phases.len(), shutdown::ShutdownPhase::ALL.len());
+        assert!(matches!(system.status().await.status, SystemStatus::Shutdown));
+
+        let mut spec = ServiceSpec::default();
+        spec.name = "late".to_string();
+        assert!(system.deploy_service(spec).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_service_spec_default() {
         let spec = ServiceSpec::default();
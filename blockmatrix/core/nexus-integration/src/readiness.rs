@@ -0,0 +1,54 @@
+//! Reporting container readiness to service discovery
+//!
+//! The runtime's probe manager decides when a container is ready; this sink
+//! marks the container's service instance on this node healthy or unhealthy
+//! so load balancers stop routing to it while it is not ready. Containers are
+//! matched to a service through their `hypermesh.io/service` and
+//! `hypermesh.io/namespace` labels; unlabelled containers are ignored.
+
+use futures::future::BoxFuture;
+use nexus_networking::{HealthStatus, ServiceDiscovery};
+use nexus_runtime::{ReadinessSink, RuntimeError};
+use nexus_shared::{NodeId, ResourceId, ServiceId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Label naming the service a container backs
+pub const SERVICE_LABEL: &str = "hypermesh.io/service";
+/// Label naming the service's namespace, `default` when absent
+pub const NAMESPACE_LABEL: &str = "hypermesh.io/namespace";
+
+/// Forwards readiness changes to service discovery
+pub struct MeshReadiness {
+    discovery: Arc<ServiceDiscovery>,
+    node_id: NodeId,
+}
+
+impl MeshReadiness {
+    pub fn new(discovery: Arc<ServiceDiscovery>, node_id: NodeId) -> Self {
+        Self { discovery, node_id }
+    }
+}
+
+impl ReadinessSink for MeshReadiness {
+    fn readiness_changed(
+        &self,
+        _container: ResourceId,
+        labels: HashMap<String, String>,
+        ready: bool,
+    ) -> BoxFuture<'static, nexus_runtime::Result<()>> {
+        let discovery = self.discovery.clone();
+        let node_id = self.node_id;
+        Box::pin(async move {
+            let Some(name) = labels.get(SERVICE_LABEL) else {
+                return Ok(());
+            };
+            let namespace = labels.get(NAMESPACE_LABEL).map(String::as_str).unwrap_or("default");
+            let status = if ready { HealthStatus::Healthy } else { HealthStatus::Unhealthy };
+            discovery
+                .update_service_health(&ServiceId::new(name.as_str(), namespace), node_id, status)
+                .await
+                .map_err(|e| RuntimeError::Network { message: e.to_string() })
+        })
+    }
+}
@@ -0,0 +1,183 @@
+//! Coordinated shutdown sequencing
+//!
+//! Components are stopped in phases so that no layer is torn down while a
+//! layer above it still depends on it: work intake stops and in-flight
+//! operations finish first, then networking and the runtime stop, then the
+//! state manager, and only then are transports closed. Every phase runs
+//! under its own timeout; a phase that fails or times out is logged and
+//! recorded, and the sequence moves on so a stuck component can't block the
+//! node from exiting.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Shutdown phases in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownPhase {
+    StopAcceptingWork,
+    StopWorkloads,
+    StopState,
+    StopTransports,
+}
+
+impl ShutdownPhase {
+    /// All phases in execution order
+    pub const ALL: [ShutdownPhase; 4] = [
+        ShutdownPhase::StopAcceptingWork,
+        ShutdownPhase::StopWorkloads,
+        ShutdownPhase::StopState,
+        ShutdownPhase::StopTransports,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShutdownPhase::StopAcceptingWork => "stop accepting work",
+            ShutdownPhase::StopWorkloads => "stop workloads",
+            ShutdownPhase::StopState => "stop state",
+            ShutdownPhase::StopTransports => "stop transports",
+        }
+    }
+}
+
+/// Per-phase shutdown timeouts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    pub stop_accepting_work: Duration,
+    pub stop_workloads: Duration,
+    pub stop_state: Duration,
+    pub stop_transports: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            stop_accepting_work: Duration::from_secs(30),
+            stop_workloads: Duration::from_secs(30),
+            stop_state: Duration::from_secs(15),
+            stop_transports: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    /// Timeout for a phase
+    pub fn timeout(&self, phase: ShutdownPhase) -> Duration {
+        match phase {
+            ShutdownPhase::StopAcceptingWork => self.stop_accepting_work,
+            ShutdownPhase::StopWorkloads => self.stop_workloads,
+            ShutdownPhase::StopState => self.stop_state,
+            ShutdownPhase::StopTransports => self.stop_transports,
+        }
+    }
+}
+
+/// How a phase ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PhaseResult {
+    Completed,
+    TimedOut,
+    Failed(String),
+}
+
+/// Outcome of one shutdown phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseOutcome {
+    pub phase: ShutdownPhase,
+    pub result: PhaseResult,
+    pub elapsed: Duration,
+}
+
+/// Outcome of a full shutdown sequence
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    pub phases: Vec<PhaseOutcome>,
+}
+
+impl ShutdownReport {
+    /// Whether every phase completed within its timeout
+    pub fn is_clean(&self) -> bool {
+        self.phases.iter().all(|p| p.result == PhaseResult::Completed)
+    }
+
+    /// Total time spent shutting down
+    pub fn elapsed(&self) -> Duration {
+        self.phases.iter().map(|p| p.elapsed).sum()
+    }
+}
+
+/// Runs shutdown phases in order, each under its timeout
+pub struct ShutdownSequence {
+    config: ShutdownConfig,
+    report: ShutdownReport,
+}
+
+impl ShutdownSequence {
+    pub fn new(config: ShutdownConfig) -> Self {
+        Self {
+            config,
+            report: ShutdownReport::default(),
+        }
+    }
+
+    /// Run a phase; failures and timeouts are recorded, never propagated
+    pub async fn run<F>(&mut self, phase: ShutdownPhase, work: F)
+    where
+        F: Future<Output = Result<()>>,
+    {
+        let index = self.report.phases.len() + 1;
+        let timeout = self.config.timeout(phase);
+        info!("⏳ Shutdown phase {}/{}: {} (timeout {:?})", index, ShutdownPhase::ALL.len(), phase.name(), timeout);
+
+        let started = Instant::now();
+        let result = match tokio::time::timeout(timeout, work).await {
+            Ok(Ok(())) => PhaseResult::Completed,
+            Ok(Err(e)) => PhaseResult::Failed(e.to_string()),
+            Err(_) => PhaseResult::TimedOut,
+        };
+        let elapsed = started.elapsed();
+
+        match &result {
+            PhaseResult::Completed => info!("✅ Shutdown phase '{}' completed in {:?}", phase.name(), elapsed),
+            PhaseResult::TimedOut => warn!("⌛ Shutdown phase '{}' timed out after {:?}, continuing", phase.name(), timeout),
+            PhaseResult::Failed(e) => warn!("❌ Shutdown phase '{}' failed: {}, continuing", phase.name(), e),
+        }
+
+        self.report.phases.push(PhaseOutcome { phase, result, elapsed });
+    }
+
+    pub fn finish(self) -> ShutdownReport {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sequence_continues_past_failures() {
+        let config = ShutdownConfig {
+            stop_workloads: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let mut sequence = ShutdownSequence::new(config);
+
+        sequence.run(ShutdownPhase::StopAcceptingWork, async { Ok(()) }).await;
+        sequence.run(ShutdownPhase::StopWorkloads, async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        }).await;
+        sequence.run(ShutdownPhase::StopState, async { Err(anyhow::anyhow!("disk full")) }).await;
+        sequence.run(ShutdownPhase::StopTransports, async { Ok(()) }).await;
+
+        let report = sequence.finish();
+        assert!(!report.is_clean());
+        assert_eq!(report.phases.len(), 4);
+        assert_eq!(report.phases[1].result, PhaseResult::TimedOut);
+        assert_eq!(report.phases[2].result, PhaseResult::Failed("disk full".to_string()));
+        assert_eq!(report.phases[3].result, PhaseResult::Completed);
+    }
+}
@@ -0,0 +1,388 @@
+//! Sink connectors forwarding cluster events to external event platforms
+//!
+//! Most enterprises already centralize events in Kafka or NATS, so a sink
+//! forwards the system event stream there: every event goes to the events
+//! topic, and audit events (operator actions and security events) also go to
+//! the audit topic. Events wait in a bounded buffer and are sent in batches
+//! of up to `batch_size`, or whatever arrived within `linger`. A failed batch
+//! is retried until it is delivered. While a slow or unreachable sink keeps
+//! the buffer full, `Block` stops reading the event stream, so events fall off
+//! its end, and `DropNewest` discards new events. Either way the loss is
+//! counted in the sink's stats. The Kafka and NATS backends are built with the
+//! `kafka` and `nats` features.
+
+use crate::events::SystemEvent;
+use anyhow::Result;
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// What a sink does with new events while its buffer is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SinkOverflow {
+    /// Stop reading the event stream until the buffer drains
+    #[default]
+    Block,
+    /// Discard new events
+    DropNewest,
+}
+
+/// Sink forwarding configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkConfig {
+    /// Topic or subject every event is sent to
+    pub events_topic: String,
+    /// Topic or subject audit events are also sent to
+    pub audit_topic: String,
+    /// Most records sent in one batch
+    pub batch_size: usize,
+    /// How long a batch waits to fill before it is sent
+    pub linger: Duration,
+    /// Records buffered while the sink is slow or unreachable
+    pub buffer_capacity: usize,
+    pub overflow: SinkOverflow,
+    /// Delay before a failed batch is retried
+    pub retry_interval: Duration,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            events_topic: "hypermesh.events".to_string(),
+            audit_topic: "hypermesh.audit".to_string(),
+            batch_size: 100,
+            linger: Duration::from_millis(100),
+            buffer_capacity: 10_000,
+            overflow: SinkOverflow::default(),
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// External platform a sink connects to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SinkBackend {
+    Kafka { brokers: Vec<String>, client_id: String },
+    Nats { servers: Vec<String> },
+}
+
+impl SinkBackend {
+    /// Connect to the backend
+    ///
+    /// Fails if the backend's feature was not enabled at build time.
+    pub async fn connect(&self) -> Result<Arc<dyn EventSink>> {
+        match self {
+            #[cfg(feature = "kafka")]
+            SinkBackend::Kafka { brokers, client_id } => Ok(Arc::new(KafkaSink::new(brokers, client_id)?)),
+            #[cfg(feature = "nats")]
+            SinkBackend::Nats { servers } => Ok(Arc::new(NatsSink::connect(servers).await?)),
+            #[allow(unreachable_patterns)]
+            other => anyhow::bail!("{:?} sink support was not built; enable the kafka or nats feature", other),
+        }
+    }
+}
+
+/// An event serialized for a topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkRecord {
+    pub topic: String,
+    /// Event type, used as the Kafka partition key
+    pub key: String,
+    /// The event as JSON
+    pub payload: Vec<u8>,
+}
+
+impl SinkRecord {
+    /// Records for an event: one for the events topic, and one for the
+    /// audit topic if it is an audit event
+    pub fn for_event(config: &SinkConfig, event: &SystemEvent) -> Result<Vec<SinkRecord>> {
+        let payload = serde_json::to_vec(event)?;
+        let mut topics = vec![config.events_topic.clone()];
+        if event.is_audit() {
+            topics.push(config.audit_topic.clone());
+        }
+        Ok(topics
+            .into_iter()
+            .map(|topic| SinkRecord { topic, key: event.name().to_string(), payload: payload.clone() })
+            .collect())
+    }
+}
+
+/// An external platform events are forwarded to
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Deliver a batch, resolving once the platform has accepted all of it
+    fn send_batch(&self, records: Vec<SinkRecord>) -> BoxFuture<'_, Result<()>>;
+}
+
+/// Sink forwarding statistics
+#[derive(Debug, Clone, Default)]
+pub struct SinkStats {
+    pub forwarded: u64,
+    pub batches: u64,
+    pub failed_batches: u64,
+    /// Events lost to a full buffer
+    pub dropped: u64,
+}
+
+/// A running sink; forwarding stops when the handle is dropped
+pub struct SinkHandle {
+    name: String,
+    stats: Arc<Mutex<SinkStats>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl SinkHandle {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn stats(&self) -> SinkStats {
+        self.stats.lock().clone()
+    }
+}
+
+impl Drop for SinkHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Forward events from `events` to `sink`
+pub fn spawn_sink(config: SinkConfig, sink: Arc<dyn EventSink>, events: broadcast::Receiver<SystemEvent>) -> SinkHandle {
+    let stats = Arc::new(Mutex::new(SinkStats::default()));
+    let (buffer, pending) = mpsc::channel(config.buffer_capacity.max(1));
+    let name = sink.name().to_string();
+    let reader = tokio::spawn(read_events(config.clone(), events, buffer, stats.clone()));
+    let writer = tokio::spawn(write_batches(config, sink, pending, stats.clone()));
+    SinkHandle { name, stats, tasks: vec![reader, writer] }
+}
+
+async fn read_events(
+    config: SinkConfig,
+    mut events: broadcast::Receiver<SystemEvent>,
+    buffer: mpsc::Sender<SinkRecord>,
+    stats: Arc<Mutex<SinkStats>>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Event sink fell behind and lost {} events", missed);
+                stats.lock().dropped += missed;
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let records = match SinkRecord::for_event(&config, &event) {
+            Ok(records) => records,
+            Err(e) => {
+                warn!("Failed to serialize {} event: {}", event.name(), e);
+                continue;
+            }
+        };
+        for record in records {
+            let sent = match config.overflow {
+                SinkOverflow::Block => buffer.send(record).await.map_err(|_| ()),
+                SinkOverflow::DropNewest => match buffer.try_send(record) {
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        stats.lock().dropped += 1;
+                        Ok(())
+                    }
+                    other => other.map_err(|_| ()),
+                },
+            };
+            if sent.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn write_batches(
+    config: SinkConfig,
+    sink: Arc<dyn EventSink>,
+    mut pending: mpsc::Receiver<SinkRecord>,
+    stats: Arc<Mutex<SinkStats>>,
+) {
+    let batch_size = config.batch_size.max(1);
+    while let Some(first) = pending.recv().await {
+        let mut batch = vec![first];
+        let linger = tokio::time::sleep(config.linger);
+        tokio::pin!(linger);
+        while batch.len() < batch_size {
+            tokio::select! {
+                record = pending.recv() => match record {
+                    Some(record) => batch.push(record),
+                    None => break,
+                },
+                _ = &mut linger => break,
+            }
+        }
+
+        loop {
+            match sink.send_batch(batch.clone()).await {
+                Ok(()) => {
+                    debug!("Forwarded {} records to {}", batch.len(), sink.name());
+                    let mut stats = stats.lock();
+                    stats.forwarded += batch.len() as u64;
+                    stats.batches += 1;
+                    break;
+                }
+                Err(e) => {
+                    warn!("Failed to forward {} records to {}: {:#}", batch.len(), sink.name(), e);
+                    stats.lock().failed_batches += 1;
+                    tokio::time::sleep(config.retry_interval).await;
+                }
+            }
+        }
+    }
+}
+
+/// Kafka producer sink
+#[cfg(feature = "kafka")]
+pub struct KafkaSink {
+    producer: rdkafka::producer::FutureProducer,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSink {
+    pub fn new(brokers: &[String], client_id: &str) -> Result<Self> {
+        let producer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", brokers.join(","))
+            .set("client.id", client_id)
+            .set("enable.idempotence", "true")
+            .create()?;
+        Ok(Self { producer })
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl EventSink for KafkaSink {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn send_batch(&self, records: Vec<SinkRecord>) -> BoxFuture<'_, Result<()>> {
+        use rdkafka::producer::FutureRecord;
+        use rdkafka::util::Timeout;
+
+        Box::pin(async move {
+            let deliveries = records.iter().map(|record| {
+                self.producer.send(
+                    FutureRecord::to(&record.topic).key(&record.key).payload(&record.payload),
+                    Timeout::After(Duration::from_secs(30)),
+                )
+            });
+            for delivery in futures::future::join_all(deliveries).await {
+                delivery.map_err(|(e, _)| anyhow::anyhow!("Kafka delivery failed: {}", e))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// NATS publisher sink
+#[cfg(feature = "nats")]
+pub struct NatsSink {
+    client: async_nats::Client,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSink {
+    pub async fn connect(servers: &[String]) -> Result<Self> {
+        let client = async_nats::connect(servers.join(",").as_str()).await?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "nats")]
+impl EventSink for NatsSink {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    fn send_batch(&self, records: Vec<SinkRecord>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            for record in records {
+                self.client.publish(record.topic, record.payload.into()).await?;
+            }
+            self.client.flush().await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nexus_shared::NodeId;
+
+    /// Records delivered batches, failing the first `failures` attempts
+    #[derive(Default)]
+    struct MemorySink {
+        failures: Mutex<u32>,
+        batches: Mutex<Vec<Vec<SinkRecord>>>,
+    }
+
+    impl EventSink for MemorySink {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        fn send_batch(&self, records: Vec<SinkRecord>) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                let mut failures = self.failures.lock();
+                if *failures > 0 {
+                    *failures -= 1;
+                    anyhow::bail!("broker unavailable");
+                }
+                self.batches.lock().push(records);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_and_routes_audit_events() {
+        let (sender, receiver) = broadcast::channel(16);
+        let sink = Arc::new(MemorySink { failures: Mutex::new(1), ..Default::default() });
+        let config = SinkConfig {
+            linger: Duration::from_millis(20),
+            retry_interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let handle = spawn_sink(config, sink.clone(), receiver);
+
+        let timestamp = chrono::Utc::now();
+        sender.send(SystemEvent::SystemStarted { node_id: NodeId::random(), timestamp }).unwrap();
+        sender
+            .send(SystemEvent::ServiceScaled { service_name: "api".to_string(), old_replicas: 1, new_replicas: 3, timestamp })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Both events arrive in one batch after the first attempt fails
+        let batches = sink.batches.lock().clone();
+        assert_eq!(batches.len(), 1);
+        let routed: Vec<(&str, &str)> = batches[0].iter().map(|r| (r.topic.as_str(), r.key.as_str())).collect();
+        assert_eq!(
+            routed,
+            vec![
+                ("hypermesh.events", "system_started"),
+                ("hypermesh.events", "service_scaled"),
+                ("hypermesh.audit", "service_scaled"),
+            ]
+        );
+
+        let stats = handle.stats();
+        assert_eq!((stats.forwarded, stats.batches, stats.failed_batches, stats.dropped), (3, 1, 1, 0));
+    }
+}
@@ -0,0 +1,487 @@
+//! Dependency-aware component supervision
+//!
+//! The [`Supervisor`] starts components in dependency order and only moves on
+//! once each one reports healthy. While running it polls component health,
+//! restarts crashed components with exponential backoff and marks components
+//! whose dependencies are unhealthy as `Degraded`, so the reported system
+//! status reflects what is actually running.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, error, info, warn};
+
+use crate::{events, health, ComponentStatus, SystemStatus};
+
+/// Boxed future returned by [`Supervised`] components
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Core components managed by the supervisor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComponentKind {
+    Transport,
+    StateManager,
+    Runtime,
+    Networking,
+    Scheduler,
+}
+
+impl ComponentKind {
+    /// Components that must be healthy before this one starts
+    pub fn dependencies(&self) -> &'static [ComponentKind] {
+        match self {
+            ComponentKind::Transport => &[],
+            ComponentKind::StateManager => &[ComponentKind::Transport],
+            ComponentKind::Runtime => &[ComponentKind::StateManager],
+            ComponentKind::Networking => &[ComponentKind::Transport, ComponentKind::StateManager],
+            ComponentKind::Scheduler => &[
+                ComponentKind::StateManager,
+                ComponentKind::Runtime,
+                ComponentKind::Networking,
+            ],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ComponentKind::Transport => "transport",
+            ComponentKind::StateManager => "state manager",
+            ComponentKind::Runtime => "runtime",
+            ComponentKind::Networking => "networking",
+            ComponentKind::Scheduler => "scheduler",
+        }
+    }
+}
+
+/// A component the supervisor can start, stop and health check
+pub trait Supervised: Send + Sync {
+    fn kind(&self) -> ComponentKind;
+    fn start(&self) -> BoxFuture<'_, Result<()>>;
+    fn stop(&self) -> BoxFuture<'_, Result<()>>;
+    fn health(&self) -> BoxFuture<'_, health::ComponentHealth>;
+}
+
+/// Supervisor configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    /// How long a started component has to report healthy
+    pub health_gate_timeout: Duration,
+
+    /// Poll interval while waiting on a health gate
+    pub health_gate_poll: Duration,
+
+    /// Interval between health checks of running components
+    pub check_interval: Duration,
+
+    /// Delay before the first restart of a crashed component
+    pub initial_backoff: Duration,
+
+    /// Upper bound on restart backoff
+    pub max_backoff: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            health_gate_timeout: Duration::from_secs(30),
+            health_gate_poll: Duration::from_millis(100),
+            check_interval: Duration::from_secs(5),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+struct Restart {
+    attempts: u32,
+    not_before: tokio::time::Instant,
+}
+
+/// Starts components in dependency order and keeps them running
+pub struct Supervisor {
+    config: SupervisorConfig,
+    components: HashMap<ComponentKind, Arc<dyn Supervised>>,
+    statuses: RwLock<HashMap<ComponentKind, ComponentStatus>>,
+    restarts: RwLock<HashMap<ComponentKind, Restart>>,
+    event_sender: broadcast::Sender<events::SystemEvent>,
+    monitor: parking_lot::Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl Supervisor {
+    pub fn new(config: SupervisorConfig, event_sender: broadcast::Sender<events::SystemEvent>) -> Self {
+        Self {
+            config,
+            components: HashMap::new(),
+            statuses: RwLock::new(HashMap::new()),
+            restarts: RwLock::new(HashMap::new()),
+            event_sender,
+            monitor: parking_lot::Mutex::new(None),
+        }
+    }
+
+    /// Register a component; must happen before `start_all`
+    pub fn register(&mut self, component: Arc<dyn Supervised>) {
+        self.components.insert(component.kind(), component);
+    }
+
+    /// Registered components in dependency order
+    pub fn start_order(&self) -> Result<Vec<ComponentKind>> {
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+
+        fn visit(
+            kind: ComponentKind,
+            registered: &HashMap<ComponentKind, Arc<dyn Supervised>>,
+            order: &mut Vec<ComponentKind>,
+            visiting: &mut Vec<ComponentKind>,
+        ) -> Result<()> {
+            if order.contains(&kind) {
+                return Ok(());
+            }
+            if visiting.contains(&kind) {
+                return Err(anyhow::anyhow!("Dependency cycle involving {}", kind.name()));
+            }
+            visiting.push(kind);
+            for dependency in kind.dependencies() {
+                if !registered.contains_key(dependency) {
+                    return Err(anyhow::anyhow!(
+                        "{} depends on {}, which is not registered",
+                        kind.name(),
+                        dependency.name()
+                    ));
+                }
+                visit(*dependency, registered, order, visiting)?;
+            }
+            visiting.pop();
+            order.push(kind);
+            Ok(())
+        }
+
+        let mut kinds: Vec<ComponentKind> = self.components.keys().copied().collect();
+        kinds.sort_by_key(|kind| *kind as u8);
+        for kind in kinds {
+            visit(kind, &self.components, &mut order, &mut visiting)?;
+        }
+        Ok(order)
+    }
+
+    /// Start every component in dependency order, gating on health
+    ///
+    /// If any component fails to start or become healthy, the ones already
+    /// started are stopped in reverse order and the error is returned.
+    pub async fn start_all(&self) -> Result<()> {
+        let order = self.start_order()?;
+        let mut started = Vec::new();
+
+        for (step, kind) in order.iter().enumerate() {
+            info!("Starting {} ({}/{})", kind.name(), step + 1, order.len());
+            self.set_status(*kind, ComponentStatus::Starting).await;
+
+            if let Err(e) = self.start_gated(*kind).await {
+                error!("Failed to start {}: {}", kind.name(), e);
+                self.set_status(*kind, ComponentStatus::Failed).await;
+
+                for started_kind in started.iter().rev() {
+                    if let Err(e) = self.components[started_kind].stop().await {
+                        warn!("Error stopping {} during startup rollback: {}", started_kind.name(), e);
+                    }
+                    self.set_status(*started_kind, ComponentStatus::Stopped).await;
+                }
+                return Err(e.context(format!("{} failed to start", kind.name())));
+            }
+
+            self.set_status(*kind, ComponentStatus::Running).await;
+            started.push(*kind);
+        }
+
+        Ok(())
+    }
+
+    /// Start a component and wait for it to report healthy
+    async fn start_gated(&self, kind: ComponentKind) -> Result<()> {
+        let component = &self.components[&kind];
+        component.start().await?;
+
+        let gate = async {
+            loop {
+                let report = component.health().await;
+                match report.status {
+                    health::HealthStatus::Healthy | health::HealthStatus::Degraded => return,
+                    _ => tokio::time::sleep(self.config.health_gate_poll).await,
+                }
+            }
+        };
+
+        tokio::time::timeout(self.config.health_gate_timeout, gate)
+            .await
+            .map_err(|_| anyhow::anyhow!(
+                "{} did not become healthy within {:?}",
+                kind.name(),
+                self.config.health_gate_timeout
+            ))
+    }
+
+    /// Start background health monitoring and restarts
+    pub fn start_monitoring(self: &Arc<Self>) {
+        let supervisor = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(supervisor.config.check_interval);
+            loop {
+                interval.tick().await;
+                supervisor.check().await;
+            }
+        });
+
+        if let Some(previous) = self.monitor.lock().replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// Stop background monitoring so shutdown doesn't trigger restarts
+    pub fn stop_monitoring(&self) {
+        if let Some(task) = self.monitor.lock().take() {
+            task.abort();
+        }
+    }
+
+    /// One supervision pass: health check, restart and status propagation
+    pub async fn check(&self) {
+        let order = match self.start_order() {
+            Ok(order) => order,
+            Err(e) => {
+                error!("Supervisor cannot order components: {}", e);
+                return;
+            }
+        };
+
+        for kind in order {
+            if matches!(self.status(kind).await, Some(ComponentStatus::Stopped)) {
+                continue;
+            }
+
+            let report = self.components[&kind].health().await;
+            let own = match report.status {
+                health::HealthStatus::Healthy => ComponentStatus::Running,
+                health::HealthStatus::Degraded => ComponentStatus::Degraded,
+                health::HealthStatus::Critical | health::HealthStatus::Unknown => ComponentStatus::Failed,
+            };
+
+            if matches!(own, ComponentStatus::Failed) {
+                self.set_status(kind, ComponentStatus::Failed).await;
+                self.try_restart(kind).await;
+                continue;
+            }
+            self.restarts.write().await.remove(&kind);
+
+            // A component is only as healthy as what it depends on
+            let mut status = own;
+            for dependency in kind.dependencies() {
+                if !matches!(self.status(*dependency).await, Some(ComponentStatus::Running)) {
+                    status = ComponentStatus::Degraded;
+                }
+            }
+            self.set_status(kind, status).await;
+        }
+    }
+
+    /// Restart a failed component once its backoff has elapsed
+    async fn try_restart(&self, kind: ComponentKind) {
+        let now = tokio::time::Instant::now();
+        let attempts = {
+            let restarts = self.restarts.read().await;
+            match restarts.get(&kind) {
+                Some(restart) if restart.not_before > now => return,
+                Some(restart) => restart.attempts,
+                None => 0,
+            }
+        };
+
+        let backoff = self
+            .config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempts))
+            .min(self.config.max_backoff);
+        self.restarts.write().await.insert(kind, Restart {
+            attempts: attempts + 1,
+            not_before: now + backoff,
+        });
+
+        warn!("Restarting {} (attempt {}, next retry in {:?})", kind.name(), attempts + 1, backoff);
+
+        let component = &self.components[&kind];
+        if let Err(e) = component.stop().await {
+            debug!("Stopping crashed {} failed: {}", kind.name(), e);
+        }
+        match self.start_gated(kind).await {
+            Ok(()) => {
+                info!("{} restarted", kind.name());
+                self.set_status(kind, ComponentStatus::Running).await;
+            }
+            Err(e) => error!("Restart of {} failed: {}", kind.name(), e),
+        }
+    }
+
+    /// Mark a component stopped (e.g. during shutdown)
+    pub async fn mark_stopped(&self, kind: ComponentKind) {
+        self.set_status(kind, ComponentStatus::Stopped).await;
+    }
+
+    async fn set_status(&self, kind: ComponentKind, status: ComponentStatus) {
+        let old = self.statuses.write().await.insert(kind, status.clone());
+
+        if let Some(old) = old {
+            if old != status {
+                let _ = self.event_sender.send(events::SystemEvent::ComponentHealthChanged {
+                    component: kind.name().to_string(),
+                    old_status: format!("{:?}", old),
+                    new_status: format!("{:?}", status),
+                    timestamp: chrono::Utc::now(),
+                });
+            }
+        }
+    }
+
+    /// Current status of a component
+    pub async fn status(&self, kind: ComponentKind) -> Option<ComponentStatus> {
+        self.statuses.read().await.get(&kind).cloned()
+    }
+
+    /// Overall status derived from component statuses
+    pub async fn system_status(&self) -> SystemStatus {
+        let statuses = self.statuses.read().await;
+
+        if statuses.values().any(|s| matches!(s, ComponentStatus::Failed)) {
+            SystemStatus::Critical
+        } else if statuses.values().any(|s| matches!(s, ComponentStatus::Degraded | ComponentStatus::Starting)) {
+            SystemStatus::Degraded
+        } else if statuses.values().all(|s| matches!(s, ComponentStatus::Stopped)) && !statuses.is_empty() {
+            SystemStatus::Shutdown
+        } else {
+            SystemStatus::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    struct Fake {
+        kind: ComponentKind,
+        healthy: AtomicBool,
+        starts: AtomicU32,
+        log: Arc<parking_lot::Mutex<Vec<ComponentKind>>>,
+    }
+
+    impl Fake {
+        fn new(kind: ComponentKind, log: &Arc<parking_lot::Mutex<Vec<ComponentKind>>>) -> Arc<Self> {
+            Arc::new(Self {
+                kind,
+                healthy: AtomicBool::new(true),
+                starts: AtomicU32::new(0),
+                log: log.clone(),
+            })
+        }
+    }
+
+    impl Supervised for Fake {
+        fn kind(&self) -> ComponentKind {
+            self.kind
+        }
+
+        fn start(&self) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                self.starts.fetch_add(1, Ordering::SeqCst);
+                self.healthy.store(true, Ordering::SeqCst);
+                self.log.lock().push(self.kind);
+                Ok(())
+            })
+        }
+
+        fn stop(&self) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn health(&self) -> BoxFuture<'_, health::ComponentHealth> {
+            Box::pin(async move {
+                health::ComponentHealth {
+                    component: self.kind.name().to_string(),
+                    status: if self.healthy.load(Ordering::SeqCst) {
+                        health::HealthStatus::Healthy
+                    } else {
+                        health::HealthStatus::Critical
+                    },
+                    message: String::new(),
+                    connections: 0,
+                    last_check: chrono::Utc::now(),
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_starts_in_dependency_order() {
+        let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let (events, _) = broadcast::channel(16);
+        let mut supervisor = Supervisor::new(SupervisorConfig::default(), events);
+
+        // Registered in reverse to make sure ordering comes from dependencies
+        for kind in [
+            ComponentKind::Scheduler,
+            ComponentKind::Networking,
+            ComponentKind::Runtime,
+            ComponentKind::StateManager,
+            ComponentKind::Transport,
+        ] {
+            supervisor.register(Fake::new(kind, &log));
+        }
+
+        supervisor.start_all().await.unwrap();
+
+        let order = log.lock().clone();
+        let position = |kind| order.iter().position(|k| *k == kind).unwrap();
+        assert!(position(ComponentKind::StateManager) < position(ComponentKind::Scheduler));
+        assert!(position(ComponentKind::Transport) < position(ComponentKind::Networking));
+        assert!(matches!(supervisor.system_status().await, SystemStatus::Healthy));
+    }
+
+    #[tokio::test]
+    async fn test_restarts_crashed_component_and_degrades_dependents() {
+        let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let (events, _) = broadcast::channel(16);
+        let mut supervisor = Supervisor::new(
+            SupervisorConfig {
+                initial_backoff: Duration::from_secs(60),
+                ..Default::default()
+            },
+            events,
+        );
+
+        let transport = Fake::new(ComponentKind::Transport, &log);
+        let state = Fake::new(ComponentKind::StateManager, &log);
+        supervisor.register(transport.clone());
+        supervisor.register(state.clone());
+        supervisor.start_all().await.unwrap();
+
+        // Crash the state manager: it is restarted on the next pass
+        state.healthy.store(false, Ordering::SeqCst);
+        supervisor.check().await;
+        assert_eq!(state.starts.load(Ordering::SeqCst), 2);
+        assert!(matches!(supervisor.status(ComponentKind::StateManager).await, Some(ComponentStatus::Running)));
+
+        // Crash transport and keep it down: the state manager is degraded
+        transport.healthy.store(false, Ordering::SeqCst);
+        supervisor.check().await;
+        transport.healthy.store(false, Ordering::SeqCst);
+        supervisor.check().await;
+        assert!(matches!(supervisor.status(ComponentKind::Transport).await, Some(ComponentStatus::Failed)));
+        assert!(matches!(supervisor.status(ComponentKind::StateManager).await, Some(ComponentStatus::Degraded)));
+        assert!(matches!(supervisor.system_status().await, SystemStatus::Critical));
+    }
+}
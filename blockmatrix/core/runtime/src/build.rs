@@ -0,0 +1,860 @@
+//! Container image builds from source
+//!
+//! A `BuildRequest` pairs a source, either an uploaded tar.gz bundle or a git
+//! repository, with a `BuildRecipe`: an optional base image and ordered
+//! steps. `Copy` steps are packed into layers locally. `Run` steps execute on
+//! a build node through a `BuildWorker`, which returns the filesystem changes
+//! as a layer. As in BuildKit, every layer is cached under a key derived from
+//! the layers below it and the step's inputs, so a rebuild only redoes the
+//! steps after the first change. Layers are packed reproducibly, so unchanged
+//! sources yield the same image digest. The image is pushed to an OCI
+//! registry together with a `BuildProvenance` artifact recording the source
+//! revision, recipe, base image and build node that produced it.
+
+use crate::image::ImageSpec;
+use crate::{Result, RuntimeError};
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::future::BoxFuture;
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+pub(crate) const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+pub(crate) const CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.image.config.v1+json";
+pub(crate) const LAYER_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+const EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+/// Artifact type of the provenance attached to built images
+pub const PROVENANCE_MEDIA_TYPE: &str = "application/vnd.hypermesh.build.provenance.v1+json";
+
+/// Image build configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildConfig {
+    /// Directory holding cached layers
+    pub cache_dir: String,
+
+    /// Git executable used to fetch repository sources
+    pub git_command: String,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: "./data/build-cache".to_string(),
+            git_command: "git".to_string(),
+        }
+    }
+}
+
+/// Where the build context comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuildSource {
+    /// A gzip-compressed tar archive of the source tree
+    Bundle { archive: Vec<u8> },
+
+    /// A branch, tag or commit of a git repository
+    Git { url: String, reference: String },
+}
+
+/// One step of a build recipe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuildStep {
+    /// Copy a file or directory of the source to an absolute image path
+    Copy { from: String, to: String },
+
+    /// Run a command on a build node on top of the layers so far
+    Run { command: Vec<String> },
+}
+
+/// How to turn a source into an image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildRecipe {
+    /// Image to build on, or none to start from scratch
+    ///
+    /// The base image must already be in the target registry.
+    pub base: Option<ImageSpec>,
+
+    pub steps: Vec<BuildStep>,
+
+    /// Environment appended to the base image's, also set for `Run` steps
+    pub env: Vec<String>,
+
+    /// Entry point, inherited from the base image if unset
+    pub entrypoint: Option<Vec<String>>,
+
+    /// Default command, inherited from the base image if unset
+    pub cmd: Option<Vec<String>>,
+
+    /// Working directory, inherited from the base image if unset
+    pub workdir: Option<String>,
+
+    /// Labels merged over the base image's
+    pub labels: BTreeMap<String, String>,
+
+    pub architecture: String,
+
+    pub os: String,
+}
+
+impl Default for BuildRecipe {
+    fn default() -> Self {
+        Self {
+            base: None,
+            steps: Vec::new(),
+            env: Vec::new(),
+            entrypoint: None,
+            cmd: None,
+            workdir: None,
+            labels: BTreeMap::new(),
+            architecture: "amd64".to_string(),
+            os: "linux".to_string(),
+        }
+    }
+}
+
+/// A request to build and push an image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildRequest {
+    pub source: BuildSource,
+    pub recipe: BuildRecipe,
+    /// Name and tag to push the image under
+    pub target: ImageSpec,
+}
+
+/// A layer of an image, as stored in a registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerDescriptor {
+    pub media_type: String,
+    /// Digest of the compressed blob
+    pub digest: String,
+    pub size: u64,
+    /// Digest of the uncompressed tar
+    pub diff_id: String,
+}
+
+/// A `Run` step handed to a build node
+#[derive(Debug, Clone)]
+pub struct RunStep {
+    /// Layers to run on, bottom first, all present in the registry
+    pub layers: Vec<LayerDescriptor>,
+    pub command: Vec<String>,
+    pub env: Vec<String>,
+    pub workdir: Option<String>,
+}
+
+/// A HyperMesh node that executes `Run` steps
+pub trait BuildWorker: Send + Sync {
+    fn node_id(&self) -> NodeId;
+
+    /// Run the step and return the files it changed as an uncompressed tar
+    fn run(&self, step: RunStep) -> BoxFuture<'static, Result<Vec<u8>>>;
+}
+
+/// The OCI registry built images are pushed to
+pub trait ImageRegistry: Send + Sync {
+    /// Manifest stored under `reference` (`name:tag` or `name@digest`)
+    fn fetch_manifest(&self, reference: &str) -> BoxFuture<'static, Result<Option<Vec<u8>>>>;
+
+    fn fetch_blob(&self, digest: &str) -> BoxFuture<'static, Result<Option<Vec<u8>>>>;
+
+    fn has_blob(&self, digest: &str) -> BoxFuture<'static, Result<bool>>;
+
+    fn push_blob(&self, digest: &str, data: Vec<u8>) -> BoxFuture<'static, Result<()>>;
+
+    fn push_manifest(&self, reference: &str, manifest: Vec<u8>) -> BoxFuture<'static, Result<()>>;
+}
+
+/// Source revision an image was built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceProvenance {
+    Bundle { digest: String },
+    Git { url: String, reference: String, commit: String },
+}
+
+/// How an image was built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildProvenance {
+    pub build_id: String,
+    /// Reference the image was pushed under
+    pub image: String,
+    pub manifest_digest: String,
+    pub source: SourceProvenance,
+    /// Digest of the recipe as submitted
+    pub recipe_digest: String,
+    /// Base image reference and manifest digest
+    pub base_image: Option<(String, String)>,
+    /// Node that ran the `Run` steps, if there were any
+    pub builder: Option<NodeId>,
+    pub layers: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Outcome of a successful build
+#[derive(Debug, Clone)]
+pub struct BuildResult {
+    /// The pushed image, pinned to its manifest digest
+    pub image: ImageSpec,
+    pub provenance: BuildProvenance,
+    /// Steps whose layer came from the cache
+    pub cached_steps: usize,
+}
+
+/// Builds images from source and pushes them to a registry
+pub struct ImageBuilder {
+    config: BuildConfig,
+    registry: Arc<dyn ImageRegistry>,
+    workers: parking_lot::RwLock<Vec<Arc<WorkerSlot>>>,
+    cache: LayerCache,
+    provenance: parking_lot::RwLock<HashMap<String, BuildProvenance>>,
+}
+
+struct WorkerSlot {
+    worker: Arc<dyn BuildWorker>,
+    active_builds: AtomicUsize,
+}
+
+/// Releases a worker slot when the build finishes
+struct WorkerLease(Arc<WorkerSlot>);
+
+impl Drop for WorkerLease {
+    fn drop(&mut self) {
+        self.0.active_builds.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ImageBuilder {
+    /// Create a builder, loading the layer cache from `config.cache_dir`
+    pub async fn new(config: &BuildConfig, registry: Arc<dyn ImageRegistry>) -> Result<Self> {
+        let cache = LayerCache::open(Path::new(&config.cache_dir)).await?;
+        Ok(Self {
+            config: config.clone(),
+            registry,
+            workers: parking_lot::RwLock::new(Vec::new()),
+            cache,
+            provenance: parking_lot::RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Make a build node available for `Run` steps
+    pub fn add_worker(&self, worker: Arc<dyn BuildWorker>) {
+        self.workers.write().push(Arc::new(WorkerSlot { worker, active_builds: AtomicUsize::new(0) }));
+    }
+
+    /// Provenance of an image this builder pushed, by manifest digest
+    pub fn provenance(&self, manifest_digest: &str) -> Option<BuildProvenance> {
+        self.provenance.read().get(manifest_digest).cloned()
+    }
+
+    /// Build the requested image, push it and attach its provenance
+    pub async fn build(&self, request: BuildRequest) -> Result<BuildResult> {
+        let started_at = Utc::now();
+        let build_id = uuid::Uuid::new_v4().to_string();
+        let recipe = &request.recipe;
+        info!("Starting build {} of {}", build_id, request.target.cache_key());
+
+        let context = tempfile::tempdir()?;
+        let source = fetch_source(&self.config, &request.source, context.path()).await?;
+
+        let base = match &recipe.base {
+            Some(spec) => Some(self.resolve_base(spec).await?),
+            None => None,
+        };
+        let needs_worker = recipe.steps.iter().any(|step| matches!(step, BuildStep::Run { .. }));
+        let lease = if needs_worker { Some(self.lease_worker()?) } else { None };
+
+        let mut layers = base.as_ref().map(|base| base.layers.clone()).unwrap_or_default();
+        let mut chain_key = base.as_ref().map_or_else(|| "scratch".to_string(), |base| base.digest.clone());
+        let mut cached_steps = 0;
+        for step in &recipe.steps {
+            let step_json = serde_json::to_string(step)?;
+            let (key, layer) = match step {
+                BuildStep::Copy { from, to } => {
+                    let root = context.path().to_path_buf();
+                    let (from, to) = (from.clone(), to.clone());
+                    let tar = tokio::task::spawn_blocking(move || pack_copy(&root, &from, &to)).await??;
+                    let key = sha256_digest(format!("{}\n{}\n{}", chain_key, step_json, sha256_digest(&tar)).as_bytes());
+                    match self.cache.get(&key).await {
+                        Some(layer) => {
+                            cached_steps += 1;
+                            (key, layer)
+                        }
+                        None => {
+                            let layer = self.cache.insert(&key, &tar).await?;
+                            (key, layer)
+                        }
+                    }
+                }
+                BuildStep::Run { command } => {
+                    let inputs = serde_json::to_string(&(&recipe.env, &recipe.workdir))?;
+                    let key = sha256_digest(format!("{}\n{}\n{}", chain_key, step_json, inputs).as_bytes());
+                    match self.cache.get(&key).await {
+                        Some(layer) => {
+                            cached_steps += 1;
+                            (key, layer)
+                        }
+                        None => {
+                            let worker = &lease.as_ref().expect("leased for run steps").0.worker;
+                            debug!("Running {:?} on build node {:?}", command, worker.node_id());
+                            let tar = worker.run(RunStep {
+                                layers: layers.clone(),
+                                command: command.clone(),
+                                env: recipe.env.clone(),
+                                workdir: recipe.workdir.clone(),
+                            }).await?;
+                            let layer = self.cache.insert(&key, &tar).await?;
+                            (key, layer)
+                        }
+                    }
+                }
+            };
+            // Pushed right away so build nodes can pull it for later steps
+            self.push_blob(&layer.digest, || self.cache.read_blob(&layer.digest)).await?;
+            layers.push(layer);
+            chain_key = key;
+        }
+
+        let config = image_config(recipe, base.as_ref().map(|base| &base.config), &layers);
+        let config_bytes = serde_json::to_vec(&config)?;
+        let config_digest = sha256_digest(&config_bytes);
+        self.push_blob(&config_digest, || async { Ok(config_bytes.clone()) }).await?;
+
+        let mut annotations = BTreeMap::new();
+        if let SourceProvenance::Git { url, commit, .. } = &source {
+            annotations.insert("org.opencontainers.image.source".to_string(), url.clone());
+            annotations.insert("org.opencontainers.image.revision".to_string(), commit.clone());
+        }
+        let manifest = OciManifest {
+            schema_version: 2,
+            media_type: MANIFEST_MEDIA_TYPE.to_string(),
+            artifact_type: None,
+            config: OciDescriptor::new(CONFIG_MEDIA_TYPE, config_digest, config_bytes.len() as u64),
+            layers: layers.iter().map(OciDescriptor::from).collect(),
+            subject: None,
+            annotations,
+        };
+        let manifest_bytes = serde_json::to_vec(&manifest)?;
+        let manifest_digest = sha256_digest(&manifest_bytes);
+        let reference = request.target.cache_key();
+        self.registry.push_manifest(&reference, manifest_bytes.clone()).await?;
+
+        let provenance = BuildProvenance {
+            build_id,
+            image: reference,
+            manifest_digest: manifest_digest.clone(),
+            source,
+            recipe_digest: sha256_digest(&serde_json::to_vec(recipe)?),
+            base_image: base.map(|base| (base.reference, base.digest)),
+            builder: lease.as_ref().map(|lease| lease.0.worker.node_id()),
+            layers: layers.iter().map(|layer| layer.digest.clone()).collect(),
+            started_at,
+            finished_at: Utc::now(),
+        };
+        self.push_provenance(
+            &request.target.name,
+            OciDescriptor::new(MANIFEST_MEDIA_TYPE, manifest_digest.clone(), manifest_bytes.len() as u64),
+            &provenance,
+        ).await?;
+        self.provenance.write().insert(manifest_digest.clone(), provenance.clone());
+
+        info!(
+            "Build {} pushed {}@{} ({} of {} steps cached)",
+            provenance.build_id, request.target.name, manifest_digest, cached_steps, recipe.steps.len()
+        );
+        Ok(BuildResult {
+            image: ImageSpec { digest: Some(manifest_digest), ..request.target },
+            provenance,
+            cached_steps,
+        })
+    }
+
+    /// Build node with the fewest builds in progress
+    fn lease_worker(&self) -> Result<WorkerLease> {
+        let workers = self.workers.read();
+        let slot = workers
+            .iter()
+            .min_by_key(|slot| slot.active_builds.load(Ordering::SeqCst))
+            .ok_or_else(|| RuntimeError::Build { message: "No build nodes available for run steps".to_string() })?;
+        slot.active_builds.fetch_add(1, Ordering::SeqCst);
+        Ok(WorkerLease(slot.clone()))
+    }
+
+    async fn resolve_base(&self, spec: &ImageSpec) -> Result<BaseImage> {
+        let reference = spec.cache_key();
+        let manifest_bytes = self.registry.fetch_manifest(&reference).await?
+            .ok_or_else(|| RuntimeError::ImageNotFound { name: spec.name.clone(), tag: spec.tag.clone() })?;
+        let manifest: OciManifest = serde_json::from_slice(&manifest_bytes)?;
+        let config_bytes = self.registry.fetch_blob(&manifest.config.digest).await?
+            .ok_or_else(|| RuntimeError::Build {
+                message: format!("Config blob {} of base image {} is missing", manifest.config.digest, reference),
+            })?;
+        let config: OciImageConfig = serde_json::from_slice(&config_bytes)?;
+        if config.rootfs.diff_ids.len() != manifest.layers.len() {
+            return Err(RuntimeError::Build {
+                message: format!("Base image {} lists {} layers but {} diff IDs",
+                    reference, manifest.layers.len(), config.rootfs.diff_ids.len()),
+            });
+        }
+
+        let layers = manifest.layers.into_iter()
+            .zip(config.rootfs.diff_ids.iter().cloned())
+            .map(|(descriptor, diff_id)| LayerDescriptor {
+                media_type: descriptor.media_type,
+                digest: descriptor.digest,
+                size: descriptor.size,
+                diff_id,
+            })
+            .collect();
+        Ok(BaseImage { reference, digest: sha256_digest(&manifest_bytes), layers, config: config.config })
+    }
+
+    /// Attach provenance to an image as an OCI artifact referring to it
+    async fn push_provenance(&self, name: &str, subject: OciDescriptor, provenance: &BuildProvenance) -> Result<()> {
+        let empty = b"{}".to_vec();
+        let empty_digest = sha256_digest(&empty);
+        self.push_blob(&empty_digest, || async { Ok(empty.clone()) }).await?;
+        let provenance_bytes = serde_json::to_vec(provenance)?;
+        let provenance_digest = sha256_digest(&provenance_bytes);
+        self.push_blob(&provenance_digest, || async { Ok(provenance_bytes.clone()) }).await?;
+
+        let artifact = OciManifest {
+            schema_version: 2,
+            media_type: MANIFEST_MEDIA_TYPE.to_string(),
+            artifact_type: Some(PROVENANCE_MEDIA_TYPE.to_string()),
+            config: OciDescriptor::new(EMPTY_MEDIA_TYPE, empty_digest, 2),
+            layers: vec![OciDescriptor::new(PROVENANCE_MEDIA_TYPE, provenance_digest, provenance_bytes.len() as u64)],
+            subject: Some(subject.clone()),
+            annotations: BTreeMap::new(),
+        };
+        self.registry.push_manifest(&provenance_reference(name, &subject.digest), serde_json::to_vec(&artifact)?).await
+    }
+
+    async fn push_blob<F, Fut>(&self, digest: &str, data: F) -> Result<()>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<u8>>>,
+    {
+        if self.registry.has_blob(digest).await? {
+            return Ok(());
+        }
+        self.registry.push_blob(digest, data().await?).await
+    }
+}
+
+/// Reference the provenance of image `name` with `digest` is tagged with
+pub fn provenance_reference(name: &str, digest: &str) -> String {
+    format!("{}:{}.provenance", name, digest.replace(':', "-"))
+}
+
+/// A base image resolved from the registry
+struct BaseImage {
+    reference: String,
+    digest: String,
+    layers: Vec<LayerDescriptor>,
+    config: OciRunConfig,
+}
+
+/// Layers stored under their cache key, persisted across restarts
+struct LayerCache {
+    dir: PathBuf,
+    index: tokio::sync::Mutex<HashMap<String, LayerDescriptor>>,
+}
+
+impl LayerCache {
+    async fn open(dir: &Path) -> Result<Self> {
+        tokio::fs::create_dir_all(dir.join("blobs")).await.map_err(|e| RuntimeError::Storage {
+            message: format!("Failed to create build cache dir: {}", e),
+        })?;
+        let index = match tokio::fs::read(dir.join("index.json")).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { dir: dir.to_path_buf(), index: tokio::sync::Mutex::new(index) })
+    }
+
+    /// Cached layer for `key`, if its blob is still on disk
+    async fn get(&self, key: &str) -> Option<LayerDescriptor> {
+        let layer = self.index.lock().await.get(key).cloned()?;
+        tokio::fs::try_exists(self.blob_path(&layer.digest)).await.ok()?.then_some(layer)
+    }
+
+    /// Compress an uncompressed layer tar and cache it under `key`
+    async fn insert(&self, key: &str, tar: &[u8]) -> Result<LayerDescriptor> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(tar)?;
+        let blob = encoder.finish()?;
+        let layer = LayerDescriptor {
+            media_type: LAYER_MEDIA_TYPE.to_string(),
+            digest: sha256_digest(&blob),
+            size: blob.len() as u64,
+            diff_id: sha256_digest(tar),
+        };
+        tokio::fs::write(self.blob_path(&layer.digest), &blob).await?;
+
+        let mut index = self.index.lock().await;
+        index.insert(key.to_string(), layer.clone());
+        tokio::fs::write(self.dir.join("index.json"), serde_json::to_vec(&*index)?).await?;
+        Ok(layer)
+    }
+
+    async fn read_blob(&self, digest: &str) -> Result<Vec<u8>> {
+        Ok(tokio::fs::read(self.blob_path(digest)).await?)
+    }
+
+    fn blob_path(&self, digest: &str) -> PathBuf {
+        self.dir.join("blobs").join(digest.replace(':', "-"))
+    }
+}
+
+/// Fetch the build context into `dir`
+async fn fetch_source(config: &BuildConfig, source: &BuildSource, dir: &Path) -> Result<SourceProvenance> {
+    match source {
+        BuildSource::Bundle { archive } => {
+            let digest = sha256_digest(archive);
+            let (archive, dir) = (archive.clone(), dir.to_path_buf());
+            tokio::task::spawn_blocking(move || tar::Archive::new(GzDecoder::new(&archive[..])).unpack(dir))
+                .await?
+                .map_err(|e| RuntimeError::Build { message: format!("Invalid source bundle: {}", e) })?;
+            Ok(SourceProvenance::Bundle { digest })
+        }
+        BuildSource::Git { url, reference } => {
+            // Fetching by reference works for branches, tags and commit IDs alike
+            git(config, dir, &["init", "--quiet"]).await?;
+            git(config, dir, &["fetch", "--quiet", "--depth", "1", url.as_str(), reference.as_str()]).await?;
+            git(config, dir, &["checkout", "--quiet", "FETCH_HEAD"]).await?;
+            let commit = git(config, dir, &["rev-parse", "HEAD"]).await?;
+            Ok(SourceProvenance::Git { url: url.clone(), reference: reference.clone(), commit })
+        }
+    }
+}
+
+/// Run git in `dir`, returning its trimmed output
+async fn git(config: &BuildConfig, dir: &Path, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new(&config.git_command)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(RuntimeError::Build {
+            message: format!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Pack `from` in the build context as an uncompressed tar placed at `to`
+///
+/// Entries are sorted and carry no timestamps or owners, so the same files
+/// always produce the same layer.
+fn pack_copy(root: &Path, from: &str, to: &str) -> Result<Vec<u8>> {
+    let relative = Path::new(from);
+    if relative.components().any(|component| !matches!(component, Component::Normal(_) | Component::CurDir)) {
+        return Err(RuntimeError::Build { message: format!("Copy source {} must be relative to the context", from) });
+    }
+    if !to.starts_with('/') {
+        return Err(RuntimeError::Build { message: format!("Copy destination {} must be absolute", to) });
+    }
+    let source = root.join(relative).canonicalize()?;
+    if !source.starts_with(root.canonicalize()?) {
+        return Err(RuntimeError::Build { message: format!("Copy source {} leaves the context", from) });
+    }
+
+    let mut destination = PathBuf::from(to.trim_start_matches('/'));
+    if source.is_file() && to.ends_with('/') {
+        if let Some(name) = source.file_name() {
+            destination.push(name);
+        }
+    }
+    let mut builder = tar::Builder::new(Vec::new());
+    append_entry(&mut builder, &source, &destination)?;
+    Ok(builder.into_inner()?)
+}
+
+fn append_entry(builder: &mut tar::Builder<Vec<u8>>, path: &Path, name: &Path) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+
+    if metadata.file_type().is_symlink() {
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_mode(0o777);
+        header.set_size(0);
+        builder.append_link(&mut header, name, std::fs::read_link(path)?)?;
+    } else if metadata.is_dir() {
+        if !name.as_os_str().is_empty() {
+            header.set_entry_type(tar::EntryType::Directory);
+            header.set_mode(mode(&metadata, 0o755));
+            header.set_size(0);
+            builder.append_data(&mut header, name, std::io::empty())?;
+        }
+        let mut children: Vec<_> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<_>>()?;
+        children.sort();
+        for child in children {
+            append_entry(builder, &path.join(&child), &name.join(&child))?;
+        }
+    } else {
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_mode(mode(&metadata, 0o644));
+        header.set_size(metadata.len());
+        builder.append_data(&mut header, name, std::fs::File::open(path)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode(metadata: &std::fs::Metadata, _default: u32) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &std::fs::Metadata, default: u32) -> u32 {
+    default
+}
+
+fn image_config(recipe: &BuildRecipe, base: Option<&OciRunConfig>, layers: &[LayerDescriptor]) -> OciImageConfig {
+    let base = base.cloned().unwrap_or_default();
+    let mut labels = base.labels;
+    labels.extend(recipe.labels.clone());
+    OciImageConfig {
+        architecture: recipe.architecture.clone(),
+        os: recipe.os.clone(),
+        config: OciRunConfig {
+            env: base.env.into_iter().chain(recipe.env.iter().cloned()).collect(),
+            entrypoint: recipe.entrypoint.clone().or(base.entrypoint),
+            cmd: recipe.cmd.clone().or(base.cmd),
+            working_dir: recipe.workdir.clone().or(base.working_dir),
+            labels,
+        },
+        rootfs: OciRootFs {
+            kind: "layers".to_string(),
+            diff_ids: layers.iter().map(|layer| layer.diff_id.clone()).collect(),
+        },
+    }
+}
+
+pub(crate) fn sha256_digest(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OciManifest {
+    pub(crate) schema_version: u32,
+    pub(crate) media_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) artifact_type: Option<String>,
+    pub(crate) config: OciDescriptor,
+    pub(crate) layers: Vec<OciDescriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) subject: Option<OciDescriptor>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OciDescriptor {
+    pub(crate) media_type: String,
+    pub(crate) digest: String,
+    pub(crate) size: u64,
+}
+
+impl OciDescriptor {
+    pub(crate) fn new(media_type: &str, digest: String, size: u64) -> Self {
+        Self { media_type: media_type.to_string(), digest, size }
+    }
+}
+
+impl From<&LayerDescriptor> for OciDescriptor {
+    fn from(layer: &LayerDescriptor) -> Self {
+        Self::new(&layer.media_type, layer.digest.clone(), layer.size)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OciImageConfig {
+    pub(crate) architecture: String,
+    pub(crate) os: String,
+    #[serde(default)]
+    pub(crate) config: OciRunConfig,
+    pub(crate) rootfs: OciRootFs,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct OciRunConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) env: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) entrypoint: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cmd: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OciRootFs {
+    #[serde(rename = "type")]
+    kind: String,
+    diff_ids: Vec<String>,
+}
+
+/// In-memory registry for tests and single-node setups
+#[derive(Debug, Default)]
+pub struct MemoryRegistry {
+    blobs: parking_lot::RwLock<HashMap<String, Vec<u8>>>,
+    manifests: parking_lot::RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ImageRegistry for MemoryRegistry {
+    fn fetch_manifest(&self, reference: &str) -> BoxFuture<'static, Result<Option<Vec<u8>>>> {
+        let manifest = self.manifests.read().get(reference).cloned();
+        Box::pin(async move { Ok(manifest) })
+    }
+
+    fn fetch_blob(&self, digest: &str) -> BoxFuture<'static, Result<Option<Vec<u8>>>> {
+        let blob = self.blobs.read().get(digest).cloned();
+        Box::pin(async move { Ok(blob) })
+    }
+
+    fn has_blob(&self, digest: &str) -> BoxFuture<'static, Result<bool>> {
+        let present = self.blobs.read().contains_key(digest);
+        Box::pin(async move { Ok(present) })
+    }
+
+    fn push_blob(&self, digest: &str, data: Vec<u8>) -> BoxFuture<'static, Result<()>> {
+        let result = if sha256_digest(&data) == digest {
+            self.blobs.write().insert(digest.to_string(), data);
+            Ok(())
+        } else {
+            Err(RuntimeError::Registry { message: format!("Blob does not match digest {}", digest) })
+        };
+        Box::pin(async move { result })
+    }
+
+    fn push_manifest(&self, reference: &str, manifest: Vec<u8>) -> BoxFuture<'static, Result<()>> {
+        let name = reference.split([':', '@']).next().unwrap_or(reference);
+        let mut manifests = self.manifests.write();
+        manifests.insert(format!("{}@{}", name, sha256_digest(&manifest)), manifest.clone());
+        manifests.insert(reference.to_string(), manifest);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build node whose run steps produce a single file
+    struct EchoWorker {
+        runs: AtomicUsize,
+    }
+
+    impl BuildWorker for Arc<EchoWorker> {
+        fn node_id(&self) -> NodeId {
+            NodeId::new([7; 32])
+        }
+
+        fn run(&self, step: RunStep) -> BoxFuture<'static, Result<Vec<u8>>> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                let output = step.command.join(" ").into_bytes();
+                let mut header = tar::Header::new_gnu();
+                header.set_size(output.len() as u64);
+                header.set_mode(0o755);
+                let mut builder = tar::Builder::new(Vec::new());
+                builder.append_data(&mut header, "app/bin/server", &output[..])?;
+                Ok(builder.into_inner()?)
+            })
+        }
+    }
+
+    fn bundle(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_build_caches_layers_and_records_provenance() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let config = BuildConfig { cache_dir: cache_dir.path().to_string_lossy().to_string(), ..Default::default() };
+        let registry = Arc::new(MemoryRegistry::new());
+        let builder = ImageBuilder::new(&config, registry.clone()).await.unwrap();
+        let worker = Arc::new(EchoWorker { runs: AtomicUsize::new(0) });
+        builder.add_worker(Arc::new(worker.clone()));
+
+        let request = |archive: Vec<u8>| BuildRequest {
+            source: BuildSource::Bundle { archive },
+            recipe: BuildRecipe {
+                steps: vec![
+                    BuildStep::Copy { from: "src".to_string(), to: "/app/src".to_string() },
+                    BuildStep::Run { command: vec!["make".to_string(), "server".to_string()] },
+                ],
+                cmd: Some(vec!["/app/bin/server".to_string()]),
+                ..Default::default()
+            },
+            target: ImageSpec { name: "shop".to_string(), tag: "v1".to_string(), registry: None, digest: None },
+        };
+
+        let archive = bundle(&[("src/main.c", b"int main() {}")]);
+        let first = builder.build(request(archive.clone())).await.unwrap();
+        assert_eq!(first.cached_steps, 0);
+        assert_eq!(first.provenance.source, SourceProvenance::Bundle { digest: sha256_digest(&archive) });
+        assert_eq!(first.provenance.builder, Some(NodeId::new([7; 32])));
+        assert_eq!(first.provenance.layers.len(), 2);
+
+        // The manifest and its provenance artifact are in the registry
+        let digest = first.image.digest.clone().unwrap();
+        let manifest: OciManifest = serde_json::from_slice(&registry.fetch_manifest("shop:v1").await.unwrap().unwrap()).unwrap();
+        assert_eq!(sha256_digest(&serde_json::to_vec(&manifest).unwrap()), digest);
+        assert!(manifest.layers.iter().all(|layer| registry.blobs.read().contains_key(&layer.digest)));
+        let artifact: OciManifest = serde_json::from_slice(
+            &registry.fetch_manifest(&provenance_reference("shop", &digest)).await.unwrap().unwrap(),
+        ).unwrap();
+        assert_eq!(artifact.subject.unwrap().digest, digest);
+        assert_eq!(artifact.artifact_type.as_deref(), Some(PROVENANCE_MEDIA_TYPE));
+
+        // Rebuilding the same source reuses every layer and yields the same image
+        let second = builder.build(request(archive)).await.unwrap();
+        assert_eq!(second.cached_steps, 2);
+        assert_eq!(second.image.digest, first.image.digest);
+        assert_eq!(worker.runs.load(Ordering::SeqCst), 1);
+
+        // A source change invalidates the copy and every step after it
+        let third = builder.build(request(bundle(&[("src/main.c", b"int main() { return 1; }")]))).await.unwrap();
+        assert_eq!(third.cached_steps, 0);
+        assert_ne!(third.image.digest, first.image.digest);
+        assert!(builder.provenance(third.image.digest.as_ref().unwrap()).is_some());
+    }
+}
@@ -238,6 +238,21 @@ pub struct StorageConfig {
     
     /// Enable quotas
     pub enable_quotas: bool,
+    
+    /// Encrypted named volumes
+    #[serde(default)]
+    pub volume_encryption: VolumeEncryptionConfig,
+    
+    /// Storage class this node's volumes are reported under
+    #[serde(default = "default_storage_class")]
+    pub storage_class: String,
+}
+
+/// Storage class of volumes that do not name one
+pub const DEFAULT_STORAGE_CLASS: &str = "standard";
+
+fn default_storage_class() -> String {
+    DEFAULT_STORAGE_CLASS.to_string()
 }
 
 impl Default for StorageConfig {
@@ -252,6 +267,33 @@ impl Default for StorageConfig {
             compression_algorithm: CompressionAlgorithm::Zstd,
             max_container_size_gb: 100.0,
             enable_quotas: true,
+            volume_encryption: VolumeEncryptionConfig::default(),
+            storage_class: default_storage_class(),
+        }
+    }
+}
+
+/// Encrypted volume configuration
+///
+/// Encrypted volumes are LUKS images opened with `cryptsetup` and mounted
+/// while in use, so this node's disk only ever holds ciphertext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeEncryptionConfig {
+    pub cryptsetup_command: String,
+    
+    /// Filesystem created inside a new volume
+    pub mkfs_command: String,
+    
+    /// Image size of encrypted volumes created without one
+    pub default_size_bytes: u64,
+}
+
+impl Default for VolumeEncryptionConfig {
+    fn default() -> Self {
+        Self {
+            cryptsetup_command: "cryptsetup".to_string(),
+            mkfs_command: "mkfs.ext4".to_string(),
+            default_size_bytes: 10 * 1024 * 1024 * 1024,
         }
     }
 }
@@ -5,6 +5,8 @@ use crate::{ImageSpec, IsolationManager, SecurityManager};
 use crate::resources::{ResourceQuotas, ResourceUsage, ResourceAllocation};
 use crate::networking::NetworkConfig;
 use crate::config::StorageConfig;
+use crate::ephemeral::EphemeralVolume;
+use crate::probes::ContainerProbes;
 use nexus_shared::ResourceId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -42,6 +44,10 @@ pub struct ContainerSpec {
     /// Volume specifications
     pub volumes: Vec<VolumeMount>,
     
+    /// Scratch volumes created with the container and destroyed with it
+    #[serde(default)]
+    pub ephemeral_volumes: Vec<EphemeralVolume>,
+    
     /// Security configuration
     pub security: ContainerSecurityConfig,
     
@@ -50,6 +56,10 @@ pub struct ContainerSpec {
     
     /// Container restart policy
     pub restart_policy: RestartPolicy,
+    
+    /// Startup, liveness and readiness probes
+    #[serde(default)]
+    pub probes: ContainerProbes,
 }
 
 impl Default for ContainerSpec {
@@ -63,9 +73,11 @@ impl Default for ContainerSpec {
             resources: ResourceQuotas::default(),
             network: NetworkConfig::default(),
             volumes: Vec::new(),
+            ephemeral_volumes: Vec::new(),
             security: ContainerSecurityConfig::default(),
             labels: HashMap::new(),
             restart_policy: RestartPolicy::Never,
+            probes: ContainerProbes::default(),
         }
     }
 }
@@ -231,6 +243,11 @@ impl Container {
         &self.spec.id
     }
     
+    /// Get container specification
+    pub fn spec(&self) -> &ContainerSpec {
+        &self.spec
+    }
+    
     /// Get container creation time
     pub fn created_at(&self) -> SystemTime {
         self.created_at
@@ -360,6 +377,15 @@ impl Container {
         Ok(())
     }
     
+    /// Stop the container, giving it `grace` to exit, and start it again
+    pub async fn restart(&self, grace: std::time::Duration) -> Result<()> {
+        if self.status().await == ContainerStatus::Running {
+            self.stop(Some(grace)).await?;
+        }
+        *self.status.write().await = ContainerStatus::Created;
+        self.start().await
+    }
+    
     /// Kill the container immediately
     pub async fn kill(&self) -> Result<()> {
         let mut status = self.status.write().await;
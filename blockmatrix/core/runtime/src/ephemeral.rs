@@ -0,0 +1,145 @@
+//! Ephemeral inline volumes
+//!
+//! A workload can declare scratch space inline rather than naming a volume.
+//! Such a volume lives exactly as long as its container: it is created when
+//! the container is created on its node and destroyed when the container is
+//! removed, and nothing in it is snapshotted or carried to another node.
+//! Disk scratch is a directory under `<data_dir>/ephemeral`, and its usage
+//! counts against the container's disk quota. Memory scratch is a tmpfs
+//! capped at its size limit, and its size counts against the memory quota.
+//! Pages written to a tmpfs are charged to the memory cgroup of the
+//! container that wrote them, so the kernel accounts for them as well.
+
+use crate::resources::{ResourceQuotas, ResourceUsage};
+use crate::{Result, RuntimeError};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Where an ephemeral volume keeps its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EphemeralMedium {
+    /// A directory on the node's data disk
+    Disk,
+    /// A tmpfs, counted as memory
+    Memory,
+}
+
+/// A volume declared inline in a workload, created and destroyed with its container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EphemeralVolume {
+    pub name: String,
+    pub mount_path: String,
+    pub medium: EphemeralMedium,
+    /// Most the volume may hold
+    pub size_limit_bytes: u64,
+}
+
+/// How much of an ephemeral volume is in use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EphemeralUsage {
+    pub name: String,
+    pub medium: EphemeralMedium,
+    pub used_bytes: u64,
+    pub size_limit_bytes: u64,
+}
+
+impl EphemeralUsage {
+    pub fn exceeded(&self) -> bool {
+        self.used_bytes > self.size_limit_bytes
+    }
+}
+
+/// Check that a container's ephemeral volumes fit within its quotas
+///
+/// Disk scratch limits together must fit in the disk quota, and tmpfs
+/// limits together in the memory quota.
+pub fn check_quota(volumes: &[EphemeralVolume], quotas: &ResourceQuotas) -> Result<()> {
+    let mut names = std::collections::HashSet::new();
+    for volume in volumes {
+        if !crate::storage::is_named_volume(&volume.name) || volume.name.starts_with('.') || !names.insert(&volume.name) {
+            return Err(RuntimeError::Storage { message: format!("Invalid ephemeral volume name: {:?}", volume.name) });
+        }
+        if volume.size_limit_bytes == 0 {
+            return Err(RuntimeError::Storage { message: format!("Ephemeral volume {} needs a size limit", volume.name) });
+        }
+    }
+
+    let disk = total(volumes, EphemeralMedium::Disk);
+    if disk > quotas.disk_limit {
+        return Err(RuntimeError::ResourceAllocation {
+            message: format!("Disk scratch of {} bytes exceeds the disk quota of {} bytes", disk, quotas.disk_limit),
+        });
+    }
+    let memory = total(volumes, EphemeralMedium::Memory);
+    if memory > quotas.memory_limit {
+        return Err(RuntimeError::ResourceAllocation {
+            message: format!("Memory scratch of {} bytes exceeds the memory quota of {} bytes", memory, quotas.memory_limit),
+        });
+    }
+    Ok(())
+}
+
+/// Sum of the size limits of the volumes on one medium
+pub fn total(volumes: &[EphemeralVolume], medium: EphemeralMedium) -> u64 {
+    volumes.iter().filter(|volume| volume.medium == medium).map(|volume| volume.size_limit_bytes).sum()
+}
+
+/// Count disk scratch in a container's disk usage
+///
+/// Memory scratch is left out: the cgroup already counts it as memory.
+pub fn add_usage(usage: &mut ResourceUsage, ephemeral: &[EphemeralUsage]) {
+    usage.disk_usage += ephemeral
+        .iter()
+        .filter(|volume| volume.medium == EphemeralMedium::Disk)
+        .map(|volume| volume.used_bytes)
+        .sum::<u64>();
+}
+
+/// Bytes allocated on disk under a directory
+pub(crate) fn allocated_size(dir: &Path) -> io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += allocated_size(&entry.path())?;
+        } else {
+            size += metadata.blocks() * 512;
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(name: &str, medium: EphemeralMedium, size_limit_bytes: u64) -> EphemeralVolume {
+        EphemeralVolume { name: name.to_string(), mount_path: format!("/scratch/{}", name), medium, size_limit_bytes }
+    }
+
+    #[test]
+    fn test_ephemeral_volumes_count_against_quota() {
+        let quotas = ResourceQuotas { disk_limit: 1000, memory_limit: 500, ..Default::default() };
+        let fits = [volume("build", EphemeralMedium::Disk, 600), volume("cache", EphemeralMedium::Disk, 400), volume("shm", EphemeralMedium::Memory, 500)];
+        assert!(check_quota(&fits, &quotas).is_ok());
+
+        let too_much_disk = [volume("build", EphemeralMedium::Disk, 600), volume("cache", EphemeralMedium::Disk, 401)];
+        assert!(matches!(check_quota(&too_much_disk, &quotas), Err(RuntimeError::ResourceAllocation { .. })));
+        assert!(check_quota(&[volume("shm", EphemeralMedium::Memory, 501)], &quotas).is_err());
+        assert!(check_quota(&[volume("a", EphemeralMedium::Disk, 1), volume("a", EphemeralMedium::Memory, 1)], &quotas).is_err());
+        assert!(check_quota(&[volume("../etc", EphemeralMedium::Disk, 1)], &quotas).is_err());
+        assert!(check_quota(&[volume("unbounded", EphemeralMedium::Disk, 0)], &quotas).is_err());
+
+        let mut usage = ResourceUsage { cpu_usage: 0.0, memory_usage: 100, disk_usage: 10 };
+        let ephemeral = [
+            EphemeralUsage { name: "build".to_string(), medium: EphemeralMedium::Disk, used_bytes: 700, size_limit_bytes: 600 },
+            EphemeralUsage { name: "shm".to_string(), medium: EphemeralMedium::Memory, used_bytes: 50, size_limit_bytes: 500 },
+        ];
+        add_usage(&mut usage, &ephemeral);
+        assert_eq!((usage.memory_usage, usage.disk_usage), (100, 710));
+        assert!(ephemeral[0].exceeded() && !ephemeral[1].exceeded());
+    }
+}
@@ -23,6 +23,12 @@ pub enum RuntimeError {
     #[error("Image pull failed: {message}")]
     ImagePullFailed { message: String },
 
+    #[error("Image build failed: {message}")]
+    Build { message: String },
+
+    #[error("Registry error: {message}")]
+    Registry { message: String },
+
     #[error("Isolation error: {message}")]
     Isolation { message: String },
 
@@ -108,6 +114,7 @@ impl RuntimeError {
     pub fn is_retryable(&self) -> bool {
         match self {
             RuntimeError::ImagePullFailed { .. } => true,
+            RuntimeError::Registry { .. } => true,
             RuntimeError::ResourceAllocation { .. } => true,
             RuntimeError::Network { .. } => true,
             RuntimeError::Storage { .. } => true,
@@ -127,6 +134,8 @@ impl RuntimeError {
             RuntimeError::ContainerNotRunning { .. } => "container_not_running",
             RuntimeError::ImageNotFound { .. } => "image_not_found",
             RuntimeError::ImagePullFailed { .. } => "image_pull",
+            RuntimeError::Build { .. } => "build",
+            RuntimeError::Registry { .. } => "registry",
             RuntimeError::Isolation { .. } => "isolation",
             RuntimeError::ResourceAllocation { .. } => "resources",
             RuntimeError::Network { .. } => "network",
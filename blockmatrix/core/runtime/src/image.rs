@@ -1,5 +1,6 @@
 //! Container image management
 
+use crate::registry::{CredentialCache, HttpsTransport, RegistryClient, RegistryConfig, RegistryTransport, SignatureVerifier};
 use crate::{Result, RuntimeError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -18,6 +19,9 @@ pub struct ImageManager {
     
     /// Image metadata store
     metadata_store: Arc<RwLock<HashMap<String, ImageMetadata>>>,
+    
+    /// Authenticated registry access
+    registry: RegistryClient,
 }
 
 /// Image configuration
@@ -32,8 +36,9 @@ pub struct ImageConfig {
     /// Default registry URL
     pub default_registry: String,
     
-    /// Authentication for registries
-    pub registry_auth: HashMap<String, RegistryAuth>,
+    /// Credentials and policy per registry host
+    #[serde(default)]
+    pub registries: HashMap<String, RegistryConfig>,
     
     /// Image pull timeout
     pub pull_timeout_seconds: u64,
@@ -45,20 +50,12 @@ impl Default for ImageConfig {
             storage_dir: "./data/images".to_string(),
             max_cache_size: 10 * 1024 * 1024 * 1024, // 10GB
             default_registry: "docker.io".to_string(),
-            registry_auth: HashMap::new(),
+            registries: HashMap::new(),
             pull_timeout_seconds: 600, // 10 minutes
         }
     }
 }
 
-/// Registry authentication
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RegistryAuth {
-    pub username: String,
-    pub password: String,
-    pub registry_url: String,
-}
-
 /// Container image specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageSpec {
@@ -166,6 +163,10 @@ pub struct ImageMetadata {
     
     /// Labels
     pub labels: HashMap<String, String>,
+    
+    /// Digest of the pulled manifest
+    #[serde(default)]
+    pub manifest_digest: Option<String>,
 }
 
 /// Image layer information
@@ -193,13 +194,34 @@ impl ImageManager {
                 message: format!("Failed to create image storage dir: {}", e) 
             })?;
         
+        let registry = RegistryClient::new(config.registries.clone());
+        registry.set_transport(Arc::new(HttpsTransport::new()?));
+        
         Ok(Self {
             config: config.clone(),
             image_cache: Arc::new(RwLock::new(HashMap::new())),
             metadata_store: Arc::new(RwLock::new(HashMap::new())),
+            registry,
         })
     }
     
+    /// Replace how registry requests are sent, e.g. to trust only a private CA
+    ///
+    /// By default they go over HTTPS, trusting the web PKI roots.
+    pub fn set_registry_transport(&self, transport: Arc<dyn RegistryTransport>) {
+        self.registry.set_transport(transport);
+    }
+    
+    /// Set the verifier for registries that require signed images
+    pub fn set_signature_verifier(&self, verifier: Arc<dyn SignatureVerifier>) {
+        self.registry.set_verifier(verifier);
+    }
+    
+    /// Bearer tokens cached from registry token services
+    pub fn credential_cache(&self) -> &CredentialCache {
+        self.registry.tokens()
+    }
+    
     /// Ensure an image is available locally
     pub async fn ensure_image(&self, spec: &ImageSpec) -> Result<Arc<Image>> {
         let cache_key = spec.cache_key();
@@ -233,53 +255,65 @@ impl ImageManager {
     
     /// Pull an image from registry
     async fn pull_image(&self, spec: &ImageSpec) -> Result<Image> {
-        // This is a simplified implementation
-        // In a real implementation, this would:
-        // 1. Connect to the registry
-        // 2. Authenticate if needed
-        // 3. Download the image manifest
-        // 4. Download all layers
-        // 5. Store layers locally
-        // 6. Build the image metadata
-        
         let full_ref = spec.full_reference(&self.config.default_registry);
         info!("Pulling image from registry: {}", full_ref);
         
-        // Simulate image pull for now
+        let timeout = std::time::Duration::from_secs(self.config.pull_timeout_seconds);
+        let image = tokio::time::timeout(timeout, self.pull_from_registry(spec)).await
+            .map_err(|_| RuntimeError::ImagePullFailed {
+                message: format!("Pull of {} timed out after {:?}", full_ref, timeout),
+            })??;
+        
+        info!("Successfully pulled image: {}", full_ref);
+        Ok(image)
+    }
+    
+    /// Pull an image's manifest, config and layers and store them locally
+    async fn pull_from_registry(&self, spec: &ImageSpec) -> Result<Image> {
+        let registry = spec.registry.as_deref().unwrap_or(&self.config.default_registry);
+        let reference = spec.digest.as_deref().unwrap_or(&spec.tag);
+        let pulled = self.registry.pull(registry, &spec.name, reference, ("linux", host_architecture())).await?;
+        
+        let storage_path = Path::new(&self.config.storage_dir).join(&pulled.config_digest);
+        tokio::fs::create_dir_all(&storage_path).await?;
+        let mut layers = Vec::with_capacity(pulled.layers.len());
+        for (media_type, digest, data) in pulled.layers {
+            let path = storage_path.join(digest.replace(':', "_"));
+            tokio::fs::write(&path, &data).await?;
+            layers.push(ImageLayer {
+                digest,
+                size: data.len() as u64,
+                path,
+                compressed: media_type.ends_with("gzip"),
+            });
+        }
+        
+        let run = pulled.config.config;
         let metadata = ImageMetadata {
-            image_id: format!("sha256:{}", blake3::hash(full_ref.as_bytes()).to_hex()),
+            image_id: pulled.config_digest,
             parent_id: None,
             created: std::time::SystemTime::now(),
-            size: 1024 * 1024, // 1MB placeholder
-            architecture: "amd64".to_string(),
-            os: "linux".to_string(),
+            size: layers.iter().map(|layer| layer.size).sum(),
+            architecture: pulled.config.architecture,
+            os: pulled.config.os,
             config: self.config.clone(),
-            env: vec!["PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".to_string()],
-            entrypoint: vec!["/bin/sh".to_string()],
-            cmd: vec![],
-            workdir: Some("/".to_string()),
+            env: run.env,
+            entrypoint: run.entrypoint.unwrap_or_default(),
+            cmd: run.cmd.unwrap_or_default(),
+            workdir: run.working_dir,
             exposed_ports: HashMap::new(),
-            labels: HashMap::new(),
+            labels: run.labels.into_iter().collect(),
+            manifest_digest: Some(pulled.manifest_digest),
         };
+        self.metadata_store.write().await.insert(spec.cache_key(), metadata.clone());
         
-        let storage_path = Path::new(&self.config.storage_dir)
-            .join(&metadata.image_id);
-        
-        // Create storage directory
-        tokio::fs::create_dir_all(&storage_path).await
-            .map_err(|e| RuntimeError::Storage { 
-                message: format!("Failed to create image storage: {}", e) 
-            })?;
-        
-        let image = Image {
+        info!("Pulled {} ({} layers)", spec.full_reference(&self.config.default_registry), layers.len());
+        Ok(Image {
             spec: spec.clone(),
             metadata,
             storage_path,
-            layers: vec![], // Would contain actual layers
-        };
-        
-        info!("Successfully pulled image: {}", full_ref);
-        Ok(image)
+            layers,
+        })
     }
     
     /// Load an image from local storage
@@ -408,6 +442,15 @@ impl Image {
     }
 }
 
+/// OCI architecture name of this host
+fn host_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    }
+}
+
 /// Container configuration from image
 #[derive(Debug, Clone)]
 pub struct ContainerImageConfig {
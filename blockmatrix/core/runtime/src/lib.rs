@@ -13,10 +13,15 @@
 
 pub mod container;
 pub mod image;
+pub mod build;
 pub mod isolation;
+pub mod probes;
+pub mod registry;
 pub mod resources;
 pub mod networking;
 pub mod storage;
+pub mod ephemeral;
+pub mod volume_crypto;
 pub mod security;
 pub mod config;
 pub mod error;
@@ -37,10 +42,15 @@ pub mod stoq_benchmark;
 
 pub use container::{Container, ContainerSpec, ContainerStatus};
 pub use image::{ImageManager, ImageSpec};
+pub use build::{BuildConfig, BuildRequest, BuildRecipe, BuildSource, BuildStep, BuildProvenance, BuildWorker, ImageBuilder, ImageRegistry};
 pub use isolation::{IsolationManager, NamespaceConfig};
+pub use probes::{ContainerProbes, LocalProbeHandler, Probe, ProbeAction, ProbeManager, ProbeStatus, ReadinessSink};
+pub use registry::{CredentialCache, RegistryConfig, RegistryCredentials, RegistryTransport, SignatureVerifier};
 pub use resources::{ResourceManager, ResourceQuotas, ResourceUsage};
 pub use networking::{NetworkManager, NetworkConfig};
-pub use storage::{StorageManager, VolumeSpec};
+pub use storage::{SnapshotMethod, StorageCapacity, StorageManager, VolumeSnapshot, VolumeSpec};
+pub use volume_crypto::{Cryptsetup, VolumeKeyRecord};
+pub use ephemeral::{EphemeralMedium, EphemeralUsage, EphemeralVolume};
 pub use security::{SecurityManager, SecurityPolicy};
 pub use config::RuntimeConfig;
 pub use error::{RuntimeError, Result};
@@ -73,6 +83,7 @@ pub struct Runtime {
     network_manager: Arc<NetworkManager>,
     storage_manager: Arc<StorageManager>,
     security_manager: Arc<SecurityManager>,
+    probe_manager: Arc<ProbeManager>,
 }
 
 impl Runtime {
@@ -84,6 +95,7 @@ impl Runtime {
         let network_manager = Arc::new(NetworkManager::new_stub(config.networking.clone()).await?);
         let storage_manager = Arc::new(StorageManager::new(&config.storage)?);
         let security_manager = Arc::new(SecurityManager::new(&config.security)?);
+        let probe_manager = Arc::new(ProbeManager::new(Arc::new(LocalProbeHandler::default())));
         
         Ok(Self {
             config,
@@ -94,13 +106,15 @@ impl Runtime {
             network_manager,
             storage_manager,
             security_manager,
+            probe_manager,
         })
     }
     
     /// Create and start a new container
-    pub async fn create_container(&self, spec: ContainerSpec) -> Result<ResourceId> {
+    pub async fn create_container(&self, mut spec: ContainerSpec) -> Result<ResourceId> {
         // Validate container specification
         self.security_manager.validate_spec(&spec).await?;
+        ephemeral::check_quota(&spec.ephemeral_volumes, &spec.resources)?;
         
         // Pull container image if needed
         let image = self.image_manager.ensure_image(&spec.image).await?;
@@ -120,8 +134,15 @@ impl Runtime {
             .prepare_volumes(&spec.volumes)
             .await?;
         
+        // Ephemeral volumes live exactly as long as the container
+        let container_id = spec.id.clone();
+        let ephemeral_mounts = self.storage_manager
+            .create_ephemeral(&container_id, &spec.ephemeral_volumes)
+            .await?;
+        spec.volumes.extend(ephemeral_mounts);
+        
         // Create container
-        let container = Container::new(
+        let container = match Container::new(
             spec,
             image,
             resource_allocation,
@@ -129,7 +150,15 @@ impl Runtime {
             storage_config,
             Arc::clone(&self.isolation_manager),
             Arc::clone(&self.security_manager),
-        ).await?;
+        ).await {
+            Ok(container) => container,
+            Err(e) => {
+                if let Err(cleanup) = self.storage_manager.destroy_ephemeral(&container_id).await {
+                    tracing::warn!("Failed to remove ephemeral volumes of {}: {}", container_id, cleanup);
+                }
+                return Err(e);
+            }
+        };
         
         let container_id = container.id().clone();
         self.containers.insert(container_id.clone(), Arc::new(container));
@@ -145,6 +174,7 @@ impl Runtime {
             .ok_or_else(|| RuntimeError::ContainerNotFound { id: id.clone() })?;
             
         container.start().await?;
+        self.probe_manager.watch(container.value().clone()).await?;
         tracing::info!("Container started: {}", id);
         Ok(())
     }
@@ -155,6 +185,8 @@ impl Runtime {
             .get(id)
             .ok_or_else(|| RuntimeError::ContainerNotFound { id: id.clone() })?;
             
+        // Stop probing first so the stop is not taken for a failure
+        self.probe_manager.unwatch(id).await;
         container.stop(timeout).await?;
         tracing::info!("Container stopped: {}", id);
         Ok(())
@@ -167,6 +199,7 @@ impl Runtime {
             .ok_or_else(|| RuntimeError::ContainerNotFound { id: id.clone() })?;
             
         // Stop container if running
+        self.probe_manager.unwatch(id).await;
         if container.status().await == ContainerStatus::Running {
             if force {
                 container.kill().await?;
@@ -177,6 +210,7 @@ impl Runtime {
         
         // Clean up resources
         container.cleanup().await?;
+        self.storage_manager.destroy_ephemeral(id).await?;
         
         // Remove from tracking
         self.containers.remove(id);
@@ -185,6 +219,36 @@ impl Runtime {
         Ok(())
     }
     
+    /// Snapshot a named volume
+    pub async fn snapshot_volume(&self, volume_id: &str) -> Result<VolumeSnapshot> {
+        self.storage_manager.snapshot_volume(volume_id).await
+    }
+
+    /// Restore a named volume from a snapshot
+    ///
+    /// Refused while a running container mounts the volume.
+    pub async fn restore_volume(&self, snapshot_id: &str) -> Result<VolumeSnapshot> {
+        let snapshot = self.storage_manager.snapshot(snapshot_id).await?;
+        let containers: Vec<Arc<Container>> = self.containers.iter().map(|c| c.value().clone()).collect();
+        for container in containers {
+            let mounted = container.spec().volumes.iter().any(|v| v.source == snapshot.volume_id);
+            if mounted && container.status().await == ContainerStatus::Running {
+                return Err(RuntimeError::ContainerRunning { id: container.id().clone() });
+            }
+        }
+        self.storage_manager.restore_volume(snapshot_id).await
+    }
+
+    /// Get the storage manager, to set the object store snapshots are uploaded to
+    pub fn storage_manager(&self) -> &Arc<StorageManager> {
+        &self.storage_manager
+    }
+
+    /// Get the probe manager, to add readiness sinks or an HTTP probe client
+    pub fn probe_manager(&self) -> &Arc<ProbeManager> {
+        &self.probe_manager
+    }
+    
     /// Get container status
     pub async fn container_status(&self, id: &ResourceId) -> Result<ContainerStatus> {
         let container = self.containers
@@ -217,7 +281,11 @@ impl Runtime {
         
         for entry in self.containers.iter() {
             let container = entry.value();
-            if let Ok(container_usage) = container.resource_usage().await {
+            if let Ok(mut container_usage) = container.resource_usage().await {
+                match self.storage_manager.ephemeral_usage(container.id(), &container.spec().ephemeral_volumes).await {
+                    Ok(ephemeral) => ephemeral::add_usage(&mut container_usage, &ephemeral),
+                    Err(e) => tracing::warn!("Failed to measure ephemeral volumes of {}: {}", container.id(), e),
+                }
                 usage.insert(container.id().clone(), container_usage);
             }
         }
@@ -225,6 +293,41 @@ impl Runtime {
         usage
     }
     
+    /// How much of each of a container's ephemeral volumes is in use
+    pub async fn ephemeral_usage(&self, id: &ResourceId) -> Result<Vec<EphemeralUsage>> {
+        let container = self.containers
+            .get(id)
+            .map(|c| c.value().clone())
+            .ok_or_else(|| RuntimeError::ContainerNotFound { id: id.clone() })?;
+        
+        self.storage_manager.ephemeral_usage(id, &container.spec().ephemeral_volumes).await
+    }
+    
+    /// Kill running containers whose disk scratch has outgrown its size limit
+    ///
+    /// A tmpfs cannot grow past its limit, so only disk scratch is checked.
+    /// Returns the containers killed.
+    pub async fn enforce_ephemeral_limits(&self) -> Result<Vec<ResourceId>> {
+        let containers: Vec<Arc<Container>> = self.containers.iter().map(|c| c.value().clone()).collect();
+        let mut killed = Vec::new();
+        for container in containers {
+            if container.spec().ephemeral_volumes.is_empty() || container.status().await != ContainerStatus::Running {
+                continue;
+            }
+            let usage = self.storage_manager.ephemeral_usage(container.id(), &container.spec().ephemeral_volumes).await?;
+            if let Some(over) = usage.iter().find(|volume| volume.exceeded()) {
+                tracing::warn!(
+                    "Container {} exceeded its {} byte limit on ephemeral volume {}; killing it",
+                    container.id(), over.size_limit_bytes, over.name
+                );
+                self.probe_manager.unwatch(container.id()).await;
+                container.kill().await?;
+                killed.push(container.id().clone());
+            }
+        }
+        Ok(killed)
+    }
+    
     /// Execute command in running container
     pub async fn exec_in_container(
         &self,
@@ -0,0 +1,522 @@
+//! Container health probes
+//!
+//! A container can declare startup, liveness and readiness probes. Each is an
+//! exec command, a TCP connect or an HTTP GET over QUIC, run every `period`
+//! once its `initial_delay` grace window has passed. Liveness and readiness
+//! probes only start after the startup probe succeeds, so slow starters are
+//! not killed while booting. A startup or liveness probe that fails
+//! `failure_threshold` times in a row hands the container to its restart
+//! policy. Readiness flips after `success_threshold` passes or
+//! `failure_threshold` failures and is reported to every `ReadinessSink`,
+//! which is how unready containers leave load balancing.
+
+use crate::container::{Container, ContainerStatus, RestartPolicy};
+use crate::{Result, RuntimeError};
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use nexus_shared::ResourceId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// What a probe checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeAction {
+    /// Run a command in the container, passing on exit code 0
+    Exec { command: Vec<String> },
+    /// Open a TCP connection to the port
+    Tcp { port: u16 },
+    /// GET the path over QUIC, passing on a 2xx or 3xx status
+    Http { port: u16, path: String },
+}
+
+/// A probe and its schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Probe {
+    pub action: ProbeAction,
+    /// Grace window after the container starts before the first check
+    pub initial_delay: Duration,
+    pub period: Duration,
+    /// A check that takes longer fails
+    pub timeout: Duration,
+    /// Consecutive passes before the probe counts as passing
+    pub success_threshold: u32,
+    /// Consecutive failures before the probe counts as failing
+    pub failure_threshold: u32,
+}
+
+impl Probe {
+    pub fn new(action: ProbeAction) -> Self {
+        Self {
+            action,
+            initial_delay: Duration::ZERO,
+            period: Duration::from_secs(10),
+            timeout: Duration::from_secs(1),
+            success_threshold: 1,
+            failure_threshold: 3,
+        }
+    }
+}
+
+/// Probes declared by a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerProbes {
+    pub startup: Option<Probe>,
+    pub liveness: Option<Probe>,
+    pub readiness: Option<Probe>,
+    /// Time a failing container gets to exit before it is killed on restart
+    pub termination_grace: Duration,
+}
+
+impl Default for ContainerProbes {
+    fn default() -> Self {
+        Self {
+            startup: None,
+            liveness: None,
+            readiness: None,
+            termination_grace: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ContainerProbes {
+    pub fn is_empty(&self) -> bool {
+        self.startup.is_none() && self.liveness.is_none() && self.readiness.is_none()
+    }
+
+    /// Check every probe has a period and non-zero thresholds
+    pub fn validate(&self) -> Result<()> {
+        for (kind, probe) in self.iter() {
+            if probe.period.is_zero() || probe.success_threshold == 0 || probe.failure_threshold == 0 {
+                return Err(RuntimeError::Configuration {
+                    message: format!("{:?} probe needs a non-zero period and thresholds", kind),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (ProbeKind, &Probe)> {
+        [
+            (ProbeKind::Startup, self.startup.as_ref()),
+            (ProbeKind::Liveness, self.liveness.as_ref()),
+            (ProbeKind::Readiness, self.readiness.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(kind, probe)| probe.map(|probe| (kind, probe)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProbeKind {
+    Startup,
+    Liveness,
+    Readiness,
+}
+
+/// Probe state of a watched container
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProbeStatus {
+    /// The startup probe has passed, or there is none
+    pub started: bool,
+    pub ready: bool,
+    /// Restarts triggered by failed probes
+    pub restarts: u32,
+    pub last_failure: Option<String>,
+}
+
+/// Runs probe checks against containers
+pub trait ProbeHandler: Send + Sync {
+    /// Run one check, resolving to whether it passed
+    fn check(&self, container: Arc<Container>, action: ProbeAction) -> BoxFuture<'static, Result<bool>>;
+}
+
+/// Client for HTTP probes over QUIC
+pub trait HttpProbeClient: Send + Sync {
+    /// GET `path` from `addr`, resolving to the response status
+    fn get(&self, addr: SocketAddr, path: String) -> BoxFuture<'static, Result<u16>>;
+}
+
+/// Notified when a container becomes ready or unready
+pub trait ReadinessSink: Send + Sync {
+    fn readiness_changed(
+        &self,
+        container: ResourceId,
+        labels: HashMap<String, String>,
+        ready: bool,
+    ) -> BoxFuture<'static, Result<()>>;
+}
+
+/// Probes containers reachable at a fixed address, such as the local host
+pub struct LocalProbeHandler {
+    address: IpAddr,
+    http: Option<Arc<dyn HttpProbeClient>>,
+}
+
+impl LocalProbeHandler {
+    pub fn new(address: IpAddr) -> Self {
+        Self { address, http: None }
+    }
+
+    pub fn with_http_client(mut self, client: Arc<dyn HttpProbeClient>) -> Self {
+        self.http = Some(client);
+        self
+    }
+}
+
+impl Default for LocalProbeHandler {
+    fn default() -> Self {
+        Self::new(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    }
+}
+
+impl ProbeHandler for LocalProbeHandler {
+    fn check(&self, container: Arc<Container>, action: ProbeAction) -> BoxFuture<'static, Result<bool>> {
+        let address = self.address;
+        let http = self.http.clone();
+        Box::pin(async move {
+            match action {
+                ProbeAction::Exec { command } => {
+                    Ok(container.exec(command, HashMap::new()).await?.exit_code == 0)
+                }
+                ProbeAction::Tcp { port } => {
+                    Ok(tokio::net::TcpStream::connect(SocketAddr::new(address, port)).await.is_ok())
+                }
+                ProbeAction::Http { port, path } => {
+                    let http = http.ok_or_else(|| RuntimeError::Configuration {
+                        message: "HTTP probes need a QUIC HTTP client".to_string(),
+                    })?;
+                    let status = http.get(SocketAddr::new(address, port), path).await?;
+                    Ok((200..400).contains(&status))
+                }
+            }
+        })
+    }
+}
+
+/// Runs the probes of started containers
+pub struct ProbeManager {
+    context: Arc<ProbeContext>,
+    watched: DashMap<ResourceId, Watched>,
+}
+
+/// What probe tasks share with the manager
+struct ProbeContext {
+    handler: parking_lot::RwLock<Arc<dyn ProbeHandler>>,
+    sinks: parking_lot::RwLock<Vec<Arc<dyn ReadinessSink>>>,
+}
+
+struct Watched {
+    container: Arc<Container>,
+    /// None for containers without probes
+    task: Option<JoinHandle<()>>,
+    status: Arc<parking_lot::Mutex<ProbeStatus>>,
+}
+
+/// Why probing of one container run ended
+enum RunEnd {
+    /// A startup or liveness probe failed
+    Failed(ProbeKind),
+    /// The container stopped or nothing is left to probe
+    Done,
+}
+
+impl ProbeManager {
+    pub fn new(handler: Arc<dyn ProbeHandler>) -> Self {
+        Self {
+            context: Arc::new(ProbeContext {
+                handler: parking_lot::RwLock::new(handler),
+                sinks: parking_lot::RwLock::new(Vec::new()),
+            }),
+            watched: DashMap::new(),
+        }
+    }
+
+    /// Replace the handler used for checks from now on
+    pub fn set_handler(&self, handler: Arc<dyn ProbeHandler>) {
+        *self.context.handler.write() = handler;
+    }
+
+    pub fn add_readiness_sink(&self, sink: Arc<dyn ReadinessSink>) {
+        self.context.sinks.write().push(sink);
+    }
+
+    /// Start probing a running container
+    ///
+    /// A container without probes is ready as soon as it is watched.
+    pub async fn watch(&self, container: Arc<Container>) -> Result<()> {
+        let probes = container.spec().probes.clone();
+        probes.validate()?;
+        self.unwatch(container.id()).await;
+
+        let status = Arc::new(parking_lot::Mutex::new(ProbeStatus::default()));
+        let task = if probes.is_empty() {
+            status.lock().started = true;
+            self.context.set_ready(&container, &status, true).await;
+            None
+        } else {
+            let context = self.context.clone();
+            let (container, status) = (container.clone(), status.clone());
+            Some(tokio::spawn(async move { context.probe_loop(container, probes, status).await }))
+        };
+        self.watched.insert(container.id().clone(), Watched { container, task, status });
+        Ok(())
+    }
+
+    /// Stop probing a container, reporting it unready if it was ready
+    pub async fn unwatch(&self, id: &ResourceId) {
+        let Some((_, watched)) = self.watched.remove(id) else {
+            return;
+        };
+        if let Some(task) = &watched.task {
+            task.abort();
+        }
+        self.context.set_ready(&watched.container, &watched.status, false).await;
+    }
+
+    pub fn status(&self, id: &ResourceId) -> Option<ProbeStatus> {
+        self.watched.get(id).map(|watched| watched.status.lock().clone())
+    }
+}
+
+impl std::fmt::Debug for ProbeManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProbeManager")
+            .field("watched", &self.watched.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ProbeManager {
+    fn drop(&mut self) {
+        for watched in self.watched.iter() {
+            if let Some(task) = &watched.task {
+                task.abort();
+            }
+        }
+    }
+}
+
+impl ProbeContext {
+    async fn probe_loop(
+        &self,
+        container: Arc<Container>,
+        probes: ContainerProbes,
+        status: Arc<parking_lot::Mutex<ProbeStatus>>,
+    ) {
+        loop {
+            let RunEnd::Failed(kind) = self.probe_run(&container, &probes, &status).await else {
+                return;
+            };
+            self.set_ready(&container, &status, false).await;
+
+            if container.spec().restart_policy == RestartPolicy::Never {
+                warn!("{:?} probe failed for {}, killing it without restart", kind, container.id());
+                if let Err(e) = container.kill().await {
+                    warn!("Failed to kill {}: {}", container.id(), e);
+                }
+                return;
+            }
+            warn!("{:?} probe failed for {}, restarting it", kind, container.id());
+            if let Err(e) = container.restart(probes.termination_grace).await {
+                warn!("Failed to restart {}: {}", container.id(), e);
+                status.lock().last_failure = Some(format!("restart failed: {}", e));
+                return;
+            }
+            let mut status = status.lock();
+            status.started = false;
+            status.restarts += 1;
+        }
+    }
+
+    /// Probe one run of the container, from its start until a probe fails
+    async fn probe_run(
+        &self,
+        container: &Arc<Container>,
+        probes: &ContainerProbes,
+        status: &parking_lot::Mutex<ProbeStatus>,
+    ) -> RunEnd {
+        let started_at = Instant::now();
+        let mut trackers: Vec<ProbeTracker> = probes
+            .iter()
+            .map(|(kind, probe)| ProbeTracker::new(kind, probe.clone(), started_at))
+            .collect();
+        let mut started = probes.startup.is_none();
+        if started {
+            self.mark_started(container, probes, status).await;
+        }
+
+        loop {
+            // Only the startup probe runs until it passes, then only the others
+            let Some(tracker) = trackers
+                .iter_mut()
+                .filter(|tracker| (tracker.kind == ProbeKind::Startup) != started)
+                .min_by_key(|tracker| tracker.next_run)
+            else {
+                return RunEnd::Done;
+            };
+            tokio::time::sleep_until(tracker.next_run).await;
+            if container.status().await != ContainerStatus::Running {
+                return RunEnd::Done;
+            }
+
+            let handler = self.handler.read().clone();
+            let check = handler.check(container.clone(), tracker.probe.action.clone());
+            let passed = match tokio::time::timeout(tracker.probe.timeout, check).await {
+                Ok(Ok(passed)) => passed,
+                Ok(Err(e)) => {
+                    status.lock().last_failure = Some(e.to_string());
+                    false
+                }
+                Err(_) => {
+                    status.lock().last_failure = Some(format!("{:?} probe timed out", tracker.kind));
+                    false
+                }
+            };
+
+            let kind = tracker.kind;
+            match tracker.record(passed, Instant::now()) {
+                Some(Transition::Started) => {
+                    started = true;
+                    // The grace windows of the other probes start once startup has passed
+                    let now = Instant::now();
+                    for tracker in &mut trackers {
+                        tracker.next_run = now + tracker.probe.initial_delay;
+                    }
+                    self.mark_started(container, probes, status).await;
+                }
+                Some(Transition::Ready) => self.set_ready(container, status, true).await,
+                Some(Transition::Unready) => self.set_ready(container, status, false).await,
+                Some(Transition::Failed) => return RunEnd::Failed(kind),
+                None => {}
+            }
+        }
+    }
+
+    async fn mark_started(&self, container: &Arc<Container>, probes: &ContainerProbes, status: &parking_lot::Mutex<ProbeStatus>) {
+        status.lock().started = true;
+        info!("Container {} started", container.id());
+        if probes.readiness.is_none() {
+            self.set_ready(container, status, true).await;
+        }
+    }
+
+    async fn set_ready(&self, container: &Arc<Container>, status: &parking_lot::Mutex<ProbeStatus>, ready: bool) {
+        {
+            let mut status = status.lock();
+            if status.ready == ready {
+                return;
+            }
+            status.ready = ready;
+        }
+        info!("Container {} is {}", container.id(), if ready { "ready" } else { "unready" });
+
+        let sinks = self.sinks.read().clone();
+        for sink in sinks {
+            let result = sink.readiness_changed(container.id().clone(), container.spec().labels.clone(), ready).await;
+            if let Err(e) = result {
+                warn!("Failed to report readiness of {}: {}", container.id(), e);
+            }
+        }
+    }
+}
+
+/// State change of a probe after a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    Started,
+    Ready,
+    Unready,
+    /// A startup or liveness probe reached its failure threshold
+    Failed,
+}
+
+/// Consecutive results and schedule of one probe
+struct ProbeTracker {
+    kind: ProbeKind,
+    probe: Probe,
+    next_run: Instant,
+    successes: u32,
+    failures: u32,
+}
+
+impl ProbeTracker {
+    fn new(kind: ProbeKind, probe: Probe, started_at: Instant) -> Self {
+        let next_run = started_at + probe.initial_delay;
+        Self { kind, probe, next_run, successes: 0, failures: 0 }
+    }
+
+    fn record(&mut self, passed: bool, now: Instant) -> Option<Transition> {
+        self.next_run = now + self.probe.period;
+        if passed {
+            self.failures = 0;
+            self.successes += 1;
+            if self.successes != self.probe.success_threshold {
+                return None;
+            }
+            match self.kind {
+                ProbeKind::Startup => Some(Transition::Started),
+                ProbeKind::Readiness => Some(Transition::Ready),
+                ProbeKind::Liveness => None,
+            }
+        } else {
+            self.successes = 0;
+            self.failures += 1;
+            if self.failures != self.probe.failure_threshold {
+                return None;
+            }
+            match self.kind {
+                ProbeKind::Readiness => Some(Transition::Unready),
+                ProbeKind::Startup | ProbeKind::Liveness => Some(Transition::Failed),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_thresholds() {
+        let now = Instant::now();
+        let probe = Probe { success_threshold: 2, failure_threshold: 3, ..Probe::new(ProbeAction::Tcp { port: 8080 }) };
+
+        let mut readiness = ProbeTracker::new(ProbeKind::Readiness, probe.clone(), now);
+        assert_eq!(readiness.record(true, now), None);
+        assert_eq!(readiness.record(true, now), Some(Transition::Ready));
+        assert_eq!(readiness.next_run, now + probe.period);
+        // A failure resets the streak of passes and vice versa
+        assert_eq!(readiness.record(false, now), None);
+        assert_eq!(readiness.record(true, now), None);
+        assert_eq!(readiness.record(false, now), None);
+        assert_eq!(readiness.record(false, now), None);
+        assert_eq!(readiness.record(false, now), Some(Transition::Unready));
+
+        let mut liveness = ProbeTracker::new(ProbeKind::Liveness, probe.clone(), now);
+        assert_eq!(liveness.record(true, now), None);
+        assert_eq!(liveness.record(true, now), None);
+        for _ in 0..2 {
+            assert_eq!(liveness.record(false, now), None);
+        }
+        assert_eq!(liveness.record(false, now), Some(Transition::Failed));
+
+        let delayed = Probe { initial_delay: Duration::from_secs(5), ..probe };
+        let mut startup = ProbeTracker::new(ProbeKind::Startup, delayed, now);
+        assert_eq!(startup.next_run, now + Duration::from_secs(5));
+        startup.record(true, now);
+        assert_eq!(startup.record(true, now), Some(Transition::Started));
+
+        let invalid = ContainerProbes {
+            liveness: Some(Probe { failure_threshold: 0, ..Probe::new(ProbeAction::Tcp { port: 80 }) }),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
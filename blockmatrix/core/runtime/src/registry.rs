@@ -0,0 +1,822 @@
+//! Registry authentication and image pulls
+//!
+//! Every registry in `ImageConfig::registries` has its own credentials:
+//! static basic credentials or a bearer token, or an identity token that is
+//! exchanged for short-lived bearer tokens at the registry's token service,
+//! following the Docker registry token flow. Exchanged tokens are cached per
+//! registry and scope until just before they expire, so repeated pulls of
+//! private images don't go back to the token service. Manifests and blobs
+//! fetched by digest are checked against it, and registries that require
+//! signatures have each manifest checked by a `SignatureVerifier` before any
+//! layer is fetched. The HTTP exchange itself goes through a
+//! `RegistryTransport`, by default an [`HttpsTransport`].
+
+use crate::build::{sha256_digest, OciImageConfig, OciManifest, MANIFEST_MEDIA_TYPE};
+use crate::{Result, RuntimeError};
+use base64::Engine;
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+const INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+const DOCKER_LIST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+
+/// Redirects followed for one request; registries often redirect blobs to storage
+const MAX_REDIRECTS: usize = 5;
+/// Largest response head accepted
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+/// Largest response body accepted
+const MAX_BODY_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Tokens are refreshed this long before the registry says they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(10);
+/// Token lifetime assumed when the token service does not give one
+const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Credentials presented to one registry
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RegistryCredentials {
+    #[default]
+    Anonymous,
+    Basic { username: String, password: String },
+    /// Token sent as is, never exchanged
+    Bearer { token: String },
+    /// Refresh token exchanged for bearer tokens at the registry's token service
+    IdentityToken { token: String },
+}
+
+impl std::fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Configs are logged; keep secrets out of them
+        match self {
+            RegistryCredentials::Anonymous => f.write_str("Anonymous"),
+            RegistryCredentials::Basic { username, .. } => {
+                f.debug_struct("Basic").field("username", username).finish_non_exhaustive()
+            }
+            RegistryCredentials::Bearer { .. } => f.write_str("Bearer"),
+            RegistryCredentials::IdentityToken { .. } => f.write_str("IdentityToken"),
+        }
+    }
+}
+
+/// Settings for one registry, keyed by its host in `ImageConfig::registries`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    #[serde(default)]
+    pub credentials: RegistryCredentials,
+    /// Refuse images whose manifest fails signature verification
+    #[serde(default)]
+    pub require_signature: bool,
+}
+
+/// HTTP request to a registry or its token service
+#[derive(Debug, Clone)]
+pub struct RegistryRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// HTTP response from a registry or its token service
+#[derive(Debug, Clone, Default)]
+pub struct RegistryResponse {
+    pub status: u16,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl RegistryResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+}
+
+/// Sends registry HTTP requests
+pub trait RegistryTransport: Send + Sync {
+    fn send(&self, request: RegistryRequest) -> BoxFuture<'static, Result<RegistryResponse>>;
+}
+
+/// Sends registry requests as HTTP/1.1 over TLS
+///
+/// Each request gets its own connection, closed once the response is read.
+/// Redirects are followed, without the `Authorization` header once they
+/// leave the host it was meant for.
+#[derive(Clone)]
+pub struct HttpsTransport {
+    connector: tokio_rustls::TlsConnector,
+}
+
+impl HttpsTransport {
+    /// Trust the web PKI roots, as public registries need
+    pub fn new() -> Result<Self> {
+        Self::with_roots(rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() })
+    }
+
+    /// Trust only `roots`, e.g. the CA of a private registry
+    pub fn with_roots(roots: rustls::RootCertStore) -> Result<Self> {
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| RuntimeError::Configuration { message: format!("Registry TLS setup failed: {}", e) })?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(Self { connector: tokio_rustls::TlsConnector::from(Arc::new(config)) })
+    }
+
+    async fn send_following(&self, mut request: RegistryRequest) -> Result<RegistryResponse> {
+        for _ in 0..=MAX_REDIRECTS {
+            let url = HttpsUrl::parse(&request.url)?;
+            let response = self.send_once(&url, &request).await?;
+            let location = match (response.status, response.header("location")) {
+                (301 | 302 | 303 | 307 | 308, Some(location)) => location.to_string(),
+                _ => return Ok(response),
+            };
+
+            let next = if location.starts_with('/') {
+                format!("https://{}{}", url.authority, location)
+            } else {
+                location
+            };
+            if HttpsUrl::parse(&next)?.authority != url.authority {
+                request.headers.retain(|(name, _)| !name.eq_ignore_ascii_case("authorization"));
+            }
+            if response.status == 303 || (matches!(response.status, 301 | 302) && request.method == "POST") {
+                request.method = "GET".to_string();
+                request.body.clear();
+            }
+            request.url = next;
+        }
+        Err(RuntimeError::Registry { message: format!("Too many redirects fetching {}", request.url) })
+    }
+
+    async fn send_once(&self, url: &HttpsUrl, request: &RegistryRequest) -> Result<RegistryResponse> {
+        let failed = |e: std::io::Error| RuntimeError::Registry { message: format!("Request to {} failed: {}", url.authority, e) };
+        let server_name = rustls::pki_types::ServerName::try_from(url.host.clone())
+            .map_err(|e| RuntimeError::Registry { message: format!("Invalid registry host {}: {}", url.host, e) })?;
+        let tcp = tokio::net::TcpStream::connect((url.host.as_str(), url.port)).await.map_err(failed)?;
+        let mut stream = self.connector.connect(server_name, tcp).await.map_err(failed)?;
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: nexus-runtime\r\nConnection: close\r\nContent-Length: {}\r\n",
+            request.method,
+            url.path,
+            url.authority,
+            request.body.len(),
+        );
+        for (name, value) in &request.headers {
+            let _ = write!(head, "{}: {}\r\n", name, value);
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).await.map_err(failed)?;
+        stream.write_all(&request.body).await.map_err(failed)?;
+        stream.flush().await.map_err(failed)?;
+
+        read_response(BufReader::new(stream)).await.map_err(failed)
+    }
+}
+
+impl RegistryTransport for HttpsTransport {
+    fn send(&self, request: RegistryRequest) -> BoxFuture<'static, Result<RegistryResponse>> {
+        let transport = self.clone();
+        Box::pin(async move { transport.send_following(request).await })
+    }
+}
+
+/// The parts of an `https://` URL a request needs
+struct HttpsUrl {
+    /// Host and port as written, for the `Host` header
+    authority: String,
+    host: String,
+    port: u16,
+    /// Path and query
+    path: String,
+}
+
+impl HttpsUrl {
+    fn parse(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| RuntimeError::Registry { message: format!("Invalid registry URL {}: {}", url, reason) };
+        let rest = url.strip_prefix("https://").ok_or_else(|| invalid("only https is supported"))?;
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('?') => (&rest[..i], format!("/{}", &rest[i..])),
+            Some(i) => (&rest[..i], rest[i..].to_string()),
+            None => (rest, "/".to_string()),
+        };
+
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed.split_once(']').ok_or_else(|| invalid("unclosed IPv6 address"))?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        if host.is_empty() {
+            return Err(invalid("no host"));
+        }
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| invalid("bad port"))?,
+            None => 443,
+        };
+
+        Ok(Self { authority: authority.to_string(), host: host.to_string(), port, path })
+    }
+}
+
+fn invalid_response(reason: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, reason.to_string())
+}
+
+async fn read_line<R: tokio::io::AsyncBufRead + Unpin>(reader: &mut R, line: &mut String) -> std::io::Result<usize> {
+    line.clear();
+    reader.read_line(line).await
+}
+
+/// Read an HTTP/1.1 response, with a sized, chunked or close-delimited body
+async fn read_response<R: tokio::io::AsyncBufRead + Unpin>(mut reader: R) -> std::io::Result<RegistryResponse> {
+    let mut line = String::new();
+    let mut head_bytes = read_line(&mut reader, &mut line).await?;
+    let status: u16 = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid_response("malformed status line"))?;
+
+    let mut headers = HashMap::new();
+    loop {
+        let read = read_line(&mut reader, &mut line).await?;
+        head_bytes += read;
+        if read == 0 || head_bytes > MAX_HEAD_BYTES {
+            return Err(invalid_response("response head too long or truncated"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let chunked = headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"));
+    let body = if matches!(status, 204 | 304) {
+        Vec::new()
+    } else if chunked {
+        read_chunked(&mut reader).await?
+    } else if let Some(length) = headers.get("content-length") {
+        let length: u64 = length.parse().map_err(|_| invalid_response("malformed content length"))?;
+        if length > MAX_BODY_BYTES {
+            return Err(invalid_response("response body too large"));
+        }
+        let mut body = vec![0; length as usize];
+        reader.read_exact(&mut body).await?;
+        body
+    } else {
+        // Servers often close without a TLS close_notify; what arrived is the body
+        let mut body = Vec::new();
+        match (&mut reader).take(MAX_BODY_BYTES + 1).read_to_end(&mut body).await {
+            Err(e) if e.kind() != std::io::ErrorKind::UnexpectedEof => return Err(e),
+            _ => {}
+        }
+        if body.len() as u64 > MAX_BODY_BYTES {
+            return Err(invalid_response("response body too large"));
+        }
+        body
+    };
+
+    Ok(RegistryResponse { status, headers, body })
+}
+
+/// Read a chunked body and the trailers after it
+async fn read_chunked<R: tokio::io::AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        read_line(reader, &mut line).await?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = u64::from_str_radix(size, 16).map_err(|_| invalid_response("malformed chunk size"))?;
+        if size == 0 {
+            while read_line(reader, &mut line).await? > 0 && !line.trim().is_empty() {}
+            return Ok(body);
+        }
+        if body.len() as u64 + size > MAX_BODY_BYTES {
+            return Err(invalid_response("response body too large"));
+        }
+        let start = body.len();
+        body.resize(start + size as usize, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        read_line(reader, &mut line).await?;
+    }
+}
+
+/// Checks the signature of an image before it is pulled
+pub trait SignatureVerifier: Send + Sync {
+    /// Fail unless `manifest_digest` is validly signed for `reference`
+    fn verify(&self, reference: &str, manifest_digest: &str) -> BoxFuture<'static, Result<()>>;
+}
+
+/// A `WWW-Authenticate` challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    /// `Basic` or `Bearer`
+    pub scheme: String,
+    pub realm: Option<String>,
+    pub service: Option<String>,
+    pub scope: Option<String>,
+}
+
+impl AuthChallenge {
+    /// Parse a header such as `Bearer realm="https://auth.example.com/token",service="registry"`
+    pub fn parse(header: &str) -> Option<Self> {
+        let (scheme, params) = header.trim().split_once(' ').unwrap_or((header.trim(), ""));
+        if scheme.is_empty() {
+            return None;
+        }
+
+        let mut challenge = Self { scheme: scheme.to_string(), realm: None, service: None, scope: None };
+        let mut rest = params.trim();
+        while let Some((key, after)) = rest.split_once('=') {
+            let key = key.trim().trim_start_matches(',').trim();
+            let (value, remainder) = match after.strip_prefix('"') {
+                Some(quoted) => {
+                    let end = quoted.find('"')?;
+                    (&quoted[..end], &quoted[end + 1..])
+                }
+                None => after.split_once(',').map_or((after, ""), |(value, rest)| (value, rest)),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "realm" => challenge.realm = Some(value.to_string()),
+                "service" => challenge.service = Some(value.to_string()),
+                "scope" => challenge.scope = Some(value.to_string()),
+                _ => {}
+            }
+            rest = remainder.trim_start_matches(',').trim();
+        }
+        Some(challenge)
+    }
+}
+
+struct CachedToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/// Bearer tokens obtained from registry token services, by registry and scope
+#[derive(Default)]
+pub struct CredentialCache {
+    tokens: DashMap<(String, String), CachedToken>,
+}
+
+impl CredentialCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A cached token that is not about to expire
+    pub fn get(&self, registry: &str, scope: &str) -> Option<String> {
+        let key = (registry.to_string(), scope.to_string());
+        let token = self.tokens.get(&key)?;
+        if token.expires_at > Instant::now() + TOKEN_EXPIRY_MARGIN {
+            return Some(token.token.clone());
+        }
+        drop(token);
+        self.tokens.remove(&key);
+        None
+    }
+
+    pub fn insert(&self, registry: &str, scope: &str, token: String, lifetime: Duration) {
+        let expires_at = Instant::now() + lifetime;
+        self.tokens.insert((registry.to_string(), scope.to_string()), CachedToken { token, expires_at });
+    }
+
+    /// Drop every token for a registry, for instance after it rejected one
+    pub fn invalidate(&self, registry: &str) {
+        self.tokens.retain(|(cached, _), _| cached != registry);
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    access_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciIndex {
+    manifests: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    digest: String,
+    #[serde(default)]
+    platform: Option<Platform>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+    architecture: String,
+    os: String,
+}
+
+/// An image fetched from a registry
+pub(crate) struct PulledImage {
+    pub manifest_digest: String,
+    pub config_digest: String,
+    pub config: OciImageConfig,
+    /// Layer media type, digest and contents, base layer first
+    pub layers: Vec<(String, String, Vec<u8>)>,
+}
+
+/// Pulls images, authenticating to each registry as configured
+pub(crate) struct RegistryClient {
+    registries: HashMap<String, RegistryConfig>,
+    transport: RwLock<Option<Arc<dyn RegistryTransport>>>,
+    verifier: RwLock<Option<Arc<dyn SignatureVerifier>>>,
+    tokens: CredentialCache,
+}
+
+impl RegistryClient {
+    pub fn new(registries: HashMap<String, RegistryConfig>) -> Self {
+        Self {
+            registries,
+            transport: RwLock::new(None),
+            verifier: RwLock::new(None),
+            tokens: CredentialCache::new(),
+        }
+    }
+
+    pub fn set_transport(&self, transport: Arc<dyn RegistryTransport>) {
+        *self.transport.write() = Some(transport);
+    }
+
+    pub fn set_verifier(&self, verifier: Arc<dyn SignatureVerifier>) {
+        *self.verifier.write() = Some(verifier);
+    }
+
+    pub fn tokens(&self) -> &CredentialCache {
+        &self.tokens
+    }
+
+    /// Pull an image by tag or digest for the given platform
+    pub async fn pull(&self, registry: &str, name: &str, reference: &str, platform: (&str, &str)) -> Result<PulledImage> {
+        let repository = repository_name(registry, name);
+        let (mut manifest_digest, mut response) = self.fetch_manifest(registry, &repository, reference).await?;
+
+        if is_index(&response) {
+            let index: OciIndex = serde_json::from_slice(&response.body)?;
+            let entry = index.manifests.into_iter()
+                .find(|entry| entry.platform.as_ref().is_some_and(|p| (p.os.as_str(), p.architecture.as_str()) == platform))
+                .ok_or_else(|| RuntimeError::ImagePullFailed {
+                    message: format!("{}:{} has no {}/{} image", repository, reference, platform.0, platform.1),
+                })?;
+            (manifest_digest, response) = self.fetch_manifest(registry, &repository, &entry.digest).await?;
+        }
+
+        let config = self.registries.get(registry).cloned().unwrap_or_default();
+        if config.require_signature {
+            let verifier = self.verifier.read().clone().ok_or_else(|| RuntimeError::Security {
+                message: format!("Registry {} requires signatures but no verifier is set", registry),
+            })?;
+            verifier.verify(&format!("{}/{}@{}", registry, repository, manifest_digest), &manifest_digest).await?;
+        }
+
+        let manifest: OciManifest = serde_json::from_slice(&response.body)?;
+        let config_blob = self.fetch_blob(registry, &repository, &manifest.config.digest).await?;
+        let mut layers = Vec::with_capacity(manifest.layers.len());
+        for layer in &manifest.layers {
+            let data = self.fetch_blob(registry, &repository, &layer.digest).await?;
+            layers.push((layer.media_type.clone(), layer.digest.clone(), data));
+        }
+
+        Ok(PulledImage {
+            manifest_digest,
+            config_digest: manifest.config.digest,
+            config: serde_json::from_slice(&config_blob)?,
+            layers,
+        })
+    }
+
+    /// Fetch a manifest and its digest, checking it when fetched by digest
+    async fn fetch_manifest(&self, registry: &str, repository: &str, reference: &str) -> Result<(String, RegistryResponse)> {
+        let accept = [MANIFEST_MEDIA_TYPE, INDEX_MEDIA_TYPE, DOCKER_MANIFEST_MEDIA_TYPE, DOCKER_LIST_MEDIA_TYPE].join(", ");
+        let response = self.get(registry, repository, &format!("manifests/{}", reference), &accept).await?;
+        let digest = sha256_digest(&response.body);
+        if reference.contains(':') && reference != digest {
+            return Err(RuntimeError::Security {
+                message: format!("Manifest for {}@{} has digest {}", repository, reference, digest),
+            });
+        }
+        Ok((digest, response))
+    }
+
+    async fn fetch_blob(&self, registry: &str, repository: &str, digest: &str) -> Result<Vec<u8>> {
+        let response = self.get(registry, repository, &format!("blobs/{}", digest), "*/*").await?;
+        if sha256_digest(&response.body) != digest {
+            return Err(RuntimeError::Security { message: format!("Blob {} does not match its digest", digest) });
+        }
+        Ok(response.body)
+    }
+
+    /// GET a registry API path, authenticating once if challenged
+    async fn get(&self, registry: &str, repository: &str, path: &str, accept: &str) -> Result<RegistryResponse> {
+        let transport = self.transport.read().clone().ok_or_else(|| RuntimeError::Configuration {
+            message: "No registry transport configured".to_string(),
+        })?;
+        let scope = format!("repository:{}:pull", repository);
+        let request = |authorization: Option<String>| RegistryRequest {
+            method: "GET".to_string(),
+            url: format!("https://{}/v2/{}/{}", api_host(registry), repository, path),
+            headers: std::iter::once(("Accept".to_string(), accept.to_string()))
+                .chain(authorization.map(|value| ("Authorization".to_string(), value)))
+                .collect(),
+            body: Vec::new(),
+        };
+
+        let mut response = transport.send(request(self.authorization(registry, &scope))).await?;
+        if response.status == 401 {
+            self.tokens.invalidate(registry);
+            let challenge = response.header("www-authenticate").and_then(AuthChallenge::parse);
+            let authorization = match challenge {
+                Some(challenge) if challenge.scheme.eq_ignore_ascii_case("bearer") => {
+                    Some(format!("Bearer {}", self.exchange(&transport, registry, &scope, &challenge).await?))
+                }
+                _ => self.basic_authorization(registry),
+            };
+            if authorization.is_some() {
+                response = transport.send(request(authorization)).await?;
+            }
+        }
+
+        match response.status {
+            200..=299 => Ok(response),
+            401 | 403 => Err(RuntimeError::Security {
+                message: format!("Registry {} denied access to {}", registry, repository),
+            }),
+            404 => Err(RuntimeError::ImagePullFailed { message: format!("{}/{} {} not found", registry, repository, path) }),
+            status => Err(RuntimeError::Registry { message: format!("Registry {} returned {} for {}", registry, status, path) }),
+        }
+    }
+
+    /// Authorization to send before any challenge
+    fn authorization(&self, registry: &str, scope: &str) -> Option<String> {
+        match &self.registries.get(registry)?.credentials {
+            RegistryCredentials::Bearer { token } => Some(format!("Bearer {}", token)),
+            _ => self.tokens.get(registry, scope).map(|token| format!("Bearer {}", token)),
+        }
+    }
+
+    fn basic_authorization(&self, registry: &str) -> Option<String> {
+        match &self.registries.get(registry)?.credentials {
+            RegistryCredentials::Basic { username, password } => Some(basic(username, password)),
+            _ => None,
+        }
+    }
+
+    /// Exchange the registry's credentials for a bearer token
+    async fn exchange(
+        &self,
+        transport: &Arc<dyn RegistryTransport>,
+        registry: &str,
+        scope: &str,
+        challenge: &AuthChallenge,
+    ) -> Result<String> {
+        let credentials = self.registries.get(registry).map(|c| c.credentials.clone()).unwrap_or_default();
+        if let RegistryCredentials::Bearer { token } = credentials {
+            return Ok(token);
+        }
+        let realm = challenge.realm.as_deref().ok_or_else(|| RuntimeError::Registry {
+            message: format!("Registry {} sent a bearer challenge without a realm", registry),
+        })?;
+        let scope = challenge.scope.as_deref().unwrap_or(scope);
+        let service = challenge.service.as_deref().unwrap_or(registry);
+
+        let request = match &credentials {
+            RegistryCredentials::IdentityToken { token } => RegistryRequest {
+                method: "POST".to_string(),
+                url: realm.to_string(),
+                headers: vec![("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string())],
+                body: form(&[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", token.as_str()),
+                    ("service", service),
+                    ("scope", scope),
+                    ("client_id", "hypermesh"),
+                ]).into_bytes(),
+            },
+            _ => RegistryRequest {
+                method: "GET".to_string(),
+                url: format!("{}?{}", realm, form(&[("service", service), ("scope", scope)])),
+                headers: match &credentials {
+                    RegistryCredentials::Basic { username, password } => {
+                        vec![("Authorization".to_string(), basic(username, password))]
+                    }
+                    _ => Vec::new(),
+                },
+                body: Vec::new(),
+            },
+        };
+
+        let response = transport.send(request).await?;
+        if !(200..300).contains(&response.status) {
+            return Err(RuntimeError::Security {
+                message: format!("Token service for {} returned {}", registry, response.status),
+            });
+        }
+        let body: TokenResponse = serde_json::from_slice(&response.body)?;
+        let token = body.token.or(body.access_token).ok_or_else(|| RuntimeError::Registry {
+            message: format!("Token service for {} returned no token", registry),
+        })?;
+        let lifetime = body.expires_in.map_or(DEFAULT_TOKEN_LIFETIME, Duration::from_secs);
+        self.tokens.insert(registry, scope, token.clone(), lifetime);
+        Ok(token)
+    }
+}
+
+impl std::fmt::Debug for RegistryClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryClient")
+            .field("registries", &self.registries)
+            .field("cached_tokens", &self.tokens.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn is_index(response: &RegistryResponse) -> bool {
+    let body_type = serde_json::from_slice::<serde_json::Value>(&response.body).ok()
+        .and_then(|body| body.get("mediaType")?.as_str().map(str::to_string));
+    [response.header("content-type"), body_type.as_deref()]
+        .into_iter()
+        .any(|media_type| matches!(media_type, Some(INDEX_MEDIA_TYPE | DOCKER_LIST_MEDIA_TYPE)))
+}
+
+/// Docker Hub serves its API from a different host than its image names use
+fn api_host(registry: &str) -> &str {
+    if registry == "docker.io" { "registry-1.docker.io" } else { registry }
+}
+
+/// Official Docker Hub images live under `library/`
+fn repository_name(registry: &str, name: &str) -> String {
+    if registry == "docker.io" && !name.contains('/') {
+        format!("library/{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn basic(username: &str, password: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+    format!("Basic {}", encoded)
+}
+
+fn form(pairs: &[(&str, &str)]) -> String {
+    pairs.iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn percent_encode(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{OciDescriptor, CONFIG_MEDIA_TYPE, LAYER_MEDIA_TYPE};
+    use parking_lot::Mutex;
+
+    /// A private registry behind a token service
+    struct PrivateRegistry {
+        blobs: HashMap<String, Vec<u8>>,
+        manifest: Vec<u8>,
+        exchanges: Mutex<usize>,
+    }
+
+    impl RegistryTransport for Arc<PrivateRegistry> {
+        fn send(&self, request: RegistryRequest) -> BoxFuture<'static, Result<RegistryResponse>> {
+            let header = |name: &str| request.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+            let ok = |body: Vec<u8>| RegistryResponse { status: 200, headers: HashMap::new(), body };
+            let response = if request.url.starts_with("https://auth.example.com/token") {
+                *self.exchanges.lock() += 1;
+                if header("Authorization") == Some(basic("ci", "secret")) {
+                    ok(br#"{"token":"t1","expires_in":300}"#.to_vec())
+                } else {
+                    RegistryResponse { status: 401, ..Default::default() }
+                }
+            } else if header("Authorization").as_deref() != Some("Bearer t1") {
+                let challenge = r#"Bearer realm="https://auth.example.com/token",service="registry.example.com""#;
+                RegistryResponse {
+                    status: 401,
+                    headers: HashMap::from([("www-authenticate".to_string(), challenge.to_string())]),
+                    body: Vec::new(),
+                }
+            } else if request.url.contains("/manifests/") {
+                ok(self.manifest.clone())
+            } else {
+                let digest = request.url.rsplit('/').next().unwrap_or_default();
+                self.blobs.get(digest).cloned().map_or(RegistryResponse { status: 404, ..Default::default() }, ok)
+            };
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_private_registry_pull() {
+        let config = br#"{"architecture":"amd64","os":"linux","rootfs":{"type":"layers","diff_ids":[]}}"#.to_vec();
+        let layer = b"layer".to_vec();
+        let manifest = serde_json::to_vec(&OciManifest {
+            schema_version: 2,
+            media_type: MANIFEST_MEDIA_TYPE.to_string(),
+            artifact_type: None,
+            config: OciDescriptor::new(CONFIG_MEDIA_TYPE, sha256_digest(&config), config.len() as u64),
+            layers: vec![OciDescriptor::new(LAYER_MEDIA_TYPE, sha256_digest(&layer), layer.len() as u64)],
+            subject: None,
+            annotations: Default::default(),
+        }).unwrap();
+        let registry = Arc::new(PrivateRegistry {
+            blobs: HashMap::from([(sha256_digest(&config), config), (sha256_digest(&layer), layer.clone())]),
+            manifest: manifest.clone(),
+            exchanges: Mutex::new(0),
+        });
+
+        let credentials = RegistryCredentials::Basic { username: "ci".to_string(), password: "secret".to_string() };
+        let client = RegistryClient::new(HashMap::from([(
+            "registry.example.com".to_string(),
+            RegistryConfig { credentials, require_signature: false },
+        )]));
+        client.set_transport(Arc::new(registry.clone()));
+
+        let image = client.pull("registry.example.com", "team/app", "v1", ("linux", "amd64")).await.unwrap();
+        assert_eq!(image.manifest_digest, sha256_digest(&manifest));
+        assert_eq!(image.layers[0].2, layer);
+
+        // The cached token serves the second pull, and a wrong digest is refused
+        client.pull("registry.example.com", "team/app", "v1", ("linux", "amd64")).await.unwrap();
+        assert_eq!(*registry.exchanges.lock(), 1);
+        assert!(client.fetch_manifest("registry.example.com", "team/app", "sha256:00").await.is_err());
+        assert!(format!("{:?}", client).contains("cached_tokens: 1"));
+        assert!(!format!("{:?}", client).contains("secret"));
+    }
+
+    #[test]
+    fn test_auth_challenge_parse() {
+        let challenge = AuthChallenge::parse(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/nginx:pull""#,
+        ).unwrap();
+        assert_eq!(challenge.scheme, "Bearer");
+        assert_eq!(challenge.realm.as_deref(), Some("https://auth.docker.io/token"));
+        assert_eq!(challenge.scope.as_deref(), Some("repository:library/nginx:pull"));
+        assert_eq!(AuthChallenge::parse(r#"Basic realm="registry""#).unwrap().scheme, "Basic");
+    }
+
+    #[tokio::test]
+    async fn test_https_response_parsing() {
+        let url = HttpsUrl::parse("https://[::1]:5000/v2/app/manifests/latest").unwrap();
+        assert_eq!((url.host.as_str(), url.port, url.path.as_str()), ("::1", 5000, "/v2/app/manifests/latest"));
+        let url = HttpsUrl::parse("https://auth.example.com?service=registry").unwrap();
+        assert_eq!((url.authority.as_str(), url.port, url.path.as_str()), ("auth.example.com", 443, "/?service=registry"));
+        assert!(HttpsUrl::parse("http://registry.example.com/v2/").is_err());
+
+        let sized: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+        let response = read_response(sized).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("Content-Type"), Some("application/json"));
+        assert_eq!(response.body, b"{}");
+
+        let chunked: &[u8] = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nlay\r\n2;ext=1\r\ner\r\n0\r\n\r\n";
+        assert_eq!(read_response(chunked).await.unwrap().body, b"layer");
+
+        let truncated: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+        assert!(read_response(truncated).await.is_err());
+    }
+}
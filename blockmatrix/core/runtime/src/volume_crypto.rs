@@ -0,0 +1,274 @@
+//! Encrypted named volumes
+//!
+//! An encrypted volume is a LUKS image beside the volume's mount point,
+//! opened with `cryptsetup` under a random per-volume key and mounted while
+//! workloads use it. The key is never stored in the clear. It is wrapped by
+//! the cluster's key wrapper (the state encryption manager) and kept as a
+//! small record beside the image, so a stolen disk holds only ciphertext and
+//! a key it cannot unwrap. Snapshots of an encrypted volume are archived and
+//! sealed with AES-256-GCM under a key derived from the volume key. They
+//! carry the wrapped key, so another node of the cluster can restore them.
+
+use crate::config::VolumeEncryptionConfig;
+use crate::{Result, RuntimeError};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use nexus_shared::{KeyWrapper, NexusError};
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Cipher LUKS images are formatted with
+pub const LUKS_CIPHER: &str = "aes-xts-plain64";
+
+/// XTS takes two 256-bit keys
+const VOLUME_KEY_LEN: usize = 64;
+const SNAPSHOT_KEY_CONTEXT: &str = "nexus volume snapshot key v1";
+
+/// A volume key as stored: wrapped by the cluster key wrapper
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeKeyRecord {
+    pub cipher: String,
+    /// Base64 of the wrapped key
+    pub wrapped_key: String,
+    /// Size of the volume's image
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An unwrapped volume key, zeroed when dropped
+pub struct VolumeKey(Vec<u8>);
+
+impl VolumeKey {
+    pub fn generate() -> Self {
+        Self(nexus_shared::random_bytes(VOLUME_KEY_LEN))
+    }
+
+    /// Wrap the key for storage
+    pub async fn wrap(&self, wrapper: &dyn KeyWrapper, size_bytes: u64) -> Result<VolumeKeyRecord> {
+        let wrapped = wrapper.wrap_key(&self.0).await.map_err(key_wrapper_error)?;
+        Ok(VolumeKeyRecord {
+            cipher: LUKS_CIPHER.to_string(),
+            wrapped_key: base64::engine::general_purpose::STANDARD.encode(wrapped),
+            size_bytes,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Unwrap a stored key
+    pub async fn unwrap(record: &VolumeKeyRecord, wrapper: &dyn KeyWrapper) -> Result<Self> {
+        let wrapped = base64::engine::general_purpose::STANDARD
+            .decode(&record.wrapped_key)
+            .map_err(|e| RuntimeError::Storage { message: format!("Corrupt volume key record: {}", e) })?;
+        let key = wrapper.unwrap_key(&wrapped).await.map_err(key_wrapper_error)?;
+        if key.len() != VOLUME_KEY_LEN {
+            return Err(RuntimeError::Storage { message: "Unwrapped volume key has the wrong length".to_string() });
+        }
+        Ok(Self(key))
+    }
+
+    fn snapshot_key(&self) -> LessSafeKey {
+        let key = blake3::derive_key(SNAPSHOT_KEY_CONTEXT, &self.0);
+        LessSafeKey::new(UnboundKey::new(&aead::AES_256_GCM, &key).expect("32-byte key"))
+    }
+
+    /// Seal a snapshot archive; the nonce leads the output
+    pub fn seal(&self, mut data: Vec<u8>) -> Result<Vec<u8>> {
+        let failed = || RuntimeError::Storage { message: "Failed to seal snapshot".to_string() };
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| failed())?;
+        self.snapshot_key()
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| failed())?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(data);
+        Ok(sealed)
+    }
+
+    /// Open a sealed snapshot archive
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let invalid = || RuntimeError::Storage { message: "Snapshot does not open under the volume key".to_string() };
+        if sealed.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+        let mut data = ciphertext.to_vec();
+        let len = self.snapshot_key().open_in_place(nonce, Aad::empty(), &mut data).map_err(|_| invalid())?.len();
+        data.truncate(len);
+        Ok(data)
+    }
+}
+
+impl Drop for VolumeKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+impl std::fmt::Debug for VolumeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("VolumeKey(..)")
+    }
+}
+
+fn key_wrapper_error(error: NexusError) -> RuntimeError {
+    RuntimeError::Storage { message: format!("Volume key wrapper: {}", error) }
+}
+
+/// Formats, opens and mounts LUKS volume images
+#[derive(Debug, Clone)]
+pub struct Cryptsetup {
+    config: VolumeEncryptionConfig,
+}
+
+impl Cryptsetup {
+    pub fn new(config: VolumeEncryptionConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn default_size(&self) -> u64 {
+        self.config.default_size_bytes
+    }
+
+    /// Device an open volume appears as
+    pub fn device(mapper: &str) -> PathBuf {
+        Path::new("/dev/mapper").join(mapper)
+    }
+
+    /// Create a sparse image, format it as LUKS and create a filesystem inside
+    pub async fn format(&self, image: &Path, mapper: &str, key: &VolumeKey, size_bytes: u64) -> Result<()> {
+        let file = tokio::fs::File::create(image).await?;
+        file.set_len(size_bytes).await?;
+        drop(file);
+
+        let image_arg = image.to_string_lossy();
+        run(
+            &self.config.cryptsetup_command,
+            &["luksFormat", "--batch-mode", "--type", "luks2", "--cipher", LUKS_CIPHER, "--key-size", "512", "--key-file", "-", image_arg.as_ref()],
+            Some(&key.0),
+        )
+        .await?;
+        let device = self.open(image, mapper, key).await?;
+        let made = run(&self.config.mkfs_command, &[device.to_string_lossy().as_ref()], None).await;
+        self.close(mapper).await?;
+        made
+    }
+
+    /// Open an image as a mapped device, unless it is open already
+    pub async fn open(&self, image: &Path, mapper: &str, key: &VolumeKey) -> Result<PathBuf> {
+        let device = Self::device(mapper);
+        if !tokio::fs::try_exists(&device).await? {
+            run(
+                &self.config.cryptsetup_command,
+                &["open", "--type", "luks", "--key-file", "-", image.to_string_lossy().as_ref(), mapper],
+                Some(&key.0),
+            )
+            .await?;
+        }
+        Ok(device)
+    }
+
+    pub async fn close(&self, mapper: &str) -> Result<()> {
+        if tokio::fs::try_exists(Self::device(mapper)).await? {
+            run(&self.config.cryptsetup_command, &["close", mapper], None).await?;
+        }
+        Ok(())
+    }
+
+    /// Mount an open device, unless something is mounted there already
+    pub async fn mount(&self, device: &Path, target: &Path) -> Result<()> {
+        tokio::fs::create_dir_all(target).await?;
+        if !is_mount_point(target)? {
+            run("mount", &[device.to_string_lossy().as_ref(), target.to_string_lossy().as_ref()], None).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn unmount(&self, target: &Path) -> Result<()> {
+        if tokio::fs::try_exists(target).await? && is_mount_point(target)? {
+            run("umount", &[target.to_string_lossy().as_ref()], None).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a directory is on a different device from its parent
+pub(crate) fn is_mount_point(path: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let Some(parent) = path.parent() else {
+        return Ok(true);
+    };
+    Ok(std::fs::metadata(path)?.dev() != std::fs::metadata(parent)?.dev())
+}
+
+/// Run a command to completion, passing key material on stdin rather than the command line
+pub(crate) async fn run(program: &str, args: &[&str], stdin: Option<&[u8]>) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| RuntimeError::Storage { message: format!("Failed to run {}: {}", program, e) })?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(RuntimeError::Storage {
+            message: format!(
+                "{} {} failed: {}",
+                program,
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+
+    /// Stands in for the cluster encryption manager
+    struct XorWrapper;
+
+    impl KeyWrapper for XorWrapper {
+        fn wrap_key<'a>(&'a self, key: &'a [u8]) -> BoxFuture<'a, nexus_shared::Result<Vec<u8>>> {
+            Box::pin(async move { Ok(key.iter().map(|b| b ^ 0x5a).collect()) })
+        }
+
+        fn unwrap_key<'a>(&'a self, wrapped: &'a [u8]) -> BoxFuture<'a, nexus_shared::Result<Vec<u8>>> {
+            self.wrap_key(wrapped)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_key_opens_sealed_snapshot() {
+        let key = VolumeKey::generate();
+        let record = key.wrap(&XorWrapper, 1 << 20).await.unwrap();
+        assert_eq!(record.cipher, LUKS_CIPHER);
+        assert_ne!(record.wrapped_key, base64::engine::general_purpose::STANDARD.encode(&key.0));
+
+        let sealed = key.seal(b"volume archive".to_vec()).unwrap();
+        assert!(!sealed.windows(6).any(|w| w == b"volume"));
+
+        // Another node unwraps the record and opens the snapshot
+        let unwrapped = VolumeKey::unwrap(&record, &XorWrapper).await.unwrap();
+        assert_eq!(unwrapped.open(&sealed).unwrap(), b"volume archive");
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(unwrapped.open(&tampered).is_err());
+        assert!(VolumeKey::generate().open(&sealed).is_err());
+    }
+}
@@ -22,6 +22,16 @@ pub enum AffinityType {
 pub struct AffinityRules {
     pub node_affinity: Vec<NodeAffinity>,
     pub pod_affinity: Vec<PodAffinity>,
+    /// Workloads this one must not share a topology domain with
+    #[serde(default)]
+    pub pod_anti_affinity: Vec<PodAffinity>,
+}
+
+impl AffinityRules {
+    /// Whether a node's labels satisfy every node affinity selector
+    pub fn matches_node(&self, labels: &HashMap<String, String>) -> bool {
+        self.node_affinity.iter().all(|affinity| affinity.matches(labels))
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -35,6 +45,15 @@ pub struct NodeAffinity {
     pub preferred_nodes: Vec<NodeId>,
 }
 
+impl NodeAffinity {
+    /// Whether the node's labels contain every selector key with the same value
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.node_selector
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PodAffinity {
     pub label_selector: HashMap<String, String>,
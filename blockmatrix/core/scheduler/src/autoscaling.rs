@@ -1,7 +1,28 @@
 //! Autoscaling module
+//!
+//! A scaling policy combines reactive scaling on CPU utilization with
+//! calendar schedules for predictable load, such as ten replicas on weekdays
+//! from 09:00 to 18:00. The target is the larger of the metric-based count
+//! and every active schedule, clamped to the policy's bounds, so a schedule
+//! sets a floor that metrics can still scale above. Once a window ends the
+//! floor is gone and the workload scales back down on metrics alone.
+//!
+//! A policy may also scale an idle workload to zero replicas. The mesh then
+//! holds the next request to the service and asks for activation, which
+//! brings the workload back to at least one replica.
+//!
+//! With predictive scaling the workload predictor's forecast of CPU load at
+//! the policy's horizon can scale out ahead of demand, never in. Each
+//! forecast is later compared with the load actually observed, and the
+//! error is kept in the stats. Cooldowns hold a workload at its count for a
+//! while after it was scaled, separately for scaling up and down.
 
 use serde::{Deserialize, Serialize};
-use nexus_shared::ResourceId;
+use nexus_shared::{ResourceId, Validate, ValidationReport};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, NaiveTime, Utc, Weekday};
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoscalingPolicy {
@@ -20,39 +41,420 @@ impl Default for AutoscalingPolicy {
     }
 }
 
+impl AutoscalingPolicy {
+    /// Replicas needed to bring CPU utilization back to the target
+    pub fn metric_replicas(&self, current_replicas: u32, cpu_utilization: f32) -> u32 {
+        if self.target_cpu_utilization <= 0.0 {
+            return current_replicas;
+        }
+        (current_replicas as f32 * cpu_utilization / self.target_cpu_utilization).ceil() as u32
+    }
+}
+
+/// A recurring window with a minimum replica count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScalingSchedule {
+    pub name: String,
+    /// Days the window starts on; empty means every day
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    /// End of the window; earlier than `start` for windows that run past midnight
+    pub end: NaiveTime,
+    pub replicas: u32,
+}
+
+impl ScalingSchedule {
+    /// Whether the window covers a local time
+    pub fn is_active(&self, local: NaiveDateTime) -> bool {
+        let starts_on = |day: Weekday| self.days.is_empty() || self.days.contains(&day);
+        let (day, time) = (local.weekday(), local.time());
+        if self.start < self.end {
+            starts_on(day) && time >= self.start && time < self.end
+        } else if self.start > self.end {
+            (starts_on(day) && time >= self.start) || (starts_on(day.pred()) && time < self.end)
+        } else {
+            false
+        }
+    }
+}
+
+/// Minimum time between scaling a workload and scaling it again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScalingCooldown {
+    /// After any scaling, before scaling up
+    pub scale_up: Duration,
+    /// After any scaling, before scaling down
+    pub scale_down: Duration,
+}
+
+impl Default for ScalingCooldown {
+    fn default() -> Self {
+        Self {
+            scale_up: Duration::from_secs(60),
+            scale_down: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Scale out on predicted load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredictiveScaling {
+    /// How far ahead the forecast looks
+    pub horizon: Duration,
+    /// Forecasts with a lower confidence are ignored
+    pub min_confidence: f64,
+}
+
+impl Default for PredictiveScaling {
+    fn default() -> Self {
+        Self {
+            horizon: Duration::from_secs(300),
+            min_confidence: 0.6,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScalingPolicy {
     pub resource_id: ResourceId,
     pub autoscaling: AutoscalingPolicy,
+    #[serde(default)]
+    pub schedules: Vec<ScalingSchedule>,
+    /// Offset from UTC that schedule times are in
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Scale to zero replicas after this long without requests
+    #[serde(default)]
+    pub scale_to_zero_after: Option<Duration>,
+    #[serde(default)]
+    pub cooldown: ScalingCooldown,
+    /// Scale out ahead of predicted demand; off when unset
+    #[serde(default)]
+    pub predictive: Option<PredictiveScaling>,
+}
+
+impl ScalingPolicy {
+    pub fn new(resource_id: ResourceId, autoscaling: AutoscalingPolicy) -> Self {
+        Self {
+            resource_id,
+            autoscaling,
+            schedules: Vec::new(),
+            utc_offset_minutes: 0,
+            scale_to_zero_after: None,
+            cooldown: ScalingCooldown::default(),
+            predictive: None,
+        }
+    }
+
+    pub fn with_cooldown(mut self, cooldown: ScalingCooldown) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_predictive(mut self, predictive: PredictiveScaling) -> Self {
+        self.predictive = Some(predictive);
+        self
+    }
+
+    pub fn with_schedule(mut self, schedule: ScalingSchedule) -> Self {
+        self.schedules.push(schedule);
+        self
+    }
+
+    pub fn with_scale_to_zero(mut self, idle_after: Duration) -> Self {
+        self.scale_to_zero_after = Some(idle_after);
+        self
+    }
+
+    /// The active schedule asking for the most replicas
+    pub fn active_schedule(&self, now: DateTime<Utc>) -> Option<&ScalingSchedule> {
+        let offset = FixedOffset::east_opt(self.utc_offset_minutes * 60)?;
+        let local = now.with_timezone(&offset).naive_local();
+        self.schedules
+            .iter()
+            .filter(|schedule| schedule.is_active(local))
+            .max_by_key(|schedule| schedule.replicas)
+    }
+
+    /// Target replica count: the larger of the metric-based count and the
+    /// active schedules, within the policy's bounds
+    ///
+    /// Without a utilization sample the metric-based count is the current one.
+    pub fn desired_replicas(&self, current_replicas: u32, cpu_utilization: Option<f32>, now: DateTime<Utc>) -> u32 {
+        let metric = cpu_utilization
+            .map_or(current_replicas, |utilization| self.autoscaling.metric_replicas(current_replicas, utilization));
+        let scheduled = self.active_schedule(now).map_or(0, |schedule| schedule.replicas);
+        metric
+            .max(scheduled)
+            .clamp(self.autoscaling.min_replicas, self.autoscaling.max_replicas.max(self.autoscaling.min_replicas))
+    }
+
+    /// Target replica count for an observation, including scale-to-zero
+    ///
+    /// An active schedule keeps the workload up even when idle. A workload at
+    /// zero stays there until the mesh requests activation.
+    pub fn target_replicas(&self, observation: &ScalingObservation, now: DateTime<Utc>) -> u32 {
+        let current = observation.current_replicas;
+        if current == 0 && observation.activation_requested {
+            return self.desired_replicas(1, observation.cpu_utilization, now).max(1);
+        }
+        if let Some(idle_after) = self.scale_to_zero_after {
+            if self.active_schedule(now).is_none() {
+                let idle = observation.idle_for.is_some_and(|idle| idle >= idle_after);
+                if idle || current == 0 {
+                    return 0;
+                }
+            }
+        }
+        let desired = self.desired_replicas(current, observation.cpu_utilization, now);
+        match self.predicted_replicas(observation) {
+            Some(predicted) if predicted > desired => predicted.min(self.max_replicas()),
+            _ => desired,
+        }
+    }
+
+    /// Replicas the forecast load calls for, when it is confident enough
+    pub fn predicted_replicas(&self, observation: &ScalingObservation) -> Option<u32> {
+        let predictive = self.predictive.as_ref()?;
+        let forecast = observation.forecast.as_ref().filter(|f| f.confidence >= predictive.min_confidence)?;
+        if observation.current_replicas == 0 {
+            return None;
+        }
+        Some(self.autoscaling.metric_replicas(observation.current_replicas, forecast.cpu_utilization))
+    }
+
+    fn max_replicas(&self) -> u32 {
+        self.autoscaling.max_replicas.max(self.autoscaling.min_replicas)
+    }
 }
 
-#[derive(Debug)]
+impl Validate for ScalingPolicy {
+    fn validate_config(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        let autoscaling = &self.autoscaling;
+        if autoscaling.min_replicas > autoscaling.max_replicas {
+            report.error("autoscaling.min_replicas", "must not exceed max_replicas");
+        }
+        if autoscaling.target_cpu_utilization <= 0.0 || autoscaling.target_cpu_utilization > 1.0 {
+            report.error("autoscaling.target_cpu_utilization", "must be in (0, 1]");
+        }
+        if FixedOffset::east_opt(self.utc_offset_minutes * 60).is_none() {
+            report.error("utc_offset_minutes", "must be less than a day");
+        }
+        if self.scale_to_zero_after.is_some_and(|idle_after| idle_after.is_zero()) {
+            report.error("scale_to_zero_after", "must be greater than zero");
+        }
+        if let Some(predictive) = &self.predictive {
+            if predictive.horizon.is_zero() {
+                report.error("predictive.horizon", "must be greater than zero");
+            }
+            if !(0.0..=1.0).contains(&predictive.min_confidence) {
+                report.error("predictive.min_confidence", "must be in [0, 1]");
+            }
+        }
+        for schedule in &self.schedules {
+            let field = format!("schedules.{}", schedule.name);
+            if schedule.start == schedule.end {
+                report.error(field.clone(), "start and end must differ");
+            }
+            if schedule.replicas > autoscaling.max_replicas {
+                report.warning(
+                    field,
+                    format!("{} replicas is capped at max_replicas {}", schedule.replicas, autoscaling.max_replicas),
+                );
+            }
+        }
+        report
+    }
+}
+
+/// Replica count and load of a workload at evaluation time
+#[derive(Debug, Clone)]
+pub struct ScalingObservation {
+    pub resource_id: ResourceId,
+    pub current_replicas: u32,
+    /// Mean CPU utilization across replicas, if measured
+    pub cpu_utilization: Option<f32>,
+    /// Time since the workload's service last received a request
+    pub idle_for: Option<Duration>,
+    /// Whether the mesh is holding requests for the workload at zero replicas
+    pub activation_requested: bool,
+    /// Predicted utilization at the current replica count, for predictive policies
+    pub forecast: Option<DemandForecast>,
+}
+
+/// CPU utilization the predictor expects at a policy's horizon
+#[derive(Debug, Clone, Copy)]
+pub struct DemandForecast {
+    pub cpu_utilization: f32,
+    /// Goodness of fit of the forecast, 0.0-1.0
+    pub confidence: f64,
+}
+
+/// A forecast waiting for its horizon to compare with the observed load
+#[derive(Debug, Clone, Copy)]
+struct PendingForecast {
+    due: DateTime<Utc>,
+    /// Predicted load in busy replicas (utilization × replicas)
+    load: f64,
+}
+
+#[derive(Debug, Default)]
 pub struct AutoScaler {
-    policies: Vec<ScalingPolicy>,
+    policies: RwLock<HashMap<ResourceId, ScalingPolicy>>,
+    stats: Mutex<AutoScalingStats>,
+    /// When each workload was last scaled, for cooldowns
+    last_scaled: Mutex<HashMap<ResourceId, DateTime<Utc>>>,
+    forecasts: Mutex<HashMap<ResourceId, PendingForecast>>,
 }
 
 impl AutoScaler {
     pub fn new() -> Self {
-        Self { policies: Vec::new() }
+        Self::default()
+    }
+
+    /// Set a workload's scaling policy, replacing any existing one
+    pub fn set_policy(&self, policy: ScalingPolicy) {
+        self.policies.write().insert(policy.resource_id.clone(), policy);
+    }
+
+    pub fn remove_policy(&self, resource_id: &ResourceId) -> Option<ScalingPolicy> {
+        self.last_scaled.lock().remove(resource_id);
+        self.forecasts.lock().remove(resource_id);
+        self.policies.write().remove(resource_id)
+    }
+
+    pub fn policy(&self, resource_id: &ResourceId) -> Option<ScalingPolicy> {
+        self.policies.read().get(resource_id).cloned()
     }
-    
-    pub async fn evaluate(&self) -> Vec<ScalingDecision> {
-        Vec::new()
+
+    /// Scaling decisions for the observed workloads at `now`
+    ///
+    /// Workloads without a policy, already at their target, or in cooldown
+    /// get no decision. Activating a workload at zero ignores the cooldown.
+    pub async fn evaluate(&self, observations: &[ScalingObservation], now: DateTime<Utc>) -> Vec<ScalingDecision> {
+        let policies = self.policies.read();
+        let mut decisions = Vec::new();
+        let mut stats = self.stats.lock();
+        let mut last_scaled = self.last_scaled.lock();
+        for observation in observations {
+            let Some(policy) = policies.get(&observation.resource_id) else {
+                continue;
+            };
+            stats.total_evaluations += 1;
+            self.score_forecast(policy, observation, now, &mut stats);
+
+            let current = observation.current_replicas;
+            let target = policy.target_replicas(observation, now);
+            if target == current {
+                continue;
+            }
+            let cooldown = if target > current { policy.cooldown.scale_up } else { policy.cooldown.scale_down };
+            let cooling = last_scaled
+                .get(&observation.resource_id)
+                .is_some_and(|last| now.signed_duration_since(*last).to_std().unwrap_or_default() < cooldown);
+            if cooling && current > 0 {
+                stats.cooldown_holds += 1;
+                continue;
+            }
+            last_scaled.insert(observation.resource_id.clone(), now);
+
+            let desired = policy.desired_replicas(current, observation.cpu_utilization, now);
+            let predictive = target > current && target > desired;
+            if target > current {
+                stats.scale_ups += 1;
+                if predictive {
+                    stats.predictive_scale_ups += 1;
+                }
+            } else {
+                stats.scale_downs += 1;
+            }
+            decisions.push(ScalingDecision {
+                resource_id: observation.resource_id.clone(),
+                current_replicas: current,
+                target_replicas: target,
+                schedule: policy
+                    .active_schedule(now)
+                    .filter(|schedule| schedule.replicas >= target)
+                    .map(|schedule| schedule.name.clone()),
+                predictive,
+            });
+        }
+        decisions
     }
-    
-    pub async fn make_scaling_decisions(&self) -> Vec<ScalingDecision> {
-        Vec::new()
+
+    /// Compare a due forecast with the observed load and keep the next one
+    fn score_forecast(&self, policy: &ScalingPolicy, observation: &ScalingObservation, now: DateTime<Utc>, stats: &mut AutoScalingStats) {
+        let Some(predictive) = &policy.predictive else {
+            return;
+        };
+        let mut forecasts = self.forecasts.lock();
+        let replicas = observation.current_replicas as f64;
+
+        if let (Some(pending), Some(utilization)) = (forecasts.get(&observation.resource_id).copied(), observation.cpu_utilization) {
+            if now >= pending.due {
+                let actual = utilization as f64 * replicas;
+                stats.prediction_error.record(pending.load, actual);
+                stats.prediction_error_by_workload
+                    .entry(observation.resource_id.clone())
+                    .or_default()
+                    .record(pending.load, actual);
+                forecasts.remove(&observation.resource_id);
+            }
+        }
+
+        if let Some(forecast) = observation.forecast {
+            let horizon = chrono::Duration::from_std(predictive.horizon).unwrap_or_else(|_| chrono::Duration::zero());
+            forecasts.entry(observation.resource_id.clone()).or_insert(PendingForecast {
+                due: now + horizon,
+                load: forecast.cpu_utilization as f64 * replicas,
+            });
+        }
+    }
+
+    pub async fn make_scaling_decisions(&self, observations: &[ScalingObservation]) -> Vec<ScalingDecision> {
+        self.evaluate(observations, Utc::now()).await
     }
-    
+
     pub async fn stats(&self) -> AutoScalingStats {
-        AutoScalingStats::default()
+        self.stats.lock().clone()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct ScalingDecision {
     pub resource_id: ResourceId,
+    pub current_replicas: u32,
     pub target_replicas: u32,
+    /// Schedule that set the target, when it was not metric-driven
+    pub schedule: Option<String>,
+    /// The forecast asked for more replicas than current load
+    pub predictive: bool,
+}
+
+/// How far forecasts were from the load observed at their horizon
+///
+/// Load is in busy replicas: utilization times replica count.
+#[derive(Debug, Default, Clone)]
+pub struct PredictionError {
+    pub samples: u64,
+    pub mean_absolute_error: f64,
+    /// Mean of the absolute error over the observed load, for loads above zero
+    pub mean_relative_error: f64,
+    relative_samples: u64,
+}
+
+impl PredictionError {
+    fn record(&mut self, predicted: f64, actual: f64) {
+        let error = (predicted - actual).abs();
+        self.samples += 1;
+        self.mean_absolute_error += (error - self.mean_absolute_error) / self.samples as f64;
+        if actual > 0.0 {
+            self.relative_samples += 1;
+            self.mean_relative_error += (error / actual - self.mean_relative_error) / self.relative_samples as f64;
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -60,4 +462,152 @@ pub struct AutoScalingStats {
     pub total_evaluations: u64,
     pub scale_ups: u64,
     pub scale_downs: u64,
-}
\ No newline at end of file
+    /// Scale-ups the forecast called for ahead of current load
+    pub predictive_scale_ups: u64,
+    /// Scaling held back by a cooldown
+    pub cooldown_holds: u64,
+    /// Forecast error over all predictive policies
+    pub prediction_error: PredictionError,
+    pub prediction_error_by_workload: HashMap<ResourceId, PredictionError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_schedules_combine_with_metrics() {
+        let id = ResourceId::new("default", "api", "workload");
+        let policy = ScalingPolicy::new(id.clone(), AutoscalingPolicy { min_replicas: 2, max_replicas: 20, target_cpu_utilization: 0.5 })
+            .with_schedule(ScalingSchedule {
+                name: "business-hours".to_string(),
+                days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+                start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                replicas: 10,
+            })
+            .with_schedule(ScalingSchedule {
+                name: "nightly-batch".to_string(),
+                days: vec![Weekday::Fri],
+                start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+                replicas: 4,
+            });
+        assert!(policy.validate_config().is_valid());
+
+        // 2026-10-16 is a Friday
+        let friday = |h, m| Utc.with_ymd_and_hms(2026, 10, 16, h, m, 0).unwrap();
+        let saturday = |h| Utc.with_ymd_and_hms(2026, 10, 17, h, 0, 0).unwrap();
+
+        // In business hours the schedule is the floor; load can push above it
+        assert_eq!(policy.desired_replicas(3, Some(0.5), friday(10, 0)), 10);
+        assert_eq!(policy.desired_replicas(10, Some(0.8), friday(10, 0)), 16);
+        assert_eq!(policy.desired_replicas(10, Some(0.5), friday(18, 0)), 10);
+        assert_eq!(policy.desired_replicas(10, Some(0.1), friday(18, 0)), 2);
+
+        // The Friday night window runs past midnight into Saturday
+        assert_eq!(policy.desired_replicas(2, None, saturday(1)), 4);
+        assert_eq!(policy.desired_replicas(4, None, saturday(3)), 4);
+        assert_eq!(policy.desired_replicas(4, Some(0.1), saturday(3)), 2);
+
+        let scaler = AutoScaler::new();
+        scaler.set_policy(policy);
+        let observations = [ScalingObservation {
+            resource_id: id,
+            current_replicas: 3,
+            cpu_utilization: Some(0.5),
+            idle_for: None,
+            activation_requested: false,
+            forecast: None,
+        }];
+        let decisions = scaler.evaluate(&observations, friday(9, 30)).await;
+        assert_eq!(decisions[0].target_replicas, 10);
+        assert_eq!(decisions[0].schedule.as_deref(), Some("business-hours"));
+        assert_eq!(scaler.stats().await.scale_ups, 1);
+    }
+
+    #[tokio::test]
+    async fn test_scale_to_zero() {
+        let id = ResourceId::new("default", "api", "workload");
+        let policy = ScalingPolicy::new(id.clone(), AutoscalingPolicy { min_replicas: 2, max_replicas: 10, target_cpu_utilization: 0.5 })
+            .with_scale_to_zero(Duration::from_secs(300))
+            .with_schedule(ScalingSchedule {
+                name: "business-hours".to_string(),
+                days: Vec::new(),
+                start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                replicas: 3,
+            });
+        assert!(policy.validate_config().is_valid());
+
+        let night = Utc.with_ymd_and_hms(2026, 10, 16, 23, 0, 0).unwrap();
+        let noon = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let observe = |current_replicas, idle_secs, activation_requested| ScalingObservation {
+            resource_id: id.clone(),
+            current_replicas,
+            cpu_utilization: None,
+            idle_for: Some(Duration::from_secs(idle_secs)),
+            activation_requested,
+            forecast: None,
+        };
+
+        // Idle past the threshold scales to zero, but not inside a schedule
+        assert_eq!(policy.target_replicas(&observe(2, 60, false), night), 2);
+        assert_eq!(policy.target_replicas(&observe(2, 600, false), night), 0);
+        assert_eq!(policy.target_replicas(&observe(2, 600, false), noon), 3);
+
+        // At zero the workload waits for activation, then returns to its minimum
+        assert_eq!(policy.target_replicas(&observe(0, 5, false), night), 0);
+        assert_eq!(policy.target_replicas(&observe(0, 0, true), night), 2);
+
+        let scaler = AutoScaler::new();
+        scaler.set_policy(policy);
+        let decisions = scaler.evaluate(&[observe(0, 0, true)], night).await;
+        assert_eq!((decisions[0].current_replicas, decisions[0].target_replicas), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_predictive_scaling() {
+        let id = ResourceId::new("default", "api", "workload");
+        let policy = ScalingPolicy::new(id.clone(), AutoscalingPolicy { min_replicas: 1, max_replicas: 10, target_cpu_utilization: 0.5 })
+            .with_predictive(PredictiveScaling::default())
+            .with_cooldown(ScalingCooldown { scale_up: Duration::from_secs(60), scale_down: Duration::from_secs(300) });
+        assert!(policy.validate_config().is_valid());
+
+        let start = Utc::now();
+        let observe = |current_replicas, utilization, forecast: Option<(f32, f64)>| ScalingObservation {
+            resource_id: id.clone(),
+            current_replicas,
+            cpu_utilization: utilization,
+            idle_for: None,
+            activation_requested: false,
+            forecast: forecast.map(|(cpu_utilization, confidence)| DemandForecast { cpu_utilization, confidence }),
+        };
+
+        // An unconfident forecast is ignored
+        assert_eq!(policy.target_replicas(&observe(2, Some(0.5), Some((1.0, 0.3))), start), 2);
+
+        let scaler = AutoScaler::new();
+        scaler.set_policy(policy);
+
+        // Load is on target now, but is forecast to double: scale out ahead of it
+        let decisions = scaler.evaluate(&[observe(2, Some(0.5), Some((1.0, 0.9)))], start).await;
+        assert_eq!(decisions[0].target_replicas, 4);
+        assert!(decisions[0].predictive);
+
+        // Inside the scale-up cooldown nothing moves
+        let soon = start + chrono::Duration::seconds(30);
+        assert!(scaler.evaluate(&[observe(4, Some(0.5), Some((1.0, 0.9)))], soon).await.is_empty());
+
+        // At the horizon the forecast of 2 busy replicas meets 1.8 observed
+        let later = start + chrono::Duration::minutes(6);
+        assert!(scaler.evaluate(&[observe(4, Some(0.45), None)], later).await.is_empty());
+
+        let stats = scaler.stats().await;
+        assert_eq!((stats.predictive_scale_ups, stats.cooldown_holds), (1, 1));
+        assert_eq!(stats.prediction_error.samples, 1);
+        assert!((stats.prediction_error.mean_absolute_error - 0.2).abs() < 1e-3);
+        assert!((stats.prediction_error_by_workload[&id].mean_relative_error - 0.2 / 1.8).abs() < 1e-3);
+    }
+}
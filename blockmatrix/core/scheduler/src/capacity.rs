@@ -0,0 +1,233 @@
+//! Capacity-planning forecasts
+//!
+//! Combines the predictor's demand history with the current node inventory
+//! to estimate when a cluster runs out of CPU, memory or GPUs if demand
+//! keeps growing at its recent rate. Nodes belong to the cluster named by
+//! their `nexus.io/cluster` label, and demand is sampled per cluster on a
+//! timer as well as on every placement and eviction. Growth is a
+//! least-squares linear fit over the sampled history, so the forecast is
+//! only as good as the window it was fitted on; the fit's R² is reported as
+//! the confidence.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Resource dimension covered by a forecast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapacityResource {
+    Cpu,
+    Memory,
+    Gpu,
+}
+
+impl CapacityResource {
+    pub const ALL: [CapacityResource; 3] = [CapacityResource::Cpu, CapacityResource::Memory, CapacityResource::Gpu];
+
+    /// Unit the capacity and demand figures are expressed in
+    pub fn unit(&self) -> &'static str {
+        match self {
+            CapacityResource::Cpu => "cores",
+            CapacityResource::Memory => "MB",
+            CapacityResource::Gpu => "GPUs",
+        }
+    }
+}
+
+/// Cluster-wide resource totals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceTotals {
+    pub cpu_cores: f64,
+    pub memory_mb: f64,
+    pub gpus: f64,
+}
+
+impl ResourceTotals {
+    pub fn get(&self, resource: CapacityResource) -> f64 {
+        match resource {
+            CapacityResource::Cpu => self.cpu_cores,
+            CapacityResource::Memory => self.memory_mb,
+            CapacityResource::Gpu => self.gpus,
+        }
+    }
+}
+
+/// Committed demand at a point in time
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DemandSample {
+    pub at: SystemTime,
+    pub demand: ResourceTotals,
+}
+
+/// Forecast for one resource dimension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceForecast {
+    pub resource: CapacityResource,
+    pub capacity: f64,
+    pub current_demand: f64,
+    /// Fitted demand growth per day (negative when shrinking)
+    pub growth_per_day: f64,
+    /// Time until demand exceeds capacity; `None` if not within the horizon
+    pub exhausted_in: Option<Duration>,
+    pub exhausted_at: Option<SystemTime>,
+    /// Goodness of fit of the growth trend, 0.0-1.0
+    pub confidence: f64,
+}
+
+impl ResourceForecast {
+    /// Current demand as a fraction of capacity
+    pub fn utilization(&self) -> f64 {
+        if self.capacity > 0.0 {
+            self.current_demand / self.capacity
+        } else if self.current_demand > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Capacity-planning report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityForecast {
+    pub generated_at: SystemTime,
+    pub horizon: Duration,
+    /// Number of demand samples the trend was fitted on
+    pub samples: usize,
+    pub node_count: usize,
+    pub resources: Vec<ResourceForecast>,
+}
+
+impl CapacityForecast {
+    /// Build a forecast from node capacity, current demand and demand history
+    pub fn build(
+        capacity: ResourceTotals,
+        current: ResourceTotals,
+        history: &[DemandSample],
+        node_count: usize,
+        horizon: Duration,
+    ) -> Self {
+        let now = SystemTime::now();
+        let resources = CapacityResource::ALL
+            .iter()
+            .map(|&resource| forecast_resource(resource, capacity.get(resource), current.get(resource), history, now, horizon))
+            .collect();
+
+        Self {
+            generated_at: now,
+            horizon,
+            samples: history.len(),
+            node_count,
+            resources,
+        }
+    }
+
+    /// The resource that runs out first within the horizon, if any
+    pub fn first_exhausted(&self) -> Option<&ResourceForecast> {
+        self.resources
+            .iter()
+            .filter(|r| r.exhausted_in.is_some())
+            .min_by_key(|r| r.exhausted_in)
+    }
+}
+
+fn forecast_resource(
+    resource: CapacityResource,
+    capacity: f64,
+    current_demand: f64,
+    history: &[DemandSample],
+    now: SystemTime,
+    horizon: Duration,
+) -> ResourceForecast {
+    let (growth_per_second, confidence) = linear_trend(resource, history).unwrap_or((0.0, 0.0));
+
+    let exhausted_in = if current_demand >= capacity && (capacity > 0.0 || current_demand > 0.0) {
+        Some(Duration::ZERO)
+    } else if growth_per_second > 0.0 {
+        let seconds = (capacity - current_demand) / growth_per_second;
+        Some(Duration::from_secs_f64(seconds)).filter(|d| *d <= horizon)
+    } else {
+        None
+    };
+
+    ResourceForecast {
+        resource,
+        capacity,
+        current_demand,
+        growth_per_day: growth_per_second * SECONDS_PER_DAY,
+        exhausted_in,
+        exhausted_at: exhausted_in.map(|d| now + d),
+        confidence,
+    }
+}
+
+/// Least-squares slope (per second) and R² of demand over time
+pub(crate) fn linear_trend(resource: CapacityResource, history: &[DemandSample]) -> Option<(f64, f64)> {
+    if history.len() < 2 {
+        return None;
+    }
+
+    let origin = history.iter().map(|s| s.at).min()?;
+    let points: Vec<(f64, f64)> = history
+        .iter()
+        .map(|s| {
+            let x = s.at.duration_since(origin).unwrap_or_default().as_secs_f64();
+            (x, s.demand.get(resource))
+        })
+        .collect();
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let syy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 { 1.0 } else { (sxy * sxy) / (sxx * syy) };
+    Some((slope, r_squared.clamp(0.0, 1.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(days_ago: u64, cpu: f64, gpus: f64) -> DemandSample {
+        DemandSample {
+            at: SystemTime::now() - Duration::from_secs(days_ago * 86_400),
+            demand: ResourceTotals { cpu_cores: cpu, memory_mb: 1024.0, gpus },
+        }
+    }
+
+    #[test]
+    fn test_forecast_exhaustion() {
+        // CPU demand grows 2 cores/day, GPU demand is flat
+        let history = vec![sample(10, 40.0, 2.0), sample(5, 50.0, 2.0), sample(0, 60.0, 2.0)];
+        let capacity = ResourceTotals { cpu_cores: 100.0, memory_mb: 4096.0, gpus: 4.0 };
+        let current = history.last().unwrap().demand;
+
+        let forecast = CapacityForecast::build(capacity, current, &history, 3, Duration::from_secs(90 * 86_400));
+
+        let cpu = &forecast.resources[0];
+        assert!((cpu.growth_per_day - 2.0).abs() < 0.01);
+        assert!(cpu.confidence > 0.99);
+        let days = cpu.exhausted_in.unwrap().as_secs_f64() / 86_400.0;
+        assert!((days - 20.0).abs() < 0.1);
+
+        let gpu = &forecast.resources[2];
+        assert!(gpu.exhausted_in.is_none());
+        assert_eq!(gpu.utilization(), 0.5);
+
+        assert_eq!(forecast.first_exhausted().unwrap().resource, CapacityResource::Cpu);
+
+        // Outside the horizon
+        let short = CapacityForecast::build(capacity, current, &history, 3, Duration::from_secs(7 * 86_400));
+        assert!(short.first_exhausted().is_none());
+    }
+}
@@ -0,0 +1,232 @@
+//! Optimistic placement claims shared between schedulers
+//!
+//! Several scheduler instances with different profiles (for example batch
+//! and latency-critical) can run against one cluster; each workload names
+//! the instance responsible for it in `scheduler_name`. Schedulers do not
+//! lock nodes. A scheduler adds a claim to the node's versioned claim ledger
+//! in the state store with a write conditioned on the revision it read, so
+//! a concurrent writer makes it re-read rather than be overwritten, and
+//! binds the claim before starting the workload. When claims compete for the same
+//! capacity the winner is decided from the claims alone, so every scheduler
+//! reaches the same answer; a scheduler whose claim was displaced re-plans.
+
+use crate::capacity::ResourceTotals;
+use crate::error::{Result, SchedulerError};
+use crate::resource_monitor::NodeResources;
+use crate::workload::Workload;
+use nexus_shared::{NodeId, ResourceId};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::SystemTime;
+
+/// State store key of a node's claim ledger
+pub fn claims_key(node_id: &NodeId) -> String {
+    format!("/scheduler/claims/{}", node_id.to_hex())
+}
+
+/// Capacity of a node in claim units
+pub fn node_capacity(resources: &NodeResources) -> ResourceTotals {
+    ResourceTotals {
+        cpu_cores: resources.cpu_total,
+        memory_mb: resources.memory_total as f64 / (1024.0 * 1024.0),
+        gpus: resources.gpu_total as f64,
+    }
+}
+
+/// Replicas a placement puts on each node
+pub fn replicas_per_node(target_node: NodeId, replica_nodes: &[NodeId], replicas: u32) -> Vec<(NodeId, usize)> {
+    if replica_nodes.is_empty() {
+        return vec![(target_node, replicas.max(1) as usize)];
+    }
+    let mut counts: Vec<(NodeId, usize)> = Vec::new();
+    for node_id in replica_nodes {
+        match counts.iter_mut().find(|(id, _)| id == node_id) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*node_id, 1)),
+        }
+    }
+    counts
+}
+
+/// A scheduler's claim on part of a node's capacity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacementClaim {
+    pub workload_id: ResourceId,
+    pub scheduler_name: String,
+    pub priority: i32,
+    pub demand: ResourceTotals,
+    pub claimed_at: SystemTime,
+    /// Confirmed by the claiming scheduler; bound claims are never displaced
+    pub bound: bool,
+}
+
+impl PlacementClaim {
+    /// Claim for `replicas` replicas of a workload on one node
+    pub fn new(workload: &Workload, scheduler_name: &str, replicas: usize) -> Self {
+        let spec = &workload.spec;
+        let replicas = replicas as f64;
+        Self {
+            workload_id: spec.id.clone(),
+            scheduler_name: scheduler_name.to_string(),
+            priority: workload.priority,
+            demand: ResourceTotals {
+                cpu_cores: spec.resources.cpu_cores * replicas,
+                memory_mb: spec.resources.memory_mb as f64 * replicas,
+                gpus: spec.gpus as f64 * replicas,
+            },
+            claimed_at: SystemTime::now(),
+            bound: false,
+        }
+    }
+
+    /// Order of precedence between competing claims; `Less` wins
+    ///
+    /// Bound claims first, then higher priority, then the earlier claim,
+    /// with scheduler name and workload ID as final tie-breakers.
+    pub fn precedence(&self, other: &Self) -> Ordering {
+        other.bound.cmp(&self.bound)
+            .then(other.priority.cmp(&self.priority))
+            .then(self.claimed_at.cmp(&other.claimed_at))
+            .then(self.scheduler_name.cmp(&other.scheduler_name))
+            .then_with(|| self.workload_id.to_string().cmp(&other.workload_id.to_string()))
+    }
+}
+
+/// Versioned set of claims on one node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClaimLedger {
+    /// Incremented by every write
+    pub version: u64,
+    pub claims: Vec<PlacementClaim>,
+}
+
+impl ClaimLedger {
+    pub fn get(&self, workload_id: &ResourceId) -> Option<&PlacementClaim> {
+        self.claims.iter().find(|c| &c.workload_id == workload_id)
+    }
+
+    /// Next ledger with `claim` admitted
+    ///
+    /// Claims are admitted in precedence order until the node is full, so
+    /// unbound claims that lose to the new one may be displaced. Fails if
+    /// the new claim itself is not admitted.
+    pub fn admit(&self, claim: PlacementClaim, capacity: ResourceTotals) -> Result<ClaimLedger> {
+        if let Some(existing) = self.get(&claim.workload_id) {
+            if existing.scheduler_name != claim.scheduler_name && existing.precedence(&claim) == Ordering::Less {
+                return Err(SchedulerError::PlacementConflict {
+                    workload_id: claim.workload_id.clone(),
+                    message: format!("already claimed by scheduler {}", existing.scheduler_name),
+                });
+            }
+        }
+
+        let mut contenders: Vec<PlacementClaim> = self.claims
+            .iter()
+            .filter(|c| c.workload_id != claim.workload_id)
+            .cloned()
+            .chain(std::iter::once(claim.clone()))
+            .collect();
+        contenders.sort_by(PlacementClaim::precedence);
+
+        let mut used = ResourceTotals::default();
+        let mut admitted = Vec::with_capacity(contenders.len());
+        for contender in contenders {
+            let fits = used.cpu_cores + contender.demand.cpu_cores <= capacity.cpu_cores
+                && used.memory_mb + contender.demand.memory_mb <= capacity.memory_mb
+                && used.gpus + contender.demand.gpus <= capacity.gpus;
+            if fits || contender.bound {
+                used.cpu_cores += contender.demand.cpu_cores;
+                used.memory_mb += contender.demand.memory_mb;
+                used.gpus += contender.demand.gpus;
+                admitted.push(contender);
+            }
+        }
+
+        if !admitted.iter().any(|c| c.workload_id == claim.workload_id) {
+            return Err(SchedulerError::PlacementConflict {
+                workload_id: claim.workload_id,
+                message: "node capacity is claimed by higher-precedence workloads".to_string(),
+            });
+        }
+
+        Ok(ClaimLedger {
+            version: self.version + 1,
+            claims: admitted,
+        })
+    }
+
+    /// Next ledger with the workload's claim bound; fails if it was displaced
+    pub fn bind(&self, workload_id: &ResourceId, scheduler_name: &str) -> Result<ClaimLedger> {
+        let mut next = self.clone();
+        let claim = next.claims
+            .iter_mut()
+            .find(|c| &c.workload_id == workload_id && c.scheduler_name == scheduler_name)
+            .ok_or_else(|| SchedulerError::PlacementConflict {
+                workload_id: workload_id.clone(),
+                message: "claim was displaced before it could be bound".to_string(),
+            })?;
+        claim.bound = true;
+        next.version += 1;
+        Ok(next)
+    }
+
+    /// Next ledger without the workload's claim
+    pub fn release(&self, workload_id: &ResourceId) -> ClaimLedger {
+        ClaimLedger {
+            version: self.version + 1,
+            claims: self.claims.iter().filter(|c| &c.workload_id != workload_id).cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn claim(name: &str, scheduler: &str, priority: i32, cpu: f64) -> PlacementClaim {
+        PlacementClaim {
+            workload_id: ResourceId::new("default", name, "workload"),
+            scheduler_name: scheduler.to_string(),
+            priority,
+            demand: ResourceTotals { cpu_cores: cpu, memory_mb: 256.0, gpus: 0.0 },
+            claimed_at: SystemTime::UNIX_EPOCH + Duration::from_secs(100),
+            bound: false,
+        }
+    }
+
+    #[test]
+    fn test_claim_resolution() {
+        let capacity = ResourceTotals { cpu_cores: 4.0, memory_mb: 4096.0, gpus: 0.0 };
+
+        let ledger = ClaimLedger::default()
+            .admit(claim("etl", "batch", 0, 3.0), capacity)
+            .unwrap();
+        assert_eq!(ledger.version, 1);
+
+        // A higher-priority claim displaces the unbound batch claim
+        let latency = claim("api", "latency-critical", 10, 2.0);
+        let contested = ledger.admit(latency.clone(), capacity).unwrap();
+        assert!(contested.get(&latency.workload_id).is_some());
+        assert!(contested.get(&ResourceId::new("default", "etl", "workload")).is_none());
+        assert!(contested.bind(&ResourceId::new("default", "etl", "workload"), "batch").is_err());
+
+        // The same contest decided from either side gives the same ledger
+        let reversed = ClaimLedger::default()
+            .admit(latency.clone(), capacity)
+            .unwrap()
+            .admit(claim("etl", "batch", 0, 3.0), capacity);
+        assert!(reversed.is_err());
+
+        // Once bound, the batch claim keeps its capacity
+        let bound = ledger.bind(&ResourceId::new("default", "etl", "workload"), "batch").unwrap();
+        assert!(bound.admit(latency, capacity).is_err());
+
+        // Equal priority and time: scheduler name breaks the tie
+        let a = claim("x", "alpha", 0, 1.0);
+        let b = claim("x", "beta", 0, 1.0);
+        assert_eq!(a.precedence(&b), Ordering::Less);
+        let ledger = ClaimLedger::default().admit(a, capacity).unwrap();
+        assert!(ledger.admit(b, capacity).is_err());
+    }
+}
@@ -1,10 +1,28 @@
 //! Scheduler configuration
 
+use crate::consolidation::ConsolidationConfig;
+use crate::diversity::DiversityConfig;
+use crate::drain::DrainConfig;
+use crate::heartbeat::HeartbeatConfig;
+use crate::network_cost::NetworkAwareConfig;
+use crate::preemption::PreemptionConfig;
+use crate::shadow::ShadowConfig;
+use nexus_shared::{NodeId, Validate, ValidationReport};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// Scheduler that handles workloads without a `scheduler_name`
+pub const DEFAULT_SCHEDULER_NAME: &str = "default-scheduler";
+
+pub(crate) fn default_scheduler_name() -> String {
+    DEFAULT_SCHEDULER_NAME.to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchedulerConfig {
+    /// Name workloads use to select this scheduler instance
+    #[serde(default = "default_scheduler_name")]
+    pub scheduler_name: String,
     pub scheduling_interval: Duration,
     pub max_concurrent_jobs: usize,
     pub retry_delay: Duration,
@@ -15,11 +33,29 @@ pub struct SchedulerConfig {
     pub optimization: OptimizationConfig,
     pub policies: PolicyConfig,
     pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub drain: DrainConfig,
+    #[serde(default)]
+    pub shadow: ShadowConfig,
+    #[serde(default)]
+    pub preemption: PreemptionConfig,
+    #[serde(default)]
+    pub consolidation: ConsolidationConfig,
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    /// Seed file of the node identity key decisions are signed with;
+    /// created on first start. Without one the key lasts until restart.
+    #[serde(default)]
+    pub identity_key_path: Option<String>,
+    /// Node IDs of the other schedulers whose signed placements are accepted
+    #[serde(default)]
+    pub trusted_schedulers: Vec<NodeId>,
 }
 
 impl Default for SchedulerConfig {
     fn default() -> Self {
         Self {
+            scheduler_name: default_scheduler_name(),
             scheduling_interval: Duration::from_secs(10),
             max_concurrent_jobs: 100,
             retry_delay: Duration::from_secs(5),
@@ -30,10 +66,112 @@ impl Default for SchedulerConfig {
             optimization: OptimizationConfig::default(),
             policies: PolicyConfig::default(),
             monitoring: MonitoringConfig::default(),
+            drain: DrainConfig::default(),
+            shadow: ShadowConfig::default(),
+            preemption: PreemptionConfig::default(),
+            consolidation: ConsolidationConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            identity_key_path: None,
+            trusted_schedulers: Vec::new(),
         }
     }
 }
 
+impl Validate for SchedulerConfig {
+    fn validate_config(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+
+        if self.scheduler_name.is_empty() {
+            report.error("scheduler_name", "must not be empty");
+        }
+        if self.scheduling_interval.is_zero() {
+            report.error("scheduling_interval", "must be greater than zero");
+        }
+        if self.max_concurrent_jobs == 0 {
+            report.error("max_concurrent_jobs", "must be at least 1");
+        }
+        if self.retry_delay >= self.scheduling_interval {
+            report.warning(
+                "retry_delay",
+                format!(
+                    "retries ({:?}) are no faster than the next scheduling pass ({:?})",
+                    self.retry_delay, self.scheduling_interval
+                ),
+            );
+        }
+        if self.node_scoring_strategy.is_empty() {
+            report.error("node_scoring_strategy", "must not be empty");
+        }
+        if self.placement.strategy.is_empty() {
+            report.error("placement.strategy", "must not be empty");
+        }
+        if self.drain.max_concurrency == 0 {
+            report.error("drain.max_concurrency", "must be at least 1");
+        }
+        if self.drain.deadline.is_zero() {
+            report.error("drain.deadline", "must be greater than zero");
+        }
+        if self.consolidation.max_parallel_moves == 0 {
+            report.error("consolidation.max_parallel_moves", "must be at least 1");
+        }
+        if !(0.0..1.0).contains(&self.consolidation.headroom) {
+            report.error("consolidation.headroom", "must be at least 0 and below 1");
+        }
+        if self.consolidation.deadline.is_zero() {
+            report.error("consolidation.deadline", "must be greater than zero");
+        }
+        if self.heartbeat.interval.is_zero() {
+            report.error("heartbeat.interval", "must be greater than zero");
+        }
+        if self.heartbeat.missed_before_not_ready == 0 {
+            report.error("heartbeat.missed_before_not_ready", "must be at least 1");
+        }
+        if self.heartbeat.full_report_every == 0 {
+            report.error("heartbeat.full_report_every", "must be at least 1");
+        }
+        if self.heartbeat.cpu_threshold < 0.0 {
+            report.error("heartbeat.cpu_threshold", "must not be negative");
+        }
+
+        if self.shadow.enabled {
+            if self.shadow.strategy.name.is_empty() {
+                report.error("shadow.strategy", "must not be empty");
+            } else if self.shadow.strategy.name == self.placement.strategy {
+                report.warning("shadow.strategy", "candidate is the active placement strategy");
+            }
+            if self.shadow.max_decisions == 0 {
+                report.error("shadow.max_decisions", "must be at least 1");
+            }
+        }
+
+        if self.autoscaling.enabled {
+            if self.autoscaling.evaluation_interval < self.scheduling_interval {
+                report.warning(
+                    "autoscaling.evaluation_interval",
+                    "autoscaler evaluates faster than the scheduler can place replicas",
+                );
+            }
+            if self.monitoring.interval > self.autoscaling.evaluation_interval {
+                report.error(
+                    "monitoring.interval",
+                    format!(
+                        "metrics collected every {:?} are stale for autoscaling every {:?}",
+                        self.monitoring.interval, self.autoscaling.evaluation_interval
+                    ),
+                );
+            }
+            if self.prediction.enabled && self.prediction.window < self.autoscaling.evaluation_interval {
+                report.error(
+                    "prediction.window",
+                    "prediction window must cover at least one autoscaling evaluation",
+                );
+            }
+        }
+
+        report
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlacementConfig {
     pub strategy: String,
@@ -64,6 +202,9 @@ impl Default for AutoscalingConfig {
 pub struct PredictionConfig {
     pub enabled: bool,
     pub window: Duration,
+    /// How often each cluster's committed demand is sampled for forecasts
+    #[serde(default = "default_demand_sample_interval")]
+    pub demand_sample_interval: Duration,
 }
 
 impl Default for PredictionConfig {
@@ -71,18 +212,33 @@ impl Default for PredictionConfig {
         Self {
             enabled: true,
             window: Duration::from_secs(300),
+            demand_sample_interval: default_demand_sample_interval(),
         }
     }
 }
 
+fn default_demand_sample_interval() -> Duration {
+    Duration::from_secs(300)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizationConfig {
     pub enabled: bool,
+    /// Spread consensus-critical replicas across trust domains
+    #[serde(default)]
+    pub diversity: DiversityConfig,
+    /// Place workloads close to their network peers by measured link quality
+    #[serde(default)]
+    pub network: NetworkAwareConfig,
 }
 
 impl Default for OptimizationConfig {
     fn default() -> Self {
-        Self { enabled: true }
+        Self {
+            enabled: true,
+            diversity: DiversityConfig::default(),
+            network: NetworkAwareConfig::default(),
+        }
     }
 }
 
@@ -106,4 +262,22 @@ impl Default for MonitoringConfig {
     fn default() -> Self {
         Self { interval: Duration::from_secs(5) }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation() {
+        assert!(SchedulerConfig::default().validate_config().is_valid());
+
+        let mut config = SchedulerConfig::default();
+        config.monitoring.interval = Duration::from_secs(60);
+        config.prediction.window = Duration::from_secs(10);
+
+        let report = config.validate_config();
+        let fields: Vec<&str> = report.errors().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, vec!["monitoring.interval", "prediction.window"]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Workload consolidation
+//!
+//! Consolidation packs the running workloads onto fewer nodes so the rest
+//! can be cordoned and powered down. Nodes are emptied least-loaded first:
+//! a node is only given up when every workload on it fits elsewhere, best
+//! fit, keeping a share of each target's capacity free and honouring node
+//! affinity and pod (anti-)affinity. Nodes that receive workloads are kept.
+//! The moves then run through the rescheduling path a few at a time.
+
+use crate::capacity::ResourceTotals;
+use crate::gang::{self, GangNode};
+use crate::workload::Workload;
+use nexus_shared::{NodeId, ResourceId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// How a consolidation pass packs and moves workloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidationConfig {
+    /// Workloads moved at the same time
+    pub max_parallel_moves: usize,
+    /// Share of each target node's capacity left free after packing
+    pub headroom: f64,
+    /// Nodes kept in service however little runs on them
+    pub min_nodes: usize,
+    /// Time allowed for all moves of a pass
+    pub deadline: Duration,
+}
+
+impl Default for ConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            max_parallel_moves: 2,
+            headroom: 0.1,
+            min_nodes: 1,
+            deadline: Duration::from_secs(300),
+        }
+    }
+}
+
+/// A node as seen by the consolidation planner
+#[derive(Debug, Clone)]
+pub struct ConsolidationNode {
+    pub node_id: NodeId,
+    pub labels: std::collections::HashMap<String, String>,
+    pub capacity: ResourceTotals,
+    /// Capacity not committed to workloads
+    pub free: ResourceTotals,
+}
+
+/// A workload where it runs now
+#[derive(Debug, Clone)]
+pub struct PlacedWorkload {
+    pub workload: Workload,
+    pub node_id: NodeId,
+    /// Resources the workload uses on the node
+    pub demand: ResourceTotals,
+    /// False for workloads that must stay, such as ones spread across nodes
+    pub movable: bool,
+}
+
+/// One workload move
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedMove {
+    pub workload_id: ResourceId,
+    pub from: NodeId,
+    pub to: NodeId,
+}
+
+/// Moves that empty some nodes, and the nodes they empty
+#[derive(Debug, Clone, Default)]
+pub struct ConsolidationPlan {
+    pub moves: Vec<PlannedMove>,
+    /// Nodes with no workloads once the moves are done
+    pub vacated: Vec<NodeId>,
+}
+
+/// Outcome of a consolidation pass
+#[derive(Debug, Clone)]
+pub struct ConsolidationReport {
+    pub results: Vec<crate::ReschedulingResult>,
+    /// Planned-empty nodes that did end up empty and can be cordoned
+    pub idle_nodes: Vec<NodeId>,
+}
+
+/// Pack the workloads onto fewer nodes
+pub fn plan_consolidation(
+    nodes: &[ConsolidationNode],
+    placed: &[PlacedWorkload],
+    config: &ConsolidationConfig,
+) -> ConsolidationPlan {
+    let mut free: Vec<ResourceTotals> = nodes.iter().map(|node| node.free).collect();
+    let mut location: Vec<usize> = placed
+        .iter()
+        .map(|p| nodes.iter().position(|node| node.node_id == p.node_id).unwrap_or(usize::MAX))
+        .collect();
+
+    let mut order: Vec<usize> = (0..nodes.len()).collect();
+    order.sort_by(|&a, &b| {
+        used_fraction(&nodes[a], &free[a])
+            .partial_cmp(&used_fraction(&nodes[b], &free[b]))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| nodes[a].node_id.to_hex().cmp(&nodes[b].node_id.to_hex()))
+    });
+
+    let mut plan = ConsolidationPlan::default();
+    let mut vacated: HashSet<usize> = HashSet::new();
+    let mut receiving: HashSet<usize> = HashSet::new();
+
+    for source in order {
+        if receiving.contains(&source) || nodes.len() - vacated.len() <= config.min_nodes {
+            continue;
+        }
+        let mut residents: Vec<usize> = (0..placed.len()).filter(|&i| location[i] == source).collect();
+        if residents.iter().any(|&i| !placed[i].movable) {
+            continue;
+        }
+        residents.sort_by(|&a, &b| {
+            placed[b].demand.cpu_cores.partial_cmp(&placed[a].demand.cpu_cores).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut trial_free = free.clone();
+        let mut trial_location = location.clone();
+        let mut moves = Vec::with_capacity(residents.len());
+        let complete = residents.iter().all(|&i| {
+            let target = (0..nodes.len())
+                .filter(|&t| t != source && !vacated.contains(&t))
+                .filter(|&t| placed[i].workload.spec.affinity.matches_node(&nodes[t].labels))
+                .filter(|&t| fits(&placed[i].demand, &trial_free[t], &reserve(&nodes[t], config.headroom)))
+                .filter(|&t| compatible(i, t, nodes, placed, &trial_location))
+                .min_by(|&a, &b| {
+                    left_after(&nodes[a], &trial_free[a], &placed[i].demand)
+                        .partial_cmp(&left_after(&nodes[b], &trial_free[b], &placed[i].demand))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then_with(|| nodes[a].node_id.to_hex().cmp(&nodes[b].node_id.to_hex()))
+                });
+            let Some(target) = target else {
+                return false;
+            };
+            add(&mut trial_free[target], &placed[i].demand, -1.0);
+            add(&mut trial_free[source], &placed[i].demand, 1.0);
+            trial_location[i] = target;
+            moves.push((i, target));
+            true
+        });
+        if !complete {
+            continue;
+        }
+
+        for (i, target) in moves {
+            receiving.insert(target);
+            plan.moves.push(PlannedMove {
+                workload_id: placed[i].workload.spec.id.clone(),
+                from: nodes[source].node_id,
+                to: nodes[target].node_id,
+            });
+        }
+        free = trial_free;
+        location = trial_location;
+        vacated.insert(source);
+        plan.vacated.push(nodes[source].node_id);
+    }
+
+    plan
+}
+
+/// Whether workload `i` may run on node `target` next to everything else
+fn compatible(
+    i: usize,
+    target: usize,
+    nodes: &[ConsolidationNode],
+    placed: &[PlacedWorkload],
+    location: &[usize],
+) -> bool {
+    let view = |n: usize| GangNode {
+        node_id: nodes[n].node_id,
+        labels: nodes[n].labels.clone(),
+        free: nodes[n].free,
+    };
+    let target_node = view(target);
+    (0..placed.len())
+        .filter(|&j| j != i && location[j] < nodes.len())
+        .all(|j| gang::compatible(&placed[i].workload, &target_node, &placed[j].workload, &view(location[j])))
+}
+
+fn reserve(node: &ConsolidationNode, headroom: f64) -> ResourceTotals {
+    ResourceTotals {
+        cpu_cores: node.capacity.cpu_cores * headroom,
+        memory_mb: node.capacity.memory_mb * headroom,
+        gpus: 0.0,
+    }
+}
+
+fn fits(demand: &ResourceTotals, free: &ResourceTotals, reserve: &ResourceTotals) -> bool {
+    demand.cpu_cores + reserve.cpu_cores <= free.cpu_cores
+        && demand.memory_mb + reserve.memory_mb <= free.memory_mb
+        && demand.gpus + reserve.gpus <= free.gpus
+}
+
+fn used_fraction(node: &ConsolidationNode, free: &ResourceTotals) -> f64 {
+    if node.capacity.cpu_cores <= 0.0 {
+        return 0.0;
+    }
+    (node.capacity.cpu_cores - free.cpu_cores) / node.capacity.cpu_cores
+}
+
+fn left_after(node: &ConsolidationNode, free: &ResourceTotals, demand: &ResourceTotals) -> f64 {
+    if node.capacity.cpu_cores <= 0.0 {
+        return 0.0;
+    }
+    (free.cpu_cores - demand.cpu_cores) / node.capacity.cpu_cores
+}
+
+fn add(total: &mut ResourceTotals, demand: &ResourceTotals, sign: f64) {
+    total.cpu_cores += demand.cpu_cores * sign;
+    total.memory_mb += demand.memory_mb * sign;
+    total.gpus += demand.gpus * sign;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::affinity::{AffinityRules, PodAffinity};
+    use crate::node_metadata::HOSTNAME_LABEL;
+    use crate::workload::{WorkloadSpec, WorkloadType};
+    use std::collections::HashMap;
+
+    fn node(free_cpu: f64) -> ConsolidationNode {
+        ConsolidationNode {
+            node_id: NodeId::random(),
+            labels: HashMap::new(),
+            capacity: ResourceTotals { cpu_cores: 8.0, memory_mb: 8192.0, gpus: 0.0 },
+            free: ResourceTotals { cpu_cores: free_cpu, memory_mb: 8192.0 - (8.0 - free_cpu) * 512.0, gpus: 0.0 },
+        }
+    }
+
+    fn placed(name: &str, node_id: NodeId, cpu: f64, affinity: AffinityRules) -> PlacedWorkload {
+        let id = ResourceId::new("default", name, "workload");
+        PlacedWorkload {
+            workload: Workload {
+                id: id.clone(),
+                workload_type: WorkloadType::Interactive,
+                priority: 0,
+                spec: WorkloadSpec {
+                    id,
+                    name: name.to_string(),
+                    image: format!("{}:latest", name),
+                    replicas: 1,
+                    gpus: 0,
+                    resources: Default::default(),
+                    labels: HashMap::from([("app".to_string(), name.to_string())]),
+                    workload_type: WorkloadType::Interactive,
+                    command: Vec::new(),
+                    environment: HashMap::new(),
+                    working_dir: None,
+                    volumes: Vec::new(),
+                    ephemeral_volumes: Vec::new(),
+                    affinity,
+                    scheduling_gates: Vec::new(),
+                    readiness_gates: Vec::new(),
+                    scheduler_name: crate::DEFAULT_SCHEDULER_NAME.to_string(),
+                },
+                metadata: Default::default(),
+                conditions: Default::default(),
+            },
+            node_id,
+            demand: ResourceTotals { cpu_cores: cpu, memory_mb: cpu * 512.0, gpus: 0.0 },
+            movable: true,
+        }
+    }
+
+    #[test]
+    fn test_plan_consolidation() {
+        let busy = node(3.0);
+        let light = node(7.0);
+        let other = node(6.0);
+        let nodes = vec![busy.clone(), light.clone(), other.clone()];
+        let workloads = vec![
+            placed("api", busy.node_id, 5.0, AffinityRules::default()),
+            placed("cache", light.node_id, 1.0, AffinityRules::default()),
+            placed("worker", other.node_id, 2.0, AffinityRules::default()),
+        ];
+
+        // Both light nodes empty onto the busy one; the last node stays up
+        let plan = plan_consolidation(&nodes, &workloads, &ConsolidationConfig { headroom: 0.0, ..Default::default() });
+        assert_eq!(plan.vacated, vec![light.node_id, other.node_id]);
+        assert!(plan.moves.iter().all(|m| m.to == busy.node_id));
+
+        // Headroom keeps the busy node from filling up completely
+        let plan = plan_consolidation(&nodes, &workloads, &ConsolidationConfig::default());
+        assert_eq!(plan.vacated, vec![light.node_id]);
+        assert_eq!(plan.moves, vec![PlannedMove {
+            workload_id: workloads[1].workload.spec.id.clone(),
+            from: light.node_id,
+            to: busy.node_id,
+        }]);
+
+        // Anti-affinity with the api keeps the cache off the busy node
+        let apart = AffinityRules {
+            pod_anti_affinity: vec![PodAffinity {
+                label_selector: HashMap::from([("app".to_string(), "api".to_string())]),
+                topology_key: HOSTNAME_LABEL.to_string(),
+            }],
+            ..Default::default()
+        };
+        let mut workloads = workloads;
+        workloads[1] = placed("cache", light.node_id, 1.0, apart);
+        let plan = plan_consolidation(&nodes, &workloads, &ConsolidationConfig::default());
+        assert_eq!(plan.vacated, vec![light.node_id]);
+        assert_eq!(plan.moves[0].to, other.node_id);
+    }
+}
@@ -0,0 +1,194 @@
+//! Trust-domain diversity for consensus-critical replicas
+//!
+//! A BFT service with `n` replicas tolerates `f = (n - 1) / 3` Byzantine
+//! replicas. If one operator runs more than `f` of them, that operator alone
+//! can stall or subvert the service, so replicas of consensus-critical
+//! workloads are spread so that no trust domain hosts more than `f` (at
+//! least one). Trust domains come from attestation metadata recorded as
+//! node labels; nodes without it share a single "unattested" domain.
+
+use crate::error::{Result, SchedulerError};
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Node label naming the attested operator of the node
+pub const OPERATOR_LABEL: &str = "nexus.io/operator";
+
+/// Node label naming the attested trust domain, used when no operator is set
+pub const TRUST_DOMAIN_LABEL: &str = "nexus.io/trust-domain";
+
+/// Workload label marking it as consensus-critical (`"true"`)
+pub const CONSENSUS_CRITICAL_LABEL: &str = "nexus.io/consensus-critical";
+
+/// Domain shared by all nodes without attestation metadata
+pub const UNATTESTED_DOMAIN: &str = "unattested";
+
+/// Trust domain of a node from its labels
+pub fn trust_domain(labels: &HashMap<String, String>) -> &str {
+    labels
+        .get(OPERATOR_LABEL)
+        .or_else(|| labels.get(TRUST_DOMAIN_LABEL))
+        .map(String::as_str)
+        .unwrap_or(UNATTESTED_DOMAIN)
+}
+
+/// Whether a workload's replicas must be spread across trust domains
+pub fn is_consensus_critical(labels: &HashMap<String, String>) -> bool {
+    labels.get(CONSENSUS_CRITICAL_LABEL).map(String::as_str) == Some("true")
+}
+
+/// Most replicas one trust domain may host for a replica count
+pub fn max_replicas_per_domain(replicas: usize) -> usize {
+    (replicas.saturating_sub(1) / 3).max(1)
+}
+
+/// A node considered for replica placement
+#[derive(Debug, Clone)]
+pub struct DomainCandidate {
+    pub node_id: NodeId,
+    pub domain: String,
+    /// Preference from other objectives; higher is better
+    pub score: f64,
+}
+
+/// Diversity objective configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiversityConfig {
+    pub enabled: bool,
+    /// Allow several replicas on the same node (still within the domain cap)
+    pub allow_node_reuse: bool,
+}
+
+impl Default for DiversityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allow_node_reuse: false,
+        }
+    }
+}
+
+/// Assigns replicas so that no trust domain exceeds its cap
+#[derive(Debug, Clone, Default)]
+pub struct TrustDomainDiversity {
+    config: DiversityConfig,
+}
+
+impl TrustDomainDiversity {
+    pub fn new(config: DiversityConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Pick a node for each replica
+    ///
+    /// Each replica goes to the least-used domain that still has room,
+    /// preferring higher-scored nodes within it. Fails if the candidates
+    /// span too few domains to keep every domain under its cap.
+    pub fn plan(&self, replicas: usize, candidates: &[DomainCandidate]) -> Result<Vec<NodeId>> {
+        let cap = max_replicas_per_domain(replicas);
+
+        let mut by_domain: HashMap<&str, Vec<&DomainCandidate>> = HashMap::new();
+        for candidate in candidates {
+            by_domain.entry(candidate.domain.as_str()).or_default().push(candidate);
+        }
+        for nodes in by_domain.values_mut() {
+            nodes.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        }
+
+        let mut used: HashMap<&str, usize> = HashMap::new();
+        let mut placement = Vec::with_capacity(replicas);
+
+        for _ in 0..replicas {
+            let choice = by_domain
+                .iter()
+                .filter_map(|(domain, nodes)| {
+                    let count = used.get(domain).copied().unwrap_or(0);
+                    if count >= cap {
+                        return None;
+                    }
+                    let node = if self.config.allow_node_reuse {
+                        nodes.first()
+                    } else {
+                        nodes.iter().find(|n| !placement.contains(&n.node_id))
+                    }?;
+                    Some((*domain, count, node))
+                })
+                // Least-used domain first, then best node, then name for determinism
+                .min_by(|(da, ca, na), (db, cb, nb)| {
+                    ca.cmp(cb)
+                        .then(nb.score.partial_cmp(&na.score).unwrap_or(std::cmp::Ordering::Equal))
+                        .then(da.cmp(db))
+                });
+
+            let Some((domain, _, node)) = choice else {
+                return Err(SchedulerError::ConstraintNotSatisfied {
+                    constraint: format!(
+                        "{} replicas need at least {} trust domains with at most {} replica(s) each; candidates span {}",
+                        replicas,
+                        replicas.div_ceil(cap),
+                        cap,
+                        by_domain.len()
+                    ),
+                });
+            };
+
+            *used.entry(domain).or_insert(0) += 1;
+            placement.push(node.node_id);
+        }
+
+        Ok(placement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(domain: &str, score: f64) -> DomainCandidate {
+        DomainCandidate {
+            node_id: NodeId::random(),
+            domain: domain.to_string(),
+            score,
+        }
+    }
+
+    #[test]
+    fn test_domain_cap() {
+        assert_eq!(max_replicas_per_domain(1), 1);
+        assert_eq!(max_replicas_per_domain(3), 1);
+        assert_eq!(max_replicas_per_domain(4), 1);
+        assert_eq!(max_replicas_per_domain(7), 2);
+    }
+
+    #[test]
+    fn test_plan_spreads_domains() {
+        let diversity = TrustDomainDiversity::default();
+        // Operator "a" has the best-scored nodes but may only host one replica of four
+        let candidates = vec![
+            candidate("a", 0.9),
+            candidate("a", 0.8),
+            candidate("a", 0.7),
+            candidate("b", 0.5),
+            candidate("c", 0.4),
+            candidate("d", 0.3),
+        ];
+
+        let placement = diversity.plan(4, &candidates).unwrap();
+        let domains: Vec<&str> = placement
+            .iter()
+            .map(|id| candidates.iter().find(|c| &c.node_id == id).unwrap().domain.as_str())
+            .collect();
+        let mut sorted = domains.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec!["a", "b", "c", "d"]);
+        assert_eq!(placement[0], candidates[0].node_id);
+
+        // Three domains cannot host four replicas at one each
+        assert!(diversity.plan(4, &candidates[..5]).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Node draining
+//!
+//! Draining marks a node `Draining` so it drops out of placement, then
+//! re-places each of its workloads on the remaining Ready nodes. A bounded
+//! number of workloads migrate at once, and the whole drain shares one
+//! deadline; workloads still on the node when it passes are reported as
+//! failures, or evicted outright when the drain is forced.
+//!
+//! `drain_workflow` runs a drain as a durable workflow: the node is cordoned,
+//! then drained, and a drain that leaves workloads behind returns the node to
+//! the status it had before, so the remaining workloads keep being served.
+//! The scheduler runs every drain this way once it has a state store, and
+//! resumes unfinished drains when it starts.
+
+use crate::{NodeStatus, Scheduler};
+use futures::future::BoxFuture;
+use nexus_shared::NodeId;
+use nexus_state::{WorkflowContext, WorkflowDefinition, WorkflowStep};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Workflow kind of node drains; the input is the node's id
+pub const DRAIN_WORKFLOW: &str = "drain-node";
+
+/// Context key the drain's per-workload results are recorded under
+pub const DRAIN_RESULTS: &str = "results";
+
+/// How a drain migrates workloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrainConfig {
+    /// Workloads migrated at the same time
+    pub max_concurrency: usize,
+    /// Time allowed for the whole drain
+    pub deadline: Duration,
+    /// Evict workloads that cannot be re-placed instead of leaving them on the node
+    pub force: bool,
+}
+
+impl Default for DrainConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 4,
+            deadline: Duration::from_secs(300),
+            force: false,
+        }
+    }
+}
+
+/// What happened to one workload during rescheduling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReschedulingOutcome {
+    /// Running on the new node
+    Migrated,
+    /// Could not be re-placed before the deadline
+    Failed { reason: String },
+    /// Stopped and removed without a new placement
+    Evicted { reason: String },
+}
+
+/// A drain workflow for `WorkflowEngine::register`
+pub fn drain_workflow(scheduler: Arc<Scheduler>, config: DrainConfig) -> WorkflowDefinition {
+    WorkflowDefinition::new(DRAIN_WORKFLOW)
+        .step(CordonNode { scheduler: scheduler.clone() })
+        .step(MigrateWorkloads { scheduler, config })
+}
+
+/// Takes the node out of placement, remembering its status for rollback
+struct CordonNode {
+    scheduler: Arc<Scheduler>,
+}
+
+impl WorkflowStep for CordonNode {
+    fn name(&self) -> &str {
+        "cordon"
+    }
+
+    fn execute<'a>(&'a self, context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let node_id: NodeId = context.input_as()?;
+            let previous = self.scheduler.set_node_status(node_id, NodeStatus::Cordoned).await?;
+            // A rerun after a restart finds the node already cordoned
+            if context.get::<NodeStatus>("previous_status").is_none() {
+                context.set("previous_status", &previous)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn compensate<'a>(&'a self, context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let node_id: NodeId = context.input_as()?;
+            let previous = context.get("previous_status").unwrap_or(NodeStatus::Ready);
+            self.scheduler.set_node_status(node_id, previous).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Moves the node's workloads, failing if any are left behind
+struct MigrateWorkloads {
+    scheduler: Arc<Scheduler>,
+    config: DrainConfig,
+}
+
+impl WorkflowStep for MigrateWorkloads {
+    fn name(&self) -> &str {
+        "migrate"
+    }
+
+    fn execute<'a>(&'a self, context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let node_id: NodeId = context.input_as()?;
+            let results = self.scheduler.drain_node(node_id, &self.config).await?;
+            context.set(DRAIN_RESULTS, &results)?;
+            let failed = results
+                .iter()
+                .filter(|result| matches!(result.outcome, ReschedulingOutcome::Failed { .. }))
+                .count();
+            if failed > 0 {
+                anyhow::bail!("{} workload(s) could not be moved off node {}", failed, node_id);
+            }
+            Ok(())
+        })
+    }
+}
@@ -32,12 +32,33 @@ pub enum SchedulerError {
     #[error("Invalid node: {node_id}")]
     InvalidNode { node_id: NodeId },
 
+    #[error("Invalid node metadata: {message}")]
+    InvalidNodeMetadata { message: String },
+
+    #[error("Invalid scheduling decision signature: {message}")]
+    InvalidSignature { message: String },
+
     #[error("No available nodes for scheduling")]
     NoAvailableNodes,
 
     #[error("No suitable nodes for workload: {workload_id}")]
     NoSuitableNodes { workload_id: ResourceId },
 
+    #[error("Workload {workload_id} is held by scheduling gates: {gates}")]
+    SchedulingGated { workload_id: ResourceId, gates: String },
+
+    #[error("Workload {workload_id} is waiting on readiness conditions: {conditions}")]
+    ConditionsNotMet { workload_id: ResourceId, conditions: String },
+
+    #[error("Workload {workload_id} belongs to scheduler {scheduler_name}")]
+    WrongScheduler { workload_id: ResourceId, scheduler_name: String },
+
+    #[error("Placement conflict for {workload_id}: {message}")]
+    PlacementConflict { workload_id: ResourceId, message: String },
+
+    #[error("{resource} was modified: expected resourceVersion {expected}, current is {current}")]
+    ResourceVersionConflict { resource: String, expected: u64, current: u64 },
+
     #[error("Insufficient resources: need {required}, available {available}")]
     InsufficientResources { required: String, available: String },
 
@@ -62,6 +83,9 @@ pub enum SchedulerError {
     #[error("Node not found: {node_id}")]
     NodeNotFound { node_id: NodeId },
 
+    #[error("Drain of node {node_id} incomplete: {remaining} workload(s) could not be moved")]
+    DrainIncomplete { node_id: NodeId, remaining: usize },
+
     #[error("Scaling limit reached: {limit_type}")]
     ScalingLimitReached { limit_type: String },
 
@@ -99,6 +123,10 @@ impl SchedulerError {
         match self {
             SchedulerError::NoAvailableNodes => true,
             SchedulerError::InsufficientResources { .. } => true,
+            SchedulerError::DrainIncomplete { .. } => true,
+            SchedulerError::PlacementConflict { .. } => true,
+            SchedulerError::ResourceVersionConflict { .. } => true,
+            SchedulerError::ConditionsNotMet { .. } => true,
             SchedulerError::RuntimeError { .. } => true,
             SchedulerError::NetworkError { .. } => true,
             SchedulerError::StateError { .. } => true,
@@ -139,7 +167,8 @@ impl SchedulerError {
             self,
             SchedulerError::Configuration { .. } |
             SchedulerError::InvalidWorkload { .. } |
-            SchedulerError::InvalidNode { .. }
+            SchedulerError::InvalidNode { .. } |
+            SchedulerError::InvalidNodeMetadata { .. }
         )
     }
 
@@ -154,8 +183,15 @@ impl SchedulerError {
             SchedulerError::ResourceMonitoring { .. } => "monitoring",
             SchedulerError::InvalidWorkload { .. } => "invalid_workload",
             SchedulerError::InvalidNode { .. } => "invalid_node",
+            SchedulerError::InvalidNodeMetadata { .. } => "invalid_node_metadata",
+            SchedulerError::InvalidSignature { .. } => "invalid_signature",
             SchedulerError::NoAvailableNodes => "no_nodes",
             SchedulerError::NoSuitableNodes { .. } => "no_suitable_nodes",
+            SchedulerError::SchedulingGated { .. } => "scheduling_gated",
+            SchedulerError::ConditionsNotMet { .. } => "conditions_not_met",
+            SchedulerError::WrongScheduler { .. } => "wrong_scheduler",
+            SchedulerError::PlacementConflict { .. } => "placement_conflict",
+            SchedulerError::ResourceVersionConflict { .. } => "resource_version_conflict",
             SchedulerError::InsufficientResources { .. } => "insufficient_resources",
             SchedulerError::ConstraintNotSatisfied { .. } => "constraint_violation",
             SchedulerError::AffinityViolation { .. } => "affinity_violation",
@@ -164,6 +200,7 @@ impl SchedulerError {
             SchedulerError::NodeSelectorNotMatched { .. } => "node_selector",
             SchedulerError::WorkloadNotFound { .. } => "workload_not_found",
             SchedulerError::NodeNotFound { .. } => "node_not_found",
+            SchedulerError::DrainIncomplete { .. } => "drain_incomplete",
             SchedulerError::ScalingLimitReached { .. } => "scaling_limit",
             SchedulerError::RuntimeError { .. } => "runtime",
             SchedulerError::NetworkError { .. } => "network",
@@ -180,6 +217,8 @@ impl SchedulerError {
     /// Get severity level
     pub fn severity(&self) -> ErrorSeverity {
         match self {
+            SchedulerError::InvalidSignature { .. } => ErrorSeverity::Critical,
+            
             SchedulerError::InvalidWorkload { .. } |
             SchedulerError::InvalidNode { .. } |
             SchedulerError::Configuration { .. } => ErrorSeverity::High,
@@ -206,6 +245,14 @@ impl SchedulerError {
             SchedulerError::InsufficientResources { .. } => "Scale up cluster resources or reduce workload requirements",
             SchedulerError::PolicyViolation { .. } => "Review and adjust scheduling policies",
             SchedulerError::InvalidWorkload { .. } => "Fix workload specification and retry",
+            SchedulerError::SchedulingGated { .. } => "Approve the workload or remove its remaining scheduling gates",
+            SchedulerError::ConditionsNotMet { .. } => "Submit the workload to hold it until its placement conditions are true",
+            SchedulerError::WrongScheduler { .. } => "Submit the workload to the scheduler named in its scheduler_name",
+            SchedulerError::PlacementConflict { .. } => "Retry; another scheduler claimed the capacity first",
+            SchedulerError::ResourceVersionConflict { .. } => "Re-read the object and reapply the change against its current resourceVersion",
+            SchedulerError::DrainIncomplete { .. } => "Free capacity on other nodes and retry, or force the drain to evict remaining workloads",
+            SchedulerError::InvalidNodeMetadata { .. } => "Use key=value to set and key- to remove; pass --overwrite to replace existing values",
+            SchedulerError::InvalidSignature { .. } => "Treat the placement as forged and audit the component that produced it",
             SchedulerError::RuntimeError { .. } => "Check runtime system health and connectivity",
             SchedulerError::NetworkError { .. } => "Verify network connectivity and configuration",
             SchedulerError::Configuration { .. } => "Review scheduler configuration settings",
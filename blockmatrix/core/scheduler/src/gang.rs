@@ -0,0 +1,278 @@
+//! Gang scheduling
+//!
+//! A workload group is placed all-or-nothing: the planner searches for a
+//! node for every member at once, accounting for the capacity the members
+//! take from each other and for pod affinity and anti-affinity between
+//! members. If no complete assignment exists nothing is placed and the group
+//! waits in the group queue, so a distributed job never starts with only
+//! part of its replicas.
+
+use crate::affinity::PodAffinity;
+use crate::capacity::ResourceTotals;
+use crate::node_metadata::HOSTNAME_LABEL;
+use crate::workload::Workload;
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Nodes tried per member before the search gives up on a branch
+const MAX_CANDIDATES_PER_MEMBER: usize = 16;
+
+/// Workloads that must be placed together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadGroup {
+    pub name: String,
+    pub members: Vec<Workload>,
+}
+
+/// A group waiting for room to place all of its members
+#[derive(Debug, Clone)]
+pub struct PendingGroup {
+    pub group: WorkloadGroup,
+    pub queued_at: SystemTime,
+    /// Why the last placement attempt failed
+    pub reason: String,
+}
+
+/// Result of scheduling a group
+#[derive(Debug, Clone)]
+pub enum GroupOutcome {
+    /// Every member was placed
+    Scheduled(Vec<crate::SchedulingResult>),
+    /// Nothing was placed; the group is queued
+    Queued { reason: String },
+}
+
+/// A node as seen by the gang planner
+#[derive(Debug, Clone)]
+pub struct GangNode {
+    pub node_id: NodeId,
+    pub labels: HashMap<String, String>,
+    /// Capacity not yet committed to other workloads
+    pub free: ResourceTotals,
+}
+
+impl GangNode {
+    /// Topology domain of the node for a pod affinity topology key
+    ///
+    /// Nodes without the label form a domain of their own.
+    fn domain(&self, topology_key: &str) -> String {
+        match self.labels.get(topology_key) {
+            Some(value) => value.clone(),
+            None if topology_key == HOSTNAME_LABEL => self.node_id.to_hex(),
+            None => format!("node:{}", self.node_id.to_hex()),
+        }
+    }
+}
+
+fn member_demand(workload: &Workload) -> ResourceTotals {
+    let spec = &workload.spec;
+    let replicas = spec.replicas.max(1) as f64;
+    ResourceTotals {
+        cpu_cores: spec.resources.cpu_cores * replicas,
+        memory_mb: spec.resources.memory_mb as f64 * replicas,
+        gpus: spec.gpus as f64 * replicas,
+    }
+}
+
+fn selects(rule: &PodAffinity, workload: &Workload) -> bool {
+    rule.label_selector
+        .iter()
+        .all(|(key, value)| workload.spec.labels.get(key) == Some(value))
+}
+
+/// Whether two members may sit on the given nodes under each other's rules
+pub(crate) fn compatible(a: &Workload, a_node: &GangNode, b: &Workload, b_node: &GangNode) -> bool {
+    let one_way = |x: &Workload, x_node: &GangNode, y: &Workload, y_node: &GangNode| {
+        let rules = &x.spec.affinity;
+        rules.pod_affinity
+            .iter()
+            .filter(|rule| selects(rule, y))
+            .all(|rule| x_node.domain(&rule.topology_key) == y_node.domain(&rule.topology_key))
+            && rules.pod_anti_affinity
+                .iter()
+                .filter(|rule| selects(rule, y))
+                .all(|rule| x_node.domain(&rule.topology_key) != y_node.domain(&rule.topology_key))
+    };
+    one_way(a, a_node, b, b_node) && one_way(b, b_node, a, a_node)
+}
+
+fn fits(demand: &ResourceTotals, free: &ResourceTotals) -> bool {
+    demand.cpu_cores <= free.cpu_cores && demand.memory_mb <= free.memory_mb && demand.gpus <= free.gpus
+}
+
+/// Find a node for every member, or explain why none exists
+///
+/// Members are placed largest first and the search backtracks when a
+/// member has no compatible node left. Returns one node per member, in
+/// member order.
+pub fn plan_group(members: &[Workload], nodes: &[GangNode]) -> std::result::Result<Vec<NodeId>, String> {
+    if members.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let demands: Vec<ResourceTotals> = members.iter().map(member_demand).collect();
+    let mut order: Vec<usize> = (0..members.len()).collect();
+    order.sort_by(|&a, &b| {
+        demands[b].cpu_cores.partial_cmp(&demands[a].cpu_cores).unwrap_or(std::cmp::Ordering::Equal)
+            .then(demands[b].memory_mb.partial_cmp(&demands[a].memory_mb).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    for &member in &order {
+        if !nodes.iter().any(|node| {
+            members[member].spec.affinity.matches_node(&node.labels) && fits(&demands[member], &node.free)
+        }) {
+            return Err(format!("no node can host member {}", members[member].spec.id));
+        }
+    }
+
+    let mut free: Vec<ResourceTotals> = nodes.iter().map(|node| node.free).collect();
+    let mut assignment: Vec<Option<usize>> = vec![None; members.len()];
+
+    if search(0, &order, members, &demands, nodes, &mut free, &mut assignment) {
+        Ok(assignment.into_iter().map(|node| nodes[node.expect("every member assigned")].node_id).collect())
+    } else {
+        Err(format!(
+            "{} members do not fit together on {} nodes under their affinity rules",
+            members.len(),
+            nodes.len()
+        ))
+    }
+}
+
+fn search(
+    depth: usize,
+    order: &[usize],
+    members: &[Workload],
+    demands: &[ResourceTotals],
+    nodes: &[GangNode],
+    free: &mut [ResourceTotals],
+    assignment: &mut [Option<usize>],
+) -> bool {
+    let Some(&member) = order.get(depth) else {
+        return true;
+    };
+    let workload = &members[member];
+    let demand = &demands[member];
+
+    // Most free capacity first spreads members and leaves room for the rest
+    let mut candidates: Vec<usize> = (0..nodes.len())
+        .filter(|&n| workload.spec.affinity.matches_node(&nodes[n].labels) && fits(demand, &free[n]))
+        .filter(|&n| {
+            order[..depth].iter().all(|&placed| {
+                let placed_node = &nodes[assignment[placed].expect("placed members are assigned")];
+                compatible(workload, &nodes[n], &members[placed], placed_node)
+            })
+        })
+        .collect();
+    candidates.sort_by(|&a, &b| {
+        free[b].cpu_cores.partial_cmp(&free[a].cpu_cores).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| nodes[a].node_id.to_hex().cmp(&nodes[b].node_id.to_hex()))
+    });
+    candidates.truncate(MAX_CANDIDATES_PER_MEMBER);
+
+    for n in candidates {
+        free[n].cpu_cores -= demand.cpu_cores;
+        free[n].memory_mb -= demand.memory_mb;
+        free[n].gpus -= demand.gpus;
+        assignment[member] = Some(n);
+
+        if search(depth + 1, order, members, demands, nodes, free, assignment) {
+            return true;
+        }
+
+        free[n].cpu_cores += demand.cpu_cores;
+        free[n].memory_mb += demand.memory_mb;
+        free[n].gpus += demand.gpus;
+        assignment[member] = None;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::{WorkloadSpec, WorkloadType};
+    use crate::config::DEFAULT_SCHEDULER_NAME;
+    use nexus_shared::ResourceId;
+
+    fn member(name: &str, cpu: f64, role: &str) -> Workload {
+        let id = ResourceId::new("ml", name, "workload");
+        Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Batch,
+            priority: 0,
+            spec: WorkloadSpec {
+                id,
+                name: name.to_string(),
+                image: "trainer:latest".to_string(),
+                replicas: 1,
+                gpus: 0,
+                resources: nexus_runtime::ResourceQuotas {
+                    cpu_cores: cpu,
+                    memory_mb: 1024,
+                    ..Default::default()
+                },
+                labels: HashMap::from([("role".to_string(), role.to_string())]),
+                workload_type: WorkloadType::Batch,
+                command: Vec::new(),
+                environment: HashMap::new(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: Default::default(),
+                scheduling_gates: Vec::new(),
+                readiness_gates: Vec::new(),
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        }
+    }
+
+    fn node(zone: &str, cpu: f64) -> GangNode {
+        GangNode {
+            node_id: NodeId::random(),
+            labels: HashMap::from([("zone".to_string(), zone.to_string())]),
+            free: ResourceTotals { cpu_cores: cpu, memory_mb: 8192.0, gpus: 0.0 },
+        }
+    }
+
+    fn rule(role: &str, topology_key: &str) -> PodAffinity {
+        PodAffinity {
+            label_selector: HashMap::from([("role".to_string(), role.to_string())]),
+            topology_key: topology_key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plan_group() {
+        let nodes = vec![node("a", 6.0), node("a", 6.0), node("b", 8.0)];
+
+        // Workers spread across nodes, all in the parameter server's zone
+        let ps = member("ps", 2.0, "ps");
+        let mut workers = Vec::new();
+        for i in 0..2 {
+            let mut worker = member(&format!("worker-{}", i), 3.0, "worker");
+            worker.spec.affinity.pod_affinity.push(rule("ps", "zone"));
+            worker.spec.affinity.pod_anti_affinity.push(rule("worker", HOSTNAME_LABEL));
+            workers.push(worker);
+        }
+        let mut group = vec![ps];
+        group.extend(workers);
+
+        let placement = plan_group(&group, &nodes).unwrap();
+        let zone = |id: &NodeId| nodes.iter().find(|n| &n.node_id == id).unwrap().labels["zone"].clone();
+        assert_ne!(placement[1], placement[2]);
+        assert_eq!(zone(&placement[1]), zone(&placement[0]));
+        assert_eq!(zone(&placement[2]), zone(&placement[0]));
+
+        // A third worker needs a third node in the same zone: all or nothing
+        let mut extra = member("worker-2", 3.0, "worker");
+        extra.spec.affinity = group[1].spec.affinity.clone();
+        group.push(extra);
+        assert!(plan_group(&group, &nodes).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Scheduling gates and approval holds
+//!
+//! A workload submitted with scheduling gates is held instead of placed.
+//! Each gate is removed independently, either by an external controller
+//! (for example a change-management window opening) or by an operator
+//! approving the workload. Placement readiness gates hold a workload the
+//! same way and are removed when their condition controller reports the
+//! condition true. Once the last gate is gone the workload is handed to the
+//! scheduler as usual.
+
+use crate::workload::Workload;
+use nexus_shared::ResourceId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::time::SystemTime;
+
+/// Gate removed by operator approval
+pub const MANUAL_APPROVAL_GATE: &str = "nexus.io/manual-approval";
+
+/// Recorded as the remover of readiness conditions that became true
+pub const CONDITION_CONTROLLER: &str = "condition-controller";
+
+/// A workload waiting for its scheduling gates to be removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeldWorkload {
+    pub workload: Workload,
+    /// Gates still blocking placement
+    pub gates: BTreeSet<String>,
+    /// Placement readiness conditions not yet true
+    #[serde(default)]
+    pub conditions: BTreeSet<String>,
+    pub held_at: SystemTime,
+    /// Who removed each gate so far, in order
+    pub released: Vec<GateRelease>,
+}
+
+/// Record of a gate being removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateRelease {
+    pub gate: String,
+    pub released_by: String,
+    pub released_at: SystemTime,
+}
+
+impl HeldWorkload {
+    pub fn new(workload: Workload) -> Self {
+        let gates = workload.spec.scheduling_gates.iter().cloned().collect();
+        Self {
+            workload,
+            gates,
+            conditions: BTreeSet::new(),
+            held_at: SystemTime::now(),
+            released: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> &ResourceId {
+        &self.workload.spec.id
+    }
+
+    /// Remove a gate; returns false if the workload did not have it
+    pub fn release(&mut self, gate: &str, released_by: &str) -> bool {
+        if !self.gates.remove(gate) {
+            return false;
+        }
+        self.released.push(GateRelease {
+            gate: gate.to_string(),
+            released_by: released_by.to_string(),
+            released_at: SystemTime::now(),
+        });
+        true
+    }
+
+    /// Hold the workload until the named conditions are true
+    pub fn with_conditions(mut self, conditions: impl IntoIterator<Item = String>) -> Self {
+        self.conditions.extend(conditions);
+        self
+    }
+
+    /// Mark a condition true; returns false if the workload was not waiting on it
+    pub fn condition_met(&mut self, condition: &str) -> bool {
+        if !self.conditions.remove(condition) {
+            return false;
+        }
+        self.released.push(GateRelease {
+            gate: condition.to_string(),
+            released_by: CONDITION_CONTROLLER.to_string(),
+            released_at: SystemTime::now(),
+        });
+        true
+    }
+
+    /// Gates and conditions still blocking placement
+    pub fn blocking(&self) -> Vec<String> {
+        self.gates.iter().chain(&self.conditions).cloned().collect()
+    }
+
+    /// Whether every gate has been removed and every condition is true
+    pub fn is_ready(&self) -> bool {
+        self.gates.is_empty() && self.conditions.is_empty()
+    }
+
+    /// The workload as it should be scheduled, with its gates cleared
+    pub fn into_workload(mut self) -> Workload {
+        self.workload.spec.scheduling_gates.clear();
+        self.workload
+    }
+}
+
+/// Result of submitting a workload
+#[derive(Debug, Clone)]
+pub enum SubmitOutcome {
+    /// The workload had no gates and was placed
+    Scheduled(crate::SchedulingResult),
+    /// The workload is held until the listed gates are removed or conditions are true
+    Held { gates: Vec<String> },
+}
@@ -0,0 +1,324 @@
+//! Node heartbeats carrying resource usage and capability changes
+//!
+//! Agents send the control plane one compact message per interval instead of
+//! answering resource polls and re-registering when their capabilities
+//! change. A heartbeat shows the node is alive and carries only what changed
+//! since the last report the control plane acknowledged: resource fields that
+//! moved by at least the configured threshold, and capabilities added or
+//! removed. A delta against a report the control plane no longer holds, after
+//! a restart or a lost acknowledgement, is answered with a request for a full
+//! report. Agents also send a full report every `full_report_every`
+//! heartbeats, so the two sides cannot drift apart for long.
+
+use crate::resource_monitor::NodeResources;
+use nexus_runtime::StorageCapacity;
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::time::Duration;
+
+/// Unacknowledged heartbeats an agent remembers
+const MAX_IN_FLIGHT: usize = 8;
+
+/// How agents report to the control plane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    /// How often agents send a heartbeat
+    pub interval: Duration,
+    /// Heartbeats a node may miss before it is marked NotReady
+    pub missed_before_not_ready: u32,
+    /// Smallest change in CPU cores worth reporting
+    pub cpu_threshold: f64,
+    /// Smallest change in memory worth reporting, in bytes
+    pub memory_threshold: u64,
+    /// Send a full report every this many heartbeats
+    pub full_report_every: u64,
+    /// Smallest change in a storage class's free space worth reporting, in bytes
+    #[serde(default = "default_storage_threshold")]
+    pub storage_threshold: u64,
+}
+
+fn default_storage_threshold() -> u64 {
+    1024 * 1024 * 1024
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            missed_before_not_ready: 3,
+            cpu_threshold: 0.1,
+            memory_threshold: 64 * 1024 * 1024,
+            full_report_every: 60,
+            storage_threshold: default_storage_threshold(),
+        }
+    }
+}
+
+/// What an agent reports about its node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeReport {
+    pub resources: NodeResources,
+    pub capabilities: BTreeSet<String>,
+}
+
+/// Resource fields that changed; absent fields are unchanged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceDelta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_total: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_available: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_available: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_total: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_available: Option<u32>,
+    /// Every storage class, sent when any of them changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, StorageCapacity>>,
+}
+
+impl ResourceDelta {
+    /// Fields of `new` that moved from `old` by at least the thresholds
+    fn between(old: &NodeResources, new: &NodeResources, config: &HeartbeatConfig) -> Self {
+        let cpu = |old: f64, new: f64| ((new - old).abs() >= config.cpu_threshold).then_some(new);
+        let memory = |old: u64, new: u64| (old.abs_diff(new) >= config.memory_threshold.max(1)).then_some(new);
+        let gpu = |old: u32, new: u32| (old != new).then_some(new);
+        let storage_moved = old.storage.len() != new.storage.len()
+            || new.storage.iter().any(|(class, new)| match old.storage.get(class) {
+                Some(old) => old.total_bytes != new.total_bytes
+                    || old.available_bytes.abs_diff(new.available_bytes) >= config.storage_threshold.max(1),
+                None => true,
+            });
+        Self {
+            cpu_total: cpu(old.cpu_total, new.cpu_total),
+            cpu_available: cpu(old.cpu_available, new.cpu_available),
+            memory_total: memory(old.memory_total, new.memory_total),
+            memory_available: memory(old.memory_available, new.memory_available),
+            gpu_total: gpu(old.gpu_total, new.gpu_total),
+            gpu_available: gpu(old.gpu_available, new.gpu_available),
+            storage: storage_moved.then(|| new.storage.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn apply(&self, resources: &mut NodeResources) {
+        resources.cpu_total = self.cpu_total.unwrap_or(resources.cpu_total);
+        resources.cpu_available = self.cpu_available.unwrap_or(resources.cpu_available);
+        resources.memory_total = self.memory_total.unwrap_or(resources.memory_total);
+        resources.memory_available = self.memory_available.unwrap_or(resources.memory_available);
+        resources.gpu_total = self.gpu_total.unwrap_or(resources.gpu_total);
+        resources.gpu_available = self.gpu_available.unwrap_or(resources.gpu_available);
+        if let Some(storage) = &self.storage {
+            resources.storage = storage.clone();
+        }
+    }
+}
+
+/// Contents of a heartbeat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HeartbeatBody {
+    /// Everything the node reports
+    Full(NodeReport),
+    /// Changes since the report of heartbeat `base`
+    Delta {
+        base: u64,
+        #[serde(default, skip_serializing_if = "ResourceDelta::is_empty")]
+        resources: ResourceDelta,
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        added: BTreeSet<String>,
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        removed: BTreeSet<String>,
+    },
+}
+
+impl HeartbeatBody {
+    /// Report after this body is applied to `base`, the report of its base heartbeat
+    fn applied_to(&self, base: &NodeReport) -> NodeReport {
+        match self {
+            HeartbeatBody::Full(report) => report.clone(),
+            HeartbeatBody::Delta { resources, added, removed, .. } => {
+                let mut report = base.clone();
+                resources.apply(&mut report.resources);
+                report.capabilities.retain(|capability| !removed.contains(capability));
+                report.capabilities.extend(added.iter().cloned());
+                report
+            }
+        }
+    }
+}
+
+/// Periodic message from an agent to the control plane
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub node_id: NodeId,
+    pub sequence: u64,
+    pub body: HeartbeatBody,
+}
+
+/// Control plane's answer to a heartbeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeartbeatAck {
+    pub sequence: u64,
+    /// The heartbeat was not applied; the next one must be a full report
+    pub resync: bool,
+}
+
+/// Builds an agent's heartbeats against what the control plane acknowledged
+#[derive(Debug)]
+pub struct HeartbeatEncoder {
+    node_id: NodeId,
+    config: HeartbeatConfig,
+    sequence: u64,
+    /// Last acknowledged heartbeat and the report the control plane holds for it
+    acked: Option<(u64, NodeReport)>,
+    /// Reports the control plane will hold once each sent heartbeat is applied
+    in_flight: VecDeque<(u64, NodeReport)>,
+}
+
+impl HeartbeatEncoder {
+    pub fn new(node_id: NodeId, config: HeartbeatConfig) -> Self {
+        Self { node_id, config, sequence: 0, acked: None, in_flight: VecDeque::new() }
+    }
+
+    /// Next heartbeat for the node's current state
+    pub fn encode(&mut self, current: &NodeReport) -> Heartbeat {
+        self.sequence += 1;
+        let body = match &self.acked {
+            Some((base, acked)) if self.sequence % self.config.full_report_every.max(1) != 0 => HeartbeatBody::Delta {
+                base: *base,
+                resources: ResourceDelta::between(&acked.resources, &current.resources, &self.config),
+                added: current.capabilities.difference(&acked.capabilities).cloned().collect(),
+                removed: acked.capabilities.difference(&current.capabilities).cloned().collect(),
+            },
+            _ => HeartbeatBody::Full(current.clone()),
+        };
+        let held = match &self.acked {
+            Some((_, acked)) => body.applied_to(acked),
+            None => body.applied_to(current),
+        };
+        if self.in_flight.len() == MAX_IN_FLIGHT {
+            self.in_flight.pop_front();
+        }
+        self.in_flight.push_back((self.sequence, held));
+        Heartbeat { node_id: self.node_id, sequence: self.sequence, body }
+    }
+
+    /// Take the control plane's answer into account for later heartbeats
+    pub fn acknowledge(&mut self, ack: HeartbeatAck) {
+        if ack.resync {
+            self.acked = None;
+            self.in_flight.clear();
+            return;
+        }
+        while let Some((sequence, report)) = self.in_flight.pop_front() {
+            if sequence == ack.sequence {
+                self.acked = Some((sequence, report));
+                break;
+            }
+        }
+    }
+}
+
+/// Heartbeat counters on the control plane
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeartbeatStats {
+    pub received: u64,
+    pub full_reports: u64,
+    pub deltas: u64,
+    pub resyncs: u64,
+}
+
+/// Last applied heartbeat of each node, on the control plane
+#[derive(Debug, Default)]
+pub(crate) struct HeartbeatTracker {
+    nodes: HashMap<NodeId, (u64, NodeReport)>,
+    stats: HeartbeatStats,
+}
+
+impl HeartbeatTracker {
+    /// Apply a heartbeat, returning the node's report, or `None` if a full report is needed
+    pub fn receive(&mut self, heartbeat: &Heartbeat) -> Option<&NodeReport> {
+        self.stats.received += 1;
+        let report = match (&heartbeat.body, self.nodes.get(&heartbeat.node_id)) {
+            (HeartbeatBody::Full(report), _) => {
+                self.stats.full_reports += 1;
+                report.clone()
+            }
+            (body @ HeartbeatBody::Delta { base, .. }, Some((applied, held))) if base == applied => {
+                self.stats.deltas += 1;
+                body.applied_to(held)
+            }
+            (HeartbeatBody::Delta { .. }, _) => {
+                self.stats.resyncs += 1;
+                return None;
+            }
+        };
+        let entry = self.nodes.entry(heartbeat.node_id).or_default();
+        *entry = (heartbeat.sequence, report);
+        Some(&entry.1)
+    }
+
+    /// Whether a node has reported through heartbeats
+    pub fn tracks(&self, node_id: &NodeId) -> bool {
+        self.nodes.contains_key(node_id)
+    }
+
+    pub fn forget(&mut self, node_id: &NodeId) {
+        self.nodes.remove(node_id);
+    }
+
+    pub fn stats(&self) -> HeartbeatStats {
+        self.stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(cpu_available: f64, capabilities: &[&str]) -> NodeReport {
+        NodeReport {
+            resources: NodeResources { cpu_total: 8.0, cpu_available, memory_total: 1 << 34, memory_available: 1 << 33, ..Default::default() },
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_deltas_track_acknowledged_reports() {
+        let node_id = NodeId::random();
+        let mut agent = HeartbeatEncoder::new(node_id, HeartbeatConfig::default());
+        let mut control_plane = HeartbeatTracker::default();
+
+        // Unknown nodes must send a full report first
+        let first = agent.encode(&report(6.0, &["gpu"]));
+        assert!(matches!(first.body, HeartbeatBody::Full(_)));
+        assert!(control_plane.receive(&first).is_some());
+        agent.acknowledge(HeartbeatAck { sequence: first.sequence, resync: false });
+
+        // Changes below the threshold are not sent
+        let quiet = agent.encode(&report(6.05, &["gpu"]));
+        let HeartbeatBody::Delta { resources, added, removed, .. } = &quiet.body else { panic!("expected a delta") };
+        assert!(resources.is_empty() && added.is_empty() && removed.is_empty());
+        assert_eq!(control_plane.receive(&quiet).unwrap().resources.cpu_available, 6.0);
+        agent.acknowledge(HeartbeatAck { sequence: quiet.sequence, resync: false });
+
+        let busy = agent.encode(&report(2.0, &["wasm"]));
+        let applied = control_plane.receive(&busy).unwrap();
+        assert_eq!(applied, &report(2.0, &["wasm"]));
+
+        // The ack was lost, so the next delta is against a report the control plane no longer has
+        let stale = agent.encode(&report(2.0, &["wasm"]));
+        assert!(control_plane.receive(&stale).is_none());
+        agent.acknowledge(HeartbeatAck { sequence: stale.sequence, resync: true });
+        assert!(matches!(agent.encode(&report(2.0, &["wasm"])).body, HeartbeatBody::Full(_)));
+        assert_eq!(control_plane.stats().resyncs, 1);
+    }
+}
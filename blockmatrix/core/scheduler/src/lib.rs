@@ -4,6 +4,7 @@
 //! - Multi-objective optimization for workload placement
 //! - Machine learning-based workload prediction
 //! - Real-time resource monitoring and autoscaling
+//! - Delta-encoded node heartbeats carrying resource usage and capabilities
 //! - Support for heterogeneous hardware (CPU, GPU, FPGA)
 //! - Policy-driven scheduling with constraints
 
@@ -16,11 +17,29 @@ pub mod resource_monitor;
 pub mod workload;
 pub mod node_selector;
 pub mod affinity;
+pub mod node_metadata;
+pub mod gates;
+pub mod capacity;
+pub mod diversity;
+pub mod drain;
+pub mod signing;
+pub mod claims;
+pub mod gang;
+pub mod shadow;
+pub mod readiness;
+pub mod volumes;
+pub mod preemption;
+pub mod consolidation;
+pub mod network_cost;
+pub mod heartbeat;
 pub mod config;
 pub mod error;
 
 pub use placement::{PlacementEngine, PlacementDecision, PlacementStrategy};
-pub use autoscaling::{AutoScaler, ScalingDecision, ScalingPolicy};
+pub use autoscaling::{
+    AutoScaler, DemandForecast, PredictionError, PredictiveScaling, ScalingCooldown, ScalingDecision,
+    ScalingObservation, ScalingPolicy, ScalingSchedule,
+};
 pub use predictor::{WorkloadPredictor, ResourceDemand, Prediction};
 pub use optimizer::{MultiObjectiveOptimizer, OptimizationObjective, Solution};
 pub use policies::{SchedulingPolicy, PolicyEngine, Constraint};
@@ -28,23 +47,53 @@ pub use resource_monitor::{ResourceMonitor, NodeResources, ResourceUsage};
 pub use workload::{Workload, WorkloadSpec, WorkloadStatus};
 pub use node_selector::{NodeSelector, NodeScore, SelectionCriteria};
 pub use affinity::{AffinityRules, AntiAffinityRules, NodeAffinity, PodAffinity};
-pub use config::SchedulerConfig;
+pub use node_metadata::{MetadataChange, NodeMetadata, NodeMetadataUpdate};
+pub use gates::{GateRelease, HeldWorkload, SubmitOutcome, MANUAL_APPROVAL_GATE};
+pub use capacity::{CapacityForecast, CapacityResource, ResourceForecast, ResourceTotals};
+pub use diversity::{DiversityConfig, TrustDomainDiversity};
+pub use drain::{drain_workflow, DrainConfig, ReschedulingOutcome, DRAIN_RESULTS, DRAIN_WORKFLOW};
+pub use signing::SignedDecision;
+pub use claims::{ClaimLedger, PlacementClaim};
+pub use gang::{GroupOutcome, PendingGroup, WorkloadGroup};
+pub use shadow::{ShadowConfig, ShadowDecision, ShadowReport};
+pub use readiness::{ConditionController, ConditionStatus, GateCondition, GatePhase, ReadinessGate};
+pub use volumes::{NodeVolumes, StorageShortfall, WorkloadVolume, DEFAULT_STORAGE_CLASS};
+pub use preemption::{DisruptionBudget, PreemptionConfig, PreemptionPolicy, PriorityClass, PRIORITY_CLASS_LABEL};
+pub use consolidation::{ConsolidationConfig, ConsolidationPlan, ConsolidationReport, PlannedMove};
+pub use network_cost::{DependencyGraph, LatencyMatrix, NetworkAwareConfig, NETWORK_PEERS_LABEL, ZONE_LABEL};
+pub use heartbeat::{
+    Heartbeat, HeartbeatAck, HeartbeatBody, HeartbeatConfig, HeartbeatEncoder, HeartbeatStats, NodeReport, ResourceDelta,
+};
+pub use config::{SchedulerConfig, DEFAULT_SCHEDULER_NAME};
 pub use error::{SchedulerError, Result};
 
-use nexus_shared::{NodeId, ResourceId};
-use nexus_runtime::{Runtime, ContainerSpec};
+use nexus_shared::{ApiMeta, KeyPair, NodeId, ResourceId, ServiceId, StatusConditions, Validate};
+use nexus_shared::api_meta::{condition_types, ConditionStatus as ObjectConditionStatus};
+use nexus_runtime::{Runtime, ContainerSpec, VolumeSnapshot};
 use nexus_networking::NetworkManager;
-use nexus_state::StateManager;
+use nexus_state::{
+    ownership::kinds, DeletionPropagation, GarbageCollector, ObjectRef, OwnerReference, StateManager, WorkflowEngine,
+    WorkflowState,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, BTreeMap, BTreeSet};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
+use futures::stream::{self, StreamExt};
 use tokio::sync::{RwLock, mpsc, broadcast};
 
+/// Placement attempts before giving up on capacity contested by other schedulers
+const MAX_PLACEMENT_ATTEMPTS: usize = 3;
+
+/// Read-modify-write attempts on a node's claim ledger
+const MAX_CLAIM_ATTEMPTS: usize = 5;
+
 /// Central scheduler for managing workload placement and scaling
 pub struct Scheduler {
     config: SchedulerConfig,
     node_id: NodeId,
+    /// TrustChain key placement decisions are signed with
+    key_pair: KeyPair,
     
     // Core components
     placement_engine: Arc<PlacementEngine>,
@@ -53,17 +102,23 @@ pub struct Scheduler {
     optimizer: Arc<MultiObjectiveOptimizer>,
     policy_engine: Arc<PolicyEngine>,
     resource_monitor: Arc<ResourceMonitor>,
-    node_selector: Arc<NodeSelector>,
+    shadow: Arc<shadow::ShadowScheduler>,
+    condition_controllers: Arc<RwLock<readiness::ConditionRegistry>>,
     
     // External dependencies
     runtime: Option<Arc<Runtime>>,
     network_manager: Option<Arc<NetworkManager>>,
     state_manager: Option<Arc<StateManager>>,
+    garbage_collector: Option<Arc<GarbageCollector>>,
+    workflow_engine: Option<Arc<WorkflowEngine>>,
     
     // State
     nodes: Arc<RwLock<HashMap<NodeId, ClusterNode>>>,
     workloads: Arc<RwLock<HashMap<ResourceId, ScheduledWorkload>>>,
     placement_queue: Arc<RwLock<Vec<PendingWorkload>>>,
+    held: Arc<RwLock<HashMap<ResourceId, HeldWorkload>>>,
+    group_queue: Arc<RwLock<Vec<PendingGroup>>>,
+    heartbeats: Arc<RwLock<heartbeat::HeartbeatTracker>>,
     
     // Event channels
     scheduler_events: broadcast::Sender<SchedulerEvent>,
@@ -72,21 +127,55 @@ pub struct Scheduler {
     // Background tasks
     scheduling_task: Option<tokio::task::JoinHandle<()>>,
     monitoring_task: Option<tokio::task::JoinHandle<()>>,
+    activation_task: Option<tokio::task::JoinHandle<()>>,
+    collection_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl Scheduler {
-    /// Create a new scheduler
+    /// Create a new scheduler signing with the node identity key
+    ///
+    /// The key is loaded from `identity_key_path`, or generated for this
+    /// process only when no path is configured.
     pub async fn new(config: SchedulerConfig) -> Result<Self> {
-        let node_id = NodeId::random();
+        let key_pair = match &config.identity_key_path {
+            Some(path) => KeyPair::load_or_generate(std::path::Path::new(path)),
+            None => KeyPair::generate(),
+        }
+        .map_err(|e| SchedulerError::Configuration {
+            message: format!("Failed to load scheduler key: {}", e),
+        })?;
+        
+        Self::with_key_pair(config, key_pair).await
+    }
+    
+    /// Create a scheduler that signs its decisions with a TrustChain key
+    ///
+    /// The scheduler's node ID is derived from the key, so decisions can be
+    /// attributed to it by anyone holding the public key.
+    pub async fn with_key_pair(config: SchedulerConfig, key_pair: KeyPair) -> Result<Self> {
+        let report = config.validate_config();
+        for warning in report.warnings() {
+            tracing::warn!("Scheduler config {}", warning);
+        }
+        if !report.is_valid() {
+            return Err(SchedulerError::Configuration { message: report.error_summary() });
+        }
+
+        let node_id = nexus_networking::dht_security::derive_node_id(key_pair.public_key());
         
         // Create core components
         let placement_engine = Arc::new(PlacementEngine::new(placement::PlacementStrategy::default()));
         let autoscaler = Arc::new(AutoScaler::new());
-        let predictor = Arc::new(WorkloadPredictor::new(ResourceId::new("scheduler", "predictor", "default")));
-        let optimizer = Arc::new(MultiObjectiveOptimizer::new());
+        let predictor = Arc::new(WorkloadPredictor::new(ResourceId::new("scheduler", "predictor", "default"))
+            .with_window(config.prediction.window));
+        let optimizer = Arc::new(MultiObjectiveOptimizer::new()
+            .with_diversity(TrustDomainDiversity::new(config.optimization.diversity.clone()))
+            .with_network(config.optimization.network.clone()));
         let policy_engine = Arc::new(PolicyEngine::new());
         let resource_monitor = Arc::new(ResourceMonitor::new(ResourceId::new("scheduler", "monitor", "default")));
-        let node_selector = Arc::new(NodeSelector::new());
+        let shadow = Arc::new(shadow::ShadowScheduler::new(config.shadow.clone()));
+        let mut condition_controllers = readiness::ConditionRegistry::new();
+        condition_controllers.register(Arc::new(readiness::TcpReachable::default()));
         
         let (scheduler_events, _) = broadcast::channel(10000);
         let (placement_requests, placement_receiver) = mpsc::unbounded_channel();
@@ -94,23 +183,32 @@ impl Scheduler {
         Ok(Self {
             config,
             node_id,
+            key_pair,
             placement_engine,
             autoscaler,
             predictor,
             optimizer,
             policy_engine,
             resource_monitor,
-            node_selector,
+            shadow,
+            condition_controllers: Arc::new(RwLock::new(condition_controllers)),
             runtime: None,
             network_manager: None,
             state_manager: None,
+            garbage_collector: None,
+            workflow_engine: None,
             nodes: Arc::new(RwLock::new(HashMap::new())),
             workloads: Arc::new(RwLock::new(HashMap::new())),
             placement_queue: Arc::new(RwLock::new(Vec::new())),
+            held: Arc::new(RwLock::new(HashMap::new())),
+            group_queue: Arc::new(RwLock::new(Vec::new())),
+            heartbeats: Arc::new(RwLock::new(heartbeat::HeartbeatTracker::default())),
             scheduler_events,
             placement_requests,
             scheduling_task: None,
             monitoring_task: None,
+            activation_task: None,
+            collection_task: None,
         })
     }
     
@@ -124,6 +222,9 @@ impl Scheduler {
         // Start workload predictor
         self.predictor.start().await.map_err(|e| SchedulerError::RuntimeError { message: e.to_string() })?;
         
+        // Drains run as workflows so a restart picks them up where they stopped
+        self.start_workflows();
+        
         // Start background tasks
         self.start_background_tasks().await?;
         
@@ -142,6 +243,12 @@ impl Scheduler {
         if let Some(task) = self.monitoring_task.take() {
             task.abort();
         }
+        if let Some(task) = self.activation_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.collection_task.take() {
+            task.abort();
+        }
         
         // Stop components
         self.predictor.stop().await.map_err(|e| SchedulerError::RuntimeError { message: e.to_string() })?;
@@ -161,55 +268,115 @@ impl Scheduler {
     }
     
     pub fn set_state_manager(&mut self, state_manager: Arc<StateManager>) {
+        self.garbage_collector = Some(Arc::new(GarbageCollector::new(state_manager.clone())));
         self.state_manager = Some(state_manager);
     }
     
+    /// Name workloads use to select this scheduler
+    pub fn scheduler_name(&self) -> &str {
+        &self.config.scheduler_name
+    }
+    
+    /// Whether this scheduler places the workload
+    pub fn is_responsible_for(&self, workload: &Workload) -> bool {
+        workload.spec.scheduler_name == self.config.scheduler_name
+    }
+    
+    /// Check a readiness condition with the given controller
+    ///
+    /// Replaces any controller already registered for the same condition.
+    pub async fn register_condition_controller(&self, controller: Arc<dyn ConditionController>) {
+        self.condition_controllers.write().await.register(controller);
+    }
+    
+    /// Node ID that signs this scheduler's decisions
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+    
+    /// Public half of the decision signing key
+    pub fn public_key(&self) -> &[u8; 32] {
+        self.key_pair.public_key()
+    }
+    
     /// Schedule a workload
-    pub async fn schedule_workload(&self, workload: Workload) -> Result<SchedulingResult> {
+    pub async fn schedule_workload(&self, mut workload: Workload) -> Result<SchedulingResult> {
         tracing::info!("Scheduling workload: {}", workload.spec.id);
+        if workload.metadata.generation == 0 {
+            workload.metadata = ApiMeta::created();
+        }
+        
+        if !self.is_responsible_for(&workload) {
+            return Err(SchedulerError::WrongScheduler {
+                workload_id: workload.spec.id.clone(),
+                scheduler_name: workload.spec.scheduler_name.clone(),
+            });
+        }
         
         // Validate workload specification
         self.validate_workload(&workload).await?;
         
-        // Apply scheduling policies
-        let _policy_check = self.policy_engine
-            .apply_policies(&workload)
-            .await
-            .map_err(|e| SchedulerError::PolicyViolation { message: e.to_string() })?;
-        
-        // Get available nodes
-        let nodes = self.get_available_nodes().await?;
-        
-        if nodes.is_empty() {
-            return Err(SchedulerError::NoAvailableNodes);
+        if !workload.spec.scheduling_gates.is_empty() {
+            return Err(SchedulerError::SchedulingGated {
+                workload_id: workload.spec.id.clone(),
+                gates: workload.spec.scheduling_gates.join(", "),
+            });
         }
         
-        // Select candidate nodes
-        let candidates = self.node_selector
-            .select_candidates(&workload)
-            .await;
-        
-        if candidates.is_empty() {
-            return Err(SchedulerError::NoSuitableNodes { 
-                workload_id: workload.spec.id.clone() 
+        let unmet = self.unmet_conditions(&workload, GatePhase::Placement).await;
+        if !unmet.is_empty() {
+            return Err(SchedulerError::ConditionsNotMet {
+                workload_id: workload.spec.id.clone(),
+                conditions: unmet.join(", "),
             });
         }
         
-        // Optimize placement
-        let selected_node = self.optimizer
-            .find_optimal_placement(&workload, candidates)
-            .await
-            .ok_or_else(|| SchedulerError::NoSuitableNodes { 
-                workload_id: workload.spec.id.clone() 
-            })?;
+        // A workload that fits on no node may make room by evicting
+        // lower-priority workloads from one
+        let preempted_node = self.preempt_for(&workload).await?;
         
-        // Execute placement - create PlacementDecision from NodeId
-        let placement_decision = placement::PlacementDecision {
-            node_id: Some(selected_node),
-            score: 1.0,
+        // Claim the chosen nodes before placing; nodes whose capacity went
+        // to another scheduler are left out of the next attempt
+        let mut contested = Vec::new();
+        let mut attempt = 0;
+        let placement_decision = loop {
+            attempt += 1;
+            let placement = match preempted_node {
+                Some(node_id) if attempt == 1 => PlacementDecision {
+                    node_id: Some(node_id),
+                    score: 1.0,
+                    replica_nodes: Vec::new(),
+                },
+                _ => self.plan_placement(&workload, &contested).await?,
+            };
+            match self.claim_placement(&workload, &placement).await {
+                Ok(()) => break placement,
+                Err(SchedulerError::PlacementConflict { message, .. }) if attempt < MAX_PLACEMENT_ATTEMPTS => {
+                    tracing::debug!("Placement of {} contested ({}), re-planning", workload.spec.id, message);
+                    contested.extend(placement.node_id);
+                    contested.extend(placement.replica_nodes);
+                }
+                Err(e) => return Err(e),
+            }
         };
+        let selected_node = placement_decision.node_id.unwrap_or_else(|| NodeId::random());
+        let claimed = claims::replicas_per_node(selected_node, &placement_decision.replica_nodes, workload.spec.replicas);
         
-        let result = self.execute_placement(&workload, placement_decision).await?;
+        // The shadow strategy sees the cluster as it was before this placement
+        let shadow_view = if self.shadow.is_enabled() {
+            let nodes = self.get_available_nodes().await.unwrap_or_default();
+            Some(self.node_headroom(&nodes).await)
+        } else {
+            None
+        };
+        
+        let result = match self.execute_placement(&workload, placement_decision).await {
+            Ok(result) => result,
+            Err(e) => {
+                self.release_claims(&workload.spec.id, claimed.iter().map(|(node_id, _)| *node_id)).await;
+                return Err(e);
+            }
+        };
         
         // Update predictions  
         self.predictor
@@ -217,6 +384,10 @@ impl Scheduler {
             .await
             .map_err(|e| SchedulerError::Prediction { message: e.to_string() })?;
         
+        if let Some(nodes) = shadow_view {
+            self.shadow.record(&workload, result.target_node, &nodes);
+        }
+        
         // Emit event
         let _ = self.scheduler_events.send(SchedulerEvent::WorkloadScheduled {
             workload_id: result.workload_id.clone(),
@@ -227,11 +398,423 @@ impl Scheduler {
         Ok(result)
     }
     
+    /// Choose where a workload would be placed, without claiming or placing it
+    ///
+    /// Runs the same validation and node selection as `schedule_workload`.
+    /// Scheduling gates, placement conditions and preemption are not
+    /// evaluated, so a workload that would be held or would evict others
+    /// reports only whether it fits on the cluster as it is now.
+    pub async fn simulate_placement(&self, workload: &Workload) -> Result<PlacementDecision> {
+        if !self.is_responsible_for(workload) {
+            return Err(SchedulerError::WrongScheduler {
+                workload_id: workload.spec.id.clone(),
+                scheduler_name: workload.spec.scheduler_name.clone(),
+            });
+        }
+        
+        self.validate_workload(workload).await?;
+        self.plan_placement(workload, &[]).await
+    }
+    
+    /// Submit a workload, holding it on scheduling gates or unmet placement conditions
+    pub async fn submit_workload(&self, workload: Workload) -> Result<SubmitOutcome> {
+        if !self.is_responsible_for(&workload) {
+            return Err(SchedulerError::WrongScheduler {
+                workload_id: workload.spec.id.clone(),
+                scheduler_name: workload.spec.scheduler_name.clone(),
+            });
+        }
+        
+        let unmet = self.unmet_conditions(&workload, GatePhase::Placement).await;
+        if workload.spec.scheduling_gates.is_empty() && unmet.is_empty() {
+            return self.schedule_workload(workload).await.map(SubmitOutcome::Scheduled);
+        }
+        
+        self.validate_workload(&workload).await?;
+        
+        let mut workload = workload;
+        if workload.metadata.generation == 0 {
+            workload.metadata = ApiMeta::created();
+        }
+        let blocking: Vec<String> = workload.spec.scheduling_gates.iter().chain(&unmet).cloned().collect();
+        workload.conditions.set(
+            condition_types::SCHEDULED,
+            ObjectConditionStatus::False,
+            "SchedulingGated",
+            format!("waiting on {}", blocking.join(", ")),
+            workload.metadata.generation,
+        );
+        workload.conditions.observe(workload.metadata.generation);
+        
+        let held = HeldWorkload::new(workload).with_conditions(unmet);
+        let workload_id = held.id().clone();
+        let gates = held.blocking();
+        
+        let mut held_workloads = self.held.write().await;
+        if held_workloads.contains_key(&workload_id) || self.workloads.read().await.contains_key(&workload_id) {
+            return Err(SchedulerError::InvalidWorkload {
+                message: format!("Workload {} already exists", workload_id),
+            });
+        }
+        held_workloads.insert(workload_id.clone(), held);
+        drop(held_workloads);
+        
+        tracing::info!("Holding workload {} until gates are removed: {}", workload_id, gates.join(", "));
+        let _ = self.scheduler_events.send(SchedulerEvent::WorkloadHeld {
+            workload_id,
+            gates: gates.clone(),
+        });
+        
+        Ok(SubmitOutcome::Held { gates })
+    }
+    
+    /// Remove one scheduling gate from a held workload
+    ///
+    /// Used by external controllers. When the last gate is removed the
+    /// workload is scheduled and the placement result returned.
+    pub async fn remove_scheduling_gate(
+        &self,
+        workload_id: &ResourceId,
+        gate: &str,
+        removed_by: &str,
+        resource_version: Option<u64>,
+    ) -> Result<Option<SchedulingResult>> {
+        let ready = {
+            let mut held_workloads = self.held.write().await;
+            let held = held_workloads.get_mut(workload_id)
+                .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() })?;
+            
+            let metadata = &held.workload.metadata;
+            if !metadata.matches(resource_version) {
+                return Err(version_conflict(format!("workload {}", workload_id), resource_version, metadata.resource_version));
+            }
+            if !held.release(gate, removed_by) {
+                return Err(SchedulerError::InvalidWorkload {
+                    message: format!("Workload {} has no scheduling gate '{}'", workload_id, gate),
+                });
+            }
+            held.workload.metadata.spec_changed();
+            
+            tracing::info!("Scheduling gate '{}' removed from {} by {}", gate, workload_id, removed_by);
+            let _ = self.scheduler_events.send(SchedulerEvent::SchedulingGateRemoved {
+                workload_id: workload_id.clone(),
+                gate: gate.to_string(),
+                removed_by: removed_by.to_string(),
+            });
+            
+            if held.is_ready() {
+                held_workloads.remove(workload_id)
+            } else {
+                None
+            }
+        };
+        
+        match ready {
+            Some(held) => self.schedule_released(held).await.map(Some),
+            None => Ok(None),
+        }
+    }
+    
+    /// Re-check readiness conditions
+    ///
+    /// Held workloads whose placement conditions have all become true are
+    /// scheduled and returned; running workloads whose ready conditions are
+    /// all true are marked ready.
+    pub async fn check_readiness_gates(&self) -> Vec<SchedulingResult> {
+        let waiting: Vec<HeldWorkload> = self.held.read().await
+            .values()
+            .filter(|held| !held.conditions.is_empty())
+            .cloned()
+            .collect();
+        
+        let mut released = Vec::new();
+        for candidate in waiting {
+            let unmet = self.unmet_conditions(&candidate.workload, GatePhase::Placement).await;
+            let workload_id = candidate.id().clone();
+            
+            let mut held_workloads = self.held.write().await;
+            let Some(held) = held_workloads.get_mut(&workload_id) else {
+                continue;
+            };
+            let met: Vec<String> = held.conditions.iter().filter(|c| !unmet.contains(c)).cloned().collect();
+            for condition in met {
+                held.condition_met(&condition);
+                tracing::info!("Readiness condition '{}' of {} is true", condition, workload_id);
+                let _ = self.scheduler_events.send(SchedulerEvent::SchedulingGateRemoved {
+                    workload_id: workload_id.clone(),
+                    gate: condition,
+                    removed_by: gates::CONDITION_CONTROLLER.to_string(),
+                });
+            }
+            if held.is_ready() {
+                released.extend(held_workloads.remove(&workload_id));
+            }
+        }
+        
+        let mut results = Vec::new();
+        for held in released {
+            let workload_id = held.id().clone();
+            match self.schedule_released(held).await {
+                Ok(result) => results.push(result),
+                Err(e) => tracing::warn!("Released workload {} failed to schedule and was queued: {}", workload_id, e),
+            }
+        }
+        
+        let not_ready: Vec<Workload> = self.workloads.read().await
+            .values()
+            .filter(|scheduled| !scheduled.ready)
+            .map(|scheduled| scheduled.workload.clone())
+            .collect();
+        let controllers = self.condition_controllers.read().await.clone();
+        for workload in not_ready {
+            let conditions = controllers.evaluate(&workload, GatePhase::Ready).await;
+            let ready = readiness::unmet(&conditions).is_empty();
+            
+            if let Some(scheduled) = self.workloads.write().await.get_mut(&workload.spec.id) {
+                let unmet = readiness::unmet(&conditions);
+                let generation = scheduled.workload.metadata.generation;
+                let (status, reason) = if ready {
+                    (ObjectConditionStatus::True, "ConditionsMet")
+                } else {
+                    (ObjectConditionStatus::False, "ConditionsNotMet")
+                };
+                if scheduled.workload.conditions.set(condition_types::READY, status, reason, unmet.join(", "), generation) {
+                    scheduled.workload.metadata.status_changed();
+                }
+                scheduled.conditions = conditions;
+                scheduled.ready = ready;
+            }
+            if ready {
+                tracing::info!("Workload {} is ready", workload.spec.id);
+                let _ = self.scheduler_events.send(SchedulerEvent::WorkloadReady {
+                    workload_id: workload.spec.id.clone(),
+                });
+            }
+        }
+        
+        results
+    }
+    
+    /// Whether a placed workload's ready conditions were all true when last checked
+    pub async fn is_workload_ready(&self, workload_id: &ResourceId) -> Result<bool> {
+        self.workloads.read().await
+            .get(workload_id)
+            .map(|scheduled| scheduled.ready)
+            .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() })
+    }
+    
+    /// Current copy of a placed or held workload, including its metadata and conditions
+    pub async fn get_workload(&self, workload_id: &ResourceId) -> Result<Workload> {
+        if let Some(scheduled) = self.workloads.read().await.get(workload_id) {
+            return Ok(scheduled.workload.clone());
+        }
+        self.held.read().await
+            .get(workload_id)
+            .map(|held| held.workload.clone())
+            .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() })
+    }
+    
+    /// Approve a workload held for manual approval
+    pub async fn approve_workload(
+        &self,
+        workload_id: &ResourceId,
+        approver: &str,
+        resource_version: Option<u64>,
+    ) -> Result<Option<SchedulingResult>> {
+        self.remove_scheduling_gate(workload_id, MANUAL_APPROVAL_GATE, approver, resource_version).await
+    }
+    
+    /// Reject a held workload, discarding it
+    pub async fn reject_workload(
+        &self,
+        workload_id: &ResourceId,
+        rejected_by: &str,
+        reason: &str,
+        resource_version: Option<u64>,
+    ) -> Result<()> {
+        let mut held_workloads = self.held.write().await;
+        let held = held_workloads.get(workload_id)
+            .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() })?;
+        if !held.workload.metadata.matches(resource_version) {
+            return Err(version_conflict(format!("workload {}", workload_id), resource_version, held.workload.metadata.resource_version));
+        }
+        held_workloads.remove(workload_id);
+        drop(held_workloads);
+        
+        tracing::info!("Held workload {} rejected by {}: {}", workload_id, rejected_by, reason);
+        let _ = self.scheduler_events.send(SchedulerEvent::WorkloadRejected {
+            workload_id: workload_id.clone(),
+            rejected_by: rejected_by.to_string(),
+            reason: reason.to_string(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Workloads waiting on scheduling gates
+    pub async fn held_workloads(&self) -> Vec<HeldWorkload> {
+        let mut held: Vec<_> = self.held.read().await.values().cloned().collect();
+        held.sort_by_key(|h| h.held_at);
+        held
+    }
+    
+    /// Place a group of workloads together or not at all
+    ///
+    /// Members are planned as one unit against the capacity left on Ready
+    /// nodes and the pod affinity rules between them. If any member cannot
+    /// be placed, none are, and the group is queued until
+    /// `retry_pending_groups` finds room for all of it.
+    pub async fn schedule_workload_group(&self, group: WorkloadGroup) -> Result<GroupOutcome> {
+        tracing::info!("Scheduling workload group {} ({} members)", group.name, group.members.len());
+        
+        let mut seen = std::collections::HashSet::new();
+        for member in &group.members {
+            if !self.is_responsible_for(member) {
+                return Err(SchedulerError::WrongScheduler {
+                    workload_id: member.spec.id.clone(),
+                    scheduler_name: member.spec.scheduler_name.clone(),
+                });
+            }
+            self.validate_workload(member).await?;
+            if !member.spec.scheduling_gates.is_empty() {
+                return Err(SchedulerError::SchedulingGated {
+                    workload_id: member.spec.id.clone(),
+                    gates: member.spec.scheduling_gates.join(", "),
+                });
+            }
+            if !seen.insert(member.spec.id.clone()) || self.workloads.read().await.contains_key(&member.spec.id) {
+                return Err(SchedulerError::InvalidWorkload {
+                    message: format!("Workload {} already exists", member.spec.id),
+                });
+            }
+        }
+        
+        match self.place_group(&group).await {
+            Ok(results) => Ok(GroupOutcome::Scheduled(results)),
+            Err(reason) => {
+                tracing::info!("Queueing workload group {}: {}", group.name, reason);
+                let _ = self.scheduler_events.send(SchedulerEvent::GroupQueued {
+                    group: group.name.clone(),
+                    reason: reason.clone(),
+                });
+                self.group_queue.write().await.push(PendingGroup {
+                    group,
+                    queued_at: SystemTime::now(),
+                    reason: reason.clone(),
+                });
+                Ok(GroupOutcome::Queued { reason })
+            }
+        }
+    }
+    
+    /// Try again to place queued groups, oldest first
+    ///
+    /// Returns the number of groups placed.
+    pub async fn retry_pending_groups(&self) -> usize {
+        let pending = std::mem::take(&mut *self.group_queue.write().await);
+        let mut placed = 0;
+        let mut still_pending = Vec::new();
+        
+        for mut entry in pending {
+            match self.place_group(&entry.group).await {
+                Ok(_) => placed += 1,
+                Err(reason) => {
+                    entry.reason = reason;
+                    still_pending.push(entry);
+                }
+            }
+        }
+        
+        // Groups queued while retrying go after the ones already waiting
+        let mut queue = self.group_queue.write().await;
+        still_pending.append(&mut queue);
+        *queue = still_pending;
+        
+        placed
+    }
+    
+    /// Try again to place queued workloads, highest priority first
+    ///
+    /// Returns the number of workloads placed. Workloads that can never be
+    /// placed as specified are dropped from the queue.
+    pub async fn retry_pending_workloads(&self) -> usize {
+        let mut pending = std::mem::take(&mut *self.placement_queue.write().await);
+        pending.sort_by_key(|entry| std::cmp::Reverse(entry.priority));
+        let mut placed = 0;
+        let mut still_pending = Vec::new();
+        
+        for entry in pending {
+            match self.schedule_workload(entry.workload.clone()).await {
+                Ok(_) => placed += 1,
+                Err(e) if e.is_configuration_error() => {
+                    tracing::warn!("Dropping queued workload {}: {}", entry.workload.spec.id, e);
+                }
+                Err(e) => {
+                    tracing::debug!("Queued workload {} still does not fit: {}", entry.workload.spec.id, e);
+                    still_pending.push(entry);
+                }
+            }
+        }
+        
+        // Workloads queued while retrying go after the ones already waiting
+        // at the same priority; the sort is stable
+        let mut queue = self.placement_queue.write().await;
+        still_pending.append(&mut queue);
+        still_pending.sort_by_key(|entry| std::cmp::Reverse(entry.priority));
+        *queue = still_pending;
+        
+        placed
+    }
+    
+    /// Workloads waiting for a later placement pass
+    pub async fn pending_workloads(&self) -> Vec<PendingWorkload> {
+        self.placement_queue.read().await.clone()
+    }
+    
+    /// Groups waiting for room to place all their members
+    pub async fn pending_groups(&self) -> Vec<PendingGroup> {
+        self.group_queue.read().await.clone()
+    }
+    
+    /// Snapshot a workload's volumes and record each as its volume's latest
+    pub async fn snapshot_workload_volumes(&self, workload_id: &ResourceId) -> Result<Vec<VolumeSnapshot>> {
+        let workload = self.workloads.read().await
+            .get(workload_id)
+            .map(|scheduled| scheduled.workload.clone())
+            .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() })?;
+        let Some(runtime) = &self.runtime else {
+            return Err(SchedulerError::RuntimeError { message: "No runtime to snapshot volumes with".to_string() });
+        };
+        
+        let mut snapshots = Vec::with_capacity(workload.spec.volumes.len());
+        for volume in &workload.spec.volumes {
+            let snapshot = runtime.snapshot_volume(&volume.name).await
+                .map_err(|e| SchedulerError::RuntimeError { message: e.to_string() })?;
+            self.store_latest_snapshot(&snapshot).await?;
+            snapshots.push(snapshot);
+        }
+        Ok(snapshots)
+    }
+    
+    /// The latest recorded snapshot of a volume
+    pub async fn latest_volume_snapshot(&self, volume: &str) -> Result<Option<VolumeSnapshot>> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(None);
+        };
+        
+        let stored = state_manager.get(&volumes::latest_snapshot_key(volume)).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })?;
+        
+        stored
+            .map(|bytes| serde_json::from_slice(&bytes))
+            .transpose()
+            .map_err(SchedulerError::from)
+    }
+    
     /// Reschedule workloads (for load rebalancing)
     pub async fn reschedule_workloads(&self, strategy: ReschedulingStrategy) -> Result<Vec<ReschedulingResult>> {
         tracing::info!("Rescheduling workloads with strategy: {:?}", strategy);
         
-        let workloads = self.workloads.read().await;
         let mut results = Vec::new();
         
         match strategy {
@@ -260,22 +843,83 @@ impl Scheduler {
         Ok(results)
     }
     
+    /// Set a workload's scaling policy, replacing any existing one
+    pub fn set_scaling_policy(&self, policy: ScalingPolicy) -> Result<()> {
+        let report = policy.validate_config();
+        for warning in report.warnings() {
+            tracing::warn!("Scaling policy for {} {}", policy.resource_id, warning);
+        }
+        if !report.is_valid() {
+            return Err(SchedulerError::Configuration { message: report.error_summary() });
+        }
+        self.autoscaler.set_policy(policy);
+        Ok(())
+    }
+    
+    /// Record a workload's mean CPU utilization across its replicas
+    ///
+    /// Utilization is the share of requested CPU in use, and feeds both
+    /// metric-based scaling and the predictor.
+    pub async fn record_utilization(&self, resource_id: &ResourceId, cpu_utilization: f32) -> Result<()> {
+        let workloads = self.workloads.read().await;
+        let scheduled = workloads
+            .get(resource_id)
+            .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: resource_id.clone() })?;
+        let spec = &scheduled.workload.spec;
+        let cores = cpu_utilization as f64 * spec.resources.cpu_cores * spec.replicas as f64;
+        self.predictor.record_workload_load(resource_id, cores, SystemTime::now());
+        Ok(())
+    }
+    
     /// Trigger autoscaling
     pub async fn check_autoscaling(&self) -> Result<Vec<ScalingDecision>> {
-        let workloads = self.workloads.read().await.clone();
-        let nodes = self.nodes.read().await.clone();
-        
-        // Get current resource usage
-        let resource_usage = self.resource_monitor
-            .get_cluster_usage()
-            .await;
+        // Without a recent utilization sample metric-based scaling holds the
+        // current count, and only schedules and request activity move it
+        let activator = self.network_manager.as_ref().map(|network| network.activator());
+        let scheduled: Vec<Workload> = self.workloads.read().await
+            .values()
+            .map(|scheduled| scheduled.workload.clone())
+            .collect();
         
-        // Skip predictions for now since we don't have a specific workload context
-        // let predictions = self.predictor.predict_demand(&some_workload).await;
+        let mut observations = Vec::with_capacity(scheduled.len());
+        for workload in &scheduled {
+            let spec = &workload.spec;
+            let service_id = Self::service_id(spec);
+            let (idle_for, activation_requested) = activator.as_ref().map_or((None, false), |activator| {
+                (activator.idle_for(&service_id), activator.pending_activations().contains(&service_id))
+            });
+            // Utilization is the recorded load over what the current replicas request
+            let requested = spec.resources.cpu_cores * spec.replicas as f64;
+            let utilization = |cores: f64| (requested > 0.0).then(|| (cores / requested) as f32);
+            let cpu_utilization = self.predictor
+                .latest_load(&spec.id)
+                .and_then(|sample| utilization(sample.demand.cpu_cores));
+            
+            let horizon = self.autoscaler.policy(&workload.id).and_then(|policy| policy.predictive).map(|p| p.horizon);
+            let forecast = match horizon {
+                Some(horizon) if self.config.prediction.enabled => {
+                    let prediction = self.predictor.predict_demand(workload, horizon).await;
+                    utilization(prediction.demand.cpu).map(|cpu_utilization| DemandForecast {
+                        cpu_utilization,
+                        confidence: prediction.confidence,
+                    })
+                }
+                _ => None,
+            };
+            
+            observations.push(ScalingObservation {
+                resource_id: workload.id.clone(),
+                current_replicas: spec.replicas,
+                cpu_utilization,
+                idle_for,
+                activation_requested,
+                forecast,
+            });
+        }
         
         // Make scaling decisions
         let decisions = self.autoscaler
-            .make_scaling_decisions()
+            .make_scaling_decisions(&observations)
             .await;
         
         // Execute scaling decisions
@@ -289,6 +933,33 @@ impl Scheduler {
         Ok(executed_decisions)
     }
     
+    /// Run autoscaling whenever the mesh asks to activate a service scaled to zero
+    ///
+    /// Returns `None` without a network manager.
+    pub fn spawn_activation_handler(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let mut requests = self.network_manager.as_ref()?.activator().subscribe();
+        let scheduler = self.clone();
+        Some(tokio::spawn(async move {
+            loop {
+                match requests.recv().await {
+                    Ok(request) => {
+                        tracing::info!("Activation requested for {}", request.service_id);
+                        if let Err(e) = scheduler.check_autoscaling().await {
+                            tracing::warn!("Autoscaling for activation of {} failed: {}", request.service_id, e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }))
+    }
+    
+    /// Mesh service a workload is reached through
+    fn service_id(spec: &WorkloadSpec) -> ServiceId {
+        ServiceId::new(spec.name.clone(), spec.id.namespace())
+    }
+    
     /// Add a node to the cluster
     pub async fn add_node(&self, node: ClusterNode) -> Result<()> {
         tracing::info!("Adding node to cluster: {}", node.node_id);
@@ -300,6 +971,17 @@ impl Scheduler {
             });
         }
         
+        // Labels changed at runtime take precedence over registration labels
+        let mut node = node;
+        if let Some(metadata) = self.load_node_metadata(&node.node_id).await? {
+            node.labels.extend(metadata.labels);
+            node.annotations.extend(metadata.annotations);
+        }
+        if node.metadata.generation == 0 {
+            node.metadata = ApiMeta::created();
+        }
+        node.refresh_conditions();
+        
         // Store node
         self.nodes.write().await.insert(node.node_id, node.clone());
         
@@ -316,17 +998,41 @@ impl Scheduler {
         Ok(())
     }
     
+    /// Set a node's status, returning the one it replaces
+    ///
+    /// Cordoning a node keeps new workloads off it; setting it Ready again
+    /// returns it to placement.
+    pub async fn set_node_status(&self, node_id: NodeId, status: NodeStatus) -> Result<NodeStatus> {
+        let mut nodes = self.nodes.write().await;
+        let node = nodes.get_mut(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+        let previous = std::mem::replace(&mut node.status, status);
+        if node.status != previous {
+            node.metadata.spec_changed();
+            node.refresh_conditions();
+        }
+        Ok(previous)
+    }
+
     /// Remove a node from the cluster
     pub async fn remove_node(&self, node_id: NodeId, drain: bool) -> Result<()> {
         tracing::info!("Removing node from cluster: {} (drain={})", node_id, drain);
         
         if drain {
-            // Move all workloads from this node
-            self.drain_node(node_id).await?;
+            // Move all workloads from this node; refuse to remove it while
+            // any are left behind
+            let results = self.run_drain(node_id).await?;
+            let remaining = results
+                .iter()
+                .filter(|r| matches!(r.outcome, ReschedulingOutcome::Failed { .. }))
+                .count();
+            if remaining > 0 {
+                return Err(SchedulerError::DrainIncomplete { node_id, remaining });
+            }
         }
         
         // Remove from nodes
         self.nodes.write().await.remove(&node_id);
+        self.heartbeats.write().await.forget(&node_id);
         
         // Stop monitoring this node
         self.resource_monitor.remove_node(node_id).await
@@ -338,49 +1044,727 @@ impl Scheduler {
         Ok(())
     }
     
-    /// Get scheduler statistics
-    pub async fn stats(&self) -> SchedulerStats {
-        let nodes = self.nodes.read().await;
-        let workloads = self.workloads.read().await;
-        let queue = self.placement_queue.read().await;
+    /// Update a node's labels and annotations at runtime
+    ///
+    /// The result is persisted in the state store before it becomes visible
+    /// to placement, so a failed write leaves the node unchanged. The write
+    /// is conditional on the stored revision the update was computed from,
+    /// and on `update.resource_version` when the caller sets it; a concurrent
+    /// change makes it fail with `ResourceVersionConflict` instead of being
+    /// overwritten.
+    pub async fn update_node_metadata(&self, node_id: NodeId, update: &NodeMetadataUpdate) -> Result<NodeMetadata> {
+        let stored_revision = self.node_metadata_revision(&node_id).await?;
+        let (current, resource_version) = {
+            let nodes = self.nodes.read().await;
+            let node = nodes.get(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+            if !node.metadata.matches(update.resource_version) {
+                return Err(version_conflict(format!("node {}", node_id), update.resource_version, node.metadata.resource_version));
+            }
+            let current = NodeMetadata {
+                labels: node.labels.clone(),
+                annotations: node.annotations.clone(),
+                resource_version: node.metadata.resource_version,
+            };
+            (current, node.metadata.resource_version)
+        };
         
-        SchedulerStats {
-            node_count: nodes.len(),
-            workload_count: workloads.len(),
-            pending_placements: queue.len(),
-            placement_stats: self.placement_engine.stats().await,
-            autoscaling_stats: self.autoscaler.stats().await,
-            prediction_stats: self.predictor.stats().await,
+        let mut updated = update.apply(&current)?;
+        if updated == current {
+            return Ok(updated);
+        }
+        
+        if !self.store_node_metadata(&node_id, stored_revision, &updated).await? {
+            // Another scheduler wrote first; adopt its metadata so the next read is current
+            let latest = self.load_node_metadata(&node_id).await?;
+            let mut nodes = self.nodes.write().await;
+            let node = nodes.get_mut(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+            if let Some(latest) = latest {
+                node.labels = latest.labels;
+                node.annotations = latest.annotations;
+                node.metadata.spec_changed();
+                node.refresh_conditions();
+            }
+            return Err(version_conflict(format!("node {}", node_id), Some(resource_version), node.metadata.resource_version));
+        }
+        
+        {
+            let mut nodes = self.nodes.write().await;
+            let node = nodes.get_mut(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+            if node.labels != current.labels || node.annotations != current.annotations {
+                return Err(version_conflict(format!("node {}", node_id), Some(resource_version), node.metadata.resource_version));
+            }
+            node.labels = updated.labels.clone();
+            node.annotations = updated.annotations.clone();
+            node.metadata.spec_changed();
+            node.refresh_conditions();
+            updated.resource_version = node.metadata.resource_version;
         }
+        
+        tracing::info!("Updated metadata for node {}: {} labels, {} annotations",
+            node_id, updated.labels.len(), updated.annotations.len());
+        
+        let _ = self.scheduler_events.send(SchedulerEvent::NodeMetadataUpdated {
+            node_id,
+            labels: updated.labels.clone(),
+        });
+        
+        Ok(updated)
     }
     
-    /// Subscribe to scheduler events
-    pub fn subscribe(&self) -> broadcast::Receiver<SchedulerEvent> {
-        self.scheduler_events.subscribe()
+    /// Resolve a node by hex ID or by its `nexus.io/hostname` label
+    pub async fn resolve_node(&self, name: &str) -> Option<NodeId> {
+        let nodes = self.nodes.read().await;
+        if let Ok(node_id) = NodeId::from_hex(name) {
+            if nodes.contains_key(&node_id) {
+                return Some(node_id);
+            }
+        }
+        nodes
+            .values()
+            .find(|node| node.labels.get(node_metadata::HOSTNAME_LABEL).map(String::as_str) == Some(name))
+            .map(|node| node.node_id)
     }
     
-    // Private helper methods
+    /// Get a node's current labels and annotations
+    pub async fn node_metadata(&self, node_id: NodeId) -> Result<NodeMetadata> {
+        let nodes = self.nodes.read().await;
+        let node = nodes.get(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+        Ok(NodeMetadata {
+            labels: node.labels.clone(),
+            annotations: node.annotations.clone(),
+            resource_version: node.metadata.resource_version,
+        })
+    }
     
-    async fn validate_workload(&self, workload: &Workload) -> Result<()> {
-        // Validate resource requirements
-        if workload.spec.resources.cpu_cores == 0.0 {
-            return Err(SchedulerError::InvalidWorkload { 
-                message: "CPU cores must be greater than 0".to_string() 
-            });
+    /// Apply a heartbeat from a node's agent
+    ///
+    /// Any heartbeat marks a NotReady or Unknown node Ready again. A delta
+    /// the scheduler cannot apply is answered with `resync`, and the agent
+    /// follows up with a full report.
+    pub async fn receive_heartbeat(&self, heartbeat: &Heartbeat) -> Result<HeartbeatAck> {
+        let node_id = heartbeat.node_id;
+        let mut nodes = self.nodes.write().await;
+        let node = nodes.get_mut(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+        node.last_heartbeat = SystemTime::now();
+        if matches!(node.status, NodeStatus::NotReady | NodeStatus::Unknown) {
+            tracing::info!("Node {} is sending heartbeats again", node_id);
+            node.status = NodeStatus::Ready;
         }
+        node.metadata.status_changed();
+        node.refresh_conditions();
         
-        if workload.spec.resources.memory_mb == 0 {
-            return Err(SchedulerError::InvalidWorkload { 
-                message: "Memory must be greater than 0".to_string() 
+        let mut heartbeats = self.heartbeats.write().await;
+        let Some(report) = heartbeats.receive(heartbeat) else {
+            return Ok(HeartbeatAck { sequence: heartbeat.sequence, resync: true });
+        };
+        node.resources = NodeResources { node_id: Some(node_id), ..report.resources.clone() };
+        if node.capabilities != report.capabilities {
+            node.capabilities = report.capabilities.clone();
+            let _ = self.scheduler_events.send(SchedulerEvent::NodeCapabilitiesUpdated {
+                node_id,
+                capabilities: node.capabilities.clone(),
             });
         }
-        
-        Ok(())
+        Ok(HeartbeatAck { sequence: heartbeat.sequence, resync: false })
     }
     
-    async fn get_available_nodes(&self) -> Result<Vec<ClusterNode>> {
-        let nodes = self.nodes.read().await;
+    /// Mark Ready nodes whose heartbeats stopped NotReady, returning them
+    ///
+    /// Only nodes that have sent a heartbeat are checked; nodes without an
+    /// agent keep their status.
+    pub async fn check_heartbeats(&self) -> Vec<NodeId> {
+        let timeout = self.config.heartbeat.interval * self.config.heartbeat.missed_before_not_ready;
+        let heartbeats = self.heartbeats.read().await;
+        let mut nodes = self.nodes.write().await;
+        let mut lapsed = Vec::new();
+        for node in nodes.values_mut() {
+            let silent_for = node.last_heartbeat.elapsed().unwrap_or_default();
+            if node.status == NodeStatus::Ready && heartbeats.tracks(&node.node_id) && silent_for > timeout {
+                tracing::warn!("No heartbeat from node {} for {:?}; marking it NotReady", node.node_id, silent_for);
+                node.status = NodeStatus::NotReady;
+                node.metadata.status_changed();
+                node.refresh_conditions();
+                let _ = self.scheduler_events.send(SchedulerEvent::NodeHeartbeatMissed { node_id: node.node_id });
+                lapsed.push(node.node_id);
+            }
+        }
+        lapsed
+    }
+    
+    /// Signed decision behind a workload's current placement
+    ///
+    /// Falls back to the state store for workloads placed by another
+    /// scheduler. The decision is returned only if it is signed by this
+    /// scheduler or one listed in `trusted_schedulers`.
+    pub async fn placement_decision(&self, workload_id: &ResourceId) -> Result<SignedDecision> {
+        let local = self.workloads.read().await
+            .get(workload_id)
+            .map(|scheduled| scheduled.decision.clone());
+        
+        let decision = match local {
+            Some(decision) => decision,
+            None => self.load_decision(workload_id).await?
+                .ok_or_else(|| SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() })?,
+        };
+        
+        let trusted = std::iter::once(&self.node_id)
+            .chain(&self.config.trusted_schedulers)
+            .find(|scheduler| **scheduler == decision.scheduler)
+            .ok_or_else(|| SchedulerError::InvalidSignature {
+                message: format!(
+                    "placement of {} signed by untrusted scheduler {}",
+                    workload_id, decision.scheduler
+                ),
+            })?;
+        decision.verify_from(trusted)?;
+        Ok(decision)
+    }
+    
+    /// Compare the shadow strategy with the active one, if shadow mode is on
+    pub fn shadow_report(&self) -> Option<ShadowReport> {
+        self.shadow
+            .is_enabled()
+            .then(|| self.shadow.report(&self.config.placement.strategy))
+    }
+    
+    /// Decisions recorded by the shadow strategy, oldest first
+    pub fn shadow_decisions(&self) -> Vec<ShadowDecision> {
+        self.shadow.decisions()
+    }
+    
+    /// Forecast when a cluster's capacity runs out at the current growth rate
+    ///
+    /// Only ready nodes labelled with `cluster` count toward capacity, and
+    /// only replicas placed on them toward demand.
+    pub async fn capacity_forecast(&self, cluster: &str, horizon: Duration) -> CapacityForecast {
+        const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+        
+        let (capacity, node_count) = {
+            let nodes = self.nodes.read().await;
+            let ready: Vec<_> = nodes.values()
+                .filter(|n| n.status == NodeStatus::Ready && node_cluster(n) == Some(cluster))
+                .collect();
+            let capacity = ResourceTotals {
+                cpu_cores: ready.iter().map(|n| n.resources.cpu_total).sum(),
+                memory_mb: ready.iter().map(|n| n.resources.memory_total as f64 / BYTES_PER_MB).sum(),
+                gpus: ready.iter().map(|n| n.resources.gpu_total as f64).sum(),
+            };
+            (capacity, ready.len())
+        };
         
+        let current = self.committed_demand().await.remove(cluster).unwrap_or_default();
+        self.predictor.forecast_capacity(cluster, capacity, current, node_count, horizon)
+    }
+    
+    /// Record every cluster's committed demand with the predictor
+    async fn sample_demand(&self) {
+        for (cluster, demand) in self.committed_demand().await {
+            self.predictor.record_demand(&cluster, demand);
+        }
+    }
+    
+    /// Resources committed to scheduled workloads, by the cluster of the node
+    /// each replica runs on; every labelled cluster appears, even when idle
+    async fn committed_demand(&self) -> HashMap<String, ResourceTotals> {
+        let nodes = self.nodes.read().await;
+        let workloads = self.workloads.read().await;
+        
+        let mut demand: HashMap<String, ResourceTotals> = nodes.values()
+            .filter_map(node_cluster)
+            .map(|cluster| (cluster.to_string(), ResourceTotals::default()))
+            .collect();
+        for scheduled in workloads.values() {
+            let spec = &scheduled.workload.spec;
+            for (node_id, replicas) in claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, spec.replicas) {
+                let Some(cluster) = nodes.get(&node_id).and_then(node_cluster) else {
+                    continue;
+                };
+                let total = demand.entry(cluster.to_string()).or_default();
+                let replicas = replicas as f64;
+                total.cpu_cores += spec.resources.cpu_cores * replicas;
+                total.memory_mb += spec.resources.memory_mb as f64 * replicas;
+                total.gpus += spec.gpus as f64 * replicas;
+            }
+        }
+        demand
+    }
+    
+    /// Get scheduler statistics
+    pub async fn stats(&self) -> SchedulerStats {
+        let nodes = self.nodes.read().await;
+        let workloads = self.workloads.read().await;
+        let queue = self.placement_queue.read().await;
+        
+        SchedulerStats {
+            node_count: nodes.len(),
+            workload_count: workloads.len(),
+            pending_placements: queue.len(),
+            held_workloads: self.held.read().await.len(),
+            pending_groups: self.group_queue.read().await.len(),
+            placement_stats: self.placement_engine.stats().await,
+            autoscaling_stats: self.autoscaler.stats().await,
+            prediction_stats: self.predictor.stats().await,
+            heartbeat_stats: self.heartbeats.read().await.stats(),
+        }
+    }
+    
+    /// Subscribe to scheduler events
+    pub fn subscribe(&self) -> broadcast::Receiver<SchedulerEvent> {
+        self.scheduler_events.subscribe()
+    }
+    
+    // Private helper methods
+    
+    async fn validate_workload(&self, workload: &Workload) -> Result<()> {
+        // Validate resource requirements
+        if workload.spec.resources.cpu_cores == 0.0 {
+            return Err(SchedulerError::InvalidWorkload { 
+                message: "CPU cores must be greater than 0".to_string() 
+            });
+        }
+        
+        if workload.spec.resources.memory_mb == 0 {
+            return Err(SchedulerError::InvalidWorkload { 
+                message: "Memory must be greater than 0".to_string() 
+            });
+        }
+        
+        Ok(())
+    }
+    
+    /// Schedule a workload whose gates are all cleared
+    async fn schedule_released(&self, held: HeldWorkload) -> Result<SchedulingResult> {
+        let workload = held.into_workload();
+        match self.schedule_workload(workload.clone()).await {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                // The gates are cleared, so keep it as an ordinary pending
+                // placement for the next retry pass rather than losing it
+                if !e.is_configuration_error() {
+                    self.enqueue_pending(workload).await;
+                }
+                Err(e)
+            }
+        }
+    }
+    
+    /// Queue a workload for a later placement pass
+    ///
+    /// The queue is kept highest priority first, in submission order
+    /// within a priority.
+    async fn enqueue_pending(&self, workload: Workload) {
+        let priority = self.config.preemption.priority_of(&workload);
+        let mut queue = self.placement_queue.write().await;
+        let position = queue
+            .iter()
+            .position(|pending| pending.priority < priority)
+            .unwrap_or(queue.len());
+        queue.insert(position, PendingWorkload {
+            priority,
+            workload,
+            submitted_at: SystemTime::now(),
+        });
+    }
+    
+    /// Evict lower-priority workloads when a workload fits on no Ready node
+    ///
+    /// Victims go through the drain path: each is re-placed elsewhere if
+    /// possible and evicted otherwise. Returns the node room was made on, or
+    /// `None` when the workload already fits or preemption cannot help.
+    async fn preempt_for(&self, workload: &Workload) -> Result<Option<NodeId>> {
+        let classes = &self.config.preemption;
+        if !classes.may_preempt(workload) {
+            return Ok(None);
+        }
+        
+        let mut nodes = self.get_available_nodes().await?;
+        nodes.retain(|node| workload.spec.affinity.matches_node(&node.labels));
+        let headroom = self.node_headroom(&nodes).await;
+        let demand = preemption::workload_demand(workload);
+        if headroom.is_empty() || headroom.iter().any(|node| node.fits(&demand)) {
+            return Ok(None);
+        }
+        
+        let (candidates, allowances) = {
+            let workloads = self.workloads.read().await;
+            let mut candidates = Vec::new();
+            let running = workloads
+                .values()
+                .filter(|s| s.status == WorkloadStatus::Running && s.workload.spec.id != workload.spec.id);
+            for scheduled in running {
+                let spec = &scheduled.workload.spec;
+                let per_node = claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, spec.replicas);
+                for (node_id, count) in per_node {
+                    candidates.push(preemption::PreemptionCandidate {
+                        workload_id: spec.id.clone(),
+                        node_id,
+                        priority: classes.priority_of(&scheduled.workload),
+                        demand: ResourceTotals {
+                            cpu_cores: spec.resources.cpu_cores * count as f64,
+                            memory_mb: spec.resources.memory_mb as f64 * count as f64,
+                            gpus: spec.gpus as f64 * count as f64,
+                        },
+                        labels: spec.labels.clone(),
+                    });
+                }
+            }
+            let unavailable = workloads
+                .values()
+                .filter(|s| s.status != WorkloadStatus::Running)
+                .map(|s| &s.workload.spec.labels);
+            (candidates, classes.allowances(unavailable))
+        };
+        
+        let priority = classes.priority_of(workload);
+        let Some(plan) = self.placement_engine.select_victims(
+            priority,
+            &demand,
+            &headroom,
+            &candidates,
+            &classes.budgets,
+            &allowances,
+        ) else {
+            return Ok(None);
+        };
+        
+        tracing::info!(
+            "Preempting {} workload(s) on node {} for {}",
+            plan.victims.len(), plan.node_id, workload.spec.id
+        );
+        let deadline = tokio::time::Instant::now() + self.config.drain.deadline;
+        for victim_id in &plan.victims {
+            let Some(victim) = self.workloads.read().await.get(victim_id).cloned() else {
+                continue;
+            };
+            let reason = format!("preempted by {}", workload.spec.id);
+            let result = self.migrate_workload(victim, plan.node_id, reason, deadline, true, None).await;
+            let _ = self.scheduler_events.send(SchedulerEvent::WorkloadPreempted {
+                workload_id: victim_id.clone(),
+                preempted_by: workload.spec.id.clone(),
+                node_id: plan.node_id,
+                outcome: result.outcome,
+            });
+        }
+        
+        Ok(Some(plan.node_id))
+    }
+    
+    /// Names of the workload's conditions for a phase that are not yet true
+    async fn unmet_conditions(&self, workload: &Workload, phase: GatePhase) -> Vec<String> {
+        if !workload.spec.readiness_gates.iter().any(|gate| gate.phase == phase) {
+            return Vec::new();
+        }
+        // Controllers may probe the network, so check outside the lock
+        let controllers = self.condition_controllers.read().await.clone();
+        readiness::unmet(&controllers.evaluate(workload, phase).await)
+    }
+    
+    /// Choose nodes for a workload among the Ready nodes without placing it
+    async fn plan_placement(&self, workload: &Workload, excluded: &[NodeId]) -> Result<PlacementDecision> {
+        // Apply scheduling policies
+        let _policy_check = self.policy_engine
+            .apply_policies(workload)
+            .await
+            .map_err(|e| SchedulerError::PolicyViolation { message: e.to_string() })?;
+        
+        // Get available nodes
+        let mut nodes = self.get_available_nodes().await?;
+        nodes.retain(|node| !excluded.contains(&node.node_id));
+        
+        if nodes.is_empty() {
+            return Err(SchedulerError::NoAvailableNodes);
+        }
+        
+        // Node affinity is checked against the labels as they are now, so
+        // runtime label changes apply to the next placement
+        let eligible: Vec<NodeId> = nodes
+            .iter()
+            .filter(|node| workload.spec.affinity.matches_node(&node.labels))
+            .map(|node| node.node_id)
+            .collect();
+        
+        if eligible.is_empty() {
+            return Err(SchedulerError::NodeSelectorNotMatched {
+                selector: format!("{:?}", workload.spec.affinity.node_affinity),
+            });
+        }
+        
+        // Volume claims have to fit in the node's storage classes now, not
+        // at container creation after the workload is already placed
+        let placed_volumes = self.placed_volumes(&workload.spec.id).await;
+        let mut storage_claims = workload.spec.volumes.clone();
+        storage_claims.extend(volumes::scratch_claims(&workload.spec.id, &workload.spec.ephemeral_volumes, 1));
+        let mut shortfall = None;
+        let eligible: Vec<NodeId> = nodes
+            .iter()
+            .filter(|node| eligible.contains(&node.node_id))
+            .filter(|node| {
+                let missing = volumes::storage_shortfall(
+                    &storage_claims,
+                    placed_volumes.get(&node.node_id),
+                    &node.resources.storage,
+                );
+                let fits = missing.is_none();
+                shortfall = shortfall.take().or(missing);
+                fits
+            })
+            .map(|node| node.node_id)
+            .collect();
+        
+        if let (true, Some(shortfall)) = (eligible.is_empty(), shortfall) {
+            return Err(SchedulerError::InsufficientResources {
+                required: format!("{} bytes of {} storage", shortfall.required_bytes, shortfall.storage_class),
+                available: format!("{} bytes", shortfall.free_bytes),
+            });
+        }
+        
+        // Candidates are the eligible nodes with room left for a replica
+        let eligible_nodes: Vec<ClusterNode> = nodes
+            .iter()
+            .filter(|node| eligible.contains(&node.node_id))
+            .cloned()
+            .collect();
+        let headroom = self.node_headroom(&eligible_nodes).await;
+        let spec = &workload.spec;
+        let replica_demand = ResourceTotals {
+            cpu_cores: spec.resources.cpu_cores,
+            memory_mb: spec.resources.memory_mb as f64,
+            gpus: spec.gpus as f64,
+        };
+        let candidates: Vec<NodeId> = headroom
+            .iter()
+            .filter(|node| node.fits(&replica_demand))
+            .map(|node| node.node_id)
+            .collect();
+        
+        if candidates.is_empty() {
+            return Err(SchedulerError::NoSuitableNodes { 
+                workload_id: workload.spec.id.clone() 
+            });
+        }
+        
+        // Consensus-critical workloads place each replica across trust
+        // domains; workloads that talk to placed peers go where expected
+        // latency to them is lowest; everything else goes where the placement
+        // strategy puts all of its replicas
+        let domain_candidates: Vec<diversity::DomainCandidate> = nodes
+            .iter()
+            .filter(|node| candidates.contains(&node.node_id))
+            .map(|node| diversity::DomainCandidate {
+                node_id: node.node_id,
+                domain: diversity::trust_domain(&node.labels).to_string(),
+                score: 1.0,
+            })
+            .collect();
+        
+        let replica_nodes = match self.optimizer.plan_replicas(workload, &domain_candidates) {
+            Some(plan) => plan?,
+            None => Vec::new(),
+        };
+        
+        let selected_node = match replica_nodes.first() {
+            Some(node_id) => *node_id,
+            None => match self.network_preferred_node(workload, &nodes, &candidates).await {
+                Some(node_id) => node_id,
+                None => self.placement_engine
+                    .select_node(&preemption::workload_demand(workload), &headroom)
+                    .ok_or_else(|| SchedulerError::NoSuitableNodes { 
+                        workload_id: workload.spec.id.clone() 
+                    })?,
+            },
+        };
+        
+        Ok(placement::PlacementDecision {
+            node_id: Some(selected_node),
+            score: 1.0,
+            replica_nodes,
+        })
+    }
+    
+    /// Candidate with the lowest expected latency to the workloads this one talks to
+    async fn network_preferred_node(&self, workload: &Workload, nodes: &[ClusterNode], candidates: &[NodeId]) -> Option<NodeId> {
+        let config = self.optimizer.network_config();
+        if !config.enabled {
+            return None;
+        }
+        
+        let peers: Vec<network_cost::PeerPlacement> = {
+            let workloads = self.workloads.read().await;
+            let mut graph = DependencyGraph::new();
+            graph.add_workload(&workload.spec.name, &workload.spec.labels);
+            for scheduled in workloads.values() {
+                graph.add_workload(&scheduled.workload.spec.name, &scheduled.workload.spec.labels);
+            }
+            
+            graph.peers(&workload.spec.name)
+                .into_iter()
+                .filter_map(|(peer, weight)| {
+                    let mut placed: Vec<NodeId> = workloads.values()
+                        .filter(|scheduled| scheduled.workload.spec.name == peer)
+                        .flat_map(|scheduled| std::iter::once(scheduled.target_node).chain(scheduled.replica_nodes.iter().copied()))
+                        .collect();
+                    placed.sort();
+                    placed.dedup();
+                    (!placed.is_empty()).then_some(network_cost::PeerPlacement { weight, nodes: placed })
+                })
+                .collect()
+        };
+        if peers.is_empty() {
+            return None;
+        }
+        
+        // Measured links when a network manager probes them; co-location counts either way
+        let links = self.network_manager
+            .as_ref()
+            .map(|network| network.link_prober().links())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|link| (link.from, link.to, network_cost::LinkCost { rtt: link.rtt, loss_rate: link.loss_rate }));
+        let zones = nodes
+            .iter()
+            .filter_map(|node| node.labels.get(ZONE_LABEL).map(|zone| (node.node_id, zone.clone())))
+            .collect();
+        let matrix = LatencyMatrix::build(config, zones, links);
+        
+        self.optimizer.place_near_peers(candidates, &peers, &matrix)
+    }
+    
+    /// Plan, claim and start every member of a group, undoing all of it on failure
+    async fn place_group(&self, group: &WorkloadGroup) -> std::result::Result<Vec<SchedulingResult>, String> {
+        // Same placement conditions as a single workload; the group stays
+        // queued until they hold for every member
+        for member in &group.members {
+            let unmet = self.unmet_conditions(member, GatePhase::Placement).await;
+            if !unmet.is_empty() {
+                return Err(format!("{} waits for conditions: {}", member.spec.id, unmet.join(", ")));
+            }
+        }
+        
+        let nodes = self.get_available_nodes().await.map_err(|e| e.to_string())?;
+        let gang_nodes = self.gang_nodes(&nodes).await;
+        let plan = gang::plan_group(&group.members, &gang_nodes)?;
+        
+        let decisions: Vec<PlacementDecision> = plan
+            .iter()
+            .map(|node_id| PlacementDecision {
+                node_id: Some(*node_id),
+                score: 1.0,
+                replica_nodes: Vec::new(),
+            })
+            .collect();
+        
+        let mut claimed: Vec<(&Workload, NodeId)> = Vec::new();
+        for (member, decision) in group.members.iter().zip(&decisions) {
+            if let Err(e) = self.claim_placement(member, decision).await {
+                for (workload, node_id) in claimed {
+                    self.release_claims(&workload.spec.id, [node_id]).await;
+                }
+                return Err(e.to_string());
+            }
+            claimed.push((member, decision.node_id.unwrap_or_else(|| NodeId::random())));
+        }
+        
+        let mut results = Vec::with_capacity(group.members.len());
+        for (member, decision) in group.members.iter().zip(decisions) {
+            match self.execute_placement(member, decision).await {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    // Stop the members already started, then drop every claim
+                    let started: Vec<ScheduledWorkload> = {
+                        let workloads = self.workloads.read().await;
+                        results.iter().filter_map(|r: &SchedulingResult| workloads.get(&r.workload_id).cloned()).collect()
+                    };
+                    for scheduled in &started {
+                        self.evict_workload(scheduled).await;
+                    }
+                    for (workload, node_id) in claimed {
+                        self.release_claims(&workload.spec.id, [node_id]).await;
+                    }
+                    return Err(e.to_string());
+                }
+            }
+        }
+        
+        for (member, result) in group.members.iter().zip(&results) {
+            if let Err(e) = self.predictor.record_placement(member, result.target_node).await {
+                tracing::warn!("Failed to record placement of {}: {}", member.spec.id, e);
+            }
+            let _ = self.scheduler_events.send(SchedulerEvent::WorkloadScheduled {
+                workload_id: result.workload_id.clone(),
+                node_id: result.target_node,
+                placement_time: result.scheduled_at,
+            });
+        }
+        let _ = self.scheduler_events.send(SchedulerEvent::GroupScheduled {
+            group: group.name.clone(),
+            members: results.iter().map(|r| r.workload_id.clone()).collect(),
+        });
+        
+        Ok(results)
+    }
+    
+    /// Nodes with the capacity not yet committed to scheduled workloads
+    async fn gang_nodes(&self, nodes: &[ClusterNode]) -> Vec<gang::GangNode> {
+        let headroom = self.node_headroom(nodes).await;
+        nodes
+            .iter()
+            .zip(headroom)
+            .map(|(node, headroom)| gang::GangNode {
+                node_id: node.node_id,
+                labels: node.labels.clone(),
+                free: headroom.free,
+            })
+            .collect()
+    }
+    
+    /// Volume claims of the scheduled workloads other than `except`, by node
+    async fn placed_volumes(&self, except: &ResourceId) -> HashMap<NodeId, NodeVolumes> {
+        let workloads = self.workloads.read().await;
+        let mut placed: HashMap<NodeId, NodeVolumes> = HashMap::new();
+        for scheduled in workloads.values().filter(|scheduled| &scheduled.workload.spec.id != except) {
+            let spec = &scheduled.workload.spec;
+            for (node_id, replicas) in claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, spec.replicas) {
+                let node = placed.entry(node_id).or_default();
+                node.add(&spec.volumes);
+                node.add(&volumes::scratch_claims(&spec.id, &spec.ephemeral_volumes, replicas));
+            }
+        }
+        placed
+    }
+    
+    /// Capacity of each node and the part not committed to scheduled workloads
+    async fn node_headroom(&self, nodes: &[ClusterNode]) -> Vec<placement::NodeHeadroom> {
+        let workloads = self.workloads.read().await;
+        nodes
+            .iter()
+            .map(|node| {
+                let capacity = claims::node_capacity(&node.resources);
+                let mut free = capacity;
+                // Workloads scaled to zero hold no capacity
+                for scheduled in workloads.values().filter(|scheduled| scheduled.workload.spec.replicas > 0) {
+                    let spec = &scheduled.workload.spec;
+                    let replicas = claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, spec.replicas)
+                        .into_iter()
+                        .find(|(node_id, _)| *node_id == node.node_id)
+                        .map(|(_, count)| count as f64)
+                        .unwrap_or(0.0);
+                    free.cpu_cores -= spec.resources.cpu_cores * replicas;
+                    free.memory_mb -= spec.resources.memory_mb as f64 * replicas;
+                    free.gpus -= spec.gpus as f64 * replicas;
+                }
+                placement::NodeHeadroom {
+                    node_id: node.node_id,
+                    capacity,
+                    free,
+                }
+            })
+            .collect()
+    }
+    
+    async fn get_available_nodes(&self) -> Result<Vec<ClusterNode>> {
+        let nodes = self.nodes.read().await;
+        
+        // Cordoned and draining nodes take no new workloads
         Ok(nodes
             .values()
             .filter(|node| node.status == NodeStatus::Ready)
@@ -393,33 +1777,75 @@ impl Scheduler {
         let container_spec = self.workload_to_container_spec(&workload).await?;
         
         // Submit to runtime if available
+        let mut container_id = None;
         if let Some(runtime) = &self.runtime {
-            let container_id = runtime.create_container(container_spec).await
+            let id = runtime.create_container(container_spec).await
                 .map_err(|e| SchedulerError::RuntimeError { 
                     message: e.to_string() 
                 })?;
             
-            runtime.start_container(&container_id).await
+            runtime.start_container(&id).await
                 .map_err(|e| SchedulerError::RuntimeError { 
                     message: e.to_string() 
                 })?;
+            container_id = Some(id);
         }
         
-        // Store scheduled workload
+        let target_node = placement.node_id.unwrap_or_else(|| NodeId::random());
+        let scheduled_at = SystemTime::now();
+        let decision = SignedDecision::sign(
+            workload.spec.id.clone(),
+            target_node,
+            placement.replica_nodes.clone(),
+            placement.score,
+            scheduled_at,
+            &self.key_pair,
+        );
+        self.store_decision(&decision).await?;
+        
+        // Store scheduled workload; this is the workload's node assignment
+        let mut workload = workload.clone();
+        let ready = !workload.spec.readiness_gates.iter().any(|gate| gate.phase == GatePhase::Ready);
+        let generation = workload.metadata.generation;
+        workload.conditions.set(
+            condition_types::SCHEDULED,
+            ObjectConditionStatus::True,
+            "Placed",
+            format!("placed on {}", target_node),
+            generation,
+        );
+        workload.conditions.set(
+            condition_types::READY,
+            if ready { ObjectConditionStatus::True } else { ObjectConditionStatus::False },
+            if ready { "NoReadinessGates" } else { "ConditionsNotMet" },
+            "",
+            generation,
+        );
+        workload.conditions.observe(generation);
+        workload.metadata.status_changed();
         let scheduled = ScheduledWorkload {
-            workload: workload.clone(),
-            target_node: placement.node_id.unwrap_or_else(|| NodeId::random()),
-            scheduled_at: SystemTime::now(),
+            workload,
+            target_node,
+            replica_nodes: placement.replica_nodes,
+            container_id,
+            scheduled_at,
             status: WorkloadStatus::Running,
+            decision: decision.clone(),
+            ready,
+            conditions: Vec::new(),
         };
         
         self.workloads.write().await.insert(scheduled.workload.spec.id.clone(), scheduled.clone());
+        self.sample_demand().await;
+        self.record_owners(&scheduled).await;
         
         Ok(SchedulingResult {
             workload_id: scheduled.workload.spec.id,
-            target_node: placement.node_id.unwrap_or_else(|| NodeId::random()),
+            target_node,
             placement_score: placement.score,
-            scheduled_at: scheduled.scheduled_at,
+            scheduled_at,
+            replica_nodes: scheduled.replica_nodes,
+            decision,
         })
     }
     
@@ -447,10 +1873,17 @@ impl Scheduler {
                 network_mbps: None,
             },
             network: Default::default(),
-            volumes: Vec::new(),
+            volumes: workload.spec.volumes.iter().map(|volume| nexus_runtime::container::VolumeMount {
+                source: volume.name.clone(),
+                target: volume.mount_path.clone(),
+                options: Vec::new(),
+                readonly: volume.readonly,
+            }).collect(),
+            ephemeral_volumes: workload.spec.ephemeral_volumes.clone(),
             security: Default::default(),
             labels: workload.spec.labels.clone(),
             restart_policy: nexus_runtime::container::RestartPolicy::Always,
+            probes: Default::default(),
         })
     }
     
@@ -489,29 +1922,653 @@ impl Scheduler {
     }
     
     async fn consolidate_workloads(&self) -> Result<Vec<ReschedulingResult>> {
-        // Implementation for workload consolidation
-        // This is a placeholder
-        Ok(Vec::new())
+        Ok(self.consolidate(&self.config.consolidation).await?.results)
+    }
+    
+    /// Pack running workloads onto fewer nodes and report the nodes left idle
+    ///
+    /// Only Ready nodes take part. Workloads with replicas on several nodes,
+    /// or not running, pin their node. The idle nodes are not cordoned here;
+    /// that is left to whoever powers them down.
+    pub async fn consolidate(&self, config: &ConsolidationConfig) -> Result<ConsolidationReport> {
+        let nodes = self.get_available_nodes().await?;
+        let headroom = self.node_headroom(&nodes).await;
+        let views: Vec<consolidation::ConsolidationNode> = nodes
+            .iter()
+            .zip(headroom)
+            .map(|(node, headroom)| consolidation::ConsolidationNode {
+                node_id: node.node_id,
+                labels: node.labels.clone(),
+                capacity: headroom.capacity,
+                free: headroom.free,
+            })
+            .collect();
+        
+        let placed: Vec<consolidation::PlacedWorkload> = {
+            let workloads = self.workloads.read().await;
+            let mut placed = Vec::new();
+            for scheduled in workloads.values() {
+                let spec = &scheduled.workload.spec;
+                let per_node = claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, spec.replicas);
+                let movable = per_node.len() == 1 && scheduled.status == WorkloadStatus::Running;
+                for (node_id, count) in per_node {
+                    placed.push(consolidation::PlacedWorkload {
+                        workload: scheduled.workload.clone(),
+                        node_id,
+                        demand: ResourceTotals {
+                            cpu_cores: spec.resources.cpu_cores * count as f64,
+                            memory_mb: spec.resources.memory_mb as f64 * count as f64,
+                            gpus: spec.gpus as f64 * count as f64,
+                        },
+                        movable,
+                    });
+                }
+            }
+            placed
+        };
+        
+        let plan = self.placement_engine.plan_consolidation(&views, &placed, config);
+        tracing::info!(
+            "Consolidation plan: {} move(s) to empty {} node(s)",
+            plan.moves.len(), plan.vacated.len()
+        );
+        
+        let moves: Vec<(ScheduledWorkload, PlannedMove)> = {
+            let workloads = self.workloads.read().await;
+            plan.moves
+                .iter()
+                .filter_map(|planned| workloads.get(&planned.workload_id).map(|s| (s.clone(), planned.clone())))
+                .collect()
+        };
+        let deadline = tokio::time::Instant::now() + config.deadline;
+        let results: Vec<ReschedulingResult> = stream::iter(moves)
+            .map(|(scheduled, planned)| {
+                let reason = format!("consolidating node {}", planned.from);
+                self.migrate_workload(scheduled, planned.from, reason, deadline, false, Some(planned.to))
+            })
+            .buffer_unordered(config.max_parallel_moves.max(1))
+            .collect()
+            .await;
+        
+        let idle_nodes: Vec<NodeId> = {
+            let workloads = self.workloads.read().await;
+            plan.vacated
+                .into_iter()
+                .filter(|node_id| !workloads.values().any(|scheduled| scheduled.is_assigned_to(node_id)))
+                .collect()
+        };
+        
+        let moved = results.iter().filter(|r| r.outcome == ReschedulingOutcome::Migrated).count();
+        let failed = results.len() - moved;
+        tracing::info!(
+            "Consolidated: {} moved, {} failed, {} node(s) idle",
+            moved, failed, idle_nodes.len()
+        );
+        let _ = self.scheduler_events.send(SchedulerEvent::ConsolidationCompleted {
+            moved,
+            failed,
+            idle_nodes: idle_nodes.clone(),
+        });
+        
+        Ok(ConsolidationReport { results, idle_nodes })
     }
     
     async fn drain_nodes_for_upgrade(&self) -> Result<Vec<ReschedulingResult>> {
-        // Implementation for node upgrade draining
-        // This is a placeholder
-        Ok(Vec::new())
+        // Nodes are cordoned ahead of an upgrade; drain them one at a time
+        // so their workloads are not all moving at once
+        let cordoned: Vec<NodeId> = self.nodes.read().await
+            .values()
+            .filter(|node| node.status == NodeStatus::Cordoned)
+            .map(|node| node.node_id)
+            .collect();
+        
+        let mut results = Vec::new();
+        for node_id in cordoned {
+            results.extend(self.run_drain(node_id).await?);
+        }
+        
+        Ok(results)
     }
     
-    async fn drain_node(&self, _node_id: NodeId) -> Result<()> {
-        // Implementation for draining all workloads from a node
-        // This is a placeholder
+    /// Drain a node through the drain workflow
+    ///
+    /// A drain that leaves workloads behind is rolled back, returning the
+    /// node to its previous status. Without a state manager there is no
+    /// workflow engine and the node is drained directly.
+    async fn run_drain(&self, node_id: NodeId) -> Result<Vec<ReschedulingResult>> {
+        let Some(engine) = &self.workflow_engine else {
+            return self.drain_node(node_id, &self.config.drain).await;
+        };
+        
+        let record = engine.run(DRAIN_WORKFLOW, serde_json::to_value(node_id)?).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })?;
+        if record.state == WorkflowState::Failed {
+            tracing::error!("Drain workflow {} of node {} could not roll back: {:?}", record.id, node_id, record.error);
+        }
+        match record.context.get::<Vec<ReschedulingResult>>(DRAIN_RESULTS) {
+            Some(results) => Ok(results),
+            None if record.state == WorkflowState::Completed => Ok(Vec::new()),
+            None => Err(SchedulerError::RuntimeError {
+                message: format!("drain of node {} failed: {}", node_id, record.error.unwrap_or_default()),
+            }),
+        }
+    }
+    
+    /// Drain a node, moving its workloads to the remaining Ready nodes
+    ///
+    /// The node is marked `Draining` first, which takes it out of placement,
+    /// and stays that way afterwards. Returns one result per workload that
+    /// was assigned to the node.
+    pub async fn drain_node(&self, node_id: NodeId, config: &DrainConfig) -> Result<Vec<ReschedulingResult>> {
+        {
+            let mut nodes = self.nodes.write().await;
+            let node = nodes.get_mut(&node_id).ok_or(SchedulerError::NodeNotFound { node_id })?;
+            node.status = NodeStatus::Draining;
+        }
+        
+        let assigned: Vec<ScheduledWorkload> = self.workloads.read().await
+            .values()
+            .filter(|scheduled| scheduled.is_assigned_to(&node_id))
+            .cloned()
+            .collect();
+        
+        tracing::info!("Draining node {}: {} workload(s)", node_id, assigned.len());
+        
+        let deadline = tokio::time::Instant::now() + config.deadline;
+        let results: Vec<ReschedulingResult> = stream::iter(assigned)
+            .map(|scheduled| self.migrate_workload(scheduled, node_id, format!("node {} drained", node_id), deadline, config.force, None))
+            .buffer_unordered(config.max_concurrency.max(1))
+            .collect()
+            .await;
+        
+        let migrated = results.iter().filter(|r| r.outcome == ReschedulingOutcome::Migrated).count();
+        let failed = results.iter().filter(|r| matches!(r.outcome, ReschedulingOutcome::Failed { .. })).count();
+        let evicted = results.len() - migrated - failed;
+        
+        tracing::info!(
+            "Drained node {}: {} migrated, {} failed, {} evicted",
+            node_id, migrated, failed, evicted
+        );
+        let _ = self.scheduler_events.send(SchedulerEvent::NodeDrainCompleted {
+            node_id,
+            migrated,
+            failed,
+            evicted,
+        });
+        
+        Ok(results)
+    }
+    
+    /// Move one workload off a node before the deadline
+    ///
+    /// Without a target the workload is placed again like a new one.
+    async fn migrate_workload(
+        &self,
+        scheduled: ScheduledWorkload,
+        old_node: NodeId,
+        reason: String,
+        deadline: tokio::time::Instant,
+        force: bool,
+        target: Option<NodeId>,
+    ) -> ReschedulingResult {
+        let workload_id = scheduled.workload.spec.id.clone();
+        
+        let relocation = self.relocate_workload(&scheduled, &[old_node], target);
+        let failure = match tokio::time::timeout_at(deadline, relocation).await {
+            Ok(Ok(result)) => {
+                let _ = self.scheduler_events.send(SchedulerEvent::WorkloadRescheduled {
+                    workload_id: workload_id.clone(),
+                    old_node,
+                    new_node: result.target_node,
+                    reason: reason.clone(),
+                });
+                return ReschedulingResult {
+                    workload_id,
+                    old_node,
+                    new_node: Some(result.target_node),
+                    reason,
+                    outcome: ReschedulingOutcome::Migrated,
+                    rescheduled_at: SystemTime::now(),
+                };
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "rescheduling deadline exceeded".to_string(),
+        };
+        
+        tracing::warn!("Could not move workload {} off node {}: {}", workload_id, old_node, failure);
+        
+        let outcome = if force {
+            self.evict_workload(&scheduled).await;
+            ReschedulingOutcome::Evicted { reason: failure }
+        } else {
+            ReschedulingOutcome::Failed { reason: failure }
+        };
+        
+        ReschedulingResult {
+            workload_id,
+            old_node,
+            new_node: None,
+            reason,
+            outcome,
+            rescheduled_at: SystemTime::now(),
+        }
+    }
+    
+    /// Place a workload again and replace its container
+    ///
+    /// The new placement is chosen before the old container is touched, so a
+    /// workload with nowhere to go keeps running where it is.
+    async fn relocate_workload(
+        &self,
+        scheduled: &ScheduledWorkload,
+        excluded: &[NodeId],
+        target: Option<NodeId>,
+    ) -> Result<SchedulingResult> {
+        let placement = match target {
+            Some(node_id) => PlacementDecision {
+                node_id: Some(node_id),
+                score: 1.0,
+                replica_nodes: Vec::new(),
+            },
+            None => self.plan_placement(&scheduled.workload, excluded).await?,
+        };
+        self.claim_placement(&scheduled.workload, &placement).await?;
+        
+        if let (Some(runtime), Some(container_id)) = (&self.runtime, &scheduled.container_id) {
+            runtime.stop_container(container_id, Some(Duration::from_secs(30))).await
+                .map_err(|e| SchedulerError::RuntimeError { message: e.to_string() })?;
+            runtime.remove_container(container_id, false).await
+                .map_err(|e| SchedulerError::RuntimeError { message: e.to_string() })?;
+            
+            let result = match self.carry_volumes(runtime, &scheduled.workload).await {
+                Ok(()) => self.execute_placement(&scheduled.workload, placement).await,
+                Err(e) => Err(e),
+            };
+            if result.is_err() {
+                // The old container is gone; don't report the workload as running there
+                if let Some(entry) = self.workloads.write().await.get_mut(&scheduled.workload.spec.id) {
+                    entry.container_id = None;
+                    entry.status = WorkloadStatus::Failed;
+                }
+            }
+            if let Ok(result) = &result {
+                self.release_previous_claims(scheduled, result).await;
+            }
+            return result;
+        }
+        
+        let result = self.execute_placement(&scheduled.workload, placement).await?;
+        self.release_previous_claims(scheduled, &result).await;
+        Ok(result)
+    }
+    
+    /// Bring a moved workload's volumes to its new container
+    ///
+    /// Each volume is snapshotted now that its old container has stopped.
+    /// If that fails, for instance because the old node is gone, the last
+    /// recorded snapshot is used instead. The snapshot is restored through
+    /// the runtime that creates the new container.
+    async fn carry_volumes(&self, runtime: &Runtime, workload: &Workload) -> Result<()> {
+        for volume in &workload.spec.volumes {
+            let snapshot = match runtime.snapshot_volume(&volume.name).await {
+                Ok(snapshot) => {
+                    self.store_latest_snapshot(&snapshot).await?;
+                    snapshot
+                }
+                Err(e) => {
+                    tracing::warn!("Could not snapshot volume {} of {}: {}", volume.name, workload.spec.id, e);
+                    match self.latest_volume_snapshot(&volume.name).await? {
+                        Some(snapshot) => snapshot,
+                        None => {
+                            tracing::warn!("Volume {} has no snapshot; {} starts with it empty", volume.name, workload.spec.id);
+                            continue;
+                        }
+                    }
+                }
+            };
+            runtime.restore_volume(&snapshot.id).await
+                .map_err(|e| SchedulerError::RuntimeError { message: e.to_string() })?;
+        }
         Ok(())
     }
     
-    async fn execute_scaling_decision(&self, _decision: &ScalingDecision) -> Result<bool> {
-        // Implementation for executing scaling decisions
-        // This is a placeholder
+    async fn store_latest_snapshot(&self, snapshot: &VolumeSnapshot) -> Result<()> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(());
+        };
+        
+        let bytes = serde_json::to_vec(snapshot)?;
+        state_manager.set(&volumes::latest_snapshot_key(&snapshot.volume_id), &bytes).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })
+    }
+    
+    /// Release claims on nodes a moved workload no longer uses
+    async fn release_previous_claims(&self, scheduled: &ScheduledWorkload, result: &SchedulingResult) {
+        let current = claims::replicas_per_node(result.target_node, &result.replica_nodes, scheduled.workload.spec.replicas);
+        let previous = claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, scheduled.workload.spec.replicas);
+        let vacated = previous
+            .into_iter()
+            .map(|(node_id, _)| node_id)
+            .filter(|node_id| !current.iter().any(|(id, _)| id == node_id));
+        self.release_claims(&scheduled.workload.spec.id, vacated).await;
+    }
+    
+    /// Stop a workload and drop its assignment without placing it elsewhere
+    async fn evict_workload(&self, scheduled: &ScheduledWorkload) {
+        if let (Some(runtime), Some(container_id)) = (&self.runtime, &scheduled.container_id) {
+            if let Err(e) = runtime.remove_container(container_id, true).await {
+                tracing::warn!("Failed to remove container {} on eviction: {}", container_id, e);
+            }
+        }
+        self.workloads.write().await.remove(&scheduled.workload.spec.id);
+        self.predictor.remove_workload(&scheduled.workload.spec.id);
+        self.sample_demand().await;
+        
+        let nodes = claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, scheduled.workload.spec.replicas);
+        self.release_claims(&scheduled.workload.spec.id, nodes.into_iter().map(|(node_id, _)| node_id)).await;
+        let _ = self.scheduler_events.send(SchedulerEvent::WorkloadEvicted {
+            workload_id: scheduled.workload.spec.id.clone(),
+            node_id: scheduled.target_node,
+        });
+        
+        if let Some(state_manager) = &self.state_manager {
+            if let Err(e) = state_manager.delete(&signing::decision_key(&scheduled.workload.spec.id)).await {
+                tracing::warn!("Failed to delete placement record of {}: {}", scheduled.workload.spec.id, e);
+            }
+        }
+        
+        // Its container and volume records go with it
+        if let Some(collector) = &self.garbage_collector {
+            let workload = ObjectRef::new(kinds::WORKLOAD, scheduled.workload.spec.id.to_string());
+            if let Err(e) = collector.delete(&workload, DeletionPropagation::Background).await {
+                tracing::warn!("Failed to delete object record of {}: {}", workload, e);
+            }
+        }
+    }
+    
+    /// Register a placed workload and the container and volumes it owns
+    ///
+    /// A workload placed again keeps its record; the record of a container
+    /// it no longer runs in is deleted.
+    async fn record_owners(&self, scheduled: &ScheduledWorkload) {
+        let Some(collector) = &self.garbage_collector else {
+            return;
+        };
+        
+        let workload = ObjectRef::new(kinds::WORKLOAD, scheduled.workload.spec.id.to_string());
+        let mut owned: Vec<ObjectRef> = scheduled.workload.spec.volumes
+            .iter()
+            .map(|volume| ObjectRef::new(kinds::VOLUME, volumes::volume_object_name(&scheduled.workload.spec.id, &volume.name)))
+            .collect();
+        let container = scheduled.container_id
+            .as_ref()
+            .map(|container_id| ObjectRef::new(kinds::CONTAINER, container_id.to_string()));
+        owned.extend(container.clone());
+        
+        let result = async {
+            create_object(collector, workload.clone(), Vec::new()).await?;
+            for dependent in collector.dependents(&workload).await? {
+                if dependent.kind == kinds::CONTAINER && Some(&dependent) != container.as_ref() {
+                    collector.delete(&dependent, DeletionPropagation::Background).await?;
+                }
+            }
+            for object in owned {
+                create_object(collector, object, vec![OwnerReference::new(workload.clone())]).await?;
+            }
+            Ok::<_, nexus_state::StateError>(())
+        };
+        if let Err(e) = result.await {
+            tracing::warn!("Failed to record owners of {}: {}", workload, e);
+        }
+    }
+    
+    async fn execute_scaling_decision(&self, decision: &ScalingDecision) -> Result<bool> {
+        let Some(current) = self.workloads.read().await.get(&decision.resource_id).cloned() else {
+            return Ok(false);
+        };
+        tracing::info!(
+            "Scaling {} from {} to {} replicas{}",
+            decision.resource_id, decision.current_replicas, decision.target_replicas,
+            decision.schedule.as_ref().map(|name| format!(" (schedule {})", name)).unwrap_or_default()
+        );
+        let mut workload = current.workload.clone();
+        workload.spec.replicas = decision.target_replicas;
+        workload.metadata.spec_changed();
+        let generation = workload.metadata.generation;
+        workload.conditions.observe(generation);
+        let service_id = Self::service_id(&workload.spec);
+        
+        if current.workload.spec.replicas == 0 && decision.target_replicas > 0 {
+            // Nothing is running, so the replicas are placed afresh
+            if let Err(e) = self.place_activated(&workload, &service_id).await {
+                tracing::warn!("Failed to activate {}: {}", decision.resource_id, e);
+                return Ok(false);
+            }
+        } else {
+            if decision.target_replicas == 0 {
+                self.stop_replicas(&current).await;
+            }
+            if let Some(scheduled) = self.workloads.write().await.get_mut(&decision.resource_id) {
+                scheduled.workload = workload;
+                if decision.target_replicas == 0 {
+                    scheduled.container_id = None;
+                }
+            }
+            if decision.target_replicas == 0 {
+                if let Some(network_manager) = &self.network_manager {
+                    network_manager.activator().mark_scaled_to_zero(&service_id);
+                }
+            }
+        }
+        
+        let _ = self.scheduler_events.send(SchedulerEvent::ScalingTriggered {
+            decision: decision.clone(),
+        });
         Ok(true)
     }
     
+    /// Place and start a workload scaled up from zero, and register its
+    /// instance with the mesh so held requests are released
+    async fn place_activated(&self, workload: &Workload, service_id: &ServiceId) -> Result<SchedulingResult> {
+        let placement = self.plan_placement(workload, &[]).await?;
+        self.claim_placement(workload, &placement).await?;
+        let selected_node = placement.node_id.unwrap_or_else(|| NodeId::random());
+        let claimed = claims::replicas_per_node(selected_node, &placement.replica_nodes, workload.spec.replicas);
+        
+        let result = match self.execute_placement(workload, placement).await {
+            Ok(result) => result,
+            Err(e) => {
+                self.release_claims(&workload.spec.id, claimed.iter().map(|(node_id, _)| *node_id)).await;
+                return Err(e);
+            }
+        };
+        
+        if let Some(network_manager) = &self.network_manager {
+            let address = self.nodes.read().await.get(&result.target_node).map(|node| node.address);
+            if let Some(address) = address {
+                let instance = nexus_networking::ServiceInstance {
+                    service_id: service_id.clone(),
+                    node_id: result.target_node,
+                    address,
+                    health_status: nexus_networking::HealthStatus::Healthy,
+                    metadata: HashMap::new(),
+                    last_seen: SystemTime::now(),
+                };
+                network_manager.register_service(instance).await
+                    .map_err(|e| SchedulerError::NetworkError { message: e.to_string() })?;
+            }
+        }
+        
+        let _ = self.scheduler_events.send(SchedulerEvent::WorkloadScheduled {
+            workload_id: result.workload_id.clone(),
+            node_id: result.target_node,
+            placement_time: result.scheduled_at,
+        });
+        Ok(result)
+    }
+    
+    /// Stop a workload's container and release its claims, keeping its
+    /// assignment so it can be activated again
+    async fn stop_replicas(&self, scheduled: &ScheduledWorkload) {
+        if let (Some(runtime), Some(container_id)) = (&self.runtime, &scheduled.container_id) {
+            if let Err(e) = runtime.stop_container(container_id, Some(Duration::from_secs(30))).await {
+                tracing::warn!("Failed to stop container {} on scale to zero: {}", container_id, e);
+            }
+        }
+        let nodes = claims::replicas_per_node(scheduled.target_node, &scheduled.replica_nodes, scheduled.workload.spec.replicas);
+        self.release_claims(&scheduled.workload.spec.id, nodes.into_iter().map(|(node_id, _)| node_id)).await;
+    }
+    
+    async fn load_node_metadata(&self, node_id: &NodeId) -> Result<Option<NodeMetadata>> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(None);
+        };
+        
+        let stored = state_manager.get(&node_metadata::metadata_key(node_id)).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })?;
+        
+        stored
+            .map(|bytes| serde_json::from_slice(&bytes))
+            .transpose()
+            .map_err(SchedulerError::from)
+    }
+    
+    /// Revision the node's stored metadata was last written at, 0 if never
+    async fn node_metadata_revision(&self, node_id: &NodeId) -> Result<u64> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(0);
+        };
+        
+        let revisions = state_manager.key_revisions(&node_metadata::metadata_key(node_id)).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })?;
+        Ok(revisions.mod_revision)
+    }
+    
+    /// Store node metadata if the stored copy is still at `expected_revision`
+    async fn store_node_metadata(&self, node_id: &NodeId, expected_revision: u64, metadata: &NodeMetadata) -> Result<bool> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(true);
+        };
+        
+        let bytes = serde_json::to_vec(metadata)?;
+        state_manager.put_if_revision(&node_metadata::metadata_key(node_id), expected_revision, &bytes).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })
+    }
+    
+    /// Claim capacity on every node a placement uses, then bind the claims
+    ///
+    /// Without a state store this scheduler is the only writer and no
+    /// claims are recorded. On failure any claims already made are released.
+    async fn claim_placement(&self, workload: &Workload, placement: &PlacementDecision) -> Result<()> {
+        if self.state_manager.is_none() {
+            return Ok(());
+        }
+        let Some(target_node) = placement.node_id else {
+            return Ok(());
+        };
+        
+        let workload_id = &workload.spec.id;
+        let scheduler_name = self.config.scheduler_name.as_str();
+        let per_node = claims::replicas_per_node(target_node, &placement.replica_nodes, workload.spec.replicas);
+        
+        let mut claimed = Vec::with_capacity(per_node.len());
+        let mut outcome = Ok(());
+        for (node_id, replicas) in &per_node {
+            let Some(capacity) = self.nodes.read().await.get(node_id).map(|n| claims::node_capacity(&n.resources)) else {
+                outcome = Err(SchedulerError::NodeNotFound { node_id: *node_id });
+                break;
+            };
+            let claim = PlacementClaim::new(workload, scheduler_name, *replicas);
+            outcome = self.update_claims(*node_id, workload_id, |ledger| ledger.admit(claim.clone(), capacity)).await;
+            if outcome.is_err() {
+                break;
+            }
+            claimed.push(*node_id);
+        }
+        
+        // A claim can be displaced by a higher-precedence one until it is bound
+        if outcome.is_ok() {
+            for node_id in &claimed {
+                outcome = self.update_claims(*node_id, workload_id, |ledger| ledger.bind(workload_id, scheduler_name)).await;
+                if outcome.is_err() {
+                    break;
+                }
+            }
+        }
+        
+        if outcome.is_err() {
+            self.release_claims(workload_id, claimed).await;
+        }
+        outcome
+    }
+    
+    /// Apply a change to a node's claim ledger with optimistic concurrency
+    ///
+    /// The ledger is written only if it is still at the revision it was read
+    /// at; if another scheduler wrote in between, the change is recomputed
+    /// against its ledger.
+    async fn update_claims<F>(&self, node_id: NodeId, workload_id: &ResourceId, change: F) -> Result<()>
+    where
+        F: Fn(&ClaimLedger) -> Result<ClaimLedger>,
+    {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(());
+        };
+        let key = claims::claims_key(&node_id);
+        let state_error = |e: nexus_state::StateError| SchedulerError::StateError { message: e.to_string() };
+        
+        for _ in 0..MAX_CLAIM_ATTEMPTS {
+            // Read the revision first: a write after it makes the put below fail
+            let revision = state_manager.key_revisions(&key).await.map_err(state_error)?.mod_revision;
+            let current: ClaimLedger = match state_manager.get(&key).await.map_err(state_error)? {
+                Some(bytes) => serde_json::from_slice(&bytes)?,
+                None => ClaimLedger::default(),
+            };
+            
+            let next = change(&current)?;
+            if state_manager.put_if_revision(&key, revision, &serde_json::to_vec(&next)?).await.map_err(state_error)? {
+                return Ok(());
+            }
+        }
+        
+        Err(SchedulerError::PlacementConflict {
+            workload_id: workload_id.clone(),
+            message: format!("claim ledger of node {} kept changing", node_id),
+        })
+    }
+    
+    async fn release_claims(&self, workload_id: &ResourceId, nodes: impl IntoIterator<Item = NodeId>) {
+        for node_id in nodes {
+            if let Err(e) = self.update_claims(node_id, workload_id, |ledger| Ok(ledger.release(workload_id))).await {
+                tracing::warn!("Failed to release claim of {} on node {}: {}", workload_id, node_id, e);
+            }
+        }
+    }
+    
+    async fn load_decision(&self, workload_id: &ResourceId) -> Result<Option<SignedDecision>> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(None);
+        };
+        
+        let stored = state_manager.get(&signing::decision_key(workload_id)).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })?;
+        
+        stored
+            .map(|bytes| serde_json::from_slice(&bytes))
+            .transpose()
+            .map_err(SchedulerError::from)
+    }
+    
+    async fn store_decision(&self, decision: &SignedDecision) -> Result<()> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(());
+        };
+        
+        let bytes = serde_json::to_vec(decision)?;
+        state_manager.set(&signing::decision_key(&decision.workload_id), &bytes).await
+            .map_err(|e| SchedulerError::StateError { message: e.to_string() })
+    }
+    
     async fn validate_node(&self, _node: &ClusterNode) -> Result<bool> {
         // Implementation for node validation
         // This is a placeholder
@@ -519,11 +2576,138 @@ impl Scheduler {
     }
     
     async fn start_background_tasks(&mut self) -> Result<()> {
-        // Start scheduling task
-        // Start monitoring task
-        // These are placeholders
+        let scheduler = self.background_handle();
+        let interval = self.config.scheduling_interval;
+        self.activation_task = scheduler.spawn_activation_handler();
+        self.collection_task = self.garbage_collector.clone().map(|collector| collector.spawn(interval));
+        let mut events = self.scheduler_events.subscribe();
+        
+        // Readiness conditions are polled: controllers report state, not
+        // changes. Queued groups and workloads are retried on every pass and
+        // as soon as a node joins or an eviction frees capacity.
+        let sample_interval = self.config.prediction.demand_sample_interval;
+        self.scheduling_task = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // Demand is also sampled between placements so idle periods show in the trend
+            let mut sampler = tokio::time::interval(sample_interval);
+            sampler.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = sampler.tick() => {
+                        scheduler.sample_demand().await;
+                        continue;
+                    }
+                    _ = ticker.tick() => {
+                        let released = scheduler.check_readiness_gates().await;
+                        if !released.is_empty() {
+                            tracing::info!("Released {} workload(s) whose readiness conditions are met", released.len());
+                        }
+                    }
+                    event = events.recv() => match event {
+                        Ok(SchedulerEvent::NodeAdded { .. } | SchedulerEvent::WorkloadEvicted { .. })
+                        | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+                
+                let placed = scheduler.retry_pending_groups().await;
+                if placed > 0 {
+                    tracing::info!("Placed {} queued workload group(s)", placed);
+                }
+                let placed = scheduler.retry_pending_workloads().await;
+                if placed > 0 {
+                    tracing::info!("Placed {} queued workload(s)", placed);
+                }
+            }
+        }));
+        
         Ok(())
     }
+    
+    /// Build the workflow engine on the state store and resume unfinished drains
+    fn start_workflows(&mut self) {
+        let Some(state_manager) = &self.state_manager else {
+            return;
+        };
+        
+        // The steps get a handle without the engine, so the two do not keep each other alive
+        let engine = Arc::new(WorkflowEngine::new(state_manager.clone()));
+        engine.register(drain::drain_workflow(self.background_handle(), self.config.drain.clone()));
+        self.workflow_engine = Some(engine.clone());
+        
+        tokio::spawn(async move {
+            match engine.resume_all().await {
+                Ok(resumed) if !resumed.is_empty() => tracing::info!("Resumed {} unfinished workflow(s)", resumed.len()),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to resume workflows: {}", e),
+            }
+        });
+    }
+    
+    /// A scheduler sharing this one's state, for background tasks to own
+    ///
+    /// External dependencies are copied as they are now, so they must be set
+    /// before `start`.
+    fn background_handle(&self) -> Arc<Self> {
+        Arc::new(Self {
+            config: self.config.clone(),
+            node_id: self.node_id,
+            key_pair: self.key_pair.clone(),
+            placement_engine: self.placement_engine.clone(),
+            autoscaler: self.autoscaler.clone(),
+            predictor: self.predictor.clone(),
+            optimizer: self.optimizer.clone(),
+            policy_engine: self.policy_engine.clone(),
+            resource_monitor: self.resource_monitor.clone(),
+            shadow: self.shadow.clone(),
+            condition_controllers: self.condition_controllers.clone(),
+            runtime: self.runtime.clone(),
+            network_manager: self.network_manager.clone(),
+            state_manager: self.state_manager.clone(),
+            garbage_collector: self.garbage_collector.clone(),
+            workflow_engine: self.workflow_engine.clone(),
+            nodes: self.nodes.clone(),
+            workloads: self.workloads.clone(),
+            placement_queue: self.placement_queue.clone(),
+            held: self.held.clone(),
+            group_queue: self.group_queue.clone(),
+            heartbeats: self.heartbeats.clone(),
+            scheduler_events: self.scheduler_events.clone(),
+            placement_requests: self.placement_requests.clone(),
+            scheduling_task: None,
+            monitoring_task: None,
+            activation_task: None,
+            collection_task: None,
+        })
+    }
+}
+
+/// Register an object, keeping the record if it is already registered
+async fn create_object(
+    collector: &GarbageCollector,
+    object: ObjectRef,
+    owners: Vec<OwnerReference>,
+) -> std::result::Result<(), nexus_state::StateError> {
+    match collector.create(object, owners).await {
+        Err(nexus_state::StateError::KeyExists { .. }) => Ok(()),
+        result => result,
+    }
+}
+
+/// Conflict error for a write made against a stale resourceVersion
+fn version_conflict(resource: String, expected: Option<u64>, current: u64) -> SchedulerError {
+    SchedulerError::ResourceVersionConflict {
+        resource,
+        expected: expected.unwrap_or(current),
+        current,
+    }
+}
+
+/// Cluster a node belongs to, from its `nexus.io/cluster` label
+fn node_cluster(node: &ClusterNode) -> Option<&str> {
+    node.labels.get(node_metadata::CLUSTER_LABEL).map(String::as_str)
 }
 
 /// Cluster node information
@@ -534,8 +2718,36 @@ pub struct ClusterNode {
     pub resources: NodeResources,
     pub status: NodeStatus,
     pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+    /// Capabilities last reported by the node's agent
+    #[serde(default)]
+    pub capabilities: BTreeSet<String>,
     pub taints: Vec<NodeTaint>,
     pub last_heartbeat: SystemTime,
+    #[serde(default)]
+    pub metadata: ApiMeta,
+    /// Scheduler-maintained `Ready` and `Schedulable` conditions
+    #[serde(default)]
+    pub conditions: StatusConditions,
+}
+
+impl ClusterNode {
+    /// Derive `Ready` and `Schedulable` from the node's status and mark the
+    /// current generation observed
+    pub fn refresh_conditions(&mut self) {
+        let (ready, schedulable, reason) = match self.status {
+            NodeStatus::Ready => (ObjectConditionStatus::True, ObjectConditionStatus::True, "NodeReady"),
+            NodeStatus::NotReady => (ObjectConditionStatus::False, ObjectConditionStatus::False, "HeartbeatMissed"),
+            NodeStatus::Unknown => (ObjectConditionStatus::Unknown, ObjectConditionStatus::False, "NodeStatusUnknown"),
+            NodeStatus::Cordoned => (ObjectConditionStatus::True, ObjectConditionStatus::False, "Cordoned"),
+            NodeStatus::Draining => (ObjectConditionStatus::True, ObjectConditionStatus::False, "Draining"),
+        };
+        let generation = self.metadata.generation;
+        self.conditions.set(condition_types::READY, ready, reason, "", generation);
+        self.conditions.set(condition_types::SCHEDULABLE, schedulable, reason, "", generation);
+        self.conditions.observe(generation);
+    }
 }
 
 /// Node status
@@ -568,8 +2780,25 @@ pub enum TaintEffect {
 pub struct ScheduledWorkload {
     pub workload: Workload,
     pub target_node: NodeId,
+    /// Node per replica, when replicas were placed individually
+    pub replica_nodes: Vec<NodeId>,
+    /// Runtime container, when a runtime is attached
+    pub container_id: Option<ResourceId>,
     pub scheduled_at: SystemTime,
     pub status: WorkloadStatus,
+    /// Scheduler signature over this placement
+    pub decision: SignedDecision,
+    /// All ready conditions were true when last checked
+    pub ready: bool,
+    /// Ready conditions as of the last check
+    pub conditions: Vec<GateCondition>,
+}
+
+impl ScheduledWorkload {
+    /// Whether the workload or any of its replicas runs on the node
+    pub fn is_assigned_to(&self, node_id: &NodeId) -> bool {
+        self.target_node == *node_id || self.replica_nodes.contains(node_id)
+    }
 }
 
 /// Pending workload in placement queue
@@ -577,6 +2806,7 @@ pub struct ScheduledWorkload {
 pub struct PendingWorkload {
     pub workload: Workload,
     pub submitted_at: SystemTime,
+    /// Priority after resolving the workload's priority class
     pub priority: i32,
 }
 
@@ -593,6 +2823,10 @@ pub struct SchedulingResult {
     pub target_node: NodeId,
     pub placement_score: f64,
     pub scheduled_at: SystemTime,
+    /// Node per replica, when replicas were placed individually
+    pub replica_nodes: Vec<NodeId>,
+    /// Scheduler signature over this placement
+    pub decision: SignedDecision,
 }
 
 /// Rescheduling strategies
@@ -604,12 +2838,14 @@ pub enum ReschedulingStrategy {
 }
 
 /// Rescheduling result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReschedulingResult {
     pub workload_id: ResourceId,
     pub old_node: NodeId,
-    pub new_node: NodeId,
+    /// Set when the workload was migrated
+    pub new_node: Option<NodeId>,
     pub reason: String,
+    pub outcome: ReschedulingOutcome,
     pub rescheduled_at: SystemTime,
 }
 
@@ -634,9 +2870,68 @@ pub enum SchedulerEvent {
     NodeRemoved {
         node_id: NodeId,
     },
+    NodeDrainCompleted {
+        node_id: NodeId,
+        migrated: usize,
+        failed: usize,
+        evicted: usize,
+    },
+    NodeMetadataUpdated {
+        node_id: NodeId,
+        labels: HashMap<String, String>,
+    },
+    NodeCapabilitiesUpdated {
+        node_id: NodeId,
+        capabilities: BTreeSet<String>,
+    },
+    NodeHeartbeatMissed {
+        node_id: NodeId,
+    },
+    WorkloadHeld {
+        workload_id: ResourceId,
+        gates: Vec<String>,
+    },
+    SchedulingGateRemoved {
+        workload_id: ResourceId,
+        gate: String,
+        removed_by: String,
+    },
+    WorkloadRejected {
+        workload_id: ResourceId,
+        rejected_by: String,
+        reason: String,
+    },
+    WorkloadReady {
+        workload_id: ResourceId,
+    },
+    /// A workload was stopped without being placed elsewhere, freeing its capacity
+    WorkloadEvicted {
+        workload_id: ResourceId,
+        node_id: NodeId,
+    },
+    GroupScheduled {
+        group: String,
+        members: Vec<ResourceId>,
+    },
+    GroupQueued {
+        group: String,
+        reason: String,
+    },
     ScalingTriggered {
         decision: ScalingDecision,
     },
+    WorkloadPreempted {
+        workload_id: ResourceId,
+        preempted_by: ResourceId,
+        node_id: NodeId,
+        outcome: ReschedulingOutcome,
+    },
+    ConsolidationCompleted {
+        moved: usize,
+        failed: usize,
+        /// Nodes left without workloads, ready to cordon
+        idle_nodes: Vec<NodeId>,
+    },
 }
 
 /// Scheduler statistics
@@ -645,14 +2940,18 @@ pub struct SchedulerStats {
     pub node_count: usize,
     pub workload_count: usize,
     pub pending_placements: usize,
+    pub held_workloads: usize,
+    pub pending_groups: usize,
     pub placement_stats: placement::PlacementStats,
     pub autoscaling_stats: autoscaling::AutoScalingStats,
     pub prediction_stats: predictor::PredictionStats,
+    pub heartbeat_stats: heartbeat::HeartbeatStats,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::workload::WorkloadType;
     
     #[tokio::test]
     async fn test_scheduler_creation() {
@@ -671,16 +2970,23 @@ mod tests {
             node_id: NodeId::random(),
             address: "127.0.0.1:8080".parse().unwrap(),
             resources: NodeResources {
-                cpu_cores: 4.0,
-                memory_mb: 8192,
-                storage_gb: 100.0,
-                network_mbps: 1000.0,
-                gpu_count: 0,
+                node_id: None,
+                cpu_total: 4.0,
+                cpu_available: 4.0,
+                memory_total: 8 * 1024 * 1024 * 1024,
+                memory_available: 8 * 1024 * 1024 * 1024,
+                gpu_total: 1,
+                gpu_available: 1,
+                storage: BTreeMap::new(),
             },
             status: NodeStatus::Ready,
-            labels: HashMap::new(),
+            labels: HashMap::from([(node_metadata::CLUSTER_LABEL.to_string(), "production".to_string())]),
+            annotations: HashMap::new(),
+            capabilities: BTreeSet::new(),
             taints: Vec::new(),
             last_heartbeat: SystemTime::now(),
+            metadata: ApiMeta::default(),
+            conditions: StatusConditions::default(),
         };
         
         // Add node
@@ -689,6 +2995,755 @@ mod tests {
         let stats = scheduler.stats().await;
         assert_eq!(stats.node_count, 1);
         
+        let forecast = scheduler.capacity_forecast("production", Duration::from_secs(86_400)).await;
+        assert_eq!(forecast.node_count, 1);
+        assert_eq!(forecast.resources[0].capacity, 4.0);
+        assert_eq!(forecast.resources[1].capacity, 8192.0);
+        assert!(forecast.first_exhausted().is_none());
+        assert_eq!(scheduler.capacity_forecast("staging", Duration::from_secs(86_400)).await.node_count, 0);
+        
+        // Label at runtime
+        let update = NodeMetadataUpdate::labels(&["gpu=a100".to_string()], false).unwrap();
+        let metadata = scheduler.update_node_metadata(node.node_id, &update).await.unwrap();
+        assert_eq!(metadata.labels.get("gpu").map(String::as_str), Some("a100"));
+        assert!(scheduler.update_node_metadata(NodeId::random(), &update).await.is_err());
+        
+        // A write against a stale resourceVersion is rejected
+        let stale = NodeMetadataUpdate::labels(&["zone=b".to_string()], false).unwrap()
+            .if_resource_version(Some(metadata.resource_version - 1));
+        assert!(matches!(
+            scheduler.update_node_metadata(node.node_id, &stale).await,
+            Err(SchedulerError::ResourceVersionConflict { .. })
+        ));
+
+        // Relabelling and cordoning are spec changes the scheduler observes immediately
+        scheduler.set_node_status(node.node_id, NodeStatus::Cordoned).await.unwrap();
+        {
+            let nodes = scheduler.nodes.read().await;
+            let stored = &nodes[&node.node_id];
+            assert_eq!(stored.metadata.generation, 3);
+            assert!(stored.conditions.is_current(&stored.metadata));
+            assert!(stored.conditions.is_true(condition_types::READY));
+            assert!(!stored.conditions.is_true(condition_types::SCHEDULABLE));
+        }
+
+        scheduler.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_scheduling_gates() {
+        let scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        
+        let id = ResourceId::new("default", "batch-report", "workload");
+        let workload = Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Batch,
+            priority: 0,
+            spec: WorkloadSpec {
+                id: id.clone(),
+                name: "batch-report".to_string(),
+                image: "report:latest".to_string(),
+                replicas: 1,
+                gpus: 0,
+                resources: nexus_runtime::ResourceQuotas {
+                    cpu_cores: 1.0,
+                    memory_mb: 512,
+                    ..Default::default()
+                },
+                labels: HashMap::new(),
+                workload_type: WorkloadType::Batch,
+                command: Vec::new(),
+                environment: HashMap::new(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: AffinityRules::default(),
+                scheduling_gates: vec![
+                    "change-window".to_string(),
+                    MANUAL_APPROVAL_GATE.to_string(),
+                ],
+                readiness_gates: Vec::new(),
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        };
+        
+        assert!(matches!(
+            scheduler.schedule_workload(workload.clone()).await,
+            Err(SchedulerError::SchedulingGated { .. })
+        ));
+        
+        let mut batch = workload.clone();
+        batch.spec.scheduler_name = "batch".to_string();
+        assert!(!scheduler.is_responsible_for(&batch));
+        assert!(matches!(
+            scheduler.submit_workload(batch).await,
+            Err(SchedulerError::WrongScheduler { .. })
+        ));
+        
+        // Simulation ignores the gates and leaves nothing behind
+        assert!(matches!(
+            scheduler.simulate_placement(&workload).await,
+            Err(SchedulerError::NoAvailableNodes)
+        ));
+        assert_eq!(scheduler.stats().await.pending_placements, 0);
+        
+        let outcome = scheduler.submit_workload(workload).await.unwrap();
+        assert!(matches!(outcome, SubmitOutcome::Held { ref gates } if gates.len() == 2));
+        assert_eq!(scheduler.held_workloads().await.len(), 1);
+        
+        // Unknown gate
+        assert!(scheduler.remove_scheduling_gate(&id, "missing", "controller", None).await.is_err());
+        
+        // First gate released, still held
+        let result = scheduler.remove_scheduling_gate(&id, "change-window", "controller", None).await.unwrap();
+        assert!(result.is_none());
+        assert_eq!(scheduler.held_workloads().await[0].gates.len(), 1);
+        
+        // Approval releases the workload; with no nodes it moves to the pending queue
+        assert!(scheduler.approve_workload(&id, "operator", None).await.is_err());
+        assert!(scheduler.held_workloads().await.is_empty());
+        assert_eq!(scheduler.stats().await.pending_placements, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_drain_node() {
+        let scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        
+        let node = ClusterNode {
+            node_id: NodeId::random(),
+            address: "127.0.0.1:8080".parse().unwrap(),
+            resources: NodeResources {
+                node_id: None,
+                cpu_total: 4.0,
+                cpu_available: 4.0,
+                memory_total: 8 * 1024 * 1024 * 1024,
+                memory_available: 8 * 1024 * 1024 * 1024,
+                gpu_total: 0,
+                gpu_available: 0,
+                storage: BTreeMap::new(),
+            },
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+            capabilities: BTreeSet::new(),
+            taints: Vec::new(),
+            last_heartbeat: SystemTime::now(),
+            metadata: ApiMeta::default(),
+            conditions: StatusConditions::default(),
+        };
+        scheduler.add_node(node.clone()).await.unwrap();
+        
+        let id = ResourceId::new("default", "web", "workload");
+        let workload = Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Interactive,
+            priority: 0,
+            spec: WorkloadSpec {
+                id: id.clone(),
+                name: "web".to_string(),
+                image: "web:latest".to_string(),
+                replicas: 1,
+                gpus: 0,
+                resources: nexus_runtime::ResourceQuotas {
+                    cpu_cores: 1.0,
+                    memory_mb: 512,
+                    ..Default::default()
+                },
+                labels: HashMap::new(),
+                workload_type: WorkloadType::Interactive,
+                command: Vec::new(),
+                environment: HashMap::new(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: AffinityRules::default(),
+                scheduling_gates: Vec::new(),
+                readiness_gates: Vec::new(),
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        };
+        let decision = SignedDecision::sign(id.clone(), node.node_id, Vec::new(), 1.0, SystemTime::now(), &scheduler.key_pair);
+        scheduler.workloads.write().await.insert(id.clone(), ScheduledWorkload {
+            workload,
+            target_node: node.node_id,
+            replica_nodes: Vec::new(),
+            container_id: None,
+            scheduled_at: SystemTime::now(),
+            status: WorkloadStatus::Running,
+            decision,
+            ready: true,
+            conditions: Vec::new(),
+        });
+        
+        let decision = scheduler.placement_decision(&id).await.unwrap();
+        assert!(decision.verify_from(&scheduler.node_id()).is_ok());
+        
+        let mut events = scheduler.subscribe();
+        
+        // No other node to move to: reported as a failure and left in place
+        let results = scheduler.drain_node(node.node_id, &DrainConfig::default()).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].outcome, ReschedulingOutcome::Failed { .. }));
+        assert!(results[0].new_node.is_none());
+        assert_eq!(scheduler.nodes.read().await[&node.node_id].status, NodeStatus::Draining);
+        assert!(scheduler.workloads.read().await.contains_key(&id));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            SchedulerEvent::NodeDrainCompleted { migrated: 0, failed: 1, evicted: 0, .. }
+        ));
+        
+        // Removal refuses to orphan the workload
+        assert!(matches!(
+            scheduler.remove_node(node.node_id, true).await,
+            Err(SchedulerError::DrainIncomplete { remaining: 1, .. })
+        ));
+        
+        // Once another node has room the workload moves there
+        let spare = group_node(4.0);
+        scheduler.add_node(spare.clone()).await.unwrap();
+        let results = scheduler.drain_node(node.node_id, &DrainConfig::default()).await.unwrap();
+        assert_eq!(results[0].outcome, ReschedulingOutcome::Migrated);
+        assert_eq!(results[0].new_node, Some(spare.node_id));
+        assert_eq!(scheduler.workloads.read().await[&id].target_node, spare.node_id);
+        let decision = scheduler.placement_decision(&id).await.unwrap();
+        assert_eq!(decision.target_node, spare.node_id);
+        scheduler.remove_node(node.node_id, true).await.unwrap();
+        
+        // With nowhere left to go, a forced drain evicts it
+        let force = DrainConfig { force: true, ..Default::default() };
+        let results = scheduler.drain_node(spare.node_id, &force).await.unwrap();
+        assert!(matches!(results[0].outcome, ReschedulingOutcome::Evicted { .. }));
+        assert!(scheduler.workloads.read().await.is_empty());
+        
+        scheduler.remove_node(spare.node_id, true).await.unwrap();
+        assert!(scheduler.drain_node(spare.node_id, &force).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_preemption() {
+        let mut config = SchedulerConfig::default();
+        config.preemption.priority_classes.push(PriorityClass {
+            name: "critical".to_string(),
+            value: 1000,
+            preemption_policy: PreemptionPolicy::PreemptLowerPriority,
+        });
+        let scheduler = Scheduler::new(config).await.unwrap();
+        
+        let node = ClusterNode {
+            node_id: NodeId::random(),
+            address: "127.0.0.1:8080".parse().unwrap(),
+            resources: NodeResources {
+                node_id: None,
+                cpu_total: 4.0,
+                cpu_available: 4.0,
+                memory_total: 8 * 1024 * 1024 * 1024,
+                memory_available: 8 * 1024 * 1024 * 1024,
+                gpu_total: 0,
+                gpu_available: 0,
+                storage: BTreeMap::new(),
+            },
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+            capabilities: BTreeSet::new(),
+            taints: Vec::new(),
+            last_heartbeat: SystemTime::now(),
+            metadata: ApiMeta::default(),
+            conditions: StatusConditions::default(),
+        };
+        scheduler.add_node(node.clone()).await.unwrap();
+        
+        let batch = group_member("batch", 3.0);
+        let batch_id = batch.spec.id.clone();
+        let decision = SignedDecision::sign(batch_id.clone(), node.node_id, Vec::new(), 1.0, SystemTime::now(), &scheduler.key_pair);
+        scheduler.workloads.write().await.insert(batch_id.clone(), ScheduledWorkload {
+            workload: batch,
+            target_node: node.node_id,
+            replica_nodes: Vec::new(),
+            container_id: None,
+            scheduled_at: SystemTime::now(),
+            status: WorkloadStatus::Running,
+            decision,
+            ready: true,
+            conditions: Vec::new(),
+        });
+        
+        let mut events = scheduler.subscribe();
+        
+        // The critical class outranks the batch workload, which has nowhere else to go
+        let mut critical = group_member("ledger", 2.0);
+        critical.spec.labels.insert(PRIORITY_CLASS_LABEL.to_string(), "critical".to_string());
+        let result = scheduler.schedule_workload(critical).await.unwrap();
+        assert_eq!(result.target_node, node.node_id);
+        assert!(!scheduler.workloads.read().await.contains_key(&batch_id));
+        
+        let preempted = loop {
+            if let SchedulerEvent::WorkloadPreempted { workload_id, outcome, .. } = events.recv().await.unwrap() {
+                break (workload_id, outcome);
+            }
+        };
+        assert_eq!(preempted.0, batch_id);
+        assert!(matches!(preempted.1, ReschedulingOutcome::Evicted { .. }));
+    }
+    
+    #[tokio::test]
+    async fn test_pending_workload_retried() {
+        let scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        let workload = group_member("report", 2.0);
+        let id = workload.id.clone();
+        scheduler.enqueue_pending(workload).await;
+        
+        // Nothing to place it on yet, so it stays queued
+        assert_eq!(scheduler.retry_pending_workloads().await, 0);
+        assert_eq!(scheduler.pending_workloads().await.len(), 1);
+        
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        assert_eq!(scheduler.retry_pending_workloads().await, 1);
+        assert!(scheduler.pending_workloads().await.is_empty());
+        assert!(scheduler.workloads.read().await.contains_key(&id));
+    }
+    
+    #[tokio::test]
+    async fn test_pending_workloads_placed_by_priority() {
+        // Without preemption the order of the retry pass alone decides who gets the room
+        let mut config = SchedulerConfig::default();
+        config.preemption.enabled = false;
+        let scheduler = Scheduler::new(config).await.unwrap();
+        
+        let low = group_member("backfill", 3.0);
+        let mut high = group_member("checkout", 3.0);
+        high.priority = 100;
+        let (low_id, high_id) = (low.id.clone(), high.id.clone());
+        scheduler.enqueue_pending(low).await;
+        scheduler.enqueue_pending(high).await;
+        assert_eq!(scheduler.pending_workloads().await[0].workload.id, high_id);
+        
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        assert_eq!(scheduler.retry_pending_workloads().await, 1);
+        assert!(scheduler.workloads.read().await.contains_key(&high_id));
+        let pending = scheduler.pending_workloads().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].workload.id, low_id);
+    }
+    
+    fn group_member(name: &str, cpu_cores: f64) -> Workload {
+        let id = ResourceId::new("ml", name, "workload");
+        Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Batch,
+            priority: 0,
+            spec: WorkloadSpec {
+                id,
+                name: name.to_string(),
+                image: "trainer:latest".to_string(),
+                replicas: 1,
+                gpus: 0,
+                resources: nexus_runtime::ResourceQuotas {
+                    cpu_cores,
+                    memory_mb: 1024,
+                    ..Default::default()
+                },
+                labels: HashMap::new(),
+                workload_type: WorkloadType::Batch,
+                command: Vec::new(),
+                environment: HashMap::new(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: AffinityRules::default(),
+                scheduling_gates: Vec::new(),
+                readiness_gates: Vec::new(),
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        }
+    }
+    
+    fn group_node(cpu_total: f64) -> ClusterNode {
+        ClusterNode {
+            node_id: NodeId::random(),
+            address: "127.0.0.1:8080".parse().unwrap(),
+            resources: NodeResources {
+                node_id: None,
+                cpu_total,
+                cpu_available: cpu_total,
+                memory_total: 8 * 1024 * 1024 * 1024,
+                memory_available: 8 * 1024 * 1024 * 1024,
+                gpu_total: 0,
+                gpu_available: 0,
+                storage: BTreeMap::new(),
+            },
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+            capabilities: BTreeSet::new(),
+            taints: Vec::new(),
+            last_heartbeat: SystemTime::now(),
+            metadata: ApiMeta::default(),
+            conditions: StatusConditions::default(),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_simulate_placement() {
+        let scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        let node = group_node(4.0);
+        let node_id = node.node_id;
+        scheduler.add_node(node).await.unwrap();
+        
+        let decision = scheduler.simulate_placement(&group_member("api", 1.0)).await.unwrap();
+        assert_eq!(decision.node_id, Some(node_id));
+        assert_eq!(scheduler.stats().await.workload_count, 0);
+        
+        // Repeated simulations do not reserve capacity
+        let decision = scheduler.simulate_placement(&group_member("trainer", 3.0)).await.unwrap();
+        assert_eq!(decision.node_id, Some(node_id));
+        
+        assert!(scheduler.simulate_placement(&group_member("oversized", 8.0)).await.is_err());
+        assert_eq!(scheduler.stats().await.pending_placements, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_concurrent_claims() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state_config = nexus_state::StateConfig::default();
+        state_config.storage.data_dir = temp_dir.path().to_string_lossy().to_string();
+        let state_manager = Arc::new(StateManager::new(state_config, NodeId::random()).await.unwrap());
+        state_manager.start().await.unwrap();
+        
+        // Two schedulers that each see the whole 4-core node as free
+        let node = group_node(4.0);
+        let node_id = node.node_id;
+        let mut schedulers = Vec::new();
+        for _ in 0..2 {
+            let mut scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+            scheduler.set_state_manager(state_manager.clone());
+            scheduler.add_node(node.clone()).await.unwrap();
+            schedulers.push(scheduler);
+        }
+        
+        let (first, second) = tokio::join!(
+            schedulers[0].schedule_workload(group_member("trainer-a", 3.0)),
+            schedulers[1].schedule_workload(group_member("trainer-b", 3.0)),
+        );
+        assert_eq!(first.is_ok() as usize + second.is_ok() as usize, 1);
+        
+        let bytes = state_manager.get(&claims::claims_key(&node_id)).await.unwrap().unwrap();
+        let ledger: ClaimLedger = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(ledger.claims.len(), 1);
+        assert!(ledger.claims[0].bound);
+        
+        state_manager.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_trusted_scheduler_decisions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state_config = nexus_state::StateConfig::default();
+        state_config.storage.data_dir = temp_dir.path().join("state").to_string_lossy().to_string();
+        let state_manager = Arc::new(StateManager::new(state_config, NodeId::random()).await.unwrap());
+        state_manager.start().await.unwrap();
+        
+        // The identity key survives a restart
+        let key_path = temp_dir.path().join("node.key").to_string_lossy().to_string();
+        let config = SchedulerConfig { identity_key_path: Some(key_path), ..Default::default() };
+        let placer_id = Scheduler::new(config.clone()).await.unwrap().node_id();
+        let mut placer = Scheduler::new(config).await.unwrap();
+        assert_eq!(placer.node_id(), placer_id);
+        placer.set_state_manager(state_manager.clone());
+        placer.add_node(group_node(4.0)).await.unwrap();
+        let workload = group_member("api", 1.0);
+        let id = workload.id.clone();
+        placer.schedule_workload(workload).await.unwrap();
+        
+        // Another scheduler accepts the stored decision only from a trusted signer
+        let mut reader = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        reader.set_state_manager(state_manager.clone());
+        assert!(matches!(reader.placement_decision(&id).await, Err(SchedulerError::InvalidSignature { .. })));
+        
+        let config = SchedulerConfig { trusted_schedulers: vec![placer_id], ..Default::default() };
+        let mut reader = Scheduler::new(config).await.unwrap();
+        reader.set_state_manager(state_manager.clone());
+        assert_eq!(reader.placement_decision(&id).await.unwrap().scheduler, placer_id);
+        
+        state_manager.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_owner_references() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state_config = nexus_state::StateConfig::default();
+        state_config.storage.data_dir = temp_dir.path().to_string_lossy().to_string();
+        let state_manager = Arc::new(StateManager::new(state_config, NodeId::random()).await.unwrap());
+        state_manager.start().await.unwrap();
+        
+        let mut scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        scheduler.set_state_manager(state_manager.clone());
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        let cache = WorkloadVolume {
+            name: "etl-cache".to_string(),
+            mount_path: "/cache".to_string(),
+            readonly: false,
+            size_bytes: 0,
+            storage_class: volumes::DEFAULT_STORAGE_CLASS.to_string(),
+        };
+        let mut workload = group_member("etl", 1.0);
+        workload.spec.volumes.push(cache.clone());
+        let id = workload.id.clone();
+        scheduler.schedule_workload(workload).await.unwrap();
+        
+        // A second workload with a volume of the same name owns its own record
+        let mut other = group_member("etl-backfill", 1.0);
+        other.spec.volumes.push(cache);
+        let other_id = other.id.clone();
+        scheduler.schedule_workload(other).await.unwrap();
+        
+        let collector = GarbageCollector::new(state_manager.clone());
+        let owner = ObjectRef::new(kinds::WORKLOAD, id.to_string());
+        assert!(collector.get(&owner).await.unwrap().is_some());
+        let volume_ref = ObjectRef::new(kinds::VOLUME, volumes::volume_object_name(&id, "etl-cache"));
+        let volume = collector.get(&volume_ref).await.unwrap().unwrap();
+        assert_eq!(volume.owners, vec![OwnerReference::new(owner.clone())]);
+        let other_volume = ObjectRef::new(kinds::VOLUME, volumes::volume_object_name(&other_id, "etl-cache"));
+        assert!(collector.get(&other_volume).await.unwrap().is_some());
+        
+        // Evicting the workload takes its volume record with it
+        let scheduled = scheduler.workloads.read().await.get(&id).cloned().unwrap();
+        scheduler.evict_workload(&scheduled).await;
+        assert!(collector.get(&owner).await.unwrap().is_none());
+        assert!(collector.dependents(&owner).await.unwrap().is_empty());
+        assert!(collector.get(&other_volume).await.unwrap().is_some());
+        
+        state_manager.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_workload_group() {
+        let scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        
+        let group = WorkloadGroup {
+            name: "train-small".to_string(),
+            members: vec![group_member("worker-0", 3.0), group_member("worker-1", 3.0)],
+        };
+        let outcome = scheduler.schedule_workload_group(group).await.unwrap();
+        assert!(matches!(outcome, GroupOutcome::Scheduled(ref results) if results.len() == 2));
+        assert_eq!(scheduler.stats().await.workload_count, 2);
+        
+        // The 1-core member would fit but the 3-core one does not, so neither is placed
+        let group = WorkloadGroup {
+            name: "train-large".to_string(),
+            members: vec![group_member("worker-2", 1.0), group_member("worker-3", 3.0)],
+        };
+        let outcome = scheduler.schedule_workload_group(group).await.unwrap();
+        assert!(matches!(outcome, GroupOutcome::Queued { .. }));
+        assert_eq!(scheduler.stats().await.workload_count, 2);
+        assert_eq!(scheduler.pending_groups().await.len(), 1);
+        
+        assert_eq!(scheduler.retry_pending_groups().await, 0);
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        assert_eq!(scheduler.retry_pending_groups().await, 1);
+        assert!(scheduler.pending_groups().await.is_empty());
+        assert_eq!(scheduler.stats().await.workload_count, 4);
+    }
+    
+    /// Condition controller whose answer the test sets
+    struct Switch {
+        on: std::sync::atomic::AtomicBool,
+    }
+    
+    #[async_trait::async_trait]
+    impl ConditionController for Switch {
+        fn condition(&self) -> &str {
+            "example.com/database-reachable"
+        }
+        
+        async fn check(&self, _workload: &Workload, _gate: &ReadinessGate) -> ConditionStatus {
+            if self.on.load(std::sync::atomic::Ordering::SeqCst) {
+                ConditionStatus::True
+            } else {
+                ConditionStatus::False { reason: "connection refused".to_string() }
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_readiness_gates() {
+        use std::sync::atomic::Ordering;
+        
+        let scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        let database = Arc::new(Switch { on: Default::default() });
+        scheduler.register_condition_controller(database.clone()).await;
+        
+        // Held until the database is reachable
+        let mut api = group_member("api", 1.0);
+        api.spec.readiness_gates.push(ReadinessGate::new("example.com/database-reachable", GatePhase::Placement));
+        assert!(matches!(
+            scheduler.schedule_workload(api.clone()).await,
+            Err(SchedulerError::ConditionsNotMet { .. })
+        ));
+        let outcome = scheduler.submit_workload(api).await.unwrap();
+        assert!(matches!(outcome, SubmitOutcome::Held { ref gates } if gates.len() == 1));
+        assert!(scheduler.check_readiness_gates().await.is_empty());
+        assert_eq!(scheduler.held_workloads().await.len(), 1);
+        
+        // Placed but not ready until the database is reachable
+        let mut worker = group_member("worker", 1.0);
+        worker.spec.readiness_gates.push(ReadinessGate::new("example.com/database-reachable", GatePhase::Ready));
+        let worker_id = worker.spec.id.clone();
+        let group = WorkloadGroup { name: "worker".to_string(), members: vec![worker] };
+        assert!(matches!(scheduler.schedule_workload_group(group).await.unwrap(), GroupOutcome::Scheduled(_)));
+        assert!(!scheduler.is_workload_ready(&worker_id).await.unwrap());
+        
+        // A group member waiting on a placement condition queues the group
+        let mut migrator = group_member("migrator", 1.0);
+        migrator.spec.readiness_gates.push(ReadinessGate::new("example.com/database-reachable", GatePhase::Placement));
+        let group = WorkloadGroup { name: "migrator".to_string(), members: vec![migrator] };
+        assert!(matches!(scheduler.schedule_workload_group(group).await.unwrap(), GroupOutcome::Queued { .. }));
+        
+        // The condition turns true: the API is released and placed, and the
+        // worker becomes ready
+        database.on.store(true, Ordering::SeqCst);
+        let released = scheduler.check_readiness_gates().await;
+        assert_eq!(released.len(), 1);
+        assert!(scheduler.held_workloads().await.is_empty());
+        assert_eq!(scheduler.stats().await.pending_placements, 0);
+        assert_eq!(scheduler.stats().await.workload_count, 2);
+        assert!(scheduler.is_workload_ready(&worker_id).await.unwrap());
+        
+        assert_eq!(scheduler.retry_pending_groups().await, 1);
+        assert_eq!(scheduler.stats().await.workload_count, 3);
+    }
+    
+    #[tokio::test]
+    async fn test_background_group_retry() {
+        let mut scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        scheduler.start().await.unwrap();
+        
+        let group = WorkloadGroup {
+            name: "train".to_string(),
+            members: vec![group_member("worker-0", 3.0), group_member("worker-1", 3.0)],
+        };
+        assert!(matches!(scheduler.schedule_workload_group(group).await.unwrap(), GroupOutcome::Queued { .. }));
+        
+        // The node joining triggers a retry well before the next scheduling pass
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !scheduler.pending_groups().await.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+        assert_eq!(scheduler.stats().await.workload_count, 2);
+        
+        scheduler.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_activation_places_replica() {
+        let network = Arc::new(NetworkManager::new(&nexus_networking::NetworkConfig::default()).await.unwrap());
+        let activator = network.activator();
+        let mut scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        scheduler.set_network_manager(network.clone());
+        let node = group_node(4.0);
+        let node_id = node.node_id;
+        scheduler.add_node(node).await.unwrap();
+        scheduler.start().await.unwrap();
+        
+        let id = ResourceId::new("default", "api", "workload");
+        let mut api = group_member("api", 1.0);
+        api.id = id.clone();
+        api.spec.id = id.clone();
+        let service_id = ServiceId::new("api", "default");
+        scheduler.set_scaling_policy(
+            ScalingPolicy::new(id.clone(), autoscaling::AutoscalingPolicy { min_replicas: 1, max_replicas: 3, target_cpu_utilization: 0.5 })
+                .with_scale_to_zero(Duration::from_millis(50)),
+        ).unwrap();
+        scheduler.schedule_workload(api).await.unwrap();
+        
+        // Idle past the policy's limit: scaled to zero, its capacity released
+        activator.record_request(&service_id);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let decisions = scheduler.check_autoscaling().await.unwrap();
+        assert!(matches!(decisions.as_slice(), [decision] if decision.target_replicas == 0));
+        assert!(activator.is_scaled_to_zero(&service_id));
+        assert_eq!(scheduler.get_workload(&id).await.unwrap().spec.replicas, 0);
+        
+        // A held request is answered by the replica the scheduler starts
+        let resolve = || {
+            let network = network.clone();
+            async move { network.discover_services("api").await }
+        };
+        let instances = tokio::time::timeout(Duration::from_secs(10), activator.activate(&service_id, resolve))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(instances[0].node_id, node_id);
+        assert_eq!(scheduler.get_workload(&id).await.unwrap().spec.replicas, 1);
+        assert_eq!(activator.stats().activations, 1);
+        
+        scheduler.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_background_readiness_check() {
+        let config = SchedulerConfig {
+            scheduling_interval: Duration::from_millis(20),
+            retry_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let mut scheduler = Scheduler::new(config).await.unwrap();
+        scheduler.add_node(group_node(4.0)).await.unwrap();
+        let database = Arc::new(Switch { on: Default::default() });
+        scheduler.register_condition_controller(database.clone()).await;
+        scheduler.start().await.unwrap();
+        
+        let mut api = group_member("api", 1.0);
+        api.spec.readiness_gates.push(ReadinessGate::new("example.com/database-reachable", GatePhase::Placement));
+        scheduler.submit_workload(api).await.unwrap();
+        
+        // Released by the scheduling loop, without an explicit check
+        database.on.store(true, std::sync::atomic::Ordering::SeqCst);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while scheduler.stats().await.workload_count == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+        assert!(scheduler.held_workloads().await.is_empty());
+        
         scheduler.stop().await.unwrap();
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_drain_runs_as_workflow() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state_config = nexus_state::StateConfig::default();
+        state_config.storage.data_dir = temp_dir.path().to_string_lossy().to_string();
+        let state_manager = Arc::new(StateManager::new(state_config, NodeId::random()).await.unwrap());
+        state_manager.start().await.unwrap();
+        
+        let mut scheduler = Scheduler::new(SchedulerConfig::default()).await.unwrap();
+        scheduler.set_state_manager(state_manager.clone());
+        scheduler.start_workflows();
+        
+        let node = group_node(4.0);
+        let node_id = node.node_id;
+        scheduler.add_node(node).await.unwrap();
+        scheduler.remove_node(node_id, true).await.unwrap();
+        
+        // The drain is recorded as a completed workflow
+        let records = scheduler.workflow_engine.as_ref().unwrap().list().await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, DRAIN_WORKFLOW);
+        assert_eq!(records[0].state, WorkflowState::Completed);
+        
+        state_manager.stop().await.unwrap();
+    }
+}
@@ -0,0 +1,287 @@
+//! Network-aware placement
+//!
+//! Workloads that talk to each other a lot name their peers in the
+//! `nexus.io/network-peers` label, as comma-separated workload names with an
+//! optional relative traffic weight (`db:3,cache`). Declarations form a
+//! dependency graph that counts in both directions, so a workload is placed
+//! near its peers whether it declared them or they declared it.
+//!
+//! The objective minimizes expected latency to the peers: for each peer, the
+//! mean cost to the nodes its replicas run on, weighted by traffic. Costs
+//! come from the measured node-to-node latency matrix: round-trip time plus
+//! a penalty for loss. Pairs without a measurement take the average measured
+//! cost between their zones (`nexus.io/zone`), and failing that count as
+//! `unmeasured_cost_ms`, so an unknown node never beats one known to be
+//! close. Running on the same node as a peer costs nothing. Without anything
+//! known about the candidates the objective abstains and the general
+//! optimizer decides.
+
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Workload label listing the workloads it exchanges traffic with
+pub const NETWORK_PEERS_LABEL: &str = "nexus.io/network-peers";
+
+/// Node label naming the zone the node runs in
+pub const ZONE_LABEL: &str = "nexus.io/zone";
+
+/// A workload another workload exchanges traffic with
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkPeer<'a> {
+    pub name: &'a str,
+    /// Relative share of traffic; 1 unless the label says otherwise
+    pub weight: f64,
+}
+
+/// Peers a workload declares in its labels
+///
+/// Entries with an unparsable or non-positive weight are skipped.
+pub fn network_peers(labels: &HashMap<String, String>) -> Vec<NetworkPeer<'_>> {
+    let Some(peers) = labels.get(NETWORK_PEERS_LABEL) else {
+        return Vec::new();
+    };
+    peers
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.split_once(':') {
+            None => Some(NetworkPeer { name: entry, weight: 1.0 }),
+            Some((name, weight)) => weight
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|w| w.is_finite() && *w > 0.0)
+                .map(|weight| NetworkPeer { name: name.trim(), weight }),
+        })
+        .collect()
+}
+
+/// Which workloads talk to which, with relative traffic weights
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    edges: HashMap<String, HashMap<String, f64>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the peers a workload declares in its labels
+    pub fn add_workload(&mut self, name: &str, labels: &HashMap<String, String>) {
+        for peer in network_peers(labels) {
+            self.add_dependency(name, peer.name, peer.weight);
+        }
+    }
+
+    /// Record traffic between two workloads; when both sides declare it the
+    /// larger weight holds
+    pub fn add_dependency(&mut self, a: &str, b: &str, weight: f64) {
+        if a == b {
+            return;
+        }
+        for (from, to) in [(a, b), (b, a)] {
+            let entry = self.edges.entry(from.to_string()).or_default().entry(to.to_string()).or_insert(0.0);
+            *entry = entry.max(weight);
+        }
+    }
+
+    /// Workloads a workload talks to, by name, with their weights
+    pub fn peers(&self, name: &str) -> Vec<(String, f64)> {
+        let mut peers: Vec<(String, f64)> = self
+            .edges
+            .get(name)
+            .map(|peers| peers.iter().map(|(peer, weight)| (peer.clone(), *weight)).collect())
+            .unwrap_or_default();
+        peers.sort_by(|a, b| a.0.cmp(&b.0));
+        peers
+    }
+}
+
+/// A peer workload and the nodes its replicas run on
+#[derive(Debug, Clone)]
+pub struct PeerPlacement {
+    pub weight: f64,
+    pub nodes: Vec<NodeId>,
+}
+
+/// Network-aware placement configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkAwareConfig {
+    pub enabled: bool,
+    /// Cost assumed for a link that is neither measured nor estimated from its zones
+    pub unmeasured_cost_ms: f64,
+    /// Cost added per unit of loss rate (a fully lossy link adds all of it)
+    pub loss_penalty_ms: f64,
+}
+
+impl Default for NetworkAwareConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            unmeasured_cost_ms: 50.0,
+            loss_penalty_ms: 200.0,
+        }
+    }
+}
+
+/// Measured link quality as placement weighs it
+#[derive(Debug, Clone, Copy)]
+pub struct LinkCost {
+    pub rtt: Duration,
+    pub loss_rate: f64,
+}
+
+impl NetworkAwareConfig {
+    /// Cost in milliseconds of a measured link
+    pub fn link_cost(&self, link: LinkCost) -> f64 {
+        link.rtt.as_secs_f64() * 1000.0 + link.loss_rate * self.loss_penalty_ms
+    }
+}
+
+/// Node-to-node costs from measured links, with zone averages for the rest
+#[derive(Debug, Clone, Default)]
+pub struct LatencyMatrix {
+    links: HashMap<(NodeId, NodeId), f64>,
+    zones: HashMap<NodeId, String>,
+    zone_costs: HashMap<(String, String), f64>,
+}
+
+impl LatencyMatrix {
+    /// Build the matrix from node zones and measured links
+    pub fn build(
+        config: &NetworkAwareConfig,
+        zones: HashMap<NodeId, String>,
+        links: impl IntoIterator<Item = (NodeId, NodeId, LinkCost)>,
+    ) -> Self {
+        let links: HashMap<(NodeId, NodeId), f64> = links
+            .into_iter()
+            .map(|(from, to, link)| ((from, to), config.link_cost(link)))
+            .collect();
+
+        let mut totals: HashMap<(String, String), (f64, usize)> = HashMap::new();
+        for ((from, to), cost) in &links {
+            if let (Some(a), Some(b)) = (zones.get(from), zones.get(to)) {
+                let total = totals.entry(zone_pair(a, b)).or_default();
+                total.0 += cost;
+                total.1 += 1;
+            }
+        }
+        let zone_costs = totals.into_iter().map(|(pair, (sum, count))| (pair, sum / count as f64)).collect();
+
+        Self { links, zones, zone_costs }
+    }
+
+    /// Cost between two nodes, if measured or estimable from their zones
+    pub fn cost(&self, a: NodeId, b: NodeId) -> Option<f64> {
+        if a == b {
+            return Some(0.0);
+        }
+        if let Some(cost) = self.links.get(&(a, b)).or_else(|| self.links.get(&(b, a))) {
+            return Some(*cost);
+        }
+        let (zone_a, zone_b) = (self.zones.get(&a)?, self.zones.get(&b)?);
+        self.zone_costs.get(&zone_pair(zone_a, zone_b)).copied()
+    }
+}
+
+fn zone_pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// Expected latency cost of running on `candidate`, given where the peers run
+pub fn expected_cost(
+    config: &NetworkAwareConfig,
+    matrix: &LatencyMatrix,
+    candidate: NodeId,
+    peers: &[PeerPlacement],
+) -> f64 {
+    peers
+        .iter()
+        .filter(|peer| !peer.nodes.is_empty())
+        .map(|peer| {
+            let total: f64 = peer
+                .nodes
+                .iter()
+                .map(|&node| matrix.cost(candidate, node).unwrap_or(config.unmeasured_cost_ms))
+                .sum();
+            peer.weight * total / peer.nodes.len() as f64
+        })
+        .sum()
+}
+
+/// Candidate with the lowest expected cost to the peers
+///
+/// Ties keep candidate order. Returns `None` when nothing is known about the
+/// links from any candidate to any peer.
+pub fn cheapest_candidate(
+    config: &NetworkAwareConfig,
+    matrix: &LatencyMatrix,
+    candidates: &[NodeId],
+    peers: &[PeerPlacement],
+) -> Option<NodeId> {
+    let known = candidates.iter().any(|&candidate| {
+        peers.iter().flat_map(|peer| &peer.nodes).any(|&node| matrix.cost(candidate, node).is_some())
+    });
+    if !known {
+        return None;
+    }
+
+    let mut best: Option<(NodeId, f64)> = None;
+    for &candidate in candidates {
+        let cost = expected_cost(config, matrix, candidate, peers);
+        if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+            best = Some((candidate, cost));
+        }
+    }
+    best.map(|(node_id, _)| node_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_places_near_peers_by_measured_latency() {
+        let config = NetworkAwareConfig::default();
+        let (near, far, zoned, unknown, peer) =
+            (NodeId::random(), NodeId::random(), NodeId::random(), NodeId::random(), NodeId::random());
+        let link = |ms, loss_rate| LinkCost { rtt: Duration::from_millis(ms), loss_rate };
+        let zones = HashMap::from([
+            (near, "a".to_string()),
+            (far, "b".to_string()),
+            (zoned, "a".to_string()),
+            (peer, "a".to_string()),
+        ]);
+        let matrix = LatencyMatrix::build(&config, zones, [
+            (near, peer, link(2, 0.0)),
+            (far, peer, link(5, 0.1)),
+        ]);
+
+        // Unmeasured pairs take their zones' average; unknown zones stay unknown
+        assert_eq!(matrix.cost(peer, near), Some(2.0));
+        assert_eq!(matrix.cost(zoned, peer), Some(2.0));
+        assert_eq!(matrix.cost(unknown, peer), None);
+
+        let peers = [PeerPlacement { weight: 1.0, nodes: vec![peer] }];
+        assert_eq!(cheapest_candidate(&config, &matrix, &[unknown, far, near], &peers), Some(near));
+        // Running next to the peer beats any link
+        assert_eq!(cheapest_candidate(&config, &matrix, &[near, peer], &peers), Some(peer));
+        // Nothing known, nothing to prefer
+        assert_eq!(cheapest_candidate(&config, &matrix, &[unknown], &peers), None);
+
+        // Dependencies count in both directions, with weights from the label
+        let mut graph = DependencyGraph::new();
+        let labels = HashMap::from([(NETWORK_PEERS_LABEL.to_string(), "db:3, cache, bad:-1,".to_string())]);
+        graph.add_workload("api", &labels);
+        graph.add_workload("db", &HashMap::new());
+        assert_eq!(graph.peers("api"), vec![("cache".to_string(), 1.0), ("db".to_string(), 3.0)]);
+        assert_eq!(graph.peers("db"), vec![("api".to_string(), 3.0)]);
+    }
+}
@@ -0,0 +1,273 @@
+//! Runtime node labels and annotations
+//!
+//! Labels drive node affinity and can be changed while a node is running
+//! (`nexus node label worker-3 gpu=a100`). Changes use kubectl syntax:
+//! `key=value` sets a key and `key-` removes it. Updated metadata is
+//! persisted in the state store so it survives node re-registration.
+
+use crate::diversity::{OPERATOR_LABEL, TRUST_DOMAIN_LABEL};
+use crate::error::{Result, SchedulerError};
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Maximum length of a label name or value
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Maximum length of a label key prefix (`example.com/`)
+const MAX_PREFIX_LENGTH: usize = 253;
+
+/// Well-known label carrying a node's human-readable name
+pub const HOSTNAME_LABEL: &str = "nexus.io/hostname";
+
+/// Well-known label naming the cluster a node belongs to
+pub const CLUSTER_LABEL: &str = "nexus.io/cluster";
+
+/// Labels set from attestation at registration; the label API cannot change them
+pub const RESERVED_LABELS: &[&str] = &[OPERATOR_LABEL, TRUST_DOMAIN_LABEL];
+
+/// State store key holding a node's metadata
+pub fn metadata_key(node_id: &NodeId) -> String {
+    format!("/scheduler/nodes/{}/metadata", node_id.to_hex())
+}
+
+/// Labels and annotations attached to a node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeMetadata {
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    /// Node resourceVersion this metadata was read at, for conditional updates
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+/// A single label or annotation change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataChange {
+    Set { key: String, value: String },
+    Remove { key: String },
+}
+
+impl MetadataChange {
+    /// Parse `key=value` or `key-`
+    pub fn parse(input: &str) -> Result<Self> {
+        if let Some((key, value)) = input.split_once('=') {
+            return Ok(MetadataChange::Set {
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+        }
+
+        match input.strip_suffix('-') {
+            Some(key) if !key.is_empty() => Ok(MetadataChange::Remove { key: key.to_string() }),
+            _ => Err(invalid(format!("'{}' is neither key=value nor key-", input))),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            MetadataChange::Set { key, .. } | MetadataChange::Remove { key } => key,
+        }
+    }
+}
+
+/// A batch of label and annotation changes for one node
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeMetadataUpdate {
+    pub labels: Vec<MetadataChange>,
+    pub annotations: Vec<MetadataChange>,
+    /// Replace keys that already have a different value
+    pub overwrite: bool,
+    /// Only apply if the node is still at this resourceVersion
+    #[serde(default)]
+    pub resource_version: Option<u64>,
+}
+
+impl NodeMetadataUpdate {
+    /// Build an update from `key=value` / `key-` label arguments
+    pub fn labels(args: &[String], overwrite: bool) -> Result<Self> {
+        Ok(Self {
+            labels: args.iter().map(|a| MetadataChange::parse(a)).collect::<Result<_>>()?,
+            annotations: Vec::new(),
+            overwrite,
+            resource_version: None,
+        })
+    }
+
+    /// Build an update from `key=value` / `key-` annotation arguments
+    pub fn annotations(args: &[String], overwrite: bool) -> Result<Self> {
+        Ok(Self {
+            labels: Vec::new(),
+            annotations: args.iter().map(|a| MetadataChange::parse(a)).collect::<Result<_>>()?,
+            overwrite,
+            resource_version: None,
+        })
+    }
+
+    /// Require the node to still be at `resource_version` when the update lands
+    pub fn if_resource_version(mut self, resource_version: Option<u64>) -> Self {
+        self.resource_version = resource_version;
+        self
+    }
+
+    /// Apply the update to existing metadata; nothing is changed on error
+    pub fn apply(&self, metadata: &NodeMetadata) -> Result<NodeMetadata> {
+        for change in &self.labels {
+            validate_key(change.key())?;
+            if RESERVED_LABELS.contains(&change.key()) {
+                return Err(invalid(format!("label '{}' is reserved and set from node attestation", change.key())));
+            }
+            if let MetadataChange::Set { key, value } = change {
+                validate_label_value(key, value)?;
+            }
+        }
+        for change in &self.annotations {
+            validate_key(change.key())?;
+        }
+
+        let mut updated = metadata.clone();
+        apply_changes(&mut updated.labels, &self.labels, self.overwrite, "label")?;
+        apply_changes(&mut updated.annotations, &self.annotations, self.overwrite, "annotation")?;
+        Ok(updated)
+    }
+}
+
+fn apply_changes(
+    target: &mut HashMap<String, String>,
+    changes: &[MetadataChange],
+    overwrite: bool,
+    kind: &str,
+) -> Result<()> {
+    for change in changes {
+        match change {
+            MetadataChange::Set { key, value } => {
+                if let Some(existing) = target.get(key) {
+                    if existing != value && !overwrite {
+                        return Err(invalid(format!(
+                            "{} '{}' already has value '{}'; use --overwrite to replace it",
+                            kind, key, existing
+                        )));
+                    }
+                }
+                target.insert(key.clone(), value.clone());
+            }
+            MetadataChange::Remove { key } => {
+                target.remove(key);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> SchedulerError {
+    SchedulerError::InvalidNodeMetadata { message }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_LABEL_LENGTH
+        && name.chars().all(is_name_char)
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+}
+
+/// Keys are `[prefix/]name`, where the prefix is a DNS subdomain
+fn validate_key(key: &str) -> Result<()> {
+    let (prefix, name) = match key.rsplit_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+
+    if let Some(prefix) = prefix {
+        let valid_prefix = !prefix.is_empty()
+            && prefix.len() <= MAX_PREFIX_LENGTH
+            && prefix.split('.').all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            });
+        if !valid_prefix {
+            return Err(invalid(format!("key '{}' has an invalid prefix", key)));
+        }
+    }
+
+    if !is_valid_name(name) {
+        return Err(invalid(format!(
+            "key '{}' must be 1-{} alphanumeric characters, '-', '_' or '.'",
+            key, MAX_LABEL_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+fn validate_label_value(key: &str, value: &str) -> Result<()> {
+    if !value.is_empty() && !is_valid_name(value) {
+        return Err(invalid(format!(
+            "value '{}' for label '{}' must be at most {} alphanumeric characters, '-', '_' or '.'",
+            value, key, MAX_LABEL_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_changes() {
+        assert_eq!(
+            MetadataChange::parse("gpu=a100").unwrap(),
+            MetadataChange::Set { key: "gpu".to_string(), value: "a100".to_string() }
+        );
+        assert_eq!(
+            MetadataChange::parse("zone-").unwrap(),
+            MetadataChange::Remove { key: "zone".to_string() }
+        );
+        assert!(MetadataChange::parse("gpu").is_err());
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let mut metadata = NodeMetadata::default();
+        metadata.labels.insert("gpu".to_string(), "t4".to_string());
+        metadata.labels.insert("zone".to_string(), "us-west-2a".to_string());
+
+        // Existing key needs overwrite
+        let update = NodeMetadataUpdate::labels(&args(&["gpu=a100"]), false).unwrap();
+        assert!(update.apply(&metadata).is_err());
+
+        let update = NodeMetadataUpdate::labels(&args(&["gpu=a100", "zone-", "nexus.io/tier=edge"]), true).unwrap();
+        let updated = update.apply(&metadata).unwrap();
+        assert_eq!(updated.labels.get("gpu").map(String::as_str), Some("a100"));
+        assert_eq!(updated.labels.get("nexus.io/tier").map(String::as_str), Some("edge"));
+        assert!(!updated.labels.contains_key("zone"));
+
+        // Annotation values are free-form, label values are not
+        let update = NodeMetadataUpdate::annotations(&args(&["owner=team a/b"]), false).unwrap();
+        assert!(update.apply(&metadata).is_ok());
+        let update = NodeMetadataUpdate::labels(&args(&["owner=team a/b"]), false).unwrap();
+        assert!(update.apply(&metadata).is_err());
+    }
+
+    #[test]
+    fn test_reserved_labels_are_read_only() {
+        let mut metadata = NodeMetadata::default();
+        metadata.labels.insert(OPERATOR_LABEL.to_string(), "acme".to_string());
+
+        for arg in ["nexus.io/operator=mallory", "nexus.io/operator-", "nexus.io/trust-domain=mallory"] {
+            let update = NodeMetadataUpdate::labels(&args(&[arg]), true).unwrap();
+            assert!(update.apply(&metadata).is_err(), "{} should be rejected", arg);
+        }
+
+        // Annotations with the same key are not trust inputs
+        let update = NodeMetadataUpdate::annotations(&args(&["nexus.io/operator=note"]), true).unwrap();
+        assert!(update.apply(&metadata).is_ok());
+    }
+}
@@ -1,6 +1,9 @@
 //! Resource optimization module
 
-use nexus_shared::ResourceId;
+use crate::diversity::{self, DomainCandidate, TrustDomainDiversity};
+use crate::error::Result;
+use crate::network_cost::{self, LatencyMatrix, NetworkAwareConfig, PeerPlacement};
+use nexus_shared::{NodeId, ResourceId};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
@@ -27,11 +30,64 @@ pub struct OptimizationResult {
 #[derive(Debug)]
 pub struct MultiObjectiveOptimizer {
     objectives: Vec<OptimizationObjective>,
+    diversity: TrustDomainDiversity,
+    network: NetworkAwareConfig,
 }
 
 impl MultiObjectiveOptimizer {
     pub fn new() -> Self {
-        Self { objectives: Vec::new() }
+        Self {
+            objectives: Vec::new(),
+            diversity: TrustDomainDiversity::default(),
+            network: NetworkAwareConfig::default(),
+        }
+    }
+    
+    /// Use the given trust-domain diversity objective
+    pub fn with_diversity(mut self, diversity: TrustDomainDiversity) -> Self {
+        self.diversity = diversity;
+        self
+    }
+    
+    /// Place every replica of a consensus-critical workload so that no
+    /// trust domain hosts enough replicas to control a quorum
+    ///
+    /// Returns `None` when the objective does not apply to the workload.
+    pub fn plan_replicas(
+        &self,
+        workload: &crate::workload::Workload,
+        candidates: &[DomainCandidate],
+    ) -> Option<Result<Vec<NodeId>>> {
+        if !self.diversity.is_enabled() || !diversity::is_consensus_critical(&workload.spec.labels) {
+            return None;
+        }
+        Some(self.diversity.plan(workload.spec.replicas.max(1) as usize, candidates))
+    }
+    
+    /// Use the given network-aware placement objective
+    pub fn with_network(mut self, network: NetworkAwareConfig) -> Self {
+        self.network = network;
+        self
+    }
+    
+    /// Candidate with the lowest expected latency to the workload's peers
+    ///
+    /// Returns `None` when the objective is disabled, the workload has no
+    /// placed peers, or nothing is known about the candidates' links.
+    pub fn place_near_peers(
+        &self,
+        candidates: &[NodeId],
+        peers: &[PeerPlacement],
+        matrix: &LatencyMatrix,
+    ) -> Option<NodeId> {
+        if !self.network.enabled || peers.is_empty() {
+            return None;
+        }
+        network_cost::cheapest_candidate(&self.network, matrix, candidates, peers)
+    }
+    
+    pub fn network_config(&self) -> &NetworkAwareConfig {
+        &self.network
     }
     
     pub async fn optimize(&self, _constraints: Vec<f64>) -> Solution {
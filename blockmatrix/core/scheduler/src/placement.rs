@@ -1,9 +1,18 @@
 //! Workload placement strategies
 
+use crate::capacity::ResourceTotals;
+use crate::consolidation::{self, ConsolidationConfig, ConsolidationNode, ConsolidationPlan, PlacedWorkload};
+use crate::preemption::{self, DisruptionBudget, PreemptionCandidate, PreemptionPlan};
 use serde::{Deserialize, Serialize};
 use nexus_shared::{NodeId, ResourceId};
 use std::collections::HashMap;
 
+/// Pack workloads onto the node with the least room left
+pub const BEST_FIT: &str = "BestFit";
+
+/// Spread workloads onto the node with the most room left
+pub const SPREAD: &str = "Spread";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlacementStrategy {
     pub name: String,
@@ -12,8 +21,56 @@ pub struct PlacementStrategy {
 impl Default for PlacementStrategy {
     fn default() -> Self {
         Self {
-            name: "BestFit".to_string(),
+            name: BEST_FIT.to_string(),
+        }
+    }
+}
+
+impl PlacementStrategy {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// Node this strategy picks for a demand, among nodes it fits on
+    ///
+    /// Returns `None` when nothing fits or the strategy is not known here.
+    pub fn select_node(&self, demand: &ResourceTotals, nodes: &[NodeHeadroom]) -> Option<NodeId> {
+        let fitting = nodes.iter().filter(|node| node.fits(demand));
+        let by_room = |a: &&NodeHeadroom, b: &&NodeHeadroom| {
+            a.cpu_fraction_left(demand)
+                .partial_cmp(&b.cpu_fraction_left(demand))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.node_id.to_hex().cmp(&b.node_id.to_hex()))
+        };
+        match self.name.as_str() {
+            BEST_FIT => fitting.min_by(by_room).map(|node| node.node_id),
+            SPREAD => fitting.max_by(by_room).map(|node| node.node_id),
+            _ => None,
+        }
+    }
+}
+
+/// Capacity of a node and the part not committed to workloads
+#[derive(Debug, Clone)]
+pub struct NodeHeadroom {
+    pub node_id: NodeId,
+    pub capacity: ResourceTotals,
+    pub free: ResourceTotals,
+}
+
+impl NodeHeadroom {
+    pub fn fits(&self, demand: &ResourceTotals) -> bool {
+        demand.cpu_cores <= self.free.cpu_cores
+            && demand.memory_mb <= self.free.memory_mb
+            && demand.gpus <= self.free.gpus
+    }
+
+    /// Share of CPU capacity left after placing the demand
+    fn cpu_fraction_left(&self, demand: &ResourceTotals) -> f64 {
+        if self.capacity.cpu_cores <= 0.0 {
+            return 0.0;
         }
+        (self.free.cpu_cores - demand.cpu_cores) / self.capacity.cpu_cores
     }
 }
 
@@ -27,10 +84,41 @@ impl PlacementEngine {
         Self { strategy }
     }
     
+    /// Node the configured strategy picks for a demand, among nodes it fits on
+    pub fn select_node(&self, demand: &ResourceTotals, nodes: &[NodeHeadroom]) -> Option<NodeId> {
+        self.strategy.select_node(demand, nodes)
+    }
+    
     pub async fn place_workload(&self, _workload: &ResourceId) -> PlacementDecision {
         PlacementDecision::default()
     }
     
+    /// Workloads to evict so a demand that fits nowhere fits on some node
+    ///
+    /// Only candidates with a priority below `priority` are considered, and
+    /// `allowances` gives the evictions each budget still permits.
+    pub fn select_victims(
+        &self,
+        priority: i32,
+        demand: &ResourceTotals,
+        nodes: &[NodeHeadroom],
+        candidates: &[PreemptionCandidate],
+        budgets: &[DisruptionBudget],
+        allowances: &[u32],
+    ) -> Option<PreemptionPlan> {
+        preemption::select_victims(priority, demand, nodes, candidates, budgets, allowances)
+    }
+    
+    /// Moves that pack the placed workloads onto fewer nodes
+    pub fn plan_consolidation(
+        &self,
+        nodes: &[ConsolidationNode],
+        placed: &[PlacedWorkload],
+        config: &ConsolidationConfig,
+    ) -> ConsolidationPlan {
+        consolidation::plan_consolidation(nodes, placed, config)
+    }
+    
     pub async fn stats(&self) -> PlacementStats {
         PlacementStats::default()
     }
@@ -40,6 +128,8 @@ impl PlacementEngine {
 pub struct PlacementDecision {
     pub node_id: Option<NodeId>,
     pub score: f64,
+    /// Node per replica when replicas are placed individually
+    pub replica_nodes: Vec<NodeId>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -1,21 +1,123 @@
 //! Workload prediction module
+//!
+//! Besides each cluster's committed demand, the predictor keeps each
+//! workload's recent CPU load in cores. A workload's demand is predicted by
+//! fitting a line to the load within the prediction window and reading it
+//! off at the requested horizon; the fit's R² is the confidence.
 
+use crate::capacity::{self, CapacityForecast, CapacityResource, DemandSample, ResourceTotals};
 use nexus_shared::ResourceId;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
+
+/// Maximum number of demand samples kept per cluster for trend fitting
+const MAX_DEMAND_SAMPLES: usize = 10_000;
+
+/// How far back demand samples are kept
+const DEMAND_RETENTION: Duration = Duration::from_secs(30 * 86_400);
+
+/// Maximum number of load samples kept per workload
+const MAX_WORKLOAD_SAMPLES: usize = 1_000;
 
 #[derive(Debug)]
 pub struct WorkloadPredictor {
     resource_id: ResourceId,
+    /// Committed demand samples, by cluster name
+    demand_history: RwLock<HashMap<String, VecDeque<DemandSample>>>,
+    /// Recent CPU load of each workload, in cores
+    workload_load: RwLock<HashMap<ResourceId, VecDeque<DemandSample>>>,
+    /// How far back workload load is fitted
+    window: Duration,
+    stats: Mutex<PredictionStats>,
 }
 
 impl WorkloadPredictor {
     pub fn new(resource_id: ResourceId) -> Self {
-        Self { resource_id }
+        Self {
+            resource_id,
+            demand_history: RwLock::new(HashMap::new()),
+            workload_load: RwLock::new(HashMap::new()),
+            window: Duration::from_secs(300),
+            stats: Mutex::new(PredictionStats::default()),
+        }
+    }
+    
+    /// Fit workload load over this window instead of the last five minutes
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+    
+    /// Record a cluster's committed demand at this moment
+    pub fn record_demand(&self, cluster: &str, demand: ResourceTotals) {
+        let now = SystemTime::now();
+        let mut clusters = self.demand_history.write();
+        let history = clusters.entry(cluster.to_string()).or_default();
+        
+        history.push_back(DemandSample { at: now, demand });
+        while history.len() > MAX_DEMAND_SAMPLES {
+            history.pop_front();
+        }
+        while history.front().map_or(false, |s| now.duration_since(s.at).unwrap_or_default() > DEMAND_RETENTION) {
+            history.pop_front();
+        }
+    }
+    
+    /// A cluster's demand samples within the retention window, oldest first
+    pub fn demand_history(&self, cluster: &str) -> Vec<DemandSample> {
+        self.demand_history
+            .read()
+            .get(cluster)
+            .map(|history| history.iter().copied().collect())
+            .unwrap_or_default()
+    }
+    
+    /// Forecast a cluster's capacity from its demand history
+    pub fn forecast_capacity(
+        &self,
+        cluster: &str,
+        capacity: ResourceTotals,
+        current: ResourceTotals,
+        node_count: usize,
+        horizon: Duration,
+    ) -> CapacityForecast {
+        let history = self.demand_history(cluster);
+        CapacityForecast::build(capacity, current, &history, node_count, horizon)
     }
     
     pub async fn predict(&self, _window: std::time::Duration) -> Prediction {
         Prediction::default()
     }
     
+    /// Record a workload's CPU load, in cores busy across all its replicas
+    pub fn record_workload_load(&self, resource_id: &ResourceId, cpu_cores: f64, at: SystemTime) {
+        let mut loads = self.workload_load.write();
+        let history = loads.entry(resource_id.clone()).or_default();
+        history.push_back(DemandSample {
+            at,
+            demand: ResourceTotals { cpu_cores, ..Default::default() },
+        });
+        while history.len() > MAX_WORKLOAD_SAMPLES {
+            history.pop_front();
+        }
+        while history.front().map_or(false, |s| at.duration_since(s.at).unwrap_or_default() > self.window) {
+            history.pop_front();
+        }
+    }
+    
+    /// The workload's most recent load sample within the window
+    pub fn latest_load(&self, resource_id: &ResourceId) -> Option<DemandSample> {
+        let sample = *self.workload_load.read().get(resource_id)?.back()?;
+        let age = SystemTime::now().duration_since(sample.at).unwrap_or_default();
+        (age <= self.window).then_some(sample)
+    }
+    
+    /// Forget a workload's load history
+    pub fn remove_workload(&self, resource_id: &ResourceId) {
+        self.workload_load.write().remove(resource_id);
+    }
+    
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Start prediction tasks
         Ok(())
@@ -30,12 +132,47 @@ impl WorkloadPredictor {
         Ok(())
     }
     
-    pub async fn predict_demand(&self, _workload: &crate::workload::Workload) -> Prediction {
-        Prediction::default()
+    /// Predicted CPU load of a workload `horizon` from now
+    ///
+    /// With fewer than two samples in the window the latest load is carried
+    /// forward with no confidence.
+    pub async fn predict_demand(&self, workload: &crate::workload::Workload, horizon: Duration) -> Prediction {
+        let now = SystemTime::now();
+        let history: Vec<DemandSample> = self.workload_load.read()
+            .get(&workload.spec.id)
+            .map(|history| {
+                history
+                    .iter()
+                    .filter(|s| now.duration_since(s.at).unwrap_or_default() <= self.window)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let Some(latest) = history.last() else {
+            return Prediction::default();
+        };
+        
+        self.stats.lock().total_predictions += 1;
+        let (cpu, confidence) = match capacity::linear_trend(CapacityResource::Cpu, &history) {
+            Some((slope, r_squared)) => {
+                let seconds = |at: SystemTime| at.duration_since(history[0].at).unwrap_or_default().as_secs_f64();
+                let n = history.len() as f64;
+                let mean_x = history.iter().map(|s| seconds(s.at)).sum::<f64>() / n;
+                let mean_y = history.iter().map(|s| s.demand.cpu_cores).sum::<f64>() / n;
+                let target_x = seconds(now) + horizon.as_secs_f64();
+                (mean_y + slope * (target_x - mean_x), r_squared)
+            }
+            None => (latest.demand.cpu_cores, 0.0),
+        };
+        
+        Prediction {
+            demand: ResourceDemand { cpu: cpu.max(0.0), ..Default::default() },
+            confidence,
+        }
     }
     
     pub async fn stats(&self) -> PredictionStats {
-        PredictionStats::default()
+        self.stats.lock().clone()
     }
 }
 
@@ -0,0 +1,270 @@
+//! Priority classes and preemption
+//!
+//! A workload names its priority class with the `hypermesh.io/priority-class`
+//! label; the class value replaces the workload's own priority. When a
+//! workload fits on no node, the placement engine looks for the smallest set
+//! of strictly lower-priority workloads on one node whose removal makes room.
+//! Disruption budgets cap how many workloads matching a selector may be
+//! unavailable at once, so a preemption never takes a protected set below
+//! its floor.
+
+use crate::capacity::ResourceTotals;
+use crate::placement::NodeHeadroom;
+use crate::workload::Workload;
+use nexus_shared::{NodeId, ResourceId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Label naming a workload's priority class
+pub const PRIORITY_CLASS_LABEL: &str = "hypermesh.io/priority-class";
+
+/// Whether workloads of a class may evict lower-priority workloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PreemptionPolicy {
+    #[default]
+    PreemptLowerPriority,
+    Never,
+}
+
+/// Named priority shared by a set of workloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityClass {
+    pub name: String,
+    pub value: i32,
+    #[serde(default)]
+    pub preemption_policy: PreemptionPolicy,
+}
+
+/// Limit on workloads matching a selector that may be unavailable at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisruptionBudget {
+    pub name: String,
+    /// Labels a workload must carry to be covered
+    pub selector: HashMap<String, String>,
+    pub max_unavailable: u32,
+}
+
+impl DisruptionBudget {
+    pub fn covers(&self, labels: &HashMap<String, String>) -> bool {
+        self.selector.iter().all(|(key, value)| labels.get(key) == Some(value))
+    }
+}
+
+/// Priority classes and budgets the scheduler preempts by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreemptionConfig {
+    pub enabled: bool,
+    #[serde(default)]
+    pub priority_classes: Vec<PriorityClass>,
+    #[serde(default)]
+    pub budgets: Vec<DisruptionBudget>,
+}
+
+impl Default for PreemptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            priority_classes: Vec::new(),
+            budgets: Vec::new(),
+        }
+    }
+}
+
+impl PreemptionConfig {
+    /// Class named by the workload's label, when it is configured
+    pub fn class_of(&self, workload: &Workload) -> Option<&PriorityClass> {
+        let name = workload.spec.labels.get(PRIORITY_CLASS_LABEL)?;
+        self.priority_classes.iter().find(|class| &class.name == name)
+    }
+
+    /// Priority the scheduler orders and preempts the workload by
+    pub fn priority_of(&self, workload: &Workload) -> i32 {
+        self.class_of(workload).map(|class| class.value).unwrap_or(workload.priority)
+    }
+
+    /// Whether the workload may evict others to make room for itself
+    pub fn may_preempt(&self, workload: &Workload) -> bool {
+        self.enabled
+            && self.class_of(workload).map(|class| class.preemption_policy) != Some(PreemptionPolicy::Never)
+    }
+
+    /// Evictions each budget still allows, given the workloads now unavailable
+    pub fn allowances<'a>(&self, unavailable: impl IntoIterator<Item = &'a HashMap<String, String>>) -> Vec<u32> {
+        let mut allowed: Vec<u32> = self.budgets.iter().map(|budget| budget.max_unavailable).collect();
+        for labels in unavailable {
+            for (budget, left) in self.budgets.iter().zip(allowed.iter_mut()) {
+                if budget.covers(labels) {
+                    *left = left.saturating_sub(1);
+                }
+            }
+        }
+        allowed
+    }
+}
+
+/// Running workload that could be evicted from a node
+#[derive(Debug, Clone)]
+pub struct PreemptionCandidate {
+    pub workload_id: ResourceId,
+    pub node_id: NodeId,
+    pub priority: i32,
+    /// Resources the workload uses on this node
+    pub demand: ResourceTotals,
+    pub labels: HashMap<String, String>,
+}
+
+/// Node to place on and the workloads to evict from it first
+#[derive(Debug, Clone)]
+pub struct PreemptionPlan {
+    pub node_id: NodeId,
+    pub victims: Vec<ResourceId>,
+}
+
+/// Resources the workload needs with all its replicas on one node
+pub fn workload_demand(workload: &Workload) -> ResourceTotals {
+    let spec = &workload.spec;
+    let replicas = spec.replicas.max(1) as f64;
+    ResourceTotals {
+        cpu_cores: spec.resources.cpu_cores * replicas,
+        memory_mb: spec.resources.memory_mb as f64 * replicas,
+        gpus: spec.gpus as f64 * replicas,
+    }
+}
+
+/// Fewest lower-priority victims that make room for the demand on one node
+///
+/// Victims are taken lowest priority first, largest first, skipping any a
+/// budget no longer allows; victims the demand fits without are then put
+/// back. Nodes needing fewer victims win, then those whose highest victim
+/// priority is lowest.
+pub fn select_victims(
+    priority: i32,
+    demand: &ResourceTotals,
+    nodes: &[NodeHeadroom],
+    candidates: &[PreemptionCandidate],
+    budgets: &[DisruptionBudget],
+    allowances: &[u32],
+) -> Option<PreemptionPlan> {
+    let mut best: Option<(usize, i32, PreemptionPlan)> = None;
+
+    for node in nodes {
+        let mut eligible: Vec<&PreemptionCandidate> = candidates
+            .iter()
+            .filter(|c| c.node_id == node.node_id && c.priority < priority)
+            .collect();
+        eligible.sort_by(|a, b| {
+            a.priority.cmp(&b.priority)
+                .then(b.demand.cpu_cores.partial_cmp(&a.demand.cpu_cores).unwrap_or(std::cmp::Ordering::Equal))
+                .then_with(|| a.workload_id.to_string().cmp(&b.workload_id.to_string()))
+        });
+
+        let mut left = allowances.to_vec();
+        let mut free = node.free;
+        let mut victims: Vec<&PreemptionCandidate> = Vec::new();
+        for candidate in eligible {
+            if fits(demand, &free) {
+                break;
+            }
+            let covering: Vec<usize> = budgets
+                .iter()
+                .enumerate()
+                .filter(|(_, budget)| budget.covers(&candidate.labels))
+                .map(|(i, _)| i)
+                .collect();
+            if covering.iter().any(|&i| left[i] == 0) {
+                continue;
+            }
+            for i in covering {
+                left[i] -= 1;
+            }
+            add(&mut free, &candidate.demand, 1.0);
+            victims.push(candidate);
+        }
+        if !fits(demand, &free) {
+            continue;
+        }
+
+        // Put back the highest-priority victims the demand does not need
+        for i in (0..victims.len()).rev() {
+            let mut without = free;
+            add(&mut without, &victims[i].demand, -1.0);
+            if fits(demand, &without) {
+                free = without;
+                victims.remove(i);
+            }
+        }
+
+        let top = victims.iter().map(|v| v.priority).max().unwrap_or(i32::MIN);
+        let better = match &best {
+            Some((count, best_top, _)) => (victims.len(), top) < (*count, *best_top),
+            None => true,
+        };
+        if better {
+            let plan = PreemptionPlan {
+                node_id: node.node_id,
+                victims: victims.iter().map(|v| v.workload_id.clone()).collect(),
+            };
+            best = Some((victims.len(), top, plan));
+        }
+    }
+
+    best.map(|(_, _, plan)| plan)
+}
+
+fn fits(demand: &ResourceTotals, free: &ResourceTotals) -> bool {
+    demand.cpu_cores <= free.cpu_cores
+        && demand.memory_mb <= free.memory_mb
+        && demand.gpus <= free.gpus
+}
+
+fn add(total: &mut ResourceTotals, demand: &ResourceTotals, sign: f64) {
+    total.cpu_cores += demand.cpu_cores * sign;
+    total.memory_mb += demand.memory_mb * sign;
+    total.gpus += demand.gpus * sign;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(cpu_cores: f64) -> ResourceTotals {
+        ResourceTotals { cpu_cores, memory_mb: 1024.0, gpus: 0.0 }
+    }
+
+    fn candidate(name: &str, node_id: NodeId, priority: i32, cpu: f64, tier: &str) -> PreemptionCandidate {
+        PreemptionCandidate {
+            workload_id: ResourceId::new("default", name, "workload"),
+            node_id,
+            priority,
+            demand: ResourceTotals { cpu_cores: cpu, memory_mb: 0.0, gpus: 0.0 },
+            labels: HashMap::from([("tier".to_string(), tier.to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_select_victims() {
+        let node = NodeHeadroom { node_id: NodeId::random(), capacity: totals(8.0), free: totals(1.0) };
+        let candidates = vec![
+            candidate("batch-a", node.node_id, 0, 1.0, "batch"),
+            candidate("batch-b", node.node_id, 0, 4.0, "batch"),
+            candidate("web", node.node_id, 10, 2.0, "web"),
+            candidate("db", node.node_id, 100, 4.0, "db"),
+        ];
+
+        // The one large batch workload is enough; the small one is put back
+        let plan = select_victims(50, &totals(4.0), &[node.clone()], &candidates, &[], &[]).unwrap();
+        assert_eq!(plan.node_id, node.node_id);
+        assert_eq!(plan.victims, vec![ResourceId::new("default", "batch-b", "workload")]);
+
+        // A budget protecting batch leaves only the web workload, which is not enough
+        let budgets = vec![DisruptionBudget {
+            name: "batch".to_string(),
+            selector: HashMap::from([("tier".to_string(), "batch".to_string())]),
+            max_unavailable: 0,
+        }];
+        assert!(select_victims(50, &totals(4.0), &[node.clone()], &candidates, &budgets, &[0]).is_none());
+
+        // Nothing below the preemptor's priority frees enough room
+        assert!(select_victims(5, &totals(7.0), &[node], &candidates, &[], &[]).is_none());
+    }
+}
@@ -0,0 +1,237 @@
+//! Readiness gates on external conditions
+//!
+//! A workload can declare conditions outside the cluster that it depends
+//! on, such as a database being reachable, a volume restored or a
+//! certificate issued. Each gate applies at one phase: placement gates hold
+//! the workload until the condition is true, and ready gates let it be
+//! placed but keep it from being marked ready. Conditions are checked by
+//! controllers registered per condition name; a condition with no
+//! controller stays unknown, which never opens a gate.
+
+use crate::workload::Workload;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Condition checked by [`TcpReachable`]; the gate's `address` parameter is `host:port`
+pub const TCP_REACHABLE_CONDITION: &str = "nexus.io/tcp-reachable";
+
+/// When a readiness gate applies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GatePhase {
+    /// The workload is held until the condition is true
+    Placement,
+    /// The workload is placed but not marked ready until the condition is true
+    #[default]
+    Ready,
+}
+
+/// An external condition a workload waits for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadinessGate {
+    /// Condition name; selects the controller that checks it
+    pub condition: String,
+    /// Controller-specific parameters, such as the address to probe
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    #[serde(default)]
+    pub phase: GatePhase,
+}
+
+impl ReadinessGate {
+    pub fn new(condition: impl Into<String>, phase: GatePhase) -> Self {
+        Self {
+            condition: condition.into(),
+            params: HashMap::new(),
+            phase,
+        }
+    }
+
+    pub fn with_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Last known state of a condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConditionStatus {
+    True,
+    False { reason: String },
+    /// Could not be determined, for example no controller is registered
+    Unknown { reason: String },
+}
+
+impl ConditionStatus {
+    pub fn is_true(&self) -> bool {
+        matches!(self, ConditionStatus::True)
+    }
+}
+
+/// Result of checking one gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateCondition {
+    pub condition: String,
+    pub phase: GatePhase,
+    pub status: ConditionStatus,
+    pub checked_at: SystemTime,
+}
+
+/// Checks one kind of external condition
+#[async_trait]
+pub trait ConditionController: Send + Sync {
+    /// Condition name this controller checks
+    fn condition(&self) -> &str;
+
+    /// Current status of the condition for a workload's gate
+    async fn check(&self, workload: &Workload, gate: &ReadinessGate) -> ConditionStatus;
+}
+
+/// Controllers by condition name
+#[derive(Clone, Default)]
+pub struct ConditionRegistry {
+    controllers: HashMap<String, Arc<dyn ConditionController>>,
+}
+
+impl ConditionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a controller, replacing any for the same condition
+    pub fn register(&mut self, controller: Arc<dyn ConditionController>) {
+        self.controllers.insert(controller.condition().to_string(), controller);
+    }
+
+    /// Check the workload's gates for one phase
+    pub async fn evaluate(&self, workload: &Workload, phase: GatePhase) -> Vec<GateCondition> {
+        let mut conditions = Vec::new();
+        for gate in workload.spec.readiness_gates.iter().filter(|gate| gate.phase == phase) {
+            let status = match self.controllers.get(&gate.condition) {
+                Some(controller) => controller.check(workload, gate).await,
+                None => ConditionStatus::Unknown {
+                    reason: format!("no controller registered for condition '{}'", gate.condition),
+                },
+            };
+            conditions.push(GateCondition {
+                condition: gate.condition.clone(),
+                phase,
+                status,
+                checked_at: SystemTime::now(),
+            });
+        }
+        conditions
+    }
+}
+
+/// Names of the conditions that are not yet true
+pub fn unmet(conditions: &[GateCondition]) -> Vec<String> {
+    conditions
+        .iter()
+        .filter(|c| !c.status.is_true())
+        .map(|c| c.condition.clone())
+        .collect()
+}
+
+/// True once a TCP connection to the gate's `address` succeeds
+#[derive(Debug, Clone)]
+pub struct TcpReachable {
+    timeout: Duration,
+}
+
+impl TcpReachable {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl Default for TcpReachable {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(2))
+    }
+}
+
+#[async_trait]
+impl ConditionController for TcpReachable {
+    fn condition(&self) -> &str {
+        TCP_REACHABLE_CONDITION
+    }
+
+    async fn check(&self, _workload: &Workload, gate: &ReadinessGate) -> ConditionStatus {
+        let Some(address) = gate.params.get("address") else {
+            return ConditionStatus::Unknown { reason: "gate has no 'address' parameter".to_string() };
+        };
+        match tokio::time::timeout(self.timeout, tokio::net::TcpStream::connect(address.as_str())).await {
+            Ok(Ok(_)) => ConditionStatus::True,
+            Ok(Err(e)) => ConditionStatus::False { reason: format!("{} unreachable: {}", address, e) },
+            Err(_) => ConditionStatus::False { reason: format!("{} timed out after {:?}", address, self.timeout) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_SCHEDULER_NAME;
+    use crate::workload::{WorkloadSpec, WorkloadType};
+    use nexus_shared::ResourceId;
+
+    fn workload(gates: Vec<ReadinessGate>) -> Workload {
+        let id = ResourceId::new("default", "api", "workload");
+        Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Interactive,
+            priority: 0,
+            spec: WorkloadSpec {
+                id,
+                name: "api".to_string(),
+                image: "api:latest".to_string(),
+                replicas: 1,
+                gpus: 0,
+                resources: Default::default(),
+                labels: HashMap::new(),
+                workload_type: WorkloadType::Interactive,
+                command: Vec::new(),
+                environment: HashMap::new(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: Default::default(),
+                scheduling_gates: Vec::new(),
+                readiness_gates: gates,
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_gates() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let workload = workload(vec![
+            ReadinessGate::new(TCP_REACHABLE_CONDITION, GatePhase::Placement).with_param("address", address),
+            ReadinessGate::new("example.com/certificate-issued", GatePhase::Ready),
+        ]);
+
+        let mut registry = ConditionRegistry::new();
+        registry.register(Arc::new(TcpReachable::default()));
+
+        let placement = registry.evaluate(&workload, GatePhase::Placement).await;
+        assert_eq!(placement.len(), 1);
+        assert!(unmet(&placement).is_empty());
+
+        // No controller for the certificate: unknown keeps the gate closed
+        let ready = registry.evaluate(&workload, GatePhase::Ready).await;
+        assert!(matches!(ready[0].status, ConditionStatus::Unknown { .. }));
+        assert_eq!(unmet(&ready), vec!["example.com/certificate-issued".to_string()]);
+
+        drop(listener);
+        let closed = registry.evaluate(&workload, GatePhase::Placement).await;
+        assert!(matches!(closed[0].status, ConditionStatus::False { .. }));
+    }
+}
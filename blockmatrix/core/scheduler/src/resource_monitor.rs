@@ -1,7 +1,9 @@
 //! Resource monitoring module
 
 use nexus_shared::{ResourceId, NodeId};
+use nexus_runtime::StorageCapacity;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug)]
 pub struct ResourceMonitor {
@@ -47,13 +49,22 @@ pub struct ResourceUsage {
     pub disk_usage: u64,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct NodeResources {
     pub node_id: Option<NodeId>,
     pub cpu_total: f64,
     pub cpu_available: f64,
+    /// Bytes
     pub memory_total: u64,
+    /// Bytes
     pub memory_available: u64,
+    #[serde(default)]
+    pub gpu_total: u32,
+    #[serde(default)]
+    pub gpu_available: u32,
+    /// Volume storage by storage class
+    #[serde(default)]
+    pub storage: BTreeMap<String, StorageCapacity>,
 }
 
 impl ResourceMonitor {
@@ -0,0 +1,304 @@
+//! Shadow scheduling
+//!
+//! A candidate placement strategy can run next to the active one without
+//! placing anything. For every workload the active strategy places, the
+//! shadow records the node the candidate would have chosen and the predicted
+//! outcome of both choices on the cluster as it was at that moment. The
+//! comparison report shows how often the two agree and how the candidate
+//! would have loaded nodes, so a new strategy can be judged on live traffic
+//! before it is switched on.
+
+use crate::capacity::ResourceTotals;
+use crate::placement::{NodeHeadroom, PlacementStrategy};
+use crate::workload::Workload;
+use nexus_shared::{NodeId, ResourceId};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+/// Shadow scheduling configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowConfig {
+    pub enabled: bool,
+    /// Strategy evaluated against the active one
+    pub strategy: PlacementStrategy,
+    /// Decisions kept for the report; the oldest are dropped first
+    pub max_decisions: usize,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strategy: PlacementStrategy::new(crate::placement::SPREAD),
+            max_decisions: 10_000,
+        }
+    }
+}
+
+/// Predicted effect of placing a workload on a node
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PredictedOutcome {
+    /// CPU utilization of the node after placement
+    pub cpu_utilization: f64,
+    /// Memory utilization of the node after placement
+    pub memory_utilization: f64,
+    /// Whether the workload fits in the node's uncommitted capacity
+    pub fits: bool,
+}
+
+impl PredictedOutcome {
+    pub fn predict(node: &NodeHeadroom, demand: &ResourceTotals) -> Self {
+        let utilization = |capacity: f64, free: f64, demand: f64| {
+            if capacity <= 0.0 {
+                1.0
+            } else {
+                (capacity - free + demand) / capacity
+            }
+        };
+        Self {
+            cpu_utilization: utilization(node.capacity.cpu_cores, node.free.cpu_cores, demand.cpu_cores),
+            memory_utilization: utilization(node.capacity.memory_mb, node.free.memory_mb, demand.memory_mb),
+            fits: node.fits(demand),
+        }
+    }
+}
+
+/// What the active and candidate strategies did with one workload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowDecision {
+    pub workload_id: ResourceId,
+    pub recorded_at: SystemTime,
+    pub active_node: NodeId,
+    pub active_outcome: PredictedOutcome,
+    /// `None` when the candidate found no node
+    pub candidate_node: Option<NodeId>,
+    pub candidate_outcome: Option<PredictedOutcome>,
+}
+
+impl ShadowDecision {
+    pub fn agrees(&self) -> bool {
+        self.candidate_node == Some(self.active_node)
+    }
+}
+
+/// Comparison of the candidate strategy with the active one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShadowReport {
+    pub active_strategy: String,
+    pub candidate_strategy: String,
+    pub decisions: usize,
+    /// Decisions where both strategies chose the same node
+    pub agreements: usize,
+    /// Workloads the candidate could not place
+    pub candidate_unplaced: usize,
+    pub active_mean_cpu_utilization: f64,
+    /// Over the workloads the candidate placed
+    pub candidate_mean_cpu_utilization: f64,
+    pub active_overcommits: usize,
+    pub candidate_overcommits: usize,
+}
+
+impl ShadowReport {
+    pub fn agreement_rate(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.agreements as f64 / self.decisions as f64
+    }
+}
+
+/// Records candidate decisions next to the active placements
+#[derive(Debug)]
+pub struct ShadowScheduler {
+    config: ShadowConfig,
+    decisions: Mutex<VecDeque<ShadowDecision>>,
+}
+
+impl ShadowScheduler {
+    pub fn new(config: ShadowConfig) -> Self {
+        Self {
+            config,
+            decisions: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Record what the candidate would have done with a placed workload
+    ///
+    /// `nodes` is the cluster as the active strategy saw it, before the
+    /// workload was placed. Only the workload's primary node is compared.
+    pub fn record(&self, workload: &Workload, active_node: NodeId, nodes: &[NodeHeadroom]) -> Option<ShadowDecision> {
+        if !self.config.enabled {
+            return None;
+        }
+        let spec = &workload.spec;
+        let replicas = spec.replicas.max(1) as f64;
+        let demand = ResourceTotals {
+            cpu_cores: spec.resources.cpu_cores * replicas,
+            memory_mb: spec.resources.memory_mb as f64 * replicas,
+            gpus: spec.gpus as f64 * replicas,
+        };
+
+        let outcome_on = |node_id: NodeId| {
+            nodes.iter()
+                .find(|node| node.node_id == node_id)
+                .map(|node| PredictedOutcome::predict(node, &demand))
+        };
+        let active_outcome = outcome_on(active_node)?;
+        let candidate_node = self.config.strategy.select_node(&demand, nodes);
+
+        let decision = ShadowDecision {
+            workload_id: spec.id.clone(),
+            recorded_at: SystemTime::now(),
+            active_node,
+            active_outcome,
+            candidate_node,
+            candidate_outcome: candidate_node.and_then(outcome_on),
+        };
+
+        let mut decisions = self.decisions.lock();
+        decisions.push_back(decision.clone());
+        while decisions.len() > self.config.max_decisions {
+            decisions.pop_front();
+        }
+        Some(decision)
+    }
+
+    /// Recorded decisions, oldest first
+    pub fn decisions(&self) -> Vec<ShadowDecision> {
+        self.decisions.lock().iter().cloned().collect()
+    }
+
+    /// Compare the candidate with the active strategy over the recorded decisions
+    pub fn report(&self, active_strategy: &str) -> ShadowReport {
+        let decisions = self.decisions.lock();
+        let mut report = ShadowReport {
+            active_strategy: active_strategy.to_string(),
+            candidate_strategy: self.config.strategy.name.clone(),
+            decisions: decisions.len(),
+            ..Default::default()
+        };
+
+        let mut active_cpu = 0.0;
+        let mut candidate_cpu = 0.0;
+        let mut candidate_placed = 0;
+        for decision in decisions.iter() {
+            if decision.agrees() {
+                report.agreements += 1;
+            }
+            active_cpu += decision.active_outcome.cpu_utilization;
+            if !decision.active_outcome.fits {
+                report.active_overcommits += 1;
+            }
+            match decision.candidate_outcome {
+                Some(outcome) => {
+                    candidate_placed += 1;
+                    candidate_cpu += outcome.cpu_utilization;
+                    if !outcome.fits {
+                        report.candidate_overcommits += 1;
+                    }
+                }
+                None => report.candidate_unplaced += 1,
+            }
+        }
+
+        if report.decisions > 0 {
+            report.active_mean_cpu_utilization = active_cpu / report.decisions as f64;
+        }
+        if candidate_placed > 0 {
+            report.candidate_mean_cpu_utilization = candidate_cpu / candidate_placed as f64;
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_SCHEDULER_NAME;
+    use crate::placement::BEST_FIT;
+    use crate::workload::{WorkloadSpec, WorkloadType};
+    use std::collections::HashMap;
+
+    fn workload(name: &str, cpu: f64) -> Workload {
+        let id = ResourceId::new("default", name, "workload");
+        Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Interactive,
+            priority: 0,
+            spec: WorkloadSpec {
+                id,
+                name: name.to_string(),
+                image: "nginx:latest".to_string(),
+                replicas: 1,
+                gpus: 0,
+                resources: nexus_runtime::ResourceQuotas {
+                    cpu_cores: cpu,
+                    memory_mb: 512,
+                    ..Default::default()
+                },
+                labels: HashMap::new(),
+                workload_type: WorkloadType::Interactive,
+                command: Vec::new(),
+                environment: HashMap::new(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: Default::default(),
+                scheduling_gates: Vec::new(),
+                readiness_gates: Vec::new(),
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: Default::default(),
+            conditions: Default::default(),
+        }
+    }
+
+    fn node(free_cpu: f64) -> NodeHeadroom {
+        NodeHeadroom {
+            node_id: NodeId::random(),
+            capacity: ResourceTotals { cpu_cores: 8.0, memory_mb: 8192.0, gpus: 0.0 },
+            free: ResourceTotals { cpu_cores: free_cpu, memory_mb: 8192.0, gpus: 0.0 },
+        }
+    }
+
+    #[test]
+    fn test_shadow_report() {
+        let shadow = ShadowScheduler::new(ShadowConfig { enabled: true, ..Default::default() });
+        let busy = node(2.0);
+        let idle = node(8.0);
+        let nodes = vec![busy.clone(), idle.clone()];
+
+        // The active best-fit choice packs the busy node; the spread candidate picks the idle one
+        let packed = workload("api", 2.0);
+        let active = PlacementStrategy::default().select_node(&ResourceTotals { cpu_cores: 2.0, memory_mb: 512.0, gpus: 0.0 }, &nodes);
+        assert_eq!(active, Some(busy.node_id));
+        let decision = shadow.record(&packed, busy.node_id, &nodes).unwrap();
+        assert_eq!(decision.candidate_node, Some(idle.node_id));
+        assert_eq!(decision.active_outcome.cpu_utilization, 1.0);
+
+        // Too large for the busy node: both choose the idle one
+        shadow.record(&workload("batch", 4.0), idle.node_id, &nodes).unwrap();
+
+        // Fits nowhere for the candidate; the active placement overcommits
+        shadow.record(&workload("huge", 10.0), idle.node_id, &nodes).unwrap();
+
+        let report = shadow.report(BEST_FIT);
+        assert_eq!(report.decisions, 3);
+        assert_eq!(report.agreements, 1);
+        assert_eq!(report.candidate_unplaced, 1);
+        assert_eq!(report.active_overcommits, 1);
+        assert_eq!(report.candidate_overcommits, 0);
+        assert!((report.agreement_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((report.candidate_mean_cpu_utilization - 0.375).abs() < 1e-9);
+
+        // Disabled shadows record nothing
+        let disabled = ShadowScheduler::new(ShadowConfig::default());
+        assert!(disabled.record(&packed, busy.node_id, &nodes).is_none());
+    }
+}
@@ -0,0 +1,155 @@
+//! Signed scheduling decisions
+//!
+//! Every placement is signed with the scheduler's TrustChain key and the
+//! signature is kept with the placement record. The scheduler's node ID is
+//! derived from that key, so a component that does not hold the key cannot
+//! produce a decision that verifies under the scheduler's identity; other
+//! nodes and auditors check the signature and then that the signer is a
+//! scheduler they recognise.
+
+use crate::error::{Result, SchedulerError};
+use nexus_networking::dht_security::derive_node_id;
+use nexus_shared::{KeyPair, NodeId, ResourceId};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Domain separator so decision signatures cannot be replayed as other records
+const DECISION_CONTEXT: &[u8] = b"nexus-scheduler-decision-v1";
+
+/// State store key of a workload's signed placement
+pub fn decision_key(workload_id: &ResourceId) -> String {
+    format!("/scheduler/placements/{}", workload_id)
+}
+
+/// A placement decision signed by the scheduler that made it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedDecision {
+    pub workload_id: ResourceId,
+    pub target_node: NodeId,
+    pub replica_nodes: Vec<NodeId>,
+    pub placement_score: f64,
+    /// Unix milliseconds
+    pub scheduled_at: u64,
+    /// Node ID of the signing scheduler, derived from `public_key`
+    pub scheduler: NodeId,
+    pub public_key: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+impl SignedDecision {
+    /// Sign a placement with the scheduler's key
+    pub fn sign(
+        workload_id: ResourceId,
+        target_node: NodeId,
+        replica_nodes: Vec<NodeId>,
+        placement_score: f64,
+        scheduled_at: SystemTime,
+        key_pair: &KeyPair,
+    ) -> Self {
+        let public_key = *key_pair.public_key();
+        let mut decision = Self {
+            workload_id,
+            target_node,
+            replica_nodes,
+            placement_score,
+            scheduled_at: scheduled_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            scheduler: derive_node_id(&public_key),
+            public_key,
+            signature: Vec::new(),
+        };
+        decision.signature = key_pair.sign(&decision.signing_bytes());
+        decision
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let workload_id = self.workload_id.to_string();
+        let mut bytes = Vec::with_capacity(DECISION_CONTEXT.len() + workload_id.len() + 32 * (self.replica_nodes.len() + 2) + 32);
+        bytes.extend_from_slice(DECISION_CONTEXT);
+        bytes.extend_from_slice(&(workload_id.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(workload_id.as_bytes());
+        bytes.extend_from_slice(self.target_node.as_bytes());
+        bytes.extend_from_slice(&(self.replica_nodes.len() as u64).to_be_bytes());
+        for node in &self.replica_nodes {
+            bytes.extend_from_slice(node.as_bytes());
+        }
+        bytes.extend_from_slice(&self.placement_score.to_be_bytes());
+        bytes.extend_from_slice(&self.scheduled_at.to_be_bytes());
+        bytes.extend_from_slice(self.scheduler.as_bytes());
+        bytes
+    }
+
+    /// Verify the signer binding and signature
+    ///
+    /// This proves the decision was made by the holder of `public_key`;
+    /// callers still check that `scheduler` is a scheduler they trust.
+    pub fn verify(&self) -> Result<()> {
+        let invalid = |reason: &str| SchedulerError::InvalidSignature {
+            message: format!("placement of {} by {}: {}", self.workload_id, self.scheduler, reason),
+        };
+
+        if derive_node_id(&self.public_key) != self.scheduler {
+            return Err(invalid("scheduler ID does not match its key"));
+        }
+        if !KeyPair::verify(&self.public_key, &self.signing_bytes(), &self.signature) {
+            return Err(invalid("bad signature"));
+        }
+        Ok(())
+    }
+
+    /// Verify the decision and that it was made by the given scheduler
+    pub fn verify_from(&self, scheduler: &NodeId) -> Result<()> {
+        self.verify()?;
+        if self.scheduler != *scheduler {
+            return Err(SchedulerError::InvalidSignature {
+                message: format!(
+                    "placement of {} signed by {}, expected {}",
+                    self.workload_id, self.scheduler, scheduler
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_decision() {
+        let key_pair = KeyPair::generate().unwrap();
+        let decision = SignedDecision::sign(
+            ResourceId::new("default", "web", "workload"),
+            NodeId::random(),
+            vec![NodeId::random(), NodeId::random()],
+            0.75,
+            SystemTime::now(),
+            &key_pair,
+        );
+
+        assert!(decision.verify().is_ok());
+        assert!(decision.verify_from(&derive_node_id(key_pair.public_key())).is_ok());
+        assert!(decision.verify_from(&NodeId::random()).is_err());
+
+        // Redirected placement
+        let mut forged = decision.clone();
+        forged.target_node = NodeId::random();
+        assert!(forged.verify().is_err());
+
+        // Re-signed by another key but claiming the original scheduler
+        let other = KeyPair::generate().unwrap();
+        let mut impersonated = SignedDecision::sign(
+            decision.workload_id.clone(),
+            NodeId::random(),
+            Vec::new(),
+            1.0,
+            SystemTime::now(),
+            &other,
+        );
+        impersonated.scheduler = decision.scheduler;
+        assert!(impersonated.verify().is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Workload volumes
+//!
+//! A workload's named volumes follow it when it is moved to another node.
+//! Every snapshot the scheduler takes is recorded in the state store as its
+//! volume's latest, and before the workload's new container is created the
+//! latest snapshot of each volume is restored.
+//!
+//! A volume may claim space in a storage class. Nodes report each class's
+//! size and free space in their heartbeats, and a node is only a placement
+//! candidate when every claim of the workload fits in what the node has left
+//! after the claims of the workloads already placed there. A workload whose
+//! volumes fit nowhere fails placement rather than container creation.
+//! Disk scratch declared inline claims space too, in the default storage
+//! class, once for each replica on the node.
+
+use nexus_runtime::{EphemeralMedium, EphemeralVolume, StorageCapacity};
+use nexus_shared::ResourceId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub use nexus_runtime::config::DEFAULT_STORAGE_CLASS;
+
+/// A named volume mounted into a workload's container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadVolume {
+    pub name: String,
+    pub mount_path: String,
+    #[serde(default)]
+    pub readonly: bool,
+    /// Space the volume claims on its node; zero claims none
+    #[serde(default)]
+    pub size_bytes: u64,
+    #[serde(default = "default_storage_class")]
+    pub storage_class: String,
+}
+
+fn default_storage_class() -> String {
+    DEFAULT_STORAGE_CLASS.to_string()
+}
+
+/// State store key of a volume's latest snapshot
+pub fn latest_snapshot_key(volume: &str) -> String {
+    format!("/scheduler/volumes/{}/latest-snapshot", volume)
+}
+
+/// Name of the ownership record of a workload's volume
+///
+/// Volume names are only unique within a workload, so two workloads with a
+/// `data` volume each own a record of their own.
+pub fn volume_object_name(workload: &ResourceId, volume: &str) -> String {
+    format!("{}#{}", workload, volume)
+}
+
+/// Claims of a workload's disk scratch for `replicas` replicas on one node
+///
+/// Each replica gets scratch of its own, so each claims it separately.
+pub fn scratch_claims(workload: &ResourceId, volumes: &[EphemeralVolume], replicas: usize) -> Vec<WorkloadVolume> {
+    (0..replicas)
+        .flat_map(|replica| {
+            volumes.iter().filter(|volume| volume.medium == EphemeralMedium::Disk).map(move |volume| WorkloadVolume {
+                name: format!("{}#{}#{}", workload, replica, volume.name),
+                mount_path: volume.mount_path.clone(),
+                readonly: false,
+                size_bytes: volume.size_limit_bytes,
+                storage_class: DEFAULT_STORAGE_CLASS.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Volume claims of the workloads placed on one node
+///
+/// Workloads sharing a named volume share its claim, so each volume counts once.
+#[derive(Debug, Clone, Default)]
+pub struct NodeVolumes {
+    claims: BTreeMap<String, (String, u64)>,
+}
+
+impl NodeVolumes {
+    pub fn add(&mut self, volumes: &[WorkloadVolume]) {
+        for volume in volumes {
+            let claim = self.claims.entry(volume.name.clone()).or_insert((volume.storage_class.clone(), 0));
+            claim.1 = claim.1.max(volume.size_bytes);
+        }
+    }
+
+    /// Bytes claimed in each storage class
+    pub fn committed(&self) -> BTreeMap<&str, u64> {
+        let mut committed = BTreeMap::new();
+        for (class, size) in self.claims.values() {
+            *committed.entry(class.as_str()).or_default() += size;
+        }
+        committed
+    }
+}
+
+/// A storage class a workload's volumes do not fit in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageShortfall {
+    pub storage_class: String,
+    pub required_bytes: u64,
+    pub free_bytes: u64,
+}
+
+/// The first storage class in which `volumes` do not fit on a node
+///
+/// Free space is the smaller of what the node reports available and its
+/// capacity less the claims already placed there, so claims not yet written
+/// to disk still count. Volumes the node already holds claim nothing more.
+pub fn storage_shortfall(
+    volumes: &[WorkloadVolume],
+    placed: Option<&NodeVolumes>,
+    reported: &BTreeMap<String, StorageCapacity>,
+) -> Option<StorageShortfall> {
+    let mut demand: BTreeMap<&str, u64> = BTreeMap::new();
+    for volume in volumes {
+        let held = placed.and_then(|placed| placed.claims.get(&volume.name)).map(|(_, size)| *size).unwrap_or(0);
+        if volume.size_bytes > held {
+            *demand.entry(volume.storage_class.as_str()).or_default() += volume.size_bytes - held;
+        }
+    }
+
+    let committed = placed.map(NodeVolumes::committed).unwrap_or_default();
+    demand.into_iter().find_map(|(class, required_bytes)| {
+        let capacity = reported.get(class).copied().unwrap_or_default();
+        let unclaimed = capacity.total_bytes.saturating_sub(committed.get(class).copied().unwrap_or(0));
+        let free_bytes = capacity.available_bytes.min(unclaimed);
+        (required_bytes > free_bytes).then(|| StorageShortfall {
+            storage_class: class.to_string(),
+            required_bytes,
+            free_bytes,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(name: &str, class: &str, size_bytes: u64) -> WorkloadVolume {
+        WorkloadVolume {
+            name: name.to_string(),
+            mount_path: format!("/data/{}", name),
+            readonly: false,
+            size_bytes,
+            storage_class: class.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_claims_must_fit_in_what_is_left() {
+        let reported: BTreeMap<String, StorageCapacity> = [
+            ("standard".to_string(), StorageCapacity { total_bytes: 100, available_bytes: 90 }),
+            ("ssd".to_string(), StorageCapacity { total_bytes: 50, available_bytes: 50 }),
+        ]
+        .into();
+        let mut placed = NodeVolumes::default();
+        placed.add(&[volume("db", "standard", 60)]);
+
+        // Capacity less claims is tighter than the reported free space
+        assert!(storage_shortfall(&[volume("logs", "standard", 40)], Some(&placed), &reported).is_none());
+        assert_eq!(
+            storage_shortfall(&[volume("logs", "standard", 41)], Some(&placed), &reported),
+            Some(StorageShortfall { storage_class: "standard".to_string(), required_bytes: 41, free_bytes: 40 })
+        );
+
+        // A volume already on the node claims only its growth; unknown classes have no room
+        assert!(storage_shortfall(&[volume("db", "standard", 100)], Some(&placed), &reported).is_none());
+        assert!(storage_shortfall(&[volume("cache", "ssd", 50)], None, &reported).is_none());
+        assert_eq!(storage_shortfall(&[volume("fast", "nvme", 1)], None, &reported).unwrap().free_bytes, 0);
+        assert!(storage_shortfall(&[volume("scratch", "nvme", 0)], None, &reported).is_none());
+
+        // Disk scratch claims per replica in the default class; tmpfs claims no disk
+        let workload = ResourceId::new("default", "build", "workload");
+        let scratch = [
+            EphemeralVolume { name: "tmp".to_string(), mount_path: "/tmp".to_string(), medium: EphemeralMedium::Disk, size_limit_bytes: 20 },
+            EphemeralVolume { name: "shm".to_string(), mount_path: "/dev/shm".to_string(), medium: EphemeralMedium::Memory, size_limit_bytes: 20 },
+        ];
+        placed.add(&scratch_claims(&workload, &scratch, 2));
+        assert_eq!(placed.committed().get("standard"), Some(&100));
+        assert!(storage_shortfall(&scratch_claims(&workload, &scratch, 1), Some(&placed), &reported).is_some());
+    }
+}
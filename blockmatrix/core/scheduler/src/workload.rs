@@ -1,7 +1,12 @@
 //! Workload definition and management
 
-use nexus_shared::ResourceId;
+use crate::affinity::AffinityRules;
+use crate::config::default_scheduler_name;
+use crate::readiness::ReadinessGate;
+use crate::volumes::WorkloadVolume;
+use nexus_shared::{ApiMeta, ResourceId, StatusConditions};
 use nexus_runtime::resources::ResourceQuotas;
+use nexus_runtime::EphemeralVolume;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -11,6 +16,11 @@ pub struct Workload {
     pub workload_type: WorkloadType,
     pub priority: i32,
     pub spec: WorkloadSpec,
+    #[serde(default)]
+    pub metadata: ApiMeta,
+    /// Scheduler-maintained `Scheduled` and `Ready` conditions
+    #[serde(default)]
+    pub conditions: StatusConditions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,15 +38,38 @@ pub struct WorkloadSpec {
     pub image: String,
     pub replicas: u32,
     pub resources: ResourceQuotas,
+    /// GPUs required per replica
+    #[serde(default)]
+    pub gpus: u32,
     pub labels: HashMap<String, String>,
     pub workload_type: WorkloadType,
     pub command: Vec<String>,
     pub environment: HashMap<String, String>,
     pub working_dir: Option<String>,
+    /// Named volumes, carried over when the workload moves to another node
+    #[serde(default)]
+    pub volumes: Vec<WorkloadVolume>,
+    /// Scratch volumes created at placement and destroyed at termination
+    #[serde(default)]
+    pub ephemeral_volumes: Vec<EphemeralVolume>,
+    /// Node affinity, evaluated against current node labels
+    #[serde(default)]
+    pub affinity: AffinityRules,
+    /// Gates that must all be removed before the workload is placed
+    #[serde(default)]
+    pub scheduling_gates: Vec<String>,
+    /// External conditions the workload waits for before placement or readiness
+    #[serde(default)]
+    pub readiness_gates: Vec<ReadinessGate>,
+    /// Scheduler instance responsible for placing the workload
+    #[serde(default = "default_scheduler_name")]
+    pub scheduler_name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WorkloadStatus {
+    /// Waiting on scheduling gates
+    Held,
     Pending,
     Running,
     Completed,
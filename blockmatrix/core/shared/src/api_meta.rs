@@ -0,0 +1,187 @@
+//! Standard metadata and status conditions for API objects
+//!
+//! Every user-facing object (services, workloads, nodes) carries an
+//! `ApiMeta` and a `StatusConditions`. `resource_version` changes on every
+//! write, `generation` only when the desired state changes, and controllers
+//! record the generation they acted on in `observed_generation`. A client
+//! knows its update has been processed once `observed_generation` catches up
+//! with `generation`.
+
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// Well-known condition types
+pub mod condition_types {
+    /// The object is healthy and serving
+    pub const READY: &str = "Ready";
+    /// A workload has been placed on a node
+    pub const SCHEDULED: &str = "Scheduled";
+    /// A node accepts new workloads
+    pub const SCHEDULABLE: &str = "Schedulable";
+    /// A service runs its desired number of replicas
+    pub const AVAILABLE: &str = "Available";
+}
+
+/// Versioning metadata attached to an API object
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiMeta {
+    /// Incremented on every write, spec or status
+    #[serde(default)]
+    pub resource_version: u64,
+    /// Incremented whenever the desired state (spec) changes
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl ApiMeta {
+    /// Metadata for a freshly created object
+    pub fn created() -> Self {
+        Self { resource_version: 1, generation: 1 }
+    }
+
+    /// Record a change to the desired state
+    pub fn spec_changed(&mut self) {
+        self.generation += 1;
+        self.resource_version += 1;
+    }
+
+    /// Record a change that leaves the desired state untouched
+    pub fn status_changed(&mut self) {
+        self.resource_version += 1;
+    }
+
+    /// Whether a client's `resource_version` precondition holds; `None` always does
+    pub fn matches(&self, expected: Option<u64>) -> bool {
+        expected.map_or(true, |version| version == self.resource_version)
+    }
+}
+
+/// Tri-state value of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionStatus {
+    True,
+    False,
+    Unknown,
+}
+
+/// A single observed condition, e.g. `Ready` or `Scheduled`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    #[serde(rename = "type")]
+    pub condition_type: String,
+    pub status: ConditionStatus,
+    /// Machine-readable reason for the last transition
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub message: String,
+    /// When `status` last changed value
+    pub last_transition_time: SystemTime,
+    /// Object generation the condition was computed against
+    #[serde(default)]
+    pub observed_generation: u64,
+}
+
+/// Controller-maintained status shared by all API objects
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusConditions {
+    /// Latest generation the owning controller has acted on
+    #[serde(default)]
+    pub observed_generation: u64,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+impl StatusConditions {
+    /// Look up a condition by type
+    pub fn get(&self, condition_type: &str) -> Option<&Condition> {
+        self.conditions.iter().find(|c| c.condition_type == condition_type)
+    }
+
+    /// Whether the condition is present and true
+    pub fn is_true(&self, condition_type: &str) -> bool {
+        self.get(condition_type).map_or(false, |c| c.status == ConditionStatus::True)
+    }
+
+    /// Set a condition observed at `generation`
+    ///
+    /// `last_transition_time` only moves when the status value changes, so
+    /// repeated reconciles with the same outcome keep the original timestamp.
+    /// Returns true if the status value changed.
+    pub fn set(
+        &mut self,
+        condition_type: &str,
+        status: ConditionStatus,
+        reason: impl Into<String>,
+        message: impl Into<String>,
+        generation: u64,
+    ) -> bool {
+        let reason = reason.into();
+        let message = message.into();
+
+        if let Some(existing) = self.conditions.iter_mut().find(|c| c.condition_type == condition_type) {
+            let transitioned = existing.status != status;
+            if transitioned {
+                existing.status = status;
+                existing.last_transition_time = SystemTime::now();
+            }
+            existing.reason = reason;
+            existing.message = message;
+            existing.observed_generation = generation;
+            return transitioned;
+        }
+
+        self.conditions.push(Condition {
+            condition_type: condition_type.to_string(),
+            status,
+            reason,
+            message,
+            last_transition_time: SystemTime::now(),
+            observed_generation: generation,
+        });
+        true
+    }
+
+    /// Mark `generation` as fully processed by the controller
+    pub fn observe(&mut self, generation: u64) {
+        self.observed_generation = self.observed_generation.max(generation);
+    }
+
+    /// Whether the controller has caught up with the object's latest spec
+    pub fn is_current(&self, meta: &ApiMeta) -> bool {
+        self.observed_generation >= meta.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_tracking_and_transitions() {
+        let mut meta = ApiMeta::created();
+        let mut status = StatusConditions::default();
+        assert!(!status.is_current(&meta));
+
+        assert!(status.set("Ready", ConditionStatus::False, "Pending", "", meta.generation));
+        let first = status.get("Ready").unwrap().last_transition_time;
+        assert!(!status.set("Ready", ConditionStatus::False, "StillPending", "", meta.generation));
+        assert_eq!(status.get("Ready").unwrap().last_transition_time, first);
+        assert_eq!(status.get("Ready").unwrap().reason, "StillPending");
+
+        status.observe(meta.generation);
+        assert!(status.is_current(&meta));
+
+        meta.status_changed();
+        assert!(status.is_current(&meta));
+        assert!(meta.matches(Some(2)) && meta.matches(None) && !meta.matches(Some(1)));
+        meta.spec_changed();
+        assert_eq!(meta, ApiMeta { resource_version: 3, generation: 2 });
+        assert!(!status.is_current(&meta));
+
+        assert!(status.set("Ready", ConditionStatus::True, "Running", "", meta.generation));
+        status.observe(meta.generation);
+        assert!(status.is_true("Ready"));
+        assert!(status.is_current(&meta));
+    }
+}
@@ -4,6 +4,10 @@ use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv6Addr};
 use std::time::Duration;
 
+use crate::crypto::{AlgorithmUse, CryptoPolicy};
+use crate::keystore::KeyStoreConfig;
+use crate::validation::{Validate, ValidationReport};
+
 /// Global configuration for Nexus core
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NexusConfig {
@@ -119,6 +123,14 @@ pub struct SecurityConfig {
     
     /// Encryption key derivation rounds
     pub key_derivation_rounds: u32,
+    
+    /// Where the node's signing key is generated and kept
+    #[serde(default)]
+    pub node_key: KeyStoreConfig,
+    
+    /// Algorithms and key sizes allowed cluster-wide
+    #[serde(default)]
+    pub crypto_policy: CryptoPolicy,
 }
 
 impl Default for SecurityConfig {
@@ -131,6 +143,8 @@ impl Default for SecurityConfig {
             cert_rotation_hours: 24,
             encrypt_at_rest: true,
             key_derivation_rounds: 100_000,
+            node_key: KeyStoreConfig::default(),
+            crypto_policy: CryptoPolicy::default(),
         }
     }
 }
@@ -248,19 +262,73 @@ impl NexusConfig {
     
     /// Validate configuration
     pub fn validate(&self) -> Result<(), String> {
+        self.validate_config()
+            .into_result()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Validate for NexusConfig {
+    fn validate_config(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        
         if self.transport.port == 0 {
-            return Err("Transport port cannot be zero".to_string());
+            report.error("transport.port", "cannot be zero");
         }
         
         if self.transport.max_connections == 0 {
-            return Err("Maximum connections must be greater than zero".to_string());
+            report.error("transport.max_connections", "must be greater than zero");
+        }
+        
+        if self.transport.keep_alive_ms >= self.transport.connection_timeout_ms {
+            report.error(
+                "transport.keep_alive_ms",
+                format!(
+                    "keep-alive ({}ms) must be shorter than the connection timeout ({}ms) or idle connections are dropped",
+                    self.transport.keep_alive_ms, self.transport.connection_timeout_ms
+                ),
+            );
         }
         
         if self.storage.max_size_mb == 0 {
-            return Err("Storage max size must be greater than zero".to_string());
+            report.error("storage.max_size_mb", "must be greater than zero");
         }
         
-        Ok(())
+        if !(0.0..=1.0).contains(&self.storage.compaction_threshold) {
+            report.error("storage.compaction_threshold", "must be between 0.0 and 1.0");
+        }
+        
+        if !self.storage.enable_wal && !matches!(self.storage.backend, StorageBackend::Memory) {
+            report.warning("storage.enable_wal", "disabling the WAL risks losing state on crash");
+        }
+        
+        if self.security.encrypt_at_rest && self.security.key_derivation_rounds < 10_000 {
+            report.warning(
+                "security.key_derivation_rounds",
+                "fewer than 10000 rounds weakens at-rest encryption keys",
+            );
+        }
+        
+        let policy = &self.security.crypto_policy;
+        if let Err(e) = policy.check(self.security.node_key.backend.algorithm().into()) {
+            report.error("security.node_key", format!("node key algorithm is refused: {}", e));
+        }
+        for (usage, name) in [
+            (AlgorithmUse::Signature, "signature"),
+            (AlgorithmUse::Cipher, "cipher"),
+            (AlgorithmUse::KeyExchange, "key exchange"),
+        ] {
+            if policy.allowed(usage).is_empty() {
+                report.error("security.crypto_policy", format!("allows no {} algorithm, so no handshake can succeed", name));
+            }
+        }
+        
+        if !["trace", "debug", "info", "warn", "error"].contains(&self.logging.level.as_str()) {
+            report.error("logging.level", format!("unknown log level '{}'", self.logging.level));
+        }
+        
+        report
     }
 }
 
@@ -2,6 +2,8 @@
 
 use ed25519_dalek::{Signer, Verifier, Signature, SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use rand::rngs::OsRng;
 
@@ -37,6 +39,30 @@ impl KeyPair {
         })
     }
 
+    /// Load the key pair whose seed is stored at `path`
+    ///
+    /// A missing file is created with a new seed, readable only by its
+    /// owner, so the node keeps the same identity across restarts.
+    pub fn load_or_generate(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if path.exists() {
+            let seed: [u8; 32] = std::fs::read(path)?
+                .try_into()
+                .map_err(|_| format!("{} does not hold a 32-byte key seed", path.display()))?;
+            return Self::from_bytes(&seed);
+        }
+
+        let key_pair = Self::generate()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(&key_pair.signing_key.to_bytes())?;
+        Ok(key_pair)
+    }
+
     /// Get the public key
     pub fn public_key(&self) -> &[u8; 32] {
         &self.public_key
@@ -77,6 +103,175 @@ pub fn random_bytes(len: usize) -> Vec<u8> {
     bytes
 }
 
+/// Wraps data keys under a cluster-wide key, so a wrapped key can be stored
+/// beside the data it protects and unwrapped by any node of the cluster
+pub trait KeyWrapper: Send + Sync {
+    fn wrap_key<'a>(&'a self, key: &'a [u8]) -> futures::future::BoxFuture<'a, crate::Result<Vec<u8>>>;
+
+    fn unwrap_key<'a>(&'a self, wrapped: &'a [u8]) -> futures::future::BoxFuture<'a, crate::Result<Vec<u8>>>;
+}
+
+/// Algorithms a crypto policy rules on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// EdDSA over Curve25519 (FIPS 186-5)
+    Ed25519,
+    EcdsaP256Sha256,
+    EcdsaP384Sha384,
+    /// RSA-PSS with a modulus of at least 2048 bits
+    RsaPss,
+    /// RSA PKCS#1 v1.5 with a modulus of at least 2048 bits
+    RsaPkcs1,
+    Aes128Gcm,
+    Aes256Gcm,
+    ChaCha20Poly1305,
+    X25519,
+    EcdheP256,
+    EcdheP384,
+}
+
+/// What an algorithm is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlgorithmUse {
+    Signature,
+    Cipher,
+    KeyExchange,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 11] = [
+        Algorithm::Ed25519,
+        Algorithm::EcdsaP256Sha256,
+        Algorithm::EcdsaP384Sha384,
+        Algorithm::RsaPss,
+        Algorithm::RsaPkcs1,
+        Algorithm::Aes128Gcm,
+        Algorithm::Aes256Gcm,
+        Algorithm::ChaCha20Poly1305,
+        Algorithm::X25519,
+        Algorithm::EcdheP256,
+        Algorithm::EcdheP384,
+    ];
+
+    pub fn usage(self) -> AlgorithmUse {
+        match self {
+            Algorithm::Ed25519
+            | Algorithm::EcdsaP256Sha256
+            | Algorithm::EcdsaP384Sha384
+            | Algorithm::RsaPss
+            | Algorithm::RsaPkcs1 => AlgorithmUse::Signature,
+            Algorithm::Aes128Gcm | Algorithm::Aes256Gcm | Algorithm::ChaCha20Poly1305 => AlgorithmUse::Cipher,
+            Algorithm::X25519 | Algorithm::EcdheP256 | Algorithm::EcdheP384 => AlgorithmUse::KeyExchange,
+        }
+    }
+
+    /// Security strength in bits, as NIST SP 800-57 rates the key size
+    pub fn security_bits(self) -> u32 {
+        match self {
+            Algorithm::RsaPss | Algorithm::RsaPkcs1 => 112,
+            Algorithm::Ed25519 | Algorithm::EcdsaP256Sha256 | Algorithm::X25519 | Algorithm::EcdheP256 => 128,
+            Algorithm::Aes128Gcm => 128,
+            Algorithm::EcdsaP384Sha384 | Algorithm::EcdheP384 => 192,
+            Algorithm::Aes256Gcm | Algorithm::ChaCha20Poly1305 => 256,
+        }
+    }
+
+    /// Whether FIPS 140-3 modules may use the algorithm in approved mode
+    pub fn is_fips_approved(self) -> bool {
+        !matches!(self, Algorithm::ChaCha20Poly1305 | Algorithm::X25519)
+    }
+}
+
+/// Baseline set of algorithms a policy starts from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CryptoProfile {
+    /// Every supported algorithm
+    #[default]
+    Standard,
+    /// FIPS-approved algorithms only
+    Fips,
+}
+
+/// Cluster-wide restriction on algorithms and key sizes
+///
+/// Every node checks its own keys against the policy at start-up and offers
+/// and accepts only allowed cipher suites, key exchange groups and signature
+/// schemes in handshakes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CryptoPolicy {
+    pub profile: CryptoProfile,
+    /// Algorithms refused even if the profile allows them
+    #[serde(default)]
+    pub denied: Vec<Algorithm>,
+    /// Smallest security strength, in bits, of any allowed algorithm
+    pub min_security_bits: u32,
+}
+
+impl Default for CryptoPolicy {
+    fn default() -> Self {
+        Self {
+            profile: CryptoProfile::Standard,
+            denied: Vec::new(),
+            min_security_bits: 112,
+        }
+    }
+}
+
+impl CryptoPolicy {
+    /// FIPS-approved algorithms only
+    pub fn fips() -> Self {
+        Self { profile: CryptoProfile::Fips, ..Self::default() }
+    }
+
+    pub fn allows(&self, algorithm: Algorithm) -> bool {
+        (self.profile != CryptoProfile::Fips || algorithm.is_fips_approved())
+            && algorithm.security_bits() >= self.min_security_bits
+            && !self.denied.contains(&algorithm)
+    }
+
+    /// Fail unless the policy allows the algorithm
+    pub fn check(&self, algorithm: Algorithm) -> crate::Result<()> {
+        if self.allows(algorithm) {
+            Ok(())
+        } else {
+            Err(crate::NexusError::Config(format!(
+                "{:?} is not allowed by the {:?} crypto policy (minimum {} bits)",
+                algorithm, self.profile, self.min_security_bits
+            )))
+        }
+    }
+
+    /// Allowed algorithms for one use, in order of preference
+    pub fn allowed(&self, usage: AlgorithmUse) -> Vec<Algorithm> {
+        Algorithm::ALL
+            .into_iter()
+            .filter(|algorithm| algorithm.usage() == usage && self.allows(*algorithm))
+            .collect()
+    }
+
+    /// The policy and what it allows, for status reporting
+    pub fn report(&self) -> CryptoPolicyReport {
+        CryptoPolicyReport {
+            policy: self.clone(),
+            signatures: self.allowed(AlgorithmUse::Signature),
+            ciphers: self.allowed(AlgorithmUse::Cipher),
+            key_exchange: self.allowed(AlgorithmUse::KeyExchange),
+        }
+    }
+}
+
+/// Active crypto policy as reported by the system status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CryptoPolicyReport {
+    pub policy: CryptoPolicy,
+    pub signatures: Vec<Algorithm>,
+    pub ciphers: Vec<Algorithm>,
+    pub key_exchange: Vec<Algorithm>,
+}
+
 /// Message authentication with timestamp and nonce
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthenticatedMessage {
@@ -134,6 +329,71 @@ impl AuthenticatedMessage {
     }
 }
 
+/// Signature over a payload that travels beside it rather than wrapping it
+///
+/// The signature binds the payload's hash to the audience it was meant for,
+/// the signer's name, a timestamp and a nonce, so it cannot be replayed
+/// against another service or passed off as another signer's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetachedSignature {
+    /// Name of the signing identity
+    pub signer: String,
+    /// Signer's Ed25519 public key
+    pub public_key: [u8; 32],
+    /// Seconds since the Unix epoch at signing
+    pub timestamp: u64,
+    pub nonce: [u8; 16],
+    pub signature: Vec<u8>,
+}
+
+impl DetachedSignature {
+    /// Prefix of every signed message, so the signature means nothing elsewhere
+    const CONTEXT: &'static [u8] = b"nexus-detached-v1:";
+
+    /// Sign a payload for one audience
+    pub fn sign(payload: &[u8], audience: &str, signer: &str, key_pair: &KeyPair) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let mut nonce = [0u8; 16];
+        nonce.copy_from_slice(&random_bytes(16));
+
+        let mut signature = Self {
+            signer: signer.to_string(),
+            public_key: *key_pair.public_key(),
+            timestamp,
+            nonce,
+            signature: Vec::new(),
+        };
+        signature.signature = key_pair.sign(&signature.signed_message(payload, audience));
+        signature
+    }
+
+    /// Verify the signature over a payload for an audience
+    pub fn verify(&self, payload: &[u8], audience: &str) -> bool {
+        KeyPair::verify(&self.public_key, &self.signed_message(payload, audience), &self.signature)
+    }
+
+    /// Hash of the signed payload, for keeping as evidence without the payload
+    pub fn payload_hash(payload: &[u8]) -> [u8; 32] {
+        hash(payload)
+    }
+
+    fn signed_message(&self, payload: &[u8], audience: &str) -> Vec<u8> {
+        // Length prefixes keep audience and signer from running into each other
+        let mut message = Self::CONTEXT.to_vec();
+        for field in [audience.as_bytes(), self.signer.as_bytes()] {
+            message.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            message.extend_from_slice(field);
+        }
+        message.extend_from_slice(&self.timestamp.to_be_bytes());
+        message.extend_from_slice(&self.nonce);
+        message.extend_from_slice(&Self::payload_hash(payload));
+        message
+    }
+}
+
 /// Certificate validation helpers
 pub mod cert {
     use super::*;
@@ -239,6 +499,37 @@ mod tests {
         assert_eq!(msg.payload, payload);
     }
     
+    #[test]
+    fn test_detached_signature() {
+        let key_pair = KeyPair::generate().unwrap();
+        let payload = b"transfer 100".to_vec();
+
+        let signature = DetachedSignature::sign(&payload, "payments", "billing", &key_pair);
+        assert!(signature.verify(&payload, "payments"));
+        assert!(!signature.verify(b"transfer 900", "payments"));
+        assert!(!signature.verify(&payload, "ledger"));
+
+        let mut impostor = signature.clone();
+        impostor.signer = "treasury".to_string();
+        assert!(!impostor.verify(&payload, "payments"));
+    }
+    
+    #[test]
+    fn test_crypto_policy() {
+        let standard = CryptoPolicy::default();
+        assert!(Algorithm::ALL.iter().all(|algorithm| standard.allows(*algorithm)));
+        
+        let fips = CryptoPolicy::fips();
+        assert!(fips.allows(Algorithm::EcdsaP256Sha256));
+        assert!(fips.check(Algorithm::ChaCha20Poly1305).is_err());
+        assert_eq!(fips.allowed(AlgorithmUse::KeyExchange), vec![Algorithm::EcdheP256, Algorithm::EcdheP384]);
+        
+        let strict = CryptoPolicy { min_security_bits: 192, denied: vec![Algorithm::Aes256Gcm], ..CryptoPolicy::fips() };
+        let report = strict.report();
+        assert_eq!(report.signatures, vec![Algorithm::EcdsaP384Sha384]);
+        assert!(report.ciphers.is_empty());
+    }
+    
     #[test]
     fn test_self_signed_certificate() {
         let key_pair = KeyPair::generate().unwrap();
@@ -43,6 +43,9 @@ pub enum NexusError {
     #[error("Storage error: {message}")]
     Storage { message: String },
 
+    #[error("Integrity check failed for {resource}: expected {expected}, got {actual}")]
+    Integrity { resource: String, expected: String, actual: String },
+
     #[error("Invalid state: {message}")]
     InvalidState { message: String },
 
@@ -79,6 +82,7 @@ impl NexusError {
             NexusError::Timeout { .. } => "timeout",
             NexusError::Consensus { .. } => "consensus",
             NexusError::Storage { .. } => "storage",
+            NexusError::Integrity { .. } => "integrity",
             NexusError::InvalidState { .. } => "invalid_state",
             NexusError::Internal { .. } => "internal",
             NexusError::System { .. } => "system",
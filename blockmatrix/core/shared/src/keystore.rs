@@ -0,0 +1,298 @@
+//! Node signing keys, in software or in hardware
+//!
+//! Components that sign with the node's private key go through `NodeSigner`
+//! rather than holding key bytes, so the key can live where it cannot be
+//! copied off a compromised node. The software backend is an Ed25519
+//! `KeyPair` in memory. The TPM backend, built with the `tpm` feature,
+//! generates an ECDSA P-256 key inside the TPM and persists it under a
+//! handle in the owner hierarchy; the private part never leaves the chip,
+//! and every signature is made by the TPM. A node configured to require
+//! hardware keys refuses to start with the software backend.
+
+use crate::crypto::KeyPair;
+use crate::{NexusError, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Algorithm a node key signs with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignatureAlgorithm {
+    /// Raw 32-byte public keys and 64-byte signatures
+    Ed25519,
+    /// Uncompressed SEC1 public keys and ASN.1 DER signatures
+    EcdsaP256Sha256,
+}
+
+impl From<SignatureAlgorithm> for crate::crypto::Algorithm {
+    fn from(algorithm: SignatureAlgorithm) -> Self {
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => Self::Ed25519,
+            SignatureAlgorithm::EcdsaP256Sha256 => Self::EcdsaP256Sha256,
+        }
+    }
+}
+
+/// Signs with a node's private key without exposing it
+pub trait NodeSigner: Send + Sync {
+    fn algorithm(&self) -> SignatureAlgorithm;
+
+    fn public_key(&self) -> &[u8];
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>>;
+
+    /// Whether the private key is held by hardware it cannot be read from
+    fn is_hardware_backed(&self) -> bool {
+        false
+    }
+}
+
+impl NodeSigner for KeyPair {
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::Ed25519
+    }
+
+    fn public_key(&self) -> &[u8] {
+        KeyPair::public_key(self)
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        Ok(KeyPair::sign(self, message))
+    }
+}
+
+/// Verify a signature made by a node signer
+pub fn verify(algorithm: SignatureAlgorithm, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => KeyPair::verify(public_key, message, signature),
+        SignatureAlgorithm::EcdsaP256Sha256 => {
+            use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+            UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, public_key).verify(message, signature).is_ok()
+        }
+    }
+}
+
+/// Where the node key is kept
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum KeyBackend {
+    /// Generated in memory at start-up
+    #[default]
+    Software,
+    /// Generated in a TPM and persisted under `persistent_handle`
+    Tpm {
+        /// TCTI to reach the TPM, e.g. `device:/dev/tpmrm0`
+        tcti: String,
+        /// Handle in the 0x81000000 range; an existing key there is reused
+        persistent_handle: u32,
+    },
+}
+
+impl KeyBackend {
+    /// Algorithm of the keys the backend generates
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            KeyBackend::Software => SignatureAlgorithm::Ed25519,
+            KeyBackend::Tpm { .. } => SignatureAlgorithm::EcdsaP256Sha256,
+        }
+    }
+}
+
+/// Node key configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyStoreConfig {
+    pub backend: KeyBackend,
+    /// Refuse to start unless the key is hardware-backed
+    pub require_hardware: bool,
+}
+
+/// Open the node's signer as configured
+pub fn open_signer(config: &KeyStoreConfig) -> Result<Arc<dyn NodeSigner>> {
+    let signer: Arc<dyn NodeSigner> = match &config.backend {
+        KeyBackend::Software => Arc::new(KeyPair::generate().map_err(|e| NexusError::Internal {
+            message: format!("Failed to generate node key: {}", e),
+        })?),
+        #[cfg(feature = "tpm")]
+        KeyBackend::Tpm { tcti, persistent_handle } => Arc::new(tpm::TpmSigner::open(tcti, *persistent_handle)?),
+        #[cfg(not(feature = "tpm"))]
+        KeyBackend::Tpm { .. } => {
+            return Err(NexusError::Config("TPM key storage needs a build with the `tpm` feature".to_string()));
+        }
+    };
+    if config.require_hardware && !signer.is_hardware_backed() {
+        return Err(NexusError::Config("A hardware-backed node key is required".to_string()));
+    }
+    Ok(signer)
+}
+
+/// Encode an ECDSA signature's `r` and `s` as an ASN.1 DER sequence
+pub fn ecdsa_signature_der(r: &[u8], s: &[u8]) -> Vec<u8> {
+    fn integer(value: &[u8]) -> Vec<u8> {
+        const ZERO: &[u8] = &[0];
+        let trimmed = match value.iter().position(|byte| *byte != 0) {
+            Some(start) => &value[start..],
+            None => ZERO,
+        };
+        let mut encoded = vec![0x02];
+        // A leading high bit would make the integer negative
+        let pad = trimmed[0] & 0x80 != 0;
+        encoded.push((trimmed.len() + pad as usize) as u8);
+        if pad {
+            encoded.push(0);
+        }
+        encoded.extend_from_slice(trimmed);
+        encoded
+    }
+
+    let body = [integer(r), integer(s)].concat();
+    let mut der = vec![0x30, body.len() as u8];
+    der.extend(body);
+    der
+}
+
+#[cfg(feature = "tpm")]
+mod tpm {
+    use super::{ecdsa_signature_der, NodeSigner, SignatureAlgorithm};
+    use crate::{NexusError, Result};
+    use parking_lot::Mutex;
+    use std::str::FromStr;
+    use tss_esapi::attributes::ObjectAttributesBuilder;
+    use tss_esapi::constants::tss::{TPM2_RH_NULL, TPM2_ST_HASHCHECK};
+    use tss_esapi::handles::{KeyHandle, PersistentTpmHandle, TpmHandle};
+    use tss_esapi::interface_types::algorithm::{HashingAlgorithm, PublicAlgorithm};
+    use tss_esapi::interface_types::dynamic_handles::Persistent;
+    use tss_esapi::interface_types::ecc::EccCurve;
+    use tss_esapi::interface_types::resource_handles::{Hierarchy, Provision};
+    use tss_esapi::interface_types::session_handles::AuthSession;
+    use tss_esapi::structures::{
+        Digest, EccPoint, EccScheme, HashScheme, KeyDerivationFunctionScheme, Public, PublicBuilder,
+        PublicEccParametersBuilder, Signature, SignatureScheme,
+    };
+    use tss_esapi::tss2_esys::TPMT_TK_HASHCHECK;
+    use tss_esapi::{Context, TctiNameConf};
+
+    /// An ECDSA P-256 key held in a TPM
+    pub struct TpmSigner {
+        context: Mutex<Context>,
+        key: KeyHandle,
+        public_key: Vec<u8>,
+    }
+
+    fn tpm_error(action: &str, error: tss_esapi::Error) -> NexusError {
+        NexusError::System { message: format!("TPM failed to {}: {}", action, error) }
+    }
+
+    impl TpmSigner {
+        /// Load the key persisted at `handle`, generating and persisting it if there is none
+        pub fn open(tcti: &str, handle: u32) -> Result<Self> {
+            let tcti = TctiNameConf::from_str(tcti).map_err(|e| tpm_error("parse the TCTI", e))?;
+            let mut context = Context::new(tcti).map_err(|e| tpm_error("open a context", e))?;
+            context.set_sessions((Some(AuthSession::Password), None, None));
+            let persistent = PersistentTpmHandle::new(handle).map_err(|e| tpm_error("use the persistent handle", e))?;
+
+            let key = match context.tr_from_tpm_public(TpmHandle::Persistent(persistent)) {
+                Ok(object) => KeyHandle::from(object),
+                Err(_) => {
+                    let created = context
+                        .create_primary(Hierarchy::Owner, signing_template()?, None, None, None, None)
+                        .map_err(|e| tpm_error("generate the node key", e))?;
+                    let object = context
+                        .evict_control(Provision::Owner, created.key_handle.into(), Persistent::Persistent(persistent))
+                        .map_err(|e| tpm_error("persist the node key", e))?;
+                    tracing::info!("Generated node key in the TPM at handle {:#x}", handle);
+                    KeyHandle::from(object)
+                }
+            };
+
+            let (public, _, _) = context.read_public(key).map_err(|e| tpm_error("read the node key", e))?;
+            let Public::Ecc { unique, .. } = public else {
+                return Err(NexusError::Config(format!("TPM key at {:#x} is not an ECC key", handle)));
+            };
+            let mut public_key = vec![0x04];
+            public_key.extend_from_slice(unique.x().value());
+            public_key.extend_from_slice(unique.y().value());
+            Ok(Self { context: Mutex::new(context), key, public_key })
+        }
+    }
+
+    /// A non-restricted ECDSA P-256 signing key bound to this TPM
+    fn signing_template() -> Result<Public> {
+        let attributes = ObjectAttributesBuilder::new()
+            .with_fixed_tpm(true)
+            .with_fixed_parent(true)
+            .with_sensitive_data_origin(true)
+            .with_user_with_auth(true)
+            .with_sign_encrypt(true)
+            .build()
+            .map_err(|e| tpm_error("build key attributes", e))?;
+        let parameters = PublicEccParametersBuilder::new()
+            .with_ecc_scheme(EccScheme::EcDsa(HashScheme::new(HashingAlgorithm::Sha256)))
+            .with_curve(EccCurve::NistP256)
+            .with_is_signing_key(true)
+            .with_is_decryption_key(false)
+            .with_restricted(false)
+            .with_key_derivation_function_scheme(KeyDerivationFunctionScheme::Null)
+            .build()
+            .map_err(|e| tpm_error("build key parameters", e))?;
+        PublicBuilder::new()
+            .with_public_algorithm(PublicAlgorithm::Ecc)
+            .with_name_hashing_algorithm(HashingAlgorithm::Sha256)
+            .with_object_attributes(attributes)
+            .with_ecc_parameters(parameters)
+            .with_ecc_unique_identifier(EccPoint::default())
+            .build()
+            .map_err(|e| tpm_error("build the key template", e))
+    }
+
+    impl NodeSigner for TpmSigner {
+        fn algorithm(&self) -> SignatureAlgorithm {
+            SignatureAlgorithm::EcdsaP256Sha256
+        }
+
+        fn public_key(&self) -> &[u8] {
+            &self.public_key
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            let digest = ring::digest::digest(&ring::digest::SHA256, message);
+            let digest = Digest::try_from(digest.as_ref().to_vec()).map_err(|e| tpm_error("wrap the digest", e))?;
+            // The key is not restricted, so no hash check ticket is needed
+            let validation = TPMT_TK_HASHCHECK { tag: TPM2_ST_HASHCHECK, hierarchy: TPM2_RH_NULL, digest: Default::default() }
+                .try_into()
+                .map_err(|e| tpm_error("build the hash check ticket", e))?;
+            let signature = self
+                .context
+                .lock()
+                .sign(self.key, digest, SignatureScheme::Null, validation)
+                .map_err(|e| tpm_error("sign", e))?;
+            match signature {
+                Signature::EcDsa(signature) => {
+                    Ok(ecdsa_signature_der(signature.signature_r().value(), signature.signature_s().value()))
+                }
+                _ => Err(NexusError::System { message: "TPM returned a non-ECDSA signature".to_string() }),
+            }
+        }
+
+        fn is_hardware_backed(&self) -> bool {
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_software_signer_and_hardware_requirement() {
+        let signer = open_signer(&KeyStoreConfig::default()).unwrap();
+        let signature = signer.sign(b"hello").unwrap();
+        assert!(verify(signer.algorithm(), signer.public_key(), b"hello", &signature));
+        assert!(!verify(signer.algorithm(), signer.public_key(), b"tampered", &signature));
+
+        let require = KeyStoreConfig { require_hardware: true, ..Default::default() };
+        assert!(open_signer(&require).is_err());
+
+        // DER integers drop leading zeros and pad a set high bit
+        assert_eq!(ecdsa_signature_der(&[0, 0x01], &[0x80]), vec![0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80]);
+    }
+}
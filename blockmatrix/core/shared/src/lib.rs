@@ -8,14 +8,27 @@ pub mod id;
 pub mod metrics;
 pub mod config;
 pub mod crypto;
+pub mod keystore;
 pub mod time;
+pub mod validation;
+pub mod object_store;
+pub mod api_meta;
+pub mod schema;
 
 pub use error::{NexusError, Result};
 pub use id::{NodeId, ResourceId, ServiceId};
 pub use config::NexusConfig;
-pub use crypto::{KeyPair, AuthenticatedMessage, hash, random_bytes};
+pub use crypto::{
+    Algorithm, AlgorithmUse, CryptoPolicy, CryptoPolicyReport, CryptoProfile, KeyPair, KeyWrapper,
+    AuthenticatedMessage, DetachedSignature, hash, random_bytes,
+};
+pub use keystore::{KeyBackend, KeyStoreConfig, NodeSigner, SignatureAlgorithm};
 pub use time::{Timestamp, RateLimiter, TimeWindow};
 pub use metrics::{MetricsCollector, Histogram};
+pub use validation::{Diagnostic, Severity, Validate, ValidationReport};
+pub use object_store::{ObjectClient, ObjectMeta, ObjectStore, ObjectStoreConfig, ServerSideEncryption};
+pub use api_meta::{ApiMeta, Condition, ConditionStatus, StatusConditions};
+pub use schema::{admit_create, admit_update, ApiSchema, FieldPath};
 
 /// Current version of the Nexus protocol
 pub const PROTOCOL_VERSION: u32 = 1;
@@ -0,0 +1,669 @@
+//! Object storage for snapshots and backups
+//!
+//! Snapshots and backups go to an S3-compatible bucket. S3, MinIO and GCS
+//! (through its S3 interoperability API) are all reached through the same
+//! `ObjectStore` trait; the S3 backend is built with the `s3` feature, and
+//! `MemoryObjectStore` serves tests and single-node setups. `ObjectClient`
+//! sits on top: it splits large objects into a multipart upload, applies the
+//! configured server-side encryption, and stores a BLAKE3 checksum with every
+//! object. Downloads are checked against that checksum and fail with
+//! `NexusError::Integrity` if the object was corrupted or truncated.
+
+use crate::{NexusError, Result};
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Object metadata key holding the BLAKE3 checksum of the contents
+pub const CHECKSUM_METADATA_KEY: &str = "blake3";
+
+/// Smallest part S3 accepts in a multipart upload, except for the last part
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Object store service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectStoreProvider {
+    #[default]
+    S3,
+    Gcs,
+    Minio,
+}
+
+/// Server-side encryption applied to stored objects
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerSideEncryption {
+    #[default]
+    None,
+    /// Keys managed by the object store
+    Aes256,
+    /// A customer-managed KMS key
+    Kms { key_id: String },
+}
+
+/// Object store configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectStoreConfig {
+    pub provider: ObjectStoreProvider,
+    pub bucket: String,
+    pub region: String,
+    /// Custom endpoint, required for MinIO
+    pub endpoint: Option<String>,
+    /// Prefix prepended to every object key
+    pub prefix: String,
+    /// Objects larger than this are uploaded in parts
+    pub multipart_threshold: usize,
+    pub part_size: usize,
+    pub encryption: ServerSideEncryption,
+}
+
+impl Default for ObjectStoreConfig {
+    fn default() -> Self {
+        Self {
+            provider: ObjectStoreProvider::default(),
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            endpoint: None,
+            prefix: String::new(),
+            multipart_threshold: 64 * 1024 * 1024,
+            part_size: 16 * 1024 * 1024,
+            encryption: ServerSideEncryption::default(),
+        }
+    }
+}
+
+impl ObjectStoreConfig {
+    /// Endpoint to connect to, if not the provider's default
+    pub fn endpoint_url(&self) -> Option<String> {
+        match (&self.endpoint, self.provider) {
+            (Some(endpoint), _) => Some(endpoint.clone()),
+            (None, ObjectStoreProvider::Gcs) => Some("https://storage.googleapis.com".to_string()),
+            (None, _) => None,
+        }
+    }
+
+    /// Whether buckets are addressed by path instead of subdomain, as MinIO expects
+    pub fn path_style(&self) -> bool {
+        self.provider == ObjectStoreProvider::Minio
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.bucket.is_empty() {
+            return Err(NexusError::Config("object store bucket must not be empty".to_string()));
+        }
+        if self.provider == ObjectStoreProvider::Minio && self.endpoint.is_none() {
+            return Err(NexusError::Config("MinIO requires an endpoint".to_string()));
+        }
+        if self.part_size < MIN_PART_SIZE {
+            return Err(NexusError::Config(format!("part_size must be at least {} bytes", MIN_PART_SIZE)));
+        }
+        if self.multipart_threshold < self.part_size {
+            return Err(NexusError::Config("multipart_threshold must be at least part_size".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// A stored object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectMeta {
+    pub key: String,
+    pub size: u64,
+    /// BLAKE3 checksum recorded at upload, if the object has one
+    pub checksum: Option<String>,
+}
+
+/// Options for a new object
+#[derive(Debug, Clone, Default)]
+pub struct PutOptions {
+    pub encryption: ServerSideEncryption,
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// An uploaded part of a multipart upload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedPart {
+    /// Part number, starting at 1
+    pub part_number: u32,
+    pub etag: String,
+}
+
+/// S3-style object storage
+pub trait ObjectStore: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>, options: &'a PutOptions) -> BoxFuture<'a, Result<()>>;
+
+    /// Object contents and metadata; fails with `ResourceNotFound` if missing
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(Vec<u8>, ObjectMeta)>>;
+
+    fn head<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<ObjectMeta>>>;
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>>;
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<ObjectMeta>>>;
+
+    /// Start a multipart upload, returning its upload ID
+    fn create_multipart<'a>(&'a self, key: &'a str, options: &'a PutOptions) -> BoxFuture<'a, Result<String>>;
+
+    /// Upload one part, returning its ETag
+    fn upload_part<'a>(
+        &'a self,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: u32,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<String>>;
+
+    fn complete_multipart<'a>(
+        &'a self,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: Vec<CompletedPart>,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    fn abort_multipart<'a>(&'a self, key: &'a str, upload_id: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Uploads and verified downloads over an object store
+#[derive(Clone)]
+pub struct ObjectClient {
+    config: ObjectStoreConfig,
+    store: Arc<dyn ObjectStore>,
+}
+
+impl ObjectClient {
+    pub fn new(config: ObjectStoreConfig, store: Arc<dyn ObjectStore>) -> Self {
+        Self { config, store }
+    }
+
+    /// Connect to the configured S3-compatible service
+    #[cfg(feature = "s3")]
+    pub async fn connect(config: ObjectStoreConfig) -> Result<Self> {
+        config.validate()?;
+        let store = Arc::new(S3ObjectStore::connect(&config).await);
+        Ok(Self::new(config, store))
+    }
+
+    pub fn config(&self) -> &ObjectStoreConfig {
+        &self.config
+    }
+
+    /// Upload an object, in parts if it is over the multipart threshold
+    pub async fn upload(&self, key: &str, data: Vec<u8>) -> Result<ObjectMeta> {
+        let full_key = self.full_key(key);
+        let checksum = blake3::hash(&data).to_hex().to_string();
+        let size = data.len() as u64;
+        let options = PutOptions {
+            encryption: self.config.encryption.clone(),
+            metadata: BTreeMap::from([(CHECKSUM_METADATA_KEY.to_string(), checksum.clone())]),
+        };
+
+        if data.len() <= self.config.multipart_threshold {
+            self.store.put(&full_key, data, &options).await?;
+        } else {
+            self.upload_parts(&full_key, &data, &options).await?;
+        }
+        debug!("Uploaded {} ({} bytes)", full_key, size);
+        Ok(ObjectMeta { key: key.to_string(), size, checksum: Some(checksum) })
+    }
+
+    /// Download an object, checking it against its upload checksum
+    pub async fn download(&self, key: &str) -> Result<Vec<u8>> {
+        let full_key = self.full_key(key);
+        let (data, meta) = self.store.get(&full_key).await?;
+        let Some(expected) = meta.checksum else {
+            return Err(NexusError::Integrity {
+                resource: full_key,
+                expected: "a recorded checksum".to_string(),
+                actual: "none".to_string(),
+            });
+        };
+        let actual = blake3::hash(&data).to_hex().to_string();
+        if actual != expected {
+            return Err(NexusError::Integrity { resource: full_key, expected, actual });
+        }
+        Ok(data)
+    }
+
+    pub async fn head(&self, key: &str) -> Result<Option<ObjectMeta>> {
+        Ok(self.store.head(&self.full_key(key)).await?.map(|meta| self.relative(meta)))
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        self.store.delete(&self.full_key(key)).await
+    }
+
+    /// Objects under `prefix`, with keys relative to the configured prefix
+    pub async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>> {
+        let objects = self.store.list(&self.full_key(prefix)).await?;
+        Ok(objects.into_iter().map(|meta| self.relative(meta)).collect())
+    }
+
+    async fn upload_parts(&self, key: &str, data: &[u8], options: &PutOptions) -> Result<()> {
+        let upload_id = self.store.create_multipart(key, options).await?;
+        let mut parts = Vec::new();
+        for (index, chunk) in data.chunks(self.config.part_size.max(1)).enumerate() {
+            let part_number = index as u32 + 1;
+            match self.store.upload_part(key, &upload_id, part_number, chunk.to_vec()).await {
+                Ok(etag) => parts.push(CompletedPart { part_number, etag }),
+                Err(e) => {
+                    if let Err(abort_error) = self.store.abort_multipart(key, &upload_id).await {
+                        warn!("Failed to abort upload {} of {}: {}", upload_id, key, abort_error);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        self.store.complete_multipart(key, &upload_id, parts).await
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        if self.config.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.config.prefix.trim_end_matches('/'), key)
+        }
+    }
+
+    fn relative(&self, mut meta: ObjectMeta) -> ObjectMeta {
+        if !self.config.prefix.is_empty() {
+            let prefix = format!("{}/", self.config.prefix.trim_end_matches('/'));
+            if let Some(key) = meta.key.strip_prefix(&prefix) {
+                meta.key = key.to_string();
+            }
+        }
+        meta
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoredObject {
+    data: Vec<u8>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl StoredObject {
+    fn meta(&self, key: &str) -> ObjectMeta {
+        ObjectMeta {
+            key: key.to_string(),
+            size: self.data.len() as u64,
+            checksum: self.metadata.get(CHECKSUM_METADATA_KEY).cloned(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PendingUpload {
+    key: String,
+    metadata: BTreeMap<String, String>,
+    parts: BTreeMap<u32, Vec<u8>>,
+}
+
+/// Object store held in memory
+#[derive(Debug, Default)]
+pub struct MemoryObjectStore {
+    objects: Mutex<BTreeMap<String, StoredObject>>,
+    uploads: Mutex<HashMap<String, PendingUpload>>,
+    next_upload: Mutex<u64>,
+}
+
+impl MemoryObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ObjectStore for MemoryObjectStore {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>, options: &'a PutOptions) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let object = StoredObject { data, metadata: options.metadata.clone() };
+            self.objects.lock().insert(key.to_string(), object);
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(Vec<u8>, ObjectMeta)>> {
+        Box::pin(async move {
+            let objects = self.objects.lock();
+            let object = objects.get(key).ok_or_else(|| NexusError::ResourceNotFound {
+                resource_type: "object".to_string(),
+                id: key.to_string(),
+            })?;
+            Ok((object.data.clone(), object.meta(key)))
+        })
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<ObjectMeta>>> {
+        Box::pin(async move { Ok(self.objects.lock().get(key).map(|object| object.meta(key))) })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.objects.lock().remove(key);
+            Ok(())
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<ObjectMeta>>> {
+        Box::pin(async move {
+            Ok(self
+                .objects
+                .lock()
+                .range(prefix.to_string()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .map(|(key, object)| object.meta(key))
+                .collect())
+        })
+    }
+
+    fn create_multipart<'a>(&'a self, key: &'a str, options: &'a PutOptions) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let upload_id = {
+                let mut next = self.next_upload.lock();
+                *next += 1;
+                format!("upload-{}", next)
+            };
+            let upload = PendingUpload { key: key.to_string(), metadata: options.metadata.clone(), parts: BTreeMap::new() };
+            self.uploads.lock().insert(upload_id.clone(), upload);
+            Ok(upload_id)
+        })
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        _key: &'a str,
+        upload_id: &'a str,
+        part_number: u32,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let etag = blake3::hash(&data).to_hex().to_string();
+            let mut uploads = self.uploads.lock();
+            let upload = uploads.get_mut(upload_id).ok_or_else(|| NexusError::ResourceNotFound {
+                resource_type: "upload".to_string(),
+                id: upload_id.to_string(),
+            })?;
+            upload.parts.insert(part_number, data);
+            Ok(etag)
+        })
+    }
+
+    fn complete_multipart<'a>(
+        &'a self,
+        _key: &'a str,
+        upload_id: &'a str,
+        parts: Vec<CompletedPart>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let upload = self.uploads.lock().remove(upload_id).ok_or_else(|| NexusError::ResourceNotFound {
+                resource_type: "upload".to_string(),
+                id: upload_id.to_string(),
+            })?;
+            let mut data = Vec::new();
+            for part in parts {
+                let bytes = upload.parts.get(&part.part_number).ok_or_else(|| NexusError::Storage {
+                    message: format!("part {} of upload {} was never uploaded", part.part_number, upload_id),
+                })?;
+                data.extend_from_slice(bytes);
+            }
+            self.objects.lock().insert(upload.key, StoredObject { data, metadata: upload.metadata });
+            Ok(())
+        })
+    }
+
+    fn abort_multipart<'a>(&'a self, _key: &'a str, upload_id: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.uploads.lock().remove(upload_id);
+            Ok(())
+        })
+    }
+}
+
+/// S3-compatible object store: AWS S3, MinIO, or GCS interoperability
+#[cfg(feature = "s3")]
+pub struct S3ObjectStore {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+#[cfg(feature = "s3")]
+impl S3ObjectStore {
+    /// Connect with credentials from the environment
+    pub async fn connect(config: &ObjectStoreConfig) -> Self {
+        let sdk_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new(config.region.clone()))
+            .load()
+            .await;
+        let mut builder = aws_sdk_s3::config::Builder::from(&sdk_config).force_path_style(config.path_style());
+        if let Some(endpoint) = config.endpoint_url() {
+            builder = builder.endpoint_url(endpoint);
+        }
+        Self {
+            client: aws_sdk_s3::Client::from_conf(builder.build()),
+            bucket: config.bucket.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+fn s3_error(error: impl std::error::Error + Send + Sync + 'static) -> NexusError {
+    NexusError::Storage { message: aws_sdk_s3::error::DisplayErrorContext(error).to_string() }
+}
+
+#[cfg(feature = "s3")]
+fn s3_encryption(encryption: &ServerSideEncryption) -> (Option<aws_sdk_s3::types::ServerSideEncryption>, Option<String>) {
+    use aws_sdk_s3::types::ServerSideEncryption as Sse;
+    match encryption {
+        ServerSideEncryption::None => (None, None),
+        ServerSideEncryption::Aes256 => (Some(Sse::Aes256), None),
+        ServerSideEncryption::Kms { key_id } => (Some(Sse::AwsKms), Some(key_id.clone())),
+    }
+}
+
+#[cfg(feature = "s3")]
+impl ObjectStore for S3ObjectStore {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>, options: &'a PutOptions) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (sse, kms_key_id) = s3_encryption(&options.encryption);
+            let mut request = self
+                .client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .body(data.into())
+                .set_server_side_encryption(sse)
+                .set_ssekms_key_id(kms_key_id);
+            for (name, value) in &options.metadata {
+                request = request.metadata(name, value);
+            }
+            request.send().await.map_err(s3_error)?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(Vec<u8>, ObjectMeta)>> {
+        Box::pin(async move {
+            let output = self.client.get_object().bucket(&self.bucket).key(key).send().await.map_err(|e| {
+                if e.as_service_error().map_or(false, |e| e.is_no_such_key()) {
+                    NexusError::ResourceNotFound { resource_type: "object".to_string(), id: key.to_string() }
+                } else {
+                    s3_error(e)
+                }
+            })?;
+            let checksum = output.metadata().and_then(|metadata| metadata.get(CHECKSUM_METADATA_KEY)).cloned();
+            let data = output.body.collect().await.map_err(s3_error)?.into_bytes().to_vec();
+            let meta = ObjectMeta { key: key.to_string(), size: data.len() as u64, checksum };
+            Ok((data, meta))
+        })
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<ObjectMeta>>> {
+        Box::pin(async move {
+            match self.client.head_object().bucket(&self.bucket).key(key).send().await {
+                Ok(output) => Ok(Some(ObjectMeta {
+                    key: key.to_string(),
+                    size: output.content_length().unwrap_or(0) as u64,
+                    checksum: output.metadata().and_then(|metadata| metadata.get(CHECKSUM_METADATA_KEY)).cloned(),
+                })),
+                Err(e) if e.as_service_error().map_or(false, |e| e.is_not_found()) => Ok(None),
+                Err(e) => Err(s3_error(e)),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client.delete_object().bucket(&self.bucket).key(key).send().await.map_err(s3_error)?;
+            Ok(())
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<ObjectMeta>>> {
+        Box::pin(async move {
+            let mut objects = Vec::new();
+            let mut continuation_token = None;
+            loop {
+                let output = self
+                    .client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(prefix)
+                    .set_continuation_token(continuation_token)
+                    .send()
+                    .await
+                    .map_err(s3_error)?;
+                objects.extend(output.contents().iter().map(|object| ObjectMeta {
+                    key: object.key().unwrap_or_default().to_string(),
+                    size: object.size().unwrap_or(0) as u64,
+                    checksum: None,
+                }));
+                continuation_token = output.next_continuation_token().map(str::to_string);
+                if continuation_token.is_none() {
+                    return Ok(objects);
+                }
+            }
+        })
+    }
+
+    fn create_multipart<'a>(&'a self, key: &'a str, options: &'a PutOptions) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let (sse, kms_key_id) = s3_encryption(&options.encryption);
+            let mut request = self
+                .client
+                .create_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .set_server_side_encryption(sse)
+                .set_ssekms_key_id(kms_key_id);
+            for (name, value) in &options.metadata {
+                request = request.metadata(name, value);
+            }
+            let output = request.send().await.map_err(s3_error)?;
+            output
+                .upload_id()
+                .map(str::to_string)
+                .ok_or_else(|| NexusError::Storage { message: format!("no upload ID returned for {}", key) })
+        })
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: u32,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let output = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number as i32)
+                .body(data.into())
+                .send()
+                .await
+                .map_err(s3_error)?;
+            output
+                .e_tag()
+                .map(str::to_string)
+                .ok_or_else(|| NexusError::Storage { message: format!("no ETag returned for part {} of {}", part_number, key) })
+        })
+    }
+
+    fn complete_multipart<'a>(
+        &'a self,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: Vec<CompletedPart>,
+    ) -> BoxFuture<'a, Result<()>> {
+        use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart as S3Part};
+
+        Box::pin(async move {
+            let parts = parts
+                .into_iter()
+                .map(|part| S3Part::builder().part_number(part.part_number as i32).e_tag(part.etag).build())
+                .collect();
+            self.client
+                .complete_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+                .send()
+                .await
+                .map_err(s3_error)?;
+            Ok(())
+        })
+    }
+
+    fn abort_multipart<'a>(&'a self, key: &'a str, upload_id: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .send()
+                .await
+                .map_err(s3_error)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_multipart_upload_and_verified_download() {
+        let store = Arc::new(MemoryObjectStore::new());
+        let config = ObjectStoreConfig {
+            bucket: "backups".to_string(),
+            prefix: "cluster-a".to_string(),
+            multipart_threshold: 8,
+            part_size: 4,
+            ..Default::default()
+        };
+        let client = ObjectClient::new(config, store.clone());
+
+        let data = b"0123456789".to_vec();
+        let meta = client.upload("snapshots/1", data.clone()).await.unwrap();
+        assert_eq!(meta.size, 10);
+        assert_eq!(client.download("snapshots/1").await.unwrap(), data);
+        assert_eq!(client.list("snapshots/").await.unwrap()[0].key, "snapshots/1");
+
+        // Contents that no longer match the recorded checksum are rejected
+        let options = PutOptions {
+            metadata: BTreeMap::from([(CHECKSUM_METADATA_KEY.to_string(), meta.checksum.unwrap())]),
+            ..Default::default()
+        };
+        store.put("cluster-a/snapshots/1", b"01234".to_vec(), &options).await.unwrap();
+        assert!(matches!(client.download("snapshots/1").await, Err(NexusError::Integrity { .. })));
+    }
+}
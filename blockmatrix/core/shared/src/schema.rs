@@ -0,0 +1,175 @@
+//! Defaulting and schema validation for API objects
+//!
+//! API types implement [`ApiSchema`] so every create and update goes through
+//! the same steps: omitted values are defaulted, fields are checked, and on
+//! update fields that may not change are compared against the stored object.
+//! Problems are reported per field path (e.g. `ports[1].target_port`) in a
+//! [`ValidationReport`], so a client sees every invalid field in one response
+//! instead of a runtime failure after the object was accepted.
+
+use crate::validation::ValidationReport;
+use std::fmt;
+
+/// Path to a field inside an API object, e.g. `spec.ports[0].port`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldPath(String);
+
+impl FieldPath {
+    /// Path of the object itself
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Path of a named field below this one
+    pub fn child(&self, name: &str) -> Self {
+        if self.0.is_empty() {
+            Self(name.to_string())
+        } else {
+            Self(format!("{}.{}", self.0, name))
+        }
+    }
+
+    /// Path of a list element
+    pub fn index(&self, index: usize) -> Self {
+        Self(format!("{}[{}]", self.0, index))
+    }
+
+    /// Path of a map entry
+    pub fn key(&self, key: &str) -> Self {
+        Self(format!("{}[{}]", self.0, key))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An API object with defaulting and validation rules
+pub trait ApiSchema {
+    /// Fill in values the client omitted
+    fn apply_defaults(&mut self) {}
+
+    /// Check every field, recording problems under `path`
+    fn validate_fields(&self, path: &FieldPath, report: &mut ValidationReport);
+
+    /// Check that fields fixed at creation are unchanged from `old`
+    fn validate_immutable(&self, _old: &Self, _path: &FieldPath, _report: &mut ValidationReport) {}
+}
+
+/// Default and validate an object about to be created
+pub fn admit_create<T: ApiSchema>(object: &mut T) -> ValidationReport {
+    object.apply_defaults();
+    let mut report = ValidationReport::new();
+    object.validate_fields(&FieldPath::root(), &mut report);
+    report
+}
+
+/// Default and validate an object replacing `old`
+pub fn admit_update<T: ApiSchema>(old: &T, object: &mut T) -> ValidationReport {
+    let mut report = admit_create(object);
+    object.validate_immutable(old, &FieldPath::root(), &mut report);
+    report
+}
+
+/// Require a non-empty string
+pub fn require(report: &mut ValidationReport, path: &FieldPath, value: &str) {
+    if value.trim().is_empty() {
+        report.error(path.as_str(), "is required");
+    }
+}
+
+/// Require a DNS label: 1-63 lowercase letters, digits or '-', alphanumeric at both ends
+pub fn check_dns_label(report: &mut ValidationReport, path: &FieldPath, value: &str) {
+    let valid = !value.is_empty()
+        && value.len() <= 63
+        && value.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !value.starts_with('-')
+        && !value.ends_with('-');
+    if !valid {
+        report.error(
+            path.as_str(),
+            format!("'{}' must be 1-63 lowercase letters, digits or '-', starting and ending alphanumeric", value),
+        );
+    }
+}
+
+/// Require `min <= value <= max`
+pub fn check_range<T: PartialOrd + fmt::Display>(report: &mut ValidationReport, path: &FieldPath, value: T, min: T, max: T) {
+    if value < min || value > max {
+        report.error(path.as_str(), format!("{} is out of range {}..={}", value, min, max));
+    }
+}
+
+/// Require that a field kept its value across an update
+pub fn check_immutable<T: PartialEq + fmt::Debug>(report: &mut ValidationReport, path: &FieldPath, old: &T, new: &T) {
+    if old != new {
+        report.error(path.as_str(), format!("is immutable: cannot change {:?} to {:?}", old, new));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct Port {
+        port: u16,
+        target_port: u16,
+    }
+
+    #[derive(Debug, Clone)]
+    struct Spec {
+        name: String,
+        namespace: Option<String>,
+        ports: Vec<Port>,
+    }
+
+    impl ApiSchema for Spec {
+        fn apply_defaults(&mut self) {
+            self.namespace.get_or_insert_with(|| "default".to_string());
+            for port in &mut self.ports {
+                if port.target_port == 0 {
+                    port.target_port = port.port;
+                }
+            }
+        }
+
+        fn validate_fields(&self, path: &FieldPath, report: &mut ValidationReport) {
+            check_dns_label(report, &path.child("name"), &self.name);
+            for (i, port) in self.ports.iter().enumerate() {
+                check_range(report, &path.child("ports").index(i).child("port"), port.port, 1, u16::MAX);
+            }
+        }
+
+        fn validate_immutable(&self, old: &Self, path: &FieldPath, report: &mut ValidationReport) {
+            check_immutable(report, &path.child("namespace"), &old.namespace, &self.namespace);
+        }
+    }
+
+    #[test]
+    fn test_defaulting_and_field_paths() {
+        let mut spec = Spec {
+            name: "web".to_string(),
+            namespace: None,
+            ports: vec![Port { port: 80, target_port: 0 }],
+        };
+        assert!(admit_create(&mut spec).is_valid());
+        assert_eq!(spec.namespace.as_deref(), Some("default"));
+        assert_eq!(spec.ports[0].target_port, 80);
+
+        let mut invalid = Spec {
+            name: "Web_1".to_string(),
+            namespace: Some("prod".to_string()),
+            ports: vec![Port { port: 80, target_port: 0 }, Port { port: 0, target_port: 8080 }],
+        };
+        let report = admit_update(&spec, &mut invalid);
+        let fields: Vec<&str> = report.errors().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, vec!["name", "ports[1].port", "namespace"]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Configuration validation diagnostics
+//!
+//! Config structs implement [`Validate`] to check field ranges and
+//! cross-field invariants before startup. Problems are collected into a
+//! [`ValidationReport`] rather than failing on the first one, so an operator
+//! sees every issue in a config file at once.
+
+use crate::error::{NexusError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    /// The configuration cannot be used
+    Error,
+    /// The configuration works but is likely a mistake
+    Warning,
+}
+
+/// A single validation finding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Dotted path of the offending field, e.g. `dht.security.record_ttl`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", level, self.field, self.message)
+    }
+}
+
+/// Collected diagnostics for a configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error for a field
+    pub fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Error, field.into(), message.into());
+    }
+
+    /// Record a warning for a field
+    pub fn warning(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Warning, field.into(), message.into());
+    }
+
+    fn push(&mut self, severity: Severity, field: String, message: String) {
+        self.diagnostics.push(Diagnostic { severity, field, message });
+    }
+
+    /// Merge a nested config's report, prefixing its field paths
+    pub fn nest(&mut self, prefix: &str, report: ValidationReport) {
+        for mut diagnostic in report.diagnostics {
+            diagnostic.field = format!("{}.{}", prefix, diagnostic.field);
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning)
+    }
+
+    /// Whether the configuration is usable (warnings allowed)
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// One-line-per-error summary suitable for an error message
+    pub fn error_summary(&self) -> String {
+        let errors: Vec<String> = self.errors().map(|d| d.to_string()).collect();
+        format!("{} invalid setting(s):\n  {}", errors.len(), errors.join("\n  "))
+    }
+
+    /// Fail with all errors if the configuration is unusable; otherwise
+    /// return the report so warnings can be logged
+    pub fn into_result(self) -> Result<Self> {
+        if self.is_valid() {
+            return Ok(self);
+        }
+        Err(NexusError::Config(self.error_summary()))
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Configuration that can check its own invariants
+pub trait Validate {
+    /// Check the configuration and report every problem found
+    fn validate_config(&self) -> ValidationReport;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_nesting_and_result() {
+        let mut inner = ValidationReport::new();
+        inner.error("timeout", "must be less than interval");
+        inner.warning("retries", "zero retries disables recovery");
+
+        let mut report = ValidationReport::new();
+        report.nest("health_check", inner);
+
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(report.diagnostics[0].field, "health_check.timeout");
+
+        let err = report.into_result().unwrap_err();
+        assert!(err.to_string().contains("health_check.timeout"));
+    }
+}
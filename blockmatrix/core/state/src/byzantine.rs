@@ -5,7 +5,7 @@
 
 use crate::{Result, StateError};
 use crate::consensus::{ConsensusEngine, ByzantineStatus, PbftMessage, ByzantineCheckpoint, Proposal};
-use nexus_shared::NodeId;
+use nexus_shared::{hash, KeyPair, NodeId};
 use serde::{Serialize, Deserialize};
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
@@ -13,6 +13,15 @@ use std::time::{SystemTime, Duration};
 use tokio::sync::{RwLock, mpsc, broadcast};
 use tracing::{info, warn, error, debug};
 
+/// Confidence at which a node is reported as suspected Byzantine
+pub const DEFAULT_FAULT_THRESHOLD: f64 = 0.7;
+
+/// Sequence numbers of PBFT messages kept for equivocation checks
+const MESSAGE_WINDOW: u64 = 1024;
+
+/// Domain separator so PBFT message signatures cannot be replayed as other records
+const PBFT_MESSAGE_CONTEXT: &[u8] = b"nexus-pbft-message-v1";
+
 /// Byzantine consensus coordinator managing multiple consensus instances
 pub struct ByzantineCoordinator {
     /// Node configuration
@@ -65,7 +74,7 @@ impl Default for ByzantineConfig {
             consensus_instances: 3,
             checkpoint_interval: 100,
             view_change_timeout: 10000,
-            fault_detection_threshold: 0.7,
+            fault_detection_threshold: DEFAULT_FAULT_THRESHOLD,
             max_byzantine_faults: 1,
         }
     }
@@ -75,12 +84,13 @@ impl ByzantineCoordinator {
     /// Create a new Byzantine coordinator
     pub async fn new(config: ByzantineConfig, node_id: NodeId) -> Result<Self> {
         let (message_sender, _) = broadcast::channel(1000);
+        let fault_detector = FaultDetector::new(node_id, config.fault_detection_threshold);
         
         Ok(Self {
             config,
             node_id,
             consensus_engines: Arc::new(RwLock::new(HashMap::new())),
-            fault_detector: Arc::new(RwLock::new(FaultDetector::new())),
+            fault_detector: Arc::new(RwLock::new(fault_detector)),
             view_change_manager: Arc::new(RwLock::new(ViewChangeManager::new())),
             checkpoint_manager: Arc::new(RwLock::new(CheckpointManager::new())),
             message_sender,
@@ -327,39 +337,212 @@ pub enum ByzantineMessage {
     },
 }
 
-/// Fault detection system
-struct FaultDetector {
-    suspected_nodes: HashMap<NodeId, FaultEvidence>,
+/// A PBFT message signed by its sender
+///
+/// A member's node ID is the hash of its public key, so the signature ties
+/// the message to the member it names as sender without a key directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedPbftMessage {
+    pub message: PbftMessage,
+    pub public_key: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+impl SignedPbftMessage {
+    /// Sign a message with the sending member's key
+    pub fn sign(message: PbftMessage, key_pair: &KeyPair) -> Self {
+        let signature = key_pair.sign(&Self::signing_bytes(&message));
+        Self {
+            message,
+            public_key: *key_pair.public_key(),
+            signature,
+        }
+    }
+
+    fn signing_bytes(message: &PbftMessage) -> Vec<u8> {
+        let mut bytes = PBFT_MESSAGE_CONTEXT.to_vec();
+        bytes.extend(bincode::serialize(message).unwrap_or_default());
+        bytes
+    }
+
+    /// Check the message was signed by the member it names as sender
+    pub fn verify(&self) -> std::result::Result<(), String> {
+        let sender = match &self.message {
+            PbftMessage::PrePrepare { primary_id, .. } => *primary_id,
+            PbftMessage::Prepare { node_id, .. }
+            | PbftMessage::Commit { node_id, .. }
+            | PbftMessage::ViewChange { node_id, .. } => *node_id,
+        };
+        if NodeId::new(hash(&self.public_key)) != sender {
+            return Err(format!("message from {} is signed with another member's key", sender));
+        }
+        if !KeyPair::verify(&self.public_key, &Self::signing_bytes(&self.message), &self.signature) {
+            return Err(format!("message from {} has a bad signature", sender));
+        }
+        Ok(())
+    }
+}
+
+/// Fault detection from observed consensus messages and reported faults
+///
+/// A member that sends two different prepare or commit messages for the same
+/// view and sequence number, or a primary that pre-prepares two proposals
+/// for one slot, has equivocated; the pair of signed messages is kept as
+/// evidence any node can check. Messages without a valid signature are not
+/// attributed to anyone. Other faults are recorded as reported.
+#[derive(Debug)]
+pub struct FaultDetector {
+    node_id: NodeId,
+    /// First message seen per sender, phase, view and sequence number
+    seen: HashMap<(NodeId, &'static str, u64, u64), SignedPbftMessage>,
+    latest_sequence: u64,
+    records: HashMap<NodeId, NodeFaultRecord>,
     detection_threshold: f64,
 }
 
 impl FaultDetector {
-    fn new() -> Self {
+    pub fn new(node_id: NodeId, detection_threshold: f64) -> Self {
         Self {
-            suspected_nodes: HashMap::new(),
-            detection_threshold: 0.7,
+            node_id,
+            seen: HashMap::new(),
+            latest_sequence: 0,
+            records: HashMap::new(),
+            detection_threshold,
         }
     }
-    
+
+    /// Check a consensus message against earlier ones from the same sender
+    ///
+    /// Returns the evidence if the message equivocates.
+    pub fn observe(&mut self, message: &SignedPbftMessage) -> Option<FaultEvidence> {
+        if let Err(reason) = message.verify() {
+            debug!("Ignoring unattributable PBFT message: {}", reason);
+            return None;
+        }
+        let slot = MessageSlot::of(&message.message)?;
+        self.latest_sequence = self.latest_sequence.max(slot.sequence_number);
+        if self.seen.len() as u64 > 4 * MESSAGE_WINDOW {
+            let oldest = self.latest_sequence.saturating_sub(MESSAGE_WINDOW);
+            self.seen.retain(|&(_, _, _, sequence), _| sequence >= oldest);
+        }
+
+        let key = (slot.sender, slot.phase, slot.view, slot.sequence_number);
+        let first = match self.seen.get(&key) {
+            Some(first) => first,
+            None => {
+                self.seen.insert(key, message.clone());
+                return None;
+            }
+        };
+        if MessageSlot::of(&first.message).map(|s| s.digest) == Some(slot.digest) {
+            return None;
+        }
+
+        let evidence = FaultEvidence {
+            fault_type: FaultType::Equivocation,
+            messages: vec![first.clone(), message.clone()],
+            description: format!(
+                "conflicting {} messages for view {} sequence {}",
+                slot.phase, slot.view, slot.sequence_number
+            ),
+            reporter: self.node_id,
+            observed_at: SystemTime::now(),
+        };
+        warn!("Node {} equivocated: {}", slot.sender, evidence.description);
+        self.record(slot.sender, evidence.clone());
+        Some(evidence)
+    }
+
+    /// Record evidence against a node
+    pub fn record(&mut self, node_id: NodeId, evidence: FaultEvidence) {
+        let record = self.records.entry(node_id).or_insert_with(|| NodeFaultRecord {
+            node_id,
+            evidence: Vec::new(),
+            first_seen: evidence.observed_at,
+            last_seen: evidence.observed_at,
+        });
+        record.last_seen = evidence.observed_at;
+        record.evidence.push(evidence);
+    }
+
+    /// Drop the records of a node that has been evicted
+    pub fn forget(&mut self, node_id: &NodeId) -> Option<NodeFaultRecord> {
+        self.seen.retain(|(sender, ..), _| sender != node_id);
+        self.records.remove(node_id)
+    }
+
+    /// Records for every node with evidence, most suspect first
+    pub fn records(&self) -> Vec<NodeFaultRecord> {
+        let mut records: Vec<_> = self.records.values().cloned().collect();
+        records.sort_by(|a, b| b.confidence().total_cmp(&a.confidence()));
+        records
+    }
+
+    /// Nodes whose evidence reaches the detection threshold
+    pub fn suspected(&self) -> Vec<NodeId> {
+        self.records()
+            .into_iter()
+            .filter(|record| record.confidence() >= self.detection_threshold)
+            .map(|record| record.node_id)
+            .collect()
+    }
+
     async fn detect_faults(&mut self) -> Vec<FaultReport> {
-        let mut reports = Vec::new();
-        
-        // Check for timeout-based faults
-        // Check for inconsistent behavior
-        // Check for signature verification failures
-        
-        // For simulation, occasionally report a suspected fault
-        if rand::random::<f64>() < 0.05 {
-            reports.push(FaultReport {
-                suspected_node: NodeId::random(),
-                fault_type: FaultType::Timeout,
-                confidence: 0.8,
-                evidence: "Node failed to respond within timeout".to_string(),
-                detected_at: SystemTime::now(),
-            });
+        self.records()
+            .into_iter()
+            .filter(|record| record.confidence() >= self.detection_threshold)
+            .filter_map(|record| {
+                let confidence = record.confidence();
+                let worst = record.evidence.iter().max_by(|a, b| {
+                    a.fault_type.weight().total_cmp(&b.fault_type.weight())
+                })?;
+                Some(FaultReport {
+                    suspected_node: record.node_id,
+                    fault_type: worst.fault_type.clone(),
+                    confidence,
+                    evidence: worst.description.clone(),
+                    detected_at: record.last_seen,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Sender, phase and position of a PBFT message, and what it vouches for
+struct MessageSlot {
+    sender: NodeId,
+    phase: &'static str,
+    view: u64,
+    sequence_number: u64,
+    digest: Vec<u8>,
+}
+
+impl MessageSlot {
+    fn of(message: &PbftMessage) -> Option<Self> {
+        match message {
+            PbftMessage::PrePrepare { view, sequence_number, proposal, primary_id } => Some(Self {
+                sender: *primary_id,
+                phase: "pre-prepare",
+                view: *view,
+                sequence_number: *sequence_number,
+                digest: serde_json::to_vec(proposal).ok()?,
+            }),
+            PbftMessage::Prepare { view, sequence_number, proposal_hash, node_id } => Some(Self {
+                sender: *node_id,
+                phase: "prepare",
+                view: *view,
+                sequence_number: *sequence_number,
+                digest: proposal_hash.clone(),
+            }),
+            PbftMessage::Commit { view, sequence_number, proposal_hash, node_id } => Some(Self {
+                sender: *node_id,
+                phase: "commit",
+                view: *view,
+                sequence_number: *sequence_number,
+                digest: proposal_hash.clone(),
+            }),
+            PbftMessage::ViewChange { .. } => None,
         }
-        
-        reports
     }
 }
 
@@ -447,20 +630,107 @@ struct ViewChangeRequest {
     timestamp: SystemTime,
 }
 
-#[derive(Debug, Clone)]
-struct FaultEvidence {
-    evidence_count: u32,
-    last_seen: SystemTime,
-    fault_types: Vec<FaultType>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FaultType {
     Timeout,
     InconsistentBehavior,
     InvalidSignature,
     MessageMissing,
     ProtocolViolation,
+    /// Conflicting messages for the same consensus slot
+    Equivocation,
+}
+
+impl FaultType {
+    /// How strongly one piece of evidence of this type indicates a Byzantine node
+    pub fn weight(&self) -> f64 {
+        match self {
+            FaultType::Equivocation => 1.0,
+            FaultType::InvalidSignature => 0.9,
+            FaultType::ProtocolViolation => 0.6,
+            FaultType::InconsistentBehavior => 0.5,
+            FaultType::MessageMissing => 0.2,
+            FaultType::Timeout => 0.1,
+        }
+    }
+}
+
+/// Evidence of one fault by a member
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaultEvidence {
+    pub fault_type: FaultType,
+    /// The conflicting signed messages, for equivocation
+    #[serde(default)]
+    pub messages: Vec<SignedPbftMessage>,
+    pub description: String,
+    pub reporter: NodeId,
+    pub observed_at: SystemTime,
+}
+
+impl FaultEvidence {
+    /// Check the evidence proves a fault by `node_id`
+    ///
+    /// Only equivocation can be proven to other nodes: both messages must be
+    /// signed by the node and conflict on the same slot. Other fault types
+    /// are one reporter's word and are rejected, so they can raise suspicion
+    /// locally but never evict a member.
+    pub fn verify(&self, node_id: NodeId) -> std::result::Result<(), String> {
+        if self.fault_type != FaultType::Equivocation {
+            return Err(format!("{:?} evidence cannot be verified by other nodes", self.fault_type));
+        }
+
+        let [first, second] = self.messages.as_slice() else {
+            return Err("equivocation evidence needs exactly two messages".to_string());
+        };
+        first.verify()?;
+        second.verify()?;
+        let (Some(first), Some(second)) = (MessageSlot::of(&first.message), MessageSlot::of(&second.message)) else {
+            return Err("equivocation evidence must be pre-prepare, prepare or commit messages".to_string());
+        };
+        if first.sender != node_id || second.sender != node_id {
+            return Err(format!("messages were not sent by {}", node_id));
+        }
+        if (first.phase, first.view, first.sequence_number) != (second.phase, second.view, second.sequence_number) {
+            return Err("messages are for different consensus slots".to_string());
+        }
+        if first.digest == second.digest {
+            return Err("messages do not conflict".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Evidence collected against one node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeFaultRecord {
+    pub node_id: NodeId,
+    pub evidence: Vec<FaultEvidence>,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+}
+
+impl NodeFaultRecord {
+    /// Combined confidence that the node is Byzantine, from 0 to 1
+    pub fn confidence(&self) -> f64 {
+        1.0 - self.evidence.iter().map(|e| 1.0 - e.fault_type.weight()).product::<f64>()
+    }
+
+    /// Whether any evidence proves equivocation
+    pub fn equivocated(&self) -> bool {
+        self.evidence.iter().any(|e| e.fault_type == FaultType::Equivocation)
+    }
+}
+
+/// Suspected Byzantine members and the evidence against them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByzantineReport {
+    pub generated_at: SystemTime,
+    pub members: usize,
+    pub max_byzantine_failures: usize,
+    /// Every node with evidence, most suspect first
+    pub nodes: Vec<NodeFaultRecord>,
+    /// Nodes whose confidence reaches the detection threshold
+    pub suspected: Vec<NodeId>,
 }
 
 #[derive(Debug, Clone)]
@@ -544,4 +814,57 @@ mod tests {
         let status = coordinator.overall_byzantine_status().await;
         assert_eq!(status.view_changes_detected, 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_equivocation_evidence() {
+        let reporter = NodeId::random();
+        let key_pair = KeyPair::generate().unwrap();
+        let faulty = NodeId::new(hash(key_pair.public_key()));
+        let mut detector = FaultDetector::new(reporter, DEFAULT_FAULT_THRESHOLD);
+        let prepare = |hash: &[u8], sequence_number| PbftMessage::Prepare {
+            view: 1,
+            sequence_number,
+            proposal_hash: hash.to_vec(),
+            node_id: faulty,
+        };
+        let signed = |hash: &[u8]| SignedPbftMessage::sign(prepare(hash, 7), &key_pair);
+
+        assert!(detector.observe(&signed(b"a")).is_none());
+        // Repeating the same message is not a fault
+        assert!(detector.observe(&signed(b"a")).is_none());
+        let evidence = detector.observe(&signed(b"b")).unwrap();
+        assert_eq!(evidence.fault_type, FaultType::Equivocation);
+        assert!(evidence.verify(faulty).is_ok());
+        assert!(evidence.verify(reporter).is_err());
+        assert_eq!(detector.suspected(), vec![faulty]);
+
+        // Messages for different slots prove nothing
+        let mut forged = evidence.clone();
+        forged.messages[1] = SignedPbftMessage::sign(prepare(b"b", 8), &key_pair);
+        assert!(forged.verify(faulty).is_err());
+
+        // Nor does a conflicting message signed by anyone but the accused
+        let other = KeyPair::generate().unwrap();
+        let mut forged = evidence.clone();
+        forged.messages[1] = SignedPbftMessage::sign(prepare(b"c", 7), &other);
+        assert!(forged.verify(faulty).is_err());
+        assert!(detector.observe(&forged.messages[1]).is_none());
+
+        // A single timeout stays below the threshold, and cannot be proven
+        let slow = NodeId::random();
+        let timeout = FaultEvidence {
+            fault_type: FaultType::Timeout,
+            messages: Vec::new(),
+            description: "no prepare within 2s".to_string(),
+            reporter,
+            observed_at: SystemTime::now(),
+        };
+        assert!(timeout.verify(slow).is_err());
+        detector.record(slow, timeout);
+        assert_eq!(detector.records().len(), 2);
+        assert_eq!(detector.suspected(), vec![faulty]);
+
+        assert!(detector.forget(&faulty).unwrap().equivocated());
+        assert!(detector.suspected().is_empty());
+    }
+}
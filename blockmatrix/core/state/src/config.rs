@@ -1,7 +1,14 @@
 //! State management configuration
 //! Emergency stub implementation for Phase 1 stabilization
 
+use crate::election::ElectionConfig;
+use crate::encryption::{parse_master_key, Algorithm};
+use crate::lease::LeaseConfig;
+use crate::replication::ConsistencyLevel;
+use nexus_shared::{Validate, ValidationReport};
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
 
 /// State management configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +22,14 @@ pub struct StateConfig {
     pub transactions: TransactionConfig,
     pub encryption: EncryptionConfig,
     pub replication: ReplicationConfig,
+    #[serde(default)]
+    pub subscriptions: SubscriptionConfig,
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+    #[serde(default)]
+    pub leases: LeaseConfig,
+    #[serde(default)]
+    pub elections: ElectionConfig,
 }
 
 /// Storage configuration
@@ -27,12 +42,68 @@ pub struct StorageConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsensusConfig {
     pub algorithm: String,
+    /// Proposals taking at least this long are traced for `nexus debug state`
+    #[serde(default = "default_slow_proposal_threshold")]
+    pub slow_proposal_threshold: Duration,
+}
+
+fn default_slow_proposal_threshold() -> Duration {
+    Duration::from_millis(100)
 }
 
 /// Sharding configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShardingConfig {
     pub enabled: bool,
+    /// Replicas of each shard, capped at the number of nodes
+    #[serde(default = "default_shard_replicas")]
+    pub replicas: usize,
+    /// Shards larger than this are split
+    #[serde(default = "default_max_shard_bytes")]
+    pub max_shard_bytes: usize,
+    /// Neighbouring shards both smaller than this are merged
+    #[serde(default = "default_min_shard_bytes")]
+    pub min_shard_bytes: usize,
+    /// How often the leader plans and runs a rebalancing round
+    #[serde(default = "default_rebalance_interval")]
+    pub rebalance_interval: Duration,
+    /// Bytes per second a shard copy may send
+    #[serde(default = "default_migration_rate")]
+    pub migration_rate: usize,
+    /// Keys sent per copy batch
+    #[serde(default = "default_migration_batch")]
+    pub migration_batch: usize,
+    /// Replica moves planned per round
+    #[serde(default = "default_max_moves_per_round")]
+    pub max_moves_per_round: usize,
+}
+
+fn default_shard_replicas() -> usize {
+    3
+}
+
+fn default_max_shard_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+fn default_min_shard_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+fn default_rebalance_interval() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_migration_rate() -> usize {
+    8 * 1024 * 1024
+}
+
+fn default_migration_batch() -> usize {
+    256
+}
+
+fn default_max_moves_per_round() -> usize {
+    4
 }
 
 /// Transaction configuration
@@ -45,12 +116,97 @@ pub struct TransactionConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptionConfig {
     pub algorithm: String,
+    /// Hex-encoded 32-byte secret every data key is derived from; values are
+    /// stored in the clear without one
+    #[serde(default)]
+    pub master_key: Option<String>,
+    /// How often the leader re-encrypts a batch of values under older keys
+    #[serde(default = "default_reencrypt_interval")]
+    pub reencrypt_interval: Duration,
+    /// Values re-encrypted per batch
+    #[serde(default = "default_reencrypt_batch")]
+    pub reencrypt_batch: usize,
+}
+
+fn default_reencrypt_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_reencrypt_batch() -> usize {
+    128
 }
 
 /// Replication configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplicationConfig {
     pub factor: usize,
+    /// Consistency level for writes to keys without a matching rule
+    #[serde(default)]
+    pub default_consistency: ConsistencyLevel,
+    /// Consistency levels by key prefix; the longest matching prefix wins
+    #[serde(default)]
+    pub key_consistency: BTreeMap<String, ConsistencyLevel>,
+    /// How long a write waits on a slow replica before hedging to a spare
+    #[serde(default = "default_hedge_after")]
+    pub hedge_after: Duration,
+    /// How long a write waits for its consistency level before failing
+    #[serde(default = "default_write_timeout")]
+    pub write_timeout: Duration,
+}
+
+fn default_hedge_after() -> Duration {
+    Duration::from_millis(50)
+}
+
+fn default_write_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+impl ReplicationConfig {
+    /// Consistency level for writes to `key`
+    pub fn consistency_for(&self, key: &str) -> ConsistencyLevel {
+        self.key_consistency
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default_consistency, |(_, level)| *level)
+    }
+}
+
+/// Watch subscription configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionConfig {
+    /// Committed changes kept for watchers to resume from
+    pub history_size: usize,
+}
+
+/// Outbox configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxConfig {
+    /// Key prefixes whose committed changes are published, and their topics
+    #[serde(default)]
+    pub subscriptions: Vec<OutboxSubscription>,
+    /// How long the relay waits before retrying a failed publish
+    #[serde(default = "default_retry_interval")]
+    pub retry_interval: Duration,
+    /// Published events kept per topic for consumers to replay
+    #[serde(default = "default_retention")]
+    pub retention: u64,
+}
+
+/// Publish changes under `prefix` to `topic`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboxSubscription {
+    pub topic: String,
+    pub prefix: String,
+}
+
+fn default_retry_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_retention() -> u64 {
+    10_000
 }
 
 impl Default for StateConfig {
@@ -65,7 +221,127 @@ impl Default for StateConfig {
             transactions: TransactionConfig::default(),
             encryption: EncryptionConfig::default(),
             replication: ReplicationConfig::default(),
+            subscriptions: SubscriptionConfig::default(),
+            outbox: OutboxConfig::default(),
+            leases: LeaseConfig::default(),
+            elections: ElectionConfig::default(),
+        }
+    }
+}
+
+impl Validate for StateConfig {
+    fn validate_config(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+
+        if self.node_id.is_empty() {
+            report.error("node_id", "must not be empty");
+        }
+        if self.data_directory.is_empty() {
+            report.error("data_directory", "must not be empty");
+        }
+        if self.cluster_size == 0 {
+            report.error("cluster_size", "must be at least 1");
+        } else if self.cluster_size % 2 == 0 {
+            report.warning(
+                "cluster_size",
+                format!(
+                    "an even cluster of {} tolerates no more failures than {}",
+                    self.cluster_size,
+                    self.cluster_size - 1
+                ),
+            );
+        }
+
+        if self.replication.factor == 0 {
+            report.error("replication.factor", "must be at least 1");
+        } else if self.replication.factor > self.cluster_size {
+            report.error(
+                "replication.factor",
+                format!(
+                    "cannot place {} replicas on a cluster of {} nodes",
+                    self.replication.factor, self.cluster_size
+                ),
+            );
+        } else if self.replication.factor == 1 && self.cluster_size > 1 {
+            report.warning("replication.factor", "a single replica loses data when its node fails");
+        }
+        if self.replication.write_timeout.is_zero() {
+            report.error("replication.write_timeout", "must be greater than zero");
+        }
+        if self.replication.hedge_after >= self.replication.write_timeout {
+            report.warning("replication.hedge_after", "writes time out before they are hedged");
+        }
+
+        if self.consensus.algorithm != "raft" {
+            report.error(
+                "consensus.algorithm",
+                format!("unsupported consensus algorithm '{}'", self.consensus.algorithm),
+            );
+        }
+        if !["serializable", "snapshot", "read_committed"].contains(&self.transactions.isolation_level.as_str()) {
+            report.error(
+                "transactions.isolation_level",
+                format!("unknown isolation level '{}'", self.transactions.isolation_level),
+            );
+        }
+        if self.subscriptions.history_size == 0 {
+            report.error("subscriptions.history_size", "must be at least 1");
+        }
+        if self.outbox.retention == 0 {
+            report.error("outbox.retention", "must be at least 1");
+        }
+        if self.outbox.subscriptions.iter().any(|subscription| subscription.topic.is_empty()) {
+            report.error("outbox.subscriptions", "topics must not be empty");
+        }
+        if self.leases.min_ttl.is_zero() {
+            report.error("leases.min_ttl", "must be greater than zero");
+        }
+        if self.leases.check_interval.is_zero() {
+            report.error("leases.check_interval", "must be greater than zero");
+        } else if self.leases.check_interval >= self.leases.min_ttl {
+            report.warning("leases.check_interval", "leases can outlive their TTL by a whole check interval");
+        }
+        let sharding = &self.sharding;
+        if sharding.enabled {
+            if sharding.replicas == 0 {
+                report.error("sharding.replicas", "must be at least 1");
+            }
+            if sharding.min_shard_bytes * 2 > sharding.max_shard_bytes {
+                report.error(
+                    "sharding.min_shard_bytes",
+                    "must be at most half of max_shard_bytes, or merged shards are split again",
+                );
+            }
+            if sharding.rebalance_interval.is_zero() {
+                report.error("sharding.rebalance_interval", "must be greater than zero");
+            }
+            if sharding.migration_rate == 0 || sharding.migration_batch == 0 {
+                report.error("sharding", "migration_rate and migration_batch must be greater than zero");
+            }
+        }
+        let encryption = &self.encryption;
+        if Algorithm::parse(&encryption.algorithm).is_none() {
+            report.error(
+                "encryption.algorithm",
+                format!("unsupported encryption algorithm '{}'", encryption.algorithm),
+            );
+        }
+        if let Some(key) = &encryption.master_key {
+            if parse_master_key(key).is_err() {
+                report.error("encryption.master_key", "must be 64 hex digits");
+            }
+            if encryption.reencrypt_interval.is_zero() || encryption.reencrypt_batch == 0 {
+                report.error("encryption", "reencrypt_interval and reencrypt_batch must be greater than zero");
+            }
+        }
+        if self.elections.lease_ttl < self.leases.min_ttl {
+            report.warning(
+                "elections.lease_ttl",
+                format!("raised to the minimum lease TTL of {:?}", self.leases.min_ttl),
+            );
         }
+
+        report
     }
 }
 
@@ -81,6 +357,7 @@ impl Default for ConsensusConfig {
     fn default() -> Self {
         Self {
             algorithm: "raft".to_string(),
+            slow_proposal_threshold: default_slow_proposal_threshold(),
         }
     }
 }
@@ -89,6 +366,13 @@ impl Default for ShardingConfig {
     fn default() -> Self {
         Self {
             enabled: false,
+            replicas: default_shard_replicas(),
+            max_shard_bytes: default_max_shard_bytes(),
+            min_shard_bytes: default_min_shard_bytes(),
+            rebalance_interval: default_rebalance_interval(),
+            migration_rate: default_migration_rate(),
+            migration_batch: default_migration_batch(),
+            max_moves_per_round: default_max_moves_per_round(),
         }
     }
 }
@@ -105,6 +389,9 @@ impl Default for EncryptionConfig {
     fn default() -> Self {
         Self {
             algorithm: "aes256".to_string(),
+            master_key: None,
+            reencrypt_interval: default_reencrypt_interval(),
+            reencrypt_batch: default_reencrypt_batch(),
         }
     }
 }
@@ -113,6 +400,46 @@ impl Default for ReplicationConfig {
     fn default() -> Self {
         Self {
             factor: 3,
+            default_consistency: ConsistencyLevel::default(),
+            key_consistency: BTreeMap::new(),
+            hedge_after: default_hedge_after(),
+            write_timeout: default_write_timeout(),
+        }
+    }
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            history_size: crate::subscriptions::DEFAULT_HISTORY_SIZE,
+        }
+    }
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+            retry_interval: default_retry_interval(),
+            retention: default_retention(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation() {
+        assert!(StateConfig::default().validate_config().is_valid());
+
+        let mut config = StateConfig::default();
+        config.cluster_size = 2;
+        config.replication.factor = 3;
+
+        let report = config.validate_config();
+        assert_eq!(report.errors().next().unwrap().field, "replication.factor");
+        assert_eq!(report.warnings().next().unwrap().field, "cluster_size");
+    }
+}
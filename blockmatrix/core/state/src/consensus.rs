@@ -1,5 +1,10 @@
 //! Raft consensus implementation with Byzantine fault tolerance
 
+use crate::byzantine::{ByzantineReport, FaultDetector, FaultEvidence, SignedPbftMessage, DEFAULT_FAULT_THRESHOLD};
+use crate::diagnostics::{LogReport, MemberLag, ProposalTrace, SlowProposals};
+use crate::lease::LeaseId;
+use crate::state_machine::StateMachine;
+use crate::transactions::{TxnId, TxnRequest};
 use crate::{Result, StateError};
 use nexus_shared::NodeId;
 use serde::{Deserialize, Serialize};
@@ -44,6 +49,15 @@ pub struct ConsensusEngine {
     proposal_sender: mpsc::UnboundedSender<ProposalRequest>,
     proposal_receiver: Arc<RwLock<Option<mpsc::UnboundedReceiver<ProposalRequest>>>>,
     
+    /// Receives committed proposals
+    state_machine: Arc<RwLock<Option<Arc<StateMachine>>>>,
+    
+    /// Evidence of Byzantine behaviour by members
+    fault_detector: Arc<RwLock<FaultDetector>>,
+    
+    /// Recent proposals slower than the configured threshold
+    slow_proposals: Arc<RwLock<SlowProposals>>,
+    
     /// Statistics
     stats: Arc<RwLock<ConsensusStats>>,
 }
@@ -65,6 +79,9 @@ pub struct ConsensusConfig {
     
     /// Minimum number of confirmations for Byzantine consensus
     pub byzantine_confirmations: usize,
+    
+    /// Proposals taking at least this long are traced
+    pub slow_proposal_threshold: Duration,
 }
 
 impl Default for ConsensusConfig {
@@ -75,6 +92,7 @@ impl Default for ConsensusConfig {
             max_entries_per_request: 1000,
             byzantine_fault_tolerance: true,
             byzantine_confirmations: 3,
+            slow_proposal_threshold: Duration::from_millis(100),
         }
     }
 }
@@ -121,11 +139,50 @@ pub enum Proposal {
     Delete {
         key: String,
     },
+    /// Grant a lease
+    GrantLease {
+        id: LeaseId,
+        ttl: Duration,
+    },
+    /// Set a key-value pair attached to a lease
+    SetWithLease {
+        key: String,
+        value: Vec<u8>,
+        lease: LeaseId,
+    },
+    /// Revoke a lease, deleting the keys attached to it
+    RevokeLease {
+        id: LeaseId,
+    },
+    /// Enter an election: create a candidate key under a lease, stamped with
+    /// the revision it commits at
+    Campaign {
+        key: String,
+        value: Vec<u8>,
+        lease: LeaseId,
+    },
+    /// Run a conditional transaction; keys and values are stored form
+    Txn {
+        id: TxnId,
+        request: TxnRequest,
+    },
+    /// Replace a stored value with the same value sealed under another key
+    /// version, unless it has changed since; watchers are not notified
+    Reencrypt {
+        key: String,
+        from: Vec<u8>,
+        to: Vec<u8>,
+    },
     /// Cluster membership change
     MembershipChange {
         action: MembershipAction,
         node_id: NodeId,
     },
+    /// Remove a Byzantine member; every node checks the evidence before applying
+    EvictMember {
+        node_id: NodeId,
+        evidence: Vec<FaultEvidence>,
+    },
 }
 
 /// Membership change actions
@@ -169,6 +226,8 @@ struct ProposalContext {
 struct ProposalRequest {
     proposal: Proposal,
     response_sender: oneshot::Sender<Result<()>>,
+    submitted_at: SystemTime,
+    queued_at: Instant,
 }
 
 /// Consensus statistics
@@ -256,10 +315,18 @@ impl ConsensusEngine {
             pending_proposals: Arc::new(RwLock::new(HashMap::new())),
             proposal_sender,
             proposal_receiver: Arc::new(RwLock::new(Some(proposal_receiver))),
+            state_machine: Arc::new(RwLock::new(None)),
+            fault_detector: Arc::new(RwLock::new(FaultDetector::new(node_id, DEFAULT_FAULT_THRESHOLD))),
+            slow_proposals: Arc::new(RwLock::new(SlowProposals::new(config.slow_proposal_threshold))),
             stats: Arc::new(RwLock::new(ConsensusStats::default())),
         })
     }
     
+    /// Apply committed proposals to a state machine
+    pub async fn set_state_machine(&self, state_machine: Arc<StateMachine>) {
+        *self.state_machine.write().await = Some(state_machine);
+    }
+    
     /// Start the consensus engine
     pub async fn start(&self) -> Result<()> {
         info!("Starting consensus engine for node {}", self.node_id);
@@ -319,6 +386,8 @@ impl ConsensusEngine {
         let request = ProposalRequest {
             proposal,
             response_sender,
+            submitted_at: SystemTime::now(),
+            queued_at: Instant::now(),
         };
         
         self.proposal_sender.send(request)
@@ -342,10 +411,90 @@ impl ConsensusEngine {
         self.stats.read().await.clone()
     }
     
+    /// Current cluster members
+    pub async fn members(&self) -> Vec<NodeId> {
+        self.cluster_members.read().await.clone()
+    }
+    
+    /// Entries each member is missing from this node's log
+    ///
+    /// Only the leader tracks followers; elsewhere their lag is unknown.
+    pub async fn member_lag(&self) -> Vec<MemberLag> {
+        let members = self.cluster_members.read().await.clone();
+        let last_index = self.log.read().await.last().map(|entry| entry.index);
+        let leader_state = self.leader_state.read().await;
+        members
+            .into_iter()
+            .map(|node_id| {
+                let match_index = if node_id == self.node_id {
+                    last_index
+                } else {
+                    leader_state.as_ref().and_then(|leader| leader.match_index.get(&node_id).copied())
+                };
+                let lag = match (node_id == self.node_id, leader_state.is_some(), match_index) {
+                    (true, _, _) => Some(0),
+                    (false, true, Some(matched)) => Some(last_index.unwrap_or(0).saturating_sub(matched)),
+                    (false, true, None) => last_index.map(|last| last + 1),
+                    (false, false, _) => None,
+                };
+                MemberLag { node_id, match_index, lag }
+            })
+            .collect()
+    }
+    
+    /// Size of the log and how much of it compaction could drop
+    pub async fn log_report(&self) -> LogReport {
+        let log = self.log.read().await;
+        let applied = self.stats.read().await.proposals_committed;
+        LogReport {
+            entries: log.len() as u64,
+            bytes: log.iter().map(|entry| bincode::serialized_size(entry).unwrap_or(0)).sum(),
+            first_index: log.first().map(|entry| entry.index),
+            last_index: log.last().map(|entry| entry.index),
+            applied,
+            compaction_backlog: applied.min(log.len() as u64),
+        }
+    }
+    
+    /// Recent slow proposals, newest first, and the threshold they exceeded
+    pub async fn slow_proposals(&self) -> (Duration, Vec<ProposalTrace>) {
+        let slow = self.slow_proposals.read().await;
+        (slow.threshold(), slow.traces())
+    }
+    
+    /// Check a received signed PBFT message for equivocation
+    pub async fn observe_message(&self, message: &SignedPbftMessage) -> Option<FaultEvidence> {
+        self.fault_detector.write().await.observe(message)
+    }
+    
+    /// Record evidence of a fault by a member
+    pub async fn report_fault(&self, node_id: NodeId, evidence: FaultEvidence) {
+        self.fault_detector.write().await.record(node_id, evidence);
+    }
+    
+    /// Members with fault evidence against them
+    pub async fn byzantine_report(&self) -> ByzantineReport {
+        let (_, max_byzantine_failures) = self.check_byzantine_fault_tolerance().await;
+        let members = self.cluster_members.read().await.len();
+        let detector = self.fault_detector.read().await;
+        ByzantineReport {
+            generated_at: SystemTime::now(),
+            members,
+            max_byzantine_failures,
+            nodes: detector.records(),
+            suspected: detector.suspected(),
+        }
+    }
+    
     /// Handle incoming proposals
     async fn handle_proposals(&self, mut receiver: mpsc::UnboundedReceiver<ProposalRequest>) {
         while let Some(request) = receiver.recv().await {
+            let started = Instant::now();
+            let mut trace = ProposalTrace::new(&request.proposal, request.submitted_at, started - request.queued_at, Duration::ZERO);
             let result = self.handle_proposal(request.proposal).await;
+            trace.commit = started.elapsed();
+            trace.error = result.as_ref().err().map(ToString::to_string);
+            self.slow_proposals.write().await.record(trace);
             let _ = request.response_sender.send(result);
         }
     }
@@ -650,13 +799,19 @@ impl ConsensusEngine {
     /// Execute a committed proposal
     async fn execute_committed_proposal(&self, proposal: Proposal) -> Result<()> {
         match proposal {
-            Proposal::Set { key, value } => {
-                info!("Executing SET operation: {} = {:?}", key, value);
-                // TODO: Apply to state machine
-            }
-            Proposal::Delete { key } => {
-                info!("Executing DELETE operation: {}", key);
-                // TODO: Apply to state machine
+            Proposal::Set { .. }
+            | Proposal::Delete { .. }
+            | Proposal::GrantLease { .. }
+            | Proposal::SetWithLease { .. }
+            | Proposal::RevokeLease { .. }
+            | Proposal::Campaign { .. }
+            | Proposal::Txn { .. }
+            | Proposal::Reencrypt { .. } => {
+                debug!("Applying committed proposal: {:?}", proposal);
+                let state_machine = self.state_machine.read().await.clone();
+                if let Some(state_machine) = state_machine {
+                    state_machine.apply(&proposal).await?;
+                }
             }
             Proposal::MembershipChange { action, node_id } => {
                 info!("Executing membership change: {:?} node {}", action, node_id);
@@ -672,6 +827,18 @@ impl ConsensusEngine {
                     }
                 }
             }
+            Proposal::EvictMember { node_id, ref evidence } => {
+                // Deterministic on the committed evidence, so honest nodes
+                // all apply or all reject the eviction
+                verify_eviction(node_id, evidence)?;
+                warn!("Evicting Byzantine member {} on {} piece(s) of evidence", node_id, evidence.len());
+                self.cluster_members.write().await.retain(|&id| id != node_id);
+                self.fault_detector.write().await.forget(&node_id);
+                let state_machine = self.state_machine.read().await.clone();
+                if let Some(state_machine) = state_machine {
+                    state_machine.apply(&proposal).await?;
+                }
+            }
         }
 
         // Update stats
@@ -708,6 +875,23 @@ impl ConsensusEngine {
     }
 }
 
+/// Check evidence submitted to evict a member
+pub fn verify_eviction(node_id: NodeId, evidence: &[FaultEvidence]) -> Result<()> {
+    if evidence.is_empty() {
+        return Err(StateError::InvalidEvidence {
+            node_id: node_id.to_string(),
+            reason: "no evidence given".to_string(),
+        });
+    }
+    for item in evidence {
+        item.verify(node_id).map_err(|reason| StateError::InvalidEvidence {
+            node_id: node_id.to_string(),
+            reason,
+        })?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -863,4 +1047,38 @@ mod tests {
         let valid = engine.validate_byzantine_signatures(&entry).await.unwrap();
         assert!(valid);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_evict_member_requires_evidence() {
+        let node_id = NodeId::random();
+        let key_pair = nexus_shared::KeyPair::generate().unwrap();
+        let faulty = NodeId::new(nexus_shared::hash(key_pair.public_key()));
+        let engine = ConsensusEngine::new(&ConsensusConfig::default(), node_id).await.unwrap();
+        engine.join_cluster(vec![node_id, faulty]).await.unwrap();
+
+        for hash in [b"a", b"b"] {
+            let commit = PbftMessage::Commit {
+                view: 0,
+                sequence_number: 3,
+                proposal_hash: hash.to_vec(),
+                node_id: faulty,
+            };
+            engine.observe_message(&SignedPbftMessage::sign(commit, &key_pair)).await;
+        }
+        let report = engine.byzantine_report().await;
+        assert_eq!(report.suspected, vec![faulty]);
+        let evidence = report.nodes[0].evidence.clone();
+
+        // Evidence against another node does not evict it
+        let rejected = engine.execute_committed_proposal(Proposal::EvictMember {
+            node_id,
+            evidence: evidence.clone(),
+        }).await;
+        assert!(matches!(rejected, Err(StateError::InvalidEvidence { .. })));
+        assert_eq!(engine.members().await.len(), 2);
+
+        engine.execute_committed_proposal(Proposal::EvictMember { node_id: faulty, evidence }).await.unwrap();
+        assert_eq!(engine.members().await, vec![node_id]);
+        assert!(engine.byzantine_report().await.nodes.is_empty());
+    }
+}
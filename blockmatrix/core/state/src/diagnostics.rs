@@ -0,0 +1,175 @@
+//! State layer diagnostics
+//!
+//! One report with what otherwise takes reading the logs of every node: how
+//! far each member trails the leader's log, how large the consensus log and
+//! the store are and how much of each compaction could reclaim, how many
+//! watchers follow which prefixes, and the slowest recent proposals with
+//! where their time went. The API server serves it to `nexus debug state`.
+
+use crate::consensus::{ConsensusState, Proposal};
+use nexus_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, SystemTime};
+
+/// Slow proposals kept for the report
+const MAX_SLOW_PROPOSALS: usize = 64;
+
+/// Timing of one proposal, from submission to being applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalTrace {
+    /// Proposal variant, such as `Set` or `Txn`
+    pub kind: String,
+    /// Key written, for single-key proposals
+    pub key: Option<String>,
+    pub submitted_at: SystemTime,
+    /// Time spent waiting behind earlier proposals
+    pub queued: Duration,
+    /// Time from being picked up to being committed and applied
+    pub commit: Duration,
+    pub error: Option<String>,
+}
+
+impl ProposalTrace {
+    pub(crate) fn new(proposal: &Proposal, submitted_at: SystemTime, queued: Duration, commit: Duration) -> Self {
+        let (kind, key) = match proposal {
+            Proposal::Set { key, .. } => ("Set", Some(key)),
+            Proposal::Delete { key } => ("Delete", Some(key)),
+            Proposal::GrantLease { .. } => ("GrantLease", None),
+            Proposal::SetWithLease { key, .. } => ("SetWithLease", Some(key)),
+            Proposal::RevokeLease { .. } => ("RevokeLease", None),
+            Proposal::Campaign { key, .. } => ("Campaign", Some(key)),
+            Proposal::Txn { .. } => ("Txn", None),
+            Proposal::Reencrypt { key, .. } => ("Reencrypt", Some(key)),
+            Proposal::MembershipChange { .. } => ("MembershipChange", None),
+            Proposal::EvictMember { .. } => ("EvictMember", None),
+        };
+        Self { kind: kind.to_string(), key: key.cloned(), submitted_at, queued, commit, error: None }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.queued + self.commit
+    }
+}
+
+/// Recent proposals that took at least the threshold
+#[derive(Debug)]
+pub(crate) struct SlowProposals {
+    threshold: Duration,
+    traces: VecDeque<ProposalTrace>,
+}
+
+impl SlowProposals {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold, traces: VecDeque::new() }
+    }
+
+    pub fn record(&mut self, trace: ProposalTrace) {
+        if trace.total() < self.threshold {
+            return;
+        }
+        if self.traces.len() == MAX_SLOW_PROPOSALS {
+            self.traces.pop_front();
+        }
+        self.traces.push_back(trace);
+    }
+
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Slow proposals, newest first
+    pub fn traces(&self) -> Vec<ProposalTrace> {
+        self.traces.iter().rev().cloned().collect()
+    }
+}
+
+/// How far a member trails the leader's log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberLag {
+    pub node_id: NodeId,
+    /// Highest entry known to be replicated on the member; only the leader knows
+    pub match_index: Option<u64>,
+    /// Entries the member is missing
+    pub lag: Option<u64>,
+}
+
+/// Size of the consensus log
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LogReport {
+    pub entries: u64,
+    pub bytes: u64,
+    pub first_index: Option<u64>,
+    pub last_index: Option<u64>,
+    /// Proposals applied to the state machine
+    pub applied: u64,
+    /// Applied entries still held in the log, which compaction would drop
+    pub compaction_backlog: u64,
+}
+
+/// Size of the store on disk
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub keys: u64,
+    /// Size of the current keys and values
+    pub live_bytes: u64,
+    /// Size of the store and its write-ahead log on disk, for disk backends
+    pub disk_bytes: Option<u64>,
+    /// Disk held by overwritten and deleted data, awaiting compaction
+    pub reclaimable_bytes: Option<u64>,
+    pub wal_enabled: bool,
+}
+
+/// Watchers and the change journal they replay from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchReport {
+    pub watchers: usize,
+    pub by_prefix: BTreeMap<String, usize>,
+    pub revision: u64,
+    /// Oldest revision watchers can still resume from
+    pub oldest_revision: Option<u64>,
+    pub journal_len: usize,
+    pub journal_capacity: usize,
+}
+
+/// Health of the state layer on one node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateDiagnostics {
+    pub node_id: NodeId,
+    pub generated_at: SystemTime,
+    pub consensus_state: ConsensusState,
+    pub term: u64,
+    pub members: Vec<MemberLag>,
+    pub log: LogReport,
+    pub storage: StorageReport,
+    pub watches: WatchReport,
+    pub slow_proposal_threshold: Duration,
+    /// Newest first
+    pub slow_proposals: Vec<ProposalTrace>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_slow_proposals_are_kept() {
+        let mut slow = SlowProposals::new(Duration::from_millis(100));
+        let proposal = Proposal::Set { key: "/a".to_string(), value: Vec::new() };
+        let trace = |queued_ms, commit_ms| {
+            ProposalTrace::new(&proposal, SystemTime::now(), Duration::from_millis(queued_ms), Duration::from_millis(commit_ms))
+        };
+
+        slow.record(trace(10, 20));
+        slow.record(trace(80, 30));
+        for _ in 0..MAX_SLOW_PROPOSALS {
+            slow.record(trace(0, 150));
+        }
+
+        let traces = slow.traces();
+        assert_eq!(traces.len(), MAX_SLOW_PROPOSALS);
+        assert!(traces.iter().all(|t| t.total() >= slow.threshold()));
+        assert_eq!(traces[0].key.as_deref(), Some("/a"));
+        assert_eq!(traces[0].kind, "Set");
+    }
+}
@@ -0,0 +1,229 @@
+//! Leader election
+//!
+//! Candidates for an election each hold a key under `/_election/<name>/`,
+//! attached to a lease the candidate's node keeps alive. The key is written
+//! by a committed `Campaign` proposal, which stamps it with the revision it
+//! was committed at. The candidate with the lowest revision leads; the others
+//! wait their turn in revision order. A leader that resigns revokes its lease,
+//! and one whose node dies lets it lapse, and either way its key is deleted
+//! and the next candidate takes over.
+//!
+//! The winning revision doubles as a fencing token: each leader's token is
+//! larger than every earlier leader's, so a resource that remembers the
+//! largest token it has seen can turn away a deposed leader that has not yet
+//! noticed.
+
+use crate::error::{Result, StateError};
+use crate::lease::{LeaseId, LeaseRenewer};
+use crate::range::KeyValue;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Prefix under which candidates of every election are kept
+pub const ELECTION_PREFIX: &str = "/_election/";
+
+/// Leader election configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElectionConfig {
+    /// TTL of a candidate's lease: how long a dead leader goes unnoticed
+    pub lease_ttl: Duration,
+}
+
+impl Default for ElectionConfig {
+    fn default() -> Self {
+        Self {
+            lease_ttl: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Keys of an election's candidates start with this
+pub fn election_prefix(election: &str) -> String {
+    format!("{}{}/", ELECTION_PREFIX, election)
+}
+
+/// Key of the candidate holding `lease`
+pub fn candidate_key(election: &str, lease: LeaseId) -> String {
+    format!("{}{:016x}", election_prefix(election), lease)
+}
+
+pub(crate) fn validate_election_name(election: &str) -> Result<()> {
+    if election.is_empty() || election.contains('/') {
+        return Err(StateError::InvalidKey { key: election_prefix(election) });
+    }
+    Ok(())
+}
+
+/// Stored value of a candidate's key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Candidacy {
+    /// Revision the campaign was committed at
+    pub revision: u64,
+    pub value: Vec<u8>,
+}
+
+/// The leader of an election
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderInfo {
+    pub election: String,
+    pub key: String,
+    pub lease: LeaseId,
+    /// Value the leader campaigned with, such as its address
+    pub value: Vec<u8>,
+    pub fencing_token: u64,
+}
+
+fn lease_of(key: &str, prefix: &str) -> Option<LeaseId> {
+    LeaseId::from_str_radix(key.strip_prefix(prefix)?, 16).ok()
+}
+
+/// Candidacy stored under `key` among an election's entries
+pub(crate) fn candidacy(entries: &[KeyValue], key: &str) -> Option<Candidacy> {
+    let entry = entries.iter().find(|entry| entry.key == key)?;
+    serde_json::from_slice(&entry.value).ok()
+}
+
+/// Leader among the entries under an election's prefix
+pub(crate) fn leader_of(election: &str, entries: &[KeyValue]) -> Option<LeaderInfo> {
+    let prefix = election_prefix(election);
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let lease = lease_of(&entry.key, &prefix)?;
+            let candidacy: Candidacy = serde_json::from_slice(&entry.value).ok()?;
+            Some((candidacy, entry.key.clone(), lease))
+        })
+        .min_by_key(|(candidacy, _, _)| candidacy.revision)
+        .map(|(candidacy, key, lease)| LeaderInfo {
+            election: election.to_string(),
+            key,
+            lease,
+            value: candidacy.value,
+            fencing_token: candidacy.revision,
+        })
+}
+
+/// Renews a lease with the consensus leader until dropped
+pub(crate) struct LeaseKeepAlive(JoinHandle<()>);
+
+impl LeaseKeepAlive {
+    pub fn spawn(renewer: LeaseRenewer, lease: LeaseId, ttl: Duration) -> Self {
+        Self(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(ttl / 3);
+            loop {
+                ticker.tick().await;
+                match renewer.renew(lease).await {
+                    Ok(_) => {}
+                    Err(StateError::LeaseNotFound { .. }) => {
+                        tracing::warn!("Lease {} was lost; stopping keep-alive", lease);
+                        return;
+                    }
+                    // One missed renewal leaves two more before the lease lapses
+                    Err(e) => tracing::debug!("Keep-alive of lease {} failed: {}", lease, e),
+                }
+            }
+        }))
+    }
+}
+
+impl Drop for LeaseKeepAlive {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Leadership won in an election
+///
+/// Dropping it stops renewing the lease, so leadership passes on once the
+/// lease lapses; `StateManager::resign` hands it over at once.
+pub struct Leadership {
+    pub(crate) leader: LeaderInfo,
+    pub(crate) _keep_alive: LeaseKeepAlive,
+}
+
+impl Leadership {
+    pub fn election(&self) -> &str {
+        &self.leader.election
+    }
+
+    pub fn lease(&self) -> LeaseId {
+        self.leader.lease
+    }
+
+    /// Pass with every action on a fenced resource
+    pub fn fencing_token(&self) -> u64 {
+        self.leader.fencing_token
+    }
+
+    pub fn info(&self) -> &LeaderInfo {
+        &self.leader
+    }
+}
+
+impl std::fmt::Debug for Leadership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Leadership").field("leader", &self.leader).finish()
+    }
+}
+
+/// Follows who leads an election
+pub struct LeaderObserver {
+    pub(crate) receiver: watch::Receiver<Option<LeaderInfo>>,
+    pub(crate) task: JoinHandle<()>,
+}
+
+impl LeaderObserver {
+    /// Current leader, `None` while there is none
+    pub fn leader(&self) -> Option<LeaderInfo> {
+        self.receiver.borrow().clone()
+    }
+
+    /// Wait for the leader to change and return the new one
+    ///
+    /// Fails once the store stops.
+    pub async fn changed(&mut self) -> Result<Option<LeaderInfo>> {
+        self.receiver.changed().await.map_err(|_| StateError::Leadership {
+            message: "state manager stopped".to_string(),
+        })?;
+        Ok(self.receiver.borrow_and_update().clone())
+    }
+}
+
+impl Drop for LeaderObserver {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(election: &str, lease: LeaseId, revision: u64) -> KeyValue {
+        KeyValue {
+            key: candidate_key(election, lease),
+            value: serde_json::to_vec(&Candidacy { revision, value: format!("node-{}", lease).into_bytes() }).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_lowest_revision_leads() {
+        assert_eq!(candidate_key("scheduler", 0xab), "/_election/scheduler/00000000000000ab");
+        assert!(validate_election_name("scheduler").is_ok());
+        assert!(validate_election_name("a/b").is_err());
+
+        // Lease ids do not decide; the earliest campaign does
+        let entries = vec![entry("scheduler", 1, 12), entry("scheduler", 2, 7), entry("scheduler", 3, 9)];
+        let leader = leader_of("scheduler", &entries).unwrap();
+        assert_eq!((leader.lease, leader.fencing_token), (2, 7));
+        assert_eq!(leader.value, b"node-2");
+        assert_eq!(candidacy(&entries, &candidate_key("scheduler", 3)).unwrap().revision, 9);
+
+        // Once it is gone the next in line takes over with a larger token
+        let next = leader_of("scheduler", &[entries[0].clone(), entries[2].clone()]).unwrap();
+        assert_eq!((next.lease, next.fencing_token), (3, 9));
+        assert!(leader_of("scheduler", &[]).is_none());
+    }
+}
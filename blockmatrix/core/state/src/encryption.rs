@@ -1,50 +1,370 @@
-//! Encryption utilities for state management
-//! Emergency stub implementation for Phase 1 stabilization
+//! Encryption of state values at rest, with key rotation
+//!
+//! Values are sealed with AES-256-GCM or ChaCha20-Poly1305 under a numbered
+//! data key, and carry the version they were sealed under. Version 1 is
+//! derived from the configured master key, so every node can seal values
+//! before any rotation. Rotating generates a fresh random key, commits it
+//! wrapped under the master key at [`data_key_key`] and then commits the new
+//! active version under [`KEY_VERSION_KEY`], so every node installs the key
+//! before it seals anything under it. Later writes are sealed under the new
+//! version, values under older versions still open, and the leader
+//! re-encrypts them in the background until a pass over the store finds
+//! none left. Keys are stored in the clear, so range scans keep their
+//! order. Without a master key values pass through unencrypted, and values
+//! written before encryption was enabled are read as they are.
 
-use crate::error::Result;
+use crate::error::{Result, StateError};
+use futures::future::BoxFuture;
+use nexus_shared::KeyWrapper;
+use parking_lot::Mutex;
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Key the active data key version is stored under
+pub const KEY_VERSION_KEY: &str = "/_encryption/active_version";
+
+/// Prefix of the keys rotated data keys are stored under, wrapped
+pub const DATA_KEY_PREFIX: &str = "/_encryption/keys/";
+
+/// Key a rotated data key version is stored under
+pub fn data_key_key(version: u32) -> String {
+    format!("{}{}", DATA_KEY_PREFIX, version)
+}
+
+/// Version a data key record is stored for
+pub fn data_key_version(key: &str) -> Option<u32> {
+    key.strip_prefix(DATA_KEY_PREFIX)?.parse().ok()
+}
+
+/// Leads every sealed value, followed by its big-endian key version
+const MAGIC: &[u8] = b"\x00nxe";
+const HEADER_LEN: usize = MAGIC.len() + 4;
+const DATA_KEY_CONTEXT: &str = "nexus state data key v1";
+const KEY_ENCRYPTION_CONTEXT: &str = "nexus state key encryption key v1";
+
+/// Leads every wrapped data key; unlike `MAGIC` it never opens as a sealed value
+const WRAPPED_KEY_MAGIC: &[u8] = b"nxk1";
+
+/// Data key version every node derives from the master key
+const INITIAL_VERSION: u32 = 1;
+
+/// AEAD values are sealed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    /// Algorithm for a configured name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "aes256" | "aes256-gcm" => Some(Algorithm::Aes256Gcm),
+            "chacha20" | "chacha20-poly1305" => Some(Algorithm::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn aead(self) -> &'static aead::Algorithm {
+        match self {
+            Algorithm::Aes256Gcm => &aead::AES_256_GCM,
+            Algorithm::ChaCha20Poly1305 => &aead::CHACHA20_POLY1305,
+        }
+    }
+}
+
+/// Decode a hex-encoded 32-byte master key
+pub fn parse_master_key(hex: &str) -> Result<[u8; 32]> {
+    let invalid = || StateError::Encryption { message: "master key must be 64 hex digits".to_string() };
+    if hex.len() != 64 {
+        return Err(invalid());
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        let digits = hex.get(2 * i..2 * i + 2).ok_or_else(invalid)?;
+        *byte = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+    }
+    Ok(key)
+}
+
+/// Progress of re-encrypting values sealed under older key versions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReencryptionStats {
+    pub active_version: u32,
+    /// Values checked so far in the current pass over the store
+    pub scanned: u64,
+    /// Values found under older versions so far in the current pass
+    pub stale: u64,
+    /// Values re-encrypted since this node started
+    pub reencrypted: u64,
+    pub completed_passes: u64,
+    /// Whether the last complete pass found every value under the active version
+    pub up_to_date: bool,
+}
+
+struct Keyring {
+    master: [u8; 32],
+    algorithm: Algorithm,
+    keys: HashMap<u32, Arc<LessSafeKey>>,
+}
+
+impl Keyring {
+    fn key(&mut self, version: u32) -> Result<Arc<LessSafeKey>> {
+        if let Some(key) = self.keys.get(&version) {
+            return Ok(key.clone());
+        }
+        if version != INITIAL_VERSION {
+            return Err(StateError::Encryption { message: format!("data key version {} is not installed", version) });
+        }
+        let mut material = self.master.to_vec();
+        material.extend_from_slice(&version.to_be_bytes());
+        let key = self.bind(&blake3::derive_key(DATA_KEY_CONTEXT, &material));
+        self.keys.insert(version, key.clone());
+        Ok(key)
+    }
+
+    fn bind(&self, key: &[u8; 32]) -> Arc<LessSafeKey> {
+        Arc::new(LessSafeKey::new(UnboundKey::new(self.algorithm.aead(), key).expect("32-byte key")))
+    }
+
+    /// Key rotated data keys are wrapped under
+    fn key_encryption_key(&self) -> LessSafeKey {
+        let key = blake3::derive_key(KEY_ENCRYPTION_CONTEXT, &self.master);
+        LessSafeKey::new(UnboundKey::new(&aead::AES_256_GCM, &key).expect("32-byte key"))
+    }
+}
 
 /// Encryption manager for state keys and values
-#[derive(Debug, Clone)]
 pub struct EncryptionManager {
-    // Stub implementation
+    keyring: Option<Mutex<Keyring>>,
+    active: AtomicU32,
+    rng: SystemRandom,
+    stats: Mutex<ReencryptionStats>,
 }
 
 /// State encryption interface (alias for EncryptionManager)
 pub type StateEncryption = EncryptionManager;
 
 impl EncryptionManager {
-    /// Create new encryption manager
+    /// Create an encryption manager that stores values unencrypted
     pub fn new() -> Self {
-        Self {}
+        Self {
+            keyring: None,
+            active: AtomicU32::new(1),
+            rng: SystemRandom::new(),
+            stats: Mutex::new(ReencryptionStats { active_version: 1, ..Default::default() }),
+        }
     }
 
     /// Create new encryption manager from config
-    pub fn from_config(_config: &crate::config::EncryptionConfig) -> Self {
-        Self {}
+    pub fn from_config(config: &crate::config::EncryptionConfig) -> Result<Self> {
+        let algorithm = Algorithm::parse(&config.algorithm).ok_or_else(|| StateError::Encryption {
+            message: format!("unsupported encryption algorithm '{}'", config.algorithm),
+        })?;
+        let mut manager = Self::new();
+        if let Some(master_key) = &config.master_key {
+            let master = parse_master_key(master_key)?;
+            manager.keyring = Some(Mutex::new(Keyring { master, algorithm, keys: HashMap::new() }));
+        }
+        Ok(manager)
     }
 
-    /// Encrypt a key - stub implementation returns key as-is
+    /// Whether values are encrypted at all
+    pub fn is_enabled(&self) -> bool {
+        self.keyring.is_some()
+    }
+
+    /// Version of the data key new values are sealed under
+    pub fn active_version(&self) -> u32 {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Seal new values under a committed version, unless a newer one is active
+    pub fn activate(&self, version: u32) -> bool {
+        if self.active.fetch_max(version, Ordering::SeqCst) >= version {
+            return false;
+        }
+        let mut stats = self.stats.lock();
+        *stats = ReencryptionStats {
+            active_version: version,
+            reencrypted: stats.reencrypted,
+            completed_passes: stats.completed_passes,
+            ..Default::default()
+        };
+        true
+    }
+
+    /// Key version a stored value was sealed under, `None` for a value stored in the clear
+    pub fn version_of(&self, data: &[u8]) -> Option<u32> {
+        if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+            return None;
+        }
+        let version = data[MAGIC.len()..HEADER_LEN].try_into().expect("4-byte version");
+        Some(u32::from_be_bytes(version))
+    }
+
+    /// Whether a stored value should be sealed again under the active version
+    pub fn needs_reencryption(&self, data: &[u8]) -> bool {
+        self.is_enabled() && self.version_of(data) != Some(self.active_version())
+    }
+
+    /// Encrypt a key - keys are stored in the clear to keep their order
     pub async fn encrypt_key(&self, key: &str) -> Result<String> {
-        // Stub: just return the key for now
         Ok(key.to_string())
     }
 
-    /// Decrypt a key - stub implementation returns key as-is  
+    /// Decrypt a key - keys are stored in the clear to keep their order
     pub async fn decrypt_key(&self, encrypted_key: &str) -> Result<String> {
-        // Stub: just return the key for now
         Ok(encrypted_key.to_string())
     }
 
-    /// Encrypt data - stub implementation returns data as-is
+    /// Seal data under the active key version
     pub async fn encrypt_data(&self, data: &[u8]) -> Result<Vec<u8>> {
-        // Stub: just return the data for now
-        Ok(data.to_vec())
+        let Some(keyring) = &self.keyring else {
+            return Ok(data.to_vec());
+        };
+        let version = self.active_version();
+        let key = keyring.lock().key(version)?;
+
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&version.to_be_bytes());
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| StateError::Encryption { message: "failed to generate a nonce".to_string() })?;
+        let mut sealed = data.to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&header), &mut sealed)
+            .map_err(|_| StateError::Encryption { message: "failed to seal value".to_string() })?;
+
+        let mut out = header;
+        out.extend_from_slice(&nonce);
+        out.extend(sealed);
+        Ok(out)
     }
 
-    /// Decrypt data - stub implementation returns data as-is
+    /// Open data sealed under any key version, or return data stored in the clear
     pub async fn decrypt_data(&self, encrypted_data: &[u8]) -> Result<Vec<u8>> {
-        // Stub: just return the data for now
-        Ok(encrypted_data.to_vec())
+        let Some(version) = self.version_of(encrypted_data) else {
+            return Ok(encrypted_data.to_vec());
+        };
+        let keyring = self.keyring.as_ref().ok_or_else(|| StateError::Encryption {
+            message: "value is encrypted but no master key is configured".to_string(),
+        })?;
+        let invalid = || StateError::Encryption { message: format!("value does not open under key version {}", version) };
+        let (header, rest) = encrypted_data.split_at(HEADER_LEN);
+        if rest.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+        let key = keyring.lock().key(version)?;
+        let mut value = ciphertext.to_vec();
+        let plaintext = key.open_in_place(nonce, Aad::from(header), &mut value).map_err(|_| invalid())?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Generate a fresh random data key for `version`, wrapped under the master key
+    ///
+    /// The key is not used until the wrapped record is installed with
+    /// [`install_key`](Self::install_key), on every node through consensus.
+    pub fn new_data_key(&self, version: u32) -> Result<Vec<u8>> {
+        let keyring = self.keyring.as_ref().ok_or_else(|| StateError::Encryption {
+            message: "no master key is configured".to_string(),
+        })?;
+        let failed = |what: &str| StateError::Encryption { message: format!("failed to {} data key", what) };
+
+        let mut key = [0u8; 32];
+        self.rng.fill(&mut key).map_err(|_| failed("generate"))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(|_| failed("generate a nonce for the"))?;
+
+        let mut sealed = key.to_vec();
+        keyring
+            .lock()
+            .key_encryption_key()
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(version.to_be_bytes()), &mut sealed)
+            .map_err(|_| failed("wrap"))?;
+
+        let mut out = WRAPPED_KEY_MAGIC.to_vec();
+        out.extend_from_slice(&nonce);
+        out.extend(sealed);
+        Ok(out)
+    }
+
+    /// Unwrap a data key made by [`new_data_key`](Self::new_data_key) and open values under its version
+    pub fn install_key(&self, version: u32, wrapped: &[u8]) -> Result<()> {
+        let keyring = self.keyring.as_ref().ok_or_else(|| StateError::Encryption {
+            message: "no master key is configured".to_string(),
+        })?;
+        let invalid = || StateError::Encryption { message: format!("data key version {} does not unwrap", version) };
+        let rest = wrapped.strip_prefix(WRAPPED_KEY_MAGIC).ok_or_else(invalid)?;
+        if rest.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+
+        let mut keyring = keyring.lock();
+        let mut value = ciphertext.to_vec();
+        let key: [u8; 32] = keyring
+            .key_encryption_key()
+            .open_in_place(nonce, Aad::from(version.to_be_bytes()), &mut value)
+            .map_err(|_| invalid())?
+            .try_into()
+            .map_err(|_| invalid())?;
+        let key = keyring.bind(&key);
+        keyring.keys.insert(version, key);
+        Ok(())
+    }
+
+    pub fn stats(&self) -> ReencryptionStats {
+        self.stats.lock().clone()
+    }
+
+    /// Count a batch of values checked by the re-encryption pass
+    pub(crate) fn record_scan(&self, scanned: usize, stale: usize) {
+        let mut stats = self.stats.lock();
+        stats.scanned += scanned as u64;
+        stats.stale += stale as u64;
+        stats.reencrypted += stale as u64;
+    }
+
+    /// Finish a pass over the store, starting the next from scratch
+    pub(crate) fn record_pass_complete(&self) {
+        let mut stats = self.stats.lock();
+        stats.completed_passes += 1;
+        stats.up_to_date = stats.stale == 0;
+        stats.scanned = 0;
+        stats.stale = 0;
+    }
+}
+
+/// Wraps other components' data keys, such as volume keys, under the
+/// active data key version
+impl KeyWrapper for EncryptionManager {
+    fn wrap_key<'a>(&'a self, key: &'a [u8]) -> BoxFuture<'a, nexus_shared::Result<Vec<u8>>> {
+        Box::pin(async move {
+            if !self.is_enabled() {
+                return Err(StateError::Encryption {
+                    message: "cannot wrap keys without a master key".to_string(),
+                }
+                .into());
+            }
+            Ok(self.encrypt_data(key).await?)
+        })
+    }
+
+    fn unwrap_key<'a>(&'a self, wrapped: &'a [u8]) -> BoxFuture<'a, nexus_shared::Result<Vec<u8>>> {
+        Box::pin(async move {
+            if self.version_of(wrapped).is_none() {
+                return Err(StateError::Encryption { message: "key is not wrapped".to_string() }.into());
+            }
+            Ok(self.decrypt_data(wrapped).await?)
+        })
     }
 }
 
@@ -52,4 +372,57 @@ impl Default for EncryptionManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+impl std::fmt::Debug for EncryptionManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionManager")
+            .field("enabled", &self.is_enabled())
+            .field("active_version", &self.active_version())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EncryptionConfig;
+
+    #[tokio::test]
+    async fn test_rotated_values_still_open() {
+        let config = EncryptionConfig { master_key: Some("2a".repeat(32)), ..Default::default() };
+        let manager = EncryptionManager::from_config(&config).unwrap();
+        let other_node = EncryptionManager::from_config(&config).unwrap();
+
+        let old = manager.encrypt_data(b"secret").await.unwrap();
+        assert_eq!(manager.version_of(&old), Some(1));
+        assert!(!manager.needs_reencryption(&old));
+
+        // Rotated keys are fresh and only usable once installed
+        let wrapped = manager.new_data_key(2).unwrap();
+        assert_ne!(wrapped, manager.new_data_key(2).unwrap());
+        assert!(manager.activate(2));
+        assert!(!manager.activate(1));
+        assert!(manager.encrypt_data(b"secret").await.is_err());
+        assert!(manager.install_key(3, &wrapped).is_err());
+        manager.install_key(2, &wrapped).unwrap();
+        other_node.install_key(2, &wrapped).unwrap();
+        let new = manager.encrypt_data(b"secret").await.unwrap();
+        assert_eq!(manager.version_of(&new), Some(2));
+        assert!(manager.needs_reencryption(&old));
+
+        // Every node opens every version it has the key for
+        assert_eq!(other_node.decrypt_data(&old).await.unwrap(), b"secret");
+        assert_eq!(other_node.decrypt_data(&new).await.unwrap(), b"secret");
+
+        // Values written before encryption was enabled still read, and are re-encrypted
+        assert_eq!(manager.decrypt_data(b"plain").await.unwrap(), b"plain");
+        assert!(manager.needs_reencryption(b"plain"));
+
+        // A sealed value cannot be passed off as another version
+        let mut relabelled = new.clone();
+        relabelled[MAGIC.len() + 3] = 1;
+        assert!(manager.decrypt_data(&relabelled).await.is_err());
+        assert!(EncryptionManager::new().decrypt_data(&new).await.is_err());
+    }
+}
@@ -56,9 +56,36 @@ pub enum StateError {
     #[error("Node not in cluster: {node_id}")]
     NodeNotInCluster { node_id: String },
 
+    #[error("Invalid fault evidence against {node_id}: {reason}")]
+    InvalidEvidence { node_id: String, reason: String },
+
     #[error("Split brain detected: multiple leaders")]
     SplitBrain,
 
+    #[error("Revision {revision} has been compacted; oldest available is {oldest}")]
+    RevisionCompacted { revision: u64, oldest: u64 },
+
+    #[error("Revision {revision} is newer than the current revision {current}")]
+    FutureRevision { revision: u64, current: u64 },
+
+    #[error("Invalid continue token: {token}")]
+    InvalidContinueToken { token: String },
+
+    #[error("Offset {offset} of topic {topic} has been compacted; oldest available is {oldest}")]
+    OffsetCompacted { topic: String, offset: u64, oldest: u64 },
+
+    #[error("Lease {id} not found")]
+    LeaseNotFound { id: u64 },
+
+    #[error("Fencing token {token} of election {election} is stale")]
+    StaleFencingToken { election: String, token: u64 },
+
+    #[error("Transport error: {message}")]
+    Transport { message: String },
+
+    #[error("Event bus error: {message}")]
+    EventBus { message: String },
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -85,6 +112,8 @@ impl StateError {
             StateError::Leadership { .. } => true,
             StateError::Io(_) => true,
             StateError::Join(_) => true,
+            StateError::EventBus { .. } => true,
+            StateError::Transport { .. } => true,
             _ => false,
         }
     }
@@ -93,7 +122,7 @@ impl StateError {
     pub fn is_leadership_error(&self) -> bool {
         matches!(
             self,
-            StateError::Leadership { .. } | StateError::SplitBrain
+            StateError::Leadership { .. } | StateError::SplitBrain | StateError::StaleFencingToken { .. }
         )
     }
 
@@ -124,7 +153,16 @@ impl StateError {
             StateError::TransactionTimeout { .. } => "transaction_timeout",
             StateError::QuorumNotAvailable { .. } => "quorum",
             StateError::NodeNotInCluster { .. } => "node_not_in_cluster",
+            StateError::InvalidEvidence { .. } => "invalid_evidence",
             StateError::SplitBrain => "split_brain",
+            StateError::RevisionCompacted { .. } => "revision_compacted",
+            StateError::FutureRevision { .. } => "future_revision",
+            StateError::InvalidContinueToken { .. } => "invalid_continue_token",
+            StateError::OffsetCompacted { .. } => "offset_compacted",
+            StateError::LeaseNotFound { .. } => "lease_not_found",
+            StateError::StaleFencingToken { .. } => "stale_fencing_token",
+            StateError::Transport { .. } => "transport",
+            StateError::EventBus { .. } => "event_bus",
             StateError::Serialization(_) => "serialization",
             StateError::Io(_) => "io",
             StateError::Time(_) => "time",
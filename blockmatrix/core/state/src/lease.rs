@@ -0,0 +1,336 @@
+//! Leases and keys that expire with them
+//!
+//! A lease is granted with a time to live and keys are written under it. The
+//! holder keeps the lease alive by renewing it, directly or over the transport
+//! from another node, before the TTL runs out; once it lapses the lease is
+//! revoked through consensus, and every node deletes the keys attached to it
+//! when it applies the revocation, so watchers see ordinary deletes. Ephemeral
+//! service registrations and leader election locks are keys under a lease:
+//! they vanish when their owner stops renewing it.
+//!
+//! Grants, attachments and revocations are committed proposals and so agree
+//! on every node. Deadlines are not: each node times a lease from when it
+//! applied the grant or last saw a renewal, and only the leader proposes
+//! revocations. Renewals made on a follower are therefore forwarded to the
+//! consensus leader, whose deadline is the one that counts.
+
+use crate::consensus::{ConsensusEngine, ConsensusState};
+use crate::error::{Result, StateError};
+use nexus_shared::NodeId;
+use nexus_transport::{MessageType, QuicClient, TransportMessage};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Identifies a lease across the cluster
+pub type LeaseId = u64;
+
+/// Lease configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseConfig {
+    /// Shortest TTL granted; shorter requests are raised to it
+    pub min_ttl: Duration,
+    /// How often the leader looks for lapsed leases
+    pub check_interval: Duration,
+}
+
+impl Default for LeaseConfig {
+    fn default() -> Self {
+        Self {
+            min_ttl: Duration::from_secs(5),
+            check_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A lease as seen by this node
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaseInfo {
+    pub id: LeaseId,
+    pub ttl: Duration,
+    /// Time left before the lease lapses
+    pub remaining: Duration,
+    /// Keys deleted when the lease is revoked
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug)]
+struct LeaseEntry {
+    ttl: Duration,
+    deadline: Instant,
+    keys: BTreeSet<String>,
+}
+
+#[derive(Debug, Default)]
+struct LeaseTable {
+    leases: HashMap<LeaseId, LeaseEntry>,
+    /// Lease each attached key belongs to
+    owners: HashMap<String, LeaseId>,
+}
+
+/// Leases granted on this node's copy of the store and the keys under them
+///
+/// Keys are held as stored, after encryption.
+#[derive(Debug, Default)]
+pub struct LeaseManager {
+    table: Mutex<LeaseTable>,
+}
+
+impl LeaseManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a committed grant; false if the lease already exists
+    pub fn grant(&self, id: LeaseId, ttl: Duration, now: Instant) -> bool {
+        let mut table = self.table.lock();
+        if table.leases.contains_key(&id) {
+            return false;
+        }
+        table.leases.insert(id, LeaseEntry { ttl, deadline: now + ttl, keys: BTreeSet::new() });
+        true
+    }
+
+    pub fn exists(&self, id: LeaseId) -> bool {
+        self.table.lock().leases.contains_key(&id)
+    }
+
+    /// Attach a key to a lease, moving it off any lease it was under
+    ///
+    /// False if the lease does not exist.
+    pub fn attach(&self, id: LeaseId, key: &str) -> bool {
+        let mut table = self.table.lock();
+        if !table.leases.contains_key(&id) {
+            return false;
+        }
+        Self::detach_locked(&mut table, key);
+        table.owners.insert(key.to_string(), id);
+        if let Some(lease) = table.leases.get_mut(&id) {
+            lease.keys.insert(key.to_string());
+        }
+        true
+    }
+
+    /// Take a key off its lease, as when it is written without one or deleted
+    pub fn detach(&self, key: &str) {
+        Self::detach_locked(&mut self.table.lock(), key);
+    }
+
+    fn detach_locked(table: &mut LeaseTable, key: &str) {
+        if let Some(id) = table.owners.remove(key) {
+            if let Some(lease) = table.leases.get_mut(&id) {
+                lease.keys.remove(key);
+            }
+        }
+    }
+
+    /// Lease a key is attached to
+    pub fn owner(&self, key: &str) -> Option<LeaseId> {
+        self.table.lock().owners.get(key).copied()
+    }
+
+    /// Renew a lease for another TTL, returning the TTL
+    ///
+    /// `None` if the lease does not exist or has already lapsed; a lapsed
+    /// lease is about to be revoked and cannot be revived.
+    pub fn keep_alive(&self, id: LeaseId, now: Instant) -> Option<Duration> {
+        let mut table = self.table.lock();
+        let lease = table.leases.get_mut(&id).filter(|lease| lease.deadline > now)?;
+        lease.deadline = now + lease.ttl;
+        Some(lease.ttl)
+    }
+
+    /// Drop a committed revocation's lease, returning the keys to delete
+    pub fn revoke(&self, id: LeaseId) -> Option<Vec<String>> {
+        let mut table = self.table.lock();
+        let lease = table.leases.remove(&id)?;
+        for key in &lease.keys {
+            table.owners.remove(key);
+        }
+        Some(lease.keys.into_iter().collect())
+    }
+
+    /// Keys attached to a lease
+    pub fn keys(&self, id: LeaseId) -> Option<Vec<String>> {
+        self.table.lock().leases.get(&id).map(|lease| lease.keys.iter().cloned().collect())
+    }
+
+    /// Leases whose deadline has passed, oldest first
+    pub fn expired(&self, now: Instant) -> Vec<LeaseId> {
+        let table = self.table.lock();
+        let mut expired: Vec<(Instant, LeaseId)> = table
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.deadline <= now)
+            .map(|(id, lease)| (lease.deadline, *id))
+            .collect();
+        expired.sort();
+        expired.into_iter().map(|(_, id)| id).collect()
+    }
+
+    pub fn info(&self, id: LeaseId, now: Instant) -> Option<LeaseInfo> {
+        let table = self.table.lock();
+        let lease = table.leases.get(&id)?;
+        Some(LeaseInfo {
+            id,
+            ttl: lease.ttl,
+            remaining: lease.deadline.saturating_duration_since(now),
+            keys: lease.keys.iter().cloned().collect(),
+        })
+    }
+
+    pub fn leases(&self) -> Vec<LeaseId> {
+        let mut ids: Vec<LeaseId> = self.table.lock().leases.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+/// Lease operation sent to the leader over the transport
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LeaseRequest {
+    Grant { ttl: Duration },
+    KeepAlive { id: LeaseId },
+    Revoke { id: LeaseId },
+}
+
+/// Reply to a `LeaseRequest`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LeaseResponse {
+    Granted { id: LeaseId, ttl: Duration },
+    KeptAlive { id: LeaseId, ttl: Duration },
+    Revoked { id: LeaseId },
+    NotFound { id: LeaseId },
+    Failed { message: String },
+}
+
+impl LeaseRequest {
+    pub fn to_message(&self, source: NodeId, destination: NodeId) -> Result<TransportMessage> {
+        Ok(TransportMessage::new(MessageType::Data, source, Some(destination), serde_json::to_vec(self)?))
+    }
+
+    pub fn from_message(message: &TransportMessage) -> Result<Self> {
+        Ok(serde_json::from_slice(&message.payload)?)
+    }
+}
+
+impl LeaseResponse {
+    pub fn to_message(&self, source: NodeId, destination: NodeId) -> Result<TransportMessage> {
+        Ok(TransportMessage::new(MessageType::Data, source, Some(destination), serde_json::to_vec(self)?))
+    }
+
+    pub fn from_message(message: &TransportMessage) -> Result<Self> {
+        Ok(serde_json::from_slice(&message.payload)?)
+    }
+}
+
+/// Renew a lease held on `leader`, returning its TTL
+pub async fn keep_alive_remote(client: &QuicClient, leader: NodeId, id: LeaseId, timeout: Duration) -> Result<Duration> {
+    let request = LeaseRequest::KeepAlive { id }.to_message(client.node_id(), leader)?;
+    let reply = client
+        .send_request(leader, request, timeout)
+        .await
+        .map_err(|e| StateError::Transport { message: e.to_string() })?;
+    match LeaseResponse::from_message(&reply)? {
+        LeaseResponse::KeptAlive { ttl, .. } => Ok(ttl),
+        LeaseResponse::NotFound { id } => Err(StateError::LeaseNotFound { id }),
+        LeaseResponse::Failed { message } => Err(StateError::Transport { message }),
+        other => Err(StateError::Transport { message: format!("unexpected reply {:?}", other) }),
+    }
+}
+
+/// Renews leases where they are timed: locally on the consensus leader,
+/// and forwarded to it over the transport from any other node
+#[derive(Clone)]
+pub(crate) struct LeaseRenewer {
+    pub node_id: NodeId,
+    pub leases: Arc<LeaseManager>,
+    pub consensus: Arc<ConsensusEngine>,
+    pub leader_node: Arc<tokio::sync::RwLock<Option<NodeId>>>,
+    pub client: Arc<parking_lot::RwLock<Option<Arc<QuicClient>>>>,
+}
+
+impl LeaseRenewer {
+    /// Renew a lease for another TTL, returning the TTL
+    pub async fn renew(&self, id: LeaseId) -> Result<Duration> {
+        let leader = *self.leader_node.read().await;
+        let leads = self.consensus.state().await == ConsensusState::Leader;
+        let Some(leader) = leader.filter(|leader| !leads && *leader != self.node_id) else {
+            return self.leases.keep_alive(id, Instant::now()).ok_or(StateError::LeaseNotFound { id });
+        };
+
+        let client = self.client.read().clone().ok_or_else(|| StateError::Transport {
+            message: format!("no transport to renew lease {} on leader {}", id, leader),
+        })?;
+        let info = self.leases.info(id, Instant::now()).ok_or(StateError::LeaseNotFound { id })?;
+        let ttl = keep_alive_remote(&client, leader, id, info.ttl / 3).await?;
+        // Keep the local deadline current too, for when this node takes over
+        self.leases.keep_alive(id, Instant::now());
+        Ok(ttl)
+    }
+}
+
+/// Keep a lease on `leader` alive until it is lost or the task is aborted
+///
+/// Renews every third of the TTL, so one lost renewal does not let it lapse.
+pub fn spawn_keep_alive(client: Arc<QuicClient>, leader: NodeId, id: LeaseId, ttl: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut period = ttl / 3;
+        loop {
+            tokio::time::sleep(period).await;
+            match keep_alive_remote(&client, leader, id, period).await {
+                Ok(ttl) => period = ttl / 3,
+                Err(StateError::LeaseNotFound { .. }) => {
+                    tracing::warn!("Lease {} was lost; stopping keep-alive", id);
+                    return;
+                }
+                Err(e) => tracing::debug!("Keep-alive of lease {} failed: {}", id, e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lease_lifecycle() {
+        let leases = LeaseManager::new();
+        let now = Instant::now();
+        let ttl = Duration::from_secs(10);
+
+        assert!(leases.grant(1, ttl, now));
+        assert!(!leases.grant(1, ttl, now));
+        assert!(leases.grant(2, ttl, now + Duration::from_secs(5)));
+        assert!(leases.attach(1, "/services/api/a"));
+        assert!(leases.attach(1, "/election/leader"));
+        assert!(!leases.attach(9, "/services/api/b"));
+
+        // Moving a key to another lease takes it off the first
+        assert!(leases.attach(2, "/election/leader"));
+        assert_eq!(leases.keys(1).unwrap(), vec!["/services/api/a".to_string()]);
+        assert_eq!(leases.owner("/election/leader"), Some(2));
+
+        // Renewal pushes the deadline out from the time of the renewal
+        assert_eq!(leases.keep_alive(1, now + Duration::from_secs(8)), Some(ttl));
+        assert_eq!(leases.expired(now + Duration::from_secs(15)), vec![2]);
+        assert_eq!(leases.expired(now + Duration::from_secs(20)), vec![2, 1]);
+
+        // A lapsed lease cannot be renewed; revoking it hands back its keys
+        assert_eq!(leases.keep_alive(2, now + Duration::from_secs(16)), None);
+        assert_eq!(leases.revoke(2), Some(vec!["/election/leader".to_string()]));
+        assert_eq!(leases.owner("/election/leader"), None);
+        assert_eq!(leases.revoke(2), None);
+
+        leases.detach("/services/api/a");
+        assert_eq!(leases.info(1, now).unwrap().keys, Vec::<String>::new());
+
+        let request = LeaseRequest::KeepAlive { id: 1 };
+        let message = request.to_message(NodeId::random(), NodeId::random()).unwrap();
+        assert_eq!(LeaseRequest::from_message(&message).unwrap(), request);
+    }
+}
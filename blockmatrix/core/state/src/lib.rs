@@ -3,9 +3,14 @@
 //! This module provides a replacement for etcd with the following features:
 //! - Raft consensus with Byzantine fault tolerance extensions
 //! - Encrypted state replication with forward secrecy
+//! - Rotation of the keys state is encrypted at rest with, without downtime
 //! - Automatic sharding and rebalancing
 //! - ACID transactions with serializable isolation
+//! - Conditional compare-and-swap transactions applied through consensus
 //! - Real-time subscriptions to state changes
+//! - Leases whose attached keys are deleted when they expire
+//! - Leader election with fencing tokens
+//! - Distribution of the cluster CA's trust bundle during CA rotation
 
 pub mod consensus;
 pub mod byzantine;
@@ -14,27 +19,65 @@ pub mod replication;
 pub mod sharding;
 pub mod transactions;
 pub mod subscriptions;
+pub mod outbox;
+pub mod lease;
+pub mod election;
+pub mod trust;
+pub mod range;
+pub mod snapshot;
+pub mod state_machine;
 pub mod encryption;
+pub mod diagnostics;
+pub mod workflow;
+pub mod ownership;
 pub mod config;
 pub mod error;
 
 pub use consensus::{ConsensusEngine, ConsensusState, Proposal, ByzantineStatus};
-pub use byzantine::{ByzantineCoordinator, ByzantineConfig, OverallByzantineStatus};
+pub use byzantine::{
+    ByzantineCoordinator, ByzantineConfig, ByzantineReport, FaultEvidence, FaultType, NodeFaultRecord,
+    OverallByzantineStatus, SignedPbftMessage,
+};
 pub use storage::{StateStore, StorageEngine, StorageConfig};
-pub use replication::{ReplicationManager, ReplicationState};
-pub use sharding::{ShardManager, ShardConfig, ShardKey};
-pub use transactions::{Transaction, TransactionManager, IsolationLevel};
+pub use replication::{ConsistencyLevel, ReplicaTarget, ReplicaWrite, ReplicationManager, ReplicationState, WriteOutcome};
+pub use sharding::{
+    ShardConfig, ShardId, ShardInfo, ShardKey, ShardManager, ShardMap, ShardMove, ShardSize, ShardTarget, ShardingStats,
+    SHARD_MAP_KEY,
+};
+pub use transactions::{
+    Compare, CompareOp, CompareTarget, IsolationLevel, KeyRevisions, Transaction, TransactionManager, TxnId, TxnOp,
+    TxnOpResponse, TxnRequest, TxnResponse,
+};
 pub use subscriptions::{SubscriptionManager, StateChange, WatchHandle};
-pub use encryption::{EncryptionManager, StateEncryption};
-pub use config::StateConfig;
+pub use outbox::{EventBus, Outbox, OutboxEvent, OutboxStats};
+pub use election::{ElectionConfig, LeaderInfo, LeaderObserver, Leadership};
+pub use trust::{TrustFollower, TRUST_BUNDLE_KEY};
+pub use lease::{LeaseConfig, LeaseId, LeaseInfo, LeaseManager, LeaseRequest, LeaseResponse};
+pub use range::{prefix_range_end, KeyValue, RangePage};
+pub use snapshot::StateSnapshot;
+pub use state_machine::StateMachine;
+pub use diagnostics::{LogReport, MemberLag, ProposalTrace, StateDiagnostics, StorageReport, WatchReport};
+pub use encryption::{EncryptionManager, ReencryptionStats, StateEncryption, KEY_VERSION_KEY};
+pub use workflow::{
+    StepRecord, StepStatus, WorkflowContext, WorkflowDefinition, WorkflowEngine, WorkflowRecord, WorkflowState,
+    WorkflowStep, WorkflowStore,
+};
+pub use ownership::{
+    plan_collection, CollectionPlan, DeletionPropagation, GarbageCollector, ObjectFinalizer, ObjectRecord, ObjectRef,
+    OwnerReference, OBJECT_PREFIX,
+};
+pub use config::{OutboxConfig, OutboxSubscription, StateConfig};
 pub use error::{StateError, Result};
 
-use nexus_shared::{NodeId, ResourceId};
+use lease::LeaseRenewer;
+use nexus_shared::{NodeId, ObjectClient, ObjectMeta, ResourceId, Validate};
+use nexus_transport::{CertificateManager, QuicClient, TransportMessage, TrustBundle};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::SystemTime;
-use tokio::sync::{RwLock, broadcast};
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
 /// Distributed state manager
 pub struct StateManager {
@@ -48,32 +91,62 @@ pub struct StateManager {
     sharding: Arc<ShardManager>,
     transactions: Arc<TransactionManager>,
     subscriptions: Arc<SubscriptionManager>,
+    outbox: Arc<Outbox>,
     encryption: Arc<EncryptionManager>,
+    state_machine: Arc<StateMachine>,
+    leases: Arc<LeaseManager>,
+    lease_expiry: parking_lot::Mutex<Option<JoinHandle<()>>>,
+    rebalancer: parking_lot::Mutex<Option<JoinHandle<()>>>,
+    reencryption: parking_lot::Mutex<Option<JoinHandle<()>>>,
     
     // State
     cluster_members: Arc<RwLock<HashMap<NodeId, ClusterMember>>>,
     leader_node: Arc<RwLock<Option<NodeId>>>,
-    
-    // Events
-    state_change_sender: broadcast::Sender<StateChange>,
+    /// Client lease renewals are forwarded to the consensus leader with
+    transport_client: Arc<parking_lot::RwLock<Option<Arc<QuicClient>>>>,
 }
 
 impl StateManager {
     /// Create a new state manager
     pub async fn new(config: StateConfig, node_id: NodeId) -> Result<Self> {
+        let report = config.validate_config();
+        for warning in report.warnings() {
+            tracing::warn!("State config {}", warning);
+        }
+        if !report.is_valid() {
+            return Err(StateError::Configuration { message: report.error_summary() });
+        }
+
         // Convert generic config to consensus-specific config
-        let consensus_cfg = consensus::ConsensusConfig::default();
+        let consensus_cfg = consensus::ConsensusConfig {
+            slow_proposal_threshold: config.consensus.slow_proposal_threshold,
+            ..Default::default()
+        };
         let consensus = Arc::new(ConsensusEngine::new(&consensus_cfg, node_id).await?);
         // Convert generic config to storage-specific config
         let storage_cfg = storage::StorageConfig::default();
         let storage = Arc::new(StateStore::new(&storage_cfg).await?);
         let replication = Arc::new(ReplicationManager::new(&config.replication, node_id)?);
-        let sharding = Arc::new(ShardManager::new(&config.sharding)?);
+        let sharding = Arc::new(ShardManager::new(&config.sharding, node_id)?);
         let transactions = Arc::new(TransactionManager::new(&config.transactions)?);
-        let subscriptions = Arc::new(SubscriptionManager::new());
-        let encryption = Arc::new(EncryptionManager::from_config(&config.encryption));
+        let subscriptions = Arc::new(SubscriptionManager::with_history(config.subscriptions.history_size));
+        let encryption = Arc::new(EncryptionManager::from_config(&config.encryption)?);
+        let outbox = Arc::new(Outbox::new(&config.outbox, storage.clone()));
+        let leases = Arc::new(LeaseManager::new());
+        
+        let cluster_members = Arc::new(RwLock::new(HashMap::new()));
+        let leader_node = Arc::new(RwLock::new(None));
         
-        let (state_change_sender, _) = broadcast::channel(10000);
+        // Committed proposals reach storage, watchers and the outbox through the state machine
+        let state_machine = Arc::new(StateMachine::new(
+            storage.clone(),
+            encryption.clone(),
+            subscriptions.clone(),
+            outbox.clone(),
+            leases.clone(),
+            sharding.clone(),
+        ).with_membership(cluster_members.clone(), leader_node.clone()));
+        consensus.set_state_machine(state_machine.clone()).await;
         
         Ok(Self {
             config,
@@ -84,13 +157,32 @@ impl StateManager {
             sharding,
             transactions,
             subscriptions,
+            outbox,
             encryption,
-            cluster_members: Arc::new(RwLock::new(HashMap::new())),
-            leader_node: Arc::new(RwLock::new(None)),
-            state_change_sender,
+            state_machine,
+            leases,
+            lease_expiry: parking_lot::Mutex::new(None),
+            rebalancer: parking_lot::Mutex::new(None),
+            reencryption: parking_lot::Mutex::new(None),
+            cluster_members,
+            leader_node,
+            transport_client: Arc::new(parking_lot::RwLock::new(None)),
         })
     }
     
+    /// Use `client` to reach the consensus leader, for lease renewals
+    pub fn set_transport_client(&self, client: Arc<QuicClient>) {
+        *self.transport_client.write() = Some(client);
+    }
+    
+    /// Record the consensus leader, as learned from the cluster
+    ///
+    /// Lease renewals on other nodes are forwarded to it, since only the
+    /// leader's deadlines decide when a lease lapses.
+    pub async fn set_leader_node(&self, leader: Option<NodeId>) {
+        *self.leader_node.write().await = leader;
+    }
+    
     /// Start the state manager
     pub async fn start(&self) -> Result<()> {
         tracing::info!("Starting state manager for node {}", self.node_id);
@@ -98,6 +190,28 @@ impl StateManager {
         // Start storage engine
         self.storage.start().await?;
         
+        // Pick up the shard map committed before a restart
+        let map_key = self.encryption.encrypt_key(SHARD_MAP_KEY).await?;
+        if let Some(data) = self.storage.get(&map_key).await? {
+            let map = serde_json::from_slice(&self.encryption.decrypt_data(&data).await?)
+                .map_err(|e| StateError::Sharding { message: format!("stored shard map is malformed: {}", e) })?;
+            self.sharding.install(map);
+        }
+        // And the rotated data keys, before the version sealing under them
+        for stored_key in self.storage.list_keys(encryption::DATA_KEY_PREFIX, None).await? {
+            let key = self.encryption.decrypt_key(&stored_key).await?;
+            let (Some(version), Some(wrapped)) = (encryption::data_key_version(&key), self.storage.get(&stored_key).await?) else {
+                continue;
+            };
+            self.encryption.install_key(version, &wrapped)?;
+        }
+        let version_key = self.encryption.encrypt_key(KEY_VERSION_KEY).await?;
+        if let Some(data) = self.storage.get(&version_key).await? {
+            let version = serde_json::from_slice(&self.encryption.decrypt_data(&data).await?)
+                .map_err(|e| StateError::Encryption { message: format!("stored data key version is malformed: {}", e) })?;
+            self.encryption.activate(version);
+        }
+        
         // Start consensus engine
         self.consensus.start().await?;
         
@@ -107,6 +221,36 @@ impl StateManager {
         // Start subscription manager
         self.subscriptions.start().await?;
         
+        // Revoke leases that lapse, while this node leads
+        *self.lease_expiry.lock() = Some(tokio::spawn(run_lease_expiry(
+            self.consensus.clone(),
+            self.replication.clone(),
+            self.encryption.clone(),
+            self.leases.clone(),
+            self.config.leases.check_interval,
+        )));
+        
+        // Split, merge and move shards, while this node leads
+        if self.config.sharding.enabled {
+            *self.rebalancer.lock() = Some(tokio::spawn(run_rebalancer(
+                self.consensus.clone(),
+                self.encryption.clone(),
+                self.sharding.clone(),
+                self.state_machine.clone(),
+            )));
+        }
+        
+        // Re-encrypt values under older data keys, while this node leads
+        if self.encryption.is_enabled() {
+            *self.reencryption.lock() = Some(tokio::spawn(run_reencryption(
+                self.consensus.clone(),
+                self.encryption.clone(),
+                self.storage.clone(),
+                self.config.encryption.reencrypt_interval,
+                self.config.encryption.reencrypt_batch,
+            )));
+        }
+        
         tracing::info!("State manager started successfully");
         Ok(())
     }
@@ -115,6 +259,15 @@ impl StateManager {
     pub async fn stop(&self) -> Result<()> {
         tracing::info!("Stopping state manager");
         
+        if let Some(task) = self.lease_expiry.lock().take() {
+            task.abort();
+        }
+        if let Some(task) = self.rebalancer.lock().take() {
+            task.abort();
+        }
+        if let Some(task) = self.reencryption.lock().take() {
+            task.abort();
+        }
         self.subscriptions.stop().await?;
         self.replication.stop().await?;
         self.consensus.stop().await?;
@@ -161,35 +314,472 @@ impl StateManager {
     }
     
     /// Set a value in the state store
+    ///
+    /// The write is replicated at the consistency level configured for the key.
     pub async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.set_with_consistency(key, value, self.replication.consistency_for(key)).await?;
+        Ok(())
+    }
+    
+    /// Set a value, returning once `level` replicas hold it
+    pub async fn set_with_consistency(&self, key: &str, value: &[u8], level: ConsistencyLevel) -> Result<WriteOutcome> {
+        if outbox::is_outbox_key(key) {
+            return Err(StateError::InvalidKey { key: key.to_string() });
+        }
         let encrypted_key = self.encryption.encrypt_key(key).await?;
         let encrypted_value = self.encryption.encrypt_data(value).await?;
         
         // Create proposal for consensus
         let proposal = Proposal::Set {
-            key: encrypted_key,
-            value: encrypted_value,
+            key: encrypted_key.clone(),
+            value: encrypted_value.clone(),
         };
         
         // Submit to consensus
         self.consensus.propose(proposal).await?;
         
-        Ok(())
+        self.replication
+            .replicate(ReplicaWrite { key: encrypted_key, value: Some(encrypted_value) }, level)
+            .await
     }
     
     /// Delete a value from the state store
     pub async fn delete(&self, key: &str) -> Result<bool> {
+        self.delete_with_consistency(key, self.replication.consistency_for(key)).await?;
+        Ok(true) // TODO: Return actual result from consensus
+    }
+    
+    /// Delete a value, returning once `level` replicas have dropped it
+    pub async fn delete_with_consistency(&self, key: &str, level: ConsistencyLevel) -> Result<WriteOutcome> {
+        if outbox::is_outbox_key(key) {
+            return Err(StateError::InvalidKey { key: key.to_string() });
+        }
         let encrypted_key = self.encryption.encrypt_key(key).await?;
         
         // Create proposal for consensus
         let proposal = Proposal::Delete {
-            key: encrypted_key,
+            key: encrypted_key.clone(),
         };
         
         // Submit to consensus
         self.consensus.propose(proposal).await?;
         
-        Ok(true) // TODO: Return actual result from consensus
+        self.replication
+            .replicate(ReplicaWrite { key: encrypted_key, value: None }, level)
+            .await
+    }
+    
+    /// Run a conditional transaction
+    ///
+    /// The comparisons are checked and the chosen operations applied as one
+    /// committed proposal, so no other write comes between them. Writes are
+    /// then replicated at the consistency level configured for each key.
+    pub async fn txn(&self, request: TxnRequest) -> Result<TxnResponse> {
+        let keys = request
+            .compare
+            .iter()
+            .map(|compare| compare.key.as_str())
+            .chain(request.success.iter().chain(&request.failure).map(TxnOp::key));
+        for key in keys {
+            if outbox::is_outbox_key(key) {
+                return Err(StateError::InvalidKey { key: key.to_string() });
+            }
+        }
+        
+        let mut stored = TxnRequest::new();
+        for compare in &request.compare {
+            let target = match &compare.target {
+                CompareTarget::Value(value) => CompareTarget::Value(self.encryption.encrypt_data(value).await?),
+                target => target.clone(),
+            };
+            stored.compare.push(Compare { key: self.encryption.encrypt_key(&compare.key).await?, op: compare.op, target });
+        }
+        for (ops, stored_ops) in [(&request.success, &mut stored.success), (&request.failure, &mut stored.failure)] {
+            for op in ops {
+                let key = self.encryption.encrypt_key(op.key()).await?;
+                stored_ops.push(match op {
+                    TxnOp::Put { value, .. } => TxnOp::Put { key, value: self.encryption.encrypt_data(value).await? },
+                    TxnOp::Delete { .. } => TxnOp::Delete { key },
+                    TxnOp::Get { .. } => TxnOp::Get { key },
+                });
+            }
+        }
+        
+        let id: TxnId = rand::random();
+        let outcome = self.state_machine.expect_txn(id);
+        if let Err(e) = self.consensus.propose(Proposal::Txn { id, request: stored.clone() }).await {
+            self.state_machine.forget_txn(id);
+            return Err(e);
+        }
+        let mut response = outcome.await.map_err(|_| StateError::Consensus {
+            message: format!("outcome of transaction {} was lost", id),
+        })?;
+        
+        let ran = if response.succeeded { &stored.success } else { &stored.failure };
+        for (op, result) in ran.iter().zip(&mut response.responses) {
+            match (op, result) {
+                (TxnOp::Put { key, value }, _) => {
+                    self.replication
+                        .replicate(ReplicaWrite { key: key.clone(), value: Some(value.clone()) }, self.consistency_of(key).await?)
+                        .await?;
+                }
+                (TxnOp::Delete { key }, TxnOpResponse::Delete { deleted: true }) => {
+                    self.replication
+                        .replicate(ReplicaWrite { key: key.clone(), value: None }, self.consistency_of(key).await?)
+                        .await?;
+                }
+                (TxnOp::Get { .. }, TxnOpResponse::Get { value: Some(value), .. }) => {
+                    *value = self.encryption.decrypt_data(value).await?;
+                }
+                _ => {}
+            }
+        }
+        Ok(response)
+    }
+    
+    /// Replace a key's value only if it still holds `expected`
+    ///
+    /// `None` expects the key not to exist. Returns whether the swap happened.
+    pub async fn compare_and_swap(&self, key: &str, expected: Option<&[u8]>, new: &[u8]) -> Result<bool> {
+        let compare = match expected {
+            Some(expected) => Compare::value(key, CompareOp::Equal, expected),
+            None => Compare::version(key, CompareOp::Equal, 0),
+        };
+        let request = TxnRequest::new().when([compare]).and_then([TxnOp::put(key, new)]);
+        Ok(self.txn(request).await?.succeeded)
+    }
+    
+    /// Write a key only if it was last modified at `expected_revision`
+    ///
+    /// `0` expects the key not to exist. Returns whether the write happened;
+    /// a false result means another writer got there first.
+    pub async fn put_if_revision(&self, key: &str, expected_revision: u64, value: &[u8]) -> Result<bool> {
+        let compare = match expected_revision {
+            0 => Compare::version(key, CompareOp::Equal, 0),
+            revision => Compare::mod_revision(key, CompareOp::Equal, revision),
+        };
+        let request = TxnRequest::new().when([compare]).and_then([TxnOp::put(key, value)]);
+        Ok(self.txn(request).await?.succeeded)
+    }
+    
+    /// Version and create/modify revisions of a key, for transaction comparisons
+    pub async fn key_revisions(&self, key: &str) -> Result<KeyRevisions> {
+        let encrypted_key = self.encryption.encrypt_key(key).await?;
+        self.state_machine.key_revisions(&encrypted_key).await
+    }
+    
+    async fn consistency_of(&self, stored_key: &str) -> Result<ConsistencyLevel> {
+        Ok(self.replication.consistency_for(&self.encryption.decrypt_key(stored_key).await?))
+    }
+    
+    /// Grant a lease that lapses unless kept alive within `ttl`
+    ///
+    /// TTLs below the configured minimum are raised to it.
+    pub async fn grant_lease(&self, ttl: Duration) -> Result<LeaseId> {
+        let ttl = ttl.max(self.config.leases.min_ttl);
+        let id = loop {
+            let id: LeaseId = rand::random();
+            if id != 0 && !self.leases.exists(id) {
+                break id;
+            }
+        };
+        self.consensus.propose(Proposal::GrantLease { id, ttl }).await?;
+        tracing::debug!("Granted lease {} with TTL {:?}", id, ttl);
+        Ok(id)
+    }
+    
+    /// Set a value that is deleted when `lease` expires or is revoked
+    ///
+    /// Writing the key again without a lease, or deleting it, detaches it.
+    pub async fn set_with_lease(&self, key: &str, value: &[u8], lease: LeaseId) -> Result<()> {
+        if outbox::is_outbox_key(key) {
+            return Err(StateError::InvalidKey { key: key.to_string() });
+        }
+        if !self.leases.exists(lease) {
+            return Err(StateError::LeaseNotFound { id: lease });
+        }
+        let encrypted_key = self.encryption.encrypt_key(key).await?;
+        let encrypted_value = self.encryption.encrypt_data(value).await?;
+        
+        self.consensus
+            .propose(Proposal::SetWithLease { key: encrypted_key.clone(), value: encrypted_value.clone(), lease })
+            .await?;
+        
+        self.replication
+            .replicate(
+                ReplicaWrite { key: encrypted_key, value: Some(encrypted_value) },
+                self.replication.consistency_for(key),
+            )
+            .await?;
+        Ok(())
+    }
+    
+    /// Renew a lease for another TTL, returning the TTL
+    ///
+    /// On a follower the renewal is forwarded to the consensus leader, which
+    /// is the node that revokes lapsed leases.
+    pub async fn keep_alive(&self, lease: LeaseId) -> Result<Duration> {
+        self.lease_renewer().renew(lease).await
+    }
+    
+    fn lease_renewer(&self) -> LeaseRenewer {
+        LeaseRenewer {
+            node_id: self.node_id,
+            leases: self.leases.clone(),
+            consensus: self.consensus.clone(),
+            leader_node: self.leader_node.clone(),
+            client: self.transport_client.clone(),
+        }
+    }
+    
+    /// Revoke a lease now, deleting the keys attached to it
+    pub async fn revoke_lease(&self, lease: LeaseId) -> Result<()> {
+        revoke_lease(&self.consensus, &self.replication, &self.encryption, &self.leases, lease).await
+    }
+    
+    /// A lease with its remaining time and attached keys
+    pub async fn lease_info(&self, lease: LeaseId) -> Result<LeaseInfo> {
+        let mut info = self
+            .leases
+            .info(lease, tokio::time::Instant::now())
+            .ok_or(StateError::LeaseNotFound { id: lease })?;
+        for key in &mut info.keys {
+            *key = self.encryption.decrypt_key(key).await?;
+        }
+        Ok(info)
+    }
+    
+    /// Leases granted and not yet revoked
+    pub fn leases(&self) -> Vec<LeaseId> {
+        self.leases.leases()
+    }
+    
+    /// Serve a `LeaseRequest` received over the transport, returning the reply
+    pub async fn handle_lease_request(&self, message: &TransportMessage) -> Result<TransportMessage> {
+        let response = match LeaseRequest::from_message(message)? {
+            LeaseRequest::Grant { ttl } => match self.grant_lease(ttl).await {
+                Ok(id) => LeaseResponse::Granted { id, ttl: ttl.max(self.config.leases.min_ttl) },
+                Err(e) => LeaseResponse::Failed { message: e.to_string() },
+            },
+            // Renewed here rather than forwarded again, so two nodes that
+            // disagree about the leader cannot pass a renewal back and forth
+            LeaseRequest::KeepAlive { id } => match self.leases.keep_alive(id, tokio::time::Instant::now()) {
+                Some(ttl) => LeaseResponse::KeptAlive { id, ttl },
+                None => LeaseResponse::NotFound { id },
+            },
+            LeaseRequest::Revoke { id } => match self.revoke_lease(id).await {
+                Ok(()) => LeaseResponse::Revoked { id },
+                Err(StateError::LeaseNotFound { id }) => LeaseResponse::NotFound { id },
+                Err(e) => LeaseResponse::Failed { message: e.to_string() },
+            },
+        };
+        response.to_message(self.node_id, message.source)
+    }
+    
+    /// Campaign in an election, returning once this node leads it
+    ///
+    /// `value` is published to observers while this node leads, typically
+    /// its address. Candidates lead in the order they campaigned. The
+    /// candidacy lives under a lease this node keeps alive for as long as the
+    /// returned `Leadership` is held; cancelling the campaign drops it too.
+    pub async fn campaign(&self, election: &str, value: &[u8]) -> Result<Leadership> {
+        election::validate_election_name(election)?;
+        let prefix = election::election_prefix(election);
+        
+        let ttl = self.config.elections.lease_ttl.max(self.config.leases.min_ttl);
+        let lease = self.grant_lease(ttl).await?;
+        let keep_alive = election::LeaseKeepAlive::spawn(self.lease_renewer(), lease, ttl);
+        let key = election::candidate_key(election, lease);
+        
+        // Watch before campaigning so no change to the line is missed
+        let mut watch = self.watch(&prefix).await?;
+        self.consensus
+            .propose(Proposal::Campaign {
+                key: self.encryption.encrypt_key(&key).await?,
+                value: self.encryption.encrypt_data(value).await?,
+                lease,
+            })
+            .await?;
+        tracing::info!("Campaigning in election {} under lease {}", election, lease);
+        
+        loop {
+            let (_, entries) = self.read_election(&prefix).await?;
+            if election::candidacy(&entries, &key).is_none() {
+                return Err(StateError::Leadership {
+                    message: format!("candidacy in election {} was lost with lease {}", election, lease),
+                });
+            }
+            if let Some(leader) = election::leader_of(election, &entries).filter(|leader| leader.key == key) {
+                tracing::info!("Won election {} with fencing token {}", election, leader.fencing_token);
+                return Ok(Leadership { leader, _keep_alive: keep_alive });
+            }
+            if watch.recv().await?.is_none() {
+                return Err(StateError::Leadership { message: "state manager stopped".to_string() });
+            }
+        }
+    }
+    
+    /// Give up leadership, handing it to the next candidate at once
+    pub async fn resign(&self, leadership: Leadership) -> Result<()> {
+        tracing::info!("Resigning from election {}", leadership.election());
+        match self.revoke_lease(leadership.lease()).await {
+            // Already lapsed, so already handed over
+            Err(StateError::LeaseNotFound { .. }) => Ok(()),
+            result => result,
+        }
+    }
+    
+    /// Current leader of an election, if any
+    pub async fn leader(&self, election: &str) -> Result<Option<LeaderInfo>> {
+        election::validate_election_name(election)?;
+        let (_, entries) = self.read_election(&election::election_prefix(election)).await?;
+        Ok(election::leader_of(election, &entries))
+    }
+    
+    /// Follow the leader of an election as it changes
+    pub async fn observe_leader(&self, election: &str) -> Result<LeaderObserver> {
+        election::validate_election_name(election)?;
+        let prefix = election::election_prefix(election);
+        let mut watch = self.watch(&prefix).await?;
+        let (revision, entries) = self.read_election(&prefix).await?;
+        let (sender, receiver) = tokio::sync::watch::channel(election::leader_of(election, &entries));
+        
+        let state_machine = self.state_machine.clone();
+        let election = election.to_string();
+        let task = tokio::spawn(async move {
+            let end = range::prefix_range_end(&prefix);
+            let mut seen = revision;
+            loop {
+                match watch.recv().await {
+                    Ok(Some(change)) if change.revision <= seen => continue,
+                    Ok(Some(_)) => {}
+                    Ok(None) => return,
+                    Err(e) => {
+                        tracing::warn!("Observer of election {} stopped: {}", election, e);
+                        return;
+                    }
+                }
+                match state_machine.read_range(&prefix, end.as_deref(), None).await {
+                    Ok((revision, entries)) => {
+                        seen = revision;
+                        sender.send_if_modified(|leader| {
+                            let current = election::leader_of(&election, &entries);
+                            let changed = *leader != current;
+                            *leader = current;
+                            changed
+                        });
+                    }
+                    Err(e) => tracing::warn!("Failed to read election {}: {}", election, e),
+                }
+            }
+        });
+        Ok(LeaderObserver { receiver, task })
+    }
+    
+    /// Check a fencing token against the current leader of an election
+    ///
+    /// Fails with `StaleFencingToken` unless the token is the current leader's.
+    pub async fn validate_fencing_token(&self, election: &str, token: u64) -> Result<()> {
+        match self.leader(election).await? {
+            Some(leader) if leader.fencing_token == token => Ok(()),
+            _ => Err(StateError::StaleFencingToken { election: election.to_string(), token }),
+        }
+    }
+    
+    async fn read_election(&self, prefix: &str) -> Result<(u64, Vec<KeyValue>)> {
+        let end = range::prefix_range_end(prefix);
+        self.state_machine.read_range(prefix, end.as_deref(), None).await
+    }
+    
+    /// Replicas this node ships writes to
+    pub fn replication(&self) -> Arc<ReplicationManager> {
+        self.replication.clone()
+    }
+    
+    /// Start sealing values under a new data key version
+    ///
+    /// The version is committed through consensus, so every node switches to
+    /// it. Values under older versions stay readable and are re-encrypted in
+    /// the background; `StateManagerStats::encryption_stats` shows how far
+    /// that has got. Returns the new version.
+    pub async fn rotate_key(&self) -> Result<u32> {
+        if !self.encryption.is_enabled() {
+            return Err(StateError::Encryption { message: "no master key is configured".to_string() });
+        }
+        let version = self.encryption.active_version() + 1;
+        // The key is committed first, so every node has it by the time it seals under it
+        self.consensus
+            .propose(Proposal::Set {
+                key: self.encryption.encrypt_key(&encryption::data_key_key(version)).await?,
+                value: self.encryption.new_data_key(version)?,
+            })
+            .await?;
+        self.consensus
+            .propose(Proposal::Set {
+                key: self.encryption.encrypt_key(KEY_VERSION_KEY).await?,
+                value: self.encryption.encrypt_data(&serde_json::to_vec(&version)?).await?,
+            })
+            .await?;
+        tracing::info!("Rotated data key to version {}", version);
+        Ok(version)
+    }
+    
+    /// Publish a trust bundle for every node and workload
+    ///
+    /// Fails if the published bundle is as new or newer.
+    pub async fn publish_trust_bundle(&self, bundle: &TrustBundle) -> Result<()> {
+        let value = serde_json::to_vec(bundle)?;
+        loop {
+            let stored = self.get(TRUST_BUNDLE_KEY).await?;
+            let current = stored.as_deref().map(trust::decode).transpose()?;
+            trust::check_newer(current.as_ref(), bundle)?;
+            if self.compare_and_swap(TRUST_BUNDLE_KEY, stored.as_deref(), &value).await? {
+                tracing::info!("Published trust bundle version {} ({:?})", bundle.version, bundle.phase);
+                return Ok(());
+            }
+        }
+    }
+    
+    /// Currently published trust bundle
+    pub async fn trust_bundle(&self) -> Result<Option<TrustBundle>> {
+        self.get(TRUST_BUNDLE_KEY).await?.as_deref().map(trust::decode).transpose()
+    }
+    
+    /// Apply the published trust bundle to a certificate manager and every later one
+    ///
+    /// The bundle is applied before this returns; later bundles are applied as
+    /// they are published until the follower is dropped.
+    pub async fn follow_trust_bundle(&self, certificates: Arc<CertificateManager>) -> Result<TrustFollower> {
+        let mut watch = self.watch(TRUST_BUNDLE_KEY).await?;
+        if let Some(bundle) = self.trust_bundle().await? {
+            trust::apply(&certificates, &bundle)?;
+        }
+        
+        let follower = certificates.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let change = match watch.recv().await {
+                    Ok(Some(change)) => change,
+                    Ok(None) => return,
+                    Err(e) => {
+                        tracing::warn!("Trust bundle follower stopped: {}", e);
+                        return;
+                    }
+                };
+                let Some(value) = change.new_value else {
+                    continue;
+                };
+                if let Err(e) = trust::decode(&value).and_then(|bundle| trust::apply(&follower, &bundle)) {
+                    tracing::warn!("Failed to apply trust bundle: {}", e);
+                }
+            }
+        });
+        Ok(TrustFollower { certificates, task })
+    }
+    
+    /// Shard map and the nodes shards are moved between
+    pub fn sharding(&self) -> Arc<ShardManager> {
+        self.sharding.clone()
     }
     
     /// List keys with prefix
@@ -198,7 +788,7 @@ impl StateManager {
         let encrypted_keys = self.storage.list_keys(&encrypted_prefix, limit).await?;
         
         let mut keys = Vec::new();
-        for encrypted_key in encrypted_keys {
+        for encrypted_key in encrypted_keys.into_iter().filter(|key| !outbox::is_outbox_key(key)) {
             let decrypted_key = self.encryption.decrypt_key(&encrypted_key).await?;
             keys.push(decrypted_key);
         }
@@ -206,6 +796,77 @@ impl StateManager {
         Ok(keys)
     }
     
+    /// Read keys in `[start, end)` with their values at one revision
+    ///
+    /// An empty `end` reads to the last key; `prefix_range_end` gives the end
+    /// of a prefix. `revision` defaults to the latest. At most `limit` entries
+    /// are returned per page; a page that stops early carries a continue token,
+    /// and passing it back reads the next page at the same revision. Watching
+    /// from the page's `revision + 1` picks up every later change.
+    pub async fn get_range(
+        &self,
+        start: &str,
+        end: &str,
+        revision: Option<u64>,
+        limit: Option<usize>,
+        continue_token: Option<&str>,
+    ) -> Result<RangePage> {
+        let end = (!end.is_empty()).then_some(end);
+        let (start, revision) = match continue_token {
+            Some(raw) => {
+                let token = range::ContinueToken::decode(raw)?;
+                if revision.is_some_and(|revision| revision != token.revision) {
+                    return Err(StateError::InvalidContinueToken { token: raw.to_string() });
+                }
+                (token.next_key().max(start.to_string()), Some(token.revision))
+            }
+            None => (start.to_string(), revision),
+        };
+        
+        let (revision, mut entries) = self.state_machine.read_range(&start, end, revision).await?;
+        
+        let limit = limit.filter(|limit| *limit > 0).unwrap_or(usize::MAX);
+        let continue_token = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|last| range::ContinueToken { revision, last_key: last.key.clone() }.encode())
+        } else {
+            None
+        };
+        Ok(RangePage { entries, revision, continue_token })
+    }
+    
+    /// Capture every key and value at the latest revision
+    pub async fn snapshot(&self) -> Result<StateSnapshot> {
+        let page = self.get_range("", "", None, None, None).await?;
+        let entries = page.entries.into_iter().map(|kv| (kv.key, kv.value)).collect();
+        Ok(StateSnapshot { revision: page.revision, taken_at: chrono::Utc::now(), entries })
+    }
+    
+    /// Snapshot the state and upload it to object storage under `key`
+    pub async fn backup_to(&self, client: &ObjectClient, key: &str) -> Result<ObjectMeta> {
+        let snapshot = self.snapshot().await?;
+        let meta = snapshot.upload(client, key).await?;
+        tracing::info!("Backed up {} keys at revision {} to {}", snapshot.entries.len(), snapshot.revision, key);
+        Ok(meta)
+    }
+    
+    /// Replace the state with a verified snapshot from object storage
+    ///
+    /// Keys not in the snapshot are deleted.
+    pub async fn restore_from(&self, client: &ObjectClient, key: &str) -> Result<StateSnapshot> {
+        let snapshot = StateSnapshot::download(client, key).await?;
+        for existing in self.list("", None).await? {
+            if !snapshot.entries.contains_key(&existing) {
+                self.delete(&existing).await?;
+            }
+        }
+        for (key, value) in &snapshot.entries {
+            self.set(key, value).await?;
+        }
+        tracing::info!("Restored {} keys from {} (revision {})", snapshot.entries.len(), key, snapshot.revision);
+        Ok(snapshot)
+    }
+    
     /// Start a transaction
     pub async fn begin_transaction(&self) -> Result<TransactionHandle> {
         let transaction = self.transactions.begin().await?;
@@ -217,8 +878,56 @@ impl StateManager {
     
     /// Watch for changes to a key or prefix
     pub async fn watch(&self, key_prefix: &str) -> Result<WatchHandle> {
-        let encrypted_prefix = self.encryption.encrypt_key(key_prefix).await?;
-        self.subscriptions.watch(&encrypted_prefix).await
+        self.subscriptions.watch(key_prefix).await
+    }
+    
+    /// Watch for changes to a key or prefix, replaying from `revision`
+    ///
+    /// A client that last saw revision `r` resumes with `r + 1` and misses
+    /// nothing, as long as `r + 1` is still in the change history.
+    pub async fn watch_from(&self, key_prefix: &str, revision: u64) -> Result<WatchHandle> {
+        self.subscriptions.watch_from(key_prefix, revision).await
+    }
+    
+    /// Latest committed revision
+    pub fn revision(&self) -> u64 {
+        self.subscriptions.revision()
+    }
+    
+    /// Outbox of committed changes published to the mesh event bus
+    pub fn outbox(&self) -> Arc<Outbox> {
+        self.outbox.clone()
+    }
+    
+    /// Publish outbox events to `bus` until the returned task is aborted
+    pub fn start_outbox_relay(&self, bus: Arc<dyn EventBus>) -> tokio::task::JoinHandle<()> {
+        self.outbox.spawn_relay(bus)
+    }
+    
+    /// Members suspected of Byzantine behaviour, with the evidence against each
+    pub async fn byzantine_report(&self) -> ByzantineReport {
+        self.consensus.byzantine_report().await
+    }
+    
+    /// Evict a Byzantine member
+    ///
+    /// The eviction is proposed through consensus with the evidence attached;
+    /// every node verifies the evidence and removes the member when the
+    /// proposal commits, so honest nodes converge on the same membership.
+    pub async fn evict_member(&self, node_id: NodeId, evidence: Vec<FaultEvidence>) -> Result<()> {
+        if node_id == self.node_id {
+            return Err(StateError::Membership {
+                message: "a node cannot evict itself".to_string(),
+            });
+        }
+        if !self.consensus.members().await.contains(&node_id) {
+            return Err(StateError::NodeNotInCluster { node_id: node_id.to_string() });
+        }
+        consensus::verify_eviction(node_id, &evidence)?;
+        
+        tracing::warn!("Proposing eviction of member {}", node_id);
+        self.consensus.propose(Proposal::EvictMember { node_id, evidence }).await?;
+        Ok(())
     }
     
     /// Get cluster status
@@ -235,6 +944,28 @@ impl StateManager {
         }
     }
     
+    /// Cluster encryption manager, which also wraps other components' data keys
+    pub fn encryption(&self) -> Arc<EncryptionManager> {
+        self.encryption.clone()
+    }
+    
+    /// Consensus lag, log and store sizes, watches and slow proposals on this node
+    pub async fn diagnostics(&self) -> Result<StateDiagnostics> {
+        let (slow_proposal_threshold, slow_proposals) = self.consensus.slow_proposals().await;
+        Ok(StateDiagnostics {
+            node_id: self.node_id,
+            generated_at: SystemTime::now(),
+            consensus_state: self.consensus.state().await,
+            term: self.consensus.stats().await.current_term,
+            members: self.consensus.member_lag().await,
+            log: self.consensus.log_report().await,
+            storage: self.storage.storage_report().await?,
+            watches: self.subscriptions.watch_report(),
+            slow_proposal_threshold,
+            slow_proposals,
+        })
+    }
+    
     /// Get node statistics
     pub async fn stats(&self) -> StateManagerStats {
         StateManagerStats {
@@ -242,6 +973,223 @@ impl StateManager {
             storage_stats: self.storage.stats().await,
             consensus_stats: self.consensus.stats().await,
             replication_stats: self.replication.stats().await,
+            sharding_stats: self.sharding.stats(),
+            encryption_stats: self.encryption.stats(),
+        }
+    }
+}
+
+/// Propose a lease's revocation and ship the deletes of its keys to replicas
+async fn revoke_lease(
+    consensus: &ConsensusEngine,
+    replication: &ReplicationManager,
+    encryption: &EncryptionManager,
+    leases: &LeaseManager,
+    lease: LeaseId,
+) -> Result<()> {
+    let keys = leases.keys(lease).ok_or(StateError::LeaseNotFound { id: lease })?;
+    consensus.propose(Proposal::RevokeLease { id: lease }).await?;
+    for key in keys {
+        let level = replication.consistency_for(&encryption.decrypt_key(&key).await?);
+        replication.replicate(ReplicaWrite { key, value: None }, level).await?;
+    }
+    Ok(())
+}
+
+/// Revoke lapsed leases every `check_interval` while this node leads
+async fn run_lease_expiry(
+    consensus: Arc<ConsensusEngine>,
+    replication: Arc<ReplicationManager>,
+    encryption: Arc<EncryptionManager>,
+    leases: Arc<LeaseManager>,
+    check_interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(check_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if consensus.state().await != ConsensusState::Leader {
+            continue;
+        }
+        for lease in leases.expired(tokio::time::Instant::now()) {
+            tracing::info!("Lease {} expired; revoking it", lease);
+            // A failed revocation is retried on the next tick
+            if let Err(e) = revoke_lease(&consensus, &replication, &encryption, &leases, lease).await {
+                tracing::warn!("Failed to revoke expired lease {}: {}", lease, e);
+            }
+        }
+    }
+}
+
+/// Carry out one planned move and commit the resulting shard map
+///
+/// A replica being added is first filled by copying the shard in batches,
+/// throttled to the configured migration rate, while writes applied in the
+/// meantime are forwarded to it. The committed map is the cutover: from
+/// then on the new owners serve the shard.
+async fn execute_move(
+    consensus: &ConsensusEngine,
+    encryption: &EncryptionManager,
+    sharding: &ShardManager,
+    state_machine: &StateMachine,
+    shard_move: &ShardMove,
+) -> Result<()> {
+    let mut map = sharding.map();
+    let split_at = match shard_move {
+        ShardMove::Split { shard } => {
+            let info = map.get(*shard).ok_or(StateError::Sharding { message: format!("unknown shard {}", shard) })?;
+            match state_machine.split_key(info).await? {
+                Some(key) => Some(key),
+                // Too few keys left to split; the next plan will see that
+                None => return Ok(()),
+            }
+        }
+        _ => None,
+    };
+
+    let migrating = match shard_move {
+        ShardMove::AddReplica { shard, to } | ShardMove::Transfer { shard, to, .. } => sharding.begin_migration(*shard, *to)?,
+        _ => false,
+    };
+    let result = async {
+        if migrating {
+            copy_shard(sharding, state_machine, &map, shard_move).await?;
+            if let Some(reason) = sharding.migration_failure() {
+                return Err(StateError::Sharding { message: format!("migration failed: {}", reason) });
+            }
+        }
+        sharding.apply_move(&mut map, shard_move, split_at.as_deref())?;
+        let value = serde_json::to_vec(&map)
+            .map_err(|e| StateError::Sharding { message: format!("failed to encode shard map: {}", e) })?;
+        consensus
+            .propose(Proposal::Set {
+                key: encryption.encrypt_key(SHARD_MAP_KEY).await?,
+                value: encryption.encrypt_data(&value).await?,
+            })
+            .await
+    }
+    .await;
+    if migrating {
+        sharding.end_migration();
+    }
+    result
+}
+
+/// Copy the shard a move migrates to its new owner, at most `migration_rate` bytes a second
+async fn copy_shard(sharding: &ShardManager, state_machine: &StateMachine, map: &ShardMap, shard_move: &ShardMove) -> Result<()> {
+    let shard = match shard_move {
+        ShardMove::AddReplica { shard, .. } | ShardMove::Transfer { shard, .. } => *shard,
+        _ => return Ok(()),
+    };
+    let info = map.get(shard).ok_or(StateError::Sharding { message: format!("unknown shard {}", shard) })?;
+    let config = sharding.config();
+    let started = tokio::time::Instant::now();
+    let mut sent = 0u64;
+    let mut cursor = info.start.clone();
+    loop {
+        let (bytes, next) = state_machine.copy_batch(&cursor, info.end.as_deref(), config.migration_batch).await?;
+        sent += bytes as u64;
+        let due = Duration::from_secs_f64(sent as f64 / config.migration_rate.max(1) as f64);
+        tokio::time::sleep_until(started + due).await;
+        match next {
+            Some(next) => cursor = next,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Plan and carry out shard moves every `rebalance_interval` while this node leads
+async fn run_rebalancer(
+    consensus: Arc<ConsensusEngine>,
+    encryption: Arc<EncryptionManager>,
+    sharding: Arc<ShardManager>,
+    state_machine: Arc<StateMachine>,
+) {
+    let mut ticker = tokio::time::interval(sharding.config().rebalance_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if consensus.state().await != ConsensusState::Leader {
+            continue;
+        }
+        let sizes = match state_machine.shard_sizes(&sharding.map()).await {
+            Ok(sizes) => sizes,
+            Err(e) => {
+                tracing::warn!("Failed to measure shards: {}", e);
+                continue;
+            }
+        };
+        for shard_move in sharding.plan(&sizes) {
+            tracing::info!("Rebalancing shards: {:?}", shard_move);
+            let result = execute_move(&consensus, &encryption, &sharding, &state_machine, &shard_move).await;
+            sharding.record_outcome(&result);
+            // Later moves were planned against the map this one failed to change
+            if let Err(e) = result {
+                tracing::warn!("Shard move {:?} failed: {}", shard_move, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Re-encrypt the values of one batch from `cursor` that are under older data keys
+///
+/// Returns where the next batch starts, `None` at the end of the store.
+async fn reencrypt_batch(
+    consensus: &ConsensusEngine,
+    encryption: &EncryptionManager,
+    storage: &StateStore,
+    cursor: &str,
+    batch: usize,
+) -> Result<Option<String>> {
+    let entries = storage.scan_range(cursor, None).await?;
+    let next = entries.get(batch).map(|(key, _)| key.clone());
+    let mut stale = 0;
+    for (key, value) in entries.iter().take(batch) {
+        // Data keys are wrapped under the master key rather than sealed
+        if key.starts_with(encryption::DATA_KEY_PREFIX) || !encryption.needs_reencryption(value) {
+            continue;
+        }
+        let resealed = encryption.encrypt_data(&encryption.decrypt_data(value).await?).await?;
+        consensus
+            .propose(Proposal::Reencrypt { key: key.clone(), from: value.clone(), to: resealed })
+            .await?;
+        stale += 1;
+    }
+    encryption.record_scan(entries.len().min(batch), stale);
+    Ok(next)
+}
+
+/// Re-encrypt a batch every `interval` while this node leads, until a pass finds nothing left
+async fn run_reencryption(
+    consensus: Arc<ConsensusEngine>,
+    encryption: Arc<EncryptionManager>,
+    storage: Arc<StateStore>,
+    interval: Duration,
+    batch: usize,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut cursor = String::new();
+    let mut pass_version = encryption.active_version();
+    loop {
+        ticker.tick().await;
+        // A rotation clears `up_to_date` and starts a new pass from the beginning
+        if encryption.active_version() != pass_version {
+            pass_version = encryption.active_version();
+            cursor.clear();
+        }
+        if encryption.stats().up_to_date || consensus.state().await != ConsensusState::Leader {
+            continue;
+        }
+        match reencrypt_batch(&consensus, &encryption, &storage, &cursor, batch).await {
+            Ok(Some(next)) => cursor = next,
+            Ok(None) => {
+                encryption.record_pass_complete();
+                cursor.clear();
+            }
+            // The batch is retried on the next tick
+            Err(e) => tracing::warn!("Failed to re-encrypt values from {:?}: {}", cursor, e),
         }
     }
 }
@@ -326,6 +1274,8 @@ pub struct StateManagerStats {
     pub storage_stats: storage::StorageStats,
     pub consensus_stats: consensus::ConsensusStats,
     pub replication_stats: replication::ReplicationStats,
+    pub sharding_stats: sharding::ShardingStats,
+    pub encryption_stats: encryption::ReencryptionStats,
 }
 
 #[cfg(test)]
@@ -0,0 +1,310 @@
+//! Transactional outbox bridging committed state changes to the mesh
+//!
+//! External systems react to cluster changes by consuming topics on a mesh
+//! event bus instead of polling watches. Each outbox subscription maps a key
+//! prefix to a topic. When the state machine applies a committed change, it
+//! records a matching event in the store in the same apply step, under the
+//! topic's next offset. A relay then publishes each topic's events in offset
+//! order. It only advances its cursor once the bus accepts an event and it
+//! retries failed publishes, so every event reaches the bus at least once.
+//! Consumers commit the offsets they have processed and, after a restart or a
+//! missed delivery, replay the rest from the outbox with `events_since`. Every
+//! node records the same events under the same offsets, so consumers drop
+//! duplicates by topic and offset.
+
+use crate::config::OutboxConfig;
+use crate::error::{Result, StateError};
+use crate::storage::StateStore;
+use crate::subscriptions::StateChange;
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+
+/// Store keys under this prefix belong to the outbox, not to clients
+const OUTBOX_PREFIX: &str = "\u{0}outbox/";
+
+/// Whether a store key holds outbox data rather than client state
+pub(crate) fn is_outbox_key(key: &str) -> bool {
+    key.starts_with(OUTBOX_PREFIX)
+}
+
+/// A committed change published to a topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEvent {
+    pub topic: String,
+    /// Position of the event in its topic, starting at 1
+    pub offset: u64,
+    pub change: StateChange,
+}
+
+/// The mesh event bus outbox events are published to
+pub trait EventBus: Send + Sync {
+    /// Publish an event, resolving once the bus has accepted it
+    fn publish(&self, event: OutboxEvent) -> BoxFuture<'static, Result<()>>;
+}
+
+/// Outbox statistics
+#[derive(Debug, Clone, Default)]
+pub struct OutboxStats {
+    pub recorded: u64,
+    pub published: u64,
+    pub failed_publishes: u64,
+}
+
+/// Records committed changes for topics and relays them to an event bus
+pub struct Outbox {
+    config: OutboxConfig,
+    storage: Arc<StateStore>,
+    /// Serializes offset assignment and cursor updates
+    write_lock: Mutex<()>,
+    pending: Notify,
+    stats: parking_lot::Mutex<OutboxStats>,
+}
+
+impl Outbox {
+    pub fn new(config: &OutboxConfig, storage: Arc<StateStore>) -> Self {
+        Self {
+            config: config.clone(),
+            storage,
+            write_lock: Mutex::new(()),
+            pending: Notify::new(),
+            stats: parking_lot::Mutex::new(OutboxStats::default()),
+        }
+    }
+
+    /// Topics changes to `key` are published to
+    pub fn topics_for(&self, key: &str) -> BTreeSet<&str> {
+        self.config
+            .subscriptions
+            .iter()
+            .filter(|subscription| key.starts_with(subscription.prefix.as_str()))
+            .map(|subscription| subscription.topic.as_str())
+            .collect()
+    }
+
+    /// Record a committed change for every topic whose prefix it matches
+    ///
+    /// Called by the state machine as it applies the change.
+    pub async fn record(&self, change: &StateChange) -> Result<Vec<OutboxEvent>> {
+        let topics = self.topics_for(&change.key);
+        if topics.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let _guard = self.write_lock.lock().await;
+        let mut events = Vec::with_capacity(topics.len());
+        for topic in topics {
+            let event = OutboxEvent {
+                topic: topic.to_string(),
+                offset: self.last_offset(topic).await? + 1,
+                change: change.clone(),
+            };
+            self.put(&event_key(topic, event.offset), &event).await?;
+            self.put(&cursor_key("last", topic), &event.offset).await?;
+            events.push(event);
+        }
+        self.stats.lock().recorded += events.len() as u64;
+        self.pending.notify_one();
+        Ok(events)
+    }
+
+    /// Offset of the latest event recorded for `topic`, or 0
+    pub async fn last_offset(&self, topic: &str) -> Result<u64> {
+        Ok(self.fetch(&cursor_key("last", topic)).await?.unwrap_or(0))
+    }
+
+    /// Up to `limit` events of `topic` from `offset` onwards
+    ///
+    /// Fails with `OffsetCompacted` if events from `offset` have already
+    /// been dropped from the outbox.
+    pub async fn events_since(&self, topic: &str, offset: u64, limit: usize) -> Result<Vec<OutboxEvent>> {
+        let offset = offset.max(1);
+        let oldest = self.oldest_offset(topic).await?;
+        if offset < oldest {
+            return Err(StateError::OffsetCompacted { topic: topic.to_string(), offset, oldest });
+        }
+
+        let last = self.last_offset(topic).await?;
+        let mut events = Vec::new();
+        for offset in (offset..=last).take(limit) {
+            if let Some(event) = self.fetch(&event_key(topic, offset)).await? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Record that consumer `group` has processed `topic` up to `offset`
+    pub async fn commit_offset(&self, topic: &str, group: &str, offset: u64) -> Result<()> {
+        self.put(&consumer_key(topic, group), &offset).await
+    }
+
+    /// Offset consumer `group` last committed for `topic`, or 0
+    ///
+    /// A consumer resumes with `events_since(topic, committed + 1, ..)`.
+    pub async fn committed_offset(&self, topic: &str, group: &str) -> Result<u64> {
+        Ok(self.fetch(&consumer_key(topic, group)).await?.unwrap_or(0))
+    }
+
+    /// Relay recorded events to `bus` until the returned task is aborted
+    pub fn spawn_relay(self: &Arc<Self>, bus: Arc<dyn EventBus>) -> JoinHandle<()> {
+        let outbox = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match outbox.relay_pending(bus.as_ref()).await {
+                    Ok(true) => outbox.pending.notified().await,
+                    Ok(false) => tokio::time::sleep(outbox.config.retry_interval).await,
+                    Err(e) => {
+                        tracing::warn!("Outbox relay failed: {}", e);
+                        tokio::time::sleep(outbox.config.retry_interval).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Publish every unrelayed event in offset order
+    ///
+    /// Returns `false` if a publish failed and the rest of its topic must be
+    /// retried.
+    pub async fn relay_pending(&self, bus: &dyn EventBus) -> Result<bool> {
+        let topics: BTreeSet<String> = self.config.subscriptions.iter().map(|s| s.topic.clone()).collect();
+        let mut caught_up = true;
+        for topic in topics {
+            let relayed: u64 = self.fetch(&cursor_key("relayed", &topic)).await?.unwrap_or(0);
+            let last = self.last_offset(&topic).await?;
+            for offset in relayed + 1..=last {
+                let Some(event) = self.fetch::<OutboxEvent>(&event_key(&topic, offset)).await? else {
+                    continue;
+                };
+                if let Err(e) = bus.publish(event).await {
+                    tracing::warn!("Failed to publish event {} of topic {}: {}", offset, topic, e);
+                    self.stats.lock().failed_publishes += 1;
+                    caught_up = false;
+                    break;
+                }
+
+                let _guard = self.write_lock.lock().await;
+                self.put(&cursor_key("relayed", &topic), &offset).await?;
+                if offset > self.config.retention {
+                    self.storage.delete(&event_key(&topic, offset - self.config.retention)).await?;
+                }
+                self.stats.lock().published += 1;
+            }
+        }
+        Ok(caught_up)
+    }
+
+    pub fn stats(&self) -> OutboxStats {
+        self.stats.lock().clone()
+    }
+
+    /// Oldest offset of `topic` still in the outbox
+    async fn oldest_offset(&self, topic: &str) -> Result<u64> {
+        let relayed: u64 = self.fetch(&cursor_key("relayed", topic)).await?.unwrap_or(0);
+        Ok(relayed.saturating_sub(self.config.retention) + 1)
+    }
+
+    async fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        self.storage.set(key, &serde_json::to_vec(value)?).await
+    }
+
+    async fn fetch<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.storage.get(key).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn event_key(topic: &str, offset: u64) -> String {
+    format!("{}events/{}/{:020}", OUTBOX_PREFIX, topic, offset)
+}
+
+fn cursor_key(cursor: &str, topic: &str) -> String {
+    format!("{}{}/{}", OUTBOX_PREFIX, cursor, topic)
+}
+
+fn consumer_key(topic: &str, group: &str) -> String {
+    format!("{}consumers/{}/{}", OUTBOX_PREFIX, topic, group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OutboxSubscription;
+    use crate::storage::{StorageBackendType, StorageConfig};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Collects published events, failing while `down` is set
+    #[derive(Default)]
+    struct RecordingBus {
+        down: AtomicBool,
+        published: parking_lot::Mutex<Vec<OutboxEvent>>,
+    }
+
+    impl EventBus for Arc<RecordingBus> {
+        fn publish(&self, event: OutboxEvent) -> BoxFuture<'static, Result<()>> {
+            let bus = self.clone();
+            Box::pin(async move {
+                if bus.down.load(Ordering::SeqCst) {
+                    return Err(StateError::EventBus { message: "unreachable".to_string() });
+                }
+                bus.published.lock().push(event);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relays_at_least_once_with_consumer_offsets() {
+        let storage_config = StorageConfig { backend: StorageBackendType::Memory, ..Default::default() };
+        let storage = Arc::new(StateStore::new(&storage_config).await.unwrap());
+        let config = OutboxConfig {
+            subscriptions: vec![OutboxSubscription { topic: "nodes".to_string(), prefix: "/nodes/".to_string() }],
+            retention: 2,
+            ..Default::default()
+        };
+        let outbox = Outbox::new(&config, storage);
+        let change = |revision, key: &str| StateChange {
+            revision,
+            key: key.to_string(),
+            old_value: None,
+            new_value: Some(b"up".to_vec()),
+        };
+
+        assert!(outbox.record(&change(1, "/pods/a")).await.unwrap().is_empty());
+        let events = outbox.record(&change(2, "/nodes/x")).await.unwrap();
+        assert_eq!((events[0].topic.as_str(), events[0].offset), ("nodes", 1));
+
+        // Nothing is lost while the bus is down
+        let bus = Arc::new(RecordingBus::default());
+        bus.down.store(true, Ordering::SeqCst);
+        assert!(!outbox.relay_pending(&bus).await.unwrap());
+        bus.down.store(false, Ordering::SeqCst);
+        for revision in 3..=5 {
+            outbox.record(&change(revision, "/nodes/y")).await.unwrap();
+        }
+        assert!(outbox.relay_pending(&bus).await.unwrap());
+        let offsets: Vec<u64> = bus.published.lock().iter().map(|event| event.offset).collect();
+        assert_eq!(offsets, vec![1, 2, 3, 4]);
+
+        // A consumer resumes after its committed offset
+        outbox.commit_offset("nodes", "billing", 3).await.unwrap();
+        let committed = outbox.committed_offset("nodes", "billing").await.unwrap();
+        let replay = outbox.events_since("nodes", committed + 1, 10).await.unwrap();
+        assert_eq!(replay.iter().map(|event| event.change.revision).collect::<Vec<_>>(), vec![5]);
+
+        // Offsets older than the retention window are gone
+        assert!(matches!(
+            outbox.events_since("nodes", 1, 10).await,
+            Err(StateError::OffsetCompacted { oldest: 3, .. })
+        ));
+
+        let stats = outbox.stats();
+        assert_eq!((stats.recorded, stats.published, stats.failed_publishes), (4, 4, 1));
+    }
+}
@@ -0,0 +1,405 @@
+//! Owner references and garbage collection
+//!
+//! Objects that create other objects record themselves as their owners: a
+//! service owns its workloads, and a workload owns its containers and
+//! volumes. Each object is registered under `/_objects/<kind>/<name>` with
+//! its owner references. Deleting an object picks what happens to its
+//! dependents. Background deletion removes the object at once and leaves
+//! the garbage collector to remove dependents whose owners are all gone.
+//! Foreground deletion marks the object and its dependents as deleting, and
+//! removes each one only after its own dependents are gone, so the owner
+//! disappears last. Orphan deletion drops the object's references from its
+//! dependents and leaves them running. Before removing an object's record,
+//! the collector runs the finalizer registered for its kind, which releases
+//! what the object holds.
+
+use crate::error::{Result, StateError};
+use crate::StateManager;
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Prefix under which owned objects are registered
+pub const OBJECT_PREFIX: &str = "/_objects/";
+
+/// Kinds of the objects orchestration creates
+pub mod kinds {
+    pub const SERVICE: &str = "service";
+    pub const WORKLOAD: &str = "workload";
+    pub const CONTAINER: &str = "container";
+    pub const VOLUME: &str = "volume";
+}
+
+/// An object by kind and name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ObjectRef {
+    pub kind: String,
+    pub name: String,
+}
+
+impl ObjectRef {
+    pub fn new(kind: impl Into<String>, name: impl Into<String>) -> Self {
+        Self { kind: kind.into(), name: name.into() }
+    }
+
+    fn key(&self) -> String {
+        format!("{}{}/{}", OBJECT_PREFIX, self.kind, self.name)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.kind.is_empty() || self.kind.contains('/') || self.name.is_empty() {
+            return Err(StateError::InvalidKey { key: self.key() });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ObjectRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.kind, self.name)
+    }
+}
+
+/// A dependent's reference to one of its owners
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnerReference {
+    pub owner: ObjectRef,
+}
+
+impl OwnerReference {
+    pub fn new(owner: ObjectRef) -> Self {
+        Self { owner }
+    }
+}
+
+/// What deleting an object does to its dependents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeletionPropagation {
+    /// Delete the object now; dependents are collected afterwards
+    Background,
+    /// Delete dependents first, then the object
+    Foreground,
+    /// Keep dependents, without their reference to the object
+    Orphan,
+}
+
+/// A registered object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectRecord {
+    pub object: ObjectRef,
+    pub owners: Vec<OwnerReference>,
+    /// Waiting for its dependents to go before it is removed
+    #[serde(default)]
+    pub foreground_deletion: bool,
+}
+
+/// What one garbage collection pass does
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionPlan {
+    /// Dependents of objects deleted in the foreground, to delete the same way
+    pub cascade: Vec<ObjectRef>,
+    /// Dependents with another live owner, to drop their references to owners being deleted
+    pub release: Vec<ObjectRef>,
+    /// Objects to finalize and remove now
+    pub delete: Vec<ObjectRef>,
+}
+
+impl CollectionPlan {
+    pub fn is_empty(&self) -> bool {
+        self.cascade.is_empty() && self.release.is_empty() && self.delete.is_empty()
+    }
+}
+
+/// Plan a collection pass over every registered object
+///
+/// An object is removed once all its owners are gone, or once it is being
+/// deleted in the foreground and nothing refers to it any more. A dependent
+/// shared with an owner that is staying is kept.
+pub fn plan_collection(records: &[ObjectRecord]) -> CollectionPlan {
+    let by_ref: HashMap<&ObjectRef, &ObjectRecord> = records.iter().map(|record| (&record.object, record)).collect();
+    let referenced: BTreeSet<&ObjectRef> = records
+        .iter()
+        .flat_map(|record| record.owners.iter().map(|reference| &reference.owner))
+        .collect();
+
+    let mut plan = CollectionPlan::default();
+    for record in records {
+        let owners: Vec<Option<&&ObjectRecord>> = record.owners.iter().map(|reference| by_ref.get(&reference.owner)).collect();
+        let orphaned = !owners.is_empty() && owners.iter().all(Option::is_none);
+
+        if record.foreground_deletion {
+            if !referenced.contains(&record.object) {
+                plan.delete.push(record.object.clone());
+            }
+        } else if orphaned {
+            plan.delete.push(record.object.clone());
+        } else if owners.iter().flatten().any(|owner| owner.foreground_deletion) {
+            if owners.iter().flatten().all(|owner| owner.foreground_deletion) {
+                plan.cascade.push(record.object.clone());
+            } else {
+                plan.release.push(record.object.clone());
+            }
+        }
+    }
+    plan
+}
+
+/// Releases what objects of one kind hold before their records are removed
+pub trait ObjectFinalizer: Send + Sync {
+    fn finalize<'a>(&'a self, object: &'a ObjectRef) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Registers owned objects and removes the ones their owners left behind
+pub struct GarbageCollector {
+    state: Arc<StateManager>,
+    finalizers: RwLock<HashMap<String, Arc<dyn ObjectFinalizer>>>,
+}
+
+impl GarbageCollector {
+    pub fn new(state: Arc<StateManager>) -> Self {
+        Self {
+            state,
+            finalizers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Run `finalizer` before removing objects of `kind`
+    pub fn register_finalizer(&self, kind: &str, finalizer: Arc<dyn ObjectFinalizer>) {
+        self.finalizers.write().insert(kind.to_string(), finalizer);
+    }
+
+    /// Register a new object with its owners, which must exist and not be deleting
+    pub async fn create(&self, object: ObjectRef, owners: Vec<OwnerReference>) -> Result<()> {
+        object.validate()?;
+        for reference in &owners {
+            match self.get(&reference.owner).await? {
+                Some(owner) if !owner.foreground_deletion => {}
+                Some(_) => {
+                    return Err(StateError::Configuration {
+                        message: format!("owner {} of {} is being deleted", reference.owner, object),
+                    })
+                }
+                None => return Err(StateError::KeyNotFound { key: reference.owner.key() }),
+            }
+        }
+
+        let key = object.key();
+        let record = ObjectRecord { object, owners, foreground_deletion: false };
+        if !self.state.compare_and_swap(&key, None, &serde_json::to_vec(&record)?).await? {
+            return Err(StateError::KeyExists { key });
+        }
+        Ok(())
+    }
+
+    pub async fn get(&self, object: &ObjectRef) -> Result<Option<ObjectRecord>> {
+        match self.state.get(&object.key()).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Objects that name `owner` among their owners
+    pub async fn dependents(&self, owner: &ObjectRef) -> Result<Vec<ObjectRef>> {
+        Ok(self.records().await?
+            .into_iter()
+            .filter(|record| record.owners.iter().any(|reference| &reference.owner == owner))
+            .map(|record| record.object)
+            .collect())
+    }
+
+    /// Delete an object, propagating to its dependents as asked
+    pub async fn delete(&self, object: &ObjectRef, propagation: DeletionPropagation) -> Result<()> {
+        let mut record = self.get(object).await?.ok_or_else(|| StateError::KeyNotFound { key: object.key() })?;
+
+        match propagation {
+            DeletionPropagation::Orphan => {
+                for dependent in self.dependents(object).await? {
+                    if let Some(mut dependent) = self.get(&dependent).await? {
+                        dependent.owners.retain(|reference| &reference.owner != object);
+                        self.put(&dependent).await?;
+                    }
+                }
+                self.remove(object).await?;
+            }
+            DeletionPropagation::Background => {
+                self.remove(object).await?;
+                self.collect().await?;
+            }
+            DeletionPropagation::Foreground => {
+                record.foreground_deletion = true;
+                self.put(&record).await?;
+                self.collect().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run collection passes until nothing more can be collected
+    ///
+    /// Returns the objects removed. An object whose finalizer fails stays
+    /// registered and is retried on the next call.
+    pub async fn collect(&self) -> Result<Vec<ObjectRef>> {
+        let mut removed = Vec::new();
+        let mut failed = BTreeSet::new();
+        loop {
+            let records = self.records().await?;
+            let mut plan = plan_collection(&records);
+            plan.delete.retain(|object| !failed.contains(object));
+            if plan.is_empty() {
+                return Ok(removed);
+            }
+
+            let deleting: BTreeSet<&ObjectRef> = records
+                .iter()
+                .filter(|record| record.foreground_deletion)
+                .map(|record| &record.object)
+                .collect();
+            for record in &records {
+                let mut record = record.clone();
+                if plan.cascade.contains(&record.object) {
+                    record.foreground_deletion = true;
+                } else if plan.release.contains(&record.object) {
+                    record.owners.retain(|reference| !deleting.contains(&reference.owner));
+                } else {
+                    continue;
+                }
+                self.put(&record).await?;
+            }
+            for object in plan.delete {
+                match self.remove(&object).await {
+                    Ok(()) => removed.push(object),
+                    Err(e) => {
+                        tracing::warn!("Could not collect {}: {}", object, e);
+                        failed.insert(object);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collect every `interval`, so dependents left by failed finalizers are retried
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.collect().await {
+                    Ok(removed) if !removed.is_empty() => tracing::info!("Garbage collected {} object(s)", removed.len()),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Garbage collection failed: {}", e),
+                }
+            }
+        })
+    }
+
+    async fn records(&self) -> Result<Vec<ObjectRecord>> {
+        let mut records = Vec::new();
+        for key in self.state.list(OBJECT_PREFIX, None).await? {
+            if let Some(bytes) = self.state.get(&key).await? {
+                records.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn put(&self, record: &ObjectRecord) -> Result<()> {
+        self.state.set(&record.object.key(), &serde_json::to_vec(record)?).await
+    }
+
+    /// Finalize an object and drop its record
+    async fn remove(&self, object: &ObjectRef) -> Result<()> {
+        let finalizer = self.finalizers.read().get(&object.kind).cloned();
+        if let Some(finalizer) = finalizer {
+            finalizer.finalize(object).await.map_err(|e| StateError::Transaction {
+                message: format!("finalizer of {} failed: {}", object, e),
+            })?;
+        }
+        self.state.delete(&object.key()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: &str, name: &str, owners: &[&ObjectRef]) -> ObjectRecord {
+        ObjectRecord {
+            object: ObjectRef::new(kind, name),
+            owners: owners.iter().map(|owner| OwnerReference::new((*owner).clone())).collect(),
+            foreground_deletion: false,
+        }
+    }
+
+    /// Apply a plan the way `GarbageCollector::collect` does
+    fn apply(records: &mut Vec<ObjectRecord>, plan: &CollectionPlan) {
+        let deleting: Vec<ObjectRef> = records.iter().filter(|r| r.foreground_deletion).map(|r| r.object.clone()).collect();
+        for record in records.iter_mut() {
+            if plan.cascade.contains(&record.object) {
+                record.foreground_deletion = true;
+            } else if plan.release.contains(&record.object) {
+                record.owners.retain(|reference| !deleting.contains(&reference.owner));
+            }
+        }
+        records.retain(|record| !plan.delete.contains(&record.object));
+    }
+
+    fn names(objects: &[ObjectRef]) -> Vec<&str> {
+        let mut names: Vec<&str> = objects.iter().map(|object| object.name.as_str()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_cascading_and_orphan_collection() {
+        let web = ObjectRef::new(kinds::SERVICE, "web");
+        let replica = ObjectRef::new(kinds::WORKLOAD, "web-0");
+        let other = ObjectRef::new(kinds::WORKLOAD, "batch-0");
+        let cluster = || {
+            vec![
+                record(kinds::SERVICE, "web", &[]),
+                record(kinds::WORKLOAD, "web-0", &[&web]),
+                record(kinds::CONTAINER, "web-0-app", &[&replica]),
+                record(kinds::VOLUME, "shared", &[&replica, &other]),
+                record(kinds::WORKLOAD, "batch-0", &[]),
+            ]
+        };
+        assert!(plan_collection(&cluster()).is_empty());
+
+        // Foreground: dependents are marked, then removed leaves first, the service last
+        let mut records = cluster();
+        records[0].foreground_deletion = true;
+        let mut order = Vec::new();
+        loop {
+            let plan = plan_collection(&records);
+            if plan.is_empty() {
+                break;
+            }
+            order.push(names(&plan.delete));
+            apply(&mut records, &plan);
+        }
+        assert_eq!(order, vec![vec![], vec![], vec!["web-0-app"], vec!["web-0"], vec!["web"]]);
+        // The volume is kept for its other owner
+        assert_eq!(names(&records.iter().map(|r| r.object.clone()).collect::<Vec<_>>()), ["batch-0", "shared"]);
+        assert_eq!(records[0].owners, vec![OwnerReference::new(other.clone())]);
+
+        // Background: the service goes first, then whatever only it owned
+        let mut records = cluster();
+        records.remove(0);
+        let plan = plan_collection(&records);
+        assert_eq!(names(&plan.delete), ["web-0"]);
+        apply(&mut records, &plan);
+        assert_eq!(names(&plan_collection(&records).delete), ["web-0-app"]);
+
+        // Orphan: dependents without owner references are never collected
+        let mut records = cluster();
+        records.remove(0);
+        records[0].owners.clear();
+        assert!(plan_collection(&records).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//! Range reads at a revision
+//!
+//! A range read returns the keys in `[start, end)` with their values as of
+//! one revision, so a controller can list a prefix and then watch it from the
+//! next revision without missing or repeating a change, as it would against
+//! etcd. Storage only holds the latest values; an older revision is rebuilt
+//! by undoing, key by key, the changes committed since, which the change
+//! journal keeps along with their previous values. Revisions older than the
+//! journal fail with `RevisionCompacted`.
+//!
+//! Large ranges come back in pages. Each page's continue token names the
+//! revision and the last key returned, so the following pages are read at the
+//! same revision and together form one consistent listing.
+
+use crate::error::{Result, StateError};
+use crate::subscriptions::StateChange;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// A key and its value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+/// One page of a range read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangePage {
+    pub entries: Vec<KeyValue>,
+    /// Revision the page was read at; watch from `revision + 1` to follow on
+    pub revision: u64,
+    /// Pass to the next call to read the next page; `None` on the last page
+    pub continue_token: Option<String>,
+}
+
+/// End of the range covering every key that starts with `prefix`
+///
+/// `None` when no key bounds it, as for the empty prefix.
+pub fn prefix_range_end(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = match last {
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+pub(crate) fn in_range(key: &str, start: &str, end: Option<&str>) -> bool {
+    key >= start && end.is_none_or(|end| key < end)
+}
+
+/// Rewind the latest entries of a range to an earlier revision
+///
+/// `changes` are those committed after the revision, oldest first; the first
+/// change to each key holds its value at the revision.
+pub(crate) fn rewind(
+    latest: Vec<(String, Vec<u8>)>,
+    changes: &[StateChange],
+    start: &str,
+    end: Option<&str>,
+) -> Vec<KeyValue> {
+    let mut entries: BTreeMap<String, Vec<u8>> = latest.into_iter().collect();
+    let mut rewound = HashSet::new();
+    for change in changes {
+        if !in_range(&change.key, start, end) || !rewound.insert(change.key.as_str()) {
+            continue;
+        }
+        match &change.old_value {
+            Some(value) => entries.insert(change.key.clone(), value.clone()),
+            None => entries.remove(&change.key),
+        };
+    }
+    entries.into_iter().map(|(key, value)| KeyValue { key, value }).collect()
+}
+
+/// Where the next page of a range read picks up
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ContinueToken {
+    pub revision: u64,
+    pub last_key: String,
+}
+
+impl ContinueToken {
+    /// Smallest key after the last one returned
+    pub fn next_key(&self) -> String {
+        format!("{}\0", self.last_key)
+    }
+
+    pub fn encode(&self) -> String {
+        let key: String = self.last_key.bytes().map(|b| format!("{:02x}", b)).collect();
+        format!("{}-{}", self.revision, key)
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        let invalid = || StateError::InvalidContinueToken { token: token.to_string() };
+        let (revision, key) = token.split_once('-').ok_or_else(invalid)?;
+        let revision = revision.parse().map_err(|_| invalid())?;
+        if !key.len().is_multiple_of(2) || !key.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..key.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&key[i..i + 2], 16))
+            .collect::<std::result::Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let last_key = String::from_utf8(bytes).map_err(|_| invalid())?;
+        Ok(Self { revision, last_key })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(revision: u64, key: &str, old: Option<&str>, new: Option<&str>) -> StateChange {
+        StateChange {
+            revision,
+            key: key.to_string(),
+            old_value: old.map(|v| v.as_bytes().to_vec()),
+            new_value: new.map(|v| v.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_rewinds_range_to_revision() {
+        let end = prefix_range_end("/pods/");
+        assert_eq!(end.as_deref(), Some("/pods0"));
+        assert_eq!(prefix_range_end(""), None);
+
+        // Latest state, after revisions 5..=9 changed b twice, created c and deleted d
+        let latest = vec![
+            ("/pods/a".to_string(), b"1".to_vec()),
+            ("/pods/b".to_string(), b"3".to_vec()),
+            ("/pods/c".to_string(), b"new".to_vec()),
+        ];
+        let changes = vec![
+            change(5, "/pods/b", Some("1"), Some("2")),
+            change(6, "/pods/c", None, Some("new")),
+            change(7, "/nodes/x", Some("n"), None),
+            change(8, "/pods/b", Some("2"), Some("3")),
+            change(9, "/pods/d", Some("gone"), None),
+        ];
+        let at_4 = rewind(latest.clone(), &changes, "/pods/", end.as_deref());
+        let keys: Vec<(&str, &[u8])> = at_4.iter().map(|kv| (kv.key.as_str(), kv.value.as_slice())).collect();
+        assert_eq!(keys, vec![("/pods/a", &b"1"[..]), ("/pods/b", &b"1"[..]), ("/pods/d", &b"gone"[..])]);
+        assert_eq!(rewind(latest, &changes[3..], "/pods/", end.as_deref()).len(), 4);
+
+        let token = ContinueToken { revision: 4, last_key: "/pods/b".to_string() };
+        assert_eq!(ContinueToken::decode(&token.encode()).unwrap(), token);
+        assert!(in_range(&token.next_key(), "/pods/", end.as_deref()));
+        assert!(token.next_key().as_str() > "/pods/b" && token.next_key().as_str() < "/pods/ba");
+        assert!(matches!(ContinueToken::decode("4-zz"), Err(StateError::InvalidContinueToken { .. })));
+    }
+}
@@ -1,13 +1,79 @@
 //! State replication management
-//! Emergency stub implementation for Phase 1 stabilization
+//!
+//! Every write names a consistency level: `One` returns once this node has
+//! the write, `Quorum` once a majority of the replica group has it, and
+//! `All` once every replica does. Ephemeral keys such as health reports can
+//! take `One` for latency while critical configuration takes `All` for
+//! durability. The replica group is this node plus up to `factor - 1`
+//! registered replicas, so a single node acknowledges every level on its own.
+//!
+//! Writes first go to the fastest replicas needed for the level. If one of
+//! them has not answered within `hedge_after`, or fails, the write is also
+//! sent to a spare replica, and whichever acknowledgements arrive first
+//! count. This trims the tail latency a single slow replica would add.
+//! Writes still in flight when the level is met finish in the background.
 
-use crate::error::Result;
+use crate::config::ReplicationConfig;
+use crate::error::{Result, StateError};
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
 use nexus_shared::NodeId;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 
-/// Replication manager for distributed state
+/// How many replicas must acknowledge a write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyLevel {
+    /// This node only
+    One,
+    /// A majority of the replica group
+    #[default]
+    Quorum,
+    /// Every replica in the group
+    All,
+}
+
+impl ConsistencyLevel {
+    /// Acknowledgements needed in a replica group of `group_size`
+    pub fn required_acks(&self, group_size: usize) -> usize {
+        match self {
+            ConsistencyLevel::One => 1,
+            ConsistencyLevel::Quorum => group_size / 2 + 1,
+            ConsistencyLevel::All => group_size,
+        }
+        .max(1)
+    }
+}
+
+/// A write shipped to a replica
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaWrite {
+    pub key: String,
+    /// New value; `None` deletes the key
+    pub value: Option<Vec<u8>>,
+}
+
+/// A node that stores replicas of this node's writes
+pub trait ReplicaTarget: Send + Sync {
+    fn node_id(&self) -> NodeId;
+
+    /// Apply the write, resolving once it is durable on the replica
+    fn write(&self, write: ReplicaWrite) -> BoxFuture<'static, Result<()>>;
+}
+
+/// Replicas that acknowledged a write
 #[derive(Debug, Clone)]
-pub struct ReplicationManager {
-    // Stub implementation
+pub struct WriteOutcome {
+    pub level: ConsistencyLevel,
+    /// Nodes holding the write when it returned, this node first
+    pub acked: Vec<NodeId>,
+    /// Writes sent to spare replicas because others were slow
+    pub hedged: usize,
 }
 
 /// Replication state for consensus
@@ -18,16 +84,41 @@ pub struct ReplicationState {
 }
 
 /// Replication statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ReplicationStats {
     pub replicas: usize,
     pub healthy_replicas: usize,
+    pub writes: u64,
+    pub hedged_writes: u64,
+    pub failed_writes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ReplicaHealth {
+    /// Smoothed write latency
+    latency: Option<Duration>,
+    failing: bool,
+}
+
+/// Replication manager for distributed state
+pub struct ReplicationManager {
+    config: ReplicationConfig,
+    node_id: NodeId,
+    replicas: Mutex<Vec<Arc<dyn ReplicaTarget>>>,
+    health: Arc<Mutex<HashMap<NodeId, ReplicaHealth>>>,
+    stats: Mutex<ReplicationStats>,
 }
 
 impl ReplicationManager {
     /// Create new replication manager
-    pub fn new(_config: &crate::config::ReplicationConfig, _node_id: NodeId) -> Result<Self> {
-        Ok(Self {})
+    pub fn new(config: &ReplicationConfig, node_id: NodeId) -> Result<Self> {
+        Ok(Self {
+            config: config.clone(),
+            node_id,
+            replicas: Mutex::new(Vec::new()),
+            health: Arc::new(Mutex::new(HashMap::new())),
+            stats: Mutex::new(ReplicationStats::default()),
+        })
     }
 
     /// Start replication services
@@ -40,11 +131,180 @@ impl ReplicationManager {
         Ok(())
     }
 
+    /// Add a replica, replacing any with the same node ID
+    pub fn add_replica(&self, replica: Arc<dyn ReplicaTarget>) {
+        let mut replicas = self.replicas.lock();
+        replicas.retain(|existing| existing.node_id() != replica.node_id());
+        replicas.push(replica);
+    }
+
+    pub fn remove_replica(&self, node_id: &NodeId) {
+        self.replicas.lock().retain(|replica| replica.node_id() != *node_id);
+        self.health.lock().remove(node_id);
+    }
+
+    /// Consistency level for writes to `key`
+    pub fn consistency_for(&self, key: &str) -> ConsistencyLevel {
+        self.config.consistency_for(key)
+    }
+
+    /// Replicate a write already applied on this node
+    ///
+    /// Fails with `QuorumNotAvailable` if the level is not met within the
+    /// write timeout.
+    pub async fn replicate(&self, write: ReplicaWrite, level: ConsistencyLevel) -> Result<WriteOutcome> {
+        let targets = self.ranked_replicas();
+        let required = level.required_acks(targets.len() + 1);
+        let mut acked = vec![self.node_id];
+        let mut hedged = 0;
+        let mut next = 0;
+        let mut in_flight = FuturesUnordered::new();
+        while next < required - 1 {
+            in_flight.push(self.send(&targets[next], write.clone()));
+            next += 1;
+        }
+
+        let deadline = Instant::now() + self.config.write_timeout;
+        let hedge = tokio::time::sleep(self.config.hedge_after);
+        tokio::pin!(hedge);
+        while acked.len() < required && !(in_flight.is_empty() && next == targets.len()) {
+            tokio::select! {
+                Some((node_id, result)) = in_flight.next() => match result {
+                    Ok(()) => acked.push(node_id),
+                    Err(e) => {
+                        tracing::warn!("Replica {} failed write to {}: {}", node_id, write.key, e);
+                        if next < targets.len() {
+                            in_flight.push(self.send(&targets[next], write.clone()));
+                            next += 1;
+                        }
+                    }
+                },
+                _ = &mut hedge, if next < targets.len() => {
+                    tracing::debug!("Hedging write to {} on replica {}", write.key, targets[next].node_id());
+                    in_flight.push(self.send(&targets[next], write.clone()));
+                    next += 1;
+                    hedged += 1;
+                    hedge.as_mut().reset(Instant::now() + self.config.hedge_after);
+                }
+                _ = tokio::time::sleep_until(deadline) => break,
+            }
+        }
+
+        if !in_flight.is_empty() {
+            tokio::spawn(async move { while in_flight.next().await.is_some() {} });
+        }
+
+        let mut stats = self.stats.lock();
+        stats.writes += 1;
+        stats.hedged_writes += hedged as u64;
+        if acked.len() < required {
+            stats.failed_writes += 1;
+            return Err(StateError::QuorumNotAvailable { required, available: acked.len() });
+        }
+        Ok(WriteOutcome { level, acked, hedged })
+    }
+
     /// Get replication statistics
     pub async fn stats(&self) -> ReplicationStats {
+        let replicas = self.replicas.lock().len().min(self.config.factor.saturating_sub(1));
+        let failing = self.health.lock().values().filter(|health| health.failing).count();
         ReplicationStats {
-            replicas: 3,
-            healthy_replicas: 3,
+            replicas: replicas + 1,
+            healthy_replicas: (replicas + 1).saturating_sub(failing),
+            ..self.stats.lock().clone()
+        }
+    }
+
+    /// The replica group's other members, healthy and fastest first
+    fn ranked_replicas(&self) -> Vec<Arc<dyn ReplicaTarget>> {
+        let health = self.health.lock();
+        let mut replicas = self.replicas.lock().clone();
+        replicas.sort_by_key(|replica| {
+            let health = health.get(&replica.node_id()).copied().unwrap_or_default();
+            (health.failing, health.latency.unwrap_or_default())
+        });
+        replicas.truncate(self.config.factor.saturating_sub(1));
+        replicas
+    }
+
+    /// Send a write, recording the replica's latency and health
+    fn send(&self, replica: &Arc<dyn ReplicaTarget>, write: ReplicaWrite) -> BoxFuture<'static, (NodeId, Result<()>)> {
+        let node_id = replica.node_id();
+        let pending = replica.write(write);
+        let health = self.health.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = pending.await;
+            let elapsed = started.elapsed();
+            let mut health = health.lock();
+            let record = health.entry(node_id).or_default();
+            record.failing = result.is_err();
+            record.latency = Some(record.latency.map_or(elapsed, |latency| (latency * 3 + elapsed) / 4));
+            (node_id, result)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DelayedReplica {
+        node_id: NodeId,
+        delay: Duration,
+    }
+
+    impl ReplicaTarget for DelayedReplica {
+        fn node_id(&self) -> NodeId {
+            self.node_id
+        }
+
+        fn write(&self, _write: ReplicaWrite) -> BoxFuture<'static, Result<()>> {
+            let delay = self.delay;
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consistency_levels_and_hedging() {
+        let config = ReplicationConfig {
+            factor: 4,
+            hedge_after: Duration::from_millis(50),
+            write_timeout: Duration::from_secs(5),
+            key_consistency: [("health/".to_string(), ConsistencyLevel::One), ("config/".to_string(), ConsistencyLevel::All)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(config.consistency_for("health/node-1"), ConsistencyLevel::One);
+        assert_eq!(config.consistency_for("config/cluster"), ConsistencyLevel::All);
+        assert_eq!(config.consistency_for("workloads/api"), ConsistencyLevel::Quorum);
+
+        let manager = ReplicationManager::new(&config, NodeId::random()).unwrap();
+        let write = || ReplicaWrite { key: "k".to_string(), value: Some(b"v".to_vec()) };
+
+        // Alone, this node meets every level
+        let outcome = manager.replicate(write(), ConsistencyLevel::All).await.unwrap();
+        assert_eq!(outcome.acked.len(), 1);
+
+        // The slow replica has no latency history yet and is tried first for a quorum
+        for delay in [300, 10, 10] {
+            manager.add_replica(Arc::new(DelayedReplica { node_id: NodeId::random(), delay: Duration::from_millis(delay) }));
         }
+        let started = Instant::now();
+        let outcome = manager.replicate(write(), ConsistencyLevel::Quorum).await.unwrap();
+        assert_eq!((outcome.acked.len(), outcome.hedged), (3, 1));
+        assert!(started.elapsed() < Duration::from_millis(250));
+
+        // All waits for the slow replica
+        let outcome = manager.replicate(write(), ConsistencyLevel::All).await.unwrap();
+        assert_eq!(outcome.acked.len(), 4);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        let stats = manager.stats().await;
+        assert_eq!((stats.replicas, stats.writes, stats.failed_writes), (4, 3, 0));
     }
-}
\ No newline at end of file
+}
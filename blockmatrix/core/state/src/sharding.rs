@@ -1,13 +1,40 @@
-//! State sharding management
-//! Emergency stub implementation for Phase 1 stabilization
+//! State sharding and live rebalancing
+//!
+//! The key space is split into contiguous ranges of stored keys, the shards,
+//! each owned by a set of replica nodes. The shard map lives in the store
+//! itself under [`SHARD_MAP_KEY`]: the rebalancer on the consensus leader
+//! commits each new map, and every node installs it as it applies the write.
+//!
+//! Each round the rebalancer measures the shards and plans moves: shards
+//! grown past `max_shard_bytes` are split at their median key, neighbouring
+//! shards both under `min_shard_bytes` with the same owners are merged, and
+//! replicas are added, dropped and transferred so every shard has its replica
+//! count on live nodes and nodes hold similar numbers of shards. Splits and
+//! merges only change the map; adding or transferring a replica copies the
+//! shard's data to the new owner first.
+//!
+//! A copy is throttled to `migration_rate` bytes per second. While it runs
+//! the shard is dual-written: every write to it committed on this node is
+//! also forwarded to the new owner, in commit order. Batches are read and
+//! sent with applies held back, so a batch never lands after a newer
+//! forwarded write. Once the copy is done the map is cut over to the new
+//! owners and the forwarding stops.
 
-use crate::error::Result;
+use crate::config::ShardingConfig;
+use crate::error::{Result, StateError};
+use crate::replication::ReplicaWrite;
+use futures::future::BoxFuture;
+use nexus_shared::NodeId;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
-/// Shard manager for distributed state partitioning
-#[derive(Debug, Clone)]
-pub struct ShardManager {
-    // Stub implementation
-}
+/// Key the committed shard map is stored under
+pub const SHARD_MAP_KEY: &str = "/_sharding/map";
+
+/// Identifies a shard
+pub type ShardId = u64;
 
 /// Shard configuration
 #[derive(Debug, Clone)]
@@ -19,10 +46,276 @@ pub struct ShardConfig {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ShardKey(pub String);
 
+/// A contiguous range of stored keys and the nodes that own it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardInfo {
+    pub id: ShardId,
+    pub start: String,
+    /// Exclusive; `None` for the last shard
+    pub end: Option<String>,
+    /// Replica nodes; the first is the primary
+    pub owners: Vec<NodeId>,
+}
+
+impl ShardInfo {
+    pub fn contains(&self, key: &str) -> bool {
+        key >= self.start.as_str() && self.end.as_deref().is_none_or(|end| key < end)
+    }
+}
+
+/// Shards covering the whole key space, in key order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardMap {
+    /// Bumped with every change
+    pub epoch: u64,
+    next_id: ShardId,
+    pub shards: Vec<ShardInfo>,
+}
+
+impl ShardMap {
+    /// One shard over every key, owned by `owner`
+    pub fn new(owner: NodeId) -> Self {
+        Self {
+            epoch: 1,
+            next_id: 2,
+            shards: vec![ShardInfo { id: 1, start: String::new(), end: None, owners: vec![owner] }],
+        }
+    }
+
+    pub fn shard_for(&self, key: &str) -> &ShardInfo {
+        let index = self.shards.partition_point(|shard| shard.start.as_str() <= key);
+        &self.shards[index.saturating_sub(1)]
+    }
+
+    pub fn get(&self, id: ShardId) -> Option<&ShardInfo> {
+        self.shards.iter().find(|shard| shard.id == id)
+    }
+
+    fn index_of(&self, id: ShardId) -> Result<usize> {
+        self.shards
+            .iter()
+            .position(|shard| shard.id == id)
+            .ok_or_else(|| StateError::Sharding { message: format!("shard {} does not exist", id) })
+    }
+
+    /// Split a shard at `at`, which starts the new right-hand shard
+    pub fn split(&mut self, id: ShardId, at: &str) -> Result<ShardId> {
+        let index = self.index_of(id)?;
+        let shard = &self.shards[index];
+        if at <= shard.start.as_str() || !shard.contains(at) {
+            return Err(StateError::Sharding { message: format!("{} does not split shard {}", at, id) });
+        }
+        let right = ShardInfo { id: self.next_id, start: at.to_string(), end: shard.end.clone(), owners: shard.owners.clone() };
+        self.shards[index].end = Some(at.to_string());
+        self.shards.insert(index + 1, right);
+        self.next_id += 1;
+        self.epoch += 1;
+        Ok(self.next_id - 1)
+    }
+
+    /// Merge a shard into its left neighbour; both must have the same owners
+    pub fn merge(&mut self, left: ShardId, right: ShardId) -> Result<()> {
+        let index = self.index_of(left)?;
+        let same_owners = self.shards.get(index + 1).is_some_and(|next| {
+            next.id == right && next.owners.iter().collect::<HashSet<_>>() == self.shards[index].owners.iter().collect()
+        });
+        if !same_owners {
+            return Err(StateError::Sharding {
+                message: format!("shards {} and {} are not neighbours with the same owners", left, right),
+            });
+        }
+        let removed = self.shards.remove(index + 1);
+        self.shards[index].end = removed.end;
+        self.epoch += 1;
+        Ok(())
+    }
+
+    fn set_owners(&mut self, id: ShardId, owners: Vec<NodeId>) -> Result<()> {
+        let index = self.index_of(id)?;
+        self.shards[index].owners = owners;
+        self.epoch += 1;
+        Ok(())
+    }
+}
+
+/// Measured size of a shard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardSize {
+    pub keys: usize,
+    pub bytes: usize,
+}
+
+/// One step of a rebalancing plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShardMove {
+    /// Split at the median key
+    Split { shard: ShardId },
+    Merge { left: ShardId, right: ShardId },
+    /// Copy to a new owner and add it
+    AddReplica { shard: ShardId, to: NodeId },
+    /// Drop an owner that has left
+    RemoveReplica { shard: ShardId, node: NodeId },
+    /// Copy to a new owner, then replace `from` with it
+    Transfer { shard: ShardId, from: NodeId, to: NodeId },
+}
+
+/// Plan the moves that bring the shards within size bounds and spread their
+/// replicas evenly over `nodes`
+pub fn plan(map: &ShardMap, sizes: &HashMap<ShardId, ShardSize>, nodes: &[NodeId], config: &ShardingConfig) -> Vec<ShardMove> {
+    let mut moves = Vec::new();
+    let size_of = |id: ShardId| sizes.get(&id).copied().unwrap_or_default();
+
+    let mut touched = HashSet::new();
+    for shard in &map.shards {
+        let size = size_of(shard.id);
+        if size.bytes > config.max_shard_bytes && size.keys >= 2 {
+            moves.push(ShardMove::Split { shard: shard.id });
+            touched.insert(shard.id);
+        }
+    }
+    for pair in map.shards.windows(2) {
+        let (left, right) = (&pair[0], &pair[1]);
+        if touched.contains(&left.id) || touched.contains(&right.id) {
+            continue;
+        }
+        let small = |id| size_of(id).bytes < config.min_shard_bytes;
+        let same_owners = left.owners.iter().collect::<HashSet<_>>() == right.owners.iter().collect();
+        if small(left.id) && small(right.id) && same_owners {
+            moves.push(ShardMove::Merge { left: left.id, right: right.id });
+            touched.extend([left.id, right.id]);
+        }
+    }
+
+    // Replica placement works on the owners as they will be after each move,
+    // leaving out shards merged into their left neighbour
+    let merged_away: HashSet<ShardId> = moves
+        .iter()
+        .filter_map(|planned| match planned {
+            ShardMove::Merge { right, .. } => Some(*right),
+            _ => None,
+        })
+        .collect();
+    let live: HashSet<NodeId> = nodes.iter().copied().collect();
+    let mut load: HashMap<NodeId, usize> = nodes.iter().map(|node| (*node, 0)).collect();
+    let mut owners: Vec<(ShardId, Vec<NodeId>)> = map
+        .shards
+        .iter()
+        .filter(|shard| !merged_away.contains(&shard.id))
+        .map(|shard| (shard.id, shard.owners.clone()))
+        .collect();
+    for (_, shard_owners) in &owners {
+        for owner in shard_owners.iter().filter(|owner| live.contains(owner)) {
+            *load.entry(*owner).or_default() += 1;
+        }
+    }
+    let replicas = config.replicas.min(nodes.len()).max(1);
+    let mut placements = 0;
+
+    for (shard, shard_owners) in &mut owners {
+        for node in shard_owners.iter().filter(|owner| !live.contains(owner)) {
+            moves.push(ShardMove::RemoveReplica { shard: *shard, node: *node });
+        }
+        shard_owners.retain(|owner| live.contains(owner));
+        while shard_owners.len() < replicas && placements < config.max_moves_per_round {
+            let Some(to) = least_loaded(&load, shard_owners) else {
+                break;
+            };
+            moves.push(ShardMove::AddReplica { shard: *shard, to });
+            shard_owners.push(to);
+            *load.entry(to).or_default() += 1;
+            placements += 1;
+        }
+    }
+
+    while placements < config.max_moves_per_round {
+        let Some((&busiest, &most)) = load.iter().max_by_key(|(node, count)| (**count, **node)) else {
+            break;
+        };
+        let transfer = owners.iter_mut().find_map(|(shard, shard_owners)| {
+            if !shard_owners.contains(&busiest) {
+                return None;
+            }
+            let to = least_loaded(&load, shard_owners)?;
+            (most > load[&to] + 1).then_some((*shard, shard_owners, to))
+        });
+        let Some((shard, shard_owners, to)) = transfer else {
+            break;
+        };
+        moves.push(ShardMove::Transfer { shard, from: busiest, to });
+        for owner in shard_owners.iter_mut().filter(|owner| **owner == busiest) {
+            *owner = to;
+        }
+        *load.get_mut(&busiest).expect("busiest is loaded") -= 1;
+        *load.entry(to).or_default() += 1;
+        placements += 1;
+    }
+    moves
+}
+
+/// Live node holding the fewest shards that is not among `exclude`
+fn least_loaded(load: &HashMap<NodeId, usize>, exclude: &[NodeId]) -> Option<NodeId> {
+    load.iter()
+        .filter(|(node, _)| !exclude.contains(node))
+        .min_by_key(|(node, count)| (**count, **node))
+        .map(|(node, _)| *node)
+}
+
+/// A node shard data is copied to
+pub trait ShardTarget: Send + Sync {
+    fn node_id(&self) -> NodeId;
+
+    /// Apply writes to a shard, resolving once they are durable on the node
+    fn write_batch(&self, shard: ShardId, writes: Vec<ReplicaWrite>) -> BoxFuture<'static, Result<()>>;
+}
+
+/// Sharding and rebalancing statistics
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardingStats {
+    pub shards: usize,
+    pub epoch: u64,
+    /// Moves planned in the current round and not yet run
+    pub pending_moves: usize,
+    /// Shard being copied, if any
+    pub migrating: Option<ShardId>,
+    pub migrated_keys: u64,
+    pub migrated_bytes: u64,
+    /// Writes forwarded to new owners while their shard was copied
+    pub dual_writes: u64,
+    pub splits: u64,
+    pub merges: u64,
+    pub completed_moves: u64,
+    pub failed_moves: u64,
+}
+
+struct Migration {
+    shard: ShardId,
+    start: String,
+    end: Option<String>,
+    target: Arc<dyn ShardTarget>,
+    failed: Option<String>,
+}
+
+/// Shard manager for distributed state partitioning
+pub struct ShardManager {
+    config: ShardingConfig,
+    node_id: NodeId,
+    map: Mutex<ShardMap>,
+    targets: Mutex<HashMap<NodeId, Arc<dyn ShardTarget>>>,
+    migration: Mutex<Option<Migration>>,
+    stats: Mutex<ShardingStats>,
+}
+
 impl ShardManager {
     /// Create new shard manager
-    pub fn new(_config: &crate::config::ShardingConfig) -> Result<Self> {
-        Ok(Self {})
+    pub fn new(config: &ShardingConfig, node_id: NodeId) -> Result<Self> {
+        Ok(Self {
+            config: config.clone(),
+            node_id,
+            map: Mutex::new(ShardMap::new(node_id)),
+            targets: Mutex::new(HashMap::new()),
+            migration: Mutex::new(None),
+            stats: Mutex::new(ShardingStats::default()),
+        })
     }
 
     /// Start sharding services
@@ -37,6 +330,244 @@ impl ShardManager {
 
     /// Get shard key for a key
     pub fn get_shard_key(&self, key: &str) -> ShardKey {
-        ShardKey(key.to_string())
+        ShardKey(self.map.lock().shard_for(key).id.to_string())
+    }
+
+    pub fn shard_for(&self, key: &str) -> ShardInfo {
+        self.map.lock().shard_for(key).clone()
+    }
+
+    pub fn map(&self) -> ShardMap {
+        self.map.lock().clone()
+    }
+
+    /// Install a committed map, unless this node already has a newer one
+    pub fn install(&self, map: ShardMap) -> bool {
+        let mut current = self.map.lock();
+        if map.epoch <= current.epoch {
+            return false;
+        }
+        *current = map;
+        true
+    }
+
+    /// A node shards can be placed on, replacing any with the same node ID
+    pub fn add_node(&self, target: Arc<dyn ShardTarget>) {
+        self.targets.lock().insert(target.node_id(), target);
+    }
+
+    /// Take a node out of placement; its shards are re-replicated elsewhere
+    pub fn remove_node(&self, node_id: &NodeId) {
+        self.targets.lock().remove(node_id);
+    }
+
+    /// This node and every node shards can be placed on
+    pub fn nodes(&self) -> Vec<NodeId> {
+        let mut nodes: Vec<NodeId> = self.targets.lock().keys().copied().collect();
+        nodes.push(self.node_id);
+        nodes.sort();
+        nodes
+    }
+
+    pub fn stats(&self) -> ShardingStats {
+        let map = self.map.lock();
+        ShardingStats {
+            shards: map.shards.len(),
+            epoch: map.epoch,
+            migrating: self.migration.lock().as_ref().map(|migration| migration.shard),
+            ..self.stats.lock().clone()
+        }
     }
-}
\ No newline at end of file
+
+    /// Plan a rebalancing round from measured shard sizes
+    pub fn plan(&self, sizes: &HashMap<ShardId, ShardSize>) -> Vec<ShardMove> {
+        let moves = plan(&self.map.lock(), sizes, &self.nodes(), &self.config);
+        self.stats.lock().pending_moves = moves.len();
+        moves
+    }
+
+    /// Update a map for a move whose data, if it needed any, has been copied
+    ///
+    /// A split needs the key it splits at.
+    pub(crate) fn apply_move(&self, map: &mut ShardMap, shard_move: &ShardMove, split_at: Option<&str>) -> Result<()> {
+        match shard_move {
+            ShardMove::Split { shard } => {
+                let at = split_at.ok_or_else(|| StateError::Sharding { message: format!("shard {} is too small to split", shard) })?;
+                map.split(*shard, at)?;
+                self.stats.lock().splits += 1;
+            }
+            ShardMove::Merge { left, right } => {
+                map.merge(*left, *right)?;
+                self.stats.lock().merges += 1;
+            }
+            ShardMove::RemoveReplica { shard, node } => {
+                let mut owners = map.get(*shard).map(|info| info.owners.clone()).unwrap_or_default();
+                owners.retain(|owner| owner != node);
+                map.set_owners(*shard, owners)?;
+            }
+            ShardMove::AddReplica { shard, to } | ShardMove::Transfer { shard, to, .. } => {
+                let mut owners = map.get(*shard).map(|info| info.owners.clone()).unwrap_or_default();
+                if let ShardMove::Transfer { from, .. } = shard_move {
+                    owners.retain(|owner| owner != from);
+                }
+                if !owners.contains(to) {
+                    owners.push(*to);
+                }
+                map.set_owners(*shard, owners)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start dual-writing a shard to the node it is copied to
+    ///
+    /// Nothing needs copying to this node, which already holds every shard;
+    /// that returns `Ok(false)`.
+    pub(crate) fn begin_migration(&self, shard: ShardId, to: NodeId) -> Result<bool> {
+        if to == self.node_id {
+            return Ok(false);
+        }
+        let target = self.targets.lock().get(&to).cloned().ok_or_else(|| StateError::Sharding {
+            message: format!("node {} is not available for shard placement", to),
+        })?;
+        let info = self
+            .map
+            .lock()
+            .get(shard)
+            .cloned()
+            .ok_or_else(|| StateError::Sharding { message: format!("shard {} does not exist", shard) })?;
+        let mut migration = self.migration.lock();
+        if let Some(running) = migration.as_ref() {
+            return Err(StateError::Sharding { message: format!("shard {} is already being migrated", running.shard) });
+        }
+        *migration = Some(Migration { shard, start: info.start, end: info.end, target, failed: None });
+        Ok(true)
+    }
+
+    /// Why the running migration failed, if it did
+    pub(crate) fn migration_failure(&self) -> Option<String> {
+        self.migration.lock().as_ref().and_then(|migration| migration.failed.clone())
+    }
+
+    /// Stop dual-writing
+    pub(crate) fn end_migration(&self) {
+        self.migration.lock().take();
+    }
+
+    /// Send a copied batch of the migrating shard
+    pub(crate) async fn send_batch(&self, writes: Vec<(String, Vec<u8>)>) -> Result<()> {
+        let (shard, target) = {
+            let migration = self.migration.lock();
+            let migration = migration.as_ref().ok_or_else(|| StateError::Sharding { message: "no migration is running".to_string() })?;
+            (migration.shard, migration.target.clone())
+        };
+        let bytes: usize = writes.iter().map(|(key, value)| key.len() + value.len()).sum();
+        let count = writes.len();
+        let writes = writes.into_iter().map(|(key, value)| ReplicaWrite { key, value: Some(value) }).collect();
+        target.write_batch(shard, writes).await?;
+        let mut stats = self.stats.lock();
+        stats.migrated_keys += count as u64;
+        stats.migrated_bytes += bytes as u64;
+        Ok(())
+    }
+
+    /// Forward a committed write to the node a migrating shard is copied to
+    ///
+    /// Called in commit order as writes are applied. A failed forward fails
+    /// the migration, since the copy would then miss the write.
+    pub(crate) async fn forward(&self, key: &str, value: Option<&[u8]>) {
+        let (shard, target) = {
+            let migration = self.migration.lock();
+            match migration.as_ref() {
+                Some(migration)
+                    if migration.failed.is_none()
+                        && key >= migration.start.as_str()
+                        && migration.end.as_deref().is_none_or(|end| key < end) =>
+                {
+                    (migration.shard, migration.target.clone())
+                }
+                _ => return,
+            }
+        };
+        let write = ReplicaWrite { key: key.to_string(), value: value.map(<[u8]>::to_vec) };
+        match target.write_batch(shard, vec![write]).await {
+            Ok(()) => self.stats.lock().dual_writes += 1,
+            Err(e) => {
+                if let Some(migration) = self.migration.lock().as_mut() {
+                    migration.failed = Some(format!("dual write of {} to {} failed: {}", key, target.node_id(), e));
+                }
+            }
+        }
+    }
+
+    pub(crate) fn record_outcome(&self, result: &Result<()>) {
+        let mut stats = self.stats.lock();
+        stats.pending_moves = stats.pending_moves.saturating_sub(1);
+        match result {
+            Ok(()) => stats.completed_moves += 1,
+            Err(_) => stats.failed_moves += 1,
+        }
+    }
+
+    pub(crate) fn config(&self) -> &ShardingConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ShardingConfig {
+        ShardingConfig {
+            enabled: true,
+            replicas: 2,
+            max_shard_bytes: 1000,
+            min_shard_bytes: 100,
+            ..ShardingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_splits_merges_and_spreads_replicas() {
+        let (a, b, c) = (NodeId::random(), NodeId::random(), NodeId::random());
+        let mut map = ShardMap::new(a);
+        let right = map.split(1, "m").unwrap();
+        let tail = map.split(right, "t").unwrap();
+        assert_eq!(map.shard_for("").id, 1);
+        assert_eq!(map.shard_for("n").id, right);
+        assert_eq!(map.shard_for("zzz").id, tail);
+        assert!(map.split(1, "q").is_err());
+
+        // The first shard is too big; the other two are small enough to merge
+        let sizes = HashMap::from([
+            (1, ShardSize { keys: 50, bytes: 5000 }),
+            (right, ShardSize { keys: 1, bytes: 10 }),
+            (tail, ShardSize { keys: 1, bytes: 10 }),
+        ]);
+        let moves = plan(&map, &sizes, &[a, b, c], &config());
+        assert_eq!(moves[0], ShardMove::Split { shard: 1 });
+        assert_eq!(moves[1], ShardMove::Merge { left: right, right: tail });
+
+        // Every shard left after the merge gains a second replica on b or c
+        let added: Vec<NodeId> = moves
+            .iter()
+            .filter_map(|m| match m {
+                ShardMove::AddReplica { to, .. } => Some(*to),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(added.len(), 2);
+        assert!(!added.contains(&a));
+        assert!(added.contains(&b) && added.contains(&c));
+
+        // Owners that left are dropped; a's shards spread once nodes join
+        map.shards[0].owners = vec![a, b];
+        let moves = plan(&map, &HashMap::new(), &[a, c], &config());
+        assert!(moves.contains(&ShardMove::RemoveReplica { shard: 1, node: b }));
+
+        map.merge(right, tail).unwrap();
+        assert_eq!(map.shards.len(), 2);
+        assert!(map.merge(1, right).is_err());
+    }
+}
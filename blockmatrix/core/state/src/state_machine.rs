@@ -0,0 +1,381 @@
+//! Applies committed proposals to the store
+//!
+//! The consensus engine hands each committed proposal to the state machine
+//! in commit order. The state machine writes it to storage and publishes the
+//! resulting change, with the previous value, to the subscription manager,
+//! and records it in the outbox for any topic whose prefix it matches.
+//! Applies are serialized so revisions and outbox offsets follow commit order.
+//! Lease grants and revocations are applied here too, so the keys attached
+//! to a lease are deleted in commit order like any other delete. So are
+//! conditional transactions: their comparisons are checked and their
+//! operations applied under one apply, with nothing in between. Writes to a
+//! shard being migrated are forwarded to its new owner as they apply, and a
+//! committed shard map is installed in the shard manager, as are committed
+//! data keys and key versions in the encryption manager. Re-encrypting a value only
+//! replaces its stored form: it is neither published nor a new revision.
+//! A committed eviction removes the member from the manager's membership
+//! view, so no node drops a member before the cluster has agreed to.
+
+use crate::consensus::Proposal;
+use crate::election::Candidacy;
+use crate::encryption::{self, EncryptionManager, KEY_VERSION_KEY};
+use crate::error::{Result, StateError};
+use crate::lease::LeaseManager;
+use crate::outbox::{self, Outbox};
+use crate::range::{self, KeyValue};
+use crate::sharding::{ShardId, ShardInfo, ShardManager, ShardMap, ShardSize, SHARD_MAP_KEY};
+use crate::storage::StateStore;
+use crate::subscriptions::{StateChange, SubscriptionManager};
+use crate::transactions::{Compare, CompareTarget, KeyRevisions, TxnId, TxnOp, TxnOpResponse, TxnRequest, TxnResponse};
+use crate::ClusterMember;
+use nexus_shared::NodeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex, RwLock};
+use tokio::time::Instant;
+
+/// Applies committed key-value and lease proposals and publishes the changes
+pub struct StateMachine {
+    storage: Arc<StateStore>,
+    encryption: Arc<EncryptionManager>,
+    subscriptions: Arc<SubscriptionManager>,
+    outbox: Arc<Outbox>,
+    leases: Arc<LeaseManager>,
+    sharding: Arc<ShardManager>,
+    /// Membership view of the owning manager, updated by committed evictions
+    cluster_members: Arc<RwLock<HashMap<NodeId, ClusterMember>>>,
+    leader_node: Arc<RwLock<Option<NodeId>>>,
+    /// Revisions of each stored key written since this node started
+    revisions: parking_lot::Mutex<HashMap<String, KeyRevisions>>,
+    /// Proposers waiting for the outcome of their transactions
+    txn_waiters: parking_lot::Mutex<HashMap<TxnId, oneshot::Sender<TxnResponse>>>,
+    apply_lock: Mutex<()>,
+}
+
+impl StateMachine {
+    pub fn new(
+        storage: Arc<StateStore>,
+        encryption: Arc<EncryptionManager>,
+        subscriptions: Arc<SubscriptionManager>,
+        outbox: Arc<Outbox>,
+        leases: Arc<LeaseManager>,
+        sharding: Arc<ShardManager>,
+    ) -> Self {
+        Self {
+            storage,
+            encryption,
+            subscriptions,
+            outbox,
+            leases,
+            sharding,
+            cluster_members: Arc::new(RwLock::new(HashMap::new())),
+            leader_node: Arc::new(RwLock::new(None)),
+            revisions: parking_lot::Mutex::new(HashMap::new()),
+            txn_waiters: parking_lot::Mutex::new(HashMap::new()),
+            apply_lock: Mutex::new(()),
+        }
+    }
+
+    /// Share the membership view committed evictions are applied to
+    pub fn with_membership(
+        mut self,
+        cluster_members: Arc<RwLock<HashMap<NodeId, ClusterMember>>>,
+        leader_node: Arc<RwLock<Option<NodeId>>>,
+    ) -> Self {
+        self.cluster_members = cluster_members;
+        self.leader_node = leader_node;
+        self
+    }
+
+    /// Apply a committed proposal
+    ///
+    /// Returns the published changes: none for membership changes, lease
+    /// grants and deletes of missing keys, one per attached key for a lease
+    /// revocation and one per write for a transaction.
+    pub async fn apply(&self, proposal: &Proposal) -> Result<Vec<StateChange>> {
+        let _guard = self.apply_lock.lock().await;
+
+        let change = match proposal {
+            Proposal::Set { key, value } => {
+                self.leases.detach(key);
+                self.apply_write(key, Some(value.as_slice())).await?
+            }
+            Proposal::Delete { key } => {
+                self.leases.detach(key);
+                self.apply_write(key, None).await?
+            }
+            Proposal::SetWithLease { key, value, lease } => {
+                // The lease may have been revoked since the write was proposed
+                if !self.leases.attach(*lease, key) {
+                    return Ok(Vec::new());
+                }
+                self.apply_write(key, Some(value.as_slice())).await?
+            }
+            Proposal::Campaign { key, value, lease } => {
+                // Campaigning again under the same lease keeps the original place in line
+                if !self.leases.exists(*lease) || self.storage.get(key).await?.is_some() {
+                    return Ok(Vec::new());
+                }
+                let candidacy = Candidacy {
+                    revision: self.subscriptions.revision() + 1,
+                    value: self.encryption.decrypt_data(value).await?,
+                };
+                let record = self.encryption.encrypt_data(&serde_json::to_vec(&candidacy)?).await?;
+                self.leases.attach(*lease, key);
+                self.apply_write(key, Some(record.as_slice())).await?
+            }
+            Proposal::GrantLease { id, ttl } => {
+                self.leases.grant(*id, *ttl, Instant::now());
+                None
+            }
+            Proposal::RevokeLease { id } => {
+                let mut changes = Vec::new();
+                for key in self.leases.revoke(*id).unwrap_or_default() {
+                    changes.extend(self.apply_write(&key, None).await?);
+                }
+                return Ok(changes);
+            }
+            Proposal::Txn { id, request } => {
+                let (response, changes) = self.apply_txn(request).await?;
+                if let Some(waiter) = self.txn_waiters.lock().remove(id) {
+                    let _ = waiter.send(response);
+                }
+                return Ok(changes);
+            }
+            Proposal::Reencrypt { key, from, to } => {
+                // A value written since it was read is already under the active key
+                if self.storage.get(key).await?.as_deref() == Some(from.as_slice()) {
+                    self.storage.set(key, to).await?;
+                }
+                None
+            }
+            Proposal::EvictMember { node_id, .. } => {
+                self.cluster_members.write().await.remove(node_id);
+                let mut leader = self.leader_node.write().await;
+                if *leader == Some(*node_id) {
+                    *leader = None;
+                }
+                None
+            }
+            Proposal::MembershipChange { .. } => None,
+        };
+        Ok(change.into_iter().collect())
+    }
+
+    /// Wait for the outcome of a transaction about to be proposed
+    pub(crate) fn expect_txn(&self, id: TxnId) -> oneshot::Receiver<TxnResponse> {
+        let (sender, receiver) = oneshot::channel();
+        self.txn_waiters.lock().insert(id, sender);
+        receiver
+    }
+
+    /// Stop waiting for a transaction whose proposal failed
+    pub(crate) fn forget_txn(&self, id: TxnId) {
+        self.txn_waiters.lock().remove(&id);
+    }
+
+    /// Revisions of a stored key
+    pub async fn key_revisions(&self, key: &str) -> Result<KeyRevisions> {
+        let _guard = self.apply_lock.lock().await;
+        let exists = self.storage.get(key).await?.is_some();
+        Ok(self.revisions_of(key, exists))
+    }
+
+    fn revisions_of(&self, key: &str, exists: bool) -> KeyRevisions {
+        match self.revisions.lock().get(key) {
+            Some(revisions) => *revisions,
+            None if exists => KeyRevisions { version: 1, ..KeyRevisions::default() },
+            None => KeyRevisions::default(),
+        }
+    }
+
+    async fn compare_holds(&self, compare: &Compare) -> Result<bool> {
+        let value = match self.storage.get(&compare.key).await? {
+            Some(data) => Some(self.encryption.decrypt_data(&data).await?),
+            None => None,
+        };
+        let revisions = self.revisions_of(&compare.key, value.is_some());
+        // Expected values are proposed encrypted like stored ones
+        let compare = match &compare.target {
+            CompareTarget::Value(expected) => Compare {
+                target: CompareTarget::Value(self.encryption.decrypt_data(expected).await?),
+                ..compare.clone()
+            },
+            _ => compare.clone(),
+        };
+        Ok(compare.evaluate(value.as_deref(), revisions))
+    }
+
+    async fn apply_txn(&self, request: &TxnRequest) -> Result<(TxnResponse, Vec<StateChange>)> {
+        let mut succeeded = true;
+        for compare in &request.compare {
+            if !self.compare_holds(compare).await? {
+                succeeded = false;
+                break;
+            }
+        }
+
+        let ops = if succeeded { &request.success } else { &request.failure };
+        let mut changes = Vec::new();
+        let mut responses = Vec::with_capacity(ops.len());
+        for op in ops {
+            match op {
+                TxnOp::Put { key, value } => {
+                    self.leases.detach(key);
+                    let change = self.apply_write(key, Some(value.as_slice())).await?;
+                    responses.push(TxnOpResponse::Put { revision: change.as_ref().map_or(0, |c| c.revision) });
+                    changes.extend(change);
+                }
+                TxnOp::Delete { key } => {
+                    self.leases.detach(key);
+                    let change = self.apply_write(key, None).await?;
+                    responses.push(TxnOpResponse::Delete { deleted: change.is_some() });
+                    changes.extend(change);
+                }
+                TxnOp::Get { key } => {
+                    let value = self.storage.get(key).await?;
+                    let revisions = self.revisions_of(key, value.is_some());
+                    // Values are handed back as stored; the proposer decrypts them
+                    responses.push(TxnOpResponse::Get { value, revisions });
+                }
+            }
+        }
+
+        let response = TxnResponse { succeeded, revision: self.subscriptions.revision(), responses };
+        Ok((response, changes))
+    }
+
+    async fn apply_write(&self, key: &str, new_value: Option<&[u8]>) -> Result<Option<StateChange>> {
+        let old_value = self.storage.get(key).await?;
+        match new_value {
+            Some(value) => self.storage.set(key, value).await?,
+            None if old_value.is_none() => return Ok(None),
+            None => {
+                self.storage.delete(key).await?;
+            }
+        }
+
+        self.sharding.forward(key, new_value).await;
+
+        // Watchers see plaintext, as returned by `StateManager::get`
+        let old_value = match old_value {
+            Some(data) => Some(self.encryption.decrypt_data(&data).await?),
+            None => None,
+        };
+        let new_value = match new_value {
+            Some(data) => Some(self.encryption.decrypt_data(data).await?),
+            None => None,
+        };
+        let stored_key = key.to_string();
+        let key = self.encryption.decrypt_key(key).await?;
+
+        if key == SHARD_MAP_KEY {
+            match new_value.as_deref().map(serde_json::from_slice::<ShardMap>) {
+                Some(Ok(map)) => {
+                    self.sharding.install(map);
+                }
+                Some(Err(e)) => tracing::warn!("Ignoring malformed shard map: {}", e),
+                None => {}
+            }
+        }
+        if let (Some(version), Some(wrapped)) = (encryption::data_key_version(&key), new_value.as_deref()) {
+            if let Err(e) = self.encryption.install_key(version, wrapped) {
+                tracing::warn!("Ignoring data key version {}: {}", version, e);
+            }
+        }
+        if key == KEY_VERSION_KEY {
+            match new_value.as_deref().map(serde_json::from_slice::<u32>) {
+                Some(Ok(version)) => {
+                    self.encryption.activate(version);
+                }
+                Some(Err(e)) => tracing::warn!("Ignoring malformed data key version: {}", e),
+                None => {}
+            }
+        }
+
+        let created = old_value.is_none();
+        let change = self.subscriptions.publish(key, old_value, new_value);
+        self.outbox.record(&change).await?;
+        self.record_revisions(stored_key, created, &change);
+        Ok(Some(change))
+    }
+
+    fn record_revisions(&self, key: String, created: bool, change: &StateChange) {
+        let mut revisions = self.revisions.lock();
+        if change.is_delete() {
+            revisions.remove(&key);
+            return;
+        }
+        let previous = revisions.get(&key).copied().filter(|_| !created);
+        let updated = match previous {
+            Some(previous) => KeyRevisions { mod_revision: change.revision, version: previous.version + 1, ..previous },
+            None if created => KeyRevisions { create_revision: change.revision, mod_revision: change.revision, version: 1 },
+            // Written before this node started; its history is unknown
+            None => KeyRevisions { create_revision: 0, mod_revision: change.revision, version: 2 },
+        };
+        revisions.insert(key, updated);
+    }
+
+    /// Size of every shard of `map`
+    pub async fn shard_sizes(&self, map: &ShardMap) -> Result<HashMap<ShardId, ShardSize>> {
+        let mut sizes: HashMap<ShardId, ShardSize> = HashMap::new();
+        for (key, value) in self.storage.scan_range("", None).await? {
+            let size = sizes.entry(map.shard_for(&key).id).or_default();
+            size.keys += 1;
+            size.bytes += key.len() + value.len();
+        }
+        Ok(sizes)
+    }
+
+    /// Median stored key of a shard, where it splits into halves
+    pub async fn split_key(&self, shard: &ShardInfo) -> Result<Option<String>> {
+        let keys = self.storage.scan_range(&shard.start, shard.end.as_deref()).await?;
+        Ok(keys.get(keys.len() / 2).filter(|_| keys.len() >= 2).map(|(key, _)| key.clone()))
+    }
+
+    /// Copy up to `limit` entries of the migrating shard from `start` to its new owner
+    ///
+    /// Applies wait while the batch is read and sent, so no forwarded write
+    /// is overtaken by an older copied value. Returns the bytes sent and
+    /// where the next batch starts, `None` once the range is done.
+    pub(crate) async fn copy_batch(&self, start: &str, end: Option<&str>, limit: usize) -> Result<(usize, Option<String>)> {
+        let _guard = self.apply_lock.lock().await;
+        let mut entries = self.storage.scan_range(start, end).await?;
+        let next = (entries.len() > limit).then(|| entries[limit].0.clone());
+        entries.truncate(limit);
+        let bytes = entries.iter().map(|(key, value)| key.len() + value.len()).sum();
+        if !entries.is_empty() {
+            self.sharding.send_batch(entries).await?;
+        }
+        Ok((bytes, next))
+    }
+
+    /// Keys and values in `[start, end)` as of `revision`, the latest if `None`
+    ///
+    /// Applies wait while the range is read so storage and the change journal
+    /// agree; the changes committed after `revision` are then undone. Returns
+    /// the revision read at with the entries.
+    pub async fn read_range(&self, start: &str, end: Option<&str>, revision: Option<u64>) -> Result<(u64, Vec<KeyValue>)> {
+        let _guard = self.apply_lock.lock().await;
+
+        let current = self.subscriptions.revision();
+        let revision = revision.unwrap_or(current);
+        if revision > current {
+            return Err(StateError::FutureRevision { revision, current });
+        }
+        let (_, changes) = self.subscriptions.changes_after(revision)?;
+
+        let encrypted_start = self.encryption.encrypt_key(start).await?;
+        let encrypted_end = match end {
+            Some(end) => Some(self.encryption.encrypt_key(end).await?),
+            None => None,
+        };
+        let stored = self.storage.scan_range(&encrypted_start, encrypted_end.as_deref()).await?;
+
+        let mut latest = Vec::with_capacity(stored.len());
+        for (key, value) in stored.into_iter().filter(|(key, _)| !outbox::is_outbox_key(key)) {
+            latest.push((self.encryption.decrypt_key(&key).await?, self.encryption.decrypt_data(&value).await?));
+        }
+        Ok((revision, range::rewind(latest, &changes, start, end)))
+    }
+}
@@ -1,75 +1,258 @@
 //! State change subscriptions and notifications
-//! Emergency stub implementation for Phase 1 stabilization
+//!
+//! Every committed change gets the next revision and is kept in a bounded
+//! journal before it is broadcast. Watchers track the last revision they
+//! saw: a watcher that falls behind the broadcast, or reconnects with a
+//! cursor, replays the missing revisions from the journal, so it receives
+//! each change under its prefix exactly once and in order. A cursor older
+//! than the journal fails with `RevisionCompacted` instead of silently
+//! skipping changes.
 
-use crate::error::Result;
+use crate::diagnostics::WatchReport;
+use crate::error::{Result, StateError};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
 use tokio::sync::broadcast;
 
-/// Event types for state changes
-#[derive(Debug, Clone)]
-pub enum StateEvent {
-    KeySet { key: String, value: Vec<u8> },
-    KeyDeleted { key: String },
+/// Changes kept for watchers that fall behind or reconnect
+pub const DEFAULT_HISTORY_SIZE: usize = 10_000;
+
+/// A committed change to one key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateChange {
+    /// Position of the change in the store's history, starting at 1
+    pub revision: u64,
+    pub key: String,
+    /// `None` if the key was created
+    pub old_value: Option<Vec<u8>>,
+    /// `None` if the key was deleted
+    pub new_value: Option<Vec<u8>>,
+}
+
+impl StateChange {
+    pub fn is_delete(&self) -> bool {
+        self.new_value.is_none()
+    }
 }
 
-/// State change notification (alias for StateEvent)
-pub type StateChange = StateEvent;
+/// Recent changes and the latest revision
+#[derive(Debug)]
+struct Journal {
+    revision: u64,
+    history: VecDeque<StateChange>,
+    capacity: usize,
+}
+
+impl Journal {
+    /// Changes from `revision` onwards; fails if some were already dropped
+    fn since(&self, revision: u64) -> Result<Vec<StateChange>> {
+        let oldest = self.history.front().map_or(self.revision + 1, |c| c.revision);
+        if revision < oldest && revision <= self.revision {
+            return Err(StateError::RevisionCompacted { revision, oldest });
+        }
+        Ok(self.history.iter().filter(|c| c.revision >= revision).cloned().collect())
+    }
+}
 
 /// Watch handle for state subscriptions
 #[derive(Debug)]
 pub struct WatchHandle {
-    receiver: broadcast::Receiver<StateEvent>,
+    prefix: String,
+    receiver: broadcast::Receiver<StateChange>,
+    journal: Arc<Mutex<Journal>>,
+    /// Replayed changes not yet returned
+    pending: VecDeque<StateChange>,
+    /// Next revision this watcher expects
+    next_revision: u64,
+    /// Open watches by prefix, shared with the subscription manager
+    watchers: Arc<Mutex<BTreeMap<String, usize>>>,
 }
 
-/// Subscription manager for state changes
-#[derive(Debug)]
-pub struct SubscriptionManager {
-    sender: broadcast::Sender<StateEvent>,
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        let mut watchers = self.watchers.lock();
+        if let Some(count) = watchers.get_mut(&self.prefix) {
+            *count -= 1;
+            if *count == 0 {
+                watchers.remove(&self.prefix);
+            }
+        }
+    }
 }
 
-impl Clone for SubscriptionManager {
-    fn clone(&self) -> Self {
-        Self {
-            sender: self.sender.clone(),
+impl WatchHandle {
+    /// Wait for the next change under the watched prefix
+    ///
+    /// Returns `Ok(None)` once the subscription manager is dropped, and
+    /// `RevisionCompacted` if the watcher fell so far behind that the
+    /// changes it missed are no longer in the journal.
+    pub async fn recv(&mut self) -> Result<Option<StateChange>> {
+        loop {
+            let change = match self.pending.pop_front() {
+                Some(change) => change,
+                None => match self.receiver.recv().await {
+                    Ok(change) => change,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Watch on '{}' lagged by {} changes, replaying", self.prefix, skipped);
+                        self.replay()?;
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(None),
+                },
+            };
+
+            if change.revision < self.next_revision {
+                // Already delivered from the journal
+                continue;
+            }
+            if change.revision > self.next_revision {
+                self.pending.push_front(change);
+                self.replay()?;
+                continue;
+            }
+
+            self.next_revision = change.revision + 1;
+            if change.key.starts_with(&self.prefix) {
+                return Ok(Some(change));
+            }
         }
     }
+
+    /// Last revision this watcher has seen; resume from `revision() + 1`
+    pub fn revision(&self) -> u64 {
+        self.next_revision - 1
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn replay(&mut self) -> Result<()> {
+        let missed = self.journal.lock().since(self.next_revision)?;
+        self.pending = missed.into();
+        Ok(())
+    }
+}
+
+/// Subscription manager for state changes
+#[derive(Debug, Clone)]
+pub struct SubscriptionManager {
+    sender: broadcast::Sender<StateChange>,
+    journal: Arc<Mutex<Journal>>,
+    watchers: Arc<Mutex<BTreeMap<String, usize>>>,
 }
 
 impl SubscriptionManager {
     /// Create new subscription manager
     pub fn new() -> Self {
+        Self::with_history(DEFAULT_HISTORY_SIZE)
+    }
+
+    /// Create a subscription manager keeping `history_size` changes for replay
+    pub fn with_history(history_size: usize) -> Self {
         let (sender, _) = broadcast::channel(1024);
-        Self { sender }
+        Self {
+            sender,
+            journal: Arc::new(Mutex::new(Journal {
+                revision: 0,
+                history: VecDeque::new(),
+                capacity: history_size.max(1),
+            })),
+            watchers: Arc::new(Mutex::new(BTreeMap::new())),
+        }
     }
 
-    /// Subscribe to state events
-    pub fn subscribe(&self) -> broadcast::Receiver<StateEvent> {
-        self.sender.subscribe()
+    /// Latest committed revision
+    pub fn revision(&self) -> u64 {
+        self.journal.lock().revision
     }
 
-    /// Notify subscribers of state change - stub implementation
-    pub async fn notify(&self, event: StateEvent) -> Result<()> {
-        // Stub: just send the event, ignore errors for now
-        let _ = self.sender.send(event);
-        Ok(())
+    /// Latest committed revision and the changes after `revision`, read together
+    pub fn changes_after(&self, revision: u64) -> Result<(u64, Vec<StateChange>)> {
+        let journal = self.journal.lock();
+        Ok((journal.revision, journal.since(revision + 1)?))
+    }
+
+    /// Record a committed change under the next revision and notify watchers
+    ///
+    /// Callers publish changes in commit order.
+    pub fn publish(&self, key: String, old_value: Option<Vec<u8>>, new_value: Option<Vec<u8>>) -> StateChange {
+        let mut journal = self.journal.lock();
+        journal.revision += 1;
+        let change = StateChange {
+            revision: journal.revision,
+            key,
+            old_value,
+            new_value,
+        };
+        journal.history.push_back(change.clone());
+        while journal.history.len() > journal.capacity {
+            journal.history.pop_front();
+        }
+        // Sent under the journal lock so watchers registering now see each
+        // change either in the journal or on the channel, never neither
+        let _ = self.sender.send(change.clone());
+        change
+    }
+
+    /// Open watches and the journal they replay from
+    pub fn watch_report(&self) -> WatchReport {
+        let by_prefix = self.watchers.lock().clone();
+        let journal = self.journal.lock();
+        WatchReport {
+            watchers: by_prefix.values().sum(),
+            by_prefix,
+            revision: journal.revision,
+            oldest_revision: journal.history.front().map(|change| change.revision),
+            journal_len: journal.history.len(),
+            journal_capacity: journal.capacity,
+        }
+    }
+
+    fn register_watch(&self, prefix: &str) -> Arc<Mutex<BTreeMap<String, usize>>> {
+        *self.watchers.lock().entry(prefix.to_string()).or_default() += 1;
+        self.watchers.clone()
     }
 
-    /// Start subscription manager - stub implementation
+    /// Start subscription manager
     pub async fn start(&self) -> Result<()> {
-        // Stub: no-op for now
         Ok(())
     }
 
-    /// Stop subscription manager - stub implementation  
+    /// Stop subscription manager
     pub async fn stop(&self) -> Result<()> {
-        // Stub: no-op for now
         Ok(())
     }
 
-    /// Watch for changes with prefix - stub implementation
-    pub async fn watch(&self, _prefix: &str) -> Result<WatchHandle> {
-        // Stub: return a new watch handle
+    /// Watch for changes under a prefix from now on
+    pub async fn watch(&self, prefix: &str) -> Result<WatchHandle> {
+        let journal = self.journal.lock();
         Ok(WatchHandle {
-            receiver: self.subscribe(),
+            prefix: prefix.to_string(),
+            receiver: self.sender.subscribe(),
+            journal: self.journal.clone(),
+            pending: VecDeque::new(),
+            next_revision: journal.revision + 1,
+            watchers: self.register_watch(prefix),
+        })
+    }
+
+    /// Watch for changes under a prefix starting at `revision`
+    ///
+    /// Clients reconnecting after seeing revision `r` pass `r + 1`.
+    pub async fn watch_from(&self, prefix: &str, revision: u64) -> Result<WatchHandle> {
+        let journal = self.journal.lock();
+        let next_revision = revision.max(1);
+        let pending = journal.since(next_revision)?.into();
+        Ok(WatchHandle {
+            prefix: prefix.to_string(),
+            receiver: self.sender.subscribe(),
+            journal: self.journal.clone(),
+            pending,
+            next_revision,
+            watchers: self.register_watch(prefix),
         })
     }
 }
@@ -78,4 +261,41 @@ impl Default for SubscriptionManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watch_resumes_from_revision() {
+        let subscriptions = SubscriptionManager::with_history(3);
+        let mut watch = subscriptions.watch("/pods/").await.unwrap();
+
+        subscriptions.publish("/pods/a".to_string(), None, Some(b"1".to_vec()));
+        subscriptions.publish("/nodes/x".to_string(), None, Some(b"n".to_vec()));
+        subscriptions.publish("/pods/a".to_string(), Some(b"1".to_vec()), None);
+
+        let created = watch.recv().await.unwrap().unwrap();
+        assert_eq!((created.revision, created.old_value), (1, None));
+        let deleted = watch.recv().await.unwrap().unwrap();
+        assert_eq!(deleted.revision, 3);
+        assert!(deleted.is_delete());
+        assert_eq!(watch.revision(), 3);
+
+        // A client that saw revision 1 reconnects and gets the rest in order
+        let mut resumed = subscriptions.watch_from("/pods/", 2).await.unwrap();
+        subscriptions.publish("/pods/b".to_string(), None, Some(b"2".to_vec()));
+        assert_eq!(resumed.recv().await.unwrap().unwrap().revision, 3);
+        assert_eq!(resumed.recv().await.unwrap().unwrap().revision, 4);
+        assert_eq!(subscriptions.watch_report().by_prefix.get("/pods/"), Some(&2));
+        drop(resumed);
+        assert_eq!(subscriptions.watch_report().watchers, 1);
+
+        // Revision 1 has been dropped from the three-change journal
+        assert!(matches!(
+            subscriptions.watch_from("/pods/", 1).await,
+            Err(StateError::RevisionCompacted { revision: 1, oldest: 2 })
+        ));
+    }
+}
@@ -1,7 +1,14 @@
 //! Transaction management for state operations  
 //! Emergency stub implementation for Phase 1 stabilization
+//!
+//! Conditional transactions are complete: a `TxnRequest` checks a list of
+//! comparisons against the store and runs its `success` operations if all of
+//! them hold, its `failure` operations otherwise. The comparisons and the
+//! chosen operations are applied as one committed proposal, so no other write
+//! lands between checking a key and writing it.
 
 use crate::error::Result;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
@@ -92,4 +99,191 @@ impl Transaction {
         let mut data = self.data.write().await;
         Ok(data.remove(key).is_some())
     }
-}
\ No newline at end of file
+}
+
+/// Identifies a conditional transaction while its outcome is awaited
+pub type TxnId = u64;
+
+/// How a key's current state is compared with the expected one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+}
+
+impl CompareOp {
+    fn holds<T: Ord + ?Sized>(self, actual: &T, expected: &T) -> bool {
+        match self {
+            CompareOp::Equal => actual == expected,
+            CompareOp::NotEqual => actual != expected,
+            CompareOp::Greater => actual > expected,
+            CompareOp::Less => actual < expected,
+        }
+    }
+}
+
+/// What of a key is compared
+///
+/// A missing key has version and revisions 0 and fails every value comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareTarget {
+    /// Writes since the key was created, 0 if it does not exist
+    Version(u64),
+    /// Revision the key was created at
+    CreateRevision(u64),
+    /// Revision the key was last written at
+    ModRevision(u64),
+    Value(Vec<u8>),
+}
+
+/// One condition of a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compare {
+    pub key: String,
+    pub op: CompareOp,
+    pub target: CompareTarget,
+}
+
+impl Compare {
+    pub fn version(key: impl Into<String>, op: CompareOp, version: u64) -> Self {
+        Self { key: key.into(), op, target: CompareTarget::Version(version) }
+    }
+
+    pub fn create_revision(key: impl Into<String>, op: CompareOp, revision: u64) -> Self {
+        Self { key: key.into(), op, target: CompareTarget::CreateRevision(revision) }
+    }
+
+    pub fn mod_revision(key: impl Into<String>, op: CompareOp, revision: u64) -> Self {
+        Self { key: key.into(), op, target: CompareTarget::ModRevision(revision) }
+    }
+
+    pub fn value(key: impl Into<String>, op: CompareOp, value: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into(), op, target: CompareTarget::Value(value.into()) }
+    }
+
+    /// Whether the condition holds for a key's current value and revisions
+    pub fn evaluate(&self, value: Option<&[u8]>, revisions: KeyRevisions) -> bool {
+        match &self.target {
+            CompareTarget::Version(expected) => self.op.holds(&revisions.version, expected),
+            CompareTarget::CreateRevision(expected) => self.op.holds(&revisions.create_revision, expected),
+            CompareTarget::ModRevision(expected) => self.op.holds(&revisions.mod_revision, expected),
+            CompareTarget::Value(expected) => value.is_some_and(|value| self.op.holds(value, expected.as_slice())),
+        }
+    }
+}
+
+/// Revisions of a key, all 0 if it does not exist
+///
+/// Keys written before this node started have version 1 and revisions 0
+/// until they are written again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRevisions {
+    pub create_revision: u64,
+    pub mod_revision: u64,
+    pub version: u64,
+}
+
+/// Operation run by a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxnOp {
+    Put { key: String, value: Vec<u8> },
+    Delete { key: String },
+    Get { key: String },
+}
+
+impl TxnOp {
+    pub fn put(key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        TxnOp::Put { key: key.into(), value: value.into() }
+    }
+
+    pub fn delete(key: impl Into<String>) -> Self {
+        TxnOp::Delete { key: key.into() }
+    }
+
+    pub fn get(key: impl Into<String>) -> Self {
+        TxnOp::Get { key: key.into() }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            TxnOp::Put { key, .. } | TxnOp::Delete { key } | TxnOp::Get { key } => key,
+        }
+    }
+}
+
+/// A conditional transaction: if every comparison holds, `success`, else `failure`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxnRequest {
+    pub compare: Vec<Compare>,
+    pub success: Vec<TxnOp>,
+    pub failure: Vec<TxnOp>,
+}
+
+impl TxnRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn when(mut self, compare: impl IntoIterator<Item = Compare>) -> Self {
+        self.compare.extend(compare);
+        self
+    }
+
+    pub fn and_then(mut self, ops: impl IntoIterator<Item = TxnOp>) -> Self {
+        self.success.extend(ops);
+        self
+    }
+
+    pub fn or_else(mut self, ops: impl IntoIterator<Item = TxnOp>) -> Self {
+        self.failure.extend(ops);
+        self
+    }
+}
+
+/// Result of one operation of a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxnOpResponse {
+    Put { revision: u64 },
+    Delete { deleted: bool },
+    Get { value: Option<Vec<u8>>, revisions: KeyRevisions },
+}
+
+/// Outcome of a conditional transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxnResponse {
+    /// Whether every comparison held and `success` ran
+    pub succeeded: bool,
+    /// Revision after the transaction applied
+    pub revision: u64,
+    /// One per operation that ran, in order
+    pub responses: Vec<TxnOpResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_evaluation() {
+        let revisions = KeyRevisions { create_revision: 3, mod_revision: 7, version: 2 };
+        assert!(Compare::version("k", CompareOp::Equal, 2).evaluate(Some(b"v"), revisions));
+        assert!(Compare::mod_revision("k", CompareOp::Less, 8).evaluate(Some(b"v"), revisions));
+        assert!(Compare::create_revision("k", CompareOp::Greater, 2).evaluate(Some(b"v"), revisions));
+        assert!(Compare::value("k", CompareOp::Equal, "v").evaluate(Some(b"v"), revisions));
+        assert!(Compare::value("k", CompareOp::Greater, "a").evaluate(Some(b"v"), revisions));
+
+        // A missing key has version 0 and no value to compare
+        let missing = KeyRevisions::default();
+        assert!(Compare::version("k", CompareOp::Equal, 0).evaluate(None, missing));
+        assert!(!Compare::value("k", CompareOp::NotEqual, "v").evaluate(None, missing));
+
+        let request = TxnRequest::new()
+            .when([Compare::version("k", CompareOp::Equal, 0)])
+            .and_then([TxnOp::put("k", "v")])
+            .or_else([TxnOp::get("k")]);
+        assert_eq!(request.success[0].key(), "k");
+        assert_eq!(request.failure, vec![TxnOp::Get { key: "k".to_string() }]);
+    }
+}
@@ -0,0 +1,83 @@
+//! Trust bundle distribution
+//!
+//! The cluster CA's trust bundle is kept under one key in the replicated
+//! store, so every node and every workload that can read the store sees the
+//! same roots. Publishing compares and swaps against the stored bundle and
+//! refuses anything not newer than it, so two rotation drivers cannot undo
+//! each other's phases. Nodes follow the key with a watch and apply each new
+//! bundle to their certificate manager; connections made afterwards trust
+//! the new roots.
+
+use crate::error::{Result, StateError};
+use nexus_transport::{CertificateManager, TrustBundle};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Key holding the current trust bundle
+pub const TRUST_BUNDLE_KEY: &str = "/_trust/bundle";
+
+/// Decode a stored trust bundle
+pub(crate) fn decode(value: &[u8]) -> Result<TrustBundle> {
+    Ok(serde_json::from_slice(value)?)
+}
+
+/// Check that a bundle may replace the stored one
+pub(crate) fn check_newer(current: Option<&TrustBundle>, bundle: &TrustBundle) -> Result<()> {
+    match current {
+        Some(current) if current.version >= bundle.version => Err(StateError::Configuration {
+            message: format!(
+                "Trust bundle version {} is not newer than the published version {}",
+                bundle.version, current.version
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Apply a bundle to a certificate manager
+pub(crate) fn apply(certificates: &CertificateManager, bundle: &TrustBundle) -> Result<bool> {
+    certificates.apply_trust_bundle(bundle).map_err(|e| StateError::Transport { message: e.to_string() })
+}
+
+/// Keeps a certificate manager's roots in step with the published bundle
+pub struct TrustFollower {
+    pub(crate) certificates: Arc<CertificateManager>,
+    pub(crate) task: JoinHandle<()>,
+}
+
+impl TrustFollower {
+    /// Version of the bundle last applied
+    pub fn version(&self) -> u64 {
+        self.certificates.trust_version()
+    }
+}
+
+impl Drop for TrustFollower {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nexus_transport::RotationPhase;
+
+    #[test]
+    fn test_only_newer_bundles_replace_the_published_one() {
+        let bundle = |version| TrustBundle {
+            version,
+            phase: RotationPhase::Stable,
+            roots: vec!["-----BEGIN CERTIFICATE-----".to_string()],
+            cross_signed: None,
+            next_phase_after: 0,
+        };
+        assert!(check_newer(None, &bundle(1)).is_ok());
+        assert!(check_newer(Some(&bundle(1)), &bundle(2)).is_ok());
+        assert!(check_newer(Some(&bundle(2)), &bundle(2)).is_err());
+        assert!(check_newer(Some(&bundle(3)), &bundle(2)).is_err());
+
+        let stored = serde_json::to_vec(&bundle(4)).unwrap();
+        assert_eq!(decode(&stored).unwrap(), bundle(4));
+    }
+}
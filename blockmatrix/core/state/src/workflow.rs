@@ -0,0 +1,493 @@
+//! Durable workflows for multi-step orchestration operations
+//!
+//! Draining a node, upgrading a cluster or deploying an application with its
+//! dependencies take several steps, and any of them can fail or be cut short
+//! by a restart. A workflow runs a registered definition's steps in order and
+//! records its progress under `/_workflows/<id>` after every step, so an
+//! engine that starts again resumes each unfinished workflow where it
+//! stopped. A step that fails is retried; once its attempts run out, or the
+//! workflow is cancelled, the completed steps are compensated in reverse
+//! order. A step cut short by a restart runs again, so steps must be
+//! idempotent. Records are written with compare-and-swap, and an engine that
+//! finds a record changed under it stops driving that workflow, so only one
+//! engine (normally the elected leader's) should resume workflows.
+
+use crate::error::{Result, StateError};
+use crate::StateManager;
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Prefix under which workflow records are kept
+pub const WORKFLOW_PREFIX: &str = "/_workflows/";
+
+/// Prefix under which cancellation requests are kept
+const CANCEL_PREFIX: &str = "/_workflow_cancel/";
+
+fn record_key(id: &str) -> String {
+    format!("{}{}", WORKFLOW_PREFIX, id)
+}
+
+fn cancel_key(id: &str) -> String {
+    format!("{}{}", CANCEL_PREFIX, id)
+}
+
+/// Where workflow records are persisted
+pub trait WorkflowStore: Send + Sync {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>>;
+
+    /// Replace a key's value only if it still holds `expected`
+    fn compare_and_swap<'a>(&'a self, key: &'a str, expected: Option<&'a [u8]>, new: &'a [u8]) -> BoxFuture<'a, Result<bool>>;
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<String>>>;
+}
+
+impl WorkflowStore for StateManager {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+        Box::pin(StateManager::get(self, key))
+    }
+
+    fn compare_and_swap<'a>(&'a self, key: &'a str, expected: Option<&'a [u8]>, new: &'a [u8]) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(StateManager::compare_and_swap(self, key, expected, new))
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(StateManager::list(self, prefix, None))
+    }
+}
+
+/// Input and intermediate results shared by a workflow's steps
+///
+/// Everything here is persisted with the workflow, so a resumed workflow
+/// sees what its earlier steps recorded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowContext {
+    pub workflow_id: String,
+    pub input: serde_json::Value,
+    data: HashMap<String, serde_json::Value>,
+}
+
+impl WorkflowContext {
+    /// The workflow's input as a typed value
+    pub fn input_as<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_value(self.input.clone())?)
+    }
+
+    /// A value recorded by an earlier step
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.data.get(key).and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Record a value for later steps and compensations
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        self.data.insert(key.to_string(), serde_json::to_value(value)?);
+        Ok(())
+    }
+}
+
+/// One step of a workflow
+pub trait WorkflowStep: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Perform the step; it may run more than once, so it must be idempotent
+    fn execute<'a>(&'a self, context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// Undo a completed step while the workflow is being rolled back
+    fn compensate<'a>(&'a self, _context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// The steps of a kind of workflow
+#[derive(Clone)]
+pub struct WorkflowDefinition {
+    pub kind: String,
+    pub steps: Vec<Arc<dyn WorkflowStep>>,
+    /// Attempts of a step or compensation before giving up on it
+    pub max_attempts: u32,
+    /// Wait between attempts
+    pub retry_backoff: Duration,
+}
+
+impl WorkflowDefinition {
+    pub fn new(kind: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            steps: Vec::new(),
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(1),
+        }
+    }
+
+    pub fn step(mut self, step: impl WorkflowStep + 'static) -> Self {
+        self.steps.push(Arc::new(step));
+        self
+    }
+
+    pub fn with_retries(mut self, max_attempts: u32, retry_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_backoff = retry_backoff;
+        self
+    }
+}
+
+/// Where a workflow is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkflowState {
+    /// Running steps forward
+    Running,
+    /// Undoing completed steps after a failure or cancellation
+    Compensating,
+    /// Every step completed
+    Completed,
+    /// Every completed step was undone
+    Compensated,
+    /// A compensation failed; the operator has to clean up
+    Failed,
+}
+
+impl WorkflowState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Compensated | Self::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Pending,
+    Completed,
+    Failed,
+    Compensated,
+}
+
+/// Progress of one step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub name: String,
+    pub status: StepStatus,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// Persisted progress of a workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRecord {
+    pub id: String,
+    pub kind: String,
+    pub state: WorkflowState,
+    pub steps: Vec<StepRecord>,
+    pub context: WorkflowContext,
+    /// Why the workflow is being or was rolled back
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl WorkflowRecord {
+    /// Index of the next step to run forward
+    fn next_step(&self) -> Option<usize> {
+        self.steps.iter().position(|step| step.status != StepStatus::Completed)
+    }
+
+    /// Index of the last completed step, the next one to compensate
+    fn next_compensation(&self) -> Option<usize> {
+        self.steps.iter().rposition(|step| step.status == StepStatus::Completed)
+    }
+}
+
+/// Runs workflows and resumes the ones left unfinished
+pub struct WorkflowEngine {
+    store: Arc<dyn WorkflowStore>,
+    definitions: RwLock<HashMap<String, Arc<WorkflowDefinition>>>,
+}
+
+impl WorkflowEngine {
+    pub fn new(store: Arc<dyn WorkflowStore>) -> Self {
+        Self {
+            store,
+            definitions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Make a kind of workflow available to `run` and `resume_all`
+    pub fn register(&self, definition: WorkflowDefinition) {
+        self.definitions.write().insert(definition.kind.clone(), Arc::new(definition));
+    }
+
+    /// Start a workflow and drive it until it finishes
+    pub async fn run(&self, kind: &str, input: serde_json::Value) -> Result<WorkflowRecord> {
+        let definition = self.definition(kind)?;
+        let id = new_workflow_id();
+        let now = unix_now();
+        let record = WorkflowRecord {
+            id: id.clone(),
+            kind: kind.to_string(),
+            state: WorkflowState::Running,
+            steps: definition.steps
+                .iter()
+                .map(|step| StepRecord { name: step.name().to_string(), status: StepStatus::Pending, attempts: 0, error: None })
+                .collect(),
+            context: WorkflowContext { workflow_id: id, input, data: HashMap::new() },
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let stored = self.save(&record, None).await?;
+        self.drive(&definition, record, stored).await
+    }
+
+    /// Drive every unfinished workflow to the end, after a restart or takeover
+    pub async fn resume_all(&self) -> Result<Vec<WorkflowRecord>> {
+        let mut finished = Vec::new();
+        for (record, stored) in self.load_all().await? {
+            if record.state.is_finished() {
+                continue;
+            }
+            let definition = self.definition(&record.kind)?;
+            tracing::info!("Resuming {} workflow {}", record.kind, record.id);
+            finished.push(self.drive(&definition, record, stored).await?);
+        }
+        Ok(finished)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<WorkflowRecord>> {
+        match self.store.get(&record_key(id)).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<WorkflowRecord>> {
+        Ok(self.load_all().await?.into_iter().map(|(record, _)| record).collect())
+    }
+
+    /// Ask a running workflow to stop and roll back before its next step
+    pub async fn cancel(&self, id: &str) -> Result<()> {
+        if self.get(id).await?.is_none() {
+            return Err(StateError::KeyNotFound { key: record_key(id) });
+        }
+        self.store.compare_and_swap(&cancel_key(id), None, b"cancel").await?;
+        Ok(())
+    }
+
+    fn definition(&self, kind: &str) -> Result<Arc<WorkflowDefinition>> {
+        self.definitions.read().get(kind).cloned().ok_or_else(|| StateError::Configuration {
+            message: format!("no workflow of kind '{}' is registered", kind),
+        })
+    }
+
+    async fn load_all(&self) -> Result<Vec<(WorkflowRecord, Vec<u8>)>> {
+        let mut records = Vec::new();
+        for key in self.store.list(WORKFLOW_PREFIX).await? {
+            if let Some(bytes) = self.store.get(&key).await? {
+                records.push((serde_json::from_slice(&bytes)?, bytes));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Persist a record over the version this engine last wrote
+    async fn save(&self, record: &WorkflowRecord, stored: Option<&[u8]>) -> Result<Vec<u8>> {
+        let key = record_key(&record.id);
+        let bytes = serde_json::to_vec(record)?;
+        if !self.store.compare_and_swap(&key, stored, &bytes).await? {
+            return Err(StateError::TransactionConflict { key });
+        }
+        Ok(bytes)
+    }
+
+    async fn drive(&self, definition: &WorkflowDefinition, mut record: WorkflowRecord, mut stored: Vec<u8>) -> Result<WorkflowRecord> {
+        loop {
+            match record.state {
+                WorkflowState::Running => {
+                    if self.store.get(&cancel_key(&record.id)).await?.is_some() {
+                        record.state = WorkflowState::Compensating;
+                        record.error = Some("cancelled".to_string());
+                    } else if let Some(index) = record.next_step() {
+                        let step = step_of(definition, &record, index)?;
+                        let result = step.execute(&mut record.context).await;
+                        let progress = &mut record.steps[index];
+                        progress.attempts += 1;
+                        match result {
+                            Ok(()) => {
+                                progress.status = StepStatus::Completed;
+                                progress.error = None;
+                            }
+                            Err(e) => {
+                                progress.error = Some(e.to_string());
+                                if progress.attempts >= definition.max_attempts {
+                                    tracing::warn!("Workflow {} step {} failed, rolling back: {}", record.id, progress.name, e);
+                                    progress.status = StepStatus::Failed;
+                                    record.error = Some(format!("step {} failed: {}", progress.name, e));
+                                    record.state = WorkflowState::Compensating;
+                                } else {
+                                    tokio::time::sleep(definition.retry_backoff).await;
+                                }
+                            }
+                        }
+                    } else {
+                        record.state = WorkflowState::Completed;
+                    }
+                }
+                WorkflowState::Compensating => match record.next_compensation() {
+                    Some(index) => {
+                        let step = step_of(definition, &record, index)?;
+                        let mut attempts = 0;
+                        let result = loop {
+                            attempts += 1;
+                            match step.compensate(&mut record.context).await {
+                                Err(_) if attempts < definition.max_attempts => tokio::time::sleep(definition.retry_backoff).await,
+                                result => break result,
+                            }
+                        };
+                        match result {
+                            Ok(()) => record.steps[index].status = StepStatus::Compensated,
+                            Err(e) => {
+                                tracing::error!("Workflow {} could not compensate step {}: {}", record.id, step.name(), e);
+                                record.steps[index].error = Some(e.to_string());
+                                record.error = Some(format!("compensating step {} failed: {}", step.name(), e));
+                                record.state = WorkflowState::Failed;
+                            }
+                        }
+                    }
+                    None => record.state = WorkflowState::Compensated,
+                },
+                WorkflowState::Completed | WorkflowState::Compensated | WorkflowState::Failed => return Ok(record),
+            }
+            record.updated_at = unix_now();
+            stored = self.save(&record, Some(&stored)).await?;
+        }
+    }
+}
+
+fn step_of<'a>(definition: &'a WorkflowDefinition, record: &WorkflowRecord, index: usize) -> Result<&'a Arc<dyn WorkflowStep>> {
+    definition.steps.get(index).ok_or_else(|| StateError::Configuration {
+        message: format!("workflow {} has more steps than its {} definition", record.id, record.kind),
+    })
+}
+
+fn new_workflow_id() -> String {
+    format!("{:016x}{:016x}", rand::random::<u64>(), rand::random::<u64>())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<BTreeMap<String, Vec<u8>>>);
+
+    impl WorkflowStore for MemoryStore {
+        fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+            Box::pin(async move { Ok(self.0.lock().get(key).cloned()) })
+        }
+
+        fn compare_and_swap<'a>(&'a self, key: &'a str, expected: Option<&'a [u8]>, new: &'a [u8]) -> BoxFuture<'a, Result<bool>> {
+            Box::pin(async move {
+                let mut map = self.0.lock();
+                if map.get(key).map(Vec::as_slice) != expected {
+                    return Ok(false);
+                }
+                map.insert(key.to_string(), new.to_vec());
+                Ok(true)
+            })
+        }
+
+        fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+            Box::pin(async move { Ok(self.0.lock().keys().filter(|key| key.starts_with(prefix)).cloned().collect()) })
+        }
+    }
+
+    /// Appends its name to a shared log, and fails while `fail` is set
+    struct Logged {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+        fail: Arc<Mutex<bool>>,
+    }
+
+    impl WorkflowStep for Logged {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn execute<'a>(&'a self, context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async move {
+                if *self.fail.lock() {
+                    anyhow::bail!("{} is unavailable", self.name);
+                }
+                self.log.lock().push(format!("do {}", self.name));
+                context.set(self.name, &true)?;
+                Ok(())
+            })
+        }
+
+        fn compensate<'a>(&'a self, context: &'a mut WorkflowContext) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async move {
+                assert_eq!(context.get::<bool>(self.name), Some(true));
+                self.log.lock().push(format!("undo {}", self.name));
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compensates_in_reverse_and_resumes() {
+        let store = Arc::new(MemoryStore::default());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let flaky = Arc::new(Mutex::new(true));
+        let definition = |flaky: &Arc<Mutex<bool>>| {
+            let step = |name, fail: &Arc<Mutex<bool>>| Logged { name, log: log.clone(), fail: fail.clone() };
+            let ok = Arc::new(Mutex::new(false));
+            WorkflowDefinition::new("deploy")
+                .step(step("database", &ok))
+                .step(step("cache", &ok))
+                .step(step("frontend", flaky))
+                .with_retries(2, Duration::ZERO)
+        };
+
+        let engine = WorkflowEngine::new(store.clone());
+        engine.register(definition(&flaky));
+        let record = engine.run("deploy", serde_json::json!({ "app": "shop" })).await.unwrap();
+        assert_eq!(record.state, WorkflowState::Compensated);
+        assert_eq!(record.steps[2].attempts, 2);
+        assert!(record.error.unwrap().contains("frontend"));
+        assert_eq!(*log.lock(), ["do database", "do cache", "undo cache", "undo database"]);
+
+        // A workflow left mid-way by a restarted engine picks up at its next step
+        log.lock().clear();
+        *flaky.lock() = false;
+        let mut interrupted = engine.get(&record.id).await.unwrap().unwrap();
+        interrupted.id = "interrupted".to_string();
+        interrupted.state = WorkflowState::Running;
+        interrupted.error = None;
+        interrupted.steps[0].status = StepStatus::Completed;
+        interrupted.steps[1].status = StepStatus::Pending;
+        interrupted.steps[2].status = StepStatus::Pending;
+        let bytes = serde_json::to_vec(&interrupted).unwrap();
+        assert!(store.compare_and_swap(&record_key("interrupted"), None, &bytes).await.unwrap());
+
+        let restarted = WorkflowEngine::new(store.clone());
+        restarted.register(definition(&flaky));
+        let resumed = restarted.resume_all().await.unwrap();
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(resumed[0].state, WorkflowState::Completed);
+        assert_eq!(*log.lock(), ["do cache", "do frontend"]);
+        assert_eq!(restarted.get("interrupted").await.unwrap().unwrap().state, WorkflowState::Completed);
+
+        // Finished workflows stay listed; unknown ones cannot be cancelled
+        assert!(restarted.cancel("missing").await.is_err());
+        assert_eq!(restarted.list().await.unwrap().len(), 2);
+    }
+}
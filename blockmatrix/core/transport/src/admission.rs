@@ -0,0 +1,191 @@
+//! Admission control and load shedding for the server path
+//!
+//! Incoming connections and requests wait in bounded queues instead of
+//! growing without limit. A connection still in its handshake holds an
+//! accept slot, and connections beyond the limit are refused. Requests are
+//! shed by priority as the request queue fills: best-effort traffic first,
+//! then normal traffic, leaving the last slots to critical traffic. A shed
+//! request is answered with an `Unavailable` message, the transport's 503,
+//! so the caller fails fast and can back off instead of timing out.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Request priority, lowest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RequestPriority {
+    /// Shed first under load
+    BestEffort,
+    #[default]
+    Normal,
+    /// Shed only when the queue is full
+    Critical,
+}
+
+/// Admission control configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionConfig {
+    /// Connections allowed in their handshake at once
+    pub max_pending_connections: usize,
+
+    /// Received requests waiting for a handler
+    pub request_queue_capacity: usize,
+
+    /// Queue fill ratio at which best-effort requests are shed
+    pub best_effort_limit: f64,
+
+    /// Queue fill ratio at which normal requests are shed
+    pub normal_limit: f64,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            max_pending_connections: 256,
+            request_queue_capacity: 4096,
+            best_effort_limit: 0.5,
+            normal_limit: 0.9,
+        }
+    }
+}
+
+impl AdmissionConfig {
+    /// Validate configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_pending_connections == 0 || self.request_queue_capacity == 0 {
+            return Err("Admission queue sizes must be greater than zero".to_string());
+        }
+        if !(0.0 < self.best_effort_limit && self.best_effort_limit <= self.normal_limit && self.normal_limit <= 1.0) {
+            return Err("Admission limits must satisfy 0 < best_effort_limit <= normal_limit <= 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Queue depth at which requests of `priority` are shed
+    pub fn shed_depth(&self, priority: RequestPriority) -> usize {
+        let capacity = self.request_queue_capacity;
+        match priority {
+            RequestPriority::BestEffort => (capacity as f64 * self.best_effort_limit).ceil() as usize,
+            RequestPriority::Normal => (capacity as f64 * self.normal_limit).ceil() as usize,
+            RequestPriority::Critical => capacity,
+        }
+    }
+}
+
+/// Admission counters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdmissionStats {
+    pub admitted: u64,
+    pub shed_best_effort: u64,
+    pub shed_normal: u64,
+    pub shed_critical: u64,
+    pub refused_connections: u64,
+    pub pending_connections: usize,
+}
+
+/// Decides which connections and requests the server takes on
+#[derive(Debug)]
+pub struct AdmissionController {
+    config: AdmissionConfig,
+    pending_connections: AtomicUsize,
+    admitted: AtomicU64,
+    shed: [AtomicU64; 3],
+    refused_connections: AtomicU64,
+}
+
+impl AdmissionController {
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self {
+            config,
+            pending_connections: AtomicUsize::new(0),
+            admitted: AtomicU64::new(0),
+            shed: Default::default(),
+            refused_connections: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &AdmissionConfig {
+        &self.config
+    }
+
+    /// Take an accept slot for a new connection, or `None` to refuse it
+    pub fn try_accept(self: &Arc<Self>) -> Option<AcceptPermit> {
+        let admitted = self
+            .pending_connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                (pending < self.config.max_pending_connections).then_some(pending + 1)
+            })
+            .is_ok();
+        if !admitted {
+            self.refused_connections.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(AcceptPermit { controller: self.clone() })
+    }
+
+    /// Whether a request may join a queue already holding `queued` requests
+    pub fn admit(&self, priority: RequestPriority, queued: usize) -> bool {
+        if queued < self.config.shed_depth(priority) {
+            self.admitted.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        self.shed[priority as usize].fetch_add(1, Ordering::Relaxed);
+        false
+    }
+
+    pub fn stats(&self) -> AdmissionStats {
+        AdmissionStats {
+            admitted: self.admitted.load(Ordering::Relaxed),
+            shed_best_effort: self.shed[RequestPriority::BestEffort as usize].load(Ordering::Relaxed),
+            shed_normal: self.shed[RequestPriority::Normal as usize].load(Ordering::Relaxed),
+            shed_critical: self.shed[RequestPriority::Critical as usize].load(Ordering::Relaxed),
+            refused_connections: self.refused_connections.load(Ordering::Relaxed),
+            pending_connections: self.pending_connections.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// An accept slot, released when the handshake finishes or fails
+#[derive(Debug)]
+pub struct AcceptPermit {
+    controller: Arc<AdmissionController>,
+}
+
+impl Drop for AcceptPermit {
+    fn drop(&mut self) {
+        self.controller.pending_connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheds_best_effort_first() {
+        let controller = Arc::new(AdmissionController::new(AdmissionConfig {
+            max_pending_connections: 1,
+            request_queue_capacity: 10,
+            best_effort_limit: 0.5,
+            normal_limit: 0.8,
+        }));
+        assert!(controller.config().validate().is_ok());
+
+        assert!(controller.admit(RequestPriority::BestEffort, 4));
+        assert!(!controller.admit(RequestPriority::BestEffort, 5));
+        assert!(controller.admit(RequestPriority::Normal, 7));
+        assert!(!controller.admit(RequestPriority::Normal, 8));
+        assert!(controller.admit(RequestPriority::Critical, 9));
+        assert!(!controller.admit(RequestPriority::Critical, 10));
+
+        let permit = controller.try_accept().unwrap();
+        assert!(controller.try_accept().is_none());
+        drop(permit);
+        assert!(controller.try_accept().is_some());
+
+        let stats = controller.stats();
+        assert_eq!((stats.admitted, stats.shed_best_effort, stats.shed_normal, stats.shed_critical), (3, 1, 1, 1));
+        assert_eq!(stats.refused_connections, 1);
+    }
+}
@@ -0,0 +1,348 @@
+//! Cluster certificate authority and its rotation
+//!
+//! Node certificates are issued by a cluster CA, and every node trusts the
+//! roots in the current trust bundle. Replacing the CA goes through three
+//! phases so that no connection fails along the way. Staging issues a new CA
+//! and adds it to the bundle while certificates are still issued by the old
+//! one. Cross-signing switches issuance to the new CA and hands out, with
+//! each certificate, the new CA signed by the old one, so nodes that have
+//! not yet picked up the bundle can still verify the chain. Retiring drops
+//! the old CA from the bundle; by then every node must hold a certificate
+//! from the new one. Each phase lasts at least the transition window, which
+//! should cover bundle distribution and the re-issuing of every node's
+//! certificate. The CA keys stay with the `CaRotation` that generated them;
+//! bundles carry only certificates.
+
+use crate::certificate::certificate_params;
+use crate::{Result, TransportError};
+use nexus_shared::NodeSigner;
+use rcgen::{BasicConstraints, Certificate, IsCa, KeyUsagePurpose};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where a CA rotation stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationPhase {
+    /// One CA, trusted and issuing
+    Stable,
+    /// Both CAs trusted, the old one issuing
+    Staged,
+    /// Both CAs trusted, the new one issuing and cross-signed by the old
+    CrossSigned,
+}
+
+/// The CA certificates nodes and workloads should trust
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustBundle {
+    /// Increases with every change, so stale copies can be ignored
+    pub version: u64,
+    pub phase: RotationPhase,
+    /// PEM certificates of the trusted CAs
+    pub roots: Vec<String>,
+    /// PEM of the new CA signed by the old one while cross-signed
+    pub cross_signed: Option<String>,
+    /// Earliest time the next phase may begin, seconds since the epoch
+    pub next_phase_after: u64,
+}
+
+impl TrustBundle {
+    /// DER encodings of the trusted CAs
+    pub fn root_certificates(&self) -> Result<Vec<Vec<u8>>> {
+        let mut roots = Vec::new();
+        for pem in &self.roots {
+            let certs = rustls_pemfile::certs(&mut pem.as_bytes()).map_err(|e| TransportError::Certificate {
+                message: format!("Failed to parse CA certificates: {}", e),
+            })?;
+            roots.extend(certs);
+        }
+        if roots.is_empty() {
+            return Err(TransportError::Certificate { message: "Trust bundle has no CA certificates".to_string() });
+        }
+        Ok(roots)
+    }
+}
+
+/// Private key of an issued certificate
+#[derive(Clone)]
+pub enum CertificateKey {
+    /// DER PKCS#8 private key, generated with the certificate
+    Der(Vec<u8>),
+    /// The node's own key, which never leaves its signer
+    Signer(Arc<dyn NodeSigner>),
+}
+
+/// A certificate and key issued by the cluster CA
+#[derive(Clone)]
+pub struct IssuedCertificate {
+    /// Generation of the CA that signed it
+    pub ca_generation: u64,
+    /// DER certificates, leaf first
+    pub chain: Vec<Vec<u8>>,
+    pub key: CertificateKey,
+}
+
+/// One generation of the cluster CA
+pub struct ClusterCa {
+    generation: u64,
+    cert: Certificate,
+    /// Serialised once; each serialisation carries a fresh signature
+    pem: String,
+}
+
+impl ClusterCa {
+    /// Generate a self-signed CA
+    pub fn generate(cluster: &str, generation: u64, validity_days: u32) -> Result<Self> {
+        let mut params = certificate_params(&format!("{} CA {}", cluster, generation), validity_days);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign, KeyUsagePurpose::DigitalSignature];
+        let cert = Certificate::from_params(params).map_err(|e| TransportError::Certificate {
+            message: format!("Failed to generate cluster CA: {}", e),
+        })?;
+        let pem = cert.serialize_pem().map_err(|e| TransportError::Certificate {
+            message: format!("Failed to serialize CA certificate: {}", e),
+        })?;
+        Ok(Self { generation, cert, pem })
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// PEM of the self-signed CA certificate
+    pub fn certificate_pem(&self) -> &str {
+        &self.pem
+    }
+
+    /// This CA's certificate signed by another CA
+    fn signed_by(&self, signer: &ClusterCa) -> Result<Vec<u8>> {
+        self.cert.serialize_der_with_signer(&signer.cert).map_err(|e| TransportError::Certificate {
+            message: format!("Failed to cross-sign CA {}: {}", self.generation, e),
+        })
+    }
+
+    /// Issue a node certificate with a freshly generated key
+    pub fn issue(&self, subject_name: &str, validity_days: u32) -> Result<IssuedCertificate> {
+        self.issue_with(subject_name, validity_days, None)
+    }
+
+    /// Issue a node certificate for the key held by a node's signer
+    pub fn issue_for(&self, subject_name: &str, validity_days: u32, signer: Arc<dyn NodeSigner>) -> Result<IssuedCertificate> {
+        self.issue_with(subject_name, validity_days, Some(signer))
+    }
+
+    fn issue_with(&self, subject_name: &str, validity_days: u32, signer: Option<Arc<dyn NodeSigner>>) -> Result<IssuedCertificate> {
+        let mut params = certificate_params(subject_name, validity_days);
+        params.subject_alt_names = vec![rcgen::SanType::DnsName(subject_name.to_string())];
+        if let Some(signer) = &signer {
+            params.alg = crate::signer::rcgen_algorithm(signer.algorithm());
+            params.key_pair = Some(crate::signer::key_pair(signer.clone())?);
+        }
+        let cert = Certificate::from_params(params).map_err(|e| TransportError::Certificate {
+            message: format!("Failed to generate certificate for {}: {}", subject_name, e),
+        })?;
+        let leaf = cert.serialize_der_with_signer(&self.cert).map_err(|e| TransportError::Certificate {
+            message: format!("Failed to sign certificate for {}: {}", subject_name, e),
+        })?;
+        let key = match signer {
+            Some(signer) => CertificateKey::Signer(signer),
+            None => CertificateKey::Der(cert.serialize_private_key_der()),
+        };
+        Ok(IssuedCertificate { ca_generation: self.generation, chain: vec![leaf], key })
+    }
+}
+
+/// Drives the cluster CA through a rotation
+pub struct CaRotation {
+    cluster: String,
+    validity_days: u32,
+    transition_window: Duration,
+    active: ClusterCa,
+    next: Option<ClusterCa>,
+    /// DER of the next CA signed by the active one, while cross-signed
+    cross_signed: Option<Vec<u8>>,
+    phase: RotationPhase,
+    phase_started: SystemTime,
+    version: u64,
+}
+
+impl CaRotation {
+    /// Start with a freshly generated CA
+    pub fn new(cluster: &str, validity_days: u32, transition_window: Duration) -> Result<Self> {
+        Ok(Self {
+            cluster: cluster.to_string(),
+            validity_days,
+            transition_window,
+            active: ClusterCa::generate(cluster, 1, validity_days)?,
+            next: None,
+            cross_signed: None,
+            phase: RotationPhase::Stable,
+            phase_started: SystemTime::now(),
+            version: 1,
+        })
+    }
+
+    pub fn phase(&self) -> RotationPhase {
+        self.phase
+    }
+
+    /// Generation of the CA issuing certificates
+    pub fn issuing_generation(&self) -> u64 {
+        self.issuer().generation
+    }
+
+    fn issuer(&self) -> &ClusterCa {
+        match (self.phase, &self.next) {
+            (RotationPhase::CrossSigned, Some(next)) => next,
+            _ => &self.active,
+        }
+    }
+
+    /// Issue a node certificate from the issuing CA
+    ///
+    /// While cross-signed the chain carries the new CA signed by the old
+    /// one, so peers that trust only the old CA accept it.
+    pub fn issue(&self, subject_name: &str, validity_days: u32) -> Result<IssuedCertificate> {
+        Ok(self.with_cross_signed(self.issuer().issue(subject_name, validity_days)?))
+    }
+
+    /// Issue a node certificate for the key held by a node's signer
+    pub fn issue_for(&self, subject_name: &str, validity_days: u32, signer: Arc<dyn NodeSigner>) -> Result<IssuedCertificate> {
+        Ok(self.with_cross_signed(self.issuer().issue_for(subject_name, validity_days, signer)?))
+    }
+
+    fn with_cross_signed(&self, mut issued: IssuedCertificate) -> IssuedCertificate {
+        if let Some(cross_signed) = &self.cross_signed {
+            issued.chain.push(cross_signed.clone());
+        }
+        issued
+    }
+
+    /// Current trust bundle
+    pub fn trust_bundle(&self) -> Result<TrustBundle> {
+        let mut roots = vec![self.active.certificate_pem().to_string()];
+        if let Some(next) = &self.next {
+            roots.push(next.certificate_pem().to_string());
+        }
+        let cross_signed = self.cross_signed.as_ref().map(|der| pem::encode(&pem::Pem::new("CERTIFICATE", der.clone())));
+        let next_phase_after = (self.phase_started + self.transition_window)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+        Ok(TrustBundle { version: self.version, phase: self.phase, roots, cross_signed, next_phase_after })
+    }
+
+    /// Issue the next CA and trust it alongside the current one
+    pub fn stage(&mut self) -> Result<TrustBundle> {
+        self.expect_phase(RotationPhase::Stable, "stage a new CA")?;
+        let generation = self.active.generation + 1;
+        self.next = Some(ClusterCa::generate(&self.cluster, generation, self.validity_days)?);
+        tracing::info!("Staged cluster CA generation {}", generation);
+        self.enter(RotationPhase::Staged)
+    }
+
+    /// Issue from the new CA, cross-signed by the old one
+    pub fn cross_sign(&mut self) -> Result<TrustBundle> {
+        self.expect_phase(RotationPhase::Staged, "cross-sign")?;
+        self.check_window()?;
+        let next = self.next.as_ref().ok_or_else(|| TransportError::Certificate {
+            message: "No new CA to cross-sign".to_string(),
+        })?;
+        self.cross_signed = Some(next.signed_by(&self.active)?);
+        tracing::info!("Cluster CA generation {} now issuing", next.generation);
+        self.enter(RotationPhase::CrossSigned)
+    }
+
+    /// Stop trusting the old CA
+    ///
+    /// Every node must hold a certificate from the new CA by now; ones still
+    /// presenting the old CA's certificates are rejected afterwards.
+    pub fn retire(&mut self) -> Result<TrustBundle> {
+        self.expect_phase(RotationPhase::CrossSigned, "retire the old CA")?;
+        self.check_window()?;
+        let next = self.next.take().ok_or_else(|| TransportError::Certificate {
+            message: "No new CA to promote".to_string(),
+        })?;
+        tracing::info!("Retired cluster CA generation {}", self.active.generation);
+        self.active = next;
+        self.cross_signed = None;
+        self.enter(RotationPhase::Stable)
+    }
+
+    /// Abandon a staged rotation and drop the new CA
+    pub fn abort(&mut self) -> Result<TrustBundle> {
+        self.expect_phase(RotationPhase::Staged, "abort")?;
+        self.next = None;
+        self.enter(RotationPhase::Stable)
+    }
+
+    fn expect_phase(&self, phase: RotationPhase, action: &str) -> Result<()> {
+        if self.phase != phase {
+            return Err(TransportError::Certificate {
+                message: format!("Cannot {} while the CA rotation is {:?}", action, self.phase),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_window(&self) -> Result<()> {
+        let elapsed = self.phase_started.elapsed().unwrap_or(Duration::ZERO);
+        if elapsed < self.transition_window {
+            return Err(TransportError::Certificate {
+                message: format!(
+                    "CA rotation entered {:?} {:?} ago; wait for the {:?} transition window",
+                    self.phase, elapsed, self.transition_window
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, phase: RotationPhase) -> Result<TrustBundle> {
+        self.phase = phase;
+        self.phase_started = SystemTime::now();
+        self.version += 1;
+        self.trust_bundle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CertificateManager;
+
+    #[tokio::test]
+    async fn test_ca_rotation_phases() {
+        let mut rotation = CaRotation::new("test", 365, Duration::ZERO).unwrap();
+        let manager = CertificateManager::new_self_signed("node-1".to_string(), 365, Duration::from_secs(3600)).await.unwrap();
+        let initial = rotation.trust_bundle().unwrap();
+        assert!(manager.apply_trust_bundle(&initial).unwrap());
+        manager.install_certificate(rotation.issue("node-1", 30).unwrap());
+        assert_eq!(manager.issuer_generation(), Some(1));
+
+        let staged = rotation.stage().unwrap();
+        assert_eq!((staged.phase, staged.roots.len()), (RotationPhase::Staged, 2));
+        assert_eq!(rotation.issuing_generation(), 1);
+        assert!(rotation.retire().is_err());
+
+        let cross_signed = rotation.cross_sign().unwrap();
+        let cross_pem = cross_signed.cross_signed.clone().unwrap();
+        let issued = rotation.issue("node-1", 30).unwrap();
+        assert_eq!((issued.ca_generation, issued.chain.len()), (2, 2));
+        assert_eq!(rustls_pemfile::certs(&mut cross_pem.as_bytes()).unwrap(), vec![issued.chain[1].clone()]);
+
+        let retired = rotation.retire().unwrap();
+        assert_eq!((retired.phase, retired.roots.len(), retired.version), (RotationPhase::Stable, 1, 4));
+        assert_eq!(retired.roots[0], staged.roots[1]);
+        assert_eq!(rotation.issue("node-1", 30).unwrap().chain.len(), 1);
+
+        assert!(manager.apply_trust_bundle(&retired).unwrap());
+        assert!(!manager.apply_trust_bundle(&staged).unwrap());
+        assert_eq!(manager.trust_version(), 4);
+
+        let mut slow = CaRotation::new("test", 365, Duration::from_secs(3600)).unwrap();
+        slow.stage().unwrap();
+        assert!(slow.cross_sign().is_err());
+        assert!(slow.abort().is_ok());
+    }
+}
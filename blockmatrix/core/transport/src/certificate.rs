@@ -1,11 +1,17 @@
 //! Certificate management for transport layer authentication
 
+use crate::ca::{CertificateKey, IssuedCertificate, TrustBundle};
+use crate::renewal::{self, CertificateRenewal, RenewalConfig, RenewalHook, RenewalHookId, RenewalHooks};
+use crate::signer::{self, SignerIdentity};
+use crate::tls_policy::{self, PolicyServerVerifier};
 use crate::{Result, TransportError};
+use nexus_shared::{Algorithm, CryptoPolicy, NodeSigner, SignatureAlgorithm};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use rcgen::{Certificate, CertificateParams, KeyPair, DistinguishedName, DnType};
 use rustls::{ServerConfig, ClientConfig};
 use rustls_pemfile;
+use tokio::task::JoinHandle;
 
 /// Certificate manager handles certificate generation, rotation, and validation
 pub struct CertificateManager {
@@ -20,6 +26,28 @@ pub struct CertificateManager {
     
     /// Last rotation time
     last_rotation: Arc<parking_lot::RwLock<SystemTime>>,
+    
+    /// Subject and validity the self-signed certificate is renewed with
+    subject_name: String,
+    validity_days: u32,
+    
+    /// Expiry of the current self-signed certificate
+    expires_at: Arc<parking_lot::RwLock<SystemTime>>,
+    
+    /// Hooks run after each renewal
+    renewal_hooks: parking_lot::RwLock<RenewalHooks>,
+    
+    /// Certificate issued by the cluster CA, used instead of the self-signed one
+    issued: Arc<parking_lot::RwLock<Option<IssuedCertificate>>>,
+    
+    /// Version of the last trust bundle applied
+    trust_version: Arc<parking_lot::RwLock<u64>>,
+    
+    /// Node key the self-signed certificate is made for, if not generated with it
+    signer: Option<Arc<dyn NodeSigner>>,
+    
+    /// Algorithms handshakes may use
+    crypto_policy: Arc<parking_lot::RwLock<CryptoPolicy>>,
 }
 
 impl CertificateManager {
@@ -30,6 +58,30 @@ impl CertificateManager {
         rotation_interval: Duration,
     ) -> Result<Self> {
         let cert = generate_self_signed_cert(&subject_name, validity_days)?;
+        Self::with_certificate(cert, subject_name, validity_days, rotation_interval, None)
+    }
+    
+    /// Create a certificate manager whose self-signed certificate uses the node's key
+    ///
+    /// The private key stays with the signer, which may be hardware; every
+    /// certificate and handshake signature is made through it.
+    pub async fn new_with_signer(
+        subject_name: String,
+        validity_days: u32,
+        rotation_interval: Duration,
+        signer: Arc<dyn NodeSigner>,
+    ) -> Result<Self> {
+        let cert = generate_signer_cert(&subject_name, validity_days, signer.clone())?;
+        Self::with_certificate(cert, subject_name, validity_days, rotation_interval, Some(signer))
+    }
+    
+    fn with_certificate(
+        cert: Certificate,
+        subject_name: String,
+        validity_days: u32,
+        rotation_interval: Duration,
+        signer: Option<Arc<dyn NodeSigner>>,
+    ) -> Result<Self> {
         let mut root_store = rustls::RootCertStore::empty();
         
         // Add self-signed cert to root store for testing
@@ -48,6 +100,14 @@ impl CertificateManager {
             root_store: Arc::new(parking_lot::RwLock::new(root_store)),
             rotation_interval,
             last_rotation: Arc::new(parking_lot::RwLock::new(SystemTime::now())),
+            expires_at: Arc::new(parking_lot::RwLock::new(expiry(validity_days))),
+            subject_name,
+            validity_days,
+            renewal_hooks: parking_lot::RwLock::new(RenewalHooks::default()),
+            issued: Arc::new(parking_lot::RwLock::new(None)),
+            trust_version: Arc::new(parking_lot::RwLock::new(0)),
+            signer,
+            crypto_policy: Arc::new(parking_lot::RwLock::new(CryptoPolicy::default())),
         })
     }
     
@@ -71,7 +131,9 @@ impl CertificateManager {
         
         // Parse certificate - simplified for now, will need proper implementation
         // TODO: Implement proper certificate loading from PEM files
-        let cert = generate_self_signed_cert("loaded-cert", 365)?;
+        let subject_name = "loaded-cert".to_string();
+        let validity_days = 365;
+        let cert = generate_self_signed_cert(&subject_name, validity_days)?;
         
         // Load CA bundle if provided
         let mut root_store = rustls::RootCertStore::empty();
@@ -100,6 +162,14 @@ impl CertificateManager {
             root_store: Arc::new(parking_lot::RwLock::new(root_store)),
             rotation_interval,
             last_rotation: Arc::new(parking_lot::RwLock::new(SystemTime::now())),
+            expires_at: Arc::new(parking_lot::RwLock::new(expiry(validity_days))),
+            subject_name,
+            validity_days,
+            renewal_hooks: parking_lot::RwLock::new(RenewalHooks::default()),
+            issued: Arc::new(parking_lot::RwLock::new(None)),
+            trust_version: Arc::new(parking_lot::RwLock::new(0)),
+            signer: None,
+            crypto_policy: Arc::new(parking_lot::RwLock::new(CryptoPolicy::default())),
         })
     }
     
@@ -108,28 +178,125 @@ impl CertificateManager {
         Arc::clone(&self.server_cert)
     }
     
-    /// Create rustls server configuration
-    pub fn server_config(&self) -> Result<ServerConfig> {
+    /// Use a certificate issued by the cluster CA for both server and client auth
+    pub fn install_certificate(&self, issued: IssuedCertificate) {
+        tracing::info!("Installed certificate issued by cluster CA generation {}", issued.ca_generation);
+        *self.issued.write() = Some(issued);
+        *self.last_rotation.write() = SystemTime::now();
+    }
+    
+    /// Generation of the cluster CA that issued the current certificate
+    pub fn issuer_generation(&self) -> Option<u64> {
+        self.issued.read().as_ref().map(|issued| issued.ca_generation)
+    }
+    
+    /// Replace the trusted roots with those of a trust bundle
+    ///
+    /// Returns `false` and changes nothing if the bundle is older than the
+    /// one last applied. Configurations created afterwards trust the new
+    /// roots; existing connections keep the trust they were made with.
+    pub fn apply_trust_bundle(&self, bundle: &TrustBundle) -> Result<bool> {
+        let mut trust_version = self.trust_version.write();
+        if bundle.version <= *trust_version {
+            return Ok(false);
+        }
+        
+        let mut root_store = rustls::RootCertStore::empty();
+        for root in bundle.root_certificates()? {
+            root_store
+                .add(&rustls::Certificate(root))
+                .map_err(|e| TransportError::Certificate {
+                    message: format!("Failed to add CA certificate: {}", e),
+                })?;
+        }
+        
+        *self.root_store.write() = root_store;
+        *trust_version = bundle.version;
+        tracing::info!("Applied trust bundle version {} ({:?}, {} roots)", bundle.version, bundle.phase, bundle.roots.len());
+        Ok(true)
+    }
+    
+    /// Version of the last trust bundle applied, 0 if none
+    pub fn trust_version(&self) -> u64 {
+        *self.trust_version.read()
+    }
+    
+    /// Whether the node key is held by hardware
+    pub fn is_hardware_backed(&self) -> bool {
+        match self.issued.read().as_ref().map(|issued| &issued.key) {
+            Some(CertificateKey::Signer(signer)) => signer.is_hardware_backed(),
+            Some(CertificateKey::Der(_)) => false,
+            None => self.signer.as_ref().is_some_and(|signer| signer.is_hardware_backed()),
+        }
+    }
+    
+    /// Algorithm of the key this node presents
+    pub fn key_algorithm(&self) -> Algorithm {
+        let signer = match self.issued.read().as_ref().map(|issued| &issued.key) {
+            Some(CertificateKey::Signer(signer)) => Some(signer.algorithm()),
+            Some(CertificateKey::Der(_)) => None,
+            None => self.signer.as_ref().map(|signer| signer.algorithm()),
+        };
+        // rcgen generates ECDSA P-256 keys unless given a signer
+        signer.unwrap_or(SignatureAlgorithm::EcdsaP256Sha256).into()
+    }
+    
+    /// Restrict handshakes to a crypto policy
+    ///
+    /// Fails, leaving the current policy, if the policy refuses this node's key.
+    pub fn set_crypto_policy(&self, policy: CryptoPolicy) -> Result<()> {
+        policy.check(self.key_algorithm()).map_err(|e| TransportError::Configuration {
+            message: e.to_string(),
+        })?;
+        *self.crypto_policy.write() = policy;
+        Ok(())
+    }
+    
+    /// Crypto policy handshakes follow
+    pub fn crypto_policy(&self) -> CryptoPolicy {
+        self.crypto_policy.read().clone()
+    }
+    
+    /// Certificate chain and private key to present
+    fn identity(&self) -> Result<(Vec<rustls::Certificate>, CertificateKey)> {
+        if let Some(issued) = self.issued.read().as_ref() {
+            let chain = issued.chain.iter().cloned().map(rustls::Certificate).collect();
+            return Ok((chain, issued.key.clone()));
+        }
+        
         let cert = self.server_cert.read();
         let cert_der = cert.serialize_der()
             .map_err(|e| TransportError::Certificate {
                 message: format!("Failed to serialize certificate: {}", e),
             })?;
             
-        let key_der = cert.serialize_private_key_der();
-        
-        let cert_chain = vec![rustls::Certificate(cert_der)];
-        let private_key = rustls::PrivateKey(key_der);
+        // A certificate made for a signer has no private key to serialize
+        let key = match &self.signer {
+            Some(signer) => CertificateKey::Signer(signer.clone()),
+            None => CertificateKey::Der(cert.serialize_private_key_der()),
+        };
+        Ok((vec![rustls::Certificate(cert_der)], key))
+    }
+    
+    /// Create rustls server configuration
+    pub fn server_config(&self) -> Result<ServerConfig> {
+        let (cert_chain, key) = self.identity()?;
         
-        let mut config = ServerConfig::builder()
-            .with_safe_defaults()
+        let policy = self.crypto_policy();
+        let builder = tls_policy::restrict(ServerConfig::builder(), &policy)?
             .with_client_cert_verifier(Arc::new(ClientCertVerifier::new(
-                self.root_store.clone()
-            )))
-            .with_single_cert(cert_chain, private_key)
-            .map_err(|e| TransportError::Certificate {
-                message: format!("Failed to create server config: {}", e),
-            })?;
+                self.root_store.clone(),
+                policy,
+            )));
+        let mut config = match key {
+            CertificateKey::Der(der) => builder
+                .with_single_cert(cert_chain, rustls::PrivateKey(der))
+                .map_err(|e| TransportError::Certificate {
+                    message: format!("Failed to create server config: {}", e),
+                })?,
+            CertificateKey::Signer(node_key) => builder
+                .with_cert_resolver(Arc::new(SignerIdentity(signer::certified_key(cert_chain, node_key)))),
+        };
             
         // Configure ALPN for QUIC
         config.alpn_protocols = vec![b"nexus/1".to_vec()];
@@ -138,13 +305,27 @@ impl CertificateManager {
     }
     
     /// Create rustls client configuration
+    ///
+    /// A certificate issued by the cluster CA is presented as the client's
+    /// identity; the self-signed one is not.
     pub fn client_config(&self) -> Result<ClientConfig> {
-        let config = ClientConfig::builder()
-            .with_safe_defaults()
-            .with_root_certificates(self.root_store.read().clone())
-            .with_no_client_auth();
+        let policy = self.crypto_policy();
+        let verifier = PolicyServerVerifier::new(self.root_store.read().clone(), policy.clone());
+        let builder = tls_policy::restrict(ClientConfig::builder(), &policy)?
+            .with_custom_certificate_verifier(Arc::new(verifier));
             
-        Ok(config)
+        if self.issued.read().is_none() {
+            return Ok(builder.with_no_client_auth());
+        }
+        match self.identity()? {
+            (cert_chain, CertificateKey::Der(der)) => builder
+                .with_client_auth_cert(cert_chain, rustls::PrivateKey(der))
+                .map_err(|e| TransportError::Certificate {
+                    message: format!("Failed to create client config: {}", e),
+                }),
+            (cert_chain, CertificateKey::Signer(node_key)) => Ok(builder
+                .with_client_cert_resolver(Arc::new(SignerIdentity(signer::certified_key(cert_chain, node_key))))),
+        }
     }
     
     /// Check if certificate needs rotation
@@ -157,8 +338,13 @@ impl CertificateManager {
     }
     
     /// Rotate certificate (generate new self-signed)
+    ///
+    /// With a node signer the new certificate is for the same key.
     pub async fn rotate_certificate(&self, subject_name: &str, validity_days: u32) -> Result<()> {
-        let new_cert = generate_self_signed_cert(subject_name, validity_days)?;
+        let new_cert = match &self.signer {
+            Some(signer) => generate_signer_cert(subject_name, validity_days, signer.clone())?,
+            None => generate_self_signed_cert(subject_name, validity_days)?,
+        };
         
         // Update certificate and root store
         let cert_der = new_cert.serialize_der().map_err(|e| TransportError::Certificate {
@@ -166,6 +352,7 @@ impl CertificateManager {
         })?;
         
         *self.server_cert.write() = new_cert;
+        *self.expires_at.write() = expiry(validity_days);
         
         // Update root store with new certificate
         let mut root_store = rustls::RootCertStore::empty();
@@ -181,13 +368,77 @@ impl CertificateManager {
         tracing::info!("Certificate rotated successfully");
         Ok(())
     }
+    
+    /// Expiry of the self-signed certificate
+    pub fn expires_at(&self) -> SystemTime {
+        *self.expires_at.read()
+    }
+    
+    /// Whether the self-signed certificate expires within `renew_before`
+    ///
+    /// Always false while a certificate issued by the cluster CA is installed.
+    pub fn needs_renewal(&self, renew_before: Duration) -> bool {
+        self.issued.read().is_none() && renewal::renewal_due(self.expires_at(), renew_before, SystemTime::now())
+    }
+    
+    /// Run `hook` after every renewal until it is removed
+    pub fn on_renewal(&self, hook: Arc<dyn RenewalHook>) -> RenewalHookId {
+        self.renewal_hooks.write().register(hook)
+    }
+    
+    /// Stop running a renewal hook; false if it was not registered
+    pub fn remove_renewal_hook(&self, id: RenewalHookId) -> bool {
+        self.renewal_hooks.write().remove(id)
+    }
+    
+    /// Re-issue the self-signed certificate with its original subject and validity, then run the hooks
+    pub async fn renew(&self) -> Result<CertificateRenewal> {
+        let previous_expiry = self.expires_at();
+        self.rotate_certificate(&self.subject_name, self.validity_days).await?;
+        
+        let renewal = CertificateRenewal {
+            subject_name: self.subject_name.clone(),
+            previous_expiry,
+            expires_at: self.expires_at(),
+            renewed_at: SystemTime::now(),
+        };
+        
+        let hooks = self.renewal_hooks.read().snapshot();
+        for hook in hooks {
+            if let Err(e) = hook.certificate_renewed(self, &renewal) {
+                tracing::warn!("Certificate renewal hook failed for {}: {}", renewal.subject_name, e);
+            }
+        }
+        
+        tracing::info!("Renewed certificate for {}, valid until {:?}", renewal.subject_name, renewal.expires_at);
+        Ok(renewal)
+    }
+    
+    /// Renew the certificate in the background whenever it nears expiry
+    pub fn spawn_renewal(self: &Arc<Self>, config: RenewalConfig) -> JoinHandle<()> {
+        let certificates = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(config.check_interval);
+            loop {
+                ticker.tick().await;
+                if !certificates.needs_renewal(config.renew_before) {
+                    continue;
+                }
+                if let Err(e) = certificates.renew().await {
+                    tracing::error!("Failed to renew certificate for {}: {}", certificates.subject_name, e);
+                }
+            }
+        })
+    }
 }
 
 impl std::fmt::Debug for CertificateManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("CertificateManager")
+            .field("subject_name", &self.subject_name)
             .field("rotation_interval", &self.rotation_interval)
             .field("last_rotation", &*self.last_rotation.read())
+            .field("expires_at", &*self.expires_at.read())
             .finish_non_exhaustive()
     }
 }
@@ -195,11 +446,12 @@ impl std::fmt::Debug for CertificateManager {
 /// Custom client certificate verifier
 struct ClientCertVerifier {
     root_store: Arc<parking_lot::RwLock<rustls::RootCertStore>>,
+    policy: CryptoPolicy,
 }
 
 impl ClientCertVerifier {
-    fn new(root_store: Arc<parking_lot::RwLock<rustls::RootCertStore>>) -> Self {
-        Self { root_store }
+    fn new(root_store: Arc<parking_lot::RwLock<rustls::RootCertStore>>, policy: CryptoPolicy) -> Self {
+        Self { root_store, policy }
     }
 }
 
@@ -222,10 +474,58 @@ impl rustls::server::ClientCertVerifier for ClientCertVerifier {
         // TODO: Implement proper verification against root store
         Ok(rustls::server::ClientCertVerified::assertion())
     }
+    
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::Certificate,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::HandshakeSignatureValid, rustls::Error> {
+        tls_policy::verify_tls13_signature(&self.policy, message, cert, dss)
+    }
+    
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        tls_policy::verify_schemes(&self.policy)
+    }
+}
+
+/// Expiry of a certificate generated now, matching `certificate_params`
+fn expiry(validity_days: u32) -> SystemTime {
+    SystemTime::now() + Duration::from_secs(validity_days as u64 * 24 * 60 * 60)
 }
 
 /// Generate a self-signed certificate
 pub fn generate_self_signed_cert(subject_name: &str, validity_days: u32) -> Result<Certificate> {
+    Certificate::from_params(self_signed_params(subject_name, validity_days)).map_err(|e| TransportError::Certificate {
+        message: format!("Failed to generate self-signed certificate: {}", e),
+    })
+}
+
+/// Generate a self-signed certificate for a node signer's key
+pub fn generate_signer_cert(subject_name: &str, validity_days: u32, signer: Arc<dyn NodeSigner>) -> Result<Certificate> {
+    let mut params = self_signed_params(subject_name, validity_days);
+    params.alg = signer::rcgen_algorithm(signer.algorithm());
+    params.key_pair = Some(signer::key_pair(signer)?);
+    Certificate::from_params(params).map_err(|e| TransportError::Certificate {
+        message: format!("Failed to generate certificate for the node key: {}", e),
+    })
+}
+
+fn self_signed_params(subject_name: &str, validity_days: u32) -> CertificateParams {
+    let mut params = certificate_params(subject_name, validity_days);
+    
+    // Add subject alternative names
+    params.subject_alt_names = vec![
+        rcgen::SanType::DnsName(subject_name.to_string()),
+        rcgen::SanType::IpAddress(std::net::IpAddr::V6(std::net::Ipv6Addr::LOCALHOST)),
+        rcgen::SanType::IpAddress(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST)),
+    ];
+    
+    params
+}
+
+/// Parameters with a common name, valid from now for `validity_days`
+pub(crate) fn certificate_params(subject_name: &str, validity_days: u32) -> CertificateParams {
     let mut params = CertificateParams::default();
     
     // Set subject name
@@ -245,17 +545,7 @@ pub fn generate_self_signed_cert(subject_name: &str, validity_days: u32) -> Resu
         (now.as_secs() + (validity_days as u64 * 24 * 60 * 60)) as i64
     ).expect("Invalid timestamp");
     
-    // Add subject alternative names
-    params.subject_alt_names = vec![
-        rcgen::SanType::DnsName(subject_name.to_string()),
-        rcgen::SanType::IpAddress(std::net::IpAddr::V6(std::net::Ipv6Addr::LOCALHOST)),
-        rcgen::SanType::IpAddress(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST)),
-    ];
-    
-    // Generate certificate
-    Certificate::from_params(params).map_err(|e| TransportError::Certificate {
-        message: format!("Failed to generate self-signed certificate: {}", e),
-    })
+    params
 }
 
 #[cfg(test)]
@@ -286,6 +576,47 @@ mod tests {
         assert_eq!(server_config.alpn_protocols, vec![b"nexus/1".to_vec()]);
     }
     
+    #[tokio::test]
+    async fn test_certificates_for_a_node_signer() {
+        let node_key: Arc<dyn NodeSigner> = Arc::new(nexus_shared::KeyPair::generate().unwrap());
+        let cert_manager = CertificateManager::new_with_signer(
+            "test-node".to_string(),
+            365,
+            Duration::from_secs(3600),
+            node_key.clone(),
+        ).await.unwrap();
+        
+        assert!(cert_manager.server_config().is_ok());
+        cert_manager.rotate_certificate("test-node", 365).await.unwrap();
+        assert!(!cert_manager.is_hardware_backed());
+        
+        let ca = crate::ClusterCa::generate("test", 1, 365).unwrap();
+        let issued = ca.issue_for("test-node", 30, node_key).unwrap();
+        assert!(matches!(issued.key, CertificateKey::Signer(_)));
+        cert_manager.install_certificate(issued);
+        assert!(cert_manager.server_config().is_ok());
+        assert!(cert_manager.client_config().is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_crypto_policy_checks_the_node_key() {
+        let node_key: Arc<dyn NodeSigner> = Arc::new(nexus_shared::KeyPair::generate().unwrap());
+        let cert_manager = CertificateManager::new_with_signer(
+            "test-node".to_string(),
+            365,
+            Duration::from_secs(3600),
+            node_key,
+        ).await.unwrap();
+        
+        let no_ed25519 = CryptoPolicy { denied: vec![Algorithm::Ed25519], ..CryptoPolicy::fips() };
+        assert!(cert_manager.set_crypto_policy(no_ed25519).is_err());
+        assert_eq!(cert_manager.crypto_policy(), CryptoPolicy::default());
+        
+        cert_manager.set_crypto_policy(CryptoPolicy::fips()).unwrap();
+        assert!(cert_manager.server_config().is_ok());
+        assert!(cert_manager.client_config().is_ok());
+    }
+    
     #[tokio::test]
     async fn test_certificate_rotation() {
         let cert_manager = CertificateManager::new_self_signed(
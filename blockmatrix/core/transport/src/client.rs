@@ -1,11 +1,17 @@
 //! QUIC client implementation for Nexus transport layer
 
 use crate::{Result, TransportError, TransportConfig, CertificateManager, Connection, TransportMessage};
+use crate::migration::{PathEvent, PathTracker};
+use crate::pool::{ConnectionPool, PoolLease, PoolStats};
+use crate::resumption::{session_store_for, ResumptionCounters, ResumptionStats, SessionStore, TicketStore};
 use nexus_shared::NodeId;
 use quinn::{Endpoint, ClientConfig};
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{info, warn, error, debug};
 
 /// QUIC client for establishing outbound connections
@@ -19,8 +25,26 @@ pub struct QuicClient {
     /// Quinn endpoint
     endpoint: Option<Endpoint>,
     
-    /// Active connections
-    connections: Arc<RwLock<std::collections::HashMap<NodeId, Arc<Connection>>>>,
+    /// Pooled connections to each peer
+    pool: Arc<ConnectionPool>,
+    
+    /// Address and server name of each peer, for opening further connections
+    peer_addresses: Arc<RwLock<HashMap<NodeId, (SocketAddr, String)>>>,
+    
+    /// Background task closing idle connections
+    reaper: Option<JoinHandle<()>>,
+    
+    /// Last known peer addresses, for reporting migrations
+    paths: Arc<PathTracker>,
+    
+    /// Background task watching connections for path changes
+    path_watcher: Option<JoinHandle<()>>,
+    
+    /// Session tickets for resuming connections
+    sessions: Arc<dyn SessionStore>,
+    
+    /// Resumption and early data counters
+    resumption: Arc<ResumptionCounters>,
     
     /// Client node ID
     node_id: NodeId,
@@ -39,21 +63,37 @@ impl QuicClient {
         cert_manager: Arc<CertificateManager>
     ) -> Result<Self> {
         config.validate().map_err(|e| TransportError::Configuration { message: e })?;
+        cert_manager.set_crypto_policy(config.crypto.clone())?;
         
         let node_id = NodeId::random();
         let (message_sender, message_receiver) = mpsc::unbounded_channel();
+        let paths = Arc::new(PathTracker::new(config.migration.event_capacity));
         
         Ok(Self {
+            pool: Arc::new(ConnectionPool::new(config.pool.clone())),
+            sessions: session_store_for(&config.resumption),
             config,
             cert_manager,
             endpoint: None,
-            connections: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            peer_addresses: Arc::new(RwLock::new(HashMap::new())),
+            reaper: None,
+            paths,
+            path_watcher: None,
+            resumption: Arc::new(ResumptionCounters::default()),
             node_id,
             message_sender,
             message_receiver: Arc::new(RwLock::new(Some(message_receiver))),
         })
     }
     
+    /// Keep session tickets in `store` instead of the configured session file
+    ///
+    /// Takes effect when the client is started.
+    pub fn with_session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.sessions = store;
+        self
+    }
+    
     /// Initialize the client endpoint
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting QUIC client");
@@ -64,7 +104,8 @@ impl QuicClient {
                 message: format!("Failed to create client config: {}", e) 
             })?;
             
-        let quinn_config = self.config.to_quinn_client_config();
+        let tickets = TicketStore::new(Arc::clone(&self.sessions), Arc::clone(&self.resumption));
+        let quinn_config = self.config.to_quinn_client_config_with_sessions(Arc::new(tickets));
         
         // Create endpoint with client configuration
         let mut endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap())
@@ -74,6 +115,36 @@ impl QuicClient {
             
         endpoint.set_default_client_config(quinn_config);
         
+        let pool = Arc::clone(&self.pool);
+        let reap_interval = self.config.pool.reap_interval;
+        self.reaper = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(reap_interval);
+            loop {
+                ticker.tick().await;
+                let closed = pool.reap();
+                if closed > 0 {
+                    debug!("Reaped {} idle or closed connections", closed);
+                }
+            }
+        }));
+        
+        let pool = Arc::clone(&self.pool);
+        let paths = Arc::clone(&self.paths);
+        let check_interval = self.config.migration.path_check_interval;
+        self.path_watcher = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(check_interval);
+            loop {
+                ticker.tick().await;
+                let connections = pool.connections();
+                for (peer, connection) in &connections {
+                    if connection.is_healthy() {
+                        paths.observe(*peer, connection.remote_address());
+                    }
+                }
+                paths.retain(|peer| connections.iter().any(|(connected, _)| connected == peer));
+            }
+        }));
+        
         info!("QUIC client started");
         self.endpoint = Some(endpoint);
         Ok(())
@@ -81,6 +152,27 @@ impl QuicClient {
     
     /// Connect to a remote server
     pub async fn connect(&self, remote_addr: SocketAddr, server_name: &str) -> Result<NodeId> {
+        let (remote_node_id, _lease) = self.open_connection(remote_addr, server_name, None, None).await?;
+        
+        self.peer_addresses.write().await
+            .insert(remote_node_id, (remote_addr, server_name.to_string()));
+        
+        info!("Successfully connected to node {}", remote_node_id);
+        Ok(remote_node_id)
+    }
+    
+    /// Open a new pooled connection, checking the remote node ID if it is known
+    ///
+    /// With a stored ticket the connection resumes in 0-RTT and `early` is
+    /// sent as early data; otherwise, or if the server refuses the resumption
+    /// or the message itself, it is sent once the handshake completes.
+    async fn open_connection(
+        &self,
+        remote_addr: SocketAddr,
+        server_name: &str,
+        expected: Option<NodeId>,
+        early: Option<&TransportMessage>,
+    ) -> Result<(NodeId, PoolLease)> {
         let endpoint = self.endpoint.as_ref()
             .ok_or_else(|| TransportError::Endpoint { 
                 message: "Client not started".to_string() 
@@ -88,49 +180,132 @@ impl QuicClient {
             
         info!("Connecting to {} ({})", remote_addr, server_name);
         
-        // Establish QUIC connection
-        let new_conn = endpoint
+        // Establish QUIC connection, resuming in 0-RTT when a ticket allows
+        let connecting = endpoint
             .connect(remote_addr, server_name)
             .map_err(|e| TransportError::Connection { 
                 message: format!("Failed to initiate connection: {}", e) 
-            })?
-            .await
-            .map_err(|e| TransportError::Connection { 
-                message: format!("Connection failed: {}", e) 
             })?;
-            
-        info!("QUIC connection established to {}", remote_addr);
+        let connecting = if self.config.resumption.enabled {
+            connecting.into_0rtt()
+        } else {
+            Err(connecting)
+        };
+        let (new_conn, zero_rtt) = match connecting {
+            Ok((new_conn, accepted)) => {
+                self.resumption.resumption_attempts.fetch_add(1, Ordering::Relaxed);
+                (new_conn, Some(accepted))
+            }
+            Err(connecting) => {
+                self.resumption.full_handshakes.fetch_add(1, Ordering::Relaxed);
+                let new_conn = connecting.await
+                    .map_err(|e| TransportError::Connection { 
+                        message: format!("Connection failed: {}", e) 
+                    })?;
+                (new_conn, None)
+            }
+        };
         
         // Create connection wrapper
         let connection = Arc::new(Connection::new(
             new_conn,
             self.node_id,
             None, // Will be set after handshake
-        ).await?);
+        ).await?.with_lanes(self.config.lanes.clone()));
+        
+        // Early data is delivered only if the server accepts the resumption
+        let sent_early = early.is_some() && zero_rtt.is_some();
+        let early_delivered = match (early, zero_rtt) {
+            (Some(message), Some(accepted)) => {
+                self.resumption.early_messages_sent.fetch_add(1, Ordering::Relaxed);
+                let (sent, accepted) = tokio::join!(connection.send_early_data(message.clone()), accepted);
+                if accepted {
+                    self.resumption.resumed.fetch_add(1, Ordering::Relaxed);
+                }
+                sent.is_ok() && accepted
+            }
+            (None, Some(accepted)) => {
+                if accepted.await {
+                    self.resumption.resumed.fetch_add(1, Ordering::Relaxed);
+                }
+                false
+            }
+            (_, None) => false,
+        };
+        
+        info!("QUIC connection established to {}", remote_addr);
         
         // Perform handshake to get remote node ID
         let remote_node_id = connection.handshake().await?;
+        if expected.is_some_and(|expected| expected != remote_node_id) {
+            connection.close().await;
+            return Err(TransportError::Authentication {
+                reason: format!("{} now answers as node {}", remote_addr, remote_node_id),
+            });
+        }
         connection.set_remote_node_id(remote_node_id).await;
         
-        // Store connection
-        self.connections.write().await.insert(remote_node_id, Arc::clone(&connection));
+        if let Some(message) = early.filter(|_| !early_delivered) {
+            if sent_early {
+                self.resumption.early_messages_resent.fetch_add(1, Ordering::Relaxed);
+            }
+            connection.send_message(message.clone()).await?;
+        }
         
-        // Handle connection messages
-        let connections = Arc::clone(&self.connections);
+        // Pool the connection; the pool drops it once it closes
+        let lease = self.pool.insert(remote_node_id, Arc::clone(&connection));
         let message_sender = self.message_sender.clone();
         
         tokio::spawn(async move {
             if let Err(e) = connection.handle_messages(message_sender).await {
                 error!("Connection message handling failed: {}", e);
             }
-            
-            // Clean up connection when it closes
-            connections.write().await.remove(&remote_node_id);
             info!("Connection closed for node {}", remote_node_id);
         });
         
-        info!("Successfully connected to node {}", remote_node_id);
-        Ok(remote_node_id)
+        Ok((remote_node_id, lease))
+    }
+    
+    /// Borrow a healthy connection to a peer, opening one if none can be reused
+    async fn pooled(&self, target: NodeId) -> Result<PoolLease> {
+        if let Some(lease) = self.pool.checkout(&target) {
+            return Ok(lease);
+        }
+        
+        let (remote_addr, server_name) = self.peer_addresses.read().await
+            .get(&target)
+            .cloned()
+            .ok_or_else(|| TransportError::Connection { 
+                message: format!("No connection to node {}", target) 
+            })?;
+        
+        debug!("Opening pooled connection to {} at {}", target, remote_addr);
+        let (_, lease) = self.open_connection(remote_addr, &server_name, Some(target), None).await?;
+        Ok(lease)
+    }
+    
+    /// Send an idempotent message, as 0-RTT early data if a new connection is needed
+    ///
+    /// Early data may be replayed, so messages not marked idempotent are refused.
+    pub async fn send_early(&self, target: NodeId, message: TransportMessage) -> Result<()> {
+        if !message.idempotent {
+            return Err(TransportError::Stream {
+                message: "only idempotent messages may be sent as early data".to_string(),
+            });
+        }
+        if let Some(connection) = self.pool.checkout(&target) {
+            return connection.send_message(message).await;
+        }
+        
+        let (remote_addr, server_name) = self.peer_addresses.read().await
+            .get(&target)
+            .cloned()
+            .ok_or_else(|| TransportError::Connection { 
+                message: format!("No connection to node {}", target) 
+            })?;
+        
+        self.open_connection(remote_addr, &server_name, Some(target), Some(&message)).await?;
+        Ok(())
     }
     
     /// Connect with retry logic
@@ -169,11 +344,11 @@ impl QuicClient {
     
     /// Disconnect from a specific peer
     pub async fn disconnect(&self, node_id: NodeId) -> Result<()> {
-        let mut connections = self.connections.write().await;
+        self.peer_addresses.write().await.remove(&node_id);
         
-        if let Some(connection) = connections.remove(&node_id) {
-            info!("Disconnecting from node {}", node_id);
-            connection.close().await;
+        let closed = self.pool.remove_peer(&node_id);
+        if closed > 0 {
+            info!("Disconnected {} connections from node {}", closed, node_id);
         }
         
         Ok(())
@@ -185,12 +360,7 @@ impl QuicClient {
         target: NodeId,
         message: TransportMessage,
     ) -> Result<()> {
-        let connections = self.connections.read().await;
-        let connection = connections.get(&target)
-            .ok_or_else(|| TransportError::Connection { 
-                message: format!("No connection to node {}", target) 
-            })?;
-            
+        let connection = self.pooled(target).await?;
         connection.send_message(message).await
     }
     
@@ -201,22 +371,21 @@ impl QuicClient {
         request: TransportMessage,
         timeout: std::time::Duration,
     ) -> Result<TransportMessage> {
-        let connections = self.connections.read().await;
-        let connection = connections.get(&target)
-            .ok_or_else(|| TransportError::Connection { 
-                message: format!("No connection to node {}", target) 
-            })?;
-            
+        let connection = self.pooled(target).await?;
         connection.send_request(request, timeout).await
     }
     
     /// Broadcast a message to all connected peers
     pub async fn broadcast_message(&self, message: TransportMessage) -> Result<usize> {
-        let connections = self.connections.read().await;
         let mut sent_count = 0;
         
-        for (node_id, connection) in connections.iter() {
-            match connection.send_message(message.clone()).await {
+        let peers: Vec<NodeId> = self.peer_addresses.read().await.keys().cloned().collect();
+        for node_id in peers {
+            let sent = match self.pooled(node_id).await {
+                Ok(connection) => connection.send_message(message.clone()).await,
+                Err(e) => Err(e),
+            };
+            match sent {
                 Ok(()) => {
                     sent_count += 1;
                     debug!("Broadcast message sent to {}", node_id);
@@ -235,13 +404,17 @@ impl QuicClient {
     pub async fn stop(&mut self) -> Result<()> {
         info!("Stopping QUIC client");
         
-        // Close all connections
-        let mut connections = self.connections.write().await;
-        for (node_id, connection) in connections.drain() {
-            debug!("Closing connection to {}", node_id);
-            connection.close().await;
+        if let Some(reaper) = self.reaper.take() {
+            reaper.abort();
+        }
+        if let Some(path_watcher) = self.path_watcher.take() {
+            path_watcher.abort();
         }
         
+        // Close all connections
+        self.peer_addresses.write().await.clear();
+        self.pool.clear();
+        
         // Close endpoint
         if let Some(endpoint) = self.endpoint.take() {
             endpoint.close(0u32.into(), b"client shutdown");
@@ -252,6 +425,42 @@ impl QuicClient {
         Ok(())
     }
     
+    /// Move the endpoint to a new local socket, keeping every connection open
+    ///
+    /// Used when the node fails over to another interface or its address
+    /// changes. Peers see packets arrive from the new address and migrate
+    /// the connections once the path is validated. Returns the new local
+    /// address.
+    pub async fn rebind(&self, bind_addr: SocketAddr) -> Result<SocketAddr> {
+        if !self.config.enable_migration {
+            return Err(TransportError::Configuration {
+                message: "Connection migration is disabled".to_string(),
+            });
+        }
+        let endpoint = self.endpoint.as_ref()
+            .ok_or_else(|| TransportError::Endpoint { 
+                message: "Client not started".to_string() 
+            })?;
+        
+        let old = endpoint.local_addr()?;
+        let socket = std::net::UdpSocket::bind(bind_addr)?;
+        endpoint.rebind(socket)?;
+        let new = endpoint.local_addr()?;
+        
+        self.paths.local_address_changed(old, new);
+        Ok(new)
+    }
+    
+    /// Subscribe to local and peer address changes
+    pub fn path_events(&self) -> broadcast::Receiver<PathEvent> {
+        self.paths.subscribe()
+    }
+    
+    /// Get the number of path changes seen, local and remote
+    pub fn migration_count(&self) -> u64 {
+        self.paths.migrations()
+    }
+    
     /// Get message receiver for incoming messages
     pub async fn take_message_receiver(
         &self
@@ -261,17 +470,27 @@ impl QuicClient {
     
     /// Get list of connected peers
     pub async fn connected_peers(&self) -> Vec<NodeId> {
-        self.connections.read().await.keys().cloned().collect()
+        self.pool.peers()
     }
     
     /// Get connection count
     pub async fn connection_count(&self) -> usize {
-        self.connections.read().await.len()
+        self.pool.stats().open_connections
     }
     
     /// Check if connected to a specific peer
     pub async fn is_connected(&self, node_id: NodeId) -> bool {
-        self.connections.read().await.contains_key(&node_id)
+        self.pool.contains(&node_id)
+    }
+    
+    /// Get connection pool counters
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+    
+    /// Get session resumption and early data counters
+    pub fn resumption_stats(&self) -> ResumptionStats {
+        self.resumption.stats()
     }
     
     /// Get client node ID
@@ -284,9 +503,9 @@ impl QuicClient {
         self.endpoint.is_some()
     }
     
-    /// Get connection to a specific peer
+    /// Get a healthy connection to a specific peer
     pub async fn get_connection(&self, node_id: NodeId) -> Option<Arc<Connection>> {
-        self.connections.read().await.get(&node_id).cloned()
+        self.pooled(node_id).await.ok().map(|lease| lease.connection())
     }
     
     /// Ping a connected peer
@@ -1,5 +1,12 @@
 //! Transport layer configuration
 
+use crate::admission::AdmissionConfig;
+use crate::migration::MigrationConfig;
+use crate::priority::LaneConfig;
+use crate::renewal::RenewalConfig;
+use crate::pool::PoolConfig;
+use crate::resumption::ResumptionConfig;
+use nexus_shared::CryptoPolicy;
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv6Addr};
 use std::time::Duration;
@@ -45,6 +52,30 @@ pub struct TransportConfig {
     
     /// Certificate configuration
     pub certificate: CertificateConfig,
+    
+    /// Accept and request queue limits
+    #[serde(default)]
+    pub admission: AdmissionConfig,
+    
+    /// Outbound connection pool limits
+    #[serde(default)]
+    pub pool: PoolConfig,
+    
+    /// Session tickets and 0-RTT early data
+    #[serde(default)]
+    pub resumption: ResumptionConfig,
+    
+    /// Path change tracking for migrated connections
+    #[serde(default)]
+    pub migration: MigrationConfig,
+    
+    /// Priority lanes for outgoing messages
+    #[serde(default)]
+    pub lanes: LaneConfig,
+    
+    /// Algorithms handshakes may negotiate
+    #[serde(default)]
+    pub crypto: CryptoPolicy,
 }
 
 impl Default for TransportConfig {
@@ -63,6 +94,12 @@ impl Default for TransportConfig {
             max_stream_data: 1048576,                   // 1MB
             max_concurrent_streams: 1000,
             certificate: CertificateConfig::default(),
+            admission: AdmissionConfig::default(),
+            pool: PoolConfig::default(),
+            resumption: ResumptionConfig::default(),
+            migration: MigrationConfig::default(),
+            lanes: LaneConfig::default(),
+            crypto: CryptoPolicy::default(),
         }
     }
 }
@@ -90,6 +127,10 @@ pub struct CertificateConfig {
     
     /// Certificate validity period for self-signed certs
     pub validity_days: u32,
+    
+    /// Renewal of self-signed certs before they expire
+    #[serde(default)]
+    pub renewal: RenewalConfig,
 }
 
 impl Default for CertificateConfig {
@@ -102,6 +143,7 @@ impl Default for CertificateConfig {
             rotation_interval: Duration::from_secs(24 * 60 * 60), // 24 hours
             subject_name: "nexus-node".to_string(),
             validity_days: 365,
+            renewal: RenewalConfig::default(),
         }
     }
 }
@@ -129,6 +171,17 @@ impl TransportConfig {
             return Err("Maximum concurrent streams must be greater than zero".to_string());
         }
         
+        let validity = Duration::from_secs(self.certificate.validity_days as u64 * 24 * 60 * 60);
+        if self.certificate.renewal.enabled && self.certificate.renewal.renew_before >= validity {
+            return Err("Certificate renewal window must be shorter than the certificate validity".to_string());
+        }
+        
+        self.admission.validate()?;
+        self.pool.validate()?;
+        self.resumption.validate()?;
+        self.migration.validate()?;
+        self.lanes.validate()?;
+        
         Ok(())
     }
     
@@ -144,9 +197,35 @@ impl TransportConfig {
         ))
     }
     
+    /// Create Quinn client configuration that resumes sessions from `sessions`
+    pub fn to_quinn_client_config_with_sessions(
+        &self,
+        sessions: std::sync::Arc<dyn rustls::client::StoresClientSessions>,
+    ) -> quinn::ClientConfig {
+        let mut config = self.create_rustls_client_config();
+        if self.resumption.enabled {
+            config.resumption = rustls::client::Resumption::store(sessions);
+            config.enable_early_data = self.resumption.early_data;
+        }
+        quinn::ClientConfig::new(std::sync::Arc::new(config))
+    }
+    
     /// Create Quinn server configuration
-    pub fn to_quinn_server_config(&self, server_config: rustls::ServerConfig) -> quinn::ServerConfig {
-        quinn::ServerConfig::with_crypto(std::sync::Arc::new(server_config))
+    pub fn to_quinn_server_config(&self, mut server_config: rustls::ServerConfig) -> quinn::ServerConfig {
+        if self.resumption.enabled {
+            match rustls::Ticketer::new() {
+                Ok(ticketer) => server_config.ticketer = ticketer,
+                Err(e) => tracing::warn!("Session tickets disabled: {:?}", e),
+            }
+            if self.resumption.early_data {
+                // QUIC requires the early data limit to be either zero or unlimited
+                server_config.max_early_data_size = u32::MAX;
+            }
+        }
+        let mut config = quinn::ServerConfig::with_crypto(std::sync::Arc::new(server_config));
+        // Peers that change address keep their connection rather than being dropped
+        config.migration(self.enable_migration);
+        config
     }
     
     /// Apply common transport configuration to Quinn
@@ -1,14 +1,19 @@
 //! Connection management and message handling
 
-use crate::{Result, TransportError, TransportMessage, MessageType};
+use crate::{Result, TransportError, TransportMessage, MessageType, AdmissionController, PooledConnection, ReplayGuard};
+use crate::priority::{LaneConfig, LaneScheduler, LaneStats, TrafficClass};
 use nexus_shared::NodeId;
 use quinn::{SendStream, RecvStream};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock, oneshot, Mutex};
+use tokio::sync::{mpsc, RwLock, oneshot, Mutex, Notify, Semaphore};
+use tokio::task::JoinHandle;
 use tracing::{info, warn, error, debug, trace};
 use std::collections::HashMap;
 use std::time::Duration;
 
+/// Control payload telling a client its early data was refused and must be resent
+const EARLY_DATA_REJECTED: &[u8] = b"early-data-rejected";
+
 /// Connection wrapper for QUIC connections
 pub struct Connection {
     /// Quinn connection
@@ -30,7 +35,42 @@ pub struct Connection {
     request_sequence: Arc<std::sync::atomic::AtomicU64>,
     
     /// Message handlers
-    message_handlers: Arc<RwLock<Vec<mpsc::UnboundedSender<(NodeId, TransportMessage)>>>>,
+    message_handlers: Arc<RwLock<Vec<mpsc::Sender<(NodeId, TransportMessage)>>>>,
+    
+    /// Screens messages received as 0-RTT early data; without one they are refused
+    replay_guard: Option<Arc<ReplayGuard>>,
+    
+    /// Message sent as 0-RTT early data, kept to resend if the server refuses it
+    early_data: Arc<parking_lot::Mutex<Option<TransportMessage>>>,
+    
+    /// Outgoing messages waiting for a stream, by traffic class
+    lanes: Arc<Lanes>,
+}
+
+/// A message waiting in its lane
+struct OutgoingMessage {
+    bytes: Vec<u8>,
+    sent: oneshot::Sender<Result<()>>,
+}
+
+/// Outgoing lanes of a connection and the task giving their messages streams
+struct Lanes {
+    scheduler: parking_lot::Mutex<LaneScheduler<OutgoingMessage>>,
+    /// In-flight slots shared by data and bulk streams
+    slots: Arc<Semaphore>,
+    wake: Notify,
+    dispatcher: parking_lot::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Lanes {
+    fn new(config: LaneConfig) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(config.max_inflight_streams)),
+            scheduler: parking_lot::Mutex::new(LaneScheduler::new(config)),
+            wake: Notify::new(),
+            dispatcher: parking_lot::Mutex::new(None),
+        }
+    }
 }
 
 impl Connection {
@@ -48,9 +88,24 @@ impl Connection {
             pending_requests: Arc::new(Mutex::new(HashMap::new())),
             request_sequence: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             message_handlers: Arc::new(RwLock::new(Vec::new())),
+            replay_guard: None,
+            early_data: Arc::new(parking_lot::Mutex::new(None)),
+            lanes: Arc::new(Lanes::new(LaneConfig::default())),
         })
     }
     
+    /// Deliver early data messages that pass `guard`
+    pub fn with_replay_guard(mut self, guard: Arc<ReplayGuard>) -> Self {
+        self.replay_guard = Some(guard);
+        self
+    }
+    
+    /// Schedule outgoing messages with `config`'s lane weights
+    pub fn with_lanes(mut self, config: LaneConfig) -> Self {
+        self.lanes = Arc::new(Lanes::new(config));
+        self
+    }
+    
     /// Perform handshake to exchange node IDs
     pub async fn handshake(&self) -> Result<NodeId> {
         debug!("Performing handshake");
@@ -110,29 +165,110 @@ impl Connection {
         *self.remote_node_id.read().await
     }
     
+    /// Send a message as 0-RTT early data
+    ///
+    /// The message is kept so that it is resent once the handshake completes
+    /// if the server refuses it as stale, replayed or over its replay window.
+    pub async fn send_early_data(&self, message: TransportMessage) -> Result<()> {
+        *self.early_data.lock() = Some(message.clone());
+        self.send_message(message).await
+    }
+    
     /// Send a message
+    ///
+    /// The message waits in its lane until the scheduler gives it a stream,
+    /// and this returns once the stream is finished.
     pub async fn send_message(&self, message: TransportMessage) -> Result<()> {
-        let mut send_stream = self.quinn_connection
+        let class = message.lane();
+        let message_bytes = message.to_bytes()?;
+        let size = message_bytes.len();
+        
+        let (sent_sender, sent) = oneshot::channel();
+        self.lanes.scheduler.lock()
+            .push(class, size, OutgoingMessage { bytes: message_bytes, sent: sent_sender })
+            .map_err(|_| TransportError::Overloaded { 
+                reason: format!("{:?} lane is full", class) 
+            })?;
+        self.ensure_dispatcher();
+        self.lanes.wake.notify_one();
+        
+        tokio::select! {
+            biased;
+            result = sent => result.map_err(|_| TransportError::Connection { 
+                message: "Message dropped before it was sent".to_string() 
+            })??,
+            reason = self.quinn_connection.closed() => {
+                return Err(TransportError::Connection { 
+                    message: format!("Connection closed before the message was sent: {}", reason) 
+                });
+            }
+        }
+        
+        // Update statistics
+        let mut stats = self.stats.write().await;
+        stats.messages_sent += 1;
+        stats.bytes_sent += size as u64;
+        
+        trace!("{:?} message sent: {} bytes", class, size);
+        Ok(())
+    }
+    
+    /// Start the task giving queued messages streams, unless it is running
+    fn ensure_dispatcher(&self) {
+        let mut dispatcher = self.lanes.dispatcher.lock();
+        if dispatcher.is_none() {
+            *dispatcher = Some(tokio::spawn(Self::dispatch(
+                Arc::clone(&self.lanes),
+                self.quinn_connection.clone(),
+            )));
+        }
+    }
+    
+    /// Hand queued messages streams in weighted order until the connection closes
+    async fn dispatch(lanes: Arc<Lanes>, connection: quinn::Connection) {
+        loop {
+            let next = lanes.scheduler.lock()
+                .pop(|class| class.is_latency_sensitive() || lanes.slots.available_permits() > 0);
+            let Some((class, message)) = next else {
+                tokio::select! {
+                    _ = lanes.wake.notified() => continue,
+                    _ = connection.closed() => break,
+                }
+            };
+            
+            // This task is the only one taking slots, so one is free
+            let slot = if class.is_latency_sensitive() {
+                None
+            } else {
+                Arc::clone(&lanes.slots).try_acquire_owned().ok()
+            };
+            let connection = connection.clone();
+            let lanes = Arc::clone(&lanes);
+            tokio::spawn(async move {
+                let result = Self::write_stream(&connection, class, &message.bytes).await;
+                let _ = message.sent.send(result);
+                drop(slot);
+                lanes.wake.notify_one();
+            });
+        }
+    }
+    
+    /// Send one message on a stream of its own, at its class's priority
+    async fn write_stream(connection: &quinn::Connection, class: TrafficClass, message: &[u8]) -> Result<()> {
+        let mut send_stream = connection
             .open_uni()
             .await
             .map_err(|e| TransportError::Stream { 
                 message: format!("Failed to open send stream: {}", e) 
             })?;
+        // Only fails if the stream is already gone, which the write reports
+        let _ = send_stream.set_priority(class.stream_priority());
         
-        let message_bytes = message.to_bytes()?;
-        Self::write_message(&mut send_stream, &message_bytes).await?;
-        
+        Self::write_message(&mut send_stream, message).await?;
         send_stream.finish().await
             .map_err(|e| TransportError::Stream { 
                 message: format!("Failed to finish send stream: {}", e) 
             })?;
-        
-        // Update statistics
-        let mut stats = self.stats.write().await;
-        stats.messages_sent += 1;
-        stats.bytes_sent += message_bytes.len() as u64;
-        
-        trace!("Message sent: {} bytes", message_bytes.len());
         Ok(())
     }
     
@@ -167,13 +303,23 @@ impl Connection {
                 message: "Request cancelled".to_string() 
             })?;
         
+        if response.message_type == MessageType::Unavailable {
+            return Err(TransportError::Overloaded {
+                reason: String::from_utf8_lossy(&response.payload).into_owned(),
+            });
+        }
+        
         Ok(response)
     }
     
     /// Handle incoming messages
+    ///
+    /// Messages go to `message_sender`'s bounded queue; `admission` decides
+    /// which to shed as it fills.
     pub async fn handle_messages(
         &self,
-        message_sender: mpsc::UnboundedSender<(NodeId, TransportMessage)>,
+        message_sender: mpsc::Sender<(NodeId, TransportMessage)>,
+        admission: Arc<AdmissionController>,
     ) -> Result<()> {
         // Add message handler
         self.message_handlers.write().await.push(message_sender);
@@ -186,6 +332,10 @@ impl Connection {
                     let stats = Arc::clone(&self.stats);
                     let pending_requests = Arc::clone(&self.pending_requests);
                     let remote_node_id = self.remote_node_id().await;
+                    let connection = self.quinn_connection.clone();
+                    let admission = Arc::clone(&admission);
+                    let replay_guard = self.replay_guard.clone();
+                    let early_data = Arc::clone(&self.early_data);
                     
                     tokio::spawn(async move {
                         if let Some(remote_id) = remote_node_id {
@@ -195,6 +345,10 @@ impl Connection {
                                 handlers,
                                 stats,
                                 pending_requests,
+                                connection,
+                                admission,
+                                replay_guard,
+                                early_data,
                             ).await {
                                 error!("Failed to handle incoming stream: {}", e);
                             }
@@ -219,13 +373,25 @@ impl Connection {
     async fn handle_incoming_stream(
         mut recv_stream: RecvStream,
         remote_node_id: NodeId,
-        handlers: Arc<RwLock<Vec<mpsc::UnboundedSender<(NodeId, TransportMessage)>>>>,
+        handlers: Arc<RwLock<Vec<mpsc::Sender<(NodeId, TransportMessage)>>>>,
         stats: Arc<RwLock<ConnectionStats>>,
         pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<TransportMessage>>>>,
+        connection: quinn::Connection,
+        admission: Arc<AdmissionController>,
+        replay_guard: Option<Arc<ReplayGuard>>,
+        early_data: Arc<parking_lot::Mutex<Option<TransportMessage>>>,
     ) -> Result<()> {
         let message_bytes = Self::read_message(&mut recv_stream).await?;
         let message = TransportMessage::from_bytes(&message_bytes)?;
         
+        // Early data can be replayed by anyone who captured it. A refused
+        // message is sent back for the client to resend over 1-RTT; a replay
+        // attacker has no keys to complete the handshake and read the answer.
+        if recv_stream.is_0rtt() && !replay_guard.is_some_and(|guard| guard.admit(&message)) {
+            warn!("Refused early data message {} from {}: not idempotent, stale or replayed", message.sequence, remote_node_id);
+            return Self::reply_early_data_rejected(&connection, &message).await;
+        }
+        
         // Update statistics
         {
             let mut stats_guard = stats.write().await;
@@ -235,6 +401,14 @@ impl Connection {
         
         // Handle control messages
         if message.message_type == MessageType::Control {
+            if message.payload == EARLY_DATA_REJECTED {
+                let refused = early_data.lock().take_if(|early| early.sequence == message.sequence);
+                if let Some(refused) = refused {
+                    debug!("Resending early data message {} to {} after the handshake", refused.sequence, remote_node_id);
+                    Self::write_stream(&connection, refused.lane(), &refused.to_bytes()?).await?;
+                }
+                return Ok(());
+            }
             if message.payload == b"ping" {
                 // Send pong response
                 // This would require opening a new stream - simplified for now
@@ -252,17 +426,61 @@ impl Connection {
             }
         }
         
-        // Send to message handlers
+        // Send to message handlers, shedding by priority as their queues fill
         let handlers_guard = handlers.read().await;
+        let queue_capacity = admission.config().request_queue_capacity;
+        let mut shed = false;
         for handler in handlers_guard.iter() {
-            if let Err(e) = handler.send((remote_node_id, message.clone())) {
+            let queued = handler.max_capacity().min(queue_capacity).saturating_sub(handler.capacity());
+            if !admission.admit(message.admission_priority(), queued) {
+                shed = true;
+                continue;
+            }
+            if let Err(e) = handler.try_send((remote_node_id, message.clone())) {
                 warn!("Failed to send message to handler: {}", e);
+                shed = true;
             }
         }
+        drop(handlers_guard);
+        
+        if shed && message.sequence != 0 {
+            debug!("Shed {:?} request {} from {}", message.priority, message.sequence, remote_node_id);
+            Self::reply_unavailable(&connection, &message, "server overloaded").await?;
+        }
         
         Ok(())
     }
     
+    /// Tell the client its early data message was refused so it resends it
+    async fn reply_early_data_rejected(connection: &quinn::Connection, refused: &TransportMessage) -> Result<()> {
+        let mut response = TransportMessage::new(
+            MessageType::Control,
+            refused.destination.unwrap_or(refused.source),
+            Some(refused.source),
+            EARLY_DATA_REJECTED.to_vec(),
+        );
+        response.sequence = refused.sequence;
+        
+        Self::write_stream(connection, response.lane(), &response.to_bytes()?).await
+    }
+    
+    /// Answer a shed request so the caller fails fast instead of timing out
+    async fn reply_unavailable(
+        connection: &quinn::Connection,
+        request: &TransportMessage,
+        reason: &str,
+    ) -> Result<()> {
+        let mut response = TransportMessage::new(
+            MessageType::Unavailable,
+            request.destination.unwrap_or(request.source),
+            Some(request.source),
+            reason.as_bytes().to_vec(),
+        );
+        response.sequence = request.sequence;
+        
+        Self::write_stream(connection, response.lane(), &response.to_bytes()?).await
+    }
+    
     /// Write a message to a stream
     async fn write_message(stream: &mut SendStream, message: &[u8]) -> Result<()> {
         // Write message length first
@@ -314,6 +532,21 @@ impl Connection {
         self.quinn_connection.close(0u32.into(), b"connection closed");
     }
     
+    /// Whether the connection is still open, neither closed by us nor lost
+    pub fn is_healthy(&self) -> bool {
+        self.quinn_connection.close_reason().is_none()
+    }
+    
+    /// Address the peer's packets currently come from, following migrations
+    pub fn remote_address(&self) -> std::net::SocketAddr {
+        self.quinn_connection.remote_address()
+    }
+    
+    /// Counters of each outgoing lane
+    pub fn lane_stats(&self) -> Vec<(TrafficClass, LaneStats)> {
+        self.lanes.scheduler.lock().stats()
+    }
+    
     /// Get connection statistics
     pub async fn stats(&self) -> ConnectionStats {
         self.stats.read().await.clone()
@@ -324,7 +557,7 @@ impl Connection {
         ConnectionInfo {
             local_node_id: self.local_node_id,
             remote_node_id: self.remote_node_id().await,
-            remote_address: self.quinn_connection.remote_address(),
+            remote_address: self.remote_address(),
             stats: self.stats().await,
         }
     }
@@ -361,6 +594,24 @@ pub struct ConnectionInfo {
     pub stats: ConnectionStats,
 }
 
+impl PooledConnection for Connection {
+    fn is_healthy(&self) -> bool {
+        Connection::is_healthy(self)
+    }
+    
+    fn close(&self) {
+        self.quinn_connection.close(0u32.into(), b"connection closed");
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(dispatcher) = self.lanes.dispatcher.lock().take() {
+            dispatcher.abort();
+        }
+    }
+}
+
 impl std::fmt::Debug for Connection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Connection")
@@ -38,6 +38,9 @@ pub enum TransportError {
     #[error("Timeout after {duration_ms}ms")]
     Timeout { duration_ms: u64 },
 
+    #[error("Service unavailable: {reason}")]
+    Overloaded { reason: String },
+
     #[error("Network error: {0}")]
     Network(#[from] std::io::Error),
 
@@ -54,6 +57,7 @@ impl TransportError {
         match self {
             TransportError::Network(_) => true,
             TransportError::Timeout { .. } => true,
+            TransportError::Overloaded { .. } => true,
             TransportError::Connection { .. } => true,
             TransportError::Quinn(e) => match e {
                 quinn::ConnectionError::TimedOut => true,
@@ -77,6 +81,7 @@ impl TransportError {
             TransportError::Authentication { .. } => "authentication",
             TransportError::ProtocolVersion { .. } => "protocol",
             TransportError::Timeout { .. } => "timeout",
+            TransportError::Overloaded { .. } => "overloaded",
             TransportError::Network(_) => "network",
             TransportError::Quinn(_) => "quinn",
             TransportError::Rustls(_) => "rustls",
@@ -2,29 +2,51 @@
 //! 
 //! This module provides the foundational transport layer for Nexus using QUIC over IPv6.
 //! Key features include:
-//! - Certificate-based authentication
-//! - Zero-round-trip connection resumption
-//! - Connection migration support
+//! - Certificate-based authentication, with node keys optionally held in a TPM
+//! - Automatic certificate renewal, swapped into running servers without dropping connections
+//! - Session resumption with 0-RTT early data for idempotent messages
+//! - Connection migration across address changes, with path change events
 //! - Built-in flow control and congestion control
 //! - Multiplexed streams within connections
+//! - Bounded request queues with priority-based load shedding
+//! - Weighted priority lanes so control and consensus traffic is not starved by bulk transfers
+//! - Pooled outbound connections with per-peer limits and idle reaping
 
 pub mod client;
 pub mod server;
 pub mod config;
 pub mod error;
 pub mod certificate;
+pub mod ca;
+pub mod renewal;
+mod signer;
+mod tls_policy;
 pub mod stream;
 pub mod connection;
+pub mod admission;
+pub mod pool;
+pub mod resumption;
+pub mod migration;
+pub mod priority;
 
 pub use client::QuicClient;
 pub use server::QuicServer;
 pub use config::TransportConfig;
 pub use error::{TransportError, Result};
-pub use certificate::{CertificateManager, generate_self_signed_cert};
+pub use certificate::{CertificateManager, generate_self_signed_cert, generate_signer_cert};
+pub use ca::{CaRotation, CertificateKey, ClusterCa, IssuedCertificate, RotationPhase, TrustBundle};
+pub use renewal::{CertificateRenewal, RenewalConfig, RenewalHook, RenewalHookId};
 pub use stream::{QuicStream, StreamType};
 pub use connection::{Connection, ConnectionInfo};
+pub use admission::{AdmissionConfig, AdmissionController, AdmissionStats, RequestPriority};
+pub use pool::{ConnectionPool, PoolConfig, PoolLease, PoolStats, PooledConnection};
+pub use resumption::{
+    FileSessionStore, MemorySessionStore, ReplayGuard, ResumptionConfig, ResumptionStats, SessionStore,
+};
+pub use migration::{MigrationConfig, PathEvent, PathTracker};
+pub use priority::{LaneConfig, LaneScheduler, LaneStats, TrafficClass};
 
-use nexus_shared::{NodeId, NexusError};
+use nexus_shared::{DetachedSignature, NodeId, NexusError};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -52,6 +74,8 @@ pub enum MessageType {
     Control,
     /// Stream management
     Stream,
+    /// Request shed under load; the caller should back off and retry
+    Unavailable,
 }
 
 /// Transport message envelope
@@ -69,6 +93,18 @@ pub struct TransportMessage {
     pub timestamp: u64,
     /// Message sequence number
     pub sequence: u64,
+    /// Priority used for load shedding
+    #[serde(default)]
+    pub priority: RequestPriority,
+    /// Safe to deliver twice, so it may be sent as 0-RTT early data
+    #[serde(default)]
+    pub idempotent: bool,
+    /// Lane the message is sent in
+    #[serde(default)]
+    pub traffic_class: TrafficClass,
+    /// Sender's signature over the payload, for services that require one
+    #[serde(default)]
+    pub signature: Option<DetachedSignature>,
 }
 
 impl TransportMessage {
@@ -90,6 +126,50 @@ impl TransportMessage {
                 .expect("Time went backwards")
                 .as_millis() as u64,
             sequence: 0, // Will be set by connection
+            priority: RequestPriority::default(),
+            idempotent: false,
+            traffic_class: TrafficClass::default(),
+            signature: None,
+        }
+    }
+    
+    /// Set the priority used for load shedding
+    pub fn with_priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+    
+    /// Set the lane the message is sent in
+    pub fn with_traffic_class(mut self, traffic_class: TrafficClass) -> Self {
+        self.traffic_class = traffic_class;
+        self
+    }
+    
+    /// Attach a detached signature over the payload
+    pub fn with_signature(mut self, signature: DetachedSignature) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+    
+    /// Mark the message safe to deliver twice, allowing it to be sent as early data
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+    
+    /// Priority the server admits the message at; protocol messages are never shed first
+    pub fn admission_priority(&self) -> RequestPriority {
+        match self.message_type {
+            MessageType::Handshake | MessageType::Control => RequestPriority::Critical,
+            _ => self.priority,
+        }
+    }
+    
+    /// Lane the message is sent in; protocol messages always take the control lane
+    pub fn lane(&self) -> TrafficClass {
+        match self.message_type {
+            MessageType::Handshake | MessageType::Control | MessageType::Unavailable => TrafficClass::Control,
+            _ => self.traffic_class,
         }
     }
     
@@ -0,0 +1,140 @@
+//! Connection migration across network changes
+//!
+//! QUIC identifies a connection by its connection IDs rather than by its
+//! addresses, so a connection survives either end moving. When a peer's
+//! address changes, for example after a NAT rebinding, quinn validates the
+//! new path and carries on. When this node fails over to another interface,
+//! [`QuicClient::rebind`](crate::QuicClient::rebind) moves the endpoint to a
+//! new socket. Either way the existing [`Connection`](crate::Connection)
+//! objects stay valid. Nothing tells the application the path changed, so
+//! the tracker polls each connection's remote address and broadcasts a
+//! [`PathEvent`] on every change. The networking layer uses these events to
+//! update service registrations.
+
+use nexus_shared::NodeId;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Migration tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationConfig {
+    /// How often connections are checked for a new remote address
+    pub path_check_interval: Duration,
+
+    /// Path events buffered for slow subscribers
+    pub event_capacity: usize,
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self {
+            path_check_interval: Duration::from_secs(1),
+            event_capacity: 256,
+        }
+    }
+}
+
+impl MigrationConfig {
+    /// Validate configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path_check_interval.is_zero() || self.event_capacity == 0 {
+            return Err("Path check interval and event capacity must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A connection path that changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathEvent {
+    /// This endpoint moved to a new local address
+    LocalAddressChanged { old: SocketAddr, new: SocketAddr },
+    /// A peer's packets now come from a new address
+    PeerAddressChanged { node_id: NodeId, old: SocketAddr, new: SocketAddr },
+}
+
+/// Last known address of each peer, broadcasting changes
+#[derive(Debug)]
+pub struct PathTracker {
+    paths: Mutex<HashMap<NodeId, SocketAddr>>,
+    events: broadcast::Sender<PathEvent>,
+    migrations: AtomicU64,
+}
+
+impl PathTracker {
+    pub fn new(event_capacity: usize) -> Self {
+        let (events, _) = broadcast::channel(event_capacity.max(1));
+        Self { paths: Mutex::new(HashMap::new()), events, migrations: AtomicU64::new(0) }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PathEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record where a peer's connection currently leads, returning the change if it moved
+    pub fn observe(&self, node_id: NodeId, address: SocketAddr) -> Option<PathEvent> {
+        let old = self.paths.lock().insert(node_id, address)?;
+        if old == address {
+            return None;
+        }
+        tracing::info!("Peer {} migrated from {} to {}", node_id, old, address);
+        Some(self.publish(PathEvent::PeerAddressChanged { node_id, old, new: address }))
+    }
+
+    /// Record that this endpoint moved to a new local address
+    pub fn local_address_changed(&self, old: SocketAddr, new: SocketAddr) -> PathEvent {
+        tracing::info!("Local endpoint migrated from {} to {}", old, new);
+        self.publish(PathEvent::LocalAddressChanged { old, new })
+    }
+
+    /// Stop tracking a peer whose connections are gone
+    pub fn forget(&self, node_id: &NodeId) {
+        self.paths.lock().remove(node_id);
+    }
+
+    /// Keep tracking only the given peers
+    pub fn retain(&self, connected: impl Fn(&NodeId) -> bool) {
+        self.paths.lock().retain(|node_id, _| connected(node_id));
+    }
+
+    /// Path changes seen so far, local and remote
+    pub fn migrations(&self) -> u64 {
+        self.migrations.load(Ordering::Relaxed)
+    }
+
+    fn publish(&self, event: PathEvent) -> PathEvent {
+        self.migrations.fetch_add(1, Ordering::Relaxed);
+        // Nobody listening is fine; the change is already in effect
+        let _ = self.events.send(event.clone());
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_peer_address_changes_are_broadcast() {
+        let tracker = PathTracker::new(8);
+        let mut events = tracker.subscribe();
+        let node_id = NodeId::random();
+        let (before, after): (SocketAddr, SocketAddr) = ("10.0.0.1:7777".parse().unwrap(), "192.168.1.9:40123".parse().unwrap());
+
+        assert_eq!(tracker.observe(node_id, before), None);
+        assert_eq!(tracker.observe(node_id, before), None);
+        let moved = tracker.observe(node_id, after).unwrap();
+        assert_eq!(moved, PathEvent::PeerAddressChanged { node_id, old: before, new: after });
+        assert_eq!(events.recv().await.unwrap(), moved);
+
+        // A peer seen again after being forgotten starts fresh
+        tracker.forget(&node_id);
+        assert_eq!(tracker.observe(node_id, before), None);
+        assert_eq!(tracker.migrations(), 1);
+    }
+}
@@ -0,0 +1,332 @@
+//! Outbound connection pooling
+//!
+//! The client keeps a small pool of connections to each peer instead of a
+//! single connection it never checks. A caller borrows a connection for a
+//! request and returns it when the lease drops. Before a connection is handed
+//! out it is checked, and one the peer closed or that sat idle past
+//! `max_idle` is discarded, so a stale connection never fails the first
+//! request after a quiet period. Idle connections are also reaped in the
+//! background. Once a peer has `max_connections_per_peer` connections, new
+//! requests share the least busy one, since QUIC multiplexes streams, rather
+//! than opening more.
+
+use crate::Connection;
+use nexus_shared::NodeId;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Connection pool configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolConfig {
+    /// Connections opened to one peer at most
+    pub max_connections_per_peer: usize,
+
+    /// How long a connection may sit unused before it is closed
+    pub max_idle: Duration,
+
+    /// How often idle and closed connections are reaped
+    pub reap_interval: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_peer: 4,
+            max_idle: Duration::from_secs(90),
+            reap_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Validate configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_connections_per_peer == 0 {
+            return Err("Maximum connections per peer must be greater than zero".to_string());
+        }
+        if self.max_idle.is_zero() || self.reap_interval.is_zero() {
+            return Err("Pool idle time and reap interval must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A connection the pool can check and close
+pub trait PooledConnection: Send + Sync {
+    /// Whether the connection can still carry requests
+    fn is_healthy(&self) -> bool;
+
+    fn close(&self);
+}
+
+/// Pool counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub peers: usize,
+    pub open_connections: usize,
+    /// Open connections without a request in flight
+    pub idle_connections: usize,
+    /// Checkouts served by an existing connection
+    pub reused: u64,
+    /// Connections opened because none could be reused
+    pub opened: u64,
+    /// Checkouts that shared a busy connection because the peer was at its limit
+    pub shared_at_limit: u64,
+    /// Connections found closed by the peer and discarded
+    pub discarded_unhealthy: u64,
+    /// Connections closed after sitting idle
+    pub reaped_idle: u64,
+}
+
+struct Slot<C> {
+    connection: Arc<C>,
+    in_flight: AtomicUsize,
+    last_used: Mutex<Instant>,
+}
+
+impl<C> Slot<C> {
+    fn idle_for(&self, now: Instant) -> Option<Duration> {
+        (self.in_flight.load(Ordering::Acquire) == 0).then(|| now.saturating_duration_since(*self.last_used.lock()))
+    }
+}
+
+/// A borrowed connection, returned to the pool when dropped
+pub struct PoolLease<C = Connection> {
+    slot: Arc<Slot<C>>,
+}
+
+impl<C> PoolLease<C> {
+    fn new(slot: Arc<Slot<C>>) -> Self {
+        slot.in_flight.fetch_add(1, Ordering::AcqRel);
+        *slot.last_used.lock() = Instant::now();
+        Self { slot }
+    }
+
+    /// The underlying connection, outliving the lease
+    pub fn connection(&self) -> Arc<C> {
+        Arc::clone(&self.slot.connection)
+    }
+}
+
+impl<C> Deref for PoolLease<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.slot.connection
+    }
+}
+
+impl<C> Drop for PoolLease<C> {
+    fn drop(&mut self) {
+        *self.slot.last_used.lock() = Instant::now();
+        self.slot.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Connections to each peer, with per-peer limits and idle reaping
+pub struct ConnectionPool<C = Connection> {
+    config: PoolConfig,
+    peers: Mutex<HashMap<NodeId, Vec<Arc<Slot<C>>>>>,
+    stats: Mutex<PoolStats>,
+}
+
+impl<C: PooledConnection> ConnectionPool<C> {
+    pub fn new(config: PoolConfig) -> Self {
+        Self { config, peers: Mutex::new(HashMap::new()), stats: Mutex::new(PoolStats::default()) }
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Borrow a healthy connection to `peer`, or `None` when a new one should be opened
+    pub fn checkout(&self, peer: &NodeId) -> Option<PoolLease<C>> {
+        let mut peers = self.peers.lock();
+        let slots = peers.get_mut(peer)?;
+        self.discard_stale(slots, Instant::now());
+
+        let idle = slots.iter().find(|slot| slot.in_flight.load(Ordering::Acquire) == 0);
+        let slot = match idle {
+            Some(slot) => Arc::clone(slot),
+            None if slots.len() < self.config.max_connections_per_peer => {
+                if slots.is_empty() {
+                    peers.remove(peer);
+                }
+                return None;
+            }
+            None => {
+                self.stats.lock().shared_at_limit += 1;
+                Arc::clone(slots.iter().min_by_key(|slot| slot.in_flight.load(Ordering::Acquire))?)
+            }
+        };
+        self.stats.lock().reused += 1;
+        Some(PoolLease::new(slot))
+    }
+
+    /// Add a newly opened connection to `peer` and borrow it
+    ///
+    /// If concurrent checkouts took the peer to its limit meanwhile, the new
+    /// connection is closed and the least busy existing one is lent instead.
+    pub fn insert(&self, peer: NodeId, connection: Arc<C>) -> PoolLease<C> {
+        let mut peers = self.peers.lock();
+        let slots = peers.entry(peer).or_default();
+        if slots.len() >= self.config.max_connections_per_peer {
+            if let Some(slot) = slots.iter().min_by_key(|slot| slot.in_flight.load(Ordering::Acquire)) {
+                connection.close();
+                self.stats.lock().shared_at_limit += 1;
+                return PoolLease::new(Arc::clone(slot));
+            }
+        }
+        let slot = Arc::new(Slot { connection, in_flight: AtomicUsize::new(0), last_used: Mutex::new(Instant::now()) });
+        slots.push(Arc::clone(&slot));
+        self.stats.lock().opened += 1;
+        PoolLease::new(slot)
+    }
+
+    /// Close connections that sat idle past `max_idle` or that the peer closed
+    pub fn reap(&self) -> usize {
+        let now = Instant::now();
+        let mut peers = self.peers.lock();
+        let mut closed = 0;
+        for slots in peers.values_mut() {
+            closed += self.discard_stale(slots, now);
+        }
+        peers.retain(|_, slots| !slots.is_empty());
+        closed
+    }
+
+    /// Close and forget every connection to `peer`
+    pub fn remove_peer(&self, peer: &NodeId) -> usize {
+        let slots = self.peers.lock().remove(peer).unwrap_or_default();
+        for slot in &slots {
+            slot.connection.close();
+        }
+        slots.len()
+    }
+
+    /// Close and forget every connection
+    pub fn clear(&self) {
+        for (_, slots) in self.peers.lock().drain() {
+            for slot in slots {
+                slot.connection.close();
+            }
+        }
+    }
+
+    /// Peers with at least one open connection
+    pub fn peers(&self) -> Vec<NodeId> {
+        self.peers.lock().keys().cloned().collect()
+    }
+
+    /// Every pooled connection with the peer it leads to
+    pub fn connections(&self) -> Vec<(NodeId, Arc<C>)> {
+        let peers = self.peers.lock();
+        peers
+            .iter()
+            .flat_map(|(peer, slots)| slots.iter().map(move |slot| (*peer, Arc::clone(&slot.connection))))
+            .collect()
+    }
+
+    pub fn contains(&self, peer: &NodeId) -> bool {
+        self.peers.lock().contains_key(peer)
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let now = Instant::now();
+        let peers = self.peers.lock();
+        let mut stats = self.stats.lock().clone();
+        stats.peers = peers.len();
+        stats.open_connections = peers.values().map(Vec::len).sum();
+        stats.idle_connections = peers.values().flatten().filter(|slot| slot.idle_for(now).is_some()).count();
+        stats
+    }
+
+    /// Drop unhealthy connections and close idle-expired ones, returning how many went
+    fn discard_stale(&self, slots: &mut Vec<Arc<Slot<C>>>, now: Instant) -> usize {
+        let before = slots.len();
+        let mut stats = self.stats.lock();
+        slots.retain(|slot| {
+            if !slot.connection.is_healthy() {
+                stats.discarded_unhealthy += 1;
+                return false;
+            }
+            if slot.idle_for(now).is_some_and(|idle| idle >= self.config.max_idle) {
+                slot.connection.close();
+                stats.reaped_idle += 1;
+                return false;
+            }
+            true
+        });
+        before - slots.len()
+    }
+}
+
+impl<C> std::fmt::Debug for ConnectionPool<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("config", &self.config)
+            .field("peers", &self.peers.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    #[derive(Default)]
+    struct FakeConnection {
+        closed: AtomicBool,
+    }
+
+    impl PooledConnection for FakeConnection {
+        fn is_healthy(&self) -> bool {
+            !self.closed.load(Ordering::SeqCst)
+        }
+
+        fn close(&self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_pool_limits_and_discards_stale_connections() {
+        let pool = ConnectionPool::<FakeConnection>::new(PoolConfig {
+            max_connections_per_peer: 2,
+            max_idle: Duration::from_millis(20),
+            ..Default::default()
+        });
+        let peer = NodeId::random();
+        assert!(pool.checkout(&peer).is_none());
+
+        // Busy connections are not reused until the peer reaches its limit
+        let first = pool.insert(peer, Arc::new(FakeConnection::default()));
+        assert!(pool.checkout(&peer).is_none());
+        let second = pool.insert(peer, Arc::new(FakeConnection::default()));
+        let shared = pool.checkout(&peer).unwrap();
+        let surplus = Arc::new(FakeConnection::default());
+        drop(pool.insert(peer, Arc::clone(&surplus)));
+        assert!(surplus.closed.load(Ordering::SeqCst));
+        assert_eq!(pool.stats().open_connections, 2);
+
+        // A connection the peer closed is never handed out
+        first.close();
+        drop((first, shared, second));
+        let reused = pool.checkout(&peer).unwrap();
+        assert!(reused.is_healthy());
+        drop(reused);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(pool.reap(), 1);
+        assert!(!pool.contains(&peer));
+
+        let stats = pool.stats();
+        assert_eq!((stats.opened, stats.shared_at_limit, stats.discarded_unhealthy, stats.reaped_idle), (2, 2, 1, 1));
+    }
+}
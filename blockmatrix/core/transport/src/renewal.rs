@@ -0,0 +1,137 @@
+//! Automatic renewal of self-signed node certificates
+//!
+//! A renewal task checks the node certificate periodically and re-issues it
+//! once it is within `renew_before` of expiry. Hooks registered on the
+//! `CertificateManager` run after every renewal; the QUIC server uses one to
+//! swap the new certificate into its endpoint, so new handshakes present it
+//! while established connections carry on undisturbed. Certificates issued by
+//! the cluster CA are renewed through `CaRotation` instead and are skipped.
+
+use crate::{CertificateManager, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// When and how often to renew the node certificate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenewalConfig {
+    /// Run the renewal task
+    pub enabled: bool,
+
+    /// How often to check the certificate's expiry
+    pub check_interval: Duration,
+
+    /// Renew once the certificate expires within this window
+    pub renew_before: Duration,
+}
+
+impl Default for RenewalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval: Duration::from_secs(60 * 60),
+            renew_before: Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// A completed renewal, passed to every hook
+#[derive(Debug, Clone)]
+pub struct CertificateRenewal {
+    pub subject_name: String,
+    /// Expiry of the certificate that was replaced
+    pub previous_expiry: SystemTime,
+    /// Expiry of the new certificate
+    pub expires_at: SystemTime,
+    pub renewed_at: SystemTime,
+}
+
+/// Reacts to a renewed certificate, e.g. by reloading TLS configuration
+///
+/// Hooks run in registration order. A failing hook is logged and does not
+/// stop the others or undo the renewal.
+pub trait RenewalHook: Send + Sync {
+    fn certificate_renewed(&self, certificates: &CertificateManager, renewal: &CertificateRenewal) -> Result<()>;
+}
+
+impl<F> RenewalHook for F
+where
+    F: Fn(&CertificateManager, &CertificateRenewal) -> Result<()> + Send + Sync,
+{
+    fn certificate_renewed(&self, certificates: &CertificateManager, renewal: &CertificateRenewal) -> Result<()> {
+        self(certificates, renewal)
+    }
+}
+
+/// Handle for removing a registered hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenewalHookId(u64);
+
+/// Hooks registered on a certificate manager
+#[derive(Default)]
+pub(crate) struct RenewalHooks {
+    next_id: u64,
+    hooks: Vec<(RenewalHookId, Arc<dyn RenewalHook>)>,
+}
+
+impl RenewalHooks {
+    pub(crate) fn register(&mut self, hook: Arc<dyn RenewalHook>) -> RenewalHookId {
+        self.next_id += 1;
+        let id = RenewalHookId(self.next_id);
+        self.hooks.push((id, hook));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: RenewalHookId) -> bool {
+        let before = self.hooks.len();
+        self.hooks.retain(|(hook_id, _)| *hook_id != id);
+        self.hooks.len() != before
+    }
+
+    /// Hooks to run, cloned so none run under the registry lock
+    pub(crate) fn snapshot(&self) -> Vec<Arc<dyn RenewalHook>> {
+        self.hooks.iter().map(|(_, hook)| Arc::clone(hook)).collect()
+    }
+}
+
+/// Whether a certificate expiring at `expires_at` is due for renewal at `now`
+pub fn renewal_due(expires_at: SystemTime, renew_before: Duration, now: SystemTime) -> bool {
+    now + renew_before >= expires_at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_renewal_runs_hooks() {
+        let certificates = CertificateManager::new_self_signed(
+            "test-node".to_string(),
+            1,
+            Duration::from_secs(3600),
+        ).await.unwrap();
+
+        assert!(!certificates.needs_renewal(Duration::from_secs(60 * 60)));
+        assert!(certificates.needs_renewal(Duration::from_secs(2 * 24 * 60 * 60)));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let id = certificates.on_renewal(Arc::new(move |_: &CertificateManager, renewal: &CertificateRenewal| -> Result<()> {
+            assert_eq!(renewal.subject_name, "test-node");
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }));
+
+        let original = certificates.server_certificate().read().serialize_der().unwrap();
+        let renewal = certificates.renew().await.unwrap();
+        assert!(renewal.expires_at >= renewal.previous_expiry);
+        assert_eq!(certificates.expires_at(), renewal.expires_at);
+        assert_ne!(certificates.server_certificate().read().serialize_der().unwrap(), original);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert!(certificates.remove_renewal_hook(id));
+        certificates.renew().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
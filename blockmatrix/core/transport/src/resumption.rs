@@ -0,0 +1,380 @@
+//! TLS session resumption and 0-RTT
+//!
+//! Servers issue session tickets; clients keep them in a pluggable
+//! [`SessionStore`], on disk by default so they survive restarts. A client
+//! holding a ticket for a server resumes without a full handshake and may
+//! send its first messages as 0-RTT early data, saving a round trip. Early
+//! data can be captured and replayed by an attacker, so only messages marked
+//! idempotent are sent early, and servers pass early messages through a
+//! [`ReplayGuard`] that drops duplicates and messages outside the replay
+//! window. Early data the server refuses is sent again once the handshake
+//! completes.
+
+use crate::{Result, TransportError, TransportMessage};
+use nexus_shared::NodeId;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Session resumption configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumptionConfig {
+    pub enabled: bool,
+
+    /// Send idempotent messages as 0-RTT early data when resuming
+    pub early_data: bool,
+
+    /// File session tickets are kept in; `None` keeps them in memory only
+    pub session_file: Option<PathBuf>,
+
+    /// Session tickets kept at most
+    pub max_sessions: usize,
+
+    /// How far an early message's timestamp may be from the server's clock
+    pub replay_window: Duration,
+
+    /// Early messages remembered for replay detection
+    pub replay_cache_capacity: usize,
+}
+
+impl Default for ResumptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            early_data: true,
+            session_file: Some(PathBuf::from("./data/quic-sessions.bin")),
+            max_sessions: 256,
+            replay_window: Duration::from_secs(10),
+            replay_cache_capacity: 65536,
+        }
+    }
+}
+
+impl ResumptionConfig {
+    /// Validate configuration
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.enabled && self.max_sessions == 0 {
+            return Err("Resumption needs room for at least one session".to_string());
+        }
+        if self.early_data && (self.replay_window.is_zero() || self.replay_cache_capacity == 0) {
+            return Err("Early data needs a replay window and replay cache".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Storage for client session tickets
+///
+/// Keys and values are opaque to the store. Values are secrets that let a
+/// holder resume a session, so stores should keep them private.
+pub trait SessionStore: Send + Sync {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    /// Store a value, returning whether it was kept
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool;
+}
+
+/// Bounded in-memory session store, evicting the oldest entry when full
+#[derive(Debug)]
+pub struct MemorySessionStore {
+    capacity: usize,
+    entries: Mutex<(HashMap<Vec<u8>, Vec<u8>>, VecDeque<Vec<u8>>)>,
+}
+
+impl MemorySessionStore {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new((HashMap::new(), VecDeque::new())) }
+    }
+
+    fn snapshot(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let entries = self.entries.lock();
+        entries.1.iter().filter_map(|key| Some((key.clone(), entries.0.get(key)?.clone()))).collect()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.entries.lock().0.get(key).cloned()
+    }
+
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        let (values, order) = &mut *self.entries.lock();
+        if values.insert(key.clone(), value).is_none() {
+            order.push_back(key);
+        }
+        while order.len() > self.capacity {
+            if let Some(oldest) = order.pop_front() {
+                values.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Session store kept in a file, readable only by its owner
+///
+/// Every stored ticket rewrites the file, through a temporary file and a
+/// rename so a crash never leaves it half written.
+#[derive(Debug)]
+pub struct FileSessionStore {
+    path: PathBuf,
+    memory: MemorySessionStore,
+}
+
+impl FileSessionStore {
+    /// Open the store, loading the tickets already in the file
+    pub fn open(path: impl Into<PathBuf>, capacity: usize) -> Result<Self> {
+        let path = path.into();
+        let memory = MemorySessionStore::new(capacity);
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let entries: Vec<(Vec<u8>, Vec<u8>)> = bincode::deserialize(&bytes).map_err(|e| {
+                    TransportError::Serialization { message: format!("Corrupt session file {}: {}", path.display(), e) }
+                })?;
+                for (key, value) in entries {
+                    memory.put(key, value);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+        Ok(Self { path, memory })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn persist(&self) -> std::io::Result<()> {
+        let bytes = bincode::serialize(&self.memory.snapshot())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let tmp = self.path.with_extension("tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        std::io::Write::write_all(&mut options.open(&tmp)?, &bytes)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.memory.get(key)
+    }
+
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
+        if !self.memory.put(key, value) {
+            return false;
+        }
+        if let Err(e) = self.persist() {
+            tracing::warn!("Failed to persist session tickets to {}: {}", self.path.display(), e);
+        }
+        true
+    }
+}
+
+/// Session store for a configuration, falling back to memory if the file cannot be opened
+pub fn session_store_for(config: &ResumptionConfig) -> Arc<dyn SessionStore> {
+    if let Some(path) = &config.session_file {
+        match FileSessionStore::open(path, config.max_sessions) {
+            Ok(store) => return Arc::new(store),
+            Err(e) => tracing::warn!("Keeping session tickets in memory only: {}", e),
+        }
+    }
+    Arc::new(MemorySessionStore::new(config.max_sessions))
+}
+
+/// Resumption and early data counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResumptionStats {
+    /// Connections opened without a usable ticket, or with early data disabled
+    pub full_handshakes: u64,
+    /// Connections that attempted a 0-RTT resumption with a stored ticket
+    pub resumption_attempts: u64,
+    /// Resumptions the server accepted along with their early data
+    pub resumed: u64,
+    pub tickets_stored: u64,
+    pub early_messages_sent: u64,
+    /// Early messages sent again after the server refused the resumption
+    pub early_messages_resent: u64,
+    /// Share of connections that skipped the full handshake
+    pub resumption_rate: f64,
+}
+
+/// Counters behind [`ResumptionStats`]
+#[derive(Debug, Default)]
+pub(crate) struct ResumptionCounters {
+    pub full_handshakes: AtomicU64,
+    pub resumption_attempts: AtomicU64,
+    pub resumed: AtomicU64,
+    pub tickets_stored: AtomicU64,
+    pub early_messages_sent: AtomicU64,
+    pub early_messages_resent: AtomicU64,
+}
+
+impl ResumptionCounters {
+    pub fn stats(&self) -> ResumptionStats {
+        let full_handshakes = self.full_handshakes.load(Ordering::Relaxed);
+        let resumed = self.resumed.load(Ordering::Relaxed);
+        let attempts = self.resumption_attempts.load(Ordering::Relaxed);
+        let connections = full_handshakes + attempts;
+        ResumptionStats {
+            full_handshakes,
+            resumption_attempts: attempts,
+            resumed,
+            tickets_stored: self.tickets_stored.load(Ordering::Relaxed),
+            early_messages_sent: self.early_messages_sent.load(Ordering::Relaxed),
+            early_messages_resent: self.early_messages_resent.load(Ordering::Relaxed),
+            resumption_rate: if connections > 0 { resumed as f64 / connections as f64 } else { 0.0 },
+        }
+    }
+}
+
+/// Adapts a [`SessionStore`] to rustls, counting stored tickets
+pub(crate) struct TicketStore {
+    store: Arc<dyn SessionStore>,
+    counters: Arc<ResumptionCounters>,
+}
+
+impl TicketStore {
+    pub fn new(store: Arc<dyn SessionStore>, counters: Arc<ResumptionCounters>) -> Self {
+        Self { store, counters }
+    }
+}
+
+impl rustls::client::StoresClientSessions for TicketStore {
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
+        let stored = self.store.put(key, value);
+        if stored {
+            self.counters.tickets_stored.fetch_add(1, Ordering::Relaxed);
+        }
+        stored
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.store.get(key)
+    }
+}
+
+/// Drops replayed early messages on the server
+#[derive(Debug)]
+pub struct ReplayGuard {
+    window: Duration,
+    capacity: usize,
+    seen: Mutex<(HashSet<u64>, VecDeque<(u64, u64)>)>,
+    rejected: AtomicU64,
+}
+
+impl ReplayGuard {
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        Self { window, capacity, seen: Mutex::new((HashSet::new(), VecDeque::new())), rejected: AtomicU64::new(0) }
+    }
+
+    /// Whether an early message may be delivered: idempotent, fresh and not seen before
+    pub fn admit(&self, message: &TransportMessage) -> bool {
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let admitted = message.idempotent
+            && now_ms.abs_diff(message.timestamp) <= self.window.as_millis() as u64
+            && self.remember(message, now_ms);
+        if !admitted {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+        admitted
+    }
+
+    /// Early messages dropped so far
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    fn remember(&self, message: &TransportMessage, now_ms: u64) -> bool {
+        let fingerprint = fingerprint(&message.source, message.timestamp, message.sequence, &message.payload);
+        let (seen, order) = &mut *self.seen.lock();
+
+        // Entries older than the window can go: their messages are now rejected as stale
+        let horizon = now_ms.saturating_sub(self.window.as_millis() as u64);
+        while order.front().is_some_and(|(timestamp, _)| *timestamp < horizon) {
+            let Some((_, oldest)) = order.pop_front() else { break };
+            seen.remove(&oldest);
+        }
+        // Forgetting a fingerprint still inside the window would let its message
+        // be replayed, so when full refuse early data and let it wait for 1-RTT
+        if order.len() >= self.capacity || !seen.insert(fingerprint) {
+            return false;
+        }
+        order.push_back((message.timestamp, fingerprint));
+        true
+    }
+}
+
+fn fingerprint(source: &NodeId, timestamp: u64, sequence: u64, payload: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (source, timestamp, sequence, payload).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageType;
+
+    #[test]
+    fn test_replayed_and_stale_early_messages_are_dropped() {
+        let guard = ReplayGuard::new(Duration::from_secs(10), 16);
+        let message = TransportMessage::new(MessageType::Data, NodeId::random(), None, b"get".to_vec()).idempotent();
+
+        assert!(guard.admit(&message));
+        assert!(!guard.admit(&message));
+
+        let mut stale = message.clone();
+        stale.timestamp -= 60_000;
+        assert!(!guard.admit(&stale));
+
+        let unsafe_to_replay = TransportMessage::new(MessageType::Data, NodeId::random(), None, b"put".to_vec());
+        assert!(!guard.admit(&unsafe_to_replay));
+        assert_eq!(guard.rejected(), 3);
+    }
+
+    #[test]
+    fn test_full_guard_refuses_early_data_instead_of_forgetting() {
+        let guard = ReplayGuard::new(Duration::from_secs(10), 2);
+        let first = TransportMessage::new(MessageType::Data, NodeId::random(), None, b"a".to_vec()).idempotent();
+        let second = TransportMessage::new(MessageType::Data, NodeId::random(), None, b"b".to_vec()).idempotent();
+        let third = TransportMessage::new(MessageType::Data, NodeId::random(), None, b"c".to_vec()).idempotent();
+
+        assert!(guard.admit(&first));
+        assert!(guard.admit(&second));
+        assert!(!guard.admit(&third));
+
+        // The first message is still remembered, so replaying it is refused
+        assert!(!guard.admit(&first));
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("nexus-sessions-{}.bin", NodeId::random()));
+        let store = FileSessionStore::open(&path, 2).unwrap();
+        assert!(store.put(b"a".to_vec(), b"1".to_vec()));
+        assert!(store.put(b"b".to_vec(), b"2".to_vec()));
+        assert!(store.put(b"c".to_vec(), b"3".to_vec()));
+
+        let reopened = FileSessionStore::open(&path, 2).unwrap();
+        assert_eq!(reopened.get(b"a"), None);
+        assert_eq!(reopened.get(b"c"), Some(b"3".to_vec()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
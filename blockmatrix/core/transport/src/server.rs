@@ -1,11 +1,16 @@
 //! QUIC server implementation for Nexus transport layer
 
 use crate::{Result, TransportError, TransportConfig, CertificateManager, Connection, TransportMessage};
+use crate::admission::{AdmissionController, AdmissionStats};
+use crate::migration::{PathEvent, PathTracker};
+use crate::priority::LaneConfig;
+use crate::renewal::{CertificateRenewal, RenewalHookId};
+use crate::resumption::ReplayGuard;
 use nexus_shared::NodeId;
 use quinn::{Endpoint, ServerConfig};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{info, warn, error, debug};
 
 /// QUIC server for accepting incoming connections
@@ -26,10 +31,22 @@ pub struct QuicServer {
     node_id: NodeId,
     
     /// Message sender for incoming messages
-    message_sender: mpsc::UnboundedSender<(NodeId, TransportMessage)>,
+    message_sender: mpsc::Sender<(NodeId, TransportMessage)>,
     
     /// Message receiver for incoming messages
-    message_receiver: Arc<RwLock<Option<mpsc::UnboundedReceiver<(NodeId, TransportMessage)>>>>,
+    message_receiver: Arc<RwLock<Option<mpsc::Receiver<(NodeId, TransportMessage)>>>>,
+    
+    /// Accept and request queue limits
+    admission: Arc<AdmissionController>,
+    
+    /// Screens messages received as 0-RTT early data
+    replay_guard: Arc<ReplayGuard>,
+    
+    /// Last known peer addresses, for reporting migrations
+    paths: Arc<PathTracker>,
+    
+    /// Hook swapping renewed certificates into the endpoint
+    renewal_hook: Option<RenewalHookId>,
     
     /// Shutdown signal
     shutdown_sender: Option<mpsc::Sender<()>>,
@@ -42,9 +59,16 @@ impl QuicServer {
         cert_manager: Arc<CertificateManager>
     ) -> Result<Self> {
         config.validate().map_err(|e| TransportError::Configuration { message: e })?;
+        cert_manager.set_crypto_policy(config.crypto.clone())?;
         
         let node_id = NodeId::random();
-        let (message_sender, message_receiver) = mpsc::unbounded_channel();
+        let (message_sender, message_receiver) = mpsc::channel(config.admission.request_queue_capacity);
+        let admission = Arc::new(AdmissionController::new(config.admission.clone()));
+        let replay_guard = Arc::new(ReplayGuard::new(
+            config.resumption.replay_window,
+            config.resumption.replay_cache_capacity,
+        ));
+        let paths = Arc::new(PathTracker::new(config.migration.event_capacity));
         
         Ok(Self {
             config,
@@ -54,6 +78,10 @@ impl QuicServer {
             node_id,
             message_sender,
             message_receiver: Arc::new(RwLock::new(Some(message_receiver))),
+            admission,
+            replay_guard,
+            paths,
+            renewal_hook: None,
             shutdown_sender: None,
         })
     }
@@ -83,6 +111,18 @@ impl QuicServer {
             
         info!("QUIC server listening on {}", local_addr);
         
+        // New handshakes pick up a renewed certificate; open connections are untouched
+        let renewed_endpoint = endpoint.clone();
+        let transport_config = self.config.clone();
+        self.renewal_hook = Some(self.cert_manager.on_renewal(Arc::new(
+            move |certificates: &CertificateManager, renewal: &CertificateRenewal| -> Result<()> {
+                let server_config = certificates.server_config()?;
+                renewed_endpoint.set_server_config(Some(transport_config.to_quinn_server_config(server_config)));
+                info!("Serving renewed certificate for {} on {}", renewal.subject_name, local_addr);
+                Ok(())
+            },
+        )));
+        
         // Start connection handling task
         let (shutdown_sender, mut shutdown_receiver) = mpsc::channel(1);
         self.shutdown_sender = Some(shutdown_sender);
@@ -90,25 +130,52 @@ impl QuicServer {
         let connections = Arc::clone(&self.connections);
         let message_sender = self.message_sender.clone();
         let node_id = self.node_id;
+        let admission = Arc::clone(&self.admission);
+        let replay_guard = Arc::clone(&self.replay_guard);
+        let paths = Arc::clone(&self.paths);
+        let lanes = self.config.lanes.clone();
+        let mut path_check = tokio::time::interval(self.config.migration.path_check_interval);
         
         let endpoint_clone = endpoint.clone();
         
         tokio::spawn(async move {
             loop {
                 tokio::select! {
+                    _ = path_check.tick() => {
+                        let connections = connections.read().await;
+                        for (node_id, connection) in connections.iter() {
+                            if connection.is_healthy() {
+                                paths.observe(*node_id, connection.remote_address());
+                            }
+                        }
+                        paths.retain(|node_id| connections.contains_key(node_id));
+                    }
                     conn = endpoint_clone.accept() => {
                         let Some(conn) = conn else { break; };
                         debug!("Received incoming connection");
                         
+                        // Refuse rather than queue handshakes without bound
+                        let Some(permit) = admission.try_accept() else {
+                            warn!("Refusing connection from {}: too many pending connections", conn.remote_address());
+                            continue;
+                        };
+                        
                         let connections = Arc::clone(&connections);
                         let message_sender = message_sender.clone();
+                        let admission = Arc::clone(&admission);
+                        let replay_guard = Arc::clone(&replay_guard);
+                        let lanes = lanes.clone();
                         
                         tokio::spawn(async move {
                             if let Err(e) = Self::handle_incoming_connection(
                                 conn, 
                                 connections,
                                 message_sender,
-                                node_id
+                                node_id,
+                                admission,
+                                replay_guard,
+                                lanes,
+                                permit,
                             ).await {
                                 error!("Failed to handle incoming connection: {}", e);
                             }
@@ -130,8 +197,12 @@ impl QuicServer {
     async fn handle_incoming_connection(
         connecting: quinn::Connecting,
         connections: Arc<RwLock<std::collections::HashMap<NodeId, Arc<Connection>>>>,
-        message_sender: mpsc::UnboundedSender<(NodeId, TransportMessage)>,
+        message_sender: mpsc::Sender<(NodeId, TransportMessage)>,
         local_node_id: NodeId,
+        admission: Arc<AdmissionController>,
+        replay_guard: Arc<ReplayGuard>,
+        lanes: LaneConfig,
+        permit: crate::admission::AcceptPermit,
     ) -> Result<()> {
         let quinn_connection = connecting.await
             .map_err(|e| TransportError::Connection { 
@@ -146,11 +217,12 @@ impl QuicServer {
             quinn_connection,
             local_node_id,
             None, // Will be set after handshake
-        ).await?);
+        ).await?.with_replay_guard(replay_guard).with_lanes(lanes));
         
         // Perform handshake to get remote node ID
         let remote_node_id = connection.handshake().await?;
         connection.set_remote_node_id(remote_node_id).await;
+        drop(permit);
         
         // Store connection
         connections.write().await.insert(remote_node_id, Arc::clone(&connection));
@@ -158,7 +230,7 @@ impl QuicServer {
         // Handle connection messages
         let conn_message_sender = message_sender.clone();
         tokio::spawn(async move {
-            if let Err(e) = connection.handle_messages(conn_message_sender).await {
+            if let Err(e) = connection.handle_messages(conn_message_sender, admission).await {
                 error!("Connection message handling failed: {}", e);
             }
             
@@ -179,6 +251,10 @@ impl QuicServer {
             let _ = sender.send(()).await;
         }
         
+        if let Some(hook) = self.renewal_hook.take() {
+            self.cert_manager.remove_renewal_hook(hook);
+        }
+        
         // Close endpoint
         if let Some(endpoint) = self.endpoint.take() {
             endpoint.close(0u32.into(), b"server shutdown");
@@ -235,10 +311,25 @@ impl QuicServer {
     /// Get message receiver for incoming messages
     pub async fn take_message_receiver(
         &self
-    ) -> Option<mpsc::UnboundedReceiver<(NodeId, TransportMessage)>> {
+    ) -> Option<mpsc::Receiver<(NodeId, TransportMessage)>> {
         self.message_receiver.write().await.take()
     }
     
+    /// Get admission counters, including shed requests
+    pub fn admission_stats(&self) -> AdmissionStats {
+        self.admission.stats()
+    }
+    
+    /// Get how many early data messages were dropped as stale or replayed
+    pub fn replays_rejected(&self) -> u64 {
+        self.replay_guard.rejected()
+    }
+    
+    /// Subscribe to peers migrating to new addresses
+    pub fn path_events(&self) -> broadcast::Receiver<PathEvent> {
+        self.paths.subscribe()
+    }
+    
     /// Get list of connected peers
     pub async fn connected_peers(&self) -> Vec<NodeId> {
         self.connections.read().await.keys().cloned().collect()
@@ -314,6 +405,9 @@ impl std::fmt::Debug for QuicServer {
 
 impl Drop for QuicServer {
     fn drop(&mut self) {
+        if let Some(hook) = self.renewal_hook.take() {
+            self.cert_manager.remove_renewal_hook(hook);
+        }
         if self.endpoint.is_some() {
             warn!("QuicServer dropped without proper shutdown");
         }
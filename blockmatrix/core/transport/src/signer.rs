@@ -0,0 +1,112 @@
+//! Certificates and TLS identities backed by a node signer
+//!
+//! A node whose key is held in hardware has no private key bytes to hand to
+//! rcgen or rustls. Certificates for such a key are generated with an rcgen
+//! remote key pair, and TLS handshakes sign through a rustls signing key;
+//! both forward every signature to the `NodeSigner`.
+
+use crate::{Result, TransportError};
+use nexus_shared::{NodeSigner, SignatureAlgorithm};
+use rustls::sign::{CertifiedKey, Signer, SigningKey};
+use rustls::SignatureScheme;
+use std::sync::Arc;
+
+/// rcgen's algorithm for a signer's keys
+pub(crate) fn rcgen_algorithm(algorithm: SignatureAlgorithm) -> &'static rcgen::SignatureAlgorithm {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => &rcgen::PKCS_ED25519,
+        SignatureAlgorithm::EcdsaP256Sha256 => &rcgen::PKCS_ECDSA_P256_SHA256,
+    }
+}
+
+/// An rcgen key pair that signs through the signer
+pub(crate) fn key_pair(signer: Arc<dyn NodeSigner>) -> Result<rcgen::KeyPair> {
+    rcgen::KeyPair::from_remote(Box::new(RemoteKey(signer))).map_err(|e| TransportError::Certificate {
+        message: format!("Failed to use the node key: {}", e),
+    })
+}
+
+struct RemoteKey(Arc<dyn NodeSigner>);
+
+impl rcgen::RemoteKeyPair for RemoteKey {
+    fn public_key(&self) -> &[u8] {
+        self.0.public_key()
+    }
+
+    fn sign(&self, msg: &[u8]) -> std::result::Result<Vec<u8>, rcgen::RcgenError> {
+        self.0.sign(msg).map_err(|e| {
+            tracing::error!("Node key failed to sign a certificate: {}", e);
+            rcgen::RcgenError::RemoteKeyError
+        })
+    }
+
+    fn algorithm(&self) -> &'static rcgen::SignatureAlgorithm {
+        rcgen_algorithm(self.0.algorithm())
+    }
+}
+
+/// A certificate chain with the signer for its leaf's key
+pub(crate) fn certified_key(chain: Vec<rustls::Certificate>, signer: Arc<dyn NodeSigner>) -> Arc<CertifiedKey> {
+    Arc::new(CertifiedKey::new(chain, Arc::new(TlsKey(signer))))
+}
+
+struct TlsKey(Arc<dyn NodeSigner>);
+
+impl TlsKey {
+    fn scheme(&self) -> SignatureScheme {
+        match self.0.algorithm() {
+            SignatureAlgorithm::Ed25519 => SignatureScheme::ED25519,
+            SignatureAlgorithm::EcdsaP256Sha256 => SignatureScheme::ECDSA_NISTP256_SHA256,
+        }
+    }
+}
+
+impl SigningKey for TlsKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        let scheme = self.scheme();
+        offered
+            .contains(&scheme)
+            .then(|| Box::new(TlsSigner { signer: self.0.clone(), scheme }) as Box<dyn Signer>)
+    }
+
+    fn algorithm(&self) -> rustls::SignatureAlgorithm {
+        match self.0.algorithm() {
+            SignatureAlgorithm::Ed25519 => rustls::SignatureAlgorithm::ED25519,
+            SignatureAlgorithm::EcdsaP256Sha256 => rustls::SignatureAlgorithm::ECDSA,
+        }
+    }
+}
+
+struct TlsSigner {
+    signer: Arc<dyn NodeSigner>,
+    scheme: SignatureScheme,
+}
+
+impl Signer for TlsSigner {
+    fn sign(&self, message: &[u8]) -> std::result::Result<Vec<u8>, rustls::Error> {
+        self.signer.sign(message).map_err(|e| rustls::Error::General(format!("Node key failed to sign: {}", e)))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+/// Presents one certified key as both server and client identity
+pub(crate) struct SignerIdentity(pub(crate) Arc<CertifiedKey>);
+
+impl rustls::server::ResolvesServerCert for SignerIdentity {
+    fn resolve(&self, _client_hello: rustls::server::ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.0.clone())
+    }
+}
+
+impl rustls::client::ResolvesClientCert for SignerIdentity {
+    fn resolve(&self, _acceptable_issuers: &[&[u8]], sigschemes: &[SignatureScheme]) -> Option<Arc<CertifiedKey>> {
+        self.0.key.choose_scheme(sigschemes).map(|_| self.0.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
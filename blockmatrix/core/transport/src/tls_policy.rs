@@ -0,0 +1,132 @@
+//! Crypto policy applied to TLS handshakes
+//!
+//! Both sides of a handshake offer only the TLS 1.3 cipher suites and key
+//! exchange groups the cluster's crypto policy allows, and accept only
+//! allowed signature schemes from the peer, so a node in FIPS mode never
+//! negotiates down to an algorithm outside the policy.
+
+use crate::{Result, TransportError};
+use nexus_shared::{Algorithm, CryptoPolicy};
+use rustls::client::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{cipher_suite, kx_group, ConfigBuilder, ConfigSide, SignatureScheme, SupportedCipherSuite, SupportedKxGroup, WantsCipherSuites, WantsVerifier};
+
+fn cipher_suites() -> [(SupportedCipherSuite, Algorithm); 3] {
+    [
+        (cipher_suite::TLS13_AES_256_GCM_SHA384, Algorithm::Aes256Gcm),
+        (cipher_suite::TLS13_AES_128_GCM_SHA256, Algorithm::Aes128Gcm),
+        (cipher_suite::TLS13_CHACHA20_POLY1305_SHA256, Algorithm::ChaCha20Poly1305),
+    ]
+}
+
+fn kx_groups() -> [(&'static SupportedKxGroup, Algorithm); 3] {
+    [
+        (&kx_group::X25519, Algorithm::X25519),
+        (&kx_group::SECP256R1, Algorithm::EcdheP256),
+        (&kx_group::SECP384R1, Algorithm::EcdheP384),
+    ]
+}
+
+const SIGNATURE_SCHEMES: [(SignatureScheme, Algorithm); 9] = [
+    (SignatureScheme::ED25519, Algorithm::Ed25519),
+    (SignatureScheme::ECDSA_NISTP256_SHA256, Algorithm::EcdsaP256Sha256),
+    (SignatureScheme::ECDSA_NISTP384_SHA384, Algorithm::EcdsaP384Sha384),
+    (SignatureScheme::RSA_PSS_SHA256, Algorithm::RsaPss),
+    (SignatureScheme::RSA_PSS_SHA384, Algorithm::RsaPss),
+    (SignatureScheme::RSA_PSS_SHA512, Algorithm::RsaPss),
+    (SignatureScheme::RSA_PKCS1_SHA256, Algorithm::RsaPkcs1),
+    (SignatureScheme::RSA_PKCS1_SHA384, Algorithm::RsaPkcs1),
+    (SignatureScheme::RSA_PKCS1_SHA512, Algorithm::RsaPkcs1),
+];
+
+/// Restrict a config builder to the policy's cipher suites and groups over TLS 1.3
+pub(crate) fn restrict<S: ConfigSide>(
+    builder: ConfigBuilder<S, WantsCipherSuites>,
+    policy: &CryptoPolicy,
+) -> Result<ConfigBuilder<S, WantsVerifier>> {
+    let suites: Vec<_> = cipher_suites().into_iter().filter(|(_, a)| policy.allows(*a)).map(|(s, _)| s).collect();
+    let groups: Vec<_> = kx_groups().into_iter().filter(|(_, a)| policy.allows(*a)).map(|(g, _)| g).collect();
+    if suites.is_empty() || groups.is_empty() {
+        return Err(TransportError::Configuration {
+            message: "Crypto policy allows no TLS 1.3 cipher suite or key exchange group".to_string(),
+        });
+    }
+    builder
+        .with_cipher_suites(&suites)
+        .with_kx_groups(&groups)
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| TransportError::Configuration { message: format!("Crypto policy cannot be applied: {}", e) })
+}
+
+/// Signature schemes the policy accepts from peers
+pub(crate) fn verify_schemes(policy: &CryptoPolicy) -> Vec<SignatureScheme> {
+    SIGNATURE_SCHEMES.iter().filter(|(_, a)| policy.allows(*a)).map(|(s, _)| *s).collect()
+}
+
+/// Verify a peer's TLS 1.3 handshake signature made with an allowed scheme
+pub(crate) fn verify_tls13_signature(
+    policy: &CryptoPolicy,
+    message: &[u8],
+    cert: &rustls::Certificate,
+    dss: &rustls::DigitallySignedStruct,
+) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+    if !verify_schemes(policy).contains(&dss.scheme) {
+        return Err(rustls::Error::General(format!("Peer signed with {:?}, which the crypto policy refuses", dss.scheme)));
+    }
+    WebPkiVerifier::new(rustls::RootCertStore::empty(), None).verify_tls13_signature(message, cert, dss)
+}
+
+/// Server certificate verification under the crypto policy
+pub(crate) struct PolicyServerVerifier {
+    inner: WebPkiVerifier,
+    policy: CryptoPolicy,
+}
+
+impl PolicyServerVerifier {
+    pub(crate) fn new(roots: rustls::RootCertStore, policy: CryptoPolicy) -> Self {
+        Self { inner: WebPkiVerifier::new(roots, None), policy }
+    }
+}
+
+impl ServerCertVerifier for PolicyServerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        intermediates: &[rustls::Certificate],
+        server_name: &rustls::ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: std::time::SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        self.inner.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::Certificate,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(&self.policy, message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        verify_schemes(&self.policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fips_policy_narrows_the_handshake() {
+        let fips = CryptoPolicy::fips();
+        let schemes = verify_schemes(&fips);
+        assert!(schemes.contains(&SignatureScheme::ECDSA_NISTP256_SHA256));
+        assert!(restrict(rustls::ServerConfig::builder(), &fips).is_ok());
+
+        let no_ciphers = CryptoPolicy { denied: vec![Algorithm::Aes128Gcm, Algorithm::Aes256Gcm], ..fips };
+        assert!(restrict(rustls::ClientConfig::builder(), &no_ciphers).is_err());
+        assert_eq!(verify_schemes(&CryptoPolicy::default()).len(), SIGNATURE_SCHEMES.len());
+    }
+}
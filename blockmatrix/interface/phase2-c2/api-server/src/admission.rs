@@ -0,0 +1,180 @@
+//! Defaulting and validation of API request bodies
+//!
+//! Create and update requests pass through [`admit`] or [`admit_update`]
+//! before they reach Nexus core. Invalid objects are rejected with 422 and a
+//! list of field errors.
+
+use crate::error::{ApiError, ApiResult};
+use crate::nexus_core::{CreateClusterRequest, DeployServiceRequest, ServicePortRequest, ServiceResourceRequest};
+use nexus_shared::schema::{self, check_dns_label, check_immutable, check_range, require, ApiSchema, FieldPath};
+use nexus_shared::ValidationReport;
+use std::collections::HashSet;
+
+const DEFAULT_NAMESPACE: &str = "default";
+const DEFAULT_CLUSTER: &str = "default";
+const DEFAULT_REGION: &str = "us-west-2";
+const DEFAULT_PROTOCOL: &str = "TCP";
+const PROTOCOLS: &[&str] = &["TCP", "UDP"];
+const MAX_REPLICAS: u32 = 10_000;
+const MAX_CLUSTER_NODES: u32 = 1_000;
+
+/// Default and validate a new object
+pub fn admit<T: ApiSchema>(object: &mut T) -> ApiResult<()> {
+    into_result(schema::admit_create(object))
+}
+
+/// Default and validate an object replacing `old`
+pub fn admit_update<T: ApiSchema>(old: &T, object: &mut T) -> ApiResult<()> {
+    into_result(schema::admit_update(old, object))
+}
+
+fn into_result(report: ValidationReport) -> ApiResult<()> {
+    for warning in report.warnings() {
+        tracing::warn!("Admitted with warning: {}", warning);
+    }
+    if report.is_valid() {
+        Ok(())
+    } else {
+        Err(ApiError::Invalid(report))
+    }
+}
+
+impl ApiSchema for DeployServiceRequest {
+    fn apply_defaults(&mut self) {
+        self.namespace.get_or_insert_with(|| DEFAULT_NAMESPACE.to_string());
+        self.cluster.get_or_insert_with(|| DEFAULT_CLUSTER.to_string());
+        for port in &mut self.ports {
+            port.apply_defaults();
+        }
+    }
+
+    fn validate_fields(&self, path: &FieldPath, report: &mut ValidationReport) {
+        check_dns_label(report, &path.child("name"), &self.name);
+        require(report, &path.child("image"), &self.image);
+        if self.image.chars().any(char::is_whitespace) {
+            report.error(path.child("image").as_str(), "must not contain whitespace");
+        }
+        check_range(report, &path.child("replicas"), self.replicas, 0, MAX_REPLICAS);
+        if let Some(namespace) = &self.namespace {
+            check_dns_label(report, &path.child("namespace"), namespace);
+        }
+        if let Some(cluster) = &self.cluster {
+            check_dns_label(report, &path.child("cluster"), cluster);
+        }
+
+        for key in self.environment.keys() {
+            if key.is_empty() || key.contains('=') || key.chars().any(char::is_whitespace) {
+                report.error(path.child("environment").key(key).as_str(), "is not a valid variable name");
+            }
+        }
+
+        self.resources.validate_fields(&path.child("resources"), report);
+
+        let mut names = HashSet::new();
+        let mut numbers = HashSet::new();
+        for (i, port) in self.ports.iter().enumerate() {
+            let port_path = path.child("ports").index(i);
+            port.validate_fields(&port_path, report);
+            if !names.insert(&port.name) {
+                report.error(port_path.child("name").as_str(), format!("duplicate port name '{}'", port.name));
+            }
+            if !numbers.insert((port.port, &port.protocol)) {
+                report.error(port_path.child("port").as_str(), format!("duplicate {} port {}", port.protocol, port.port));
+            }
+        }
+    }
+
+    fn validate_immutable(&self, old: &Self, path: &FieldPath, report: &mut ValidationReport) {
+        check_immutable(report, &path.child("name"), &old.name, &self.name);
+        check_immutable(report, &path.child("namespace"), &old.namespace, &self.namespace);
+        check_immutable(report, &path.child("cluster"), &old.cluster, &self.cluster);
+    }
+}
+
+impl ApiSchema for ServicePortRequest {
+    fn apply_defaults(&mut self) {
+        if self.target_port == 0 {
+            self.target_port = self.port;
+        }
+        if self.protocol.is_empty() {
+            self.protocol = DEFAULT_PROTOCOL.to_string();
+        }
+    }
+
+    fn validate_fields(&self, path: &FieldPath, report: &mut ValidationReport) {
+        check_dns_label(report, &path.child("name"), &self.name);
+        check_range(report, &path.child("port"), self.port, 1, u16::MAX);
+        check_range(report, &path.child("target_port"), self.target_port, 1, u16::MAX);
+        if !PROTOCOLS.contains(&self.protocol.as_str()) {
+            report.error(
+                path.child("protocol").as_str(),
+                format!("'{}' is not one of {}", self.protocol, PROTOCOLS.join(", ")),
+            );
+        }
+    }
+}
+
+impl ApiSchema for ServiceResourceRequest {
+    fn validate_fields(&self, path: &FieldPath, report: &mut ValidationReport) {
+        if let Some(cpu) = self.cpu {
+            if !(cpu.is_finite() && cpu > 0.0) {
+                report.error(path.child("cpu").as_str(), format!("{} must be a positive number of cores", cpu));
+            }
+        }
+        if let Some(memory) = &self.memory {
+            if memory_bytes(memory).is_none() {
+                report.error(
+                    path.child("memory").as_str(),
+                    format!("'{}' is not a quantity like 512Mi or 2Gi", memory),
+                );
+            }
+        }
+    }
+}
+
+impl ApiSchema for CreateClusterRequest {
+    fn apply_defaults(&mut self) {
+        self.region.get_or_insert_with(|| DEFAULT_REGION.to_string());
+    }
+
+    fn validate_fields(&self, path: &FieldPath, report: &mut ValidationReport) {
+        check_dns_label(report, &path.child("name"), &self.name);
+        require(report, &path.child("node_size"), &self.node_size);
+        check_range(report, &path.child("node_count"), self.node_count, 1, MAX_CLUSTER_NODES);
+        if self.high_availability && self.node_count < 3 {
+            report.error(
+                path.child("node_count").as_str(),
+                format!("high availability needs at least 3 nodes, got {}", self.node_count),
+            );
+        }
+        if let Some(region) = &self.region {
+            require(report, &path.child("region"), region);
+        }
+        if let Some(config) = &self.config {
+            if !config.is_object() {
+                report.error(path.child("config").as_str(), "must be an object");
+            }
+        }
+    }
+
+    fn validate_immutable(&self, old: &Self, path: &FieldPath, report: &mut ValidationReport) {
+        check_immutable(report, &path.child("name"), &old.name, &self.name);
+        check_immutable(report, &path.child("region"), &old.region, &self.region);
+    }
+}
+
+/// Bytes in a quantity like `512Mi`, `2Gi` or `1000000`
+pub(crate) fn memory_bytes(value: &str) -> Option<u64> {
+    const SUFFIXES: &[(&str, u64)] = &[
+        ("Ki", 1 << 10), ("Mi", 1 << 20), ("Gi", 1 << 30), ("Ti", 1 << 40),
+        ("K", 1_000), ("M", 1_000_000), ("G", 1_000_000_000), ("T", 1_000_000_000_000),
+        ("", 1),
+    ];
+    SUFFIXES.iter().find_map(|(suffix, multiplier)| {
+        let digits = value.strip_suffix(suffix)?;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse::<u64>().ok()?.checked_mul(*multiplier)
+    })
+}
@@ -0,0 +1,22 @@
+//! Capacity-planning endpoints
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+
+use crate::{
+    error::ApiResult,
+    nexus_core::{CapacityForecastQuery, CapacityForecastReport},
+    AppState,
+};
+
+/// GET /api/v1/clusters/:name/capacity-forecast?horizon_days=90
+pub async fn get_forecast(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<CapacityForecastQuery>,
+) -> ApiResult<Json<CapacityForecastReport>> {
+    let report = state.nexus_core.capacity_forecast(&name, query.horizon_days).await?;
+    Ok(Json(report))
+}
@@ -0,0 +1,99 @@
+//! Cluster management endpoints
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    error::{ApiError, ApiResult},
+    nexus_core::{
+        ClusterDetails, ClusterInfo, ClusterScaleRequest, CreateClusterRequest, DryRunQuery, NodeInfo,
+        UpdateClusterRequest,
+    },
+    AppState,
+};
+
+/// GET /api/v1/clusters
+pub async fn list_clusters(State(state): State<AppState>) -> ApiResult<Json<Vec<ClusterInfo>>> {
+    let clusters = state.nexus_core.list_clusters().await?;
+    Ok(Json(clusters))
+}
+
+/// POST /api/v1/clusters
+pub async fn create_cluster(
+    State(state): State<AppState>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<CreateClusterRequest>,
+) -> ApiResult<(StatusCode, Json<ClusterDetails>)> {
+    let cluster = state.nexus_core.create_cluster(&request, query.dry_run).await?;
+    let status = if query.dry_run { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(cluster)))
+}
+
+/// GET /api/v1/clusters/:name
+pub async fn get_cluster(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<ClusterDetails>> {
+    let cluster = state.nexus_core.get_cluster(&name).await?;
+    Ok(Json(cluster))
+}
+
+/// DELETE /api/v1/clusters/:name
+pub async fn delete_cluster(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DryRunQuery>,
+) -> ApiResult<StatusCode> {
+    state.nexus_core.delete_cluster(&name, query.dry_run).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PATCH /api/v1/clusters/:name
+pub async fn update_cluster(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<UpdateClusterRequest>,
+) -> ApiResult<Json<ClusterDetails>> {
+    let cluster = state.nexus_core.update_cluster(&name, &request, query.dry_run).await?;
+    Ok(Json(cluster))
+}
+
+/// PATCH /api/v1/clusters/:name/scale
+pub async fn scale_cluster(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<ClusterScaleRequest>,
+) -> ApiResult<Json<ClusterDetails>> {
+    let update = UpdateClusterRequest {
+        node_count: Some(request.node_count),
+        ..Default::default()
+    };
+    let cluster = state.nexus_core.update_cluster(&name, &update, query.dry_run).await?;
+    Ok(Json(cluster))
+}
+
+/// GET /api/v1/clusters/:name/nodes
+pub async fn list_nodes(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<Vec<NodeInfo>>> {
+    let cluster = state.nexus_core.get_cluster(&name).await?;
+    Ok(Json(cluster.nodes))
+}
+
+/// GET /api/v1/clusters/:name/nodes/:node_id
+pub async fn get_node(
+    State(state): State<AppState>,
+    Path((name, node_id)): Path<(String, String)>,
+) -> ApiResult<Json<NodeInfo>> {
+    let cluster = state.nexus_core.get_cluster(&name).await?;
+    cluster.nodes.into_iter()
+        .find(|node| node.id == node_id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Node '{}' not found in cluster '{}'", node_id, name)))
+}
@@ -0,0 +1,11 @@
+//! Diagnostics endpoints
+
+use axum::{extract::State, Json};
+
+use crate::{error::ApiResult, nexus_core::StateDiagnosticsReport, AppState};
+
+/// GET /api/v1/debug/state
+pub async fn get_state(State(state): State<AppState>) -> ApiResult<Json<StateDiagnosticsReport>> {
+    let report = state.nexus_core.state_diagnostics().await?;
+    Ok(Json(report))
+}
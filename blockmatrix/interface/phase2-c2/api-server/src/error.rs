@@ -5,6 +5,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use nexus_shared::ValidationReport;
 use serde_json::json;
 use thiserror::Error;
 
@@ -27,6 +28,9 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Invalid object: {} field error(s)", .0.errors().count())]
+    Invalid(ValidationReport),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 
@@ -54,6 +58,7 @@ impl IntoResponse for ApiError {
             ApiError::NotFound(_) => (StatusCode::NOT_FOUND, "NOT_FOUND", self.to_string()),
             ApiError::BadRequest(_) => (StatusCode::BAD_REQUEST, "BAD_REQUEST", self.to_string()),
             ApiError::Conflict(_) => (StatusCode::CONFLICT, "CONFLICT", self.to_string()),
+            ApiError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, "INVALID", self.to_string()),
             ApiError::NexusCore(_) => (StatusCode::BAD_GATEWAY, "NEXUS_ERROR", self.to_string()),
             ApiError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", self.to_string()),
             ApiError::Serialization(_) => (StatusCode::BAD_REQUEST, "SERIALIZATION_ERROR", self.to_string()),
@@ -62,7 +67,7 @@ impl IntoResponse for ApiError {
             ApiError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", self.to_string()),
         };
 
-        let body = json!({
+        let mut body = json!({
             "error": {
                 "code": error_code,
                 "message": message,
@@ -70,6 +75,9 @@ impl IntoResponse for ApiError {
                 "request_id": uuid::Uuid::new_v4()
             }
         });
+        if let ApiError::Invalid(report) = &self {
+            body["error"]["fields"] = json!(report.errors().collect::<Vec<_>>());
+        }
 
         (status, Json(body)).into_response()
     }
@@ -24,9 +24,15 @@ use tower_http::{
 };
 use tracing::{info, warn, error};
 
+mod admission;
 mod auth;
 mod cluster;
+mod capacity;
+mod debug;
+mod node;
+mod workload;
 mod service;
+mod service_account;
 mod system;
 mod graphql;
 mod middleware_auth;
@@ -37,6 +43,9 @@ mod error;
 use auth::{AuthService, Claims};
 use error::{ApiError, ApiResult};
 use nexus_core::NexusCore;
+use nexus_scheduler::{Scheduler, SchedulerConfig};
+use nexus_state::{StateConfig, StateManager};
+use service_account::ServiceAccountStore;
 
 #[derive(Parser)]
 #[command(name = "nexus-api-server")]
@@ -99,6 +108,7 @@ enum MigrateCommand {
 pub struct AppState {
     pub nexus_core: Arc<NexusCore>,
     pub auth_service: Arc<AuthService>,
+    pub service_accounts: Arc<ServiceAccountStore>,
     pub config: Arc<config::ServerConfig>,
 }
 
@@ -128,18 +138,10 @@ async fn serve(cli: Cli) -> Result<()> {
 
     // Initialize Nexus core connection
     info!("🔗 Connecting to Nexus core...");
-    let nexus_core = Arc::new(NexusCore::new(&config.nexus).await?);
+    let nexus_core = connect_core(&config, StateConfig::default(), SchedulerConfig::default()).await?;
     
-    // Initialize authentication service
-    info!("🔐 Initializing authentication service...");
-    let auth_service = Arc::new(AuthService::new(&config.auth)?);
-
     // Create application state
-    let state = AppState {
-        nexus_core,
-        auth_service,
-        config: Arc::new(config),
-    };
+    let state = build_state(config, nexus_core).await?;
 
     // Build our application with routes
     let app = create_router(state.clone()).await?;
@@ -164,6 +166,53 @@ async fn serve(cli: Cli) -> Result<()> {
     Ok(())
 }
 
+/// Start this node's state manager and scheduler and attach them to the core
+/// connection that backs the API
+async fn connect_core(
+    config: &config::ServerConfig,
+    state_config: StateConfig,
+    mut scheduler_config: SchedulerConfig,
+) -> Result<NexusCore> {
+    // Sign placements with the node's persistent identity, kept beside its state
+    if scheduler_config.identity_key_path.is_none() {
+        let key_path = std::path::Path::new(&state_config.storage.data_dir).join("node.key");
+        scheduler_config.identity_key_path = Some(key_path.to_string_lossy().to_string());
+    }
+    let mut scheduler = Scheduler::new(scheduler_config).await?;
+
+    let state_manager = Arc::new(StateManager::new(state_config, scheduler.node_id()).await?);
+    state_manager.start().await?;
+
+    scheduler.set_state_manager(state_manager.clone());
+    scheduler.start().await?;
+
+    Ok(NexusCore::new(&config.nexus).await?
+        .with_scheduler(Arc::new(scheduler))
+        .with_state_manager(state_manager))
+}
+
+async fn build_state(config: config::ServerConfig, nexus_core: NexusCore) -> Result<AppState> {
+    // Initialize authentication service
+    info!("🔐 Initializing authentication service...");
+    let auth_service = Arc::new(AuthService::new(&config.auth)?);
+
+    // Keep service accounts in cluster state so every API server sees them
+    let mut service_accounts = ServiceAccountStore::new();
+    if let Some(state_manager) = nexus_core.state_manager() {
+        service_accounts = service_accounts.with_state_manager(state_manager);
+    }
+    let loaded = service_accounts.load().await?;
+    info!("🔑 Loaded {} service accounts", loaded);
+    let service_accounts = Arc::new(service_accounts);
+
+    Ok(AppState {
+        nexus_core: Arc::new(nexus_core),
+        auth_service,
+        service_accounts,
+        config: Arc::new(config),
+    })
+}
+
 async fn create_router(state: AppState) -> Result<Router> {
     // Create GraphQL schema
     let schema = graphql::create_schema(state.clone()).await?;
@@ -199,6 +248,17 @@ async fn create_router(state: AppState) -> Result<Router> {
         // Static file serving
         .nest_service("/static", ServeDir::new("static"))
         
+        // Authentication; router layers run in reverse order of addition, so
+        // service account tokens are checked before user authentication
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            middleware_auth::auth_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            service_account::authenticate,
+        ))
+        
         // Middleware
         .layer(
             ServiceBuilder::new()
@@ -209,10 +269,6 @@ async fn create_router(state: AppState) -> Result<Router> {
                         .allow_methods(Any)
                         .allow_headers(Any),
                 )
-                .layer(middleware::from_fn_with_state(
-                    state.clone(),
-                    middleware_auth::auth_middleware,
-                ))
         )
         .with_state(state);
 
@@ -236,6 +292,17 @@ fn api_v1_routes() -> Router<AppState> {
         .route("/clusters/:name/scale", patch(cluster::scale_cluster))
         .route("/clusters/:name/nodes", get(cluster::list_nodes))
         .route("/clusters/:name/nodes/:node_id", get(cluster::get_node))
+        .route("/clusters/:name/capacity-forecast", get(capacity::get_forecast))
+        
+        // Node metadata
+        .route("/nodes/:node/labels", patch(node::update_labels))
+        .route("/nodes/:node/annotations", patch(node::update_annotations))
+        
+        // Scheduling gates
+        .route("/workloads/held", get(workload::list_held))
+        .route("/workloads/:namespace/:name/approve", post(workload::approve))
+        .route("/workloads/:namespace/:name/gates/remove", post(workload::remove_gate))
+        .route("/workloads/:namespace/:name/reject", post(workload::reject))
         
         // Service management  
         .route("/services", get(service::list_services).post(service::deploy_service))
@@ -248,6 +315,15 @@ fn api_v1_routes() -> Router<AppState> {
         .route("/services/:name/logs", get(service::get_logs))
         .route("/services/:name/exec", post(service::exec_command))
         
+        // Diagnostics
+        .route("/debug/state", get(debug::get_state))
+        
+        // Service accounts for automation
+        .route("/service-accounts", get(service_account::list).post(service_account::create))
+        .route("/service-accounts/:name", get(service_account::get))
+        .route("/service-accounts/:name/rotate", post(service_account::rotate))
+        .route("/service-accounts/:name/revoke", post(service_account::revoke))
+        
         // Authentication
         .route("/auth/login", post(auth::login))
         .route("/auth/refresh", post(auth::refresh_token))
@@ -374,4 +450,30 @@ async fn shutdown_signal() {
     }
     
     info!("👋 Nexus API Server shutdown complete");
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_node_routes_reach_scheduler() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config::load_config(None).unwrap();
+        let mut state_config = StateConfig::default();
+        state_config.storage.data_dir = dir.path().to_string_lossy().to_string();
+        let nexus_core = connect_core(&config, state_config, SchedulerConfig::default()).await.unwrap();
+        let state = build_state(config, nexus_core).await.unwrap();
+        let server = TestServer::new(api_v1_routes().with_state(state)).unwrap();
+
+        // Answered by the scheduler rather than "Scheduler is not connected"
+        let response = server.patch("/nodes/missing/labels")
+            .json(&serde_json::json!({ "changes": ["zone=east"] }))
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        let response = server.get("/workloads/held").await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>(), serde_json::json!([]));
+    }
+}
@@ -1,16 +1,28 @@
 //! Nexus Core integration layer
 
-use crate::{config::NexusConfig, error::{ApiError, ApiResult}};
+use crate::{admission, config::NexusConfig, error::{ApiError, ApiResult}};
+use nexus_runtime::ResourceQuotas;
+use nexus_scheduler::workload::WorkloadType;
+use nexus_scheduler::{
+    NodeMetadata, NodeMetadataUpdate, PlacementDecision, MANUAL_APPROVAL_GATE, Scheduler, SchedulerError, SchedulingResult, Workload,
+    WorkloadSpec, DEFAULT_SCHEDULER_NAME,
+};
 use nexus_shared::*;
+use nexus_state::StateManager;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::time::sleep;
 
+/// Status reported on objects returned by a dry run
+const DRY_RUN_STATUS: &str = "dry-run";
+
 /// Nexus Core connection and communication layer
 pub struct NexusCore {
     config: NexusConfig,
     // In a real implementation, this would contain actual connections
     // to the various Nexus core components
+    scheduler: Option<Arc<Scheduler>>,
+    state_manager: Option<Arc<StateManager>>,
 }
 
 impl NexusCore {
@@ -22,9 +34,28 @@ impl NexusCore {
         
         Ok(Self {
             config: config.clone(),
+            scheduler: None,
+            state_manager: None,
         })
     }
 
+    /// Attach the scheduler that owns node labels and placement
+    pub fn with_scheduler(mut self, scheduler: Arc<Scheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Attach the state manager of this node, for state diagnostics
+    pub fn with_state_manager(mut self, state_manager: Arc<StateManager>) -> Self {
+        self.state_manager = Some(state_manager);
+        self
+    }
+
+    /// State manager of this node, if one is attached
+    pub fn state_manager(&self) -> Option<Arc<StateManager>> {
+        self.state_manager.clone()
+    }
+
     pub async fn ping(&self) -> ApiResult<CoreStatus> {
         // Simulate communication with Nexus core
         sleep(Duration::from_millis(10)).await;
@@ -167,34 +198,62 @@ impl NexusCore {
         })
     }
 
-    pub async fn create_cluster(&self, request: &CreateClusterRequest) -> ApiResult<ClusterDetails> {
-        // Simulate cluster creation
-        sleep(Duration::from_millis(100)).await;
+    pub async fn create_cluster(&self, request: &CreateClusterRequest, dry_run: bool) -> ApiResult<ClusterDetails> {
+        let mut request = request.clone();
+        admission::admit(&mut request)?;
+
+        if !dry_run {
+            // Simulate cluster creation
+            sleep(Duration::from_millis(100)).await;
+        }
 
         Ok(ClusterDetails {
             name: request.name.clone(),
-            status: "creating".to_string(),
+            status: if dry_run { DRY_RUN_STATUS } else { "creating" }.to_string(),
             node_count: request.node_count,
             version: "0.1.0".to_string(),
             created_at: chrono::Utc::now(),
             high_availability: request.high_availability,
             endpoint: format!("https://{}.nexus.local", request.name),
-            region: request.region.clone().unwrap_or("us-west-2".to_string()),
+            region: request.region.clone().unwrap_or_default(),
             nodes: vec![],
             services: vec![],
         })
     }
 
-    pub async fn delete_cluster(&self, name: &str) -> ApiResult<()> {
+    pub async fn delete_cluster(&self, name: &str, dry_run: bool) -> ApiResult<()> {
         if name == "not-found" {
             return Err(ApiError::NotFound(format!("Cluster '{}' not found", name)));
         }
+        if dry_run {
+            return Ok(());
+        }
 
         // Simulate cluster deletion
         sleep(Duration::from_millis(50)).await;
         Ok(())
     }
 
+    /// Change a cluster's node count or high-availability mode
+    pub async fn update_cluster(&self, name: &str, request: &UpdateClusterRequest, dry_run: bool) -> ApiResult<ClusterDetails> {
+        let current = self.get_cluster(name).await?;
+        if request.node_count == Some(0) {
+            return Err(ApiError::BadRequest("node_count must be at least 1".to_string()));
+        }
+
+        if !dry_run {
+            // Simulate reconfiguring the cluster
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        Ok(ClusterDetails {
+            status: if dry_run { DRY_RUN_STATUS } else { "updating" }.to_string(),
+            node_count: request.node_count.unwrap_or(current.node_count),
+            high_availability: request.high_availability.unwrap_or(current.high_availability),
+            ..current
+        })
+    }
+
     pub async fn list_services(&self, cluster: Option<&str>) -> ApiResult<Vec<ServiceInfo>> {
         Ok(vec![
             ServiceInfo {
@@ -290,21 +349,29 @@ impl NexusCore {
         })
     }
 
-    pub async fn deploy_service(&self, request: &DeployServiceRequest) -> ApiResult<ServiceDetails> {
-        // Simulate service deployment
-        sleep(Duration::from_millis(100)).await;
+    pub async fn deploy_service(&self, request: &DeployServiceRequest, dry_run: bool) -> ApiResult<ServiceDetails> {
+        let mut request = request.clone();
+        admission::admit(&mut request)?;
+
+        let pods = if dry_run {
+            self.planned_pods(&request).await?
+        } else {
+            // Simulate service deployment
+            sleep(Duration::from_millis(100)).await;
+            vec![]
+        };
 
         Ok(ServiceDetails {
             name: request.name.clone(),
             image: request.image.clone(),
-            status: "creating".to_string(),
+            status: if dry_run { DRY_RUN_STATUS } else { "creating" }.to_string(),
             replicas: request.replicas,
             ready_replicas: 0,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
             endpoint: request.ports.first().map(|p| format!("http://{}:{}", request.name, p.port)),
-            cluster: request.cluster.clone().unwrap_or("default".to_string()),
-            namespace: request.namespace.clone().unwrap_or("default".to_string()),
+            cluster: request.cluster.clone().unwrap_or_default(),
+            namespace: request.namespace.clone().unwrap_or_default(),
             environment: request.environment.clone(),
             resources: ServiceResources {
                 cpu_limit: request.resources.cpu,
@@ -314,29 +381,69 @@ impl NexusCore {
                 network_tx: 0,
                 network_rx: 0,
             },
-            pods: vec![],
+            pods,
         })
     }
 
-    pub async fn delete_service(&self, name: &str) -> ApiResult<()> {
+    pub async fn delete_service(&self, name: &str, dry_run: bool) -> ApiResult<()> {
         if name == "not-found" {
             return Err(ApiError::NotFound(format!("Service '{}' not found", name)));
         }
+        if dry_run {
+            return Ok(());
+        }
 
         // Simulate service deletion
         sleep(Duration::from_millis(50)).await;
         Ok(())
     }
 
-    pub async fn scale_service(&self, name: &str, replicas: u32) -> ApiResult<ServiceDetails> {
+    /// Replace a service's spec; name, namespace and cluster are fixed at creation
+    pub async fn update_service(&self, name: &str, request: &DeployServiceRequest, dry_run: bool) -> ApiResult<ServiceDetails> {
+        let current = self.get_service(name).await?;
+        let mut request = request.clone();
+        admission::admit_update(&current.spec(), &mut request)?;
+
+        if dry_run {
+            let pods = self.planned_pods(&request).await?;
+            return Ok(ServiceDetails {
+                status: DRY_RUN_STATUS.to_string(),
+                pods,
+                ..request.into_details(current)
+            });
+        }
+
+        // Simulate rolling out the new spec
+        sleep(Duration::from_millis(100)).await;
+
+        Ok(ServiceDetails {
+            updated_at: chrono::Utc::now(),
+            ..request.into_details(current)
+        })
+    }
+
+    pub async fn scale_service(&self, name: &str, replicas: u32, dry_run: bool) -> ApiResult<ServiceDetails> {
         if name == "not-found" {
             return Err(ApiError::NotFound(format!("Service '{}' not found", name)));
         }
 
+        let service = self.get_service(name).await?;
+        if dry_run {
+            let current = service.spec();
+            let mut scaled = DeployServiceRequest { replicas, ..current.clone() };
+            admission::admit_update(&current, &mut scaled)?;
+            let pods = self.planned_pods(&scaled).await?;
+            return Ok(ServiceDetails {
+                replicas,
+                status: DRY_RUN_STATUS.to_string(),
+                pods,
+                ..service
+            });
+        }
+
         // Simulate scaling operation
         sleep(Duration::from_millis(100)).await;
 
-        let service = self.get_service(name).await?;
         Ok(ServiceDetails {
             replicas,
             ready_replicas: replicas.min(service.ready_replicas + 1), // Simulate gradual scaling
@@ -344,6 +451,302 @@ impl NexusCore {
             ..service
         })
     }
+
+    /// Where a service's replicas would run, from a scheduling simulation
+    ///
+    /// Empty when no scheduler is connected. A service that would not fit
+    /// fails with 409 and the scheduler's reason.
+    async fn planned_pods(&self, request: &DeployServiceRequest) -> ApiResult<Vec<PodInfo>> {
+        let Some(scheduler) = self.scheduler.as_deref() else {
+            return Ok(vec![]);
+        };
+        if request.replicas == 0 {
+            return Ok(vec![]);
+        }
+
+        let workload = request.workload();
+        let placement = scheduler.simulate_placement(&workload).await
+            .map_err(|e| ApiError::Conflict(format!("Service '{}' would not be scheduled: {}", request.name, e)))?;
+        Ok(planned_nodes(&placement, request.replicas)
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| PodInfo {
+                name: format!("{}-pod-{}", request.name, i + 1),
+                status: "planned".to_string(),
+                node,
+                cpu_usage: 0.0,
+                memory_usage: 0.0,
+                restarts: 0,
+                created_at: chrono::Utc::now(),
+            })
+            .collect())
+    }
+
+    pub async fn update_node_labels(&self, node: &str, request: &NodeMetadataRequest, dry_run: bool) -> ApiResult<NodeMetadata> {
+        let update = NodeMetadataUpdate::labels(&request.changes, request.overwrite)
+            .map_err(scheduler_error)?
+            .if_resource_version(request.resource_version);
+        self.update_node_metadata(node, &update, dry_run).await
+    }
+
+    pub async fn update_node_annotations(&self, node: &str, request: &NodeMetadataRequest, dry_run: bool) -> ApiResult<NodeMetadata> {
+        let update = NodeMetadataUpdate::annotations(&request.changes, request.overwrite)
+            .map_err(scheduler_error)?
+            .if_resource_version(request.resource_version);
+        self.update_node_metadata(node, &update, dry_run).await
+    }
+
+    async fn update_node_metadata(&self, node: &str, update: &NodeMetadataUpdate, dry_run: bool) -> ApiResult<NodeMetadata> {
+        let scheduler = self.connected_scheduler()?;
+        let node_id = scheduler.resolve_node(node).await
+            .ok_or_else(|| ApiError::NotFound(format!("Node '{}' not found", node)))?;
+
+        if dry_run {
+            let current = scheduler.node_metadata(node_id).await.map_err(scheduler_error)?;
+            if update.resource_version.is_some_and(|expected| expected != current.resource_version) {
+                return Err(scheduler_error(SchedulerError::ResourceVersionConflict {
+                    resource: format!("node {}", node_id),
+                    expected: update.resource_version.unwrap_or_default(),
+                    current: current.resource_version,
+                }));
+            }
+            return update.apply(&current).map_err(scheduler_error);
+        }
+
+        scheduler.update_node_metadata(node_id, update).await.map_err(scheduler_error)
+    }
+
+    pub async fn list_held_workloads(&self) -> ApiResult<Vec<HeldWorkloadInfo>> {
+        let scheduler = self.connected_scheduler()?;
+
+        Ok(scheduler.held_workloads().await
+            .into_iter()
+            .map(|held| HeldWorkloadInfo {
+                namespace: held.id().namespace().to_string(),
+                name: held.id().name().to_string(),
+                gates: held.gates.iter().cloned().collect(),
+                held_at: held.held_at.into(),
+                resource_version: held.workload.metadata.resource_version,
+                released_by: held.released.iter().map(|r| format!("{} ({})", r.released_by, r.gate)).collect(),
+            })
+            .collect())
+    }
+
+    pub async fn approve_workload(&self, namespace: &str, name: &str, request: &ApproveWorkloadRequest, dry_run: bool) -> ApiResult<WorkloadGateResponse> {
+        let scheduler = self.connected_scheduler()?;
+        let workload_id = ResourceId::new(namespace, name, "workload");
+        if dry_run {
+            return self.gate_dry_run(scheduler, workload_id, Some(MANUAL_APPROVAL_GATE), request.resource_version).await;
+        }
+
+        let result = scheduler.approve_workload(&workload_id, &request.approver, request.resource_version).await
+            .map_err(scheduler_error)?;
+        self.gate_response(scheduler, workload_id, result).await
+    }
+
+    pub async fn remove_scheduling_gate(&self, namespace: &str, name: &str, request: &RemoveGateRequest, dry_run: bool) -> ApiResult<WorkloadGateResponse> {
+        let scheduler = self.connected_scheduler()?;
+        let workload_id = ResourceId::new(namespace, name, "workload");
+        if dry_run {
+            return self.gate_dry_run(scheduler, workload_id, Some(&request.gate), request.resource_version).await;
+        }
+
+        let result = scheduler.remove_scheduling_gate(&workload_id, &request.gate, &request.removed_by, request.resource_version).await
+            .map_err(scheduler_error)?;
+        self.gate_response(scheduler, workload_id, result).await
+    }
+
+    pub async fn reject_workload(&self, namespace: &str, name: &str, request: &RejectWorkloadRequest, dry_run: bool) -> ApiResult<()> {
+        let scheduler = self.connected_scheduler()?;
+        let workload_id = ResourceId::new(namespace, name, "workload");
+        if dry_run {
+            return self.gate_dry_run(scheduler, workload_id, None, request.resource_version).await.map(|_| ());
+        }
+
+        scheduler.reject_workload(&workload_id, &request.rejected_by, &request.reason, request.resource_version).await
+            .map_err(scheduler_error)
+    }
+
+    /// Check a gate change against the held workload without applying it
+    ///
+    /// Removing the last gate also simulates the placement that would follow.
+    async fn gate_dry_run(
+        &self,
+        scheduler: &Scheduler,
+        workload_id: ResourceId,
+        gate: Option<&str>,
+        resource_version: Option<u64>,
+    ) -> ApiResult<WorkloadGateResponse> {
+        let held = scheduler.held_workloads().await
+            .into_iter()
+            .find(|held| held.id() == &workload_id)
+            .ok_or_else(|| scheduler_error(SchedulerError::WorkloadNotFound { workload_id: workload_id.clone() }))?;
+
+        let metadata = &held.workload.metadata;
+        if !metadata.matches(resource_version) {
+            return Err(scheduler_error(SchedulerError::ResourceVersionConflict {
+                resource: format!("workload {}", workload_id),
+                expected: resource_version.unwrap_or_default(),
+                current: metadata.resource_version,
+            }));
+        }
+
+        let mut remaining_gates = held.gates.clone();
+        let scheduled_node = match gate {
+            Some(gate) => {
+                if !remaining_gates.remove(gate) {
+                    return Err(ApiError::BadRequest(format!("Workload {} has no scheduling gate '{}'", workload_id, gate)));
+                }
+                if remaining_gates.is_empty() {
+                    let placement = scheduler.simulate_placement(&held.workload).await
+                        .map_err(|e| ApiError::Conflict(format!("Workload {} would not be scheduled: {}", workload_id, e)))?;
+                    placement.node_id.map(|node_id| node_id.to_hex())
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        Ok(WorkloadGateResponse {
+            namespace: workload_id.namespace().to_string(),
+            name: workload_id.name().to_string(),
+            remaining_gates: remaining_gates.into_iter().collect(),
+            scheduled_node,
+        })
+    }
+
+    async fn gate_response(
+        &self,
+        scheduler: &Scheduler,
+        workload_id: ResourceId,
+        result: Option<SchedulingResult>,
+    ) -> ApiResult<WorkloadGateResponse> {
+        let remaining_gates = match &result {
+            Some(_) => Vec::new(),
+            None => scheduler.held_workloads().await
+                .into_iter()
+                .find(|held| held.id() == &workload_id)
+                .map(|held| held.gates.into_iter().collect())
+                .unwrap_or_default(),
+        };
+
+        Ok(WorkloadGateResponse {
+            namespace: workload_id.namespace().to_string(),
+            name: workload_id.name().to_string(),
+            remaining_gates,
+            scheduled_node: result.map(|r| r.target_node.to_hex()),
+        })
+    }
+
+    pub async fn capacity_forecast(&self, cluster: &str, horizon_days: u32) -> ApiResult<CapacityForecastReport> {
+        if horizon_days == 0 {
+            return Err(ApiError::BadRequest("horizon_days must be at least 1".to_string()));
+        }
+
+        let scheduler = self.connected_scheduler()?;
+        let forecast = scheduler
+            .capacity_forecast(cluster, Duration::from_secs(horizon_days as u64 * 86_400))
+            .await;
+        if forecast.node_count == 0 {
+            return Err(ApiError::NotFound(format!("Cluster '{}' has no ready nodes", cluster)));
+        }
+
+        Ok(CapacityForecastReport {
+            cluster: cluster.to_string(),
+            generated_at: forecast.generated_at.into(),
+            horizon_days,
+            samples: forecast.samples,
+            node_count: forecast.node_count,
+            first_exhausted: forecast.first_exhausted().map(|r| format!("{:?}", r.resource).to_lowercase()),
+            resources: forecast.resources.iter()
+                .map(|r| ResourceForecastInfo {
+                    resource: format!("{:?}", r.resource).to_lowercase(),
+                    unit: r.resource.unit().to_string(),
+                    capacity: r.capacity,
+                    current_demand: r.current_demand,
+                    utilization: r.utilization(),
+                    growth_per_day: r.growth_per_day,
+                    days_until_exhausted: r.exhausted_in.map(|d| d.as_secs_f64() / 86_400.0),
+                    exhausted_at: r.exhausted_at.map(Into::into),
+                    confidence: r.confidence,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn state_diagnostics(&self) -> ApiResult<StateDiagnosticsReport> {
+        let state_manager = self.state_manager.as_deref()
+            .ok_or_else(|| ApiError::Internal("State manager is not connected".to_string()))?;
+        let diagnostics = state_manager.diagnostics().await
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+        Ok(StateDiagnosticsReport {
+            node_id: diagnostics.node_id.to_hex(),
+            generated_at: diagnostics.generated_at.into(),
+            consensus_state: format!("{:?}", diagnostics.consensus_state).to_lowercase(),
+            term: diagnostics.term,
+            members: diagnostics.members.iter()
+                .map(|m| MemberLagInfo {
+                    node_id: m.node_id.to_hex(),
+                    match_index: m.match_index,
+                    lag: m.lag,
+                })
+                .collect(),
+            log: ConsensusLogInfo {
+                entries: diagnostics.log.entries,
+                bytes: diagnostics.log.bytes,
+                first_index: diagnostics.log.first_index,
+                last_index: diagnostics.log.last_index,
+                applied: diagnostics.log.applied,
+                compaction_backlog: diagnostics.log.compaction_backlog,
+            },
+            storage: StorageInfo {
+                keys: diagnostics.storage.keys,
+                live_bytes: diagnostics.storage.live_bytes,
+                disk_bytes: diagnostics.storage.disk_bytes,
+                reclaimable_bytes: diagnostics.storage.reclaimable_bytes,
+                wal_enabled: diagnostics.storage.wal_enabled,
+            },
+            watches: WatchInfo {
+                watchers: diagnostics.watches.watchers,
+                by_prefix: diagnostics.watches.by_prefix,
+                revision: diagnostics.watches.revision,
+                oldest_revision: diagnostics.watches.oldest_revision,
+                journal_len: diagnostics.watches.journal_len,
+                journal_capacity: diagnostics.watches.journal_capacity,
+            },
+            slow_proposal_threshold_ms: diagnostics.slow_proposal_threshold.as_millis() as u64,
+            slow_proposals: diagnostics.slow_proposals.iter()
+                .map(|t| ProposalTraceInfo {
+                    kind: t.kind.clone(),
+                    key: t.key.clone(),
+                    submitted_at: t.submitted_at.into(),
+                    queued_ms: t.queued.as_secs_f64() * 1000.0,
+                    commit_ms: t.commit.as_secs_f64() * 1000.0,
+                    total_ms: t.total().as_secs_f64() * 1000.0,
+                    error: t.error.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    fn connected_scheduler(&self) -> ApiResult<&Scheduler> {
+        self.scheduler.as_deref()
+            .ok_or_else(|| ApiError::Internal("Scheduler is not connected".to_string()))
+    }
+}
+
+fn scheduler_error(err: SchedulerError) -> ApiError {
+    match err {
+        SchedulerError::NodeNotFound { node_id } => ApiError::NotFound(format!("Node '{}' not found", node_id)),
+        SchedulerError::InvalidNodeMetadata { message } => ApiError::BadRequest(message),
+        SchedulerError::WorkloadNotFound { workload_id } => ApiError::NotFound(format!("Workload '{}' is not held", workload_id)),
+        SchedulerError::InvalidWorkload { message } => ApiError::BadRequest(message),
+        err @ SchedulerError::SchedulingGated { .. } => ApiError::Conflict(err.to_string()),
+        err @ SchedulerError::ResourceVersionConflict { .. } => ApiError::Conflict(err.to_string()),
+        other => ApiError::Internal(other.to_string()),
+    }
 }
 
 // Data structures
@@ -448,6 +851,98 @@ pub struct ServiceDetails {
     pub pods: Vec<PodInfo>,
 }
 
+impl ServiceDetails {
+    /// The spec this service is running, as a deploy request
+    ///
+    /// Ports are not tracked on the details, so the result is only suitable
+    /// for immutable-field checks.
+    pub fn spec(&self) -> DeployServiceRequest {
+        DeployServiceRequest {
+            name: self.name.clone(),
+            image: self.image.clone(),
+            replicas: self.replicas,
+            environment: self.environment.clone(),
+            resources: ServiceResourceRequest {
+                cpu: self.resources.cpu_limit,
+                memory: self.resources.memory_limit.clone(),
+            },
+            ports: Vec::new(),
+            cluster: Some(self.cluster.clone()),
+            namespace: Some(self.namespace.clone()),
+        }
+    }
+}
+
+impl DeployServiceRequest {
+    /// `current` with this spec applied
+    fn into_details(self, current: ServiceDetails) -> ServiceDetails {
+        ServiceDetails {
+            image: self.image,
+            replicas: self.replicas,
+            environment: self.environment,
+            resources: ServiceResources {
+                cpu_limit: self.resources.cpu,
+                memory_limit: self.resources.memory,
+                ..current.resources
+            },
+            ..current
+        }
+    }
+
+    /// The workload the scheduler would place for this service
+    fn workload(&self) -> Workload {
+        let id = ResourceId::new(self.namespace.as_deref().unwrap_or("default"), &self.name, "workload");
+        let defaults = ResourceQuotas::default();
+        let cpu_cores = self.resources.cpu.unwrap_or(defaults.cpu_cores);
+        let memory = self.resources.memory.as_deref()
+            .and_then(admission::memory_bytes)
+            .unwrap_or(defaults.memory_limit);
+
+        Workload {
+            id: id.clone(),
+            workload_type: WorkloadType::Interactive,
+            priority: 0,
+            spec: WorkloadSpec {
+                id,
+                name: self.name.clone(),
+                image: self.image.clone(),
+                replicas: self.replicas,
+                resources: ResourceQuotas {
+                    cpu_limit: cpu_cores,
+                    memory_limit: memory,
+                    cpu_cores,
+                    memory_mb: (memory >> 20).max(1),
+                    ..defaults
+                },
+                gpus: 0,
+                labels: HashMap::new(),
+                workload_type: WorkloadType::Interactive,
+                command: Vec::new(),
+                environment: self.environment.clone(),
+                working_dir: None,
+                volumes: Vec::new(),
+                ephemeral_volumes: Vec::new(),
+                affinity: Default::default(),
+                scheduling_gates: Vec::new(),
+                readiness_gates: Vec::new(),
+                scheduler_name: DEFAULT_SCHEDULER_NAME.to_string(),
+            },
+            metadata: ApiMeta::created(),
+            conditions: Default::default(),
+        }
+    }
+}
+
+/// Node each replica runs on under a placement decision
+fn planned_nodes(placement: &PlacementDecision, replicas: u32) -> Vec<String> {
+    let nodes: Vec<NodeId> = if placement.replica_nodes.is_empty() {
+        placement.node_id.into_iter().collect()
+    } else {
+        placement.replica_nodes.clone()
+    };
+    nodes.iter().cycle().take(replicas as usize).map(|node_id| node_id.to_hex()).collect()
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServiceSummary {
     pub name: String,
@@ -478,9 +973,187 @@ pub struct PodInfo {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeldWorkloadInfo {
+    pub namespace: String,
+    pub name: String,
+    pub gates: Vec<String>,
+    pub held_at: chrono::DateTime<chrono::Utc>,
+    pub released_by: Vec<String>,
+    /// Current version, to pass back as a write precondition
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkloadGateResponse {
+    pub namespace: String,
+    pub name: String,
+    /// Gates still blocking placement; empty once the workload is released
+    pub remaining_gates: Vec<String>,
+    /// Node the workload was placed on, if it was released and scheduled
+    pub scheduled_node: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapacityForecastReport {
+    pub cluster: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub horizon_days: u32,
+    /// Demand samples the growth trend was fitted on
+    pub samples: usize,
+    pub node_count: usize,
+    /// Resource that runs out first within the horizon
+    pub first_exhausted: Option<String>,
+    pub resources: Vec<ResourceForecastInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceForecastInfo {
+    pub resource: String,
+    pub unit: String,
+    pub capacity: f64,
+    pub current_demand: f64,
+    pub utilization: f64,
+    pub growth_per_day: f64,
+    pub days_until_exhausted: Option<f64>,
+    pub exhausted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateDiagnosticsReport {
+    pub node_id: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub consensus_state: String,
+    pub term: u64,
+    pub members: Vec<MemberLagInfo>,
+    pub log: ConsensusLogInfo,
+    pub storage: StorageInfo,
+    pub watches: WatchInfo,
+    pub slow_proposal_threshold_ms: u64,
+    /// Newest first
+    pub slow_proposals: Vec<ProposalTraceInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberLagInfo {
+    pub node_id: String,
+    /// Only known on the leader
+    pub match_index: Option<u64>,
+    pub lag: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsensusLogInfo {
+    pub entries: u64,
+    pub bytes: u64,
+    pub first_index: Option<u64>,
+    pub last_index: Option<u64>,
+    pub applied: u64,
+    /// Applied entries compaction would drop
+    pub compaction_backlog: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageInfo {
+    pub keys: u64,
+    pub live_bytes: u64,
+    pub disk_bytes: Option<u64>,
+    pub reclaimable_bytes: Option<u64>,
+    pub wal_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchInfo {
+    pub watchers: usize,
+    pub by_prefix: std::collections::BTreeMap<String, usize>,
+    pub revision: u64,
+    pub oldest_revision: Option<u64>,
+    pub journal_len: usize,
+    pub journal_capacity: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProposalTraceInfo {
+    pub kind: String,
+    pub key: Option<String>,
+    pub submitted_at: chrono::DateTime<chrono::Utc>,
+    pub queued_ms: f64,
+    pub commit_ms: f64,
+    pub total_ms: f64,
+    pub error: Option<String>,
+}
+
 // Request types
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct CapacityForecastQuery {
+    #[serde(default = "default_horizon_days")]
+    pub horizon_days: u32,
+}
+
+fn default_horizon_days() -> u32 {
+    90
+}
+
+/// `?dryRun=true` runs admission, validation and scheduling simulation but persists nothing
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DryRunQuery {
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApproveWorkloadRequest {
+    pub approver: String,
+    /// Reject the write with 409 unless the object is still at this version
+    #[serde(default)]
+    pub resource_version: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveGateRequest {
+    pub gate: String,
+    pub removed_by: String,
+    /// Reject the write with 409 unless the object is still at this version
+    #[serde(default)]
+    pub resource_version: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectWorkloadRequest {
+    pub rejected_by: String,
+    pub reason: String,
+    /// Reject the write with 409 unless the object is still at this version
+    #[serde(default)]
+    pub resource_version: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateClusterRequest {
+    #[serde(default)]
+    pub node_count: Option<u32>,
+    #[serde(default)]
+    pub high_availability: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterScaleRequest {
+    pub node_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceScaleRequest {
+    pub replicas: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ServiceListQuery {
+    pub cluster: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateClusterRequest {
     pub name: String,
     pub node_count: u32,
@@ -490,28 +1163,48 @@ pub struct CreateClusterRequest {
     pub config: Option<serde_json::Value>,
 }
 
+/// Label or annotation changes: `key=value` sets, `key-` removes
 #[derive(Debug, Serialize, Deserialize)]
+pub struct NodeMetadataRequest {
+    pub changes: Vec<String>,
+    #[serde(default)]
+    pub overwrite: bool,
+    /// Reject the write with 409 unless the object is still at this version
+    #[serde(default)]
+    pub resource_version: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeployServiceRequest {
     pub name: String,
     pub image: String,
     pub replicas: u32,
+    #[serde(default)]
     pub environment: HashMap<String, String>,
+    #[serde(default)]
     pub resources: ServiceResourceRequest,
+    #[serde(default)]
     pub ports: Vec<ServicePortRequest>,
+    /// Defaults to `default`
     pub cluster: Option<String>,
+    /// Defaults to `default`
     pub namespace: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ServiceResourceRequest {
     pub cpu: Option<f64>,
     pub memory: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServicePortRequest {
     pub name: String,
     pub port: u16,
+    /// Defaults to `port`
+    #[serde(default)]
     pub target_port: u16,
+    /// Defaults to `TCP`
+    #[serde(default)]
     pub protocol: String,
 }
\ No newline at end of file
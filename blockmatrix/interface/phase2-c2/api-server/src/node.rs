@@ -0,0 +1,35 @@
+//! Node management endpoints
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use nexus_scheduler::NodeMetadata;
+
+use crate::{
+    error::ApiResult,
+    nexus_core::{DryRunQuery, NodeMetadataRequest},
+    AppState,
+};
+
+/// PATCH /api/v1/nodes/:node/labels
+pub async fn update_labels(
+    State(state): State<AppState>,
+    Path(node): Path<String>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<NodeMetadataRequest>,
+) -> ApiResult<Json<NodeMetadata>> {
+    let metadata = state.nexus_core.update_node_labels(&node, &request, query.dry_run).await?;
+    Ok(Json(metadata))
+}
+
+/// PATCH /api/v1/nodes/:node/annotations
+pub async fn update_annotations(
+    State(state): State<AppState>,
+    Path(node): Path<String>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<NodeMetadataRequest>,
+) -> ApiResult<Json<NodeMetadata>> {
+    let metadata = state.nexus_core.update_node_annotations(&node, &request, query.dry_run).await?;
+    Ok(Json(metadata))
+}
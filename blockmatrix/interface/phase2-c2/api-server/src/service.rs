@@ -0,0 +1,95 @@
+//! Service management endpoints
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    error::{ApiError, ApiResult},
+    nexus_core::{
+        DeployServiceRequest, DryRunQuery, ServiceDetails, ServiceInfo, ServiceListQuery, ServiceScaleRequest,
+    },
+    AppState,
+};
+
+/// GET /api/v1/services?cluster=...
+pub async fn list_services(
+    State(state): State<AppState>,
+    Query(query): Query<ServiceListQuery>,
+) -> ApiResult<Json<Vec<ServiceInfo>>> {
+    let services = state.nexus_core.list_services(query.cluster.as_deref()).await?;
+    Ok(Json(services))
+}
+
+/// POST /api/v1/services
+pub async fn deploy_service(
+    State(state): State<AppState>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<DeployServiceRequest>,
+) -> ApiResult<(StatusCode, Json<ServiceDetails>)> {
+    let service = state.nexus_core.deploy_service(&request, query.dry_run).await?;
+    let status = if query.dry_run { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(service)))
+}
+
+/// GET /api/v1/services/:name
+pub async fn get_service(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<ServiceDetails>> {
+    let service = state.nexus_core.get_service(&name).await?;
+    Ok(Json(service))
+}
+
+/// DELETE /api/v1/services/:name
+pub async fn delete_service(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DryRunQuery>,
+) -> ApiResult<StatusCode> {
+    state.nexus_core.delete_service(&name, query.dry_run).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PATCH /api/v1/services/:name
+pub async fn update_service(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<DeployServiceRequest>,
+) -> ApiResult<Json<ServiceDetails>> {
+    let service = state.nexus_core.update_service(&name, &request, query.dry_run).await?;
+    Ok(Json(service))
+}
+
+/// PATCH /api/v1/services/:name/scale
+pub async fn scale_service(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<ServiceScaleRequest>,
+) -> ApiResult<Json<ServiceDetails>> {
+    let service = state.nexus_core.scale_service(&name, request.replicas, query.dry_run).await?;
+    Ok(Json(service))
+}
+
+/// GET /api/v1/services/:name/logs
+pub async fn get_logs(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<Vec<String>>> {
+    // No log source is connected yet; an existing service has no lines to show
+    state.nexus_core.get_service(&name).await?;
+    Ok(Json(Vec::new()))
+}
+
+/// POST /api/v1/services/:name/exec
+pub async fn exec_command(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.nexus_core.get_service(&name).await?;
+    Err(ApiError::Conflict(format!("Service '{}' does not accept exec sessions", name)))
+}
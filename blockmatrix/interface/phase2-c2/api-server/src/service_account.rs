@@ -0,0 +1,458 @@
+//! Service account endpoints and token authentication
+//!
+//! Workloads and external controllers call the API with a service account's
+//! scoped bearer token instead of reusing human credentials. Tokens look like
+//! `nxsa.<id>.<secret>` and only a SHA-256 hash of the secret is kept.
+//! Requests carrying one are authenticated and checked against the account's
+//! scopes before the user authentication middleware runs.
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use nexus_state::StateManager;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    error::{ApiError, ApiResult},
+    AppState,
+};
+
+const TOKEN_PREFIX: &str = "nxsa";
+const STATE_PREFIX: &str = "/service-accounts/";
+
+/// Resources a scope can name; `system` covers status, version and metrics
+const RESOURCES: &[&str] = &["clusters", "nodes", "services", "workloads", "debug", "system", "service-accounts", "*"];
+const ACTIONS: &[&str] = &["read", "write", "*"];
+
+/// Service accounts and their tokens, optionally persisted in cluster state
+pub struct ServiceAccountStore {
+    accounts: RwLock<HashMap<String, ServiceAccount>>,
+    state_manager: Option<Arc<StateManager>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ServiceAccount {
+    name: String,
+    description: Option<String>,
+    scopes: Vec<String>,
+    token_ttl_secs: Option<u64>,
+    created_at: DateTime<Utc>,
+    tokens: Vec<StoredToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    id: String,
+    secret_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Service account a request was authenticated as
+#[derive(Debug, Clone)]
+pub struct ServiceAccountIdentity {
+    pub name: String,
+    pub token_id: String,
+    pub scopes: Vec<String>,
+}
+
+impl ServiceAccountStore {
+    pub fn new() -> Self {
+        Self {
+            accounts: RwLock::new(HashMap::new()),
+            state_manager: None,
+        }
+    }
+
+    /// Persist accounts in the state layer so every API server sees them
+    pub fn with_state_manager(mut self, state_manager: Arc<StateManager>) -> Self {
+        self.state_manager = Some(state_manager);
+        self
+    }
+
+    /// Load persisted accounts from the state layer
+    pub async fn load(&self) -> ApiResult<usize> {
+        let Some(state_manager) = &self.state_manager else {
+            return Ok(0);
+        };
+
+        let mut loaded = HashMap::new();
+        for key in state_manager.list(STATE_PREFIX, None).await? {
+            if let Some(bytes) = state_manager.get(&key).await? {
+                let account: ServiceAccount = serde_json::from_slice(&bytes)?;
+                loaded.insert(account.name.clone(), account);
+            }
+        }
+
+        let count = loaded.len();
+        *self.accounts.write().await = loaded;
+        Ok(count)
+    }
+
+    pub async fn list(&self) -> Vec<ServiceAccountInfo> {
+        let accounts = self.accounts.read().await;
+        let mut infos: Vec<_> = accounts.values().map(ServiceAccount::info).collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    pub async fn get(&self, name: &str) -> ApiResult<ServiceAccountInfo> {
+        let accounts = self.accounts.read().await;
+        accounts.get(name).map(ServiceAccount::info).ok_or_else(|| not_found(name))
+    }
+
+    /// Create an account and issue its first token
+    pub async fn create(&self, request: &CreateServiceAccountRequest) -> ApiResult<IssuedTokenResponse> {
+        validate_name(&request.name)?;
+        validate_scopes(&request.scopes)?;
+
+        let now = Utc::now();
+        let mut account = ServiceAccount {
+            name: request.name.clone(),
+            description: request.description.clone(),
+            scopes: request.scopes.clone(),
+            token_ttl_secs: request.token_ttl_secs,
+            created_at: now,
+            tokens: Vec::new(),
+        };
+        let token = account.issue_token(now);
+
+        {
+            let mut accounts = self.accounts.write().await;
+            if accounts.contains_key(&request.name) {
+                return Err(ApiError::Conflict(format!("Service account '{}' already exists", request.name)));
+            }
+            accounts.insert(account.name.clone(), account.clone());
+        }
+        self.persist(&account).await?;
+
+        tracing::info!("Created service account '{}' with scopes {:?}", account.name, account.scopes);
+        Ok(IssuedTokenResponse { account: account.info(), token })
+    }
+
+    /// Issue a new token; current tokens stay valid for `grace_secs`
+    pub async fn rotate(&self, name: &str, grace_secs: u64) -> ApiResult<IssuedTokenResponse> {
+        let now = Utc::now();
+        let grace_end = now + chrono::Duration::seconds(grace_secs as i64);
+
+        let (account, token) = {
+            let mut accounts = self.accounts.write().await;
+            let account = accounts.get_mut(name).ok_or_else(|| not_found(name))?;
+            for stored in account.tokens.iter_mut().filter(|t| t.is_valid(now)) {
+                if grace_secs == 0 {
+                    stored.revoked_at = Some(now);
+                } else if stored.expires_at.map_or(true, |expires| expires > grace_end) {
+                    stored.expires_at = Some(grace_end);
+                }
+            }
+            let token = account.issue_token(now);
+            (account.clone(), token)
+        };
+        self.persist(&account).await?;
+
+        tracing::info!("Rotated token of service account '{}'", name);
+        Ok(IssuedTokenResponse { account: account.info(), token })
+    }
+
+    /// Revoke one token, or every token of the account
+    pub async fn revoke(&self, name: &str, token_id: Option<&str>) -> ApiResult<ServiceAccountInfo> {
+        let now = Utc::now();
+
+        let account = {
+            let mut accounts = self.accounts.write().await;
+            let account = accounts.get_mut(name).ok_or_else(|| not_found(name))?;
+            let mut revoked = 0;
+            for stored in account.tokens.iter_mut() {
+                if stored.revoked_at.is_none() && token_id.map_or(true, |id| id == stored.id) {
+                    stored.revoked_at = Some(now);
+                    revoked += 1;
+                }
+            }
+            if let (Some(id), 0) = (token_id, revoked) {
+                return Err(ApiError::NotFound(format!("Active token '{}' of service account '{}'", id, name)));
+            }
+            account.clone()
+        };
+        self.persist(&account).await?;
+
+        tracing::info!("Revoked {} of service account '{}'", token_id.unwrap_or("all tokens"), name);
+        Ok(account.info())
+    }
+
+    /// Resolve a bearer token to the service account it belongs to
+    pub async fn authenticate(&self, token: &str) -> ApiResult<ServiceAccountIdentity> {
+        let invalid = || ApiError::Auth("Invalid or expired service account token".to_string());
+
+        let mut parts = token.splitn(3, '.');
+        let (Some(TOKEN_PREFIX), Some(id), Some(secret)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+
+        let now = Utc::now();
+        let secret_hash = hash_secret(secret);
+        let accounts = self.accounts.read().await;
+        accounts.values()
+            .find_map(|account| {
+                account.tokens.iter()
+                    .find(|t| t.id == id && t.secret_hash == secret_hash && t.is_valid(now))
+                    .map(|t| ServiceAccountIdentity {
+                        name: account.name.clone(),
+                        token_id: t.id.clone(),
+                        scopes: account.scopes.clone(),
+                    })
+            })
+            .ok_or_else(invalid)
+    }
+
+    async fn persist(&self, account: &ServiceAccount) -> ApiResult<()> {
+        if let Some(state_manager) = &self.state_manager {
+            let key = format!("{}{}", STATE_PREFIX, account.name);
+            state_manager.set(&key, &serde_json::to_vec(account)?).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ServiceAccountStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServiceAccount {
+    fn issue_token(&mut self, now: DateTime<Utc>) -> String {
+        let id = Uuid::new_v4().simple().to_string();
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        self.tokens.push(StoredToken {
+            id: id.clone(),
+            secret_hash: hash_secret(&secret),
+            created_at: now,
+            expires_at: self.token_ttl_secs.map(|ttl| now + chrono::Duration::seconds(ttl as i64)),
+            revoked_at: None,
+        });
+        format!("{}.{}.{}", TOKEN_PREFIX, id, secret)
+    }
+
+    fn info(&self) -> ServiceAccountInfo {
+        let now = Utc::now();
+        ServiceAccountInfo {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            scopes: self.scopes.clone(),
+            created_at: self.created_at,
+            tokens: self.tokens.iter()
+                .map(|t| TokenInfo {
+                    id: t.id.clone(),
+                    created_at: t.created_at,
+                    expires_at: t.expires_at,
+                    revoked_at: t.revoked_at,
+                    active: t.is_valid(now),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl StoredToken {
+    fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map_or(true, |expires| now < expires)
+    }
+}
+
+impl ServiceAccountIdentity {
+    /// Check the account's scopes cover a request
+    pub fn authorize(&self, method: &Method, path: &str) -> ApiResult<()> {
+        let (resource, action) = required_scope(method, path);
+        let allowed = self.scopes.iter().any(|scope| {
+            let (scope_resource, scope_action) = scope.split_once(':').unwrap_or((scope, ""));
+            (scope_resource == "*" || scope_resource == resource)
+                && (scope_action == "*" || scope_action == action)
+        });
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(format!(
+                "Service account '{}' lacks scope {}:{}",
+                self.name, resource, action
+            )))
+        }
+    }
+}
+
+/// Scope a request needs; paths outside the REST API need a `*` scope
+fn required_scope(method: &Method, path: &str) -> (String, &'static str) {
+    let action = if matches!(*method, Method::GET | Method::HEAD) { "read" } else { "write" };
+    let resource = match path.strip_prefix("/api/v1/").and_then(|rest| rest.split('/').next()) {
+        Some("status" | "version" | "metrics") => "system",
+        Some(resource) if !resource.is_empty() => resource,
+        _ => "*",
+    };
+    (resource.to_string(), action)
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn validate_name(name: &str) -> ApiResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "Service account name '{}' must be 1-63 lowercase letters, digits or '-'",
+            name
+        )))
+    }
+}
+
+fn validate_scopes(scopes: &[String]) -> ApiResult<()> {
+    if scopes.is_empty() {
+        return Err(ApiError::BadRequest("A service account needs at least one scope".to_string()));
+    }
+    for scope in scopes {
+        let valid = scope.split_once(':')
+            .is_some_and(|(resource, action)| RESOURCES.contains(&resource) && ACTIONS.contains(&action));
+        if !valid {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid scope '{}', expected <resource>:<read|write|*> with resource one of {}",
+                scope,
+                RESOURCES.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn not_found(name: &str) -> ApiError {
+    ApiError::NotFound(format!("Service account '{}'", name))
+}
+
+/// Authenticate service account tokens and enforce their scopes
+///
+/// Other requests pass through to user authentication unchanged.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let token = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| token.split('.').next() == Some(TOKEN_PREFIX))
+        .map(str::to_string);
+
+    if let Some(token) = token {
+        let identity = state.service_accounts.authenticate(&token).await?;
+        identity.authorize(request.method(), request.uri().path())?;
+        tracing::debug!("Request authenticated as service account '{}'", identity.name);
+        request.extensions_mut().insert(identity);
+    }
+
+    Ok(next.run(request).await)
+}
+
+// Handlers
+
+/// GET /api/v1/service-accounts
+pub async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<ServiceAccountInfo>>> {
+    Ok(Json(state.service_accounts.list().await))
+}
+
+/// POST /api/v1/service-accounts
+pub async fn create(
+    State(state): State<AppState>,
+    Json(request): Json<CreateServiceAccountRequest>,
+) -> ApiResult<(StatusCode, Json<IssuedTokenResponse>)> {
+    let issued = state.service_accounts.create(&request).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+/// GET /api/v1/service-accounts/:name
+pub async fn get(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<ServiceAccountInfo>> {
+    Ok(Json(state.service_accounts.get(&name).await?))
+}
+
+/// POST /api/v1/service-accounts/:name/rotate
+pub async fn rotate(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<RotateTokenRequest>,
+) -> ApiResult<Json<IssuedTokenResponse>> {
+    Ok(Json(state.service_accounts.rotate(&name, request.grace_secs).await?))
+}
+
+/// POST /api/v1/service-accounts/:name/revoke
+pub async fn revoke(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<RevokeTokenRequest>,
+) -> ApiResult<Json<ServiceAccountInfo>> {
+    Ok(Json(state.service_accounts.revoke(&name, request.token_id.as_deref()).await?))
+}
+
+// Data structures
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceAccountInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub tokens: Vec<TokenInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub active: bool,
+}
+
+/// New token, shown once
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedTokenResponse {
+    pub account: ServiceAccountInfo,
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateServiceAccountRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub scopes: Vec<String>,
+    pub token_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateTokenRequest {
+    #[serde(default)]
+    pub grace_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeTokenRequest {
+    pub token_id: Option<String>,
+}
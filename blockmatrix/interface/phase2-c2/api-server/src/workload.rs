@@ -0,0 +1,55 @@
+//! Workload scheduling gate endpoints
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    error::ApiResult,
+    nexus_core::{
+        ApproveWorkloadRequest, DryRunQuery, HeldWorkloadInfo, RejectWorkloadRequest, RemoveGateRequest,
+        WorkloadGateResponse,
+    },
+    AppState,
+};
+
+/// GET /api/v1/workloads/held
+pub async fn list_held(State(state): State<AppState>) -> ApiResult<Json<Vec<HeldWorkloadInfo>>> {
+    let held = state.nexus_core.list_held_workloads().await?;
+    Ok(Json(held))
+}
+
+/// POST /api/v1/workloads/:namespace/:name/approve
+pub async fn approve(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<ApproveWorkloadRequest>,
+) -> ApiResult<Json<WorkloadGateResponse>> {
+    let response = state.nexus_core.approve_workload(&namespace, &name, &request, query.dry_run).await?;
+    Ok(Json(response))
+}
+
+/// POST /api/v1/workloads/:namespace/:name/gates/remove
+pub async fn remove_gate(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<RemoveGateRequest>,
+) -> ApiResult<Json<WorkloadGateResponse>> {
+    let response = state.nexus_core.remove_scheduling_gate(&namespace, &name, &request, query.dry_run).await?;
+    Ok(Json(response))
+}
+
+/// POST /api/v1/workloads/:namespace/:name/reject
+pub async fn reject(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<RejectWorkloadRequest>,
+) -> ApiResult<StatusCode> {
+    state.nexus_core.reject_workload(&namespace, &name, &request, query.dry_run).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
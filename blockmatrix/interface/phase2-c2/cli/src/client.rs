@@ -274,10 +274,389 @@ impl NexusClient {
         let service = response.json().await?;
         Ok(service)
     }
+    
+    /// Set or remove node labels (`key=value` / `key-`)
+    pub async fn update_node_labels(&self, node: &str, request: &NodeMetadataRequest) -> Result<NodeMetadataResponse> {
+        self.update_node_metadata(node, "labels", request).await
+    }
+    
+    /// Set or remove node annotations (`key=value` / `key-`)
+    pub async fn update_node_annotations(&self, node: &str, request: &NodeMetadataRequest) -> Result<NodeMetadataResponse> {
+        self.update_node_metadata(node, "annotations", request).await
+    }
+    
+    async fn update_node_metadata(&self, node: &str, kind: &str, body: &NodeMetadataRequest) -> Result<NodeMetadataResponse> {
+        let url = self.base_url.join(&format!("/api/v1/nodes/{}/{}", node, kind))?;
+        
+        let mut request = self.http_client.patch(url)
+            .json(body);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Failed to update {} on node '{}': {} {}",
+                kind,
+                node,
+                status,
+                detail
+            ));
+        }
+        
+        let metadata = response.json().await?;
+        Ok(metadata)
+    }
+    
+    /// Forecast when the cluster runs out of capacity
+    pub async fn get_capacity_forecast(&self, cluster: &str, horizon_days: u32) -> Result<CapacityForecastResponse> {
+        let mut url = self.base_url.join(&format!("/api/v1/clusters/{}/capacity-forecast", cluster))?;
+        url.query_pairs_mut().append_pair("horizon_days", &horizon_days.to_string());
+        
+        let mut request = self.http_client.get(url);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to get capacity forecast for '{}': {}",
+                cluster,
+                response.status()
+            ));
+        }
+        
+        let forecast = response.json().await?;
+        Ok(forecast)
+    }
+    
+    /// Diagnostics of the state layer on the node serving the API
+    pub async fn get_state_diagnostics(&self) -> Result<StateDiagnosticsResponse> {
+        let url = self.base_url.join("/api/v1/debug/state")?;
+        
+        let mut request = self.http_client.get(url);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to get state diagnostics: {}",
+                response.status()
+            ));
+        }
+        
+        let diagnostics = response.json().await?;
+        Ok(diagnostics)
+    }
+    
+    /// List workloads waiting on scheduling gates
+    pub async fn list_held_workloads(&self) -> Result<Vec<HeldWorkloadResponse>> {
+        let url = self.base_url.join("/api/v1/workloads/held")?;
+        
+        let mut request = self.http_client.get(url);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to list held workloads: {}",
+                response.status()
+            ));
+        }
+        
+        let held = response.json().await?;
+        Ok(held)
+    }
+    
+    /// Approve a workload held for manual approval
+    pub async fn approve_workload(&self, namespace: &str, name: &str, approver: &str) -> Result<WorkloadGateResponse> {
+        let url = self.base_url.join(&format!("/api/v1/workloads/{}/{}/approve", namespace, name))?;
+        
+        let mut request = self.http_client.post(url)
+            .json(&serde_json::json!({ "approver": approver }));
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to approve workload '{}/{}': {}",
+                namespace,
+                name,
+                response.status()
+            ));
+        }
+        
+        let result = response.json().await?;
+        Ok(result)
+    }
+    
+    /// Reject a held workload
+    pub async fn reject_workload(&self, namespace: &str, name: &str, rejected_by: &str, reason: &str) -> Result<()> {
+        let url = self.base_url.join(&format!("/api/v1/workloads/{}/{}/reject", namespace, name))?;
+        
+        let mut request = self.http_client.post(url)
+            .json(&serde_json::json!({ "rejected_by": rejected_by, "reason": reason }));
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to reject workload '{}/{}': {}",
+                namespace,
+                name,
+                response.status()
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    /// List service accounts
+    pub async fn list_service_accounts(&self) -> Result<Vec<ServiceAccountResponse>> {
+        let url = self.base_url.join("/api/v1/service-accounts")?;
+        
+        let mut request = self.http_client.get(url);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to list service accounts: {}",
+                response.status()
+            ));
+        }
+        
+        let accounts = response.json().await?;
+        Ok(accounts)
+    }
+    
+    /// Get a service account and its tokens
+    pub async fn get_service_account(&self, name: &str) -> Result<ServiceAccountResponse> {
+        let url = self.base_url.join(&format!("/api/v1/service-accounts/{}", name))?;
+        
+        let mut request = self.http_client.get(url);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to get service account '{}': {}",
+                name,
+                response.status()
+            ));
+        }
+        
+        let account = response.json().await?;
+        Ok(account)
+    }
+    
+    /// Create a service account and its first token
+    pub async fn create_service_account(&self, body: &serde_json::Value) -> Result<IssuedTokenResponse> {
+        let url = self.base_url.join("/api/v1/service-accounts")?;
+        self.service_account_action(url, body, "create service account").await
+    }
+    
+    /// Issue a new token, keeping current ones valid for `grace_secs`
+    pub async fn rotate_service_account(&self, name: &str, grace_secs: u64) -> Result<IssuedTokenResponse> {
+        let url = self.base_url.join(&format!("/api/v1/service-accounts/{}/rotate", name))?;
+        self.service_account_action(url, &serde_json::json!({ "grace_secs": grace_secs }), "rotate service account token").await
+    }
+    
+    /// Revoke one token of a service account, or all of them
+    pub async fn revoke_service_account(&self, name: &str, token_id: Option<&str>) -> Result<ServiceAccountResponse> {
+        let url = self.base_url.join(&format!("/api/v1/service-accounts/{}/revoke", name))?;
+        self.service_account_action(url, &serde_json::json!({ "token_id": token_id }), "revoke service account token").await
+    }
+    
+    async fn service_account_action<T: serde::de::DeserializeOwned>(&self, url: Url, body: &serde_json::Value, action: &str) -> Result<T> {
+        let mut request = self.http_client.post(url)
+            .json(body);
+        
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Failed to {}: {} {}", action, status, detail));
+        }
+        
+        let result = response.json().await?;
+        Ok(result)
+    }
 }
 
 // API Response Types
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceAccountResponse {
+    pub name: String,
+    pub description: Option<String>,
+    pub scopes: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub tokens: Vec<ServiceAccountTokenResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceAccountTokenResponse {
+    pub id: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedTokenResponse {
+    pub account: ServiceAccountResponse,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapacityForecastResponse {
+    pub cluster: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub horizon_days: u32,
+    pub samples: usize,
+    pub node_count: usize,
+    pub first_exhausted: Option<String>,
+    pub resources: Vec<ResourceForecastResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceForecastResponse {
+    pub resource: String,
+    pub unit: String,
+    pub capacity: f64,
+    pub current_demand: f64,
+    pub utilization: f64,
+    pub growth_per_day: f64,
+    pub days_until_exhausted: Option<f64>,
+    pub exhausted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateDiagnosticsResponse {
+    pub node_id: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub consensus_state: String,
+    pub term: u64,
+    pub members: Vec<MemberLagResponse>,
+    pub log: ConsensusLogResponse,
+    pub storage: StateStorageResponse,
+    pub watches: WatchResponse,
+    pub slow_proposal_threshold_ms: u64,
+    pub slow_proposals: Vec<ProposalTraceResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberLagResponse {
+    pub node_id: String,
+    pub match_index: Option<u64>,
+    pub lag: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsensusLogResponse {
+    pub entries: u64,
+    pub bytes: u64,
+    pub first_index: Option<u64>,
+    pub last_index: Option<u64>,
+    pub applied: u64,
+    pub compaction_backlog: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateStorageResponse {
+    pub keys: u64,
+    pub live_bytes: u64,
+    pub disk_bytes: Option<u64>,
+    pub reclaimable_bytes: Option<u64>,
+    pub wal_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchResponse {
+    pub watchers: usize,
+    pub by_prefix: std::collections::BTreeMap<String, usize>,
+    pub revision: u64,
+    pub oldest_revision: Option<u64>,
+    pub journal_len: usize,
+    pub journal_capacity: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProposalTraceResponse {
+    pub kind: String,
+    pub key: Option<String>,
+    pub submitted_at: chrono::DateTime<chrono::Utc>,
+    pub queued_ms: f64,
+    pub commit_ms: f64,
+    pub total_ms: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeldWorkloadResponse {
+    pub namespace: String,
+    pub name: String,
+    pub gates: Vec<String>,
+    pub held_at: chrono::DateTime<chrono::Utc>,
+    pub released_by: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkloadGateResponse {
+    pub namespace: String,
+    pub name: String,
+    pub remaining_gates: Vec<String>,
+    pub scheduled_node: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeMetadataResponse {
+    pub labels: std::collections::HashMap<String, String>,
+    pub annotations: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SystemStatusResponse {
     pub cluster_health: String,
@@ -411,4 +790,12 @@ pub struct ServicePort {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServiceScaleRequest {
     pub replicas: u32,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeMetadataRequest {
+    pub changes: Vec<String>,
+    pub overwrite: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_version: Option<u64>,
+}
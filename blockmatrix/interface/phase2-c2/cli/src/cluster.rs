@@ -84,6 +84,16 @@ pub enum ClusterCommand {
         version: String,
     },
 
+    /// Forecast when CPU, memory or GPU capacity runs out at current growth
+    CapacityForecast {
+        /// Cluster name
+        name: String,
+        
+        /// How far ahead to look, in days
+        #[arg(long, default_value = "90")]
+        horizon_days: u32,
+    },
+
     /// Get cluster configuration
     Config {
         /// Cluster name
@@ -125,12 +135,59 @@ pub async fn execute_command(
             upgrade_cluster(client, &name, &version, output_format).await
         },
 
+        ClusterCommand::CapacityForecast { name, horizon_days } => {
+            capacity_forecast(client, &name, horizon_days, output_format).await
+        },
+
         ClusterCommand::Config { name, format } => {
             get_cluster_config(client, &name, &format, output_format).await
         },
     }
 }
 
+async fn capacity_forecast(
+    client: &NexusClient,
+    name: &str,
+    horizon_days: u32,
+    output_format: &str,
+) -> Result<()> {
+    let forecast = client.get_capacity_forecast(name, horizon_days).await?;
+
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&forecast)?),
+        "yaml" => println!("{}", serde_yaml::to_string(&forecast)?),
+        _ => {
+            println!("{} Capacity forecast for '{}' ({} nodes, next {} days, {} samples)",
+                     "●".bright_blue(), name.bright_white(), forecast.node_count, horizon_days, forecast.samples);
+            println!();
+            println!("  {:<8} {:>12} {:>12} {:>7} {:>12} {:>16} {:>6}",
+                     "RESOURCE", "CAPACITY", "DEMAND", "USED", "GROWTH/DAY", "EXHAUSTED IN", "FIT");
+            for r in &forecast.resources {
+                let exhausted = match r.days_until_exhausted {
+                    Some(days) if days < 1.0 => "now".bright_red().to_string(),
+                    Some(days) => format!("{:.0} days", days).bright_yellow().to_string(),
+                    None => "-".dimmed().to_string(),
+                };
+                println!("  {:<8} {:>12} {:>12} {:>6.0}% {:>+12.2} {:>16} {:>5.0}%",
+                         r.resource,
+                         format!("{:.1} {}", r.capacity, r.unit),
+                         format!("{:.1}", r.current_demand),
+                         r.utilization * 100.0,
+                         r.growth_per_day,
+                         exhausted,
+                         r.confidence * 100.0);
+            }
+            println!();
+            match &forecast.first_exhausted {
+                Some(resource) => println!("{} {} runs out first within the horizon", "⚠".bright_yellow(), resource.bright_white()),
+                None => println!("{} No resource runs out within {} days at current growth", "✓".bright_green(), horizon_days),
+            }
+        }
+    }
+
+    Ok(())
+}
+
 async fn create_cluster(
     client: &NexusClient,
     name: &str,
@@ -0,0 +1,117 @@
+//! Kubernetes manifest conversion command
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::*;
+use nexus_integration::convert::{convert_manifests, ConversionOutput};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// Kubernetes manifest file (multi-document YAML, '-' for stdin)
+    #[arg(short, long, value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Write converted objects into this directory instead of stdout
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Exit with an error if any field could not be converted
+    #[arg(long)]
+    pub strict: bool,
+}
+
+pub async fn execute_command(args: ConvertArgs, output_format: &str) -> Result<()> {
+    let input = read_input(&args.file)?;
+    let converted = convert_manifests(&input)
+        .with_context(|| format!("Failed to convert {}", args.file.display()))?;
+
+    match &args.output_dir {
+        Some(dir) => write_objects(dir, &converted)?,
+        None => {
+            let rendered = match output_format {
+                "json" => serde_json::to_string_pretty(&converted)?,
+                _ => serde_yaml::to_string(&converted)?,
+            };
+            println!("{}", rendered);
+        }
+    }
+
+    print_summary(&converted);
+
+    if args.strict && !converted.report.is_clean() {
+        anyhow::bail!(
+            "{} field(s) could not be converted",
+            converted.report.unsupported.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn read_input(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)?;
+        return Ok(input);
+    }
+
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest file: {}", path.display()))
+}
+
+fn write_objects(dir: &Path, converted: &ConversionOutput) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory: {}", dir.display()))?;
+
+    for service in &converted.services {
+        let path = dir.join(format!("service-{}.yaml", service.name));
+        std::fs::write(&path, serde_yaml::to_string(service)?)?;
+        println!("{} Wrote {}", "✓".bright_green(), path.display());
+    }
+
+    for workload in &converted.workloads {
+        let path = dir.join(format!("workload-{}.yaml", workload.spec.name));
+        std::fs::write(&path, serde_yaml::to_string(workload)?)?;
+        println!("{} Wrote {}", "✓".bright_green(), path.display());
+    }
+
+    for policy in &converted.network_policies {
+        let path = dir.join(format!("network-policy-{}.yaml", policy.name));
+        std::fs::write(&path, serde_yaml::to_string(policy)?)?;
+        println!("{} Wrote {}", "✓".bright_green(), path.display());
+    }
+
+    Ok(())
+}
+
+fn print_summary(converted: &ConversionOutput) {
+    eprintln!(
+        "{} Converted {} service(s), {} workload(s), {} network policy(ies)",
+        "●".bright_blue(),
+        converted.services.len(),
+        converted.workloads.len(),
+        converted.network_policies.len(),
+    );
+
+    if converted.report.is_clean() {
+        return;
+    }
+
+    eprintln!("{} Unsupported fields:", "⚠".bright_yellow());
+    for field in &converted.report.unsupported {
+        let location = if field.path.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", field.path)
+        };
+        eprintln!(
+            "  {} {}/{}{}: {}",
+            "→".dimmed(),
+            field.kind.bright_white(),
+            field.name,
+            location.bright_yellow(),
+            field.reason.dimmed(),
+        );
+    }
+}
@@ -153,6 +153,25 @@ pub enum DebugCommand {
         #[arg(long)]
         certs: bool,
     },
+
+    /// Show consensus, storage and watch health of the state layer
+    State {
+        /// Show one section only
+        #[command(subcommand)]
+        view: Option<StateView>,
+    },
+}
+
+#[derive(Subcommand, Clone, Copy, PartialEq, Eq)]
+pub enum StateView {
+    /// Consensus lag per member
+    Members,
+    /// Consensus log and store sizes, with compaction backlog
+    Log,
+    /// Watchers per prefix and the change journal
+    Watches,
+    /// Recent proposals slower than the threshold
+    Slow,
 }
 
 #[derive(Subcommand)]
@@ -232,7 +251,128 @@ pub async fn execute_command(
         DebugCommand::Troubleshoot { resource, network, dns, certs } => {
             troubleshoot_resource(client, &resource, network, dns, certs, output_format).await
         },
+
+        DebugCommand::State { view } => {
+            show_state(client, view, output_format).await
+        },
+    }
+}
+
+async fn show_state(client: &NexusClient, view: Option<StateView>, output_format: &str) -> Result<()> {
+    let state = client.get_state_diagnostics().await?;
+
+    match output_format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&state)?);
+            return Ok(());
+        },
+        "yaml" => {
+            println!("{}", serde_yaml::to_string(&state)?);
+            return Ok(());
+        },
+        _ => {}
+    }
+
+    let shows = |section| view.is_none() || view == Some(section);
+
+    println!("{} State layer on {} ({}, term {})",
+             "●".bright_blue(), state.node_id.bright_white(), state.consensus_state.bright_cyan(), state.term);
+
+    if shows(StateView::Members) {
+        println!();
+        println!("  {:<20} {:>12} {:>10}", "MEMBER", "MATCH INDEX", "LAG");
+        for member in &state.members {
+            let lag = match member.lag {
+                Some(0) => "0".bright_green().to_string(),
+                Some(lag) => lag.to_string().bright_yellow().to_string(),
+                None => "-".dimmed().to_string(),
+            };
+            println!("  {:<20} {:>12} {:>10}",
+                     short_id(&member.node_id),
+                     member.match_index.map_or("-".to_string(), |i| i.to_string()),
+                     lag);
+        }
+        if state.consensus_state != "leader" {
+            println!("  {} Lag is only known on the leader", "→".dimmed());
+        }
+    }
+
+    if shows(StateView::Log) {
+        let log = &state.log;
+        let storage = &state.storage;
+        println!();
+        println!("  {} Log: {} entries, {} (indexes {}..{})",
+                 "→".dimmed(),
+                 log.entries.to_string().bright_cyan(),
+                 format_bytes(log.bytes).bright_cyan(),
+                 log.first_index.map_or("-".to_string(), |i| i.to_string()),
+                 log.last_index.map_or("-".to_string(), |i| i.to_string()));
+        println!("  {} Applied: {}, compaction backlog: {} entries",
+                 "→".dimmed(), log.applied, log.compaction_backlog.to_string().bright_cyan());
+        println!("  {} Store: {} keys, {} live",
+                 "→".dimmed(), storage.keys.to_string().bright_cyan(), format_bytes(storage.live_bytes).bright_cyan());
+        if let Some(disk_bytes) = storage.disk_bytes {
+            println!("  {} On disk: {} ({} reclaimable), WAL {}",
+                     "→".dimmed(),
+                     format_bytes(disk_bytes).bright_cyan(),
+                     storage.reclaimable_bytes.map_or("-".to_string(), format_bytes),
+                     if storage.wal_enabled { "enabled".bright_green() } else { "disabled".dimmed() });
+        }
+    }
+
+    if shows(StateView::Watches) {
+        let watches = &state.watches;
+        println!();
+        println!("  {} Watchers: {} at revision {} (journal {}/{}, oldest {})",
+                 "→".dimmed(),
+                 watches.watchers.to_string().bright_cyan(),
+                 watches.revision,
+                 watches.journal_len,
+                 watches.journal_capacity,
+                 watches.oldest_revision.map_or("-".to_string(), |r| r.to_string()));
+        for (prefix, count) in &watches.by_prefix {
+            println!("    {:<40} {:>6}", prefix, count);
+        }
+    }
+
+    if shows(StateView::Slow) {
+        println!();
+        if state.slow_proposals.is_empty() {
+            println!("{} No proposals slower than {}ms", "✓".bright_green(), state.slow_proposal_threshold_ms);
+        } else {
+            println!("  {:<20} {:<16} {:<32} {:>10} {:>10} {:>10}",
+                     "SUBMITTED", "KIND", "KEY", "QUEUED", "COMMIT", "TOTAL");
+            for trace in &state.slow_proposals {
+                println!("  {:<20} {:<16} {:<32} {:>8.1}ms {:>8.1}ms {:>10}",
+                         trace.submitted_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                         trace.kind,
+                         trace.key.as_deref().unwrap_or("-"),
+                         trace.queued_ms,
+                         trace.commit_ms,
+                         format!("{:.1}ms", trace.total_ms).bright_yellow());
+                if let Some(error) = &trace.error {
+                    println!("    {} {}", "✗".bright_red(), error);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn short_id(node_id: &str) -> &str {
+    node_id.get(..16).unwrap_or(node_id)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
     }
+    format!("{:.1} {}", value, UNITS[unit])
 }
 
 async fn show_logs(
@@ -11,6 +11,7 @@ use tracing::info;
 
 mod cluster;
 mod service;
+mod service_account;
 mod config;
 mod output;
 mod client;
@@ -21,9 +22,11 @@ mod security;
 mod debug;
 mod workload;
 mod metrics;
+mod convert;
 
 use cluster::ClusterCommand;
 use service::ServiceCommand;
+use service_account::ServiceAccountCommand;
 use config::ConfigCommand;
 use node::NodeCommand;
 use network::NetworkCommand;
@@ -32,6 +35,7 @@ use security::SecurityCommand;
 use debug::DebugCommand;
 use workload::WorkloadCommand;
 use metrics::MetricsCommand;
+use convert::ConvertArgs;
 
 #[derive(Parser)]
 #[command(name = "nexus")]
@@ -46,6 +50,7 @@ EXAMPLES:
   nexus service deploy nginx:1.20 --replicas 5
   nexus service scale myapp --replicas 10
   nexus cluster status --detailed
+  nexus convert -f k8s.yaml
 ")]
 struct Cli {
     /// Increase logging verbosity (-v, -vv, -vvv)
@@ -88,6 +93,13 @@ enum Commands {
         command: ServiceCommand,
     },
 
+    /// Service accounts and their API tokens, for automation
+    #[command(alias = "sa")]
+    ServiceAccount {
+        #[command(subcommand)]
+        command: ServiceAccountCommand,
+    },
+
     /// Configuration management
     #[command(alias = "cfg")]
     Config {
@@ -137,6 +149,9 @@ enum Commands {
         command: MetricsCommand,
     },
 
+    /// Convert Kubernetes manifests into Nexus objects
+    Convert(ConvertArgs),
+
     /// Display system status and health
     Status {
         /// Show detailed status information
@@ -207,6 +222,10 @@ async fn main() -> Result<()> {
             service::execute_command(command, &client, &cli.output).await
         },
 
+        Commands::ServiceAccount { command } => {
+            service_account::execute_command(command, &client, &cli.output).await
+        },
+
         Commands::Config { command } => {
             config::execute_command(command, &cli.output).await
         },
@@ -239,6 +258,10 @@ async fn main() -> Result<()> {
             metrics::execute_command(command, &client, &cli.output).await
         },
 
+        Commands::Convert(args) => {
+            convert::execute_command(args, &cli.output).await
+        },
+
         Commands::Status { detailed, watch } => {
             execute_status(detailed, watch, &client, &cli.output).await
         },
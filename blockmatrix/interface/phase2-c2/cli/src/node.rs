@@ -4,8 +4,9 @@ use anyhow::Result;
 use clap::Subcommand;
 use colored::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use crate::{client::NexusClient, output};
+use crate::{client::{NexusClient, NodeMetadataRequest}, output};
 
 #[derive(Subcommand)]
 pub enum NodeCommand {
@@ -76,32 +77,40 @@ pub enum NodeCommand {
         node_name: String,
     },
 
-    /// Label a node
+    /// Label a node (key=value to set, key- to remove)
     Label {
         /// Node name
         node_name: String,
         
-        /// Labels to add (key=value,...)
-        #[arg(short, long)]
-        labels: String,
+        /// Label changes, e.g. gpu=a100 zone-
+        #[arg(required = true)]
+        labels: Vec<String>,
         
         /// Overwrite existing labels
         #[arg(long)]
         overwrite: bool,
+        
+        /// Only apply if the node is still at this resource version
+        #[arg(long)]
+        resource_version: Option<u64>,
     },
 
-    /// Annotate a node
+    /// Annotate a node (key=value to set, key- to remove)
     Annotate {
         /// Node name
         node_name: String,
         
-        /// Annotations to add (key=value,...)
-        #[arg(short, long)]
-        annotations: String,
+        /// Annotation changes, e.g. owner=platform-team
+        #[arg(required = true)]
+        annotations: Vec<String>,
         
         /// Overwrite existing annotations
         #[arg(long)]
         overwrite: bool,
+        
+        /// Only apply if the node is still at this resource version
+        #[arg(long)]
+        resource_version: Option<u64>,
     },
 
     /// Show node resource usage
@@ -142,12 +151,12 @@ pub async fn execute_command(
             uncordon_node(client, &node_name, output_format).await
         },
 
-        NodeCommand::Label { node_name, labels, overwrite } => {
-            label_node(client, &node_name, &labels, overwrite, output_format).await
+        NodeCommand::Label { node_name, labels, overwrite, resource_version } => {
+            label_node(client, &node_name, &labels, overwrite, resource_version, output_format).await
         },
 
-        NodeCommand::Annotate { node_name, annotations, overwrite } => {
-            annotate_node(client, &node_name, &annotations, overwrite, output_format).await
+        NodeCommand::Annotate { node_name, annotations, overwrite, resource_version } => {
+            annotate_node(client, &node_name, &annotations, overwrite, resource_version, output_format).await
         },
 
         NodeCommand::Top { sort_by, no_headers } => {
@@ -419,40 +428,51 @@ async fn uncordon_node(
 async fn label_node(
     client: &NexusClient,
     node_name: &str,
-    labels: &str,
+    labels: &[String],
     overwrite: bool,
+    resource_version: Option<u64>,
     output_format: &str,
 ) -> Result<()> {
     println!("{} Labeling node '{}'...", "●".bright_blue(), node_name.bright_white());
-    println!("  {} Labels: {}", "→".dimmed(), labels.bright_cyan());
+    println!("  {} Labels: {}", "→".dimmed(), labels.join(" ").bright_cyan());
     
-    // Simulate labeling
-    use std::time::Duration;
-    use tokio::time::sleep;
-    sleep(Duration::from_millis(300)).await;
+    let request = NodeMetadataRequest { changes: labels.to_vec(), overwrite, resource_version };
+    let metadata = client.update_node_labels(node_name, &request).await?;
     
     println!("{} Node '{}' labeled successfully!", "✓".bright_green(), node_name.bright_white());
-    
-    Ok(())
+    display_metadata(&metadata.labels, output_format)
 }
 
 async fn annotate_node(
     client: &NexusClient,
     node_name: &str,
-    annotations: &str,
+    annotations: &[String],
     overwrite: bool,
+    resource_version: Option<u64>,
     output_format: &str,
 ) -> Result<()> {
     println!("{} Annotating node '{}'...", "●".bright_blue(), node_name.bright_white());
-    println!("  {} Annotations: {}", "→".dimmed(), annotations.bright_cyan());
+    println!("  {} Annotations: {}", "→".dimmed(), annotations.join(" ").bright_cyan());
     
-    // Simulate annotating
-    use std::time::Duration;
-    use tokio::time::sleep;
-    sleep(Duration::from_millis(300)).await;
+    let request = NodeMetadataRequest { changes: annotations.to_vec(), overwrite, resource_version };
+    let metadata = client.update_node_annotations(node_name, &request).await?;
     
     println!("{} Node '{}' annotated successfully!", "✓".bright_green(), node_name.bright_white());
-    
+    display_metadata(&metadata.annotations, output_format)
+}
+
+fn display_metadata(entries: &HashMap<String, String>, output_format: &str) -> Result<()> {
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(entries)?),
+        "yaml" => println!("{}", serde_yaml::to_string(entries)?),
+        _ => {
+            let mut entries: Vec<_> = entries.iter().collect();
+            entries.sort();
+            for (key, value) in entries {
+                println!("  {} {}={}", "→".dimmed(), key, value.bright_cyan());
+            }
+        }
+    }
     Ok(())
 }
 
@@ -0,0 +1,162 @@
+//! Service account commands
+//!
+//! Service accounts give workloads and controllers their own scoped API
+//! tokens, so automation does not run with a person's credentials.
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+
+use crate::client::{IssuedTokenResponse, NexusClient, ServiceAccountResponse};
+
+#[derive(Subcommand)]
+pub enum ServiceAccountCommand {
+    /// List service accounts
+    List,
+
+    /// Show a service account and its tokens
+    Get {
+        /// Service account name
+        name: String,
+    },
+
+    /// Create a service account and print its first token
+    Create {
+        /// Service account name
+        name: String,
+
+        /// Scope as <resource>:<read|write|*>, repeatable (e.g. services:write)
+        #[arg(long = "scope", required = true)]
+        scopes: Vec<String>,
+
+        /// What the account is used for
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Lifetime of issued tokens in seconds; tokens never expire if unset
+        #[arg(long)]
+        token_ttl: Option<u64>,
+    },
+
+    /// Issue a new token and print it
+    Rotate {
+        /// Service account name
+        name: String,
+
+        /// Seconds the current tokens stay valid, for rolling the new one out
+        #[arg(long, default_value = "0")]
+        grace: u64,
+    },
+
+    /// Revoke a token, or every token of the account
+    Revoke {
+        /// Service account name
+        name: String,
+
+        /// Token to revoke; all tokens if omitted
+        #[arg(long)]
+        token_id: Option<String>,
+    },
+}
+
+pub async fn execute_command(
+    command: ServiceAccountCommand,
+    client: &NexusClient,
+    output_format: &str,
+) -> Result<()> {
+    match command {
+        ServiceAccountCommand::List => {
+            let accounts = client.list_service_accounts().await?;
+            match output_format {
+                "json" => println!("{}", serde_json::to_string_pretty(&accounts)?),
+                "yaml" => println!("{}", serde_yaml::to_string(&accounts)?),
+                _ => {
+                    if accounts.is_empty() {
+                        println!("No service accounts");
+                        return Ok(());
+                    }
+                    for account in &accounts {
+                        let active = account.tokens.iter().filter(|t| t.active).count();
+                        println!("{}  {} active token(s)  scopes: {}",
+                                 account.name.bright_white(),
+                                 active,
+                                 account.scopes.join(", ").bright_cyan());
+                    }
+                }
+            }
+            Ok(())
+        },
+        ServiceAccountCommand::Get { name } => {
+            let account = client.get_service_account(&name).await?;
+            display_account(&account, output_format)
+        },
+        ServiceAccountCommand::Create { name, scopes, description, token_ttl } => {
+            let issued = client.create_service_account(&serde_json::json!({
+                "name": name,
+                "description": description,
+                "scopes": scopes,
+                "token_ttl_secs": token_ttl,
+            })).await?;
+            println!("{} Service account '{}' created", "✓".bright_green(), name);
+            display_token(&issued, output_format)
+        },
+        ServiceAccountCommand::Rotate { name, grace } => {
+            let issued = client.rotate_service_account(&name, grace).await?;
+            if grace > 0 {
+                println!("{} Token of '{}' rotated; previous tokens expire in {}s", "✓".bright_green(), name, grace);
+            } else {
+                println!("{} Token of '{}' rotated; previous tokens revoked", "✓".bright_green(), name);
+            }
+            display_token(&issued, output_format)
+        },
+        ServiceAccountCommand::Revoke { name, token_id } => {
+            client.revoke_service_account(&name, token_id.as_deref()).await?;
+            match token_id {
+                Some(id) => println!("{} Token {} of '{}' revoked", "✓".bright_green(), id, name),
+                None => println!("{} All tokens of '{}' revoked", "✓".bright_green(), name),
+            }
+            Ok(())
+        },
+    }
+}
+
+fn display_account(account: &ServiceAccountResponse, output_format: &str) -> Result<()> {
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(account)?),
+        "yaml" => println!("{}", serde_yaml::to_string(account)?),
+        _ => {
+            println!("{}", account.name.bright_white().bold());
+            if let Some(description) = &account.description {
+                println!("  {}", description.dimmed());
+            }
+            println!("  Scopes:  {}", account.scopes.join(", ").bright_cyan());
+            println!("  Created: {}", account.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
+            for token in &account.tokens {
+                let state = match (token.active, token.revoked_at) {
+                    (true, _) => "active".bright_green(),
+                    (false, Some(_)) => "revoked".bright_red(),
+                    (false, None) => "expired".bright_yellow(),
+                };
+                let expires = token.expires_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                    .unwrap_or_else(|| "never".to_string());
+                println!("  Token {}  {}  expires {}", token.id, state, expires);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn display_token(issued: &IssuedTokenResponse, output_format: &str) -> Result<()> {
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(issued)?),
+        "yaml" => println!("{}", serde_yaml::to_string(issued)?),
+        _ => {
+            println!();
+            println!("  {}", issued.token.bright_white().bold());
+            println!();
+            println!("{}", "This token is shown only once; store it in the workload's secret now.".bright_yellow());
+        }
+    }
+    Ok(())
+}
@@ -49,6 +49,33 @@ pub enum WorkloadCommand {
         #[arg(long)]
         image: String,
     },
+    
+    /// List workloads held by scheduling gates
+    Held,
+    
+    /// Approve a workload held for manual approval
+    Approve {
+        /// Workload name
+        name: String,
+        
+        /// Workload namespace
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
+    },
+    
+    /// Reject and discard a held workload
+    Reject {
+        /// Workload name
+        name: String,
+        
+        /// Workload namespace
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
+        
+        /// Reason recorded with the rejection
+        #[arg(long)]
+        reason: String,
+    },
 }
 
 pub async fn execute_command(
@@ -70,5 +97,54 @@ pub async fn execute_command(
             println!("{} CronJob '{}' created with schedule '{}'", "✓".bright_green(), name, schedule);
             Ok(())
         },
+        WorkloadCommand::Held => {
+            list_held(client, output_format).await
+        },
+        WorkloadCommand::Approve { name, namespace } => {
+            let result = client.approve_workload(&namespace, &name, &operator()).await?;
+            if result.remaining_gates.is_empty() {
+                println!("{} Workload '{}/{}' approved and released for scheduling", "✓".bright_green(), namespace, name);
+                if let Some(node) = result.scheduled_node {
+                    println!("  {} Scheduled on node {}", "→".dimmed(), node.bright_cyan());
+                }
+            } else {
+                println!("{} Workload '{}/{}' approved, still waiting on: {}", "✓".bright_green(), namespace, name,
+                         result.remaining_gates.join(", ").bright_yellow());
+            }
+            Ok(())
+        },
+        WorkloadCommand::Reject { name, namespace, reason } => {
+            client.reject_workload(&namespace, &name, &operator(), &reason).await?;
+            println!("{} Workload '{}/{}' rejected", "✗".bright_red(), namespace, name);
+            Ok(())
+        },
     }
+}
+
+async fn list_held(client: &NexusClient, output_format: &str) -> Result<()> {
+    let held = client.list_held_workloads().await?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&held)?),
+        "yaml" => println!("{}", serde_yaml::to_string(&held)?),
+        _ => {
+            if held.is_empty() {
+                println!("No held workloads");
+                return Ok(());
+            }
+            for workload in &held {
+                println!("{}/{}  held since {}  gates: {}",
+                         workload.namespace,
+                         workload.name.bright_white(),
+                         workload.held_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                         workload.gates.join(", ").bright_yellow());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Identity recorded as the approver or rejecter
+fn operator() -> String {
+    std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
 }
\ No newline at end of file
@@ -1,81 +1,434 @@
 //! Edge cache management
+//!
+//! Each edge node caches objects in a memory tier with a hard byte budget and,
+//! optionally, a disk tier with its own budget. When a tier goes over budget
+//! its `CachePolicy` picks the victims. Expired entries always go first, then
+//! the least recently used (LRU), least frequently used (LFU), soonest to
+//! expire (TTL) or fewest hits per second cached (Adaptive). Memory victims are
+//! demoted to disk instead of being dropped, and a disk entry read often
+//! enough is promoted back to memory. The disk index lives in memory only, so
+//! object files left by a previous run are removed on startup.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bytes::Bytes;
-use std::time::Duration;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
 use std::collections::HashMap;
-use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
 use serde::{Serialize, Deserialize};
+use tracing::warn;
+use super::CacheConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CachePolicy {
+    /// Evict the least recently used entry
     Lru,
+    /// Evict the least frequently used entry
     Lfu,
+    /// Evict the entry closest to expiry
     Ttl,
+    /// Evict the entry with the fewest hits per second cached
     Adaptive,
 }
 
+/// Disk tier behind the memory tier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskTierConfig {
+    /// Directory cached objects are written to
+    pub path: PathBuf,
+    /// Maximum bytes stored on disk
+    pub budget: u64,
+    /// Disk hits after which an entry is promoted to memory
+    pub promote_after_hits: u64,
+}
+
+/// Tier usage and movement between tiers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub memory_entries: usize,
+    pub memory_bytes: u64,
+    pub disk_entries: usize,
+    pub disk_bytes: u64,
+    /// Entries dropped to stay within budget
+    pub evictions: u64,
+    pub promotions: u64,
+    pub demotions: u64,
+}
+
 pub struct CacheManager {
     policy: CachePolicy,
     max_object_size: usize,
-    cache: RwLock<HashMap<String, CacheEntry>>,
+    disk: Option<DiskTierConfig>,
+    tiers: Mutex<Tiers>,
+}
+
+struct Tiers {
+    memory: Tier<Bytes>,
+    /// Objects are stored in files named after their key
+    disk: Tier<()>,
+    evictions: u64,
+    promotions: u64,
+    demotions: u64,
+}
+
+/// Entries of one tier and their total size
+struct Tier<T> {
+    entries: HashMap<String, (T, EntryMeta)>,
+    bytes: u64,
+    budget: u64,
 }
 
-struct CacheEntry {
-    data: Bytes,
-    expires_at: std::time::Instant,
+#[derive(Debug, Clone)]
+struct EntryMeta {
+    size: u64,
+    inserted_at: Instant,
+    expires_at: Instant,
     access_count: u64,
-    last_access: std::time::Instant,
+    last_access: Instant,
+    /// Hits since the entry entered its current tier
+    tier_hits: u64,
 }
 
 impl CacheManager {
-    pub fn new(policy: CachePolicy, max_object_size: usize) -> Result<Self> {
+    pub fn new(config: &CacheConfig) -> Result<Self> {
+        if let Some(disk) = &config.disk_tier {
+            clear_disk_tier(&disk.path)?;
+        }
+
         Ok(Self {
-            policy,
-            max_object_size,
-            cache: RwLock::new(HashMap::new()),
+            policy: config.policy.clone(),
+            max_object_size: config.max_object_size,
+            disk: config.disk_tier.clone(),
+            tiers: Mutex::new(Tiers {
+                memory: Tier::new(config.memory_budget),
+                disk: Tier::new(config.disk_tier.as_ref().map_or(0, |disk| disk.budget)),
+                evictions: 0,
+                promotions: 0,
+                demotions: 0,
+            }),
         })
     }
-    
+
     pub async fn put(&self, key: String, data: Bytes, ttl: Duration) -> Result<()> {
         if data.len() > self.max_object_size {
-            return Err(anyhow::anyhow!("Object too large for cache"));
+            return Err(anyhow!("Object too large for cache"));
         }
-        
-        let entry = CacheEntry {
-            data,
-            expires_at: std::time::Instant::now() + ttl,
-            access_count: 0,
-            last_access: std::time::Instant::now(),
+        let capacity = self.disk.as_ref().map_or(0, |disk| disk.budget);
+        let capacity = capacity.max(self.tiers.lock().memory.budget);
+        if data.len() as u64 > capacity {
+            return Err(anyhow!("Object exceeds the cache budget of {} bytes", capacity));
+        }
+
+        let meta = EntryMeta::new(data.len() as u64, ttl);
+        let (stale, victims) = {
+            let mut tiers = self.tiers.lock();
+            let stale = tiers.disk.remove(&key).is_some();
+            tiers.memory.insert(key.clone(), data, meta);
+            let victims = tiers.memory.evict(&self.policy, Instant::now());
+            (stale, victims)
         };
-        
-        self.cache.write().insert(key, entry);
+        if stale {
+            self.remove_file(&key).await;
+        }
+        self.demote(victims).await;
         Ok(())
     }
-    
+
     pub async fn get(&self, key: &str) -> Option<Bytes> {
-        let mut cache = self.cache.write();
-        if let Some(entry) = cache.get_mut(key) {
-            if entry.expires_at > std::time::Instant::now() {
-                entry.access_count += 1;
-                entry.last_access = std::time::Instant::now();
-                Some(entry.data.clone())
-            } else {
-                cache.remove(key);
+        let now = Instant::now();
+        let promote = {
+            let mut tiers = self.tiers.lock();
+            if let Some((data, meta)) = tiers.memory.entries.get_mut(key) {
+                if !meta.is_expired(now) {
+                    meta.touch(now);
+                    return Some(data.clone());
+                }
+                tiers.memory.remove(key);
+                return None;
+            }
+
+            let memory_budget = tiers.memory.budget;
+            let promote_after_hits = self.disk.as_ref()?.promote_after_hits;
+            let ((), meta) = tiers.disk.entries.get_mut(key)?;
+            if meta.is_expired(now) {
+                tiers.disk.remove(key);
                 None
+            } else {
+                meta.touch(now);
+                Some(meta.tier_hits >= promote_after_hits && meta.size <= memory_budget)
             }
-        } else {
-            None
+        };
+        let Some(promote) = promote else {
+            self.remove_file(key).await;
+            return None;
+        };
+
+        let data = match tokio::fs::read(self.disk_path(key)?).await {
+            Ok(data) => Bytes::from(data),
+            Err(e) => {
+                warn!("Dropping unreadable disk cache entry {}: {}", key, e);
+                self.tiers.lock().disk.remove(key);
+                return None;
+            }
+        };
+        if promote {
+            self.promote(key, data.clone()).await;
         }
+        Some(data)
     }
-    
+
     pub async fn remove(&self, key: &str) {
-        self.cache.write().remove(key);
+        let on_disk = {
+            let mut tiers = self.tiers.lock();
+            tiers.memory.remove(key);
+            tiers.disk.remove(key).is_some()
+        };
+        if on_disk {
+            self.remove_file(key).await;
+        }
     }
-    
+
+    /// Bytes cached across both tiers
     pub fn size(&self) -> u64 {
-        self.cache.read().values()
-            .map(|entry| entry.data.len() as u64)
-            .sum()
+        let tiers = self.tiers.lock();
+        tiers.memory.bytes + tiers.disk.bytes
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let tiers = self.tiers.lock();
+        CacheStats {
+            memory_entries: tiers.memory.entries.len(),
+            memory_bytes: tiers.memory.bytes,
+            disk_entries: tiers.disk.entries.len(),
+            disk_bytes: tiers.disk.bytes,
+            evictions: tiers.evictions,
+            promotions: tiers.promotions,
+            demotions: tiers.demotions,
+        }
+    }
+
+    /// Move a disk entry back to memory, demoting whatever that displaces
+    async fn promote(&self, key: &str, data: Bytes) {
+        let victims = {
+            let mut tiers = self.tiers.lock();
+            let Some(((), mut meta)) = tiers.disk.remove(key) else {
+                return;
+            };
+            meta.tier_hits = 0;
+            tiers.memory.insert(key.to_string(), data, meta);
+            tiers.promotions += 1;
+            tiers.memory.evict(&self.policy, Instant::now())
+        };
+        self.remove_file(key).await;
+        self.demote(victims).await;
     }
-}
\ No newline at end of file
+
+    /// Write memory victims to disk, or drop them without a disk tier
+    async fn demote(&self, victims: Vec<(String, Bytes, EntryMeta)>) {
+        let now = Instant::now();
+        for (key, data, mut meta) in victims {
+            let fits = self.disk.as_ref().is_some_and(|disk| meta.size <= disk.budget);
+            let path = match self.disk_path(&key) {
+                Some(path) if fits && !meta.is_expired(now) => path,
+                _ => {
+                    self.tiers.lock().evictions += 1;
+                    continue;
+                }
+            };
+            if let Err(e) = tokio::fs::write(&path, &data).await {
+                warn!("Failed to demote cache entry {} to disk: {}", key, e);
+                self.tiers.lock().evictions += 1;
+                continue;
+            }
+
+            meta.tier_hits = 0;
+            let dropped = {
+                let mut tiers = self.tiers.lock();
+                // Stored again while the file was written; memory holds the newer copy
+                if tiers.memory.entries.contains_key(&key) {
+                    None
+                } else {
+                    tiers.disk.insert(key.clone(), (), meta);
+                    tiers.demotions += 1;
+                    let dropped = tiers.disk.evict(&self.policy, now);
+                    tiers.evictions += dropped.len() as u64;
+                    Some(dropped)
+                }
+            };
+            match dropped {
+                Some(dropped) => {
+                    for (key, (), _) in dropped {
+                        self.remove_file(&key).await;
+                    }
+                }
+                None => self.remove_file(&key).await,
+            }
+        }
+    }
+
+    fn disk_path(&self, key: &str) -> Option<PathBuf> {
+        let disk = self.disk.as_ref()?;
+        Some(disk.path.join(blake3::hash(key.as_bytes()).to_hex().as_str()))
+    }
+
+    async fn remove_file(&self, key: &str) {
+        let Some(path) = self.disk_path(key) else {
+            return;
+        };
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove disk cache file {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+impl<T> Tier<T> {
+    fn new(budget: u64) -> Self {
+        Self { entries: HashMap::new(), bytes: 0, budget }
+    }
+
+    fn insert(&mut self, key: String, value: T, meta: EntryMeta) {
+        self.bytes += meta.size;
+        if let Some((_, old)) = self.entries.insert(key, (value, meta)) {
+            self.bytes -= old.size;
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<(T, EntryMeta)> {
+        let (value, meta) = self.entries.remove(key)?;
+        self.bytes -= meta.size;
+        Some((value, meta))
+    }
+
+    /// Remove entries in eviction order until the tier is within budget
+    fn evict(&mut self, policy: &CachePolicy, now: Instant) -> Vec<(String, T, EntryMeta)> {
+        if self.bytes <= self.budget {
+            return Vec::new();
+        }
+
+        let mut order: Vec<(String, EntryMeta)> = self.entries.iter()
+            .map(|(key, (_, meta))| (key.clone(), meta.clone()))
+            .collect();
+        order.sort_by(|(_, a), (_, b)| eviction_order(policy, a, b, now));
+
+        let mut victims = Vec::new();
+        for (key, _) in order {
+            if self.bytes <= self.budget {
+                break;
+            }
+            if let Some((value, meta)) = self.remove(&key) {
+                victims.push((key, value, meta));
+            }
+        }
+        victims
+    }
+}
+
+impl EntryMeta {
+    fn new(size: u64, ttl: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            size,
+            inserted_at: now,
+            expires_at: now + ttl,
+            access_count: 0,
+            last_access: now,
+            tier_hits: 0,
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at <= now
+    }
+
+    fn touch(&mut self, now: Instant) {
+        self.access_count += 1;
+        self.tier_hits += 1;
+        self.last_access = now;
+    }
+
+    fn hit_rate(&self, now: Instant) -> f64 {
+        let age = now.saturating_duration_since(self.inserted_at).as_secs_f64();
+        self.access_count as f64 / age.max(0.001)
+    }
+}
+
+/// Orders entries so that the first should be evicted first
+fn eviction_order(policy: &CachePolicy, a: &EntryMeta, b: &EntryMeta, now: Instant) -> Ordering {
+    b.is_expired(now).cmp(&a.is_expired(now)).then_with(|| match policy {
+        CachePolicy::Lru => a.last_access.cmp(&b.last_access),
+        CachePolicy::Lfu => a.access_count.cmp(&b.access_count)
+            .then(a.last_access.cmp(&b.last_access)),
+        CachePolicy::Ttl => a.expires_at.cmp(&b.expires_at),
+        CachePolicy::Adaptive => a.hit_rate(now).total_cmp(&b.hit_rate(now))
+            .then(a.last_access.cmp(&b.last_access)),
+    })
+}
+
+/// Create the disk tier directory and remove objects a previous run left in it
+fn clear_disk_tier(path: &Path) -> Result<()> {
+    std::fs::create_dir_all(path)?;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let is_object = entry.file_name().to_str()
+            .is_some_and(|name| name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit()));
+        if is_object {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tiers_stay_within_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CacheConfig {
+            policy: CachePolicy::Lru,
+            memory_budget: 8,
+            disk_tier: Some(DiskTierConfig { path: dir.path().to_path_buf(), budget: 8, promote_after_hits: 2 }),
+            ..Default::default()
+        };
+        let cache = CacheManager::new(&config).unwrap();
+        let ttl = Duration::from_secs(60);
+
+        cache.put("a".to_string(), Bytes::from_static(b"aaaa"), ttl).await.unwrap();
+        cache.put("b".to_string(), Bytes::from_static(b"bbbb"), ttl).await.unwrap();
+        assert!(cache.get("a").await.is_some());
+        // "b" is least recently used and moves to disk
+        cache.put("c".to_string(), Bytes::from_static(b"cccc"), ttl).await.unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.memory_bytes, stats.disk_bytes, stats.demotions), (8, 4, 1));
+
+        // The second disk hit promotes "b", demoting "a"
+        assert_eq!(cache.get("b").await.unwrap(), Bytes::from_static(b"bbbb"));
+        assert_eq!(cache.stats().promotions, 0);
+        assert_eq!(cache.get("b").await.unwrap(), Bytes::from_static(b"bbbb"));
+        let stats = cache.stats();
+        assert_eq!((stats.promotions, stats.demotions, stats.disk_entries), (1, 2, 1));
+
+        // Demoting "c" and then "b" overflows the disk, which drops "a"
+        cache.put("d".to_string(), Bytes::from_static(b"dddd"), ttl).await.unwrap();
+        cache.put("e".to_string(), Bytes::from_static(b"eeee"), ttl).await.unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.memory_bytes, stats.disk_bytes, stats.evictions), (8, 8, 1));
+        assert!(cache.get("a").await.is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        assert!(cache.put("big".to_string(), Bytes::from(vec![0; 9]), ttl).await.is_err());
+
+        // Expired entries go first, then the policy decides
+        let hot = EntryMeta { access_count: 5, ..EntryMeta::new(1, ttl) };
+        let cold = EntryMeta::new(1, Duration::from_secs(120));
+        let expired = EntryMeta::new(1, Duration::ZERO);
+        let now = Instant::now();
+        assert_eq!(eviction_order(&CachePolicy::Lfu, &cold, &hot, now), Ordering::Less);
+        assert_eq!(eviction_order(&CachePolicy::Ttl, &hot, &cold, now), Ordering::Less);
+        assert_eq!(eviction_order(&CachePolicy::Lfu, &expired, &cold, now), Ordering::Less);
+    }
+}
@@ -17,7 +17,7 @@ pub mod cache;
 pub mod replication;
 pub mod prefetch;
 
-use cache::{CacheManager, CachePolicy};
+use cache::{CacheManager, CachePolicy, CacheStats, DiskTierConfig};
 use replication::ReplicationManager;
 use prefetch::PrefetchEngine;
 
@@ -175,6 +175,16 @@ pub struct CacheConfig {
     pub enable_prefetch: bool,
     /// Prefetch threshold (popularity score)
     pub prefetch_threshold: f64,
+    /// Hard limit on bytes cached in memory
+    #[serde(default = "default_memory_budget")]
+    pub memory_budget: u64,
+    /// Disk tier that memory evictions are demoted to
+    #[serde(default)]
+    pub disk_tier: Option<DiskTierConfig>,
+}
+
+fn default_memory_budget() -> u64 {
+    1024 * 1024 * 1024 // 1GB
 }
 
 impl Default for CacheConfig {
@@ -185,6 +195,8 @@ impl Default for CacheConfig {
             max_object_size: 100 * 1024 * 1024, // 100MB
             enable_prefetch: true,
             prefetch_threshold: 0.7,
+            memory_budget: default_memory_budget(),
+            disk_tier: None,
         }
     }
 }
@@ -239,7 +251,7 @@ pub struct EdgeCache {
 impl EdgeCache {
     /// Create a new edge cache
     pub fn new(node_id: EdgeNodeId, config: CacheConfig) -> Result<Self> {
-        let cache = Arc::new(CacheManager::new(config.policy, config.max_object_size)?);
+        let cache = Arc::new(CacheManager::new(&config)?);
         let metrics = Arc::new(RwLock::new(EdgeMetrics::default()));
         
         Ok(Self {
@@ -261,6 +273,8 @@ impl EdgeCache {
     pub async fn get(&self, key: &str) -> Option<Bytes> {
         let result = self.cache.get(key).await;
         let mut metrics = self.metrics.write();
+        // A disk hit may promote the entry and evict others from disk
+        metrics.cache_used = self.cache.size();
         
         if result.is_some() {
             metrics.cache_hits += 1;
@@ -283,6 +297,11 @@ impl EdgeCache {
     pub fn metrics(&self) -> EdgeMetrics {
         self.metrics.read().clone()
     }
+
+    /// Get per-tier cache usage and eviction counters
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
 }
 
 /// Main edge network implementation
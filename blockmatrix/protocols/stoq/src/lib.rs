@@ -23,7 +23,7 @@ use serde::{Serialize, Deserialize};
 
 // Re-export key types
 pub use transport::{StoqTransport, Connection, Endpoint};
-pub use routing::{StoqRouter, Route, NodeId, NodeMetrics, RoutingMatrix};
+pub use routing::{StoqRouter, Route, NodeId, NodeMetrics, RoutingMatrix, LinkMeasurement};
 pub use chunking::{ChunkEngine, Chunk, ChunkId};
 pub use edge::{StoqEdgeNetwork, EdgeNode, EdgeCache};
 pub use routing::GeoLocation;
@@ -1,4 +1,7 @@
 //! STOQ Routing - CDN routing with matrix-based optimization
+//!
+//! Edge costs are estimated from node metrics until a link has been measured
+//! by active probing; measured links then take precedence over estimates.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -87,6 +90,27 @@ impl GeoLocation {
     }
 }
 
+/// Measured quality of a directed link between two nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkMeasurement {
+    /// Round-trip time in microseconds
+    pub rtt_us: u64,
+    /// Fraction of probes lost (0-1)
+    pub loss_rate: f64,
+    /// Measured bandwidth in Mbps, if a bandwidth probe completed
+    pub bandwidth_mbps: Option<f64>,
+    /// When the link was measured
+    pub measured_at: DateTime<Utc>,
+}
+
+impl LinkMeasurement {
+    /// One-way latency in microseconds, inflated by the retransmissions loss causes
+    pub fn effective_latency_us(&self) -> f64 {
+        let one_way = self.rtt_us as f64 / 2.0;
+        one_way / (1.0 - self.loss_rate.clamp(0.0, 0.99))
+    }
+}
+
 /// Routing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingConfig {
@@ -236,6 +260,7 @@ pub struct StoqRouter {
     graph: Arc<RwLock<Graph<NodeId, f64>>>,
     optimizer: Arc<RouteOptimizer>,
     discovery: Arc<RouteDiscovery>,
+    measured_links: Arc<DashMap<(NodeId, NodeId), LinkMeasurement>>,
 }
 
 impl StoqRouter {
@@ -262,6 +287,7 @@ impl StoqRouter {
             graph,
             optimizer,
             discovery,
+            measured_links: Arc::new(DashMap::new()),
         })
     }
     
@@ -394,9 +420,11 @@ impl StoqRouter {
         // Store metrics
         self.metrics.insert(metrics.node_id.clone(), metrics.clone());
         
-        // Update routing matrix for connected nodes
+        // Update routing matrix for connected nodes, keeping measured links as measured
         for other_metrics in self.metrics.iter() {
-            if other_metrics.node_id != metrics.node_id {
+            let measured = self.measured_links
+                .contains_key(&(metrics.node_id.clone(), other_metrics.node_id.clone()));
+            if other_metrics.node_id != metrics.node_id && !measured {
                 // Calculate edge metrics based on node metrics
                 let latency = self.estimate_latency(&metrics, &other_metrics);
                 let bandwidth = self.estimate_bandwidth(&metrics, &other_metrics);
@@ -413,6 +441,38 @@ impl StoqRouter {
         Ok(())
     }
     
+    /// Record a probed link, replacing the estimate for that edge
+    pub fn record_link_measurement(&self, from: NodeId, to: NodeId, measurement: LinkMeasurement) -> Result<()> {
+        let latency = measurement.effective_latency_us();
+        let bandwidth = match measurement.bandwidth_mbps {
+            Some(bandwidth) => bandwidth,
+            None => match (self.metrics.get(&from), self.metrics.get(&to)) {
+                (Some(a), Some(b)) => self.estimate_bandwidth(&a, &b),
+                _ => self.matrix.read().get_bandwidth(&from, &to).unwrap_or(0.0),
+            },
+        };
+        debug!("Measured link {:?} -> {:?}: {:.0}us, {:.1} Mbps", from, to, latency, bandwidth);
+        
+        self.matrix.write().update_edge(&from, &to, latency, bandwidth)?;
+        
+        // The source's own latency reflects its best measured link
+        if let Some(mut node) = self.metrics.get_mut(&from) {
+            let best = self.measured_links.iter()
+                .filter(|link| link.key().0 == from && link.key().1 != to)
+                .map(|link| link.effective_latency_us())
+                .fold(latency, f64::min);
+            node.latency_us = best as u64;
+            node.last_update = measurement.measured_at;
+        }
+        self.measured_links.insert((from, to), measurement);
+        Ok(())
+    }
+    
+    /// Measurement for a link, if it has been probed
+    pub fn link_measurement(&self, from: &NodeId, to: &NodeId) -> Option<LinkMeasurement> {
+        self.measured_links.get(&(from.clone(), to.clone())).map(|m| m.clone())
+    }
+    
     /// Estimate latency between nodes
     fn estimate_latency(&self, node1: &NodeMetrics, node2: &NodeMetrics) -> f64 {
         // Base latency on geographic distance if available
@@ -505,6 +565,38 @@ mod tests {
         assert_eq!(matrix.get_bandwidth(&node1, &node2), Some(1000.0));
     }
     
+    #[tokio::test]
+    async fn test_measured_link_overrides_estimate() {
+        let router = StoqRouter::new(RoutingConfig { matrix_size: 10, ..Default::default() }).unwrap();
+        let node = |id: &str, latency_us| NodeMetrics {
+            node_id: NodeId::new(id),
+            latency_us,
+            bandwidth_mbps: 1000.0,
+            cpu_load: 10.0,
+            memory_usage: 10.0,
+            active_connections: 0,
+            location: None,
+            last_update: Utc::now(),
+        };
+        let (a, b) = (NodeId::new("a"), NodeId::new("b"));
+        router.update_metrics(node("b", 100)).await.unwrap();
+        router.update_metrics(node("a", 100)).await.unwrap();
+        assert_eq!(router.routing_matrix().read().get_latency(&a, &b), Some(100.0));
+        
+        router.record_link_measurement(a.clone(), b.clone(), LinkMeasurement {
+            rtt_us: 2000,
+            loss_rate: 0.5,
+            bandwidth_mbps: Some(40.0),
+            measured_at: Utc::now(),
+        }).unwrap();
+        
+        // Later metric updates no longer overwrite the measured edge
+        router.update_metrics(node("a", 100)).await.unwrap();
+        let matrix = router.routing_matrix();
+        assert_eq!(matrix.read().get_latency(&a, &b), Some(2000.0));
+        assert_eq!(matrix.read().get_bandwidth(&a, &b), Some(40.0));
+    }
+    
     #[tokio::test]
     async fn test_router_creation() {
         let config = RoutingConfig::default();
@@ -11,9 +11,10 @@ use std::net::Ipv6Addr;
 use anyhow::Result;
 use tracing::info;
 
-use stoq::{StoqApiServer, ApiHandler};
+use stoq::{StoqApiServer, StoqApiClient, ApiHandler};
 use stoq::transport::{StoqTransport, TransportConfig};
 
+use crate::assets::chain_repair::{ChainBlocksHandler, ChainPeer, StoqChainPeer};
 use crate::HyperMeshSystem;
 
 /// API server configuration
@@ -27,6 +28,8 @@ pub struct ApiConfig {
     pub enable_logging: bool,
     /// API service name
     pub service_name: String,
+    /// STOQ services of bootstrap peers entity chains are repaired from
+    pub chain_peers: Vec<String>,
 }
 
 impl Default for ApiConfig {
@@ -36,6 +39,7 @@ impl Default for ApiConfig {
             port: 3000,
             enable_logging: true,
             service_name: "hypermesh".to_string(),
+            chain_peers: Vec::new(),
         }
     }
 }
@@ -79,6 +83,14 @@ pub async fn start_api_server(
 
     let transport = Arc::new(StoqTransport::new(transport_config).await?);
 
+    // Repair corrupt entity chains from bootstrap peers before serving them
+    let client = Arc::new(StoqApiClient::new(Arc::clone(&transport)));
+    let chain_peers: Vec<Arc<dyn ChainPeer>> = config.chain_peers
+        .iter()
+        .map(|service| Arc::new(StoqChainPeer::new(service.clone(), Arc::clone(&client))) as Arc<dyn ChainPeer>)
+        .collect();
+    system.matrix_manager().write().await.repair_all(&chain_peers).await;
+
     // Create STOQ server
     let server = StoqApiServer::new(transport);
 
@@ -87,6 +99,7 @@ pub async fn start_api_server(
     for handler in extension_handlers {
         server.register_handler(handler);
     }
+    server.register_handler(Arc::new(ChainBlocksHandler::new(&config.service_name, system.matrix_manager())));
 
     info!(
         "Starting STOQ API server on [{}]:{}",
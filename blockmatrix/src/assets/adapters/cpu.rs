@@ -21,9 +21,9 @@ use crate::assets::core::{
     PrivacyLevel, AssetAllocation, ProxyAddress,
     ResourceUsage, ResourceLimits, CpuUsage, CpuLimit,
     AdapterHealth, AdapterCapabilities, ConsensusProof,
-    CpuRequirements,
+    CpuRequirements, NumaPinning,
 };
-use crate::os_integration::{create_os_abstraction, OsAbstraction};
+use crate::os_integration::{create_os_abstraction, MemoryInfo, NumaNode, NumaTopology, OsAbstraction};
 
 /// CPU core allocation record
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,8 +38,10 @@ pub struct CpuAllocation {
     pub frequency_mhz: u32,
     /// CPU features enabled (AVX, SSE, etc.)
     pub enabled_features: Vec<String>,
-    /// NUMA node affinity
+    /// NUMA node affinity, when every core is on one node
     pub numa_node: Option<u32>,
+    /// CPUs and memory nodes the workload is pinned to
+    pub pinning: NumaPinning,
     /// Privacy level
     pub privacy_level: PrivacyLevel,
     /// Process isolation enabled
@@ -136,6 +138,8 @@ pub struct CpuAssetAdapter {
     scheduler: Arc<RwLock<CpuScheduler>>,
     /// Total CPU cores available
     total_cores: u32,
+    /// NUMA nodes and their CPUs and memory
+    numa_topology: NumaTopology,
     /// CPU usage statistics
     usage_stats: Arc<RwLock<CpuUsageStats>>,
 }
@@ -163,7 +167,7 @@ impl CpuAssetAdapter {
     /// Create new CPU adapter
     pub async fn new() -> Self {
         // Detect system CPU configuration
-        let (total_cores, cpu_cores) = Self::detect_cpu_configuration().await;
+        let (total_cores, cpu_cores, numa_topology) = Self::detect_cpu_configuration().await;
         
         let scheduler = CpuScheduler {
             algorithm: SchedulingAlgorithm::Cfs,
@@ -179,17 +183,22 @@ impl CpuAssetAdapter {
             proxy_mappings: Arc::new(RwLock::new(HashMap::new())),
             scheduler: Arc::new(RwLock::new(scheduler)),
             total_cores,
+            numa_topology,
             usage_stats: Arc::new(RwLock::new(CpuUsageStats::default())),
         }
     }
     
     /// Detect system CPU configuration using OS abstraction layer
-    async fn detect_cpu_configuration() -> (u32, HashMap<u32, CpuCore>) {
+    async fn detect_cpu_configuration() -> (u32, HashMap<u32, CpuCore>, NumaTopology) {
         // Use OS abstraction for real hardware detection
         match create_os_abstraction() {
             Ok(os) => {
                 if let Ok(cpu_info) = os.detect_cpu() {
                     let total_cores = cpu_info.cores as u32;
+                    let topology = os.detect_numa_topology().unwrap_or_else(|e| {
+                        tracing::warn!("Failed to detect NUMA topology: {}, assuming a single node", e);
+                        NumaTopology::single_node(&cpu_info, &MemoryInfo::default())
+                    });
                     let mut cpu_cores = HashMap::new();
 
                     // Create CpuCore entries based on detected hardware
@@ -198,7 +207,7 @@ impl CpuAssetAdapter {
                             core_id,
                             physical_id: core_id / 2, // Assume 2 logical per physical (SMT/HT)
                             is_logical: core_id % 2 == 1,
-                            numa_node: core_id / 4, // Assume 4 cores per NUMA node
+                            numa_node: topology.node_of_cpu(core_id).unwrap_or(0),
                             current_frequency_mhz: cpu_info.frequency_mhz.unwrap_or(2400),
                             base_frequency_mhz: cpu_info.frequency_mhz.unwrap_or(2400),
                             max_frequency_mhz: cpu_info.frequency_mhz.map(|f| (f as f32 * 1.5) as u32).unwrap_or(3600),
@@ -209,13 +218,14 @@ impl CpuAssetAdapter {
                     }
 
                     tracing::info!(
-                        "Detected {} CPU cores via OS abstraction: {} ({})",
+                        "Detected {} CPU cores on {} NUMA node(s) via OS abstraction: {} ({})",
                         total_cores,
+                        topology.nodes.len(),
                         cpu_info.model,
                         cpu_info.architecture
                     );
 
-                    return (total_cores, cpu_cores);
+                    return (total_cores, cpu_cores, topology);
                 } else {
                     tracing::warn!("Failed to detect CPU via OS abstraction, using fallback");
                 }
@@ -244,14 +254,28 @@ impl CpuAssetAdapter {
             });
         }
 
+        // Simulated nodes of 4 cores each, with no memory information
+        let nodes = (0..total_cores.div_ceil(4))
+            .map(|id| NumaNode {
+                id,
+                cpus: (id * 4..(id * 4 + 4).min(total_cores)).collect(),
+                memory_total_bytes: 0,
+                memory_free_bytes: 0,
+            })
+            .collect();
+
         tracing::info!("Using fallback CPU configuration: {} cores", total_cores);
-        (total_cores, cpu_cores)
+        (total_cores, cpu_cores, NumaTopology { nodes })
     }
     
     /// Allocate CPU cores based on requirements
+    ///
+    /// NUMA-local requests take every core from one node, which must also
+    /// have `local_memory_bytes` free when given.
     async fn allocate_cpu_cores(
         &self,
         cpu_req: &CpuRequirements,
+        local_memory_bytes: Option<u64>,
         asset_id: &AssetId,
     ) -> AssetResult<Vec<u32>> {
         let mut cores = self.cpu_cores.write().await;
@@ -270,11 +294,13 @@ impl CpuAssetAdapter {
             .map(|(core_id, _)| *core_id)
             .collect();
         
-        // Sort by NUMA node if preference specified
-        available_cores.sort_by_key(|core_id| {
-            let core = cores.get(core_id).unwrap();
-            core.numa_node
-        });
+        // Group cores by NUMA node so allocations span as few nodes as possible
+        available_cores.sort_by_key(|core_id| (cores[core_id].numa_node, *core_id));
+        
+        if cpu_req.numa_local {
+            let node = self.pick_numa_node(&cores, &available_cores, cpu_req.cores, local_memory_bytes)?;
+            available_cores.retain(|core_id| cores[core_id].numa_node == node);
+        }
         
         // Check if we have enough cores
         if available_cores.len() < cpu_req.cores as usize {
@@ -299,6 +325,52 @@ impl CpuAssetAdapter {
         Ok(allocated_cores)
     }
     
+    /// Node with enough free cores and local memory, preferring the tightest fit
+    fn pick_numa_node(
+        &self,
+        cores: &HashMap<u32, CpuCore>,
+        available_cores: &[u32],
+        wanted_cores: u32,
+        local_memory_bytes: Option<u64>,
+    ) -> AssetResult<u32> {
+        let mut free_per_node: HashMap<u32, u32> = HashMap::new();
+        for core_id in available_cores {
+            *free_per_node.entry(cores[core_id].numa_node).or_default() += 1;
+        }
+        
+        free_per_node
+            .into_iter()
+            .filter(|(_, free)| *free >= wanted_cores)
+            .filter(|(node, _)| match local_memory_bytes {
+                Some(bytes) => self.numa_topology.node(*node).is_some_and(|node| node.memory_free_bytes >= bytes),
+                None => true,
+            })
+            .min_by_key(|(node, free)| (*free, *node))
+            .map(|(node, _)| node)
+            .ok_or_else(|| AssetError::AllocationFailed {
+                reason: format!(
+                    "No NUMA node has {} free cores{}",
+                    wanted_cores,
+                    local_memory_bytes.map(|bytes| format!(" and {} bytes of free memory", bytes)).unwrap_or_default()
+                )
+            })
+    }
+    
+    /// NUMA topology the adapter allocates against
+    pub fn numa_topology(&self) -> &NumaTopology {
+        &self.numa_topology
+    }
+    
+    /// CPUs and memory nodes an allocation is pinned to, for the container runtime's cpuset
+    pub async fn pinning(&self, asset_id: &AssetId) -> AssetResult<NumaPinning> {
+        self.allocations.read().await
+            .get(asset_id)
+            .map(|allocation| allocation.pinning.clone())
+            .ok_or_else(|| AssetError::AssetNotFound {
+                asset_id: asset_id.to_string()
+            })
+    }
+    
     /// Generate proxy address for CPU access
     async fn generate_proxy_address(asset_id: &AssetId) -> ProxyAddress {
         let uuid_bytes = asset_id.uuid.as_bytes();
@@ -424,8 +496,19 @@ impl AssetAdapter for CpuAssetAdapter {
         // Create asset ID
         let asset_id = AssetId::new(AssetType::Cpu);
         
-        // Allocate CPU cores
-        let allocated_cores = self.allocate_cpu_cores(cpu_req, &asset_id).await?;
+        // Allocate CPU cores, next to the request's memory when asked to
+        let local_memory_bytes = request.requested_resources.memory_usage.as_ref()
+            .filter(|_| cpu_req.numa_local)
+            .map(|memory_req| memory_req.size_bytes);
+        let allocated_cores = self.allocate_cpu_cores(cpu_req, local_memory_bytes, &asset_id).await?;
+        
+        let pinning = {
+            let cores = self.cpu_cores.read().await;
+            let mut mems: Vec<u32> = allocated_cores.iter().map(|core_id| cores[core_id].numa_node).collect();
+            mems.sort_unstable();
+            mems.dedup();
+            NumaPinning { cpus: allocated_cores.clone(), mems }
+        };
         
         // Generate proxy address
         let proxy_address = Self::generate_proxy_address(&asset_id).await;
@@ -437,11 +520,11 @@ impl AssetAdapter for CpuAssetAdapter {
             architecture: cpu_req.architecture.clone().unwrap_or_else(|| "x86_64".to_string()),
             frequency_mhz: cpu_req.min_frequency_mhz.unwrap_or(2400),
             enabled_features: cpu_req.required_features.clone(),
-            numa_node: allocated_cores.first().and_then(|&core_id| {
-                // Get NUMA node from first allocated core
-                // This is simplified - in practice, might want to validate all cores are on same NUMA node
-                None // TODO: Implement NUMA node detection
-            }),
+            numa_node: match pinning.mems.as_slice() {
+                [node] => Some(*node),
+                _ => None,
+            },
+            pinning: pinning.clone(),
             privacy_level: request.privacy_level.clone(),
             isolation_enabled: true, // Enable isolation by default
             time_slice_ms: 100, // Default time slice
@@ -484,7 +567,10 @@ impl AssetAdapter for CpuAssetAdapter {
                 proxy_address: None,
                 consensus_proofs: Vec::new(),
                 owner_certificate_fingerprint: request.certificate_fingerprint.clone(),
-                metadata: HashMap::new(),
+                metadata: HashMap::from([
+                    ("cpuset.cpus".to_string(), pinning.cpuset_cpus()),
+                    ("cpuset.mems".to_string(), pinning.cpuset_mems()),
+                ]),
                 health_status: crate::assets::core::status::AssetHealthStatus::default(),
                 performance_metrics: crate::assets::core::status::AssetPerformanceMetrics::default(),
             },
@@ -572,6 +658,8 @@ impl AssetAdapter for CpuAssetAdapter {
                 metadata.insert("utilization_percent".to_string(), utilization.to_string());
                 metadata.insert("priority".to_string(), allocation.priority.to_string());
                 metadata.insert("isolation_enabled".to_string(), allocation.isolation_enabled.to_string());
+                metadata.insert("cpuset.cpus".to_string(), allocation.pinning.cpuset_cpus());
+                metadata.insert("cpuset.mems".to_string(), allocation.pinning.cpuset_mems());
                 metadata
             },
             health_status: crate::assets::core::status::AssetHealthStatus::default(),
@@ -725,6 +813,7 @@ mod tests {
                     min_frequency_mhz: Some(2400),
                     architecture: Some("x86_64".to_string()),
                     required_features: vec!["AVX2".to_string()],
+                    numa_local: false,
                 }),
                 ..Default::default()
             },
@@ -759,6 +848,8 @@ mod tests {
                 },
             ),
             certificate_fingerprint: "test-cert".to_string(),
+            duration_limit: None,
+            tags: HashMap::new(),
         }
     }
     
@@ -781,6 +872,30 @@ mod tests {
         adapter.deallocate_asset(&allocation.asset_id).await.unwrap();
     }
     
+    #[tokio::test]
+    async fn test_numa_local_allocation() {
+        let adapter = CpuAssetAdapter::new().await;
+        let largest_node = adapter.numa_topology().nodes.iter().map(|node| node.cpus.len()).max().unwrap() as u32;
+        
+        let mut request = create_test_cpu_request().await;
+        let cpu_req = request.requested_resources.cpu.as_mut().unwrap();
+        cpu_req.cores = 1;
+        cpu_req.numa_local = true;
+        cpu_req.min_frequency_mhz = None;
+        
+        let allocation = adapter.allocate_asset(&request).await.unwrap();
+        let pinning = adapter.pinning(&allocation.asset_id).await.unwrap();
+        assert_eq!(pinning.cpus.len(), 1);
+        assert_eq!(pinning.mems.len(), 1, "NUMA-local cores share one node");
+        assert_eq!(adapter.numa_topology().node_of_cpu(pinning.cpus[0]), Some(pinning.mems[0]));
+        assert_eq!(allocation.status.metadata["cpuset.mems"], pinning.cpuset_mems());
+        adapter.deallocate_asset(&allocation.asset_id).await.unwrap();
+        
+        // No single node can hold more cores than it has
+        request.requested_resources.cpu.as_mut().unwrap().cores = largest_node + 1;
+        assert!(adapter.allocate_asset(&request).await.is_err());
+    }
+    
     #[tokio::test]
     async fn test_cpu_health_check() {
         let adapter = CpuAssetAdapter::new().await;
@@ -4,6 +4,7 @@
 //! - Nova engine GPU compute unit allocation (Vulkan compute shaders)
 //! - Vulkan-based memory management (device memory, buffers)
 //! - Multi-GPU coordination and scheduling via Nova
+//! - Fractional allocation through MIG partitions or SM slices, with per-partition usage accounting
 //! - Hardware acceleration for consensus proofs
 //! - Quantum-resistant security with FALCON-1024
 //! - Remote proxy access for distributed GPU compute
@@ -54,6 +55,9 @@ pub struct GpuAllocation {
     pub memory_utilization: f32,
     /// GPU context handle
     pub context_handle: Option<String>,
+    /// Partition served, when less than a whole device was allocated
+    #[serde(default)]
+    pub partition: Option<GpuPartition>,
 }
 
 /// GPU device information
@@ -87,6 +91,118 @@ pub struct GpuDevice {
     pub temperature_celsius: Option<f32>,
     /// Power consumption in watts
     pub power_watts: Option<f32>,
+    /// How the device is shared between allocations
+    #[serde(default)]
+    pub partitioning: GpuPartitioning,
+    /// Compute slices held by partitions
+    #[serde(default)]
+    pub used_slices: u32,
+}
+
+impl GpuDevice {
+    /// Compute slices not held by partitions
+    pub fn free_slices(&self) -> u32 {
+        self.partitioning.slices().saturating_sub(self.used_slices)
+    }
+}
+
+/// How a GPU is shared between allocations
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuPartitioning {
+    /// Whole-device allocation only
+    #[default]
+    Unpartitioned,
+    /// Hardware MIG instances with their own SMs and memory, in seven compute slices
+    Mig,
+    /// Streaming multiprocessors split into equal slices, with memory capped per slice
+    SmSlices { slices: u32 },
+}
+
+impl GpuPartitioning {
+    /// GPUs that offer MIG instances
+    const MIG_CAPABLE: [&'static str; 3] = ["A100", "H100", "H200"];
+
+    /// Partitioning supported by a device, from its model name
+    pub fn detect(device_name: &str) -> Self {
+        if Self::MIG_CAPABLE.iter().any(|model| device_name.contains(model)) {
+            GpuPartitioning::Mig
+        } else {
+            GpuPartitioning::SmSlices { slices: 8 }
+        }
+    }
+
+    /// Compute slices a device is divided into
+    pub fn slices(&self) -> u32 {
+        match self {
+            GpuPartitioning::Unpartitioned => 1,
+            GpuPartitioning::Mig => 7,
+            GpuPartitioning::SmSlices { slices } => *slices,
+        }
+    }
+
+    /// Partition profiles of a device, smallest first
+    pub fn profiles(&self, total_memory_bytes: u64) -> Vec<GpuPartitionProfile> {
+        match self {
+            GpuPartitioning::Unpartitioned => Vec::new(),
+            // Compute slices and eighths of memory of the standard MIG profiles
+            GpuPartitioning::Mig => [(1, 1), (2, 2), (3, 4), (4, 4), (7, 8)]
+                .into_iter()
+                .map(|(slices, eighths)| GpuPartitionProfile::new("g", slices, total_memory_bytes * eighths / 8))
+                .collect(),
+            GpuPartitioning::SmSlices { slices } => (1..=*slices)
+                .map(|n| GpuPartitionProfile::new("s", n, total_memory_bytes * n as u64 / *slices as u64))
+                .collect(),
+        }
+    }
+}
+
+/// Size of a GPU partition
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuPartitionProfile {
+    /// Profile name, e.g. `3g.20gb` for MIG or `4s.8gb` for SM slices
+    pub name: String,
+    /// Compute slices of the device
+    pub compute_slices: u32,
+    /// Device memory reserved for the partition
+    pub memory_bytes: u64,
+}
+
+impl GpuPartitionProfile {
+    fn new(unit: &str, compute_slices: u32, memory_bytes: u64) -> Self {
+        Self {
+            name: format!("{}{}.{}gb", compute_slices, unit, memory_bytes / (1024 * 1024 * 1024)),
+            compute_slices,
+            memory_bytes,
+        }
+    }
+}
+
+/// A partition of one GPU held by an allocation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GpuPartition {
+    /// Partition ID
+    pub partition_id: String,
+    /// Device the partition is on
+    pub device_id: u32,
+    /// Partition size
+    pub profile: GpuPartitionProfile,
+    /// Hardware (MIG) or SM slice isolation
+    pub partitioning: GpuPartitioning,
+    /// Usage measured for the partition
+    pub usage: GpuPartitionUsage,
+}
+
+/// Usage accounted to one partition
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GpuPartitionUsage {
+    /// Latest utilization of the partition's slices (0.0 - 100.0)
+    pub utilization_percent: f32,
+    /// Latest memory in use
+    pub memory_used_bytes: u64,
+    /// Busy compute slice-seconds since allocation
+    pub slice_seconds: f64,
+    /// When usage was last recorded
+    pub last_sample: SystemTime,
 }
 
 /// GPU device status
@@ -207,6 +323,8 @@ impl GpuAssetAdapter {
                                 allocated_to: None,
                                 temperature_celsius: Some(35.0 + (device_id as f32 * 5.0)),
                                 power_watts: Some(220.0),
+                                partitioning: GpuPartitioning::detect(&gpu_info.model),
+                                used_slices: 0,
                             });
                         }
 
@@ -250,6 +368,8 @@ impl GpuAssetAdapter {
                 allocated_to: None,
                 temperature_celsius: Some(35.0 + (device_id as f32 * 5.0)),
                 power_watts: Some(220.0),
+                partitioning: GpuPartitioning::SmSlices { slices: 8 },
+                used_slices: 0,
             });
         }
 
@@ -273,6 +393,7 @@ impl GpuAssetAdapter {
             .iter()
             .filter(|(_, device)| {
                 matches!(device.status, GpuStatus::Available) &&
+                device.used_slices == 0 &&
                 device.available_memory_bytes >= gpu_req.min_memory_mb.unwrap_or(0) as u64 * 1024 * 1024 &&
                 (gpu_req.compute_capability.is_none() || 
                  device.compute_capability >= *gpu_req.compute_capability.as_ref().unwrap())
@@ -334,6 +455,132 @@ impl GpuAssetAdapter {
         Ok((allocated_devices, total_allocated_memory))
     }
     
+    /// Carve a partition for a fractional request out of the best-fitting device
+    ///
+    /// Devices already partitioned are filled first, keeping whole GPUs free,
+    /// and a partition never takes more slices or memory than the device has
+    /// left, so partitions on one device cannot overlap.
+    async fn allocate_gpu_partition(
+        &self,
+        gpu_req: &GpuRequirements,
+        asset_id: &AssetId,
+    ) -> AssetResult<GpuPartition> {
+        if gpu_req.units > 1 {
+            return Err(AssetError::AllocationFailed {
+                reason: format!("A GPU partition is part of one device; {} units were requested", gpu_req.units),
+            });
+        }
+        let min_memory = gpu_req.min_memory_mb.unwrap_or(0) * 1024 * 1024;
+        let mut devices = self.gpu_devices.write().await;
+        
+        let mut best: Option<(u32, GpuPartitionProfile)> = None;
+        for (device_id, device) in devices.iter() {
+            let compatible = matches!(device.status, GpuStatus::Available)
+                && device.partitioning != GpuPartitioning::Unpartitioned
+                && gpu_req.compute_capability.as_ref().map_or(true, |cc| device.compute_capability >= *cc);
+            if !compatible {
+                continue;
+            }
+            let profiles = device.partitioning.profiles(device.total_memory_bytes);
+            let profile = match (&gpu_req.partition_profile, gpu_req.fraction) {
+                (Some(name), _) => profiles.into_iter().find(|p| p.name == *name),
+                (None, Some(fraction)) => {
+                    let slices = ((fraction * device.partitioning.slices() as f32).ceil() as u32).max(1);
+                    profiles.into_iter().find(|p| p.compute_slices >= slices && p.memory_bytes >= min_memory)
+                }
+                (None, None) => None,
+            };
+            let Some(profile) = profile else { continue };
+            if profile.compute_slices > device.free_slices() || profile.memory_bytes > device.available_memory_bytes {
+                continue;
+            }
+            let tighter = best.as_ref().map_or(true, |(best_id, _)| {
+                let best_device = &devices[best_id];
+                (device.free_slices(), *device_id) < (best_device.free_slices(), *best_id)
+            });
+            if tighter {
+                best = Some((*device_id, profile));
+            }
+        }
+        
+        let (device_id, profile) = best.ok_or_else(|| AssetError::AllocationFailed {
+            reason: match &gpu_req.partition_profile {
+                Some(name) => format!("No GPU has a free {} partition", name),
+                None => format!("No GPU has a free partition for {:?} of a device", gpu_req.fraction),
+            },
+        })?;
+        let device = devices.get_mut(&device_id).unwrap();
+        device.used_slices += profile.compute_slices;
+        device.available_memory_bytes -= profile.memory_bytes;
+        
+        Ok(GpuPartition {
+            partition_id: format!("gpu{}-{}-{}", device_id, profile.name, &asset_id.uuid.simple().to_string()[..8]),
+            device_id,
+            profile,
+            partitioning: device.partitioning.clone(),
+            usage: GpuPartitionUsage {
+                utilization_percent: 0.0,
+                memory_used_bytes: 0,
+                slice_seconds: 0.0,
+                last_sample: SystemTime::now(),
+            },
+        })
+    }
+    
+    /// Partition profiles each device offers
+    pub async fn partition_profiles(&self) -> HashMap<u32, Vec<GpuPartitionProfile>> {
+        self.gpu_devices
+            .read()
+            .await
+            .iter()
+            .map(|(device_id, device)| (*device_id, device.partitioning.profiles(device.total_memory_bytes)))
+            .collect()
+    }
+    
+    /// Record measured usage of a partitioned allocation
+    ///
+    /// Memory beyond the partition's reservation breaks its isolation, so
+    /// the sample is refused with an error.
+    pub async fn record_partition_usage(
+        &self,
+        asset_id: &AssetId,
+        utilization_percent: f32,
+        memory_used_bytes: u64,
+    ) -> AssetResult<GpuPartitionUsage> {
+        let mut allocations = self.allocations.write().await;
+        let partition = allocations
+            .get_mut(asset_id)
+            .and_then(|allocation| allocation.partition.as_mut())
+            .ok_or_else(|| AssetError::AssetNotFound { asset_id: asset_id.to_string() })?;
+        if memory_used_bytes > partition.profile.memory_bytes {
+            return Err(AssetError::AllocationFailed {
+                reason: format!(
+                    "Partition {} uses {} bytes of its {} byte reservation",
+                    partition.partition_id, memory_used_bytes, partition.profile.memory_bytes
+                ),
+            });
+        }
+        
+        let now = SystemTime::now();
+        let usage = &mut partition.usage;
+        let elapsed = now.duration_since(usage.last_sample).unwrap_or_default().as_secs_f64();
+        usage.slice_seconds += elapsed * partition.profile.compute_slices as f64 * (usage.utilization_percent as f64 / 100.0);
+        usage.utilization_percent = utilization_percent.clamp(0.0, 100.0);
+        usage.memory_used_bytes = memory_used_bytes;
+        usage.last_sample = now;
+        Ok(usage.clone())
+    }
+    
+    /// Partitions currently allocated, with their usage
+    pub async fn partition_usage(&self) -> Vec<(AssetId, GpuPartition)> {
+        self.allocations
+            .read()
+            .await
+            .iter()
+            .filter_map(|(asset_id, allocation)| allocation.partition.clone().map(|p| (asset_id.clone(), p)))
+            .collect()
+    }
+    
     /// Generate proxy address for GPU access
     async fn generate_proxy_address(asset_id: &AssetId) -> ProxyAddress {
         let uuid_bytes = asset_id.uuid.as_bytes();
@@ -465,7 +712,7 @@ impl AssetAdapter for GpuAssetAdapter {
         }
         
         // Get GPU requirements
-        let gpu_req = request.requested_resources.gpu.as_ref()
+        let gpu_req = request.requested_resources.gpu_usage.as_ref()
             .ok_or_else(|| AssetError::AllocationFailed {
                 reason: "No GPU requirements specified".to_string()
             })?;
@@ -473,8 +720,31 @@ impl AssetAdapter for GpuAssetAdapter {
         // Create asset ID
         let asset_id = AssetId::new(AssetType::Gpu);
         
-        // Allocate GPU devices and memory
-        let (allocated_devices, allocated_memory) = self.allocate_gpu_devices(gpu_req, &asset_id).await?;
+        if let Some(fraction) = gpu_req.fraction {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                return Err(AssetError::AllocationFailed {
+                    reason: format!("GPU fraction must be above 0 and at most 1, not {}", fraction),
+                });
+            }
+        }
+        
+        // Allocate a partition for a fraction of a GPU, whole devices otherwise
+        let partitioned = gpu_req.partition_profile.is_some() || gpu_req.fraction.is_some_and(|f| f < 1.0);
+        let (allocated_devices, allocated_memory, partition) = if partitioned {
+            let partition = self.allocate_gpu_partition(gpu_req, &asset_id).await?;
+            (vec![partition.device_id], partition.profile.memory_bytes, Some(partition))
+        } else {
+            let (devices, memory) = self.allocate_gpu_devices(gpu_req, &asset_id).await?;
+            (devices, memory, None)
+        };
+        let gpu_share = partition.as_ref().map_or(1.0, |p| {
+            p.profile.compute_slices as f32 / p.partitioning.slices() as f32
+        });
+        let mut metadata = HashMap::new();
+        if let Some(partition) = &partition {
+            metadata.insert("gpu_partition".to_string(), partition.partition_id.clone());
+            metadata.insert("gpu_partition_profile".to_string(), partition.profile.name.clone());
+        }
         
         // Generate proxy address
         let proxy_address = Self::generate_proxy_address(&asset_id).await;
@@ -501,6 +771,7 @@ impl AssetAdapter for GpuAssetAdapter {
             current_utilization: 0.0,
             memory_utilization: 0.0,
             context_handle: context_handles.first().cloned(),
+            partition,
         };
         
         // Store allocation and proxy mapping
@@ -536,13 +807,16 @@ impl AssetAdapter for GpuAssetAdapter {
                 proxy_address: None,
                 consensus_proofs: Vec::new(),
                 owner_certificate_fingerprint: request.certificate_fingerprint.clone(),
-                metadata: HashMap::new(),
+                metadata,
                 health_status: crate::assets::core::status::AssetHealthStatus::default(),
                 performance_metrics: crate::assets::core::status::AssetPerformanceMetrics::default(),
             },
             allocation_config: crate::assets::core::privacy::AllocationConfig {
                 privacy_level: request.privacy_level.clone(),
-                resource_allocation: crate::assets::core::privacy::ResourceAllocationConfig::default(),
+                resource_allocation: crate::assets::core::privacy::ResourceAllocationConfig {
+                    gpu_allocation: gpu_share,
+                    ..Default::default()
+                },
                 concurrency_limits: crate::assets::core::privacy::ConcurrencyLimits::default(),
                 duration_config: crate::assets::core::privacy::DurationConfig::default(),
                 consensus_requirements: crate::assets::core::privacy::ConsensusRequirements::default(),
@@ -574,9 +848,16 @@ impl AssetAdapter for GpuAssetAdapter {
             let mut devices = self.gpu_devices.write().await;
             let mut device_allocations = self.device_allocations.write().await;
             
+            if let Some(partition) = &allocation.partition {
+                if let Some(device) = devices.get_mut(&partition.device_id) {
+                    device.used_slices = device.used_slices.saturating_sub(partition.profile.compute_slices);
+                    device.available_memory_bytes += partition.profile.memory_bytes;
+                }
+            }
+            
             let memory_per_device = allocation.allocated_memory_bytes / allocation.allocated_devices.len() as u64;
             
-            for device_id in &allocation.allocated_devices {
+            for device_id in allocation.allocated_devices.iter().filter(|_| allocation.partition.is_none()) {
                 if let Some(device) = devices.get_mut(device_id) {
                     device.status = GpuStatus::Available;
                     device.allocated_to = None;
@@ -642,6 +923,11 @@ impl AssetAdapter for GpuAssetAdapter {
                 metadata.insert("utilization_percent".to_string(), allocation.current_utilization.to_string());
                 metadata.insert("memory_utilization_percent".to_string(), allocation.memory_utilization.to_string());
                 metadata.insert("isolation_enabled".to_string(), allocation.isolation_enabled.to_string());
+                if let Some(partition) = &allocation.partition {
+                    metadata.insert("gpu_partition".to_string(), partition.partition_id.clone());
+                    metadata.insert("gpu_partition_profile".to_string(), partition.profile.name.clone());
+                    metadata.insert("gpu_partition_slice_seconds".to_string(), partition.usage.slice_seconds.to_string());
+                }
                 metadata
             },
         })
@@ -691,9 +977,16 @@ impl AssetAdapter for GpuAssetAdapter {
             })?;
         
         // TODO: Implement actual GPU usage monitoring
+        let (utilization_percent, memory_utilization_percent) = match &allocation.partition {
+            Some(partition) => (
+                partition.usage.utilization_percent,
+                partition.usage.memory_used_bytes as f32 / partition.profile.memory_bytes.max(1) as f32 * 100.0,
+            ),
+            None => (allocation.current_utilization, allocation.memory_utilization),
+        };
         let gpu_usage = GpuUsage {
-            utilization_percent: allocation.current_utilization,
-            memory_utilization_percent: allocation.memory_utilization,
+            utilization_percent,
+            memory_utilization_percent,
             temperature_celsius: Some(65.0), // TODO: Get actual temperature
             power_watts: Some(200.0), // TODO: Get actual power consumption
         };
@@ -771,6 +1064,7 @@ impl AssetAdapter for GpuAssetAdapter {
                 "nova_vulkan_support".to_string(),
                 "opencl_support".to_string(),
                 "multi_gpu".to_string(),
+                "fractional_allocation".to_string(),
                 "memory_management".to_string(),
                 "compute_isolation".to_string(),
                 "consensus_acceleration".to_string(),
@@ -796,6 +1090,8 @@ mod tests {
                     min_memory_mb: Some(8192), // 8GB
                     compute_capability: Some("8.0".to_string()),
                     required_features: vec!["nova_vulkan_support".to_string()],
+                    fraction: None,
+                    partition_profile: None,
                 }),
                 ..Default::default()
             },
@@ -830,6 +1126,8 @@ mod tests {
                 },
             ),
             certificate_fingerprint: "test-cert".to_string(),
+            duration_limit: None,
+            tags: HashMap::new(),
         }
     }
     
@@ -852,6 +1150,42 @@ mod tests {
         adapter.deallocate_asset(&allocation.asset_id).await.unwrap();
     }
     
+    #[tokio::test]
+    async fn test_fractional_gpu_partitions() {
+        let adapter = GpuAssetAdapter::new().await;
+        let slices: u32 = adapter.gpu_devices.read().await.values().map(|d| d.partitioning.slices()).sum();
+        
+        let mut half = create_test_gpu_request().await;
+        let gpu = half.requested_resources.gpu_usage.as_mut().unwrap();
+        gpu.fraction = Some(0.5);
+        gpu.min_memory_mb = None;
+        gpu.compute_capability = None;
+        
+        // Half-GPU partitions pack onto devices until their slices run out
+        let mut allocations = Vec::new();
+        while let Ok(allocation) = adapter.allocate_asset(&half).await {
+            assert!(allocation.status.metadata.contains_key("gpu_partition"));
+            assert!(allocation.allocation_config.resource_allocation.gpu_allocation >= 0.5);
+            allocations.push(allocation);
+            assert!(allocations.len() <= slices as usize);
+        }
+        assert!(!allocations.is_empty());
+        for device in adapter.gpu_devices.read().await.values() {
+            assert!(device.used_slices <= device.partitioning.slices());
+        }
+        
+        // Usage is accounted per partition and bounded by its reservation
+        let asset_id = &allocations[0].asset_id;
+        let partition = adapter.partition_usage().await.into_iter().find(|(id, _)| id == asset_id).unwrap().1;
+        adapter.record_partition_usage(asset_id, 80.0, partition.profile.memory_bytes / 2).await.unwrap();
+        assert!(adapter.record_partition_usage(asset_id, 80.0, partition.profile.memory_bytes + 1).await.is_err());
+        
+        for allocation in &allocations {
+            adapter.deallocate_asset(&allocation.asset_id).await.unwrap();
+        }
+        assert!(adapter.gpu_devices.read().await.values().all(|d| d.used_slices == 0));
+    }
+    
     #[tokio::test]
     async fn test_gpu_health_check() {
         let adapter = GpuAssetAdapter::new().await;
@@ -26,7 +26,7 @@ use crate::assets::core::{
     AdapterHealth, AdapterCapabilities, ConsensusProof,
     MemoryRequirements,
 };
-use crate::os_integration::{create_os_abstraction, OsAbstraction};
+use crate::os_integration::{create_os_abstraction, NumaNode, OsAbstraction};
 
 /// Memory allocation record with NAT-like addressing
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -41,7 +41,7 @@ pub struct MemoryAllocation {
     pub memory_type: String,
     /// ECC enabled
     pub ecc_enabled: bool,
-    /// NUMA node
+    /// NUMA node the memory is bound to
     pub numa_node: Option<u32>,
     /// Privacy level
     pub privacy_level: PrivacyLevel,
@@ -165,6 +165,20 @@ impl MemoryAssetAdapter {
             allocations: Vec::new(),
         });
         
+        // One pool per NUMA node for allocations bound to a node
+        for node in Self::detect_numa_nodes().await {
+            let pool_id = Self::pool_id(Some(node.id));
+            memory_pools.insert(pool_id.clone(), MemoryPool {
+                pool_id,
+                total_size: node.memory_total_bytes,
+                available_size: node.memory_free_bytes,
+                memory_type: "DDR4".to_string(),
+                numa_node: Some(node.id),
+                privacy_level: PrivacyLevel::Private,
+                allocations: Vec::new(),
+            });
+        }
+        
         Self {
             allocations: Arc::new(RwLock::new(HashMap::new())),
             memory_pools: Arc::new(RwLock::new(memory_pools)),
@@ -203,6 +217,25 @@ impl MemoryAssetAdapter {
         fallback_memory
     }
     
+    /// Detect NUMA nodes and their memory using OS abstraction layer
+    async fn detect_numa_nodes() -> Vec<NumaNode> {
+        match create_os_abstraction().and_then(|os| os.detect_numa_topology()) {
+            Ok(topology) => topology.nodes.into_iter().filter(|node| node.memory_total_bytes > 0).collect(),
+            Err(e) => {
+                tracing::warn!("Failed to detect NUMA topology: {}, node-bound allocations unavailable", e);
+                Vec::new()
+            }
+        }
+    }
+    
+    /// Pool serving allocations bound to a NUMA node, or unbound ones
+    fn pool_id(numa_node: Option<u32>) -> String {
+        match numa_node {
+            Some(node) => format!("numa_{}", node),
+            None => "default".to_string(),
+        }
+    }
+    
     /// Allocate memory from pool
     async fn allocate_memory_from_pool(
         &self,
@@ -382,7 +415,7 @@ impl AssetAdapter for MemoryAssetAdapter {
         }
         
         // Get memory requirements
-        let memory_req = request.requested_resources.memory.as_ref()
+        let memory_req = request.requested_resources.memory_usage.as_ref()
             .ok_or_else(|| AssetError::AllocationFailed {
                 reason: "No memory requirements specified".to_string()
             })?;
@@ -399,11 +432,7 @@ impl AssetAdapter for MemoryAssetAdapter {
         }
         
         // Allocate memory from appropriate pool
-        let pool_id = if memory_req.numa_node.is_some() {
-            format!("numa_{}", memory_req.numa_node.unwrap())
-        } else {
-            "default".to_string()
-        };
+        let pool_id = Self::pool_id(memory_req.numa_node);
         
         let local_address = self.allocate_memory_from_pool(
             &pool_id,
@@ -506,7 +535,9 @@ impl AssetAdapter for MemoryAssetAdapter {
                 proxy_address: None,
                 consensus_proofs: Vec::new(),
                 owner_certificate_fingerprint: request.certificate_fingerprint.clone(),
-                metadata: HashMap::new(),
+                metadata: memory_req.numa_node
+                    .map(|node| HashMap::from([("cpuset.mems".to_string(), node.to_string())]))
+                    .unwrap_or_default(),
                 health_status: crate::assets::core::status::AssetHealthStatus::default(),
                 performance_metrics: crate::assets::core::status::AssetPerformanceMetrics::default(),
             },
@@ -556,6 +587,9 @@ impl AssetAdapter for MemoryAssetAdapter {
             let mut available = self.available_memory.write().await;
             *available += allocation.size_bytes;
         }
+        if let Some(pool) = self.memory_pools.write().await.get_mut(&Self::pool_id(allocation.numa_node)) {
+            pool.available_size += allocation.size_bytes;
+        }
         
         // Update usage statistics
         self.update_usage_stats(MemoryOperation::Deallocate, allocation.size_bytes).await;
@@ -591,6 +625,9 @@ impl AssetAdapter for MemoryAssetAdapter {
                 metadata.insert("size_bytes".to_string(), allocation.size_bytes.to_string());
                 metadata.insert("local_address".to_string(), format!("0x{:x}", allocation.local_address));
                 metadata.insert("numa_node".to_string(), allocation.numa_node.map(|n| n.to_string()).unwrap_or_else(|| "none".to_string()));
+                if let Some(node) = allocation.numa_node {
+                    metadata.insert("cpuset.mems".to_string(), node.to_string());
+                }
                 metadata.insert("ecc_enabled".to_string(), allocation.ecc_enabled.to_string());
                 metadata.insert("cow_enabled".to_string(), allocation.cow_enabled.to_string());
                 metadata.insert("reference_count".to_string(), allocation.reference_count.to_string());
@@ -779,6 +816,8 @@ mod tests {
                 },
             ),
             certificate_fingerprint: "test-cert".to_string(),
+            duration_limit: None,
+            tags: HashMap::new(),
         }
     }
     
@@ -802,6 +841,31 @@ mod tests {
         adapter.deallocate_asset(&allocation.asset_id).await.unwrap();
     }
     
+    #[tokio::test]
+    async fn test_numa_bound_allocation() {
+        let adapter = MemoryAssetAdapter::new().await;
+        let Some(node) = MemoryAssetAdapter::detect_numa_nodes().await.into_iter().next() else {
+            return; // No NUMA memory information on this machine
+        };
+        
+        let mut request = create_test_memory_request().await;
+        let memory_req = request.requested_resources.memory_usage.as_mut().unwrap();
+        memory_req.size_bytes = 1024 * 1024;
+        memory_req.numa_node = Some(node.id);
+        
+        let allocation = adapter.allocate_asset(&request).await.unwrap();
+        assert_eq!(allocation.status.metadata["cpuset.mems"], node.id.to_string());
+        let available = adapter.memory_pools.read().await[&MemoryAssetAdapter::pool_id(Some(node.id))].available_size;
+        
+        adapter.deallocate_asset(&allocation.asset_id).await.unwrap();
+        let restored = adapter.memory_pools.read().await[&MemoryAssetAdapter::pool_id(Some(node.id))].available_size;
+        assert_eq!(restored, available + 1024 * 1024);
+        
+        // Nodes the machine does not have cannot be bound to
+        request.requested_resources.memory_usage.as_mut().unwrap().numa_node = Some(u32::MAX);
+        assert!(adapter.allocate_asset(&request).await.is_err());
+    }
+    
     #[tokio::test]
     async fn test_proxy_address_resolution() {
         let adapter = MemoryAssetAdapter::new().await;
@@ -25,6 +25,8 @@ pub enum AssetRecordType {
     PrivacyUpdate,
     /// Asset adapter configuration
     AdapterConfig,
+    /// Metered usage billed for a shared allocation
+    Billing,
     /// Custom asset operation
     Custom(String),
 }
@@ -74,6 +76,19 @@ pub enum AssetPrivacyLevel {
     FullPublic,
 }
 
+impl From<&crate::assets::core::PrivacyLevel> for AssetPrivacyLevel {
+    fn from(level: &crate::assets::core::PrivacyLevel) -> Self {
+        use crate::assets::core::PrivacyLevel;
+        match level {
+            PrivacyLevel::Private => AssetPrivacyLevel::Private,
+            PrivacyLevel::PrivateNetwork => AssetPrivacyLevel::PrivateNetwork,
+            PrivacyLevel::P2P => AssetPrivacyLevel::P2P,
+            PrivacyLevel::PublicNetwork => AssetPrivacyLevel::PublicNetwork,
+            PrivacyLevel::FullPublic => AssetPrivacyLevel::FullPublic,
+        }
+    }
+}
+
 impl HyperMeshAssetRecord {
     /// Create new asset record with consensus validation
     pub fn new(
@@ -211,7 +226,7 @@ impl HyperMeshBlockData {
 /// Asset blockchain manager for HyperMesh
 pub struct AssetBlockchainManager {
     /// Consensus system for blockchain operations
-    consensus: Arc<dyn Consensus>,
+    consensus: Arc<dyn Consensus + Send + Sync>,
     /// Current node authority for asset operations
     node_authority: String,
 }
@@ -220,7 +235,7 @@ use std::sync::Arc;
 
 impl AssetBlockchainManager {
     /// Create new asset blockchain manager
-    pub fn new(consensus: Arc<Consensus>, node_authority: String) -> Self {
+    pub fn new(consensus: Arc<dyn Consensus + Send + Sync>, node_authority: String) -> Self {
         Self {
             consensus,
             node_authority,
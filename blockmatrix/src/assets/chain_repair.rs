@@ -0,0 +1,332 @@
+//! Entity chain integrity verification and repair
+//!
+//! A node whose entity chain fails verification, for example a block whose
+//! `previous_hash` does not match its predecessor after a torn write, does
+//! not have to be rebuilt by hand. Repair truncates the chain to the last
+//! valid block, moves the corrupt tail into quarantine for inspection, and
+//! resyncs the missing range from bootstrap peers. A peer's block is accepted
+//! only if it links to the repaired chain, carries a valid consensus proof
+//! and is signed with a key the entity registered in its genesis block, so a
+//! peer cannot extend the chain with blocks the entity never wrote.
+//!
+//! Blocks written before block hashes were computed, genesis blocks in
+//! particular, were stored with an all-zero hash. Their contents cannot be
+//! checked against it, so local verification accepts them as long as the
+//! chain still links.
+
+use super::matrix_blockchain::{EntityBlock, EntityBlockchain, MatrixBlockchainManager};
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use stoq::{ApiError, ApiHandler, ApiRequest, ApiResponse, StoqApiClient};
+use tokio::sync::RwLock;
+
+/// Hash stored on blocks written before block hashes were computed
+pub const LEGACY_BLOCK_HASH: [u8; 32] = [0u8; 32];
+
+/// STOQ method peers serve entity chain blocks on
+pub const CHAIN_BLOCKS_METHOD: &str = "chain/blocks";
+
+/// Why a block failed verification
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChainViolation {
+    /// Block is not at the position its index claims
+    IndexMismatch { index: u64, expected: u64 },
+    /// Block does not link to the block before it
+    PreviousHashMismatch { index: u64 },
+    /// Block contents do not match its stored hash
+    HashMismatch { index: u64 },
+}
+
+/// Why a peer's block was not accepted
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PeerBlockRejection {
+    Link(ChainViolation),
+    InvalidConsensusProof { index: u64 },
+    UnsignedOrUnknownSigner { index: u64 },
+}
+
+impl std::fmt::Display for PeerBlockRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeerBlockRejection::Link(violation) => violation.fmt(f),
+            PeerBlockRejection::InvalidConsensusProof { index } => write!(f, "invalid consensus proof on block {}", index),
+            PeerBlockRejection::UnsignedOrUnknownSigner { index } => {
+                write!(f, "block {} is not signed with a registered entity key", index)
+            }
+        }
+    }
+}
+
+impl ChainViolation {
+    /// Chain position of the offending block
+    pub fn position(&self) -> u64 {
+        match self {
+            ChainViolation::IndexMismatch { expected, .. } => *expected,
+            ChainViolation::PreviousHashMismatch { index } | ChainViolation::HashMismatch { index } => *index,
+        }
+    }
+}
+
+impl std::fmt::Display for ChainViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainViolation::IndexMismatch { index, expected } => {
+                write!(f, "block at position {} has index {}", expected, index)
+            }
+            ChainViolation::PreviousHashMismatch { index } => write!(f, "previous_hash mismatch at block {}", index),
+            ChainViolation::HashMismatch { index } => write!(f, "hash mismatch at block {}", index),
+        }
+    }
+}
+
+/// A block removed from the chain by repair
+#[derive(Clone, Serialize, Deserialize)]
+pub struct QuarantinedBlock {
+    pub block: EntityBlock,
+    /// Violation that triggered the repair
+    pub violation: ChainViolation,
+    pub quarantined_at: SystemTime,
+}
+
+/// Outcome of a chain repair
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RepairReport {
+    /// First violation found; `None` if the chain was already valid
+    pub violation: Option<ChainViolation>,
+    /// Blocks moved into quarantine
+    pub quarantined: usize,
+    /// Blocks fetched from peers and appended
+    pub resynced: usize,
+    /// Index of the last block after repair
+    pub head: u64,
+    /// Indices lost to truncation that no peer could supply, as `start..end`
+    pub missing: Option<(u64, u64)>,
+}
+
+/// Source of blocks for resyncing an entity chain, usually a bootstrap peer
+#[async_trait]
+pub trait ChainPeer: Send + Sync {
+    /// Peer identifier for logging
+    fn peer_id(&self) -> &str;
+
+    /// Blocks of the entity's chain starting at `from_index`, in order
+    async fn fetch_blocks(&self, network_domain: &str, from_index: u64) -> Result<Vec<EntityBlock>, String>;
+}
+
+/// Check that `block` may follow `previous` (or start the chain when `None`)
+///
+/// `allow_legacy` accepts a block stored with `LEGACY_BLOCK_HASH` without
+/// checking its contents.
+fn check_link(previous: Option<&EntityBlock>, block: &EntityBlock, allow_legacy: bool) -> Result<(), ChainViolation> {
+    let expected = previous.map_or(0, |p| p.index + 1);
+    if block.index != expected {
+        return Err(ChainViolation::IndexMismatch { index: block.index, expected });
+    }
+    let previous_hash = previous.map_or([0u8; 32], |p| p.hash);
+    if block.previous_hash != previous_hash {
+        return Err(ChainViolation::PreviousHashMismatch { index: block.index });
+    }
+    let legacy = allow_legacy && block.hash == LEGACY_BLOCK_HASH;
+    if !legacy && block.hash != block.calculate_hash() {
+        return Err(ChainViolation::HashMismatch { index: block.index });
+    }
+    Ok(())
+}
+
+/// Check a block fetched from a peer before it is appended
+async fn check_peer_block(
+    previous: &EntityBlock,
+    block: &EntityBlock,
+    entity_keys: &[Vec<u8>],
+) -> Result<(), PeerBlockRejection> {
+    check_link(Some(previous), block, false).map_err(PeerBlockRejection::Link)?;
+    if !block.consensus_proof.validate().await.unwrap_or(false) {
+        return Err(PeerBlockRejection::InvalidConsensusProof { index: block.index });
+    }
+    if !entity_keys.iter().any(|key| block.verify_signature(key)) {
+        return Err(PeerBlockRejection::UnsignedOrUnknownSigner { index: block.index });
+    }
+    Ok(())
+}
+
+impl EntityBlockchain {
+    /// Verify every block's index, link and hash, returning the first violation
+    pub fn verify_chain(&self) -> Result<(), ChainViolation> {
+        let mut previous = None;
+        for block in &self.chain {
+            check_link(previous, block, true)?;
+            previous = Some(block);
+        }
+        Ok(())
+    }
+
+    /// Repair the chain after an integrity violation
+    ///
+    /// Truncates to the last valid block, quarantines the rest and resyncs
+    /// from `peers` in order until the chain is back to its previous length
+    /// or the peers run out. Peer blocks are checked against the keys in the
+    /// surviving genesis block. Fails, leaving the chain untouched, only when
+    /// the genesis block itself is corrupt: without it there is no trusted
+    /// key to check a replacement against.
+    pub async fn repair(&mut self, peers: &[Arc<dyn ChainPeer>]) -> Result<RepairReport, String> {
+        let violation = match self.verify_chain() {
+            Ok(()) => {
+                return Ok(RepairReport {
+                    head: self.chain.len().saturating_sub(1) as u64,
+                    ..Default::default()
+                });
+            }
+            Err(violation) => violation,
+        };
+
+        if violation.position() == 0 {
+            return Err(format!(
+                "Genesis block of {} is corrupt ({}) and cannot be replaced from peers",
+                self.config.network_domain, violation
+            ));
+        }
+
+        let original_len = self.chain.len() as u64;
+        let mut chain = self.chain.clone();
+        let corrupt = chain.split_off(violation.position() as usize);
+        tracing::warn!(
+            "Entity chain {} failed verification ({}), quarantining {} blocks",
+            self.config.network_domain, violation, corrupt.len()
+        );
+
+        let entity_keys = EntityBlockchain::genesis_keys(&chain[0]);
+        if entity_keys.is_empty() && !peers.is_empty() {
+            tracing::warn!(
+                "Entity {} registers no signing keys, so no peer block can be accepted",
+                self.config.network_domain
+            );
+        }
+
+        let mut resynced = 0;
+        for peer in peers {
+            if chain.len() as u64 >= original_len {
+                break;
+            }
+            let blocks = match peer.fetch_blocks(&self.config.network_domain, chain.len() as u64).await {
+                Ok(blocks) => blocks,
+                Err(e) => {
+                    tracing::warn!("Resync of {} from {} failed: {}", self.config.network_domain, peer.peer_id(), e);
+                    continue;
+                }
+            };
+            for block in blocks {
+                let previous = chain.last().expect("chain keeps its genesis block");
+                if let Err(e) = check_peer_block(previous, &block, &entity_keys).await {
+                    tracing::warn!("Rejected block from {}: {}", peer.peer_id(), e);
+                    break;
+                }
+                chain.push(block);
+                resynced += 1;
+            }
+        }
+
+        let quarantined_at = SystemTime::now();
+        let quarantined = corrupt.len();
+        self.quarantined_blocks.extend(corrupt.into_iter().map(|block| QuarantinedBlock {
+            block,
+            violation: violation.clone(),
+            quarantined_at,
+        }));
+        self.chain = chain;
+        let head = self.chain.len() as u64 - 1;
+        self.last_validated_index = head;
+
+        Ok(RepairReport {
+            violation: Some(violation),
+            quarantined,
+            resynced,
+            head,
+            missing: (head + 1 < original_len).then_some((head + 1, original_len)),
+        })
+    }
+}
+
+/// Request for the blocks of an entity chain from an index onwards
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainBlocksRequest {
+    pub network_domain: String,
+    pub from_index: u64,
+}
+
+/// A bootstrap peer reached over the STOQ API
+pub struct StoqChainPeer {
+    /// STOQ service name of the peer
+    service: String,
+    client: Arc<StoqApiClient>,
+}
+
+impl StoqChainPeer {
+    pub fn new(service: impl Into<String>, client: Arc<StoqApiClient>) -> Self {
+        Self { service: service.into(), client }
+    }
+}
+
+#[async_trait]
+impl ChainPeer for StoqChainPeer {
+    fn peer_id(&self) -> &str {
+        &self.service
+    }
+
+    async fn fetch_blocks(&self, network_domain: &str, from_index: u64) -> Result<Vec<EntityBlock>, String> {
+        let request = ChainBlocksRequest {
+            network_domain: network_domain.to_string(),
+            from_index,
+        };
+        self.client
+            .call(&self.service, CHAIN_BLOCKS_METHOD, &request)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Serves this node's entity chain blocks to peers repairing theirs
+pub struct ChainBlocksHandler {
+    path: String,
+    manager: Arc<RwLock<MatrixBlockchainManager>>,
+}
+
+impl ChainBlocksHandler {
+    /// Handler for the chain blocks method of STOQ service `service`
+    pub fn new(service: &str, manager: Arc<RwLock<MatrixBlockchainManager>>) -> Self {
+        Self {
+            path: format!("{}/{}", service, CHAIN_BLOCKS_METHOD),
+            manager,
+        }
+    }
+}
+
+#[async_trait]
+impl ApiHandler for ChainBlocksHandler {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    async fn handle(&self, req: ApiRequest) -> Result<ApiResponse, ApiError> {
+        let request: ChainBlocksRequest = serde_json::from_slice(&req.payload)
+            .map_err(|e| ApiError::InvalidRequest(e.to_string()))?;
+
+        let manager = self.manager.read().await;
+        let chain = manager
+            .entity_chain(&request.network_domain)
+            .ok_or_else(|| ApiError::NotFound(format!("Unknown entity: {}", request.network_domain)))?;
+        let blocks: Vec<&EntityBlock> = chain.chain.iter().filter(|block| block.index >= request.from_index).collect();
+        let payload = serde_json::to_vec(&blocks).map_err(|e| ApiError::SerializationError(e.to_string()))?;
+
+        Ok(ApiResponse {
+            request_id: req.id,
+            success: true,
+            payload: Bytes::from(payload),
+            error: None,
+            metadata: HashMap::new(),
+        })
+    }
+}
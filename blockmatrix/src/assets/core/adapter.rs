@@ -104,6 +104,30 @@ pub struct CpuRequirements {
     pub architecture: Option<String>,
     /// CPU features required (AVX, SSE, etc.)
     pub required_features: Vec<String>,
+    /// Keep every core, and the request's memory, on one NUMA node
+    #[serde(default)]
+    pub numa_local: bool,
+}
+
+/// CPUs and NUMA memory nodes a workload is pinned to
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumaPinning {
+    /// Logical CPUs the workload may run on
+    pub cpus: Vec<u32>,
+    /// NUMA nodes the workload may allocate memory from
+    pub mems: Vec<u32>,
+}
+
+impl NumaPinning {
+    /// Value for a cgroup's `cpuset.cpus`
+    pub fn cpuset_cpus(&self) -> String {
+        crate::os_integration::format_cpu_list(&self.cpus)
+    }
+
+    /// Value for a cgroup's `cpuset.mems`
+    pub fn cpuset_mems(&self) -> String {
+        crate::os_integration::format_cpu_list(&self.mems)
+    }
 }
 
 /// GPU resource requirements
@@ -117,6 +141,12 @@ pub struct GpuRequirements {
     pub compute_capability: Option<String>,
     /// Required GPU features
     pub required_features: Vec<String>,
+    /// Share of one GPU, e.g. `0.5`, served by a partition; whole devices when unset
+    #[serde(default)]
+    pub fraction: Option<f32>,
+    /// Partition profile such as `3g.20gb`; takes precedence over `fraction`
+    #[serde(default)]
+    pub partition_profile: Option<String>,
 }
 
 /// Memory resource requirements
@@ -467,6 +497,7 @@ mod tests {
                 min_frequency_mhz: Some(2400),
                 architecture: Some("x86_64".to_string()),
                 required_features: vec!["AVX2".to_string()],
+                numa_local: false,
             }),
             memory_usage: Some(MemoryRequirements {
                 size_bytes: 8 * 1024 * 1024 * 1024, // 8GB
@@ -24,12 +24,13 @@ pub mod adapter;
 pub mod status;
 pub mod privacy;
 pub mod proxy;
+pub mod reservation;
 
 // Re-exports
 pub use asset_id::{AssetId, AssetType};
 pub use adapter::{
     AssetAdapter, AssetAllocationRequest, ResourceRequirements, ResourceLimits, ResourceUsage,
-    CpuRequirements, CpuUsage, CpuLimit,
+    CpuRequirements, CpuUsage, CpuLimit, NumaPinning,
     GpuRequirements, GpuUsage, GpuLimit,
     MemoryRequirements, MemoryUsage, MemoryLimit,
     StorageRequirements, StorageUsage, StorageLimit, StorageType,
@@ -40,6 +41,7 @@ pub use adapter::{
 };
 pub use status::{AssetStatus, AssetState};
 pub use privacy::{PrivacyLevel, AssetAllocation};
+pub use reservation::{Reservation, ReservationBook, ReservationLookup, ReservationToken};
 pub use proxy::{
     ProxyAddress, ProxyType, ProxyAddressResolver, ProxyNodeInfo, ProxyCapabilities, ProxyStatistics,
     // CRITICAL Remote Proxy/NAT system exports
@@ -115,6 +117,10 @@ pub enum AssetError {
     #[error("Operation timeout: {operation}")]
     OperationTimeout { operation: String },
 
+    /// Reservation lapsed before it was committed
+    #[error("Reservation expired: {token}")]
+    ReservationExpired { token: String },
+
     /// Resource not found
     #[error("Resource not found: {resource}")]
     NotFound { resource: String },
@@ -150,6 +156,10 @@ pub struct AssetManager {
     proxy_resolver: Arc<ProxyAddressResolver>,
     /// Consensus validation requirements
     consensus_requirements: ConsensusRequirements,
+    /// Allocations held for pending placement decisions
+    reservations: Arc<RwLock<ReservationBook>>,
+    /// Usage meter for allocations shared with the public network
+    meter: Option<Arc<crate::assets::metering::UsageMeter>>,
 }
 
 /// Consensus requirements configuration
@@ -184,9 +194,22 @@ impl AssetManager {
             adapters: Arc::new(RwLock::new(HashMap::new())),
             proxy_resolver: Arc::new(ProxyAddressResolver::new()),
             consensus_requirements: ConsensusRequirements::default(),
+            reservations: Arc::new(RwLock::new(ReservationBook::new())),
+            meter: None,
         }
     }
     
+    /// Meter and bill allocations made at `PublicNetwork` or `FullPublic` privacy
+    pub fn with_usage_meter(mut self, meter: Arc<crate::assets::metering::UsageMeter>) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+    
+    /// Usage meter, if metering is enabled
+    pub fn usage_meter(&self) -> Option<&Arc<crate::assets::metering::UsageMeter>> {
+        self.meter.as_ref()
+    }
+    
     /// Register an asset adapter for a specific asset type
     pub async fn register_adapter(
         &self,
@@ -220,11 +243,131 @@ impl AssetManager {
         // Register asset status
         let mut assets = self.assets.write().await;
         assets.insert(allocation.asset_id.clone(), allocation.status.clone());
+        drop(assets);
+        
+        if let Some(meter) = &self.meter {
+            meter.open(&allocation, &request.certificate_fingerprint, request.consensus_proof.clone()).await;
+        }
         
         tracing::info!("Allocated asset: {}", allocation.asset_id);
         Ok(allocation)
     }
     
+    /// Reserve resources for `ttl` without registering the allocation
+    ///
+    /// The adapter allocates immediately so the capacity cannot be handed to
+    /// anyone else; the allocation only becomes visible once `commit` is called.
+    /// Uncommitted reservations are released when their TTL lapses.
+    pub async fn reserve(
+        &self,
+        request: AssetAllocationRequest,
+        ttl: Duration,
+    ) -> AssetResult<ReservationToken> {
+        self.expire_reservations().await?;
+        self.validate_consensus_proof(&request.consensus_proof).await?;
+
+        let allocation = {
+            let adapters = self.adapters.read().await;
+            let adapter = adapters.get(&request.asset_type)
+                .ok_or_else(|| AssetError::AdapterError {
+                    message: format!("No adapter found for asset type: {:?}", request.asset_type)
+                })?;
+            adapter.allocate_asset(&request).await?
+        };
+
+        let asset_id = allocation.asset_id.clone();
+        let token = self.reservations.write().await.insert(
+            allocation,
+            request.certificate_fingerprint.clone(),
+            Some(request.consensus_proof.clone()),
+            ttl,
+            SystemTime::now(),
+        );
+        tracing::info!("Reserved asset {} as {} for {:?}", asset_id, token, ttl);
+        Ok(token)
+    }
+
+    /// Commit a reservation, turning it into a regular allocation
+    pub async fn commit(&self, token: &ReservationToken) -> AssetResult<AssetAllocation> {
+        let lookup = self.reservations.write().await.take(token, SystemTime::now());
+        match lookup {
+            ReservationLookup::Live(reservation) => {
+                let allocation = reservation.allocation;
+                self.assets.write().await.insert(allocation.asset_id.clone(), allocation.status.clone());
+                if let (Some(meter), Some(proof)) = (&self.meter, reservation.proof) {
+                    meter.open(&allocation, &reservation.consumer, proof).await;
+                }
+                tracing::info!("Committed reservation {} for asset {}", token, allocation.asset_id);
+                Ok(allocation)
+            }
+            ReservationLookup::Expired(reservation) => {
+                self.release_reserved(&reservation).await;
+                Err(AssetError::ReservationExpired { token: token.to_string() })
+            }
+            ReservationLookup::Unknown => Err(AssetError::NotFound {
+                resource: token.to_string()
+            }),
+        }
+    }
+
+    /// Release a reservation before its TTL lapses
+    pub async fn release(&self, token: &ReservationToken) -> AssetResult<()> {
+        let lookup = self.reservations.write().await.take(token, SystemTime::now());
+        match lookup {
+            ReservationLookup::Live(reservation) | ReservationLookup::Expired(reservation) => {
+                self.release_reserved(&reservation).await;
+                Ok(())
+            }
+            ReservationLookup::Unknown => Err(AssetError::NotFound {
+                resource: token.to_string()
+            }),
+        }
+    }
+
+    /// Release every reservation whose TTL has lapsed, returning how many were freed
+    pub async fn expire_reservations(&self) -> AssetResult<usize> {
+        let expired = self.reservations.write().await.drain_expired(SystemTime::now());
+        for reservation in &expired {
+            tracing::debug!("Reservation {} expired", reservation.token);
+            self.release_reserved(reservation).await;
+        }
+        Ok(expired.len())
+    }
+
+    /// Look up an outstanding reservation
+    pub async fn get_reservation(&self, token: &ReservationToken) -> Option<Reservation> {
+        self.reservations.read().await.get(token).cloned()
+    }
+
+    /// Periodically release lapsed reservations until the manager is dropped
+    pub fn spawn_reservation_expiry(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else { break };
+                if let Err(e) = manager.expire_reservations().await {
+                    tracing::warn!("Reservation expiry failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Hand reserved capacity back to its adapter
+    async fn release_reserved(&self, reservation: &Reservation) {
+        let asset_id = &reservation.allocation.asset_id;
+        let adapters = self.adapters.read().await;
+        match adapters.get(&asset_id.asset_type) {
+            Some(adapter) => {
+                if let Err(e) = adapter.deallocate_asset(asset_id).await {
+                    tracing::warn!("Failed to release reserved asset {}: {}", asset_id, e);
+                }
+            }
+            None => tracing::warn!("No adapter to release reserved asset {}", asset_id),
+        }
+    }
+
     /// Deallocate an asset
     pub async fn deallocate_asset(&self, asset_id: &AssetId) -> AssetResult<()> {
         // Get adapter for asset type
@@ -240,11 +383,34 @@ impl AssetManager {
         // Remove from registry
         let mut assets = self.assets.write().await;
         assets.remove(asset_id);
+        drop(assets);
+        
+        // Bill whatever was used since the last settlement
+        if let Some(meter) = &self.meter {
+            if let Err(e) = meter.close(asset_id).await {
+                tracing::warn!("Failed to settle usage of {}: {}", asset_id, e);
+            }
+        }
         
         tracing::info!("Deallocated asset: {}", asset_id);
         Ok(())
     }
     
+    /// Sample resource usage of every metered allocation held for `interval`
+    pub async fn meter_usage(&self, interval: Duration) -> AssetResult<usize> {
+        let Some(meter) = &self.meter else {
+            return Ok(0);
+        };
+        let metered = meter.metered_assets().await;
+        for asset_id in &metered {
+            match self.get_resource_usage(asset_id).await {
+                Ok(usage) => meter.record(asset_id, &usage, interval).await,
+                Err(e) => tracing::debug!("No usage sample for {}: {}", asset_id, e),
+            }
+        }
+        Ok(metered.len())
+    }
+    
     /// Get current status of an asset
     pub async fn get_asset_status(&self, asset_id: &AssetId) -> AssetResult<AssetStatus> {
         // First check local registry
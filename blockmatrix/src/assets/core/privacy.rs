@@ -82,6 +82,11 @@ impl PrivacyLevel {
         !matches!(self, PrivacyLevel::Private)
     }
     
+    /// Whether allocations at this level are metered and billed
+    pub fn is_metered(&self) -> bool {
+        matches!(self, PrivacyLevel::PublicNetwork | PrivacyLevel::FullPublic)
+    }
+    
     pub fn supports_proxy_addressing(&self) -> bool {
         matches!(self, 
             PrivacyLevel::P2P | 
@@ -0,0 +1,178 @@
+//! Time-limited asset reservations
+//!
+//! A reservation holds adapter capacity for a placement decision that has not
+//! been finalised yet. The scheduler reserves resources on each candidate node,
+//! commits the reservation on the node it picks, and releases (or lets expire)
+//! the rest, so two workloads placed concurrently can never book the same assets.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::privacy::AssetAllocation;
+use super::ConsensusProof;
+
+/// Opaque handle returned by `AssetManager::reserve`
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReservationToken(Uuid);
+
+impl ReservationToken {
+    /// Generate a fresh token
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for ReservationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ReservationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rsv-{}", self.0)
+    }
+}
+
+/// Allocation held on behalf of a pending placement decision
+#[derive(Clone, Debug)]
+pub struct Reservation {
+    /// Token identifying this reservation
+    pub token: ReservationToken,
+    /// Allocation performed by the adapter, not yet registered with the manager
+    pub allocation: AssetAllocation,
+    /// Certificate fingerprint of the requester
+    pub consumer: String,
+    /// Consensus proof the reservation was made under
+    pub proof: Option<ConsensusProof>,
+    /// When the reservation was taken
+    pub reserved_at: SystemTime,
+    /// When the reservation lapses if not committed
+    pub expires_at: SystemTime,
+}
+
+impl Reservation {
+    /// Whether the reservation has lapsed at `now`
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Outcome of taking a reservation out of the book
+#[derive(Debug)]
+pub enum ReservationLookup {
+    /// Reservation is live and may be committed
+    Live(Reservation),
+    /// Reservation existed but its TTL has passed; capacity must be released
+    Expired(Reservation),
+    /// No reservation with that token
+    Unknown,
+}
+
+/// Bookkeeping for outstanding reservations
+#[derive(Debug, Default)]
+pub struct ReservationBook {
+    reservations: HashMap<ReservationToken, Reservation>,
+}
+
+impl ReservationBook {
+    /// Create an empty book
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an allocation as reserved for `ttl` starting at `now`
+    pub fn insert(
+        &mut self,
+        allocation: AssetAllocation,
+        consumer: String,
+        proof: Option<ConsensusProof>,
+        ttl: Duration,
+        now: SystemTime,
+    ) -> ReservationToken {
+        let token = ReservationToken::new();
+        self.reservations.insert(token, Reservation {
+            token,
+            allocation,
+            consumer,
+            proof,
+            reserved_at: now,
+            expires_at: now + ttl,
+        });
+        token
+    }
+
+    /// Remove a reservation, reporting whether it was still live at `now`
+    pub fn take(&mut self, token: &ReservationToken, now: SystemTime) -> ReservationLookup {
+        match self.reservations.remove(token) {
+            Some(reservation) if reservation.is_expired(now) => ReservationLookup::Expired(reservation),
+            Some(reservation) => ReservationLookup::Live(reservation),
+            None => ReservationLookup::Unknown,
+        }
+    }
+
+    /// Look up a reservation without removing it
+    pub fn get(&self, token: &ReservationToken) -> Option<&Reservation> {
+        self.reservations.get(token)
+    }
+
+    /// Remove and return every reservation that has lapsed at `now`
+    pub fn drain_expired(&mut self, now: SystemTime) -> Vec<Reservation> {
+        let expired: Vec<ReservationToken> = self.reservations
+            .values()
+            .filter(|r| r.is_expired(now))
+            .map(|r| r.token)
+            .collect();
+        expired
+            .iter()
+            .filter_map(|token| self.reservations.remove(token))
+            .collect()
+    }
+
+    /// Number of outstanding reservations
+    pub fn len(&self) -> usize {
+        self.reservations.len()
+    }
+
+    /// Whether there are no outstanding reservations
+    pub fn is_empty(&self) -> bool {
+        self.reservations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::core::{AssetId, AssetStatus, AssetType, PrivacyLevel};
+
+    fn allocation() -> AssetAllocation {
+        let asset_id = AssetId::new(AssetType::Cpu);
+        let status = AssetStatus::new(asset_id.clone(), "test-cert".to_string(), PrivacyLevel::Private);
+        AssetAllocation::new(asset_id, status, PrivacyLevel::Private)
+    }
+
+    #[test]
+    fn test_reservation_expiry_and_take() {
+        let mut book = ReservationBook::new();
+        let now = SystemTime::now();
+
+        let short = book.insert(allocation(), "test-cert".to_string(), None, Duration::from_secs(5), now);
+        let long = book.insert(allocation(), "test-cert".to_string(), None, Duration::from_secs(60), now);
+        assert_eq!(book.len(), 2);
+
+        let later = now + Duration::from_secs(10);
+        let expired = book.drain_expired(later);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].token, short);
+        assert!(matches!(book.take(&short, later), ReservationLookup::Unknown));
+
+        assert!(matches!(book.take(&long, later), ReservationLookup::Live(_)));
+        assert!(book.is_empty());
+
+        let lapsed = book.insert(allocation(), "test-cert".to_string(), None, Duration::from_secs(1), now);
+        assert!(matches!(book.take(&lapsed, later), ReservationLookup::Expired(_)));
+    }
+}
@@ -130,6 +130,8 @@ pub mod proxy;
 pub mod blockchain;
 pub mod cross_chain;
 pub mod matrix_blockchain;
+pub mod chain_repair;
+pub mod metering;
 
 // Main exports
 pub use core::{
@@ -225,6 +227,11 @@ pub use matrix_blockchain::{
     MatrixCoordinate, ValidationRequest, PublicValidationResponse, ValidationResult,
 };
 
+// Chain repair exports
+pub use chain_repair::{
+    ChainBlocksHandler, ChainPeer, ChainViolation, QuarantinedBlock, RepairReport, StoqChainPeer,
+};
+
 /// Library version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -10,6 +10,8 @@ use serde::{Serialize, Deserialize};
 use crate::assets::core::asset_id::{AssetId, AssetType};
 pub use super::blockchain::{HyperMeshAssetRecord, AssetRecordType, AssetPrivacyLevel};
 use crate::consensus::ConsensusProof;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use super::chain_repair::{ChainPeer, QuarantinedBlock, RepairReport};
 
 /// Matrix coordinate system for entity organization
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -89,6 +91,12 @@ pub struct EntityBlockchain {
     pub chain_state_hash: [u8; 32],
     /// Cross-chain validation cache
     pub validation_cache: HashMap<String, CrossChainValidationResult>,
+    /// Blocks removed by chain repair, kept for inspection
+    #[serde(default)]
+    pub quarantined_blocks: Vec<QuarantinedBlock>,
+    /// Key this node signs the entity's blocks with, if it holds one
+    #[serde(skip)]
+    signing_key: Option<SigningKey>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -174,6 +182,44 @@ pub enum EntityBlockData {
     },
 }
 
+impl EntityBlock {
+    /// Hash of the block's index, link, timestamp and data
+    pub fn calculate_hash(&self) -> [u8; 32] {
+        use sha2::{Sha256, Digest};
+
+        let mut hasher = Sha256::new();
+        hasher.update(&self.index.to_le_bytes());
+        hasher.update(&self.previous_hash);
+        if let Ok(duration) = self.timestamp.duration_since(SystemTime::UNIX_EPOCH) {
+            hasher.update(&duration.as_micros().to_le_bytes());
+        }
+        hasher.update(&serde_json::to_vec(&self.data).unwrap_or_default());
+
+        let result = hasher.finalize();
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&result);
+        hash
+    }
+
+    /// Sign the block hash with the entity's key
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        self.entity_signature = signing_key.sign(&self.hash).to_bytes().to_vec();
+    }
+
+    /// Whether the block hash is signed with the Ed25519 key `public_key`
+    pub fn verify_signature(&self, public_key: &[u8]) -> bool {
+        let Ok(public_key) = <[u8; 32]>::try_from(public_key) else {
+            return false;
+        };
+        let Ok(signature) = <[u8; 64]>::try_from(self.entity_signature.as_slice()) else {
+            return false;
+        };
+        VerifyingKey::from_bytes(&public_key)
+            .map(|key| key.verify(&self.hash, &Signature::from_bytes(&signature)).is_ok())
+            .unwrap_or(false)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EntityInfo {
     pub name: String,
@@ -326,7 +372,7 @@ impl EntityBlockchain {
         };
 
         // Create genesis block (would have real consensus proof in production)
-        let genesis_block = EntityBlock {
+        let mut genesis_block = EntityBlock {
             index: 0,
             previous_hash: [0u8; 32],
             timestamp: SystemTime::now(),
@@ -357,9 +403,10 @@ impl EntityBlockchain {
                 ).unwrap(),
                 crate::consensus::proof::TimeProof::new(0, None, 0),
             ),
-            hash: [0u8; 32], // Would be calculated
+            hash: [0u8; 32],
             entity_signature: vec![],
         };
+        genesis_block.hash = genesis_block.calculate_hash();
 
         Self {
             config,
@@ -369,6 +416,35 @@ impl EntityBlockchain {
             last_validated_index: 0,
             chain_state_hash: [0u8; 32],
             validation_cache: HashMap::new(),
+            quarantined_blocks: vec![],
+            signing_key: None,
+        }
+    }
+
+    /// Sign the entity's blocks with `signing_key`
+    ///
+    /// Registers the public key in the genesis block, so call this before
+    /// the chain is shared; peers repairing the chain accept only blocks
+    /// signed with a key registered there.
+    pub fn with_signing_key(mut self, signing_key: SigningKey) -> Self {
+        let genesis = &mut self.chain[0];
+        if let EntityBlockData::Genesis { entity_info, .. } = &mut genesis.data {
+            entity_info.public_keys.insert(
+                "entity".to_string(),
+                signing_key.verifying_key().to_bytes().to_vec(),
+            );
+        }
+        genesis.hash = genesis.calculate_hash();
+        genesis.sign(&signing_key);
+        self.signing_key = Some(signing_key);
+        self
+    }
+
+    /// Signing keys registered in a genesis block
+    pub fn genesis_keys(genesis: &EntityBlock) -> Vec<Vec<u8>> {
+        match &genesis.data {
+            EntityBlockData::Genesis { entity_info, .. } => entity_info.public_keys.values().cloned().collect(),
+            _ => Vec::new(),
         }
     }
 
@@ -387,15 +463,19 @@ impl EntityBlockchain {
         let block_index = self.chain.len() as u64;
         let previous_hash = self.chain.last().unwrap().hash;
         
-        let block = EntityBlock {
+        let mut block = EntityBlock {
             index: block_index,
             previous_hash,
             timestamp: SystemTime::now(),
             data: EntityBlockData::AssetRecord(asset_record),
             consensus_proof,
-            hash: [0u8; 32], // Would be calculated properly
-            entity_signature: vec![], // Would be signed by entity
+            hash: [0u8; 32],
+            entity_signature: vec![],
         };
+        block.hash = block.calculate_hash();
+        if let Some(signing_key) = &self.signing_key {
+            block.sign(signing_key);
+        }
 
         self.chain.push(block);
         self.last_validated_index = block_index;
@@ -553,11 +633,49 @@ impl MatrixBlockchainManager {
         
         Ok(results)
     }
+
+    /// Entity chain kept for `network_domain`
+    pub fn entity_chain(&self, network_domain: &str) -> Option<&EntityBlockchain> {
+        self.entity_chains.get(network_domain)
+    }
+
+    /// Verify every entity chain and repair those that fail, as at startup
+    pub async fn repair_all(
+        &mut self,
+        peers: &[std::sync::Arc<dyn ChainPeer>],
+    ) -> HashMap<String, Result<RepairReport, String>> {
+        let mut reports = HashMap::new();
+        for (network_domain, chain) in self.entity_chains.iter_mut() {
+            let report = chain.repair(peers).await;
+            match &report {
+                Ok(report) if report.violation.is_some() => tracing::warn!(
+                    "Repaired entity chain {}: {} quarantined, {} resynced",
+                    network_domain, report.quarantined, report.resynced
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Entity chain {} could not be repaired: {}", network_domain, e),
+            }
+            reports.insert(network_domain.clone(), report);
+        }
+        reports
+    }
+
+    /// Repair an entity's chain, resyncing the missing range from `peers`
+    pub async fn repair_entity_chain(
+        &mut self,
+        network_domain: &str,
+        peers: &[std::sync::Arc<dyn ChainPeer>],
+    ) -> Result<RepairReport, String> {
+        let chain = self.entity_chains.get_mut(network_domain)
+            .ok_or_else(|| format!("Unknown entity: {}", network_domain))?;
+        chain.repair(peers).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assets::chain_repair::ChainViolation;
 
     #[test]
     fn test_matrix_coordinate_creation() {
@@ -671,4 +789,114 @@ mod tests {
         assert!(manager.entity_chains.contains_key("dmv.hypermesh.online"));
         assert!(manager.routing_table.contains_key("dmv.hypermesh.online"));
     }
-}
\ No newline at end of file
+
+    fn honda_config() -> EntityConfig {
+        EntityConfig {
+            network_domain: "honda.hypermesh.online".to_string(),
+            entity_type: EntityType::Manufacturer,
+            matrix_coordinate: MatrixCoordinate {
+                geographic: GeographicDimension {
+                    region: "north-america".to_string(),
+                    country: "US".to_string(),
+                    state_province: "OH".to_string(),
+                    locality: "Marysville".to_string(),
+                    latitude: 40.2314,
+                    longitude: -83.3677,
+                },
+                organizational: OrganizationalDimension {
+                    network_id: "honda.hypermesh.online".to_string(),
+                    division_id: "manufacturing".to_string(),
+                    department_id: "assembly".to_string(),
+                    unit_id: "line-a".to_string(),
+                    hierarchy_level: 3,
+                },
+                access_level: AccessLevel::Administrative,
+                temporal_index: 0,
+                node_id: "honda-marysville".to_string(),
+                cell_hash: [0u8; 32],
+            },
+            privacy_policies: PrivacyPolicyConfig {
+                public_fields: vec!["asset_type".to_string(), "vin".to_string()],
+                federated_fields: HashMap::new(),
+                zk_proof_fields: vec!["manufacturing_cost".to_string()],
+                default_privacy_level: AssetPrivacyLevel::Private,
+            },
+            trusted_partners: vec!["dealer.hypermesh.online".to_string()],
+        }
+    }
+
+    struct StaticPeer(Vec<EntityBlock>);
+
+    #[async_trait::async_trait]
+    impl ChainPeer for StaticPeer {
+        fn peer_id(&self) -> &str {
+            "bootstrap-1"
+        }
+
+        async fn fetch_blocks(&self, _network_domain: &str, from_index: u64) -> Result<Vec<EntityBlock>, String> {
+            Ok(self.0.iter().filter(|b| b.index >= from_index).cloned().collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_repair() {
+        let signing_key = SigningKey::from_bytes(&[42u8; 32]);
+        let mut blockchain = EntityBlockchain::new(honda_config()).with_signing_key(signing_key.clone());
+        for policy_type in ["retention", "sharing", "audit"] {
+            let previous = blockchain.chain.last().unwrap();
+            let mut block = previous.clone();
+            block.index = previous.index + 1;
+            block.previous_hash = previous.hash;
+            block.data = EntityBlockData::PolicyUpdate {
+                policy_type: policy_type.to_string(),
+                policy_data: vec![],
+                effective_date: SystemTime::now(),
+            };
+            block.hash = block.calculate_hash();
+            block.sign(&signing_key);
+            blockchain.chain.push(block);
+        }
+        assert!(blockchain.verify_chain().is_ok());
+        let peer = StaticPeer(blockchain.chain.clone());
+
+        // A peer serving well-linked blocks signed with another key
+        let mut forged = blockchain.chain.clone();
+        let impostor = SigningKey::from_bytes(&[7u8; 32]);
+        for block in &mut forged[2..] {
+            block.sign(&impostor);
+        }
+        let forger = StaticPeer(forged);
+
+        // Block 2 no longer links to block 1
+        blockchain.chain[2].previous_hash = [7u8; 32];
+        assert_eq!(blockchain.verify_chain(), Err(ChainViolation::PreviousHashMismatch { index: 2 }));
+
+        // Without peers the chain is truncated and the gap reported
+        let mut offline = blockchain.clone();
+        let report = offline.repair(&[]).await.unwrap();
+        assert_eq!((report.quarantined, report.head, report.missing), (2, 1, Some((2, 4))));
+        assert_eq!(offline.quarantined_blocks.len(), 2);
+        assert!(offline.verify_chain().is_ok());
+
+        // Blocks the entity did not sign are rejected
+        let mut tampered = blockchain.clone();
+        let report = tampered.repair(&[std::sync::Arc::new(forger)]).await.unwrap();
+        assert_eq!((report.resynced, report.head), (0, 1));
+
+        let report = blockchain.repair(&[std::sync::Arc::new(peer)]).await.unwrap();
+        assert_eq!((report.resynced, report.head, report.missing), (2, 3, None));
+        assert_eq!(blockchain.chain.len(), 4);
+        assert!(blockchain.verify_chain().is_ok());
+    }
+
+    #[test]
+    fn test_legacy_genesis_hash() {
+        // Genesis blocks written before hashes were computed
+        let mut blockchain = EntityBlockchain::new(honda_config());
+        blockchain.chain[0].hash = crate::assets::chain_repair::LEGACY_BLOCK_HASH;
+        assert!(blockchain.verify_chain().is_ok());
+
+        blockchain.chain[0].hash = [1u8; 32];
+        assert_eq!(blockchain.verify_chain(), Err(ChainViolation::HashMismatch { index: 0 }));
+    }
+}
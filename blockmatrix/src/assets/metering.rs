@@ -0,0 +1,411 @@
+//! Usage metering and billing for shared assets
+//!
+//! Allocations made at `PublicNetwork` or `FullPublic` privacy are used by
+//! strangers, so their consumption is metered: CPU-seconds, GPU-seconds and
+//! memory/storage GB-hours accumulate from adapter usage samples and are
+//! periodically settled into billing records. Each record is priced by a
+//! pluggable `PricingPolicy` and appended to the asset blockchain, and the
+//! meter keeps an index providers can query to see what their hardware earned.
+
+use std::collections::HashMap;
+use std::ops::{Add, AddAssign, Sub};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::assets::blockchain::{AssetBlockchainManager, AssetRecordType, HyperMeshAssetRecord};
+use crate::assets::core::{
+    AssetAllocation, AssetError, AssetId, AssetResult, AssetType, ConsensusProof, PrivacyLevel, ResourceUsage,
+};
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Resource consumption accumulated over a metering period
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeteredUsage {
+    /// Core-seconds of CPU time
+    pub cpu_seconds: f64,
+    /// Seconds of full GPU utilisation
+    pub gpu_seconds: f64,
+    /// Resident memory integrated over time
+    pub memory_gb_hours: f64,
+    /// Stored data integrated over time
+    pub storage_gb_hours: f64,
+}
+
+impl MeteredUsage {
+    /// Consumption implied by one usage sample held for `interval`
+    pub fn from_sample(usage: &ResourceUsage, interval: Duration) -> Self {
+        let secs = interval.as_secs_f64();
+        let hours = secs / 3600.0;
+        Self {
+            cpu_seconds: usage.cpu_usage.as_ref()
+                .map_or(0.0, |cpu| cpu.active_cores as f64 * (cpu.utilization_percent as f64 / 100.0) * secs),
+            gpu_seconds: usage.gpu_usage.as_ref()
+                .map_or(0.0, |gpu| (gpu.utilization_percent as f64 / 100.0) * secs),
+            memory_gb_hours: usage.memory_usage.as_ref()
+                .map_or(0.0, |memory| memory.used_bytes as f64 / BYTES_PER_GB * hours),
+            storage_gb_hours: usage.storage_usage.as_ref()
+                .map_or(0.0, |storage| storage.used_bytes as f64 / BYTES_PER_GB * hours),
+        }
+    }
+
+    /// Whether nothing has been consumed
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Add for MeteredUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            cpu_seconds: self.cpu_seconds + other.cpu_seconds,
+            gpu_seconds: self.gpu_seconds + other.gpu_seconds,
+            memory_gb_hours: self.memory_gb_hours + other.memory_gb_hours,
+            storage_gb_hours: self.storage_gb_hours + other.storage_gb_hours,
+        }
+    }
+}
+
+impl AddAssign for MeteredUsage {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for MeteredUsage {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            cpu_seconds: (self.cpu_seconds - other.cpu_seconds).max(0.0),
+            gpu_seconds: (self.gpu_seconds - other.gpu_seconds).max(0.0),
+            memory_gb_hours: (self.memory_gb_hours - other.memory_gb_hours).max(0.0),
+            storage_gb_hours: (self.storage_gb_hours - other.storage_gb_hours).max(0.0),
+        }
+    }
+}
+
+/// Converts metered usage into an amount of CAESAR tokens
+pub trait PricingPolicy: Send + Sync {
+    /// Name recorded on each billing record priced by this policy
+    fn name(&self) -> &str;
+
+    /// Price `usage` of an asset shared at `privacy_level`
+    fn price(&self, asset_type: &AssetType, privacy_level: &PrivacyLevel, usage: &MeteredUsage) -> u64;
+}
+
+/// Fixed rate per unit, scaled by the privacy level's reward multiplier
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlatRatePricing {
+    pub per_cpu_second: f64,
+    pub per_gpu_second: f64,
+    pub per_memory_gb_hour: f64,
+    pub per_storage_gb_hour: f64,
+}
+
+impl Default for FlatRatePricing {
+    fn default() -> Self {
+        Self {
+            per_cpu_second: 1.0,
+            per_gpu_second: 10.0,
+            per_memory_gb_hour: 50.0,
+            per_storage_gb_hour: 5.0,
+        }
+    }
+}
+
+impl PricingPolicy for FlatRatePricing {
+    fn name(&self) -> &str {
+        "flat-rate"
+    }
+
+    fn price(&self, _asset_type: &AssetType, privacy_level: &PrivacyLevel, usage: &MeteredUsage) -> u64 {
+        let base = usage.cpu_seconds * self.per_cpu_second
+            + usage.gpu_seconds * self.per_gpu_second
+            + usage.memory_gb_hours * self.per_memory_gb_hour
+            + usage.storage_gb_hours * self.per_storage_gb_hour;
+        (base * privacy_level.caesar_reward_multiplier() as f64).round() as u64
+    }
+}
+
+/// Priced usage of one allocation over one metering period
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BillingRecord {
+    pub record_id: Uuid,
+    pub asset_id: AssetId,
+    /// Node whose hardware was used
+    pub provider: String,
+    /// Certificate fingerprint of the consumer
+    pub consumer: String,
+    pub privacy_level: PrivacyLevel,
+    pub period_start: SystemTime,
+    pub period_end: SystemTime,
+    pub usage: MeteredUsage,
+    /// Amount owed to the provider in CAESAR tokens
+    pub amount: u64,
+    pub pricing_policy: String,
+    /// Hash of the blockchain record carrying this bill
+    pub ledger_hash: Option<[u8; 32]>,
+}
+
+/// Durable destination for billing records
+#[async_trait]
+pub trait BillingLedger: Send + Sync {
+    /// Append a record, returning its ledger hash
+    async fn append(&self, record: &BillingRecord, proofs: Vec<ConsensusProof>) -> Result<[u8; 32], String>;
+}
+
+#[async_trait]
+impl BillingLedger for AssetBlockchainManager {
+    async fn append(&self, record: &BillingRecord, proofs: Vec<ConsensusProof>) -> Result<[u8; 32], String> {
+        let payload = serde_json::to_vec(record).map_err(|e| e.to_string())?;
+        let asset_record = HyperMeshAssetRecord::new(
+            record.asset_id.clone(),
+            AssetRecordType::Billing,
+            record.provider.clone(),
+            payload,
+            proofs,
+            (&record.privacy_level).into(),
+        );
+        self.add_asset_record(asset_record).await
+    }
+}
+
+/// What a provider earned over a time range
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProviderEarnings {
+    pub provider: String,
+    pub records: usize,
+    pub total_amount: u64,
+    pub usage: MeteredUsage,
+    pub by_asset_type: HashMap<AssetType, u64>,
+}
+
+/// A metered allocation with usage not yet billed
+#[derive(Clone, Debug)]
+struct OpenMeter {
+    consumer: String,
+    privacy_level: PrivacyLevel,
+    proof: ConsensusProof,
+    period_start: SystemTime,
+    usage: MeteredUsage,
+}
+
+/// Meters shared allocations on this node and settles them into billing records
+pub struct UsageMeter {
+    provider: String,
+    pricing: RwLock<Arc<dyn PricingPolicy>>,
+    ledger: Arc<dyn BillingLedger>,
+    open: RwLock<HashMap<AssetId, OpenMeter>>,
+    records: RwLock<Vec<BillingRecord>>,
+}
+
+impl UsageMeter {
+    /// Create a meter billing on behalf of `provider`
+    pub fn new(provider: impl Into<String>, pricing: Arc<dyn PricingPolicy>, ledger: Arc<dyn BillingLedger>) -> Self {
+        Self {
+            provider: provider.into(),
+            pricing: RwLock::new(pricing),
+            ledger,
+            open: RwLock::new(HashMap::new()),
+            records: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Replace the pricing policy for future settlements
+    pub async fn set_pricing(&self, pricing: Arc<dyn PricingPolicy>) {
+        *self.pricing.write().await = pricing;
+    }
+
+    /// Start metering an allocation; returns false if its privacy level is not billed
+    pub async fn open(&self, allocation: &AssetAllocation, consumer: &str, proof: ConsensusProof) -> bool {
+        let privacy_level = allocation.allocation_config.privacy_level.clone();
+        if !privacy_level.is_metered() {
+            return false;
+        }
+        self.open.write().await.insert(allocation.asset_id.clone(), OpenMeter {
+            consumer: consumer.to_string(),
+            privacy_level,
+            proof,
+            period_start: SystemTime::now(),
+            usage: MeteredUsage::default(),
+        });
+        tracing::debug!("Metering shared allocation {}", allocation.asset_id);
+        true
+    }
+
+    /// Assets currently being metered
+    pub async fn metered_assets(&self) -> Vec<AssetId> {
+        self.open.read().await.keys().cloned().collect()
+    }
+
+    /// Add a usage sample held for `interval`; samples for unmetered assets are ignored
+    pub async fn record(&self, asset_id: &AssetId, usage: &ResourceUsage, interval: Duration) {
+        if let Some(meter) = self.open.write().await.get_mut(asset_id) {
+            meter.usage += MeteredUsage::from_sample(usage, interval);
+        }
+    }
+
+    /// Bill usage accumulated since the last settlement
+    ///
+    /// Nothing is billed for a period without usage. If the ledger rejects the
+    /// record the usage stays on the meter and is billed by the next settlement.
+    pub async fn settle(&self, asset_id: &AssetId) -> AssetResult<Option<BillingRecord>> {
+        let meter = self.open.read().await.get(asset_id).cloned()
+            .ok_or_else(|| AssetError::AssetNotFound { asset_id: asset_id.to_string() })?;
+        if meter.usage.is_zero() {
+            return Ok(None);
+        }
+
+        let pricing = self.pricing.read().await.clone();
+        let mut record = BillingRecord {
+            record_id: Uuid::new_v4(),
+            asset_id: asset_id.clone(),
+            provider: self.provider.clone(),
+            consumer: meter.consumer.clone(),
+            privacy_level: meter.privacy_level.clone(),
+            period_start: meter.period_start,
+            period_end: SystemTime::now(),
+            usage: meter.usage,
+            amount: pricing.price(&asset_id.asset_type, &meter.privacy_level, &meter.usage),
+            pricing_policy: pricing.name().to_string(),
+            ledger_hash: None,
+        };
+
+        let hash = self.ledger.append(&record, vec![meter.proof.clone()]).await
+            .map_err(|e| AssetError::Internal(anyhow::anyhow!("billing ledger rejected record for {}: {}", asset_id, e)))?;
+        record.ledger_hash = Some(hash);
+
+        if let Some(open) = self.open.write().await.get_mut(asset_id) {
+            open.usage = open.usage - record.usage;
+            open.period_start = record.period_end;
+        }
+        self.records.write().await.push(record.clone());
+        tracing::info!("Billed {} tokens for {} to {}", record.amount, asset_id, record.consumer);
+        Ok(Some(record))
+    }
+
+    /// Settle every open meter, returning the records produced
+    pub async fn settle_all(&self) -> Vec<BillingRecord> {
+        let mut settled = Vec::new();
+        for asset_id in self.metered_assets().await {
+            match self.settle(&asset_id).await {
+                Ok(Some(record)) => settled.push(record),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to settle usage for {}: {}", asset_id, e),
+            }
+        }
+        settled
+    }
+
+    /// Settle and stop metering an allocation
+    pub async fn close(&self, asset_id: &AssetId) -> AssetResult<Option<BillingRecord>> {
+        if !self.open.read().await.contains_key(asset_id) {
+            return Ok(None);
+        }
+        let record = self.settle(asset_id).await?;
+        self.open.write().await.remove(asset_id);
+        Ok(record)
+    }
+
+    /// Billing records for one asset, oldest first
+    pub async fn records_for_asset(&self, asset_id: &AssetId) -> Vec<BillingRecord> {
+        self.records.read().await
+            .iter()
+            .filter(|record| &record.asset_id == asset_id)
+            .cloned()
+            .collect()
+    }
+
+    /// What `provider` earned from records ending within `[since, until)`
+    pub async fn earnings(&self, provider: &str, since: SystemTime, until: SystemTime) -> ProviderEarnings {
+        let records = self.records.read().await;
+        summarize(provider, records.iter().filter(|r| r.period_end >= since && r.period_end < until))
+    }
+}
+
+/// Fold billing records of one provider into earnings
+pub fn summarize<'a>(provider: &str, records: impl IntoIterator<Item = &'a BillingRecord>) -> ProviderEarnings {
+    let mut earnings = ProviderEarnings {
+        provider: provider.to_string(),
+        ..Default::default()
+    };
+    for record in records.into_iter().filter(|r| r.provider == provider) {
+        earnings.records += 1;
+        earnings.total_amount += record.amount;
+        earnings.usage += record.usage;
+        *earnings.by_asset_type.entry(record.asset_id.asset_type.clone()).or_default() += record.amount;
+    }
+    earnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::core::{CpuUsage, MemoryUsage};
+
+    fn sample() -> ResourceUsage {
+        ResourceUsage {
+            cpu_usage: Some(CpuUsage {
+                utilization_percent: 50.0,
+                frequency_mhz: 3000,
+                temperature_celsius: None,
+                active_cores: 4,
+            }),
+            gpu_usage: None,
+            memory_usage: Some(MemoryUsage {
+                used_bytes: 2 * 1024 * 1024 * 1024,
+                total_bytes: 8 * 1024 * 1024 * 1024,
+                cached_bytes: 0,
+                swap_used_bytes: 0,
+            }),
+            storage_usage: None,
+            network_usage: None,
+            measurement_timestamp: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_metering_and_pricing() {
+        let usage = MeteredUsage::from_sample(&sample(), Duration::from_secs(3600));
+        assert_eq!(usage.cpu_seconds, 7200.0);
+        assert_eq!(usage.memory_gb_hours, 2.0);
+        assert_eq!(usage.gpu_seconds, 0.0);
+
+        let pricing = FlatRatePricing::default();
+        assert_eq!(pricing.price(&AssetType::Cpu, &PrivacyLevel::FullPublic, &usage), 7300);
+        assert_eq!(pricing.price(&AssetType::Cpu, &PrivacyLevel::PublicNetwork, &usage), 5475);
+        assert!(!PrivacyLevel::P2P.is_metered());
+
+        let billed = |provider: &str, asset_type: AssetType, amount: u64| BillingRecord {
+            record_id: Uuid::new_v4(),
+            asset_id: AssetId::new(asset_type),
+            provider: provider.to_string(),
+            consumer: "consumer".to_string(),
+            privacy_level: PrivacyLevel::FullPublic,
+            period_start: SystemTime::now(),
+            period_end: SystemTime::now(),
+            usage,
+            amount,
+            pricing_policy: pricing.name().to_string(),
+            ledger_hash: None,
+        };
+        let records = vec![
+            billed("node-a", AssetType::Cpu, 100),
+            billed("node-a", AssetType::Memory, 40),
+            billed("node-b", AssetType::Cpu, 7),
+        ];
+        let earnings = summarize("node-a", &records);
+        assert_eq!(earnings.records, 2);
+        assert_eq!(earnings.total_amount, 140);
+        assert_eq!(earnings.by_asset_type[&AssetType::Memory], 40);
+        assert_eq!(earnings.usage.cpu_seconds, 14400.0);
+    }
+}
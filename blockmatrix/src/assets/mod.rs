@@ -10,12 +10,15 @@ pub mod privacy;
 pub mod multi_node;
 pub mod blockchain;
 pub mod matrix_blockchain;
+pub mod chain_repair;
 pub mod cross_chain;
+pub mod metering;
 
 // Re-export main types for easy access
 pub use core::{
     AssetManager, AssetId, AssetType, AssetAllocation,
     ConsensusProof, PrivacyLevel, AssetStatistics, AssetAdapter, AssetError,
+    ReservationToken,
 };
 
 pub use adapters::{
@@ -43,4 +46,12 @@ pub use blockchain::{
 pub use matrix_blockchain::{
     MatrixCoordinate, EntityBlockchain, EntityType,
     MatrixBlockchainManager,
+};
+
+pub use chain_repair::{
+    ChainBlocksHandler, ChainPeer, ChainViolation, QuarantinedBlock, RepairReport, StoqChainPeer,
+};
+
+pub use metering::{
+    BillingLedger, BillingRecord, FlatRatePricing, MeteredUsage, PricingPolicy, ProviderEarnings, UsageMeter,
 };
\ No newline at end of file
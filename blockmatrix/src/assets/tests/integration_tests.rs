@@ -59,6 +59,7 @@ fn create_cpu_allocation_request() -> AssetAllocationRequest {
                 min_frequency_mhz: Some(2400),
                 architecture: Some("x86_64".to_string()),
                 required_features: vec!["AVX2".to_string()],
+                numa_local: false,
             }),
             ..Default::default()
         },
@@ -80,6 +81,8 @@ fn create_gpu_allocation_request() -> AssetAllocationRequest {
                 min_memory_mb: Some(8192), // 8GB
                 compute_capability: Some("8.0".to_string()),
                 required_features: vec!["Nova".to_string()],
+                fraction: None,
+                partition_profile: None,
             }),
             ..Default::default()
         },
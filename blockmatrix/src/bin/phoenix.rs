@@ -0,0 +1,140 @@
+//! Phoenix CLI
+//!
+//! Developer tooling for Phoenix SDK applications. `phoenix up` deploys a
+//! multi-service application described in a compose file onto the local
+//! container runtime or a remote cluster; `phoenix serve` runs the cluster
+//! endpoint that remote deployments are sent to, on loopback by default.
+
+use std::net::Ipv6Addr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use tracing::{info, warn};
+
+use blockmatrix::container::{ContainerConfig, ContainerRuntime};
+use blockmatrix::runtime::phoenix::compose::{ComposeFile, ComposeServer, ComposeTarget, DEFAULT_COMPOSE_FILE};
+use blockmatrix::runtime::phoenix::PhoenixBuilder;
+
+#[derive(Parser)]
+#[command(name = "phoenix", version = "0.1.0", about = "Phoenix SDK developer tooling")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Deploy the services of a compose file
+    Up {
+        /// Compose file
+        #[arg(short, long, default_value = DEFAULT_COMPOSE_FILE)]
+        file: PathBuf,
+
+        /// Remote cluster endpoint (defaults to the local container runtime)
+        #[arg(long)]
+        cluster: Option<String>,
+
+        /// Print the deployment plan without deploying
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove an application from a remote cluster
+    Down {
+        /// Compose file
+        #[arg(short, long, default_value = DEFAULT_COMPOSE_FILE)]
+        file: PathBuf,
+
+        /// Remote cluster endpoint
+        #[arg(long)]
+        cluster: String,
+    },
+
+    /// Run a cluster endpoint that deploys compose applications on this node
+    Serve {
+        /// Port to listen on
+        #[arg(short, long)]
+        port: u16,
+
+        /// Address to listen on; requests are not authenticated, so only
+        /// bind beyond loopback on a trusted network
+        #[arg(long, default_value_t = Ipv6Addr::LOCALHOST)]
+        bind: Ipv6Addr,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .map_err(|_| anyhow::anyhow!("Failed to install crypto provider"))?;
+
+    match Cli::parse().command {
+        Command::Up { file, cluster, dry_run } => {
+            let plan = ComposeFile::load(&file)?.plan()?;
+
+            if dry_run {
+                println!("{}", serde_yaml::to_string(&plan)?);
+                return Ok(());
+            }
+
+            let target = match cluster {
+                Some(endpoint) => ComposeTarget::Remote(endpoint),
+                None => ComposeTarget::Local,
+            };
+            let deployment = plan.up(&target).await?;
+
+            match &target {
+                ComposeTarget::Remote(endpoint) => {
+                    println!("Application '{}' submitted to {}", plan.app, endpoint);
+                }
+                ComposeTarget::Local => {
+                    for (name, endpoint) in deployment.endpoints() {
+                        println!("{:<24} {}", name, endpoint);
+                    }
+                    println!("Application '{}' running locally, press Ctrl+C to stop", plan.app);
+
+                    tokio::signal::ctrl_c().await?;
+                    info!("Stopping application '{}'", plan.app);
+                    deployment.down().await?;
+                }
+            }
+        }
+
+        Command::Down { file, cluster } => {
+            let compose = ComposeFile::load(&file)?;
+            blockmatrix::runtime::phoenix::compose::ComposeDeployment::Remote {
+                app: compose.name.clone(),
+                endpoint: cluster.clone(),
+            }
+            .down()
+            .await?;
+            println!("Application '{}' removed from {}", compose.name, cluster);
+        }
+
+        Command::Serve { port, bind } => {
+            let runtime = Arc::new(ContainerRuntime::new(ContainerConfig::default()).await?);
+            if !bind.is_loopback() {
+                warn!("Compose endpoint on {} accepts unauthenticated requests", bind);
+            }
+            let transport = PhoenixBuilder::new("phoenix-compose-server")
+                .bind_address(bind)
+                .port(port)
+                .high_performance(false)
+                .build()
+                .await?;
+            println!("Serving compose deployments on {}", transport.local_addr()?);
+
+            let server = Arc::new(ComposeServer::new(runtime));
+            tokio::select! {
+                result = server.serve(transport) => result?,
+                _ = tokio::signal::ctrl_c() => info!("Stopping compose endpoint"),
+            }
+        }
+    }
+
+    Ok(())
+}
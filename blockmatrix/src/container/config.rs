@@ -42,6 +42,9 @@ pub struct RuntimeConfig {
     pub startup_timeout: Duration,
     /// Container shutdown timeout
     pub shutdown_timeout: Duration,
+    /// cgroup v2 directory container cpusets are written under, e.g. /sys/fs/cgroup/hypermesh
+    #[serde(default)]
+    pub cgroup_root: Option<PathBuf>,
 }
 
 /// Storage configuration
@@ -198,6 +201,7 @@ impl Default for RuntimeConfig {
             max_containers: 1000,
             startup_timeout: Duration::from_millis(100),
             shutdown_timeout: Duration::from_secs(5),
+            cgroup_root: None,
         }
     }
 }
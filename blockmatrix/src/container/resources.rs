@@ -5,6 +5,7 @@ use super::error::{Result, ContainerError};
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::{Duration, SystemTime, Instant};
 use tracing::{info, debug, warn, error};
 
@@ -55,6 +56,37 @@ pub struct ResourceQuota {
     pub disk_space_limit: Option<u64>,
     /// PID namespace limit
     pub pid_limit: Option<u32>,
+    /// CPUs the container is pinned to, as a list such as "0-3,8"
+    #[serde(default)]
+    pub cpuset_cpus: Option<String>,
+    /// NUMA nodes the container may allocate memory from
+    #[serde(default)]
+    pub cpuset_mems: Option<String>,
+}
+
+impl ResourceQuota {
+    /// Pin the container to the CPUs and memory nodes an asset allocation reserved
+    pub fn with_pinning(mut self, pinning: &crate::assets::core::NumaPinning) -> Self {
+        self.cpuset_cpus = Some(pinning.cpuset_cpus());
+        self.cpuset_mems = Some(pinning.cpuset_mems());
+        self
+    }
+
+    /// cgroup cpuset files and the values the quota sets them to
+    pub fn cpuset_files(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut files = Vec::new();
+        for (file, list) in [("cpuset.cpus", &self.cpuset_cpus), ("cpuset.mems", &self.cpuset_mems)] {
+            if let Some(list) = list {
+                let ids = crate::os_integration::parse_cpu_list(list)
+                    .filter(|ids| !ids.is_empty())
+                    .ok_or_else(|| ContainerError::Resource {
+                        message: format!("invalid {} list: {}", file, list),
+                    })?;
+                files.push((file, crate::os_integration::format_cpu_list(&ids)));
+            }
+        }
+        Ok(files)
+    }
 }
 
 /// Resource usage metrics
@@ -159,6 +191,8 @@ pub trait ResourceManager: Send + Sync {
 
 /// Cgroup-based resource manager implementation
 pub struct CgroupResourceManager {
+    /// cgroup v2 directory holding one child group per container; cpusets are only written when set
+    cgroup_root: Option<PathBuf>,
     quotas: std::sync::Arc<tokio::sync::RwLock<HashMap<ContainerId, ResourceQuota>>>,
     usage_history: std::sync::Arc<tokio::sync::RwLock<HashMap<ContainerId, Vec<ResourceUsage>>>>,
     monitoring_enabled: bool,
@@ -168,12 +202,19 @@ impl CgroupResourceManager {
     /// Create a new cgroup resource manager
     pub fn new() -> Self {
         Self {
+            cgroup_root: None,
             quotas: std::sync::Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             usage_history: std::sync::Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             monitoring_enabled: true,
         }
     }
     
+    /// Write cpusets into `<root>/<container id>/`, e.g. under /sys/fs/cgroup
+    pub fn with_cgroup_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.cgroup_root = Some(root.into());
+        self
+    }
+    
     /// Enable or disable monitoring
     pub fn set_monitoring(&mut self, enabled: bool) {
         self.monitoring_enabled = enabled;
@@ -271,6 +312,17 @@ impl CgroupResourceManager {
 #[async_trait]
 impl ResourceManager for CgroupResourceManager {
     async fn set_quota(&self, id: ContainerId, quota: ResourceQuota) -> Result<()> {
+        // Pin latency-sensitive containers to the cores and memory nodes they were allocated
+        let cpuset = quota.cpuset_files()?;
+        if let (Some(root), false) = (&self.cgroup_root, cpuset.is_empty()) {
+            let group = root.join(id.to_string());
+            tokio::fs::create_dir_all(&group).await?;
+            for (file, value) in &cpuset {
+                tokio::fs::write(group.join(file), value).await?;
+            }
+            debug!("Applied cpuset {:?} to {}", cpuset, group.display());
+        }
+        
         let mut quotas = self.quotas.write().await;
         quotas.insert(id, quota.clone());
         
@@ -422,6 +474,8 @@ impl Default for ResourceQuota {
             max_processes: Some(256),
             disk_space_limit: Some(10 * 1024 * 1024 * 1024), // 10GB default
             pid_limit: Some(1024),
+            cpuset_cpus: None,
+            cpuset_mems: None,
         }
     }
 }
\ No newline at end of file
@@ -15,6 +15,7 @@ use super::{
 };
 
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
@@ -132,7 +133,10 @@ impl ContainerRuntime {
         let image_manager = Arc::new(DefaultImageManager::new(&config.storage_usage)?);
         let network = Arc::new(DefaultContainerNetwork::new());
         let filesystem = Arc::new(DefaultContainerFilesystem::new(&config.storage_usage)?);
-        let resource_manager = Arc::new(CgroupResourceManager::new());
+        let resource_manager = match (&config.runtime.cgroup_root, config.runtime.cpu_isolation) {
+            (Some(root), true) => Arc::new(CgroupResourceManager::new().with_cgroup_root(root)),
+            _ => Arc::new(CgroupResourceManager::new()),
+        };
         let migration_manager = Arc::new(DefaultMigrationManager::new());
         let monitor = Arc::new(DefaultContainerMonitor::new());
         
@@ -412,6 +416,16 @@ impl ContainerRuntime {
             .ok_or_else(|| ContainerError::NotFound { id: id.to_string() })
     }
     
+    /// Forward a host port to a port of the container
+    pub async fn publish_port(&self, id: ContainerId, host_port: u16, container_port: u16) -> Result<()> {
+        self.network_usage.configure_port_forwarding(id, host_port, container_port).await
+    }
+    
+    /// Address of the container on the container network
+    pub async fn address(&self, id: ContainerId) -> Result<IpAddr> {
+        Ok(self.network_usage.get_namespace(id).await?.ip_address)
+    }
+    
     /// Get resource usage for container
     pub async fn get_usage(&self, id: ContainerId) -> Result<ResourceUsage> {
         self.resource_manager.get_usage(id).await
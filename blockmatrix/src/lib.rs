@@ -36,6 +36,8 @@ pub struct HyperMeshSystem {
     adapter_registry: Arc<AdapterRegistry>,
     /// Extension manager
     extension_manager: Arc<UnifiedExtensionManager>,
+    /// Entity chains kept by this node
+    matrix_manager: Arc<tokio::sync::RwLock<assets::matrix_blockchain::MatrixBlockchainManager>>,
 }
 
 impl HyperMeshSystem {
@@ -55,6 +57,10 @@ impl HyperMeshSystem {
         // Initialize extension manager
         let extension_manager = Arc::new(UnifiedExtensionManager::new());
 
+        let matrix_manager = Arc::new(tokio::sync::RwLock::new(
+            assets::matrix_blockchain::MatrixBlockchainManager::new(),
+        ));
+
         tracing::info!("HyperMesh Asset System initialized with all adapters");
 
         Ok(Self {
@@ -62,6 +68,7 @@ impl HyperMeshSystem {
             asset_manager,
             adapter_registry,
             extension_manager,
+            matrix_manager,
         })
     }
 
@@ -80,6 +87,11 @@ impl HyperMeshSystem {
         Arc::clone(&self.extension_manager)
     }
 
+    /// Get entity chain manager reference
+    pub fn matrix_manager(&self) -> Arc<tokio::sync::RwLock<assets::matrix_blockchain::MatrixBlockchainManager>> {
+        Arc::clone(&self.matrix_manager)
+    }
+
     /// Shutdown system cleanly
     pub async fn shutdown(&self) -> Result<()> {
         tracing::info!("HyperMesh shutdown initiated");
@@ -204,12 +216,9 @@ pub mod config {
         pub max_size: u64,
     }
 }
-// Runtime and monitoring as stubs for now
-/// Runtime stub
-pub mod runtime {
-    /// Placeholder
-    pub struct Runtime;
-}
+/// Runtime services and SDKs (Phoenix)
+pub mod runtime;
+// Monitoring as a stub for now
 /// Monitoring stub
 pub mod monitoring {
     /// Placeholder
@@ -24,6 +24,7 @@ pub mod container;
 pub mod integration;
 
 use anyhow::Result;
+use nexus_shared::{Validate, ValidationReport};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -309,6 +310,14 @@ pub struct OrchestrationEngine {
 impl OrchestrationEngine {
     /// Create a new orchestration engine
     pub async fn new(config: OrchestrationConfig) -> Result<Self> {
+        let report = config.validate_config();
+        for warning in report.warnings() {
+            tracing::warn!("Orchestration config {}", warning);
+        }
+        if !report.is_valid() {
+            anyhow::bail!(report.error_summary());
+        }
+
         // Initialize MFN bridge first
         let mfn_bridge = Arc::new(MfnBridge::new(config.mfn_integration.clone()).await?);
         
@@ -522,6 +531,92 @@ impl Default for OrchestrationConfig {
     }
 }
 
+impl Validate for OrchestrationConfig {
+    fn validate_config(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+
+        let breaker = &self.service_mesh.circuit_breaker;
+        if !(breaker.failure_threshold > 0.0 && breaker.failure_threshold <= 1.0) {
+            report.error(
+                "service_mesh.circuit_breaker.failure_threshold",
+                format!("{} is not a failure rate in (0, 1]", breaker.failure_threshold),
+            );
+        }
+        if breaker.recovery_timeout_ms == 0 {
+            report.error("service_mesh.circuit_breaker.recovery_timeout_ms", "must be greater than zero");
+        }
+
+        let lb = &self.service_mesh.load_balancing;
+        if lb.health_check_timeout_ms >= lb.health_check_interval_ms {
+            report.error(
+                "service_mesh.load_balancing.health_check_timeout_ms",
+                format!(
+                    "timeout ({}ms) must be shorter than the check interval ({}ms)",
+                    lb.health_check_timeout_ms, lb.health_check_interval_ms
+                ),
+            );
+        }
+
+        if self.container.max_scheduling_candidates == 0 {
+            report.error("container.max_scheduling_candidates", "must be at least 1");
+        }
+        if self.container.scheduling_timeout_ms == 0 {
+            report.error("container.scheduling_timeout_ms", "must be greater than zero");
+        } else if self.container.scheduling_timeout_ms > CONTAINER_SCHEDULING_LATENCY_MS {
+            report.warning(
+                "container.scheduling_timeout_ms",
+                format!(
+                    "{}ms exceeds the {}ms scheduling latency target",
+                    self.container.scheduling_timeout_ms, CONTAINER_SCHEDULING_LATENCY_MS
+                ),
+            );
+        }
+
+        if self.scaling.check_interval_ms == 0 {
+            report.error("scaling.check_interval_ms", "must be greater than zero");
+        }
+        if self.scaling.min_scaling_interval_ms < self.scaling.check_interval_ms {
+            report.error(
+                "scaling.min_scaling_interval_ms",
+                format!(
+                    "cooldown ({}ms) is shorter than the check interval ({}ms)",
+                    self.scaling.min_scaling_interval_ms, self.scaling.check_interval_ms
+                ),
+            );
+        }
+
+        if self.monitoring.metrics_interval_ms == 0 {
+            report.error("monitoring.metrics_interval_ms", "must be greater than zero");
+        }
+        if self.monitoring.alert_interval_ms < self.monitoring.metrics_interval_ms {
+            report.warning(
+                "monitoring.alert_interval_ms",
+                "alerts are evaluated more often than metrics are collected",
+            );
+        }
+
+        // Features that depend on an MFN layer which is switched off
+        let mfn = &self.mfn_integration;
+        let dependencies = [
+            ("service_mesh.alm_routing_enabled", self.service_mesh.alm_routing_enabled, "alm", mfn.alm_enabled),
+            ("service_mesh.cpe_discovery_enabled", self.service_mesh.cpe_discovery_enabled, "cpe", mfn.cpe_enabled),
+            ("container.dsr_scheduling_enabled", self.container.dsr_scheduling_enabled, "dsr", mfn.dsr_enabled),
+            ("container.ifr_resource_lookup_enabled", self.container.ifr_resource_lookup_enabled, "ifr", mfn.ifr_enabled),
+            ("scaling.cpe_predictive_enabled", self.scaling.cpe_predictive_enabled, "cpe", mfn.cpe_enabled),
+        ];
+        for (field, feature_enabled, layer, layer_enabled) in dependencies {
+            if feature_enabled && !layer_enabled {
+                report.warning(
+                    field,
+                    format!("has no effect while mfn_integration.{}_enabled is false", layer),
+                );
+            }
+        }
+
+        report
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -551,4 +646,17 @@ mod tests {
         assert!(config.mfn_integration.cpe_enabled);
         assert!(matches!(config.service_mesh.load_balancing.strategy, LoadBalancingStrategy::NeuralOptimal));
     }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(OrchestrationConfig::default().validate_config().is_valid());
+
+        let mut config = OrchestrationConfig::default();
+        config.service_mesh.load_balancing.health_check_timeout_ms = 10_000;
+        config.mfn_integration.alm_enabled = false;
+
+        let report = config.validate_config();
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.warnings().next().unwrap().field, "service_mesh.alm_routing_enabled");
+    }
 }
\ No newline at end of file
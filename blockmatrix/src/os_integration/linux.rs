@@ -259,6 +259,60 @@ impl LinuxAbstraction {
         })
     }
 
+    /// Parse NUMA nodes from /sys/devices/system/node
+    ///
+    /// Kernels built without NUMA have no node directories; the machine is
+    /// then reported as a single node.
+    fn parse_numa_nodes(&self) -> Result<NumaTopology> {
+        let node_root = Path::new("/sys/devices/system/node");
+        let mut nodes = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(node_root) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some(id) = name.strip_prefix("node").and_then(|id| id.parse::<u32>().ok()) else {
+                    continue;
+                };
+
+                let cpulist = fs::read_to_string(entry.path().join("cpulist"))
+                    .with_context(|| format!("Failed to read cpulist of NUMA node {}", id))?;
+                let cpus = parse_cpu_list(&cpulist)
+                    .with_context(|| format!("Invalid cpulist of NUMA node {}: {}", id, cpulist.trim()))?;
+                let meminfo = fs::read_to_string(entry.path().join("meminfo")).unwrap_or_default();
+                let (memory_total_bytes, memory_free_bytes) = Self::parse_node_meminfo(&meminfo);
+
+                nodes.push(NumaNode { id, cpus, memory_total_bytes, memory_free_bytes });
+            }
+        }
+
+        if nodes.is_empty() {
+            return Ok(NumaTopology::single_node(&self.parse_cpuinfo()?, &self.parse_meminfo()?));
+        }
+        nodes.sort_by_key(|node| node.id);
+        Ok(NumaTopology { nodes })
+    }
+
+    /// Total and free bytes from a node's meminfo ("Node 0 MemTotal: 16314128 kB")
+    fn parse_node_meminfo(content: &str) -> (u64, u64) {
+        let mut total_kb = 0u64;
+        let mut free_kb = 0u64;
+
+        for line in content.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 4 {
+                if let Ok(value) = parts[3].parse::<u64>() {
+                    match parts[2].trim_end_matches(':') {
+                        "MemTotal" => total_kb = value,
+                        "MemFree" => free_kb = value,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        (total_kb * 1024, free_kb * 1024)
+    }
+
     /// Detect storage from /proc/mounts and statvfs
     fn detect_storage_devices(&self) -> Result<Vec<StorageInfo>> {
         let mut devices = Vec::new();
@@ -452,6 +506,10 @@ impl OsAbstraction for LinuxAbstraction {
         self.detect_storage_devices()
     }
 
+    fn detect_numa_topology(&self) -> Result<NumaTopology> {
+        self.parse_numa_nodes()
+    }
+
     fn get_resource_usage(&self) -> Result<ResourceUsage> {
         self.get_current_resource_usage()
     }
@@ -673,6 +731,22 @@ mod tests {
         assert!(memory.usage_percent >= 0.0 && memory.usage_percent <= 100.0);
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_linux_numa_detection() {
+        let linux = LinuxAbstraction::new().expect("Failed to create Linux abstraction");
+        let topology = linux.detect_numa_topology().expect("Failed to detect NUMA topology");
+
+        assert!(!topology.nodes.is_empty(), "Should detect at least one NUMA node");
+        assert!(topology.nodes.iter().any(|node| !node.cpus.is_empty()), "Should place CPUs on nodes");
+
+        let (total, free) = LinuxAbstraction::parse_node_meminfo("Node 1 MemTotal: 2048 kB\nNode 1 MemFree: 1024 kB\n");
+        assert_eq!((total, free), (2048 * 1024, 1024 * 1024));
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(format_cpu_list(&[11, 0, 1, 2, 3, 8, 10]), "0-3,8,10-11");
+        assert_eq!(parse_cpu_list("3-1"), None);
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_linux_storage_detection() {
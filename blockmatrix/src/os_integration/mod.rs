@@ -55,6 +55,13 @@ pub trait OsAbstraction: Send + Sync {
     /// - macOS: Use diskutil list, df
     fn detect_storage(&self) -> Result<Vec<StorageInfo>>;
 
+    /// Detect NUMA topology (CPUs and memory of each node)
+    /// - Linux: Parse /sys/devices/system/node
+    /// - Other platforms: one node holding every CPU and all memory
+    fn detect_numa_topology(&self) -> Result<NumaTopology> {
+        Ok(NumaTopology::single_node(&self.detect_cpu()?, &self.detect_memory()?))
+    }
+
     /// Get current resource usage (real-time metrics)
     /// - Linux: Parse /proc/stat, /proc/meminfo, /proc/net/dev
     /// - Windows: Use Performance Counters API
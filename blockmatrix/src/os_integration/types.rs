@@ -91,6 +91,88 @@ pub struct MemoryInfo {
     pub swap_used_bytes: Option<u64>,
 }
 
+/// NUMA topology: which CPUs and how much memory belong to each node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumaTopology {
+    pub nodes: Vec<NumaNode>,
+}
+
+/// One NUMA node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumaNode {
+    /// Node ID, as in /sys/devices/system/node/node<id>
+    pub id: u32,
+
+    /// Logical CPUs local to this node
+    pub cpus: Vec<u32>,
+
+    /// Memory attached to this node in bytes
+    pub memory_total_bytes: u64,
+
+    /// Free memory on this node in bytes
+    pub memory_free_bytes: u64,
+}
+
+impl NumaTopology {
+    /// A machine without NUMA information: every CPU and all memory on node 0
+    pub fn single_node(cpu: &CpuInfo, memory: &MemoryInfo) -> Self {
+        Self {
+            nodes: vec![NumaNode {
+                id: 0,
+                cpus: (0..cpu.cores as u32).collect(),
+                memory_total_bytes: memory.total_bytes,
+                memory_free_bytes: memory.available_bytes,
+            }],
+        }
+    }
+
+    /// Node a logical CPU belongs to
+    pub fn node_of_cpu(&self, cpu: u32) -> Option<u32> {
+        self.nodes.iter().find(|node| node.cpus.contains(&cpu)).map(|node| node.id)
+    }
+
+    pub fn node(&self, id: u32) -> Option<&NumaNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+}
+
+/// Parse a kernel CPU or node list such as "0-3,8,10-11"
+pub fn parse_cpu_list(list: &str) -> Option<Vec<u32>> {
+    let mut ids = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end): (u32, u32) = (start.parse().ok()?, end.parse().ok()?);
+                if start > end {
+                    return None;
+                }
+                ids.extend(start..=end);
+            }
+            None => ids.push(part.parse().ok()?),
+        }
+    }
+    Some(ids)
+}
+
+/// Format CPU or node IDs as a kernel list, the format cpuset files take
+pub fn format_cpu_list(ids: &[u32]) -> String {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < ids.len() {
+        let start = ids[i];
+        while i + 1 < ids.len() && ids[i + 1] == ids[i] + 1 {
+            i += 1;
+        }
+        ranges.push(if ids[i] == start { start.to_string() } else { format!("{}-{}", start, ids[i]) });
+        i += 1;
+    }
+    ranges.join(",")
+}
+
 /// Storage Device Information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageInfo {
@@ -8,4 +8,6 @@ pub mod phoenix;
 pub use phoenix::{
     PhoenixTransport, PhoenixConfig, PhoenixConnection,
     PerformanceMetrics, PhoenixBuilder,
+    RpcClient, RpcError, RpcRouter, RpcService,
+    BackpressurePolicy, PubSubError, Subscription, SubscriptionConfig, TopicMessage,
 };
\ No newline at end of file
@@ -0,0 +1,773 @@
+//! Phoenix compose - multi-service application files
+//!
+//! A `phoenix-compose.yaml` file declares the services of an application,
+//! the connections between them and the resources they need. `phoenix up`
+//! turns it into a [`ComposePlan`] and deploys it either onto the local
+//! container runtime or onto a remote cluster by shipping the plan to a
+//! cluster endpoint, where a [`ComposeServer`] runs it. Either way every
+//! replica of every service is created and started as a container from the
+//! service's image, resources and environment.
+//!
+//! Replica `n` of a service publishes each of its ports on the host at
+//! `port + n`. A service is told where the services it connects to listen
+//! through `PHOENIX_<SERVICE>_ENDPOINTS`, the comma-separated container
+//! addresses of every replica and port of that service.
+//!
+//! ```yaml
+//! version: "1"
+//! name: shop
+//! services:
+//!   api:
+//!     image: shop/api:latest
+//!     replicas: 2
+//!     ports: [8080]
+//!     resources: { cpu: 0.5, memory: 256Mi }
+//!     connects_to: [db]
+//!   db:
+//!     image: postgres:16
+//! ```
+
+use super::{PhoenixBuilder, PhoenixTransport};
+use crate::container::{
+    ContainerConfig, ContainerId, ContainerRuntime, ContainerSpec, CreateOptions, ResourceRequirements,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Supported compose file versions
+pub const COMPOSE_VERSIONS: &[&str] = &["1"];
+
+/// Default compose file name looked up by `phoenix up`
+pub const DEFAULT_COMPOSE_FILE: &str = "phoenix-compose.yaml";
+
+/// Label carrying the application a container belongs to
+pub const APP_LABEL: &str = "phoenix.io/app";
+
+/// Label carrying the compose service a container belongs to
+pub const SERVICE_LABEL: &str = "phoenix.io/service";
+
+/// How long a container is given to stop when its application goes down
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parsed compose file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComposeFile {
+    /// File format version
+    #[serde(default = "default_version")]
+    pub version: String,
+    /// Application name, used as the prefix of every Phoenix app id
+    pub name: String,
+    /// Services keyed by name
+    pub services: BTreeMap<String, ComposeService>,
+}
+
+/// A single service in a compose file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComposeService {
+    /// Container image
+    pub image: String,
+    /// Number of replicas
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+    /// Ports exposed by the service
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// Resource requirements
+    #[serde(default)]
+    pub resources: ComposeResources,
+    /// Environment variables
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    /// Services this service opens connections to
+    #[serde(default)]
+    pub connects_to: Vec<String>,
+}
+
+/// Resource requirements of a compose service
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComposeResources {
+    /// CPU cores (e.g. 0.5)
+    pub cpu: Option<f64>,
+    /// Memory with unit suffix (e.g. 256Mi, 1Gi, 500M), or MiB without one
+    pub memory: Option<String>,
+}
+
+/// Where a compose application is deployed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposeTarget {
+    /// Container runtime on this machine
+    Local,
+    /// Remote cluster reachable at the given Phoenix endpoint
+    Remote(String),
+}
+
+/// Validated deployment plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposePlan {
+    /// Application name
+    pub app: String,
+    /// Services in dependency order (connection targets first)
+    pub services: Vec<PlannedService>,
+}
+
+/// A service ready for deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedService {
+    /// Service name
+    pub name: String,
+    /// Container image
+    pub image: String,
+    /// Number of replicas
+    pub replicas: u32,
+    /// Exposed ports
+    pub ports: Vec<u16>,
+    /// CPU cores
+    pub cpu_cores: f64,
+    /// Memory in MiB
+    pub memory_mb: u64,
+    /// Environment variables
+    pub environment: HashMap<String, String>,
+    /// Services this service connects to
+    pub connects_to: Vec<String>,
+}
+
+/// Control messages exchanged with a remote cluster endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ComposeRequest {
+    /// Deploy (or update) an application
+    Up(ComposePlan),
+    /// Remove an application
+    Down {
+        /// Application name
+        app: String,
+    },
+}
+
+/// Acknowledgement from a remote cluster endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposeResponse {
+    /// Whether the request was accepted
+    pub accepted: bool,
+    /// Human-readable detail
+    pub message: String,
+}
+
+/// A service running on the local container runtime
+pub struct LocalService {
+    /// Service name
+    pub name: String,
+    /// Loopback endpoints its ports are published on, for every replica
+    pub endpoints: Vec<SocketAddr>,
+}
+
+/// Containers running the replicas of one service
+#[derive(Debug, Clone)]
+pub struct ServiceContainers {
+    /// Service name
+    pub name: String,
+    /// One container per replica
+    pub containers: Vec<ContainerId>,
+}
+
+/// A deployed compose application
+pub enum ComposeDeployment {
+    /// Services running on the local container runtime
+    Local {
+        /// Application name
+        app: String,
+        /// Running services, in start order
+        services: Vec<LocalService>,
+        /// Runtime the service containers run on
+        runtime: Arc<ContainerRuntime>,
+        /// Containers of each service, in start order
+        containers: Vec<ServiceContainers>,
+    },
+    /// Application accepted by a remote cluster
+    Remote {
+        /// Application name
+        app: String,
+        /// Cluster endpoint
+        endpoint: String,
+    },
+}
+
+fn default_version() -> String {
+    "1".to_string()
+}
+
+fn default_replicas() -> u32 {
+    1
+}
+
+impl ComposeFile {
+    /// Load and parse a compose file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read compose file {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid compose file {}", path.display()))
+    }
+
+    /// Parse compose YAML
+    pub fn parse(contents: &str) -> Result<Self> {
+        let file: ComposeFile = serde_yaml::from_str(contents)?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    /// Check version, service references and connection cycles
+    pub fn validate(&self) -> Result<()> {
+        if !COMPOSE_VERSIONS.contains(&self.version.as_str()) {
+            bail!("Unsupported compose version '{}'", self.version);
+        }
+        if self.services.is_empty() {
+            bail!("Compose file defines no services");
+        }
+
+        for (name, service) in &self.services {
+            if service.image.is_empty() {
+                bail!("Service '{}' has no image", name);
+            }
+            if service.replicas == 0 {
+                bail!("Service '{}' must have at least one replica", name);
+            }
+            if let Some(memory) = &service.resources.memory {
+                parse_memory_mb(memory)
+                    .ok_or_else(|| anyhow!("Service '{}' has invalid memory '{}'", name, memory))?;
+            }
+            for port in &service.ports {
+                if *port == 0 || u32::from(*port) + service.replicas - 1 > u32::from(u16::MAX) {
+                    bail!("Service '{}' cannot publish port {} for {} replica(s)", name, port, service.replicas);
+                }
+            }
+            for target in &service.connects_to {
+                if target == name {
+                    bail!("Service '{}' connects to itself", name);
+                }
+                if !self.services.contains_key(target) {
+                    bail!("Service '{}' connects to unknown service '{}'", name, target);
+                }
+            }
+        }
+
+        self.start_order().map(|_| ())
+    }
+
+    /// Service names ordered so every connection target starts first
+    pub fn start_order(&self) -> Result<Vec<String>> {
+        let mut order = Vec::with_capacity(self.services.len());
+        let mut done = HashSet::new();
+        let mut visiting = HashSet::new();
+
+        fn visit(
+            name: &str,
+            file: &ComposeFile,
+            done: &mut HashSet<String>,
+            visiting: &mut HashSet<String>,
+            order: &mut Vec<String>,
+        ) -> Result<()> {
+            if done.contains(name) {
+                return Ok(());
+            }
+            if !visiting.insert(name.to_string()) {
+                bail!("Connection cycle involving service '{}'", name);
+            }
+            for target in &file.services[name].connects_to {
+                visit(target, file, done, visiting, order)?;
+            }
+            visiting.remove(name);
+            done.insert(name.to_string());
+            order.push(name.to_string());
+            Ok(())
+        }
+
+        for name in self.services.keys() {
+            visit(name, self, &mut done, &mut visiting, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Build the deployment plan
+    pub fn plan(&self) -> Result<ComposePlan> {
+        let services = self.start_order()?
+            .into_iter()
+            .map(|name| {
+                let service = &self.services[&name];
+                PlannedService {
+                    image: service.image.clone(),
+                    replicas: service.replicas,
+                    ports: service.ports.clone(),
+                    cpu_cores: service.resources.cpu.unwrap_or(0.1),
+                    memory_mb: service.resources.memory
+                        .as_deref()
+                        .and_then(parse_memory_mb)
+                        .unwrap_or(128),
+                    environment: service.environment.clone(),
+                    connects_to: service.connects_to.clone(),
+                    name,
+                }
+            })
+            .collect();
+
+        Ok(ComposePlan {
+            app: self.name.clone(),
+            services,
+        })
+    }
+}
+
+impl ComposePlan {
+    /// Deploy the plan onto the given target
+    pub async fn up(&self, target: &ComposeTarget) -> Result<ComposeDeployment> {
+        match target {
+            ComposeTarget::Local => self.up_local().await,
+            ComposeTarget::Remote(endpoint) => {
+                send_request(&self.app, endpoint, &ComposeRequest::Up(self.clone())).await?;
+                Ok(ComposeDeployment::Remote {
+                    app: self.app.clone(),
+                    endpoint: endpoint.clone(),
+                })
+            }
+        }
+    }
+
+    async fn up_local(&self) -> Result<ComposeDeployment> {
+        let runtime = Arc::new(
+            ContainerRuntime::new(ContainerConfig::default())
+                .await
+                .context("Failed to start the local container runtime")?,
+        );
+        let containers = self.deploy(&runtime).await?;
+
+        let services = self.services.iter()
+            .map(|planned| {
+                let endpoints: Vec<SocketAddr> = (0..planned.replicas)
+                    .flat_map(|replica| planned.published_ports(replica))
+                    .map(|(host_port, _)| SocketAddr::new(Ipv6Addr::LOCALHOST.into(), host_port))
+                    .collect();
+                info!("Service '{}' up with {} published endpoint(s)", planned.name, endpoints.len());
+                LocalService { name: planned.name.clone(), endpoints }
+            })
+            .collect();
+
+        Ok(ComposeDeployment::Local {
+            app: self.app.clone(),
+            services,
+            runtime,
+            containers,
+        })
+    }
+
+    /// Create and start a container for every replica, in start order
+    ///
+    /// If any replica fails, the containers already started are removed.
+    pub async fn deploy(&self, runtime: &ContainerRuntime) -> Result<Vec<ServiceContainers>> {
+        let mut deployed: Vec<ServiceContainers> = Vec::with_capacity(self.services.len());
+
+        for planned in &self.services {
+            // Targets were started earlier because the plan is in dependency order
+            let peers = match self.peer_environment(runtime, &deployed, planned).await {
+                Ok(peers) => peers,
+                Err(e) => {
+                    remove_containers(runtime, deployed).await;
+                    return Err(e);
+                }
+            };
+
+            let mut containers = Vec::with_capacity(planned.replicas as usize);
+            let mut started = Ok(());
+            for replica in 0..planned.replicas {
+                match start_replica(runtime, &self.app, planned, replica, &peers).await {
+                    Ok(id) => containers.push(id),
+                    Err(e) => {
+                        started = Err(e.context(format!("Failed to start replica {} of '{}'", replica, planned.name)));
+                        break;
+                    }
+                }
+            }
+            deployed.push(ServiceContainers { name: planned.name.clone(), containers });
+
+            if let Err(e) = started {
+                remove_containers(runtime, deployed).await;
+                return Err(e);
+            }
+            info!("Service '{}' running {} replica(s)", planned.name, planned.replicas);
+        }
+
+        Ok(deployed)
+    }
+
+    /// Endpoints of the services `planned` connects to, as its environment
+    async fn peer_environment(
+        &self,
+        runtime: &ContainerRuntime,
+        deployed: &[ServiceContainers],
+        planned: &PlannedService,
+    ) -> Result<HashMap<String, String>> {
+        let mut environment = HashMap::with_capacity(planned.connects_to.len());
+        for target in &planned.connects_to {
+            let service = self.services.iter()
+                .find(|s| &s.name == target)
+                .ok_or_else(|| anyhow!("Service '{}' connects to unknown service '{}'", planned.name, target))?;
+            let containers = deployed.iter()
+                .find(|s| &s.name == target)
+                .ok_or_else(|| anyhow!("Service '{}' was not started", target))?;
+
+            let mut endpoints = Vec::with_capacity(containers.containers.len() * service.ports.len());
+            for id in &containers.containers {
+                let address = runtime.address(id.clone())
+                    .await
+                    .with_context(|| format!("No address for container {} of '{}'", id, target))?;
+                endpoints.extend(service.ports.iter().map(|port| SocketAddr::new(address, *port).to_string()));
+            }
+            environment.insert(endpoints_variable(target), endpoints.join(","));
+        }
+        Ok(environment)
+    }
+}
+
+/// Environment variable listing the endpoints of a service
+pub fn endpoints_variable(service: &str) -> String {
+    format!("PHOENIX_{}_ENDPOINTS", service.to_uppercase().replace('-', "_"))
+}
+
+impl PlannedService {
+    /// Host and container port pairs one replica publishes
+    fn published_ports(&self, replica: u32) -> Vec<(u16, u16)> {
+        self.ports.iter()
+            .filter_map(|port| Some((u16::try_from(u32::from(*port) + replica).ok()?, *port)))
+            .collect()
+    }
+
+    /// Container spec of one replica, told where its peers listen
+    ///
+    /// Variables set in the compose file take precedence over peer endpoints.
+    fn container_spec(&self, app: &str, replica: u32, peers: &HashMap<String, String>) -> ContainerSpec {
+        let mut env = peers.clone();
+        env.extend(self.environment.iter().map(|(key, value)| (key.clone(), value.clone())));
+        ContainerSpec {
+            name: format!("{}-{}-{}", app, self.name, replica),
+            image: self.image.clone(),
+            command: None,
+            args: None,
+            env,
+            resources: ResourceRequirements {
+                cpu_millicores: (self.cpu_cores * 1000.0).round() as u64,
+                memory_bytes: self.memory_mb * 1024 * 1024,
+                storage_bytes: 0,
+                gpu_count: None,
+            },
+            limits: None,
+            labels: HashMap::from([
+                (APP_LABEL.to_string(), app.to_string()),
+                (SERVICE_LABEL.to_string(), self.name.clone()),
+            ]),
+        }
+    }
+}
+
+async fn start_replica(
+    runtime: &ContainerRuntime,
+    app: &str,
+    planned: &PlannedService,
+    replica: u32,
+    peers: &HashMap<String, String>,
+) -> Result<ContainerId> {
+    let spec = planned.container_spec(app, replica, peers);
+    let options = CreateOptions {
+        name: spec.name.clone(),
+        image: spec.image.clone(),
+        env: spec.env.clone(),
+        resources: spec.resources.clone(),
+    };
+    let handle = runtime.create(spec, options).await?;
+    let started = async {
+        for (host_port, container_port) in planned.published_ports(replica) {
+            runtime.publish_port(handle.id.clone(), host_port, container_port)
+                .await
+                .with_context(|| format!("Failed to publish port {} on {}", container_port, host_port))?;
+        }
+        runtime.start(handle.id.clone()).await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = started {
+        let _ = runtime.delete(handle.id.clone()).await;
+        return Err(e);
+    }
+    Ok(handle.id)
+}
+
+/// Stop and delete containers, dependents before the services they connect to
+async fn remove_containers(runtime: &ContainerRuntime, services: Vec<ServiceContainers>) {
+    for service in services.into_iter().rev() {
+        for id in service.containers {
+            if let Err(e) = runtime.stop(id.clone(), Some(STOP_TIMEOUT)).await {
+                warn!("Failed to stop container {} of '{}': {}", id, service.name, e);
+            }
+            if let Err(e) = runtime.delete(id.clone()).await {
+                warn!("Failed to delete container {} of '{}': {}", id, service.name, e);
+            }
+        }
+    }
+}
+
+impl ComposeDeployment {
+    /// Tear the application down
+    pub async fn down(self) -> Result<()> {
+        match self {
+            ComposeDeployment::Local { app, runtime, containers, .. } => {
+                remove_containers(&runtime, containers).await;
+                info!("Application '{}' stopped", app);
+                Ok(())
+            }
+            ComposeDeployment::Remote { app, endpoint } => {
+                send_request(&app, &endpoint, &ComposeRequest::Down { app: app.clone() }).await
+            }
+        }
+    }
+
+    /// Service names and published endpoints of a local deployment
+    pub fn endpoints(&self) -> Vec<(String, String)> {
+        match self {
+            ComposeDeployment::Local { services, .. } => services.iter()
+                .flat_map(|s| s.endpoints.iter().map(|endpoint| (s.name.clone(), endpoint.to_string())))
+                .collect(),
+            ComposeDeployment::Remote { .. } => Vec::new(),
+        }
+    }
+}
+
+async fn send_request(app: &str, endpoint: &str, request: &ComposeRequest) -> Result<()> {
+    let transport = PhoenixBuilder::new(format!("{}-compose", app))
+        .high_performance(false)
+        .build()
+        .await?;
+
+    let result = async {
+        let mut connection = transport.connect(endpoint).await?;
+        connection.send_data(&serde_json::to_vec(request)?).await?;
+        let reply = connection.receive_data().await?;
+        let response: ComposeResponse = serde_json::from_slice(&reply)
+            .context("Invalid response from cluster endpoint")?;
+        if !response.accepted {
+            bail!("Cluster rejected request: {}", response.message);
+        }
+        if !response.message.is_empty() {
+            warn!("Cluster: {}", response.message);
+        }
+        Ok(())
+    }
+    .await;
+
+    transport.shutdown().await;
+    result
+}
+
+/// Cluster endpoint that runs compose applications on a container runtime
+///
+/// `phoenix up --cluster` and `phoenix down --cluster` talk to this. An `Up`
+/// for an application that is already running replaces its containers.
+/// Requests are not authenticated, so the endpoint belongs on loopback or a
+/// trusted network only.
+pub struct ComposeServer {
+    runtime: Arc<ContainerRuntime>,
+    apps: Mutex<HashMap<String, Vec<ServiceContainers>>>,
+    /// Applications with a request in progress
+    busy: Mutex<HashSet<String>>,
+}
+
+impl ComposeServer {
+    pub fn new(runtime: Arc<ContainerRuntime>) -> Self {
+        Self {
+            runtime,
+            apps: Mutex::new(HashMap::new()),
+            busy: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Apply one request
+    ///
+    /// Requests for one application are refused while another is in
+    /// progress; requests for different applications run concurrently.
+    pub async fn handle(&self, request: ComposeRequest) -> ComposeResponse {
+        let app = match &request {
+            ComposeRequest::Up(plan) => plan.app.clone(),
+            ComposeRequest::Down { app } => app.clone(),
+        };
+        if !self.busy.lock().await.insert(app.clone()) {
+            return ComposeResponse {
+                accepted: false,
+                message: format!("Application '{}' has a request in progress", app),
+            };
+        }
+
+        let response = self.apply(request).await;
+        self.busy.lock().await.remove(&app);
+        response
+    }
+
+    async fn apply(&self, request: ComposeRequest) -> ComposeResponse {
+        match request {
+            ComposeRequest::Up(plan) => match plan.deploy(&self.runtime).await {
+                Ok(containers) => {
+                    let replaced = self.apps.lock().await.insert(plan.app.clone(), containers);
+                    if let Some(previous) = replaced {
+                        remove_containers(&self.runtime, previous).await;
+                    }
+                    info!("Application '{}' deployed", plan.app);
+                    ComposeResponse { accepted: true, message: String::new() }
+                }
+                Err(e) => ComposeResponse {
+                    accepted: false,
+                    message: format!("{:#}", e),
+                },
+            },
+            ComposeRequest::Down { app } => {
+                let removed = self.apps.lock().await.remove(&app);
+                match removed {
+                    Some(containers) => {
+                        remove_containers(&self.runtime, containers).await;
+                        info!("Application '{}' removed", app);
+                        ComposeResponse { accepted: true, message: String::new() }
+                    }
+                    None => ComposeResponse {
+                        accepted: false,
+                        message: format!("Application '{}' is not deployed", app),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Serve requests arriving on `transport` until it shuts down
+    pub async fn serve(self: Arc<Self>, transport: PhoenixTransport) -> Result<()> {
+        loop {
+            let mut connection = transport.accept().await?;
+            let server = Arc::clone(&self);
+            tokio::spawn(async move {
+                let result = async {
+                    let request: ComposeRequest = serde_json::from_slice(&connection.receive_data().await?)
+                        .context("Invalid compose request")?;
+                    let response = server.handle(request).await;
+                    connection.send_data(&serde_json::to_vec(&response)?).await
+                }
+                .await;
+                if let Err(e) = result {
+                    warn!("Compose request from {} failed: {:#}", connection.endpoint(), e);
+                }
+            });
+        }
+    }
+}
+
+/// Parse a memory size into MiB, rounding up
+///
+/// Accepts binary ("256Mi", "1.5Gi") and decimal ("500M", "2G") units; a
+/// bare number ("512") is MiB.
+fn parse_memory_mb(memory: &str) -> Option<u64> {
+    const MIB: f64 = 1024.0 * 1024.0;
+    const UNITS: &[(&str, f64)] = &[
+        ("Ki", 1024.0),
+        ("Mi", MIB),
+        ("Gi", MIB * 1024.0),
+        ("Ti", MIB * 1024.0 * 1024.0),
+        ("k", 1e3),
+        ("K", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+
+    let (number, unit) = UNITS.iter()
+        .find_map(|(suffix, unit)| memory.strip_suffix(suffix).map(|number| (number, *unit)))
+        .unwrap_or((memory, MIB));
+    let value: f64 = number.parse().ok()?;
+    if !value.is_finite() || value <= 0.0 {
+        return None;
+    }
+    Some((value * unit / MIB).ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSE: &str = r#"
+version: "1"
+name: shop
+services:
+  api:
+    image: shop/api:latest
+    replicas: 2
+    ports: [8080]
+    resources: { cpu: 0.5, memory: 256Mi }
+    connects_to: [db, cache]
+  cache:
+    image: redis:7
+    connects_to: [db]
+  db:
+    image: postgres:16
+    resources: { memory: 1Gi }
+"#;
+
+    #[test]
+    fn test_plan_orders_connection_targets_first() {
+        let plan = ComposeFile::parse(COMPOSE).unwrap().plan().unwrap();
+        let order: Vec<&str> = plan.services.iter().map(|s| s.name.as_str()).collect();
+
+        assert_eq!(order, vec!["db", "cache", "api"]);
+        assert_eq!(plan.services[0].memory_mb, 1024);
+        assert_eq!(plan.services[2].replicas, 2);
+        assert_eq!(plan.services[2].cpu_cores, 0.5);
+    }
+
+    #[test]
+    fn test_container_spec_carries_service_settings() {
+        let plan = ComposeFile::parse(COMPOSE).unwrap().plan().unwrap();
+        let peers = HashMap::from([(endpoints_variable("db"), "172.17.0.2:5432".to_string())]);
+        let spec = plan.services[2].container_spec(&plan.app, 1, &peers);
+
+        assert_eq!(spec.name, "shop-api-1");
+        assert_eq!(spec.image, "shop/api:latest");
+        assert_eq!(spec.resources.cpu_millicores, 500);
+        assert_eq!(spec.resources.memory_bytes, 256 * 1024 * 1024);
+        assert_eq!(spec.labels[SERVICE_LABEL], "api");
+        assert_eq!(spec.env["PHOENIX_DB_ENDPOINTS"], "172.17.0.2:5432");
+
+        // Each replica publishes its ports on a host port of its own
+        assert_eq!(plan.services[2].published_ports(0), vec![(8080, 8080)]);
+        assert_eq!(plan.services[2].published_ports(1), vec![(8081, 8080)]);
+    }
+
+    #[test]
+    fn test_memory_units() {
+        assert_eq!(parse_memory_mb("256Mi"), Some(256));
+        assert_eq!(parse_memory_mb("1.5Gi"), Some(1536));
+        assert_eq!(parse_memory_mb("64Ki"), Some(1));
+        assert_eq!(parse_memory_mb("500M"), Some(477));
+        assert_eq!(parse_memory_mb("2G"), Some(1908));
+        assert_eq!(parse_memory_mb("512"), Some(512));
+        assert_eq!(parse_memory_mb("0Mi"), None);
+        assert_eq!(parse_memory_mb("lots"), None);
+    }
+
+    #[test]
+    fn test_rejects_unknown_connection_and_cycles() {
+        let unknown = "name: a\nservices:\n  web:\n    image: x\n    connects_to: [db]\n";
+        assert!(ComposeFile::parse(unknown).is_err());
+
+        let cycle = "name: a\nservices:\n  a:\n    image: x\n    connects_to: [b]\n  b:\n    image: y\n    connects_to: [a]\n";
+        assert!(ComposeFile::parse(cycle).is_err());
+    }
+}
@@ -1,7 +1,8 @@
 //! Phoenix SDK API - Developer-friendly wrapper for STOQ high-performance transport
 //!
 //! Provides a simple, powerful API for Phoenix SDK developers with automatic
-//! certificate management, connection pooling, and performance monitoring.
+//! certificate management, connection pooling, performance monitoring,
+//! typed request/response RPC and pub/sub topics over connections.
 
 use stoq::transport::{StoqTransport, TransportConfig, Endpoint, Connection};
 use std::net::Ipv6Addr;
@@ -12,11 +13,19 @@ use tokio::sync::RwLock;
 use std::collections::HashMap;
 use tracing::{info, debug, warn};
 
+pub mod compose;
+pub mod pubsub;
+pub mod rpc;
+
+pub use pubsub::{BackpressurePolicy, PubSubError, Subscription, SubscriptionConfig, TopicMessage};
+pub use rpc::{RpcClient, RpcError, RpcRouter, RpcService};
+
 /// Phoenix SDK Transport - Simple, powerful, developer-focused
 pub struct PhoenixTransport {
     inner: Arc<StoqTransport>,
     app_id: String,
     connections: Arc<RwLock<HashMap<String, Arc<Connection>>>>,
+    topics: Arc<pubsub::TopicBroker>,
     config: PhoenixConfig,
 }
 
@@ -130,6 +139,7 @@ impl PhoenixTransport {
             inner,
             app_id: config.app_id.clone(),
             connections: Arc::new(RwLock::new(HashMap::new())),
+            topics: Arc::new(pubsub::TopicBroker::default()),
             config,
         })
     }
@@ -239,6 +249,11 @@ impl PhoenixTransport {
         Ok(())
     }
 
+    /// Local address the transport is bound to
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        self.inner.local_addr().context("Failed to read local address")
+    }
+
     /// Shutdown transport gracefully
     pub async fn shutdown(&self) {
         info!("Shutting down Phoenix transport for {}", self.app_id);
@@ -362,7 +377,7 @@ fn parse_endpoint(endpoint: &str) -> Result<(Ipv6Addr, u16)> {
             Ok((Ipv6Addr::LOCALHOST, port))
         } else {
             // No port specified, use default
-            Ok((Ipv6Addr::LOCALHOST, crate::DEFAULT_PORT))
+            Ok((Ipv6Addr::LOCALHOST, stoq::DEFAULT_PORT))
         }
     }
 }
@@ -438,7 +453,7 @@ mod tests {
         // No port (uses default)
         let (ip, port) = parse_endpoint("::1").unwrap();
         assert_eq!(ip, Ipv6Addr::LOCALHOST);
-        assert_eq!(port, crate::DEFAULT_PORT);
+        assert_eq!(port, stoq::DEFAULT_PORT);
     }
 
     #[tokio::test]
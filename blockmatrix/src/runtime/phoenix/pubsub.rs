@@ -0,0 +1,371 @@
+//! Phoenix pub/sub - topics with per-subscriber backpressure
+//!
+//! `phoenix.publish("orders", &order)` delivers a message to every
+//! subscriber of the topic, and `phoenix.subscribe("orders")` returns a
+//! [`Subscription`] that yields them in order. Each subscriber has its own
+//! bounded queue, so one slow consumer never holds up the others; what
+//! happens when a queue is full is the subscriber's [`BackpressurePolicy`].
+//!
+//! Topics span connections once both ends call
+//! [`PhoenixTransport::serve_topics`] on the connection. A remote
+//! subscription is then forwarded over STOQ streams, one per message, and
+//! the forwarder's own queue applies the same backpressure when the network
+//! falls behind.
+
+use super::{PhoenixConnection, PhoenixTransport};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use stoq::transport::Connection;
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+/// What a full subscriber queue does with a new message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackpressurePolicy {
+    /// Discard the oldest queued message to make room
+    #[default]
+    DropOldest,
+    /// Make the publisher wait for room
+    Block,
+    /// Fail the publish with [`PubSubError::SubscriberFull`]
+    Error,
+}
+
+/// Subscriber queue configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionConfig {
+    /// Messages queued before the backpressure policy applies
+    pub capacity: usize,
+    pub policy: BackpressurePolicy,
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            policy: BackpressurePolicy::default(),
+        }
+    }
+}
+
+/// Pub/sub failures callers may want to handle, carried inside `anyhow::Error`
+#[derive(Debug, thiserror::Error)]
+pub enum PubSubError {
+    #[error("{subscribers} subscriber(s) of {topic} are full")]
+    SubscriberFull { topic: String, subscribers: usize },
+}
+
+/// A message received on a topic
+#[derive(Debug, Clone)]
+pub struct TopicMessage {
+    pub topic: String,
+    pub payload: Bytes,
+}
+
+impl TopicMessage {
+    /// Decode the payload as published with `publish`
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.payload).context(format!("Malformed message on {}", self.topic))
+    }
+}
+
+/// Wire frame exchanged by `serve_topics`
+#[derive(Debug, Serialize, Deserialize)]
+enum TopicFrame {
+    Subscribe { topic: String, config: SubscriptionConfig },
+    Publish { topic: String, payload: Vec<u8> },
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    messages: VecDeque<Bytes>,
+    closed: bool,
+    dropped: u64,
+}
+
+#[derive(Debug)]
+struct SubscriberQueue {
+    id: u64,
+    config: SubscriptionConfig,
+    state: Mutex<QueueState>,
+    readable: Notify,
+    writable: Notify,
+}
+
+impl SubscriberQueue {
+    /// Queue a message, returning false if the queue is full under `Error`
+    async fn push(&self, payload: Bytes) -> bool {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return true;
+                }
+                if state.messages.len() < self.config.capacity {
+                    state.messages.push_back(payload);
+                    self.readable.notify_one();
+                    return true;
+                }
+                match self.config.policy {
+                    BackpressurePolicy::DropOldest => {
+                        state.messages.pop_front();
+                        state.messages.push_back(payload);
+                        state.dropped += 1;
+                        self.readable.notify_one();
+                        return true;
+                    }
+                    BackpressurePolicy::Error => return false,
+                    BackpressurePolicy::Block => self.writable.notified(),
+                }
+            };
+            wait.await;
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.readable.notify_one();
+        self.writable.notify_waiters();
+    }
+}
+
+/// Topic registry shared by a transport's publishers and subscribers
+#[derive(Debug, Default)]
+pub(super) struct TopicBroker {
+    topics: Mutex<HashMap<String, Vec<Arc<SubscriberQueue>>>>,
+    next_id: AtomicU64,
+}
+
+impl TopicBroker {
+    pub(super) fn subscribe(self: &Arc<Self>, topic: &str, config: SubscriptionConfig) -> Subscription {
+        let queue = Arc::new(SubscriberQueue {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            config,
+            state: Mutex::new(QueueState::default()),
+            readable: Notify::new(),
+            writable: Notify::new(),
+        });
+        self.topics.lock().unwrap().entry(topic.to_string()).or_default().push(queue.clone());
+        Subscription {
+            topic: topic.to_string(),
+            queue,
+            broker: Arc::downgrade(self),
+        }
+    }
+
+    /// Deliver to every subscriber of `topic`, returning how many received it
+    pub(super) async fn publish(&self, topic: &str, payload: Bytes) -> Result<usize> {
+        let subscribers = self.topics.lock().unwrap().get(topic).cloned().unwrap_or_default();
+        let mut full = 0;
+        for queue in &subscribers {
+            if !queue.push(payload.clone()).await {
+                full += 1;
+            }
+        }
+        if full > 0 {
+            return Err(PubSubError::SubscriberFull { topic: topic.to_string(), subscribers: full }.into());
+        }
+        Ok(subscribers.len())
+    }
+
+    fn unsubscribe(&self, topic: &str, id: u64) {
+        let mut topics = self.topics.lock().unwrap();
+        if let Some(queues) = topics.get_mut(topic) {
+            queues.retain(|queue| queue.id != id);
+            if queues.is_empty() {
+                topics.remove(topic);
+            }
+        }
+    }
+}
+
+/// A subscriber's queue on a topic; unsubscribes when dropped
+#[derive(Debug)]
+pub struct Subscription {
+    topic: String,
+    queue: Arc<SubscriberQueue>,
+    broker: Weak<TopicBroker>,
+}
+
+impl Subscription {
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Next message, or `None` once the subscription is closed
+    pub async fn recv(&mut self) -> Option<TopicMessage> {
+        loop {
+            {
+                let mut state = self.queue.state.lock().unwrap();
+                if let Some(payload) = state.messages.pop_front() {
+                    self.queue.writable.notify_one();
+                    return Some(TopicMessage { topic: self.topic.clone(), payload });
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.queue.readable.notified().await;
+        }
+    }
+
+    /// Next message decoded as `T`
+    pub async fn recv_as<T: DeserializeOwned>(&mut self) -> Option<Result<T>> {
+        self.recv().await.map(|message| message.decode())
+    }
+
+    /// Messages discarded under `DropOldest`
+    pub fn dropped(&self) -> u64 {
+        self.queue.state.lock().unwrap().dropped
+    }
+
+    /// Messages waiting to be received
+    pub fn pending(&self) -> usize {
+        self.queue.state.lock().unwrap().messages.len()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.queue.close();
+        if let Some(broker) = self.broker.upgrade() {
+            broker.unsubscribe(&self.topic, self.queue.id);
+        }
+    }
+}
+
+impl PhoenixTransport {
+    /// Publish a message to a topic, returning how many subscribers received it
+    ///
+    /// Waits for room in any full `Block` subscriber, and fails with
+    /// `PubSubError::SubscriberFull` if an `Error` subscriber is full; other
+    /// subscribers still receive the message.
+    pub async fn publish<T: Serialize>(&self, topic: &str, message: &T) -> Result<usize> {
+        let payload = serde_json::to_vec(message).context("Failed to encode message")?;
+        self.topics.publish(topic, Bytes::from(payload)).await
+    }
+
+    /// Subscribe to a topic with the default queue configuration
+    pub fn subscribe(&self, topic: &str) -> Subscription {
+        self.subscribe_with(topic, SubscriptionConfig::default())
+    }
+
+    pub fn subscribe_with(&self, topic: &str, config: SubscriptionConfig) -> Subscription {
+        self.topics.subscribe(topic, config)
+    }
+
+    /// Subscribe to a topic published on the other end of a connection
+    ///
+    /// Both ends must be serving topics on the connection. The remote end
+    /// queues for this subscriber with `config`.
+    pub async fn subscribe_remote(
+        &self,
+        connection: &PhoenixConnection,
+        topic: &str,
+        config: SubscriptionConfig,
+    ) -> Result<Subscription> {
+        let subscription = self.subscribe_with(topic, config.clone());
+        send_frame(&connection.inner, &TopicFrame::Subscribe { topic: topic.to_string(), config }).await?;
+        Ok(subscription)
+    }
+
+    /// Exchange topic traffic over a connection until it closes
+    ///
+    /// Remote subscriptions are forwarded from this transport's topics, and
+    /// messages the other end forwards are published here.
+    pub fn serve_topics(&self, connection: &PhoenixConnection) -> tokio::task::JoinHandle<()> {
+        let connection = connection.inner.clone();
+        let broker = self.topics.clone();
+        tokio::spawn(async move {
+            while connection.is_active() {
+                let mut stream = match connection.accept_stream().await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        debug!("Topic connection closed: {}", e);
+                        break;
+                    }
+                };
+                let frame = match stream.receive().await.map_err(anyhow::Error::from).and_then(|bytes| {
+                    bincode::deserialize::<TopicFrame>(&bytes).context("Malformed topic frame")
+                }) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        warn!("Failed to read topic frame: {}", e);
+                        continue;
+                    }
+                };
+                match frame {
+                    TopicFrame::Subscribe { topic, config } => {
+                        let subscription = broker.subscribe(&topic, config);
+                        tokio::spawn(forward(subscription, connection.clone()));
+                    }
+                    TopicFrame::Publish { topic, payload } => {
+                        if let Err(e) = broker.publish(&topic, Bytes::from(payload)).await {
+                            warn!("Failed to publish forwarded message: {}", e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Forward a local subscription to the other end of a connection
+async fn forward(mut subscription: Subscription, connection: Arc<Connection>) {
+    while let Some(message) = subscription.recv().await {
+        let frame = TopicFrame::Publish { topic: message.topic, payload: message.payload.to_vec() };
+        if let Err(e) = send_frame(&connection, &frame).await {
+            debug!("Stopped forwarding {}: {}", subscription.topic(), e);
+            break;
+        }
+    }
+}
+
+async fn send_frame(connection: &Connection, frame: &TopicFrame) -> Result<()> {
+    let bytes = bincode::serialize(frame).context("Failed to encode topic frame")?;
+    let mut stream = connection.open_stream().await.context("Failed to open topic stream")?;
+    stream.send(&bytes).await.context("Failed to send topic frame")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_backpressure_policies() {
+        let broker = Arc::new(TopicBroker::default());
+        let config = |policy| SubscriptionConfig { capacity: 2, policy };
+        let mut drop_oldest = broker.subscribe("events", config(BackpressurePolicy::DropOldest));
+        let mut blocking = broker.subscribe("events", config(BackpressurePolicy::Block));
+
+        for n in 1..=2u8 {
+            assert_eq!(broker.publish("events", Bytes::from(vec![n])).await.unwrap(), 2);
+        }
+
+        // The blocking subscriber holds up the third publish until it receives
+        let publisher = {
+            let broker = broker.clone();
+            tokio::spawn(async move { broker.publish("events", Bytes::from(vec![3])).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!publisher.is_finished());
+        assert_eq!(blocking.recv().await.unwrap().payload[..], [1]);
+        assert_eq!(publisher.await.unwrap().unwrap(), 2);
+
+        // The drop-oldest subscriber lost message 1 instead
+        assert_eq!(drop_oldest.dropped(), 1);
+        assert_eq!(drop_oldest.recv().await.unwrap().payload[..], [2]);
+
+        // An erroring subscriber fails the publish once full
+        drop(blocking);
+        let _erroring = broker.subscribe("events", SubscriptionConfig { capacity: 0, policy: BackpressurePolicy::Error });
+        let error = broker.publish("events", Bytes::from(vec![4])).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<PubSubError>(), Some(PubSubError::SubscriberFull { subscribers: 1, .. })));
+        assert_eq!(drop_oldest.pending(), 2);
+    }
+}
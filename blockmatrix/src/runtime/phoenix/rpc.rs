@@ -0,0 +1,301 @@
+//! Phoenix RPC - typed request/response calls over a connection
+//!
+//! A service is a type implementing [`RpcService`], which names the service
+//! and its request and response types. `connection.service::<MyApi>()`
+//! returns a client whose calls each run on their own stream of the
+//! connection, so any number of calls can be in flight at once without
+//! head-of-line blocking. Every call carries a request ID that the response
+//! must echo, and fails with [`RpcError::Timeout`] if no response arrives in
+//! time. The other side serves calls by registering handlers on an
+//! [`RpcRouter`].
+//!
+//! ```ignore
+//! struct Inventory;
+//! impl RpcService for Inventory {
+//!     const NAME: &'static str = "inventory";
+//!     type Request = StockQuery;
+//!     type Response = StockLevel;
+//! }
+//!
+//! let level = connection.service::<Inventory>().call(&query).await?;
+//! ```
+
+use super::PhoenixConnection;
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use stoq::transport::Connection;
+use tracing::{debug, warn};
+
+/// Timeout for calls made without an explicit one
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A typed RPC service
+pub trait RpcService: Send + Sync + 'static {
+    /// Name calls are routed by; unique per connection
+    const NAME: &'static str;
+    type Request: Serialize + DeserializeOwned + Send + Sync;
+    type Response: Serialize + DeserializeOwned + Send;
+}
+
+/// RPC failures callers may want to handle, carried inside `anyhow::Error`
+#[derive(Debug, thiserror::Error)]
+pub enum RpcError {
+    #[error("call {request_id} to {service} timed out after {timeout:?}")]
+    Timeout { service: String, request_id: u64, timeout: Duration },
+
+    #[error("{service} failed call {request_id}: {message}")]
+    Remote { service: String, request_id: u64, message: String },
+
+    #[error("response to call {request_id} carried request ID {received}")]
+    RequestIdMismatch { request_id: u64, received: u64 },
+}
+
+/// Request envelope
+#[derive(Debug, Serialize, Deserialize)]
+struct RpcRequest {
+    id: u64,
+    service: String,
+    payload: serde_json::Value,
+}
+
+/// Response envelope; `payload` or `error` is set
+#[derive(Debug, Serialize, Deserialize)]
+struct RpcResponse {
+    id: u64,
+    #[serde(default)]
+    payload: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Client for one service over a connection
+pub struct RpcClient<S: RpcService> {
+    connection: Arc<Connection>,
+    next_id: Arc<AtomicU64>,
+    timeout: Duration,
+    _service: PhantomData<fn() -> S>,
+}
+
+impl<S: RpcService> Clone for RpcClient<S> {
+    fn clone(&self) -> Self {
+        Self {
+            connection: self.connection.clone(),
+            next_id: self.next_id.clone(),
+            timeout: self.timeout,
+            _service: PhantomData,
+        }
+    }
+}
+
+impl<S: RpcService> RpcClient<S> {
+    fn new(connection: Arc<Connection>) -> Self {
+        Self {
+            connection,
+            next_id: Arc::new(AtomicU64::new(1)),
+            timeout: DEFAULT_CALL_TIMEOUT,
+            _service: PhantomData,
+        }
+    }
+
+    /// Set the timeout for calls made with `call`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Call the service with the client's timeout
+    pub async fn call(&self, request: &S::Request) -> Result<S::Response> {
+        self.call_with_timeout(request, self.timeout).await
+    }
+
+    /// Call the service, failing with `RpcError::Timeout` after `timeout`
+    pub async fn call_with_timeout(&self, request: &S::Request, timeout: Duration) -> Result<S::Response> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let envelope = RpcRequest {
+            id,
+            service: S::NAME.to_string(),
+            payload: serde_json::to_value(request).context("Failed to encode RPC request")?,
+        };
+
+        let response = tokio::time::timeout(timeout, self.exchange(&envelope))
+            .await
+            .map_err(|_| RpcError::Timeout { service: S::NAME.to_string(), request_id: id, timeout })??;
+
+        if response.id != id {
+            return Err(RpcError::RequestIdMismatch { request_id: id, received: response.id }.into());
+        }
+        if let Some(message) = response.error {
+            return Err(RpcError::Remote { service: S::NAME.to_string(), request_id: id, message }.into());
+        }
+        let payload = response.payload.unwrap_or(serde_json::Value::Null);
+        serde_json::from_value(payload).context(format!("Failed to decode response from {}", S::NAME))
+    }
+
+    async fn exchange(&self, envelope: &RpcRequest) -> Result<RpcResponse> {
+        let mut stream = self.connection.open_stream().await.context("Failed to open RPC stream")?;
+        stream.send(&serde_json::to_vec(envelope)?).await.context("Failed to send RPC request")?;
+        let bytes = stream.receive().await.context("Failed to receive RPC response")?;
+        serde_json::from_slice(&bytes).context("Malformed RPC response")
+    }
+}
+
+type BoxedHandler =
+    Arc<dyn Fn(serde_json::Value) -> Pin<Box<dyn Future<Output = Result<serde_json::Value>> + Send>> + Send + Sync>;
+
+/// Dispatches incoming calls to registered service handlers
+#[derive(Clone, Default)]
+pub struct RpcRouter {
+    handlers: HashMap<&'static str, BoxedHandler>,
+}
+
+impl RpcRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle calls to `S`, replacing any existing handler
+    pub fn register<S, F, Fut>(mut self, handler: F) -> Self
+    where
+        S: RpcService,
+        F: Fn(S::Request) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S::Response>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.handlers.insert(
+            S::NAME,
+            Arc::new(move |payload| {
+                let handler = handler.clone();
+                Box::pin(async move {
+                    let request: S::Request = serde_json::from_value(payload)
+                        .context(format!("Malformed request for {}", S::NAME))?;
+                    let response = handler(request).await?;
+                    Ok(serde_json::to_value(response)?)
+                })
+            }),
+        );
+        self
+    }
+
+    /// Serve calls arriving on the connection until it closes
+    ///
+    /// Each call is handled on its own task, so slow calls do not hold up others.
+    pub async fn serve(&self, connection: &PhoenixConnection) -> Result<()> {
+        let connection = connection.inner.clone();
+        while connection.is_active() {
+            let mut stream = match connection.accept_stream().await {
+                Ok(stream) => stream,
+                Err(e) if !connection.is_active() => {
+                    debug!("RPC connection closed: {}", e);
+                    break;
+                }
+                Err(e) => return Err(e.context("Failed to accept RPC stream")),
+            };
+
+            let router = self.clone();
+            tokio::spawn(async move {
+                let response = match stream.receive().await {
+                    Ok(bytes) => router.dispatch(&bytes).await,
+                    Err(e) => {
+                        warn!("Failed to read RPC request: {}", e);
+                        return;
+                    }
+                };
+                let sent = match serde_json::to_vec(&response) {
+                    Ok(bytes) => stream.send(&bytes).await,
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = sent {
+                    warn!("Failed to send response to call {}: {}", response.id, e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, bytes: &[u8]) -> RpcResponse {
+        let request: RpcRequest = match serde_json::from_slice(bytes) {
+            Ok(request) => request,
+            Err(e) => return RpcResponse { id: 0, payload: None, error: Some(format!("Malformed RPC request: {}", e)) },
+        };
+        let Some(handler) = self.handlers.get(request.service.as_str()) else {
+            return RpcResponse { id: request.id, payload: None, error: Some(format!("Unknown service {}", request.service)) };
+        };
+        match handler(request.payload).await {
+            Ok(payload) => RpcResponse { id: request.id, payload: Some(payload), error: None },
+            Err(e) => RpcResponse { id: request.id, payload: None, error: Some(format!("{:#}", e)) },
+        }
+    }
+}
+
+impl PhoenixConnection {
+    /// Typed client for a service on the other side of this connection
+    pub fn service<S: RpcService>(&self) -> RpcClient<S> {
+        RpcClient::new(self.inner.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::phoenix::PhoenixBuilder;
+    use std::net::Ipv6Addr;
+
+    struct Echo;
+
+    impl RpcService for Echo {
+        const NAME: &'static str = "echo";
+        type Request = (String, u64);
+        type Response = String;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_and_timeouts() {
+        let server = PhoenixBuilder::new("rpc-server")
+            .bind_address(Ipv6Addr::LOCALHOST)
+            .port(19393)
+            .high_performance(false)
+            .build()
+            .await
+            .unwrap();
+        let client = PhoenixBuilder::new("rpc-client").high_performance(false).build().await.unwrap();
+
+        let router = RpcRouter::new().register::<Echo, _, _>(|(text, delay_ms)| async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            if text.is_empty() {
+                anyhow::bail!("nothing to echo");
+            }
+            Ok(text.to_uppercase())
+        });
+        tokio::spawn(async move {
+            let connection = server.accept().await.unwrap();
+            let _ = router.serve(&connection).await;
+        });
+
+        let connection = client.connect("[::1]:19393").await.unwrap();
+        let echo = connection.service::<Echo>();
+
+        // A slow call does not hold up a fast one on the same connection
+        let (slow, fast) = tokio::join!(
+            echo.call(&("slow".to_string(), 300)),
+            tokio::time::timeout(Duration::from_millis(200), echo.call(&("fast".to_string(), 0))),
+        );
+        assert_eq!(slow.unwrap(), "SLOW");
+        assert_eq!(fast.unwrap().unwrap(), "FAST");
+
+        let error = echo.call(&(String::new(), 0)).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<RpcError>(), Some(RpcError::Remote { .. })));
+
+        let error = echo
+            .call_with_timeout(&("late".to_string(), 500), Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(error.downcast_ref::<RpcError>(), Some(RpcError::Timeout { .. })));
+    }
+}
@@ -0,0 +1,496 @@
+//! Execution Admission
+//!
+//! Before an asset runs on HyperMesh, the consuming account's entitlements
+//! are checked: how many executions may run at once, how many may start in
+//! a rolling day, and how many CPU cores, megabytes of memory and GPUs its
+//! running executions may hold together. An execution over a limit is
+//! rejected with the reason, or queued when the caller asks for deferred
+//! execution, and starts once the account has room again. A request that
+//! exceeds a limit on its own is always rejected, as waiting cannot help.
+
+use crate::assets::AssetPackage;
+use crate::hypermesh_integration::HyperMeshResource;
+use crate::AssetId;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Limits on one account's executions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionQuota {
+    /// Executions running at the same time
+    pub max_concurrent: u32,
+    /// Executions started in any 24 hours
+    #[serde(default)]
+    pub max_per_day: Option<u32>,
+    /// CPU cores held by running executions together
+    #[serde(default)]
+    pub max_cpu_cores: Option<u64>,
+    /// Memory held by running executions together (MB)
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+    /// GPUs held by running executions together
+    #[serde(default)]
+    pub max_gpus: Option<u64>,
+}
+
+impl Default for ExecutionQuota {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            max_per_day: None,
+            max_cpu_cores: None,
+            max_memory_mb: None,
+            max_gpus: None,
+        }
+    }
+}
+
+/// Execution quotas by account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionConfig {
+    /// Quota of accounts without their own
+    pub default_quota: ExecutionQuota,
+    /// Quotas of specific accounts
+    #[serde(default)]
+    pub accounts: HashMap<String, ExecutionQuota>,
+    /// Deferred executions an account may have waiting
+    pub max_queued_per_account: usize,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            default_quota: ExecutionQuota::default(),
+            accounts: HashMap::new(),
+            max_queued_per_account: 16,
+        }
+    }
+}
+
+impl AdmissionConfig {
+    /// Quota that applies to an account
+    pub fn quota_for(&self, account: &str) -> &ExecutionQuota {
+        self.accounts.get(account).unwrap_or(&self.default_quota)
+    }
+}
+
+/// Resources one execution holds while it runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionDemand {
+    /// CPU cores
+    pub cpu_cores: u64,
+    /// Memory (MB)
+    pub memory_mb: u64,
+    /// GPUs
+    pub gpus: u64,
+}
+
+impl ExecutionDemand {
+    /// Demand of the HyperMesh resources an asset maps to
+    pub fn of(resources: &[HyperMeshResource]) -> Self {
+        let mut demand = Self::default();
+        for resource in resources {
+            match resource {
+                HyperMeshResource::Cpu { cores, .. } => demand.cpu_cores += *cores as u64,
+                HyperMeshResource::Memory { size_mb, .. } => demand.memory_mb += size_mb,
+                HyperMeshResource::Gpu { .. } => demand.gpus += 1,
+                HyperMeshResource::Storage { .. } | HyperMeshResource::Network { .. } => {}
+            }
+        }
+        demand
+    }
+
+    fn add(&mut self, other: &ExecutionDemand) {
+        self.cpu_cores += other.cpu_cores;
+        self.memory_mb += other.memory_mb;
+        self.gpus += other.gpus;
+    }
+
+    /// The first resource over the quota's limit, as (name, amount, limit)
+    fn over(&self, quota: &ExecutionQuota) -> Option<(&'static str, u64, u64)> {
+        [
+            ("CPU cores", self.cpu_cores, quota.max_cpu_cores),
+            ("MB of memory", self.memory_mb, quota.max_memory_mb),
+            ("GPUs", self.gpus, quota.max_gpus),
+        ]
+        .into_iter()
+        .find_map(|(name, amount, limit)| limit.filter(|limit| amount > *limit).map(|limit| (name, amount, limit)))
+    }
+}
+
+/// Why an execution was not admitted
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AdmissionRejection {
+    /// The account runs as many executions as it may
+    #[error("account '{account}' is running {running} of {limit} allowed concurrent executions")]
+    ConcurrencyLimit {
+        /// Account
+        account: String,
+        /// Concurrent executions allowed
+        limit: u32,
+        /// Executions running
+        running: u32,
+    },
+    /// The account started as many executions as it may in a day
+    #[error("account '{account}' started its {limit} executions for the day; next one allowed at {retry_at}")]
+    DailyQuotaExhausted {
+        /// Account
+        account: String,
+        /// Executions allowed per day
+        limit: u32,
+        /// When the oldest counted execution leaves the window
+        retry_at: DateTime<Utc>,
+    },
+    /// Running executions already hold too much of a resource
+    #[error("account '{account}' would hold {requested} {resource}, over its limit of {limit}")]
+    ResourceLimit {
+        /// Account
+        account: String,
+        /// Resource name
+        resource: String,
+        /// Amount held with this execution
+        requested: u64,
+        /// Amount allowed
+        limit: u64,
+    },
+    /// The execution alone needs more than the account may ever hold
+    #[error("execution needs {requested} {resource}, more than account '{account}' may hold ({limit})")]
+    ExceedsQuota {
+        /// Account
+        account: String,
+        /// Resource name
+        resource: String,
+        /// Amount the execution needs
+        requested: u64,
+        /// Amount allowed
+        limit: u64,
+    },
+    /// The account has as many deferred executions as it may
+    #[error("account '{account}' already has {limit} executions queued")]
+    QueueFull {
+        /// Account
+        account: String,
+        /// Queued executions allowed
+        limit: usize,
+    },
+}
+
+impl AdmissionRejection {
+    /// Whether the same request may be admitted later
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, AdmissionRejection::ExceedsQuota { .. })
+    }
+}
+
+/// What to do with an execution over its account's limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdmissionMode {
+    /// Reject it with the reason
+    #[default]
+    Reject,
+    /// Queue it to start when the account has room
+    Queue,
+}
+
+/// Slot held for an admitted execution until it starts or is given back
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reservation(String);
+
+/// An execution waiting for its account to have room
+#[derive(Debug, Clone)]
+pub struct QueuedExecution {
+    /// Ticket to follow the execution by
+    pub ticket: String,
+    /// Consuming account
+    pub account: String,
+    /// Asset to execute
+    pub asset_id: AssetId,
+    /// Package of the asset
+    pub package: AssetPackage,
+    /// Resources it will hold
+    pub demand: ExecutionDemand,
+    /// When it was queued
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Where a deferred execution is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TicketStatus {
+    /// Waiting, behind `position` other executions
+    Queued {
+        /// Executions ahead of it
+        position: usize,
+    },
+    /// Started under this execution ID
+    Started {
+        /// HyperMesh execution ID
+        execution_id: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct AccountState {
+    /// Demand of admitted executions, by reservation or execution ID
+    running: HashMap<String, ExecutionDemand>,
+    /// Start times within the last day, oldest first
+    started: VecDeque<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct AdmissionState {
+    accounts: HashMap<String, AccountState>,
+    /// Account of each admitted execution
+    owners: HashMap<String, String>,
+    queue: VecDeque<QueuedExecution>,
+    /// Execution each started ticket runs as
+    started_tickets: HashMap<String, String>,
+}
+
+/// Enforces execution quotas and holds deferred executions
+#[derive(Debug)]
+pub struct AdmissionController {
+    config: AdmissionConfig,
+    state: Mutex<AdmissionState>,
+}
+
+impl AdmissionController {
+    /// Create a controller enforcing the given quotas
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(AdmissionState::default()),
+        }
+    }
+
+    /// Reserve a slot for an execution, or say why there is none
+    pub fn try_admit(&self, account: &str, demand: ExecutionDemand, now: DateTime<Utc>) -> Result<Reservation, AdmissionRejection> {
+        let mut state = self.state.lock();
+        self.check(&mut state, account, &demand, now)?;
+        Ok(Self::reserve(&mut state, account, demand, now))
+    }
+
+    /// Queue an execution to start when its account has room
+    ///
+    /// Returns the ticket and the number of executions ahead of it.
+    pub fn enqueue(
+        &self,
+        account: &str,
+        asset_id: AssetId,
+        package: AssetPackage,
+        demand: ExecutionDemand,
+        now: DateTime<Utc>,
+    ) -> Result<(String, usize), AdmissionRejection> {
+        let quota = self.config.quota_for(account);
+        if let Some((resource, requested, limit)) = demand.over(quota) {
+            return Err(AdmissionRejection::ExceedsQuota {
+                account: account.to_string(),
+                resource: resource.to_string(),
+                requested,
+                limit,
+            });
+        }
+
+        let mut state = self.state.lock();
+        let limit = self.config.max_queued_per_account;
+        if state.queue.iter().filter(|queued| queued.account == account).count() >= limit {
+            return Err(AdmissionRejection::QueueFull { account: account.to_string(), limit });
+        }
+        let ticket = uuid::Uuid::new_v4().to_string();
+        state.queue.push_back(QueuedExecution {
+            ticket: ticket.clone(),
+            account: account.to_string(),
+            asset_id,
+            package,
+            demand,
+            queued_at: now,
+        });
+        Ok((ticket, state.queue.len() - 1))
+    }
+
+    /// Take the first queued execution whose account now has room
+    ///
+    /// Each account's executions start in the order they were queued.
+    pub fn next_ready(&self, now: DateTime<Utc>) -> Option<(QueuedExecution, Reservation)> {
+        let mut state = self.state.lock();
+        let mut blocked: Vec<String> = Vec::new();
+        for i in 0..state.queue.len() {
+            let (account, demand) = (state.queue[i].account.clone(), state.queue[i].demand);
+            if blocked.contains(&account) {
+                continue;
+            }
+            if self.check(&mut state, &account, &demand, now).is_err() {
+                blocked.push(account);
+                continue;
+            }
+            let queued = state.queue.remove(i)?;
+            let reservation = Self::reserve(&mut state, &account, demand, now);
+            return Some((queued, reservation));
+        }
+        None
+    }
+
+    /// Move a reservation to the execution it started, and its ticket if queued
+    pub fn bind(&self, reservation: &Reservation, execution_id: &str, ticket: Option<&str>) {
+        let mut state = self.state.lock();
+        let Some(account) = state.owners.remove(&reservation.0) else {
+            return;
+        };
+        if let Some(demand) = state.accounts.get_mut(&account).and_then(|a| a.running.remove(&reservation.0)) {
+            state.accounts.entry(account.clone()).or_default().running.insert(execution_id.to_string(), demand);
+        }
+        state.owners.insert(execution_id.to_string(), account);
+        if let Some(ticket) = ticket {
+            state.started_tickets.insert(ticket.to_string(), execution_id.to_string());
+        }
+    }
+
+    /// Give back a slot whose execution did not start
+    ///
+    /// The start still counts towards the daily quota.
+    pub fn cancel(&self, reservation: &Reservation) {
+        self.release(&reservation.0);
+    }
+
+    /// Free the slot of a finished execution; returns its account
+    pub fn finish(&self, execution_id: &str) -> Option<String> {
+        let account = self.release(execution_id);
+        self.state.lock().started_tickets.retain(|_, id| id != execution_id);
+        account
+    }
+
+    /// Where a deferred execution is; `None` for unknown or finished tickets
+    pub fn ticket_status(&self, ticket: &str) -> Option<TicketStatus> {
+        let state = self.state.lock();
+        if let Some(execution_id) = state.started_tickets.get(ticket) {
+            return Some(TicketStatus::Started { execution_id: execution_id.clone() });
+        }
+        state
+            .queue
+            .iter()
+            .position(|queued| queued.ticket == ticket)
+            .map(|position| TicketStatus::Queued { position })
+    }
+
+    fn release(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock();
+        let account = state.owners.remove(key)?;
+        if let Some(account_state) = state.accounts.get_mut(&account) {
+            account_state.running.remove(key);
+        }
+        Some(account)
+    }
+
+    fn check(&self, state: &mut AdmissionState, account: &str, demand: &ExecutionDemand, now: DateTime<Utc>) -> Result<(), AdmissionRejection> {
+        let quota = self.config.quota_for(account);
+        if let Some((resource, requested, limit)) = demand.over(quota) {
+            return Err(AdmissionRejection::ExceedsQuota {
+                account: account.to_string(),
+                resource: resource.to_string(),
+                requested,
+                limit,
+            });
+        }
+
+        let account_state = state.accounts.entry(account.to_string()).or_default();
+        while account_state.started.front().is_some_and(|at| now - *at >= Duration::days(1)) {
+            account_state.started.pop_front();
+        }
+
+        let running = account_state.running.len() as u32;
+        if running >= quota.max_concurrent {
+            return Err(AdmissionRejection::ConcurrencyLimit {
+                account: account.to_string(),
+                limit: quota.max_concurrent,
+                running,
+            });
+        }
+        if let Some(limit) = quota.max_per_day {
+            if account_state.started.len() as u32 >= limit {
+                let oldest = account_state.started.front().copied().unwrap_or(now);
+                return Err(AdmissionRejection::DailyQuotaExhausted {
+                    account: account.to_string(),
+                    limit,
+                    retry_at: oldest + Duration::days(1),
+                });
+            }
+        }
+
+        let mut held = *demand;
+        for running in account_state.running.values() {
+            held.add(running);
+        }
+        if let Some((resource, requested, limit)) = held.over(quota) {
+            return Err(AdmissionRejection::ResourceLimit {
+                account: account.to_string(),
+                resource: resource.to_string(),
+                requested,
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    fn reserve(state: &mut AdmissionState, account: &str, demand: ExecutionDemand, now: DateTime<Utc>) -> Reservation {
+        let key = uuid::Uuid::new_v4().to_string();
+        let account_state = state.accounts.entry(account.to_string()).or_default();
+        account_state.running.insert(key.clone(), demand);
+        account_state.started.push_back(now);
+        state.owners.insert(key.clone(), account.to_string());
+        Reservation(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cores(cpu_cores: u64) -> ExecutionDemand {
+        ExecutionDemand { cpu_cores, ..Default::default() }
+    }
+
+    #[test]
+    fn test_admission_limits() {
+        let config = AdmissionConfig {
+            default_quota: ExecutionQuota {
+                max_concurrent: 2,
+                max_per_day: Some(3),
+                max_cpu_cores: Some(8),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let admission = AdmissionController::new(config);
+        let now = Utc::now();
+
+        let first = admission.try_admit("alice", cores(4), now).unwrap();
+        admission.bind(&first, "exec-1", None);
+
+        // The second would hold 10 of 8 cores; on its own it can never fit
+        assert!(matches!(
+            admission.try_admit("alice", cores(6), now),
+            Err(AdmissionRejection::ResourceLimit { requested: 10, limit: 8, .. })
+        ));
+        let too_big = admission.try_admit("alice", cores(16), now).unwrap_err();
+        assert!(!too_big.is_retryable());
+
+        admission.try_admit("alice", cores(2), now).unwrap();
+        assert!(matches!(
+            admission.try_admit("alice", cores(1), now),
+            Err(AdmissionRejection::ConcurrencyLimit { running: 2, limit: 2, .. })
+        ));
+        // Other accounts have their own quota
+        admission.try_admit("bob", cores(8), now).unwrap();
+
+        // Finishing frees a slot, but the day's third start is the last
+        assert_eq!(admission.finish("exec-1").as_deref(), Some("alice"));
+        let third = admission.try_admit("alice", cores(1), now).unwrap();
+        admission.cancel(&third);
+        assert!(matches!(
+            admission.try_admit("alice", cores(1), now + Duration::hours(1)),
+            Err(AdmissionRejection::DailyQuotaExhausted { limit: 3, .. })
+        ));
+        admission.try_admit("alice", cores(1), now + Duration::days(1)).unwrap();
+    }
+}
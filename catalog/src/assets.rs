@@ -10,6 +10,7 @@ use std::path::Path;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use base64::Engine;
+use crate::provenance::PackageSignature;
 
 /// Asset package unique identifier
 pub type AssetPackageId = Uuid;
@@ -25,6 +26,9 @@ pub struct AssetPackage {
     pub validation: AssetValidationStatus,
     /// Computed package hash for integrity verification
     pub package_hash: String,
+    /// Publisher signatures over the package hash
+    #[serde(default)]
+    pub signatures: Vec<PackageSignature>,
     /// Package creation timestamp
     pub created_at: DateTime<Utc>,
     /// Last modification timestamp
@@ -534,7 +538,59 @@ impl AssetPackage {
         let yaml_content = tokio::fs::read_to_string(yaml_path).await?;
         let spec: AssetSpec = serde_yaml::from_str(&yaml_content)?;
         
-        let mut package = Self {
+        let mut package = Self::unloaded(spec);
+        
+        // Load content files
+        package.load_content().await?;
+        
+        // Compute package hash
+        package.compute_hash()?;
+        
+        Ok(package)
+    }
+    
+    /// Create an asset package from a specification and in-memory files
+    ///
+    /// Content paths are looked up in `files` instead of on disk; missing
+    /// files are recorded as validation errors just as `from_yaml` does.
+    pub fn from_rendered(spec: AssetSpec, files: &HashMap<String, String>) -> Result<Self> {
+        let mut package = Self::unloaded(spec);
+        
+        let content = package.spec.spec.content.clone();
+        let mut paths: Vec<(&String, ErrorSeverity)> = content.files.iter().map(|f| (f, ErrorSeverity::Error)).collect();
+        if !content.main.is_empty() {
+            paths.insert(0, (&content.main, ErrorSeverity::Critical));
+        }
+        for (path, severity) in paths {
+            let Some(text) = files.get(path) else {
+                package.validation.errors.push(ValidationError {
+                    code: if *path == content.main { "MAIN_FILE_NOT_FOUND" } else { "FILE_NOT_FOUND" }.to_string(),
+                    message: format!("File '{}' was not rendered by the template", path),
+                    file: Some(path.clone()),
+                    line: None,
+                    column: None,
+                    severity,
+                });
+                continue;
+            };
+            if *path == content.main {
+                package.content.main_content = text.clone();
+            } else {
+                package.content.file_contents.insert(path.clone(), text.clone());
+            }
+        }
+        if let Some(inline_content) = &content.inline {
+            package.content.main_content = inline_content.clone();
+        }
+        package.decode_binary_assets();
+        
+        package.compute_hash()?;
+        Ok(package)
+    }
+    
+    /// Package for a specification with no content loaded yet
+    fn unloaded(spec: AssetSpec) -> Self {
+        Self {
             spec: spec.clone(),
             content: AssetContentResolved {
                 main_content: String::new(),
@@ -564,17 +620,10 @@ impl AssetPackage {
                 },
             },
             package_hash: String::new(),
+            signatures: Vec::new(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
-        };
-        
-        // Load content files
-        package.load_content().await?;
-        
-        // Compute package hash
-        package.compute_hash()?;
-        
-        Ok(package)
+        }
     }
     
     /// Load all content files referenced in the asset specification
@@ -622,7 +671,13 @@ impl AssetPackage {
             self.content.main_content = inline_content.clone();
         }
         
-        // Process binary assets
+        self.decode_binary_assets();
+        
+        Ok(())
+    }
+    
+    /// Decode the base64 binary assets embedded in the specification
+    fn decode_binary_assets(&mut self) {
         for binary_asset in &self.spec.spec.content.binary {
             match base64::engine::general_purpose::STANDARD.decode(&binary_asset.content) {
                 Ok(decoded) => {
@@ -640,44 +695,50 @@ impl AssetPackage {
                 }
             }
         }
-        
-        Ok(())
     }
     
     /// Compute package hash for integrity verification
-    fn compute_hash(&mut self) -> Result<()> {
+    pub(crate) fn compute_hash(&mut self) -> Result<()> {
+        self.package_hash = self.content_hash()?;
+        Ok(())
+    }
+    
+    /// Hash of the specification and content
+    ///
+    /// Maps are hashed in key order, so a package hashes the same after it
+    /// has been serialized and fetched from another node.
+    pub(crate) fn content_hash(&self) -> Result<String> {
         use sha2::{Sha256, Digest};
         
         let mut hasher = Sha256::new();
         
         // Hash the specification
-        let spec_json = serde_json::to_string(&self.spec)?;
-        hasher.update(spec_json.as_bytes());
+        let spec_json = serde_json::to_vec(&canonical_json(serde_json::to_value(&self.spec)?))?;
+        hasher.update(&spec_json);
         
         // Hash all content
         hasher.update(self.content.main_content.as_bytes());
         
-        for (path, content) in &self.content.file_contents {
+        let mut files: Vec<_> = self.content.file_contents.iter().collect();
+        files.sort();
+        for (path, content) in files {
             hasher.update(path.as_bytes());
             hasher.update(content.as_bytes());
         }
         
-        for (name, content) in &self.content.binary_contents {
+        let mut binaries: Vec<_> = self.content.binary_contents.iter().collect();
+        binaries.sort();
+        for (name, content) in binaries {
             hasher.update(name.as_bytes());
             hasher.update(content);
         }
         
-        let result = hasher.finalize();
-        self.package_hash = hex::encode(result);
-        
-        Ok(())
+        Ok(hex::encode(hasher.finalize()))
     }
     
     /// Verify package integrity against stored hash
     pub fn verify_integrity(&self) -> Result<bool> {
-        let mut temp_package = self.clone();
-        temp_package.compute_hash()?;
-        Ok(temp_package.package_hash == self.package_hash)
+        Ok(self.content_hash()? == self.package_hash)
     }
     
     /// Get asset package unique identifier
@@ -711,6 +772,19 @@ impl AssetPackage {
     }
 }
 
+/// JSON value with every object's keys in order
+fn canonical_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(entries.into_iter().map(|(k, v)| (k, canonical_json(v))).collect())
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(values.into_iter().map(canonical_json).collect()),
+        other => other,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -897,6 +971,7 @@ spec:
                 },
             },
             package_hash: String::new(),
+            signatures: vec![],
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
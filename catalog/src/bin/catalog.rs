@@ -0,0 +1,170 @@
+//! Catalog command line
+//!
+//! `catalog template lint` checks a template definition file and, with
+//! `--render`, expands it with the given parameters and validates the
+//! result in memory. It exits non-zero when any error is found, so it can
+//! gate asset templates in CI.
+
+use anyhow::{Context, Result};
+use catalog::template::{CatalogTemplateGenerator, TemplateConfig, TemplateContext, TemplateDefinition};
+use catalog::template_lint::{has_errors, DiagnosticSeverity, TemplateDiagnostic};
+use catalog::validation::{AssetValidator, ValidationConfig};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "catalog", version, about = "HyperMesh asset catalog tools")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Work with asset templates
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    /// Check a template definition and optionally dry-run render it
+    Lint(LintArgs),
+}
+
+#[derive(clap::Args)]
+struct LintArgs {
+    /// Template definition file (YAML or JSON)
+    template: PathBuf,
+    /// Render with these parameters and validate the result
+    #[arg(long)]
+    render: bool,
+    /// Template parameter as NAME=VALUE; VALUE is parsed as JSON when it can be
+    #[arg(long = "param", short = 'p', value_name = "NAME=VALUE")]
+    params: Vec<String>,
+    /// File of parameters (YAML or JSON object)
+    #[arg(long, value_name = "FILE")]
+    params_file: Option<PathBuf>,
+    /// Asset name to render with
+    #[arg(long, default_value = "lint-asset")]
+    asset_name: String,
+    /// Asset version to render with
+    #[arg(long, default_value = "0.0.0")]
+    asset_version: String,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+    /// Fail on warnings as well as errors
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Human,
+    Json,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Template { command: TemplateCommand::Lint(args) } => lint(args).await,
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Lint and optionally render a template; returns whether it passed
+async fn lint(args: LintArgs) -> Result<bool> {
+    let text = std::fs::read_to_string(&args.template)
+        .with_context(|| format!("Failed to read {}", args.template.display()))?;
+    let template: TemplateDefinition = serde_yaml::from_str(&text)
+        .with_context(|| format!("{} is not a template definition", args.template.display()))?;
+
+    let generator = CatalogTemplateGenerator::new(TemplateConfig::default())?;
+    let (diagnostics, files) = if args.render {
+        let context = TemplateContext {
+            parameters: parameters(&args)?,
+            output_dir: String::new(),
+            asset_name: args.asset_name.clone(),
+            asset_version: args.asset_version.clone(),
+            author: None,
+            metadata: HashMap::new(),
+        };
+        let validator = AssetValidator::new(ValidationConfig::default());
+        let report = generator.dry_run(&template, &context, &validator).await?;
+        (report.diagnostics, report.files.into_keys().collect())
+    } else {
+        (generator.lint_template(&template), Vec::new())
+    };
+
+    let passed = !has_errors(&diagnostics)
+        && !(args.deny_warnings && diagnostics.iter().any(|d| d.severity == DiagnosticSeverity::Warning));
+
+    match args.format {
+        Format::Json => {
+            let output = serde_json::json!({
+                "template": template.name,
+                "passed": passed,
+                "rendered_files": files,
+                "diagnostics": diagnostics,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        Format::Human => print_human(&template.name, &diagnostics, &files, passed),
+    }
+
+    Ok(passed)
+}
+
+fn parameters(args: &LintArgs) -> Result<HashMap<String, serde_json::Value>> {
+    let mut parameters = match &args.params_file {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_yaml::from_str(&text).with_context(|| format!("{} is not a parameter map", path.display()))?
+        }
+        None => HashMap::new(),
+    };
+    for param in &args.params {
+        let (name, value) = param
+            .split_once('=')
+            .with_context(|| format!("Parameter '{}' is not NAME=VALUE", param))?;
+        let value = serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        parameters.insert(name.to_string(), value);
+    }
+    Ok(parameters)
+}
+
+fn print_human(name: &str, diagnostics: &[TemplateDiagnostic], files: &[String], passed: bool) {
+    for file in files {
+        println!("rendered {}", file);
+    }
+    for diagnostic in diagnostics {
+        let severity = match diagnostic.severity {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Info => "info",
+        };
+        match &diagnostic.location {
+            Some(location) => println!("{}[{}] {}: {}", severity, diagnostic.code, location, diagnostic.message),
+            None => println!("{}[{}] {}", severity, diagnostic.code, diagnostic.message),
+        }
+    }
+    println!(
+        "{}: {} ({} finding(s))",
+        name,
+        if passed { "passed" } else { "failed" },
+        diagnostics.len()
+    );
+}
@@ -271,12 +271,41 @@ pub struct ProofNode {
 
 /// Content chunker for efficient distribution
 pub struct ContentChunker {
-    /// Chunk size in bytes
+    /// Chunk size in bytes, the average for content-defined boundaries
     chunk_size: usize,
     /// Compression type
     compression: CompressionType,
+    /// Where chunks are cut
+    boundaries: ChunkBoundaries,
 }
 
+/// Where data is cut into chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkBoundaries {
+    /// Every `chunk_size` bytes
+    Fixed,
+    /// Where a rolling hash of the content matches, so an edit only changes
+    /// the chunks around it and the rest keep their addresses
+    ContentDefined,
+}
+
+/// Random values for the gear rolling hash, one per byte value
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut seed = 0u64;
+    let mut i = 0;
+    while i < 256 {
+        // splitmix64
+        seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
 /// Compression type for chunks
 #[derive(Debug, Clone, Copy)]
 pub enum CompressionType {
@@ -292,22 +321,40 @@ impl ContentChunker {
         Self {
             chunk_size,
             compression,
+            boundaries: ChunkBoundaries::Fixed,
+        }
+    }
+
+    /// Create a chunker cutting at content-defined boundaries
+    ///
+    /// Chunks average `chunk_size` bytes and stay between a quarter and four
+    /// times that.
+    pub fn content_defined(chunk_size: usize, compression: CompressionType) -> Self {
+        Self {
+            chunk_size,
+            compression,
+            boundaries: ChunkBoundaries::ContentDefined,
         }
     }
 
     /// Split data into chunks
     pub fn chunk_data(&self, data: &[u8]) -> Result<Vec<Chunk>> {
+        let pieces: Vec<&[u8]> = match self.boundaries {
+            ChunkBoundaries::Fixed => data.chunks(self.chunk_size).collect(),
+            ChunkBoundaries::ContentDefined => self.content_defined_pieces(data),
+        };
+
         let mut chunks = Vec::new();
 
-        for (i, chunk_data) in data.chunks(self.chunk_size).enumerate() {
+        for (i, chunk_data) in pieces.into_iter().enumerate() {
             let compressed_data = self.compress(chunk_data)?;
 
             let chunk = Chunk {
                 index: i,
-                data: compressed_data,
                 hash: ContentAddress::from_data(chunk_data),
                 size: chunk_data.len(),
                 compressed_size: compressed_data.len(),
+                data: compressed_data,
                 compression: self.compression,
             };
 
@@ -317,6 +364,32 @@ impl ContentChunker {
         Ok(chunks)
     }
 
+    /// Cut data where the top bits of a gear hash over the last 64 bytes are zero
+    fn content_defined_pieces<'a>(&self, data: &'a [u8]) -> Vec<&'a [u8]> {
+        let min_size = (self.chunk_size / 4).max(1);
+        let max_size = self.chunk_size.max(1) * 4;
+        let bits = (self.chunk_size - min_size).max(2).next_power_of_two().trailing_zeros();
+
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        let mut hash = 0u64;
+
+        for (i, &byte) in data.iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+            let len = i + 1 - start;
+            if (len >= min_size && hash >> (64 - bits) == 0) || len >= max_size {
+                pieces.push(&data[start..=i]);
+                start = i + 1;
+                hash = 0;
+            }
+        }
+        if start < data.len() {
+            pieces.push(&data[start..]);
+        }
+
+        pieces
+    }
+
     /// Reassemble chunks into original data
     pub fn reassemble(&self, chunks: &[Chunk]) -> Result<Vec<u8>> {
         // Sort chunks by index
@@ -607,6 +680,30 @@ mod tests {
         assert_eq!(reassembled, data);
     }
 
+    #[test]
+    fn test_content_defined_chunks_survive_an_insert() {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let data: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let mut edited = data.clone();
+        edited.splice(1000..1000, b"inserted".iter().copied());
+
+        let chunker = ContentChunker::content_defined(4096, CompressionType::None);
+        let before = chunker.chunk_data(&data).unwrap();
+        let after = chunker.chunk_data(&edited).unwrap();
+        assert!(after.iter().all(|c| (c.size >= 1024 && c.size <= 16384) || c.index == after.len() - 1));
+
+        let shared = after.iter().filter(|c| before.iter().any(|b| b.hash == c.hash)).count();
+        assert!(shared + 2 >= after.len());
+        assert_eq!(chunker.reassemble(&after).unwrap(), edited);
+    }
+
     #[test]
     fn test_binary_diff() {
         let old_data = b"Hello, World!";
@@ -0,0 +1,166 @@
+//! Delta updates between package versions
+//!
+//! Packages are chunked at content-defined boundaries, so a new version of a
+//! large asset shares most chunk addresses with versions already in the local
+//! content store. Only the chunks missing locally are fetched from peers, and
+//! the reassembled package is checked chunk by chunk and against the
+//! published package hash before it is accepted.
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use super::content_addressing::{Chunk, ContentAddress};
+use super::stoq_transport::PackageInfo;
+use super::ContentStorage;
+
+/// Chunks of a package version, split into those held locally and those to fetch
+#[derive(Debug, Clone)]
+pub struct DeltaPlan {
+    /// Address of every chunk in order
+    pub addresses: Vec<ContentAddress>,
+    /// Indices of chunks missing from the local content store
+    pub missing: Vec<usize>,
+}
+
+/// Savings from a delta download
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaStats {
+    /// Chunks in the new version
+    pub total_chunks: usize,
+    /// Chunks read from the local content store
+    pub reused_chunks: usize,
+    /// Bytes fetched from peers
+    pub bytes_downloaded: u64,
+    /// Bytes not fetched because they were held locally
+    pub bytes_saved: u64,
+}
+
+impl DeltaPlan {
+    /// Plan a download against the chunks held in local storage
+    pub async fn new(info: &PackageInfo, storage: &dyn ContentStorage) -> Result<Self> {
+        if info.chunk_addresses.len() != info.metadata.chunk_count {
+            return Err(anyhow::anyhow!(
+                "Package info lists {} chunk addresses for {} chunks",
+                info.chunk_addresses.len(),
+                info.metadata.chunk_count
+            ));
+        }
+
+        let addresses = info.chunk_addresses
+            .iter()
+            .map(|hex| ContentAddress::from_hex(hex))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut missing = Vec::new();
+        for (i, address) in addresses.iter().enumerate() {
+            if !storage.has_chunk(address).await? {
+                missing.push(i);
+            }
+        }
+
+        Ok(Self { addresses, missing })
+    }
+
+    /// Indices of chunks held locally
+    pub fn reused(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.addresses.len()).filter(|i| !self.missing.contains(i))
+    }
+
+    /// Check every chunk against the address the publisher listed for it
+    pub fn verify_chunks(&self, chunks: &[Chunk]) -> Result<()> {
+        if chunks.len() != self.addresses.len() {
+            return Err(anyhow::anyhow!(
+                "Chunk count mismatch: expected {}, got {}",
+                self.addresses.len(),
+                chunks.len()
+            ));
+        }
+
+        for chunk in chunks {
+            let expected = self.addresses.get(chunk.index)
+                .ok_or_else(|| anyhow::anyhow!("Chunk index {} out of bounds", chunk.index))?;
+            if ContentAddress::from_data(&chunk.data) != *expected {
+                return Err(anyhow::anyhow!("Chunk {} does not match address {}", chunk.index, expected));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the reassembled package data against the published package hash
+    pub fn verify_package(&self, package_data: &[u8], expected_hash: &str) -> Result<()> {
+        let hash = ContentAddress::from_data(package_data).to_hex();
+        if hash != expected_hash {
+            return Err(anyhow::anyhow!(
+                "Reassembled package hash {} does not match {}",
+                hash,
+                expected_hash
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::content_addressing::{CompressionType, ContentChunker};
+    use super::super::stoq_transport::PackageMetadata;
+    use super::super::FileBasedStorage;
+    use tempfile::TempDir;
+
+    fn package_info(chunks: &[Chunk], package_data: &[u8]) -> PackageInfo {
+        PackageInfo {
+            metadata: PackageMetadata {
+                name: "model".to_string(),
+                version: "2.0.0".to_string(),
+                size: package_data.len() as u64,
+                chunk_count: chunks.len(),
+                chunk_size: 4096,
+                hash: ContentAddress::from_data(package_data).to_hex(),
+                created_at: chrono::Utc::now(),
+            },
+            available_chunks: (0..chunks.len()).collect(),
+            merkle_root: String::new(),
+            chunk_addresses: chunks.iter().map(|c| ContentAddress::from_data(&c.data).to_hex()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delta_plan_fetches_only_changed_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileBasedStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let chunker = ContentChunker::content_defined(4096, CompressionType::None);
+
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let old: Vec<u8> = (0..400_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        for chunk in chunker.chunk_data(&old).unwrap() {
+            storage.store_chunk(&ContentAddress::from_data(&chunk.data), &chunk.data).await.unwrap();
+        }
+
+        let mut new = old.clone();
+        new.splice(200_000..200_000, b"patched weights".iter().copied());
+        let chunks = chunker.chunk_data(&new).unwrap();
+        let info = package_info(&chunks, &new);
+
+        let plan = DeltaPlan::new(&info, &storage).await.unwrap();
+        assert!(!plan.missing.is_empty());
+        assert!(plan.missing.len() <= 3);
+        assert_eq!(plan.reused().count() + plan.missing.len(), chunks.len());
+
+        plan.verify_chunks(&chunks).unwrap();
+        plan.verify_package(&chunker.reassemble(&chunks).unwrap(), &info.metadata.hash).unwrap();
+        assert!(plan.verify_package(&old, &info.metadata.hash).is_err());
+
+        let mut tampered = chunks.clone();
+        tampered[0].data[0] ^= 1;
+        assert!(plan.verify_chunks(&tampered).is_err());
+    }
+}
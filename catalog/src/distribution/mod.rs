@@ -5,6 +5,7 @@
 pub mod stoq_transport;
 pub mod dht;
 pub mod content_addressing;
+pub mod delta;
 pub mod package_manager;
 pub mod peer_discovery;
 
@@ -21,6 +22,7 @@ use crate::security::{SecurityManager, SecurityConfig, VerificationResult};
 use stoq_transport::StoqTransportLayer;
 use dht::{DhtNetwork, NodeId};
 use content_addressing::{ContentAddress, MerkleTree};
+use delta::DeltaStats;
 use package_manager::PackageManager;
 use peer_discovery::PeerDiscovery;
 
@@ -115,6 +117,8 @@ pub struct TransferState {
     pub eta: Option<std::time::Duration>,
     /// Transfer status
     pub status: TransferStatus,
+    /// Chunks reused from local storage, for delta downloads
+    pub delta: Option<DeltaStats>,
 }
 
 /// Transfer direction
@@ -233,6 +237,8 @@ pub struct DistributionMetrics {
     pub bytes_uploaded: Arc<std::sync::atomic::AtomicU64>,
     /// Total bytes downloaded
     pub bytes_downloaded: Arc<std::sync::atomic::AtomicU64>,
+    /// Bytes delta downloads read from local storage instead of peers
+    pub bytes_saved: Arc<std::sync::atomic::AtomicU64>,
     /// Active upload connections
     pub active_uploads: Arc<std::sync::atomic::AtomicUsize>,
     /// Active download connections
@@ -372,6 +378,7 @@ impl P2PDistribution {
             started_at: std::time::Instant::now(),
             eta: None,
             status: TransferStatus::Active,
+            delta: None,
         };
 
         {
@@ -379,14 +386,26 @@ impl P2PDistribution {
             transfers.insert(*package_id, transfer_state);
         }
 
-        // Download package chunks from peers
-        let package = self.package_manager
-            .download_from_peers(package_id, &peers, self.transport.clone())
-            .await
-            .context("Failed to download package from peers")?;
+        // Download package chunks from peers, only the changed ones for incremental updates
+        let (package, delta) = if self.config.enable_incremental_updates {
+            let (package, stats) = self.package_manager
+                .download_delta(package_id, &peers, self.transport.clone())
+                .await
+                .context("Failed to download package delta from peers")?;
+            (package, Some(stats))
+        } else {
+            let package = self.package_manager
+                .download_from_peers(package_id, &peers, self.transport.clone())
+                .await
+                .context("Failed to download package from peers")?;
+            (package, None)
+        };
 
-        // Verify package integrity using Merkle tree
-        self.verify_package_integrity(&package).await?;
+        // Verify package integrity using Merkle tree; delta downloads were
+        // verified chunk by chunk and by package hash while reassembling
+        if delta.is_none() {
+            self.verify_package_integrity(&package).await?;
+        }
 
         // Verify package signature and security
         if self.config.require_signatures {
@@ -433,6 +452,7 @@ impl P2PDistribution {
             if let Some(state) = transfers.get_mut(package_id) {
                 state.status = TransferStatus::Completed;
                 state.progress = 1.0;
+                state.delta = delta.clone();
             }
         }
 
@@ -442,10 +462,18 @@ impl P2PDistribution {
         }
 
         // Update metrics
-        self.metrics.bytes_downloaded.fetch_add(
-            package.calculate_size() as u64,
-            std::sync::atomic::Ordering::Relaxed,
-        );
+        match &delta {
+            Some(stats) => {
+                self.metrics.bytes_downloaded.fetch_add(stats.bytes_downloaded, std::sync::atomic::Ordering::Relaxed);
+                self.metrics.bytes_saved.fetch_add(stats.bytes_saved, std::sync::atomic::Ordering::Relaxed);
+            }
+            None => {
+                self.metrics.bytes_downloaded.fetch_add(
+                    package.calculate_size() as u64,
+                    std::sync::atomic::Ordering::Relaxed,
+                );
+            }
+        }
         self.metrics.successful_transfers.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
         Ok(package)
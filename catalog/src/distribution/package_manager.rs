@@ -13,6 +13,7 @@ use crate::assets::{AssetPackage, AssetPackageId};
 use super::{
     ContentStore, ContentAddress,
     content_addressing::{MerkleTree, ContentChunker, CompressionType, Chunk},
+    delta::{DeltaPlan, DeltaStats},
     stoq_transport::{StoqTransportLayer, PackageInfo, ChunkData, RequestType, ResponseData, PackageMetadata},
     dht::NodeId,
 };
@@ -64,7 +65,8 @@ impl PackageManager {
             chunk_cache: Arc::new(RwLock::new(ChunkCache::new(100 * 1024 * 1024))), // 100MB cache
             download_semaphore: Arc::new(Semaphore::new(10)), // Max 10 concurrent downloads
             upload_semaphore: Arc::new(Semaphore::new(10)),   // Max 10 concurrent uploads
-            chunker: ContentChunker::new(1024 * 1024, CompressionType::Zstd), // 1MB chunks with Zstd
+            // ~1MB content-defined chunks with Zstd, so versions share chunks
+            chunker: ContentChunker::content_defined(1024 * 1024, CompressionType::Zstd),
         })
     }
 
@@ -178,6 +180,91 @@ impl PackageManager {
         Ok(package)
     }
 
+    /// Download a package, fetching only chunks not already stored locally
+    ///
+    /// Chunks shared with any locally stored version are read from the
+    /// content store. Peers that do not list chunk addresses get a full
+    /// download.
+    pub async fn download_delta(
+        &self,
+        package_id: &AssetPackageId,
+        peers: &[NodeId],
+        transport: Arc<StoqTransportLayer>,
+    ) -> Result<(AssetPackage, DeltaStats)> {
+        if peers.is_empty() {
+            return Err(anyhow::anyhow!("No peers available for download"));
+        }
+
+        let package_info = self.get_package_info_from_peers(package_id, peers, &transport).await?;
+        if package_info.chunk_addresses.is_empty() {
+            let package = self.download_from_peers(package_id, peers, transport).await?;
+            let stats = DeltaStats {
+                total_chunks: package_info.metadata.chunk_count,
+                bytes_downloaded: package_info.metadata.size,
+                ..Default::default()
+            };
+            return Ok((package, stats));
+        }
+
+        let plan = DeltaPlan::new(&package_info, self.content_store.storage.as_ref()).await?;
+        let mut chunks = self.download_chunks_parallel(
+            package_id,
+            &plan.missing,
+            peers,
+            &transport,
+        ).await?;
+
+        let mut stats = DeltaStats {
+            total_chunks: plan.addresses.len(),
+            bytes_downloaded: chunks.iter().map(|c| c.data.len() as u64).sum(),
+            ..Default::default()
+        };
+
+        for index in plan.reused() {
+            let address = &plan.addresses[index];
+            let data = self.content_store.storage
+                .get_chunk(address)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Chunk {} disappeared from local storage", address))?;
+
+            stats.reused_chunks += 1;
+            stats.bytes_saved += data.len() as u64;
+            chunks.push(Chunk {
+                index,
+                hash: address.clone(),
+                size: data.len(),
+                compressed_size: data.len(),
+                data,
+                compression: CompressionType::Zstd,
+            });
+        }
+
+        // Verify chunks and the reassembled package before accepting it
+        plan.verify_chunks(&chunks)?;
+        let package_data = self.chunker.reassemble(&chunks)
+            .context("Failed to reassemble package")?;
+        plan.verify_package(&package_data, &package_info.metadata.hash)?;
+
+        let package: AssetPackage = bincode::deserialize(&package_data)
+            .context("Failed to deserialize package")?;
+        if !package.verify_integrity()? {
+            return Err(anyhow::anyhow!("Package {} failed its integrity check", package_id));
+        }
+
+        // Store locally for future seeding and later deltas
+        self.store_package(&package).await?;
+
+        tracing::info!(
+            "Delta download of {}: reused {}/{} chunks, saved {} bytes",
+            package_id,
+            stats.reused_chunks,
+            stats.total_chunks,
+            stats.bytes_saved
+        );
+
+        Ok((package, stats))
+    }
+
     /// Get package info from peers
     async fn get_package_info_from_peers(
         &self,
@@ -209,6 +296,9 @@ impl PackageManager {
     ) -> Result<Vec<Chunk>> {
         let mut chunks = Vec::with_capacity(chunk_indices.len());
         let chunk_count = chunk_indices.len();
+        if chunk_count == 0 {
+            return Ok(chunks);
+        }
 
         // Create download tasks
         let mut download_futures = Vec::new();
@@ -267,16 +357,16 @@ impl PackageManager {
         }
 
         // Sort chunks by index
-        for i in 0..chunk_count {
+        for &i in chunk_indices {
             let chunk_data = chunk_map.remove(&i)
                 .ok_or_else(|| anyhow::anyhow!("Missing chunk {}", i))?;
 
             chunks.push(Chunk {
                 index: chunk_data.index,
-                data: chunk_data.data,
                 hash: ContentAddress::from_hex(&chunk_data.hash)?,
                 size: chunk_data.data.len(),
                 compressed_size: chunk_data.data.len(),
+                data: chunk_data.data,
                 compression: CompressionType::Zstd,
             });
         }
@@ -297,6 +387,13 @@ impl PackageManager {
         // Get available chunks
         let available_chunks = self.get_available_chunks(package_id).await?;
 
+        let chunk_addresses = {
+            let index = self.content_store.index.read().await;
+            index.by_package.get(package_id)
+                .map(|addresses| addresses.iter().map(|a| a.to_hex()).collect())
+                .unwrap_or_default()
+        };
+
         // Get Merkle root
         let merkle_root = {
             let trees = self.content_store.merkle_trees.read().await;
@@ -309,6 +406,7 @@ impl PackageManager {
             metadata,
             available_chunks,
             merkle_root,
+            chunk_addresses,
         })
     }
 
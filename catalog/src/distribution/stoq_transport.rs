@@ -142,6 +142,9 @@ pub struct PackageInfo {
     pub available_chunks: Vec<usize>,
     /// Merkle root hash
     pub merkle_root: String,
+    /// Content address of each chunk, for delta downloads
+    #[serde(default)]
+    pub chunk_addresses: Vec<String>,
 }
 
 /// Chunk data for transfer
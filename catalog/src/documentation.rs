@@ -774,6 +774,7 @@ mod tests {
                 },
             },
             package_hash: "test-hash".to_string(),
+            signatures: vec![],
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
@@ -0,0 +1,284 @@
+//! Side-by-side Installs and Environments
+//!
+//! Several versions of an asset package can be installed at once. Which one
+//! a consumer gets is decided by environments: a workload's environment pins
+//! versions for that workload, a namespace's for everything in it, and the
+//! global one for everything else. Resolution walks from the narrowest scope
+//! out and falls back to the newest stable installed version, so upgrading
+//! one consumer never moves another that is pinned. A version cannot be
+//! uninstalled while any environment still activates it.
+
+use crate::assets::{AssetPackage, AssetPackageId};
+use crate::versioning::SemanticVersion;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Where a version activation applies
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EnvironmentScope {
+    /// Every consumer without a narrower activation
+    Global,
+    /// Every workload in a namespace
+    Namespace(String),
+    /// One workload
+    Workload {
+        /// Namespace of the workload
+        namespace: String,
+        /// Workload name
+        workload: String,
+    },
+}
+
+impl EnvironmentScope {
+    /// Scopes consulted for this one, narrowest first
+    pub fn lookup_chain(&self) -> Vec<EnvironmentScope> {
+        match self {
+            Self::Global => vec![Self::Global],
+            Self::Namespace(_) => vec![self.clone(), Self::Global],
+            Self::Workload { namespace, .. } => {
+                vec![self.clone(), Self::Namespace(namespace.clone()), Self::Global]
+            }
+        }
+    }
+}
+
+impl fmt::Display for EnvironmentScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Namespace(namespace) => write!(f, "namespace/{}", namespace),
+            Self::Workload { namespace, workload } => write!(f, "workload/{}/{}", namespace, workload),
+        }
+    }
+}
+
+/// One installed version of an asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledVersion {
+    /// Asset name
+    pub name: String,
+    /// Installed version
+    pub version: String,
+    /// Package the version was installed from
+    pub package_id: AssetPackageId,
+    /// Hash of the installed package
+    pub package_hash: String,
+    /// When it was installed
+    pub installed_at: DateTime<Utc>,
+}
+
+/// Installed asset versions and the environments that activate them
+#[derive(Default)]
+pub struct AssetEnvironments {
+    /// Asset name -> version -> installed package
+    installed: RwLock<HashMap<String, HashMap<String, (InstalledVersion, Arc<AssetPackage>)>>>,
+    /// Scope -> asset name -> active version
+    environments: RwLock<HashMap<EnvironmentScope, HashMap<String, String>>>,
+}
+
+impl AssetEnvironments {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Install a package next to any other versions of the same asset
+    ///
+    /// Installing a version again replaces it in place.
+    pub fn install(&self, package_id: AssetPackageId, package: AssetPackage) -> InstalledVersion {
+        let metadata = &package.spec.metadata;
+        let installed = InstalledVersion {
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            package_id,
+            package_hash: package.package_hash.clone(),
+            installed_at: Utc::now(),
+        };
+        self.installed.write()
+            .entry(installed.name.clone())
+            .or_default()
+            .insert(installed.version.clone(), (installed.clone(), Arc::new(package)));
+        installed
+    }
+
+    /// Remove an installed version that no environment activates
+    pub fn uninstall(&self, name: &str, version: &str) -> Result<InstalledVersion> {
+        let pinned_by: Vec<String> = self.environments.read()
+            .iter()
+            .filter(|(_, active)| active.get(name).map(String::as_str) == Some(version))
+            .map(|(scope, _)| scope.to_string())
+            .collect();
+        if !pinned_by.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} {} is active in {}; deactivate it first", name, version, pinned_by.join(", ")
+            ));
+        }
+
+        let mut installed = self.installed.write();
+        let versions = installed.get_mut(name)
+            .ok_or_else(|| anyhow::anyhow!("{} is not installed", name))?;
+        let (removed, _) = versions.remove(version)
+            .ok_or_else(|| anyhow::anyhow!("{} {} is not installed", name, version))?;
+        if versions.is_empty() {
+            installed.remove(name);
+        }
+        Ok(removed)
+    }
+
+    /// Installed versions of an asset, oldest first
+    pub fn installed_versions(&self, name: &str) -> Vec<InstalledVersion> {
+        let mut versions: Vec<InstalledVersion> = self.installed.read()
+            .get(name)
+            .map(|versions| versions.values().map(|(installed, _)| installed.clone()).collect())
+            .unwrap_or_default();
+        versions.sort_by(|a, b| version_order(&a.version).cmp(&version_order(&b.version)));
+        versions
+    }
+
+    /// Use an installed version in a scope; returns the version it replaces
+    pub fn activate(&self, scope: EnvironmentScope, name: &str, version: &str) -> Result<Option<String>> {
+        let installed = self.installed.read()
+            .get(name)
+            .is_some_and(|versions| versions.contains_key(version));
+        if !installed {
+            return Err(anyhow::anyhow!("{} {} is not installed", name, version));
+        }
+
+        let previous = self.environments.write()
+            .entry(scope.clone())
+            .or_default()
+            .insert(name.to_string(), version.to_string());
+        tracing::info!("Activated {} {} in {}", name, version, scope);
+        Ok(previous)
+    }
+
+    /// Stop pinning an asset in a scope, so wider scopes decide again
+    pub fn deactivate(&self, scope: &EnvironmentScope, name: &str) -> Option<String> {
+        let mut environments = self.environments.write();
+        let active = environments.get_mut(scope)?;
+        let removed = active.remove(name);
+        if active.is_empty() {
+            environments.remove(scope);
+        }
+        removed
+    }
+
+    /// Versions a scope pins itself, by asset name
+    pub fn environment(&self, scope: &EnvironmentScope) -> HashMap<String, String> {
+        self.environments.read().get(scope).cloned().unwrap_or_default()
+    }
+
+    /// Version of an asset a consumer in `scope` gets
+    pub fn resolve(&self, scope: &EnvironmentScope, name: &str) -> Option<InstalledVersion> {
+        self.resolve_package(scope, name).map(|(installed, _)| installed)
+    }
+
+    /// Version of an asset a consumer in `scope` gets, with its package
+    pub fn resolve_package(&self, scope: &EnvironmentScope, name: &str) -> Option<(InstalledVersion, Arc<AssetPackage>)> {
+        let installed = self.installed.read();
+        let versions = installed.get(name)?;
+
+        let environments = self.environments.read();
+        let pinned = scope.lookup_chain().into_iter().find_map(|scope| {
+            environments.get(&scope)?.get(name).and_then(|version| versions.get(version))
+        });
+        if let Some(pinned) = pinned {
+            return Some(pinned.clone());
+        }
+
+        // Nothing pinned: the newest stable version, else the newest of any kind
+        versions.values()
+            .max_by(|(a, _), (b, _)| {
+                let (a, b) = (version_order(&a.version), version_order(&b.version));
+                let stable = |v: &Option<SemanticVersion>| v.as_ref().is_some_and(|v| v.is_stable());
+                stable(&a).cmp(&stable(&b)).then_with(|| a.cmp(&b))
+            })
+            .cloned()
+    }
+}
+
+/// Semantic versions in order; unparsable versions sort first
+fn version_order(version: &str) -> Option<SemanticVersion> {
+    SemanticVersion::parse(version).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn package(name: &str, version: &str) -> AssetPackage {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("asset.yaml");
+        std::fs::write(&path, format!(r#"
+apiVersion: "catalog.v1"
+kind: "Asset"
+metadata:
+  name: "{name}"
+  version: "{version}"
+  tags: []
+  keywords: []
+spec:
+  type: "julia-program"
+  content: {{ main: "", files: [], binary: [], templates: [] }}
+  security:
+    consensus_required: false
+    certificate_pinning: false
+    hash_validation: "sha256"
+    sandbox_level: "standard"
+    allowed_syscalls: []
+    network_access: {{ enabled: false, allowed_domains: [], allowed_ports: [], require_tls: true }}
+    file_access: {{ level: "read_only", allowed_paths: [], denied_paths: [], allow_temp: false }}
+    permissions: []
+  resources:
+    cpu_limit: "1000m"
+    memory_limit: "1Gi"
+    execution_timeout: "30s"
+    gpu_required: false
+    hardware_requirements: []
+  execution:
+    delegation_strategy: "nearest_node"
+    minimum_consensus: 1
+    retry_policy: "none"
+    priority: "normal"
+    timeout_config: {{ execution: "30s", network: "10s", io: "5s" }}
+    scheduling: {{ timing: "immediate", allocation_strategy: "best_fit", node_affinity: [], anti_affinity: [] }}
+  dependencies: []
+  environment: {{}}
+"#)).unwrap();
+        AssetPackage::from_yaml(&path).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_side_by_side_versions() {
+        let envs = AssetEnvironments::new();
+        envs.install(uuid::Uuid::new_v4(), package("solver", "1.2.0").await);
+        envs.install(uuid::Uuid::new_v4(), package("solver", "2.0.0").await);
+        envs.install(uuid::Uuid::new_v4(), package("solver", "2.1.0-beta.1").await);
+
+        let pinned = EnvironmentScope::Workload { namespace: "ml".into(), workload: "trainer".into() };
+        let neighbour = EnvironmentScope::Workload { namespace: "ml".into(), workload: "server".into() };
+
+        // Without activations consumers get the newest stable version
+        assert_eq!(envs.resolve(&pinned, "solver").unwrap().version, "2.0.0");
+
+        // A namespace activation applies to its workloads until one pins its own
+        envs.activate(EnvironmentScope::Namespace("ml".into()), "solver", "2.1.0-beta.1").unwrap();
+        envs.activate(pinned.clone(), "solver", "1.2.0").unwrap();
+        assert_eq!(envs.resolve(&pinned, "solver").unwrap().version, "1.2.0");
+        assert_eq!(envs.resolve(&neighbour, "solver").unwrap().version, "2.1.0-beta.1");
+        assert_eq!(envs.resolve(&EnvironmentScope::Global, "solver").unwrap().version, "2.0.0");
+
+        assert!(envs.activate(pinned.clone(), "solver", "3.0.0").is_err());
+        assert!(envs.uninstall("solver", "1.2.0").is_err());
+        assert_eq!(envs.deactivate(&pinned, "solver").as_deref(), Some("1.2.0"));
+        envs.uninstall("solver", "1.2.0").unwrap();
+
+        let versions: Vec<String> = envs.installed_versions("solver").into_iter().map(|v| v.version).collect();
+        assert_eq!(versions, vec!["2.0.0", "2.1.0-beta.1"]);
+    }
+}
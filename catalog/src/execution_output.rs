@@ -0,0 +1,274 @@
+//! Execution Output
+//!
+//! Holds what asset executions on HyperMesh produce: their stdout and stderr
+//! as numbered chunks, and the files they leave behind as artifacts. Output
+//! is kept per execution up to a byte budget, oldest chunks dropped first,
+//! and also broadcast as it arrives. A stream replays the kept output from a
+//! chosen sequence number, follows live output, and ends once the execution
+//! is closed, so a client can reconnect where it left off.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Output bytes kept per execution for replay
+pub const MAX_BUFFERED_BYTES: usize = 8 * 1024 * 1024;
+
+/// Which output stream a chunk came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputStream {
+    /// Standard output
+    Stdout,
+    /// Standard error
+    Stderr,
+}
+
+/// A piece of execution output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputChunk {
+    /// Execution that wrote it
+    pub execution_id: String,
+    /// Stream it was written to
+    pub stream: OutputStream,
+    /// Position among the execution's chunks, from 0
+    pub sequence: u64,
+    /// Bytes written
+    pub data: Vec<u8>,
+    /// When it was received
+    pub received_at: DateTime<Utc>,
+}
+
+/// A file an execution produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionArtifact {
+    /// Path of the file relative to the execution's output directory
+    pub name: String,
+    /// Size in bytes
+    pub size_bytes: u64,
+    /// SHA-256 of the content, hex encoded
+    pub sha256: String,
+    /// When it was received
+    pub produced_at: DateTime<Utc>,
+}
+
+struct ExecutionRecord {
+    chunks: VecDeque<OutputChunk>,
+    buffered_bytes: usize,
+    next_sequence: u64,
+    /// Dropped once the execution is closed, which ends live streams
+    live: Option<broadcast::Sender<OutputChunk>>,
+    artifacts: HashMap<String, (ExecutionArtifact, Arc<Vec<u8>>)>,
+}
+
+/// Output and artifacts of executions, by execution ID
+#[derive(Default)]
+pub struct ExecutionOutputs {
+    executions: RwLock<HashMap<String, ExecutionRecord>>,
+}
+
+impl ExecutionOutputs {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start collecting output for an execution
+    pub fn open(&self, execution_id: &str) {
+        self.executions.write().entry(execution_id.to_string()).or_insert_with(|| ExecutionRecord {
+            chunks: VecDeque::new(),
+            buffered_bytes: 0,
+            next_sequence: 0,
+            live: Some(broadcast::channel(1024).0),
+            artifacts: HashMap::new(),
+        });
+    }
+
+    /// Add output written by an execution; returns the chunk's sequence number
+    pub fn append(&self, execution_id: &str, stream: OutputStream, data: Vec<u8>) -> Result<u64> {
+        let mut executions = self.executions.write();
+        let record = executions.get_mut(execution_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown execution {}", execution_id))?;
+        if record.live.is_none() {
+            return Err(anyhow::anyhow!("Execution {} is closed", execution_id));
+        }
+
+        let chunk = OutputChunk {
+            execution_id: execution_id.to_string(),
+            stream,
+            sequence: record.next_sequence,
+            data,
+            received_at: Utc::now(),
+        };
+        record.next_sequence += 1;
+        record.buffered_bytes += chunk.data.len();
+        record.chunks.push_back(chunk.clone());
+        while record.buffered_bytes > MAX_BUFFERED_BYTES {
+            let Some(dropped) = record.chunks.pop_front() else { break };
+            record.buffered_bytes -= dropped.data.len();
+        }
+        if let Some(live) = &record.live {
+            // No receivers is fine; the chunk stays in the buffer
+            let _ = live.send(chunk.clone());
+        }
+        Ok(chunk.sequence)
+    }
+
+    /// Store a file an execution produced, replacing one of the same name
+    pub fn add_artifact(&self, execution_id: &str, name: &str, content: Vec<u8>) -> Result<ExecutionArtifact> {
+        let mut executions = self.executions.write();
+        let record = executions.get_mut(execution_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown execution {}", execution_id))?;
+
+        let artifact = ExecutionArtifact {
+            name: name.to_string(),
+            size_bytes: content.len() as u64,
+            sha256: hex_digest(&content),
+            produced_at: Utc::now(),
+        };
+        record.artifacts.insert(name.to_string(), (artifact.clone(), Arc::new(content)));
+        Ok(artifact)
+    }
+
+    /// Mark an execution finished; its streams end after the remaining output
+    pub fn close(&self, execution_id: &str) {
+        if let Some(record) = self.executions.write().get_mut(execution_id) {
+            record.live = None;
+        }
+    }
+
+    /// Drop everything kept for an execution
+    pub fn remove(&self, execution_id: &str) {
+        self.executions.write().remove(execution_id);
+    }
+
+    /// Output from `from_sequence` on, followed live until the execution closes
+    ///
+    /// Chunks no longer kept are skipped, so the first chunk may have a
+    /// higher sequence number than asked for.
+    pub fn stream(self: &Arc<Self>, execution_id: &str, from_sequence: u64) -> Result<BoxStream<'static, OutputChunk>> {
+        let receiver = {
+            let executions = self.executions.read();
+            let record = executions.get(execution_id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown execution {}", execution_id))?;
+            record.live.as_ref().map(|live| live.subscribe())
+        };
+
+        let state = (Arc::clone(self), execution_id.to_string(), from_sequence, receiver, VecDeque::new());
+        Ok(Box::pin(stream::unfold(state, |(outputs, execution_id, mut next, mut receiver, mut pending)| async move {
+            loop {
+                if let Some(chunk) = pending.pop_front() {
+                    return Some((chunk, (outputs, execution_id, next, receiver, pending)));
+                }
+
+                // Catch up from the buffer before waiting on live output
+                pending = outputs.buffered_from(&execution_id, next);
+                if let Some(last) = pending.back() {
+                    next = last.sequence + 1;
+                    continue;
+                }
+
+                let live = receiver.as_mut()?;
+                match live.recv().await {
+                    Ok(chunk) if chunk.sequence >= next => {
+                        next = chunk.sequence + 1;
+                        pending.push_back(chunk);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => receiver = None,
+                }
+            }
+        })))
+    }
+
+    /// Artifacts an execution produced, by name
+    pub fn artifacts(&self, execution_id: &str) -> Result<Vec<ExecutionArtifact>> {
+        let executions = self.executions.read();
+        let record = executions.get(execution_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown execution {}", execution_id))?;
+        let mut artifacts: Vec<ExecutionArtifact> = record.artifacts.values().map(|(artifact, _)| artifact.clone()).collect();
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(artifacts)
+    }
+
+    /// Content of one artifact
+    pub fn artifact_content(&self, execution_id: &str, name: &str) -> Result<(ExecutionArtifact, Arc<Vec<u8>>)> {
+        self.executions.read()
+            .get(execution_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown execution {}", execution_id))?
+            .artifacts
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Execution {} has no artifact '{}'", execution_id, name))
+    }
+
+    /// Write an artifact to a file, checking its digest first
+    pub async fn download_artifact(&self, execution_id: &str, name: &str, destination: &Path) -> Result<ExecutionArtifact> {
+        let (artifact, content) = self.artifact_content(execution_id, name)?;
+        if hex_digest(&content) != artifact.sha256 {
+            return Err(anyhow::anyhow!("Artifact '{}' of {} does not match its digest", name, execution_id));
+        }
+        if let Some(parent) = destination.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        tokio::fs::write(destination, content.as_slice()).await
+            .with_context(|| format!("Failed to write {}", destination.display()))?;
+        Ok(artifact)
+    }
+
+    fn buffered_from(&self, execution_id: &str, from_sequence: u64) -> VecDeque<OutputChunk> {
+        self.executions.read()
+            .get(execution_id)
+            .map(|record| record.chunks.iter().filter(|c| c.sequence >= from_sequence).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn hex_digest(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_output_stream_and_artifacts() {
+        let outputs = Arc::new(ExecutionOutputs::new());
+        outputs.open("exec-1");
+        outputs.append("exec-1", OutputStream::Stdout, b"starting\n".to_vec()).unwrap();
+
+        // A stream replays what is kept, then follows live output until close
+        let mut live = outputs.stream("exec-1", 0).unwrap();
+        assert_eq!(live.next().await.unwrap().data, b"starting\n");
+        outputs.append("exec-1", OutputStream::Stderr, b"warning\n".to_vec()).unwrap();
+        let chunk = live.next().await.unwrap();
+        assert_eq!((chunk.sequence, chunk.stream), (1, OutputStream::Stderr));
+
+        outputs.add_artifact("exec-1", "results/out.csv", b"x,y\n1,2\n".to_vec()).unwrap();
+        outputs.append("exec-1", OutputStream::Stdout, b"done\n".to_vec()).unwrap();
+        outputs.close("exec-1");
+        assert_eq!(live.next().await.unwrap().data, b"done\n");
+        assert!(live.next().await.is_none());
+        assert!(outputs.append("exec-1", OutputStream::Stdout, Vec::new()).is_err());
+
+        // Reconnecting from a sequence number skips what was already seen
+        let rest: Vec<u64> = outputs.stream("exec-1", 1).unwrap().map(|c| c.sequence).collect().await;
+        assert_eq!(rest, vec![1, 2]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("out.csv");
+        let artifact = outputs.download_artifact("exec-1", "results/out.csv", &destination).await.unwrap();
+        assert_eq!(artifact.size_bytes, 8);
+        assert_eq!(std::fs::read(&destination).unwrap(), b"x,y\n1,2\n");
+        assert!(outputs.artifact_content("exec-1", "missing").is_err());
+    }
+}
@@ -11,6 +11,8 @@
 //! - Full compatibility with existing Catalog functionality
 
 use crate::assets::*;
+use crate::triggers::CatalogEvent;
+use crate::usage::{AssetUsage, UsageAnalytics, UsageKind};
 use crate::library::{
     AssetLibrary, LibraryAssetPackage, LibraryConfig, LibraryInterface,
     LibraryStats, PackageSummary, SearchQuery as LibrarySearchQuery,
@@ -30,7 +32,7 @@ use blockmatrix::assets::core::{
 };
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
@@ -44,8 +46,15 @@ pub struct HyperMeshAssetRegistry {
     catalog_cache: Arc<RwLock<CatalogCache>>,
     /// Bridge configuration
     config: BridgeConfig,
+    /// Catalog events, such as published assets
+    events: broadcast::Sender<CatalogEvent>,
+    /// Download, install and execution counts per asset version
+    usage: Arc<UsageAnalytics>,
 }
 
+/// Weight of usage popularity in relevance ranking
+const POPULARITY_RANK_WEIGHT: f64 = 0.25;
+
 /// Bridge configuration for HyperMesh integration
 #[derive(Debug, Clone)]
 pub struct BridgeConfig {
@@ -87,6 +96,10 @@ struct CatalogCache {
 /// Catalog-specific metadata for packages
 #[derive(Debug, Clone)]
 struct CatalogMetadata {
+    /// Asset name
+    name: String,
+    /// Asset version
+    version: String,
     /// Package tags for categorization
     tags: Vec<String>,
     /// Package description
@@ -167,6 +180,8 @@ impl HyperMeshAssetRegistry {
             asset_library: Arc::new(asset_library),
             catalog_cache,
             config,
+            events: broadcast::channel(256).0,
+            usage: Arc::new(UsageAnalytics::default()),
         })
     }
 
@@ -290,6 +305,8 @@ impl HyperMeshAssetRegistry {
         // Update catalog cache with metadata
         let mut cache = self.catalog_cache.write().await;
         cache.package_metadata.insert(package_id, CatalogMetadata {
+            name: package.spec.metadata.name.clone(),
+            version: package.spec.metadata.version.clone(),
             tags: package.spec.metadata.tags.clone(),
             description: package.spec.metadata.description.clone(),
             author: package.spec.metadata.author.clone(),
@@ -304,15 +321,29 @@ impl HyperMeshAssetRegistry {
         tracing::info!("Published asset {} through HyperMesh with allocation ID: {}",
             package_id, allocation.asset_id);
 
+        // No subscribers is fine
+        let _ = self.events.send(CatalogEvent::AssetPublished {
+            package_id: package_id.to_string(),
+            name: package.spec.metadata.name.clone(),
+            version: package.spec.metadata.version.clone(),
+        });
+
         Ok(package_id)
     }
 
+    /// Subscribe to catalog events, for instance to run triggered scripts
+    pub fn subscribe(&self) -> broadcast::Receiver<CatalogEvent> {
+        self.events.subscribe()
+    }
+
     /// Install an asset package from HyperMesh
     pub async fn install(&self, id: &AssetPackageId) -> Result<AssetPackage> {
         // First check if package exists in library
         if let Some(package) = self.asset_library.get_package(&id.to_string()).await? {
             // Convert from library package format
-            return self.library_package_to_asset_package(package);
+            let package = self.library_package_to_asset_package(package)?;
+            self.record_usage(id, UsageKind::Install).await;
+            return Ok(package);
         }
 
         // If not found locally, this would normally query other HyperMesh nodes
@@ -357,6 +388,7 @@ impl HyperMeshAssetRegistry {
                 verified: false,
             },
             package_hash: lib_package.hash.clone(),
+            signatures: Vec::new(),
         })
     }
 
@@ -436,10 +468,38 @@ impl HyperMeshAssetRegistry {
 
     /// Increment download count
     pub async fn increment_downloads(&self, id: &AssetPackageId) -> Result<()> {
-        let mut cache = self.catalog_cache.write().await;
-        cache.package_stats.entry(*id).or_default().download_count += 1;
+        self.catalog_cache.write().await.package_stats.entry(*id).or_default().download_count += 1;
+        self.record_usage(id, UsageKind::Download).await;
         Ok(())
     }
+
+    /// Count a usage event against a published package's version
+    ///
+    /// Usage is best effort: a failure to record is logged, not returned,
+    /// so it never fails the operation being counted.
+    pub async fn record_usage(&self, id: &AssetPackageId, kind: UsageKind) {
+        let asset = self.catalog_cache.read().await
+            .package_metadata
+            .get(id)
+            .map(|metadata| (metadata.name.clone(), metadata.version.clone()));
+        let Some((name, version)) = asset else {
+            tracing::debug!("Not counting {:?} of unknown package {}", kind, id);
+            return;
+        };
+        if let Err(e) = self.usage.record(&name, &version, kind, Utc::now()).await {
+            tracing::warn!("Failed to record {:?} of {} {}: {}", kind, name, version, e);
+        }
+    }
+
+    /// Usage of every version of an asset, by asset name
+    pub async fn asset_usage(&self, name: &str) -> Result<AssetUsage> {
+        self.usage.asset_usage(name).await
+    }
+
+    /// Usage analytics, for instance to keep records in the state layer
+    pub fn usage_analytics(&self) -> Arc<UsageAnalytics> {
+        Arc::clone(&self.usage)
+    }
 }
 
 #[async_trait::async_trait]
@@ -454,9 +514,10 @@ impl AssetDiscovery for HyperMeshAssetRegistry {
             // Return all assets if no query
             for (package_id, metadata) in &cache.package_metadata {
                 if self.matches_filters(metadata, query).await {
+                    let asset = self.metadata_to_index_entry(*package_id, metadata).await?;
                     results.push(AssetSearchResult {
-                        asset: self.metadata_to_index_entry(*package_id, metadata).await?,
-                        score: 1.0,
+                        score: usage_boost(asset.popularity),
+                        asset,
                         highlights: vec![],
                     });
                 }
@@ -493,9 +554,10 @@ impl AssetDiscovery for HyperMeshAssetRegistry {
 
             for (package_id, score) in scored_vec {
                 if let Some(metadata) = cache.package_metadata.get(&package_id) {
+                    let asset = self.metadata_to_index_entry(package_id, metadata).await?;
                     results.push(AssetSearchResult {
-                        asset: self.metadata_to_index_entry(package_id, metadata).await?,
-                        score: score / query_terms.len() as f64,
+                        score: score / query_terms.len() as f64 * usage_boost(asset.popularity),
+                        asset,
                         highlights: self.generate_highlights(metadata, &query_terms),
                     });
                 }
@@ -634,6 +696,7 @@ impl HyperMeshAssetRegistry {
         metadata: &CatalogMetadata,
     ) -> Result<AssetIndexEntry> {
         let stats = self.get_package_stats(&package_id).await?;
+        let usage = self.usage.version_usage(&metadata.name, &metadata.version).await?;
 
         // Fetch package info from library for complete data
         let package_info = self.asset_library.get_package(&package_id.to_string()).await?
@@ -654,7 +717,10 @@ impl HyperMeshAssetRegistry {
             updated_at: metadata.updated_at,
             registry: "hypermesh".to_string(),
             rating: stats.rating,
-            download_count: stats.download_count,
+            download_count: usage.totals.downloads,
+            install_count: usage.totals.installs,
+            execution_count: usage.totals.executions,
+            popularity: usage.popularity(Utc::now()),
             verified: true, // All HyperMesh assets are consensus-verified
         })
     }
@@ -710,7 +776,11 @@ impl HyperMeshAssetRegistry {
                 results.sort_by(|a, b| b.asset.updated_at.cmp(&a.asset.updated_at));
             }
             SortCriteria::Popularity => {
-                results.sort_by(|a, b| b.asset.download_count.cmp(&a.asset.download_count));
+                results.sort_by(|a, b| {
+                    b.asset.popularity.partial_cmp(&a.asset.popularity)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then(b.asset.download_count.cmp(&a.asset.download_count))
+                });
             }
             SortCriteria::Rating => {
                 results.sort_by(|a, b| b.asset.rating.partial_cmp(&a.asset.rating).unwrap_or(std::cmp::Ordering::Equal));
@@ -722,6 +792,11 @@ impl HyperMeshAssetRegistry {
     }
 }
 
+/// Relevance multiplier for recent usage; grows slowly so text match still leads
+fn usage_boost(popularity: f64) -> f64 {
+    1.0 + POPULARITY_RANK_WEIGHT * popularity.max(0.0).ln_1p()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
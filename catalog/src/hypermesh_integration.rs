@@ -3,9 +3,13 @@
 //! Provides integration with HyperMesh native resource system.
 //! Catalog runs as a HyperMesh service at catalog.hypermesh.online
 
+use crate::execution_output::{ExecutionArtifact, ExecutionOutputs, OutputChunk};
 use anyhow::Result;
+use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 
 /// HyperMesh client for catalog operations
 pub struct HyperMeshClient {
@@ -15,6 +19,8 @@ pub struct HyperMeshClient {
     trustchain_cert_path: Option<String>,
     /// Asset adapter for HyperMesh integration
     asset_adapter: HyperMeshAssetAdapter,
+    /// Output and artifacts reported by executing nodes
+    outputs: Arc<ExecutionOutputs>,
 }
 
 /// HyperMesh Asset Adapter for catalog assets
@@ -122,6 +128,7 @@ impl Default for HyperMeshClient {
             network_address: "catalog.hypermesh.online".to_string(),
             trustchain_cert_path: None,
             asset_adapter: HyperMeshAssetAdapter::default(),
+            outputs: Arc::new(ExecutionOutputs::new()),
         }
     }
 }
@@ -133,6 +140,7 @@ impl HyperMeshClient {
             network_address,
             trustchain_cert_path: None,
             asset_adapter: HyperMeshAssetAdapter::default(),
+            outputs: Arc::new(ExecutionOutputs::new()),
         }
     }
 
@@ -184,6 +192,7 @@ impl HyperMeshClient {
         };
 
         // TODO: Implement actual HyperMesh resource allocation and execution
+        self.outputs.open(&execution_id);
 
         Ok(context)
     }
@@ -203,10 +212,33 @@ impl HyperMeshClient {
         // TODO: Implement execution termination on HyperMesh
 
         tracing::info!("Terminating execution: {}", execution_id);
+        self.outputs.close(execution_id);
 
         Ok(())
     }
 
+    /// Follow an execution's stdout and stderr from a chunk sequence number
+    ///
+    /// The stream ends once the execution finishes and its output is read.
+    pub fn stream_output(&self, execution_id: &str, from_sequence: u64) -> Result<BoxStream<'static, OutputChunk>> {
+        self.outputs.stream(execution_id, from_sequence)
+    }
+
+    /// Files an execution produced
+    pub fn list_artifacts(&self, execution_id: &str) -> Result<Vec<ExecutionArtifact>> {
+        self.outputs.artifacts(execution_id)
+    }
+
+    /// Save an execution's artifact to a local file
+    pub async fn download_artifact(&self, execution_id: &str, name: &str, destination: &Path) -> Result<ExecutionArtifact> {
+        self.outputs.download_artifact(execution_id, name, destination).await
+    }
+
+    /// Store that executing nodes report output and artifacts into
+    pub fn execution_outputs(&self) -> Arc<ExecutionOutputs> {
+        Arc::clone(&self.outputs)
+    }
+
     /// Set TrustChain certificate path
     pub fn set_trustchain_certificate<P: Into<String>>(&mut self, cert_path: P) {
         self.trustchain_cert_path = Some(cert_path.into());
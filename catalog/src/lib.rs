@@ -16,11 +16,19 @@
 
 pub mod assets;
 pub mod template;
+pub mod template_lint;
 pub mod registry;
 pub mod validation;
 pub mod documentation;
 pub mod versioning;
 pub mod scripting;
+pub mod triggers;
+pub mod usage;
+pub mod environments;
+pub mod admission;
+pub mod execution_output;
+pub mod provenance;
+pub mod mirror;
 pub mod hypermesh_integration;
 pub mod library;
 pub mod hypermesh_bridge;
@@ -66,11 +74,19 @@ pub use assets::{
     AssetResources, AssetExecution, AssetDependency
 };
 pub use template::{CatalogTemplateGenerator, TemplateConfig, TemplateType};
-pub use registry::{AssetRegistry, RegistryConfig, AssetDiscovery};
+pub use template_lint::{DiagnosticSeverity, TemplateDiagnostic, TemplateDryRun};
+pub use registry::{AssetRegistry, RegistryConfig, MirrorConfig, AssetDiscovery};
 pub use validation::{AssetValidator, ValidationConfig, ValidationResult};
 pub use documentation::DocumentationGenerator;
 pub use versioning::{VersionManager, SemanticVersion, DependencyResolver};
 pub use scripting::{ScriptingEngine, ScriptResult};
+pub use triggers::{CatalogEvent, ScriptBinding, ScriptTrigger, ScriptTriggers};
+pub use usage::{AssetUsage, UsageAnalytics, UsageKind, UsageStore};
+pub use environments::{AssetEnvironments, EnvironmentScope, InstalledVersion};
+pub use execution_output::{ExecutionArtifact, ExecutionOutputs, OutputChunk, OutputStream};
+pub use provenance::{PackageSignature, PublisherKey, TrustPolicy, TrustViolation};
+pub use mirror::{BundleEntry, BundleManifest, MirrorStore, VerifiedBundle};
+pub use admission::{AdmissionConfig, AdmissionController, AdmissionMode, AdmissionRejection, ExecutionQuota, TicketStatus};
 pub use hypermesh_integration::{HyperMeshClient, HyperMeshAssetAdapter};
 pub use hypermesh_bridge::{HyperMeshAssetRegistry, BridgeConfig};
 
@@ -86,6 +102,24 @@ pub struct Catalog {
     documentation_generator: Arc<documentation::DocumentationGenerator>,
     version_manager: Arc<versioning::VersionManager>,
     hypermesh_client: Arc<tokio::sync::Mutex<hypermesh_integration::HyperMeshClient>>,
+    admission: Arc<admission::AdmissionController>,
+    environments: Arc<environments::AssetEnvironments>,
+    trust_policy: provenance::TrustPolicy,
+    publisher_key: Option<Arc<provenance::PublisherKey>>,
+}
+
+/// Outcome of submitting an asset execution
+#[derive(Debug, Clone)]
+pub enum ExecutionAdmission {
+    /// Admitted and started
+    Started(hypermesh_integration::CatalogExecutionContext),
+    /// Over the account's limits and queued; follow it by ticket
+    Queued {
+        /// Ticket to follow the execution by
+        ticket: String,
+        /// Executions ahead of it
+        position: usize,
+    },
 }
 
 /// Catalog configuration for HyperMesh integration
@@ -105,6 +139,12 @@ pub struct CatalogConfig {
     pub hypermesh_address: Option<String>,
     /// TrustChain certificate path
     pub trustchain_cert_path: Option<String>,
+    /// Per-account execution quotas
+    #[serde(default)]
+    pub admission: admission::AdmissionConfig,
+    /// Signatures packages need before they are installed
+    #[serde(default)]
+    pub trust: provenance::TrustPolicy,
 }
 
 impl Default for CatalogConfig {
@@ -117,6 +157,8 @@ impl Default for CatalogConfig {
             documentation: documentation::DocumentationConfig::default(),
             hypermesh_address: Some("catalog.hypermesh.online".to_string()),
             trustchain_cert_path: None,
+            admission: admission::AdmissionConfig::default(),
+            trust: provenance::TrustPolicy::default(),
         }
     }
 }
@@ -127,11 +169,13 @@ impl Catalog {
         let consensus_context = Arc::new(config.consensus);
 
         // Initialize components
+        let offline_mirror = config.registry.mirror.enabled;
         let asset_registry = Arc::new(registry::AssetRegistry::new(config.registry).await?);
         let template_generator = Arc::new(template::CatalogTemplateGenerator::new(config.template)?);
         let asset_validator = Arc::new(validation::AssetValidator::new(config.validation));
         let documentation_generator = Arc::new(documentation::DocumentationGenerator::new(config.documentation)?);
         let version_manager = Arc::new(versioning::VersionManager::new());
+        let admission = Arc::new(admission::AdmissionController::new(config.admission));
 
         // Initialize HyperMesh client
         let hypermesh_address = config.hypermesh_address
@@ -142,8 +186,12 @@ impl Catalog {
             hypermesh_client.set_trustchain_certificate(cert_path);
         }
 
-        // Connect to HyperMesh network
-        hypermesh_client.connect().await?;
+        // Connect to HyperMesh network; an offline mirror serves only imported bundles
+        if offline_mirror {
+            tracing::info!("Registry is an offline mirror; not connecting to {}", hypermesh_client.network_address());
+        } else {
+            hypermesh_client.connect().await?;
+        }
 
         Ok(Self {
             consensus_context,
@@ -153,6 +201,10 @@ impl Catalog {
             documentation_generator,
             version_manager,
             hypermesh_client: Arc::new(tokio::sync::Mutex::new(hypermesh_client)),
+            admission,
+            environments: Arc::new(environments::AssetEnvironments::new()),
+            trust_policy: config.trust,
+            publisher_key: None,
         })
     }
     
@@ -187,7 +239,21 @@ impl Catalog {
     }
     
     /// Publish an asset package
-    pub async fn publish_asset(&self, package: AssetPackage) -> Result<AssetId> {
+    ///
+    /// With a publisher key the package is signed first; without one it is
+    /// published with the signatures it already carries. Either way the
+    /// package must pass the trust policy, so a policy that requires
+    /// signatures refuses unsigned or untrusted packages with a
+    /// [`TrustViolation`].
+    pub async fn publish_asset(&self, mut package: AssetPackage) -> Result<AssetId> {
+        // Refuse untrusted packages before scanning their content
+        if let Some(key) = &self.publisher_key {
+            key.sign(&mut package)?;
+        }
+        let publishers = self.trust_policy.verify(&package, chrono::Utc::now())?;
+        let metadata = &package.spec.metadata;
+        tracing::info!("Publishing {} {} signed by {}", metadata.name, metadata.version, publishers.join(", "));
+        
         // Validate the asset package
         let validation_result = self.asset_validator.validate(&package).await?;
         
@@ -205,8 +271,85 @@ impl Catalog {
     }
     
     /// Install an asset package
+    ///
+    /// The version is installed next to any others of the same asset;
+    /// consumers that have not activated a version get the newest stable one.
+    /// Packages whose hash or signatures fail the trust policy are refused
+    /// with a [`TrustViolation`].
     pub async fn install_asset(&self, id: &AssetId) -> Result<AssetPackage> {
-        self.asset_registry.install(id).await
+        let package = self.fetch_trusted(id).await?;
+        self.environments.install(*id, package.clone());
+        Ok(package)
+    }
+    
+    /// Install an asset package and activate it in one environment
+    pub async fn install_asset_in(&self, id: &AssetId, scope: EnvironmentScope) -> Result<InstalledVersion> {
+        let package = self.fetch_trusted(id).await?;
+        let installed = self.environments.install(*id, package);
+        self.environments.activate(scope, &installed.name, &installed.version)?;
+        Ok(installed)
+    }
+    
+    /// Fetch a package and check it against the trust policy
+    async fn fetch_trusted(&self, id: &AssetId) -> Result<AssetPackage> {
+        let package = self.asset_registry.install(id).await?;
+        let publishers = self.trust_policy.verify(&package, chrono::Utc::now())?;
+        let metadata = &package.spec.metadata;
+        tracing::info!("Verified {} {} signed by {}", metadata.name, metadata.version, publishers.join(", "));
+        Ok(package)
+    }
+    
+    /// Export packages into a signed bundle for an air-gapped cluster
+    ///
+    /// Each package must pass the trust policy here before it is exported.
+    /// The manifest is signed with the publisher key when one is set.
+    pub async fn export_bundle(&self, asset_ids: &[AssetId], path: impl AsRef<std::path::Path>) -> Result<BundleManifest> {
+        let mut packages = Vec::with_capacity(asset_ids.len());
+        for id in asset_ids {
+            packages.push(self.fetch_trusted(id).await?);
+        }
+        let path = path.as_ref().to_path_buf();
+        let key = self.publisher_key.clone();
+        tokio::task::spawn_blocking(move || mirror::write_bundle(&path, &packages, key.as_deref())).await?
+    }
+    
+    /// Import a bundle into the registry's offline mirror
+    ///
+    /// The manifest signatures and every package are checked against the
+    /// trust policy; nothing is imported unless all of them pass.
+    pub async fn import_bundle(&self, path: impl AsRef<std::path::Path>) -> Result<BundleManifest> {
+        let path = path.as_ref().to_path_buf();
+        let policy = self.trust_policy.clone();
+        let bundle = tokio::task::spawn_blocking(move || mirror::read_bundle(&path, &policy, chrono::Utc::now())).await??;
+        self.asset_registry.import_mirrored(bundle.packages).await?;
+        tracing::info!(
+            "Imported {} packages from bundle signed by {}",
+            bundle.manifest.entries.len(),
+            if bundle.signed_by.is_empty() { "nobody".to_string() } else { bundle.signed_by.join(", ") }
+        );
+        Ok(bundle.manifest)
+    }
+    
+    /// Pin an installed version of an asset for an environment
+    ///
+    /// Returns the version the environment used to pin.
+    pub fn activate_asset_version(&self, scope: EnvironmentScope, name: &str, version: &str) -> Result<Option<String>> {
+        self.environments.activate(scope, name, version)
+    }
+    
+    /// Package of an asset as a consumer in `scope` sees it
+    pub fn resolve_asset(&self, scope: &EnvironmentScope, name: &str) -> Option<Arc<AssetPackage>> {
+        self.environments.resolve_package(scope, name).map(|(_, package)| package)
+    }
+    
+    /// Remove an installed version no environment activates
+    pub fn uninstall_asset_version(&self, name: &str, version: &str) -> Result<InstalledVersion> {
+        self.environments.uninstall(name, version)
+    }
+    
+    /// Installed asset versions and their environments
+    pub fn asset_environments(&self) -> Arc<environments::AssetEnvironments> {
+        Arc::clone(&self.environments)
     }
     
     /// Search for assets
@@ -223,6 +366,17 @@ impl Catalog {
         self.template_generator.generate_from_template(template_name, context).await
     }
     
+    /// Render a registered template in memory and validate it without publishing
+    pub async fn dry_run_template(
+        &self,
+        template_name: &str,
+        context: &template::TemplateContext,
+    ) -> Result<template_lint::TemplateDryRun> {
+        let template = self.template_generator.get_template(template_name)
+            .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", template_name))?;
+        self.template_generator.dry_run(template, context, &self.asset_validator).await
+    }
+    
     /// Validate an asset package
     pub async fn validate_asset(&self, package: &AssetPackage) -> Result<validation::ValidationResult> {
         self.asset_validator.validate(package).await
@@ -233,20 +387,112 @@ impl Catalog {
         self.documentation_generator.generate(package).await
     }
 
-    /// Execute asset on HyperMesh infrastructure
+    /// Execute asset on HyperMesh infrastructure for an account
+    ///
+    /// Fails with an [`AdmissionRejection`] when the account is over its
+    /// execution quota.
     pub async fn execute_asset_on_hypermesh(
         &self,
+        account: &str,
         asset_id: &AssetId,
         package: &AssetPackage,
     ) -> Result<hypermesh_integration::CatalogExecutionContext> {
-        let hypermesh_client = self.hypermesh_client.lock().await;
+        match self.submit_asset_execution(account, asset_id, package, AdmissionMode::Reject).await? {
+            ExecutionAdmission::Started(context) => Ok(context),
+            ExecutionAdmission::Queued { ticket, .. } => Err(anyhow::anyhow!("Execution unexpectedly queued as {}", ticket)),
+        }
+    }
 
+    /// Execute asset on HyperMesh, or queue it when the account is over its limits
+    ///
+    /// With [`AdmissionMode::Reject`] an execution over the limits fails with
+    /// the [`AdmissionRejection`]. Requests no quota allows are rejected in
+    /// either mode.
+    pub async fn submit_asset_execution(
+        &self,
+        account: &str,
+        asset_id: &AssetId,
+        package: &AssetPackage,
+        mode: AdmissionMode,
+    ) -> Result<ExecutionAdmission> {
         // Map asset requirements to HyperMesh resources
         let asset_adapter = hypermesh_integration::HyperMeshAssetAdapter::new();
         let resource_requirements = asset_adapter.map_asset_to_resources(package);
+        let demand = admission::ExecutionDemand::of(&resource_requirements);
+
+        let now = chrono::Utc::now();
+        let reservation = match (self.admission.try_admit(account, demand, now), mode) {
+            (Ok(reservation), _) => reservation,
+            (Err(rejection), AdmissionMode::Queue) if rejection.is_retryable() => {
+                let (ticket, position) = self.admission
+                    .enqueue(account, asset_id.clone(), package.clone(), demand, now)?;
+                tracing::info!("Queued execution of {} for {} ({}): {}", asset_id, account, ticket, rejection);
+                return Ok(ExecutionAdmission::Queued { ticket, position });
+            }
+            (Err(rejection), _) => return Err(rejection.into()),
+        };
+
+        let context = self.start_execution(&reservation, None, asset_id, package, resource_requirements).await?;
+        Ok(ExecutionAdmission::Started(context))
+    }
+
+    /// Where a queued execution is
+    pub fn queued_execution_status(&self, ticket: &str) -> Option<TicketStatus> {
+        self.admission.ticket_status(ticket)
+    }
+
+    /// Release a finished execution's quota and start queued executions
+    ///
+    /// Returns the executions that started.
+    pub async fn finish_hypermesh_execution(
+        &self,
+        execution_id: &str,
+    ) -> Result<Vec<hypermesh_integration::CatalogExecutionContext>> {
+        self.hypermesh_client.lock().await.execution_outputs().close(execution_id);
+        self.admission.finish(execution_id);
+
+        let mut started = Vec::new();
+        while let Some((queued, reservation)) = self.admission.next_ready(chrono::Utc::now()) {
+            let asset_adapter = hypermesh_integration::HyperMeshAssetAdapter::new();
+            let resource_requirements = asset_adapter.map_asset_to_resources(&queued.package);
+            match self.start_execution(&reservation, Some(&queued.ticket), &queued.asset_id, &queued.package, resource_requirements).await {
+                Ok(context) => started.push(context),
+                Err(e) => tracing::warn!("Queued execution {} of {} failed to start: {}", queued.ticket, queued.asset_id, e),
+            }
+        }
+        Ok(started)
+    }
+
+    /// Start an admitted execution, giving its slot back if it fails to start
+    async fn start_execution(
+        &self,
+        reservation: &admission::Reservation,
+        ticket: Option<&str>,
+        asset_id: &AssetId,
+        package: &AssetPackage,
+        resource_requirements: Vec<hypermesh_integration::HyperMeshResource>,
+    ) -> Result<hypermesh_integration::CatalogExecutionContext> {
+        let hypermesh_client = self.hypermesh_client.lock().await;
 
         // Execute on HyperMesh
-        hypermesh_client.execute_asset(asset_id, resource_requirements).await
+        let context = match hypermesh_client.execute_asset(asset_id, resource_requirements).await {
+            Ok(context) => context,
+            Err(e) => {
+                self.admission.cancel(reservation);
+                return Err(e);
+            }
+        };
+        self.admission.bind(reservation, &context.execution_id, ticket);
+
+        let metadata = &package.spec.metadata;
+        if let Err(e) = self.asset_registry.usage_analytics()
+            .record(&metadata.name, &metadata.version, UsageKind::Execution, chrono::Utc::now())
+            .await
+        {
+            tracing::warn!("Failed to record execution of {} {}: {}", metadata.name, metadata.version, e);
+        }
+
+        Ok(context)
     }
 
     /// Query execution status on HyperMesh
@@ -258,10 +504,39 @@ impl Catalog {
         hypermesh_client.query_execution(execution_id).await
     }
 
+    /// Follow an execution's stdout and stderr live
+    ///
+    /// Output from `from_sequence` on is replayed first, so a client that
+    /// lost its stream can resume after the last chunk it saw.
+    pub async fn stream_execution_output(
+        &self,
+        execution_id: &str,
+        from_sequence: u64,
+    ) -> Result<futures::stream::BoxStream<'static, OutputChunk>> {
+        self.hypermesh_client.lock().await.stream_output(execution_id, from_sequence)
+    }
+
+    /// Files an execution produced
+    pub async fn execution_artifacts(&self, execution_id: &str) -> Result<Vec<ExecutionArtifact>> {
+        self.hypermesh_client.lock().await.list_artifacts(execution_id)
+    }
+
+    /// Save an execution's artifact to a local file
+    pub async fn download_execution_artifact(
+        &self,
+        execution_id: &str,
+        name: &str,
+        destination: &std::path::Path,
+    ) -> Result<ExecutionArtifact> {
+        let outputs = self.hypermesh_client.lock().await.execution_outputs();
+        outputs.download_artifact(execution_id, name, destination).await
+    }
+
     /// Terminate execution on HyperMesh
     pub async fn terminate_hypermesh_execution(&self, execution_id: &str) -> Result<()> {
-        let hypermesh_client = self.hypermesh_client.lock().await;
-        hypermesh_client.terminate_execution(execution_id).await
+        self.hypermesh_client.lock().await.terminate_execution(execution_id).await?;
+        self.finish_hypermesh_execution(execution_id).await?;
+        Ok(())
     }
 
     /// Get HyperMesh network address
@@ -274,6 +549,7 @@ impl Catalog {
 /// Builder for creating Catalog instances
 pub struct CatalogBuilder {
     config: CatalogConfig,
+    publisher_key: Option<provenance::PublisherKey>,
 }
 
 impl CatalogBuilder {
@@ -281,6 +557,7 @@ impl CatalogBuilder {
     pub fn new() -> Self {
         Self {
             config: CatalogConfig::default(),
+            publisher_key: None,
         }
     }
     
@@ -302,9 +579,23 @@ impl CatalogBuilder {
         self
     }
     
+    /// Set the trust policy installed packages must pass
+    pub fn with_trust_policy(mut self, policy: TrustPolicy) -> Self {
+        self.config.trust = policy;
+        self
+    }
+    
+    /// Sign published packages with a publisher's TrustChain key
+    pub fn with_publisher_key(mut self, key: PublisherKey) -> Self {
+        self.publisher_key = Some(key);
+        self
+    }
+    
     /// Build the Catalog instance
     pub async fn build(self) -> Result<Catalog> {
-        Catalog::new(self.config).await
+        let mut catalog = Catalog::new(self.config).await?;
+        catalog.publisher_key = self.publisher_key.map(Arc::new);
+        Ok(catalog)
     }
 }
 
@@ -324,6 +615,25 @@ mod tests {
         assert!(catalog.is_ok());
     }
     
+    #[tokio::test]
+    async fn test_publish_checks_carried_signatures() {
+        let (root, _) = provenance::tests::publisher("acme", 7);
+        let (_, rogue) = provenance::tests::publisher("rogue", 9);
+        let policy = TrustPolicy { trusted_roots: vec![root], ..Default::default() };
+        let catalog = CatalogBuilder::new().with_trust_policy(policy).build().await.unwrap();
+
+        let mut package = provenance::tests::package().await;
+        let unsigned = catalog.publish_asset(package.clone()).await.unwrap_err();
+        assert!(unsigned.downcast_ref::<TrustViolation>().is_some());
+
+        rogue.sign(&mut package).unwrap();
+        let untrusted = catalog.publish_asset(package).await.unwrap_err();
+        assert!(matches!(
+            untrusted.downcast_ref::<TrustViolation>(),
+            Some(TrustViolation::InsufficientSignatures { valid: 0, .. })
+        ));
+    }
+    
     #[test]
     fn test_catalog_version() {
         assert_eq!(CATALOG_VERSION, "0.1.0");
@@ -0,0 +1,398 @@
+//! Offline Mirrors
+//!
+//! Air-gapped clusters cannot reach catalog.hypermesh.online, so packages are
+//! carried in as bundles: a gzipped tar archive holding every package as JSON
+//! next to a manifest of their names, versions and package hashes. The
+//! exporting publisher signs the manifest's digest. On import that signature
+//! is checked against the local trust policy, each package must hash to its
+//! manifest entry, and each package's own signatures must pass the policy as
+//! they would on a normal install; one bad package refuses the whole bundle.
+//! Imported packages are kept in a mirror directory the registry serves from.
+
+use crate::assets::{AssetPackage, AssetPackageId};
+use crate::provenance::{PackageSignature, PublisherKey, TrustPolicy};
+use crate::registry::{AssetFilters, AssetIndexEntry, AssetSearchResult, SearchQuery, SearchResults};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+/// Bundle layout version written by this build
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SIGNATURES_FILE: &str = "manifest.sig.json";
+
+/// Packages carried by a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle layout version
+    pub format_version: u32,
+    /// When the bundle was exported
+    pub created_at: DateTime<Utc>,
+    /// One entry per package
+    pub entries: Vec<BundleEntry>,
+}
+
+/// A package listed in a bundle manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    /// Package ID
+    pub id: AssetPackageId,
+    /// Asset name
+    pub name: String,
+    /// Asset version
+    pub version: String,
+    /// Asset type
+    pub asset_type: String,
+    /// Package hash the package must match
+    pub package_hash: String,
+    /// Path of the package inside the archive
+    pub file: String,
+}
+
+/// A bundle whose manifest and packages passed the trust policy
+#[derive(Debug, Clone)]
+pub struct VerifiedBundle {
+    /// Bundle manifest
+    pub manifest: BundleManifest,
+    /// Publishers that signed the manifest
+    pub signed_by: Vec<String>,
+    /// Packages in manifest order
+    pub packages: Vec<AssetPackage>,
+}
+
+/// Write packages into a bundle, signing its manifest when a key is given
+pub fn write_bundle(path: &Path, packages: &[AssetPackage], signer: Option<&PublisherKey>) -> Result<BundleManifest> {
+    let mut entries = Vec::with_capacity(packages.len());
+    let mut files = Vec::with_capacity(packages.len() + 2);
+    for package in packages {
+        if !package.verify_integrity()? {
+            return Err(anyhow::anyhow!(
+                "{} {} does not match its package hash",
+                package.spec.metadata.name,
+                package.spec.metadata.version
+            ));
+        }
+        let id = package.get_package_id();
+        let file = format!("packages/{}.json", id);
+        entries.push(BundleEntry {
+            id,
+            name: package.spec.metadata.name.clone(),
+            version: package.spec.metadata.version.clone(),
+            asset_type: package.spec.spec.asset_type.clone(),
+            package_hash: package.package_hash.clone(),
+            file: file.clone(),
+        });
+        files.push((file, serde_json::to_vec_pretty(package)?));
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: Utc::now(),
+        entries,
+    };
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    let signatures: Vec<PackageSignature> = signer
+        .map(|key| key.sign_bundle(&manifest_digest(&manifest_bytes)))
+        .into_iter()
+        .collect();
+    files.insert(0, (SIGNATURES_FILE.to_string(), serde_json::to_vec_pretty(&signatures)?));
+    files.insert(0, (MANIFEST_FILE.to_string(), manifest_bytes));
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create bundle {}", path.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (name, data) in &files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(manifest.created_at.timestamp().max(0) as u64);
+        archive.append_data(&mut header, name, data.as_slice())?;
+    }
+    archive.into_inner()?.finish()?;
+
+    Ok(manifest)
+}
+
+/// Read a bundle and check its manifest and packages against the trust policy
+pub fn read_bundle(path: &Path, policy: &TrustPolicy, now: DateTime<Utc>) -> Result<VerifiedBundle> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open bundle {}", path.display()))?;
+    let mut files = HashMap::new();
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if files.insert(name.clone(), data).is_some() {
+            return Err(anyhow::anyhow!("Bundle holds {} more than once", name));
+        }
+    }
+
+    let manifest_bytes = files.remove(MANIFEST_FILE)
+        .ok_or_else(|| anyhow::anyhow!("Bundle has no {}", MANIFEST_FILE))?;
+    let signatures: Vec<PackageSignature> = match files.remove(SIGNATURES_FILE) {
+        Some(data) => serde_json::from_slice(&data).context("Bundle signatures do not parse")?,
+        None => Vec::new(),
+    };
+    let signed_by = policy.verify_bundle(&signatures, &manifest_digest(&manifest_bytes), now)
+        .context("Bundle manifest failed the trust policy")?;
+
+    let manifest: BundleManifest = serde_json::from_slice(&manifest_bytes).context("Bundle manifest does not parse")?;
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Bundle format {} is newer than the supported {}",
+            manifest.format_version,
+            BUNDLE_FORMAT_VERSION
+        ));
+    }
+
+    let mut packages = Vec::with_capacity(manifest.entries.len());
+    for entry in &manifest.entries {
+        let data = files.get(&entry.file)
+            .ok_or_else(|| anyhow::anyhow!("Bundle is missing {} for {} {}", entry.file, entry.name, entry.version))?;
+        let package: AssetPackage = serde_json::from_slice(data)
+            .with_context(|| format!("{} does not parse as a package", entry.file))?;
+        if package.package_hash != entry.package_hash || package.get_package_id() != entry.id {
+            return Err(anyhow::anyhow!("{} is not the package the manifest lists", entry.file));
+        }
+        policy.verify(&package, now)
+            .with_context(|| format!("{} {} failed the trust policy", entry.name, entry.version))?;
+        packages.push(package);
+    }
+
+    Ok(VerifiedBundle { manifest, signed_by, packages })
+}
+
+fn manifest_digest(manifest: &[u8]) -> String {
+    hex::encode(Sha256::digest(manifest))
+}
+
+/// Imported packages kept on local disk
+pub struct MirrorStore {
+    dir: PathBuf,
+    packages: RwLock<HashMap<AssetPackageId, AssetPackage>>,
+}
+
+impl MirrorStore {
+    /// Open a mirror directory, loading the packages imported into it earlier
+    pub async fn open(dir: PathBuf) -> Result<Self> {
+        tokio::fs::create_dir_all(&dir).await?;
+        let mut packages = HashMap::new();
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let data = tokio::fs::read(&path).await?;
+            let package: AssetPackage = serde_json::from_slice(&data)
+                .with_context(|| format!("Mirrored package {} does not parse", path.display()))?;
+            packages.insert(package.get_package_id(), package);
+        }
+        Ok(Self { dir, packages: RwLock::new(packages) })
+    }
+
+    /// Add a package, replacing an earlier import of the same package
+    pub async fn insert(&self, package: AssetPackage) -> Result<AssetPackageId> {
+        let id = package.get_package_id();
+        let path = self.dir.join(format!("{}.json", id));
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&package)?).await
+            .with_context(|| format!("Failed to write mirrored package {}", path.display()))?;
+        self.packages.write().await.insert(id, package);
+        Ok(id)
+    }
+
+    /// Package by ID
+    pub async fn get(&self, id: &AssetPackageId) -> Option<AssetPackage> {
+        self.packages.read().await.get(id).cloned()
+    }
+
+    /// Number of mirrored packages
+    pub async fn len(&self) -> usize {
+        self.packages.read().await.len()
+    }
+
+    /// Whether nothing has been imported
+    pub async fn is_empty(&self) -> bool {
+        self.packages.read().await.is_empty()
+    }
+
+    /// Search mirrored packages by name, description, tags and keywords
+    pub async fn search(&self, query: &SearchQuery) -> SearchResults {
+        let start_time = std::time::Instant::now();
+        let terms: Vec<String> = query.query.split_whitespace().map(|s| s.to_lowercase()).collect();
+
+        let packages = self.packages.read().await;
+        let mut results: Vec<AssetSearchResult> = packages
+            .iter()
+            .filter(|(_, package)| {
+                let metadata = &package.spec.metadata;
+                query.asset_type.as_ref().map_or(true, |t| *t == package.spec.spec.asset_type)
+                    && query.tags.iter().all(|tag| metadata.tags.contains(tag))
+                    && query.author.as_ref().map_or(true, |a| metadata.author.as_ref() == Some(a))
+            })
+            .filter_map(|(id, package)| {
+                let entry = index_entry(*id, package, &self.dir);
+                if terms.is_empty() {
+                    return Some(AssetSearchResult { asset: entry, score: 1.0, highlights: vec![] });
+                }
+                let text = searchable_text(&entry);
+                let matched: Vec<String> = terms.iter().filter(|t| text.contains(t.as_str())).cloned().collect();
+                if matched.is_empty() {
+                    return None;
+                }
+                Some(AssetSearchResult {
+                    score: matched.len() as f64 / terms.len() as f64,
+                    asset: entry,
+                    highlights: matched,
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.asset.name.cmp(&b.asset.name))
+                .then_with(|| a.asset.version.cmp(&b.asset.version))
+        });
+
+        let total_count = results.len();
+        let assets = results.into_iter().skip(query.offset).take(query.limit).collect();
+        SearchResults {
+            assets,
+            total_count,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+            query: query.query.clone(),
+        }
+    }
+
+    /// Mirrored packages matching the filters
+    pub async fn list(&self, filters: &AssetFilters) -> Vec<AssetIndexEntry> {
+        let packages = self.packages.read().await;
+        let mut entries: Vec<AssetIndexEntry> = packages
+            .iter()
+            .map(|(id, package)| index_entry(*id, package, &self.dir))
+            .filter(|entry| {
+                filters.asset_type.as_ref().map_or(true, |t| entry.asset_type == *t)
+                    && filters.tags.iter().all(|tag| entry.tags.contains(tag))
+                    && (!filters.verified_only || entry.verified)
+                    && filters.registry.as_ref().map_or(true, |r| entry.registry == *r)
+            })
+            .filter(|entry| {
+                filters.author.as_ref().map_or(true, |author| {
+                    packages.get(&entry.id).and_then(|p| p.spec.metadata.author.as_ref()) == Some(author)
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+        entries
+    }
+}
+
+fn index_entry(id: AssetPackageId, package: &AssetPackage, dir: &Path) -> AssetIndexEntry {
+    let metadata = &package.spec.metadata;
+    let content = &package.content;
+    let size = content.main_content.len()
+        + content.file_contents.values().map(String::len).sum::<usize>()
+        + content.binary_contents.values().map(Vec::len).sum::<usize>();
+    AssetIndexEntry {
+        id,
+        name: metadata.name.clone(),
+        version: metadata.version.clone(),
+        asset_type: package.spec.spec.asset_type.clone(),
+        description: metadata.description.clone(),
+        tags: metadata.tags.clone(),
+        keywords: metadata.keywords.clone(),
+        location: dir.join(format!("{}.json", id)).to_string_lossy().into_owned(),
+        size: size as u64,
+        hash: package.package_hash.clone(),
+        published_at: package.created_at,
+        updated_at: package.updated_at,
+        registry: "mirror".to_string(),
+        rating: 0.0,
+        download_count: 0,
+        install_count: 0,
+        execution_count: 0,
+        popularity: 0.0,
+        verified: !package.signatures.is_empty(),
+    }
+}
+
+fn searchable_text(entry: &AssetIndexEntry) -> String {
+    let mut text = vec![entry.name.clone(), entry.asset_type.clone()];
+    text.extend(entry.description.clone());
+    text.extend(entry.tags.iter().cloned());
+    text.extend(entry.keywords.iter().cloned());
+    text.join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provenance::tests::{package, publisher};
+    use crate::registry::SortCriteria;
+
+    #[tokio::test]
+    async fn test_bundle_round_trip_into_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let (root, acme) = publisher("acme", 7);
+        let (_, rogue) = publisher("rogue", 9);
+        let policy = TrustPolicy { trusted_roots: vec![root], ..Default::default() };
+        let now = Utc::now();
+
+        let mut solver = package().await;
+        acme.sign(&mut solver).unwrap();
+
+        let bundle = dir.path().join("solver.bundle.tar.gz");
+        let manifest = write_bundle(&bundle, &[solver.clone()], Some(&acme)).unwrap();
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].id, solver.get_package_id());
+
+        let verified = read_bundle(&bundle, &policy, now).unwrap();
+        assert_eq!(verified.signed_by, vec!["acme".to_string()]);
+        assert_eq!(verified.manifest, manifest);
+        assert_eq!(verified.packages[0].package_hash, solver.package_hash);
+
+        // A manifest signed by an untrusted root, or not signed at all, is refused
+        let rogue_bundle = dir.path().join("rogue.bundle.tar.gz");
+        write_bundle(&rogue_bundle, &[solver.clone()], Some(&rogue)).unwrap();
+        assert!(read_bundle(&rogue_bundle, &policy, now).is_err());
+        let unsigned = dir.path().join("unsigned.bundle.tar.gz");
+        write_bundle(&unsigned, &[solver.clone()], None).unwrap();
+        assert!(read_bundle(&unsigned, &policy, now).is_err());
+
+        // Imports survive reopening the mirror and are searchable offline
+        let mirror_dir = dir.path().join("mirror");
+        let mirror = MirrorStore::open(mirror_dir.clone()).await.unwrap();
+        for package in verified.packages {
+            mirror.insert(package).await.unwrap();
+        }
+        let mirror = MirrorStore::open(mirror_dir).await.unwrap();
+        assert_eq!(mirror.len().await, 1);
+        assert!(mirror.get(&solver.get_package_id()).await.is_some());
+
+        let mut query = SearchQuery {
+            query: "solver".to_string(),
+            asset_type: None,
+            tags: vec![],
+            author: None,
+            version: None,
+            date_range: None,
+            sort_by: SortCriteria::Relevance,
+            limit: 10,
+            offset: 0,
+        };
+        assert_eq!(mirror.search(&query).await.total_count, 1);
+        query.query = "renderer".to_string();
+        assert_eq!(mirror.search(&query).await.total_count, 0);
+    }
+}
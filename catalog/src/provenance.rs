@@ -0,0 +1,383 @@
+//! Package Provenance
+//!
+//! Publishers sign a package's hash with the Ed25519 key of their TrustChain
+//! certificate, and the signatures travel with the package. Before a package
+//! fetched from the registry is installed, its hash is recomputed from its
+//! content and each signature is checked against the local trust policy:
+//! the certificate must be issued by a trusted TrustChain root and valid at
+//! the time of the check, its subject must be an allowed publisher, and the
+//! signature must be over the recomputed hash. A package without enough
+//! distinct publishers passing those checks is refused.
+
+use crate::assets::AssetPackage;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use x509_parser::oid_registry::OID_SIG_ED25519;
+use x509_parser::prelude::*;
+
+/// Prefix of every signed message, so a package signature means nothing elsewhere
+const SIGNING_CONTEXT: &[u8] = b"catalog-package-v1:";
+
+/// Prefix of signed bundle manifests, kept apart from package signatures
+const BUNDLE_CONTEXT: &[u8] = b"catalog-bundle-v1:";
+
+/// A publisher's signature over a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageSignature {
+    /// Publisher's TrustChain certificate (DER)
+    pub certificate: Vec<u8>,
+    /// Package hash that was signed
+    pub package_hash: String,
+    /// Ed25519 signature
+    pub signature: Vec<u8>,
+    /// When the package was signed
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Why a package was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TrustViolation {
+    /// The content does not hash to the recorded package hash
+    #[error("package content hashes to {actual}, not its recorded hash {expected}")]
+    HashMismatch {
+        /// Recorded package hash
+        expected: String,
+        /// Hash of the content
+        actual: String,
+    },
+    /// Too few allowed publishers signed the package
+    #[error("package has {valid} of {required} required publisher signatures{}", rejected_reasons(.rejected))]
+    InsufficientSignatures {
+        /// Distinct publishers required
+        required: usize,
+        /// Distinct publishers whose signatures passed
+        valid: usize,
+        /// Why each other signature was rejected
+        rejected: Vec<String>,
+    },
+    /// A certificate or key could not be used
+    #[error("{0}")]
+    BadCertificate(String),
+}
+
+fn rejected_reasons(rejected: &[String]) -> String {
+    if rejected.is_empty() {
+        String::new()
+    } else {
+        format!(" (rejected: {})", rejected.join("; "))
+    }
+}
+
+/// A publisher's TrustChain certificate with its signing key
+pub struct PublisherKey {
+    certificate: Vec<u8>,
+    signing_key: SigningKey,
+    publisher: String,
+}
+
+impl PublisherKey {
+    /// Pair a DER certificate with the Ed25519 secret key it certifies
+    pub fn new(certificate: Vec<u8>, secret_key: &[u8; 32]) -> Result<Self, TrustViolation> {
+        let signing_key = SigningKey::from_bytes(secret_key);
+        let (_, cert) = X509Certificate::from_der(&certificate)
+            .map_err(|e| TrustViolation::BadCertificate(format!("publisher certificate does not parse: {}", e)))?;
+        let (publisher, public_key) = certified_key(&cert).map_err(TrustViolation::BadCertificate)?;
+        if public_key != signing_key.verifying_key() {
+            return Err(TrustViolation::BadCertificate(format!(
+                "certificate of '{}' does not certify this signing key", publisher
+            )));
+        }
+        Ok(Self { certificate, signing_key, publisher })
+    }
+
+    /// Publisher named by the certificate
+    pub fn publisher(&self) -> &str {
+        &self.publisher
+    }
+
+    /// Sign the package's current content, replacing an earlier signature of this publisher
+    pub fn sign(&self, package: &mut AssetPackage) -> anyhow::Result<()> {
+        package.compute_hash()?;
+        let signature = self.signature_over(SIGNING_CONTEXT, &package.package_hash);
+        package.signatures.retain(|existing| existing.certificate != self.certificate);
+        package.signatures.push(signature);
+        Ok(())
+    }
+
+    /// Sign the digest of an offline bundle's manifest
+    pub(crate) fn sign_bundle(&self, manifest_hash: &str) -> PackageSignature {
+        self.signature_over(BUNDLE_CONTEXT, manifest_hash)
+    }
+
+    fn signature_over(&self, context: &[u8], hash: &str) -> PackageSignature {
+        let signature = self.signing_key.sign(&signed_message(context, hash));
+        PackageSignature {
+            certificate: self.certificate.clone(),
+            package_hash: hash.to_string(),
+            signature: signature.to_bytes().to_vec(),
+            signed_at: Utc::now(),
+        }
+    }
+}
+
+/// Which signed packages may be installed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustPolicy {
+    /// TrustChain roots (PEM) that must have issued publisher certificates
+    #[serde(default)]
+    pub trusted_roots: Vec<String>,
+    /// Publishers whose signatures count; empty accepts any publisher a trusted root certified
+    #[serde(default)]
+    pub allowed_publishers: Vec<String>,
+    /// Distinct publishers that must have signed; zero accepts unsigned packages
+    pub min_signatures: usize,
+}
+
+impl Default for TrustPolicy {
+    fn default() -> Self {
+        Self {
+            trusted_roots: Vec::new(),
+            allowed_publishers: Vec::new(),
+            min_signatures: 1,
+        }
+    }
+}
+
+impl TrustPolicy {
+    /// Check a package's hash and signatures, returning the publishers that signed it
+    pub fn verify(&self, package: &AssetPackage, now: DateTime<Utc>) -> Result<Vec<String>, TrustViolation> {
+        let actual = package.content_hash().map_err(|e| TrustViolation::HashMismatch {
+            expected: package.package_hash.clone(),
+            actual: format!("<unhashable: {}>", e),
+        })?;
+        if actual != package.package_hash {
+            return Err(TrustViolation::HashMismatch { expected: package.package_hash.clone(), actual });
+        }
+        self.check_signatures(&package.signatures, &package.package_hash, SIGNING_CONTEXT, now)
+    }
+
+    /// Check the signatures over an offline bundle's manifest, returning the publishers that signed it
+    pub(crate) fn verify_bundle(
+        &self,
+        signatures: &[PackageSignature],
+        manifest_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<String>, TrustViolation> {
+        self.check_signatures(signatures, manifest_hash, BUNDLE_CONTEXT, now)
+    }
+
+    fn check_signatures(
+        &self,
+        signatures: &[PackageSignature],
+        hash: &str,
+        context: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<String>, TrustViolation> {
+        let roots = self.root_certificates()?;
+        let roots = roots
+            .iter()
+            .map(|der| X509Certificate::from_der(der).map(|(_, cert)| cert))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TrustViolation::BadCertificate(format!("trusted root does not parse: {}", e)))?;
+        let now = ASN1Time::from_timestamp(now.timestamp())
+            .map_err(|e| TrustViolation::BadCertificate(format!("time is out of certificate range: {}", e)))?;
+
+        let mut publishers = BTreeSet::new();
+        let mut rejected = Vec::new();
+        for signature in signatures {
+            match self.check_signature(signature, hash, context, &roots, now) {
+                Ok(publisher) => {
+                    publishers.insert(publisher);
+                }
+                Err(reason) => rejected.push(reason),
+            }
+        }
+
+        if publishers.len() < self.min_signatures {
+            return Err(TrustViolation::InsufficientSignatures {
+                required: self.min_signatures,
+                valid: publishers.len(),
+                rejected,
+            });
+        }
+        Ok(publishers.into_iter().collect())
+    }
+
+    fn root_certificates(&self) -> Result<Vec<Vec<u8>>, TrustViolation> {
+        self.trusted_roots
+            .iter()
+            .map(|root| {
+                x509_parser::pem::parse_x509_pem(root.as_bytes())
+                    .map(|(_, pem)| pem.contents)
+                    .map_err(|e| TrustViolation::BadCertificate(format!("trusted root is not a PEM certificate: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Publisher of a signature that passes the policy, or why it does not
+    fn check_signature(
+        &self,
+        signature: &PackageSignature,
+        package_hash: &str,
+        context: &[u8],
+        roots: &[X509Certificate<'_>],
+        now: ASN1Time,
+    ) -> Result<String, String> {
+        let (_, cert) = X509Certificate::from_der(&signature.certificate)
+            .map_err(|e| format!("certificate does not parse: {}", e))?;
+        let (publisher, public_key) = certified_key(&cert)?;
+
+        if signature.package_hash != package_hash {
+            return Err(format!("'{}' signed hash {}, not the package's", publisher, signature.package_hash));
+        }
+        if !cert.validity().is_valid_at(now) {
+            return Err(format!("certificate of '{}' is not valid now", publisher));
+        }
+        let issued_by_root = roots.iter().any(|root| {
+            root.subject() == cert.issuer()
+                && root.validity().is_valid_at(now)
+                && cert.verify_signature(Some(root.public_key())).is_ok()
+        });
+        if !issued_by_root {
+            return Err(format!("certificate of '{}' is not issued by a trusted root", publisher));
+        }
+        if !self.allowed_publishers.is_empty() && !self.allowed_publishers.contains(&publisher) {
+            return Err(format!("'{}' is not an allowed publisher", publisher));
+        }
+
+        let signature_bytes = Signature::from_slice(&signature.signature)
+            .map_err(|_| format!("signature of '{}' is malformed", publisher))?;
+        public_key
+            .verify(&signed_message(context, package_hash), &signature_bytes)
+            .map_err(|_| format!("signature of '{}' does not match the package", publisher))?;
+        Ok(publisher)
+    }
+}
+
+/// Subject common name and Ed25519 key of a certificate
+fn certified_key(cert: &X509Certificate<'_>) -> Result<(String, VerifyingKey), String> {
+    let publisher = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|name| name.as_str().ok())
+        .ok_or_else(|| "certificate has no subject common name".to_string())?
+        .to_string();
+    let spki = cert.public_key();
+    if spki.algorithm.algorithm != OID_SIG_ED25519 {
+        return Err(format!("certificate of '{}' does not hold an Ed25519 key", publisher));
+    }
+    let key = <[u8; 32]>::try_from(spki.subject_public_key.data.as_ref())
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| format!("certificate of '{}' holds a malformed Ed25519 key", publisher))?;
+    Ok((publisher, key))
+}
+
+fn signed_message(context: &[u8], hash: &str) -> Vec<u8> {
+    [context, hash.as_bytes()].concat()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair, PKCS_ED25519};
+
+    pub(crate) async fn package() -> AssetPackage {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("asset.yaml");
+        std::fs::write(&path, r#"
+apiVersion: "catalog.v1"
+kind: "Asset"
+metadata:
+  name: "solver"
+  version: "1.0.0"
+  tags: []
+  keywords: []
+spec:
+  type: "lua-script"
+  content: { main: "", files: [], inline: "return 42", binary: [], templates: [] }
+  security:
+    consensus_required: false
+    certificate_pinning: false
+    hash_validation: "sha256"
+    sandbox_level: "standard"
+    allowed_syscalls: []
+    network_access: { enabled: false, allowed_domains: [], allowed_ports: [], require_tls: true }
+    file_access: { level: "read_only", allowed_paths: [], denied_paths: [], allow_temp: false }
+    permissions: []
+  resources:
+    cpu_limit: "1000m"
+    memory_limit: "1Gi"
+    execution_timeout: "30s"
+    gpu_required: false
+    hardware_requirements: []
+  execution:
+    delegation_strategy: "nearest_node"
+    minimum_consensus: 1
+    retry_policy: "none"
+    priority: "normal"
+    timeout_config: { execution: "30s", network: "10s", io: "5s" }
+    scheduling: { timing: "immediate", allocation_strategy: "best_fit", node_affinity: [], anti_affinity: [] }
+  dependencies: []
+  environment: { LANG: "C", MODE: "fast" }
+"#).unwrap();
+        AssetPackage::from_yaml(&path).await.unwrap()
+    }
+
+    /// A TrustChain root and a publisher key it certified
+    pub(crate) fn publisher(name: &str, seed: u8) -> (String, PublisherKey) {
+        let root_key = KeyPair::generate_for(&PKCS_ED25519).unwrap();
+        let mut root_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        root_params.distinguished_name.push(DnType::CommonName, "TrustChain Root");
+        root_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let root = root_params.self_signed(&root_key).unwrap();
+
+        // PKCS#8 wrapping of a raw Ed25519 seed
+        let secret = [seed; 32];
+        let pkcs8 = [&hex::decode("302e020100300506032b657004220420").unwrap()[..], &secret].concat();
+        let key = KeyPair::try_from(pkcs8.as_slice()).unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.not_after = rcgen::date_time_ymd(2100, 1, 1);
+        let cert = params.signed_by(&key, &root, &root_key).unwrap();
+
+        (root.pem(), PublisherKey::new(cert.der().to_vec(), &secret).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_signed_packages_against_the_trust_policy() {
+        let (root, acme) = publisher("acme", 7);
+        let (_, rogue) = publisher("rogue", 9);
+        let policy = TrustPolicy { trusted_roots: vec![root], ..Default::default() };
+        let now = Utc::now();
+
+        let mut package = package().await;
+        assert!(matches!(
+            policy.verify(&package, now),
+            Err(TrustViolation::InsufficientSignatures { required: 1, valid: 0, .. })
+        ));
+
+        // Signatures survive the trip through the registry's serialization
+        acme.sign(&mut package).unwrap();
+        rogue.sign(&mut package).unwrap();
+        let fetched: AssetPackage = serde_json::from_slice(&serde_json::to_vec(&package).unwrap()).unwrap();
+        assert_eq!(policy.verify(&fetched, now).unwrap(), vec!["acme".to_string()]);
+
+        let two = TrustPolicy { min_signatures: 2, ..policy.clone() };
+        assert!(two.verify(&fetched, now).is_err());
+        let others = TrustPolicy { allowed_publishers: vec!["globex".to_string()], ..policy.clone() };
+        assert!(others.verify(&fetched, now).is_err());
+        assert!(policy.verify(&fetched, now + chrono::Duration::days(365 * 100)).is_err());
+
+        let mut tampered = fetched.clone();
+        tampered.content.main_content = "os.execute('curl evil')".to_string();
+        assert!(matches!(policy.verify(&tampered, now), Err(TrustViolation::HashMismatch { .. })));
+
+        // Rehashing the tampered content does not help without a new signature
+        tampered.compute_hash().unwrap();
+        assert!(policy.verify(&tampered, now).is_err());
+    }
+}
@@ -5,6 +5,8 @@
 
 use crate::assets::*;
 use crate::hypermesh_bridge::{HyperMeshAssetRegistry, BridgeConfig};
+use crate::mirror::MirrorStore;
+use crate::usage::{AssetUsage, UsageAnalytics, UsageKind, UsageStore};
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -22,6 +24,8 @@ pub struct AssetRegistry {
     hypermesh_registry: Arc<HyperMeshAssetRegistry>,
     /// Asset cache directory (for compatibility)
     cache_dir: PathBuf,
+    /// Packages imported from offline bundles
+    mirror: MirrorStore,
 }
 
 /// Registry configuration
@@ -39,6 +43,27 @@ pub struct RegistryConfig {
     pub verification: VerificationConfig,
     /// Download and upload settings
     pub network: NetworkConfig,
+    /// Offline mirror of imported bundles
+    #[serde(default)]
+    pub mirror: MirrorConfig,
+}
+
+/// Offline mirror configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorConfig {
+    /// Serve only imported packages and never contact remote registries
+    pub enabled: bool,
+    /// Directory imported packages are kept in
+    pub dir: String,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "~/.catalog/mirror".to_string(),
+        }
+    }
 }
 
 /// Remote registry configuration
@@ -147,6 +172,15 @@ pub struct AssetIndexEntry {
     pub rating: f64,
     /// Download count
     pub download_count: u64,
+    /// Install count
+    #[serde(default)]
+    pub install_count: u64,
+    /// Execution count on HyperMesh
+    #[serde(default)]
+    pub execution_count: u64,
+    /// Recent usage with older days decayed; see [`crate::usage`]
+    #[serde(default)]
+    pub popularity: f64,
     /// Whether asset is verified
     pub verified: bool,
 }
@@ -355,6 +389,7 @@ impl Default for RegistryConfig {
                 retry_attempts: 3,
                 user_agent: "Catalog-Registry/1.0".to_string(),
             },
+            mirror: MirrorConfig::default(),
         }
     }
 }
@@ -364,6 +399,8 @@ impl AssetRegistry {
     pub async fn new(config: RegistryConfig) -> Result<Self> {
         let cache_dir = shellexpand::tilde(&config.cache_dir).into_owned().into();
         tokio::fs::create_dir_all(&cache_dir).await?;
+        let mirror = MirrorStore::open(shellexpand::tilde(&config.mirror.dir).into_owned().into()).await
+            .context("Failed to open registry mirror")?;
 
         // Get HyperMesh AssetManager instance
         let asset_manager = Arc::new(hypermesh::assets::core::AssetManager::new());
@@ -386,21 +423,71 @@ impl AssetRegistry {
             config,
             hypermesh_registry,
             cache_dir,
+            mirror,
         })
     }
     
+    /// Whether the registry serves only imported packages
+    pub fn is_mirror(&self) -> bool {
+        self.config.mirror.enabled
+    }
+    
     /// Publish an asset package through HyperMesh
     pub async fn publish(&self, package: AssetPackage) -> Result<AssetPackageId> {
+        if self.is_mirror() {
+            return Err(anyhow::anyhow!("Registry is an offline mirror; import a bundle instead of publishing"));
+        }
         // Delegate to HyperMesh-integrated registry
         self.hypermesh_registry.publish(package).await
     }
     
-    /// Install an asset package from HyperMesh
+    /// Install an asset package, from the mirror if it was imported and from HyperMesh otherwise
     pub async fn install(&self, id: &AssetPackageId) -> Result<AssetPackage> {
+        if let Some(package) = self.mirror.get(id).await {
+            self.record_usage(id, UsageKind::Install).await;
+            return Ok(package);
+        }
+        if self.is_mirror() {
+            return Err(anyhow::anyhow!("Asset package {} has not been imported into the mirror", id));
+        }
         // Delegate to HyperMesh-integrated registry
         self.hypermesh_registry.install(id).await
     }
     
+    /// Add packages from a verified offline bundle to the mirror
+    pub async fn import_mirrored(&self, packages: Vec<AssetPackage>) -> Result<Vec<AssetPackageId>> {
+        let mut ids = Vec::with_capacity(packages.len());
+        for package in packages {
+            ids.push(self.mirror.insert(package).await?);
+        }
+        Ok(ids)
+    }
+    
+    /// Packages imported from offline bundles
+    pub fn mirror(&self) -> &MirrorStore {
+        &self.mirror
+    }
+    
+    /// Count a usage event against a published package
+    pub async fn record_usage(&self, id: &AssetPackageId, kind: UsageKind) {
+        self.hypermesh_registry.record_usage(id, kind).await
+    }
+    
+    /// Download, install and execution history of every version of an asset
+    pub async fn asset_usage(&self, name: &str) -> Result<AssetUsage> {
+        self.hypermesh_registry.asset_usage(name).await
+    }
+    
+    /// Keep usage records in another store, such as the state layer
+    pub fn set_usage_store(&self, store: Arc<dyn UsageStore>) {
+        self.hypermesh_registry.usage_analytics().set_store(store);
+    }
+    
+    /// Usage analytics shared with the HyperMesh registry
+    pub fn usage_analytics(&self) -> Arc<UsageAnalytics> {
+        self.hypermesh_registry.usage_analytics()
+    }
+    
     /* Removed - now handled by HyperMesh bridge
     /// Generate search keywords for an asset
     fn generate_keywords(&self, package: &AssetPackage) -> Vec<String> {
@@ -586,16 +673,28 @@ impl AssetRegistry {
 #[async_trait::async_trait]
 impl AssetDiscovery for AssetRegistry {
     async fn search(&self, query: &SearchQuery) -> Result<SearchResults> {
+        if self.is_mirror() {
+            return Ok(self.mirror.search(query).await);
+        }
         // Delegate to HyperMesh-integrated registry
         self.hypermesh_registry.search(query).await
     }
     
     async fn get_asset(&self, id: &AssetPackageId) -> Result<Option<AssetPackage>> {
+        if let Some(package) = self.mirror.get(id).await {
+            return Ok(Some(package));
+        }
+        if self.is_mirror() {
+            return Ok(None);
+        }
         // Delegate to HyperMesh-integrated registry
         self.hypermesh_registry.get_asset(id).await
     }
     
     async fn list_assets(&self, filters: &AssetFilters) -> Result<Vec<AssetIndexEntry>> {
+        if self.is_mirror() {
+            return Ok(self.mirror.list(filters).await);
+        }
         // Delegate to HyperMesh-integrated registry
         self.hypermesh_registry.list_assets(filters).await
     }
@@ -852,6 +951,10 @@ mod tests {
             indexing: IndexingConfig::default(),
             verification: VerificationConfig::default(),
             network: NetworkConfig::default(),
+            mirror: MirrorConfig {
+                enabled: false,
+                dir: temp_dir.path().join("mirror").to_string_lossy().to_string(),
+            },
         };
         
         let registry = AssetRegistry::new(config).await.unwrap();
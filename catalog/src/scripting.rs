@@ -5,7 +5,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Scripting engine trait
 #[async_trait::async_trait]
@@ -227,6 +227,20 @@ impl LuaEngine {
             lua.globals().set("os", mlua::Nil)?;
         }
         
+        // Enforce resource limits; the hook aborts scripts that overrun their time
+        if let Some(limit) = context.max_memory_bytes.or(self.config.memory_limit) {
+            lua.set_memory_limit(limit as usize)?;
+        }
+        if let Some(timeout_secs) = context.timeout_secs {
+            let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+            lua.set_hook(mlua::HookTriggers::new().every_nth_instruction(10_000), move |_, _| {
+                if Instant::now() > deadline {
+                    return Err(mlua::Error::RuntimeError("script timed out".to_string()));
+                }
+                Ok(())
+            });
+        }
+        
         // Set global variables from context
         for (key, value) in &context.globals {
             let lua_value = self.json_to_lua_value(&lua, value)?;
@@ -465,6 +479,17 @@ mod tests {
         assert_eq!(result.return_value, Some(serde_json::Value::Number(serde_json::Number::from(84))));
     }
     
+    #[tokio::test]
+    async fn test_lua_engine_timeout() {
+        let engine = LuaEngine::new(LuaEngineConfig::default());
+        let context = ScriptContext { timeout_secs: Some(1), ..Default::default() };
+        
+        let result = engine.execute("while true do end", context).await.unwrap();
+        
+        assert!(!result.success);
+        assert!(result.errors[0].contains("timed out"));
+    }
+    
     #[tokio::test]
     async fn test_lua_syntax_validation() {
         let config = LuaEngineConfig::default();
@@ -740,17 +740,7 @@ return {{script_name}}
         self.validate_context(template, &context)?;
         
         // Prepare template context
-        let mut template_context = context.parameters.clone();
-        template_context.insert("asset_name".to_string(), serde_json::Value::String(context.asset_name.clone()));
-        template_context.insert("asset_version".to_string(), serde_json::Value::String(context.asset_version.clone()));
-        
-        if let Some(author) = &context.author {
-            template_context.insert("author".to_string(), serde_json::Value::String(author.clone()));
-        }
-        
-        for (key, value) in &context.metadata {
-            template_context.insert(key.clone(), value.clone());
-        }
+        let template_context = self.render_values(&context);
         
         // Generate files
         let mut generated_files = Vec::new();
@@ -826,6 +816,33 @@ return {{script_name}}
         })
     }
     
+    /// Values templates are rendered with: parameters, asset details and metadata
+    pub(crate) fn render_values(&self, context: &TemplateContext) -> HashMap<String, serde_json::Value> {
+        let mut values = context.parameters.clone();
+        values.insert("asset_name".to_string(), serde_json::Value::String(context.asset_name.clone()));
+        values.insert("asset_version".to_string(), serde_json::Value::String(context.asset_version.clone()));
+        
+        if let Some(author) = &context.author {
+            values.insert("author".to_string(), serde_json::Value::String(author.clone()));
+        }
+        
+        for (key, value) in &context.metadata {
+            values.insert(key.clone(), value.clone());
+        }
+        
+        values
+    }
+    
+    /// Template engine with the built-in helpers registered
+    pub(crate) fn handlebars(&self) -> &Handlebars<'static> {
+        &self.handlebars
+    }
+    
+    /// Whether a helper with this name is registered
+    pub(crate) fn has_helper(&self, name: &str) -> bool {
+        self.handlebars.get_helper(name).is_some()
+    }
+    
     /// Execute post-generation action
     async fn execute_post_action(&self, action: &PostGenerationAction, context: &TemplateContext) -> Result<()> {
         match action {
@@ -871,7 +888,7 @@ return {{script_name}}
     }
     
     /// Validate template context
-    fn validate_context(&self, template: &TemplateDefinition, context: &TemplateContext) -> Result<()> {
+    pub(crate) fn validate_context(&self, template: &TemplateDefinition, context: &TemplateContext) -> Result<()> {
         for param in &template.parameters {
             if param.required && !context.parameters.contains_key(&param.name) {
                 return Err(anyhow::anyhow!(
@@ -0,0 +1,407 @@
+//! Template Linting and Dry-Run Rendering
+//!
+//! Checks template definitions for problems that would only surface at
+//! generation time, and renders a template with given parameters entirely in
+//! memory so the asset validator can run against the result without anything
+//! being written or published. Both return structured diagnostics so CI can
+//! gate on them.
+
+use crate::assets::{AssetPackage, AssetSpec, ErrorSeverity, TemplateParameter};
+use crate::template::{CatalogTemplateGenerator, PostGenerationAction, TemplateContext, TemplateDefinition};
+use crate::validation::{AssetValidator, SecuritySeverity, ValidationResult};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// File every template must produce; generation loads the package from it
+pub const ASSET_SPEC_FILE: &str = "asset.yaml";
+
+/// Variables generation provides besides the template parameters
+const BUILTIN_VARIABLES: &[&str] = &["asset_name", "asset_version", "author", "this", "else"];
+
+/// Parameter types a template may declare
+const PARAMETER_TYPES: &[&str] = &["string", "number", "boolean", "array", "object"];
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    /// Informational note
+    Info,
+    /// Likely mistake that does not stop generation
+    Warning,
+    /// Generation or validation would fail
+    Error,
+}
+
+/// One finding from linting or a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateDiagnostic {
+    /// Severity of the finding
+    pub severity: DiagnosticSeverity,
+    /// Stable machine-readable code, e.g. `undeclared-variable`
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+    /// File or parameter the finding is about
+    pub location: Option<String>,
+}
+
+impl TemplateDiagnostic {
+    fn new(severity: DiagnosticSeverity, code: &str, message: impl Into<String>, location: Option<&str>) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            message: message.into(),
+            location: location.map(str::to_string),
+        }
+    }
+
+    fn error(code: &str, message: impl Into<String>, location: Option<&str>) -> Self {
+        Self::new(DiagnosticSeverity::Error, code, message, location)
+    }
+
+    fn warning(code: &str, message: impl Into<String>, location: Option<&str>) -> Self {
+        Self::new(DiagnosticSeverity::Warning, code, message, location)
+    }
+}
+
+/// Outcome of rendering a template without publishing it
+#[derive(Debug, Clone)]
+pub struct TemplateDryRun {
+    /// Rendered files by rendered path
+    pub files: BTreeMap<String, String>,
+    /// Package built from the rendered files, when the asset spec parsed
+    pub package: Option<AssetPackage>,
+    /// Asset validator result for the package
+    pub validation: Option<ValidationResult>,
+    /// Lint, render and validation findings
+    pub diagnostics: Vec<TemplateDiagnostic>,
+}
+
+impl TemplateDryRun {
+    /// Whether no finding is an error
+    pub fn passed(&self) -> bool {
+        !has_errors(&self.diagnostics)
+    }
+}
+
+/// Whether any of the diagnostics is an error
+pub fn has_errors(diagnostics: &[TemplateDiagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == DiagnosticSeverity::Error)
+}
+
+impl CatalogTemplateGenerator {
+    /// Check a template definition without rendering it
+    pub fn lint_template(&self, template: &TemplateDefinition) -> Vec<TemplateDiagnostic> {
+        let mut diagnostics = Vec::new();
+
+        if template.name.is_empty() {
+            diagnostics.push(TemplateDiagnostic::error("empty-name", "Template name cannot be empty", None));
+        }
+        if template.files.is_empty() {
+            diagnostics.push(TemplateDiagnostic::error("no-files", "Template must have at least one file", None));
+        } else if !template.files.contains_key(ASSET_SPEC_FILE) {
+            diagnostics.push(TemplateDiagnostic::error(
+                "missing-asset-spec",
+                format!("Template has no {} file to load the package from", ASSET_SPEC_FILE),
+                None,
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for param in &template.parameters {
+            if !seen.insert(param.name.as_str()) {
+                diagnostics.push(TemplateDiagnostic::error(
+                    "duplicate-parameter",
+                    format!("Parameter '{}' is declared more than once", param.name),
+                    Some(&param.name),
+                ));
+            }
+            lint_parameter(param, &mut diagnostics);
+        }
+
+        let mut referenced = HashSet::new();
+        let mut files: Vec<(&String, &String)> = template.files.iter().collect();
+        files.sort();
+        for (file_name, content) in files {
+            for (text, what) in [(file_name, "file name"), (content, "content")] {
+                match handlebars::Template::compile(text) {
+                    Ok(_) => referenced.extend(variables(text)),
+                    Err(e) => diagnostics.push(TemplateDiagnostic::error(
+                        "template-syntax",
+                        format!("Invalid {}: {}", what, e),
+                        Some(file_name),
+                    )),
+                }
+            }
+        }
+
+        let declared: HashSet<&str> = template.parameters.iter().map(|p| p.name.as_str()).collect();
+        let mut undeclared: Vec<&String> = referenced
+            .iter()
+            .filter(|name| {
+                !declared.contains(name.as_str())
+                    && !BUILTIN_VARIABLES.contains(&name.as_str())
+                    && !self.has_helper(name)
+            })
+            .collect();
+        undeclared.sort();
+        for name in undeclared {
+            diagnostics.push(TemplateDiagnostic::warning(
+                "undeclared-variable",
+                format!("'{}' is not a declared parameter and renders empty unless metadata provides it", name),
+                Some(name),
+            ));
+        }
+        for param in &template.parameters {
+            if !referenced.contains(&param.name) {
+                diagnostics.push(TemplateDiagnostic::warning(
+                    "unused-parameter",
+                    format!("Parameter '{}' is not used by any file", param.name),
+                    Some(&param.name),
+                ));
+            }
+        }
+
+        for action in &template.post_actions {
+            if let PostGenerationAction::ExecuteCommand { command, .. } = action {
+                diagnostics.push(TemplateDiagnostic::error(
+                    "command-action",
+                    format!("Post-generation command '{}' is not allowed; generation will fail", command),
+                    None,
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Render a template in memory and validate the resulting package
+    ///
+    /// Nothing is written and post-generation actions are not run. Render
+    /// and validation failures are reported as diagnostics rather than
+    /// errors; `Err` is only returned when the validator itself fails.
+    pub async fn dry_run(
+        &self,
+        template: &TemplateDefinition,
+        context: &TemplateContext,
+        validator: &AssetValidator,
+    ) -> Result<TemplateDryRun> {
+        let mut diagnostics = self.lint_template(template);
+
+        if let Err(e) = self.validate_context(template, context) {
+            diagnostics.push(TemplateDiagnostic::error("invalid-parameters", e.to_string(), None));
+        }
+
+        let values = self.render_values(context);
+        let mut files = BTreeMap::new();
+        for (file_name, content) in &template.files {
+            let rendered = self.handlebars().render_template(file_name, &values)
+                .and_then(|path| Ok((path, self.handlebars().render_template(content, &values)?)));
+            match rendered {
+                Ok((path, text)) => {
+                    files.insert(path, text);
+                }
+                Err(e) => diagnostics.push(TemplateDiagnostic::error("render-failed", e.to_string(), Some(file_name))),
+            }
+        }
+
+        let spec = match files.get(ASSET_SPEC_FILE).map(|text| serde_yaml::from_str::<AssetSpec>(text)) {
+            Some(Ok(spec)) => Some(spec),
+            Some(Err(e)) => {
+                diagnostics.push(TemplateDiagnostic::error(
+                    "invalid-asset-spec",
+                    format!("Rendered asset spec does not parse: {}", e),
+                    Some(ASSET_SPEC_FILE),
+                ));
+                None
+            }
+            None => None,
+        };
+
+        let mut package = None;
+        let mut validation = None;
+        if let Some(spec) = spec {
+            let rendered: std::collections::HashMap<String, String> = files.clone().into_iter().collect();
+            let built = AssetPackage::from_rendered(spec, &rendered)?;
+            for error in &built.validation.errors {
+                let severity = match error.severity {
+                    ErrorSeverity::Critical | ErrorSeverity::Error => DiagnosticSeverity::Error,
+                    ErrorSeverity::Warning => DiagnosticSeverity::Warning,
+                    ErrorSeverity::Info => DiagnosticSeverity::Info,
+                };
+                diagnostics.push(TemplateDiagnostic::new(severity, &error.code.to_lowercase(), error.message.clone(), error.file.as_deref()));
+            }
+
+            let result = validator.validate(&built).await?;
+            diagnostics.extend(validation_diagnostics(&result));
+            validation = Some(result);
+            package = Some(built);
+        }
+
+        Ok(TemplateDryRun { files, package, validation, diagnostics })
+    }
+}
+
+fn lint_parameter(param: &TemplateParameter, diagnostics: &mut Vec<TemplateDiagnostic>) {
+    if param.name.is_empty() {
+        diagnostics.push(TemplateDiagnostic::error("empty-parameter-name", "Parameter name cannot be empty", None));
+    }
+    if !PARAMETER_TYPES.contains(&param.param_type.as_str()) {
+        diagnostics.push(TemplateDiagnostic::error(
+            "invalid-parameter-type",
+            format!("Parameter '{}' has unknown type '{}'", param.name, param.param_type),
+            Some(&param.name),
+        ));
+        return;
+    }
+    if let Some(default) = &param.default {
+        if !matches_type(default, &param.param_type) {
+            diagnostics.push(TemplateDiagnostic::error(
+                "default-type-mismatch",
+                format!("Default of parameter '{}' is not a {}", param.name, param.param_type),
+                Some(&param.name),
+            ));
+        }
+        if param.required {
+            diagnostics.push(TemplateDiagnostic::warning(
+                "required-with-default",
+                format!("Parameter '{}' is required, so its default is never used", param.name),
+                Some(&param.name),
+            ));
+        }
+    }
+    if let Some(pattern) = param.constraints.as_ref().and_then(|c| c.pattern.as_ref()) {
+        if let Err(e) = regex::Regex::new(pattern) {
+            diagnostics.push(TemplateDiagnostic::error(
+                "invalid-pattern",
+                format!("Pattern of parameter '{}' does not compile: {}", param.name, e),
+                Some(&param.name),
+            ));
+        }
+    }
+}
+
+fn matches_type(value: &serde_json::Value, param_type: &str) -> bool {
+    match param_type {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+/// Names of the variables a template refers to
+///
+/// Helper names are skipped when the helper is called with arguments;
+/// only the first segment of a dotted path is kept.
+fn variables(text: &str) -> HashSet<String> {
+    lazy_static::lazy_static! {
+        static ref EXPRESSION: regex::Regex = regex::Regex::new(r"\{\{~?\{?(.*?)\}?~?\}\}").unwrap();
+        static ref STRING: regex::Regex = regex::Regex::new(r#""[^"]*"|'[^']*'"#).unwrap();
+        static ref IDENTIFIER: regex::Regex = regex::Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    }
+
+    let mut names = HashSet::new();
+    for capture in EXPRESSION.captures_iter(text) {
+        let body = STRING.replace_all(&capture[1], " ").replace(['(', ')'], " ");
+        let body = body.trim();
+        if body.starts_with(['/', '!', '>']) {
+            continue;
+        }
+        let tokens: Vec<&str> = body.trim_start_matches(['#', '^']).split_whitespace().collect();
+        let arguments = if tokens.len() > 1 || body.starts_with('#') { tokens.get(1..).unwrap_or(&[]) } else { &tokens[..] };
+        for token in arguments {
+            let token = token.rsplit('=').next().unwrap_or(token);
+            if let Some(name) = IDENTIFIER.find(token) {
+                names.insert(name.as_str().to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Syntax errors and security findings from the asset validator
+fn validation_diagnostics(result: &ValidationResult) -> Vec<TemplateDiagnostic> {
+    let mut diagnostics = Vec::new();
+    if let Some(syntax) = &result.syntax {
+        for error in &syntax.errors {
+            diagnostics.push(TemplateDiagnostic::error(
+                error.error_code.as_deref().unwrap_or("syntax-error"),
+                error.message.clone(),
+                Some(&error.location.file),
+            ));
+        }
+    }
+    if let Some(security) = &result.security {
+        for vulnerability in &security.vulnerabilities {
+            let severity = match vulnerability.severity {
+                SecuritySeverity::High | SecuritySeverity::Critical => DiagnosticSeverity::Error,
+                SecuritySeverity::Medium | SecuritySeverity::Low => DiagnosticSeverity::Warning,
+                SecuritySeverity::Info => DiagnosticSeverity::Info,
+            };
+            diagnostics.push(TemplateDiagnostic::new(
+                severity,
+                "vulnerability",
+                vulnerability.description.clone(),
+                Some(&vulnerability.component),
+            ));
+        }
+    }
+    if !result.passed && !has_errors(&diagnostics) {
+        diagnostics.push(TemplateDiagnostic::error(
+            "validation-failed",
+            format!("Asset validation failed with {} issue(s)", result.summary.total_issues),
+            None,
+        ));
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::TemplateConfig;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_lint_and_dry_run() {
+        let generator = CatalogTemplateGenerator::new(TemplateConfig::default()).unwrap();
+        let validator = AssetValidator::new(Default::default());
+
+        let mut template = generator.get_template("julia-program").unwrap().clone();
+        let diagnostics = generator.lint_template(&template);
+        assert!(!has_errors(&diagnostics), "{:?}", diagnostics);
+
+        let context = TemplateContext {
+            parameters: HashMap::from([
+                ("program_name".to_string(), serde_json::json!("dry_run")),
+                ("description".to_string(), serde_json::json!("Rendered in memory")),
+                ("consensus_required".to_string(), serde_json::json!(false)),
+            ]),
+            output_dir: "unused".to_string(),
+            asset_name: "dry_run".to_string(),
+            asset_version: "1.0.0".to_string(),
+            author: None,
+            metadata: HashMap::new(),
+        };
+        let report = generator.dry_run(&template, &context, &validator).await.unwrap();
+        assert!(report.files.contains_key(ASSET_SPEC_FILE));
+        assert_eq!(report.package.unwrap().spec.metadata.name, "dry_run");
+        assert!(!std::path::Path::new("unused").exists());
+
+        // Broken syntax and a missing spec file are both reported
+        template.files.remove(ASSET_SPEC_FILE);
+        template.files.insert("broken.jl".to_string(), "{{#if x}}".to_string());
+        let codes: Vec<String> = generator.lint_template(&template).into_iter().map(|d| d.code).collect();
+        assert!(codes.contains(&"missing-asset-spec".to_string()));
+        assert!(codes.contains(&"template-syntax".to_string()));
+
+        let report = generator.dry_run(&template, &context, &validator).await.unwrap();
+        assert!(!report.passed());
+        assert!(report.package.is_none());
+    }
+}
@@ -0,0 +1,511 @@
+//! Script Triggers
+//!
+//! Binds catalog scripts to cron schedules or cluster events so simple
+//! automation runs without an external CI system. Cron bindings use the
+//! five-field syntax (minute, hour, day of month, month, day of week) in
+//! UTC; event bindings fire when an asset is published or a service turns
+//! unhealthy, optionally only for one asset or service name. Every run goes
+//! through a `ScriptExecutor` with the binding's resource limits; HyperMesh
+//! nodes execute scripts with an `EngineExecutor`.
+
+use crate::scripting::{ScriptContext, ScriptResult, ScriptingEngine};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, TimeZone, Timelike, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Cluster events scripts can be bound to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CatalogEvent {
+    /// An asset package was published to the catalog
+    AssetPublished {
+        /// Package identifier
+        package_id: String,
+        /// Asset name
+        name: String,
+        /// Asset version
+        version: String,
+    },
+    /// A service failed its health checks
+    ServiceUnhealthy {
+        /// Service name
+        service: String,
+        /// Node the unhealthy instance runs on
+        node: Option<String>,
+        /// Why the service is unhealthy
+        reason: String,
+    },
+}
+
+/// Kinds of cluster events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// `CatalogEvent::AssetPublished`
+    AssetPublished,
+    /// `CatalogEvent::ServiceUnhealthy`
+    ServiceUnhealthy,
+}
+
+impl CatalogEvent {
+    /// Kind of this event
+    pub fn kind(&self) -> EventKind {
+        match self {
+            CatalogEvent::AssetPublished { .. } => EventKind::AssetPublished,
+            CatalogEvent::ServiceUnhealthy { .. } => EventKind::ServiceUnhealthy,
+        }
+    }
+
+    /// Asset or service the event is about
+    pub fn subject(&self) -> &str {
+        match self {
+            CatalogEvent::AssetPublished { name, .. } => name,
+            CatalogEvent::ServiceUnhealthy { service, .. } => service,
+        }
+    }
+}
+
+/// What makes a bound script run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptTrigger {
+    /// Run on a cron schedule
+    Cron {
+        /// Five-field cron expression, in UTC
+        schedule: String,
+    },
+    /// Run when a cluster event occurs
+    Event {
+        /// Event kind to run on
+        kind: EventKind,
+        /// Only run for events about this asset or service
+        #[serde(default)]
+        subject: Option<String>,
+    },
+}
+
+/// Resources a triggered script may use
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptLimits {
+    /// Wall-clock time allowed for one run
+    pub timeout: Duration,
+    /// Memory the script's interpreter may allocate
+    pub max_memory_bytes: u64,
+    /// CPU cores reserved on the executing node
+    pub cpu_cores: u32,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_memory_bytes: 64 * 1024 * 1024, // 64MB
+            cpu_cores: 1,
+        }
+    }
+}
+
+/// A script bound to a trigger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptBinding {
+    /// Human-readable binding name
+    pub name: String,
+    /// Script language, matching a `ScriptingEngine::language`
+    pub language: String,
+    /// Script source
+    pub code: String,
+    /// When the script runs
+    pub trigger: ScriptTrigger,
+    /// Resource limits for each run
+    #[serde(default)]
+    pub limits: ScriptLimits,
+}
+
+/// Runs triggered scripts
+#[async_trait::async_trait]
+pub trait ScriptExecutor: Send + Sync {
+    /// Run a bound script; the context already carries its limits
+    async fn execute(&self, binding: &ScriptBinding, context: ScriptContext) -> Result<ScriptResult>;
+}
+
+/// Executes scripts with the node's scripting engines
+#[derive(Default)]
+pub struct EngineExecutor {
+    engines: HashMap<String, Arc<dyn ScriptingEngine>>,
+}
+
+impl EngineExecutor {
+    /// Create an executor without engines
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an engine for its language
+    pub fn with_engine(mut self, engine: Arc<dyn ScriptingEngine>) -> Self {
+        self.engines.insert(engine.language().to_string(), engine);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ScriptExecutor for EngineExecutor {
+    async fn execute(&self, binding: &ScriptBinding, context: ScriptContext) -> Result<ScriptResult> {
+        let engine = self.engines.get(&binding.language)
+            .ok_or_else(|| anyhow!("No scripting engine for {}", binding.language))?;
+
+        // Engines enforce the limit themselves; this catches engines that don't
+        tokio::time::timeout(binding.limits.timeout, engine.execute(&binding.code, context))
+            .await
+            .map_err(|_| anyhow!("Script {} timed out after {:?}", binding.name, binding.limits.timeout))?
+    }
+}
+
+/// One run of a bound script
+#[derive(Debug, Clone)]
+pub struct TriggerRun {
+    /// Binding identifier
+    pub binding_id: String,
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// Script result, or why the script could not run
+    pub outcome: std::result::Result<ScriptResult, String>,
+}
+
+struct Bound {
+    binding: ScriptBinding,
+    schedule: Option<CronSchedule>,
+    next_run: Option<DateTime<Utc>>,
+}
+
+/// Runs bound scripts when their triggers fire
+pub struct ScriptTriggers {
+    executor: Arc<dyn ScriptExecutor>,
+    bindings: DashMap<String, Bound>,
+}
+
+impl ScriptTriggers {
+    /// Create triggers running scripts through an executor
+    pub fn new(executor: Arc<dyn ScriptExecutor>) -> Self {
+        Self {
+            executor,
+            bindings: DashMap::new(),
+        }
+    }
+
+    /// Bind a script and return the binding's identifier
+    pub fn bind(&self, binding: ScriptBinding) -> Result<String> {
+        if binding.limits.timeout.is_zero() || binding.limits.cpu_cores == 0 {
+            bail!("Script {} needs a non-zero timeout and CPU allowance", binding.name);
+        }
+        let schedule = match &binding.trigger {
+            ScriptTrigger::Cron { schedule } => Some(CronSchedule::parse(schedule)?),
+            ScriptTrigger::Event { .. } => None,
+        };
+        let next_run = schedule.as_ref().and_then(|s| s.next_after(Utc::now()));
+
+        let id = uuid::Uuid::new_v4().to_string();
+        tracing::info!("Bound script {} ({}) to {:?}", binding.name, id, binding.trigger);
+        self.bindings.insert(id.clone(), Bound { binding, schedule, next_run });
+        Ok(id)
+    }
+
+    /// Remove a binding
+    pub fn unbind(&self, id: &str) -> bool {
+        self.bindings.remove(id).is_some()
+    }
+
+    /// Bound scripts by identifier
+    pub fn bindings(&self) -> Vec<(String, ScriptBinding)> {
+        self.bindings.iter().map(|b| (b.key().clone(), b.binding.clone())).collect()
+    }
+
+    /// Run the scripts bound to an event
+    pub async fn dispatch(&self, event: &CatalogEvent) -> Vec<TriggerRun> {
+        let matching: Vec<(String, ScriptBinding)> = self.bindings.iter()
+            .filter(|b| match &b.binding.trigger {
+                ScriptTrigger::Event { kind, subject } => {
+                    *kind == event.kind() && subject.as_deref().map_or(true, |s| s == event.subject())
+                }
+                ScriptTrigger::Cron { .. } => false,
+            })
+            .map(|b| (b.key().clone(), b.binding.clone()))
+            .collect();
+
+        let globals = HashMap::from([("event".to_string(), serde_json::to_value(event).unwrap_or_default())]);
+        let runs = matching.into_iter().map(|(id, binding)| self.run(id, binding, globals.clone()));
+        futures::future::join_all(runs).await
+    }
+
+    /// Run the cron-bound scripts due at `now`
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Vec<TriggerRun> {
+        let mut due = Vec::new();
+        for mut bound in self.bindings.iter_mut() {
+            let Some(scheduled_for) = bound.next_run.filter(|next| *next <= now) else {
+                continue;
+            };
+            bound.next_run = bound.schedule.as_ref().and_then(|s| s.next_after(now));
+            due.push((bound.key().clone(), bound.binding.clone(), scheduled_for));
+        }
+
+        let runs = due.into_iter().map(|(id, binding, scheduled_for)| {
+            let trigger = serde_json::json!({ "scheduled_for": scheduled_for.to_rfc3339() });
+            self.run(id, binding, HashMap::from([("trigger".to_string(), trigger)]))
+        });
+        futures::future::join_all(runs).await
+    }
+
+    /// Run cron-bound scripts as they come due
+    pub fn spawn_scheduler(self: &Arc<Self>) -> JoinHandle<()> {
+        let triggers = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                // Schedules have minute resolution, so check at each minute
+                let now = Utc::now();
+                let wait = 60 - u64::from(now.second());
+                tokio::time::sleep(Duration::from_secs(wait)).await;
+                triggers.run_due(Utc::now()).await;
+            }
+        })
+    }
+
+    /// Run event-bound scripts for every event received
+    pub fn spawn_listener(self: &Arc<Self>, mut events: broadcast::Receiver<CatalogEvent>) -> JoinHandle<()> {
+        let triggers = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        triggers.dispatch(&event).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Script triggers missed {} events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn run(&self, binding_id: String, binding: ScriptBinding, globals: HashMap<String, serde_json::Value>) -> TriggerRun {
+        let context = ScriptContext {
+            globals,
+            timeout_secs: Some(binding.limits.timeout.as_secs().max(1)),
+            max_memory_bytes: Some(binding.limits.max_memory_bytes),
+            ..Default::default()
+        };
+        let started_at = Utc::now();
+        let outcome = self.executor.execute(&binding, context).await.map_err(|e| e.to_string());
+        match &outcome {
+            Ok(result) if result.success => tracing::debug!("Script {} ran in {}ms", binding.name, result.execution_time_ms),
+            Ok(result) => tracing::warn!("Script {} failed: {}", binding.name, result.errors.join("; ")),
+            Err(e) => tracing::warn!("Script {} could not run: {}", binding.name, e),
+        }
+        TriggerRun { binding_id, started_at, outcome }
+    }
+}
+
+/// A five-field cron schedule: minute, hour, day of month, month, day of week
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Day of month and day of week are both restricted, so either may match
+    either_day: bool,
+}
+
+impl CronSchedule {
+    /// Parse an expression such as `*/15 9-17 * * 1-5`
+    pub fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            bail!("Cron expression {:?} needs five fields", expression);
+        };
+
+        // Both 0 and 7 mean Sunday
+        let mut days_of_week = parse_field(day_of_week, 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day_of_month, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            either_day: !day_of_month.starts_with('*') && !day_of_week.starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires in the minute containing `time`
+    pub fn matches(&self, time: DateTime<Utc>) -> bool {
+        self.matches_day(time) && bit(self.hours, time.hour()) && bit(self.minutes, time.minute())
+    }
+
+    /// First minute after `after` the schedule fires in
+    ///
+    /// Returns `None` for schedules that never fire, such as `0 0 30 2 *`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        // Leap days recur within eight years
+        let limit = after + ChronoDuration::days(8 * 366);
+        while time < limit {
+            if !bit(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = start_of_day(NaiveDate::from_ymd_opt(year, month, 1)?);
+            } else if !self.matches_day(time) {
+                time = start_of_day(time.date_naive().succ_opt()?);
+            } else if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + ChronoDuration::hours(1);
+            } else if !bit(self.minutes, time.minute()) {
+                time += ChronoDuration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = bit(self.days_of_month, time.day());
+        let day_of_week = bit(self.days_of_week, time.weekday().num_days_from_sunday());
+        let day = if self.either_day { day_of_month || day_of_week } else { day_of_month && day_of_week };
+        bit(self.months, time.month()) && day
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+/// Parse one cron field into a bit set of the values it allows
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| anyhow!("Invalid cron step in {:?}", field))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("Cron step in {:?} must be positive", field);
+        }
+
+        let value = |s: &str| s.parse::<u32>().map_err(|_| anyhow!("Invalid cron value {:?} in {:?}", s, field));
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` runs from 5 to the end of the range
+                None if part.contains('/') => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start < min || end > max || start > end {
+            bail!("Cron field {:?} is outside {}-{}", field, min, max);
+        }
+
+        for v in (start..=end).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripting::ScriptStatistics;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingExecutor(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl ScriptExecutor for CountingExecutor {
+        async fn execute(&self, _binding: &ScriptBinding, context: ScriptContext) -> Result<ScriptResult> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(ScriptResult {
+                success: true,
+                return_value: context.globals.get("event").cloned(),
+                output: vec![],
+                errors: vec![],
+                execution_time_ms: 0,
+                memory_usage_bytes: None,
+                statistics: ScriptStatistics {
+                    lines_executed: 0,
+                    function_calls: 0,
+                    variables_created: 0,
+                    peak_memory_bytes: None,
+                },
+            })
+        }
+    }
+
+    fn binding(trigger: ScriptTrigger) -> ScriptBinding {
+        ScriptBinding {
+            name: "notify".to_string(),
+            language: "lua".to_string(),
+            code: "return event".to_string(),
+            trigger,
+            limits: ScriptLimits::default(),
+        }
+    }
+
+    #[test]
+    fn test_cron_schedule() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let weekdays = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        // Friday 17:50 rolls over the weekend to Monday 09:00
+        assert_eq!(weekdays.next_after(at("2024-03-08T17:50:00Z")), Some(at("2024-03-11T09:00:00Z")));
+        assert!(weekdays.matches(at("2024-03-11T09:30:59Z")));
+
+        let leap = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(leap.next_after(at("2024-03-01T00:00:00Z")), Some(at("2028-02-29T00:00:00Z")));
+        assert_eq!(CronSchedule::parse("0 0 30 2 *").unwrap().next_after(at("2024-01-01T00:00:00Z")), None);
+
+        assert!(CronSchedule::parse("* * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_event_and_cron_triggers() {
+        let executor = Arc::new(CountingExecutor(AtomicUsize::new(0)));
+        let triggers = ScriptTriggers::new(executor.clone());
+        triggers.bind(binding(ScriptTrigger::Event {
+            kind: EventKind::ServiceUnhealthy,
+            subject: Some("api".to_string()),
+        })).unwrap();
+        triggers.bind(binding(ScriptTrigger::Cron { schedule: "* * * * *".to_string() })).unwrap();
+        assert!(triggers.bind(binding(ScriptTrigger::Cron { schedule: "bad".to_string() })).is_err());
+
+        let unhealthy = |service: &str| CatalogEvent::ServiceUnhealthy {
+            service: service.to_string(),
+            node: None,
+            reason: "probe failed".to_string(),
+        };
+        let runs = triggers.dispatch(&unhealthy("api")).await;
+        assert_eq!(runs.len(), 1);
+        let result = runs[0].outcome.as_ref().unwrap();
+        assert_eq!(result.return_value.as_ref().unwrap()["service"], "api");
+        assert!(triggers.dispatch(&unhealthy("db")).await.is_empty());
+
+        // Nothing is due yet; two minutes on, the cron binding runs once
+        assert!(triggers.run_due(Utc::now()).await.is_empty());
+        let later = Utc::now() + ChronoDuration::minutes(2);
+        assert_eq!(triggers.run_due(later).await.len(), 1);
+        assert!(triggers.run_due(later).await.is_empty());
+        assert_eq!(executor.0.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,310 @@
+//! Asset Usage Analytics
+//!
+//! Counts downloads, installs and executions per asset version and keeps
+//! hourly and daily rollups of them, so publishers can follow adoption and
+//! search can rank by real usage. Records live in a key-value [`UsageStore`]
+//! under `/catalog/usage/<name>/<version>`, with the versions seen for an
+//! asset listed under `/catalog/usage/<name>`; the in-memory store is the
+//! default and a state-layer backed store can be set in its place.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Hourly buckets kept per version
+pub const HOURLY_RETENTION: usize = 48;
+
+/// Daily buckets kept per version
+pub const DAILY_RETENTION: usize = 90;
+
+/// Days after which a day's usage counts half as much towards popularity
+pub const POPULARITY_HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Kind of usage being counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UsageKind {
+    /// Package content fetched
+    Download,
+    /// Package installed
+    Install,
+    /// Asset executed on HyperMesh
+    Execution,
+}
+
+impl UsageKind {
+    /// Weight of one event towards popularity; executions show real use
+    fn weight(self) -> f64 {
+        match self {
+            UsageKind::Download => 1.0,
+            UsageKind::Install => 2.0,
+            UsageKind::Execution => 3.0,
+        }
+    }
+}
+
+/// Event counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageCounts {
+    /// Downloads
+    pub downloads: u64,
+    /// Installs
+    pub installs: u64,
+    /// Executions
+    pub executions: u64,
+}
+
+impl UsageCounts {
+    fn add(&mut self, kind: UsageKind) {
+        match kind {
+            UsageKind::Download => self.downloads += 1,
+            UsageKind::Install => self.installs += 1,
+            UsageKind::Execution => self.executions += 1,
+        }
+    }
+
+    fn merge(&mut self, other: &UsageCounts) {
+        self.downloads += other.downloads;
+        self.installs += other.installs;
+        self.executions += other.executions;
+    }
+
+    /// Counts weighted by kind
+    pub fn weighted(&self) -> f64 {
+        self.downloads as f64 * UsageKind::Download.weight()
+            + self.installs as f64 * UsageKind::Install.weight()
+            + self.executions as f64 * UsageKind::Execution.weight()
+    }
+}
+
+/// Counts for one hour or day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageBucket {
+    /// Start of the period
+    pub start: DateTime<Utc>,
+    /// Events in the period
+    pub counts: UsageCounts,
+}
+
+/// Usage of one asset version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersionUsage {
+    /// All-time counts
+    pub totals: UsageCounts,
+    /// Hourly rollups, oldest first
+    pub hourly: Vec<UsageBucket>,
+    /// Daily rollups, oldest first
+    pub daily: Vec<UsageBucket>,
+    /// Last event, if any
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl VersionUsage {
+    fn record(&mut self, kind: UsageKind, at: DateTime<Utc>) {
+        self.totals.add(kind);
+        add_to_bucket(&mut self.hourly, truncate(at, Duration::hours(1)), kind, HOURLY_RETENTION);
+        add_to_bucket(&mut self.daily, truncate(at, Duration::days(1)), kind, DAILY_RETENTION);
+        self.last_used = Some(self.last_used.map_or(at, |last| last.max(at)));
+    }
+
+    /// Recent weighted usage, each day decaying with the popularity half-life
+    pub fn popularity(&self, now: DateTime<Utc>) -> f64 {
+        self.daily
+            .iter()
+            .map(|bucket| {
+                let age_days = (now - bucket.start).num_seconds().max(0) as f64 / 86_400.0;
+                bucket.counts.weighted() * 0.5f64.powf(age_days / POPULARITY_HALF_LIFE_DAYS)
+            })
+            .sum()
+    }
+}
+
+/// Usage of every version of an asset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetUsage {
+    /// Asset name
+    pub name: String,
+    /// Usage by version
+    pub versions: BTreeMap<String, VersionUsage>,
+    /// All-time counts over all versions
+    pub totals: UsageCounts,
+}
+
+impl AssetUsage {
+    /// Daily counts over all versions, oldest first
+    pub fn daily(&self) -> Vec<UsageBucket> {
+        let mut days: BTreeMap<DateTime<Utc>, UsageCounts> = BTreeMap::new();
+        for usage in self.versions.values() {
+            for bucket in &usage.daily {
+                days.entry(bucket.start).or_default().merge(&bucket.counts);
+            }
+        }
+        days.into_iter().map(|(start, counts)| UsageBucket { start, counts }).collect()
+    }
+}
+
+/// Key-value storage for usage records
+#[async_trait::async_trait]
+pub trait UsageStore: Send + Sync {
+    /// Read the value at a key
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Write the value at a key
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()>;
+}
+
+/// Usage store kept in process memory
+#[derive(Debug, Default)]
+pub struct MemoryUsageStore {
+    entries: DashMap<String, Vec<u8>>,
+}
+
+#[async_trait::async_trait]
+impl UsageStore for MemoryUsageStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.entries.get(key).map(|value| value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.entries.insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+}
+
+/// Records usage events and answers usage queries
+pub struct UsageAnalytics {
+    store: RwLock<Arc<dyn UsageStore>>,
+    /// Serializes read-modify-write of records
+    writes: tokio::sync::Mutex<()>,
+}
+
+impl Default for UsageAnalytics {
+    fn default() -> Self {
+        Self::new(Arc::new(MemoryUsageStore::default()))
+    }
+}
+
+impl UsageAnalytics {
+    /// Create analytics over a store
+    pub fn new(store: Arc<dyn UsageStore>) -> Self {
+        Self {
+            store: RwLock::new(store),
+            writes: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Keep records in another store from now on
+    pub fn set_store(&self, store: Arc<dyn UsageStore>) {
+        *self.store.write() = store;
+    }
+
+    fn store(&self) -> Arc<dyn UsageStore> {
+        Arc::clone(&self.store.read())
+    }
+
+    /// Count one event for an asset version
+    pub async fn record(&self, name: &str, version: &str, kind: UsageKind, at: DateTime<Utc>) -> Result<()> {
+        let store = self.store();
+        let _guard = self.writes.lock().await;
+
+        let mut versions: Vec<String> = read(&*store, &asset_key(name)).await?.unwrap_or_default();
+        if !versions.iter().any(|v| v == version) {
+            versions.push(version.to_string());
+            store.set(&asset_key(name), &serde_json::to_vec(&versions)?).await?;
+        }
+
+        let key = version_key(name, version);
+        let mut usage: VersionUsage = read(&*store, &key).await?.unwrap_or_default();
+        usage.record(kind, at);
+        store.set(&key, &serde_json::to_vec(&usage)?).await
+    }
+
+    /// Usage of one asset version
+    pub async fn version_usage(&self, name: &str, version: &str) -> Result<VersionUsage> {
+        Ok(read(&*self.store(), &version_key(name, version)).await?.unwrap_or_default())
+    }
+
+    /// Usage of every version of an asset
+    pub async fn asset_usage(&self, name: &str) -> Result<AssetUsage> {
+        let store = self.store();
+        let versions: Vec<String> = read(&*store, &asset_key(name)).await?.unwrap_or_default();
+
+        let mut usage = AssetUsage { name: name.to_string(), ..Default::default() };
+        for version in versions {
+            let record: VersionUsage = read(&*store, &version_key(name, &version)).await?.unwrap_or_default();
+            usage.totals.merge(&record.totals);
+            usage.versions.insert(version, record);
+        }
+        Ok(usage)
+    }
+}
+
+/// Key listing the versions of an asset with recorded usage
+pub fn asset_key(name: &str) -> String {
+    format!("/catalog/usage/{}", name)
+}
+
+/// Key of one asset version's usage record
+pub fn version_key(name: &str, version: &str) -> String {
+    format!("/catalog/usage/{}/{}", name, version)
+}
+
+async fn read<T: serde::de::DeserializeOwned>(store: &dyn UsageStore, key: &str) -> Result<Option<T>> {
+    store.get(key).await?
+        .map(|bytes| serde_json::from_slice(&bytes))
+        .transpose()
+        .map_err(Into::into)
+}
+
+fn truncate(at: DateTime<Utc>, period: Duration) -> DateTime<Utc> {
+    at.duration_trunc(period).unwrap_or(at)
+}
+
+fn add_to_bucket(buckets: &mut Vec<UsageBucket>, start: DateTime<Utc>, kind: UsageKind, retention: usize) {
+    match buckets.iter().position(|bucket| bucket.start >= start) {
+        Some(i) if buckets[i].start == start => buckets[i].counts.add(kind),
+        position => {
+            let mut counts = UsageCounts::default();
+            counts.add(kind);
+            buckets.insert(position.unwrap_or(buckets.len()), UsageBucket { start, counts });
+        }
+    }
+    let excess = buckets.len().saturating_sub(retention);
+    buckets.drain(..excess);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_usage_rollups() {
+        let analytics = UsageAnalytics::default();
+        let now = Utc::now();
+        let last_week = now - Duration::days(7);
+
+        analytics.record("solver", "1.0.0", UsageKind::Download, last_week).await.unwrap();
+        analytics.record("solver", "1.0.0", UsageKind::Install, now).await.unwrap();
+        analytics.record("solver", "1.1.0", UsageKind::Execution, now).await.unwrap();
+        analytics.record("solver", "1.1.0", UsageKind::Execution, now).await.unwrap();
+
+        let v1 = analytics.version_usage("solver", "1.0.0").await.unwrap();
+        assert_eq!(v1.totals, UsageCounts { downloads: 1, installs: 1, executions: 0 });
+        assert_eq!(v1.daily.len(), 2);
+        assert!(v1.daily[0].start < v1.daily[1].start);
+
+        let usage = analytics.asset_usage("solver").await.unwrap();
+        assert_eq!(usage.versions.len(), 2);
+        assert_eq!(usage.totals.executions, 2);
+        assert_eq!(usage.daily().last().unwrap().counts.executions, 2);
+
+        // Week-old downloads count half; the newer version is used more
+        let v2 = analytics.version_usage("solver", "1.1.0").await.unwrap();
+        assert!((2.0..=2.5).contains(&v1.popularity(now)));
+        assert!(v2.popularity(now) > v1.popularity(now));
+        assert_eq!(analytics.asset_usage("unknown").await.unwrap().totals, UsageCounts::default());
+    }
+}
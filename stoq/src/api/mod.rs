@@ -356,16 +356,19 @@ impl StoqApiClient {
                 address: std::net::Ipv6Addr::LOCALHOST,
                 port: 9293,
                 server_name: Some("trustchain".to_string()),
+                congestion_control: None,
             }),
             "hypermesh" => Ok(Endpoint {
                 address: std::net::Ipv6Addr::LOCALHOST,
                 port: 9292,
                 server_name: Some("hypermesh".to_string()),
+                congestion_control: None,
             }),
             "caesar" => Ok(Endpoint {
                 address: std::net::Ipv6Addr::LOCALHOST,
                 port: 9294,
                 server_name: Some("caesar".to_string()),
+                congestion_control: None,
             }),
             _ => Err(anyhow!("Unknown service: {}", service)),
         }
@@ -24,7 +24,7 @@ use anyhow::Result;
 use serde::{Serialize, Deserialize};
 
 // Re-export pure transport types and protocol extensions
-pub use transport::{StoqTransport, TransportConfig, Connection, Endpoint, Stream, NetworkTier};
+pub use transport::{StoqTransport, TransportConfig, Connection, Endpoint, Stream, NetworkTier, CongestionControl, CongestionState};
 pub use transport::falcon::{
     FalconEngine, FalconTransport, FalconVariant, FalconPublicKey,
     FalconPrivateKey, FalconSignature
@@ -93,6 +93,9 @@ pub struct TransportStats {
     pub throughput_gbps: f64,
     /// Average latency in microseconds
     pub avg_latency_us: u64,
+    /// Congestion controller state per active connection
+    #[serde(default)]
+    pub congestion: Vec<transport::CongestionState>,
 }
 
 // REMOVED: Application-layer statistics and content types
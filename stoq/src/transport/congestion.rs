@@ -0,0 +1,156 @@
+//! Congestion controller selection and state reporting
+//!
+//! `CongestionControl` picks the controller quinn runs for each connection:
+//! BBR for throughput on long fat pipes, CUBIC or NewReno for loss-based
+//! fairness, or a fixed-rate pacer for links with a known, dedicated capacity.
+//! The fixed-rate controller keeps its window at rate × RTT and ignores loss,
+//! so it must only be used where the rate is known not to congest the path.
+//! Controller state read back from quinn is reported as `CongestionState`.
+
+use super::CongestionControl;
+use quinn::congestion::{BbrConfig, Controller, ControllerFactory, CubicConfig, NewRenoConfig};
+use quinn_proto::RttEstimator;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Packets allowed in flight before the first RTT sample
+const INITIAL_WINDOW_PACKETS: u64 = 10;
+
+/// Controller factory quinn uses to build `algorithm` per connection
+pub fn controller_factory(algorithm: &CongestionControl) -> Arc<dyn ControllerFactory + Send + Sync> {
+    match algorithm {
+        CongestionControl::Bbr2 => Arc::new(BbrConfig::default()),
+        CongestionControl::Cubic => Arc::new(CubicConfig::default()),
+        CongestionControl::NewReno => Arc::new(NewRenoConfig::default()),
+        CongestionControl::FixedRate { bits_per_second } => Arc::new(FixedRateConfig { bits_per_second: *bits_per_second }),
+    }
+}
+
+/// Fixed-rate pacing controller configuration
+#[derive(Debug, Clone, Copy)]
+pub struct FixedRateConfig {
+    /// Target send rate in bits per second
+    pub bits_per_second: u64,
+}
+
+impl ControllerFactory for FixedRateConfig {
+    fn build(self: Arc<Self>, _now: Instant, current_mtu: u16) -> Box<dyn Controller> {
+        let initial_window = INITIAL_WINDOW_PACKETS * current_mtu as u64;
+        Box::new(FixedRate {
+            bits_per_second: self.bits_per_second,
+            mtu: current_mtu as u64,
+            initial_window,
+            window: initial_window,
+        })
+    }
+}
+
+/// Sends at a configured rate regardless of loss
+#[derive(Debug, Clone)]
+pub struct FixedRate {
+    bits_per_second: u64,
+    mtu: u64,
+    initial_window: u64,
+    window: u64,
+}
+
+impl FixedRate {
+    /// Bytes in flight that sustain the rate over `rtt`
+    fn window_for(&self, rtt: Duration) -> u64 {
+        let bytes = self.bits_per_second as u128 * rtt.as_nanos() / 8 / 1_000_000_000;
+        (bytes as u64).max(2 * self.mtu)
+    }
+}
+
+impl Controller for FixedRate {
+    fn on_ack(&mut self, _now: Instant, _sent: Instant, _bytes: u64, _app_limited: bool, rtt: &RttEstimator) {
+        self.window = self.window_for(rtt.get());
+    }
+
+    fn on_congestion_event(&mut self, _now: Instant, _sent: Instant, _is_persistent_congestion: bool, _lost_bytes: u64) {}
+
+    fn on_mtu_update(&mut self, new_mtu: u16) {
+        self.mtu = new_mtu as u64;
+        self.window = self.window.max(2 * self.mtu);
+    }
+
+    fn window(&self) -> u64 {
+        self.window
+    }
+
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+
+    fn initial_window(&self) -> u64 {
+        self.initial_window
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Congestion controller state of one connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CongestionState {
+    /// Connection the state was read from
+    pub connection_id: String,
+    /// Algorithm the connection was configured with
+    pub algorithm: CongestionControl,
+    /// Congestion window in bytes
+    pub cwnd: u64,
+    /// Smoothed round-trip time
+    pub rtt: Duration,
+    /// Pacing rate in bits per second, the window sent once per RTT
+    pub pacing_rate_bps: u64,
+    /// Congestion events seen since the connection opened
+    pub congestion_events: u64,
+    /// Packets declared lost since the connection opened
+    pub lost_packets: u64,
+}
+
+impl CongestionState {
+    /// Read the controller state of a live connection
+    pub fn from_connection(connection_id: String, algorithm: CongestionControl, connection: &quinn::Connection) -> Self {
+        let path = connection.stats().path;
+        let pacing_rate_bps = if path.rtt.is_zero() {
+            0
+        } else {
+            (path.cwnd as u128 * 8 * 1_000_000_000 / path.rtt.as_nanos()) as u64
+        };
+        Self {
+            connection_id,
+            algorithm,
+            cwnd: path.cwnd,
+            rtt: path.rtt,
+            pacing_rate_bps,
+            congestion_events: path.congestion_events,
+            lost_packets: path.lost_packets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_rate_window_tracks_rtt() {
+        let factory = Arc::new(FixedRateConfig { bits_per_second: 800_000_000 });
+        let controller = factory.build(Instant::now(), 1200);
+        assert_eq!(controller.window(), 12_000);
+
+        let rate = FixedRate { bits_per_second: 800_000_000, mtu: 1200, initial_window: 12_000, window: 12_000 };
+        // 100 MB/s over 10ms keeps 1 MB in flight
+        assert_eq!(rate.window_for(Duration::from_millis(10)), 1_000_000);
+        // Never below two packets, however short the RTT
+        assert_eq!(rate.window_for(Duration::from_nanos(1)), 2400);
+
+        let mut lossy = rate.clone();
+        lossy.on_congestion_event(Instant::now(), Instant::now(), true, 1_000_000);
+        assert_eq!(lossy.window(), rate.window());
+    }
+}
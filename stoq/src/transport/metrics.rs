@@ -194,6 +194,7 @@ impl TransportMetrics {
             total_connections,
             throughput_gbps,
             avg_latency_us,
+            congestion: Vec::new(),
         }
     }
 
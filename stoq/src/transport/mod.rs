@@ -22,6 +22,7 @@ pub mod streams;
 pub mod metrics;
 pub mod falcon;
 pub mod adaptive;
+pub mod congestion;
 #[cfg(feature = "ebpf")]
 pub mod ebpf;
 
@@ -30,6 +31,7 @@ use metrics::TransportMetrics;
 pub use metrics::{ProtocolMetrics, IntervalMetrics};
 use falcon::{FalconTransport, FalconVariant};
 use adaptive::{AdaptiveConnection, AdaptationManager};
+pub use congestion::CongestionState;
 
 // Protocol integration
 use crate::protocol::{StoqProtocolHandler, handshake::StoqHandshakeExtension};
@@ -117,7 +119,7 @@ pub struct TransportConfig {
 }
 
 /// Congestion control algorithms
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CongestionControl {
     /// BBR v2 for maximum throughput
     Bbr2,
@@ -125,6 +127,8 @@ pub enum CongestionControl {
     Cubic,
     /// NewReno
     NewReno,
+    /// Pace at a fixed rate, ignoring loss; for dedicated links of known capacity
+    FixedRate { bits_per_second: u64 },
 }
 
 impl Default for CongestionControl {
@@ -223,6 +227,8 @@ pub struct Endpoint {
     pub port: u16,
     /// Optional server name for SNI
     pub server_name: Option<String>,
+    /// Congestion control for this connection, overriding the transport's
+    pub congestion_control: Option<CongestionControl>,
 }
 
 impl Endpoint {
@@ -232,6 +238,7 @@ impl Endpoint {
             address,
             port,
             server_name: None,
+            congestion_control: None,
         }
     }
     
@@ -241,6 +248,12 @@ impl Endpoint {
         self
     }
     
+    /// Use a different congestion controller for connections to this endpoint
+    pub fn with_congestion_control(mut self, congestion_control: CongestionControl) -> Self {
+        self.congestion_control = Some(congestion_control);
+        self
+    }
+    
     /// Convert to socket address
     pub fn to_socket_addr(&self) -> SocketAddr {
         SocketAddr::from((self.address, self.port))
@@ -513,40 +526,9 @@ impl StoqTransport {
         let cert_manager = Arc::new(CertificateManager::new(cert_config).await?);
         
         // Configure QUIC transport for adaptive network tiers performance
-        let mut server_transport_config = QuinnTransportConfig::default();
-        server_transport_config.max_concurrent_bidi_streams(config.max_concurrent_streams.into());
-        server_transport_config.max_concurrent_uni_streams(config.max_concurrent_streams.into());
-        server_transport_config.max_idle_timeout(Some(config.max_idle_timeout.try_into()?));
-        
-        // QUIC performance optimizations
-        server_transport_config.send_window(config.send_buffer_size as u64);
-        server_transport_config.receive_window(VarInt::try_from(config.receive_buffer_size as u64).unwrap_or(VarInt::MAX));
-        server_transport_config.datagram_receive_buffer_size(Some(config.max_datagram_size));
-        server_transport_config.datagram_send_buffer_size(config.max_datagram_size);
-        
-        // Create client transport config
-        let mut client_transport_config = QuinnTransportConfig::default();
-        client_transport_config.max_concurrent_bidi_streams(config.max_concurrent_streams.into());
-        client_transport_config.max_concurrent_uni_streams(config.max_concurrent_streams.into());
-        client_transport_config.max_idle_timeout(Some(config.max_idle_timeout.try_into()?));
-        client_transport_config.send_window(config.send_buffer_size as u64);
-        client_transport_config.receive_window(VarInt::try_from(config.receive_buffer_size as u64).unwrap_or(VarInt::MAX));
-        client_transport_config.datagram_receive_buffer_size(Some(config.max_datagram_size));
-        client_transport_config.datagram_send_buffer_size(config.max_datagram_size);
-        
-        // Advanced congestion control for high performance
-        match config.congestion_control {
-            CongestionControl::Bbr2 => {
-                // BBR v2 would be configured here when available in Quinn
-                debug!("Using BBR-optimized settings for high performance");
-            }
-            CongestionControl::Cubic => {
-                debug!("Using CUBIC congestion control");
-            }
-            CongestionControl::NewReno => {
-                debug!("Using NewReno congestion control");
-            }
-        }
+        let server_transport_config = Self::quinn_transport_config(&config, &config.congestion_control)?;
+        let client_transport_config = Self::quinn_transport_config(&config, &config.congestion_control)?;
+        debug!("Using {:?} congestion control", config.congestion_control);
         
         // Create server configuration with TLS
         let rustls_server_config = cert_manager.server_crypto_config().await?;
@@ -694,16 +676,37 @@ impl StoqTransport {
         })
     }
     
+    /// QUIC transport parameters shared by the server and client sides
+    fn quinn_transport_config(config: &TransportConfig, congestion_control: &CongestionControl) -> Result<QuinnTransportConfig> {
+        let mut transport_config = QuinnTransportConfig::default();
+        transport_config.max_concurrent_bidi_streams(config.max_concurrent_streams.into());
+        transport_config.max_concurrent_uni_streams(config.max_concurrent_streams.into());
+        transport_config.max_idle_timeout(Some(config.max_idle_timeout.try_into()?));
+        
+        // QUIC performance optimizations
+        transport_config.send_window(config.send_buffer_size as u64);
+        transport_config.receive_window(VarInt::try_from(config.receive_buffer_size as u64).unwrap_or(VarInt::MAX));
+        transport_config.datagram_receive_buffer_size(Some(config.max_datagram_size));
+        transport_config.datagram_send_buffer_size(config.max_datagram_size);
+        transport_config.congestion_controller_factory(congestion::controller_factory(congestion_control));
+        Ok(transport_config)
+    }
+    
     /// Connect to a remote endpoint with connection pooling for performance
+    ///
+    /// Endpoints with a congestion control override always get a new
+    /// connection, since pooled ones run the transport's controller.
     pub async fn connect(&self, endpoint: &Endpoint) -> Result<Arc<Connection>> {
         let pool_key = format!("{}:{}", endpoint.address, endpoint.port);
         
         // Try to reuse existing connection from pool for maximum performance
-        if let Some(mut pool) = self.connection_pool.get_mut(&pool_key) {
-            if let Some(pooled_conn) = pool.pop() {
-                if pooled_conn.is_active() {
-                    debug!("Reusing pooled connection to [{}]:{}", endpoint.address, endpoint.port);
-                    return Ok(pooled_conn);
+        if endpoint.congestion_control.is_none() {
+            if let Some(mut pool) = self.connection_pool.get_mut(&pool_key) {
+                if let Some(pooled_conn) = pool.pop() {
+                    if pooled_conn.is_active() {
+                        debug!("Reusing pooled connection to [{}]:{}", endpoint.address, endpoint.port);
+                        return Ok(pooled_conn);
+                    }
                 }
             }
         }
@@ -711,7 +714,16 @@ impl StoqTransport {
         debug!("Creating new connection to [{}]:{}", endpoint.address, endpoint.port);
         
         let socket_addr = endpoint.to_socket_addr();
-        let connecting = self.endpoint.connect(socket_addr, endpoint.server_name.as_deref().unwrap_or("localhost"))?;
+        let server_name = endpoint.server_name.as_deref().unwrap_or("localhost");
+        let connecting = match &endpoint.congestion_control {
+            Some(congestion_control) => {
+                let mut client_config = self.cached_client_config.read().clone()
+                    .ok_or_else(|| anyhow!("Client configuration not initialized"))?;
+                client_config.transport_config(Arc::new(Self::quinn_transport_config(&self.config, congestion_control)?));
+                self.endpoint.connect_with(client_config, socket_addr, server_name)?
+            }
+            None => self.endpoint.connect(socket_addr, server_name)?,
+        };
         
         let quinn_conn = connecting.await?;
         
@@ -743,8 +755,8 @@ impl StoqTransport {
     
     /// Return connection to pool for reuse (optimization)
     pub fn return_to_pool(&self, connection: Arc<Connection>) {
-        if !connection.is_active() {
-            return; // Don't pool inactive connections
+        if !connection.is_active() || connection.endpoint().congestion_control.is_some() {
+            return; // Don't pool inactive or overridden connections
         }
         
         let pool_key = format!("{}:{}", connection.endpoint().address, connection.endpoint().port);
@@ -962,7 +974,23 @@ impl StoqTransport {
         
         info!("Memory Pool Stats: Available buffers: {}, Allocated: {}", pool_available, pool_allocated);
         
-        base_stats
+        crate::TransportStats {
+            congestion: self.congestion_states(),
+            ..base_stats
+        }
+    }
+    
+    /// Congestion controller state of every active connection
+    pub fn congestion_states(&self) -> Vec<CongestionState> {
+        self.connections
+            .iter()
+            .map(|entry| {
+                let connection = entry.value();
+                let algorithm = connection.endpoint().congestion_control.clone()
+                    .unwrap_or_else(|| self.config.congestion_control.clone());
+                CongestionState::from_connection(entry.key().clone(), algorithm, &connection.inner)
+            })
+            .collect()
     }
     
     /// Get active connections count